@@ -0,0 +1,354 @@
+//! Real-Time Clock
+//!
+//! Calendar arithmetic and the system wall clock. The clock is set from
+//! the hardware RTC at boot and corrected from CAT, GPS (NMEA RMC) or the
+//! host (NTP via the web UI); between corrections it runs from the
+//! millisecond uptime counter. All stored time is UTC; local time is a
+//! display-only offset.
+
+use heapless::String;
+
+/// Seconds per day
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Calendar date and time (UTC unless stated otherwise)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    /// Year (1970-2099)
+    pub year: u16,
+    /// Month (1-12)
+    pub month: u8,
+    /// Day of month (1-31)
+    pub day: u8,
+    /// Hour (0-23)
+    pub hour: u8,
+    /// Minute (0-59)
+    pub minute: u8,
+    /// Second (0-59)
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Create a validated date and time
+    #[must_use]
+    pub const fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        if year < 1970
+            || year > 2099
+            || month == 0
+            || month > 12
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        Some(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Convert from seconds since the Unix epoch
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub const fn from_unix(secs: u64) -> Self {
+        let days = (secs / SECS_PER_DAY) as i64;
+        let rem = secs % SECS_PER_DAY;
+
+        // Civil-from-days (proleptic Gregorian), era-based
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (rem / 3600) as u8,
+            minute: ((rem / 60) % 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+
+    /// Convert to seconds since the Unix epoch
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub const fn to_unix(&self) -> u64 {
+        let y = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let m = self.month as i64;
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = if m > 2 { m - 3 } else { m + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        days as u64 * SECS_PER_DAY
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64
+    }
+
+    /// Day of week (1 = Monday, 7 = Sunday)
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn weekday(&self) -> u8 {
+        // 1970-01-01 was a Thursday
+        (((self.to_unix() / SECS_PER_DAY) + 3) % 7) as u8 + 1
+    }
+
+    /// Shift by an offset in minutes (for local time display)
+    #[must_use]
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+    pub const fn offset_minutes(&self, minutes: i16) -> Self {
+        let secs = self.to_unix() as i64 + minutes as i64 * 60;
+        Self::from_unix(if secs < 0 { 0 } else { secs as u64 })
+    }
+
+    /// Format as `HH:MM` for the display clock
+    #[must_use]
+    pub fn format_hm(&self) -> String<5> {
+        let mut s = String::new();
+        let _ = core::fmt::write(&mut s, format_args!("{:02}:{:02}", self.hour, self.minute));
+        s
+    }
+
+    /// Format as ISO 8601 `YYYY-MM-DDTHH:MM:SSZ` for logs
+    #[must_use]
+    pub fn format_iso(&self) -> String<20> {
+        let mut s = String::new();
+        let _ = core::fmt::write(
+            &mut s,
+            format_args!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                self.year, self.month, self.day, self.hour, self.minute, self.second
+            ),
+        );
+        s
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for DateTime {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        );
+    }
+}
+
+/// Check for a leap year
+#[must_use]
+pub const fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// Number of days in a month (0 for an invalid month)
+#[must_use]
+pub const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Where the current time came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TimeSource {
+    /// Clock not set
+    #[default]
+    None,
+    /// Battery-backed RTC at boot
+    Rtc,
+    /// CAT command
+    Cat,
+    /// GPS receiver
+    Gps,
+    /// Host computer (NTP)
+    Host,
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for TimeSource {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::None => defmt::write!(f, "None"),
+            Self::Rtc => defmt::write!(f, "RTC"),
+            Self::Cat => defmt::write!(f, "CAT"),
+            Self::Gps => defmt::write!(f, "GPS"),
+            Self::Host => defmt::write!(f, "Host"),
+        }
+    }
+}
+
+/// System wall clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Clock {
+    /// Unix time in milliseconds at the last set
+    base_unix_ms: u64,
+    /// Uptime in milliseconds at the last set
+    base_uptime_ms: u32,
+    /// Source of the last set
+    source: TimeSource,
+    /// Local time offset from UTC in minutes
+    utc_offset_min: i16,
+}
+
+impl Clock {
+    /// Create an unset clock
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            base_unix_ms: 0,
+            base_uptime_ms: 0,
+            source: TimeSource::None,
+            utc_offset_min: 0,
+        }
+    }
+
+    /// Set the clock from a UTC date and time
+    pub fn set(&mut self, utc: &DateTime, now_ms: u32, source: TimeSource) {
+        self.set_unix_ms(utc.to_unix() * 1000, now_ms, source);
+    }
+
+    /// Set the clock from Unix time in milliseconds
+    pub fn set_unix_ms(&mut self, unix_ms: u64, now_ms: u32, source: TimeSource) {
+        self.base_unix_ms = unix_ms;
+        self.base_uptime_ms = now_ms;
+        self.source = source;
+    }
+
+    /// Check if the clock has been set
+    #[must_use]
+    pub const fn is_set(&self) -> bool {
+        !matches!(self.source, TimeSource::None)
+    }
+
+    /// Get the source of the current time
+    #[must_use]
+    pub const fn source(&self) -> TimeSource {
+        self.source
+    }
+
+    /// Set local time offset from UTC in minutes (clamped to ±14 h)
+    pub fn set_utc_offset(&mut self, minutes: i16) {
+        self.utc_offset_min = minutes.clamp(-14 * 60, 14 * 60);
+    }
+
+    /// Get local time offset from UTC in minutes
+    #[must_use]
+    pub const fn utc_offset(&self) -> i16 {
+        self.utc_offset_min
+    }
+
+    /// Get Unix time in milliseconds
+    #[must_use]
+    pub fn unix_ms(&self, now_ms: u32) -> Option<u64> {
+        if !self.is_set() {
+            return None;
+        }
+        Some(self.base_unix_ms + u64::from(now_ms.wrapping_sub(self.base_uptime_ms)))
+    }
+
+    /// Get UTC date and time
+    #[must_use]
+    pub fn utc(&self, now_ms: u32) -> Option<DateTime> {
+        self.unix_ms(now_ms).map(|ms| DateTime::from_unix(ms / 1000))
+    }
+
+    /// Get local date and time
+    #[must_use]
+    pub fn local(&self, now_ms: u32) -> Option<DateTime> {
+        self.utc(now_ms).map(|t| t.offset_minutes(self.utc_offset_min))
+    }
+
+    /// Milliseconds since the last slot boundary (FT8 15 s, WSPR 120 s)
+    ///
+    /// Slot boundaries are aligned to UTC.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn ms_into_slot(&self, now_ms: u32, slot_ms: u32) -> Option<u32> {
+        if slot_ms == 0 {
+            return None;
+        }
+        self.unix_ms(now_ms)
+            .map(|ms| (ms % u64::from(slot_ms)) as u32)
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for Clock {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Clock({}, offset={}min)", self.source, self.utc_offset_min);
+    }
+}
+
+/// Parse UTC date and time from an NMEA RMC sentence
+///
+/// Accepts any talker (`$GPRMC`, `$GNRMC`, ...). Returns `None` if the
+/// sentence is malformed, the checksum is wrong or the fix is invalid.
+#[must_use]
+pub fn parse_nmea_rmc(sentence: &str) -> Option<DateTime> {
+    let sentence = sentence.trim_end();
+    let body = sentence.strip_prefix('$')?;
+
+    // Verify checksum if present
+    let body = match body.split_once('*') {
+        Some((data, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16).ok()?;
+            let actual = data.bytes().fold(0u8, |acc, b| acc ^ b);
+            if expected != actual {
+                return None;
+            }
+            data
+        }
+        None => body,
+    };
+
+    let mut fields = body.split(',');
+    let id = fields.next()?;
+    if id.len() != 5 || !id.ends_with("RMC") {
+        return None;
+    }
+    let time = fields.next()?;
+    let status = fields.next()?;
+    if status != "A" {
+        return None;
+    }
+    // Skip lat, N/S, lon, E/W, speed, course
+    let date = fields.nth(6)?;
+
+    if time.len() < 6 || date.len() != 6 {
+        return None;
+    }
+    let hour = time.get(0..2)?.parse().ok()?;
+    let minute = time.get(2..4)?.parse().ok()?;
+    let second = time.get(4..6)?.parse().ok()?;
+    let day = date.get(0..2)?.parse().ok()?;
+    let month = date.get(2..4)?.parse().ok()?;
+    let year: u16 = date.get(4..6)?.parse().ok()?;
+
+    DateTime::new(2000 + year, month, day, hour, minute, second)
+}
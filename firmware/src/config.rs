@@ -32,6 +32,9 @@ pub const DISPLAY_WIDTH: u32 = 128;
 /// Display height in pixels
 pub const DISPLAY_HEIGHT: u32 = 64;
 
+/// ST7735 LCD SPI clock
+pub const LCD_SPI_FREQUENCY_HZ: u32 = 16_000_000;
+
 /// `Si5351A` crystal frequency (25 MHz standard)
 pub const SI5351_XTAL_FREQ: u32 = 25_000_000;
 
@@ -41,12 +44,30 @@ pub const NUM_LPF_BANKS: usize = 5;
 /// T/R relay switching delay in microseconds
 pub const TR_RELAY_DELAY_US: u32 = 10_000;
 
+/// T/R relay operate and bounce time in microseconds
+pub const TR_RELAY_SETTLE_US: u32 = 6_000;
+
+/// LPF relay operate and bounce time in microseconds
+pub const LPF_RELAY_SETTLE_US: u32 = 8_000;
+
 /// SWR protection threshold (3:1)
 pub const SWR_PROTECTION_THRESHOLD: f32 = 3.0;
 
+/// SWR bridge sampling interval in milliseconds
+pub const SWR_SAMPLE_INTERVAL_MS: u64 = 1;
+
+/// SWR bridge samples averaged into each reading
+pub const SWR_AVERAGE_SAMPLES: u32 = 20;
+
 /// Maximum transmit power in watts
 pub const MAX_TX_POWER_WATTS: f32 = 5.0;
 
+/// Maximum transmit power in watts on a 15 V USB-PD supply
+pub const PD_MAX_TX_POWER_WATTS: f32 = 15.0;
+
+/// CW keying envelope rise/fall time in milliseconds
+pub const CW_RISE_TIME_MS: f32 = 5.0;
+
 /// USB VID (use test VID for development)
 pub const USB_VID: u16 = 0x1209;
 
@@ -89,6 +110,60 @@ pub const CAT_BUFFER_SIZE: usize = 64;
 /// USB CDC ACM packet size
 pub const USB_CDC_PACKET_SIZE: u16 = 64;
 
+/// SPI flash size in bytes (W25Q128, 16 MB)
+pub const FLASH_SIZE: u32 = 16 * 1024 * 1024;
+
+/// SPI flash erase sector size in bytes
+pub const FLASH_SECTOR_SIZE: u32 = 4096;
+
+/// SPI flash clock (Hz)
+pub const FLASH_SPI_FREQUENCY_HZ: u32 = 20_000_000;
+
+/// POST: allowed deviation of idle IQ ADC readings from mid-scale (counts)
+pub const POST_ADC_OFFSET_TOLERANCE: u16 = 200;
+
+/// POST: maximum PA supply current with the drive off (milliamps)
+pub const POST_PA_IDLE_CURRENT_MAX_MA: u16 = 20;
+
+/// POST: number of ADC readings averaged per offset check
+pub const POST_ADC_SAMPLES: u32 = 64;
+
+/// PA current sense gain (milliamps per volt at the ADC pin)
+pub const PA_CURRENT_SENSE_MA_PER_V: f32 = 1000.0;
+
+/// Remote head serial link baud rate
+pub const REMOTE_HEAD_BAUD: u32 = 115_200;
+
+/// Remote head: interval between status refreshes when nothing changed (ms)
+pub const REMOTE_HEAD_REFRESH_MS: u32 = 1000;
+
+/// Radio events queued for the radio control task
+pub const RADIO_EVENT_QUEUE_LEN: usize = 16;
+
+/// DSP commands queued for the DSP task
+pub const DSP_COMMAND_QUEUE_LEN: usize = 8;
+
+/// UI task encoder polling interval (ms)
+pub const UI_POLL_MS: u64 = 1;
+
+/// Interval between band scope spectrum rows from the DSP task (ms)
+pub const SPECTRUM_ROW_MS: u32 = 100;
+
+/// Transmit requests queued for the transmit task
+pub const TX_REQUEST_QUEUE_LEN: usize = 8;
+
+/// PA temperature polling interval (ms)
+pub const PA_THERMAL_POLL_MS: u64 = 1000;
+
+/// `WinKeyer` port keyer clock (one element tick per millisecond)
+pub const WINKEYER_TICK_HZ: u32 = 1000;
+
+/// Band monitor schedule tick in the radio control task (ms)
+pub const MONITOR_TICK_MS: u32 = 1000;
+
+/// Band monitor commands queued for the radio control task
+pub const MONITOR_COMMAND_QUEUE_LEN: usize = 4;
+
 /// Pin assignments for GPIO
 pub mod pins {
     //! GPIO pin assignments matching the schematic
@@ -97,10 +172,10 @@ pub mod pins {
     pub const LED_STATUS: &str = "PA5";
 
     /// I2C1 SCL (Si5351, Display)
-    pub const I2C1_SCL: &str = "PB6";
+    pub const I2C1_SCL: &str = "PB8";
 
     /// I2C1 SDA (Si5351, Display)
-    pub const I2C1_SDA: &str = "PB7";
+    pub const I2C1_SDA: &str = "PB9";
 
     /// Encoder A input
     pub const ENCODER_A: &str = "PA0";
@@ -135,8 +210,17 @@ pub mod pins {
     /// Forward power ADC
     pub const FWD_POWER: &str = "PB1";
 
-    /// Reflected power ADC
-    pub const REF_POWER: &str = "PB2";
+    /// Reflected power ADC (ADC1, with the forward detector)
+    pub const REF_POWER: &str = "PB11";
+
+    /// QSD I channel ADC input (ADC2)
+    pub const IQ_I: &str = "PA6";
+
+    /// QSD Q channel ADC input (ADC2)
+    pub const IQ_Q: &str = "PA7";
+
+    /// PA supply current sense ADC input (ADC2)
+    pub const PA_ISENSE: &str = "PC4";
 
     /// USB D+ (handled by USB peripheral)
     pub const USB_DP: &str = "PA12";
@@ -145,25 +229,58 @@ pub mod pins {
     pub const USB_DM: &str = "PA11";
 
     /// USB-C CC1 for UCPD
-    pub const USB_CC1: &str = "PB4";
+    pub const USB_CC1: &str = "PB6";
 
     /// USB-C CC2 for UCPD
-    pub const USB_CC2: &str = "PB5";
+    pub const USB_CC2: &str = "PB4";
 
-    /// PA drive PWM output
+    /// PA supply PWM output (HRTIM CHA1)
     pub const PA_DRIVE: &str = "PA8";
 
-    /// Class-E H-bridge A high side
-    pub const PA_AH: &str = "PA9";
+    /// Class-E H-bridge A high side (HRTIM CHC1)
+    pub const PA_AH: &str = "PB12";
+
+    /// Class-E H-bridge A low side (HRTIM CHC2)
+    pub const PA_AL: &str = "PB13";
+
+    /// Class-E H-bridge B high side (HRTIM CHD1)
+    pub const PA_BH: &str = "PB14";
+
+    /// Class-E H-bridge B low side (HRTIM CHD2)
+    pub const PA_BL: &str = "PB15";
+
+    /// SPI flash clock (SPI3)
+    pub const FLASH_SCK: &str = "PC10";
+
+    /// SPI flash data in (SPI3 MISO)
+    pub const FLASH_MISO: &str = "PC11";
+
+    /// SPI flash data out (SPI3 MOSI)
+    pub const FLASH_MOSI: &str = "PC12";
+
+    /// SPI flash chip select
+    pub const FLASH_CS: &str = "PA15";
+
+    /// Remote head link TX (USART1)
+    pub const REMOTE_HEAD_TX: &str = "PA9";
+
+    /// Remote head link RX (USART1)
+    pub const REMOTE_HEAD_RX: &str = "PA10";
+
+    /// ST7735 LCD clock (SPI1, shared with SWO)
+    pub const LCD_SCK: &str = "PB3";
+
+    /// ST7735 LCD data (SPI1)
+    pub const LCD_MOSI: &str = "PB5";
 
-    /// Class-E H-bridge A low side
-    pub const PA_AL: &str = "PA10";
+    /// ST7735 LCD data/command select
+    pub const LCD_DC: &str = "PC6";
 
-    /// Class-E H-bridge B high side
-    pub const PA_BH: &str = "PB13";
+    /// ST7735 LCD chip select
+    pub const LCD_CS: &str = "PC7";
 
-    /// Class-E H-bridge B low side
-    pub const PA_BL: &str = "PB14";
+    /// ST7735 LCD reset
+    pub const LCD_RST: &str = "PC8";
 }
 
 /// DMA channel assignments
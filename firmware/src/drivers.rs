@@ -4,5 +4,12 @@
 //! These provide domain-specific abstractions over the HAL layer.
 
 pub mod si5351;
+pub mod pa;
+pub mod relay;
+#[cfg(feature = "display")]
 pub mod display;
+#[cfg(feature = "display")]
 pub mod encoder;
+#[cfg(feature = "st7735")]
+pub mod st7735;
+pub mod spi_flash;
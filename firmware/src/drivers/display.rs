@@ -1,19 +1,29 @@
 //! OLED Display Driver
 //!
 //! Provides display rendering for the SDR transceiver UI.
-//! Uses the SSD1306 controller with I2C interface.
+//! Uses the SSD1306 controller with I2C interface; the ST7735 colour
+//! LCD (`st7735` feature) is in `drivers::st7735`.
+//!
+//! Both panels draw from a 1 bpp [`DisplayBuffer`] and implement
+//! [`Panel`], so the UI task sends partial updates from the
+//! [`redraw`](crate::ui::redraw) scheduler the same way to either.
 
+use crate::clock::DateTime;
 use crate::hal::i2c::{I2cAddress, I2cBus, I2cResult};
+use crate::radio::tx_policy::TxVerdict;
 use crate::types::{Band, Frequency, Mode, TuningStep, TxRxState};
-use embassy_stm32::i2c::I2c;
+use crate::ui::redraw::{Geometry, Region, MAX_BUFFER_BYTES};
+use embassy_stm32::i2c::{Error as I2cError, I2c};
 use embassy_stm32::mode::Async;
 use embedded_graphics::mono_font::ascii::FONT_6X10;
 use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
-use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
 use embedded_graphics::text::{Baseline, Text};
 use heapless::String;
+use sdr_dsp_core::conditions::Condition;
+use sdr_dsp_core::units;
 
 /// Display width in pixels
 pub const DISPLAY_WIDTH: u32 = 128;
@@ -44,17 +54,40 @@ mod cmd {
     pub const CHARGE_PUMP: u8 = 0x8D;
 }
 
-/// Display buffer (1 bit per pixel)
+/// Display buffer (1 bit per pixel, SSD1306 page layout)
 pub struct DisplayBuffer {
-    /// Pixel data (128x64 / 8 = 1024 bytes)
-    buffer: [u8; 1024],
+    /// Pixel data (up to 160x128 / 8 = 2560 bytes)
+    buffer: [u8; MAX_BUFFER_BYTES],
+    /// Panel size
+    geometry: Geometry,
 }
 
 impl DisplayBuffer {
-    /// Create a new empty display buffer
+    /// Create a new empty display buffer for the 128x64 SSD1306
     #[must_use]
     pub const fn new() -> Self {
-        Self { buffer: [0; 1024] }
+        Self::with_geometry(Geometry::SSD1306)
+    }
+
+    /// Create a new empty display buffer for a panel
+    ///
+    /// Unsupported geometries fall back to the SSD1306.
+    #[must_use]
+    pub const fn with_geometry(geometry: Geometry) -> Self {
+        Self {
+            buffer: [0; MAX_BUFFER_BYTES],
+            geometry: if geometry.is_supported() {
+                geometry
+            } else {
+                Geometry::SSD1306
+            },
+        }
+    }
+
+    /// Get the panel size
+    #[must_use]
+    pub const fn geometry(&self) -> Geometry {
+        self.geometry
     }
 
     /// Clear the buffer
@@ -64,11 +97,12 @@ impl DisplayBuffer {
 
     /// Set a pixel
     pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
-        if x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT {
+        let width = u32::from(self.geometry.width);
+        if x >= width || y >= u32::from(self.geometry.height) {
             return;
         }
 
-        let byte_idx = (y / 8 * DISPLAY_WIDTH + x) as usize;
+        let byte_idx = (y / 8 * width + x) as usize;
         let bit = 1 << (y % 8);
 
         if on {
@@ -78,10 +112,28 @@ impl DisplayBuffer {
         }
     }
 
+    /// Check if a pixel is set
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        let width = u32::from(self.geometry.width);
+        if x >= width || y >= u32::from(self.geometry.height) {
+            return false;
+        }
+        self.buffer[(y / 8 * width + x) as usize] & (1 << (y % 8)) != 0
+    }
+
     /// Get the raw buffer
     #[must_use]
     pub fn as_bytes(&self) -> &[u8] {
-        &self.buffer
+        &self.buffer[..self.geometry.buffer_len()]
+    }
+
+    /// Get the bytes of one region (a run of columns in one page)
+    #[must_use]
+    pub fn region_bytes(&self, region: Region) -> &[u8] {
+        let range = region.bytes(self.geometry);
+        let len = self.geometry.buffer_len();
+        &self.buffer[range.start.min(len)..range.end.min(len)]
     }
 }
 
@@ -101,11 +153,7 @@ impl DrawTarget for DisplayBuffer {
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
         for Pixel(coord, color) in pixels {
-            if coord.x >= 0
-                && coord.x < DISPLAY_WIDTH as i32
-                && coord.y >= 0
-                && coord.y < DISPLAY_HEIGHT as i32
-            {
+            if coord.x >= 0 && coord.y >= 0 {
                 self.set_pixel(coord.x as u32, coord.y as u32, color.is_on());
             }
         }
@@ -115,10 +163,30 @@ impl DrawTarget for DisplayBuffer {
 
 impl OriginDimensions for DisplayBuffer {
     fn size(&self) -> Size {
-        Size::new(DISPLAY_WIDTH, DISPLAY_HEIGHT)
+        Size::new(u32::from(self.geometry.width), u32::from(self.geometry.height))
     }
 }
 
+/// A display the UI task can draw on
+///
+/// Pages are drawn into [`Panel::buffer_mut`], and the regions the
+/// redraw scheduler picks are sent with [`Panel::flush_region`], one
+/// short transfer at a time.
+#[allow(async_fn_in_trait)]
+pub trait Panel {
+    /// Bus error
+    type Error;
+
+    /// Get the frame buffer
+    fn buffer(&self) -> &DisplayBuffer;
+
+    /// Get the frame buffer for drawing
+    fn buffer_mut(&mut self) -> &mut DisplayBuffer;
+
+    /// Send one region of the frame buffer to the panel
+    async fn flush_region(&mut self, region: Region) -> Result<(), Self::Error>;
+}
+
 /// OLED display driver
 pub struct Display<'d> {
     bus: I2cBus<'d>,
@@ -129,8 +197,14 @@ impl<'d> Display<'d> {
     /// Create a new display driver
     #[must_use] 
     pub fn new(i2c: I2c<'d, Async>) -> Self {
+        Self::with_bus(I2cBus::new(i2c))
+    }
+
+    /// Create a driver on an owned or shared bus
+    #[must_use]
+    pub fn with_bus(bus: I2cBus<'d>) -> Self {
         Self {
-            bus: I2cBus::new(i2c),
+            bus,
             buffer: DisplayBuffer::new(),
         }
     }
@@ -208,12 +282,52 @@ impl<'d> Display<'d> {
         Ok(())
     }
 
+    /// Send one region of the buffer to the display
+    ///
+    /// A region is a run of columns in one page, so it is a single
+    /// window of at most [`DISPLAY_WIDTH`] bytes.
+    #[allow(clippy::cast_possible_truncation)]
+    pub async fn flush_region(&mut self, region: Region) -> I2cResult<()> {
+        if region.width == 0 {
+            return Ok(());
+        }
+        let last_column = region.x + region.width - 1;
+        let commands = [
+            cmd::COLUMN_ADDR,
+            region.x as u8,
+            last_column as u8,
+            cmd::PAGE_ADDR,
+            region.page() as u8,
+            region.page() as u8,
+        ];
+        for &c in &commands {
+            self.send_command(c).await?;
+        }
+
+        for chunk in self.buffer.region_bytes(region).chunks(32) {
+            let mut buf = [0u8; 33];
+            buf[0] = 0x40; // Data mode
+            buf[1..=chunk.len()].copy_from_slice(chunk);
+            self.bus
+                .write(I2cAddress::SSD1306, &buf[..=chunk.len()])
+                .await?;
+        }
+
+        Ok(())
+    }
+
     /// Get mutable access to the buffer for drawing
     #[must_use]
     pub fn buffer_mut(&mut self) -> &mut DisplayBuffer {
         &mut self.buffer
     }
 
+    /// Get the buffer
+    #[must_use]
+    pub const fn buffer(&self) -> &DisplayBuffer {
+        &self.buffer
+    }
+
     /// Clear the display
     pub fn clear(&mut self) {
         self.buffer.clear();
@@ -235,18 +349,32 @@ impl<'d> Display<'d> {
     }
 }
 
+impl Panel for Display<'_> {
+    type Error = I2cError;
+
+    fn buffer(&self) -> &DisplayBuffer {
+        &self.buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut DisplayBuffer {
+        &mut self.buffer
+    }
+
+    async fn flush_region(&mut self, region: Region) -> I2cResult<()> {
+        Display::flush_region(self, region).await
+    }
+}
+
 /// Radio status display renderer
 pub struct StatusRenderer;
 
 impl StatusRenderer {
     /// Render frequency display (large, centered)
     pub fn render_frequency(buffer: &mut DisplayBuffer, freq: Frequency) {
-        let mhz = freq.as_hz() / 1_000_000;
-        let khz = (freq.as_hz() % 1_000_000) / 1000;
-        let hz = freq.as_hz() % 1000;
+        let grouped = units::format_frequency(u64::from(freq.as_hz()));
 
         let mut s: String<16> = String::new();
-        core::fmt::write(&mut s, format_args!("{mhz:2}.{khz:03}.{hz:03}")).ok();
+        core::fmt::write(&mut s, format_args!("{:>10}", grouped.as_str())).ok();
 
         let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
         let _ = Text::with_baseline(&s, Point::new(20, 20), style, Baseline::Top)
@@ -286,6 +414,17 @@ impl StatusRenderer {
             .draw(buffer);
     }
 
+    /// Render band condition rating next to the band indicator
+    pub fn render_conditions(buffer: &mut DisplayBuffer, condition: Option<Condition>) {
+        let Some(condition) = condition else {
+            return;
+        };
+
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        let _ = Text::with_baseline(condition.label(), Point::new(22, 0), style, Baseline::Top)
+            .draw(buffer);
+    }
+
     /// Render TX/RX indicator
     pub fn render_txrx(buffer: &mut DisplayBuffer, state: TxRxState) {
         let (text, invert) = match state {
@@ -353,6 +492,72 @@ impl StatusRenderer {
         let _ = Text::with_baseline("S", Point::new(2, y), style, Baseline::Top).draw(buffer);
     }
 
+    /// Render clock (`HH:MM`, with `z` suffix for UTC)
+    pub fn render_clock(buffer: &mut DisplayBuffer, time: &DateTime, utc: bool) {
+        let mut s: String<8> = String::new();
+        let _ = s.push_str(&time.format_hm());
+        if utc {
+            let _ = s.push('z');
+        }
+
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        let _ = Text::with_baseline(&s, Point::new(92, 54), style, Baseline::Top)
+            .draw(buffer);
+    }
+
+    /// Render the CW tuning indicator
+    ///
+    /// A centre tick marks zero-beat and a block marks the received
+    /// tone's offset from the sidetone, pinned to the ends beyond
+    /// +/-300 Hz. Without a tone only the scale is drawn.
+    pub fn render_tuning(buffer: &mut DisplayBuffer, offset_hz: Option<i16>) {
+        const LEFT: i32 = 34;
+        const HALF_WIDTH: i32 = 26;
+        const SCALE_HZ: i32 = 300;
+        let centre = LEFT + HALF_WIDTH;
+        let stroke = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+        let _ = Line::new(Point::new(LEFT, 62), Point::new(LEFT + 2 * HALF_WIDTH, 62))
+            .into_styled(stroke)
+            .draw(buffer);
+        let _ = Line::new(Point::new(centre, 55), Point::new(centre, 62))
+            .into_styled(stroke)
+            .draw(buffer);
+
+        if let Some(offset) = offset_hz {
+            let shift = (i32::from(offset) * HALF_WIDTH / SCALE_HZ).clamp(-HALF_WIDTH, HALF_WIDTH);
+            let marker = Rectangle::new(Point::new(centre + shift - 1, 56), Size::new(3, 5));
+            let _ = marker
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+        }
+    }
+
+    /// Render a transmit policy warning or denial below the frequency
+    ///
+    /// A denial is drawn inverted, a warning plainly; nothing when clear.
+    pub fn render_tx_notice(buffer: &mut DisplayBuffer, verdict: TxVerdict) {
+        let Some(reason) = verdict.reason() else {
+            return;
+        };
+
+        if verdict.is_denied() {
+            #[allow(clippy::cast_possible_truncation)]
+            let width = 6 * reason.label().len() as u32 + 4;
+            let rect = Rectangle::new(Point::new(18, 30), Size::new(width, 10));
+            let _ = rect
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+
+            let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+            let _ = Text::with_baseline(reason.label(), Point::new(20, 30), style, Baseline::Top)
+                .draw(buffer);
+        } else {
+            let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+            let _ = Text::with_baseline(reason.label(), Point::new(20, 30), style, Baseline::Top)
+                .draw(buffer);
+        }
+    }
+
     /// Render SWR indicator
     pub fn render_swr(buffer: &mut DisplayBuffer, swr: f32) {
         let mut s: String<8> = String::new();
@@ -2,8 +2,12 @@
 //!
 //! Handles rotary encoder input for tuning and menu navigation.
 //! Supports quadrature decoding with debouncing.
+//!
+//! Step acceleration and push button timing come from
+//! [`radio::input`](crate::radio::input), which is tested on the host;
+//! this driver only samples the pins.
 
-use crate::hal::gpio::{ButtonState, EncoderButton};
+use crate::radio::input::{ButtonEvent, PushButton, TuningAcceleration, LONG_PRESS_MS};
 use embassy_stm32::gpio::Input;
 
 /// Encoder rotation direction
@@ -134,95 +138,36 @@ impl Default for QuadratureDecoder {
     }
 }
 
-/// Acceleration curve for fast tuning
-#[derive(Clone, Copy, Debug)]
-pub struct AccelerationCurve {
-    /// Time threshold for acceleration in milliseconds
-    threshold_ms: u32,
-    /// Multiplier when accelerating
-    multiplier: u32,
-    /// Last event timestamp
-    last_event_ms: u32,
-    /// Accumulated steps for acceleration
-    step_count: u32,
-}
-
-impl AccelerationCurve {
-    /// Create a new acceleration curve
-    #[must_use]
-    pub const fn new(threshold_ms: u32, multiplier: u32) -> Self {
-        Self {
-            threshold_ms,
-            multiplier,
-            last_event_ms: 0,
-            step_count: 0,
-        }
-    }
-
-    /// Process a step and return effective step count
-    pub fn process(&mut self, current_ms: u32) -> u32 {
-        let elapsed = current_ms.wrapping_sub(self.last_event_ms);
-        self.last_event_ms = current_ms;
-
-        if elapsed < self.threshold_ms {
-            // Fast rotation - apply acceleration
-            self.step_count = self.step_count.saturating_add(1).min(10);
-            1 + (self.step_count * self.multiplier / 10)
-        } else {
-            // Slow rotation - reset acceleration
-            self.step_count = 0;
-            1
-        }
-    }
-
-    /// Reset acceleration state
-    pub fn reset(&mut self) {
-        self.step_count = 0;
-    }
-}
-
-impl Default for AccelerationCurve {
-    fn default() -> Self {
-        Self::new(50, 5) // Accelerate after 50ms, up to 5x
-    }
-}
-
 /// Complete encoder driver with button
 pub struct Encoder<'d> {
     /// A phase input
     a_pin: Input<'d>,
     /// B phase input
     b_pin: Input<'d>,
-    /// Push button
-    button: EncoderButton<'d>,
+    /// Push button input (active low with pull-up)
+    button_pin: Input<'d>,
+    /// Debounced push button
+    button: PushButton,
     /// Quadrature decoder
     decoder: QuadratureDecoder,
-    /// Acceleration curve
-    acceleration: AccelerationCurve,
-    /// Button press start time for long press detection
-    press_start_ms: Option<u32>,
-    /// Long press threshold in milliseconds
-    long_press_ms: u32,
-    /// Whether long press was triggered
-    long_press_triggered: bool,
+    /// Velocity-based step acceleration
+    acceleration: TuningAcceleration,
 }
 
 impl<'d> Encoder<'d> {
     /// Default long press threshold
-    pub const DEFAULT_LONG_PRESS_MS: u32 = 500;
+    pub const DEFAULT_LONG_PRESS_MS: u32 = LONG_PRESS_MS;
 
     /// Create a new encoder driver
     #[must_use] 
-    pub fn new(a_pin: Input<'d>, b_pin: Input<'d>, button: EncoderButton<'d>) -> Self {
+    pub fn new(a_pin: Input<'d>, b_pin: Input<'d>, button_pin: Input<'d>) -> Self {
         Self {
             a_pin,
             b_pin,
-            button,
+            button_pin,
+            button: PushButton::new(),
             decoder: QuadratureDecoder::new(),
-            acceleration: AccelerationCurve::default(),
-            press_start_ms: None,
-            long_press_ms: Self::DEFAULT_LONG_PRESS_MS,
-            long_press_triggered: false,
+            acceleration: TuningAcceleration::new(),
         }
     }
 
@@ -233,41 +178,18 @@ impl<'d> Encoder<'d> {
         let b = self.b_pin.is_high();
 
         if let Some(direction) = self.decoder.update(a, b) {
-            let steps = self.acceleration.process(current_ms);
+            let steps = self.acceleration.detent(current_ms);
             return Some(EncoderEvent::Rotate { direction, steps });
         }
 
         // Check for button events
-        let button_changed = self.button.update();
-
-        if button_changed {
-            match self.button.state() {
-                ButtonState::Pressed => {
-                    self.press_start_ms = Some(current_ms);
-                    self.long_press_triggered = false;
-                    return Some(EncoderEvent::ButtonPress);
-                }
-                ButtonState::Released => {
-                    self.press_start_ms = None;
-                    if !self.long_press_triggered {
-                        return Some(EncoderEvent::ButtonRelease);
-                    }
-                }
-            }
-        }
-
-        // Check for long press
-        if let Some(start) = self.press_start_ms {
-            if !self.long_press_triggered {
-                let held_ms = current_ms.wrapping_sub(start);
-                if held_ms >= self.long_press_ms {
-                    self.long_press_triggered = true;
-                    return Some(EncoderEvent::LongPress);
-                }
-            }
-        }
-
-        None
+        self.button
+            .update(self.button_pin.is_low(), current_ms)
+            .map(|event| match event {
+                ButtonEvent::Press => EncoderEvent::ButtonPress,
+                ButtonEvent::Release => EncoderEvent::ButtonRelease,
+                ButtonEvent::LongPress => EncoderEvent::LongPress,
+            })
     }
 
     /// Check if button is currently pressed
@@ -278,12 +200,12 @@ impl<'d> Encoder<'d> {
 
     /// Set long press threshold
     pub fn set_long_press_ms(&mut self, ms: u32) {
-        self.long_press_ms = ms;
+        self.button.set_long_press_ms(ms);
     }
 
-    /// Set acceleration parameters
-    pub fn set_acceleration(&mut self, threshold_ms: u32, multiplier: u32) {
-        self.acceleration = AccelerationCurve::new(threshold_ms, multiplier);
+    /// Enable or disable step acceleration (off for menu navigation)
+    pub fn set_acceleration(&mut self, enabled: bool) {
+        self.acceleration.set_enabled(enabled);
     }
 }
 
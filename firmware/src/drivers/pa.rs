@@ -0,0 +1,226 @@
+//! Class-E PA HRTIM Driver
+//!
+//! Drives the H-bridge and the PA supply PWM from HRTIM1, writing the
+//! settings [`PaController`] works out:
+//!
+//! | Output    | Pins        | Function                  |
+//! |-----------|-------------|---------------------------|
+//! | Timer A   | PA8         | PA supply PWM             |
+//! | Timer C   | PB12 / PB13 | bridge leg A high / low   |
+//! | Timer D   | PB14 / PB15 | bridge leg B high / low   |
+//!
+//! Each leg's low switch is the complement of its high switch, with the
+//! timer's dead time generator keeping both off around each edge. Leg A
+//! goes high at the start of the period and leg B at the half period;
+//! timers C and D are started together so the legs stay in antiphase.
+//!
+//! Whenever the controller's interlocks say the PA may not be driven,
+//! the bridge outputs are disabled first (both switches of each leg
+//! idle low) and the supply is then turned down.
+
+use embassy_stm32::hrtim::{AdvancedPwm, ComplementaryPwmPin, PwmPin};
+use embassy_stm32::pac;
+use embassy_stm32::peripherals::{HRTIM1, PA8, PB12, PB13, PB14, PB15};
+use embassy_stm32::Peripheral;
+
+use crate::radio::pa::{BridgeTiming, EnvelopeMode, PaController, PaOutput, SUPPLY_PERIOD};
+use crate::radio::transmit::TxAction;
+use crate::types::Frequency;
+
+/// HRTIM timer index for the supply PWM
+const SUPPLY_TIMER: usize = 0;
+
+/// HRTIM timer index for bridge leg A
+const LEG_A_TIMER: usize = 2;
+
+/// HRTIM timer index for bridge leg B
+const LEG_B_TIMER: usize = 3;
+
+/// Class-E PA driver
+pub struct HrtimPa<'d> {
+    /// Pins and DLL calibration, held so the outputs stay routed
+    _pwm: AdvancedPwm<'d, HRTIM1>,
+    /// Drive planning and interlocks
+    control: PaController,
+    /// Outputs as last written
+    output: PaOutput,
+}
+
+impl<'d> HrtimPa<'d> {
+    /// Configure HRTIM1 with all outputs off
+    pub fn new(
+        hrtim: impl Peripheral<P = HRTIM1> + 'd,
+        supply: impl Peripheral<P = PA8> + 'd,
+        leg_a_high: impl Peripheral<P = PB12> + 'd,
+        leg_a_low: impl Peripheral<P = PB13> + 'd,
+        leg_b_high: impl Peripheral<P = PB14> + 'd,
+        leg_b_low: impl Peripheral<P = PB15> + 'd,
+    ) -> Self {
+        let pwm = AdvancedPwm::new(
+            hrtim,
+            Some(PwmPin::new_cha(supply)),
+            None,
+            None,
+            None,
+            Some(PwmPin::new_chc(leg_a_high)),
+            Some(ComplementaryPwmPin::new_chc(leg_a_low)),
+            Some(PwmPin::new_chd(leg_b_high)),
+            Some(ComplementaryPwmPin::new_chd(leg_b_low)),
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let regs = pac::HRTIM1;
+        disable_outputs();
+
+        // Supply: set at the period, reset at compare 1
+        let tim = regs.tim(SUPPLY_TIMER);
+        tim.cr().modify(|w| w.set_cont(true));
+        tim.per().modify(|w| w.set_per(SUPPLY_PERIOD));
+        tim.cmp(0).modify(|w| w.set_cmp(0));
+        tim.setr(0).modify(|w| w.set_per(true));
+        tim.rstr(0).modify(|w| w.set_cmp(0, true));
+
+        // Legs: A high for the first half period, B for the second
+        for (index, high_first) in [(LEG_A_TIMER, true), (LEG_B_TIMER, false)] {
+            let tim = regs.tim(index);
+            tim.cr().modify(|w| w.set_cont(true));
+            tim.outr().modify(|w| w.set_dten(true));
+            tim.setr(0).modify(|w| {
+                w.set_per(high_first);
+                w.set_cmp(0, !high_first);
+            });
+            tim.rstr(0).modify(|w| {
+                w.set_cmp(0, high_first);
+                w.set_per(!high_first);
+            });
+        }
+
+        regs.mcr().modify(|w| {
+            w.set_tcen(SUPPLY_TIMER, true);
+            w.set_tcen(LEG_A_TIMER, true);
+            w.set_tcen(LEG_B_TIMER, true);
+        });
+
+        Self {
+            _pwm: pwm,
+            control: PaController::new(),
+            output: PaOutput::OFF,
+        }
+    }
+
+    /// Get the drive planner
+    #[must_use]
+    pub const fn controller(&self) -> &PaController {
+        &self.control
+    }
+
+    /// Set the carrier frequency
+    ///
+    /// Returns `false` if it is out of the HRTIM's range, which stops
+    /// the drive.
+    pub fn set_carrier(&mut self, carrier: Frequency) -> bool {
+        let ok = self.control.set_carrier(carrier);
+        self.write(self.control.output(1.0));
+        ok
+    }
+
+    /// Set how the supply follows the transmit signal
+    pub fn set_mode(&mut self, mode: EnvelopeMode) {
+        self.control.set_mode(mode);
+        self.write(self.control.output(1.0));
+    }
+
+    /// Apply a transmit controller action
+    ///
+    /// Returns `true` if the bridge is being driven afterwards.
+    pub fn apply(&mut self, action: TxAction) -> bool {
+        let output = self.control.apply(action);
+        self.write(output);
+        self.control.is_driving()
+    }
+
+    /// Follow an envelope sample (0.0 to 1.0) in polar mode
+    pub fn set_envelope(&mut self, envelope: f32) {
+        self.write(self.control.output(envelope));
+    }
+
+    /// Stop the drive immediately
+    pub fn stop(&mut self) {
+        self.apply(TxAction::DisablePa);
+    }
+
+    /// Write changed outputs, bridge off before supply down and supply
+    /// up before bridge on
+    fn write(&mut self, output: PaOutput) {
+        if output == self.output {
+            return;
+        }
+        let regs = pac::HRTIM1;
+        match output.bridge {
+            None => {
+                disable_outputs();
+                regs.tim(SUPPLY_TIMER).cmp(0).modify(|w| w.set_cmp(0));
+            }
+            Some(timing) => {
+                regs.tim(SUPPLY_TIMER).cmp(0).modify(|w| w.set_cmp(output.supply));
+                if self.output.bridge != Some(timing) {
+                    program_bridge(&timing);
+                }
+                if self.output.bridge.is_none() {
+                    enable_outputs();
+                }
+            }
+        }
+        self.output = output;
+    }
+}
+
+impl Drop for HrtimPa<'_> {
+    fn drop(&mut self) {
+        disable_outputs();
+    }
+}
+
+/// Write the carrier period, half period and dead time to both legs
+fn program_bridge(timing: &BridgeTiming) {
+    let regs = pac::HRTIM1;
+    for index in [LEG_A_TIMER, LEG_B_TIMER] {
+        let tim = regs.tim(index);
+        tim.per().modify(|w| w.set_per(timing.period()));
+        tim.cmp(0).modify(|w| w.set_cmp(timing.half_period()));
+        tim.dt().modify(|w| {
+            w.set_dtr(timing.dead_time());
+            w.set_dtf(timing.dead_time());
+        });
+    }
+    // Restart both legs together so they stay in antiphase
+    regs.cr2().modify(|w| {
+        w.set_trst(LEG_A_TIMER, true);
+        w.set_trst(LEG_B_TIMER, true);
+    });
+}
+
+/// Route the supply and both legs to their pins
+fn enable_outputs() {
+    pac::HRTIM1.oenr().write(|w| {
+        w.set_t1oen(SUPPLY_TIMER, true);
+        for index in [LEG_A_TIMER, LEG_B_TIMER] {
+            w.set_t1oen(index, true);
+            w.set_t2oen(index, true);
+        }
+    });
+}
+
+/// Put every output in its idle (low) state
+fn disable_outputs() {
+    pac::HRTIM1.odisr().write(|w| {
+        w.set_t1odis(SUPPLY_TIMER, true);
+        for index in [LEG_A_TIMER, LEG_B_TIMER] {
+            w.set_t1odis(index, true);
+            w.set_t2odis(index, true);
+        }
+    });
+}
@@ -0,0 +1,203 @@
+//! T/R Relay and Band Filter Driver
+//!
+//! Drives the T/R relay and the LPF relays as [`RelaySequencer`] asks,
+//! either straight from GPIO (T/R on its own pin, the filter bank as a
+//! 3-bit code for the decoder on the filter board) or through a 74HC595
+//! shift register with one output per relay:
+//!
+//! | Bit | Relay        |
+//! |-----|--------------|
+//! | 0-4 | LPF bank 0-4 |
+//! | 7   | T/R          |
+//!
+//! An optional auxiliary contact on the T/R relay (closed in transmit,
+//! pulled up, so low when closed) lets the sequencer spot a stuck relay.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let action = tx.update(elapsed_us);
+//! pa.apply(relays.apply(action));
+//! pa.apply(relays.update(elapsed_us));
+//! if relays.fault().is_some() {
+//!     tx.set_inhibit(true);
+//! }
+//! ```
+
+use embassy_stm32::gpio::{Input, Output};
+
+use crate::hal::gpio::{LpfSelector, TrRelay};
+use crate::radio::relay::{RelayFault, RelayOutputs, RelaySense, RelaySequencer, RelayTiming};
+use crate::radio::transmit::TxAction;
+use crate::types::Band;
+
+/// 74HC595 relay driver, bit-banged
+pub struct ShiftRegister<'d> {
+    /// Serial data (DS)
+    data: Output<'d>,
+    /// Shift clock (SHCP)
+    clock: Output<'d>,
+    /// Storage latch (STCP)
+    latch: Output<'d>,
+}
+
+impl<'d> ShiftRegister<'d> {
+    /// Create from the data, clock and latch pins
+    #[must_use]
+    pub fn new(data: Output<'d>, clock: Output<'d>, latch: Output<'d>) -> Self {
+        Self { data, clock, latch }
+    }
+
+    /// Shift out a byte, MSB first, and latch it onto the outputs
+    pub fn write(&mut self, word: u8) {
+        self.latch.set_low();
+        for bit in (0..8).rev() {
+            if word & (1 << bit) != 0 {
+                self.data.set_high();
+            } else {
+                self.data.set_low();
+            }
+            self.clock.set_high();
+            self.clock.set_low();
+        }
+        self.latch.set_high();
+    }
+}
+
+/// How the relays are wired
+enum RelayPins<'d> {
+    /// T/R pin and 3-bit filter select
+    Gpio {
+        /// T/R relay
+        tr: TrRelay<'d>,
+        /// Filter bank select
+        lpf: LpfSelector<'d>,
+    },
+    /// One shift register output per relay
+    ShiftRegister(ShiftRegister<'d>),
+}
+
+/// T/R relay and band filter driver
+pub struct RelayDriver<'d> {
+    /// Relay outputs
+    pins: RelayPins<'d>,
+    /// T/R auxiliary contact, if fitted
+    tr_sense: Option<Input<'d>>,
+    /// Sequencing and fault detection
+    sequencer: RelaySequencer,
+    /// Outputs as last written
+    written: RelayOutputs,
+}
+
+impl<'d> RelayDriver<'d> {
+    /// Create a driver for GPIO-wired relays
+    #[must_use]
+    pub fn new_gpio(
+        tr: TrRelay<'d>,
+        lpf: LpfSelector<'d>,
+        tr_sense: Option<Input<'d>>,
+        timing: RelayTiming,
+    ) -> Self {
+        Self::new(RelayPins::Gpio { tr, lpf }, tr_sense, timing)
+    }
+
+    /// Create a driver for relays on a shift register
+    #[must_use]
+    pub fn new_shift_register(
+        register: ShiftRegister<'d>,
+        tr_sense: Option<Input<'d>>,
+        timing: RelayTiming,
+    ) -> Self {
+        Self::new(RelayPins::ShiftRegister(register), tr_sense, timing)
+    }
+
+    /// Create and drive the initial (receive, bank 0) outputs
+    fn new(pins: RelayPins<'d>, tr_sense: Option<Input<'d>>, timing: RelayTiming) -> Self {
+        let sequencer = RelaySequencer::new(timing);
+        let mut driver = Self {
+            pins,
+            tr_sense,
+            sequencer,
+            written: sequencer.outputs(),
+        };
+        driver.write(sequencer.outputs());
+        driver
+    }
+
+    /// Get the sequencer
+    #[must_use]
+    pub const fn sequencer(&self) -> &RelaySequencer {
+        &self.sequencer
+    }
+
+    /// Select the filter for a band
+    ///
+    /// Returns `false` if it is deferred until the end of the over.
+    pub fn select_band(&mut self, band: Band) -> bool {
+        let now = self.sequencer.select_band(band);
+        self.sync();
+        now
+    }
+
+    /// Apply a transmit controller action
+    ///
+    /// Returns the action to pass on to the PA.
+    pub fn apply(&mut self, action: TxAction) -> TxAction {
+        let action = self.sequencer.apply(action);
+        self.sync();
+        action
+    }
+
+    /// Advance the settle timers and check the relays
+    ///
+    /// Returns the action to pass on to the PA.
+    pub fn update(&mut self, elapsed_us: u32) -> TxAction {
+        let sense = RelaySense {
+            tr: self.tr_sense.as_ref().map(Input::is_low),
+            lpf: None,
+        };
+        let faults = self.sequencer.fault_count();
+        let action = self.sequencer.update(elapsed_us, sense);
+        if self.sequencer.fault_count() != faults {
+            if let Some(fault) = self.sequencer.fault() {
+                defmt::error!("Relay fault: {}", fault);
+            }
+        }
+        action
+    }
+
+    /// Get the latched fault
+    #[must_use]
+    pub const fn fault(&self) -> Option<RelayFault> {
+        self.sequencer.fault()
+    }
+
+    /// Clear a latched fault
+    pub fn clear_fault(&mut self) {
+        self.sequencer.clear_fault();
+    }
+
+    /// Write the sequencer's outputs if they changed
+    fn sync(&mut self) {
+        let outputs = self.sequencer.outputs();
+        if outputs != self.written {
+            self.write(outputs);
+        }
+    }
+
+    /// Drive the relays
+    fn write(&mut self, outputs: RelayOutputs) {
+        match &mut self.pins {
+            RelayPins::Gpio { tr, lpf } => {
+                lpf.select(outputs.lpf);
+                if outputs.tr {
+                    tr.set_tx();
+                } else {
+                    tr.set_rx();
+                }
+            }
+            RelayPins::ShiftRegister(register) => register.write(outputs.as_shift_word()),
+        }
+        self.written = outputs;
+    }
+}
@@ -0,0 +1,269 @@
+//! SPI NOR Flash Driver
+//!
+//! Driver for W25Q-series serial NOR flash on SPI3. Implements
+//! [`BlockDevice`] so the flash can back the
+//! [`FileStore`](crate::storage::FileStore) used for logs, captures and
+//! voice messages.
+//!
+//! The 64-pin STM32G474 cannot give the QUADSPI bank its data pins
+//! without taking the I/Q ADC inputs, so the flash runs in single-line
+//! mode with a GPIO chip select. Operations are blocking and poll the
+//! status register for completion.
+
+use crate::config::{FLASH_SECTOR_SIZE, FLASH_SIZE};
+use crate::storage::BlockDevice;
+use embassy_stm32::gpio::Output;
+use embassy_stm32::mode::Blocking;
+use embassy_stm32::spi::Spi;
+
+/// W25Q command opcodes
+mod cmd {
+    pub const WRITE_ENABLE: u8 = 0x06;
+    pub const READ_STATUS1: u8 = 0x05;
+    pub const PAGE_PROGRAM: u8 = 0x02;
+    pub const SECTOR_ERASE: u8 = 0x20;
+    pub const FAST_READ: u8 = 0x0B;
+    pub const JEDEC_ID: u8 = 0x9F;
+    pub const RESET_ENABLE: u8 = 0x66;
+    pub const RESET: u8 = 0x99;
+}
+
+/// Status register 1: write in progress
+const STATUS_BUSY: u8 = 0x01;
+
+/// Program page size in bytes
+const PAGE_SIZE: u32 = 256;
+
+/// Status polls before giving up (sector erase is up to 400 ms)
+const MAX_BUSY_POLLS: u32 = 2_000_000;
+
+/// SPI flash errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpiFlashError {
+    /// Address outside the flash array
+    OutOfRange,
+    /// Device stayed busy too long
+    Timeout,
+    /// SPI transfer failed
+    Bus,
+}
+
+impl defmt::Format for SpiFlashError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::OutOfRange => defmt::write!(f, "OutOfRange"),
+            Self::Timeout => defmt::write!(f, "Timeout"),
+            Self::Bus => defmt::write!(f, "Bus"),
+        }
+    }
+}
+
+/// JEDEC identification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JedecId {
+    /// Manufacturer ID (0xEF = Winbond)
+    pub manufacturer: u8,
+    /// Memory type
+    pub memory_type: u8,
+    /// Capacity code (log2 of size in bytes)
+    pub capacity: u8,
+}
+
+impl JedecId {
+    /// Get flash size in bytes from the capacity code
+    #[must_use]
+    pub const fn size_bytes(self) -> u32 {
+        if self.capacity >= 32 {
+            0
+        } else {
+            1 << self.capacity
+        }
+    }
+}
+
+impl defmt::Format for JedecId {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "JEDEC({:02X} {:02X} {:02X})",
+            self.manufacturer,
+            self.memory_type,
+            self.capacity
+        );
+    }
+}
+
+/// SPI NOR flash driver
+pub struct SpiFlash<'d> {
+    spi: Spi<'d, Blocking>,
+    cs: Output<'d>,
+    size: u32,
+}
+
+impl<'d> SpiFlash<'d> {
+    /// Create a driver for the configured flash size
+    ///
+    /// `cs` must start high (deselected).
+    #[must_use]
+    pub fn new(spi: Spi<'d, Blocking>, cs: Output<'d>) -> Self {
+        Self {
+            spi,
+            cs,
+            size: FLASH_SIZE,
+        }
+    }
+
+    /// Reset the device and read its JEDEC ID
+    ///
+    /// The usable size is taken from the ID if it reports one. A bus
+    /// error reads as an all-zero ID.
+    pub fn init(&mut self) -> JedecId {
+        let _ = self.command(cmd::RESET_ENABLE);
+        let _ = self.command(cmd::RESET);
+
+        let id = self.read_id().unwrap_or(JedecId {
+            manufacturer: 0,
+            memory_type: 0,
+            capacity: 0,
+        });
+        let size = id.size_bytes();
+        if size > 0 {
+            self.size = size;
+        }
+        id
+    }
+
+    /// Read the JEDEC ID
+    ///
+    /// # Errors
+    /// Returns [`SpiFlashError::Bus`] if the transfer fails
+    pub fn read_id(&mut self) -> Result<JedecId, SpiFlashError> {
+        let mut buf = [0u8; 3];
+        self.transaction(&[cmd::JEDEC_ID], |spi| spi.blocking_read(&mut buf))?;
+        Ok(JedecId {
+            manufacturer: buf[0],
+            memory_type: buf[1],
+            capacity: buf[2],
+        })
+    }
+
+    /// Get flash size in bytes
+    #[must_use]
+    pub const fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Run one chip-selected transaction: a header, then `body`
+    fn transaction(
+        &mut self,
+        header: &[u8],
+        body: impl FnOnce(&mut Spi<'d, Blocking>) -> Result<(), embassy_stm32::spi::Error>,
+    ) -> Result<(), SpiFlashError> {
+        self.cs.set_low();
+        let result = self
+            .spi
+            .blocking_write(header)
+            .and_then(|()| body(&mut self.spi));
+        self.cs.set_high();
+        result.map_err(|_| SpiFlashError::Bus)
+    }
+
+    /// Issue an instruction-only command
+    fn command(&mut self, instruction: u8) -> Result<(), SpiFlashError> {
+        self.transaction(&[instruction], |_| Ok(()))
+    }
+
+    /// Read status register 1
+    fn read_status(&mut self) -> Result<u8, SpiFlashError> {
+        let mut status = [0u8];
+        self.transaction(&[cmd::READ_STATUS1], |spi| spi.blocking_read(&mut status))?;
+        Ok(status[0])
+    }
+
+    /// Wait for the current program/erase to finish
+    fn wait_ready(&mut self) -> Result<(), SpiFlashError> {
+        for _ in 0..MAX_BUSY_POLLS {
+            if self.read_status()? & STATUS_BUSY == 0 {
+                return Ok(());
+            }
+        }
+        Err(SpiFlashError::Timeout)
+    }
+
+    /// Check that a range lies inside the array
+    fn check_range(&self, addr: u32, len: usize) -> Result<(), SpiFlashError> {
+        let end = u64::from(addr) + len as u64;
+        if end > u64::from(self.size) {
+            Err(SpiFlashError::OutOfRange)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Instruction followed by a 24-bit address
+fn addressed(instruction: u8, addr: u32) -> [u8; 4] {
+    let [_, a2, a1, a0] = addr.to_be_bytes();
+    [instruction, a2, a1, a0]
+}
+
+impl BlockDevice for SpiFlash<'_> {
+    type Error = SpiFlashError;
+
+    fn block_size(&self) -> u32 {
+        FLASH_SECTOR_SIZE
+    }
+
+    fn block_count(&self) -> u32 {
+        self.size / FLASH_SECTOR_SIZE
+    }
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_range(addr, buf.len())?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        // Fast read takes one dummy byte after the address
+        let [i, a2, a1, a0] = addressed(cmd::FAST_READ, addr);
+        self.transaction(&[i, a2, a1, a0, 0], |spi| spi.blocking_read(buf))
+    }
+
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), Self::Error> {
+        self.check_range(addr, data.len())?;
+
+        let mut addr = addr;
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            // Page program must not cross a page boundary
+            let room = (PAGE_SIZE - addr % PAGE_SIZE) as usize;
+            let n = remaining.len().min(room);
+
+            self.command(cmd::WRITE_ENABLE)?;
+            let page = &remaining[..n];
+            self.transaction(&addressed(cmd::PAGE_PROGRAM, addr), |spi| {
+                spi.blocking_write(page)
+            })?;
+            self.wait_ready()?;
+
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                addr += n as u32;
+            }
+            remaining = &remaining[n..];
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, block: u32) -> Result<(), Self::Error> {
+        if block >= self.block_count() {
+            return Err(SpiFlashError::OutOfRange);
+        }
+
+        self.command(cmd::WRITE_ENABLE)?;
+        self.transaction(
+            &addressed(cmd::SECTOR_ERASE, block * FLASH_SECTOR_SIZE),
+            |_| Ok(()),
+        )?;
+        self.wait_ready()
+    }
+}
@@ -0,0 +1,239 @@
+//! ST7735 Colour LCD Driver
+//!
+//! Drives a 1.8" 160x128 ST7735 TFT over SPI in landscape. The UI still
+//! draws into a 1 bpp [`DisplayBuffer`]; regions are expanded to RGB565
+//! in foreground and background colours a row at a time as they are
+//! sent, so the panel needs no 40 KiB colour frame buffer.
+
+use crate::drivers::display::{DisplayBuffer, Panel};
+use crate::ui::redraw::{Geometry, Region};
+use embassy_stm32::gpio::Output;
+use embassy_stm32::mode::Async;
+use embassy_stm32::spi::{Error as SpiError, Spi};
+use embassy_time::{Duration, Timer};
+
+/// Result type for LCD operations
+pub type LcdResult<T> = Result<T, SpiError>;
+
+/// ST7735 commands
+mod cmd {
+    pub const SWRESET: u8 = 0x01;
+    pub const SLPOUT: u8 = 0x11;
+    pub const NORON: u8 = 0x13;
+    pub const INVOFF: u8 = 0x20;
+    pub const INVON: u8 = 0x21;
+    pub const DISPOFF: u8 = 0x28;
+    pub const DISPON: u8 = 0x29;
+    pub const CASET: u8 = 0x2A;
+    pub const RASET: u8 = 0x2B;
+    pub const RAMWR: u8 = 0x2C;
+    pub const MADCTL: u8 = 0x36;
+    pub const COLMOD: u8 = 0x3A;
+    pub const FRMCTR1: u8 = 0xB1;
+}
+
+/// Memory access control: row/column exchange and column mirror for
+/// landscape, BGR panel
+const MADCTL_LANDSCAPE: u8 = 0x60 | 0x08;
+
+/// Interface pixel format: 16 bits per pixel
+const COLMOD_RGB565: u8 = 0x05;
+
+/// RGB565 colour
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb565(pub u16);
+
+impl Rgb565 {
+    /// Black
+    pub const BLACK: Self = Self(0x0000);
+    /// White
+    pub const WHITE: Self = Self(0xFFFF);
+    /// Amber, easy on night-adapted eyes
+    pub const AMBER: Self = Self::from_rgb(255, 176, 0);
+    /// Green
+    pub const GREEN: Self = Self::from_rgb(0, 255, 64);
+
+    /// Create from 8-bit components
+    #[must_use]
+    pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self(((r as u16 & 0xF8) << 8) | ((g as u16 & 0xFC) << 3) | (b as u16 >> 3))
+    }
+
+    /// Get the big-endian bytes the panel expects
+    #[must_use]
+    pub const fn to_be_bytes(self) -> [u8; 2] {
+        self.0.to_be_bytes()
+    }
+}
+
+/// ST7735 LCD driver
+pub struct St7735<'d> {
+    spi: Spi<'d, Async>,
+    dc: Output<'d>,
+    cs: Output<'d>,
+    rst: Output<'d>,
+    buffer: DisplayBuffer,
+    foreground: Rgb565,
+    background: Rgb565,
+}
+
+impl<'d> St7735<'d> {
+    /// Create a new LCD driver
+    #[must_use]
+    pub fn new(spi: Spi<'d, Async>, dc: Output<'d>, cs: Output<'d>, rst: Output<'d>) -> Self {
+        Self {
+            spi,
+            dc,
+            cs,
+            rst,
+            buffer: DisplayBuffer::with_geometry(Geometry::ST7735),
+            foreground: Rgb565::AMBER,
+            background: Rgb565::BLACK,
+        }
+    }
+
+    /// Reset and initialize the panel
+    pub async fn init(&mut self) -> LcdResult<()> {
+        // Hardware reset
+        self.cs.set_high();
+        self.rst.set_low();
+        Timer::after(Duration::from_millis(10)).await;
+        self.rst.set_high();
+        Timer::after(Duration::from_millis(120)).await;
+
+        self.command(cmd::SWRESET, &[]).await?;
+        Timer::after(Duration::from_millis(150)).await;
+        self.command(cmd::SLPOUT, &[]).await?;
+        Timer::after(Duration::from_millis(120)).await;
+
+        // Frame rate: fosc / (1 + 20) / (160 + 44)
+        self.command(cmd::FRMCTR1, &[0x01, 0x2C, 0x2D]).await?;
+        self.command(cmd::COLMOD, &[COLMOD_RGB565]).await?;
+        self.command(cmd::MADCTL, &[MADCTL_LANDSCAPE]).await?;
+        self.command(cmd::INVOFF, &[]).await?;
+        self.command(cmd::NORON, &[]).await?;
+        self.command(cmd::DISPON, &[]).await?;
+
+        // Clear the panel
+        self.buffer.clear();
+        self.flush().await
+    }
+
+    /// Set the colours lit and unlit pixels are drawn in
+    ///
+    /// Takes effect as regions are next sent; invalidate the redraw
+    /// scheduler to repaint the whole panel.
+    pub fn set_colors(&mut self, foreground: Rgb565, background: Rgb565) {
+        self.foreground = foreground;
+        self.background = background;
+    }
+
+    /// Send the whole buffer to the panel
+    pub async fn flush(&mut self) -> LcdResult<()> {
+        let geometry = self.buffer.geometry();
+        for page in 0..geometry.pages() {
+            self.flush_region(Region {
+                x: 0,
+                y: page * 8,
+                width: geometry.width,
+                height: 8,
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Send one region of the buffer to the panel
+    pub async fn flush_region(&mut self, region: Region) -> LcdResult<()> {
+        if region.width == 0 || region.height == 0 {
+            return Ok(());
+        }
+        let x_end = region.x + region.width - 1;
+        let y_end = region.y + region.height - 1;
+        let [x0h, x0l] = region.x.to_be_bytes();
+        let [x1h, x1l] = x_end.to_be_bytes();
+        let [y0h, y0l] = region.y.to_be_bytes();
+        let [y1h, y1l] = y_end.to_be_bytes();
+        self.command(cmd::CASET, &[x0h, x0l, x1h, x1l]).await?;
+        self.command(cmd::RASET, &[y0h, y0l, y1h, y1l]).await?;
+        self.command(cmd::RAMWR, &[]).await?;
+
+        // Expand a row at a time
+        let mut line = [0u8; Geometry::ST7735.width as usize * 2];
+        self.dc.set_high();
+        self.cs.set_low();
+        let mut result = Ok(());
+        for y in region.y..=y_end {
+            for (i, x) in (region.x..=x_end).enumerate() {
+                let color = if self.buffer.pixel(u32::from(x), u32::from(y)) {
+                    self.foreground
+                } else {
+                    self.background
+                };
+                line[i * 2..i * 2 + 2].copy_from_slice(&color.to_be_bytes());
+            }
+            result = self
+                .spi
+                .write(&line[..usize::from(region.width) * 2])
+                .await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.cs.set_high();
+        result
+    }
+
+    /// Turn the panel on or off
+    pub async fn set_enabled(&mut self, enabled: bool) -> LcdResult<()> {
+        let c = if enabled { cmd::DISPON } else { cmd::DISPOFF };
+        self.command(c, &[]).await
+    }
+
+    /// Invert display colors
+    pub async fn invert(&mut self, invert: bool) -> LcdResult<()> {
+        let c = if invert { cmd::INVON } else { cmd::INVOFF };
+        self.command(c, &[]).await
+    }
+
+    /// Get mutable access to the buffer for drawing
+    #[must_use]
+    pub fn buffer_mut(&mut self) -> &mut DisplayBuffer {
+        &mut self.buffer
+    }
+
+    /// Get the buffer
+    #[must_use]
+    pub const fn buffer(&self) -> &DisplayBuffer {
+        &self.buffer
+    }
+
+    /// Send a command and its parameters
+    async fn command(&mut self, command: u8, params: &[u8]) -> LcdResult<()> {
+        self.cs.set_low();
+        self.dc.set_low();
+        let mut result = self.spi.write(&[command]).await;
+        if result.is_ok() && !params.is_empty() {
+            self.dc.set_high();
+            result = self.spi.write(params).await;
+        }
+        self.cs.set_high();
+        result
+    }
+}
+
+impl Panel for St7735<'_> {
+    type Error = SpiError;
+
+    fn buffer(&self) -> &DisplayBuffer {
+        &self.buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut DisplayBuffer {
+        &mut self.buffer
+    }
+
+    async fn flush_region(&mut self, region: Region) -> LcdResult<()> {
+        St7735::flush_region(self, region).await
+    }
+}
@@ -4,15 +4,26 @@
 //! - FIR/IIR filters for audio processing
 //! - Hilbert transform for SSB generation
 //! - AGC (Automatic Gain Control)
-//! - CW tone generation
-//! - Audio processing chain
+//! - CW tone generation and keying envelope shaping
+//! - Audio processing chain with per-stage bypass
+//! - Speech processor (compressor, limiter, ALC) for transmit
+//! - Five-band graphic equalizer for receive and transmit audio
+//! - DTMF digit detection for remote control over a link
+//! - Receive pipeline from I/Q ADC blocks to audio for the DSP task
 
 pub mod filter;
 pub mod agc;
 pub mod oscillator;
+pub mod keying;
+pub mod bypass;
 pub mod modulation;
 pub mod si5351_calc;
 pub mod filter_design;
 pub mod audio_chain;
 pub mod noise_reduction;
+pub mod speech;
+pub mod equalizer;
+pub mod dtmf;
 pub mod spectrum;
+pub mod pipeline;
+pub mod iq_capture;
@@ -5,6 +5,8 @@
 
 #[cfg(feature = "embedded")]
 use micromath::F32Ext;
+use sdr_dsp_core::agc::{SmeterCalibration, SmeterReading};
+use sdr_dsp_core::units;
 
 /// AGC configuration
 #[derive(Clone, Copy, Debug)]
@@ -188,6 +190,10 @@ impl Default for Agc {
 }
 
 /// S-meter reading derived from AGC
+///
+/// Readings are IARU S-units (S9 = -73 dBm, 6 dB per unit). The nominal
+/// level from the AGC gain or the raw detector is corrected by a
+/// [`SmeterCalibration`], normally the one stored for the current band.
 #[derive(Clone, Copy, Debug)]
 pub struct SMeter {
     /// Current S-meter value (0-9, then +10, +20, etc.)
@@ -196,6 +202,8 @@ pub struct SMeter {
     smoothed: f32,
     /// Smoothing coefficient
     alpha: f32,
+    /// Calibration applied to each nominal reading
+    calibration: SmeterCalibration,
 }
 
 impl SMeter {
@@ -206,9 +214,28 @@ impl SMeter {
             value: 0.0,
             smoothed: 0.0,
             alpha: 0.1,
+            calibration: SmeterCalibration::IDENTITY,
         }
     }
 
+    /// Set the calibration (e.g. on band change)
+    pub fn set_calibration(&mut self, calibration: SmeterCalibration) {
+        self.calibration = calibration;
+    }
+
+    /// Get the calibration
+    #[must_use]
+    pub const fn calibration(&self) -> SmeterCalibration {
+        self.calibration
+    }
+
+    /// Apply the calibration to a nominal S-unit value and smooth it
+    fn update_s_units(&mut self, nominal: f32) {
+        let dbm = self.calibration.apply(units::s_units_to_dbm(nominal));
+        self.value = units::dbm_to_s_units(dbm).clamp(0.0, 15.0); // S0 to S9+36
+        self.smoothed += self.alpha * (self.value - self.smoothed);
+    }
+
     /// Update from AGC gain (inverse relationship)
     pub fn update_from_agc(&mut self, agc: &Agc) {
         // S-meter is inversely related to AGC gain
@@ -218,19 +245,13 @@ impl SMeter {
 
         // Map to S-units (approximate)
         // S1 = -121 dBm, S9 = -73 dBm, +60 = -13 dBm
-        let s_value = (signal_db + 121.0) / 6.0;
-        self.value = s_value.clamp(0.0, 15.0); // S0 to S9+60
-
-        // Apply smoothing
-        self.smoothed += self.alpha * (self.value - self.smoothed);
+        self.update_s_units((signal_db + 121.0) / 6.0);
     }
 
     /// Update from raw signal level
     pub fn update_from_level(&mut self, level: f32) {
         let db = 20.0 * (level.max(0.00001)).log10();
-        let s_value = (db + 80.0) / 6.0; // Approximate mapping
-        self.value = s_value.clamp(0.0, 15.0);
-        self.smoothed += self.alpha * (self.value - self.smoothed);
+        self.update_s_units((db + 80.0) / 6.0); // Approximate mapping
     }
 
     /// Get smoothed S-meter value
@@ -239,6 +260,12 @@ impl SMeter {
         self.smoothed
     }
 
+    /// Get the calibrated signal level in dBm
+    #[must_use]
+    pub fn dbm(&self) -> f32 {
+        units::s_units_to_dbm(self.smoothed)
+    }
+
     /// Get S-meter as integer (S-units)
     #[must_use]
     pub fn s_units(&self) -> u8 {
@@ -255,6 +282,12 @@ impl SMeter {
         }
     }
 
+    /// Get as a meter reading (`S7`, `S9+20`)
+    #[must_use]
+    pub fn reading(&self) -> SmeterReading {
+        units::s_units_to_reading(self.smoothed)
+    }
+
     /// Get as percentage (0-100)
     #[must_use]
     pub fn as_percent(&self) -> u8 {
@@ -271,12 +304,6 @@ impl Default for SMeter {
 #[cfg(feature = "embedded")]
 impl defmt::Format for SMeter {
     fn format(&self, f: defmt::Formatter) {
-        let s = self.s_units();
-        let db = self.db_over_s9();
-        if db > 0 {
-            defmt::write!(f, "S9+{}", db);
-        } else {
-            defmt::write!(f, "S{}", s);
-        }
+        defmt::write!(f, "{}", self.reading().to_string().as_str());
     }
 }
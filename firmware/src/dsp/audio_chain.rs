@@ -2,16 +2,150 @@
 //!
 //! Integrates filters, AGC, and other DSP elements into a complete
 //! receive audio processing pipeline for each modulation mode.
+//!
+//! The CW sidetone is mixed in after the volume control with its own
+//! level, so the operator hears their keying while the receiver is
+//! muted for transmit and independently of the receive volume.
+//!
+//! The noise blanker, noise reduction, notch and AGC stages can each be
+//! bypassed at runtime for A/B comparison; see [`super::bypass`].
+//!
+//! Two manual notches, each with its own frequency and width, can null
+//! two carriers at once; they share the notch bypass switch.
+//!
+//! In SSB and CW the filter passband can be narrowed, re-centered or
+//! slid by an IF shift to move it off an interfering signal without
+//! retuning the dial.
+//!
+//! A five-band graphic [`Equalizer`] shapes the leveled audio after the
+//! AGC; it is off by default and its curve is set per mode by the
+//! caller (see [`super::equalizer::EqPresets`]).
+//!
+//! An [`SnrMeter`] watches the filtered audio, ahead of noise reduction,
+//! and reports the signal power, noise floor and SNR in the passband. A
+//! [`PitchMeter`] watches the same audio and reports how far the received
+//! tone is from the sidetone pitch in CW, for the tuning indicator, or
+//! from the passband centre in SSB, for auto-tune.
+//!
+//! [`TxAudioChain`] is the transmit counterpart: microphone high-pass,
+//! speech compressor, two-band TX tone shelves, the five-band TX
+//! equalizer and an ALC loop around the SSB modulator.
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+use sdr_dsp_core::pitch::PitchMeter;
+use sdr_dsp_core::snr::{SnrMeter, SnrReading};
 
 use super::agc::{Agc, AgcConfig, SMeter};
+use super::bypass::{BypassSet, DspStage};
+use super::equalizer::Equalizer;
 use super::filter_design::{
     design_am_filter, design_cw_filter, design_dc_blocker, design_ssb_filter,
-    design_deemphasis_filter, AmBandwidth, Biquad, CwBandwidth, SsbBandwidth,
+    design_deemphasis_filter, AmBandwidth, Biquad, BiquadCoeffs, CwBandwidth, SsbBandwidth,
 };
+use super::modulation::{IqSample, SsbModulator};
+use super::noise_reduction::NoiseReductionChain;
+use super::oscillator::CwToneGenerator;
+use super::speech::{Alc, Compressor};
 
 /// Sample rate used by the audio chain
 pub const AUDIO_SAMPLE_RATE: f32 = 48000.0;
 
+/// Default sidetone pitch in Hz (matches the keyer default)
+pub const DEFAULT_SIDETONE_HZ: f32 = 700.0;
+
+/// Default sidetone level (0.0 to 1.0)
+pub const DEFAULT_SIDETONE_LEVEL: f32 = 0.3;
+
+/// Microphone high-pass corner in Hz (removes rumble and breath pops)
+pub const TX_HIGHPASS_HZ: f32 = 150.0;
+
+/// TX equalizer bass shelf corner in Hz
+pub const TX_BASS_HZ: f32 = 300.0;
+
+/// TX equalizer treble shelf corner in Hz
+pub const TX_TREBLE_HZ: f32 = 2000.0;
+
+/// TX equalizer gain range in dB (either direction)
+pub const TX_EQ_RANGE_DB: f32 = 12.0;
+
+/// Default speech compression in dB
+pub const DEFAULT_COMPRESSION_DB: f32 = 10.0;
+
+/// Largest IF shift either side of the nominal passband in Hz
+pub const MAX_IF_SHIFT_HZ: f32 = 1200.0;
+
+/// Lowest passband edge in Hz (keeps the high-pass away from DC)
+pub const MIN_PASSBAND_HZ: f32 = 50.0;
+
+/// Highest passband edge in Hz
+pub const MAX_PASSBAND_HZ: f32 = 5000.0;
+
+/// Narrowest passband in Hz
+pub const MIN_PASSBAND_WIDTH_HZ: f32 = 50.0;
+
+/// Number of independent manual notches
+pub const NOTCH_COUNT: usize = 2;
+
+/// Default notch width in Hz
+pub const DEFAULT_NOTCH_WIDTH_HZ: f32 = 100.0;
+
+/// Narrowest notch in Hz
+pub const MIN_NOTCH_WIDTH_HZ: f32 = 10.0;
+
+/// Widest notch in Hz
+pub const MAX_NOTCH_WIDTH_HZ: f32 = 1000.0;
+
+/// Audio filter passband edges in Hz
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Passband {
+    /// Lower edge in Hz
+    pub low_hz: f32,
+    /// Upper edge in Hz
+    pub high_hz: f32,
+}
+
+impl Passband {
+    /// Create a passband from its edges
+    #[must_use]
+    pub const fn new(low_hz: f32, high_hz: f32) -> Self {
+        Self { low_hz, high_hz }
+    }
+
+    /// Create a passband from its center and width
+    #[must_use]
+    pub fn from_center(center_hz: f32, width_hz: f32) -> Self {
+        let half = width_hz / 2.0;
+        Self::new(center_hz - half, center_hz + half)
+    }
+
+    /// Get the center frequency in Hz
+    #[must_use]
+    pub fn center(&self) -> f32 {
+        f32::midpoint(self.low_hz, self.high_hz)
+    }
+
+    /// Get the width in Hz
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        self.high_hz - self.low_hz
+    }
+
+    /// Slide both edges by `shift_hz`, then fit the result inside
+    /// [`MIN_PASSBAND_HZ`] to [`MAX_PASSBAND_HZ`]
+    ///
+    /// An edge pushed past a limit stops there, so a large shift
+    /// narrows the passband rather than moving it out of the audio
+    /// range, but never below [`MIN_PASSBAND_WIDTH_HZ`].
+    #[must_use]
+    pub fn shifted(&self, shift_hz: f32) -> Self {
+        let low = (self.low_hz + shift_hz)
+            .clamp(MIN_PASSBAND_HZ, MAX_PASSBAND_HZ - MIN_PASSBAND_WIDTH_HZ);
+        let high = (self.high_hz + shift_hz).clamp(low + MIN_PASSBAND_WIDTH_HZ, MAX_PASSBAND_HZ);
+        Self::new(low, high)
+    }
+}
+
 /// Complete audio processing chain for receive
 #[derive(Clone)]
 pub struct AudioChain {
@@ -27,6 +161,26 @@ pub struct AudioChain {
     volume: f32,
     /// Muted state
     muted: bool,
+    /// CW sidetone generator
+    sidetone: CwToneGenerator,
+    /// Sidetone level (0.0 to 1.0), independent of volume
+    sidetone_level: f32,
+    /// Noise blanker and noise reduction (all disabled by default)
+    noise_reduction: NoiseReductionChain,
+    /// Manual notches (disabled by default)
+    notches: [NotchFilter; NOTCH_COUNT],
+    /// Graphic equalizer after the AGC (disabled by default)
+    equalizer: Equalizer,
+    /// Passband SNR of the filtered audio
+    snr: SnrMeter,
+    /// Received tone against the sidetone or passband centre (CW and SSB)
+    pitch: Option<PitchMeter>,
+    /// Per-stage bypass switches
+    bypass: BypassSet,
+    /// Nominal SSB or CW passband, before the IF shift
+    passband: Option<Passband>,
+    /// IF shift in Hz
+    if_shift: f32,
 }
 
 /// Filter configuration for different modes
@@ -72,6 +226,7 @@ impl AudioChain {
     #[must_use]
     pub fn new_cw(center_freq: f32, bandwidth: CwBandwidth) -> Self {
         let coeffs = design_cw_filter(center_freq, bandwidth, AUDIO_SAMPLE_RATE);
+        let passband = Passband::from_center(center_freq, f32::from(bandwidth.hz()));
         Self {
             filter_stage: FilterStage::Cw {
                 bandpass: Biquad::new(coeffs),
@@ -83,6 +238,16 @@ impl AudioChain {
             smeter: SMeter::new(),
             volume: 0.5,
             muted: false,
+            sidetone: CwToneGenerator::new(DEFAULT_SIDETONE_HZ, AUDIO_SAMPLE_RATE),
+            sidetone_level: DEFAULT_SIDETONE_LEVEL,
+            noise_reduction: idle_noise_reduction(),
+            notches: idle_notches(),
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            snr: snr_meter(Some(passband)),
+            pitch: Some(PitchMeter::new(AUDIO_SAMPLE_RATE, DEFAULT_SIDETONE_HZ)),
+            bypass: BypassSet::new(AUDIO_SAMPLE_RATE),
+            passband: Some(passband),
+            if_shift: 0.0,
         }
     }
 
@@ -90,6 +255,7 @@ impl AudioChain {
     #[must_use]
     pub fn new_ssb(bandwidth: SsbBandwidth) -> Self {
         let (hpf_coeffs, lpf_coeffs) = design_ssb_filter(bandwidth, AUDIO_SAMPLE_RATE);
+        let passband = ssb_passband(bandwidth);
         Self {
             filter_stage: FilterStage::Ssb {
                 highpass: Biquad::new(hpf_coeffs),
@@ -101,6 +267,16 @@ impl AudioChain {
             smeter: SMeter::new(),
             volume: 0.5,
             muted: false,
+            sidetone: CwToneGenerator::new(DEFAULT_SIDETONE_HZ, AUDIO_SAMPLE_RATE),
+            sidetone_level: DEFAULT_SIDETONE_LEVEL,
+            noise_reduction: idle_noise_reduction(),
+            notches: idle_notches(),
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            snr: snr_meter(Some(passband)),
+            pitch: Some(ssb_pitch_meter(passband)),
+            bypass: BypassSet::new(AUDIO_SAMPLE_RATE),
+            passband: Some(passband),
+            if_shift: 0.0,
         }
     }
 
@@ -118,6 +294,16 @@ impl AudioChain {
             smeter: SMeter::new(),
             volume: 0.5,
             muted: false,
+            sidetone: CwToneGenerator::new(DEFAULT_SIDETONE_HZ, AUDIO_SAMPLE_RATE),
+            sidetone_level: DEFAULT_SIDETONE_LEVEL,
+            noise_reduction: idle_noise_reduction(),
+            notches: idle_notches(),
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            snr: snr_meter(None),
+            pitch: None,
+            bypass: BypassSet::new(AUDIO_SAMPLE_RATE),
+            passband: None,
+            if_shift: 0.0,
         }
     }
 
@@ -135,6 +321,16 @@ impl AudioChain {
             smeter: SMeter::new(),
             volume: 0.5,
             muted: false,
+            sidetone: CwToneGenerator::new(DEFAULT_SIDETONE_HZ, AUDIO_SAMPLE_RATE),
+            sidetone_level: DEFAULT_SIDETONE_LEVEL,
+            noise_reduction: idle_noise_reduction(),
+            notches: idle_notches(),
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            snr: snr_meter(None),
+            pitch: None,
+            bypass: BypassSet::new(AUDIO_SAMPLE_RATE),
+            passband: None,
+            if_shift: 0.0,
         }
     }
 
@@ -148,21 +344,37 @@ impl AudioChain {
             smeter: SMeter::new(),
             volume: 0.5,
             muted: false,
+            sidetone: CwToneGenerator::new(DEFAULT_SIDETONE_HZ, AUDIO_SAMPLE_RATE),
+            sidetone_level: DEFAULT_SIDETONE_LEVEL,
+            noise_reduction: idle_noise_reduction(),
+            notches: idle_notches(),
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            snr: snr_meter(None),
+            pitch: None,
+            bypass: BypassSet::new(AUDIO_SAMPLE_RATE),
+            passband: None,
+            if_shift: 0.0,
         }
     }
 
     /// Process a single sample through the chain
     pub fn process(&mut self, input: f32) -> f32 {
+        let sidetone = self.sidetone.next() * self.sidetone_level;
+
         if self.muted {
             // Still update S-meter even when muted
             self.smeter.update_from_level(input.abs());
-            return 0.0;
+            return sidetone;
         }
 
         // Stage 1: DC blocking
         let sample = self.dc_blocker.process(input);
 
-        // Stage 2: Mode-specific filtering
+        // Stage 2: Noise blanker, ahead of the filters that would smear impulses
+        let blanked = self.noise_reduction.blanker_mut().process(sample);
+        let sample = self.bypass.mix(DspStage::NoiseBlanker, sample, blanked);
+
+        // Stage 3: Mode-specific filtering
         let sample = match &mut self.filter_stage {
             FilterStage::Cw { bandpass, .. } => bandpass.process(sample),
             FilterStage::Ssb {
@@ -175,15 +387,36 @@ impl AudioChain {
             FilterStage::Fm { deemphasis } => deemphasis.process(sample),
             FilterStage::Bypass => sample,
         };
+        self.snr.process(sample);
+        if let Some(pitch) = &mut self.pitch {
+            pitch.process(sample);
+        }
+
+        // Stage 4: Noise reduction
+        let reduced = self.noise_reduction.lms_mut().process(sample);
+        let reduced = self.noise_reduction.spectral_mut().process(reduced);
+        let reduced = self.noise_reduction.subtractor_mut().process(reduced);
+        let sample = self.bypass.mix(DspStage::NoiseReduction, sample, reduced);
+
+        // Stage 5: Notches, in series
+        let notched = self
+            .notches
+            .iter_mut()
+            .fold(sample, |sample, notch| notch.process(sample));
+        let sample = self.bypass.mix(DspStage::Notch, sample, notched);
 
-        // Stage 3: AGC
-        let sample = self.agc.process(sample);
+        // Stage 6: AGC (always run so the S-meter keeps tracking)
+        let leveled = self.agc.process(sample);
+        let sample = self.bypass.mix(DspStage::Agc, sample, leveled);
 
         // Update S-meter from AGC
         self.smeter.update_from_agc(&self.agc);
 
-        // Stage 4: Volume control
-        sample * self.volume
+        // Stage 7: Graphic equalizer on the leveled audio
+        let sample = self.equalizer.process(sample);
+
+        // Stage 8: Volume control, then sidetone
+        sample * self.volume + sidetone
     }
 
     /// Process a block of samples in-place
@@ -199,6 +432,24 @@ impl AudioChain {
         &self.smeter
     }
 
+    /// Get the passband signal power, noise floor and SNR
+    ///
+    /// `None` until the meter has measured its first frame.
+    #[must_use]
+    pub fn snr(&self) -> Option<SnrReading> {
+        self.snr.reading()
+    }
+
+    /// Get the received tone's pitch less the reference pitch in Hz
+    ///
+    /// The reference is the sidetone pitch in CW and the passband centre
+    /// in SSB. Positive when the tone is higher; `None` in AM and FM or
+    /// while no tone stands out in the passband.
+    #[must_use]
+    pub fn pitch_offset_hz(&self) -> Option<f32> {
+        self.pitch.as_ref().and_then(PitchMeter::offset_hz)
+    }
+
     /// Get current AGC gain in dB
     #[must_use]
     pub fn agc_gain_db(&self) -> f32 {
@@ -227,46 +478,146 @@ impl AudioChain {
         self.muted
     }
 
+    /// Key the sidetone (follow `Keyer::is_key_down` during transmit)
+    pub fn set_sidetone_key(&mut self, down: bool) {
+        self.sidetone.set_key(down);
+    }
+
+    /// Set sidetone pitch in Hz
+    pub fn set_sidetone_frequency(&mut self, freq_hz: f32) {
+        self.sidetone.set_frequency(freq_hz, AUDIO_SAMPLE_RATE);
+        if let Some(pitch) = &mut self.pitch {
+            pitch.set_center_hz(freq_hz);
+        }
+    }
+
+    /// Set sidetone level (0.0 to 1.0)
+    pub fn set_sidetone_level(&mut self, level: f32) {
+        self.sidetone_level = level.clamp(0.0, 1.0);
+    }
+
+    /// Get sidetone level
+    #[must_use]
+    pub fn sidetone_level(&self) -> f32 {
+        self.sidetone_level
+    }
+
+    /// Check if the sidetone is sounding (keyed or still decaying)
+    #[must_use]
+    pub fn is_sidetone_active(&self) -> bool {
+        self.sidetone.is_active()
+    }
+
     /// Update CW filter center frequency
     pub fn set_cw_frequency(&mut self, center_freq: f32) {
-        if let FilterStage::Cw {
-            bandpass,
-            center_freq: freq,
-            bandwidth,
-        } = &mut self.filter_stage
-        {
-            *freq = center_freq;
-            let coeffs = design_cw_filter(center_freq, *bandwidth, AUDIO_SAMPLE_RATE);
-            *bandpass = Biquad::new(coeffs);
+        if let FilterStage::Cw { bandwidth, .. } = &self.filter_stage {
+            let width = f32::from(bandwidth.hz());
+            self.set_passband(Passband::from_center(center_freq, width));
         }
     }
 
-    /// Update CW bandwidth
+    /// Update CW bandwidth (the passband width returns to the preset)
     pub fn set_cw_bandwidth(&mut self, new_bandwidth: CwBandwidth) {
         if let FilterStage::Cw {
-            bandpass,
             center_freq,
             bandwidth,
+            ..
         } = &mut self.filter_stage
         {
             *bandwidth = new_bandwidth;
-            let coeffs = design_cw_filter(*center_freq, new_bandwidth, AUDIO_SAMPLE_RATE);
-            *bandpass = Biquad::new(coeffs);
+            let passband = Passband::from_center(*center_freq, f32::from(new_bandwidth.hz()));
+            self.set_passband(passband);
         }
     }
 
-    /// Update SSB bandwidth
+    /// Update SSB bandwidth (the passband edges return to the preset)
     pub fn set_ssb_bandwidth(&mut self, new_bandwidth: SsbBandwidth) {
-        if let FilterStage::Ssb {
-            highpass,
-            lowpass,
-            bandwidth,
-        } = &mut self.filter_stage
-        {
+        if let FilterStage::Ssb { bandwidth, .. } = &mut self.filter_stage {
             *bandwidth = new_bandwidth;
-            let (hpf_coeffs, lpf_coeffs) = design_ssb_filter(new_bandwidth, AUDIO_SAMPLE_RATE);
-            *highpass = Biquad::new(hpf_coeffs);
-            *lowpass = Biquad::new(lpf_coeffs);
+            self.set_passband(ssb_passband(new_bandwidth));
+        }
+    }
+
+    /// Set the IF shift in Hz (clamped to +/-[`MAX_IF_SHIFT_HZ`])
+    ///
+    /// Slides the SSB or CW passband off an interfering signal without
+    /// changing the nominal passband; zero puts it back. The filter
+    /// state is kept, so the audio does not click as it moves. Has no
+    /// effect in AM and FM.
+    pub fn set_if_shift(&mut self, shift_hz: f32) {
+        self.if_shift = shift_hz.clamp(-MAX_IF_SHIFT_HZ, MAX_IF_SHIFT_HZ);
+        self.redesign_filter();
+    }
+
+    /// Get the IF shift in Hz
+    #[must_use]
+    pub fn if_shift(&self) -> f32 {
+        self.if_shift
+    }
+
+    /// Set the nominal passband center in Hz, keeping its width
+    pub fn set_passband_center(&mut self, center_hz: f32) {
+        if let Some(passband) = self.passband {
+            self.set_passband(Passband::from_center(center_hz, passband.width()));
+        }
+    }
+
+    /// Set the nominal passband width in Hz, keeping its center
+    pub fn set_passband_width(&mut self, width_hz: f32) {
+        if let Some(passband) = self.passband {
+            let width = width_hz.max(MIN_PASSBAND_WIDTH_HZ);
+            self.set_passband(Passband::from_center(passband.center(), width));
+        }
+    }
+
+    /// Get the passband the filter is using (nominal plus IF shift)
+    ///
+    /// Returns `None` in modes without passband tuning.
+    #[must_use]
+    pub fn passband(&self) -> Option<Passband> {
+        self.passband.map(|passband| passband.shifted(self.if_shift))
+    }
+
+    /// Set the nominal passband and redesign the filter
+    fn set_passband(&mut self, passband: Passband) {
+        self.passband = Some(passband);
+        if let FilterStage::Cw { center_freq, .. } = &mut self.filter_stage {
+            *center_freq = passband.center();
+        }
+        self.redesign_filter();
+    }
+
+    /// Redesign the SSB or CW filter for the passband and IF shift
+    fn redesign_filter(&mut self) {
+        let Some(passband) = self.passband() else {
+            return;
+        };
+        self.snr.set_passband(passband.low_hz, passband.high_hz);
+        if let (FilterStage::Ssb { .. }, Some(pitch)) = (&self.filter_stage, &mut self.pitch) {
+            pitch.set_center_hz(passband.center());
+            pitch.set_range_hz(passband.width() / 2.0);
+        }
+        match &mut self.filter_stage {
+            FilterStage::Cw { bandpass, .. } => {
+                let center = passband.center();
+                let q = center / passband.width();
+                bandpass.set_coeffs(BiquadCoeffs::bandpass_peak(center, AUDIO_SAMPLE_RATE, q));
+            }
+            FilterStage::Ssb {
+                highpass, lowpass, ..
+            } => {
+                highpass.set_coeffs(BiquadCoeffs::highpass(
+                    passband.low_hz,
+                    AUDIO_SAMPLE_RATE,
+                    0.707,
+                ));
+                lowpass.set_coeffs(BiquadCoeffs::lowpass(
+                    passband.high_hz,
+                    AUDIO_SAMPLE_RATE,
+                    0.707,
+                ));
+            }
+            _ => {}
         }
     }
 
@@ -294,7 +645,70 @@ impl AudioChain {
             FilterStage::Fm { deemphasis } => deemphasis.reset(),
             FilterStage::Bypass => {}
         }
+        self.noise_reduction.reset();
+        for notch in &mut self.notches {
+            notch.reset();
+        }
         self.agc.reset();
+        self.equalizer.reset();
+        self.snr.reset();
+        if let Some(pitch) = &mut self.pitch {
+            pitch.reset();
+        }
+    }
+
+    /// Get mutable access to the noise blanker and noise reduction stages
+    pub fn noise_reduction_mut(&mut self) -> &mut NoiseReductionChain {
+        &mut self.noise_reduction
+    }
+
+    /// Get a manual notch by index (0 to [`NOTCH_COUNT`] - 1)
+    #[must_use]
+    pub fn notch(&self, index: usize) -> Option<&NotchFilter> {
+        self.notches.get(index)
+    }
+
+    /// Get mutable access to a manual notch by index
+    pub fn notch_mut(&mut self, index: usize) -> Option<&mut NotchFilter> {
+        self.notches.get_mut(index)
+    }
+
+    /// Get all manual notches
+    #[must_use]
+    pub fn notches(&self) -> &[NotchFilter; NOTCH_COUNT] {
+        &self.notches
+    }
+
+    /// Replace all manual notches (e.g. to carry them over a mode change)
+    pub fn set_notches(&mut self, notches: [NotchFilter; NOTCH_COUNT]) {
+        self.notches = notches;
+    }
+
+    /// Get the receive equalizer
+    #[must_use]
+    pub fn equalizer(&self) -> &Equalizer {
+        &self.equalizer
+    }
+
+    /// Get mutable access to the receive equalizer (curve, on/off)
+    pub fn equalizer_mut(&mut self) -> &mut Equalizer {
+        &mut self.equalizer
+    }
+
+    /// Bypass a stage or put it back in circuit (crossfaded)
+    pub fn set_stage_bypass(&mut self, stage: DspStage, bypassed: bool) {
+        self.bypass.set(stage, bypassed);
+    }
+
+    /// Toggle a stage's bypass, returning the new state
+    pub fn toggle_stage_bypass(&mut self, stage: DspStage) -> bool {
+        self.bypass.toggle(stage)
+    }
+
+    /// Check if a stage is bypassed
+    #[must_use]
+    pub fn is_stage_bypassed(&self, stage: DspStage) -> bool {
+        self.bypass.is_bypassed(stage)
     }
 
     /// Configure AGC parameters
@@ -315,17 +729,254 @@ impl AudioChain {
     }
 }
 
+/// Noise reduction chain with every stage switched off
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn idle_noise_reduction() -> NoiseReductionChain {
+    let mut chain = NoiseReductionChain::new(AUDIO_SAMPLE_RATE as u32);
+    chain.blanker_mut().set_enabled(false);
+    chain.lms_mut().set_enabled(false);
+    chain.spectral_mut().set_enabled(false);
+    chain.subtractor_mut().set_strength(0);
+    chain
+}
+
+/// Manual notches, all switched off
+fn idle_notches() -> [NotchFilter; NOTCH_COUNT] {
+    core::array::from_fn(|_| NotchFilter {
+        enabled: false,
+        ..NotchFilter::default()
+    })
+}
+
+/// SNR meter for a passband (the meter's default for modes without one)
+fn snr_meter(passband: Option<Passband>) -> SnrMeter {
+    let mut meter = SnrMeter::new(AUDIO_SAMPLE_RATE);
+    if let Some(passband) = passband {
+        meter.set_passband(passband.low_hz, passband.high_hz);
+    }
+    meter
+}
+
+/// Nominal passband of an SSB bandwidth preset
+fn ssb_passband(bandwidth: SsbBandwidth) -> Passband {
+    Passband::new(
+        f32::from(bandwidth.low_cutoff()),
+        f32::from(bandwidth.high_cutoff()),
+    )
+}
+
+/// Pitch meter spanning an SSB passband, for centering a tone in it
+fn ssb_pitch_meter(passband: Passband) -> PitchMeter {
+    let mut meter = PitchMeter::new(AUDIO_SAMPLE_RATE, passband.center());
+    meter.set_range_hz(passband.width() / 2.0);
+    meter
+}
+
 impl Default for AudioChain {
     fn default() -> Self {
         Self::new_ssb(SsbBandwidth::Standard)
     }
 }
 
+/// Complete audio processing chain for transmit
+///
+/// Microphone audio passes through the high-pass, the speech compressor
+/// and limiter, the TX tone shelves and the graphic equalizer, then the
+/// ALC-controlled drive into the SSB modulator.
+pub struct TxAudioChain {
+    /// Microphone gain (linear)
+    mic_gain: f32,
+    /// Microphone high-pass filter
+    highpass: Biquad,
+    /// Speech compressor and limiter
+    compressor: Compressor,
+    /// Whether the compressor is in circuit
+    processor_enabled: bool,
+    /// TX EQ bass shelf
+    bass: Biquad,
+    /// TX EQ treble shelf
+    treble: Biquad,
+    /// Bass shelf gain in dB
+    bass_db: f32,
+    /// Treble shelf gain in dB
+    treble_db: f32,
+    /// Graphic equalizer (disabled by default)
+    equalizer: Equalizer,
+    /// ALC loop on the modulator output
+    alc: Alc,
+    /// SSB modulator
+    modulator: SsbModulator,
+}
+
+impl TxAudioChain {
+    /// Create a transmit chain with a flat EQ and default compression
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mic_gain: 1.0,
+            highpass: Biquad::new(BiquadCoeffs::highpass(
+                TX_HIGHPASS_HZ,
+                AUDIO_SAMPLE_RATE,
+                0.707,
+            )),
+            compressor: Compressor::new(DEFAULT_COMPRESSION_DB, AUDIO_SAMPLE_RATE),
+            processor_enabled: true,
+            bass: Biquad::new(BiquadCoeffs::low_shelf(TX_BASS_HZ, AUDIO_SAMPLE_RATE, 0.0, 1.0)),
+            treble: Biquad::new(BiquadCoeffs::high_shelf(
+                TX_TREBLE_HZ,
+                AUDIO_SAMPLE_RATE,
+                0.0,
+                1.0,
+            )),
+            bass_db: 0.0,
+            treble_db: 0.0,
+            equalizer: Equalizer::new(AUDIO_SAMPLE_RATE),
+            alc: Alc::new(AUDIO_SAMPLE_RATE),
+            modulator: SsbModulator::new(AUDIO_SAMPLE_RATE),
+        }
+    }
+
+    /// Process one microphone sample to audio ready for the modulator
+    pub fn process_audio(&mut self, input: f32) -> f32 {
+        // Stage 1: Mic gain and high-pass
+        let sample = self.highpass.process(input * self.mic_gain);
+
+        // Stage 2: Compressor and limiter
+        let sample = if self.processor_enabled {
+            self.compressor.process(sample)
+        } else {
+            sample
+        };
+
+        // Stage 3: TX tone shelves
+        let sample = self.bass.process(sample);
+        let sample = self.treble.process(sample);
+
+        // Stage 4: Graphic equalizer
+        self.equalizer.process(sample)
+    }
+
+    /// Process one microphone sample to modulated IQ
+    ///
+    /// The ALC gain is applied before the modulator and updated from the
+    /// modulator's output magnitude.
+    pub fn process(&mut self, input: f32) -> IqSample {
+        let audio = self.process_audio(input);
+        let iq = self.modulator.process(audio * self.alc.gain());
+        self.alc.update(iq.magnitude());
+        iq
+    }
+
+    /// Process a block of microphone samples into IQ
+    pub fn process_block(&mut self, input: &[f32], output: &mut [IqSample]) {
+        for (sample, out) in input.iter().zip(output.iter_mut()) {
+            *out = self.process(*sample);
+        }
+    }
+
+    /// Set microphone gain in dB
+    pub fn set_mic_gain_db(&mut self, gain_db: f32) {
+        self.mic_gain = 10.0_f32.powf(gain_db / 20.0);
+    }
+
+    /// Set speech compression in dB (0 to 20)
+    pub fn set_compression(&mut self, compression_db: f32) {
+        self.compressor.set_compression(compression_db);
+    }
+
+    /// Get speech compression in dB
+    #[must_use]
+    pub fn compression_db(&self) -> f32 {
+        self.compressor.compression_db()
+    }
+
+    /// Switch the speech processor in or out
+    pub fn set_processor_enabled(&mut self, enabled: bool) {
+        self.processor_enabled = enabled;
+        self.compressor.reset();
+    }
+
+    /// Check if the speech processor is in circuit
+    #[must_use]
+    pub fn is_processor_enabled(&self) -> bool {
+        self.processor_enabled
+    }
+
+    /// Set TX EQ shelf gains in dB (clamped to +/-12 dB)
+    pub fn set_eq(&mut self, bass_db: f32, treble_db: f32) {
+        self.bass_db = bass_db.clamp(-TX_EQ_RANGE_DB, TX_EQ_RANGE_DB);
+        self.treble_db = treble_db.clamp(-TX_EQ_RANGE_DB, TX_EQ_RANGE_DB);
+        self.bass.set_coeffs(BiquadCoeffs::low_shelf(
+            TX_BASS_HZ,
+            AUDIO_SAMPLE_RATE,
+            self.bass_db,
+            1.0,
+        ));
+        self.treble.set_coeffs(BiquadCoeffs::high_shelf(
+            TX_TREBLE_HZ,
+            AUDIO_SAMPLE_RATE,
+            self.treble_db,
+            1.0,
+        ));
+    }
+
+    /// Get TX EQ shelf gains in dB (bass, treble)
+    #[must_use]
+    pub fn eq(&self) -> (f32, f32) {
+        (self.bass_db, self.treble_db)
+    }
+
+    /// Get the TX graphic equalizer
+    #[must_use]
+    pub fn equalizer(&self) -> &Equalizer {
+        &self.equalizer
+    }
+
+    /// Get mutable access to the TX graphic equalizer (curve, on/off)
+    pub fn equalizer_mut(&mut self) -> &mut Equalizer {
+        &mut self.equalizer
+    }
+
+    /// Set the ALC target peak output level
+    pub fn set_alc_target(&mut self, target: f32) {
+        self.alc.set_target(target);
+    }
+
+    /// Get the current ALC gain reduction in dB (for the ALC meter)
+    #[must_use]
+    pub fn alc_reduction_db(&self) -> f32 {
+        self.alc.reduction_db()
+    }
+
+    /// Get mutable access to the SSB modulator (sideband, orientation)
+    pub fn modulator_mut(&mut self) -> &mut SsbModulator {
+        &mut self.modulator
+    }
+
+    /// Reset all internal state
+    pub fn reset(&mut self) {
+        self.highpass.reset();
+        self.compressor.reset();
+        self.bass.reset();
+        self.treble.reset();
+        self.equalizer.reset();
+        self.alc.reset();
+        self.modulator.reset();
+    }
+}
+
+impl Default for TxAudioChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Notch filter for removing interference
 #[derive(Clone)]
 pub struct NotchFilter {
     filter: Biquad,
     frequency: f32,
+    width: f32,
     enabled: bool,
 }
 
@@ -333,11 +984,17 @@ impl NotchFilter {
     /// Create a new notch filter at the specified frequency
     #[must_use]
     pub fn new(frequency: f32) -> Self {
-        use super::filter_design::BiquadCoeffs;
-        let coeffs = BiquadCoeffs::notch(frequency, AUDIO_SAMPLE_RATE, 10.0);
+        Self::with_width(frequency, DEFAULT_NOTCH_WIDTH_HZ)
+    }
+
+    /// Create a new notch filter with a width in Hz
+    #[must_use]
+    pub fn with_width(frequency: f32, width: f32) -> Self {
+        let width = width.clamp(MIN_NOTCH_WIDTH_HZ, MAX_NOTCH_WIDTH_HZ);
         Self {
-            filter: Biquad::new(coeffs),
+            filter: Biquad::new(notch_coeffs(frequency, width)),
             frequency,
+            width,
             enabled: true,
         }
     }
@@ -351,12 +1008,30 @@ impl NotchFilter {
         }
     }
 
-    /// Set notch frequency
+    /// Set notch frequency, keeping the width
+    ///
+    /// The filter state is kept, so the notch can be swept onto a
+    /// carrier without clicks.
     pub fn set_frequency(&mut self, frequency: f32) {
-        use super::filter_design::BiquadCoeffs;
         self.frequency = frequency;
-        let coeffs = BiquadCoeffs::notch(frequency, AUDIO_SAMPLE_RATE, 10.0);
-        self.filter = Biquad::new(coeffs);
+        self.filter.set_coeffs(notch_coeffs(frequency, self.width));
+    }
+
+    /// Set notch width in Hz (clamped to [`MIN_NOTCH_WIDTH_HZ`] to [`MAX_NOTCH_WIDTH_HZ`])
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(MIN_NOTCH_WIDTH_HZ, MAX_NOTCH_WIDTH_HZ);
+        self.filter.set_coeffs(notch_coeffs(self.frequency, self.width));
+    }
+
+    /// Get notch width in Hz
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Clear the filter state
+    pub fn reset(&mut self) {
+        self.filter.reset();
     }
 
     /// Enable/disable the notch
@@ -383,6 +1058,11 @@ impl Default for NotchFilter {
     }
 }
 
+/// Notch coefficients for a frequency and width in Hz
+fn notch_coeffs(frequency: f32, width: f32) -> BiquadCoeffs {
+    BiquadCoeffs::notch(frequency, AUDIO_SAMPLE_RATE, frequency / width)
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
@@ -553,6 +1233,48 @@ mod tests {
         // Should not panic
     }
 
+    #[test]
+    fn audio_chain_sidetone_while_muted() {
+        let mut chain = AudioChain::new_cw(700.0, CwBandwidth::Hz400);
+        chain.set_muted(true);
+        chain.set_sidetone_key(true);
+
+        // 20 ms of key-down: well past the 5 ms rise
+        let peak = (0..960)
+            .map(|_| chain.process(0.0).abs())
+            .fold(0.0f32, f32::max);
+        assert!(
+            (peak - DEFAULT_SIDETONE_LEVEL).abs() < 0.01,
+            "Sidetone should reach its level while RX is muted, got {peak}"
+        );
+
+        // Key up: decays to silence within the fall time
+        chain.set_sidetone_key(false);
+        for _ in 0..480 {
+            chain.process(0.0);
+        }
+        assert!(!chain.is_sidetone_active());
+        assert!(chain.process(0.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn audio_chain_sidetone_level_independent_of_volume() {
+        let mut chain = AudioChain::new_cw(700.0, CwBandwidth::Hz400);
+        chain.set_muted(true);
+        chain.set_volume(0.0);
+        chain.set_sidetone_level(0.8);
+        assert!((chain.sidetone_level() - 0.8).abs() < f32::EPSILON);
+        chain.set_sidetone_key(true);
+
+        let peak = (0..960)
+            .map(|_| chain.process(0.0).abs())
+            .fold(0.0f32, f32::max);
+        assert!(peak > 0.79, "Sidetone ignores volume, got {peak}");
+
+        chain.set_sidetone_level(2.0);
+        assert!((chain.sidetone_level() - 1.0).abs() < f32::EPSILON);
+    }
+
     #[test]
     fn notch_filter_creation() {
         let notch = NotchFilter::new(1000.0);
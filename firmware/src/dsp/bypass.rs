@@ -0,0 +1,208 @@
+//! Per-Stage DSP Bypass
+//!
+//! Runtime bypass switches for A/B listening tests. Each stage keeps
+//! processing while bypassed and its output is crossfaded against the
+//! unprocessed signal over a few milliseconds, so toggling a stage never
+//! clicks and switching back picks up a settled filter rather than one
+//! starting from cold state.
+
+/// Crossfade time when a bypass switch changes, in milliseconds
+pub const BYPASS_FADE_MS: f32 = 5.0;
+
+/// DSP stage that can be bypassed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DspStage {
+    /// Impulse noise blanker
+    #[default]
+    NoiseBlanker,
+    /// Adaptive and spectral noise reduction
+    NoiseReduction,
+    /// Manual notch filter
+    Notch,
+    /// Automatic gain control
+    Agc,
+}
+
+impl DspStage {
+    /// Number of bypassable stages
+    pub const COUNT: usize = 4;
+
+    /// All stages in processing order
+    pub const ALL: [Self; Self::COUNT] =
+        [Self::NoiseBlanker, Self::NoiseReduction, Self::Notch, Self::Agc];
+
+    /// Stable code for CAT and storage
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::NoiseBlanker => 0,
+            Self::NoiseReduction => 1,
+            Self::Notch => 2,
+            Self::Agc => 3,
+        }
+    }
+
+    /// Parse a stage code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::NoiseBlanker),
+            1 => Some(Self::NoiseReduction),
+            2 => Some(Self::Notch),
+            3 => Some(Self::Agc),
+            _ => None,
+        }
+    }
+
+    /// Short label for the display
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::NoiseBlanker => "NB",
+            Self::NoiseReduction => "NR",
+            Self::Notch => "NOTCH",
+            Self::Agc => "AGC",
+        }
+    }
+
+    /// Index into per-stage tables
+    const fn index(self) -> usize {
+        self.code() as usize
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for DspStage {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.label());
+    }
+}
+
+/// Crossfading bypass switch for one stage
+#[derive(Clone, Copy, Debug)]
+pub struct StageBypass {
+    /// Requested state
+    bypassed: bool,
+    /// Fade position in samples (0 = bypassed, `fade_samples` = processed)
+    position: u32,
+    /// Crossfade length in samples
+    fade_samples: u32,
+}
+
+impl StageBypass {
+    /// Create an active (not bypassed) switch
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new(sample_rate: f32) -> Self {
+        let fade_samples = ((BYPASS_FADE_MS / 1000.0 * sample_rate) as u32).max(1);
+        Self {
+            bypassed: false,
+            position: fade_samples,
+            fade_samples,
+        }
+    }
+
+    /// Request bypass on or off (fades over [`BYPASS_FADE_MS`])
+    pub fn set_bypassed(&mut self, bypassed: bool) {
+        self.bypassed = bypassed;
+    }
+
+    /// Check if bypass is requested
+    #[must_use]
+    pub const fn is_bypassed(&self) -> bool {
+        self.bypassed
+    }
+
+    /// Check if a crossfade is in progress
+    #[must_use]
+    pub const fn is_fading(&self) -> bool {
+        self.position != self.target()
+    }
+
+    /// Mix one sample of unprocessed (`dry`) and processed (`wet`) signal
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mix(&mut self, dry: f32, wet: f32) -> f32 {
+        if self.bypassed {
+            self.position = self.position.saturating_sub(1);
+        } else {
+            self.position = (self.position + 1).min(self.fade_samples);
+        }
+        dry + (wet - dry) * (self.position as f32 / self.fade_samples as f32)
+    }
+
+    /// Jump to the requested state without fading
+    pub fn settle(&mut self) {
+        self.position = self.target();
+    }
+
+    /// Fade position for the requested state
+    const fn target(&self) -> u32 {
+        if self.bypassed {
+            0
+        } else {
+            self.fade_samples
+        }
+    }
+}
+
+/// Bypass switches for every stage
+#[derive(Clone, Copy, Debug)]
+pub struct BypassSet {
+    stages: [StageBypass; DspStage::COUNT],
+}
+
+impl BypassSet {
+    /// Create with every stage active
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            stages: [StageBypass::new(sample_rate); DspStage::COUNT],
+        }
+    }
+
+    /// Bypass a stage or put it back in circuit
+    pub fn set(&mut self, stage: DspStage, bypassed: bool) {
+        self.stages[stage.index()].set_bypassed(bypassed);
+    }
+
+    /// Toggle a stage's bypass, returning the new state
+    pub fn toggle(&mut self, stage: DspStage) -> bool {
+        let bypassed = !self.is_bypassed(stage);
+        self.set(stage, bypassed);
+        bypassed
+    }
+
+    /// Check if a stage is bypassed
+    #[must_use]
+    pub const fn is_bypassed(&self, stage: DspStage) -> bool {
+        self.stages[stage.index()].is_bypassed()
+    }
+
+    /// Crossfade a stage's input (`dry`) and output (`wet`)
+    pub fn mix(&mut self, stage: DspStage, dry: f32, wet: f32) -> f32 {
+        self.stages[stage.index()].mix(dry, wet)
+    }
+
+    /// Bit mask of bypassed stages (bit n = stage code n)
+    #[must_use]
+    pub fn mask(&self) -> u8 {
+        DspStage::ALL
+            .iter()
+            .filter(|stage| self.is_bypassed(**stage))
+            .fold(0, |mask, stage| mask | (1 << stage.code()))
+    }
+
+    /// Put every stage back in circuit immediately
+    pub fn clear(&mut self) {
+        for stage in &mut self.stages {
+            stage.set_bypassed(false);
+            stage.settle();
+        }
+    }
+}
+
+impl Default for BypassSet {
+    fn default() -> Self {
+        Self::new(48000.0)
+    }
+}
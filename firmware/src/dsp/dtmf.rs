@@ -0,0 +1,221 @@
+//! DTMF Decoder
+//!
+//! Detects touch-tone digits in receive audio with a Goertzel filter per
+//! DTMF frequency (four row tones, four column tones) over short blocks.
+//! A block counts as a digit only if it passes the usual checks:
+//!
+//! - both the strongest row and the strongest column tone are above the
+//!   minimum level
+//! - the twist (row over column level) is within limits either way
+//! - each is well clear of the other tones in its group
+//! - the pair carries a good share of the block's energy, so speech and
+//!   noise with a lot going on around the tones are rejected
+//!
+//! A digit is reported once after [`DTMF_HIT_BLOCKS`] valid blocks in a
+//! row, and the same digit again only after [`DTMF_GAP_BLOCKS`] blocks
+//! without it, so a long press is one digit and a dropped block mid-tone
+//! does not repeat it.
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+use sdr_dsp_core::GoertzelBank;
+
+/// Row (low group) frequencies in Hz
+pub const DTMF_ROW_HZ: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+
+/// Column (high group) frequencies in Hz
+pub const DTMF_COLUMN_HZ: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+
+/// Digit for each row and column
+pub const DTMF_KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Detection block length in milliseconds (62.5 Hz resolution)
+pub const DTMF_BLOCK_MS: u32 = 16;
+
+/// Valid blocks in a row before a digit is reported
+pub const DTMF_HIT_BLOCKS: u8 = 2;
+
+/// Blocks without the digit before the same digit is reported again
+pub const DTMF_GAP_BLOCKS: u8 = 2;
+
+/// Default minimum level of each tone in dBFS
+pub const DEFAULT_MIN_LEVEL_DBFS: f32 = -30.0;
+
+/// Largest normal twist (column tone weaker than row tone) in dB
+pub const MAX_TWIST_DB: f32 = 8.0;
+
+/// Largest reverse twist (column tone stronger than row tone) in dB
+pub const MAX_REVERSE_TWIST_DB: f32 = 4.0;
+
+/// Least margin of each tone over the others in its group in dB
+pub const MIN_GROUP_RATIO_DB: f32 = 6.0;
+
+/// Least share of the block energy in the two tones
+const MIN_TONE_FRACTION: f32 = 0.25;
+
+/// Power of a full-scale sine as reported by the Goertzel bank
+const FULL_SCALE_POWER: f32 = 0.5;
+
+/// Check if a character is a DTMF digit (`0-9`, `A-D`, `*`, `#`)
+#[must_use]
+pub fn is_dtmf_digit(digit: char) -> bool {
+    DTMF_KEYS.iter().flatten().any(|&key| key == digit)
+}
+
+/// DTMF digit detector for receive audio
+#[derive(Clone, Debug)]
+pub struct DtmfDetector {
+    /// Row tones at 0-3, column tones at 4-7
+    bank: GoertzelBank<8>,
+    /// Sum of squares over the current block
+    energy: f32,
+    /// Minimum power of each tone (linear)
+    min_power: f32,
+    /// Whether the detector is running
+    enabled: bool,
+    /// Digit (or none) seen in the last block
+    candidate: Option<char>,
+    /// Blocks in a row with the same candidate
+    run: u8,
+    /// Digit reported and still held
+    held: Option<char>,
+}
+
+impl DtmfDetector {
+    /// Create a detector for audio at `sample_rate` Hz, switched off
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(sample_rate: u32) -> Self {
+        let block_len = (sample_rate * DTMF_BLOCK_MS / 1000) as usize;
+        let mut bank = GoertzelBank::new(sample_rate as f32, block_len);
+        for &freq in DTMF_ROW_HZ.iter().chain(&DTMF_COLUMN_HZ) {
+            bank.add_tone(freq);
+        }
+        let mut detector = Self {
+            bank,
+            energy: 0.0,
+            min_power: 0.0,
+            enabled: false,
+            candidate: None,
+            run: 0,
+            held: None,
+        };
+        detector.set_min_level_dbfs(DEFAULT_MIN_LEVEL_DBFS);
+        detector
+    }
+
+    /// Set the minimum level of each tone in dBFS
+    pub fn set_min_level_dbfs(&mut self, dbfs: f32) {
+        self.min_power = FULL_SCALE_POWER * 10.0_f32.powf(dbfs / 10.0);
+    }
+
+    /// Switch the detector on or off, clearing its state
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled != self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Check if the detector is running
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Feed one audio sample, returning a newly pressed digit
+    pub fn process(&mut self, sample: f32) -> Option<char> {
+        if !self.enabled {
+            return None;
+        }
+        self.energy += sample * sample;
+        if !self.bank.push(sample) {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let mean_square = self.energy / self.bank.block_len() as f32;
+        self.energy = 0.0;
+        let key = self.classify(mean_square);
+        self.debounce(key)
+    }
+
+    /// Clear the current block and the held digit
+    pub fn reset(&mut self) {
+        self.bank.reset();
+        self.energy = 0.0;
+        self.candidate = None;
+        self.run = 0;
+        self.held = None;
+    }
+
+    /// Find the digit in the last block, if it passes validation
+    fn classify(&self, mean_square: f32) -> Option<char> {
+        let power = |i: usize| self.bank.power(i).unwrap_or(0.0);
+        let (row, row_power) = strongest(power, 0)?;
+        let (column, column_power) = strongest(power, 4)?;
+        if row_power < self.min_power || column_power < self.min_power {
+            return None;
+        }
+
+        let twist = db_to_power(MAX_TWIST_DB);
+        let reverse_twist = db_to_power(MAX_REVERSE_TWIST_DB);
+        if row_power > column_power * twist || column_power > row_power * reverse_twist {
+            return None;
+        }
+
+        let ratio = db_to_power(MIN_GROUP_RATIO_DB);
+        let clear = |group: usize, best: usize, best_power: f32| {
+            (0..4)
+                .filter(|&i| i != best)
+                .all(|i| power(group + i) * ratio <= best_power)
+        };
+        if !clear(0, row, row_power) || !clear(4, column, column_power) {
+            return None;
+        }
+
+        if row_power + column_power < mean_square * MIN_TONE_FRACTION {
+            return None;
+        }
+        Some(DTMF_KEYS[row][column])
+    }
+
+    /// Track the digit over blocks and report each press once
+    fn debounce(&mut self, key: Option<char>) -> Option<char> {
+        if key == self.candidate {
+            self.run = self.run.saturating_add(1);
+        } else {
+            self.candidate = key;
+            self.run = 1;
+        }
+        match key {
+            None => {
+                if self.run >= DTMF_GAP_BLOCKS {
+                    self.held = None;
+                }
+                None
+            }
+            Some(_) if self.run >= DTMF_HIT_BLOCKS && self.held != key => {
+                self.held = key;
+                key
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// Strongest of the four tones starting at `group`, as (index in group, power)
+fn strongest(power: impl Fn(usize) -> f32, group: usize) -> Option<(usize, f32)> {
+    (0..4)
+        .map(|i| (i, power(group + i)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Convert dB to a power ratio
+fn db_to_power(db: f32) -> f32 {
+    10.0_f32.powf(db / 10.0)
+}
@@ -0,0 +1,366 @@
+//! Graphic Equalizer
+//!
+//! Five peaking-EQ biquads in series at fixed speech-band centers, each
+//! with its own gain. The receiver runs one on the leveled audio after
+//! the AGC, so a boost does not pump the gain; the transmitter runs one
+//! after the speech processor to shape the mic for the band.
+//!
+//! Curves are whole dB per band and kept per mode in [`EqPresets`]:
+//! SSB, CW, AM and FM each have their own RX and TX curve, so a punchy
+//! SSB transmit curve does not follow the radio onto AM. The factory
+//! curves come from [`EqCurve::rx_default`] and [`EqCurve::tx_default`].
+
+use core::f32::consts::PI;
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+
+use super::filter_design::{Biquad, BiquadCoeffs};
+use crate::types::Mode;
+
+/// Number of equalizer bands
+pub const EQ_BANDS: usize = 5;
+
+/// Band center frequencies in Hz
+pub const EQ_BAND_HZ: [f32; EQ_BANDS] = [200.0, 500.0, 1000.0, 2000.0, 3000.0];
+
+/// Largest band gain in dB (either direction)
+pub const MAX_EQ_GAIN_DB: i8 = 12;
+
+/// Band Q (about 1.4 octaves, so neighbouring bands overlap smoothly)
+const EQ_Q: f32 = 1.0;
+
+/// Number of per-mode curves (SSB, CW, AM, FM)
+const EQ_PRESETS: usize = 4;
+
+/// Which audio path an equalizer curve belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EqPath {
+    /// Receive audio
+    Rx,
+    /// Transmit (microphone) audio
+    Tx,
+}
+
+impl EqPath {
+    /// CAT code (0 = RX, 1 = TX)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Rx => 0,
+            Self::Tx => 1,
+        }
+    }
+
+    /// Parse a CAT code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Rx),
+            1 => Some(Self::Tx),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for EqPath {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Rx => defmt::write!(f, "RX"),
+            Self::Tx => defmt::write!(f, "TX"),
+        }
+    }
+}
+
+/// Band gains of an equalizer curve in dB
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct EqCurve {
+    /// Gain per band, lowest band first
+    gains_db: [i8; EQ_BANDS],
+}
+
+impl EqCurve {
+    /// Flat curve (all bands 0 dB)
+    pub const FLAT: Self = Self {
+        gains_db: [0; EQ_BANDS],
+    };
+
+    /// Create a curve, clamping each band to +/-[`MAX_EQ_GAIN_DB`]
+    #[must_use]
+    pub const fn new(gains_db: [i8; EQ_BANDS]) -> Self {
+        let mut curve = Self::FLAT;
+        let mut band = 0;
+        while band < EQ_BANDS {
+            curve.gains_db[band] = clamp_gain(gains_db[band]);
+            band += 1;
+        }
+        curve
+    }
+
+    /// Factory receive curve for a mode
+    ///
+    /// SSB trims the low end and lifts presence, CW rolls off either
+    /// side of the usual pitch, AM keeps some warmth and FM tames the
+    /// hiss left after de-emphasis.
+    #[must_use]
+    pub const fn rx_default(mode: Mode) -> Self {
+        match mode {
+            Mode::Lsb | Mode::Usb => Self::new([-3, 0, 0, 2, 1]),
+            Mode::Cw | Mode::CwR => Self::new([-6, -2, 0, -4, -8]),
+            Mode::Am => Self::new([2, 1, 0, 0, -2]),
+            Mode::Fm => Self::new([0, 0, 0, -1, -3]),
+        }
+    }
+
+    /// Factory transmit curve for a mode
+    ///
+    /// SSB cuts the lows that eat PEP and lifts the 2-3 kHz articulation
+    /// band; AM and FM are gentler, and CW has no microphone audio.
+    #[must_use]
+    pub const fn tx_default(mode: Mode) -> Self {
+        match mode {
+            Mode::Lsb | Mode::Usb => Self::new([-6, -2, 0, 3, 4]),
+            Mode::Cw | Mode::CwR => Self::FLAT,
+            Mode::Am => Self::new([-2, 0, 0, 2, 1]),
+            Mode::Fm => Self::new([-4, 0, 0, 2, 2]),
+        }
+    }
+
+    /// Get all band gains in dB
+    #[must_use]
+    pub const fn gains_db(&self) -> [i8; EQ_BANDS] {
+        self.gains_db
+    }
+
+    /// Get one band's gain in dB (`None` for a band past [`EQ_BANDS`])
+    #[must_use]
+    pub fn gain_db(&self, band: usize) -> Option<i8> {
+        self.gains_db.get(band).copied()
+    }
+
+    /// Set one band's gain in dB, clamped to +/-[`MAX_EQ_GAIN_DB`]
+    ///
+    /// Returns `false` and changes nothing for a band past [`EQ_BANDS`].
+    pub fn set_gain_db(&mut self, band: usize, gain_db: i8) -> bool {
+        match self.gains_db.get_mut(band) {
+            Some(gain) => {
+                *gain = clamp_gain(gain_db);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if every band is at 0 dB
+    #[must_use]
+    pub fn is_flat(&self) -> bool {
+        self.gains_db.iter().all(|&gain| gain == 0)
+    }
+}
+
+/// Clamp a band gain to the supported range
+const fn clamp_gain(gain_db: i8) -> i8 {
+    if gain_db > MAX_EQ_GAIN_DB {
+        MAX_EQ_GAIN_DB
+    } else if gain_db < -MAX_EQ_GAIN_DB {
+        -MAX_EQ_GAIN_DB
+    } else {
+        gain_db
+    }
+}
+
+/// One equalizer curve per mode for one audio path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EqPresets {
+    /// Curves for SSB, CW, AM and FM
+    curves: [EqCurve; EQ_PRESETS],
+}
+
+impl EqPresets {
+    /// Number of curves stored (SSB, CW, AM, FM)
+    pub const COUNT: usize = EQ_PRESETS;
+
+    /// Factory curves for an audio path
+    #[must_use]
+    pub const fn defaults(path: EqPath) -> Self {
+        let modes = [Mode::Usb, Mode::Cw, Mode::Am, Mode::Fm];
+        let mut curves = [EqCurve::FLAT; EQ_PRESETS];
+        let mut i = 0;
+        while i < EQ_PRESETS {
+            curves[i] = match path {
+                EqPath::Rx => EqCurve::rx_default(modes[i]),
+                EqPath::Tx => EqCurve::tx_default(modes[i]),
+            };
+            i += 1;
+        }
+        Self { curves }
+    }
+
+    /// Get the curve for a mode
+    #[must_use]
+    pub const fn get(&self, mode: Mode) -> EqCurve {
+        self.curves[preset_index(mode)]
+    }
+
+    /// Set the curve for a mode (shared by LSB and USB, and by CW and CW-R)
+    pub fn set(&mut self, mode: Mode, curve: EqCurve) {
+        self.curves[preset_index(mode)] = curve;
+    }
+
+    /// Get all curves in storage order (SSB, CW, AM, FM)
+    #[must_use]
+    pub const fn curves(&self) -> &[EqCurve; EQ_PRESETS] {
+        &self.curves
+    }
+
+    /// Build from curves in storage order
+    #[must_use]
+    pub const fn from_curves(curves: [EqCurve; EQ_PRESETS]) -> Self {
+        Self { curves }
+    }
+}
+
+/// Preset slot of a mode
+const fn preset_index(mode: Mode) -> usize {
+    match mode {
+        Mode::Lsb | Mode::Usb => 0,
+        Mode::Cw | Mode::CwR => 1,
+        Mode::Am => 2,
+        Mode::Fm => 3,
+    }
+}
+
+/// Five-band graphic equalizer
+///
+/// Switched off, or with a flat curve, the audio passes untouched.
+#[derive(Clone, Debug)]
+pub struct Equalizer {
+    /// Sample rate in Hz
+    sample_rate: f32,
+    /// One peaking filter per band
+    bands: [Biquad; EQ_BANDS],
+    /// Current curve
+    curve: EqCurve,
+    /// Whether the equalizer is in circuit
+    enabled: bool,
+}
+
+impl Equalizer {
+    /// Create an equalizer with a flat curve, switched off
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            bands: [Biquad::default(); EQ_BANDS],
+            curve: EqCurve::FLAT,
+            enabled: false,
+        }
+    }
+
+    /// Set the curve, keeping the filter state so the change does not click
+    pub fn set_curve(&mut self, curve: EqCurve) {
+        self.curve = curve;
+        for band in 0..EQ_BANDS {
+            let coeffs = self.band_coeffs(band);
+            self.bands[band].set_coeffs(coeffs);
+        }
+    }
+
+    /// Get the current curve
+    #[must_use]
+    pub const fn curve(&self) -> EqCurve {
+        self.curve
+    }
+
+    /// Set one band's gain in dB (clamped to +/-[`MAX_EQ_GAIN_DB`])
+    ///
+    /// Returns `false` and changes nothing for a band past [`EQ_BANDS`].
+    pub fn set_band(&mut self, band: usize, gain_db: i8) -> bool {
+        if !self.curve.set_gain_db(band, gain_db) {
+            return false;
+        }
+        let coeffs = self.band_coeffs(band);
+        self.bands[band].set_coeffs(coeffs);
+        true
+    }
+
+    /// Switch the equalizer in or out, clearing its state when switched in
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Check if the equalizer is in circuit
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Equalize one sample (unchanged when off or flat)
+    pub fn process(&mut self, input: f32) -> f32 {
+        if !self.enabled || self.curve.is_flat() {
+            return input;
+        }
+        self.bands
+            .iter_mut()
+            .fold(input, |sample, band| band.process(sample))
+    }
+
+    /// Equalize a block of samples in place
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Get the curve's linear magnitude response at a frequency
+    ///
+    /// For drawing the curve; ignores whether the equalizer is on.
+    #[must_use]
+    pub fn magnitude_at(&self, freq: f32) -> f32 {
+        let omega = 2.0 * PI * freq / self.sample_rate;
+        self.bands
+            .iter()
+            .map(|band| biquad_magnitude(&band.coeffs(), omega))
+            .product()
+    }
+
+    /// Clear the filter state
+    pub fn reset(&mut self) {
+        for band in &mut self.bands {
+            band.reset();
+        }
+    }
+
+    /// Design one band's peaking filter for the current curve
+    fn band_coeffs(&self, band: usize) -> BiquadCoeffs {
+        let gain_db = self.curve.gain_db(band).unwrap_or(0);
+        if gain_db == 0 {
+            return BiquadCoeffs::UNITY;
+        }
+        BiquadCoeffs::peaking_eq(EQ_BAND_HZ[band], self.sample_rate, EQ_Q, f32::from(gain_db))
+    }
+}
+
+/// Magnitude of a biquad at `omega` radians per sample
+///
+/// Evaluates numerator and denominator as complex values rather than
+/// [`BiquadCoeffs::magnitude_at`]'s expanded squares, which cancel to
+/// nothing in `f32` for a low band whose poles sit near the unit circle.
+fn biquad_magnitude(coeffs: &BiquadCoeffs, omega: f32) -> f32 {
+    let (sin1, cos1) = (omega.sin(), omega.cos());
+    let (sin2, cos2) = ((2.0 * omega).sin(), (2.0 * omega).cos());
+    let num_re = coeffs.b0 + coeffs.b1 * cos1 + coeffs.b2 * cos2;
+    let num_im = coeffs.b1 * sin1 + coeffs.b2 * sin2;
+    let den_re = 1.0 + coeffs.a1 * cos1 + coeffs.a2 * cos2;
+    let den_im = coeffs.a1 * sin1 + coeffs.a2 * sin2;
+    let den = den_re * den_re + den_im * den_im;
+    if den > 0.0 {
+        ((num_re * num_re + num_im * num_im) / den).sqrt()
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,176 @@
+//! IQ Capture
+//!
+//! Diagnostic snapshot of the receive IQ stream after decimation, for
+//! looking at filter and image rejection problems on a PC without extra
+//! tools. A capture is armed for a number of samples, fills from the
+//! [`RxPipeline`](super::pipeline::RxPipeline), then is read out in
+//! chunks over CAT (`IQ`) either on request or streamed unsolicited.
+//!
+//! Samples are held as signed 16-bit I and Q, full scale 1.0 = 32767,
+//! and sent as little-endian I, Q pairs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // CAT: IQ1024; arms the capture through the control task
+//! pipeline.apply(DspCommand::CaptureIq(1024));
+//! pipeline.process_block(&adc, &mut audio);
+//! // once complete, stream it out a chunk per pass
+//! if let Some(offset) = pipeline.capture_mut().next_chunk() {
+//!     cat.iq_chunk(pipeline.capture(), offset);
+//! }
+//! ```
+
+use super::modulation::IqSample;
+
+/// Most samples one capture holds
+pub const IQ_CAPTURE_LEN: usize = 1024;
+
+/// Samples per CAT chunk (40 bytes, 56 Base64 characters)
+pub const IQ_CHUNK_SAMPLES: usize = 10;
+
+/// Capture progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CaptureState {
+    /// Nothing captured yet
+    #[default]
+    Idle,
+    /// Filling
+    Capturing,
+    /// Full and ready to read
+    Complete,
+}
+
+impl CaptureState {
+    /// CAT code (0 idle, 1 capturing, 2 complete)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Idle => 0,
+            Self::Capturing => 1,
+            Self::Complete => 2,
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for CaptureState {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Idle => defmt::write!(f, "Idle"),
+            Self::Capturing => defmt::write!(f, "Capturing"),
+            Self::Complete => defmt::write!(f, "Complete"),
+        }
+    }
+}
+
+/// Snapshot buffer of decimated IQ samples
+#[derive(Clone, Debug)]
+pub struct IqCapture {
+    /// Captured samples, I then Q
+    samples: [[i16; 2]; IQ_CAPTURE_LEN],
+    /// Samples captured
+    len: usize,
+    /// Samples requested
+    target: usize,
+    /// Offset of the next chunk to stream
+    cursor: usize,
+    /// Capture progress
+    state: CaptureState,
+}
+
+impl IqCapture {
+    /// Create an empty capture
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            samples: [[0; 2]; IQ_CAPTURE_LEN],
+            len: 0,
+            target: 0,
+            cursor: 0,
+            state: CaptureState::Idle,
+        }
+    }
+
+    /// Start capturing `count` samples (clamped to 1..=[`IQ_CAPTURE_LEN`]),
+    /// discarding any earlier capture; returns the count armed
+    pub fn arm(&mut self, count: usize) -> usize {
+        self.target = count.clamp(1, IQ_CAPTURE_LEN);
+        self.len = 0;
+        self.cursor = 0;
+        self.state = CaptureState::Capturing;
+        self.target
+    }
+
+    /// Add a decimated sample (ignored unless capturing)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn push(&mut self, iq: IqSample) {
+        if self.state != CaptureState::Capturing {
+            return;
+        }
+        let code = |x: f32| (x.clamp(-1.0, 1.0) * 32767.0) as i16;
+        self.samples[self.len] = [code(iq.i), code(iq.q)];
+        self.len += 1;
+        if self.len == self.target {
+            self.state = CaptureState::Complete;
+        }
+    }
+
+    /// Get the capture progress
+    #[must_use]
+    pub const fn state(&self) -> CaptureState {
+        self.state
+    }
+
+    /// Get the number of samples captured
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if nothing has been captured
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of samples requested
+    #[must_use]
+    pub const fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Get the captured samples
+    #[must_use]
+    pub fn samples(&self) -> &[[i16; 2]] {
+        &self.samples[..self.len]
+    }
+
+    /// Get up to [`IQ_CHUNK_SAMPLES`] samples from `offset` (empty past
+    /// the end, or before the capture completes)
+    #[must_use]
+    pub fn chunk(&self, offset: usize) -> &[[i16; 2]] {
+        if self.state != CaptureState::Complete || offset >= self.len {
+            return &[];
+        }
+        let end = (offset + IQ_CHUNK_SAMPLES).min(self.len);
+        &self.samples[offset..end]
+    }
+
+    /// Get the offset of the next chunk to stream, once the capture is
+    /// complete, and move past it
+    pub fn next_chunk(&mut self) -> Option<usize> {
+        if self.state != CaptureState::Complete || self.cursor >= self.len {
+            return None;
+        }
+        let offset = self.cursor;
+        self.cursor += IQ_CHUNK_SAMPLES;
+        Some(offset)
+    }
+}
+
+impl Default for IqCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,163 @@
+//! CW Keying Envelope
+//!
+//! Shapes the transmit drive when the keyer opens and closes so the
+//! carrier rises and falls smoothly instead of switching hard. A hard-keyed
+//! carrier splatters key clicks hundreds of Hz either side; a 3-8 ms
+//! raised-cosine or Blackman edge keeps the occupied bandwidth to a few
+//! times the keying speed. Longer rise times narrow the spectrum further
+//! at the cost of softer-sounding keying.
+//!
+//! The envelope follows the key state one sample at a time: a key-up
+//! during the rise turns around from the current level rather than
+//! jumping, so short elements at high speed stay click-free.
+
+use core::f32::consts::PI;
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+
+use crate::config::CW_RISE_TIME_MS;
+
+/// Shortest allowed rise/fall time in milliseconds
+pub const MIN_RISE_TIME_MS: f32 = 3.0;
+
+/// Longest allowed rise/fall time in milliseconds
+pub const MAX_RISE_TIME_MS: f32 = 8.0;
+
+/// Shape of the keying edge
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EnvelopeShape {
+    /// Raised cosine (Hann half-window)
+    #[default]
+    RaisedCosine,
+    /// Blackman half-window
+    Blackman,
+}
+
+impl EnvelopeShape {
+    /// Gain at a point along the edge (0.0 = key up, 1.0 = full drive)
+    #[must_use]
+    pub fn gain(self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Self::RaisedCosine => 0.5 - 0.5 * (PI * x).cos(),
+            Self::Blackman => 0.42 - 0.5 * (PI * x).cos() + 0.08 * (2.0 * PI * x).cos(),
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for EnvelopeShape {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::RaisedCosine => defmt::write!(f, "RaisedCosine"),
+            Self::Blackman => defmt::write!(f, "Blackman"),
+        }
+    }
+}
+
+/// Keying envelope applied to the transmit drive
+#[derive(Clone, Copy, Debug)]
+pub struct KeyingEnvelope {
+    /// Edge shape
+    shape: EnvelopeShape,
+    /// Rise/fall time in milliseconds
+    rise_ms: f32,
+    /// Rise/fall time in samples
+    rise_samples: u32,
+    /// Position along the edge in samples (0 = key up)
+    position: u32,
+    /// Key state
+    key_down: bool,
+}
+
+impl KeyingEnvelope {
+    /// Create an envelope with the default rise time
+    #[must_use]
+    pub fn new(shape: EnvelopeShape, sample_rate: f32) -> Self {
+        let mut envelope = Self {
+            shape,
+            rise_ms: CW_RISE_TIME_MS,
+            rise_samples: 1,
+            position: 0,
+            key_down: false,
+        };
+        envelope.set_rise_time(CW_RISE_TIME_MS, sample_rate);
+        envelope
+    }
+
+    /// Set the edge shape
+    pub fn set_shape(&mut self, shape: EnvelopeShape) {
+        self.shape = shape;
+    }
+
+    /// Get the edge shape
+    #[must_use]
+    pub const fn shape(&self) -> EnvelopeShape {
+        self.shape
+    }
+
+    /// Set rise/fall time (clamped to 3-8 ms)
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn set_rise_time(&mut self, ms: f32, sample_rate: f32) {
+        let fraction = self.fraction();
+        self.rise_ms = ms.clamp(MIN_RISE_TIME_MS, MAX_RISE_TIME_MS);
+        self.rise_samples = ((self.rise_ms / 1000.0 * sample_rate) as u32).max(1);
+        // Keep the current level when the timing changes mid-edge
+        self.position = (fraction * self.rise_samples as f32) as u32;
+    }
+
+    /// Get rise/fall time in milliseconds
+    #[must_use]
+    pub const fn rise_time_ms(&self) -> f32 {
+        self.rise_ms
+    }
+
+    /// Set key state (from `Keyer::is_key_down`)
+    pub fn set_key(&mut self, down: bool) {
+        self.key_down = down;
+    }
+
+    /// Advance one sample and return the drive gain (0.0 to 1.0)
+    pub fn next_gain(&mut self) -> f32 {
+        if self.key_down {
+            self.position = (self.position + 1).min(self.rise_samples);
+        } else {
+            self.position = self.position.saturating_sub(1);
+        }
+        self.shape.gain(self.fraction())
+    }
+
+    /// Shape one sample of transmit drive
+    pub fn process(&mut self, drive: f32) -> f32 {
+        drive * self.next_gain()
+    }
+
+    /// Shape a block of transmit drive in place
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Check if any drive is passing (keyed or still falling)
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.key_down || self.position > 0
+    }
+
+    /// Drop to key-up immediately (PA fault, TX inhibit)
+    pub fn reset(&mut self) {
+        self.key_down = false;
+        self.position = 0;
+    }
+
+    /// Position along the edge as a fraction
+    #[allow(clippy::cast_precision_loss)]
+    fn fraction(&self) -> f32 {
+        self.position as f32 / self.rise_samples as f32
+    }
+}
@@ -5,7 +5,7 @@
 
 use super::filter::{BiquadCoeffs, BiquadFilter, DcBlocker};
 use super::oscillator::{Nco, QuadratureOscillator};
-#[cfg(feature = "embedded")]
+use crate::types::IqOrientation;
 use crate::types::Mode;
 // F32Ext provides sqrt, sin, cos, atan2 for no_std; in std these are built-in
 #[cfg(not(feature = "std"))]
@@ -67,6 +67,19 @@ impl IqSample {
         }
     }
 
+    /// Apply an IQ orientation correction
+    #[must_use]
+    pub const fn oriented(&self, orientation: IqOrientation) -> Self {
+        match orientation {
+            IqOrientation::Normal => *self,
+            IqOrientation::Swapped => Self {
+                i: self.q,
+                q: self.i,
+            },
+            IqOrientation::InvertQ => self.conjugate(),
+        }
+    }
+
     /// Scale by a real factor
     #[must_use]
     pub fn scale(&self, factor: f32) -> Self {
@@ -144,7 +157,6 @@ impl SsbDemodulator {
     }
 
     /// Set sideband mode
-    #[cfg(feature = "embedded")]
     pub fn set_mode(&mut self, mode: Mode) {
         self.usb = matches!(mode, Mode::Usb | Mode::Cw);
     }
@@ -338,6 +350,8 @@ pub struct SsbModulator {
     audio_filter: BiquadFilter,
     /// USB mode
     usb: bool,
+    /// Output IQ orientation correction
+    orientation: IqOrientation,
 }
 
 impl SsbModulator {
@@ -353,11 +367,11 @@ impl SsbModulator {
             carrier: QuadratureOscillator::new(),
             audio_filter: BiquadFilter::with_coeffs(BiquadCoeffs::bandpass(center, q)),
             usb: true,
+            orientation: IqOrientation::Normal,
         }
     }
 
     /// Set sideband mode
-    #[cfg(feature = "embedded")]
     pub fn set_mode(&mut self, mode: Mode) {
         self.usb = matches!(mode, Mode::Usb | Mode::Cw);
     }
@@ -367,6 +381,11 @@ impl SsbModulator {
         self.usb = usb;
     }
 
+    /// Set the IQ orientation correction for the transmit output
+    pub fn set_orientation(&mut self, orientation: IqOrientation) {
+        self.orientation = orientation;
+    }
+
     /// Set carrier frequency for up-conversion
     pub fn set_carrier(&mut self, freq_hz: f32, sample_rate: f32) {
         self.carrier.set_frequency(freq_hz, sample_rate);
@@ -382,11 +401,12 @@ impl SsbModulator {
         let q = self.hilbert.process(filtered);
 
         // Create analytic signal (USB) or conjugate (LSB)
-        if self.usb {
+        let iq = if self.usb {
             IqSample::new(i, q)
         } else {
             IqSample::new(i, -q)
-        }
+        };
+        iq.oriented(self.orientation)
     }
 
     /// Reset modulator state
@@ -430,7 +450,6 @@ impl AmModulator {
 }
 
 /// Complete demodulator supporting all modes
-#[cfg(feature = "embedded")]
 pub struct Demodulator {
     ssb: SsbDemodulator,
     am: AmDemodulator,
@@ -438,7 +457,6 @@ pub struct Demodulator {
     mode: Mode,
 }
 
-#[cfg(feature = "embedded")]
 impl Demodulator {
     /// Create a new multi-mode demodulator
     #[must_use]
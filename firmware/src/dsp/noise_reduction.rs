@@ -2,10 +2,15 @@
 //!
 //! Provides noise reduction algorithms for improving signal quality.
 //! Implements spectral subtraction and other techniques suitable for
-//! real-time embedded processing.
+//! real-time embedded processing. The FFT spectral subtractor comes from
+//! `sdr-dsp-core`, so the radio and the browser reduce noise alike.
 
 #[cfg(feature = "embedded")]
 use micromath::F32Ext;
+use sdr_dsp_core::SpectralSubtractor;
+
+/// Default FFT spectral subtraction strength (0-10)
+pub const DEFAULT_SUBTRACTION_STRENGTH: u8 = 5;
 
 /// Noise blanker for impulse noise removal
 ///
@@ -133,19 +138,67 @@ impl Default for NoiseBlanker {
     }
 }
 
+/// LMS filter taps
+const LMS_TAPS: usize = 32;
+
+/// LMS history length, a power of two holding the delay and the taps
+const LMS_HISTORY: usize = 64;
+
+/// Longest LMS decorrelation delay in samples
+pub const MAX_LMS_DELAY: usize = LMS_HISTORY - LMS_TAPS;
+
+/// Default LMS decorrelation delay in samples
+pub const DEFAULT_LMS_DELAY: usize = 16;
+
+/// Default LMS weight leakage per sample (1.0 = none)
+pub const DEFAULT_LMS_LEAKAGE: f32 = 0.9999;
+
+/// Lowest LMS weight leakage per sample
+pub const MIN_LMS_LEAKAGE: f32 = 0.99;
+
+/// What the LMS filter outputs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LmsMode {
+    /// Prediction error: the carriers and tones the filter can predict
+    /// are taken out, leaving speech and noise
+    #[default]
+    Denoise,
+    /// Prediction: only what the filter can predict passes, so a CW
+    /// signal is peaked out of the noise wherever it is tuned
+    AutoPeak,
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for LmsMode {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Denoise => defmt::write!(f, "Denoise"),
+            Self::AutoPeak => defmt::write!(f, "AutoPeak"),
+        }
+    }
+}
+
 /// LMS (Least Mean Squares) adaptive noise filter
 ///
-/// Adapts to reduce narrowband interference and repetitive noise.
+/// Predicts each sample from samples at least the decorrelation delay
+/// old. Tones stay correlated over the delay and are predicted; noise
+/// does not. [`LmsMode`] picks which of the two parts is output.
 #[derive(Clone)]
 pub struct LmsFilter {
     /// Filter weights
-    weights: [f32; 32],
-    /// Delay line for reference signal
-    delay: [f32; 32],
-    /// Current position in delay line
+    weights: [f32; LMS_TAPS],
+    /// Past input samples (circular)
+    delay: [f32; LMS_HISTORY],
+    /// Next write position in the history
     pos: usize,
     /// Adaptation step size (mu)
     mu: f32,
+    /// Decorrelation delay in samples
+    delay_samples: usize,
+    /// Weight leakage per sample
+    leakage: f32,
+    /// Output selection
+    mode: LmsMode,
     /// Enabled state
     enabled: bool,
 }
@@ -158,57 +211,50 @@ impl LmsFilter {
     #[must_use]
     pub fn new(mu: f32) -> Self {
         Self {
-            weights: [0.0; 32],
-            delay: [0.0; 32],
+            weights: [0.0; LMS_TAPS],
+            delay: [0.0; LMS_HISTORY],
             pos: 0,
             mu: mu.clamp(0.0001, 0.5),
+            delay_samples: DEFAULT_LMS_DELAY,
+            leakage: DEFAULT_LMS_LEAKAGE,
+            mode: LmsMode::default(),
             enabled: true,
         }
     }
 
-    /// Process a sample (noise cancellation mode)
+    /// Process a sample
     ///
-    /// Uses delayed version of input as noise reference
+    /// Uses the input from the decorrelation delay back as the reference
     pub fn process(&mut self, input: f32) -> f32 {
         if !self.enabled {
             return input;
         }
 
-        // Store input in delay line
-        self.delay[self.pos] = input;
+        let pos = self.pos;
+        let delay = self.delay_samples;
+        let tap = |n: usize| (pos + LMS_HISTORY - delay - n) & (LMS_HISTORY - 1);
 
-        // Compute filter output (estimate of noise)
-        let mut noise_estimate = 0.0;
-        let mut idx = self.pos;
-        for &w in &self.weights {
-            noise_estimate += w * self.delay[idx];
-            if idx == 0 {
-                idx = 31;
-            } else {
-                idx -= 1;
-            }
+        // Prediction from the delayed samples
+        let mut estimate = 0.0;
+        for (n, &w) in self.weights.iter().enumerate() {
+            estimate += w * self.delay[tap(n)];
         }
 
         // Error signal (desired - estimated)
-        let error = input - noise_estimate;
-
-        // Update weights using LMS algorithm
-        idx = self.pos;
-        for w in &mut self.weights {
-            *w += self.mu * error * self.delay[idx];
-            // Limit weight growth
-            *w = w.clamp(-1.0, 1.0);
-            if idx == 0 {
-                idx = 31;
-            } else {
-                idx -= 1;
-            }
+        let error = input - estimate;
+
+        // Update weights using LMS algorithm, leaking toward zero
+        for (n, w) in self.weights.iter_mut().enumerate() {
+            *w = (*w * self.leakage + self.mu * error * self.delay[tap(n)]).clamp(-1.0, 1.0);
         }
 
-        // Advance position
-        self.pos = (self.pos + 1) & 31;
+        self.delay[pos] = input;
+        self.pos = (pos + 1) & (LMS_HISTORY - 1);
 
-        error
+        match self.mode {
+            LmsMode::Denoise => error,
+            LmsMode::AutoPeak => estimate,
+        }
     }
 
     /// Process a block of samples in-place
@@ -229,6 +275,44 @@ impl LmsFilter {
         self.mu
     }
 
+    /// Select denoise or auto-peak output
+    pub fn set_mode(&mut self, mode: LmsMode) {
+        self.mode = mode;
+    }
+
+    /// Get the output selection
+    #[must_use]
+    pub fn mode(&self) -> LmsMode {
+        self.mode
+    }
+
+    /// Set the decorrelation delay (1 to [`MAX_LMS_DELAY`] samples)
+    ///
+    /// Longer delays decorrelate more of the noise but track changing
+    /// signals less closely.
+    pub fn set_delay(&mut self, samples: usize) {
+        self.delay_samples = samples.clamp(1, MAX_LMS_DELAY);
+    }
+
+    /// Get the decorrelation delay in samples
+    #[must_use]
+    pub fn delay(&self) -> usize {
+        self.delay_samples
+    }
+
+    /// Set the weight leakage per sample ([`MIN_LMS_LEAKAGE`] to 1.0)
+    ///
+    /// Lower values forget a signal that has gone sooner.
+    pub fn set_leakage(&mut self, leakage: f32) {
+        self.leakage = leakage.clamp(MIN_LMS_LEAKAGE, 1.0);
+    }
+
+    /// Get the weight leakage per sample
+    #[must_use]
+    pub fn leakage(&self) -> f32 {
+        self.leakage
+    }
+
     /// Enable/disable the filter
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
@@ -368,16 +452,20 @@ pub struct NoiseReductionChain {
     lms: LmsFilter,
     /// Spectral reducer for broadband noise
     spectral: SpectralNoiseReducer,
+    /// FFT spectral subtraction for broadband noise
+    subtractor: SpectralSubtractor,
 }
 
 impl NoiseReductionChain {
     /// Create a new noise reduction chain
     #[must_use]
+    #[allow(clippy::cast_precision_loss)]
     pub fn new(sample_rate: u32) -> Self {
         Self {
             blanker: NoiseBlanker::new(sample_rate, 0.5, 100),
             lms: LmsFilter::new(0.01),
             spectral: SpectralNoiseReducer::new(0.5),
+            subtractor: SpectralSubtractor::new(sample_rate as f32, DEFAULT_SUBTRACTION_STRENGTH),
         }
     }
 
@@ -385,7 +473,8 @@ impl NoiseReductionChain {
     pub fn process(&mut self, input: f32) -> f32 {
         let sample = self.blanker.process(input);
         let sample = self.lms.process(sample);
-        self.spectral.process(sample)
+        let sample = self.spectral.process(sample);
+        self.subtractor.process(sample)
     }
 
     /// Process a block of samples in-place
@@ -410,11 +499,20 @@ impl NoiseReductionChain {
         &mut self.spectral
     }
 
+    /// Get mutable reference to the FFT spectral subtractor
+    ///
+    /// Its strength runs from 0 (off) to 10; it delays the audio by
+    /// [`sdr_dsp_core::nr::SPECTRAL_FRAME`] samples while on.
+    pub fn subtractor_mut(&mut self) -> &mut SpectralSubtractor {
+        &mut self.subtractor
+    }
+
     /// Reset all stages
     pub fn reset(&mut self) {
         self.blanker.reset();
         self.lms.reset();
         self.spectral.reset();
+        self.subtractor.reset();
     }
 }
 
@@ -572,6 +670,75 @@ mod tests {
         // Weights should be cleared
     }
 
+    /// Deterministic white noise in -0.5..0.5
+    fn white_noise(state: &mut u32) -> f32 {
+        *state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (*state >> 8) as f32 / (1 << 24) as f32 - 0.5
+    }
+
+    /// Mean square error of the filter output against a tone, once converged
+    fn lms_tone_error(lms: &mut LmsFilter, tone_level: f32, noise_level: f32) -> (f32, f32) {
+        let mut state = 1;
+        let (mut input_err, mut output_err) = (0.0, 0.0);
+        for n in 0..48000 {
+            let tone =
+                tone_level * (2.0 * core::f32::consts::PI * 700.0 * n as f32 / 48000.0).sin();
+            let input = tone + noise_level * white_noise(&mut state);
+            let output = lms.process(input);
+            if n >= 24000 {
+                input_err += (input - tone) * (input - tone);
+                output_err += (output - tone) * (output - tone);
+            }
+        }
+        (input_err, output_err)
+    }
+
+    #[test]
+    fn lms_auto_peak_lifts_tone_out_of_noise() {
+        let mut lms = LmsFilter::new(0.005);
+        lms.set_mode(LmsMode::AutoPeak);
+        let (input_err, output_err) = lms_tone_error(&mut lms, 0.3, 0.5);
+        assert!(output_err < input_err / 2.0, "{output_err} vs {input_err}");
+    }
+
+    #[test]
+    fn lms_denoise_removes_carrier() {
+        let mut lms = LmsFilter::new(0.005);
+        assert_eq!(lms.mode(), LmsMode::Denoise);
+        // Output against a silent "tone": what is left of the carrier
+        let mut carrier_power = 0.0;
+        let mut output_power = 0.0;
+        for n in 0..48000 {
+            let input = 0.3 * (2.0 * core::f32::consts::PI * 1000.0 * n as f32 / 48000.0).sin();
+            let output = lms.process(input);
+            if n >= 24000 {
+                carrier_power += input * input;
+                output_power += output * output;
+            }
+        }
+        assert!(
+            output_power < carrier_power / 100.0,
+            "{output_power} vs {carrier_power}"
+        );
+    }
+
+    #[test]
+    fn lms_delay_and_leakage_clamp() {
+        let mut lms = LmsFilter::default();
+        assert_eq!(lms.delay(), DEFAULT_LMS_DELAY);
+        assert_eq!(lms.leakage(), DEFAULT_LMS_LEAKAGE);
+
+        lms.set_delay(0);
+        assert_eq!(lms.delay(), 1);
+        lms.set_delay(1000);
+        assert_eq!(lms.delay(), MAX_LMS_DELAY);
+
+        lms.set_leakage(0.5);
+        assert_eq!(lms.leakage(), MIN_LMS_LEAKAGE);
+        lms.set_leakage(2.0);
+        assert_eq!(lms.leakage(), 1.0);
+    }
+
     #[test]
     fn lms_process_block() {
         let mut lms = LmsFilter::default();
@@ -704,6 +871,7 @@ mod tests {
         assert!(chain.blanker.is_enabled());
         assert!(chain.lms.is_enabled());
         assert!(chain.spectral.is_enabled());
+        assert_eq!(chain.subtractor.strength(), DEFAULT_SUBTRACTION_STRENGTH);
     }
 
     #[test]
@@ -740,10 +908,22 @@ mod tests {
         chain.blanker_mut().set_threshold(0.3);
         chain.lms_mut().set_mu(0.02);
         chain.spectral_mut().set_reduction(0.7);
+        chain.subtractor_mut().set_strength(8);
 
         // Should not panic
     }
 
+    #[test]
+    fn chain_subtractor_off_passes_through() {
+        let mut chain = NoiseReductionChain::default();
+        chain.blanker_mut().set_enabled(false);
+        chain.lms_mut().set_enabled(false);
+        chain.spectral_mut().set_enabled(false);
+        chain.subtractor_mut().set_strength(0);
+
+        assert_eq!(chain.process(0.3), 0.3);
+    }
+
     #[test]
     fn chain_reset() {
         let mut chain = NoiseReductionChain::default();
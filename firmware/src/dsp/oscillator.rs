@@ -2,10 +2,15 @@
 //!
 //! Provides oscillators for tone generation and carrier synthesis.
 //! Uses efficient algorithms suitable for embedded systems.
+//!
+//! The CW sidetone and DTMF generators run on the shared
+//! [`sdr_dsp_core::tone`] bank, which also provides the two-tone test
+//! signal.
 
 use core::f32::consts::PI;
 #[cfg(feature = "embedded")]
 use micromath::F32Ext;
+use sdr_dsp_core::ToneGenerator;
 
 /// Sine wave oscillator using direct computation
 #[derive(Clone, Copy, Debug)]
@@ -213,11 +218,14 @@ impl Default for Nco {
 }
 
 /// CW sidetone oscillator with envelope shaping
+///
+/// The envelope ramps linearly over the rise time and is shaped with a
+/// raised cosine, so key-down and key-up are click-free.
 #[derive(Clone, Copy, Debug)]
 pub struct CwToneGenerator {
     /// Tone oscillator
-    osc: SineOscillator,
-    /// Current envelope level
+    osc: ToneGenerator<1>,
+    /// Envelope ramp position (0.0 to 1.0)
     envelope: f32,
     /// Envelope attack rate
     attack_rate: f32,
@@ -231,8 +239,7 @@ impl CwToneGenerator {
     /// Create a new CW tone generator
     #[must_use]
     pub fn new(freq_hz: f32, sample_rate: f32) -> Self {
-        let mut osc = SineOscillator::new();
-        osc.set_frequency(freq_hz, sample_rate);
+        let osc = ToneGenerator::single(sample_rate, freq_hz, 1.0);
 
         // 5ms attack/decay at sample rate
         let rate = 1.0 / (0.005 * sample_rate);
@@ -253,7 +260,8 @@ impl CwToneGenerator {
 
     /// Set tone frequency
     pub fn set_frequency(&mut self, freq_hz: f32, sample_rate: f32) {
-        self.osc.set_frequency(freq_hz, sample_rate);
+        self.osc.set_sample_rate(sample_rate);
+        self.osc.set_frequency(0, freq_hz);
     }
 
     /// Set rise/fall time in milliseconds
@@ -274,12 +282,18 @@ impl CwToneGenerator {
 
         // Generate shaped tone
         if self.envelope > 0.0001 {
-            self.osc.next() * self.envelope
+            self.osc.next_sample() * self.level()
         } else {
             0.0
         }
     }
 
+    /// Current envelope gain (raised cosine of the ramp position)
+    #[must_use]
+    pub fn level(&self) -> f32 {
+        0.5 - 0.5 * (PI * self.envelope).cos()
+    }
+
     /// Check if tone is active
     #[must_use]
     pub fn is_active(&self) -> bool {
@@ -290,10 +304,8 @@ impl CwToneGenerator {
 /// DTMF tone generator
 #[derive(Clone, Copy, Debug)]
 pub struct DtmfGenerator {
-    /// Low frequency oscillator
-    low_osc: SineOscillator,
-    /// High frequency oscillator
-    high_osc: SineOscillator,
+    /// Low and high group tones, half amplitude each
+    tones: ToneGenerator<2>,
     /// Envelope for soft keying
     envelope: f32,
     /// Attack/decay rate
@@ -311,16 +323,12 @@ impl DtmfGenerator {
     /// Create a new DTMF generator
     #[must_use]
     pub fn new(sample_rate: f32) -> Self {
-        let mut gen = Self {
-            low_osc: SineOscillator::new(),
-            high_osc: SineOscillator::new(),
+        Self {
+            tones: ToneGenerator::two_tone(sample_rate, 697.0, 1209.0, 1.0),
             envelope: 0.0,
             rate: 1.0 / (0.002 * sample_rate), // 2ms rise time
             active: false,
-        };
-        gen.low_osc.set_frequency(697.0, sample_rate);
-        gen.high_osc.set_frequency(1209.0, sample_rate);
-        gen
+        }
     }
 
     /// Set digit (0-9, *, #, A-D)
@@ -345,8 +353,9 @@ impl DtmfGenerator {
             _ => return,
         };
 
-        self.low_osc.set_frequency(Self::LOW_FREQS[low_idx], sample_rate);
-        self.high_osc.set_frequency(Self::HIGH_FREQS[high_idx], sample_rate);
+        self.tones.set_sample_rate(sample_rate);
+        self.tones.set_frequency(0, Self::LOW_FREQS[low_idx]);
+        self.tones.set_frequency(1, Self::HIGH_FREQS[high_idx]);
         self.active = true;
     }
 
@@ -365,7 +374,7 @@ impl DtmfGenerator {
         }
 
         if self.envelope > 0.0001 {
-            (self.low_osc.next() + self.high_osc.next()) * 0.5 * self.envelope
+            self.tones.next_sample() * self.envelope
         } else {
             0.0
         }
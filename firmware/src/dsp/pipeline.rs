@@ -0,0 +1,542 @@
+//! Receive Pipeline
+//!
+//! Block processing for the DSP task: interleaved 12-bit I/Q ADC codes
+//! (or I/Q samples scaled from them with [`iq_from_adc`]) at
+//! [`IQ_SAMPLE_RATE`] in, audio at [`AUDIO_SAMPLE_RATE`] out. Each
+//! block is oriented, IQ balanced, run through the IQ noise blanker,
+//! decimated to the audio rate by averaging groups of [`DECIMATION`]
+//! pairs, demodulated, and run through the [`AudioChain`] for the mode.
+//! Partial groups carry over to the next block, so block sizes need not
+//! be a multiple of the decimation.
+//!
+//! The pipeline follows the [`DspCommand`]s sent by the radio control
+//! task; everything else about the chain (volume, notches, bypass) is
+//! reached through [`RxPipeline::chain_mut`], and the blanker threshold
+//! and width through [`RxPipeline::blanker_mut`]. The pipeline keeps the
+//! receive equalizer curve for each mode and loads it on a mode change.
+//!
+//! In CW the chain measures the received tone against the sidetone
+//! pitch, and in SSB the strongest tone against the passband centre;
+//! [`RxPipeline::zero_beat_hz`] turns that into the VFO change that
+//! would zero-beat the station or center it. [`DspCommand::AutoTune`]
+//! latches that correction for [`RxPipeline::take_tune_correction`].
+//!
+//! When switched on, the [`DtmfDetector`] listens to the demodulated
+//! audio ahead of the AGC and volume, and queues the digits it hears for
+//! [`RxPipeline::pop_dtmf_digit`].
+//!
+//! [`DspCommand::CaptureIq`] arms the [`IqCapture`], which then records
+//! the decimated IQ ahead of the demodulator for [`RxPipeline::capture`].
+//!
+//! The [`IqBalance`] corrects the QSD's gain and phase imbalance at the
+//! full IQ rate. The pipeline holds an [`IqCalibration`] per band (from
+//! the settings) and loads the current band's on [`DspCommand::SetBand`];
+//! [`DspCommand::SetIqAdaptive`] lets the correction follow the signal
+//! instead, and [`RxPipeline::iq_balance`] reads back what it found.
+//!
+//! [`DspCommand::Announce`] queues a Morse annunciation on the
+//! [`Annunciator`], which keys the sidetone a millisecond at a time as
+//! audio is produced, at its own level.
+
+use heapless::Deque;
+use sdr_dsp_core::{IqBalance, IqBlanker, IqCalibration, IqSample as CoreIq, SnrReading};
+
+use super::agc::AgcConfig;
+use super::audio_chain::{AudioChain, DEFAULT_SIDETONE_HZ};
+use super::dtmf::DtmfDetector;
+use super::equalizer::{EqCurve, EqPath, EqPresets};
+use super::filter_design::{AmBandwidth, CwBandwidth, SsbBandwidth};
+use super::iq_capture::IqCapture;
+use super::modulation::{Demodulator, IqSample};
+use crate::config::{AUDIO_SAMPLE_RATE, IQ_SAMPLE_RATE};
+use crate::dsp::bypass::DspStage;
+use crate::radio::annunciator::Annunciator;
+use crate::radio::control::DspCommand;
+use crate::radio::state::AgcMode;
+use crate::types::{Band, IqOrientation, Mode};
+
+/// IQ samples averaged into each audio sample
+#[allow(clippy::cast_possible_truncation)]
+pub const DECIMATION: usize = (IQ_SAMPLE_RATE / AUDIO_SAMPLE_RATE) as usize;
+
+/// ADC mid-scale code (0 V after the bias)
+const ADC_MID: f32 = 2048.0;
+
+/// Decoded DTMF digits held until read
+pub const DTMF_QUEUE_LEN: usize = 16;
+
+/// Audio samples per annunciator step (one millisecond)
+const ANNOUNCE_STEP_SAMPLES: u32 = AUDIO_SAMPLE_RATE / 1000;
+
+/// Scale a pair of 12-bit ADC codes to an I/Q sample (-1.0 to 1.0)
+#[must_use]
+pub fn iq_from_adc(i: u16, q: u16) -> IqSample {
+    IqSample::new(
+        (f32::from(i) - ADC_MID) / ADC_MID,
+        (f32::from(q) - ADC_MID) / ADC_MID,
+    )
+}
+
+/// Receive DSP from ADC codes to audio
+pub struct RxPipeline {
+    /// Multi-mode demodulator at the audio rate
+    demod: Demodulator,
+    /// Gain and phase imbalance correction at the IQ rate
+    iq_balance: IqBalance,
+    /// IQ balance calibration per band
+    iq_calibration: [IqCalibration; Band::COUNT],
+    /// Band the radio is on, for its IQ balance calibration
+    band: Option<Band>,
+    /// Impulse blanker at the IQ rate, ahead of decimation
+    blanker: IqBlanker,
+    /// Filters, AGC and volume for the mode
+    chain: AudioChain,
+    /// DTMF detector on the demodulated audio
+    dtmf: DtmfDetector,
+    /// Decoded DTMF digits not yet read
+    dtmf_digits: Deque<char, DTMF_QUEUE_LEN>,
+    /// Receive equalizer curve per mode
+    eq_presets: EqPresets,
+    /// Current mode
+    mode: Mode,
+    /// Current AGC mode
+    agc: AgcMode,
+    /// CW sidetone pitch in Hz, also the zero-beat reference
+    sidetone_hz: f32,
+    /// Dial correction measured for the last auto-tune, not yet taken
+    tune_correction: Option<i32>,
+    /// I/Q wiring correction
+    orientation: IqOrientation,
+    /// Sum of the pairs in the current decimation group
+    acc: IqSample,
+    /// Pairs in the current decimation group
+    acc_len: usize,
+    /// Diagnostic snapshot of the decimated IQ
+    capture: IqCapture,
+    /// Morse annunciations on the sidetone
+    annunciator: Annunciator,
+    /// Audio samples since the last annunciator step
+    announce_samples: u32,
+    /// Sidetone level to restore once an annunciation ends
+    saved_sidetone_level: Option<f32>,
+}
+
+impl RxPipeline {
+    /// Create a pipeline for a mode
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(mode: Mode) -> Self {
+        let mut demod = Demodulator::new(AUDIO_SAMPLE_RATE as f32);
+        demod.set_mode(mode);
+        let mut pipeline = Self {
+            demod,
+            iq_balance: IqBalance::new(),
+            iq_calibration: [IqCalibration::IDENTITY; Band::COUNT],
+            band: None,
+            blanker: IqBlanker::new(IQ_SAMPLE_RATE as f32),
+            chain: chain_for(mode),
+            dtmf: DtmfDetector::new(AUDIO_SAMPLE_RATE),
+            dtmf_digits: Deque::new(),
+            eq_presets: EqPresets::defaults(EqPath::Rx),
+            mode,
+            agc: AgcMode::default(),
+            sidetone_hz: DEFAULT_SIDETONE_HZ,
+            tune_correction: None,
+            orientation: IqOrientation::Normal,
+            acc: IqSample::default(),
+            acc_len: 0,
+            capture: IqCapture::new(),
+            annunciator: Annunciator::new(),
+            announce_samples: 0,
+            saved_sidetone_level: None,
+        };
+        pipeline.set_agc(AgcMode::default());
+        pipeline
+            .chain
+            .equalizer_mut()
+            .set_curve(pipeline.eq_presets.get(mode));
+        pipeline
+    }
+
+    /// Apply a command from the radio control task
+    pub fn apply(&mut self, command: DspCommand) {
+        match command {
+            DspCommand::SetMode(mode) => self.set_mode(mode),
+            DspCommand::SetAgc(agc) => self.set_agc(agc),
+            DspCommand::SetNoiseBlanker(on) => {
+                self.blanker.set_enabled(on);
+                self.chain.set_stage_bypass(DspStage::NoiseBlanker, !on);
+            }
+            DspCommand::SetTransmit(tx) => self.chain.set_muted(tx),
+            DspCommand::AutoTune => {
+                self.tune_correction = self.zero_beat_hz().filter(|&hz| hz != 0);
+            }
+            DspCommand::CaptureIq(count) => {
+                self.capture.arm(usize::from(count));
+            }
+            DspCommand::SetBand(band) => {
+                self.band = band;
+                self.load_iq_calibration();
+            }
+            DspCommand::SetIqAdaptive(on) => self.iq_balance.set_adaptive(on),
+            DspCommand::Announce(event) => {
+                self.annunciator.announce(event);
+            }
+        }
+    }
+
+    /// Get the current mode
+    #[must_use]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Get the current AGC mode
+    #[must_use]
+    pub const fn agc(&self) -> AgcMode {
+        self.agc
+    }
+
+    /// Set the CW sidetone pitch in Hz (follow the keyer setting)
+    ///
+    /// The received tone is zero-beat when it matches this pitch.
+    pub fn set_sidetone_hz(&mut self, sidetone_hz: f32) {
+        self.sidetone_hz = sidetone_hz;
+        self.chain.set_sidetone_frequency(sidetone_hz);
+    }
+
+    /// Get the CW sidetone pitch in Hz
+    #[must_use]
+    pub const fn sidetone_hz(&self) -> f32 {
+        self.sidetone_hz
+    }
+
+    /// Set the I/Q wiring correction
+    pub fn set_orientation(&mut self, orientation: IqOrientation) {
+        self.orientation = orientation;
+    }
+
+    /// Replace the per-band IQ balance calibrations (e.g. from the
+    /// settings), loading the current band's
+    pub fn set_iq_calibrations(&mut self, calibrations: [IqCalibration; Band::COUNT]) {
+        self.iq_calibration = calibrations;
+        self.load_iq_calibration();
+    }
+
+    /// Set the IQ balance calibration of a band, loading it if the
+    /// radio is on that band
+    pub fn set_iq_calibration(&mut self, band: Band, calibration: IqCalibration) {
+        self.iq_calibration[band.index()] = calibration;
+        if self.band == Some(band) {
+            self.load_iq_calibration();
+        }
+    }
+
+    /// Get the IQ balance calibration held for a band
+    #[must_use]
+    pub const fn iq_calibration(&self, band: Band) -> IqCalibration {
+        self.iq_calibration[band.index()]
+    }
+
+    /// Get the IQ balance corrector (correction in use and image
+    /// rejection estimate)
+    #[must_use]
+    pub const fn iq_balance(&self) -> &IqBalance {
+        &self.iq_balance
+    }
+
+    /// Get the audio chain (volume, notches, stage bypass)
+    pub fn chain_mut(&mut self) -> &mut AudioChain {
+        &mut self.chain
+    }
+
+    /// Get the IQ noise blanker
+    #[must_use]
+    pub const fn blanker(&self) -> &IqBlanker {
+        &self.blanker
+    }
+
+    /// Get the IQ noise blanker (threshold and width)
+    pub fn blanker_mut(&mut self) -> &mut IqBlanker {
+        &mut self.blanker
+    }
+
+    /// Get the DTMF detector
+    #[must_use]
+    pub const fn dtmf(&self) -> &DtmfDetector {
+        &self.dtmf
+    }
+
+    /// Get the DTMF detector (on/off and level)
+    pub fn dtmf_mut(&mut self) -> &mut DtmfDetector {
+        &mut self.dtmf
+    }
+
+    /// Take the oldest decoded DTMF digit
+    ///
+    /// Digits heard while the queue is full are dropped.
+    pub fn pop_dtmf_digit(&mut self) -> Option<char> {
+        self.dtmf_digits.pop_front()
+    }
+
+    /// Get the receive equalizer curves
+    #[must_use]
+    pub const fn eq_presets(&self) -> &EqPresets {
+        &self.eq_presets
+    }
+
+    /// Replace the receive equalizer curves (e.g. from the settings),
+    /// loading the current mode's curve
+    pub fn set_eq_presets(&mut self, presets: EqPresets) {
+        self.eq_presets = presets;
+        self.chain.equalizer_mut().set_curve(presets.get(self.mode));
+    }
+
+    /// Set the receive equalizer curve for the current mode
+    pub fn set_eq_curve(&mut self, curve: EqCurve) {
+        self.eq_presets.set(self.mode, curve);
+        self.chain.equalizer_mut().set_curve(curve);
+    }
+
+    /// Get the S-meter reading as 0-100 for the display
+    #[must_use]
+    pub fn smeter_percent(&self) -> u8 {
+        self.chain.smeter().as_percent()
+    }
+
+    /// Get the audio SNR reading of the receive chain
+    #[must_use]
+    pub fn snr(&self) -> Option<SnrReading> {
+        self.chain.snr()
+    }
+
+    /// Get the received tone's offset from the reference in Hz (the
+    /// sidetone in CW, the passband centre in SSB; `None` in AM and FM or
+    /// without a clear tone)
+    #[must_use]
+    pub fn pitch_offset_hz(&self) -> Option<f32> {
+        self.chain.pitch_offset_hz()
+    }
+
+    /// Get the VFO change in Hz that would zero-beat the received CW tone
+    /// (or center the strongest SSB tone in the passband)
+    ///
+    /// A tone above the reference is above the dial in CW and USB and
+    /// below it in CW-R and LSB. `None` when there is no tone to measure.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn zero_beat_hz(&self) -> Option<i32> {
+        let offset = self.pitch_offset_hz()?;
+        let hz = (if offset < 0.0 { offset - 0.5 } else { offset + 0.5 }) as i32;
+        Some(if self.mode.inverted_sideband() { -hz } else { hz })
+    }
+
+    /// Take the dial correction from the last [`DspCommand::AutoTune`]
+    ///
+    /// `None` if no tone was found or the radio is already on it.
+    pub fn take_tune_correction(&mut self) -> Option<i32> {
+        self.tune_correction.take()
+    }
+
+    /// Process a block of interleaved I/Q ADC codes into audio
+    ///
+    /// Writes one audio sample per [`DECIMATION`] pairs and returns how
+    /// many were written; audio that does not fit in `audio` is dropped.
+    pub fn process_block(&mut self, iq: &[u16], audio: &mut [f32]) -> usize {
+        let mut written = 0;
+        for pair in iq.chunks_exact(2) {
+            self.process_sample(iq_from_adc(pair[0], pair[1]), audio, &mut [], &mut written);
+        }
+        written
+    }
+
+    /// Process a block of I/Q samples (as from [`iq_from_adc`]) into audio
+    ///
+    /// Same as [`process_block`](Self::process_block), for samples already
+    /// taken off the ADC codes by the audio I/O task.
+    pub fn process_iq(&mut self, iq: &[IqSample], audio: &mut [f32]) -> usize {
+        self.process_iq_tap(iq, audio, &mut [])
+    }
+
+    /// Process a block of I/Q samples into audio, keeping the decimated I/Q
+    ///
+    /// Same as [`process_iq`](Self::process_iq), and also writes the I/Q
+    /// at the audio rate, ahead of the demodulator, into `tap` (for the
+    /// USB audio stream). `tap[n]` goes with `audio[n]`.
+    pub fn process_iq_tap(
+        &mut self,
+        iq: &[IqSample],
+        audio: &mut [f32],
+        tap: &mut [IqSample],
+    ) -> usize {
+        let mut written = 0;
+        for &sample in iq {
+            self.process_sample(sample, audio, tap, &mut written);
+        }
+        written
+    }
+
+    /// Run one I/Q sample through the chain, appending any audio sample
+    /// and its decimated I/Q
+    #[allow(clippy::cast_precision_loss)]
+    fn process_sample(
+        &mut self,
+        sample: IqSample,
+        audio: &mut [f32],
+        tap: &mut [IqSample],
+        written: &mut usize,
+    ) {
+        let sample = sample.oriented(self.orientation);
+        let sample = self.iq_balance.process(CoreIq::new(sample.i, sample.q));
+        let sample = self.blanker.process(sample);
+        self.acc.i += sample.i;
+        self.acc.q += sample.q;
+        self.acc_len += 1;
+        if self.acc_len < DECIMATION {
+            return;
+        }
+
+        let scale = 1.0 / DECIMATION as f32;
+        let decimated = IqSample::new(self.acc.i * scale, self.acc.q * scale);
+        self.acc = IqSample::default();
+        self.acc_len = 0;
+        self.capture.push(decimated);
+        if let Some(slot) = tap.get_mut(*written) {
+            *slot = decimated;
+        }
+
+        let demodulated = self.demod.process(decimated);
+        if let Some(digit) = self.dtmf.process(demodulated) {
+            let _ = self.dtmf_digits.push_back(digit);
+        }
+        self.announce_samples += 1;
+        if self.announce_samples == ANNOUNCE_STEP_SAMPLES {
+            self.announce_samples = 0;
+            self.step_annunciator();
+        }
+        let out = self.chain.process(demodulated);
+        if let Some(slot) = audio.get_mut(*written) {
+            *slot = out;
+            *written += 1;
+        }
+    }
+
+    /// Get the annunciator (to enable it or set its speed and level)
+    pub fn annunciator_mut(&mut self) -> &mut Annunciator {
+        &mut self.annunciator
+    }
+
+    /// Advance the annunciator by a millisecond and key the sidetone
+    ///
+    /// The sidetone runs at the annunciator's level while it is active
+    /// and goes back to its own level afterwards.
+    fn step_annunciator(&mut self) {
+        match (self.annunciator.is_active(), self.saved_sidetone_level) {
+            (true, None) => {
+                self.saved_sidetone_level = Some(self.chain.sidetone_level());
+                self.chain.set_sidetone_level(self.annunciator.level());
+            }
+            (false, Some(level)) => {
+                self.chain.set_sidetone_key(false);
+                self.chain.set_sidetone_level(level);
+                self.saved_sidetone_level = None;
+                return;
+            }
+            (false, None) => return,
+            (true, Some(_)) => {}
+        }
+        let key = self.annunciator.update(1);
+        self.chain.set_sidetone_key(key);
+    }
+
+    /// Get the IQ capture
+    #[must_use]
+    pub const fn capture(&self) -> &IqCapture {
+        &self.capture
+    }
+
+    /// Get mutable access to the IQ capture (to stream it out)
+    pub fn capture_mut(&mut self) -> &mut IqCapture {
+        &mut self.capture
+    }
+
+    /// Clear all filter and decimation state
+    pub fn reset(&mut self) {
+        self.demod.reset();
+        self.iq_balance.reset();
+        self.blanker.reset();
+        self.chain.reset();
+        self.dtmf.reset();
+        self.dtmf_digits.clear();
+        self.acc = IqSample::default();
+        self.acc_len = 0;
+    }
+
+    /// Load the current band's IQ balance calibration (none outside the
+    /// bands)
+    fn load_iq_calibration(&mut self) {
+        let calibration = self
+            .band
+            .map_or(IqCalibration::IDENTITY, |band| self.iq_calibration[band.index()]);
+        self.iq_balance.set_calibration(calibration);
+    }
+
+    /// Switch mode, rebuilding the chain but keeping its settings
+    fn set_mode(&mut self, mode: Mode) {
+        if mode == self.mode {
+            return;
+        }
+        let volume = self.chain.volume();
+        let muted = self.chain.is_muted();
+        let nb_bypassed = self.chain.is_stage_bypassed(DspStage::NoiseBlanker);
+        let notches = self.chain.notches().clone();
+        let eq_enabled = self.chain.equalizer().is_enabled();
+
+        self.mode = mode;
+        self.demod.set_mode(mode);
+        self.demod.reset();
+        self.chain = chain_for(mode);
+        self.chain.set_volume(volume);
+        self.chain.set_muted(muted);
+        self.chain.set_stage_bypass(DspStage::NoiseBlanker, nb_bypassed);
+        self.chain.set_notches(notches);
+        self.chain.set_sidetone_frequency(self.sidetone_hz);
+        let equalizer = self.chain.equalizer_mut();
+        equalizer.set_curve(self.eq_presets.get(mode));
+        equalizer.set_enabled(eq_enabled);
+        self.set_agc(self.agc);
+    }
+
+    /// Set the AGC time constants, bypassing the AGC when it is off
+    fn set_agc(&mut self, agc: AgcMode) {
+        self.agc = agc;
+        if agc == AgcMode::Off {
+            self.chain.set_stage_bypass(DspStage::Agc, true);
+            return;
+        }
+        let mut config = AgcConfig::from_ms(AUDIO_SAMPLE_RATE, agc.attack_ms(), agc.decay_ms());
+        config.hang_samples = agc.hang_ms() * (AUDIO_SAMPLE_RATE / 1000);
+        config.max_gain_db = agc.max_gain_db();
+        self.chain.set_agc_config(config);
+        self.chain.set_stage_bypass(DspStage::Agc, false);
+    }
+}
+
+impl Default for RxPipeline {
+    fn default() -> Self {
+        Self::new(Mode::Usb)
+    }
+}
+
+/// Build the audio chain for a mode with its default bandwidth
+fn chain_for(mode: Mode) -> AudioChain {
+    match mode {
+        Mode::Lsb | Mode::Usb => AudioChain::new_ssb(SsbBandwidth::default()),
+        Mode::Cw | Mode::CwR => AudioChain::new_cw(
+            super::audio_chain::DEFAULT_SIDETONE_HZ,
+            CwBandwidth::default(),
+        ),
+        Mode::Am => AudioChain::new_am(AmBandwidth::default()),
+        Mode::Fm => AudioChain::new_fm(),
+    }
+}
@@ -13,6 +13,39 @@
 //! - Higher VCO frequencies (closer to 900 MHz)
 //! - Integer multisynth divisors when possible
 //! - Even multisynth divisors for quadrature operation
+//!
+//! # Crystal Calibration
+//!
+//! The crystal is rarely exactly 25 MHz. A [`PpmCorrection`], measured
+//! once by comparing an output against a frequency counter or a known
+//! signal and stored in the settings, is applied to the crystal
+//! frequency inside [`calculate_frequency`] and [`calculate_quadrature`],
+//! so every output comes out corrected.
+//!
+//! # Smooth Tuning
+//!
+//! A full recalculation picks a new multisynth divider and needs a PLL
+//! reset, which clicks in the receiver. [`SmoothTuning`] instead retunes
+//! small steps by the PLL numerator `b` alone, over the finest
+//! denominator, keeping the multisynth and the PLL integer part fixed, so
+//! only the PLL registers are rewritten and the outputs stay
+//! phase-continuous:
+//!
+//! - Resolution is the crystal over the denominator (about 24 Hz at the
+//!   VCO) divided by the output divider: 0.1 Hz at 3.5 MHz to 0.8 Hz at
+//!   30 MHz, so steps of 1 Hz and up land within 1 Hz
+//! - Steps up to [`MAX_SMOOTH_STEP_HZ`] are retuned smoothly, as long as
+//!   the VCO stays within one crystal frequency of the PLL integer part
+//!   (a window of 25 MHz / divider, e.g. about 390 kHz around 14 MHz in
+//!   quadrature) and within the VCO range
+//! - Larger steps, or ones leaving the window, fall back to a full
+//!   recalculation
+//!
+//! # Registers
+//!
+//! The register map ([`reg`]) and the serialization of parameters and
+//! clock control into register bytes live here too, so the bytes the
+//! driver writes over I2C can be checked on the host.
 
 /// PLL parameters for frequency calculation
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -76,6 +109,13 @@ impl PllParams {
         let p3 = self.c;
         (p1, p2, p3)
     }
+
+    /// Serialize into the eight PLL parameter registers
+    #[must_use]
+    pub fn to_register_bytes(&self) -> [u8; 8] {
+        let (p1, p2, p3) = self.to_registers();
+        parameter_bytes(p1, p2, p3, 0)
+    }
 }
 
 /// Multisynth divider parameters
@@ -175,6 +215,291 @@ impl MsParams {
         let p3 = self.c;
         (p1, p2, p3)
     }
+
+    /// Check if the multisynth should run in integer mode (`MS_INT`)
+    #[must_use]
+    pub const fn is_integer_mode(&self) -> bool {
+        self.b == 0 && self.a.is_multiple_of(2)
+    }
+
+    /// Serialize into the eight multisynth parameter registers,
+    /// including the R divider and the divide-by-4 mode
+    #[must_use]
+    pub fn to_register_bytes(&self) -> [u8; 8] {
+        let (p1, p2, p3) = self.to_registers();
+        let divby4 = if self.a == 4 && self.b == 0 { 0b11 } else { 0 };
+        parameter_bytes(p1, p2, p3, ((self.r_div & 0x07) << 4) | (divby4 << 2))
+    }
+}
+
+/// Pack P1, P2 and P3 into eight parameter registers, with `flags` in
+/// the upper bits of the third
+#[allow(clippy::cast_possible_truncation)]
+const fn parameter_bytes(p1: u32, p2: u32, p3: u32, flags: u8) -> [u8; 8] {
+    [
+        (p3 >> 8) as u8,
+        p3 as u8,
+        flags | ((p1 >> 16) & 0x03) as u8,
+        (p1 >> 8) as u8,
+        p1 as u8,
+        (((p3 >> 12) & 0xF0) | ((p2 >> 16) & 0x0F)) as u8,
+        (p2 >> 8) as u8,
+        p2 as u8,
+    ]
+}
+
+/// `Si5351A` register addresses
+pub mod reg {
+    /// Device status (`SYS_INIT`, `LOL_B`, `LOL_A`, `LOS`)
+    pub const DEVICE_STATUS: u8 = 0;
+    /// Output enable control, a set bit disables the output
+    pub const OUTPUT_ENABLE: u8 = 3;
+    /// CLK0 control
+    pub const CLK0_CONTROL: u8 = 16;
+    /// CLK1 control
+    pub const CLK1_CONTROL: u8 = 17;
+    /// CLK2 control
+    pub const CLK2_CONTROL: u8 = 18;
+    /// PLL A parameters (8 registers)
+    pub const PLLA_PARAMS: u8 = 26;
+    /// PLL B parameters (8 registers)
+    pub const PLLB_PARAMS: u8 = 34;
+    /// Multisynth 0 parameters (8 registers)
+    pub const MS0_PARAMS: u8 = 42;
+    /// Multisynth 1 parameters (8 registers)
+    pub const MS1_PARAMS: u8 = 50;
+    /// Multisynth 2 parameters (8 registers)
+    pub const MS2_PARAMS: u8 = 58;
+    /// CLK0 initial phase offset
+    pub const CLK0_PHASE: u8 = 165;
+    /// CLK1 initial phase offset
+    pub const CLK1_PHASE: u8 = 166;
+    /// CLK2 initial phase offset
+    pub const CLK2_PHASE: u8 = 167;
+    /// PLL soft reset
+    pub const PLL_RESET: u8 = 177;
+    /// Crystal internal load capacitance
+    pub const CRYSTAL_LOAD: u8 = 183;
+}
+
+/// Largest initial phase offset (7 bits, in quarter VCO periods)
+pub const MAX_PHASE_OFFSET: u8 = 127;
+
+/// Clock output identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockOutput {
+    /// CLK0 output
+    Clk0,
+    /// CLK1 output
+    Clk1,
+    /// CLK2 output
+    Clk2,
+}
+
+impl ClockOutput {
+    /// All outputs of the `Si5351A`
+    pub const ALL: [Self; 3] = [Self::Clk0, Self::Clk1, Self::Clk2];
+
+    /// Get the output index
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Clk0 => 0,
+            Self::Clk1 => 1,
+            Self::Clk2 => 2,
+        }
+    }
+
+    /// Get the control register for this output
+    #[must_use]
+    pub const fn control_reg(self) -> u8 {
+        match self {
+            Self::Clk0 => reg::CLK0_CONTROL,
+            Self::Clk1 => reg::CLK1_CONTROL,
+            Self::Clk2 => reg::CLK2_CONTROL,
+        }
+    }
+
+    /// Get the multisynth parameter base register
+    #[must_use]
+    pub const fn ms_reg(self) -> u8 {
+        match self {
+            Self::Clk0 => reg::MS0_PARAMS,
+            Self::Clk1 => reg::MS1_PARAMS,
+            Self::Clk2 => reg::MS2_PARAMS,
+        }
+    }
+
+    /// Get the phase offset register
+    #[must_use]
+    pub const fn phase_reg(self) -> u8 {
+        match self {
+            Self::Clk0 => reg::CLK0_PHASE,
+            Self::Clk1 => reg::CLK1_PHASE,
+            Self::Clk2 => reg::CLK2_PHASE,
+        }
+    }
+
+    /// Get the output enable bit
+    #[must_use]
+    pub const fn enable_bit(self) -> u8 {
+        match self {
+            Self::Clk0 => 0,
+            Self::Clk1 => 1,
+            Self::Clk2 => 2,
+        }
+    }
+
+    /// Get the PLL this output runs from: the quadrature pair shares
+    /// PLL A, leaving PLL B to CLK2
+    #[must_use]
+    pub const fn pll(self) -> PllSource {
+        match self {
+            Self::Clk0 | Self::Clk1 => PllSource::PllA,
+            Self::Clk2 => PllSource::PllB,
+        }
+    }
+}
+
+/// Drive strength setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DriveStrength {
+    /// 2mA drive
+    Drive2mA,
+    /// 4mA drive
+    Drive4mA,
+    /// 6mA drive
+    Drive6mA,
+    /// 8mA drive (maximum)
+    #[default]
+    Drive8mA,
+}
+
+impl DriveStrength {
+    /// Get register value
+    #[must_use]
+    pub const fn as_reg(self) -> u8 {
+        match self {
+            Self::Drive2mA => 0,
+            Self::Drive4mA => 1,
+            Self::Drive6mA => 2,
+            Self::Drive8mA => 3,
+        }
+    }
+}
+
+/// PLL source selection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PllSource {
+    /// Use PLL A
+    #[default]
+    PllA,
+    /// Use PLL B
+    PllB,
+}
+
+impl PllSource {
+    /// Get the PLL parameter base register
+    #[must_use]
+    pub const fn params_reg(self) -> u8 {
+        match self {
+            Self::PllA => reg::PLLA_PARAMS,
+            Self::PllB => reg::PLLB_PARAMS,
+        }
+    }
+
+    /// Get the soft reset bit in [`reg::PLL_RESET`]
+    #[must_use]
+    pub const fn reset_mask(self) -> u8 {
+        match self {
+            Self::PllA => 0x20,
+            Self::PllB => 0x80,
+        }
+    }
+}
+
+/// Crystal load capacitance
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CrystalLoad {
+    /// 6 pF load
+    Load6pF,
+    /// 8 pF load
+    Load8pF,
+    /// 10 pF load
+    #[default]
+    Load10pF,
+}
+
+impl CrystalLoad {
+    /// Get register value, with the reserved bits at their required
+    /// `010010`
+    #[must_use]
+    pub const fn as_reg(self) -> u8 {
+        let load = match self {
+            Self::Load6pF => 0b0100_0000,
+            Self::Load8pF => 0b1000_0000,
+            Self::Load10pF => 0b1100_0000,
+        };
+        load | 0b01_0010
+    }
+}
+
+/// Clock output control register
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ClockControl {
+    /// Output driver powered down
+    pub power_down: bool,
+    /// Multisynth in integer mode (`MS_INT`)
+    pub integer: bool,
+    /// PLL feeding the multisynth
+    pub pll: PllSource,
+    /// Output inverted
+    pub inverted: bool,
+    /// Output drive strength
+    pub drive: DriveStrength,
+}
+
+impl ClockControl {
+    /// Output powered down
+    pub const POWER_DOWN: Self = Self {
+        power_down: true,
+        integer: false,
+        pll: PllSource::PllA,
+        inverted: false,
+        drive: DriveStrength::Drive8mA,
+    };
+
+    /// Control for a multisynth fed from `pll`
+    #[must_use]
+    pub const fn new(ms: &MsParams, pll: PllSource, drive: DriveStrength) -> Self {
+        Self {
+            power_down: false,
+            integer: ms.is_integer_mode(),
+            pll,
+            inverted: false,
+            drive,
+        }
+    }
+
+    /// Serialize into the control register, with the output driven from
+    /// its own multisynth
+    #[must_use]
+    pub const fn to_register(&self) -> u8 {
+        let mut value = 0b0000_1100 | self.drive.as_reg();
+        if self.power_down {
+            value |= 0x80;
+        }
+        if self.integer {
+            value |= 0x40;
+        }
+        if matches!(self.pll, PllSource::PllB) {
+            value |= 0x20;
+        }
+        if self.inverted {
+            value |= 0x10;
+        }
+        value
+    }
 }
 
 /// Minimum VCO frequency (600 MHz)
@@ -185,17 +510,240 @@ pub const VCO_MAX_HZ: u64 = 900_000_000;
 /// Default crystal frequency (25 MHz)
 pub const DEFAULT_XTAL_HZ: u64 = 25_000_000;
 
+/// Largest crystal correction accepted (±200 ppm) in parts per billion
+pub const MAX_CORRECTION_PPB: i32 = 200_000;
+
+/// Parts per billion in one
+const PPB: i64 = 1_000_000_000;
+
+/// Crystal frequency error in parts per billion
+///
+/// Positive when the crystal runs fast, so outputs come out high
+/// without correction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PpmCorrection {
+    ppb: i32,
+}
+
+impl PpmCorrection {
+    /// No correction (nominal crystal)
+    pub const NONE: Self = Self { ppb: 0 };
+
+    /// Create from parts per billion
+    ///
+    /// Returns `None` beyond [`MAX_CORRECTION_PPB`].
+    #[must_use]
+    pub const fn from_ppb(ppb: i32) -> Option<Self> {
+        if ppb.abs() > MAX_CORRECTION_PPB {
+            None
+        } else {
+            Some(Self { ppb })
+        }
+    }
+
+    /// Get the correction in parts per billion
+    #[must_use]
+    pub const fn ppb(self) -> i32 {
+        self.ppb
+    }
+
+    /// Get the correction in parts per million
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ppm(self) -> f32 {
+        self.ppb as f32 / 1000.0
+    }
+
+    /// Calibrate from an output programmed for `expected_hz` with this
+    /// correction in force and measured at `measured_hz`
+    ///
+    /// Returns the new correction, folding in the one already applied,
+    /// or `None` if the result is out of range.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn calibrate(self, expected_hz: u64, measured_hz: u64) -> Option<Self> {
+        if expected_hz == 0 || measured_hz == 0 {
+            return None;
+        }
+        // The output scales with the crystal: measured / expected is the
+        // actual crystal over the one assumed
+        let assumed = PPB + i64::from(self.ppb);
+        let actual = (measured_hz as i64 * assumed + expected_hz as i64 / 2) / expected_hz as i64;
+        let ppb = actual - PPB;
+        if ppb.abs() > i64::from(MAX_CORRECTION_PPB) {
+            return None;
+        }
+        Self::from_ppb(ppb as i32)
+    }
+
+    /// Get the actual frequency of a nominal `xtal_hz` crystal
+    #[must_use]
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+    pub const fn apply(self, xtal_hz: u64) -> u64 {
+        let scaled = xtal_hz as i64 * (PPB + self.ppb as i64);
+        ((scaled + PPB / 2) / PPB) as u64
+    }
+}
+
+/// Largest step retuned by the PLL numerator alone
+pub const MAX_SMOOTH_STEP_HZ: u64 = 10_000;
+
+/// Register update needed for a tuning step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TuneStep {
+    /// Rewrite the PLL registers only, without a PLL reset
+    Smooth(PllParams),
+    /// Rewrite the PLL and multisynth registers (and the CLK1 phase in
+    /// quadrature), then reset the PLL
+    Full(PllParams, MsParams),
+}
+
+/// Phase-continuous VFO tuning
+///
+/// Tracks the parameters programmed into the Si5351 so each step can be
+/// made with the fewest register writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmoothTuning {
+    /// Corrected crystal frequency in Hz
+    xtal_hz: u64,
+    /// Outputs run at 4× the frequency for quadrature
+    quadrature: bool,
+    /// PLL parameters in force
+    pll: PllParams,
+    /// Multisynth parameters in force
+    ms: MsParams,
+    /// Actual frequency in Hz
+    frequency: u64,
+}
+
+impl SmoothTuning {
+    /// PLL denominator while tuning smoothly (the finest resolution)
+    const DENOMINATOR: u32 = PllParams::MAX_C;
+
+    /// Start tuning at `target_hz` with a full calculation
+    ///
+    /// With `quadrature`, the parameters are those of
+    /// [`calculate_quadrature`]; otherwise of [`calculate_frequency`].
+    #[must_use]
+    pub fn new(
+        xtal_hz: u64,
+        correction: PpmCorrection,
+        target_hz: u64,
+        quadrature: bool,
+    ) -> Option<Self> {
+        let mut tuning = Self {
+            xtal_hz: correction.apply(xtal_hz),
+            quadrature,
+            pll: PllParams::integer(PllParams::MIN_A),
+            ms: MsParams::integer(MsParams::MIN_A),
+            frequency: 0,
+        };
+        tuning.recalculate(target_hz)?;
+        Some(tuning)
+    }
+
+    /// Get the PLL parameters in force
+    #[must_use]
+    pub const fn pll(&self) -> PllParams {
+        self.pll
+    }
+
+    /// Get the multisynth parameters in force
+    #[must_use]
+    pub const fn ms(&self) -> MsParams {
+        self.ms
+    }
+
+    /// Get the CLK1 phase offset for quadrature
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn phase(&self) -> u8 {
+        (self.ms.a / 4) as u8
+    }
+
+    /// Get the actual frequency in Hz
+    #[must_use]
+    pub const fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    /// Tune to `target_hz`
+    ///
+    /// Returns the registers to rewrite, or `None` if the frequency
+    /// cannot be synthesized (the parameters in force are kept).
+    pub fn tune(&mut self, target_hz: u64) -> Option<TuneStep> {
+        if target_hz.abs_diff(self.frequency) <= MAX_SMOOTH_STEP_HZ {
+            if let Some(pll) = self.numerator_step(target_hz) {
+                self.pll = pll;
+                self.frequency = self.output_frequency();
+                return Some(TuneStep::Smooth(pll));
+            }
+        }
+        self.recalculate(target_hz)?;
+        Some(TuneStep::Full(self.pll, self.ms))
+    }
+
+    /// PLL parameters reaching `target_hz` with the same multisynth and
+    /// PLL integer part, if the VCO stays in range
+    #[allow(clippy::cast_possible_truncation)]
+    fn numerator_step(&self, target_hz: u64) -> Option<PllParams> {
+        // VCO = output × (a + b/c) × R, in u128 as c is up to 20 bits
+        let output = u128::from(target_hz) * if self.quadrature { 4 } else { 1 };
+        let divisor = u128::from(self.ms.a) * u128::from(self.ms.c) + u128::from(self.ms.b);
+        let scaled = output * divisor * (1u128 << self.ms.r_div);
+        let c = u128::from(self.ms.c);
+        let vco = (scaled + c / 2) / c;
+        if !(u128::from(VCO_MIN_HZ)..=u128::from(VCO_MAX_HZ)).contains(&vco) {
+            return None;
+        }
+        let xtal = u128::from(self.xtal_hz);
+        let remainder = vco.checked_sub(u128::from(self.pll.a) * xtal)?;
+        let denominator = u128::from(Self::DENOMINATOR);
+        let b = (remainder * denominator + xtal / 2) / xtal;
+        (b < denominator).then_some(PllParams::fractional(self.pll.a, b as u32, Self::DENOMINATOR))
+    }
+
+    /// Full calculation for `target_hz`
+    fn recalculate(&mut self, target_hz: u64) -> Option<()> {
+        let (pll, ms) = if self.quadrature {
+            let (pll, ms, ..) = calculate_quadrature(self.xtal_hz, PpmCorrection::NONE, target_hz)?;
+            (pll, ms)
+        } else {
+            let (pll, ms, ..) = calculate_frequency(self.xtal_hz, PpmCorrection::NONE, target_hz)?;
+            (pll, ms)
+        };
+        self.pll = pll;
+        self.ms = ms;
+        self.frequency = self.output_frequency();
+        Some(())
+    }
+
+    /// Output frequency of the parameters in force
+    fn output_frequency(&self) -> u64 {
+        let output = self.ms.output_frequency(self.pll.vco_frequency(self.xtal_hz));
+        if self.quadrature {
+            output / 4
+        } else {
+            output
+        }
+    }
+}
+
 /// Calculate frequency synthesis parameters for a target frequency
 ///
-/// Returns (PLL params, Multisynth params, actual frequency in Hz, error in Hz)
+/// The PLL is planned against the crystal frequency corrected by
+/// `correction`. Returns (PLL params, Multisynth params, actual
+/// frequency in Hz, error in Hz)
 #[must_use]
 pub fn calculate_frequency(
     xtal_hz: u64,
+    correction: PpmCorrection,
     target_hz: u64,
 ) -> Option<(PllParams, MsParams, u64, i64)> {
     if target_hz == 0 {
         return None;
     }
+    let xtal_hz = correction.apply(xtal_hz);
 
     // Try to find optimal parameters
     // Strategy:
@@ -370,11 +918,18 @@ fn rational_approximation(num: u64, den: u64, max_c: u32) -> (u32, u32) {
 ///
 /// For quadrature operation, the multisynth divisor must be an even integer.
 /// The phase offset is set by programming the CLK1 phase register.
+/// The crystal frequency is corrected by `correction` as in
+/// [`calculate_frequency`].
 #[must_use]
 pub fn calculate_quadrature(
     xtal_hz: u64,
+    correction: PpmCorrection,
     target_hz: u64,
 ) -> Option<(PllParams, MsParams, u64, i64, u8)> {
+    if target_hz == 0 {
+        return None;
+    }
+    let xtal_hz = correction.apply(xtal_hz);
     // For quadrature, we need 4× the LO frequency and even integer divisor
     let target_4x = target_hz * 4;
 
@@ -497,7 +1052,7 @@ mod tests {
 
     #[test]
     fn calculate_7mhz() {
-        let result = calculate_frequency(DEFAULT_XTAL_HZ, 7_000_000);
+        let result = calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 7_000_000);
         assert!(result.is_some());
 
         let (pll, ms, _actual, error) = result.unwrap();
@@ -511,7 +1066,7 @@ mod tests {
 
     #[test]
     fn calculate_14mhz() {
-        let result = calculate_frequency(DEFAULT_XTAL_HZ, 14_000_000);
+        let result = calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 14_000_000);
         assert!(result.is_some());
 
         let (pll, ms, _actual, error) = result.unwrap();
@@ -524,7 +1079,7 @@ mod tests {
 
     #[test]
     fn calculate_28mhz() {
-        let result = calculate_frequency(DEFAULT_XTAL_HZ, 28_000_000);
+        let result = calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 28_000_000);
         assert!(result.is_some());
 
         let (pll, ms, _actual, _error) = result.unwrap();
@@ -537,7 +1092,7 @@ mod tests {
         // 100 kHz should require R divider
         // VCO max = 900 MHz, MS max = 1800
         // 900 MHz / 1800 = 500 kHz, so below 500 kHz needs R divider
-        let result = calculate_frequency(DEFAULT_XTAL_HZ, 100_000);
+        let result = calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 100_000);
         assert!(result.is_some());
 
         let (pll, ms, _actual, _error) = result.unwrap();
@@ -548,7 +1103,7 @@ mod tests {
 
     #[test]
     fn calculate_quadrature_7mhz() {
-        let result = calculate_quadrature(DEFAULT_XTAL_HZ, 7_000_000);
+        let result = calculate_quadrature(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 7_000_000);
         assert!(result.is_some());
 
         let (pll, ms, _actual, _error, phase) = result.unwrap();
@@ -560,7 +1115,7 @@ mod tests {
 
     #[test]
     fn calculate_quadrature_14mhz() {
-        let result = calculate_quadrature(DEFAULT_XTAL_HZ, 14_074_000);
+        let result = calculate_quadrature(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 14_074_000);
         assert!(result.is_some());
 
         let (_pll, ms, _actual, error, _phase) = result.unwrap();
@@ -630,7 +1185,7 @@ mod tests {
     fn vco_range_check() {
         // All generated VCO frequencies should be in valid range
         for freq in [3_500_000, 7_074_000, 14_074_000, 21_074_000] {
-            if let Some((pll, _, _, _)) = calculate_frequency(DEFAULT_XTAL_HZ, freq) {
+            if let Some((pll, _, _, _)) = calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, freq) {
                 let vco = pll.vco_frequency(DEFAULT_XTAL_HZ);
                 assert!(
                     vco >= VCO_MIN_HZ && vco <= VCO_MAX_HZ,
@@ -641,4 +1196,147 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn ppm_correction_calibrate() {
+        // Programmed 10 MHz, counted 10.000 120 MHz: crystal 12 ppm fast
+        let cal = PpmCorrection::NONE.calibrate(10_000_000, 10_000_120).unwrap();
+        assert_eq!(cal.ppb(), 12_000);
+        assert!((cal.ppm() - 12.0).abs() < 1e-6);
+        assert_eq!(cal.apply(DEFAULT_XTAL_HZ), 25_000_300);
+
+        // Recalibrating with the correction in force refines it
+        let refined = cal.calibrate(10_000_000, 9_999_995).unwrap();
+        assert!((refined.ppb() - 11_500).abs() <= 1, "{}", refined.ppb());
+
+        assert!(PpmCorrection::NONE.calibrate(10_000_000, 10_100_000).is_none());
+        assert!(PpmCorrection::NONE.calibrate(0, 10_000_000).is_none());
+        assert!(PpmCorrection::from_ppb(-MAX_CORRECTION_PPB).is_some());
+        assert!(PpmCorrection::from_ppb(MAX_CORRECTION_PPB + 1).is_none());
+    }
+
+    #[test]
+    fn corrected_outputs_land_on_target() {
+        let cal = PpmCorrection::from_ppb(-23_400).unwrap();
+        let real_xtal = cal.apply(DEFAULT_XTAL_HZ);
+        for freq in [3_573_000, 7_074_000, 14_074_000, 21_074_000] {
+            // Planned against the real crystal, the output is on frequency
+            let (pll, ms, actual, error) =
+                calculate_frequency(DEFAULT_XTAL_HZ, cal, freq).unwrap();
+            assert_eq!(ms.output_frequency(pll.vco_frequency(real_xtal)), actual);
+            assert!(error.abs() <= 1, "{freq}: {error}");
+
+            let (pll, ms, actual, _, _) = calculate_quadrature(DEFAULT_XTAL_HZ, cal, freq).unwrap();
+            assert_eq!(ms.output_frequency(pll.vco_frequency(real_xtal)) / 4, actual);
+
+            // Uncorrected parameters would be off by the crystal error
+            let (pll, ms, _, _) =
+                calculate_frequency(DEFAULT_XTAL_HZ, PpmCorrection::NONE, freq).unwrap();
+            let off = ms.output_frequency(pll.vco_frequency(real_xtal)) as i64 - freq as i64;
+            assert!(off < -50, "{freq}: {off}");
+        }
+    }
+
+    #[test]
+    fn smooth_tuning_small_steps() {
+        let mut tuning =
+            SmoothTuning::new(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 14_074_000, true).unwrap();
+        let ms = tuning.ms();
+        let a = tuning.pll().a;
+        assert!(ms.is_even_integer());
+
+        // Tune up 20 kHz in 10 Hz steps and back in 1 kHz steps
+        let steps = (1..=2000)
+            .map(|n| 14_074_000 + n * 10)
+            .chain((0..20).rev().map(|n| 14_074_000 + n * 1000));
+        for target in steps {
+            match tuning.tune(target) {
+                Some(TuneStep::Smooth(pll)) => {
+                    assert_eq!(pll.a, a);
+                    assert!(pll.is_valid());
+                }
+                other => panic!("{target}: {other:?}"),
+            }
+            assert_eq!(tuning.ms(), ms, "multisynth untouched");
+            assert!(tuning.frequency().abs_diff(target) <= 1, "{target}: {}", tuning.frequency());
+        }
+
+        let mut plain =
+            SmoothTuning::new(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 7_074_000, false).unwrap();
+        assert!(matches!(plain.tune(7_074_500), Some(TuneStep::Smooth(_))));
+        assert!(plain.frequency().abs_diff(7_074_500) <= 1);
+    }
+
+    #[test]
+    fn smooth_tuning_falls_back_to_full() {
+        let mut tuning =
+            SmoothTuning::new(DEFAULT_XTAL_HZ, PpmCorrection::NONE, 14_074_000, true).unwrap();
+
+        // Too large a step
+        let target = 14_074_000 + MAX_SMOOTH_STEP_HZ + 1;
+        assert!(matches!(tuning.tune(target), Some(TuneStep::Full(..))));
+        assert!(tuning.frequency().abs_diff(target) <= 1);
+
+        // Walking out of the PLL integer window needs one full step
+        let mut target = tuning.frequency();
+        let mut full = 0;
+        for _ in 0..100 {
+            target += 9_000;
+            if matches!(tuning.tune(target), Some(TuneStep::Full(..))) {
+                full += 1;
+            }
+            assert!(tuning.frequency().abs_diff(target) <= 1, "{target}");
+        }
+        assert!((1..=3).contains(&full), "{full} full steps over 900 kHz");
+
+        assert!(tuning.tune(0).is_none());
+        assert!(tuning.frequency().abs_diff(target) <= 1, "kept on failure");
+    }
+
+    #[test]
+    fn parameter_register_bytes() {
+        // a=35, b=1, c=2: P1 = 4480 + 64 - 512 = 4032, P2 = 0, P3 = 2
+        let pll = PllParams::fractional(35, 1, 2);
+        assert_eq!(pll.to_register_bytes(), [0x00, 0x02, 0x00, 0x0F, 0xC0, 0x00, 0x00, 0x00]);
+
+        // 20-bit P2 and P3 split across the shared nibble register
+        let pll = PllParams::fractional(31, 524_287, PllParams::MAX_C);
+        let (p1, p2, p3) = pll.to_registers();
+        let bytes = pll.to_register_bytes();
+        assert_eq!(u32::from(bytes[5] >> 4) << 16 | u32::from(bytes[0]) << 8 | u32::from(bytes[1]), p3);
+        assert_eq!(u32::from(bytes[5] & 0x0F) << 16 | u32::from(bytes[6]) << 8 | u32::from(bytes[7]), p2);
+        assert_eq!(u32::from(bytes[2] & 0x03) << 16 | u32::from(bytes[3]) << 8 | u32::from(bytes[4]), p1);
+
+        // R divider in the upper bits of the third register
+        let ms = MsParams::integer_with_r(100, 3);
+        assert_eq!(ms.to_register_bytes(), [0x00, 0x01, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00]);
+        assert!(ms.is_integer_mode());
+
+        // Divide by 4 sets MSx_DIVBY4 with P1 = P2 = 0
+        let ms = MsParams::integer(4);
+        assert_eq!(ms.to_register_bytes(), [0x00, 0x01, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert!(!MsParams::integer(101).is_integer_mode());
+    }
+
+    #[test]
+    fn control_register_bytes() {
+        assert_eq!(ClockControl::POWER_DOWN.to_register(), 0x8F);
+
+        let ms = MsParams::integer(16);
+        let control = ClockControl::new(&ms, PllSource::PllA, DriveStrength::Drive2mA);
+        assert_eq!(control.to_register(), 0x4C);
+
+        let ms = MsParams::fractional(45, 1, 3);
+        let mut control = ClockControl::new(&ms, PllSource::PllB, DriveStrength::Drive6mA);
+        assert_eq!(control.to_register(), 0x2E);
+        control.inverted = true;
+        assert_eq!(control.to_register(), 0x3E);
+
+        assert_eq!(CrystalLoad::Load10pF.as_reg(), 0xD2);
+        assert_eq!(CrystalLoad::Load6pF.as_reg(), 0x52);
+        assert_eq!(PllSource::PllA.reset_mask() | PllSource::PllB.reset_mask(), 0xA0);
+        assert_eq!(ClockOutput::Clk2.pll(), PllSource::PllB);
+        assert_eq!(ClockOutput::Clk1.phase_reg(), reg::CLK1_PHASE);
+        assert_eq!(ClockOutput::Clk2.ms_reg(), reg::MS0_PARAMS + 16);
+    }
 }
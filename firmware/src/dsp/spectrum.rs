@@ -6,6 +6,8 @@
 #[cfg(feature = "embedded")]
 use micromath::F32Ext;
 
+use super::modulation::IqSample;
+
 /// Power spectrum bin for display
 #[derive(Clone, Copy, Debug, Default)]
 pub struct SpectrumBin {
@@ -336,6 +338,147 @@ impl<const ROWS: usize> Default for WaterfallBuffer<ROWS> {
     }
 }
 
+/// I/Q samples per [`IqSpectrum`] frame
+pub const IQ_SPECTRUM_SIZE: usize = 256;
+
+/// Band scope spectrum of complex I/Q samples
+///
+/// Collects [`IQ_SPECTRUM_SIZE`] samples, applies a Hann window and
+/// transforms them with a radix-2 FFT. Being complex, the spectrum runs
+/// from -fs/2 to +fs/2 around the LO, so signals either side of it
+/// land in different columns instead of folding onto each other.
+#[derive(Clone)]
+pub struct IqSpectrum {
+    /// Real parts, samples then transform
+    re: [f32; IQ_SPECTRUM_SIZE],
+    /// Imaginary parts, samples then transform
+    im: [f32; IQ_SPECTRUM_SIZE],
+    /// Samples collected in this frame
+    len: usize,
+    /// Hann window
+    window: [f32; IQ_SPECTRUM_SIZE],
+    /// Twiddle factors, cos and -sin of 2πk/N
+    twiddles: [(f32, f32); IQ_SPECTRUM_SIZE / 2],
+    /// Sample rate in Hz
+    sample_rate: u32,
+}
+
+impl IqSpectrum {
+    /// Create an analyzer for I/Q at `sample_rate`
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(sample_rate: u32) -> Self {
+        let n = IQ_SPECTRUM_SIZE as f32;
+        let window = core::array::from_fn(|i| {
+            0.5 * (1.0 - (2.0 * core::f32::consts::PI * i as f32 / n).cos())
+        });
+        let twiddles = core::array::from_fn(|k| {
+            let angle = 2.0 * core::f32::consts::PI * k as f32 / n;
+            (angle.cos(), -angle.sin())
+        });
+        Self {
+            re: [0.0; IQ_SPECTRUM_SIZE],
+            im: [0.0; IQ_SPECTRUM_SIZE],
+            len: 0,
+            window,
+            twiddles,
+            sample_rate,
+        }
+    }
+
+    /// Add a sample, returning `true` once the frame is full
+    ///
+    /// Samples after a full frame are ignored until [`row`](Self::row)
+    /// takes it.
+    pub fn push(&mut self, sample: IqSample) -> bool {
+        if self.len < IQ_SPECTRUM_SIZE {
+            self.re[self.len] = sample.i * self.window[self.len];
+            self.im[self.len] = sample.q * self.window[self.len];
+            self.len += 1;
+        }
+        self.is_ready()
+    }
+
+    /// Check if a full frame is waiting
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        self.len == IQ_SPECTRUM_SIZE
+    }
+
+    /// Discard the samples collected so far
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Transform the frame into a waterfall row and start the next one
+    ///
+    /// Columns run from the lowest frequency to the highest, with the LO
+    /// in the middle. Levels are relative to a full-scale carrier.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn row(&mut self, timestamp: u32) -> WaterfallRow {
+        self.fft();
+        // A full-scale carrier peaks at the window sum squared
+        let full_scale = 20.0 * (IQ_SPECTRUM_SIZE as f32 / 2.0).log10();
+        let bin_width = self.sample_rate as f32 / IQ_SPECTRUM_SIZE as f32;
+        let mut bins = [SpectrumBin::default(); IQ_SPECTRUM_SIZE];
+        for (i, bin) in bins.iter_mut().enumerate() {
+            let k = (i + IQ_SPECTRUM_SIZE / 2) % IQ_SPECTRUM_SIZE;
+            let power = self.re[k] * self.re[k] + self.im[k] * self.im[k];
+            // Offset from the lower edge of the span
+            bin.frequency = (i as f32 * bin_width) as u32;
+            bin.power_db = if power > 1e-20 {
+                10.0 * power.log10() - full_scale
+            } else {
+                -120.0
+            };
+        }
+        self.len = 0;
+        WaterfallRow::from_spectrum(timestamp, &bins, 128)
+    }
+
+    /// In-place radix-2 decimation-in-time FFT of the frame
+    fn fft(&mut self) {
+        let n = IQ_SPECTRUM_SIZE;
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                self.re.swap(i, j);
+                self.im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let stride = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..len / 2 {
+                    let (cos, sin) = self.twiddles[k * stride];
+                    let a = start + k;
+                    let b = a + len / 2;
+                    let t_re = self.re[b] * cos - self.im[b] * sin;
+                    let t_im = self.re[b] * sin + self.im[b] * cos;
+                    self.re[b] = self.re[a] - t_re;
+                    self.im[b] = self.im[a] - t_im;
+                    self.re[a] += t_re;
+                    self.im[a] += t_im;
+                }
+            }
+            len *= 2;
+        }
+    }
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
@@ -592,4 +735,48 @@ mod tests {
         let buffer: WaterfallBuffer<4> = WaterfallBuffer::new();
         assert!(buffer.get(0).is_none());
     }
+
+    // =========================================================================
+    // I/Q Spectrum Tests
+    // =========================================================================
+
+    fn tone_row(offset_hz: f32) -> WaterfallRow {
+        let mut spectrum = IqSpectrum::new(48_000);
+        let mut n = 0;
+        while !spectrum.is_ready() {
+            let phase = 2.0 * core::f32::consts::PI * offset_hz * n as f32 / 48_000.0;
+            spectrum.push(IqSample::new(phase.cos(), phase.sin()));
+            n += 1;
+        }
+        spectrum.row(0)
+    }
+
+    fn strongest_column(row: &WaterfallRow) -> usize {
+        (0..128).max_by_key(|&col| row.power_at(col)).unwrap()
+    }
+
+    #[test]
+    fn iq_spectrum_separates_sidebands() {
+        // 6 kHz is 32 bins of 187.5 Hz, 16 columns either side of center
+        assert_eq!(strongest_column(&tone_row(6000.0)), 64 + 16);
+        assert_eq!(strongest_column(&tone_row(-6000.0)), 64 - 16);
+    }
+
+    #[test]
+    fn iq_spectrum_full_scale_carrier_is_zero_db() {
+        let row = tone_row(6000.0);
+        assert_eq!(row.power_at(80), 100);
+        assert!(row.power_at(20) < 40);
+    }
+
+    #[test]
+    fn iq_spectrum_starts_a_new_frame_after_a_row() {
+        let mut spectrum = IqSpectrum::new(48_000);
+        for _ in 0..IQ_SPECTRUM_SIZE {
+            spectrum.push(IqSample::new(1.0, 0.0));
+        }
+        assert!(spectrum.is_ready());
+        let _ = spectrum.row(0);
+        assert!(!spectrum.is_ready());
+    }
 }
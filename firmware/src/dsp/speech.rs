@@ -0,0 +1,183 @@
+//! Speech Processor
+//!
+//! Dynamics stages for the transmit audio path. The compressor raises
+//! the average level of speech relative to its peaks, so the same PEP
+//! carries more talk power; the limiter catches what the compressor's
+//! attack lets through. The ALC loop watches the modulator output and
+//! backs the drive off when it would exceed full scale, the same way a
+//! transceiver's ALC holds the PA at rated power on voice peaks.
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+
+/// Maximum compression in dB
+pub const MAX_COMPRESSION_DB: f32 = 20.0;
+
+/// Compressor ratio above threshold
+const COMPRESSOR_RATIO: f32 = 4.0;
+
+/// Compressor attack time in milliseconds
+const COMPRESSOR_ATTACK_MS: f32 = 2.0;
+
+/// Compressor release time in milliseconds
+const COMPRESSOR_RELEASE_MS: f32 = 150.0;
+
+/// Limiter ceiling (linear, full scale = 1.0)
+pub const LIMITER_CEILING: f32 = 0.95;
+
+/// Default ALC target peak level
+pub const DEFAULT_ALC_TARGET: f32 = 0.9;
+
+/// ALC release time in milliseconds
+const ALC_RELEASE_MS: f32 = 300.0;
+
+/// One-pole smoothing coefficient for a time constant
+fn time_coeff(ms: f32, sample_rate: f32) -> f32 {
+    let samples = (ms / 1000.0 * sample_rate).max(1.0);
+    1.0 - (-1.0 / samples).exp()
+}
+
+/// Speech compressor with a brick-wall limiter
+///
+/// The threshold sits `compression_db` below full scale and make-up gain
+/// restores full-scale peaks, so raising the compression raises the
+/// average level while peaks stay put.
+#[derive(Clone, Copy, Debug)]
+pub struct Compressor {
+    /// Compression amount in dB (0 = off)
+    compression_db: f32,
+    /// Threshold (linear)
+    threshold: f32,
+    /// Make-up gain (linear)
+    makeup: f32,
+    /// Peak envelope
+    envelope: f32,
+    /// Attack coefficient
+    attack: f32,
+    /// Release coefficient
+    release: f32,
+}
+
+impl Compressor {
+    /// Create a compressor with the given compression in dB
+    #[must_use]
+    pub fn new(compression_db: f32, sample_rate: f32) -> Self {
+        let mut compressor = Self {
+            compression_db: 0.0,
+            threshold: 1.0,
+            makeup: 1.0,
+            envelope: 0.0,
+            attack: time_coeff(COMPRESSOR_ATTACK_MS, sample_rate),
+            release: time_coeff(COMPRESSOR_RELEASE_MS, sample_rate),
+        };
+        compressor.set_compression(compression_db);
+        compressor
+    }
+
+    /// Set compression in dB (clamped to 0..[`MAX_COMPRESSION_DB`])
+    pub fn set_compression(&mut self, compression_db: f32) {
+        self.compression_db = compression_db.clamp(0.0, MAX_COMPRESSION_DB);
+        self.threshold = 10.0_f32.powf(-self.compression_db / 20.0);
+        let makeup_db = self.compression_db * (1.0 - 1.0 / COMPRESSOR_RATIO);
+        self.makeup = 10.0_f32.powf(makeup_db / 20.0);
+    }
+
+    /// Get compression in dB
+    #[must_use]
+    pub const fn compression_db(&self) -> f32 {
+        self.compression_db
+    }
+
+    /// Current gain applied before make-up (linear, 1.0 = no reduction)
+    #[must_use]
+    pub fn gain_reduction(&self) -> f32 {
+        if self.envelope > self.threshold {
+            (self.threshold / self.envelope).powf(1.0 - 1.0 / COMPRESSOR_RATIO)
+        } else {
+            1.0
+        }
+    }
+
+    /// Process one sample
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coeff = if level > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope += coeff * (level - self.envelope);
+
+        let output = input * self.gain_reduction() * self.makeup;
+        output.clamp(-LIMITER_CEILING, LIMITER_CEILING)
+    }
+
+    /// Reset the envelope
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+/// Automatic level control loop on the modulator output
+///
+/// Attack is instantaneous: as soon as the output exceeds the target
+/// the drive is cut so the next sample lands on it. The drive then
+/// recovers slowly, so the loop rides voice peaks without pumping.
+#[derive(Clone, Copy, Debug)]
+pub struct Alc {
+    /// Target peak output level
+    target: f32,
+    /// Current drive gain (linear, at most 1.0)
+    gain: f32,
+    /// Release coefficient
+    release: f32,
+}
+
+impl Alc {
+    /// Create an ALC loop with the default target
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            target: DEFAULT_ALC_TARGET,
+            gain: 1.0,
+            release: time_coeff(ALC_RELEASE_MS, sample_rate),
+        }
+    }
+
+    /// Set the target peak output level (0.1 to 1.0)
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target.clamp(0.1, 1.0);
+    }
+
+    /// Get the target peak output level
+    #[must_use]
+    pub const fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Drive gain to apply to the next sample
+    #[must_use]
+    pub const fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Feed back the output level produced at the current gain
+    pub fn update(&mut self, output_level: f32) {
+        if output_level > self.target {
+            self.gain *= self.target / output_level;
+        } else {
+            self.gain += self.release * (1.0 - self.gain);
+        }
+    }
+
+    /// Gain reduction in dB (0 when the ALC is idle)
+    #[must_use]
+    pub fn reduction_db(&self) -> f32 {
+        -20.0 * self.gain.max(1e-6).log10()
+    }
+
+    /// Reset to full drive
+    pub fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
@@ -5,8 +5,11 @@
 //! async interfaces for all peripheral operations.
 
 pub mod adc;
+pub mod audio;
+pub mod bootloader;
 pub mod dac;
 pub mod gpio;
 pub mod i2c;
 pub mod pwm;
+pub mod rtc;
 pub mod timer;
@@ -2,12 +2,25 @@
 //!
 //! Provides async ADC reading for audio input and power measurement.
 //! Uses DMA for efficient bulk transfers of audio samples.
-
-use embassy_stm32::adc::{Adc, AdcChannel, SampleTime};
+//!
+//! [`SwrAdc`] samples the SWR bridge detectors on ADC1 and averages
+//! them into calibrated readings for the transmit controller:
+//!
+//! ```ignore
+//! let mut swr = SwrAdc::new(p.ADC1, p.PB1.degrade_adc(), p.PB11.degrade_adc());
+//! loop {
+//!     tx.update_swr(swr.next_reading().await);
+//! }
+//! ```
+
+use embassy_stm32::adc::{Adc, AdcChannel, AnyAdcChannel, SampleTime};
 use embassy_stm32::peripherals::{ADC1, ADC2};
+use embassy_time::{Duration, Ticker};
 use micromath::F32Ext;
 
-use crate::config::{AUDIO_BUFFER_SIZE, IQ_BUFFER_SIZE};
+use crate::config::{AUDIO_BUFFER_SIZE, IQ_BUFFER_SIZE, SWR_SAMPLE_INTERVAL_MS};
+use crate::radio::swr::{SwrCalibration, SwrMeter};
+use crate::types::{IqOrientation, SwrReading};
 
 /// ADC reading result
 #[derive(Clone, Copy, Debug)]
@@ -146,6 +159,81 @@ impl defmt::Format for PowerReading {
     }
 }
 
+/// SWR bridge sampler
+pub struct SwrAdc<'d> {
+    /// ADC1, shared by both detectors
+    adc: Adc<'d, ADC1>,
+    /// Forward detector input
+    forward: AnyAdcChannel<ADC1>,
+    /// Reflected detector input
+    reflected: AnyAdcChannel<ADC1>,
+    /// Averaging and calibration
+    meter: SwrMeter,
+    /// Sample pacing
+    ticker: Ticker,
+}
+
+impl SwrAdc<'_> {
+    /// Create a sampler with the default calibration
+    #[must_use]
+    pub fn new(adc: ADC1, forward: AnyAdcChannel<ADC1>, reflected: AnyAdcChannel<ADC1>) -> Self {
+        let mut adc = Adc::new(adc);
+        // Detector outputs are filtered DC, so take the longest sample
+        adc.set_sample_time(SampleTime::CYCLES640_5);
+        Self {
+            adc,
+            forward,
+            reflected,
+            meter: SwrMeter::default(),
+            ticker: Ticker::every(Duration::from_millis(SWR_SAMPLE_INTERVAL_MS)),
+        }
+    }
+
+    /// Replace the detector calibration
+    pub fn set_calibration(&mut self, calibration: SwrCalibration) {
+        self.meter.set_calibration(calibration);
+    }
+
+    /// Read both detectors once
+    pub fn read(&mut self) -> PowerReading {
+        PowerReading {
+            forward: AdcReading::from_raw(self.adc.blocking_read(&mut self.forward)),
+            reflected: AdcReading::from_raw(self.adc.blocking_read(&mut self.reflected)),
+        }
+    }
+
+    /// Read both detectors into the average, returning a reading once
+    /// the average is complete
+    ///
+    /// For callers that keep to the bridge rate themselves.
+    pub fn sample(&mut self) -> Option<SwrReading> {
+        let sample = self.read();
+        self.meter.push(sample.forward.raw(), sample.reflected.raw())
+    }
+
+    /// Sample at the bridge rate until the next averaged reading
+    pub async fn next_reading(&mut self) -> SwrReading {
+        loop {
+            self.ticker.next().await;
+            if let Some(reading) = self.sample() {
+                return reading;
+            }
+        }
+    }
+
+    /// Start a fresh average (e.g. at key-down)
+    pub fn reset(&mut self) {
+        self.meter.reset();
+        self.ticker.reset();
+    }
+
+    /// Get the averaging meter
+    #[must_use]
+    pub const fn meter(&self) -> &SwrMeter {
+        &self.meter
+    }
+}
+
 /// Audio sample buffer for DMA transfers
 pub struct AudioBuffer {
     /// Sample buffer
@@ -170,6 +258,16 @@ impl AudioBuffer {
         &self.samples[..self.len]
     }
 
+    /// Correct the I/Q orientation of the received pairs in place
+    pub fn orient(&mut self, orientation: IqOrientation) {
+        if orientation == IqOrientation::Normal {
+            return;
+        }
+        for pair in self.samples[..self.len].chunks_exact_mut(2) {
+            (pair[0], pair[1]) = orientation.apply_raw(pair[0], pair[1]);
+        }
+    }
+
     /// Get mutable access to the buffer
     #[must_use]
     pub fn as_mut_slice(&mut self) -> &mut [i16] {
@@ -230,6 +328,16 @@ impl IqBuffer {
         self.samples[idx * 2 + 1]
     }
 
+    /// Correct the I/Q orientation of the received pairs in place
+    pub fn orient(&mut self, orientation: IqOrientation) {
+        if orientation == IqOrientation::Normal {
+            return;
+        }
+        for pair in self.samples[..self.len].chunks_exact_mut(2) {
+            (pair[0], pair[1]) = orientation.apply_raw(pair[0], pair[1]);
+        }
+    }
+
     /// Get mutable access to the buffer
     #[must_use]
     pub fn as_mut_slice(&mut self) -> &mut [i16] {
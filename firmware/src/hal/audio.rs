@@ -0,0 +1,312 @@
+//! Audio I/O
+//!
+//! Streams the quadrature sampling detector in and audio out with
+//! circular DMA, so the audio I/O task only ever waits for the next
+//! block:
+//!
+//! - [`IqInput`]: ADC2 scans I then Q on every TIM6 update at
+//!   [`IQ_SAMPLE_RATE`], and DMA writes the pairs round a ring of
+//!   [`IQ_RING_LEN`] samples. [`IqInput::next_block`] waits for the next
+//!   [`IqBlock`] of [`IQ_BUFFER_SIZE`] interleaved samples.
+//! - [`AudioOutput`]: DAC1 channel 1 loads one sample on every TIM7
+//!   update at [`DAC_SAMPLE_RATE`], and DMA reads them round a ring of
+//!   [`AUDIO_RING_LEN`] samples that the I/O task keeps topped up.
+//!
+//! Each ring holds two blocks, so DMA fills (or drains) one half while
+//! the I/O task works on the other. The timers pace the converters, so
+//! the sample rates do not depend on how busy the CPU is. If the I/O
+//! task falls a whole ring behind, the ring is restarted and the overrun
+//! counted; the DSP hears a gap rather than stale samples.
+//!
+//! The DSP task does not touch the DMA rings itself. An audio I/O task
+//! moves each block between them and two [`SpscRing`] queues:
+//! [`IqInput::fill`] scales the codes to I/Q samples and queues them on
+//! an [`IqQueue`], and [`AudioOutput::drain`] plays a block from an
+//! [`AudioQueue`]. A DSP task that falls behind shows up as samples
+//! dropped from the first and underruns of the second.
+//!
+//! [`SpscRing`]: crate::types::ring::SpscRing
+//!
+//! # Example
+//!
+//! ```ignore
+//! let (mut iq_in, mut iq_out) = IQ_QUEUE.init(IqQueue::new()).split();
+//! let (mut audio_in, mut audio_out) = AUDIO_QUEUE.init(AudioQueue::new()).split();
+//! // audio I/O task
+//! loop {
+//!     input.fill(&mut iq_in).await;
+//!     output.drain(&mut audio_out).await;
+//! }
+//! // DSP task
+//! while iq_out.len() >= IQ_BLOCK_PAIRS {
+//!     iq_out.pop_slice(&mut block);
+//!     let len = pipeline.process_iq(&block, &mut audio);
+//!     audio_in.push_slice(&audio[..len]);
+//! }
+//! ```
+
+#![allow(unsafe_code)]
+
+use embassy_stm32::adc::{AnyAdcChannel, RxDma, SampleTime};
+use embassy_stm32::dac::{DacCh1, DacDma1, DacPin, TriggerSel};
+use embassy_stm32::dma::{NoDma, ReadableRingBuffer, TransferOptions, WritableRingBuffer};
+use embassy_stm32::pac;
+use embassy_stm32::pac::adc::vals::{Dmacfg, Dmaen, Exten};
+use embassy_stm32::pac::timer::vals::Mms;
+use embassy_stm32::peripherals::{ADC2, DAC1, TIM6, TIM7};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::low_level::Timer;
+use embassy_stm32::timer::BasicInstance;
+use embassy_stm32::{into_ref, Peripheral};
+
+use crate::config::{DAC_SAMPLE_RATE, IQ_BUFFER_SIZE, IQ_SAMPLE_RATE};
+use crate::dsp::modulation::IqSample;
+use crate::dsp::pipeline::{iq_from_adc, DECIMATION};
+use crate::hal::adc::IqAdc;
+use crate::hal::dac::{DacSample, OutputBuffer};
+use crate::types::ring::{AudioRing, Consumer, IqRing, Producer};
+
+/// One block of interleaved I/Q ADC codes
+pub type IqBlock = [u16; IQ_BUFFER_SIZE];
+
+/// IQ ring length in samples (two blocks)
+pub const IQ_RING_LEN: usize = IQ_BUFFER_SIZE * 2;
+
+/// Audio samples produced from one IQ block
+pub const AUDIO_BLOCK_LEN: usize = IQ_BUFFER_SIZE / 2 / DECIMATION;
+
+/// Audio ring length in samples (two blocks)
+pub const AUDIO_RING_LEN: usize = AUDIO_BLOCK_LEN * 2;
+
+/// I/Q pairs in one block
+pub const IQ_BLOCK_PAIRS: usize = IQ_BUFFER_SIZE / 2;
+
+/// I/Q queue length to the DSP task in samples (four blocks)
+pub const IQ_QUEUE_LEN: usize = IQ_BLOCK_PAIRS * 4;
+
+/// Audio queue length from the DSP task in samples (four blocks)
+pub const AUDIO_QUEUE_LEN: usize = AUDIO_BLOCK_LEN * 4;
+
+/// I/Q samples from the audio I/O task to the DSP task
+pub type IqQueue = IqRing<IQ_QUEUE_LEN>;
+
+/// Audio samples from the DSP task to the audio I/O task
+pub type AudioQueue = AudioRing<AUDIO_QUEUE_LEN>;
+
+/// ADC1/2 regular external trigger: TIM6 TRGO
+const ADC_EXTSEL_TIM6_TRGO: u8 = 13;
+
+/// ADC2 input channel for I (PA6 is ADC2_IN3)
+const IQ_I_CHANNEL: u8 = 3;
+
+/// ADC2 input channel for Q (PA7 is ADC2_IN4)
+const IQ_Q_CHANNEL: u8 = 4;
+
+/// Sample time for the I and Q channels
+const IQ_SAMPLE_TIME: SampleTime = SampleTime::CYCLES47_5;
+
+/// Start a basic timer whose update event drives TRGO
+fn trigger_timer<'d, T: BasicInstance>(
+    tim: impl Peripheral<P = T> + 'd,
+    rate_hz: u32,
+) -> Timer<'d, T> {
+    let timer = Timer::new(tim);
+    timer.set_frequency(Hertz(rate_hz));
+    timer.regs_basic().cr2().modify(|w| w.set_mms(Mms::UPDATE));
+    timer.start();
+    timer
+}
+
+/// Quadrature ADC input streamed in blocks
+pub struct IqInput<'d> {
+    /// Calibrated and enabled ADC, held so it stays powered
+    _adc: IqAdc<'d>,
+    /// I and Q pins, held so they stay analog
+    _channels: [AnyAdcChannel<ADC2>; 2],
+    /// Conversion trigger
+    _timer: Timer<'d, TIM6>,
+    /// DMA ring the scans land in
+    ring: ReadableRingBuffer<'d, u16>,
+    /// Latest block
+    block: IqBlock,
+    /// Times the ring was restarted after falling behind
+    overruns: u32,
+}
+
+impl<'d> IqInput<'d> {
+    /// Configure ADC2 to scan `i` then `q` at [`IQ_SAMPLE_RATE`] into `ring`
+    ///
+    /// `i` and `q` must be PA6 and PA7; the scan sequence is programmed
+    /// with their ADC2 channel numbers, which the HAL does not expose.
+    /// Takes over the ADC from single reads (e.g. the self test).
+    /// Conversions start immediately.
+    pub fn new(
+        adc: IqAdc<'d>,
+        dma: impl Peripheral<P = impl RxDma<ADC2>> + 'd,
+        i: AnyAdcChannel<ADC2>,
+        q: AnyAdcChannel<ADC2>,
+        tim: impl Peripheral<P = TIM6> + 'd,
+        ring: &'d mut [u16; IQ_RING_LEN],
+    ) -> Self {
+        into_ref!(dma);
+
+        let regs = pac::ADC2;
+        regs.smpr().modify(|w| {
+            w.set_smp(usize::from(IQ_I_CHANNEL), IQ_SAMPLE_TIME);
+            w.set_smp(usize::from(IQ_Q_CHANNEL), IQ_SAMPLE_TIME);
+        });
+        regs.sqr1().modify(|w| {
+            w.set_l(1);
+            w.set_sq(0, IQ_I_CHANNEL);
+            w.set_sq(1, IQ_Q_CHANNEL);
+        });
+        regs.cfgr().modify(|w| {
+            w.set_cont(false);
+            w.set_exten(Exten::RISING_EDGE);
+            w.set_extsel(ADC_EXTSEL_TIM6_TRGO);
+            w.set_dmacfg(Dmacfg::CIRCULAR);
+            w.set_dmaen(Dmaen::ENABLE);
+        });
+
+        let request = dma.request();
+        // SAFETY: the ADC data register is a fixed peripheral address, so
+        // it outlives the ring, and the DMA is the only reader of it;
+        // ADC2 makes no requests until ADSTART is set below
+        let mut ring = unsafe {
+            ReadableRingBuffer::new(
+                dma,
+                request,
+                regs.dr().as_ptr().cast::<u16>(),
+                ring,
+                TransferOptions::default(),
+            )
+        };
+        ring.start();
+        regs.cr().modify(|w| w.set_adstart(true));
+
+        Self {
+            _adc: adc,
+            _channels: [i, q],
+            _timer: trigger_timer(tim, IQ_SAMPLE_RATE),
+            ring,
+            block: [0; IQ_BUFFER_SIZE],
+            overruns: 0,
+        }
+    }
+
+    /// Wait for the next block of interleaved I/Q codes
+    pub async fn next_block(&mut self) -> &IqBlock {
+        while self.ring.read_exact(&mut self.block).await.is_err() {
+            self.overruns = self.overruns.wrapping_add(1);
+            self.ring.clear();
+        }
+        &self.block
+    }
+
+    /// Wait for the next block and queue it for the DSP task as samples
+    ///
+    /// Returns how many pairs were queued; the rest are counted as
+    /// dropped on the queue.
+    pub async fn fill<const N: usize>(&mut self, queue: &mut Producer<'_, IqSample, N>) -> usize {
+        let mut samples = [IqSample::default(); IQ_BLOCK_PAIRS];
+        let block = self.next_block().await;
+        for (sample, pair) in samples.iter_mut().zip(block.chunks_exact(2)) {
+            *sample = iq_from_adc(pair[0], pair[1]);
+        }
+        queue.push_slice(&samples)
+    }
+
+    /// Get the number of times the input fell a whole ring behind
+    #[must_use]
+    pub const fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
+
+/// Audio DAC output streamed from blocks
+pub struct AudioOutput<'d> {
+    /// Enabled DAC channel, held so the pin stays analog
+    _channel: DacCh1<'d, DAC1, NoDma>,
+    /// Conversion trigger
+    _timer: Timer<'d, TIM7>,
+    /// DMA ring the DAC reads from
+    ring: WritableRingBuffer<'d, u16>,
+    /// Times the ring ran dry and was restarted
+    underruns: u32,
+}
+
+impl<'d> AudioOutput<'d> {
+    /// Configure DAC1 channel 1 to play `ring` at [`DAC_SAMPLE_RATE`]
+    ///
+    /// The ring starts at mid-scale, so the output is silent until the
+    /// first block is played.
+    pub fn new(
+        dac: impl Peripheral<P = DAC1> + 'd,
+        dma: impl Peripheral<P = impl DacDma1<DAC1>> + 'd,
+        pin: impl Peripheral<P = impl DacPin<DAC1, 1> + embassy_stm32::gpio::Pin> + 'd,
+        tim: impl Peripheral<P = TIM7> + 'd,
+        ring: &'d mut [u16; AUDIO_RING_LEN],
+    ) -> Self {
+        into_ref!(dma);
+        ring.fill(DacSample::default().raw());
+
+        let mut channel = DacCh1::new(dac, NoDma, pin);
+        channel.set_trigger(TriggerSel::Tim7);
+        channel.set_triggering(true);
+        pac::DAC1.cr().modify(|w| w.set_dmaen(0, true));
+        channel.enable();
+
+        let request = dma.request();
+        // SAFETY: the DAC holding register is a fixed peripheral address,
+        // so it outlives the ring, and `channel` is held with `NoDma` so
+        // nothing else writes it; the DAC makes no requests until TIM7
+        // is started below
+        let mut ring = unsafe {
+            WritableRingBuffer::new(
+                dma,
+                request,
+                pac::DAC1.dhr12r(0).as_ptr().cast::<u16>(),
+                ring,
+                TransferOptions::default(),
+            )
+        };
+        ring.start();
+
+        Self {
+            _channel: channel,
+            _timer: trigger_timer(tim, DAC_SAMPLE_RATE),
+            ring,
+            underruns: 0,
+        }
+    }
+
+    /// Queue a buffer for playback, waiting for room in the ring
+    pub async fn play(&mut self, buffer: &OutputBuffer) {
+        let samples = buffer.as_slice();
+        if samples.is_empty() {
+            return;
+        }
+        if self.ring.write_exact(samples).await.is_err() {
+            self.underruns = self.underruns.wrapping_add(1);
+            self.ring.clear();
+        }
+    }
+
+    /// Play the next block from the DSP task's queue
+    ///
+    /// A short read is counted as an underrun on the queue and the
+    /// missing samples play as silence.
+    pub async fn drain<const N: usize>(&mut self, queue: &mut Consumer<'_, f32, N>) {
+        let mut audio = [0.0; AUDIO_BLOCK_LEN];
+        queue.pop_slice(&mut audio);
+        let mut buffer = OutputBuffer::new();
+        buffer.fill_from_audio(&audio);
+        self.play(&buffer).await;
+    }
+
+    /// Get the number of times the ring ran dry
+    #[must_use]
+    pub const fn underruns(&self) -> u32 {
+        self.underruns
+    }
+}
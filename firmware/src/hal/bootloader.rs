@@ -0,0 +1,61 @@
+//! ROM Bootloader Entry
+//!
+//! Reboots the STM32G474 into its built-in system memory bootloader,
+//! which enumerates as a USB DFU device (0483:DF11) for firmware updates.
+//!
+//! Jumping straight from running firmware is unreliable because clocks,
+//! interrupts and DMA are already configured. Instead a magic word is
+//! left in non-initialized RAM and the core is reset; `check_request`
+//! runs first thing after reset, while the chip is still in its reset
+//! state, and performs the jump.
+
+#![allow(unsafe_code)]
+
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// System memory (ROM bootloader) base address on STM32G4
+const SYSTEM_MEMORY_BASE: u32 = 0x1FFF_0000;
+
+/// Magic value requesting bootloader entry
+const BOOT_MAGIC: u32 = 0xB007_1DF0;
+
+/// Reset-surviving request word (not zeroed by the runtime)
+#[link_section = ".uninit.BOOT_REQUEST"]
+static mut BOOT_REQUEST: MaybeUninit<u32> = MaybeUninit::uninit();
+
+/// Request the ROM bootloader and reset the MCU
+///
+/// Does not return. The USB device disappears and re-enumerates as
+/// the ST DFU bootloader.
+pub fn enter_bootloader() -> ! {
+    // SAFETY: single word write to a static only touched here and in
+    // `check_request`, which runs before anything else after reset
+    unsafe {
+        ptr::addr_of_mut!(BOOT_REQUEST).cast::<u32>().write_volatile(BOOT_MAGIC);
+    }
+    cortex_m::peripheral::SCB::sys_reset()
+}
+
+/// Jump to the ROM bootloader if it was requested before the last reset
+///
+/// Must be called at the very start of `main`, before clocks or
+/// peripherals are initialized.
+pub fn check_request() {
+    // SAFETY: see `enter_bootloader`; reading uninitialized RAM as a
+    // plain u32 is fine since any bit pattern is valid
+    let requested = unsafe {
+        let word = ptr::addr_of_mut!(BOOT_REQUEST).cast::<u32>();
+        let value = word.read_volatile();
+        word.write_volatile(0);
+        value == BOOT_MAGIC
+    };
+
+    if requested {
+        // SAFETY: the system memory holds a valid vector table and the
+        // core is still in its reset configuration
+        unsafe {
+            cortex_m::asm::bootload(SYSTEM_MEMORY_BASE as *const u32);
+        }
+    }
+}
@@ -138,6 +138,14 @@ impl OutputBuffer {
             self.push(DacSample::from_i16(sample));
         }
     }
+
+    /// Fill buffer from audio samples (-1.0 to 1.0)
+    pub fn fill_from_audio(&mut self, samples: &[f32]) {
+        self.reset();
+        for &sample in samples.iter().take(AUDIO_BUFFER_SIZE) {
+            self.push(DacSample::from_audio(sample));
+        }
+    }
 }
 
 impl Default for OutputBuffer {
@@ -2,13 +2,22 @@
 //!
 //! Provides async I2C communication for peripherals like `Si5351A` and display.
 //! Uses embassy-stm32 async I2C driver with DMA.
+//!
+//! The `Si5351A` and the display sit on the same bus but are driven from
+//! different tasks, so a bus can be a [`SharedI2c`] that each driver
+//! locks for one transaction at a time.
 
 use embassy_stm32::i2c::{Error as I2cError, I2c};
 use embassy_stm32::mode::Async;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::mutex::Mutex;
 
 /// I2C operation result
 pub type I2cResult<T> = Result<T, I2cError>;
 
+/// I2C peripheral shared between tasks
+pub type SharedI2c<'d> = Mutex<CriticalSectionRawMutex, I2c<'d, Async>>;
+
 /// I2C device address wrapper
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct I2cAddress(u8);
@@ -20,6 +29,9 @@ impl I2cAddress {
     /// SSD1306 OLED display address
     pub const SSD1306: Self = Self(0x3C);
 
+    /// LM75 PA heatsink temperature sensor address
+    pub const LM75: Self = Self(0x48);
+
     /// Create from 7-bit address
     #[must_use]
     pub const fn new(addr: u8) -> Self {
@@ -39,26 +51,61 @@ impl defmt::Format for I2cAddress {
     }
 }
 
+/// Owned or shared I2C peripheral
+enum Bus<'d> {
+    /// Exclusive use
+    Owned(I2c<'d, Async>),
+    /// Locked per transaction
+    Shared(&'d SharedI2c<'d>),
+}
+
 /// I2C bus wrapper for shared access
 pub struct I2cBus<'d> {
-    i2c: I2c<'d, Async>,
+    i2c: Bus<'d>,
 }
 
 impl<'d> I2cBus<'d> {
     /// Create a new I2C bus wrapper
     #[must_use] 
     pub fn new(i2c: I2c<'d, Async>) -> Self {
-        Self { i2c }
+        Self { i2c: Bus::Owned(i2c) }
+    }
+
+    /// Create a bus wrapper that locks a shared peripheral per transaction
+    #[must_use]
+    pub const fn shared(i2c: &'d SharedI2c<'d>) -> Self {
+        Self { i2c: Bus::Shared(i2c) }
+    }
+
+    /// Release the underlying I2C peripheral (`None` for a shared bus)
+    #[must_use]
+    pub fn into_inner(self) -> Option<I2c<'d, Async>> {
+        match self.i2c {
+            Bus::Owned(i2c) => Some(i2c),
+            Bus::Shared(_) => None,
+        }
+    }
+
+    /// Check if a device acknowledges its address
+    pub async fn probe(&mut self, addr: I2cAddress) -> bool {
+        let mut buf = [0u8; 1];
+        self.read(addr, &mut buf).await.is_ok()
     }
 
     /// Write bytes to a device
     pub async fn write(&mut self, addr: I2cAddress, data: &[u8]) -> I2cResult<()> {
-        self.i2c.write(addr.addr(), data).await
+        match &mut self.i2c {
+            Bus::Owned(i2c) => i2c.write(addr.addr(), data).await,
+            Bus::Shared(i2c) => i2c.lock().await.write(addr.addr(), data).await,
+        }
     }
 
     /// Read bytes from a device
     pub async fn read(&mut self, addr: I2cAddress, buffer: &mut [u8]) -> I2cResult<()> {
-        self.i2c.read(addr.addr(), buffer).await
+        match &mut self.i2c {
+            Bus::Owned(i2c) => i2c.read(addr.addr(), buffer).await,
+            Bus::Shared(i2c) => i2c.lock().await.read(addr.addr(), buffer).await,
+        }
     }
 
     /// Write then read (combined transaction)
@@ -68,18 +115,21 @@ impl<'d> I2cBus<'d> {
         write: &[u8],
         read: &mut [u8],
     ) -> I2cResult<()> {
-        self.i2c.write_read(addr.addr(), write, read).await
+        match &mut self.i2c {
+            Bus::Owned(i2c) => i2c.write_read(addr.addr(), write, read).await,
+            Bus::Shared(i2c) => i2c.lock().await.write_read(addr.addr(), write, read).await,
+        }
     }
 
     /// Write a single register
     pub async fn write_reg(&mut self, addr: I2cAddress, reg: u8, value: u8) -> I2cResult<()> {
-        self.i2c.write(addr.addr(), &[reg, value]).await
+        self.write(addr, &[reg, value]).await
     }
 
     /// Read a single register
     pub async fn read_reg(&mut self, addr: I2cAddress, reg: u8) -> I2cResult<u8> {
         let mut buf = [0u8];
-        self.i2c.write_read(addr.addr(), &[reg], &mut buf).await?;
+        self.write_read(addr, &[reg], &mut buf).await?;
         Ok(buf[0])
     }
 
@@ -91,7 +141,7 @@ impl<'d> I2cBus<'d> {
             let mut buf = [0u8; 17];
             buf[0] = base_reg;
             buf[1..=values.len()].copy_from_slice(values);
-            self.i2c.write(addr.addr(), &buf[..=values.len()]).await
+            self.write(addr, &buf[..=values.len()]).await
         } else {
             // For larger writes, do individual register writes
             for (i, &value) in values.iter().enumerate() {
@@ -108,7 +158,7 @@ impl<'d> I2cBus<'d> {
         base_reg: u8,
         buffer: &mut [u8],
     ) -> I2cResult<()> {
-        self.i2c.write_read(addr.addr(), &[base_reg], buffer).await
+        self.write_read(addr, &[base_reg], buffer).await
     }
 
     /// Scan the I2C bus for devices
@@ -116,9 +166,9 @@ impl<'d> I2cBus<'d> {
         let mut devices = heapless::Vec::new();
 
         for addr in 0x08..0x78 {
-            let mut buf = [0u8; 1];
-            if self.i2c.read(addr, &mut buf).await.is_ok() {
-                let _ = devices.push(I2cAddress::new(addr));
+            let addr = I2cAddress::new(addr);
+            if self.probe(addr).await {
+                let _ = devices.push(addr);
             }
         }
 
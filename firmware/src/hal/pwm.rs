@@ -107,6 +107,15 @@ impl PaDrive {
         self.duty
     }
 
+    /// Set drive level scaled by a keying envelope gain (0.0 to 1.0)
+    ///
+    /// Call once per envelope step with the gain from
+    /// [`KeyingEnvelope::next_gain`](crate::dsp::keying::KeyingEnvelope::next_gain)
+    /// so the carrier ramps rather than switching hard.
+    pub fn set_shaped(&mut self, duty: DutyCycle, gain: f32) -> DutyCycle {
+        self.set(DutyCycle::from_fraction(duty.as_fraction() * gain.clamp(0.0, 1.0)))
+    }
+
     /// Set drive level from percentage
     pub fn set_percent(&mut self, percent: u8) -> DutyCycle {
         self.set(DutyCycle::from_percent(percent))
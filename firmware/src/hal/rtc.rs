@@ -0,0 +1,75 @@
+//! RTC Peripheral
+//!
+//! Battery-backed real-time clock on the STM32G474 backup domain. The
+//! RTC runs from the 32.768 kHz LSE crystal and keeps time through power
+//! cycles when VBAT is fitted. It is read once at boot to seed
+//! [`Clock`](crate::clock::Clock) and written whenever a better source
+//! (GPS, CAT, host) sets the time.
+
+use embassy_stm32::rcc::LsConfig;
+use embassy_stm32::rtc::{DateTime as HwDateTime, DayOfWeek, Rtc, RtcError};
+
+use crate::clock::DateTime;
+
+/// Low-speed clock configuration for the backup domain
+///
+/// Assign to `Config::rcc.ls` before `embassy_stm32::init` so the RTC
+/// is clocked from the LSE crystal rather than the inaccurate LSI.
+#[must_use]
+pub fn backup_domain_config() -> LsConfig {
+    LsConfig::default_lse()
+}
+
+/// Battery-backed RTC wrapper
+pub struct BackupRtc {
+    rtc: Rtc,
+}
+
+impl BackupRtc {
+    /// Wrap an initialized RTC
+    #[must_use]
+    pub fn new(rtc: Rtc) -> Self {
+        Self { rtc }
+    }
+
+    /// Read the current UTC time (None if the RTC was never set)
+    #[must_use]
+    pub fn read(&self) -> Option<DateTime> {
+        let now = self.rtc.now().ok()?;
+        DateTime::new(
+            now.year(),
+            now.month(),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second(),
+        )
+    }
+
+    /// Write UTC time to the RTC
+    ///
+    /// # Errors
+    /// Returns an error if the RTC rejects the date
+    pub fn write(&mut self, time: &DateTime) -> Result<(), RtcError> {
+        let day_of_week = match time.weekday() {
+            1 => DayOfWeek::Monday,
+            2 => DayOfWeek::Tuesday,
+            3 => DayOfWeek::Wednesday,
+            4 => DayOfWeek::Thursday,
+            5 => DayOfWeek::Friday,
+            6 => DayOfWeek::Saturday,
+            _ => DayOfWeek::Sunday,
+        };
+        let hw = HwDateTime::from(
+            time.year,
+            time.month,
+            time.day,
+            day_of_week,
+            time.hour,
+            time.minute,
+            time.second,
+        )
+        .map_err(RtcError::InvalidDateTime)?;
+        self.rtc.set_datetime(hw)
+    }
+}
@@ -79,8 +79,8 @@ pub mod power;
 
 /// User Interface
 ///
-/// Display rendering, menu system, input handling.
-#[cfg(feature = "embedded")]
+/// Display rendering, menu system, input handling. Headless boards
+/// build only the UI state and redraw scheduling, without drawing.
 pub mod ui;
 
 /// USB Subsystem
@@ -89,6 +89,31 @@ pub mod ui;
 #[cfg(feature = "embedded")]
 pub mod usb;
 
+/// Real-Time Clock
+///
+/// Calendar, UTC/local time and time sources.
+pub mod clock;
+
+/// Storage
+///
+/// Flat file store for logs, captures and voice messages.
+pub mod storage;
+
+/// Firmware Update
+///
+/// Version handshake and bootloader entry.
+pub mod update;
+
+/// Power-On Self Test
+///
+/// Boot-time hardware checks and the pass/fail report.
+pub mod selftest;
+
+/// Persistent Settings
+///
+/// Startup policy and the last-used and fixed power-on states.
+pub mod settings;
+
 /// Communication Protocols
 ///
 /// CAT command parser, IQ data formatting.
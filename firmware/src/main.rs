@@ -2,44 +2,367 @@
 //!
 //! Entry point for the STM32G474-based SDR radio firmware.
 //! Initializes hardware and spawns async tasks.
+//!
+//! # Tasks
+//!
+//! | Task                  | Executor                   | Owns                          |
+//! |-----------------------|----------------------------|-------------------------------|
+//! | `audio_io_task`       | interrupt (`UART4`, P5)    | ADC and DAC DMA rings         |
+//! | `dsp_processing_task` | interrupt (`UART5`, P6)    | receive pipeline              |
+//! | `radio_control_task`  | thread                     | `Si5351A`, radio state        |
+//! | `tx_task`             | thread                     | PTT, relays, PA, SWR bridge   |
+//! | `pa_thermal_task`     | thread                     | PA temperature sensor         |
+//! | `ui_task`             | thread (`display` feature) | display, encoder              |
+//! | `usb_task`            | thread                     | USB device                    |
+//! | `cat_task`            | thread                     | CAT serial port, RTC          |
+//! | `winkeyer_task`       | thread                     | `WinKeyer` serial port, keyer |
+//! | `usb_audio_task`      | thread                     | USB audio I/Q capture         |
+//! | `update_task`         | thread                     | DFU runtime interface         |
+//! | `heartbeat_task`      | thread                     | status LED                    |
+//!
+//! The DSP task runs on an interrupt executor so it preempts the
+//! thread-mode tasks: an audio block is never late behind a display
+//! transfer or a retune. The thread-mode tasks run while it waits for
+//! samples. The audio I/O task runs above it and only moves blocks
+//! between the DMA rings and two lock-free sample queues, so the
+//! converters are serviced even while a block is being processed. Tasks
+//! share nothing but these queues and typed channels:
+//!
+//! - `IQ_QUEUE`: I/Q samples from audio I/O to the DSP, with
+//!   `IQ_READY` raised for each block
+//! - `AUDIO_QUEUE`: audio samples from the DSP to audio I/O
+//! - `USB_IQ_QUEUE`: decimated I/Q from the DSP to USB audio while the
+//!   host is capturing, with `USB_IQ_READY` raised for each block
+//! - `RADIO_EVENTS`: [`RadioEvent`]s from the UI and CAT to radio control,
+//!   and the start and end of each over from the transmit task
+//! - `TX_REQUESTS`: transmit on/off, the `WinKeyer` key line, the policy
+//!   override and the PA temperature limits for the transmit task
+//! - `DSP_COMMANDS`: [`DspCommand`]s from radio control to the DSP
+//! - `MONITOR_COMMANDS`: band monitor start, stop and spots from CAT to
+//!   radio control
+//! - `CONFIG_UPDATES`: operator settings changed on the front panel, for
+//!   radio control to save and hand on as `KEYER_CONFIG` to the
+//!   `WinKeyer` task and as a transmit request (VOX) to the transmit task
+//! - `RADIO_STATUS`: latest [`RadioState`] from radio control to the UI
+//! - `TX_OPERATING`: latest [`RadioState`] from radio control to the
+//!   transmit task, which checks the [`TxPolicy`] against it
+//! - `TX_POLICY`: the transmit task's policy, for the UI and CAT
+//! - `S_METER`: latest S-meter reading (0-100) from the DSP to the UI
+//! - `SPECTRUM_ROW`: latest band scope row from the DSP to the UI, which
+//!   keeps the waterfall
+//! - `BOOTLOADER_REQUEST`: confirmed bootloader entry from CAT or the
+//!   front panel to the update task
+//!
+//! The `Si5351A`, the display and the PA temperature sensor share I2C1,
+//! locked per transaction.
+//!
+//! Every request to transmit, from the PTT input, the front panel, CAT
+//! or the `WinKeyer` port, goes through the transmit task's [`TxController`], so keying is
+//! always checked against the [`TxPolicy`] and the SWR, thermal and
+//! relay protection. The radio control task only hears about an over
+//! once the controller has started it.
+//!
+//! `CLOCK` is the wall clock, seeded from the RTC at boot and set over
+//! CAT. Log lines are stamped with it, or with the uptime from the epoch
+//! while it is unset.
 
 #![no_std]
 #![no_main]
 
-use defmt::info;
-use embassy_executor::Spawner;
-use embassy_stm32::gpio::{Level, Output, Speed};
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::{error, info, warn};
+use embassy_executor::{InterruptExecutor, Spawner};
+use embassy_futures::select::{select4, Either4};
+use embassy_stm32::adc::{AdcChannel, AnyAdcChannel};
+use embassy_stm32::gpio::{Input, Level, Output, Pull, Speed};
 use embassy_stm32::i2c::I2c;
+use embassy_stm32::interrupt;
+use embassy_stm32::interrupt::{InterruptExt, Priority};
+use embassy_stm32::rtc::{Rtc, RtcConfig};
 use embassy_stm32::time::Hertz;
+use embassy_stm32::usb::{self, Driver};
 use embassy_stm32::{bind_interrupts, peripherals};
-use embassy_time::Timer;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Ticker, Timer};
+use embassy_usb::class::cdc_acm::CdcAcmClass;
+use embassy_usb::{Builder, UsbDevice};
+use static_cell::StaticCell;
 use {defmt_rtt as _, panic_probe as _};
 
+use embassy_stm32::spi::{self, Spi};
+#[cfg(all(feature = "display", not(feature = "st7735")))]
+use sdr_firmware::drivers::display::Display;
+#[cfg(feature = "display")]
+use sdr_firmware::drivers::encoder::Encoder;
+use sdr_firmware::clock::{Clock, TimeSource};
+use sdr_firmware::drivers::spi_flash::SpiFlash;
+use sdr_firmware::drivers::si5351::{CrystalLoad, Si5351};
+#[cfg(feature = "st7735")]
+use sdr_firmware::drivers::st7735::St7735;
+use sdr_firmware::dsp::modulation::IqSample;
+use sdr_firmware::dsp::pipeline::RxPipeline;
+#[cfg(feature = "display")]
+use sdr_firmware::dsp::spectrum::{IqSpectrum, WaterfallBuffer, WaterfallRow};
+use sdr_dsp_core::IqCalibration;
+use sdr_firmware::hal::adc::{AdcReading, IqAdc};
+use sdr_firmware::hal::bootloader::enter_bootloader;
+use sdr_firmware::hal::audio::{
+    AudioOutput, AudioQueue, IqInput, IqQueue, AUDIO_BLOCK_LEN, AUDIO_QUEUE_LEN, AUDIO_RING_LEN,
+    IQ_BLOCK_PAIRS, IQ_QUEUE_LEN, IQ_RING_LEN,
+};
+use sdr_firmware::drivers::pa::HrtimPa;
+use sdr_firmware::drivers::relay::RelayDriver;
+use sdr_firmware::hal::adc::SwrAdc;
+use sdr_firmware::hal::gpio::{LpfSelector, PttInput, TrRelay};
+use sdr_firmware::hal::i2c::{I2cAddress, I2cBus, SharedI2c};
+use sdr_firmware::hal::rtc::{backup_domain_config, BackupRtc};
+use sdr_firmware::power::thermal::{lm75_temperature, DeratingCurve, PaThermal};
 use sdr_firmware::prelude::*;
+use sdr_firmware::protocol::{CatCommand, CatParser, CatResponse};
+#[cfg(feature = "display")]
+use sdr_firmware::radio::keyer::CwMemory;
+use sdr_firmware::radio::keyer::{Keyer, PaddleState};
+use sdr_firmware::radio::winkeyer::WinKeyer;
+use sdr_firmware::radio::annunciator::Annunciation;
+use sdr_firmware::radio::control::{ControlEffects, DspCommand, RadioController};
+use sdr_firmware::radio::monitor::{BandMonitor, MonitorAction, MonitorCommand};
+use sdr_firmware::radio::state::{RadioEvent, RadioState};
+use sdr_firmware::radio::relay::RelayTiming;
+use sdr_firmware::radio::transmit::TxController;
+use sdr_firmware::radio::tx_policy::TxPolicy;
+#[cfg(feature = "display")]
+use sdr_firmware::radio::vfo::MemoryBank;
+use sdr_firmware::radio::loopback::{LoopbackCheck, LoopbackTest};
+use sdr_firmware::selftest::{PostItem, PostReport, PostResult};
+use sdr_firmware::settings::{OperatorConfig, Settings, StartupState};
+use sdr_firmware::storage::FileStore;
+use sdr_firmware::types::ring::{Consumer, IqRing, Producer};
+use sdr_firmware::usb::audio::{IqPacketizer, UacIqClass, UacSampleRate, UacState};
+#[cfg(feature = "display")]
+use sdr_firmware::ui::redraw::RedrawScheduler;
+#[cfg(feature = "display")]
+use sdr_firmware::ui::waterfall::WATERFALL_ROWS;
+#[cfg(feature = "display")]
+use sdr_firmware::ui::{render_page, render_post_screen, PageContext, Screen, UiState};
+use sdr_firmware::update::{capability, BootloaderGuard, FirmwareVersion, PROTOCOL_VERSION};
+use sdr_firmware::usb::cdc::{CdcState, UsbDeviceInfo, UsbStrings};
+use sdr_firmware::usb::dfu::{DfuRuntime, DfuState};
+use sdr_firmware::usb::winkeyer::WinKeyerPort;
 
 // Bind interrupt handlers
 bind_interrupts!(struct Irqs {
     I2C1_EV => embassy_stm32::i2c::EventInterruptHandler<peripherals::I2C1>;
     I2C1_ER => embassy_stm32::i2c::ErrorInterruptHandler<peripherals::I2C1>;
+    USB_LP => usb::InterruptHandler<peripherals::USB>;
+});
+
+/// USB device driver
+type UsbDriver = Driver<'static, peripherals::USB>;
+
+/// File store on the SPI flash
+type SettingsStore = FileStore<SpiFlash<'static>>;
+
+/// Front panel display the UI draws on
+#[cfg(all(feature = "display", not(feature = "st7735")))]
+type UiPanel = Display<'static>;
+/// Front panel display the UI draws on (`st7735` feature)
+#[cfg(feature = "st7735")]
+type UiPanel = St7735<'static>;
+
+/// Decimated I/Q from the DSP task to the USB audio task
+type UsbIqQueue = IqRing<AUDIO_QUEUE_LEN>;
+
+/// Request for the transmit task
+#[derive(Clone, Copy)]
+enum TxRequest {
+    /// Transmit on or off, from the front panel or CAT
+    Transmit(bool),
+    /// Key line from the `WinKeyer` port's keyer
+    Key(bool),
+    /// Relax the band rules to warnings, or restore them
+    SetOverride(bool),
+    /// PA thermal power limit in percent and hard cutoff
+    Thermal(u8, bool),
+    /// Operator settings (VOX)
+    Config(OperatorConfig),
+}
+
+/// Display kept after the self test
+#[cfg(feature = "display")]
+type PostDisplay = Option<UiPanel>;
+/// Headless builds have no display
+#[cfg(not(feature = "display"))]
+type PostDisplay = ();
+
+/// Executor for the audio I/O task, above the DSP task
+static EXECUTOR_AUDIO: InterruptExecutor = InterruptExecutor::new();
+
+/// Executor for the DSP task, above the thread-mode tasks
+static EXECUTOR_HIGH: InterruptExecutor = InterruptExecutor::new();
+
+/// I2C1, shared by the `Si5351A` and the display
+static I2C1_BUS: StaticCell<SharedI2c<'static>> = StaticCell::new();
+
+/// DMA ring for the I/Q ADC scans
+static IQ_RING: StaticCell<[u16; IQ_RING_LEN]> = StaticCell::new();
+
+/// DMA ring for the audio DAC
+static AUDIO_RING: StaticCell<[u16; AUDIO_RING_LEN]> = StaticCell::new();
+
+/// I/Q samples from the audio I/O task to the DSP task
+static IQ_QUEUE: StaticCell<IqQueue> = StaticCell::new();
+
+/// Audio samples from the DSP task to the audio I/O task
+static AUDIO_QUEUE: StaticCell<AudioQueue> = StaticCell::new();
+
+/// Decimated I/Q from the DSP task to the USB audio task
+static USB_IQ_QUEUE: StaticCell<UsbIqQueue> = StaticCell::new();
+
+/// USB configuration descriptor buffer
+static USB_CONFIG_DESCRIPTOR: StaticCell<[u8; 512]> = StaticCell::new();
+
+/// USB BOS descriptor buffer
+static USB_BOS_DESCRIPTOR: StaticCell<[u8; 256]> = StaticCell::new();
+
+/// USB control transfer buffer
+static USB_CONTROL_BUF: StaticCell<[u8; 64]> = StaticCell::new();
+
+/// CDC ACM state for the CAT port
+static CAT_STATE: StaticCell<CdcState<'static>> = StaticCell::new();
+
+/// `WinKeyer` serial port state
+static WINKEYER_STATE: StaticCell<CdcState<'static>> = StaticCell::new();
+
+/// USB audio class state
+static UAC_STATE: StaticCell<UacState<'static>> = StaticCell::new();
+
+/// DFU runtime interface state
+static DFU_STATE: StaticCell<DfuState<'static>> = StaticCell::new();
+
+/// Front panel and CAT events for the radio control task
+static RADIO_EVENTS: Channel<CriticalSectionRawMutex, RadioEvent, RADIO_EVENT_QUEUE_LEN> =
+    Channel::new();
+
+/// Commands from the radio control task to the DSP task
+static DSP_COMMANDS: Channel<CriticalSectionRawMutex, DspCommand, DSP_COMMAND_QUEUE_LEN> =
+    Channel::new();
+
+/// Band monitor commands from CAT for the radio control task
+static MONITOR_COMMANDS: Channel<
+    CriticalSectionRawMutex,
+    MonitorCommand,
+    MONITOR_COMMAND_QUEUE_LEN,
+> = Channel::new();
+
+/// Requests for the transmit task
+static TX_REQUESTS: Channel<CriticalSectionRawMutex, TxRequest, TX_REQUEST_QUEUE_LEN> =
+    Channel::new();
+
+/// Latest radio state from the radio control task
+static RADIO_STATUS: Signal<CriticalSectionRawMutex, RadioState> = Signal::new();
+
+/// Operator settings changed on the front panel, for radio control
+static CONFIG_UPDATES: Signal<CriticalSectionRawMutex, OperatorConfig> = Signal::new();
+
+/// Operator settings from radio control, for the `WinKeyer` task's keyer
+static KEYER_CONFIG: Signal<CriticalSectionRawMutex, OperatorConfig> = Signal::new();
+
+/// Latest radio state from the radio control task, for the transmit task
+static TX_OPERATING: Signal<CriticalSectionRawMutex, RadioState> = Signal::new();
+
+/// Transmit policy as the transmit task last checked it
+static TX_POLICY: BlockingMutex<CriticalSectionRawMutex, Cell<TxPolicy>> =
+    BlockingMutex::new(Cell::new(TxPolicy::new()));
+
+/// PTT input state, for the front panel's bootloader combination
+static PTT_PRESSED: AtomicBool = AtomicBool::new(false);
+
+/// Latest S-meter reading from the DSP task
+static S_METER: Signal<CriticalSectionRawMutex, u8> = Signal::new();
+
+/// Latest band scope spectrum row from the DSP task
+#[cfg(feature = "display")]
+static SPECTRUM_ROW: Signal<CriticalSectionRawMutex, WaterfallRow> = Signal::new();
+
+/// Latest CW tone offset from the sidetone in Hz, from the DSP task
+static PITCH_OFFSET: Signal<CriticalSectionRawMutex, Option<i16>> = Signal::new();
+
+/// Wall clock, seeded from the RTC and set over CAT
+static CLOCK: BlockingMutex<CriticalSectionRawMutex, Cell<Clock>> =
+    BlockingMutex::new(Cell::new(Clock::new()));
+
+/// Bootloader entry confirmed over CAT or on the front panel
+static BOOTLOADER_REQUEST: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// A block of I/Q samples was queued for the DSP task
+static IQ_READY: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// The host is capturing, so the DSP task feeds `USB_IQ_QUEUE`
+static USB_IQ_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// A block of decimated I/Q was queued for the USB audio task
+static USB_IQ_READY: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+// Stamp log lines with UTC, or with the uptime while the clock is unset
+defmt::timestamp!("{=u64:iso8601ms}", {
+    let now_ms = Instant::now().as_millis();
+    #[allow(clippy::cast_possible_truncation)]
+    CLOCK
+        .lock(|clock| clock.get().unix_ms(now_ms as u32))
+        .unwrap_or(now_ms)
 });
 
+#[interrupt]
+unsafe fn UART4() {
+    EXECUTOR_AUDIO.on_interrupt();
+}
+
+#[interrupt]
+unsafe fn UART5() {
+    EXECUTOR_HIGH.on_interrupt();
+}
+
 /// Main entry point
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
+    // Jump to the ROM DFU bootloader if requested before the last reset
+    sdr_firmware::hal::bootloader::check_request();
+
     info!("SDR Transceiver Firmware v{}", env!("CARGO_PKG_VERSION"));
 
-    // Initialize STM32G474 peripherals with default clock configuration
-    let config = embassy_stm32::Config::default();
+    // Initialize STM32G474 peripherals with default clock configuration,
+    // plus the 48 MHz USB clock from HSI48 trimmed by the USB SOF and the
+    // RTC on the LSE crystal
+    let mut config = embassy_stm32::Config::default();
+    config.rcc.hsi48 = Some(embassy_stm32::rcc::Hsi48Config { sync_from_usb: true });
+    config.rcc.mux.clk48sel = embassy_stm32::rcc::mux::Clk48sel::HSI48;
+    config.rcc.ls = backup_domain_config();
     let p = embassy_stm32::init(config);
 
     info!("Peripherals initialized");
 
+    // Seed the wall clock from the battery-backed RTC
+    let rtc = BackupRtc::new(Rtc::new(p.RTC, RtcConfig::default()));
+    match rtc.read() {
+        Some(now) => {
+            set_clock(|clock| clock.set(&now, uptime_ms(), TimeSource::Rtc));
+            info!("Clock: {} from RTC", now);
+        }
+        None => warn!("Clock: RTC not set"),
+    }
+
     // Initialize status LED (typically on PA5 for Nucleo boards)
     let led = Output::new(p.PA5, Level::Low, Speed::Low);
 
     // Initialize I2C1 for Si5351A and other peripherals
     // PB8 = SCL, PB9 = SDA for I2C1 on STM32G474
-    let _i2c = I2c::new(
+    let i2c = I2c::new(
         p.I2C1,
         p.PB8, // SCL
         p.PB9, // SDA
@@ -50,13 +373,156 @@ async fn main(spawner: Spawner) {
         Default::default(),
     );
 
+    let i2c_bus = I2C1_BUS.init(Mutex::new(i2c));
+
     info!("I2C1 initialized at 400kHz");
 
+    // Settings flash, read before any task touches the radio hardware
+    // SPI3: PC10 = SCK, PC11 = MISO, PC12 = MOSI, PA15 = CS
+    let mut flash_config = spi::Config::default();
+    flash_config.frequency = Hertz(FLASH_SPI_FREQUENCY_HZ);
+    let mut flash = SpiFlash::new(
+        Spi::new_blocking(p.SPI3, p.PC10, p.PC12, p.PC11, flash_config),
+        Output::new(p.PA15, Level::High, Speed::VeryHigh),
+    );
+    info!("SPI flash: {}", flash.init());
+    let (settings, store) = load_settings(flash);
+    let radio = settings.startup_state().radio_state();
+    info!("Startup: {} at {} Hz", settings.policy(), radio.frequency().as_hz());
+
+    // Power-on self test, before anything can key the PA
+    let mut iq_adc = IqAdc::new(p.ADC2);
+    iq_adc.configure();
+    let mut i_pin = p.PA6.degrade_adc();
+    let mut q_pin = p.PA7.degrade_adc();
+    // Front panel display: the OLED shares I2C1; the ST7735 LCD is on
+    // SPI1 with PB3 = SCK, PB5 = MOSI, PC6 = D/C, PC7 = CS, PC8 = reset
+    #[cfg(all(feature = "display", not(feature = "st7735")))]
+    let panel = Display::with_bus(I2cBus::shared(i2c_bus));
+    #[cfg(feature = "st7735")]
+    let panel = {
+        let mut spi_config = spi::Config::default();
+        spi_config.frequency = Hertz(LCD_SPI_FREQUENCY_HZ);
+        St7735::new(
+            Spi::new_txonly(p.SPI1, p.PB3, p.PB5, p.DMA1_CH6, spi_config),
+            Output::new(p.PC6, Level::High, Speed::VeryHigh),
+            Output::new(p.PC7, Level::High, Speed::VeryHigh),
+            Output::new(p.PC8, Level::High, Speed::Low),
+        )
+    };
+
+    #[cfg(feature = "display")]
+    let post = power_on_self_test(
+        i2c_bus,
+        &mut iq_adc,
+        &radio,
+        &mut i_pin,
+        &mut q_pin,
+        p.PC4,
+        panel,
+    );
+    #[cfg(not(feature = "display"))]
+    let post = power_on_self_test(i2c_bus, &mut iq_adc, &radio, &mut i_pin, &mut q_pin, p.PC4);
+    let (report, mut synth, display) = post.await;
+    if !report.tx_allowed() {
+        error!("POST: transmit inhibited");
+    }
+    synth.set_correction(settings.xtal_correction());
+
+    // Streamed audio I/O: I/Q in on ADC2 (PA6, PA7), audio out on
+    // DAC1 OUT1 (PA4)
+    let input = IqInput::new(
+        iq_adc,
+        p.DMA1_CH5,
+        i_pin,
+        q_pin,
+        p.TIM6,
+        IQ_RING.init([0; IQ_RING_LEN]),
+    );
+    let output = AudioOutput::new(
+        p.DAC1,
+        p.DMA1_CH4,
+        p.PA4,
+        p.TIM7,
+        AUDIO_RING.init([0; AUDIO_RING_LEN]),
+    );
+
+    let (iq_producer, iq_consumer) = IQ_QUEUE.init(IqQueue::new()).split();
+    let (audio_producer, audio_consumer) = AUDIO_QUEUE.init(AudioQueue::new()).split();
+    let (usb_iq_producer, usb_iq_consumer) = USB_IQ_QUEUE.init(UsbIqQueue::new()).split();
+
+    // DSP on the interrupt executor, audio I/O on one above it
+    interrupt::UART5.set_priority(Priority::P6);
+    let high_spawner = EXECUTOR_HIGH.start(interrupt::UART5);
+    high_spawner
+        .spawn(dsp_processing_task(
+            iq_consumer,
+            audio_producer,
+            usb_iq_producer,
+            settings.iq_orientation(),
+            settings.iq_calibrations(),
+        ))
+        .unwrap();
+    interrupt::UART4.set_priority(Priority::P5);
+    let audio_spawner = EXECUTOR_AUDIO.start(interrupt::UART4);
+    audio_spawner
+        .spawn(audio_io_task(input, output, iq_producer, audio_consumer))
+        .unwrap();
+
+    // Transmit path: PTT on PA3, T/R relay on PB0, LPF select on
+    // PC0-PC2, the PA on HRTIM1 and the SWR bridge on ADC1 (PB1, PB11)
+    let ptt = PttInput::new(Input::new(p.PA3, Pull::Up));
+    let relays = RelayDriver::new_gpio(
+        TrRelay::new(Output::new(p.PB0, Level::Low, Speed::Low)),
+        LpfSelector::new(
+            Output::new(p.PC0, Level::Low, Speed::Low),
+            Output::new(p.PC1, Level::Low, Speed::Low),
+            Output::new(p.PC2, Level::Low, Speed::Low),
+        ),
+        None,
+        RelayTiming::DEFAULT,
+    );
+    let pa = HrtimPa::new(p.HRTIM1, p.PA8, p.PB12, p.PB13, p.PB14, p.PB15);
+    let swr = SwrAdc::new(p.ADC1, p.PB1.degrade_adc(), p.PB11.degrade_adc());
+
+    // USB: CAT and WinKeyer serial ports, I/Q audio and the DFU runtime
+    // interface
+    let (usb, cat, winkeyer, uac, dfu) = usb_device(Driver::new(p.USB, Irqs, p.PA12, p.PA11));
+
     // Spawn background tasks
-    spawner.spawn(heartbeat_task(led)).unwrap();
-    // spawner.spawn(radio_control_task()).unwrap();
-    // spawner.spawn(dsp_processing_task()).unwrap();
-    // spawner.spawn(ui_task()).unwrap();
+    spawner.spawn(heartbeat_task(led, report.tx_allowed())).unwrap();
+    spawner.spawn(usb_task(usb)).unwrap();
+    spawner.spawn(cat_task(cat, rtc)).unwrap();
+    spawner.spawn(winkeyer_task(winkeyer)).unwrap();
+    spawner.spawn(usb_audio_task(uac, usb_iq_consumer)).unwrap();
+    spawner.spawn(update_task(dfu)).unwrap();
+    spawner
+        .spawn(tx_task(ptt, relays, pa, swr, radio, report.tx_allowed()))
+        .unwrap();
+    spawner.spawn(pa_thermal_task(i2c_bus)).unwrap();
+    let control = RadioController::new(radio, report.tx_allowed());
+    #[cfg(feature = "display")]
+    let config = *settings.config();
+    spawner
+        .spawn(radio_control_task(synth, control, settings, store))
+        .unwrap();
+    #[cfg(feature = "display")]
+    if let Some(display) = display {
+        let encoder = Encoder::new(
+            Input::new(p.PA0, Pull::Up),
+            Input::new(p.PA1, Pull::Up),
+            Input::new(p.PA2, Pull::Up),
+        );
+        spawner.spawn(ui_task(display, encoder, radio, config)).unwrap();
+    }
+    // Headless builds have no POST screen, so announce the result
+    #[cfg(not(feature = "display"))]
+    {
+        let () = display;
+        DSP_COMMANDS
+            .send(DspCommand::Announce(Annunciation::from_post(&report)))
+            .await;
+    }
 
     info!("Tasks spawned, entering main loop");
 
@@ -67,13 +533,896 @@ async fn main(spawner: Spawner) {
     }
 }
 
+/// Mount the file store and load persistent settings
+///
+/// A missing or corrupt settings record, or an unformatted flash, falls
+/// back to factory defaults. The flash is never formatted here.
+fn load_settings(
+    flash: SpiFlash<'static>,
+) -> (Settings, Option<SettingsStore>) {
+    match FileStore::mount(flash) {
+        Ok(mut store) => {
+            let settings = Settings::load(&mut store).unwrap_or_else(|| {
+                warn!("No saved settings, using defaults");
+                Settings::new()
+            });
+            (settings, Some(store))
+        }
+        Err(e) => {
+            error!("File store unavailable: {}", e);
+            (Settings::new(), None)
+        }
+    }
+}
+
+/// Run the power-on self test
+///
+/// Probes the I2C devices, brings up the synthesizer at the startup
+/// frequency and checks PLL lock, measures idle ADC offsets and PA current,
+/// runs the numerical RX/TX loopback test, then shows the
+/// report on the display. Returns the synthesizer and the display (if it
+/// came up) for the tasks that own them. The ST7735 is on SPI and cannot
+/// be probed, so its init is the whole check.
+/// Headless builds skip the display and leave its result as not run; the
+/// caller announces the result on the sidetone instead.
+async fn power_on_self_test(
+    bus: &'static SharedI2c<'static>,
+    adc: &mut IqAdc<'_>,
+    radio: &RadioState,
+    i_pin: &mut AnyAdcChannel<peripherals::ADC2>,
+    q_pin: &mut AnyAdcChannel<peripherals::ADC2>,
+    mut isense_pin: impl AdcChannel<peripherals::ADC2>,
+    #[cfg(feature = "display")] panel: UiPanel,
+) -> (PostReport, Si5351<'static>, PostDisplay) {
+    let mut report = PostReport::new();
+
+    // I2C devices
+    let mut probe = I2cBus::shared(bus);
+    let si5351_present = probe.probe(I2cAddress::SI5351).await;
+    report.record_probe(PostItem::Si5351, si5351_present);
+    #[cfg(all(feature = "display", not(feature = "st7735")))]
+    let display_present = probe.probe(I2cAddress::SSD1306).await;
+    #[cfg(feature = "st7735")]
+    let display_present = true;
+    #[cfg(feature = "display")]
+    report.record_probe(PostItem::Display, display_present);
+
+    // Synthesizer lock at the startup frequency
+    let mut synth = Si5351::with_bus(I2cBus::shared(bus));
+    if si5351_present {
+        let locked = synth.init(CrystalLoad::default()).await.is_ok()
+            && synth.set_quadrature(radio.frequency()).await.is_ok();
+        Timer::after(Duration::from_millis(10)).await;
+        match synth.status().await {
+            Ok(status) if locked => report.record_pll_status(status),
+            _ => report.record(PostItem::PllLock, PostResult::Fail, 0),
+        }
+    }
+
+    // Idle receive path and PA (drive is off until the PWM is configured)
+    report.record_adc_offset(PostItem::AdcI, adc_mean(adc, i_pin));
+    report.record_adc_offset(PostItem::AdcQ, adc_mean(adc, q_pin));
+    let isense = AdcReading::from_raw(adc_mean(adc, &mut isense_pin));
+    report.record_pa_current((isense.as_voltage() * PA_CURRENT_SENSE_MA_PER_V) as u16);
+
+    // Signal path, with the TX modulator looped numerically into the receiver
+    let loopback = LoopbackTest::new().run_to_completion();
+    for check in LoopbackCheck::ALL {
+        let result = loopback.result(check);
+        if result == PostResult::Fail {
+            error!("Loopback {}: {} ({} dB)", check, result, loopback.value_db(check));
+        } else {
+            info!("Loopback {}: {} ({} dB)", check, result, loopback.value_db(check));
+        }
+    }
+
+    for (item, result) in report.iter() {
+        match result {
+            PostResult::Fail => error!("POST {}: {} ({})", item, result, report.value(item)),
+            PostResult::NotRun => warn!("POST {}: {}", item, result),
+            PostResult::Pass => info!("POST {}: {} ({})", item, result, report.value(item)),
+        }
+    }
+
+    // Show the report; a display that fails to init is itself a POST failure
+    #[cfg(feature = "display")]
+    let display = if display_present {
+        let mut display = panel;
+        if display.init().await.is_ok() {
+            render_post_screen(display.buffer_mut(), &report);
+            if display.flush().await.is_err() {
+                report.record(PostItem::Display, PostResult::Fail, 0);
+            }
+            Some(display)
+        } else {
+            report.record(PostItem::Display, PostResult::Fail, 0);
+            None
+        }
+    } else {
+        None
+    };
+    #[cfg(not(feature = "display"))]
+    let display = ();
+
+    info!("{}", report);
+    (report, synth, display)
+}
+
+/// Average idle ADC readings on one channel
+fn adc_mean(adc: &mut IqAdc<'_>, channel: &mut impl AdcChannel<peripherals::ADC2>) -> u16 {
+    let mut sum = 0u32;
+    for _ in 0..POST_ADC_SAMPLES {
+        sum += u32::from(adc.read(channel).raw());
+    }
+    (sum / POST_ADC_SAMPLES) as u16
+}
+
+/// Build the USB device: CDC ACM for CAT, USB audio for I/Q and the DFU
+/// runtime interface
+///
+/// The audio function is capture only; see [`UacIqClass::capture_only`].
+fn usb_device(
+    driver: UsbDriver,
+) -> (
+    UsbDevice<'static, UsbDriver>,
+    CdcAcmClass<'static, UsbDriver>,
+    WinKeyerPort<'static, UsbDriver>,
+    UacIqClass<'static, UsbDriver>,
+    DfuRuntime<'static>,
+) {
+    let info = UsbDeviceInfo::default();
+    let strings = UsbStrings::default();
+    let mut config = embassy_usb::Config::new(info.vid, info.pid);
+    config.device_release = info.device_release;
+    config.manufacturer = Some(strings.manufacturer);
+    config.product = Some(strings.product);
+    config.serial_number = Some(strings.serial);
+    config.max_power = 100;
+    config.max_packet_size_0 = 64;
+    // Composite device, so hosts bind CDC ACM, audio and DFU side by side
+    config.device_class = 0xEF;
+    config.device_sub_class = 0x02;
+    config.device_protocol = 0x01;
+    config.composite_with_iads = true;
+
+    let mut builder = Builder::new(
+        driver,
+        config,
+        USB_CONFIG_DESCRIPTOR.init([0; 512]),
+        USB_BOS_DESCRIPTOR.init([0; 256]),
+        &mut [],
+        USB_CONTROL_BUF.init([0; 64]),
+    );
+    let cat = CdcAcmClass::new(
+        &mut builder,
+        CAT_STATE.init(CdcState::new()).state_mut(),
+        USB_CDC_PACKET_SIZE,
+    );
+    let winkeyer = WinKeyerPort::new(&mut builder, WINKEYER_STATE.init(CdcState::new()));
+    // The two serial ports leave endpoints for the capture stream only
+    let uac = UacIqClass::capture_only(&mut builder, UAC_STATE.init(UacState::new()));
+    let dfu = DfuRuntime::new(&mut builder, DFU_STATE.init(DfuState::new()));
+    (builder.build(), cat, winkeyer, uac, dfu)
+}
+
+/// USB task - runs the USB device stack
+#[embassy_executor::task]
+async fn usb_task(mut device: UsbDevice<'static, UsbDriver>) {
+    device.run().await;
+}
+
+/// Milliseconds since boot, as the clock and CAT count them
+#[allow(clippy::cast_possible_truncation)]
+fn uptime_ms() -> u32 {
+    Instant::now().as_millis() as u32
+}
+
+/// CAT task - CAT commands over the USB serial port
+///
+/// Forwards radio commands to the radio control task as events and
+/// answers the version handshake and the two-step bootloader request.
+/// Owns the RTC: setting the clock over CAT also writes it back.
+#[embassy_executor::task]
+async fn cat_task(mut class: CdcAcmClass<'static, UsbDriver>, mut rtc: BackupRtc) {
+    let mut parser = CatParser::new();
+    let mut guard = BootloaderGuard::new();
+    let mut packet = [0u8; USB_CDC_PACKET_SIZE as usize];
+
+    loop {
+        class.wait_connection().await;
+        info!("CAT: connected");
+        while let Ok(len) = class.read_packet(&mut packet).await {
+            let now_ms = uptime_ms();
+            for &byte in &packet[..len] {
+                let Some(command) = parser.feed(byte) else {
+                    continue;
+                };
+                let mut response = CatResponse::new();
+                if !guard.handle_cat(&command, now_ms, &mut response) {
+                    match (&command, command.to_radio_event()) {
+                        (CatCommand::ReadVersion, _) => response.version(
+                            FirmwareVersion::CURRENT,
+                            PROTOCOL_VERSION,
+                            capability::CURRENT,
+                        ),
+                        (CatCommand::ReadClock, _) => {
+                            match CLOCK.lock(|clock| clock.get().utc(now_ms)) {
+                                Some(time) => response.clock(&time),
+                                None => response.error(),
+                            }
+                        }
+                        (CatCommand::SetClock(time), _) => {
+                            set_clock(|clock| clock.set(time, now_ms, TimeSource::Cat));
+                            if let Err(e) = rtc.write(time) {
+                                warn!("CAT: RTC write failed: {}", defmt::Debug2Format(&e));
+                            }
+                        }
+                        (CatCommand::ReadUtcOffset, _) => {
+                            response.utc_offset(CLOCK.lock(|clock| clock.get().utc_offset()));
+                        }
+                        (CatCommand::SetUtcOffset(minutes), _) => {
+                            set_clock(|clock| clock.set_utc_offset(*minutes));
+                        }
+                        (CatCommand::Monitor(command), _) => {
+                            if MONITOR_COMMANDS.try_send(command.clone()).is_err() {
+                                warn!("CAT: monitor queue full");
+                            }
+                        }
+                        (CatCommand::ReadTxPolicy, _) => {
+                            response.tx_policy(&TX_POLICY.lock(Cell::get));
+                        }
+                        (CatCommand::SetTxOverride(on), _) => {
+                            if TX_REQUESTS.try_send(TxRequest::SetOverride(*on)).is_err() {
+                                warn!("CAT: transmit request queue full");
+                            }
+                        }
+                        (_, Some(event)) if !send_radio_event(event) => {
+                            warn!("CAT: radio event queue full");
+                        }
+                        (_, Some(_)) => {}
+                        (_, None) => response.error(),
+                    }
+                }
+                if !response.as_str().is_empty()
+                    && class.write_packet(response.as_bytes()).await.is_err()
+                {
+                    break;
+                }
+                if guard.is_requested() {
+                    BOOTLOADER_REQUEST.signal(());
+                }
+            }
+        }
+        info!("CAT: disconnected");
+    }
+}
+
+/// `WinKeyer` task - CW from a contest logger on the second serial port
+///
+/// Applies the logger's commands to a keyer clocked once a millisecond
+/// and passes its key line to the transmit task. Reads give up after a
+/// tick so the keyer keeps time while the logger is quiet. Queued text
+/// is dropped and the key released when the port closes.
+#[embassy_executor::task]
+async fn winkeyer_task(mut port: WinKeyerPort<'static, UsbDriver>) {
+    let mut winkeyer = WinKeyer::new();
+    let mut keyer = Keyer::new(WINKEYER_TICK_HZ);
+    let tick = Duration::from_hz(u64::from(WINKEYER_TICK_HZ));
+
+    loop {
+        port.wait_connection().await;
+        info!("WinKeyer: connected");
+        let mut key = false;
+        let mut last = Instant::now();
+        loop {
+            if let Some(config) = KEYER_CONFIG.try_take() {
+                config.apply_keyer(&mut keyer);
+            }
+            let serviced = with_timeout(tick, port.service(&mut winkeyer, &mut keyer)).await;
+            if let Ok(Err(_)) = serviced {
+                break;
+            }
+            while last + tick <= Instant::now() {
+                last += tick;
+                keyer.process(winkeyer.paddles(PaddleState::default()));
+            }
+            if keyer.is_key_down() != key {
+                key = keyer.is_key_down();
+                TX_REQUESTS.send(TxRequest::Key(key)).await;
+            }
+            if port.send_unsolicited(&mut winkeyer, &mut keyer).await.is_err() {
+                break;
+            }
+        }
+        keyer.clear_text();
+        if key {
+            TX_REQUESTS.send(TxRequest::Key(false)).await;
+        }
+        info!("WinKeyer: disconnected");
+    }
+}
+
+/// Queue a front panel or CAT event
+///
+/// Transmit on and off go to the transmit task, everything else to radio
+/// control. Returns `false` if the queue was full.
+fn send_radio_event(event: RadioEvent) -> bool {
+    match event {
+        RadioEvent::StartTx => TX_REQUESTS.try_send(TxRequest::Transmit(true)).is_ok(),
+        RadioEvent::StopTx => TX_REQUESTS.try_send(TxRequest::Transmit(false)).is_ok(),
+        event => RADIO_EVENTS.try_send(event).is_ok(),
+    }
+}
+
+/// USB audio task - streams the receive I/Q to the host
+///
+/// Sends the DSP task's decimated I/Q as stereo, a packet per
+/// millisecond, while the host has the capture interface open. The I/Q
+/// is at 48 kHz, so a host that picks 96 kHz gets each sample twice.
+#[embassy_executor::task]
+async fn usb_audio_task(
+    mut uac: UacIqClass<'static, UsbDriver>,
+    mut iq: Consumer<'static, IqSample, AUDIO_QUEUE_LEN>,
+) {
+    let mut packetizer = IqPacketizer::new(uac.sample_rate());
+
+    loop {
+        uac.wait_capture_enabled().await;
+        info!("USB audio: capturing at {}", uac.sample_rate());
+        iq.clear();
+        USB_IQ_ENABLED.store(true, Ordering::Relaxed);
+        while uac.capture_active() {
+            if let Some(rate) = uac.take_rate_change() {
+                info!("USB audio: rate {}", rate);
+                packetizer.set_rate(rate);
+            }
+            let repeat = if uac.sample_rate() == UacSampleRate::Khz96 { 2 } else { 1 };
+            while !packetizer.is_ready() {
+                match iq.pop() {
+                    Some(sample) => {
+                        for _ in 0..repeat {
+                            packetizer.push_iq(sample);
+                        }
+                    }
+                    None => USB_IQ_READY.wait().await,
+                }
+            }
+            let Some(packet) = packetizer.next_packet() else {
+                continue;
+            };
+            if uac.write_iq_packet(packet).await.is_err() {
+                break;
+            }
+        }
+        USB_IQ_ENABLED.store(false, Ordering::Relaxed);
+        info!("USB audio: capture stopped");
+    }
+}
+
+/// Update the shared wall clock
+fn set_clock(update: impl FnOnce(&mut Clock)) {
+    CLOCK.lock(|clock| {
+        let mut c = clock.get();
+        update(&mut c);
+        clock.set(c);
+    });
+}
+
+/// Update task - reboots into the ROM bootloader when asked
+///
+/// Watches the DFU detach flag and the CAT and front panel requests. The
+/// short delay lets the acknowledgement (or the DFU_DETACH status stage)
+/// reach the host before the USB device disappears.
+#[embassy_executor::task]
+async fn update_task(dfu: DfuRuntime<'static>) {
+    let mut ticker = Ticker::every(Duration::from_millis(100));
+    while !dfu.detach_requested() && !BOOTLOADER_REQUEST.signaled() {
+        ticker.next().await;
+    }
+    warn!("Entering the ROM bootloader");
+    Timer::after(Duration::from_millis(50)).await;
+    enter_bootloader();
+}
+
 /// Heartbeat task - blinks LED to show system is running
+///
+/// Blinks once a second, or five times a second when the self test
+/// inhibited transmit, so a headless radio shows the fault too.
 #[embassy_executor::task]
-async fn heartbeat_task(mut led: Output<'static>) {
+async fn heartbeat_task(mut led: Output<'static>, tx_allowed: bool) {
+    let off_ms = if tx_allowed { 900 } else { 100 };
     loop {
         led.set_high();
         Timer::after(Duration::from_millis(100)).await;
         led.set_low();
-        Timer::after(Duration::from_millis(900)).await;
+        Timer::after(Duration::from_millis(off_ms)).await;
+    }
+}
+
+/// Transmit task - keys the transmitter through the [`TxController`]
+///
+/// Combines the PTT input with transmit requests from the front panel
+/// and CAT and the `WinKeyer` key line, and runs the controller once per SWR bridge sample. Each
+/// request to transmit is checked against the [`TxPolicy`] at the
+/// operating point radio control publishes; a denied one is logged with
+/// its reason and announced. Controller actions go through the relay
+/// sequencer to the PA, and a relay fault or a failed self test
+/// inhibits transmit. Radio control is told when an over starts and
+/// ends, so it retunes and switches the DSP.
+#[embassy_executor::task]
+async fn tx_task(
+    ptt: PttInput<'static>,
+    mut relays: RelayDriver<'static>,
+    mut pa: HrtimPa<'static>,
+    mut swr: SwrAdc<'static>,
+    state: RadioState,
+    tx_allowed: bool,
+) {
+    const TICK_US: u32 = SWR_SAMPLE_INTERVAL_MS as u32 * 1000;
+
+    let mut tx = TxController::new();
+    let mut requested = false;
+    let mut key = false;
+    let mut wanted = false;
+    let mut on_air = false;
+    let mut second_us = 0u32;
+    set_operating(&mut tx, &mut relays, &mut pa, &state);
+    let mut ticker = Ticker::every(Duration::from_millis(SWR_SAMPLE_INTERVAL_MS));
+
+    loop {
+        ticker.next().await;
+
+        while let Ok(request) = TX_REQUESTS.try_receive() {
+            match request {
+                TxRequest::Transmit(on) => requested = on,
+                TxRequest::Key(down) => key = down,
+                TxRequest::SetOverride(on) => tx.policy_mut().set_override(on),
+                TxRequest::Thermal(limit, cutoff) => tx.update_thermal(limit, cutoff),
+                TxRequest::Config(config) => {
+                    config.apply_vox(tx.vox_control_mut(), AUDIO_SAMPLE_RATE);
+                }
+            }
+        }
+        if let Some(state) = TX_OPERATING.try_take() {
+            set_operating(&mut tx, &mut relays, &mut pa, &state);
+        }
+
+        let pressed = ptt.is_pressed();
+        PTT_PRESSED.store(pressed, Ordering::Relaxed);
+        let want = pressed || requested || key;
+        if want && !wanted {
+            let verdict = tx.verdict();
+            if verdict.is_denied() {
+                warn!("TX: denied, {}", verdict);
+                let _ = DSP_COMMANDS.try_send(DspCommand::Announce(Annunciation::Error));
+            }
+        }
+        wanted = want;
+        tx.set_ptt(pressed || requested);
+        tx.set_key(key);
+        tx.set_inhibit(!tx_allowed || relays.fault().is_some());
+
+        let action = tx.update(TICK_US);
+        pa.apply(relays.apply(action));
+        pa.apply(relays.update(TICK_US));
+
+        if tx.is_transmitting() {
+            if let Some(reading) = swr.sample() {
+                tx.update_swr(reading);
+            }
+            second_us += TICK_US;
+            if second_us >= 1_000_000 {
+                second_us = 0;
+                tx.tick_timeout();
+            }
+        } else {
+            second_us = 0;
+        }
+
+        let keyed = tx.txrx() != TxRxState::Rx;
+        if keyed != on_air {
+            on_air = keyed;
+            if keyed {
+                swr.reset();
+            }
+            let event = if keyed { RadioEvent::StartTx } else { RadioEvent::StopTx };
+            RADIO_EVENTS.send(event).await;
+        }
+        TX_POLICY.lock(|policy| policy.set(*tx.policy()));
+    }
+}
+
+/// Point the transmit path at a new operating state
+///
+/// Sets the policy's operating point, the requested power, the filter
+/// for the band and the PA carrier. CW runs full break-in.
+fn set_operating(
+    tx: &mut TxController,
+    relays: &mut RelayDriver<'_>,
+    pa: &mut HrtimPa<'_>,
+    state: &RadioState,
+) {
+    tx.policy_mut().set_operating(state.tx_frequency(), state.mode());
+    tx.set_power(state.power());
+    tx.set_qsk(matches!(state.mode(), Mode::Cw | Mode::CwR));
+    if let Some(band) = state.band() {
+        relays.select_band(band);
+    }
+    if !pa.set_carrier(state.tx_frequency()) {
+        warn!("TX: {} Hz out of PA range", state.tx_frequency().as_hz());
+    }
+}
+
+/// PA thermal task - reads the heatsink sensor and derates the PA
+///
+/// Reads the LM75 on I2C1 once a second and passes the derated power
+/// limit and the hard cutoff to the transmit task. A missing or failed
+/// sensor holds the PA at the curve's lowest limit.
+#[embassy_executor::task]
+async fn pa_thermal_task(bus: &'static SharedI2c<'static>) {
+    let mut sensor = I2cBus::shared(bus);
+    let mut thermal = PaThermal::new(DeratingCurve::DEFAULT);
+    let mut ticker = Ticker::every(Duration::from_millis(PA_THERMAL_POLL_MS));
+    let mut last = None;
+
+    loop {
+        let mut register = [0u8; 2];
+        match sensor.read_regs(I2cAddress::LM75, 0, &mut register).await {
+            Ok(()) => thermal.update(lm75_temperature(register)),
+            Err(_) => thermal.sensor_fault(),
+        }
+        let limits = (thermal.limit_percent(), thermal.is_cutoff());
+        if last != Some(limits) {
+            last = Some(limits);
+            match thermal.temperature() {
+                Some(temp) => info!("PA: {}, limit {}%", temp, limits.0),
+                None => warn!("PA: no temperature sensor, limit {}%", limits.0),
+            }
+            TX_REQUESTS.send(TxRequest::Thermal(limits.0, limits.1)).await;
+        }
+        ticker.next().await;
     }
 }
+
+/// Radio control task - applies radio events to the state and hardware
+///
+/// Owns the synthesizer, the radio state and the settings; the UI and
+/// DSP only see the state through `RADIO_STATUS` and `DSP_COMMANDS`.
+/// Also runs the band monitor schedule, whose retunes go through the same
+/// state machine as any other event.
+///
+/// Operator settings from the front panel are applied at once (AGC here,
+/// the keyer and VOX in their tasks) and saved on the next schedule tick,
+/// so a run of encoder steps is one flash write.
+#[embassy_executor::task]
+async fn radio_control_task(
+    mut synth: Si5351<'static>,
+    mut control: RadioController,
+    mut settings: Settings,
+    mut store: Option<SettingsStore>,
+) {
+    let mut monitor = BandMonitor::default();
+    let mut ticker = Ticker::every(Duration::from_millis(u64::from(MONITOR_TICK_MS)));
+    let mut config_changed = false;
+    apply_effects(&mut synth, &control, control.startup()).await;
+    apply_config(settings.config()).await;
+    loop {
+        let action = match select4(
+            RADIO_EVENTS.receive(),
+            MONITOR_COMMANDS.receive(),
+            CONFIG_UPDATES.wait(),
+            ticker.next(),
+        )
+        .await
+        {
+            Either4::First(event) => {
+                let effects = control.handle(event);
+                let save = effects.save;
+                apply_effects(&mut synth, &control, effects).await;
+                if save {
+                    let result = save_settings(&mut settings, store.as_mut(), control.state());
+                    DSP_COMMANDS.send(DspCommand::Announce(result)).await;
+                }
+                continue;
+            }
+            Either4::Second(command) => monitor.handle(command),
+            Either4::Third(config) => {
+                *settings.config_mut() = config;
+                let effects = control.handle(RadioEvent::SetAgc(config.agc));
+                apply_effects(&mut synth, &control, effects).await;
+                apply_config(&config).await;
+                config_changed = true;
+                continue;
+            }
+            Either4::Fourth(()) => {
+                if config_changed {
+                    config_changed = false;
+                    write_settings(&settings, store.as_mut());
+                }
+                monitor.update(MONITOR_TICK_MS)
+            }
+        };
+        if let MonitorAction::SlotComplete { band, .. } = action {
+            info!("Monitor: {} slot done, {} spots", band, monitor.spot_count(band));
+        }
+        for event in action.radio_events() {
+            let effects = control.handle(event);
+            apply_effects(&mut synth, &control, effects).await;
+        }
+    }
+}
+
+/// Retune, command the DSP and publish the state after an event
+async fn apply_effects(
+    synth: &mut Si5351<'_>,
+    control: &RadioController,
+    effects: ControlEffects,
+) {
+    if let Some(freq) = effects.retune {
+        if let Err(e) = synth.tune_quadrature(freq).await {
+            error!("Retune to {} Hz failed: {}", freq.as_hz(), e);
+        }
+    }
+    for command in effects.dsp {
+        DSP_COMMANDS.send(command).await;
+    }
+    if let Some(event) = effects.announce {
+        DSP_COMMANDS.send(DspCommand::Announce(event)).await;
+    }
+    if effects.state_changed {
+        RADIO_STATUS.signal(*control.state());
+        TX_OPERATING.signal(*control.state());
+    }
+}
+
+/// Hand operator settings to the tasks that apply them
+async fn apply_config(config: &OperatorConfig) {
+    KEYER_CONFIG.signal(*config);
+    TX_REQUESTS.send(TxRequest::Config(*config)).await;
+}
+
+/// Store the state as the fixed startup state and save the settings
+///
+/// Returns what to announce: saved, or an error if there is no file
+/// store or the write failed.
+fn save_settings(
+    settings: &mut Settings,
+    store: Option<&mut SettingsStore>,
+    state: &RadioState,
+) -> Annunciation {
+    settings.set_fixed(StartupState::from_radio(state));
+    write_settings(settings, store)
+}
+
+/// Save the settings, returning what to announce
+fn write_settings(settings: &Settings, store: Option<&mut SettingsStore>) -> Annunciation {
+    match store.map(|store| settings.save(store)) {
+        Some(Ok(())) => {
+            info!("Settings saved");
+            Annunciation::Saved
+        }
+        Some(Err(e)) => {
+            error!("Settings save failed: {}", e);
+            Annunciation::Error
+        }
+        None => {
+            warn!("Settings not saved: no file store");
+            Annunciation::Error
+        }
+    }
+}
+
+/// Audio I/O task - DMA rings to and from the DSP task's queues
+///
+/// Queues each I/Q block for the DSP task and plays one audio block in
+/// its place, so both converters keep to the timers even if the DSP task
+/// is late. Reports DMA restarts, I/Q samples the DSP task left to be
+/// dropped and audio blocks it did not deliver in time.
+#[embassy_executor::task]
+async fn audio_io_task(
+    mut input: IqInput<'static>,
+    mut output: AudioOutput<'static>,
+    mut iq: Producer<'static, IqSample, IQ_QUEUE_LEN>,
+    mut audio: Consumer<'static, f32, AUDIO_QUEUE_LEN>,
+) {
+    let mut counts = (0, 0, 0, 0);
+
+    loop {
+        input.fill(&mut iq).await;
+        IQ_READY.signal(());
+        output.drain(&mut audio).await;
+
+        let now = (input.overruns(), iq.dropped(), audio.underruns(), output.underruns());
+        if now != counts {
+            counts = now;
+            warn!(
+                "Audio: {} input overruns, {} I/Q dropped, {} audio underruns, {} output underruns",
+                now.0, now.1, now.2, now.3
+            );
+        }
+    }
+}
+
+/// DSP processing task - I/Q queue to receive pipeline to audio queue
+///
+/// Wakes for each block the audio I/O task queues. The audio queue starts
+/// one block of silence ahead, so the first block played does not count
+/// as an underrun. While the host is capturing over USB audio, the
+/// decimated I/Q behind each audio block is queued for it as well. With
+/// a display, the same I/Q feeds the band scope, a spectrum row every
+/// `SPECTRUM_ROW_MS`.
+#[embassy_executor::task]
+async fn dsp_processing_task(
+    mut iq: Consumer<'static, IqSample, IQ_QUEUE_LEN>,
+    mut audio_out: Producer<'static, f32, AUDIO_QUEUE_LEN>,
+    mut usb_iq: Producer<'static, IqSample, AUDIO_QUEUE_LEN>,
+    orientation: IqOrientation,
+    iq_calibrations: [IqCalibration; Band::COUNT],
+) {
+    let mut pipeline = RxPipeline::default();
+    // Annunciations stand in for the display on headless builds
+    pipeline.annunciator_mut().set_enabled(cfg!(not(feature = "display")));
+    pipeline.set_orientation(orientation);
+    pipeline.set_iq_calibrations(iq_calibrations);
+    let mut block = [IqSample::default(); IQ_BLOCK_PAIRS];
+    let mut audio = [0.0f32; AUDIO_BLOCK_LEN];
+    let mut tap = [IqSample::default(); AUDIO_BLOCK_LEN];
+    #[cfg(feature = "display")]
+    let mut spectrum = IqSpectrum::new(AUDIO_SAMPLE_RATE);
+    #[cfg(feature = "display")]
+    let mut last_row_ms = uptime_ms();
+    audio_out.push_slice(&audio);
+
+    loop {
+        IQ_READY.wait().await;
+        while iq.len() >= IQ_BLOCK_PAIRS {
+            while let Ok(command) = DSP_COMMANDS.try_receive() {
+                pipeline.apply(command);
+            }
+            if let Some(hz) = pipeline.take_tune_correction() {
+                if RADIO_EVENTS.try_send(RadioEvent::ShiftFrequency(hz)).is_err() {
+                    warn!("DSP: event queue full, auto-tune dropped");
+                }
+            }
+
+            iq.pop_slice(&mut block);
+            let len = pipeline.process_iq_tap(&block, &mut audio, &mut tap);
+            audio_out.push_slice(&audio[..len]);
+            if USB_IQ_ENABLED.load(Ordering::Relaxed) {
+                usb_iq.push_slice(&tap[..len]);
+                USB_IQ_READY.signal(());
+            }
+            #[cfg(feature = "display")]
+            for &sample in &tap[..len] {
+                if spectrum.push(sample) {
+                    let now_ms = uptime_ms();
+                    if now_ms.wrapping_sub(last_row_ms) >= SPECTRUM_ROW_MS {
+                        last_row_ms = now_ms;
+                        SPECTRUM_ROW.signal(spectrum.row(now_ms));
+                    } else {
+                        // Keep the next row's frame fresh
+                        spectrum.reset();
+                    }
+                }
+            }
+            S_METER.signal(pipeline.smeter_percent());
+            #[allow(clippy::cast_possible_truncation)]
+            PITCH_OFFSET.signal(pipeline.pitch_offset_hz().map(|hz| hz as i16));
+        }
+    }
+}
+
+/// UI task - encoder input and display pages
+///
+/// Turns encoder actions into radio events and redraws from the state
+/// the radio control task publishes, sending only changed tiles. Shows
+/// the transmit task's policy verdict. Changes on the settings screen go
+/// to radio control, which saves them and applies them everywhere but
+/// the display brightness, set here. Holding the encoder switch and PTT
+/// together asks for the ROM bootloader.
+#[cfg(feature = "display")]
+#[embassy_executor::task]
+async fn ui_task(
+    mut display: UiPanel,
+    mut encoder: Encoder<'static>,
+    mut state: RadioState,
+    mut config: OperatorConfig,
+) {
+    let mut ui = UiState::new();
+    set_brightness(&mut display, &config).await;
+    let mut scheduler = RedrawScheduler::new(display.buffer().geometry());
+    let memories = MemoryBank::new();
+    let messages = CwMemory::new();
+    let mut spectrum = None;
+    let mut waterfall = WaterfallBuffer::<WATERFALL_ROWS>::new();
+    let mut bootloader = BootloaderGuard::new();
+    let mut ticker = Ticker::every(Duration::from_millis(UI_POLL_MS));
+    #[allow(clippy::cast_possible_truncation)]
+    let mut last_ms = Instant::now().as_millis() as u32;
+
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let now_ms = Instant::now().as_millis() as u32;
+
+        let held = now_ms.wrapping_sub(last_ms);
+        last_ms = now_ms;
+        let ptt = PTT_PRESSED.load(Ordering::Relaxed);
+        if bootloader.update_buttons(encoder.is_pressed(), ptt, held) {
+            BOOTLOADER_REQUEST.signal(());
+        }
+
+        if let Some(event) = encoder.poll(now_ms) {
+            if ui.screen() == Screen::Settings {
+                let brightness = config.brightness;
+                if ui.handle_settings_encoder(event, &mut config) {
+                    CONFIG_UPDATES.signal(config);
+                    if config.brightness != brightness {
+                        set_brightness(&mut display, &config).await;
+                    }
+                }
+            } else if let Some(action) = ui.handle_encoder(event) {
+                match action.to_radio_event(state.is_transmitting()) {
+                    Some(event) if !send_radio_event(event) => {
+                        warn!("UI: radio event queue full");
+                    }
+                    Some(_) => {}
+                    None => info!("UI: {} not handled", action),
+                }
+            }
+            encoder.set_acceleration(ui.screen() == Screen::Main);
+        }
+        if let Some(new_state) = RADIO_STATUS.try_take() {
+            state = new_state;
+            ui.invalidate();
+        }
+        ui.set_tx_verdict(TX_POLICY.lock(Cell::get).verdict());
+        if let Some(level) = S_METER.try_take() {
+            ui.set_s_meter(level);
+        }
+        if let Some(offset) = PITCH_OFFSET.try_take() {
+            ui.set_pitch_offset(offset);
+        }
+        if let Some(row) = SPECTRUM_ROW.try_take() {
+            waterfall.push(row.clone());
+            spectrum = Some(row);
+            if matches!(ui.screen(), Screen::Main | Screen::Scope) {
+                ui.invalidate();
+            }
+        }
+
+        if ui.needs_update() && scheduler.frame_due(now_ms) {
+            let ctx = PageContext {
+                state: &state,
+                ui: &ui,
+                memories: &memories,
+                messages: &messages,
+                config: &config,
+                spectrum: spectrum.as_ref(),
+                waterfall: Some(&waterfall),
+            };
+            render_page(display.buffer_mut(), &ctx);
+            scheduler.update(display.buffer().as_bytes());
+            ui.mark_updated();
+        }
+        if let Some(region) = scheduler.next_region() {
+            if display.flush_region(region).await.is_err() {
+                warn!("UI: display write failed");
+                scheduler.invalidate();
+            }
+        }
+
+        ticker.next().await;
+    }
+}
+
+/// Set the OLED contrast for the brightness setting
+#[cfg(all(feature = "display", not(feature = "st7735")))]
+async fn set_brightness(display: &mut UiPanel, config: &OperatorConfig) {
+    if display.set_contrast(config.contrast()).await.is_err() {
+        warn!("UI: contrast write failed");
+    }
+}
+
+/// The ST7735 backlight is not dimmable
+#[cfg(feature = "st7735")]
+async fn set_brightness(_display: &mut UiPanel, _config: &OperatorConfig) {}
@@ -1,6 +1,16 @@
 //! Power Management
 //!
-//! Battery monitoring, thermal management, and power control.
+//! Battery monitoring, thermal management, and power control. The
+//! battery state of charge gauge is in [`battery`], PA temperature
+//! sensing and derating in [`thermal`] and the USB-PD sink policy in
+//! [`usb_pd`].
+
+pub mod battery;
+pub mod thermal;
+pub mod usb_pd;
+
+use battery::BatteryGauge;
+use thermal::{DeratingCurve, PaThermal};
 
 /// Battery voltage reading
 #[derive(Clone, Copy, Debug)]
@@ -157,14 +167,14 @@ pub struct PowerManager {
     battery: Option<BatteryVoltage>,
     /// Number of battery cells
     cells: u8,
+    /// State of charge gauge, when the pack is configured
+    gauge: Option<BatteryGauge>,
     /// PA temperature
     pa_temp: Option<Temperature>,
     /// MCU temperature
     mcu_temp: Option<Temperature>,
-    /// TX power limit due to thermal
-    thermal_limit_percent: u8,
-    /// Over temperature threshold
-    over_temp_threshold: f32,
+    /// PA thermal derating and cutoff
+    pa_thermal: PaThermal,
 }
 
 impl PowerManager {
@@ -175,10 +185,10 @@ impl PowerManager {
             state: PowerState::Battery,
             battery: None,
             cells,
+            gauge: None,
             pa_temp: None,
             mcu_temp: None,
-            thermal_limit_percent: 100,
-            over_temp_threshold: 70.0,
+            pa_thermal: PaThermal::new(DeratingCurve::DEFAULT),
         }
     }
 
@@ -194,10 +204,29 @@ impl PowerManager {
         self.battery
     }
 
-    /// Get battery percentage
+    /// Get battery percentage, from the gauge once it has an estimate
     #[must_use]
     pub fn battery_percent(&self) -> Option<u8> {
-        self.battery.map(|b| b.percentage(self.cells))
+        self.gauge
+            .as_ref()
+            .and_then(BatteryGauge::soc_percent)
+            .or_else(|| self.battery.map(|b| b.percentage(self.cells)))
+    }
+
+    /// Set the battery state of charge gauge for the fitted pack
+    pub fn set_battery_gauge(&mut self, gauge: BatteryGauge) {
+        self.gauge = Some(gauge);
+    }
+
+    /// Get the battery state of charge gauge
+    #[must_use]
+    pub const fn battery_gauge(&self) -> Option<&BatteryGauge> {
+        self.gauge.as_ref()
+    }
+
+    /// Get mutable access to the battery gauge (low voltage threshold)
+    pub fn battery_gauge_mut(&mut self) -> Option<&mut BatteryGauge> {
+        self.gauge.as_mut()
     }
 
     /// Get PA temperature
@@ -209,7 +238,18 @@ impl PowerManager {
     /// Get thermal power limit
     #[must_use]
     pub const fn thermal_limit(&self) -> u8 {
-        self.thermal_limit_percent
+        self.pa_thermal.limit_percent()
+    }
+
+    /// Get the PA thermal monitor
+    #[must_use]
+    pub const fn pa_thermal(&self) -> &PaThermal {
+        &self.pa_thermal
+    }
+
+    /// Get mutable access to the PA thermal monitor (curve, sensor faults)
+    pub fn pa_thermal_mut(&mut self) -> &mut PaThermal {
+        &mut self.pa_thermal
     }
 
     /// Update battery voltage
@@ -217,26 +257,34 @@ impl PowerManager {
         self.battery = Some(voltage);
 
         // Check for critical battery
-        if voltage.is_critical(self.cells) && self.state == PowerState::Battery {
+        if self.battery_critical() && self.state == PowerState::Battery {
             self.state = PowerState::LowPower;
         }
     }
 
+    /// Update battery voltage and current (positive when discharging)
+    /// measured `elapsed_ms` after the last, feeding the gauge
+    pub fn update_battery_gauge(&mut self, voltage: BatteryVoltage, current_ma: i32, elapsed_ms: u32) {
+        self.update_battery(voltage);
+        if let Some(gauge) = &mut self.gauge {
+            gauge.update(voltage.voltage(), current_ma, elapsed_ms);
+        }
+    }
+
+    /// Check the battery against the gauge's empty voltage, or the
+    /// `LiPo` limit without a gauge
+    fn battery_critical(&self) -> bool {
+        match (&self.gauge, self.battery) {
+            (Some(gauge), Some(batt)) => gauge.is_critical_at(batt.voltage()),
+            (None, Some(batt)) => batt.is_critical(self.cells),
+            (_, None) => false,
+        }
+    }
+
     /// Update PA temperature
     pub fn update_pa_temp(&mut self, temp: Temperature) {
         self.pa_temp = Some(temp);
-
-        // Thermal limiting
-        let celsius = temp.celsius();
-        if celsius > self.over_temp_threshold {
-            self.thermal_limit_percent = 0;
-        } else if celsius > self.over_temp_threshold - 10.0 {
-            // Linear ramp down
-            let over = celsius - (self.over_temp_threshold - 10.0);
-            self.thermal_limit_percent = (100.0 - over * 10.0) as u8;
-        } else {
-            self.thermal_limit_percent = 100;
-        }
+        self.pa_thermal.update(temp);
     }
 
     /// Update MCU temperature
@@ -253,14 +301,12 @@ impl PowerManager {
     #[must_use]
     pub fn tx_allowed(&self) -> bool {
         // Don't allow TX on low battery
-        if let Some(batt) = self.battery {
-            if batt.is_critical(self.cells) {
-                return false;
-            }
+        if self.battery_critical() {
+            return false;
         }
 
         // Don't allow TX if over temperature
-        if self.thermal_limit_percent == 0 {
+        if self.pa_thermal.is_cutoff() {
             return false;
         }
 
@@ -270,10 +316,12 @@ impl PowerManager {
     /// Get effective power limit (0-100)
     #[must_use]
     pub fn effective_power_limit(&self) -> u8 {
-        let mut limit = self.thermal_limit_percent;
+        let mut limit = self.pa_thermal.limit_percent();
 
         // Reduce power on low battery
-        if let Some(batt) = self.battery {
+        if let Some(gauge) = &self.gauge {
+            limit = limit.min(gauge.tx_power_limit());
+        } else if let Some(batt) = self.battery {
             if batt.is_low(self.cells) {
                 limit = limit.min(50);
             }
@@ -292,6 +340,6 @@ impl Default for PowerManager {
 #[cfg(feature = "embedded")]
 impl defmt::Format for PowerManager {
     fn format(&self, f: defmt::Formatter) {
-        defmt::write!(f, "Power({}, limit={}%)", self.state, self.thermal_limit_percent);
+        defmt::write!(f, "Power({}, limit={}%)", self.state, self.thermal_limit());
     }
 }
@@ -0,0 +1,309 @@
+//! Battery Gauge
+//!
+//! Estimates state of charge by counting the charge drawn from the pack
+//! (coulomb counting) between rests, and re-anchors the count on the
+//! chemistry's open-circuit discharge curve whenever the current has
+//! been low long enough for the cell voltage to settle. Until the first
+//! rest the estimate starts from the voltage alone.
+//!
+//! Below a configurable pack voltage the gauge also limits TX power,
+//! falling linearly to [`LOW_BATTERY_TX_PERCENT`] at the empty voltage,
+//! so a tired pack is not pulled down into a brown-out on voice peaks.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut gauge = BatteryGauge::new(Chemistry::LiFePo4, 4, 6000);
+//! // every 100 ms
+//! gauge.update(adc.battery_volts(), current_sense.milliamps(), 100);
+//! tx.set_power(requested.min(gauge.tx_power_limit()));
+//! cat.battery(gauge.soc_percent(), gauge.voltage_mv(), gauge.current_ma());
+//! ```
+
+/// Lowest TX power limit from the gauge, reached at the empty voltage
+pub const LOW_BATTERY_TX_PERCENT: u8 = 25;
+
+/// Currents below this (either direction) count as resting, in mA
+pub const REST_CURRENT_MA: i32 = 50;
+
+/// Time at rest before the open-circuit voltage is trusted
+pub const REST_SETTLE_MS: u32 = 60_000;
+
+/// Milliamp-milliseconds per mAh
+const MA_MS_PER_MAH: f32 = 3_600_000.0;
+
+/// Battery chemistry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Chemistry {
+    /// Lithium ion / lithium polymer (4.2 V full)
+    #[default]
+    LiIon,
+    /// Lithium iron phosphate (3.4 V full, very flat curve)
+    LiFePo4,
+    /// Nickel metal hydride
+    NiMh,
+    /// Sealed lead acid (2 V cells)
+    LeadAcid,
+}
+
+impl Chemistry {
+    /// Get the resting discharge curve as (mV per cell, percent) points,
+    /// highest voltage first
+    #[must_use]
+    pub const fn discharge_curve(self) -> &'static [(u16, u8)] {
+        match self {
+            Self::LiIon => &[
+                (4200, 100),
+                (4100, 90),
+                (4000, 80),
+                (3920, 70),
+                (3850, 60),
+                (3800, 50),
+                (3750, 40),
+                (3700, 30),
+                (3650, 20),
+                (3550, 10),
+                (3300, 3),
+                (3000, 0),
+            ],
+            Self::LiFePo4 => &[
+                (3400, 100),
+                (3350, 95),
+                (3320, 90),
+                (3300, 70),
+                (3270, 40),
+                (3250, 30),
+                (3200, 20),
+                (3100, 10),
+                (3000, 5),
+                (2500, 0),
+            ],
+            Self::NiMh => &[
+                (1400, 100),
+                (1320, 90),
+                (1280, 70),
+                (1250, 50),
+                (1220, 30),
+                (1180, 15),
+                (1100, 5),
+                (1000, 0),
+            ],
+            Self::LeadAcid => &[
+                (2120, 100),
+                (2100, 90),
+                (2080, 80),
+                (2060, 70),
+                (2040, 60),
+                (2020, 50),
+                (2000, 40),
+                (1980, 30),
+                (1960, 20),
+                (1940, 10),
+                (1750, 0),
+            ],
+        }
+    }
+
+    /// Get the empty cell voltage in mV
+    #[must_use]
+    pub const fn empty_mv(self) -> u16 {
+        let curve = self.discharge_curve();
+        curve[curve.len() - 1].0
+    }
+
+    /// Get the default cell voltage in mV below which TX power is reduced
+    #[must_use]
+    pub const fn default_low_mv(self) -> u16 {
+        match self {
+            Self::LiIon => 3550,
+            Self::LiFePo4 => 3150,
+            Self::NiMh => 1150,
+            Self::LeadAcid => 1950,
+        }
+    }
+
+    /// Get the state of charge (0.0 to 1.0) at a resting cell voltage
+    #[must_use]
+    pub fn soc_at(self, cell_mv: f32) -> f32 {
+        let curve = self.discharge_curve();
+        let (full_mv, _) = curve[0];
+        if cell_mv >= f32::from(full_mv) {
+            return 1.0;
+        }
+        for pair in curve.windows(2) {
+            let (high_mv, high_pct) = pair[0];
+            let (low_mv, low_pct) = pair[1];
+            if cell_mv >= f32::from(low_mv) {
+                let fraction =
+                    (cell_mv - f32::from(low_mv)) / f32::from(high_mv - low_mv);
+                let pct = f32::from(low_pct) + fraction * f32::from(high_pct - low_pct);
+                return pct / 100.0;
+            }
+        }
+        0.0
+    }
+}
+
+/// Battery state of charge estimator
+#[derive(Clone, Debug)]
+pub struct BatteryGauge {
+    /// Cell chemistry
+    chemistry: Chemistry,
+    /// Cells in series
+    cells: u8,
+    /// Pack capacity in mAh
+    capacity_mah: u32,
+    /// State of charge (0.0 to 1.0), once estimated
+    soc: Option<f32>,
+    /// Last pack voltage in volts
+    voltage: f32,
+    /// Last current in mA, positive when discharging
+    current_ma: i32,
+    /// Time the current has been below [`REST_CURRENT_MA`]
+    rest_ms: u32,
+    /// Pack voltage below which TX power is reduced
+    low_voltage: f32,
+}
+
+impl BatteryGauge {
+    /// Create a gauge for `cells` cells in series of `capacity_mah`
+    #[must_use]
+    pub fn new(chemistry: Chemistry, cells: u8, capacity_mah: u32) -> Self {
+        let cells = cells.max(1);
+        Self {
+            chemistry,
+            cells,
+            capacity_mah: capacity_mah.max(1),
+            soc: None,
+            voltage: 0.0,
+            current_ma: 0,
+            rest_ms: 0,
+            low_voltage: f32::from(chemistry.default_low_mv()) * f32::from(cells) / 1000.0,
+        }
+    }
+
+    /// Get the cell chemistry
+    #[must_use]
+    pub const fn chemistry(&self) -> Chemistry {
+        self.chemistry
+    }
+
+    /// Get the number of cells in series
+    #[must_use]
+    pub const fn cells(&self) -> u8 {
+        self.cells
+    }
+
+    /// Get the pack capacity in mAh
+    #[must_use]
+    pub const fn capacity_mah(&self) -> u32 {
+        self.capacity_mah
+    }
+
+    /// Set the pack voltage below which TX power is reduced
+    pub fn set_low_voltage(&mut self, volts: f32) {
+        self.low_voltage = volts.max(0.0);
+    }
+
+    /// Get the pack voltage below which TX power is reduced
+    #[must_use]
+    pub const fn low_voltage(&self) -> f32 {
+        self.low_voltage
+    }
+
+    /// Add a reading of pack voltage and current (positive when
+    /// discharging) taken `elapsed_ms` after the last
+    #[allow(clippy::cast_precision_loss)]
+    pub fn update(&mut self, volts: f32, current_ma: i32, elapsed_ms: u32) {
+        self.voltage = volts;
+        self.current_ma = current_ma;
+
+        if current_ma.abs() < REST_CURRENT_MA {
+            self.rest_ms = self.rest_ms.saturating_add(elapsed_ms);
+        } else {
+            self.rest_ms = 0;
+        }
+
+        let resting = self.resting_soc();
+        self.soc = Some(match self.soc {
+            // Anchor on the open-circuit voltage once it has settled
+            Some(_) if self.rest_ms >= REST_SETTLE_MS => resting,
+            Some(soc) => {
+                let drawn = current_ma as f32 * elapsed_ms as f32 / MA_MS_PER_MAH;
+                (soc - drawn / self.capacity_mah as f32).clamp(0.0, 1.0)
+            }
+            None => resting,
+        });
+    }
+
+    /// Get the state of charge in percent, once estimated
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn soc_percent(&self) -> Option<u8> {
+        self.soc.map(|soc| (soc * 100.0 + 0.5) as u8)
+    }
+
+    /// Get the estimated charge remaining in mAh
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn remaining_mah(&self) -> Option<u32> {
+        self.soc.map(|soc| (soc * self.capacity_mah as f32) as u32)
+    }
+
+    /// Get the last pack voltage in volts
+    #[must_use]
+    pub const fn voltage(&self) -> f32 {
+        self.voltage
+    }
+
+    /// Get the last pack voltage in mV
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn voltage_mv(&self) -> u32 {
+        (self.voltage.max(0.0) * 1000.0 + 0.5) as u32
+    }
+
+    /// Get the last current in mA, positive when discharging
+    #[must_use]
+    pub const fn current_ma(&self) -> i32 {
+        self.current_ma
+    }
+
+    /// Get the TX power limit in percent for the pack voltage
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn tx_power_limit(&self) -> u8 {
+        if self.soc.is_none() || self.voltage >= self.low_voltage {
+            return 100;
+        }
+        let empty = self.empty_voltage();
+        let span = self.low_voltage - empty;
+        if span <= 0.0 || self.voltage <= empty {
+            return LOW_BATTERY_TX_PERCENT;
+        }
+        let min = f32::from(LOW_BATTERY_TX_PERCENT);
+        let fraction = (self.voltage - empty) / span;
+        (min + fraction * (100.0 - min) + 0.5) as u8
+    }
+
+    /// Check if a pack voltage is at or below the empty voltage
+    #[must_use]
+    pub fn is_critical_at(&self, volts: f32) -> bool {
+        volts <= self.empty_voltage()
+    }
+
+    /// Pack voltage at the bottom of the discharge curve
+    fn empty_voltage(&self) -> f32 {
+        f32::from(self.chemistry.empty_mv()) * f32::from(self.cells) / 1000.0
+    }
+
+    /// State of charge from the last voltage, taken as open-circuit
+    fn resting_soc(&self) -> f32 {
+        let cell_mv = self.voltage * 1000.0 / f32::from(self.cells);
+        self.chemistry.soc_at(cell_mv)
+    }
+}
@@ -0,0 +1,251 @@
+//! PA Thermal Protection
+//!
+//! The PA heatsink temperature is read from an NTC thermistor on an ADC
+//! channel or an LM75 style I2C sensor. [`PaThermal`] smooths the
+//! readings and maps them through a [`DeratingCurve`] to a power limit
+//! that falls progressively as the PA heats, then trips a hard cutoff
+//! that holds until the PA has cooled by [`CUTOFF_HYSTERESIS_C`].
+//!
+//! The limit and cutoff are applied to the transmitter with
+//! [`TxController::update_thermal`](crate::radio::transmit::TxController::update_thermal),
+//! which inhibits TX while the cutoff holds.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut thermal = PaThermal::new(DeratingCurve::DEFAULT);
+//! // every second
+//! match Thermistor::DEFAULT.temperature(adc.read(PA_TEMP_CHANNEL)) {
+//!     Some(temp) => thermal.update(temp),
+//!     None => thermal.sensor_fault(),
+//! }
+//! tx.update_thermal(thermal.limit_percent(), thermal.is_cutoff());
+//! ```
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+
+use super::Temperature;
+
+/// Degrees below the cutoff the PA must cool to before TX is allowed again
+pub const CUTOFF_HYSTERESIS_C: f32 = 10.0;
+
+/// Weight of each new reading in the smoothed temperature
+const SMOOTHING: f32 = 0.25;
+
+/// Full scale of the 12-bit ADC
+const ADC_FULL_SCALE: u16 = 4095;
+
+/// 25°C in kelvin
+const T25_KELVIN: f32 = 298.15;
+
+/// NTC thermistor from the ADC input to ground, with a series resistor
+/// from the ADC reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thermistor {
+    /// Resistance at 25°C in ohms
+    r25_ohms: f32,
+    /// Beta constant in kelvin
+    beta: f32,
+    /// Series resistor in ohms
+    series_ohms: f32,
+}
+
+impl Thermistor {
+    /// 10k B3950 thermistor with a 10k series resistor
+    pub const DEFAULT: Self = Self::new(10_000.0, 3950.0, 10_000.0);
+
+    /// Create a thermistor divider
+    #[must_use]
+    pub const fn new(r25_ohms: f32, beta: f32, series_ohms: f32) -> Self {
+        Self {
+            r25_ohms,
+            beta,
+            series_ohms,
+        }
+    }
+
+    /// Convert a 12-bit ADC reading to temperature
+    ///
+    /// Returns `None` at either end of the scale, where the thermistor
+    /// is open or shorted.
+    #[must_use]
+    pub fn temperature(&self, raw: u16) -> Option<Temperature> {
+        if raw == 0 || raw >= ADC_FULL_SCALE {
+            return None;
+        }
+        let ohms = self.series_ohms * f32::from(raw) / f32::from(ADC_FULL_SCALE - raw);
+        let inv_kelvin = 1.0 / T25_KELVIN + (ohms / self.r25_ohms).ln() / self.beta;
+        Some(Temperature::from_celsius(1.0 / inv_kelvin - 273.15))
+    }
+}
+
+/// Convert the two byte temperature register of an LM75 style I2C
+/// sensor (11 bits, 0.125°C per step, most significant byte first)
+#[must_use]
+pub const fn lm75_temperature(register: [u8; 2]) -> Temperature {
+    let eighths = i16::from_be_bytes(register) >> 5;
+    Temperature::from_tenths(eighths * 5 / 4)
+}
+
+/// Power limit against PA temperature
+///
+/// Full power up to `start_c`, falling linearly to `min_percent` at
+/// `end_c` and held there up to the hard cutoff at `cutoff_c`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeratingCurve {
+    /// Temperature derating starts at
+    pub start_c: f32,
+    /// Temperature the limit reaches `min_percent` at
+    pub end_c: f32,
+    /// Lowest limit before the cutoff
+    pub min_percent: u8,
+    /// Temperature TX is cut off at
+    pub cutoff_c: f32,
+}
+
+impl DeratingCurve {
+    /// For SSB and CW, where the PA idles between words
+    pub const DEFAULT: Self = Self {
+        start_c: 60.0,
+        end_c: 70.0,
+        min_percent: 25,
+        cutoff_c: 80.0,
+    };
+
+    /// For continuous carrier modes (FT8, RTTY, tuning), derating sooner
+    pub const CONTINUOUS: Self = Self {
+        start_c: 50.0,
+        end_c: 65.0,
+        min_percent: 25,
+        cutoff_c: 75.0,
+    };
+
+    /// Get the power limit in percent at `celsius`, ignoring the cutoff
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn limit_percent(&self, celsius: f32) -> u8 {
+        if celsius <= self.start_c {
+            return 100;
+        }
+        if celsius >= self.end_c {
+            return self.min_percent;
+        }
+        let fraction = (celsius - self.start_c) / (self.end_c - self.start_c);
+        let min = f32::from(self.min_percent);
+        (100.0 - fraction * (100.0 - min) + 0.5) as u8
+    }
+}
+
+impl Default for DeratingCurve {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// PA temperature monitor
+#[derive(Clone, Debug)]
+pub struct PaThermal {
+    /// Derating curve in use
+    curve: DeratingCurve,
+    /// Smoothed PA temperature in °C
+    celsius: Option<f32>,
+    /// Current power limit in percent
+    limit_percent: u8,
+    /// Hard cutoff tripped
+    cutoff: bool,
+    /// Last reading failed
+    fault: bool,
+}
+
+impl PaThermal {
+    /// Create a monitor with no readings yet (full power)
+    #[must_use]
+    pub const fn new(curve: DeratingCurve) -> Self {
+        Self {
+            curve,
+            celsius: None,
+            limit_percent: 100,
+            cutoff: false,
+            fault: false,
+        }
+    }
+
+    /// Get the derating curve
+    #[must_use]
+    pub const fn curve(&self) -> DeratingCurve {
+        self.curve
+    }
+
+    /// Change the derating curve, e.g. for a continuous carrier mode
+    pub fn set_curve(&mut self, curve: DeratingCurve) {
+        self.curve = curve;
+        if let Some(celsius) = self.celsius {
+            self.apply(celsius);
+        }
+    }
+
+    /// Add a PA temperature reading
+    pub fn update(&mut self, temp: Temperature) {
+        let celsius = match self.celsius {
+            Some(smoothed) => smoothed + (temp.celsius() - smoothed) * SMOOTHING,
+            None => temp.celsius(),
+        };
+        self.celsius = Some(celsius);
+        self.fault = false;
+        self.apply(celsius);
+    }
+
+    /// Record a failed reading (open or shorted sensor)
+    ///
+    /// With the PA temperature unknown, power is held at the curve's
+    /// lowest limit until a good reading arrives.
+    pub fn sensor_fault(&mut self) {
+        self.fault = true;
+        self.limit_percent = self.limit_percent.min(self.curve.min_percent);
+    }
+
+    /// Get the smoothed PA temperature
+    #[must_use]
+    pub fn temperature(&self) -> Option<Temperature> {
+        self.celsius.map(Temperature::from_celsius)
+    }
+
+    /// Get the power limit in percent (0 while cut off)
+    #[must_use]
+    pub const fn limit_percent(&self) -> u8 {
+        if self.cutoff {
+            0
+        } else {
+            self.limit_percent
+        }
+    }
+
+    /// Check if the hard cutoff has tripped
+    #[must_use]
+    pub const fn is_cutoff(&self) -> bool {
+        self.cutoff
+    }
+
+    /// Check if the last reading failed
+    #[must_use]
+    pub const fn is_sensor_fault(&self) -> bool {
+        self.fault
+    }
+
+    /// Update the limit and cutoff for the smoothed temperature
+    fn apply(&mut self, celsius: f32) {
+        if celsius >= self.curve.cutoff_c {
+            self.cutoff = true;
+        } else if celsius < self.curve.cutoff_c - CUTOFF_HYSTERESIS_C {
+            self.cutoff = false;
+        }
+        self.limit_percent = self.curve.limit_percent(celsius);
+    }
+}
+
+impl Default for PaThermal {
+    fn default() -> Self {
+        Self::new(DeratingCurve::DEFAULT)
+    }
+}
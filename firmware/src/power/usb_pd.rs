@@ -0,0 +1,387 @@
+//! USB Power Delivery Sink Policy
+//!
+//! The PD protocol layer (X-CUBE-TCPP on the UCPD peripheral, built
+//! with the `usb-pd` feature) hands the source's capabilities to
+//! [`UsbPdSink`], which picks the contract to request: 15 V at up to
+//! 3 A when offered, otherwise the highest power fixed supply at or
+//! below 15 V. Without PD, or when the request is rejected, the sink
+//! stays on 5 V at the current the Type-C pull-up advertises.
+//!
+//! The negotiated [`PdContract`] sets the TX power ceiling: the PA gets
+//! more headroom from a higher rail, so every event that changes the
+//! contract passes [`PdContract::tx_ceiling_watts`] to
+//! [`TxController::set_power_ceiling`], and power settings remain a
+//! percentage of it. The ceiling is only raised once the source reports
+//! `PS_RDY`, and drops back to [`MAX_TX_POWER_WATTS`] as soon as the
+//! sink falls back to 5 V (detach, hard reset, a rejected request or a
+//! renegotiation).
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut sink = UsbPdSink::new();
+//! sink.attach(TypeCCurrent::Default, &mut tx);
+//! // DPM callbacks from the PD stack
+//! let rdo = sink.source_capabilities(&pdos, &mut tx);
+//! sink.accepted();
+//! sink.ps_ready(&mut tx);
+//! ```
+
+use heapless::Vec;
+
+use crate::config::{MAX_TX_POWER_WATTS, PD_MAX_TX_POWER_WATTS};
+use crate::radio::transmit::TxController;
+
+/// Supply voltage requested when the source offers it, in mV
+pub const PREFERRED_MV: u16 = 15_000;
+
+/// Highest current drawn from any supply, in mA
+pub const MAX_CURRENT_MA: u16 = 3_000;
+
+/// Most power data objects in a source capabilities message
+pub const MAX_PDOS: usize = 7;
+
+/// Time to wait for source capabilities after attach
+pub const CAPABILITIES_TIMEOUT_MS: u32 = 620;
+
+/// Fraction of the contract's power the PA can turn into RF
+const PA_EFFICIENCY: f32 = 0.5;
+
+/// A power data object from the source capabilities
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pdo {
+    /// Fixed supply
+    Fixed {
+        /// Voltage in mV
+        mv: u16,
+        /// Maximum current in mA
+        max_ma: u16,
+    },
+    /// Battery supply
+    Battery {
+        /// Minimum voltage in mV
+        min_mv: u16,
+        /// Maximum voltage in mV
+        max_mv: u16,
+        /// Maximum power in mW
+        max_mw: u32,
+    },
+    /// Variable (non-battery) supply
+    Variable {
+        /// Minimum voltage in mV
+        min_mv: u16,
+        /// Maximum voltage in mV
+        max_mv: u16,
+        /// Maximum current in mA
+        max_ma: u16,
+    },
+    /// Programmable power supply (augmented PDO)
+    Pps {
+        /// Minimum voltage in mV
+        min_mv: u16,
+        /// Maximum voltage in mV
+        max_mv: u16,
+        /// Maximum current in mA
+        max_ma: u16,
+    },
+}
+
+impl Pdo {
+    /// Decode a 32-bit power data object
+    ///
+    /// Returns `None` for reserved augmented PDO types.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        let field = |shift: u32, bits: u32| (raw >> shift) & ((1 << bits) - 1);
+        match raw >> 30 {
+            0 => Some(Self::Fixed {
+                mv: (field(10, 10) * 50) as u16,
+                max_ma: (field(0, 10) * 10) as u16,
+            }),
+            1 => Some(Self::Battery {
+                min_mv: (field(10, 10) * 50) as u16,
+                max_mv: (field(20, 10) * 50) as u16,
+                max_mw: field(0, 10) * 250,
+            }),
+            2 => Some(Self::Variable {
+                min_mv: (field(10, 10) * 50) as u16,
+                max_mv: (field(20, 10) * 50) as u16,
+                max_ma: (field(0, 10) * 10) as u16,
+            }),
+            _ if field(28, 2) == 0 => Some(Self::Pps {
+                min_mv: (field(8, 8) * 100) as u16,
+                max_mv: (field(17, 8) * 100) as u16,
+                max_ma: (field(0, 7) * 50) as u16,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Current advertised by the source's Type-C pull-up at 5 V
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TypeCCurrent {
+    /// USB default (500 mA for USB 2.0)
+    #[default]
+    Default,
+    /// 1.5 A
+    Medium,
+    /// 3.0 A
+    High,
+}
+
+impl TypeCCurrent {
+    /// Get the current in mA
+    #[must_use]
+    pub const fn ma(self) -> u16 {
+        match self {
+            Self::Default => 500,
+            Self::Medium => 1_500,
+            Self::High => 3_000,
+        }
+    }
+}
+
+/// Supply contract in force
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PdContract {
+    /// Supply voltage in mV
+    pub mv: u16,
+    /// Current the sink may draw in mA
+    pub ma: u16,
+}
+
+impl PdContract {
+    /// 5 V at the USB default current, before anything is negotiated
+    pub const DEFAULT_5V: Self = Self {
+        mv: 5_000,
+        ma: TypeCCurrent::Default.ma(),
+    };
+
+    /// Get the contract power in mW
+    #[must_use]
+    pub const fn power_mw(&self) -> u32 {
+        self.mv as u32 * self.ma as u32 / 1000
+    }
+
+    /// Check if this is a negotiated contract above 5 V
+    #[must_use]
+    pub const fn is_high_voltage(&self) -> bool {
+        self.mv > 5_000
+    }
+
+    /// Get the TX power ceiling this supply allows, in watts
+    ///
+    /// [`MAX_TX_POWER_WATTS`] on 5 V; above it the PA's share of the
+    /// contract power, up to [`PD_MAX_TX_POWER_WATTS`] at 15 V and
+    /// scaled down with the square of the rail below that.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn tx_ceiling_watts(&self) -> f32 {
+        if !self.is_high_voltage() {
+            return MAX_TX_POWER_WATTS;
+        }
+        let rail = f32::from(self.mv.min(PREFERRED_MV)) / f32::from(PREFERRED_MV);
+        let swing = PD_MAX_TX_POWER_WATTS * rail * rail;
+        let supply = self.power_mw() as f32 / 1000.0 * PA_EFFICIENCY;
+        swing.min(supply).max(MAX_TX_POWER_WATTS)
+    }
+}
+
+impl Default for PdContract {
+    fn default() -> Self {
+        Self::DEFAULT_5V
+    }
+}
+
+/// A request for one of the source's fixed supplies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PdRequest {
+    /// Object position in the capabilities (1-based)
+    pub position: u8,
+    /// Contract asked for
+    pub contract: PdContract,
+}
+
+impl PdRequest {
+    /// Encode as a fixed supply request data object
+    #[must_use]
+    pub const fn rdo(&self) -> u32 {
+        let ma_10 = (self.contract.ma / 10) as u32;
+        ((self.position as u32) << 28)
+            // USB communications capable, no USB suspend
+            | (1 << 25)
+            | (1 << 24)
+            | (ma_10 << 10)
+            | ma_10
+    }
+}
+
+/// Sink negotiation state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SinkState {
+    /// No source attached
+    #[default]
+    Detached,
+    /// Attached, waiting for source capabilities
+    WaitCapabilities,
+    /// Request sent, waiting for accept
+    Requested(PdRequest),
+    /// Request accepted, waiting for the supply to settle
+    TransitionSupply(PdRequest),
+    /// Contract in force
+    Ready,
+    /// No PD source, or the request failed; 5 V only
+    TypeCOnly,
+}
+
+/// USB-PD sink policy engine
+#[derive(Clone, Debug, Default)]
+pub struct UsbPdSink {
+    /// Negotiation state
+    state: SinkState,
+    /// Type-C current at 5 V
+    type_c: TypeCCurrent,
+    /// Contract in force
+    contract: PdContract,
+    /// Time since attach
+    elapsed_ms: u32,
+}
+
+impl UsbPdSink {
+    /// Create a detached sink
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the negotiation state
+    #[must_use]
+    pub const fn state(&self) -> SinkState {
+        self.state
+    }
+
+    /// Get the contract in force
+    #[must_use]
+    pub const fn contract(&self) -> PdContract {
+        self.contract
+    }
+
+    /// Source attached with `current` advertised on CC
+    pub fn attach(&mut self, current: TypeCCurrent, tx: &mut TxController) {
+        self.type_c = current;
+        self.set_contract(self.type_c_contract(), tx);
+        self.state = SinkState::WaitCapabilities;
+        self.elapsed_ms = 0;
+    }
+
+    /// Source detached
+    pub fn detach(&mut self, tx: &mut TxController) {
+        self.state = SinkState::Detached;
+        self.set_contract(PdContract::DEFAULT_5V, tx);
+    }
+
+    /// Advance the capabilities timer
+    ///
+    /// A source that sends no capabilities in time is not PD capable.
+    pub fn update(&mut self, elapsed_ms: u32) {
+        if self.state != SinkState::WaitCapabilities {
+            return;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(elapsed_ms);
+        if self.elapsed_ms >= CAPABILITIES_TIMEOUT_MS {
+            self.state = SinkState::TypeCOnly;
+        }
+    }
+
+    /// Evaluate source capabilities (raw PDOs)
+    ///
+    /// Returns the request data object to send. A new capabilities
+    /// message renegotiates from 5 V.
+    pub fn source_capabilities(&mut self, pdos: &[u32], tx: &mut TxController) -> u32 {
+        let request = Self::select(pdos);
+        self.set_contract(self.type_c_contract(), tx);
+        self.state = SinkState::Requested(request);
+        request.rdo()
+    }
+
+    /// Source accepted the request
+    pub fn accepted(&mut self) {
+        if let SinkState::Requested(request) = self.state {
+            self.state = SinkState::TransitionSupply(request);
+        }
+    }
+
+    /// Source rejected the request, or asked the sink to wait
+    pub fn rejected(&mut self, tx: &mut TxController) {
+        if matches!(self.state, SinkState::Requested(_)) {
+            self.state = SinkState::TypeCOnly;
+            self.set_contract(self.type_c_contract(), tx);
+        }
+    }
+
+    /// Supply has settled at the new contract (`PS_RDY`)
+    ///
+    /// Raises the TX power ceiling to what the contract allows.
+    pub fn ps_ready(&mut self, tx: &mut TxController) {
+        if let SinkState::TransitionSupply(request) = self.state {
+            self.set_contract(request.contract, tx);
+            self.state = SinkState::Ready;
+        }
+    }
+
+    /// Hard reset: back to 5 V until capabilities arrive again
+    pub fn hard_reset(&mut self, tx: &mut TxController) {
+        if self.state != SinkState::Detached {
+            self.set_contract(self.type_c_contract(), tx);
+            self.state = SinkState::WaitCapabilities;
+            self.elapsed_ms = 0;
+        }
+    }
+
+    /// Pick the fixed supply to request
+    ///
+    /// [`PREFERRED_MV`] if offered, otherwise the most power at or below
+    /// it. The first PDO is always vSafe5V, so there is always a choice.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn select(pdos: &[u32]) -> PdRequest {
+        let fixed: Vec<(u8, PdContract), MAX_PDOS> = pdos
+            .iter()
+            .take(MAX_PDOS)
+            .enumerate()
+            .filter_map(|(index, raw)| match Pdo::from_raw(*raw)? {
+                Pdo::Fixed { mv, max_ma } if mv <= PREFERRED_MV => Some((
+                    index as u8 + 1,
+                    PdContract {
+                        mv,
+                        ma: max_ma.min(MAX_CURRENT_MA),
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+        let best = fixed
+            .iter()
+            .find(|(_, contract)| contract.mv == PREFERRED_MV)
+            .or_else(|| fixed.iter().max_by_key(|(_, contract)| contract.power_mw()))
+            .copied();
+        let (position, contract) = best.unwrap_or((1, PdContract::DEFAULT_5V));
+        PdRequest { position, contract }
+    }
+
+    /// Put a contract in force and hand its TX power ceiling over
+    fn set_contract(&mut self, contract: PdContract, tx: &mut TxController) {
+        self.contract = contract;
+        tx.set_power_ceiling(contract.tx_ceiling_watts());
+    }
+
+    /// 5 V contract from the Type-C current
+    const fn type_c_contract(&self) -> PdContract {
+        PdContract {
+            mv: 5_000,
+            ma: self.type_c.ma(),
+        }
+    }
+}
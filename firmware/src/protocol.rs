@@ -1,17 +1,51 @@
 //! Communication Protocols
 //!
 //! CAT (Computer Aided Transceiver) command parsing and handling.
-//! Implements Kenwood-style TS-2000 compatible commands.
+//! Implements Kenwood-style TS-2000 compatible commands. The remote head
+//! link to a detached front panel is in [`remote_head`], and the binary
+//! spectrum stream sent alongside CAT is in [`panadapter`].
+
+pub mod panadapter;
+pub mod remote_head;
 
 use heapless::{String, Vec};
 
 #[cfg(feature = "embedded")]
 use crate::radio::state::RadioEvent;
-use crate::types::{Frequency, Mode, PowerLevel};
+use crate::clock::DateTime;
+use crate::dsp::audio_chain::{MAX_NOTCH_WIDTH_HZ, MIN_NOTCH_WIDTH_HZ, NOTCH_COUNT};
+use crate::dsp::bypass::DspStage;
+use crate::dsp::iq_capture::{IqCapture, IQ_CAPTURE_LEN, IQ_CHUNK_SAMPLES};
+use crate::dsp::equalizer::{EqCurve, EqPath, EQ_BANDS, MAX_EQ_GAIN_DB};
+use crate::dsp::si5351_calc::PpmCorrection;
+use crate::protocol::panadapter::PanadapterStream;
+use crate::radio::dtmf::DtmfMode;
+use crate::radio::keyer::{CALLSIGN_LEN, MESSAGE_LEN};
+use crate::radio::loopback::{LoopbackCheck, LoopbackReport};
+use crate::radio::monitor::{MonitorCommand, Spot};
+use crate::radio::practice::PracticeReport;
+use crate::radio::voice_keyer::{VoiceKeyer, VOICE_SLOTS};
+use crate::radio::transmit::{VOX_DELAY_MAX_MS, VOX_GAIN_MAX};
+use crate::radio::sweep::{SweepPoint, MAX_POINTS};
+use crate::radio::tx_policy::{TxPolicy, TxReason};
+use crate::radio::tx_test::ImdReport;
+use crate::selftest::{PostItem, PostReport, PostResult};
+use crate::settings::StartupPolicy;
+use crate::storage::FileEntry;
+use crate::types::{Band, Frequency, IqOrientation, Mode, PowerLevel};
+use sdr_dsp_core::agc::SmeterCalibration;
+use sdr_dsp_core::iq_balance::{IqBalance, IqCalibration};
+use crate::update::FirmwareVersion;
 
 /// Maximum command length
 pub const MAX_CMD_LEN: usize = 64;
 
+/// Bytes of file data returned per `ZFR` read
+pub const FILE_CHUNK_LEN: usize = 16;
+
+/// Maximum text length of a `KY` command (Kenwood limit)
+pub const KEY_TEXT_LEN: usize = 24;
+
 /// CAT command parser
 pub struct CatParser {
     /// Command buffer
@@ -64,6 +98,7 @@ impl CatParser {
             "MD" => self.parse_mode(cmd),
             "IF" => Some(CatCommand::ReadStatus),
             "ID" => Some(CatCommand::ReadId),
+            "IQ" => self.parse_iq(cmd),
             "PS" => self.parse_power_switch(cmd),
             "TX" => Some(CatCommand::Transmit(true)),
             "RX" => Some(CatCommand::Transmit(false)),
@@ -75,12 +110,47 @@ impl CatParser {
             "FR" => self.parse_vfo_select(cmd, true),
             "FT" => self.parse_vfo_select(cmd, false),
             "VX" => self.parse_vox(cmd),
+            "VG" => self.parse_vox_gain(cmd),
+            "VD" => self.parse_vox_delay(cmd),
             "GT" => self.parse_agc(cmd),
             "NB" => self.parse_nb(cmd),
+            "BC" => self.parse_notch(cmd),
+            "BP" => self.parse_notch_frequency(cmd),
+            "EQ" => self.parse_eq(cmd),
             "PA" => self.parse_preamp(cmd),
             "RA" => self.parse_att(cmd),
             "UP" => Some(CatCommand::TuneUp),
             "DN" => Some(CatCommand::TuneDown),
+            "ZF" => self.parse_file(cmd),
+            "ZV" => Some(CatCommand::ReadVersion),
+            "ZB" => self.parse_bootloader(cmd),
+            "ZT" => self.parse_clock(cmd),
+            "ZO" => self.parse_utc_offset(cmd),
+            "ZP" => self.parse_self_test(cmd),
+            "KY" => self.parse_key_text(cmd),
+            "ZM" => self.parse_message(cmd),
+            "ZN" => self.parse_serial(cmd),
+            "ZC" => self.parse_callsign(cmd),
+            "ZS" => self.parse_startup(cmd),
+            "ZQ" => self.parse_iq_orientation(cmd),
+            "ZD" => self.parse_stage_bypass(cmd),
+            "ZA" => self.parse_anti_vox(cmd),
+            "SM" => self.parse_smeter(cmd),
+            "ZR" => Some(CatCommand::ReadSignalDbm),
+            "ZU" => Some(CatCommand::ReadSnr),
+            "ZG" => self.parse_dtmf_mode(cmd),
+            "ZI" => (cmd.len() == 2).then_some(CatCommand::AutoTune),
+            "ZJ" => self.parse_tx_policy(cmd),
+            "ZY" => self.parse_practice(cmd),
+            "ZZ" => self.parse_panadapter(cmd),
+            "PB" => self.parse_voice_play(cmd),
+            "LM" => self.parse_voice_record(cmd),
+            "ZL" => self.parse_smeter_calibration(cmd),
+            "ZX" => self.parse_tx_test(cmd),
+            "ZW" => self.parse_sweep(cmd),
+            "ZE" => self.parse_battery(cmd),
+            "ZK" => self.parse_xtal_correction(cmd),
+            "ZH" => self.parse_monitor(cmd),
             _ => Some(CatCommand::Unknown(cmd.chars().take(2).collect())),
         }
     }
@@ -191,6 +261,33 @@ impl CatParser {
         }
     }
 
+    /// Parse VOX gain (`VG;` read, `VGnnn;` set 000-100)
+    fn parse_vox_gain(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadVoxGain);
+        }
+        let gain: u8 = cmd.get(2..5)?.parse().ok()?;
+        (gain <= VOX_GAIN_MAX).then_some(CatCommand::SetVoxGain(gain))
+    }
+
+    /// Parse VOX delay (`VD;` read, `VDnnnn;` set in milliseconds)
+    fn parse_vox_delay(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadVoxDelay);
+        }
+        let ms: u16 = cmd.get(2..6)?.parse().ok()?;
+        (u32::from(ms) <= VOX_DELAY_MAX_MS).then_some(CatCommand::SetVoxDelay(ms))
+    }
+
+    /// Parse anti-VOX gain (`ZA;` read, `ZAnnn;` set 000-100)
+    fn parse_anti_vox(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadAntiVoxGain);
+        }
+        let gain: u8 = cmd.get(2..5)?.parse().ok()?;
+        (gain <= VOX_GAIN_MAX).then_some(CatCommand::SetAntiVoxGain(gain))
+    }
+
     fn parse_agc(&self, cmd: &str) -> Option<CatCommand> {
         if cmd.len() >= 5 {
             let agc: u8 = cmd[2..5].parse().ok()?;
@@ -209,6 +306,83 @@ impl CatParser {
         }
     }
 
+    /// Parse manual notch switch (`BCn;` read, `BCnp;` set: p 1 = on)
+    ///
+    /// `n` is the notch, 0 or 1.
+    fn parse_notch(&self, cmd: &str) -> Option<CatCommand> {
+        let index = notch_index(cmd.get(2..3)?)?;
+        match cmd.get(3..)? {
+            "" => Some(CatCommand::ReadNotch(index)),
+            "0" => Some(CatCommand::SetNotchEnabled(index, false)),
+            "1" => Some(CatCommand::SetNotchEnabled(index, true)),
+            _ => None,
+        }
+    }
+
+    /// Parse manual notch tuning (`BPn;` read, `BPnffff;` set frequency,
+    /// `BPnffffwwww;` set frequency and width)
+    ///
+    /// `n` is the notch, 0 or 1; frequency and width are in Hz.
+    fn parse_notch_frequency(&self, cmd: &str) -> Option<CatCommand> {
+        let index = notch_index(cmd.get(2..3)?)?;
+        if cmd.len() == 3 {
+            return Some(CatCommand::ReadNotchFrequency(index));
+        }
+        let frequency_hz: u16 = cmd.get(3..7)?.parse().ok()?;
+        let width_hz = match cmd.get(7..)? {
+            "" => None,
+            width if width.len() == 4 => {
+                let width: u16 = width.parse().ok()?;
+                if !(MIN_NOTCH_WIDTH_HZ..=MAX_NOTCH_WIDTH_HZ).contains(&f32::from(width)) {
+                    return None;
+                }
+                Some(width)
+            }
+            _ => return None,
+        };
+        (frequency_hz > 0).then_some(CatCommand::SetNotchFrequency {
+            index,
+            frequency_hz,
+            width_hz,
+        })
+    }
+
+    /// Parse graphic equalizer (`EQt;` read, `EQtp;` switch: p 1 = on,
+    /// `EQtbsgg;` set band `b` to `sgg` dB, e.g. `EQ13+04`)
+    ///
+    /// `t` is 0 for RX and 1 for TX; bands count from 0 at the lowest.
+    /// Gains apply to the current mode's curve.
+    fn parse_eq(&self, cmd: &str) -> Option<CatCommand> {
+        let path = EqPath::from_code(cmd.get(2..3)?.parse().ok()?)?;
+        match cmd.get(3..)? {
+            "" => Some(CatCommand::ReadEq(path)),
+            "0" => Some(CatCommand::SetEqEnabled(path, false)),
+            "1" => Some(CatCommand::SetEqEnabled(path, true)),
+            band if band.len() == 4 => {
+                let index: u8 = band.get(..1)?.parse().ok()?;
+                let sign = match band.get(1..2)? {
+                    "+" => 1,
+                    "-" => -1,
+                    _ => return None,
+                };
+                let digits = band.get(2..)?;
+                if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let magnitude: i8 = digits.parse().ok()?;
+                if usize::from(index) >= EQ_BANDS || magnitude > MAX_EQ_GAIN_DB {
+                    return None;
+                }
+                Some(CatCommand::SetEqBand {
+                    path,
+                    band: index,
+                    gain_db: sign * magnitude,
+                })
+            }
+            _ => None,
+        }
+    }
+
     fn parse_preamp(&self, cmd: &str) -> Option<CatCommand> {
         if cmd.len() >= 3 {
             let on = cmd.chars().nth(2)? == '1';
@@ -227,12 +401,449 @@ impl CatParser {
         }
     }
 
+    /// Parse file store extension commands
+    ///
+    /// - `ZFL;` file count
+    /// - `ZFInn;` info for the nth file
+    /// - `ZFRiioooooooooo;` read a chunk of file `ii` at offset `o`
+    /// - `ZFDii;` delete file `ii`
+    fn parse_file(&self, cmd: &str) -> Option<CatCommand> {
+        let op = cmd.get(2..3)?;
+        match op {
+            "L" => Some(CatCommand::ListFiles),
+            "I" => Some(CatCommand::ReadFileInfo(cmd.get(3..5)?.parse().ok()?)),
+            "R" => {
+                let id = cmd.get(3..5)?.parse().ok()?;
+                let offset = cmd.get(5..15)?.parse().ok()?;
+                Some(CatCommand::ReadFile { id, offset })
+            }
+            "D" => Some(CatCommand::DeleteFile(cmd.get(3..5)?.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    fn parse_bootloader(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..3)? {
+            "1" => Some(CatCommand::ArmBootloader),
+            "2" => Some(CatCommand::EnterBootloader),
+            _ => None,
+        }
+    }
+
+    fn parse_clock(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadClock);
+        }
+        // ZTyyyymmddhhmmss; (UTC)
+        let time = DateTime::new(
+            cmd.get(2..6)?.parse().ok()?,
+            cmd.get(6..8)?.parse().ok()?,
+            cmd.get(8..10)?.parse().ok()?,
+            cmd.get(10..12)?.parse().ok()?,
+            cmd.get(12..14)?.parse().ok()?,
+            cmd.get(14..16)?.parse().ok()?,
+        )?;
+        Some(CatCommand::SetClock(time))
+    }
+
+    fn parse_utc_offset(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadUtcOffset);
+        }
+        // ZO+hhmm; or ZO-hhmm;
+        let negative = match cmd.get(2..3)? {
+            "+" => false,
+            "-" => true,
+            _ => return None,
+        };
+        let hours: i16 = cmd.get(3..5)?.parse().ok()?;
+        let minutes: i16 = cmd.get(5..7)?.parse().ok()?;
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+        let total = hours * 60 + minutes;
+        Some(CatCommand::SetUtcOffset(if negative { -total } else { total }))
+    }
+
+    /// Parse self test report commands
+    ///
+    /// - `ZP;` summary
+    /// - `ZPn;` detail for check `n`
+    /// - `ZPL;` loopback test summary, `ZPLn;` detail for loopback check
+    ///   `n`, `ZPLR;` run the loopback test
+    fn parse_self_test(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadSelfTest);
+        }
+        if let Some(rest) = cmd.strip_prefix("ZPL") {
+            return match rest {
+                "" => Some(CatCommand::ReadLoopbackTest),
+                "R" => Some(CatCommand::RunLoopbackTest),
+                n => Some(CatCommand::ReadLoopbackCheck(LoopbackCheck::from_index(
+                    n.parse().ok()?,
+                )?)),
+            };
+        }
+        let index = cmd.get(2..3)?.parse().ok()?;
+        Some(CatCommand::ReadSelfTestItem(PostItem::from_index(index)?))
+    }
+
+    /// Parse CW text (`KY text;`, Kenwood)
+    fn parse_key_text(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadKeyBuffer);
+        }
+        // One separator character precedes the text
+        let text = cmd.get(3..)?.trim_end();
+        let mut out = String::new();
+        out.push_str(text).ok()?;
+        Some(CatCommand::SendCw(out))
+    }
+
+    /// Parse CW message memory commands
+    ///
+    /// - `ZMn;` play message `n` (1-8), `ZM0;` abort
+    /// - `ZMRn;` read message `n`
+    /// - `ZMWntext;` store message `n`
+    fn parse_message(&self, cmd: &str) -> Option<CatCommand> {
+        let slot = |s: &str| -> Option<u8> {
+            let n: u8 = s.parse().ok()?;
+            (1..=8).contains(&n).then_some(n)
+        };
+        match cmd.get(2..3)? {
+            "0" => Some(CatCommand::AbortMessage),
+            "R" => Some(CatCommand::ReadMessage(slot(cmd.get(3..4)?)?)),
+            "W" => {
+                let index = slot(cmd.get(3..4)?)?;
+                let mut text = String::new();
+                text.push_str(cmd.get(4..)?).ok()?;
+                Some(CatCommand::WriteMessage(index, text))
+            }
+            n => Some(CatCommand::PlayMessage(slot(n)?)),
+        }
+    }
+
+    /// Parse contest serial number (`ZN;` read, `ZNnnnn;` set)
+    fn parse_serial(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadSerial);
+        }
+        Some(CatCommand::SetSerial(cmd.get(2..6)?.parse().ok()?))
+    }
+
+    /// Parse station callsign (`ZC;` read, `ZCcall;` set)
+    fn parse_callsign(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadCallsign);
+        }
+        let mut call = String::new();
+        call.push_str(cmd.get(2..)?).ok()?;
+        Some(CatCommand::SetCallsign(call))
+    }
+
+    /// Parse startup policy commands
+    ///
+    /// - `ZS;` read policy
+    /// - `ZS0;` restore last-used state, `ZS1;` boot to the fixed state
+    /// - `ZSW;` store the current state as the fixed state
+    fn parse_startup(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadStartupPolicy),
+            "W" => Some(CatCommand::StoreStartupState),
+            code => Some(CatCommand::SetStartupPolicy(StartupPolicy::from_code(
+                code.parse().ok()?,
+            )?)),
+        }
+    }
+
+    /// Parse IQ orientation (`ZQ;` read, `ZQn;` set: 0 normal, 1 swap, 2 invert Q)
+    fn parse_iq_orientation(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadIqOrientation);
+        }
+        let code = cmd.get(2..)?.parse().ok()?;
+        Some(CatCommand::SetIqOrientation(IqOrientation::from_code(code)?))
+    }
+
+    /// Parse S-meter read (`SM;` or `SM0;`, main receiver only)
+    fn parse_smeter(&self, cmd: &str) -> Option<CatCommand> {
+        matches!(cmd.get(2..)?, "" | "0").then_some(CatCommand::ReadSMeter)
+    }
+
+    /// Parse S-meter calibration (`ZLb;` read, `ZLbsoooSSSS;` set)
+    ///
+    /// `b` is the band index (0 = 80 m), `sooo` the signed offset in
+    /// 0.1 dB and `SSSS` the slope in 1/1000.
+    fn parse_smeter_calibration(&self, cmd: &str) -> Option<CatCommand> {
+        let band = *Band::ALL.get(usize::from(cmd.get(2..3)?.parse::<u8>().ok()?))?;
+        if cmd.len() == 3 {
+            return Some(CatCommand::ReadSmeterCalibration(band));
+        }
+        let offset: i16 = cmd.get(3..7)?.parse().ok()?;
+        let slope: u16 = cmd.get(7..11)?.parse().ok()?;
+        if !cmd[3..4].starts_with(['+', '-']) || cmd.len() != 11 {
+            return None;
+        }
+        let calibration = SmeterCalibration::new(offset, slope);
+        (calibration.offset_tenths_db() == offset && calibration.slope_permille() == slope)
+            .then_some(CatCommand::SetSmeterCalibration(band, calibration))
+    }
+
+    /// Parse DSP stage bypass (`ZDs;` read, `ZDsn;` set: n 1 = bypassed)
+    ///
+    /// Stages: 0 noise blanker, 1 noise reduction, 2 notch, 3 AGC
+    fn parse_stage_bypass(&self, cmd: &str) -> Option<CatCommand> {
+        let stage = DspStage::from_code(cmd.get(2..3)?.parse().ok()?)?;
+        match cmd.get(3..)? {
+            "" => Some(CatCommand::ReadStageBypass(stage)),
+            "0" => Some(CatCommand::SetStageBypass(stage, false)),
+            "1" => Some(CatCommand::SetStageBypass(stage, true)),
+            _ => None,
+        }
+    }
+
+    /// Parse DTMF decoder mode (`ZG;` read, `ZGn;` set: 0 off, 1 decode, 2 remote)
+    fn parse_dtmf_mode(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadDtmfMode),
+            code => Some(CatCommand::SetDtmfMode(DtmfMode::from_code(code.parse().ok()?)?)),
+        }
+    }
+
+    /// Parse TX policy (`ZJ;` read, `ZJ0;`/`ZJ1;` user override off/on)
+    fn parse_tx_policy(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadTxPolicy),
+            "0" => Some(CatCommand::SetTxOverride(false)),
+            "1" => Some(CatCommand::SetTxOverride(true)),
+            _ => None,
+        }
+    }
+
+    /// Parse keyer practice (`ZY;` read the report, `ZY0;`/`ZY1;` off/on)
+    fn parse_practice(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadPractice),
+            "0" => Some(CatCommand::SetPractice(false)),
+            "1" => Some(CatCommand::SetPractice(true)),
+            _ => None,
+        }
+    }
+
+    /// Parse band monitor commands
+    ///
+    /// - `ZH0;` stop, `ZH1;` start
+    /// - `ZHSfffffffffff±nnCALL,LOC;` spot from the host's decoder: RF
+    ///   frequency in Hz (11 digits), SNR in dB, callsign and locator
+    ///   (may be empty)
+    fn parse_monitor(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "0" => Some(CatCommand::Monitor(MonitorCommand::Stop)),
+            "1" => Some(CatCommand::Monitor(MonitorCommand::Start)),
+            arg if arg.starts_with('S') => {
+                let hz: u32 = cmd.get(3..14)?.parse().ok()?;
+                let snr_db: i8 = cmd.get(14..17)?.parse().ok()?;
+                let (call, locator) = cmd.get(17..)?.split_once(',')?;
+                if call.is_empty() {
+                    return None;
+                }
+                let mut spot = Spot {
+                    band: Band::from_frequency(Frequency::from_hz(hz)?)?,
+                    frequency_hz: hz,
+                    snr_db,
+                    callsign: String::new(),
+                    locator: String::new(),
+                    slot: 0,
+                };
+                spot.callsign.push_str(call).ok()?;
+                spot.locator.push_str(locator).ok()?;
+                Some(CatCommand::Monitor(MonitorCommand::Spot(spot)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse IQ capture and balance commands
+    ///
+    /// `IQB` is the per-band balance calibration and `IQA` adaptive
+    /// balance (`IQA;` read, `IQAn;` set: n 1 = adaptive); anything else
+    /// is the capture.
+    fn parse_iq(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            arg if arg.starts_with('B') => self.parse_iq_calibration(cmd),
+            "A" => Some(CatCommand::ReadIqAdaptive),
+            "A0" => Some(CatCommand::SetIqAdaptive(false)),
+            "A1" => Some(CatCommand::SetIqAdaptive(true)),
+            _ => self.parse_iq_capture(cmd),
+        }
+    }
+
+    /// Parse IQ balance calibration (`IQBb;` read, `IQBbsgggspppp;` set)
+    ///
+    /// `b` is the band index (0 = 80 m), `sggg` the signed Q gain
+    /// correction in 0.01 dB and `spppp` the signed phase correction in
+    /// 0.01 degree.
+    fn parse_iq_calibration(&self, cmd: &str) -> Option<CatCommand> {
+        let band = *Band::ALL.get(usize::from(cmd.get(3..4)?.parse::<u8>().ok()?))?;
+        if cmd.len() == 4 {
+            return Some(CatCommand::ReadIqCalibration(band));
+        }
+        let gain: i16 = cmd.get(4..8)?.parse().ok()?;
+        let phase: i16 = cmd.get(8..13)?.parse().ok()?;
+        let signed = |at: usize| cmd[at..=at].starts_with(['+', '-']);
+        if !signed(4) || !signed(8) || cmd.len() != 13 {
+            return None;
+        }
+        let calibration = IqCalibration::new(gain, phase);
+        (calibration.gain_hundredths_db() == gain && calibration.phase_hundredths_deg() == phase)
+            .then_some(CatCommand::SetIqCalibration(band, calibration))
+    }
+
+    /// Parse IQ capture (`IQ;` read state, `IQnnnn;` capture `nnnn`
+    /// samples, `IQRoooo;` read the chunk at sample offset `oooo`)
+    fn parse_iq_capture(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadIqCapture),
+            arg if arg.len() == 5 && arg.starts_with('R') => {
+                Some(CatCommand::ReadIqChunk(arg.get(1..)?.parse().ok()?))
+            }
+            arg if arg.len() == 4 => {
+                let count: u16 = arg.parse().ok()?;
+                (1..=IQ_CAPTURE_LEN)
+                    .contains(&usize::from(count))
+                    .then_some(CatCommand::CaptureIq(count))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse panadapter streaming (`ZZ;` read, `ZZnn;` frames per
+    /// second, `ZZ00;` stop, `ZZP;` read peaks, `ZZPnn;` peaks listed
+    /// per frame, `ZZP00;` no peaks)
+    fn parse_panadapter(&self, cmd: &str) -> Option<CatCommand> {
+        if cmd.len() == 2 {
+            return Some(CatCommand::ReadPanadapter);
+        }
+        if let Some(arg) = cmd.get(2..)?.strip_prefix('P') {
+            if arg.is_empty() {
+                return Some(CatCommand::ReadPanadapterPeaks);
+            }
+            let peaks = arg.get(..2).filter(|_| arg.len() == 2)?.parse().ok()?;
+            return Some(CatCommand::SetPanadapterPeaks(peaks));
+        }
+        let fps = cmd.get(2..4).filter(|_| cmd.len() == 4)?.parse().ok()?;
+        Some(CatCommand::SetPanadapterRate(fps))
+    }
+
+    /// Parse voice keyer playback (`PB;` read, `PBn;` play slot 1-4,
+    /// `PB0;` stop, `PBRsss;` repeat interval in seconds, 0 = once)
+    fn parse_voice_play(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadVoice),
+            "0" => Some(CatCommand::StopVoice),
+            arg if arg.len() == 4 && arg.starts_with('R') => {
+                let seconds: u16 = arg.get(1..)?.parse().ok()?;
+                Some(CatCommand::SetVoiceRepeat(seconds))
+            }
+            arg => Some(CatCommand::PlayVoice(voice_slot(arg)?)),
+        }
+    }
+
+    /// Parse voice keyer recording (`LM;` read storage, `LMn;` record
+    /// slot 1-4, `LM0;` stop, `LMDn;` delete slot n)
+    fn parse_voice_record(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadVoiceStorage),
+            "0" => Some(CatCommand::StopVoiceRecording),
+            arg => match arg.strip_prefix('D') {
+                Some(slot) => Some(CatCommand::DeleteVoice(voice_slot(slot)?)),
+                None => Some(CatCommand::RecordVoice(voice_slot(arg)?)),
+            },
+        }
+    }
+
+    /// Parse two-tone TX test (`ZX;` read, `ZX0;` stop, `ZX1;` start)
+    fn parse_tx_test(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadTxTest),
+            "0" => Some(CatCommand::SetTxTest(false)),
+            "1" => Some(CatCommand::SetTxTest(true)),
+            _ => None,
+        }
+    }
+
+    /// Parse antenna sweep (`ZW;` read, `ZW0;` stop, `ZWPiii;` read point,
+    /// `ZW1ssssssssssseeeeeeeeeeeppp;` start)
+    ///
+    /// A sweep runs from `s` to `e` Hz (11 digits each, as `FA`) in `ppp`
+    /// points.
+    fn parse_sweep(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..3) {
+            None => Some(CatCommand::ReadSweep),
+            Some("0") if cmd.len() == 3 => Some(CatCommand::StopSweep),
+            Some("P") if cmd.len() == 6 => Some(CatCommand::ReadSweepPoint(cmd[3..].parse().ok()?)),
+            Some("1") if cmd.len() == 28 => {
+                let start = Frequency::from_hz(cmd[3..14].parse().ok()?)?;
+                let end = Frequency::from_hz(cmd[14..25].parse().ok()?)?;
+                let points: u8 = cmd[25..].parse().ok()?;
+                (start.as_hz() < end.as_hz() && (2..=MAX_POINTS).contains(&usize::from(points)))
+                    .then_some(CatCommand::StartSweep { start, end, points })
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse battery gauge (`ZE;` read, `ZEL;` read low voltage,
+    /// `ZELvvvvv;` set the pack voltage in mV below which TX power is reduced)
+    fn parse_battery(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..)? {
+            "" => Some(CatCommand::ReadBattery),
+            "L" => Some(CatCommand::ReadLowBatteryVoltage),
+            rest if rest.len() == 6 && rest.starts_with('L') => {
+                Some(CatCommand::SetLowBatteryVoltage(rest[1..].parse().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse crystal correction (`ZK;` read, `ZKsnnnnnn;` set in parts per
+    /// billion, `ZKMeeeeeeeeeeemmmmmmmmmmm;` calibrate)
+    ///
+    /// Calibration takes the frequency an output was set to (`e`) and the
+    /// frequency it was measured at (`m`), 11 digits each as `FA`.
+    fn parse_xtal_correction(&self, cmd: &str) -> Option<CatCommand> {
+        match cmd.get(2..3) {
+            None => Some(CatCommand::ReadXtalCorrection),
+            Some("+" | "-") if cmd.len() == 9 => {
+                let correction = PpmCorrection::from_ppb(cmd[2..].parse().ok()?)?;
+                Some(CatCommand::SetXtalCorrection(correction))
+            }
+            Some("M") if cmd.len() == 25 => Some(CatCommand::CalibrateXtal {
+                expected: Frequency::from_hz(cmd[3..14].parse().ok()?)?,
+                measured: Frequency::from_hz(cmd[14..].parse().ok()?)?,
+            }),
+            _ => None,
+        }
+    }
+
     /// Clear the buffer
     pub fn clear(&mut self) {
         self.buffer.clear();
     }
 }
 
+/// Parse a manual notch number
+fn notch_index(digit: &str) -> Option<u8> {
+    let index: u8 = digit.parse().ok()?;
+    (usize::from(index) < NOTCH_COUNT).then_some(index)
+}
+
+/// Parse a voice message slot number (1-based on the wire, 0-based here)
+fn voice_slot(digit: &str) -> Option<u8> {
+    let slot: u8 = digit.parse().ok()?;
+    (1..=VOICE_SLOTS).contains(&usize::from(slot)).then(|| slot - 1)
+}
+
 impl Default for CatParser {
     fn default() -> Self {
         Self::new()
@@ -286,6 +897,18 @@ pub enum CatCommand {
     ReadVox,
     /// Set VOX state
     SetVox(bool),
+    /// Read VOX gain
+    ReadVoxGain,
+    /// Set VOX gain (0-100)
+    SetVoxGain(u8),
+    /// Read VOX delay
+    ReadVoxDelay,
+    /// Set VOX delay in milliseconds
+    SetVoxDelay(u16),
+    /// Read anti-VOX gain
+    ReadAntiVoxGain,
+    /// Set anti-VOX gain (0-100)
+    SetAntiVoxGain(u8),
     /// Read AGC setting
     ReadAgc,
     /// Set AGC setting
@@ -294,6 +917,34 @@ pub enum CatCommand {
     ReadNb,
     /// Set noise blanker state
     SetNb(bool),
+    /// Read whether a manual notch is on
+    ReadNotch(u8),
+    /// Switch a manual notch on or off
+    SetNotchEnabled(u8, bool),
+    /// Read a manual notch frequency and width
+    ReadNotchFrequency(u8),
+    /// Tune a manual notch
+    SetNotchFrequency {
+        /// Notch number (0 or 1)
+        index: u8,
+        /// Notch frequency in Hz
+        frequency_hz: u16,
+        /// Notch width in Hz, if changed
+        width_hz: Option<u16>,
+    },
+    /// Read an equalizer's switch and current curve
+    ReadEq(EqPath),
+    /// Switch an equalizer in or out
+    SetEqEnabled(EqPath, bool),
+    /// Set one band of an equalizer's current curve
+    SetEqBand {
+        /// RX or TX equalizer
+        path: EqPath,
+        /// Band (0 = lowest)
+        band: u8,
+        /// Gain in dB
+        gain_db: i8,
+    },
     /// Read preamp state
     ReadPreamp,
     /// Set preamp state
@@ -306,6 +957,177 @@ pub enum CatCommand {
     TuneUp,
     /// Tune down one step
     TuneDown,
+    /// Read number of stored files
+    ListFiles,
+    /// Read directory entry by list position
+    ReadFileInfo(u8),
+    /// Read a chunk of file data
+    ReadFile {
+        /// File identifier
+        id: u8,
+        /// Byte offset into the file
+        offset: u32,
+    },
+    /// Delete a stored file
+    DeleteFile(u8),
+    /// Read firmware version and capabilities (handshake)
+    ReadVersion,
+    /// Arm bootloader entry (first step)
+    ArmBootloader,
+    /// Confirm bootloader entry (second step)
+    EnterBootloader,
+    /// Read UTC date and time
+    ReadClock,
+    /// Set UTC date and time
+    SetClock(DateTime),
+    /// Read local time offset
+    ReadUtcOffset,
+    /// Set local time offset in minutes
+    SetUtcOffset(i16),
+    /// Read power-on self test summary
+    ReadSelfTest,
+    /// Read one power-on self test check
+    ReadSelfTestItem(PostItem),
+    /// Read RX/TX loopback test state and summary
+    ReadLoopbackTest,
+    /// Read one loopback test check
+    ReadLoopbackCheck(LoopbackCheck),
+    /// Run the loopback test
+    RunLoopbackTest,
+    /// Read CW send buffer state
+    ReadKeyBuffer,
+    /// Send CW text
+    SendCw(String<KEY_TEXT_LEN>),
+    /// Play a stored CW message (1-8)
+    PlayMessage(u8),
+    /// Abort CW message playback
+    AbortMessage,
+    /// Read a stored CW message (1-8)
+    ReadMessage(u8),
+    /// Store a CW message (1-8)
+    WriteMessage(u8, String<MESSAGE_LEN>),
+    /// Read next contest serial number
+    ReadSerial,
+    /// Set next contest serial number
+    SetSerial(u16),
+    /// Read station callsign
+    ReadCallsign,
+    /// Set station callsign
+    SetCallsign(String<CALLSIGN_LEN>),
+    /// Read startup policy
+    ReadStartupPolicy,
+    /// Set startup policy
+    SetStartupPolicy(StartupPolicy),
+    /// Store the current state as the fixed startup state
+    StoreStartupState,
+    /// Read IQ orientation correction
+    ReadIqOrientation,
+    /// Set IQ orientation correction (persisted)
+    SetIqOrientation(IqOrientation),
+    /// Read whether a DSP stage is bypassed
+    ReadStageBypass(DspStage),
+    /// Bypass a DSP stage or put it back in circuit
+    SetStageBypass(DspStage, bool),
+    /// Read S-meter (Kenwood 0-30 scale)
+    ReadSMeter,
+    /// Read calibrated signal level in dBm
+    ReadSignalDbm,
+    /// Read the audio SNR in the passband
+    ReadSnr,
+    /// Read the DTMF decoder mode
+    ReadDtmfMode,
+    /// Set the DTMF decoder mode
+    SetDtmfMode(DtmfMode),
+    /// Center the received tone (CW zero-beat, SSB passband)
+    AutoTune,
+    /// Read the TX policy verdict and override
+    ReadTxPolicy,
+    /// Set the user override of the band rules
+    SetTxOverride(bool),
+    /// Read keyer practice mode and its timing report
+    ReadPractice,
+    /// Turn keyer practice mode on (starting a new session) or off
+    SetPractice(bool),
+    /// Start or stop the band monitor, or give it a decoded spot
+    Monitor(MonitorCommand),
+    /// Read IQ capture state and sample count
+    ReadIqCapture,
+    /// Capture this many decimated IQ samples
+    CaptureIq(u16),
+    /// Read the captured IQ chunk at a sample offset
+    ReadIqChunk(u16),
+    /// Read the IQ balance calibration of a band
+    ReadIqCalibration(Band),
+    /// Set the IQ balance calibration of a band (persisted)
+    SetIqCalibration(Band, IqCalibration),
+    /// Read adaptive IQ balance and the image rejection estimate
+    ReadIqAdaptive,
+    /// Turn adaptive IQ balance on or off
+    SetIqAdaptive(bool),
+    /// Read panadapter stream rate and dropped frames
+    ReadPanadapter,
+    /// Set panadapter stream rate in frames per second (0 = off)
+    SetPanadapterRate(u8),
+    /// Read peaks listed per panadapter frame
+    ReadPanadapterPeaks,
+    /// Set peaks listed per panadapter frame (0 = off)
+    SetPanadapterPeaks(u8),
+    /// Read voice keyer state and repeat interval
+    ReadVoice,
+    /// Play a voice message slot (0-based)
+    PlayVoice(u8),
+    /// Stop voice message playback and repeats
+    StopVoice,
+    /// Set the pause between voice message repeats in seconds (0 = once)
+    SetVoiceRepeat(u16),
+    /// Read the voice message lengths and the recording time left
+    ReadVoiceStorage,
+    /// Record a voice message slot (0-based)
+    RecordVoice(u8),
+    /// Finish recording a voice message
+    StopVoiceRecording,
+    /// Delete a voice message slot (0-based)
+    DeleteVoice(u8),
+    /// Read the S-meter calibration of a band
+    ReadSmeterCalibration(Band),
+    /// Set the S-meter calibration of a band (persisted)
+    SetSmeterCalibration(Band, SmeterCalibration),
+    /// Read two-tone TX test state and the last IMD measurement
+    ReadTxTest,
+    /// Start or stop the two-tone TX test
+    SetTxTest(bool),
+    /// Read antenna sweep progress
+    ReadSweep,
+    /// Start an antenna sweep
+    StartSweep {
+        /// First frequency
+        start: Frequency,
+        /// Last frequency
+        end: Frequency,
+        /// Number of points
+        points: u8,
+    },
+    /// Stop the antenna sweep
+    StopSweep,
+    /// Read one measured sweep point
+    ReadSweepPoint(u8),
+    /// Read battery state of charge, voltage and current
+    ReadBattery,
+    /// Read the pack voltage below which TX power is reduced
+    ReadLowBatteryVoltage,
+    /// Set the pack voltage in mV below which TX power is reduced (persisted)
+    SetLowBatteryVoltage(u16),
+    /// Read the Si5351 crystal correction
+    ReadXtalCorrection,
+    /// Set the Si5351 crystal correction (persisted)
+    SetXtalCorrection(PpmCorrection),
+    /// Calibrate the crystal from an output measured against a reference
+    CalibrateXtal {
+        /// Frequency the output was set to
+        expected: Frequency,
+        /// Frequency it was measured at
+        measured: Frequency,
+    },
     /// Unknown/unparsed command
     Unknown(String<4>),
 }
@@ -321,6 +1143,7 @@ impl defmt::Format for CatCommand {
             Self::ReadStatus => defmt::write!(f, "ReadStatus"),
             Self::ReadId => defmt::write!(f, "ReadId"),
             Self::Transmit(tx) => defmt::write!(f, "TX({})", tx),
+            Self::ReadFile { id, offset } => defmt::write!(f, "ReadFile({}, {})", id, offset),
             _ => defmt::write!(f, "CAT(...)"),
         }
     }
@@ -359,6 +1182,9 @@ impl CatCommand {
                     None
                 }
             }
+            Self::CaptureIq(count) => Some(RadioEvent::CaptureIq(*count)),
+            Self::SetIqAdaptive(on) => Some(RadioEvent::SetIqAdaptive(*on)),
+            Self::StoreStartupState => Some(RadioEvent::StoreState),
             Self::TuneUp => Some(RadioEvent::Tune(1)),
             Self::TuneDown => Some(RadioEvent::Tune(-1)),
             _ => None,
@@ -451,6 +1277,578 @@ impl CatResponse {
         );
     }
 
+    /// Format file count response
+    pub fn file_count(&mut self, count: usize) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZFL{count:02};"));
+    }
+
+    /// Format directory entry response
+    ///
+    /// `ZFInniikllllllllllname;` with list position, file id, kind
+    /// code and 10-digit length
+    pub fn file_info(&mut self, position: u8, entry: &FileEntry) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZFI{:02}{:02}{}{:010}{};",
+                position,
+                entry.id,
+                entry.kind.as_char(),
+                entry.length,
+                entry.name
+            ),
+        );
+    }
+
+    /// Format file data response (hex encoded, empty at end of file)
+    pub fn file_data(&mut self, id: u8, offset: u32, data: &[u8]) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZFR{id:02}{offset:010}"));
+        for byte in data.iter().take(FILE_CHUNK_LEN) {
+            let _ = core::fmt::write(&mut self.buffer, format_args!("{byte:02X}"));
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format version handshake response
+    ///
+    /// `ZVmmnnppvvcccc;` with major, minor, patch, protocol version
+    /// and hex capability bits
+    pub fn version(&mut self, version: FirmwareVersion, protocol: u8, capabilities: u16) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZV{:02}{:02}{:02}{:02}{:04X};",
+                version.major, version.minor, version.patch, protocol, capabilities
+            ),
+        );
+    }
+
+    /// Format bootloader request acknowledgement (1 = armed, 2 = entering)
+    pub fn bootloader(&mut self, step: u8) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZB{step};"));
+    }
+
+    /// Format clock response (`ZTyyyymmddhhmmss;`, UTC)
+    pub fn clock(&mut self, time: &DateTime) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZT{:04}{:02}{:02}{:02}{:02}{:02};",
+                time.year, time.month, time.day, time.hour, time.minute, time.second
+            ),
+        );
+    }
+
+    /// Format local time offset response (`ZO+hhmm;`)
+    pub fn utc_offset(&mut self, minutes: i16) {
+        self.buffer.clear();
+        let sign = if minutes < 0 { '-' } else { '+' };
+        let abs = minutes.unsigned_abs();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZO{}{:02}{:02};", sign, abs / 60, abs % 60),
+        );
+    }
+
+    /// Format self test summary (`ZP` and one `P`/`F`/`-` per check)
+    pub fn self_test(&mut self, report: &PostReport) {
+        self.buffer.clear();
+        let _ = self.buffer.push_str("ZP");
+        for (_, result) in report.iter() {
+            let _ = self.buffer.push(result.as_char());
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format self test detail (`ZPnrvvvvv;` with check, result and raw value)
+    pub fn self_test_item(&mut self, report: &PostReport, item: PostItem) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZP{}{}{:05};",
+                item.index(),
+                report.result(item).as_char(),
+                report.value(item)
+            ),
+        );
+    }
+
+    /// Format loopback test summary (`ZPLn` and one `P`/`F`/`-` per check)
+    ///
+    /// `n` is 1 while the test runs; the checks read `-` until it has
+    /// completed once.
+    pub fn loopback_test(&mut self, active: bool, report: Option<&LoopbackReport>) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZPL{}", u8::from(active)));
+        for check in LoopbackCheck::ALL {
+            let result = report.map_or(PostResult::NotRun, |report| report.result(check));
+            let _ = self.buffer.push(result.as_char());
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format loopback test detail (`ZPLnrsvvvv;` with check, result and
+    /// value signed in 0.1 dB)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn loopback_check(&mut self, report: &LoopbackReport, check: LoopbackCheck) {
+        let value = (report.value_db(check) * 10.0).clamp(-9999.0, 9999.0);
+        let tenths = (if value < 0.0 { value - 0.5 } else { value + 0.5 }) as i16;
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZPL{}{}{:+05};",
+                check.index(),
+                report.result(check).as_char(),
+                tenths
+            ),
+        );
+    }
+
+    /// Format CW buffer state (`KY0;` space available, `KY1;` full)
+    pub fn key_buffer(&mut self, full: bool) {
+        self.buffer.clear();
+        let _ = self.buffer.push_str(if full { "KY1;" } else { "KY0;" });
+    }
+
+    /// Format stored message (`ZMRntext;`)
+    pub fn message(&mut self, index: u8, text: &str) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZMR{index}{text};"));
+    }
+
+    /// Format contest serial number (`ZNnnnn;`)
+    pub fn serial(&mut self, serial: u16) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZN{serial:04};"));
+    }
+
+    /// Format station callsign (`ZCcall;`)
+    pub fn callsign(&mut self, call: &str) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZC{call};"));
+    }
+
+    /// Format startup policy (`ZSn;`)
+    pub fn startup_policy(&mut self, policy: StartupPolicy) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZS{};", policy.code()));
+    }
+
+    /// Format IQ orientation (`ZQn;`)
+    pub fn iq_orientation(&mut self, orientation: IqOrientation) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZQ{};", orientation.code()));
+    }
+
+    /// Format DSP stage bypass (`ZDsn;`)
+    pub fn stage_bypass(&mut self, stage: DspStage, bypassed: bool) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZD{}{};", stage.code(), u8::from(bypassed)),
+        );
+    }
+
+    /// Format manual notch switch (`BCnp;`)
+    pub fn notch(&mut self, index: u8, enabled: bool) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("BC{index}{};", u8::from(enabled)),
+        );
+    }
+
+    /// Format manual notch tuning (`BPnffffwwww;`, Hz)
+    pub fn notch_frequency(&mut self, index: u8, frequency_hz: u16, width_hz: u16) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("BP{index}{:04}{:04};", frequency_hz.min(9999), width_hz.min(9999)),
+        );
+    }
+
+    /// Format graphic equalizer state (`EQtp` then `sgg` per band, e.g.
+    /// `EQ01-03+00+00+02+01;`)
+    pub fn eq(&mut self, path: EqPath, enabled: bool, curve: &EqCurve) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("EQ{}{}", path.code(), u8::from(enabled)),
+        );
+        for gain in curve.gains_db() {
+            let _ = core::fmt::write(&mut self.buffer, format_args!("{gain:+03}"));
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format VOX gain (`VGnnn;`)
+    pub fn vox_gain(&mut self, gain: u8) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("VG{gain:03};"));
+    }
+
+    /// Format VOX delay (`VDnnnn;`)
+    pub fn vox_delay(&mut self, ms: u16) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("VD{ms:04};"));
+    }
+
+    /// Format anti-VOX gain (`ZAnnn;`)
+    pub fn anti_vox_gain(&mut self, gain: u8) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZA{gain:03};"));
+    }
+
+    /// Format S-meter on the Kenwood scale (`SM0nnnn;`)
+    ///
+    /// 0-15 covers S0 to S9, then each step is 4 dB up to S9+60 at 30.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn smeter(&mut self, s_units: f32) {
+        let steps = if s_units <= 9.0 {
+            s_units.max(0.0) * 15.0 / 9.0
+        } else {
+            15.0 + (s_units - 9.0) * 1.5
+        };
+        let level = ((steps + 0.5) as u16).min(30);
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("SM0{level:04};"));
+    }
+
+    /// Format calibrated signal level in whole dBm (`ZR-073;`)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn signal_dbm(&mut self, dbm: f32) {
+        let value = (if dbm < 0.0 { dbm - 0.5 } else { dbm + 0.5 }) as i16;
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZR{value:+04};"));
+    }
+
+    /// Format the audio SNR in whole dB (`ZU+012;`)
+    ///
+    /// A reading of `None` (nothing measured yet) is sent as `ZU;`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn snr(&mut self, snr_db: Option<f32>) {
+        self.buffer.clear();
+        if let Some(snr) = snr_db {
+            let value = (if snr < 0.0 { snr - 0.5 } else { snr + 0.5 }) as i16;
+            let _ = core::fmt::write(&mut self.buffer, format_args!("ZU{value:+04};"));
+        } else {
+            let _ = self.buffer.push_str("ZU;");
+        }
+    }
+
+    /// Format the DTMF decoder mode (`ZG2;`)
+    pub fn dtmf_mode(&mut self, mode: DtmfMode) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZG{};", mode.code()));
+    }
+
+    /// Format a decoded DTMF digit for the log (`ZH5;`), sent unsolicited
+    pub fn dtmf_digit(&mut self, digit: char) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZH{digit};"));
+    }
+
+    /// Format the dial shift made by an auto-tune in Hz (`ZI+0060;`)
+    ///
+    /// `None` (no tone found, or already centered) is sent as `ZI;`.
+    pub fn auto_tune(&mut self, shift_hz: Option<i32>) {
+        self.buffer.clear();
+        if let Some(shift) = shift_hz {
+            let _ = core::fmt::write(&mut self.buffer, format_args!("ZI{shift:+05};"));
+        } else {
+            let _ = self.buffer.push_str("ZI;");
+        }
+    }
+
+    /// Format the TX policy (`ZJosr;`)
+    ///
+    /// `o` is the user override (0/1), `s` the verdict (0 clear,
+    /// 1 warning, 2 denied) and `r` the reason (0 none, 1 out of band,
+    /// 2 band edge, 3 mode segment, 4 external inhibit, 5 practice).
+    pub fn tx_policy(&mut self, policy: &TxPolicy) {
+        self.buffer.clear();
+        let verdict = policy.verdict();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZJ{}{}{};",
+                u8::from(policy.is_override()),
+                verdict.code(),
+                verdict.reason().map_or(0, TxReason::code)
+            ),
+        );
+    }
+
+    /// Format the keyer practice report (`ZYpwwttaaarr;`)
+    ///
+    /// `p` is practice mode (0/1), `ww` the measured and `tt` the set
+    /// speed in WPM, `aaa` the timing accuracy in percent and `rr` the
+    /// dah to dit ratio in tenths.
+    pub fn practice(&mut self, enabled: bool, report: &PracticeReport) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZY{}{:02}{:02}{:03}{:02};",
+                u8::from(enabled),
+                report.wpm,
+                report.target_wpm,
+                report.accuracy,
+                report.dah_ratio
+            ),
+        );
+    }
+
+    /// Format IQ capture state (`IQsnnnn;`)
+    ///
+    /// `s` is 0 idle, 1 capturing or 2 complete and `nnnn` the samples
+    /// captured so far.
+    pub fn iq_capture(&mut self, capture: &IqCapture) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("IQ{}{:04};", capture.state().code(), capture.len()),
+        );
+    }
+
+    /// Format a captured IQ chunk (`IQDoooodata;`)
+    ///
+    /// `oooo` is the offset of the first sample and `data` up to
+    /// [`IQ_CHUNK_SAMPLES`] little-endian 16-bit I, Q pairs in Base64,
+    /// empty past the end of the capture.
+    pub fn iq_chunk(&mut self, capture: &IqCapture, offset: usize) {
+        let mut bytes: Vec<u8, { IQ_CHUNK_SAMPLES * 4 }> = Vec::new();
+        for [i, q] in capture.chunk(offset) {
+            let _ = bytes.extend_from_slice(&i.to_le_bytes());
+            let _ = bytes.extend_from_slice(&q.to_le_bytes());
+        }
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("IQD{offset:04}"));
+        push_base64(&mut self.buffer, &bytes);
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format IQ balance calibration (`IQBbsgggspppp;`)
+    pub fn iq_calibration(&mut self, band: Band, calibration: IqCalibration) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "IQB{}{:+04}{:+05};",
+                band.index(),
+                calibration.gain_hundredths_db(),
+                calibration.phase_hundredths_deg()
+            ),
+        );
+    }
+
+    /// Format adaptive IQ balance state (`IQAnrr;`)
+    ///
+    /// `n` is 1 while adapting and `rr` the estimated image rejection in
+    /// dB, `--` until measured.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn iq_adaptive(&mut self, balance: &IqBalance) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("IQA{}", u8::from(balance.is_adaptive())),
+        );
+        match balance.image_rejection_db() {
+            Some(db) => {
+                let db = (db + 0.5).clamp(0.0, 99.0) as u8;
+                let _ = core::fmt::write(&mut self.buffer, format_args!("{db:02}"));
+            }
+            None => {
+                let _ = self.buffer.push_str("--");
+            }
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format panadapter stream state (`ZZffddddd;`)
+    ///
+    /// `ff` is the rate in frames per second (00 when stopped) and
+    /// `ddddd` the frames dropped with the USB endpoint busy.
+    pub fn panadapter(&mut self, stream: &PanadapterStream) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZZ{:02}{:05};", stream.rate(), stream.dropped() % 100_000),
+        );
+    }
+
+    /// Format peaks listed per panadapter frame (`ZZPnn;`, 00 when off)
+    pub fn panadapter_peaks(&mut self, stream: &PanadapterStream) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZZP{:02};", stream.peaks()));
+    }
+
+    /// Format a character decoded in practice mode (`ZYTA;`), sent
+    /// unsolicited
+    ///
+    /// A decoded `;` would end the response early and is sent as `ZYT:;`.
+    pub fn practice_char(&mut self, ch: char) {
+        self.buffer.clear();
+        let ch = if ch == ';' { ':' } else { ch };
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZYT{ch};"));
+    }
+
+    /// Format the voice keyer state (`PBsnrrr;`)
+    ///
+    /// `s` is the state (0 idle, 1 recording, 2 playing, 3 waiting to
+    /// repeat), `n` the slot in use (1-4, 0 none) and `rrr` the repeat
+    /// interval in seconds.
+    pub fn voice_status(&mut self, voice: &VoiceKeyer) {
+        self.buffer.clear();
+        let state = voice.state();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "PB{}{}{:03};",
+                state.code(),
+                state.slot().map_or(0, |slot| u16::from(slot) + 1),
+                voice.repeat_s()
+            ),
+        );
+    }
+
+    /// Format the voice message storage (`LMtttt111222333444;`)
+    ///
+    /// `tttt` is the recording time left in seconds (at most 9999), then
+    /// each slot's message length in tenths of a second (000 if empty).
+    pub fn voice_storage(&mut self, voice: &VoiceKeyer, remaining_ms: u32) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("LM{:04}", (remaining_ms / 1000).min(9999)),
+        );
+        #[allow(clippy::cast_possible_truncation)]
+        for slot in 0..VOICE_SLOTS as u8 {
+            let tenths = voice.message_ms(slot).map_or(0, |ms| (ms + 50) / 100);
+            let _ = core::fmt::write(&mut self.buffer, format_args!("{tenths:03}"));
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format S-meter calibration (`ZLbsoooSSSS;`)
+    pub fn smeter_calibration(&mut self, band: Band, calibration: SmeterCalibration) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "ZL{}{:+04}{:04};",
+                band.index(),
+                calibration.offset_tenths_db(),
+                calibration.slope_permille()
+            ),
+        );
+    }
+
+    /// Format two-tone TX test state and IMD (`ZXnsiiisjjjsbbb;`)
+    ///
+    /// `n` is 1 while the test runs, then the stronger 3rd and 5th order
+    /// products and the high tone level relative to the low tone, each
+    /// signed in 0.1 dB. The levels are left out until measured (`ZXn;`).
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn tx_test(&mut self, active: bool, report: Option<ImdReport>) {
+        let tenths = |db: f32| {
+            let value = (db * 10.0).clamp(-999.0, 999.0);
+            (if value < 0.0 { value - 0.5 } else { value + 0.5 }) as i16
+        };
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZX{}", u8::from(active)));
+        if let Some(report) = report {
+            let _ = core::fmt::write(
+                &mut self.buffer,
+                format_args!(
+                    "{:+04}{:+04}{:+04}",
+                    tenths(report.imd3_dbc),
+                    tenths(report.imd5_dbc),
+                    tenths(report.balance_db)
+                ),
+            );
+        }
+        let _ = self.buffer.push(';');
+    }
+
+    /// Format antenna sweep progress (`ZWnpppttt;`)
+    ///
+    /// `n` is 1 while the sweep runs, `ppp` the points measured and `ttt`
+    /// the points in the sweep.
+    pub fn sweep_status(&mut self, active: bool, measured: usize, total: usize) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZW{}{measured:03}{total:03};", u8::from(active)),
+        );
+    }
+
+    /// Format one antenna sweep point (`ZWPiiifffffffffffssss;`)
+    ///
+    /// Sent for each point as it is measured, and in reply to `ZWPiii;`.
+    /// `f` is the frequency in Hz and `ssss` the SWR in 1/100, 9999 when
+    /// off scale.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn sweep_point(&mut self, index: usize, point: &SweepPoint) {
+        let swr = (point.swr() * 100.0 + 0.5).clamp(100.0, 9999.0) as u16;
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZWP{index:03}{:011}{swr:04};", point.frequency.as_hz()),
+        );
+    }
+
+    /// Format battery gauge (`ZEpppvvvvvsiiii;`)
+    ///
+    /// `ppp` is the state of charge in percent (`---` until estimated),
+    /// `vvvvv` the pack voltage in mV and `siiii` the current in mA,
+    /// positive when discharging.
+    pub fn battery(&mut self, soc_percent: Option<u8>, voltage_mv: u32, current_ma: i32) {
+        self.buffer.clear();
+        let _ = self.buffer.push_str("ZE");
+        let _ = match soc_percent {
+            Some(soc) => core::fmt::write(&mut self.buffer, format_args!("{:03}", soc.min(100))),
+            None => core::fmt::write(&mut self.buffer, format_args!("---")),
+        };
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!(
+                "{:05}{:+05};",
+                voltage_mv.min(99_999),
+                current_ma.clamp(-9999, 9999)
+            ),
+        );
+    }
+
+    /// Format low battery voltage (`ZELvvvvv;`)
+    pub fn low_battery_voltage(&mut self, voltage_mv: u16) {
+        self.buffer.clear();
+        let _ = core::fmt::write(&mut self.buffer, format_args!("ZEL{voltage_mv:05};"));
+    }
+
+    /// Format crystal correction in parts per billion (`ZKsnnnnnn;`)
+    pub fn xtal_correction(&mut self, correction: PpmCorrection) {
+        self.buffer.clear();
+        let _ = core::fmt::write(
+            &mut self.buffer,
+            format_args!("ZK{:+07};", correction.ppb()),
+        );
+    }
+
+    /// Format error response for an unsupported or failed command
+    pub fn error(&mut self) {
+        self.buffer.clear();
+        let _ = self.buffer.push_str("?;");
+    }
+
     /// Get the response string
     #[must_use]
     pub fn as_str(&self) -> &str {
@@ -474,3 +1872,23 @@ impl Default for CatResponse {
         Self::new()
     }
 }
+
+/// Append `data` in standard Base64 (with padding)
+fn push_base64<const N: usize>(out: &mut String<N>, data: &[u8]) {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for group in data.chunks(3) {
+        let bits = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (n, &byte)| bits | u32::from(byte) << (16 - 8 * n));
+        for n in 0..4 {
+            let ch = if n <= group.len() {
+                ALPHABET[((bits >> (18 - 6 * n)) & 0x3F) as usize]
+            } else {
+                b'='
+            };
+            let _ = out.push(char::from(ch));
+        }
+    }
+}
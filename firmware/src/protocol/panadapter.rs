@@ -0,0 +1,525 @@
+//! Panadapter Stream
+//!
+//! Binary protocol streaming spectrum rows to the host over the CAT
+//! serial port, so the web UI can show the radio's own panadapter. Frames
+//! are interleaved with CAT text on the same port; their start byte never
+//! occurs in CAT text, so a host splits the stream on it.
+//!
+//! # Framing
+//!
+//! ```text
+//! 0xFE | 'P' | seq u16 | time u32 | center u32 | span u32 | bins u8 | power[bins] | crc8
+//! ```
+//!
+//! Multi-byte fields are little-endian. `time` is the row's timestamp in
+//! milliseconds, `center` and `span` are in Hz and each power byte is
+//! 0 (-100 dB) to 100 (0 dB), as in [`WaterfallRow`]. The CRC is the
+//! [remote head](super::remote_head) CRC-8 over everything after the
+//! two start bytes.
+//!
+//! # Peak lists
+//!
+//! With peaks on, each spectrum frame is followed by a list of its
+//! strongest signals, strongest first, so the host can list them and tune
+//! to one without searching the spectrum itself:
+//!
+//! ```text
+//! 0xFE | 'K' | seq u16 | center u32 | count u8 | count * (offset i32 | level i8 | width u16) | crc8
+//! ```
+//!
+//! `seq` is that of the spectrum frame the peaks were found in, `offset`
+//! is the peak's frequency in Hz from `center`, `level` is in dB (-100 to
+//! 0) and `width` is the width 6 dB down in Hz.
+//!
+//! # Rate and drops
+//!
+//! The radio sends at a set rate from [`MIN_FPS`] to [`MAX_FPS`] (or not
+//! at all). A frame that falls due while the USB endpoint is still busy
+//! is dropped rather than queued, so the display never lags behind the
+//! band; it still takes a sequence number, and the host counts gaps in
+//! the sequence as dropped frames. Frames lost to a bad CRC show up as
+//! gaps too.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // CAT: ZZ15; streams at 15 frames per second
+//! stream.set_rate(15);
+//! // after each spectrum update
+//! if stream.poll(now_ms, cdc.write_ready()) {
+//!     cdc.write(&stream.encode(&row, state.frequency(), span_hz));
+//!     if let Some(peaks) = stream.encode_peaks(&row, state.frequency(), span_hz) {
+//!         cdc.write(&peaks);
+//!     }
+//! }
+//! ```
+
+use heapless::Vec;
+use sdr_dsp_core::spectrum::{find_peaks, SpectrumPeak, DEFAULT_PEAK_THRESHOLD_DB};
+
+use super::remote_head::crc8;
+use crate::dsp::spectrum::WaterfallRow;
+use crate::types::Frequency;
+
+/// Frame start byte (never sent in CAT text)
+pub const FRAME_START: u8 = 0xFE;
+
+/// Frame type byte following the start byte
+pub const FRAME_KIND: u8 = b'P';
+
+/// Power bins per frame
+pub const FRAME_BINS: usize = 128;
+
+/// Header bytes after the start bytes (sequence to bin count)
+const HEADER_LEN: usize = 15;
+
+/// Maximum encoded frame length
+pub const MAX_FRAME_LEN: usize = 2 + HEADER_LEN + FRAME_BINS + 1;
+
+/// Peak list frame type byte following the start byte
+pub const PEAK_KIND: u8 = b'K';
+
+/// Most peaks per peak list frame
+pub const MAX_FRAME_PEAKS: usize = 8;
+
+/// Peak list header bytes after the start bytes (sequence to count)
+const PEAK_HEADER_LEN: usize = 7;
+
+/// Bytes per peak in a peak list
+const PEAK_LEN: usize = 7;
+
+/// Maximum encoded peak list frame length
+pub const MAX_PEAK_FRAME_LEN: usize = 2 + PEAK_HEADER_LEN + MAX_FRAME_PEAKS * PEAK_LEN + 1;
+
+/// Slowest streaming rate in frames per second
+pub const MIN_FPS: u8 = 10;
+
+/// Fastest streaming rate in frames per second
+pub const MAX_FPS: u8 = 30;
+
+/// One spectrum frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanadapterFrame {
+    /// Sequence number (wraps)
+    pub seq: u16,
+    /// Row timestamp in milliseconds
+    pub time_ms: u32,
+    /// Centre frequency in Hz
+    pub center_hz: u32,
+    /// Span in Hz
+    pub span_hz: u32,
+    /// Power per bin, 0 (-100 dB) to 100 (0 dB)
+    pub power: Vec<u8, FRAME_BINS>,
+}
+
+impl PanadapterFrame {
+    /// Build a frame from a waterfall row
+    #[must_use]
+    pub fn from_row(seq: u16, row: &WaterfallRow, center: Frequency, span_hz: u32) -> Self {
+        let mut power = Vec::new();
+        for col in 0..FRAME_BINS {
+            let _ = power.push(row.power_at(col).min(100));
+        }
+        Self {
+            seq,
+            time_ms: row.timestamp,
+            center_hz: center.as_hz(),
+            span_hz,
+            power,
+        }
+    }
+
+    /// Encode with start bytes and CRC
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8, MAX_FRAME_LEN> {
+        let mut frame = Vec::new();
+        let mut put = |bytes: &[u8]| {
+            let _ = frame.extend_from_slice(bytes);
+        };
+        put(&[FRAME_START, FRAME_KIND]);
+        put(&self.seq.to_le_bytes());
+        put(&self.time_ms.to_le_bytes());
+        put(&self.center_hz.to_le_bytes());
+        put(&self.span_hz.to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        put(&[self.power.len() as u8]);
+        put(&self.power);
+        let crc = crc8(&frame[2..]);
+        let _ = frame.push(crc);
+        frame
+    }
+
+    /// Decode the bytes after the start bytes, CRC included
+    fn decode(body: &[u8]) -> Option<Self> {
+        let (&crc, body) = body.split_last()?;
+        if crc8(body) != crc {
+            return None;
+        }
+        let word = |at: usize| Some(u32::from_le_bytes(body.get(at..at + 4)?.try_into().ok()?));
+        let bins = usize::from(*body.get(HEADER_LEN - 1)?);
+        let mut power = Vec::new();
+        power
+            .extend_from_slice(body.get(HEADER_LEN..HEADER_LEN + bins)?)
+            .ok()?;
+        Some(Self {
+            seq: u16::from_le_bytes(body.get(..2)?.try_into().ok()?),
+            time_ms: word(2)?,
+            center_hz: word(6)?,
+            span_hz: word(10)?,
+            power,
+        })
+    }
+}
+
+/// Signal in a peak list
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeakEntry {
+    /// Frequency from the centre in Hz
+    pub offset_hz: i32,
+    /// Level in dB, -100 to 0
+    pub level_db: i8,
+    /// Width 6 dB down in Hz
+    pub width_hz: u16,
+}
+
+/// Strongest signals in one spectrum frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeakListFrame {
+    /// Sequence number of the spectrum frame
+    pub seq: u16,
+    /// Centre frequency in Hz
+    pub center_hz: u32,
+    /// Peaks, strongest first
+    pub peaks: Vec<PeakEntry, MAX_FRAME_PEAKS>,
+}
+
+impl PeakListFrame {
+    /// Find up to `max` peaks (at most [`MAX_FRAME_PEAKS`]) in a
+    /// waterfall row
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn from_row(
+        seq: u16,
+        row: &WaterfallRow,
+        center: Frequency,
+        span_hz: u32,
+        max: usize,
+    ) -> Self {
+        let levels: [f32; FRAME_BINS] =
+            core::array::from_fn(|col| f32::from(row.power_at(col).min(100)) - 100.0);
+        let mut found = [SpectrumPeak::default(); MAX_FRAME_PEAKS];
+        let bin_width = span_hz as f32 / FRAME_BINS as f32;
+        let count = find_peaks(
+            &levels,
+            bin_width,
+            DEFAULT_PEAK_THRESHOLD_DB,
+            &mut found[..max.min(MAX_FRAME_PEAKS)],
+        );
+        let half_span = span_hz as f32 / 2.0;
+        let mut peaks = Vec::new();
+        for peak in &found[..count] {
+            let _ = peaks.push(PeakEntry {
+                offset_hz: (peak.frequency_hz - half_span) as i32,
+                level_db: peak.level_db as i8,
+                width_hz: peak.width_hz.min(f32::from(u16::MAX)) as u16,
+            });
+        }
+        Self {
+            seq,
+            center_hz: center.as_hz(),
+            peaks,
+        }
+    }
+
+    /// Encode with start bytes and CRC
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8, MAX_PEAK_FRAME_LEN> {
+        let mut frame = Vec::new();
+        let mut put = |bytes: &[u8]| {
+            let _ = frame.extend_from_slice(bytes);
+        };
+        put(&[FRAME_START, PEAK_KIND]);
+        put(&self.seq.to_le_bytes());
+        put(&self.center_hz.to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        put(&[self.peaks.len() as u8]);
+        for peak in &self.peaks {
+            put(&peak.offset_hz.to_le_bytes());
+            put(&peak.level_db.to_le_bytes());
+            put(&peak.width_hz.to_le_bytes());
+        }
+        let crc = crc8(&frame[2..]);
+        let _ = frame.push(crc);
+        frame
+    }
+
+    /// Decode the bytes after the start bytes, CRC included
+    fn decode(body: &[u8]) -> Option<Self> {
+        let (&crc, body) = body.split_last()?;
+        if crc8(body) != crc {
+            return None;
+        }
+        let count = usize::from(*body.get(PEAK_HEADER_LEN - 1)?);
+        let list = body.get(PEAK_HEADER_LEN..PEAK_HEADER_LEN + count * PEAK_LEN)?;
+        let mut peaks = Vec::new();
+        for entry in list.chunks_exact(PEAK_LEN) {
+            peaks
+                .push(PeakEntry {
+                    offset_hz: i32::from_le_bytes(entry[..4].try_into().ok()?),
+                    level_db: i8::from_le_bytes([entry[4]]),
+                    width_hz: u16::from_le_bytes(entry[5..].try_into().ok()?),
+                })
+                .ok()?;
+        }
+        Some(Self {
+            seq: u16::from_le_bytes(body.get(..2)?.try_into().ok()?),
+            center_hz: u32::from_le_bytes(body.get(2..6)?.try_into().ok()?),
+            peaks,
+        })
+    }
+}
+
+/// Radio side of the stream: rate, sequence and drop accounting
+#[derive(Clone, Debug, Default)]
+pub struct PanadapterStream {
+    /// Frames per second (0 = off)
+    fps: u8,
+    /// Time the last frame fell due in milliseconds
+    last_ms: Option<u32>,
+    /// Sequence number of the next frame
+    seq: u16,
+    /// Frames sent
+    sent: u32,
+    /// Frames dropped with the endpoint busy
+    dropped: u32,
+    /// Peaks listed after each frame (0 = none)
+    peaks: u8,
+}
+
+impl PanadapterStream {
+    /// Create a stopped stream
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fps: 0,
+            last_ms: None,
+            seq: 0,
+            sent: 0,
+            dropped: 0,
+            peaks: 0,
+        }
+    }
+
+    /// Set the rate in frames per second (0 stops, others are clamped to
+    /// [`MIN_FPS`]..=[`MAX_FPS`])
+    pub fn set_rate(&mut self, fps: u8) {
+        self.fps = if fps == 0 {
+            0
+        } else {
+            fps.clamp(MIN_FPS, MAX_FPS)
+        };
+        self.last_ms = None;
+    }
+
+    /// Get the rate in frames per second (0 when stopped)
+    #[must_use]
+    pub const fn rate(&self) -> u8 {
+        self.fps
+    }
+
+    /// Check if the stream is running
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.fps > 0
+    }
+
+    /// Check whether a frame is due at `now_ms`
+    ///
+    /// Returns true when one should be sent now, followed by
+    /// [`PanadapterStream::encode`]. A frame due while the endpoint is
+    /// busy (`ready` false) is dropped and its sequence number skipped.
+    pub fn poll(&mut self, now_ms: u32, ready: bool) -> bool {
+        if self.fps == 0 {
+            return false;
+        }
+        let interval = 1000 / u32::from(self.fps);
+        match self.last_ms {
+            Some(last) if now_ms.wrapping_sub(last) < interval => return false,
+            // Keep the cadence, but never burst to catch up after a stall
+            Some(last) if now_ms.wrapping_sub(last) < 2 * interval => {
+                self.last_ms = Some(last.wrapping_add(interval));
+            }
+            _ => self.last_ms = Some(now_ms),
+        }
+        if !ready {
+            self.seq = self.seq.wrapping_add(1);
+            self.dropped = self.dropped.saturating_add(1);
+            return false;
+        }
+        true
+    }
+
+    /// Encode the next frame from a waterfall row
+    pub fn encode(
+        &mut self,
+        row: &WaterfallRow,
+        center: Frequency,
+        span_hz: u32,
+    ) -> Vec<u8, MAX_FRAME_LEN> {
+        let frame = PanadapterFrame::from_row(self.seq, row, center, span_hz);
+        self.seq = self.seq.wrapping_add(1);
+        self.sent = self.sent.saturating_add(1);
+        frame.encode()
+    }
+
+    /// Set the peaks listed after each frame (0 stops, others are
+    /// limited to [`MAX_FRAME_PEAKS`])
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn set_peaks(&mut self, peaks: u8) {
+        self.peaks = peaks.min(MAX_FRAME_PEAKS as u8);
+    }
+
+    /// Get the peaks listed after each frame (0 when off)
+    #[must_use]
+    pub const fn peaks(&self) -> u8 {
+        self.peaks
+    }
+
+    /// Encode the peak list for the frame just encoded, if peaks are on
+    #[must_use]
+    pub fn encode_peaks(
+        &self,
+        row: &WaterfallRow,
+        center: Frequency,
+        span_hz: u32,
+    ) -> Option<Vec<u8, MAX_PEAK_FRAME_LEN>> {
+        if self.peaks == 0 {
+            return None;
+        }
+        let seq = self.seq.wrapping_sub(1);
+        let frame = PeakListFrame::from_row(seq, row, center, span_hz, usize::from(self.peaks));
+        Some(frame.encode())
+    }
+
+    /// Get the number of frames sent
+    #[must_use]
+    pub const fn sent(&self) -> u32 {
+        self.sent
+    }
+
+    /// Get the number of frames dropped with the endpoint busy
+    #[must_use]
+    pub const fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+/// Item split out of the serial stream by [`PanadapterDecoder`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamItem {
+    /// A byte of CAT text
+    Text(u8),
+    /// A complete, valid spectrum frame
+    Frame(PanadapterFrame),
+    /// A complete, valid peak list
+    Peaks(PeakListFrame),
+}
+
+/// Host side of the stream: splits frames from CAT text and counts drops
+#[derive(Clone, Debug, Default)]
+pub struct PanadapterDecoder {
+    /// Frame bytes after the start byte
+    body: Vec<u8, { MAX_FRAME_LEN - 1 }>,
+    /// Inside a frame
+    in_frame: bool,
+    /// Sequence number expected next
+    next_seq: Option<u16>,
+    /// Frames missing from the sequence
+    dropped: u32,
+    /// Frames discarded for a bad CRC or length
+    errors: u32,
+}
+
+impl PanadapterDecoder {
+    /// Create a decoder expecting CAT text
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            body: Vec::new(),
+            in_frame: false,
+            next_seq: None,
+            dropped: 0,
+            errors: 0,
+        }
+    }
+
+    /// Feed a received byte
+    pub fn feed(&mut self, byte: u8) -> Option<StreamItem> {
+        if !self.in_frame {
+            if byte == FRAME_START {
+                self.in_frame = true;
+                self.body.clear();
+                return None;
+            }
+            return Some(StreamItem::Text(byte));
+        }
+
+        if self.body.push(byte).is_err() {
+            return self.reject();
+        }
+        let (header, entries, entry_len) = match self.body.first() {
+            Some(&FRAME_KIND) => (HEADER_LEN, FRAME_BINS, 1),
+            Some(&PEAK_KIND) => (PEAK_HEADER_LEN, MAX_FRAME_PEAKS, PEAK_LEN),
+            _ => return self.reject(),
+        };
+        let count = usize::from(*self.body.get(1 + header - 1)?);
+        if count > entries {
+            return self.reject();
+        }
+        if self.body.len() < 1 + header + count * entry_len + 1 {
+            return None;
+        }
+
+        self.in_frame = false;
+        if self.body[0] == PEAK_KIND {
+            let Some(peaks) = PeakListFrame::decode(&self.body[1..]) else {
+                self.errors = self.errors.saturating_add(1);
+                return None;
+            };
+            return Some(StreamItem::Peaks(peaks));
+        }
+        let Some(frame) = PanadapterFrame::decode(&self.body[1..]) else {
+            self.errors = self.errors.saturating_add(1);
+            return None;
+        };
+        if let Some(expected) = self.next_seq {
+            let gap = frame.seq.wrapping_sub(expected);
+            self.dropped = self.dropped.saturating_add(u32::from(gap));
+        }
+        self.next_seq = Some(frame.seq.wrapping_add(1));
+        Some(StreamItem::Frame(frame))
+    }
+
+    /// Get the number of frames missing from the sequence
+    #[must_use]
+    pub const fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Get the number of frames discarded for a bad CRC or length
+    #[must_use]
+    pub const fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Abandon a malformed frame
+    fn reject(&mut self) -> Option<StreamItem> {
+        self.in_frame = false;
+        self.errors = self.errors.saturating_add(1);
+        None
+    }
+}
@@ -0,0 +1,578 @@
+//! Remote Head Protocol
+//!
+//! Compact serial protocol between the main radio and a detached front
+//! panel (a small MCU driving the display and encoder). The head runs the
+//! UI state machine and renderer locally; it sends the actions the
+//! operator makes and the radio answers with status snapshots, so the
+//! link carries a few dozen bytes per update rather than frame buffers.
+//!
+//! # Framing
+//!
+//! ```text
+//! 0xA5 | type | len | payload[len] | crc8
+//! ```
+//!
+//! The CRC (polynomial 0x07) covers type, length and payload. A receiver
+//! resynchronises on the next start byte after any error.
+//!
+//! # Session
+//!
+//! The head sends `Hello` at power-on; the radio answers `Welcome` and a
+//! full `Status`. After that the radio sends `Status` whenever something
+//! shown on the panel changes, and the head sends `Action`s. The head may
+//! send `RequestStatus` at any time (e.g. after a CRC error). The radio
+//! side of the session is handled by [`RadioLink`].
+
+use heapless::{String, Vec};
+
+use crate::clock::DateTime;
+use crate::config::REMOTE_HEAD_REFRESH_MS;
+use crate::radio::state::RadioState;
+use crate::types::{Frequency, Mode, PowerLevel, TuningStep, TxRxState};
+
+/// Protocol version exchanged in `Hello` / `Welcome`
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Frame start byte
+pub const FRAME_START: u8 = 0xA5;
+
+/// Maximum payload length
+pub const MAX_PAYLOAD: usize = 24;
+
+/// Maximum encoded frame length
+pub const MAX_FRAME_LEN: usize = MAX_PAYLOAD + 4;
+
+/// Maximum length of an `Execute` command name
+pub const EXEC_NAME_LEN: usize = 12;
+
+/// Encoded status payload length
+const STATUS_LEN: usize = 16;
+
+/// Message types
+mod kind {
+    pub const HELLO: u8 = 0x01;
+    pub const REQUEST_STATUS: u8 = 0x02;
+    pub const ACTION: u8 = 0x03;
+    pub const WELCOME: u8 = 0x81;
+    pub const STATUS: u8 = 0x82;
+}
+
+/// Action tags
+mod action {
+    pub const TUNE: u8 = 1;
+    pub const SET_FREQUENCY: u8 = 2;
+    pub const SET_MODE: u8 = 3;
+    pub const NEXT_STEP: u8 = 4;
+    pub const TOGGLE_PTT: u8 = 5;
+    pub const EXECUTE: u8 = 6;
+    pub const PLAY_MESSAGE: u8 = 7;
+    pub const ABORT_MESSAGE: u8 = 8;
+    pub const RECALL_MEMORY: u8 = 9;
+}
+
+/// Status flag: clock shows UTC
+const FLAG_CLOCK_UTC: u8 = 0x01;
+
+/// Status flag: clock valid
+const FLAG_CLOCK_SET: u8 = 0x02;
+
+/// CRC-8 (polynomial 0x07, initial value 0)
+#[must_use]
+pub fn crc8(data: &[u8]) -> u8 {
+    crc8_update(0, data)
+}
+
+/// Continue a CRC-8 over more data
+fn crc8_update(crc: u8, data: &[u8]) -> u8 {
+    data.iter().fold(crc, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Encode a frame around a payload
+fn encode_frame(kind: u8, payload: &[u8]) -> Vec<u8, MAX_FRAME_LEN> {
+    let mut frame = Vec::new();
+    let len = payload.len().min(MAX_PAYLOAD);
+    let _ = frame.push(FRAME_START);
+    let _ = frame.push(kind);
+    #[allow(clippy::cast_possible_truncation)]
+    let _ = frame.push(len as u8);
+    let _ = frame.extend_from_slice(&payload[..len]);
+    let crc = crc8(&frame[1..]);
+    let _ = frame.push(crc);
+    frame
+}
+
+/// Received frame (CRC already checked)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawFrame {
+    /// Message type
+    pub kind: u8,
+    /// Payload bytes
+    pub payload: Vec<u8, MAX_PAYLOAD>,
+}
+
+/// Frame decoder state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DecodeState {
+    /// Waiting for the start byte
+    Start,
+    /// Expecting the type byte
+    Kind,
+    /// Expecting the length byte
+    Length,
+    /// Receiving payload
+    Payload,
+    /// Expecting the CRC byte
+    Crc,
+}
+
+/// Byte-at-a-time frame decoder
+#[derive(Clone, Debug)]
+pub struct FrameDecoder {
+    /// Decoder state
+    state: DecodeState,
+    /// Frame being received
+    frame: RawFrame,
+    /// Expected payload length
+    len: usize,
+    /// Frames dropped for a bad CRC or length
+    errors: u32,
+}
+
+impl FrameDecoder {
+    /// Create a new decoder
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: DecodeState::Start,
+            frame: RawFrame {
+                kind: 0,
+                payload: Vec::new(),
+            },
+            len: 0,
+            errors: 0,
+        }
+    }
+
+    /// Feed a received byte, returns a frame once one is complete and valid
+    pub fn feed(&mut self, byte: u8) -> Option<RawFrame> {
+        match self.state {
+            DecodeState::Start => {
+                if byte == FRAME_START {
+                    self.state = DecodeState::Kind;
+                }
+            }
+            DecodeState::Kind => {
+                self.frame.kind = byte;
+                self.frame.payload.clear();
+                self.state = DecodeState::Length;
+            }
+            DecodeState::Length => {
+                self.len = usize::from(byte);
+                self.state = if self.len > MAX_PAYLOAD {
+                    self.errors += 1;
+                    DecodeState::Start
+                } else if self.len == 0 {
+                    DecodeState::Crc
+                } else {
+                    DecodeState::Payload
+                };
+            }
+            DecodeState::Payload => {
+                let _ = self.frame.payload.push(byte);
+                if self.frame.payload.len() == self.len {
+                    self.state = DecodeState::Crc;
+                }
+            }
+            DecodeState::Crc => {
+                self.state = DecodeState::Start;
+                #[allow(clippy::cast_possible_truncation)]
+                let header = [self.frame.kind, self.len as u8];
+                if crc8_update(crc8(&header), &self.frame.payload) == byte {
+                    return Some(self.frame.clone());
+                }
+                self.errors += 1;
+            }
+        }
+        None
+    }
+
+    /// Number of frames dropped for a bad CRC or length
+    #[must_use]
+    pub const fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Operator action sent from the head to the radio
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemoteAction {
+    /// Tune by steps
+    Tune(i32),
+    /// Set frequency directly
+    SetFrequency(Frequency),
+    /// Change mode
+    SetMode(Mode),
+    /// Next tuning step
+    NextStep,
+    /// Toggle PTT
+    TogglePtt,
+    /// Execute a menu command by name
+    Execute(String<EXEC_NAME_LEN>),
+    /// Play stored CW message (0-based)
+    PlayMessage(u8),
+    /// Abort CW message playback
+    AbortMessage,
+    /// Recall memory channel
+    RecallMemory(u8),
+}
+
+impl RemoteAction {
+    /// Encode as an action payload
+    fn encode(&self) -> Vec<u8, MAX_PAYLOAD> {
+        let mut out = Vec::new();
+        let mut put = |bytes: &[u8]| {
+            let _ = out.extend_from_slice(bytes);
+        };
+        match self {
+            Self::Tune(steps) => {
+                put(&[action::TUNE]);
+                put(&steps.to_le_bytes());
+            }
+            Self::SetFrequency(freq) => {
+                put(&[action::SET_FREQUENCY]);
+                put(&freq.as_hz().to_le_bytes());
+            }
+            Self::SetMode(mode) => put(&[action::SET_MODE, mode.code()]),
+            Self::NextStep => put(&[action::NEXT_STEP]),
+            Self::TogglePtt => put(&[action::TOGGLE_PTT]),
+            Self::Execute(name) => {
+                put(&[action::EXECUTE]);
+                put(name.as_bytes());
+            }
+            Self::PlayMessage(index) => put(&[action::PLAY_MESSAGE, *index]),
+            Self::AbortMessage => put(&[action::ABORT_MESSAGE]),
+            Self::RecallMemory(channel) => put(&[action::RECALL_MEMORY, *channel]),
+        }
+        out
+    }
+
+    /// Decode an action payload
+    fn decode(payload: &[u8]) -> Option<Self> {
+        let (&tag, rest) = payload.split_first()?;
+        let word = || rest.get(..4)?.try_into().ok();
+        Some(match tag {
+            action::TUNE => Self::Tune(i32::from_le_bytes(word()?)),
+            action::SET_FREQUENCY => {
+                Self::SetFrequency(Frequency::from_hz(u32::from_le_bytes(word()?))?)
+            }
+            action::SET_MODE => Self::SetMode(Mode::from_code(*rest.first()?)?),
+            action::NEXT_STEP => Self::NextStep,
+            action::TOGGLE_PTT => Self::TogglePtt,
+            action::EXECUTE => {
+                let mut name = String::new();
+                name.push_str(core::str::from_utf8(rest).ok()?).ok()?;
+                Self::Execute(name)
+            }
+            action::PLAY_MESSAGE => Self::PlayMessage(*rest.first()?),
+            action::ABORT_MESSAGE => Self::AbortMessage,
+            action::RECALL_MEMORY => Self::RecallMemory(*rest.first()?),
+            _ => return None,
+        })
+    }
+}
+
+/// Everything the front panel shows, sent from the radio to the head
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RemoteStatus {
+    /// VFO frequency
+    pub frequency: Frequency,
+    /// Operating mode
+    pub mode: Mode,
+    /// Tuning step
+    pub step: TuningStep,
+    /// TX/RX state
+    pub txrx: TxRxState,
+    /// TX power level
+    pub power: PowerLevel,
+    /// S-meter level (0-100)
+    pub s_meter: u8,
+    /// SWR
+    pub swr: f32,
+    /// Clock time to show (None hides the clock)
+    pub clock: Option<DateTime>,
+    /// Clock shows UTC rather than local time
+    pub clock_utc: bool,
+}
+
+impl RemoteStatus {
+    /// Build a status snapshot from the radio state and meters
+    #[must_use]
+    pub const fn new(state: &RadioState, s_meter: u8, swr: f32) -> Self {
+        Self {
+            frequency: state.frequency(),
+            mode: state.mode(),
+            step: state.step(),
+            txrx: state.txrx(),
+            power: state.power(),
+            s_meter,
+            swr,
+            clock: None,
+            clock_utc: true,
+        }
+    }
+
+    /// Set the clock shown on the panel
+    #[must_use]
+    pub const fn with_clock(mut self, time: Option<DateTime>, utc: bool) -> Self {
+        self.clock = time;
+        self.clock_utc = utc;
+        self
+    }
+
+    /// Rebuild a radio state on the head for rendering
+    #[must_use]
+    pub fn radio_state(&self) -> RadioState {
+        RadioState::new(self.frequency)
+            .with_mode(self.mode)
+            .with_step(self.step)
+            .with_txrx(self.txrx)
+            .with_power(self.power)
+    }
+
+    /// Encode as a status payload
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn encode(&self) -> [u8; STATUS_LEN] {
+        let mut out = [0u8; STATUS_LEN];
+        out[..4].copy_from_slice(&self.frequency.as_hz().to_le_bytes());
+        out[4] = self.mode.code();
+        out[5] = self.step.code();
+        out[6] = match self.txrx {
+            TxRxState::Rx => 0,
+            TxRxState::Tx => 1,
+            TxRxState::Switching => 2,
+        };
+        out[7] = self.power.as_percent();
+        out[8] = self.s_meter;
+        out[9..11].copy_from_slice(&((self.swr.clamp(1.0, 99.0) * 100.0) as u16).to_le_bytes());
+        let mut flags = 0;
+        if self.clock_utc {
+            flags |= FLAG_CLOCK_UTC;
+        }
+        if let Some(time) = self.clock {
+            flags |= FLAG_CLOCK_SET;
+            out[12..16].copy_from_slice(&(time.to_unix() as u32).to_le_bytes());
+        }
+        out[11] = flags;
+        out
+    }
+
+    /// Decode a status payload
+    fn decode(payload: &[u8]) -> Option<Self> {
+        let payload = payload.get(..STATUS_LEN)?;
+        let word = |i: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(payload.get(i..i + 4)?.try_into().ok()?))
+        };
+        let flags = payload[11];
+        Some(Self {
+            frequency: Frequency::from_hz(word(0)?)?,
+            mode: Mode::from_code(payload[4])?,
+            step: TuningStep::from_code(payload[5])?,
+            txrx: match payload[6] {
+                0 => TxRxState::Rx,
+                1 => TxRxState::Tx,
+                2 => TxRxState::Switching,
+                _ => return None,
+            },
+            power: PowerLevel::from_percent(payload[7]),
+            s_meter: payload[8],
+            swr: f32::from(u16::from_le_bytes([payload[9], payload[10]])) / 100.0,
+            clock: (flags & FLAG_CLOCK_SET != 0)
+                .then(|| DateTime::from_unix(u64::from(word(12).unwrap_or(0)))),
+            clock_utc: flags & FLAG_CLOCK_UTC != 0,
+        })
+    }
+}
+
+/// Message from the head to the radio
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeadMessage {
+    /// Head powered up (protocol version)
+    Hello(u8),
+    /// Ask for a full status snapshot
+    RequestStatus,
+    /// Operator action
+    Action(RemoteAction),
+}
+
+impl HeadMessage {
+    /// Encode as a frame
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8, MAX_FRAME_LEN> {
+        match self {
+            Self::Hello(version) => encode_frame(kind::HELLO, &[*version]),
+            Self::RequestStatus => encode_frame(kind::REQUEST_STATUS, &[]),
+            Self::Action(action) => encode_frame(kind::ACTION, &action.encode()),
+        }
+    }
+
+    /// Decode a received frame
+    #[must_use]
+    pub fn decode(frame: &RawFrame) -> Option<Self> {
+        match frame.kind {
+            kind::HELLO => Some(Self::Hello(*frame.payload.first()?)),
+            kind::REQUEST_STATUS => Some(Self::RequestStatus),
+            kind::ACTION => RemoteAction::decode(&frame.payload).map(Self::Action),
+            _ => None,
+        }
+    }
+}
+
+/// Message from the radio to the head
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RadioMessage {
+    /// Reply to `Hello` (protocol version)
+    Welcome(u8),
+    /// Status snapshot
+    Status(RemoteStatus),
+}
+
+impl RadioMessage {
+    /// Encode as a frame
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8, MAX_FRAME_LEN> {
+        match self {
+            Self::Welcome(version) => encode_frame(kind::WELCOME, &[*version]),
+            Self::Status(status) => encode_frame(kind::STATUS, &status.encode()),
+        }
+    }
+
+    /// Decode a received frame
+    #[must_use]
+    pub fn decode(frame: &RawFrame) -> Option<Self> {
+        match frame.kind {
+            kind::WELCOME => Some(Self::Welcome(*frame.payload.first()?)),
+            kind::STATUS => RemoteStatus::decode(&frame.payload).map(Self::Status),
+            _ => None,
+        }
+    }
+}
+
+/// Radio side of the link
+///
+/// Decodes frames from the head, answers the session messages and
+/// decides when to push a fresh status snapshot.
+#[derive(Clone, Debug)]
+pub struct RadioLink {
+    /// Frame decoder
+    decoder: FrameDecoder,
+    /// Head has said hello with a compatible version
+    connected: bool,
+    /// Last status sent
+    sent: Option<RemoteStatus>,
+    /// Time since the last status was sent (ms)
+    since_sent_ms: u32,
+}
+
+impl RadioLink {
+    /// Create a link with no head connected
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            decoder: FrameDecoder::new(),
+            connected: false,
+            sent: None,
+            since_sent_ms: 0,
+        }
+    }
+
+    /// Check if a head is connected
+    #[must_use]
+    pub const fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Feed a byte from the head
+    ///
+    /// Session replies are appended to `reply`; an operator action is
+    /// returned for the caller to perform.
+    pub fn feed<const N: usize>(
+        &mut self,
+        byte: u8,
+        status: &RemoteStatus,
+        reply: &mut Vec<u8, N>,
+    ) -> Option<RemoteAction> {
+        let frame = self.decoder.feed(byte)?;
+        match HeadMessage::decode(&frame)? {
+            HeadMessage::Hello(version) => {
+                self.connected = version == PROTOCOL_VERSION;
+                let _ = reply.extend_from_slice(&RadioMessage::Welcome(PROTOCOL_VERSION).encode());
+                if self.connected {
+                    self.push_status(status, reply);
+                }
+                None
+            }
+            HeadMessage::RequestStatus if self.connected => {
+                self.push_status(status, reply);
+                None
+            }
+            HeadMessage::Action(action) if self.connected => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Periodic update, returns a status frame to send if one is due
+    ///
+    /// A status is sent when anything shown changed, or every
+    /// [`REMOTE_HEAD_REFRESH_MS`] so a head that missed a frame recovers.
+    pub fn update(
+        &mut self,
+        status: &RemoteStatus,
+        elapsed_ms: u32,
+    ) -> Option<Vec<u8, MAX_FRAME_LEN>> {
+        if !self.connected {
+            return None;
+        }
+        self.since_sent_ms = self.since_sent_ms.saturating_add(elapsed_ms);
+        if self.sent.as_ref() == Some(status) && self.since_sent_ms < REMOTE_HEAD_REFRESH_MS {
+            return None;
+        }
+        self.sent = Some(*status);
+        self.since_sent_ms = 0;
+        Some(RadioMessage::Status(*status).encode())
+    }
+
+    /// Append a status frame and remember it as sent
+    fn push_status<const N: usize>(&mut self, status: &RemoteStatus, reply: &mut Vec<u8, N>) {
+        let _ = reply.extend_from_slice(&RadioMessage::Status(*status).encode());
+        self.sent = Some(*status);
+        self.since_sent_ms = 0;
+    }
+
+    /// Drop the session (link lost)
+    pub fn disconnect(&mut self) {
+        self.connected = false;
+        self.sent = None;
+    }
+}
+
+impl Default for RadioLink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
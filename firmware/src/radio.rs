@@ -7,3 +7,21 @@ pub mod state;
 pub mod vfo;
 pub mod transmit;
 pub mod keyer;
+pub mod monitor;
+pub mod recorder;
+pub mod winkeyer;
+pub mod annunciator;
+#[cfg(feature = "aprs-beacon")]
+pub mod beacon;
+pub mod tx_test;
+pub mod sweep;
+pub mod input;
+pub mod control;
+pub mod pa;
+pub mod relay;
+pub mod swr;
+pub mod dtmf;
+pub mod tx_policy;
+pub mod practice;
+pub mod voice_keyer;
+pub mod loopback;
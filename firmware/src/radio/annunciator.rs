@@ -0,0 +1,278 @@
+//! Morse Annunciator
+//!
+//! Short Morse feedback through the sidetone generator so the radio can
+//! be operated without a display (headless builds). State changes are
+//! announced as one or two characters:
+//!
+//! - band change: the band's whole MHz (`7` for 40 m, `14` for 20 m)
+//! - error or rejected input: `E`
+//! - settings saved: `R`
+//! - power-on self test passed: `OK` (a failed test is an error)
+//!
+//! The annunciator only produces a key state; the caller feeds it to
+//! `AudioChain::set_sidetone_key` and applies [`Annunciator::level`] as
+//! the sidetone level while [`Annunciator::is_active`] is true. Nothing
+//! is sent over the air.
+//!
+//! # Example
+//!
+//! ```ignore
+//! annunciator.announce_band(Band::M40);
+//! // every millisecond tick
+//! let key = annunciator.update(1);
+//! audio.set_sidetone_key(key || keyer_key);
+//! ```
+
+use heapless::Deque;
+
+use crate::radio::keyer::{Element, MorseEncoder};
+use crate::selftest::PostReport;
+use crate::types::Band;
+
+/// Maximum queued characters
+pub const QUEUE_LEN: usize = 16;
+
+/// Default annunciation speed in WPM
+pub const DEFAULT_WPM: u8 = 25;
+
+/// Minimum annunciation speed in WPM
+pub const MIN_WPM: u8 = 10;
+
+/// Maximum annunciation speed in WPM
+pub const MAX_WPM: u8 = 40;
+
+/// Default annunciation level (0.0 to 1.0)
+pub const DEFAULT_LEVEL: f32 = 0.3;
+
+/// Event announced in Morse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Annunciation {
+    /// Band changed
+    Band(Band),
+    /// Error or rejected input
+    Error,
+    /// Settings saved
+    Saved,
+    /// Power-on self test passed
+    Ready,
+}
+
+impl Annunciation {
+    /// Get the text sent for this event
+    #[must_use]
+    pub const fn text(&self) -> &'static str {
+        match self {
+            Self::Band(band) => match band {
+                Band::M80 => "3",
+                Band::M40 => "7",
+                Band::M30 => "10",
+                Band::M20 => "14",
+                Band::M17 => "18",
+                Band::M15 => "21",
+            },
+            Self::Error => "E",
+            Self::Saved => "R",
+            Self::Ready => "OK",
+        }
+    }
+
+    /// Get the annunciation for a power-on self test report
+    ///
+    /// Checks that did not run (the display on headless builds) do not
+    /// count as failures.
+    #[must_use]
+    pub fn from_post(report: &PostReport) -> Self {
+        if report.failures().next().is_none() {
+            Self::Ready
+        } else {
+            Self::Error
+        }
+    }
+}
+
+/// Morse annunciator driving the sidetone
+#[derive(Clone, Debug)]
+pub struct Annunciator {
+    /// Annunciations enabled
+    enabled: bool,
+    /// Speed in WPM
+    wpm: u8,
+    /// Sidetone level while announcing
+    level: f32,
+    /// Characters waiting to be sent
+    queue: Deque<char, QUEUE_LEN>,
+    /// Encoder for the character being sent
+    encoder: MorseEncoder,
+    /// Current key state
+    key_down: bool,
+    /// Time left in the current element or gap
+    remaining_ms: u32,
+}
+
+impl Annunciator {
+    /// Create an enabled annunciator with default speed and level
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            enabled: true,
+            wpm: DEFAULT_WPM,
+            level: DEFAULT_LEVEL,
+            queue: Deque::new(),
+            encoder: MorseEncoder::new(),
+            key_down: false,
+            remaining_ms: 0,
+        }
+    }
+
+    /// Enable or disable annunciations (disabling stops any in progress)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.stop();
+        }
+    }
+
+    /// Check if annunciations are enabled
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set speed in WPM (clamped to 10-40)
+    pub fn set_wpm(&mut self, wpm: u8) {
+        self.wpm = wpm.clamp(MIN_WPM, MAX_WPM);
+    }
+
+    /// Get speed in WPM
+    #[must_use]
+    pub const fn wpm(&self) -> u8 {
+        self.wpm
+    }
+
+    /// Set sidetone level while announcing (0.0 to 1.0)
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level.clamp(0.0, 1.0);
+    }
+
+    /// Get sidetone level while announcing
+    #[must_use]
+    pub const fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Get dit length in milliseconds
+    #[must_use]
+    pub const fn unit_ms(&self) -> u32 {
+        1200 / self.wpm as u32
+    }
+
+    /// Queue an event
+    ///
+    /// A new event replaces one still being sent, so quick band changes
+    /// only announce the band finally selected.
+    pub fn announce(&mut self, event: Annunciation) -> bool {
+        self.stop();
+        self.announce_text(event.text())
+    }
+
+    /// Queue free text (e.g. a menu item name)
+    ///
+    /// Returns false if disabled or the text does not fit in the queue.
+    /// Characters without a Morse code are skipped.
+    pub fn announce_text(&mut self, text: &str) -> bool {
+        if !self.enabled || text.chars().count() > self.queue.capacity() - self.queue.len() {
+            return false;
+        }
+        for c in text.chars() {
+            // Capacity was checked above
+            let _ = self.queue.push_back(c);
+        }
+        true
+    }
+
+    /// Announce a band change
+    pub fn announce_band(&mut self, band: Band) -> bool {
+        self.announce(Annunciation::Band(band))
+    }
+
+    /// Announce an error
+    pub fn announce_error(&mut self) -> bool {
+        self.announce(Annunciation::Error)
+    }
+
+    /// Announce that settings were saved
+    pub fn announce_saved(&mut self) -> bool {
+        self.announce(Annunciation::Saved)
+    }
+
+    /// Stop sending and clear the queue
+    pub fn stop(&mut self) {
+        self.queue.clear();
+        self.encoder = MorseEncoder::new();
+        self.key_down = false;
+        self.remaining_ms = 0;
+    }
+
+    /// Check if an annunciation is in progress
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.remaining_ms > 0 || !self.encoder.is_idle() || !self.queue.is_empty()
+    }
+
+    /// Check if the sidetone should be keyed
+    #[must_use]
+    pub const fn is_key_down(&self) -> bool {
+        self.key_down
+    }
+
+    /// Advance by `elapsed_ms` and return the sidetone key state
+    pub fn update(&mut self, elapsed_ms: u32) -> bool {
+        let mut elapsed = elapsed_ms;
+        loop {
+            if self.remaining_ms >= elapsed {
+                self.remaining_ms -= elapsed;
+                break;
+            }
+            elapsed -= self.remaining_ms;
+            self.remaining_ms = 0;
+            if !self.next_step() {
+                break;
+            }
+        }
+        self.key_down
+    }
+
+    /// Start the next element or gap, false when nothing is left
+    fn next_step(&mut self) -> bool {
+        let unit = self.unit_ms();
+
+        if self.key_down {
+            // Element gap after every dit or dah
+            self.key_down = false;
+            self.remaining_ms = unit * Element::ElementGap.units();
+            return true;
+        }
+
+        while self.encoder.is_idle() {
+            let Some(c) = self.queue.pop_front() else {
+                return false;
+            };
+            self.encoder.load(c);
+        }
+
+        let Some(element) = self.encoder.next_element() else {
+            // Unknown pattern byte, drop the character
+            self.encoder = MorseEncoder::new();
+            return true;
+        };
+        self.key_down = element.is_tone();
+        self.remaining_ms = unit * element.units();
+        true
+    }
+}
+
+impl Default for Annunciator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,223 @@
+//! APRS Position Beacon
+//!
+//! Sends the station position as an AX.25 UI frame in 1200 baud Bell
+//! 202 AFSK at a fixed interval, using the same encoder the web UI's
+//! decoder is tested against.
+//!
+//! The beacon only produces audio. The caller keys the transmitter
+//! while [`AprsBeacon::is_transmitting`] is true and feeds
+//! [`AprsBeacon::next_sample`] to the modulator in place of microphone
+//! audio (FM on VHF, or USB for HF packet).
+//!
+//! Only built with the `aprs-beacon` feature, which pulls in the shared
+//! `sdr-mode-aprs` encoder.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut beacon = AprsBeacon::new("N0CALL", 7, SAMPLE_RATE)?;
+//! beacon.set_position(49.0583, -72.0292);
+//! beacon.set_enabled(true);
+//! // every millisecond tick
+//! if beacon.update(1) {
+//!     radio.start_tx();
+//! }
+//! // in the TX audio loop
+//! match beacon.next_sample() {
+//!     Some(sample) => modulator.push(sample),
+//!     None => radio.stop_tx(),
+//! }
+//! ```
+
+use heapless::{String, Vec};
+use sdr_mode_aprs::{Address, AprsEncoder, AprsPacket, Ax25Error, Ax25Frame, Position, TOCALL};
+
+/// Default beacon interval in seconds
+pub const DEFAULT_INTERVAL_S: u32 = 600;
+
+/// Shortest beacon interval in seconds
+pub const MIN_INTERVAL_S: u32 = 60;
+
+/// Longest position comment in characters
+pub const MAX_COMMENT_LEN: usize = 43;
+
+/// Most digipeaters in the beacon path
+pub const MAX_PATH_LEN: usize = 2;
+
+/// Default AFSK level (0.0 to 1.0)
+pub const DEFAULT_LEVEL: f32 = 0.5;
+
+/// Default map symbol: house, primary table
+const DEFAULT_SYMBOL: (char, char) = ('/', '-');
+
+/// Periodic APRS position beacon
+#[derive(Clone, Debug)]
+pub struct AprsBeacon {
+    /// Beacon enabled
+    enabled: bool,
+    /// Station callsign and SSID
+    source: Address,
+    /// Digipeater path
+    path: Vec<Address, MAX_PATH_LEN>,
+    /// Latitude and longitude in degrees, once known
+    location: Option<(f32, f32)>,
+    /// Symbol table and code
+    symbol: (char, char),
+    /// Text sent after the position
+    comment: String<MAX_COMMENT_LEN>,
+    /// Time between beacons
+    interval_ms: u32,
+    /// Time since the last beacon
+    elapsed_ms: u32,
+    /// AFSK encoder for the frame being sent
+    encoder: AprsEncoder,
+}
+
+impl AprsBeacon {
+    /// Create a disabled beacon for `callsign`-`ssid` with the path `WIDE1-1,WIDE2-1`
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::BadAddress`] if the callsign or SSID is not valid.
+    pub fn new(callsign: &str, ssid: u8, sample_rate: f32) -> Result<Self, Ax25Error> {
+        let mut beacon = Self {
+            enabled: false,
+            source: Address::new(callsign, ssid)?,
+            path: Vec::new(),
+            location: None,
+            symbol: DEFAULT_SYMBOL,
+            comment: String::new(),
+            interval_ms: DEFAULT_INTERVAL_S * 1000,
+            elapsed_ms: 0,
+            encoder: AprsEncoder::new(sample_rate, DEFAULT_LEVEL),
+        };
+        beacon.set_path(&["WIDE1-1", "WIDE2-1"])?;
+        Ok(beacon)
+    }
+
+    /// Enable or disable beaconing (disabling stops a beacon in progress)
+    ///
+    /// The first beacon goes out one interval after enabling, or use
+    /// [`AprsBeacon::send_now`].
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.elapsed_ms = 0;
+        if !enabled {
+            self.encoder.reset();
+        }
+    }
+
+    /// Check if beaconing is enabled
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the beacon interval in seconds (at least one minute)
+    pub fn set_interval_s(&mut self, seconds: u32) {
+        self.interval_ms = seconds.max(MIN_INTERVAL_S).saturating_mul(1000);
+    }
+
+    /// Get the beacon interval in seconds
+    #[must_use]
+    pub const fn interval_s(&self) -> u32 {
+        self.interval_ms / 1000
+    }
+
+    /// Set the station position in degrees (north and east positive)
+    pub fn set_position(&mut self, latitude: f32, longitude: f32) {
+        self.location = Some((latitude.clamp(-90.0, 90.0), longitude.clamp(-180.0, 180.0)));
+    }
+
+    /// Set the map symbol table and code (e.g. `'/'`, `'>'` for a car)
+    pub fn set_symbol(&mut self, table: char, code: char) {
+        self.symbol = (table, code);
+    }
+
+    /// Set the comment sent after the position
+    ///
+    /// Returns false (leaving the comment unchanged) if it is longer than
+    /// [`MAX_COMMENT_LEN`] or not printable ASCII.
+    pub fn set_comment(&mut self, comment: &str) -> bool {
+        if !comment.bytes().all(|b| b == b' ' || b.is_ascii_graphic()) {
+            return false;
+        }
+        match String::try_from(comment) {
+            Ok(comment) => {
+                self.comment = comment;
+                true
+            }
+            Err(()) => false,
+        }
+    }
+
+    /// Set the digipeater path, e.g. `["WIDE1-1", "WIDE2-1"]` (empty for direct)
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::BadAddress`] for an invalid address, or
+    /// [`Ax25Error::TooManyDigipeaters`] for more than [`MAX_PATH_LEN`].
+    pub fn set_path(&mut self, path: &[&str]) -> Result<(), Ax25Error> {
+        let mut addresses = Vec::new();
+        for text in path {
+            addresses
+                .push(Address::parse(text)?)
+                .map_err(|_| Ax25Error::TooManyDigipeaters)?;
+        }
+        self.path = addresses;
+        Ok(())
+    }
+
+    /// Build the beacon frame, or `None` until a position is set
+    #[must_use]
+    pub fn frame(&self) -> Option<Ax25Frame> {
+        let (latitude, longitude) = self.location?;
+        let packet = AprsPacket::Position {
+            position: Position {
+                latitude,
+                longitude,
+                symbol_table: self.symbol.0,
+                symbol_code: self.symbol.1,
+            },
+            messaging: false,
+            comment: &self.comment,
+        };
+        let mut info = String::<{ 1 + 19 + MAX_COMMENT_LEN }>::new();
+        packet.write(&mut info).ok()?;
+        let destination = Address::new(TOCALL, 0).ok()?;
+        Ax25Frame::new_ui(destination, self.source.clone(), &self.path, info.as_bytes()).ok()
+    }
+
+    /// Start sending a beacon now and restart the interval
+    ///
+    /// Returns false if no position is set.
+    pub fn send_now(&mut self) -> bool {
+        let Some(frame) = self.frame() else {
+            return false;
+        };
+        self.elapsed_ms = 0;
+        self.encoder.load(&frame).is_ok()
+    }
+
+    /// Advance the beacon timer
+    ///
+    /// Returns true when a beacon starts, and the transmitter should be keyed.
+    pub fn update(&mut self, elapsed_ms: u32) -> bool {
+        if !self.enabled || self.is_transmitting() {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(elapsed_ms);
+        self.elapsed_ms >= self.interval_ms && self.send_now()
+    }
+
+    /// Check if a beacon is being sent
+    #[must_use]
+    pub fn is_transmitting(&self) -> bool {
+        self.encoder.is_busy()
+    }
+
+    /// Get the next AFSK audio sample, or `None` when the beacon is done
+    pub fn next_sample(&mut self) -> Option<f32> {
+        self.encoder.next_sample()
+    }
+}
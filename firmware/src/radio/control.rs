@@ -0,0 +1,245 @@
+//! Radio Control
+//!
+//! The functional core of the radio control task. [`RadioController`]
+//! applies [`RadioEvent`]s from the front panel, CAT and remote head to
+//! the [`RadioState`] and works out what the hardware must do about each
+//! one: retune the synthesizer, or send [`DspCommand`]s to the DSP task.
+//! The task itself only moves events in and effects out.
+//!
+//! [`RadioEvent::AutoTune`] only asks the DSP task for a measurement
+//! ([`DspCommand::AutoTune`]); the DSP task answers with a
+//! [`RadioEvent::ShiftFrequency`] that moves the dial onto the signal.
+//! [`RadioEvent::CaptureIq`] likewise only arms the DSP task's IQ capture,
+//! and [`RadioEvent::SetIqAdaptive`] only switches its IQ balance. A band
+//! change sends [`DspCommand::SetBand`], so the DSP task loads that
+//! band's IQ balance calibration.
+//!
+//! T/R switching completes here: [`RadioEvent::StartTx`] goes straight to
+//! transmit unless transmit is inhibited (e.g. by a failed power-on self
+//! test), in which case the radio stays in receive.
+//!
+//! Effects also carry the [`Annunciation`] for the event, which the task
+//! passes on to the DSP task's annunciator with [`DspCommand::Announce`]:
+//! the new band on a band change and an error for a refused transmit.
+//! [`RadioEvent::StoreState`] asks the task to save the settings, and it
+//! announces whether that worked.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut control = RadioController::new(state, report.tx_allowed());
+//! apply(&mut synth, control.startup()).await;
+//! loop {
+//!     let effects = control.handle(RADIO_EVENTS.receive().await);
+//!     if let Some(freq) = effects.retune {
+//!         synth.tune_quadrature(freq).await?;
+//!     }
+//!     for command in effects.dsp {
+//!         DSP_COMMANDS.send(command).await;
+//!     }
+//! }
+//! ```
+
+use heapless::Vec;
+
+use crate::radio::annunciator::Annunciation;
+use crate::radio::state::{apply_event, AgcMode, RadioEvent, RadioState};
+use crate::types::{Band, Frequency, Mode, TxRxState};
+
+/// Most DSP commands one event can produce
+pub const MAX_DSP_COMMANDS: usize = 6;
+
+/// Command from the radio control task to the DSP task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DspCommand {
+    /// Rebuild the demodulator and filters for a mode
+    SetMode(Mode),
+    /// Change the AGC time constants (or turn AGC off)
+    SetAgc(AgcMode),
+    /// Enable or disable the noise blanker
+    SetNoiseBlanker(bool),
+    /// Mute receive audio while transmitting
+    SetTransmit(bool),
+    /// Measure the tone to center and report the dial correction
+    AutoTune,
+    /// Capture this many decimated IQ samples for diagnostics
+    CaptureIq(u16),
+    /// Load the IQ balance calibration of the band (`None` outside the bands)
+    SetBand(Option<Band>),
+    /// Switch adaptive IQ balance on or off
+    SetIqAdaptive(bool),
+    /// Announce an event in Morse on the sidetone
+    Announce(Annunciation),
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for DspCommand {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::SetMode(mode) => defmt::write!(f, "SetMode({})", mode),
+            Self::SetAgc(agc) => defmt::write!(f, "SetAgc({})", agc),
+            Self::SetNoiseBlanker(on) => defmt::write!(f, "SetNb({})", on),
+            Self::SetTransmit(tx) => defmt::write!(f, "SetTx({})", tx),
+            Self::AutoTune => defmt::write!(f, "AutoTune"),
+            Self::CaptureIq(count) => defmt::write!(f, "CaptureIq({})", count),
+            Self::SetBand(band) => defmt::write!(f, "SetBand({})", band),
+            Self::SetIqAdaptive(on) => defmt::write!(f, "SetIqAdaptive({})", on),
+            Self::Announce(event) => defmt::write!(f, "Announce({})", event.text()),
+        }
+    }
+}
+
+/// What the hardware must do after an event
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ControlEffects {
+    /// New synthesizer frequency
+    pub retune: Option<Frequency>,
+    /// Commands for the DSP task, in order
+    pub dsp: Vec<DspCommand, MAX_DSP_COMMANDS>,
+    /// The state changed, so displays and remote heads need it
+    pub state_changed: bool,
+    /// Event to announce in Morse
+    pub announce: Option<Annunciation>,
+    /// The settings must be saved with the current state as the fixed
+    /// startup state
+    pub save: bool,
+}
+
+impl ControlEffects {
+    /// Check if there is nothing to do
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.retune.is_none()
+            && self.dsp.is_empty()
+            && !self.state_changed
+            && self.announce.is_none()
+            && !self.save
+    }
+}
+
+/// Radio control state machine
+#[derive(Clone, Copy, Debug)]
+pub struct RadioController {
+    /// Current radio state
+    state: RadioState,
+    /// Transmit permitted
+    tx_allowed: bool,
+}
+
+impl RadioController {
+    /// Create a controller at `state`
+    #[must_use]
+    pub const fn new(state: RadioState, tx_allowed: bool) -> Self {
+        Self { state, tx_allowed }
+    }
+
+    /// Get the current radio state
+    #[must_use]
+    pub const fn state(&self) -> &RadioState {
+        &self.state
+    }
+
+    /// Check if transmit is permitted
+    #[must_use]
+    pub const fn is_tx_allowed(&self) -> bool {
+        self.tx_allowed
+    }
+
+    /// Get the effects that bring the hardware up to the current state
+    ///
+    /// Used once at startup, before any events.
+    #[must_use]
+    pub fn startup(&self) -> ControlEffects {
+        let mut dsp = Vec::new();
+        let _ = dsp.push(DspCommand::SetMode(self.state.mode()));
+        let _ = dsp.push(DspCommand::SetAgc(self.state.agc_mode()));
+        let _ = dsp.push(DspCommand::SetNoiseBlanker(self.state.noise_blanker_enabled()));
+        let _ = dsp.push(DspCommand::SetTransmit(self.state.is_transmitting()));
+        let _ = dsp.push(DspCommand::SetBand(self.state.band()));
+        ControlEffects {
+            retune: Some(lo_frequency(&self.state)),
+            dsp,
+            state_changed: true,
+            ..ControlEffects::default()
+        }
+    }
+
+    /// Permit or inhibit transmit, dropping to receive if inhibited
+    pub fn set_tx_allowed(&mut self, allowed: bool) -> ControlEffects {
+        self.tx_allowed = allowed;
+        if !allowed && self.state.txrx() != TxRxState::Rx {
+            return self.handle(RadioEvent::StopTx);
+        }
+        ControlEffects::default()
+    }
+
+    /// Apply an event and get what the hardware must do
+    pub fn handle(&mut self, event: RadioEvent) -> ControlEffects {
+        let old = self.state;
+        let new = apply_event(old, event);
+        let new = match event {
+            RadioEvent::StartTx if self.tx_allowed => new.with_txrx(TxRxState::Tx),
+            RadioEvent::StartTx => new.with_txrx(old.txrx()),
+            RadioEvent::StopTx => new.with_txrx(TxRxState::Rx),
+            _ => new,
+        };
+        self.state = new;
+        let mut effects = changes(&old, &new);
+        if matches!(event, RadioEvent::AutoTune) && !new.is_transmitting() {
+            let _ = effects.dsp.push(DspCommand::AutoTune);
+        }
+        if let RadioEvent::CaptureIq(count) = event {
+            let _ = effects.dsp.push(DspCommand::CaptureIq(count));
+        }
+        if let RadioEvent::SetIqAdaptive(on) = event {
+            let _ = effects.dsp.push(DspCommand::SetIqAdaptive(on));
+        }
+        if new.band() != old.band() {
+            effects.announce = new.band().map(Annunciation::Band);
+        }
+        if matches!(event, RadioEvent::StartTx) && !self.tx_allowed {
+            effects.announce = Some(Annunciation::Error);
+        }
+        effects.save = matches!(event, RadioEvent::StoreState);
+        effects
+    }
+}
+
+/// Frequency the synthesizer runs at for a state
+fn lo_frequency(state: &RadioState) -> Frequency {
+    if state.is_transmitting() {
+        state.tx_frequency()
+    } else {
+        state.rx_frequency()
+    }
+}
+
+/// Work out the effects of a state change
+fn changes(old: &RadioState, new: &RadioState) -> ControlEffects {
+    let mut effects = ControlEffects {
+        state_changed: old != new,
+        ..ControlEffects::default()
+    };
+    let lo = lo_frequency(new);
+    if lo != lo_frequency(old) {
+        effects.retune = Some(lo);
+    }
+    if new.is_transmitting() != old.is_transmitting() {
+        let _ = effects.dsp.push(DspCommand::SetTransmit(new.is_transmitting()));
+    }
+    if new.mode() != old.mode() {
+        let _ = effects.dsp.push(DspCommand::SetMode(new.mode()));
+    }
+    if new.agc_mode() != old.agc_mode() {
+        let _ = effects.dsp.push(DspCommand::SetAgc(new.agc_mode()));
+    }
+    if new.noise_blanker_enabled() != old.noise_blanker_enabled() {
+        let _ = effects
+            .dsp
+            .push(DspCommand::SetNoiseBlanker(new.noise_blanker_enabled()));
+    }
+    if new.band() != old.band() {
+        let _ = effects.dsp.push(DspCommand::SetBand(new.band()));
+    }
+    effects
+}
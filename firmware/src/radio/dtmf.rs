@@ -0,0 +1,293 @@
+//! DTMF Remote Control
+//!
+//! Turns DTMF digits heard on the receiver into radio events, so a
+//! station can be worked through a remote FM link with a handheld's
+//! keypad. A command is keyed as the access code, then the command code,
+//! then `#`; `*` throws away what has been keyed so far, as does a pause
+//! longer than [`DIGIT_TIMEOUT_MS`]. For example with access code `42`
+//! and the factory map, `421#` cycles the mode.
+//!
+//! The command map is configurable and starts empty; [`DtmfRemote::load_defaults`]
+//! loads the factory map. Transmit is deliberately not in it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut remote = DtmfRemote::new();
+//! remote.set_access_code("42");
+//! remote.load_defaults();
+//! remote.set_enabled(true);
+//! while let Some(digit) = pipeline.pop_dtmf_digit() {
+//!     cat.dtmf_digit(digit);
+//!     if let Some(event) = remote.push_digit(digit, now_ms) {
+//!         controller.handle(event);
+//!     }
+//! }
+//! ```
+
+use heapless::{String, Vec};
+
+use super::state::RadioEvent;
+use crate::dsp::dtmf::is_dtmf_digit;
+
+/// Longest access or command code in digits
+pub const MAX_CODE_LEN: usize = 8;
+
+/// Most entries in the command map
+pub const MAX_COMMANDS: usize = 16;
+
+/// Pause after which a partly keyed command is discarded
+pub const DIGIT_TIMEOUT_MS: u32 = 5000;
+
+/// Longest keyed sequence (access code and command code)
+const MAX_ENTRY_LEN: usize = 2 * MAX_CODE_LEN;
+
+/// What the DTMF decoder is used for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DtmfMode {
+    /// Decoder off
+    #[default]
+    Off,
+    /// Digits decoded and reported over CAT only
+    Decode,
+    /// Digits reported and run through the remote command map
+    Remote,
+}
+
+impl DtmfMode {
+    /// CAT code (0 = off, 1 = decode, 2 = remote)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Decode => 1,
+            Self::Remote => 2,
+        }
+    }
+
+    /// Parse a CAT code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Off),
+            1 => Some(Self::Decode),
+            2 => Some(Self::Remote),
+            _ => None,
+        }
+    }
+
+    /// Check if the decoder runs in this mode
+    #[must_use]
+    pub const fn decodes(self) -> bool {
+        !matches!(self, Self::Off)
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for DtmfMode {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Off => defmt::write!(f, "Off"),
+            Self::Decode => defmt::write!(f, "Decode"),
+            Self::Remote => defmt::write!(f, "Remote"),
+        }
+    }
+}
+
+/// Factory command map
+const DEFAULT_COMMANDS: [(&str, RadioEvent); 9] = [
+    ("1", RadioEvent::NextMode),
+    ("2", RadioEvent::NextStep),
+    ("3", RadioEvent::CycleAgc),
+    ("4", RadioEvent::ToggleNb),
+    ("5", RadioEvent::TogglePreamp),
+    ("6", RadioEvent::ToggleAtt),
+    ("7", RadioEvent::Tune(-1)),
+    ("9", RadioEvent::Tune(1)),
+    ("0", RadioEvent::SwitchVfo),
+];
+
+/// Check if a code is non-empty, short enough and made of command digits
+fn valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= MAX_CODE_LEN
+        && code.chars().all(|c| c != '*' && c != '#' && is_dtmf_digit(c))
+}
+
+/// DTMF command entry and map
+#[derive(Clone, Debug)]
+pub struct DtmfRemote {
+    /// Whether digits run commands
+    enabled: bool,
+    /// Code keyed ahead of every command (may be empty)
+    access_code: String<MAX_CODE_LEN>,
+    /// Command codes and their events
+    commands: Vec<(String<MAX_CODE_LEN>, RadioEvent), MAX_COMMANDS>,
+    /// Digits keyed since the last `#` or `*`
+    entry: String<MAX_ENTRY_LEN>,
+    /// Whether the entry ran past its length (rejected at `#`)
+    overflow: bool,
+    /// Time of the last digit
+    last_digit_ms: Option<u32>,
+}
+
+impl Default for DtmfRemote {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DtmfRemote {
+    /// Create a disabled remote with no access code and an empty map
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            access_code: String::new(),
+            commands: Vec::new(),
+            entry: String::new(),
+            overflow: false,
+            last_digit_ms: None,
+        }
+    }
+
+    /// Enable or disable commands, discarding any partial entry
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear_entry();
+    }
+
+    /// Check if digits run commands
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the access code (empty for none)
+    ///
+    /// Returns `false` and changes nothing if it is too long or holds
+    /// `*`, `#` or a non-DTMF character.
+    pub fn set_access_code(&mut self, code: &str) -> bool {
+        if !code.is_empty() && !valid_code(code) {
+            return false;
+        }
+        self.access_code.clear();
+        let _ = self.access_code.push_str(code);
+        true
+    }
+
+    /// Get the access code
+    #[must_use]
+    pub fn access_code(&self) -> &str {
+        &self.access_code
+    }
+
+    /// Map a command code to an event, replacing any existing mapping
+    ///
+    /// Returns `false` if the code is invalid or the map is full.
+    pub fn set_command(&mut self, code: &str, event: RadioEvent) -> bool {
+        if !valid_code(code) {
+            return false;
+        }
+        if let Some(entry) = self.commands.iter_mut().find(|(c, _)| c == code) {
+            entry.1 = event;
+            return true;
+        }
+        let mut key = String::new();
+        let _ = key.push_str(code);
+        self.commands.push((key, event)).is_ok()
+    }
+
+    /// Remove a command code, returning `false` if it was not mapped
+    pub fn remove_command(&mut self, code: &str) -> bool {
+        match self.commands.iter().position(|(c, _)| c == code) {
+            Some(index) => {
+                self.commands.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Look up the event for a command code
+    #[must_use]
+    pub fn command(&self, code: &str) -> Option<RadioEvent> {
+        self.commands
+            .iter()
+            .find(|(c, _)| c == code)
+            .map(|&(_, event)| event)
+    }
+
+    /// Get the number of mapped commands
+    #[must_use]
+    pub fn command_count(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Remove every command
+    pub fn clear_commands(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Replace the map with the factory commands
+    pub fn load_defaults(&mut self) {
+        self.commands.clear();
+        for (code, event) in DEFAULT_COMMANDS {
+            self.set_command(code, event);
+        }
+    }
+
+    /// Feed a decoded digit at `now_ms`, returning the event of a completed command
+    ///
+    /// Unknown commands, a wrong access code and digits while disabled
+    /// are ignored.
+    pub fn push_digit(&mut self, digit: char, now_ms: u32) -> Option<RadioEvent> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(last) = self.last_digit_ms {
+            if now_ms.wrapping_sub(last) > DIGIT_TIMEOUT_MS {
+                self.clear_entry();
+            }
+        }
+        self.last_digit_ms = Some(now_ms);
+
+        match digit {
+            '*' => {
+                self.clear_entry();
+                None
+            }
+            '#' => {
+                let event = if self.overflow {
+                    None
+                } else {
+                    self.entry
+                        .strip_prefix(self.access_code.as_str())
+                        .and_then(|code| self.command(code))
+                };
+                self.clear_entry();
+                event
+            }
+            _ if is_dtmf_digit(digit) => {
+                if self.entry.push(digit).is_err() {
+                    self.overflow = true;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the digits keyed so far
+    #[must_use]
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    /// Discard a partly keyed command
+    pub fn clear_entry(&mut self) {
+        self.entry.clear();
+        self.overflow = false;
+        self.last_digit_ms = None;
+    }
+}
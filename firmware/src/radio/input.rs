@@ -0,0 +1,250 @@
+//! Front Panel Input
+//!
+//! Logic behind the tuning encoder and its push button, kept free of
+//! hardware so it runs on the host. [`TuningAcceleration`] scales each
+//! detent by how fast the knob is turning, and [`PushButton`] debounces
+//! the switch and tells short presses from long ones. The encoder driver
+//! feeds them pin levels and timestamps; the UI turns the result into
+//! [`RadioEvent`]s for the radio state machine.
+//!
+//! # Acceleration
+//!
+//! Below [`SLOW_DETENTS_PER_SEC`] every detent is one tuning step, so
+//! with the 10 Hz step a slow turn tunes 10 Hz at a time. Faster turns
+//! multiply the step through 2, 5, 10, 20 and 50 up to
+//! [`MAX_MULTIPLIER`] at [`FAST_DETENTS_PER_SEC`], where a fast spin
+//! tunes 1 kHz per detent. Pausing for a moment drops straight back to
+//! single steps for the final adjustment.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut accel = TuningAcceleration::new();
+//! let mut button = PushButton::new();
+//! // every millisecond
+//! if let Some(direction) = decoder.update(a, b) {
+//!     let clockwise = direction == Direction::Clockwise;
+//!     events.send(accel.tune(clockwise, now_ms)).await;
+//! }
+//! match button.update(switch.is_low(), now_ms) {
+//!     Some(ButtonEvent::Release) => events.send(RadioEvent::NextStep).await,
+//!     Some(ButtonEvent::LongPress) => ui.set_screen(Screen::Menu),
+//!     _ => {}
+//! }
+//! ```
+
+use crate::config::BUTTON_DEBOUNCE_MS;
+use crate::radio::state::RadioEvent;
+
+/// Turn rate up to which each detent is a single step
+pub const SLOW_DETENTS_PER_SEC: f32 = 10.0;
+
+/// Turn rate at which the step multiplier reaches [`MAX_MULTIPLIER`]
+pub const FAST_DETENTS_PER_SEC: f32 = 80.0;
+
+/// Largest step multiplier (10 Hz steps become 1 kHz)
+pub const MAX_MULTIPLIER: u32 = 100;
+
+/// Default hold time for a long press
+pub const LONG_PRESS_MS: u32 = 500;
+
+/// Step multipliers from slow to fast
+const MULTIPLIERS: [u32; 7] = [1, 2, 5, 10, 20, 50, MAX_MULTIPLIER];
+
+/// Pause after which the knob is taken to have stopped
+const IDLE_MS: u32 = 250;
+
+/// Weight of each new detent interval in the smoothed rate
+const SMOOTHING: f32 = 0.5;
+
+/// Velocity-based tuning step acceleration
+#[derive(Clone, Copy, Debug)]
+pub struct TuningAcceleration {
+    /// Smoothed turn rate in detents per second
+    rate: f32,
+    /// Time of the last detent
+    last_ms: Option<u32>,
+    /// Acceleration applied (off in menus, which want single detents)
+    enabled: bool,
+}
+
+impl TuningAcceleration {
+    /// Create with acceleration enabled and the knob at rest
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rate: 0.0,
+            last_ms: None,
+            enabled: true,
+        }
+    }
+
+    /// Enable or disable acceleration
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check if acceleration is enabled
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record a detent at `now_ms` and get its step multiplier
+    #[allow(clippy::cast_precision_loss)]
+    pub fn detent(&mut self, now_ms: u32) -> u32 {
+        let instant = match self.last_ms {
+            Some(last) => {
+                let interval = now_ms.wrapping_sub(last);
+                if interval >= IDLE_MS {
+                    0.0
+                } else {
+                    1000.0 / interval.max(1) as f32
+                }
+            }
+            None => 0.0,
+        };
+        self.last_ms = Some(now_ms);
+        self.rate = if instant == 0.0 || self.rate == 0.0 {
+            instant
+        } else {
+            self.rate + (instant - self.rate) * SMOOTHING
+        };
+        self.multiplier()
+    }
+
+    /// Record a detent and get the tune event for it
+    pub fn tune(&mut self, clockwise: bool, now_ms: u32) -> RadioEvent {
+        let steps = i32::try_from(self.detent(now_ms)).unwrap_or(1);
+        RadioEvent::Tune(if clockwise { steps } else { -steps })
+    }
+
+    /// Get the smoothed turn rate in detents per second
+    #[must_use]
+    pub const fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Get the step multiplier for the current turn rate
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn multiplier(&self) -> u32 {
+        if !self.enabled || self.rate <= SLOW_DETENTS_PER_SEC {
+            return 1;
+        }
+        let fraction = ((self.rate - SLOW_DETENTS_PER_SEC)
+            / (FAST_DETENTS_PER_SEC - SLOW_DETENTS_PER_SEC))
+            .min(1.0);
+        let last = MULTIPLIERS.len() - 1;
+        let index = (fraction * last as f32 + 0.5) as usize;
+        MULTIPLIERS[index.min(last)]
+    }
+
+    /// Forget the turn rate (e.g. after a band change)
+    pub fn reset(&mut self) {
+        self.rate = 0.0;
+        self.last_ms = None;
+    }
+}
+
+impl Default for TuningAcceleration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Push button event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// Button went down
+    Press,
+    /// Button released before the long press time (a short press)
+    Release,
+    /// Button held for the long press time (no release follows)
+    LongPress,
+}
+
+/// Debounced push button with long press detection
+#[derive(Clone, Copy, Debug)]
+pub struct PushButton {
+    /// Last raw level (true when pressed)
+    raw: bool,
+    /// Time the raw level last changed
+    raw_since_ms: u32,
+    /// Debounced level
+    pressed: bool,
+    /// Time of the debounced press
+    pressed_at_ms: u32,
+    /// Long press already reported for this press
+    long_sent: bool,
+    /// Hold time for a long press
+    long_press_ms: u32,
+}
+
+impl PushButton {
+    /// Create a released button
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            raw: false,
+            raw_since_ms: 0,
+            pressed: false,
+            pressed_at_ms: 0,
+            long_sent: false,
+            long_press_ms: LONG_PRESS_MS,
+        }
+    }
+
+    /// Set the hold time for a long press
+    pub fn set_long_press_ms(&mut self, ms: u32) {
+        self.long_press_ms = ms;
+    }
+
+    /// Sample the switch (true when pressed) at `now_ms`
+    ///
+    /// A level must hold for [`BUTTON_DEBOUNCE_MS`] before it counts.
+    pub fn update(&mut self, raw_pressed: bool, now_ms: u32) -> Option<ButtonEvent> {
+        if raw_pressed != self.raw {
+            self.raw = raw_pressed;
+            self.raw_since_ms = now_ms;
+        }
+
+        if self.raw != self.pressed
+            && now_ms.wrapping_sub(self.raw_since_ms) >= BUTTON_DEBOUNCE_MS
+        {
+            self.pressed = self.raw;
+            if self.pressed {
+                self.pressed_at_ms = now_ms;
+                self.long_sent = false;
+                return Some(ButtonEvent::Press);
+            }
+            return (!self.long_sent).then_some(ButtonEvent::Release);
+        }
+
+        if self.pressed
+            && !self.long_sent
+            && now_ms.wrapping_sub(self.pressed_at_ms) >= self.long_press_ms
+        {
+            self.long_sent = true;
+            return Some(ButtonEvent::LongPress);
+        }
+
+        None
+    }
+
+    /// Check if the button is pressed (debounced)
+    #[must_use]
+    pub const fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+}
+
+impl Default for PushButton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -6,8 +6,73 @@
 //! # Iambic Keying
 //!
 //! Iambic keyers use squeeze paddles where pressing both paddles
-//! alternates between dit and dah. Mode A releases at element end,
-//! Mode B adds one more element after release.
+//! alternates between dit and dah. The paddles are read at the end of
+//! each element's space, and a paddle newly pressed while an element is
+//! being sent is remembered until then, so a quick tap is never lost.
+//! Mode A stops after the element in progress once the paddles are
+//! released; Mode B, like the Curtis chip, remembers a squeeze while an
+//! element sounds and answers it with exactly one opposite element.
+//!
+//! The paddles can be swapped for left-handed operation. The dah length
+//! is set as a ratio to the dit, separately from the weight, and the
+//! first element after the key has been idle can be lengthened to make
+//! up for what a slow T/R relay cuts off the front of it.
+//!
+//! # Text Sending
+//!
+//! Characters queued from a host (e.g. the `WinKeyer` emulation) are sent
+//! through the same timing engine. Touching a paddle while text is being
+//! sent aborts the queue (break-in).
+//!
+//! Letters enclosed in angle brackets are sent as one prosign without
+//! inter-character gaps (`<SK>`, `<AR>`, `<BT>`); `+`, `=` and `(` are
+//! shortcuts for AR, BT and KN.
+//!
+//! # Farnsworth Timing
+//!
+//! With an effective speed below the character speed, characters keep
+//! their full-speed shape and the extra time goes into the character and
+//! word spaces (ARRL method). The word space length is adjustable for
+//! beacons and practice sending.
+//!
+//! # Message Memories
+//!
+//! Eight stored messages can be played into the same queue. Macros are
+//! expanded at playback time:
+//!
+//! - `{CALL}` station callsign
+//! - `{RST}` signal report
+//! - `{NR}` contest serial number, incremented after each message that
+//!   sends it
+//!
+//! With cut numbers enabled, `0` and `9` in reports and serials are sent
+//! as `T` and `N` (599 001 becomes 5NN TT1).
+
+use heapless::{Deque, String};
+
+/// Capacity of the text send queue
+pub const TEXT_QUEUE_LEN: usize = 128;
+
+/// Number of stored messages
+pub const NUM_MESSAGES: usize = 8;
+
+/// Maximum length of a stored message (before macro expansion)
+pub const MESSAGE_LEN: usize = 48;
+
+/// Maximum callsign length
+pub const CALLSIGN_LEN: usize = 12;
+
+/// Maximum length of an encoded Morse pattern (merged prosigns)
+pub const PATTERN_LEN: usize = 24;
+
+/// Standard dah length in tenths of a dit (3:1)
+pub const DEFAULT_DAH_RATIO: u8 = 30;
+
+/// Dah length range in tenths of a dit
+pub const DAH_RATIO_RANGE: (u8, u8) = (20, 45);
+
+/// Longest first-element extension in milliseconds
+pub const MAX_FIRST_EXTENSION_MS: u8 = 250;
 
 /// Keyer operating mode
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -111,12 +176,11 @@ enum KeyerState {
     SendingDah,
     /// Inter-element gap
     ElementGap,
-    /// Waiting for next element (Mode B check)
-    WaitNext,
 }
 
 /// CW Keyer with iambic support
 #[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Keyer {
     /// Operating mode
     mode: KeyerMode,
@@ -128,20 +192,42 @@ pub struct Keyer {
     state: KeyerState,
     /// Samples remaining in current element
     samples_remaining: u32,
-    /// Last element sent (for alternation)
+    /// Last tone element started (for alternation)
     last_element: Element,
-    /// Paddle state at element start (for Mode B)
-    paddle_at_start: PaddleState,
-    /// Memory for squeeze detection
+    /// Paddles on the previous sample (for press detection)
+    prev_paddle: PaddleState,
+    /// Dit to send after the current element
     dit_memory: bool,
-    /// Memory for squeeze detection
+    /// Dah to send after the current element
     dah_memory: bool,
+    /// Dit and dah paddles swapped
+    paddle_swap: bool,
     /// Weighting (50 = standard, <50 = lighter, >50 = heavier)
     weight: u8,
+    /// Dah length in tenths of a dit
+    dah_ratio: u8,
+    /// Extension of the first element after idle in milliseconds
+    first_extension_ms: u8,
     /// Sidetone frequency in Hz
     sidetone_freq: u16,
     /// Current output state (key down)
     key_down: bool,
+    /// Characters waiting to be sent
+    text: Deque<char, TEXT_QUEUE_LEN>,
+    /// Encoder for the character being sent
+    encoder: MorseEncoder,
+    /// Character that most recently started sending
+    sent: Option<char>,
+    /// Hold queued text at the next character boundary
+    text_paused: bool,
+    /// Key held down for tuning
+    tune: bool,
+    /// Stored messages and contest state
+    memory: CwMemory,
+    /// Farnsworth effective speed in WPM (0 = off)
+    farnsworth_wpm: u8,
+    /// Word space in (Farnsworth) units, measured from the end of a character
+    word_space: u8,
 }
 
 impl Keyer {
@@ -160,6 +246,15 @@ impl Keyer {
     /// Default sidetone frequency
     pub const DEFAULT_SIDETONE_HZ: u16 = 700;
 
+    /// Sidetone frequency range in Hz
+    pub const SIDETONE_RANGE_HZ: (u16, u16) = (300, 1200);
+
+    /// Standard word space in units
+    pub const DEFAULT_WORD_SPACE: u8 = 7;
+
+    /// Word space range in units (must exceed the 3 unit character space)
+    pub const WORD_SPACE_RANGE: (u8, u8) = (4, 21);
+
     /// Create a new keyer
     #[must_use]
     pub fn new(sample_rate: u32) -> Self {
@@ -170,12 +265,23 @@ impl Keyer {
             state: KeyerState::Idle,
             samples_remaining: 0,
             last_element: Element::None,
-            paddle_at_start: PaddleState::default(),
+            prev_paddle: PaddleState::default(),
             dit_memory: false,
             dah_memory: false,
+            paddle_swap: false,
             weight: 50,
+            dah_ratio: DEFAULT_DAH_RATIO,
+            first_extension_ms: 0,
             sidetone_freq: Self::DEFAULT_SIDETONE_HZ,
             key_down: false,
+            text: Deque::new(),
+            encoder: MorseEncoder::new(),
+            sent: None,
+            text_paused: false,
+            tune: false,
+            memory: CwMemory::new(),
+            farnsworth_wpm: 0,
+            word_space: Self::DEFAULT_WORD_SPACE,
         }
     }
 
@@ -201,6 +307,44 @@ impl Keyer {
         self.wpm
     }
 
+    /// Set Farnsworth effective speed in WPM (0 = off)
+    ///
+    /// Has no effect while at or above the character speed.
+    pub fn set_farnsworth(&mut self, wpm: u8) {
+        self.farnsworth_wpm = if wpm == 0 {
+            0
+        } else {
+            wpm.clamp(Self::MIN_WPM, Self::MAX_WPM)
+        };
+    }
+
+    /// Get Farnsworth effective speed (0 = off)
+    #[must_use]
+    pub const fn farnsworth(&self) -> u8 {
+        self.farnsworth_wpm
+    }
+
+    /// Get the overall sending speed in WPM
+    #[must_use]
+    pub const fn effective_wpm(&self) -> u8 {
+        if self.farnsworth_wpm != 0 && self.farnsworth_wpm < self.wpm {
+            self.farnsworth_wpm
+        } else {
+            self.wpm
+        }
+    }
+
+    /// Set word space in units (7 = standard)
+    pub fn set_word_space(&mut self, units: u8) {
+        self.word_space = units.clamp(Self::WORD_SPACE_RANGE.0, Self::WORD_SPACE_RANGE.1);
+    }
+
+    /// Get word space in units
+    #[must_use]
+    pub const fn word_space(&self) -> u8 {
+        self.word_space
+    }
+
     /// Set weighting (50 = standard)
     pub fn set_weight(&mut self, weight: u8) {
         self.weight = weight.clamp(25, 75);
@@ -212,9 +356,47 @@ impl Keyer {
         self.weight
     }
 
+    /// Set the dah length in tenths of a dit (30 = standard 3:1)
+    ///
+    /// Unlike the weight, this leaves dits and spaces alone.
+    pub fn set_dah_ratio(&mut self, tenths: u8) {
+        self.dah_ratio = tenths.clamp(DAH_RATIO_RANGE.0, DAH_RATIO_RANGE.1);
+    }
+
+    /// Get the dah length in tenths of a dit
+    #[must_use]
+    pub const fn dah_ratio(&self) -> u8 {
+        self.dah_ratio
+    }
+
+    /// Set the extension of the first element after idle in milliseconds
+    ///
+    /// Makes up for the T/R switching time that would otherwise shorten
+    /// the first dit or dah of an over.
+    pub fn set_first_extension_ms(&mut self, ms: u8) {
+        self.first_extension_ms = ms.min(MAX_FIRST_EXTENSION_MS);
+    }
+
+    /// Get the first-element extension in milliseconds
+    #[must_use]
+    pub const fn first_extension_ms(&self) -> u8 {
+        self.first_extension_ms
+    }
+
+    /// Swap the dit and dah paddles (left-handed operation)
+    pub fn set_paddle_swap(&mut self, swap: bool) {
+        self.paddle_swap = swap;
+    }
+
+    /// Check if the paddles are swapped
+    #[must_use]
+    pub const fn paddle_swap(&self) -> bool {
+        self.paddle_swap
+    }
+
     /// Set sidetone frequency
     pub fn set_sidetone(&mut self, freq: u16) {
-        self.sidetone_freq = freq.clamp(300, 1200);
+        self.sidetone_freq = freq.clamp(Self::SIDETONE_RANGE_HZ.0, Self::SIDETONE_RANGE_HZ.1);
     }
 
     /// Get sidetone frequency
@@ -231,21 +413,144 @@ impl Keyer {
 
     /// Check if keyer is idle
     #[must_use]
-    pub const fn is_idle(&self) -> bool {
-        matches!(self.state, KeyerState::Idle)
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, KeyerState::Idle) && !self.is_sending_text() && !self.tune
+    }
+
+    /// Queue a character for sending, returns false if the queue is full
+    pub fn queue_char(&mut self, c: char) -> bool {
+        self.text.push_back(c).is_ok()
+    }
+
+    /// Queue a string for sending, returns the number of characters queued
+    pub fn queue_text(&mut self, text: &str) -> usize {
+        text.chars().take_while(|&c| self.queue_char(c)).count()
+    }
+
+    /// Remove the most recently queued character (if not yet started)
+    pub fn backspace(&mut self) -> Option<char> {
+        self.text.pop_back()
+    }
+
+    /// Abort text sending and discard the queue
+    pub fn clear_text(&mut self) {
+        self.text.clear();
+        self.encoder = MorseEncoder::new();
+        if !matches!(self.state, KeyerState::Idle) && !self.tune {
+            self.state = KeyerState::Idle;
+            self.samples_remaining = 0;
+            self.key_down = false;
+        }
+    }
+
+    /// Number of characters waiting to be sent
+    #[must_use]
+    pub fn text_pending(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Check if queued text is being sent
+    #[must_use]
+    pub fn is_sending_text(&self) -> bool {
+        !self.text.is_empty() || !self.encoder.is_idle()
+    }
+
+    /// Take the character that most recently started sending (for echo)
+    pub fn take_sent(&mut self) -> Option<char> {
+        self.sent.take()
+    }
+
+    /// Hold or release queued text at the next character boundary
+    pub fn set_text_paused(&mut self, paused: bool) {
+        self.text_paused = paused;
+    }
+
+    /// Get stored messages
+    #[must_use]
+    pub const fn memory(&self) -> &CwMemory {
+        &self.memory
+    }
+
+    /// Get stored messages for editing
+    pub fn memory_mut(&mut self) -> &mut CwMemory {
+        &mut self.memory
+    }
+
+    /// Play a stored message (0-based), replacing any queued text
+    ///
+    /// Returns false if the slot is empty or out of range.
+    pub fn play_message(&mut self, index: usize) -> bool {
+        let Some(text) = self.memory.expand(index) else {
+            return false;
+        };
+        self.clear_text();
+        self.queue_text(&text);
+        if self.memory.uses_serial(index) {
+            self.memory.increment_serial();
+        }
+        true
+    }
+
+    /// Abort message playback
+    pub fn abort_message(&mut self) {
+        self.clear_text();
+    }
+
+    /// Check if the key is held down for tuning
+    #[must_use]
+    pub const fn is_tuning(&self) -> bool {
+        self.tune
+    }
+
+    /// Hold the key down (tune) or release it
+    pub fn set_tune(&mut self, on: bool) {
+        self.tune = on;
+        self.key_down = on;
+        if !on {
+            self.state = KeyerState::Idle;
+            self.samples_remaining = 0;
+        }
     }
 
     /// Calculate samples per timing unit at current WPM
+    ///
+    /// Worked out in samples rather than whole milliseconds, so speeds
+    /// that do not divide 1200 ms keep their exact unit length.
+    #[allow(clippy::cast_possible_truncation)]
     fn samples_per_unit(&self) -> u32 {
         // Unit duration in ms = 1200 / WPM
-        // Samples = duration_ms * sample_rate / 1000
-        let ms_per_unit = Self::MS_PER_UNIT_AT_1WPM / u32::from(self.wpm);
-        ms_per_unit * self.sample_rate / 1000
+        let samples = u64::from(Self::MS_PER_UNIT_AT_1WPM) * u64::from(self.sample_rate)
+            / (1000 * u64::from(self.wpm));
+        samples as u32
+    }
+
+    /// Calculate samples per spacing unit (stretched by Farnsworth timing)
+    ///
+    /// A PARIS word at effective speed `s` and character speed `c` has
+    /// `(60c - 37.2s) / sc` seconds of delay, spread over 19 spacing units.
+    #[allow(clippy::cast_possible_truncation)]
+    fn samples_per_space_unit(&self) -> u32 {
+        let c = u64::from(self.wpm);
+        let s = u64::from(self.effective_wpm());
+        if s >= c {
+            return self.samples_per_unit();
+        }
+        let delay_ms_x19 = (60_000 * c - 37_200 * s) / (s * c);
+        (delay_ms_x19 * u64::from(self.sample_rate) / 19_000) as u32
     }
 
     /// Calculate samples for an element with weighting
     fn samples_for_element(&self, element: Element) -> u32 {
-        let base_samples = self.samples_per_unit() * element.units();
+        let base_samples = match element {
+            // Character space is 3 units including the element gap already sent
+            Element::CharGap => {
+                (3 * self.samples_per_space_unit()).saturating_sub(self.samples_per_unit())
+            }
+            // Word space replaces the character space
+            Element::WordGap => u32::from(self.word_space - 3) * self.samples_per_space_unit(),
+            Element::Dah => self.samples_per_unit() * u32::from(self.dah_ratio) / 10,
+            _ => self.samples_per_unit() * element.units(),
+        };
 
         if element.is_tone() {
             // Apply weighting to tone elements
@@ -262,12 +567,24 @@ impl Keyer {
     pub fn process(&mut self, paddle: PaddleState) -> bool {
         let old_key_down = self.key_down;
 
-        // Update memories on paddle press
-        if paddle.dit {
-            self.dit_memory = true;
+        if self.tune {
+            return false;
+        }
+
+        let paddle = if self.paddle_swap {
+            PaddleState::new(paddle.dah, paddle.dit)
+        } else {
+            paddle
+        };
+
+        // Paddle break-in aborts queued text
+        if paddle.is_pressed() && self.is_sending_text() {
+            self.clear_text();
         }
-        if paddle.dah {
-            self.dah_memory = true;
+
+        if self.is_sending_text() {
+            self.process_text();
+            return old_key_down != self.key_down;
         }
 
         match self.mode {
@@ -281,6 +598,65 @@ impl Keyer {
         old_key_down != self.key_down
     }
 
+    /// Send queued text
+    fn process_text(&mut self) {
+        if self.samples_remaining > 0 {
+            self.samples_remaining -= 1;
+            return;
+        }
+
+        // Tone finished, start the element gap
+        if self.key_down {
+            self.key_down = false;
+            self.state = KeyerState::ElementGap;
+            self.samples_remaining = self.samples_for_element(Element::ElementGap);
+            return;
+        }
+
+        // Load the next character at a character boundary
+        if self.encoder.is_idle() {
+            if self.text_paused {
+                self.state = KeyerState::Idle;
+                return;
+            }
+            let Some(c) = self.text.pop_front() else {
+                self.state = KeyerState::Idle;
+                return;
+            };
+            if c == '<' {
+                self.load_prosign();
+            } else {
+                self.encoder.load(c);
+            }
+            if self.encoder.is_idle() {
+                // Not in the Morse table, skip
+                return;
+            }
+            self.sent = Some(c);
+        }
+
+        match self.encoder.next_element() {
+            Some(element) if element.is_tone() => self.start_element(element),
+            Some(gap) => {
+                self.state = KeyerState::ElementGap;
+                self.samples_remaining = self.samples_for_element(gap);
+            }
+            None => {}
+        }
+    }
+
+    /// Load the letters up to the closing `>` as one merged prosign
+    fn load_prosign(&mut self) {
+        let mut letters: String<8> = String::new();
+        while let Some(c) = self.text.pop_front() {
+            if c == '>' {
+                break;
+            }
+            let _ = letters.push(c);
+        }
+        self.encoder.load_merged(&letters);
+    }
+
     /// Process straight key mode
     fn process_straight(&mut self, paddle: PaddleState) {
         // In straight key mode, dit paddle = key down
@@ -294,6 +670,25 @@ impl Keyer {
 
     /// Process iambic mode (A or B)
     fn process_iambic(&mut self, paddle: PaddleState, mode_b: bool) {
+        // Remember paddles pressed during an element; in Mode B a
+        // squeeze while the element sounds queues the opposite element
+        let pressed = PaddleState::new(
+            paddle.dit && !self.prev_paddle.dit,
+            paddle.dah && !self.prev_paddle.dah,
+        );
+        self.prev_paddle = paddle;
+        if self.state != KeyerState::Idle {
+            self.dit_memory |= pressed.dit;
+            self.dah_memory |= pressed.dah;
+            if mode_b && self.key_down && paddle.is_squeeze() {
+                if self.last_element == Element::Dit {
+                    self.dah_memory = true;
+                } else {
+                    self.dit_memory = true;
+                }
+            }
+        }
+
         if self.samples_remaining > 0 {
             self.samples_remaining -= 1;
             return;
@@ -301,15 +696,10 @@ impl Keyer {
 
         match self.state {
             KeyerState::Idle => {
-                // Start new element based on paddle
-                if self.dit_memory {
+                if paddle.dit {
                     self.start_element(Element::Dit);
-                    self.dit_memory = false;
-                    self.paddle_at_start = paddle;
-                } else if self.dah_memory {
+                } else if paddle.dah {
                     self.start_element(Element::Dah);
-                    self.dah_memory = false;
-                    self.paddle_at_start = paddle;
                 }
             }
 
@@ -318,45 +708,25 @@ impl Keyer {
                 self.state = KeyerState::ElementGap;
                 self.samples_remaining = self.samples_for_element(Element::ElementGap);
                 self.key_down = false;
-                self.last_element = if self.state == KeyerState::SendingDit {
-                    Element::Dit
-                } else {
-                    Element::Dah
-                };
             }
 
             KeyerState::ElementGap => {
-                // Gap finished, check for next element
-                if mode_b {
-                    self.state = KeyerState::WaitNext;
-                    // Check memories for Mode B continuation
-                    if self.paddle_at_start.is_squeeze() {
-                        // Alternate
-                        if self.last_element == Element::Dit && !self.dah_memory {
-                            self.dah_memory = true;
-                        } else if self.last_element == Element::Dah && !self.dit_memory {
-                            self.dit_memory = true;
-                        }
-                    }
-                }
-
-                // Start next element or go idle
-                if self.dit_memory {
-                    self.start_element(Element::Dit);
-                    self.dit_memory = false;
-                    self.paddle_at_start = paddle;
-                } else if self.dah_memory {
-                    self.start_element(Element::Dah);
-                    self.dah_memory = false;
-                    self.paddle_at_start = paddle;
-                } else {
+                // Alternate if the other paddle is held or remembered,
+                // else repeat while this one is
+                let dit_wanted = paddle.dit || self.dit_memory;
+                let dah_wanted = paddle.dah || self.dah_memory;
+                let next = match self.last_element {
+                    Element::Dit if dah_wanted => Element::Dah,
+                    _ if dit_wanted => Element::Dit,
+                    _ if dah_wanted => Element::Dah,
+                    _ => Element::None,
+                };
+                if next == Element::None {
                     self.state = KeyerState::Idle;
+                } else {
+                    self.start_element(next);
                 }
             }
-
-            KeyerState::WaitNext => {
-                self.state = KeyerState::Idle;
-            }
         }
     }
 
@@ -401,10 +771,6 @@ impl Keyer {
                     self.state = KeyerState::Idle;
                 }
             }
-
-            KeyerState::WaitNext => {
-                self.state = KeyerState::Idle;
-            }
         }
     }
 
@@ -441,22 +807,28 @@ impl Keyer {
                     self.state = KeyerState::Idle;
                 }
             }
-
-            KeyerState::WaitNext => {
-                self.state = KeyerState::Idle;
-            }
         }
     }
 
     /// Start sending an element
+    ///
+    /// A tone started from idle gets the first-element extension.
     fn start_element(&mut self, element: Element) {
         self.samples_remaining = self.samples_for_element(element);
+        if element.is_tone() && self.state == KeyerState::Idle {
+            self.samples_remaining += u32::from(self.first_extension_ms) * self.sample_rate / 1000;
+        }
         self.key_down = element.is_tone();
         self.state = match element {
             Element::Dit => KeyerState::SendingDit,
             Element::Dah => KeyerState::SendingDah,
             _ => KeyerState::Idle,
         };
+        match element {
+            Element::Dit => self.dit_memory = false,
+            Element::Dah => self.dah_memory = false,
+            _ => {}
+        }
         self.last_element = element;
     }
 
@@ -467,6 +839,11 @@ impl Keyer {
         self.key_down = false;
         self.dit_memory = false;
         self.dah_memory = false;
+        self.prev_paddle = PaddleState::default();
+        self.text.clear();
+        self.encoder = MorseEncoder::new();
+        self.sent = None;
+        self.tune = false;
     }
 }
 
@@ -476,10 +853,186 @@ impl Default for Keyer {
     }
 }
 
+/// Stored CW messages with contest macros
+#[derive(Clone, Debug)]
+pub struct CwMemory {
+    /// Message slots
+    messages: [String<MESSAGE_LEN>; NUM_MESSAGES],
+    /// Station callsign for `{CALL}`
+    callsign: String<CALLSIGN_LEN>,
+    /// Signal report for `{RST}`
+    rst: String<3>,
+    /// Next serial number for `{NR}`
+    serial: u16,
+    /// Send 0 as T and 9 as N in numbers
+    cut_numbers: bool,
+}
+
+impl CwMemory {
+    /// Callsign macro
+    pub const MACRO_CALL: &'static str = "{CALL}";
+
+    /// Signal report macro
+    pub const MACRO_RST: &'static str = "{RST}";
+
+    /// Serial number macro
+    pub const MACRO_NR: &'static str = "{NR}";
+
+    /// Expanded message capacity (macros may lengthen the text)
+    pub const EXPANDED_LEN: usize = 96;
+
+    /// Create empty memories (serial starts at 1, report 599)
+    #[must_use]
+    pub fn new() -> Self {
+        let mut rst = String::new();
+        let _ = rst.push_str("599");
+        Self {
+            messages: Default::default(),
+            callsign: String::new(),
+            rst,
+            serial: 1,
+            cut_numbers: false,
+        }
+    }
+
+    /// Store a message (0-based slot), returns false if out of range or too long
+    pub fn set_message(&mut self, index: usize, text: &str) -> bool {
+        let Some(slot) = self.messages.get_mut(index) else {
+            return false;
+        };
+        let mut new = String::new();
+        if new.push_str(text).is_err() {
+            return false;
+        }
+        *slot = new;
+        true
+    }
+
+    /// Get a stored message (0-based slot)
+    #[must_use]
+    pub fn message(&self, index: usize) -> Option<&str> {
+        self.messages.get(index).map(String::as_str)
+    }
+
+    /// Set the station callsign, returns false if too long
+    pub fn set_callsign(&mut self, call: &str) -> bool {
+        let mut new = String::new();
+        if new.push_str(call).is_err() {
+            return false;
+        }
+        self.callsign = new;
+        true
+    }
+
+    /// Get the station callsign
+    #[must_use]
+    pub fn callsign(&self) -> &str {
+        &self.callsign
+    }
+
+    /// Set the signal report, returns false if longer than 3 characters
+    pub fn set_rst(&mut self, rst: &str) -> bool {
+        let mut new = String::new();
+        if new.push_str(rst).is_err() {
+            return false;
+        }
+        self.rst = new;
+        true
+    }
+
+    /// Get the signal report
+    #[must_use]
+    pub fn rst(&self) -> &str {
+        &self.rst
+    }
+
+    /// Set the next serial number
+    pub fn set_serial(&mut self, serial: u16) {
+        self.serial = serial.clamp(1, 9999);
+    }
+
+    /// Get the next serial number
+    #[must_use]
+    pub const fn serial(&self) -> u16 {
+        self.serial
+    }
+
+    /// Advance to the next serial number
+    pub fn increment_serial(&mut self) {
+        self.serial = (self.serial + 1).min(9999);
+    }
+
+    /// Enable or disable cut numbers
+    pub fn set_cut_numbers(&mut self, on: bool) {
+        self.cut_numbers = on;
+    }
+
+    /// Check if cut numbers are enabled
+    #[must_use]
+    pub const fn cut_numbers(&self) -> bool {
+        self.cut_numbers
+    }
+
+    /// Check if a message sends the serial number
+    #[must_use]
+    pub fn uses_serial(&self, index: usize) -> bool {
+        self.message(index).is_some_and(|m| m.contains(Self::MACRO_NR))
+    }
+
+    /// Expand macros in a stored message (None if empty or out of range)
+    #[must_use]
+    pub fn expand(&self, index: usize) -> Option<String<{ Self::EXPANDED_LEN }>> {
+        let mut rest = self.message(index).filter(|m| !m.is_empty())?;
+        let mut out = String::new();
+
+        while let Some(start) = rest.find('{') {
+            let _ = out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(tail) = rest.strip_prefix(Self::MACRO_CALL) {
+                let _ = out.push_str(&self.callsign);
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix(Self::MACRO_RST) {
+                self.push_number(&mut out, &self.rst);
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix(Self::MACRO_NR) {
+                let mut nr: String<4> = String::new();
+                let _ = core::fmt::write(&mut nr, format_args!("{:03}", self.serial));
+                self.push_number(&mut out, &nr);
+                rest = tail;
+            } else {
+                // Not a macro, send the brace literally
+                let _ = out.push('{');
+                rest = &rest[1..];
+            }
+        }
+        let _ = out.push_str(rest);
+        Some(out)
+    }
+
+    /// Append digits, applying cut numbers if enabled
+    fn push_number<const N: usize>(&self, out: &mut String<N>, digits: &str) {
+        for c in digits.chars() {
+            let c = match c {
+                '0' if self.cut_numbers => 'T',
+                '9' if self.cut_numbers => 'N',
+                _ => c,
+            };
+            let _ = out.push(c);
+        }
+    }
+}
+
+impl Default for CwMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Morse code character encoder
+#[derive(Clone, Debug)]
 pub struct MorseEncoder {
-    /// Current character being sent
-    current: Option<&'static str>,
+    /// Pattern of the character being sent (`.`, `-`, or `' '` for a word space)
+    current: Option<String<PATTERN_LEN>>,
     /// Position within current character
     position: usize,
 }
@@ -496,13 +1049,36 @@ impl MorseEncoder {
 
     /// Load a character to send
     pub fn load(&mut self, c: char) {
-        self.current = Self::char_to_morse(c);
+        self.current = Self::char_to_morse(c).and_then(|morse| String::try_from(morse).ok());
         self.position = 0;
     }
 
+    /// Load letters to send as one prosign (no inter-character gaps)
+    ///
+    /// Returns false (and stays idle) if a letter has no Morse code or
+    /// the merged pattern is too long.
+    pub fn load_merged(&mut self, letters: &str) -> bool {
+        self.current = None;
+        self.position = 0;
+
+        let mut pattern = String::new();
+        for c in letters.chars() {
+            let Some(morse) = Self::char_to_morse(c).filter(|m| *m != " ") else {
+                return false;
+            };
+            if pattern.push_str(morse).is_err() {
+                return false;
+            }
+        }
+        if !pattern.is_empty() {
+            self.current = Some(pattern);
+        }
+        self.current.is_some()
+    }
+
     /// Get next element to send
     pub fn next_element(&mut self) -> Option<Element> {
-        let morse = self.current?;
+        let morse = self.current.as_ref()?;
         let bytes = morse.as_bytes();
 
         if self.position >= bytes.len() {
@@ -513,6 +1089,11 @@ impl MorseEncoder {
         let element = match bytes[self.position] {
             b'.' => Element::Dit,
             b'-' => Element::Dah,
+            b' ' => {
+                // Word gap replaces the character gap
+                self.current = None;
+                return Some(Element::WordGap);
+            }
             _ => return None,
         };
 
@@ -571,7 +1152,10 @@ impl MorseEncoder {
             ',' => Some("--..--"),
             '?' => Some("..--.."),
             '/' => Some("-..-."),
-            '=' => Some("-...-"),
+            '=' => Some("-...-"), // BT
+            '+' => Some(".-.-."), // AR
+            '(' => Some("-.--."), // KN
+            '&' => Some(".-..."), // AS
             ' ' => Some(" "), // Word gap
             _ => None,
         }
@@ -704,6 +1288,139 @@ mod tests {
         assert!(!keyer.is_key_down());
     }
 
+    /// Key-down run lengths in samples over `samples` calls with the
+    /// paddles given for each sample
+    fn key_runs(keyer: &mut Keyer, samples: u32, paddle: impl Fn(u32) -> PaddleState) -> Vec<u32> {
+        let mut runs = Vec::new();
+        let mut run = 0;
+        for n in 0..samples {
+            keyer.process(paddle(n));
+            if keyer.is_key_down() {
+                run += 1;
+            } else if run > 0 {
+                runs.push(run);
+                run = 0;
+            }
+        }
+        runs
+    }
+
+    /// Check runs against dit/dah lengths in units (1 sample of slack)
+    fn assert_elements(runs: &[u32], units: &[u32], spu: u32) {
+        assert_eq!(runs.len(), units.len(), "{runs:?}");
+        for (run, unit) in runs.iter().zip(units) {
+            assert!(run.abs_diff(unit * spu) <= 1, "{runs:?} for {units:?}");
+        }
+    }
+
+    #[test]
+    fn keyer_unit_is_exact() {
+        let mut keyer = Keyer::new(8000);
+        keyer.set_wpm(22);
+        // 54.5 ms, not a truncated 54 ms
+        assert_eq!(keyer.samples_per_unit(), 436);
+    }
+
+    #[test]
+    fn keyer_iambic_squeeze_alternates() {
+        for mode in [KeyerMode::IambicA, KeyerMode::IambicB] {
+            let mut keyer = Keyer::new(8000);
+            keyer.set_mode(mode);
+            let spu = keyer.samples_per_unit();
+            let runs = key_runs(&mut keyer, 15 * spu, |_| PaddleState::new(true, true));
+            assert_elements(&runs[..4], &[1, 3, 1, 3], spu);
+
+            // A single paddle repeats its element
+            let mut keyer = Keyer::new(8000);
+            keyer.set_mode(mode);
+            let runs = key_runs(&mut keyer, 12 * spu, |_| PaddleState::new(false, true));
+            assert_elements(&runs, &[3, 3, 3], spu);
+        }
+    }
+
+    #[test]
+    fn keyer_mode_b_adds_one_element() {
+        let spu = Keyer::new(8000).samples_per_unit();
+        // Squeeze released during the first dit, then during the dah
+        let cases = [
+            (spu / 2, &[1][..], &[1, 3][..]),
+            (2 * spu + spu, &[1, 3][..], &[1, 3, 1][..]),
+        ];
+        for (release, mode_a, mode_b) in cases {
+            for (mode, expected) in [(KeyerMode::IambicA, mode_a), (KeyerMode::IambicB, mode_b)] {
+                let mut keyer = Keyer::new(8000);
+                keyer.set_mode(mode);
+                let runs = key_runs(&mut keyer, 20 * spu, |n| {
+                    PaddleState::new(n < release, n < release)
+                });
+                assert_elements(&runs, expected, spu);
+            }
+        }
+    }
+
+    #[test]
+    fn keyer_remembers_tap_during_element() {
+        let mut keyer = Keyer::new(8000);
+        let spu = keyer.samples_per_unit();
+        // Dah tapped and released while a dit sounds
+        let runs = key_runs(&mut keyer, 10 * spu, |n| {
+            PaddleState::new(n < spu / 4, (spu / 2 + 1..3 * spu / 4).contains(&n))
+        });
+        assert_elements(&runs, &[1, 3], spu);
+    }
+
+    #[test]
+    fn keyer_paddle_swap() {
+        let mut keyer = Keyer::new(8000);
+        keyer.set_paddle_swap(true);
+        assert!(keyer.paddle_swap());
+        let spu = keyer.samples_per_unit();
+        let runs = key_runs(&mut keyer, 5 * spu, |n| PaddleState::new(n < 10, false));
+        assert_elements(&runs, &[3], spu);
+    }
+
+    #[test]
+    fn keyer_dah_ratio_is_separate_from_weight() {
+        let mut keyer = Keyer::new(8000);
+        keyer.set_dah_ratio(40);
+        assert_eq!(keyer.dah_ratio(), 40);
+        let spu = keyer.samples_per_unit();
+        let runs = key_runs(&mut keyer, 12 * spu, |n| PaddleState::new(n < 10, (10..20).contains(&n)));
+        assert_elements(&runs, &[1, 4], spu);
+
+        keyer.set_dah_ratio(5);
+        assert_eq!(keyer.dah_ratio(), DAH_RATIO_RANGE.0);
+        keyer.set_dah_ratio(DEFAULT_DAH_RATIO);
+        keyer.set_weight(60);
+        assert_eq!(
+            keyer.samples_for_element(Element::Dah),
+            3 * keyer.samples_for_element(Element::Dit)
+        );
+    }
+
+    #[test]
+    fn keyer_first_element_extension() {
+        let mut keyer = Keyer::new(8000);
+        keyer.set_first_extension_ms(30);
+        assert_eq!(keyer.first_extension_ms(), 30);
+        let spu = keyer.samples_per_unit();
+        let extension = 30 * 8;
+
+        // Only the first element of a run of dits is lengthened
+        let runs = key_runs(&mut keyer, 8 * spu, |n| PaddleState::new(n < 5 * spu, false));
+        assert!(runs[0].abs_diff(spu + extension) <= 1, "{runs:?}");
+        assert!(runs[1..].iter().all(|run| run.abs_diff(spu) <= 1), "{runs:?}");
+
+        // Sent text gets it too
+        let mut keyer = Keyer::new(8000);
+        keyer.set_first_extension_ms(30);
+        let (down, _) = send_text(&mut keyer, "E");
+        assert!(down.abs_diff(spu + extension) <= 1);
+
+        keyer.set_first_extension_ms(255);
+        assert_eq!(keyer.first_extension_ms(), MAX_FIRST_EXTENSION_MS);
+    }
+
     #[test]
     fn keyer_reset() {
         let mut keyer = Keyer::new(48000);
@@ -816,6 +1533,212 @@ mod tests {
         assert!(encoder.is_idle());
     }
 
+    #[test]
+    fn morse_encoder_word_gap() {
+        let mut encoder = MorseEncoder::new();
+        encoder.load(' ');
+        assert_eq!(encoder.next_element(), Some(Element::WordGap));
+        assert!(encoder.is_idle());
+    }
+
+    #[test]
+    fn morse_encoder_merged_prosign() {
+        let mut encoder = MorseEncoder::new();
+
+        // SK is ...-.- with no character gap between S and K
+        assert!(encoder.load_merged("SK"));
+        let mut elements = heapless::Vec::<Element, 8>::new();
+        while let Some(element) = encoder.next_element() {
+            let _ = elements.push(element);
+            if element == Element::CharGap {
+                break;
+            }
+        }
+        assert_eq!(
+            elements.as_slice(),
+            &[
+                Element::Dit,
+                Element::Dit,
+                Element::Dit,
+                Element::Dah,
+                Element::Dit,
+                Element::Dah,
+                Element::CharGap
+            ]
+        );
+
+        assert!(!encoder.load_merged("S@"));
+        assert!(encoder.is_idle());
+    }
+
+    /// Run the keyer until idle, returns (key-down, total) samples
+    fn send_text(keyer: &mut Keyer, text: &str) -> (u32, u32) {
+        keyer.queue_text(text);
+        let (mut down, mut total) = (0, 0);
+        while !keyer.is_idle() && total < 10_000_000 {
+            keyer.process(PaddleState::default());
+            total += 1;
+            if keyer.is_key_down() {
+                down += 1;
+            }
+        }
+        (down, total)
+    }
+
+    #[test]
+    fn keyer_prosign_has_no_char_gap() {
+        let mut merged = Keyer::new(8000);
+        let mut separate = Keyer::new(8000);
+        let (down_merged, total_merged) = send_text(&mut merged, "<AR>");
+        let (down_separate, total_separate) = send_text(&mut separate, "AR");
+
+        // Same tones, one character space (2 extra units) shorter
+        assert_eq!(down_merged, down_separate);
+        let saved = total_separate - total_merged;
+        assert!(saved.abs_diff(2 * merged.samples_per_unit()) <= 2);
+    }
+
+    #[test]
+    fn keyer_farnsworth_stretches_spaces_only() {
+        let mut normal = Keyer::new(8000);
+        normal.set_wpm(18);
+        let mut farnsworth = Keyer::new(8000);
+        farnsworth.set_wpm(18);
+        farnsworth.set_farnsworth(5);
+        assert_eq!(farnsworth.effective_wpm(), 5);
+
+        let (down_normal, total_normal) = send_text(&mut normal, "PARIS ");
+        let (down_farns, total_farns) = send_text(&mut farnsworth, "PARIS ");
+        assert_eq!(down_normal, down_farns);
+
+        // PARIS is 50 units: 12 s at 5 WPM, 3.33 s at 18 WPM
+        assert!(total_farns.abs_diff(12 * 8000) < 8000 / 10);
+        assert!(total_normal.abs_diff(50 * normal.samples_per_unit()) < 100);
+
+        // Effective speed at or above character speed is plain timing
+        farnsworth.set_farnsworth(25);
+        assert_eq!(farnsworth.effective_wpm(), 18);
+        assert_eq!(farnsworth.samples_per_space_unit(), farnsworth.samples_per_unit());
+    }
+
+    #[test]
+    fn keyer_word_space_adjustable() {
+        let mut keyer = Keyer::new(8000);
+        assert_eq!(keyer.word_space(), Keyer::DEFAULT_WORD_SPACE);
+        let (_, standard) = send_text(&mut keyer, "E E");
+
+        keyer.set_word_space(10);
+        let (_, wide) = send_text(&mut keyer, "E E");
+        assert_eq!(wide - standard, 3 * keyer.samples_per_unit());
+
+        keyer.set_word_space(1);
+        assert_eq!(keyer.word_space(), Keyer::WORD_SPACE_RANGE.0);
+    }
+
+    #[test]
+    fn keyer_sends_queued_text() {
+        let mut keyer = Keyer::new(48000);
+        keyer.set_wpm(20);
+        assert_eq!(keyer.queue_text("EE"), 2);
+        assert!(!keyer.is_idle());
+
+        let idle = PaddleState::default();
+        let mut key_down_samples = 0;
+        for _ in 0..48000 {
+            keyer.process(idle);
+            if keyer.is_key_down() {
+                key_down_samples += 1;
+            }
+        }
+
+        // Two dits of one unit each
+        assert_eq!(key_down_samples, 2 * (keyer.samples_per_unit() + 1));
+        assert_eq!(keyer.take_sent(), Some('E'));
+        assert!(keyer.is_idle());
+    }
+
+    #[test]
+    fn keyer_paddle_breaks_in() {
+        let mut keyer = Keyer::new(48000);
+        keyer.queue_text("TEST");
+        keyer.process(PaddleState::default());
+        assert!(keyer.is_sending_text());
+
+        keyer.process(PaddleState::new(true, false));
+        assert!(!keyer.is_sending_text());
+        assert_eq!(keyer.text_pending(), 0);
+    }
+
+    #[test]
+    fn keyer_text_queue_full() {
+        let mut keyer = Keyer::new(48000);
+        for _ in 0..TEXT_QUEUE_LEN {
+            assert!(keyer.queue_char('E'));
+        }
+        assert!(!keyer.queue_char('E'));
+        assert_eq!(keyer.backspace(), Some('E'));
+        assert_eq!(keyer.text_pending(), TEXT_QUEUE_LEN - 1);
+    }
+
+    #[test]
+    fn memory_expands_macros() {
+        let mut memory = CwMemory::new();
+        memory.set_callsign("W1AW");
+        memory.set_message(0, "CQ TEST {CALL} {CALL}");
+        memory.set_message(1, "{RST} {NR}");
+        memory.set_serial(7);
+
+        assert_eq!(memory.expand(0).unwrap().as_str(), "CQ TEST W1AW W1AW");
+        assert_eq!(memory.expand(1).unwrap().as_str(), "599 007");
+        assert!(memory.uses_serial(1));
+        assert!(!memory.uses_serial(0));
+    }
+
+    #[test]
+    fn memory_cut_numbers() {
+        let mut memory = CwMemory::new();
+        memory.set_message(0, "{RST} {NR}");
+        memory.set_serial(109);
+        memory.set_cut_numbers(true);
+        assert_eq!(memory.expand(0).unwrap().as_str(), "5NN 1TN");
+    }
+
+    #[test]
+    fn memory_unknown_macro_is_literal() {
+        let mut memory = CwMemory::new();
+        memory.set_message(0, "{FOO} {CALL");
+        assert_eq!(memory.expand(0).unwrap().as_str(), "{FOO} {CALL");
+    }
+
+    #[test]
+    fn memory_empty_and_out_of_range() {
+        let mut memory = CwMemory::new();
+        assert!(memory.expand(0).is_none());
+        assert!(memory.expand(NUM_MESSAGES).is_none());
+        assert!(!memory.set_message(NUM_MESSAGES, "TEST"));
+        assert!(!memory.set_callsign("VERYLONGCALLSIGN"));
+    }
+
+    #[test]
+    fn keyer_play_message_increments_serial() {
+        let mut keyer = Keyer::new(48000);
+        keyer.memory_mut().set_message(2, "TU {NR}");
+        keyer.memory_mut().set_message(3, "TU");
+
+        assert!(keyer.play_message(2));
+        assert_eq!(keyer.text_pending(), 6);
+        assert_eq!(keyer.memory().serial(), 2);
+
+        // Replaces the queue, serial unchanged without {NR}
+        assert!(keyer.play_message(3));
+        assert_eq!(keyer.text_pending(), 2);
+        assert_eq!(keyer.memory().serial(), 2);
+
+        keyer.abort_message();
+        assert!(!keyer.is_sending_text());
+        assert!(!keyer.play_message(0));
+    }
+
     #[test]
     fn keyer_mode_default() {
         assert_eq!(KeyerMode::default(), KeyerMode::IambicA);
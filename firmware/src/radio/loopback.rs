@@ -0,0 +1,402 @@
+//! RX/TX Loopback Self Test
+//!
+//! Routes the transmit audio chain's modulated IQ straight into the
+//! receive demodulator and audio chain, numerically and with no hardware
+//! in the loop, and checks the whole SSB signal path end to end. One test
+//! tone at a time is sent through a fresh transmit chain (speech processor
+//! off, flat EQ), received on USB with the AGC bypassed, and correlated
+//! over a window of whole tenths of a second after a settling time.
+//!
+//! The tone steps give three checks:
+//!
+//! - gain: the 1 kHz loop gain against [`NOMINAL_GAIN_DB`]
+//! - sideband rejection: the 1 kHz tone received on the opposite sideband,
+//!   below the same tone on the right one
+//! - filter shape: the response from 100 Hz to 3.5 kHz relative to 1 kHz,
+//!   against the design response of the modulator, demodulator and SSB
+//!   filters in [`SHAPE_TONES`]; the value is the worst deviation
+//!
+//! Each check records its value and a pass/fail result in the same form
+//! as the [power-on self test](crate::selftest), and can be run at boot
+//! or on demand over CAT (`ZPL`).
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut loopback = LoopbackTest::new();
+//! // at boot
+//! let report = loopback.run_to_completion();
+//! // or on demand, a slice per main loop pass
+//! loopback.start();
+//! if let Some(report) = loopback.run(LOOPBACK_SLICE) {
+//!     cat.loopback(&report);
+//! }
+//! ```
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+use sdr_dsp_core::{IqSample as CoreIq, Nco, ToneGenerator};
+
+use crate::dsp::audio_chain::{AudioChain, TxAudioChain, AUDIO_SAMPLE_RATE};
+use crate::dsp::bypass::DspStage;
+use crate::dsp::filter_design::SsbBandwidth;
+use crate::dsp::modulation::Demodulator;
+use crate::selftest::PostResult;
+use crate::types::Mode;
+
+/// Test tone level (peak, full scale 1.0)
+pub const TEST_LEVEL: f32 = 0.25;
+
+/// Settling time before each measurement in milliseconds
+pub const SETTLE_MS: u16 = 50;
+
+/// Measurement window in milliseconds (whole periods of every tone)
+pub const WINDOW_MS: u16 = 100;
+
+/// Reference tone for the gain and sideband rejection checks in Hz
+pub const REFERENCE_HZ: f32 = 1000.0;
+
+/// Expected loop gain at the reference tone in dB (the modulator and
+/// demodulator bandpass filters peak near 1.5 kHz)
+pub const NOMINAL_GAIN_DB: f32 = 3.0;
+
+/// Allowed loop gain error in dB
+pub const GAIN_TOLERANCE_DB: f32 = 1.0;
+
+/// Minimum opposite sideband rejection in dB
+pub const MIN_SIDEBAND_REJECTION_DB: f32 = 40.0;
+
+/// Filter shape tones in Hz and their design response relative to the
+/// reference tone in dB
+pub const SHAPE_TONES: [(f32, f32); 5] = [
+    (100.0, -68.5),
+    (500.0, -12.4),
+    (1500.0, 3.7),
+    (2000.0, 2.8),
+    (3500.0, -8.8),
+];
+
+/// Allowed deviation from the design response in dB
+pub const SHAPE_TOLERANCE_DB: f32 = 2.0;
+
+/// Lowest level reported in dB
+pub const FLOOR_DB: f32 = -99.9;
+
+/// Tone steps: the reference tone on the transmitted sideband, then on
+/// the opposite one, then the shape tones
+const STEP_COUNT: usize = 2 + SHAPE_TONES.len();
+
+/// Test tone in Hz and whether the receiver is on the transmitted
+/// sideband, for a step
+const fn step_tone(step: usize) -> (f32, bool) {
+    match step {
+        0 => (REFERENCE_HZ, true),
+        1 => (REFERENCE_HZ, false),
+        n => (SHAPE_TONES[n - 2].0, true),
+    }
+}
+
+/// Loopback check
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopbackCheck {
+    /// End-to-end gain at 1 kHz
+    Gain,
+    /// Opposite sideband rejection
+    SidebandRejection,
+    /// Audio response against the design response
+    FilterShape,
+}
+
+impl LoopbackCheck {
+    /// Number of checks
+    pub const COUNT: usize = 3;
+
+    /// All checks in report order
+    pub const ALL: [Self; Self::COUNT] = [Self::Gain, Self::SidebandRejection, Self::FilterShape];
+
+    /// Position in the report
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Gain => 0,
+            Self::SidebandRejection => 1,
+            Self::FilterShape => 2,
+        }
+    }
+
+    /// Get a check by report position
+    #[must_use]
+    pub const fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self::Gain),
+            1 => Some(Self::SidebandRejection),
+            2 => Some(Self::FilterShape),
+            _ => None,
+        }
+    }
+
+    /// Short label for the display
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Gain => "GAIN",
+            Self::SidebandRejection => "OPP SB",
+            Self::FilterShape => "SHAPE",
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for LoopbackCheck {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.label());
+    }
+}
+
+/// Loopback test results
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct LoopbackReport {
+    /// Result of each check, in [`LoopbackCheck::ALL`] order
+    results: [PostResult; LoopbackCheck::COUNT],
+    /// Value each check was judged on in dB
+    values_db: [f32; LoopbackCheck::COUNT],
+}
+
+impl LoopbackReport {
+    /// Judge the tone levels measured for each step
+    fn from_levels(levels: &[f32; STEP_COUNT]) -> Self {
+        let db = |ratio: f32| (20.0 * ratio.max(1e-20).log10()).clamp(FLOOR_DB, -FLOOR_DB);
+        let reference = levels[0].max(1e-20);
+        let gain = db(reference / TEST_LEVEL);
+        let rejection = db(reference / levels[1].max(1e-20));
+        // Worst deviation from the design response, keeping its sign
+        let shape = levels[2..]
+            .iter()
+            .zip(SHAPE_TONES)
+            .map(|(&level, (_, expected_db))| db(level / reference) - expected_db)
+            .fold(
+                0.0_f32,
+                |worst, dev| if dev.abs() > worst.abs() { dev } else { worst },
+            );
+
+        let mut report = Self::default();
+        report.set(
+            LoopbackCheck::Gain,
+            gain,
+            (gain - NOMINAL_GAIN_DB).abs() <= GAIN_TOLERANCE_DB,
+        );
+        report.set(
+            LoopbackCheck::SidebandRejection,
+            rejection,
+            rejection >= MIN_SIDEBAND_REJECTION_DB,
+        );
+        report.set(
+            LoopbackCheck::FilterShape,
+            shape,
+            shape.abs() <= SHAPE_TOLERANCE_DB,
+        );
+        report
+    }
+
+    /// Record a check's value and outcome
+    fn set(&mut self, check: LoopbackCheck, value_db: f32, ok: bool) {
+        self.results[check.index()] = PostResult::from_bool(ok);
+        self.values_db[check.index()] = value_db;
+    }
+
+    /// Get the result of one check
+    #[must_use]
+    pub const fn result(&self, check: LoopbackCheck) -> PostResult {
+        self.results[check.index()]
+    }
+
+    /// Get the value one check was judged on in dB
+    #[must_use]
+    pub const fn value_db(&self, check: LoopbackCheck) -> f32 {
+        self.values_db[check.index()]
+    }
+
+    /// Check that every check passed
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| *result == PostResult::Pass)
+    }
+
+    /// Count failed checks
+    #[must_use]
+    pub fn failures(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| **result == PostResult::Fail)
+            .count()
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for LoopbackReport {
+    fn format(&self, f: defmt::Formatter) {
+        for check in LoopbackCheck::ALL {
+            defmt::write!(
+                f,
+                "{}={} ({} dB) ",
+                check,
+                self.result(check),
+                self.value_db(check)
+            );
+        }
+    }
+}
+
+/// Numerical RX/TX loopback test runner
+pub struct LoopbackTest {
+    /// Test running
+    active: bool,
+    /// Current step (see [`step_tone`])
+    step: usize,
+    /// Samples run in the current step
+    count: u32,
+    /// Test tone
+    tone: ToneGenerator<1>,
+    /// Transmit chain under test
+    tx: TxAudioChain,
+    /// Receive demodulator under test
+    demodulator: Demodulator,
+    /// Receive audio chain under test
+    rx: AudioChain,
+    /// Correlator at the test tone
+    reference: Nco,
+    sum: CoreIq,
+    /// Tone level measured at each step
+    levels: [f32; STEP_COUNT],
+    /// Samples settled before each window
+    settle: u32,
+    /// Samples per measurement window
+    window: u32,
+    /// Last completed report
+    report: Option<LoopbackReport>,
+}
+
+impl LoopbackTest {
+    /// Create an idle test of the SSB path at the audio sample rate
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new() -> Self {
+        let samples = |ms: u16| ((AUDIO_SAMPLE_RATE * f32::from(ms) / 1000.0) as u32).max(1);
+        let mut tx = TxAudioChain::new();
+        tx.set_processor_enabled(false);
+        let mut rx = AudioChain::new_ssb(SsbBandwidth::Standard);
+        rx.set_stage_bypass(DspStage::Agc, true);
+        rx.set_volume(1.0);
+        Self {
+            active: false,
+            step: 0,
+            count: 0,
+            tone: ToneGenerator::single(AUDIO_SAMPLE_RATE, REFERENCE_HZ, TEST_LEVEL),
+            tx,
+            demodulator: Demodulator::new(AUDIO_SAMPLE_RATE),
+            rx,
+            reference: Nco::new(AUDIO_SAMPLE_RATE, REFERENCE_HZ),
+            sum: CoreIq::ZERO,
+            levels: [0.0; STEP_COUNT],
+            settle: samples(SETTLE_MS),
+            window: samples(WINDOW_MS),
+            report: None,
+        }
+    }
+
+    /// Start the test from the first step, discarding any earlier report
+    pub fn start(&mut self) {
+        self.levels = [0.0; STEP_COUNT];
+        self.report = None;
+        self.active = true;
+        self.begin_step(0);
+    }
+
+    /// Stop the test, keeping the last report
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// Check if the test is running
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Get the step being run and the number of steps (for a progress bar)
+    #[must_use]
+    pub const fn progress(&self) -> (usize, usize) {
+        (self.step, STEP_COUNT)
+    }
+
+    /// Get the last completed report
+    #[must_use]
+    pub const fn report(&self) -> Option<LoopbackReport> {
+        self.report
+    }
+
+    /// Run up to `samples` loopback samples
+    ///
+    /// Returns the report when the last step completes.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn run(&mut self, samples: usize) -> Option<LoopbackReport> {
+        for _ in 0..samples {
+            if !self.active {
+                break;
+            }
+            let iq = self.tx.process(self.tone.next_sample());
+            let audio = self.rx.process(self.demodulator.process(iq));
+            self.count += 1;
+            if self.count <= self.settle {
+                continue;
+            }
+
+            self.sum = self.sum + self.reference.mix(CoreIq::new(audio, 0.0));
+            if self.count < self.settle + self.window {
+                continue;
+            }
+            // A real tone of amplitude A correlates to A / 2
+            self.levels[self.step] = 2.0 * self.sum.magnitude() / self.window as f32;
+            if self.step + 1 < STEP_COUNT {
+                self.begin_step(self.step + 1);
+            } else {
+                self.active = false;
+                let report = LoopbackReport::from_levels(&self.levels);
+                self.report = Some(report);
+                return Some(report);
+            }
+        }
+        None
+    }
+
+    /// Run the whole test at once (at boot, before the audio starts)
+    pub fn run_to_completion(&mut self) -> LoopbackReport {
+        self.start();
+        self.run(usize::MAX).unwrap_or_default()
+    }
+
+    /// Reset the chains and the correlator for a step
+    fn begin_step(&mut self, step: usize) {
+        let (tone_hz, same_sideband) = step_tone(step);
+        self.step = step;
+        self.count = 0;
+        self.tone.set_frequency(0, tone_hz);
+        self.tone.reset();
+        self.tx.reset();
+        self.demodulator.reset();
+        self.demodulator
+            .set_mode(if same_sideband { Mode::Usb } else { Mode::Lsb });
+        self.rx.reset();
+        self.reference.set_frequency(tone_hz);
+        self.reference.reset();
+        self.sum = CoreIq::ZERO;
+    }
+}
+
+impl Default for LoopbackTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
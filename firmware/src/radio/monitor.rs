@@ -0,0 +1,425 @@
+//! Band Monitor
+//!
+//! Round-robin propagation monitor for weak-signal modes. Retunes
+//! across a list of bands on a fixed slot schedule, hands each completed
+//! slot to the decoder and accumulates the resulting spots. Spot
+//! distances and per-band noise floor readings feed a condition score
+//! for each band.
+//!
+//! The radio control task owns the monitor: it ticks [`BandMonitor::update`],
+//! carries out each [`MonitorAction`] as radio events and applies the
+//! [`MonitorCommand`]s that CAT sends, including the spots the host's
+//! decoder reports.
+
+use heapless::{String, Vec};
+use sdr_dsp_core::conditions::{
+    locator_distance_km, locator_to_lat_lon, BandConditions, Condition,
+};
+
+use crate::clock::Clock;
+use crate::radio::state::RadioEvent;
+use crate::types::{Band, Frequency, Mode};
+
+/// Maximum number of bands in a monitor schedule
+pub const MAX_MONITOR_BANDS: usize = 6;
+
+/// Maximum number of spots retained (oldest dropped first)
+pub const MAX_SPOTS: usize = 64;
+
+/// Weak-signal mode being monitored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MonitorMode {
+    /// WSPR (2 minute slots)
+    #[default]
+    Wspr,
+    /// FT8 (15 second slots)
+    Ft8,
+}
+
+impl MonitorMode {
+    /// Get the slot duration in milliseconds
+    #[must_use]
+    pub const fn slot_ms(self) -> u32 {
+        match self {
+            Self::Wspr => 120_000,
+            Self::Ft8 => 15_000,
+        }
+    }
+
+    /// Get the standard USB dial frequency for a band
+    #[must_use]
+    pub const fn dial_frequency(self, band: Band) -> Frequency {
+        let hz = match (self, band) {
+            (Self::Wspr, Band::M80) => 3_568_600,
+            (Self::Wspr, Band::M40) => 7_038_600,
+            (Self::Wspr, Band::M30) => 10_138_700,
+            (Self::Wspr, Band::M20) => 14_095_600,
+            (Self::Wspr, Band::M17) => 18_104_600,
+            (Self::Wspr, Band::M15) => 21_094_600,
+            (Self::Ft8, Band::M80) => 3_573_000,
+            (Self::Ft8, Band::M40) => 7_074_000,
+            (Self::Ft8, Band::M30) => 10_136_000,
+            (Self::Ft8, Band::M20) => 14_074_000,
+            (Self::Ft8, Band::M17) => 18_100_000,
+            (Self::Ft8, Band::M15) => 21_074_000,
+        };
+        Frequency::from_hz_const(hz)
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for MonitorMode {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Wspr => defmt::write!(f, "WSPR"),
+            Self::Ft8 => defmt::write!(f, "FT8"),
+        }
+    }
+}
+
+/// A decoded station report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spot {
+    /// Band the spot was heard on
+    pub band: Band,
+    /// RF frequency of the signal in Hz
+    pub frequency_hz: u32,
+    /// Reported SNR in dB (2.5 kHz reference bandwidth)
+    pub snr_db: i8,
+    /// Callsign of the transmitting station
+    pub callsign: String<12>,
+    /// Maidenhead locator (may be empty)
+    pub locator: String<6>,
+    /// Monitor slot number the spot was decoded in
+    pub slot: u32,
+}
+
+/// Action requested by the monitor scheduler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonitorAction {
+    /// No action needed
+    None,
+    /// Retune to the given dial frequency (USB)
+    Retune(Frequency),
+    /// A slot finished: decode the captured audio, then retune
+    SlotComplete {
+        /// Band the finished slot was recorded on
+        band: Band,
+        /// Dial frequency for the next slot
+        next: Frequency,
+    },
+}
+
+impl MonitorAction {
+    /// Get the radio events that carry out the action
+    ///
+    /// Every retune is to a dial frequency in USB, as the decoders expect.
+    #[must_use]
+    pub fn radio_events(self) -> Vec<RadioEvent, 2> {
+        let mut events = Vec::new();
+        if let Self::Retune(dial) | Self::SlotComplete { next: dial, .. } = self {
+            let _ = events.push(RadioEvent::SetMode(Mode::Usb));
+            let _ = events.push(RadioEvent::SetFrequency(dial));
+        }
+        events
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for MonitorAction {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::None => defmt::write!(f, "None"),
+            Self::Retune(freq) => defmt::write!(f, "Retune({})", freq),
+            Self::SlotComplete { band, next } => {
+                defmt::write!(f, "SlotComplete({}, next={})", band, next);
+            }
+        }
+    }
+}
+
+/// Command for the band monitor from CAT
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MonitorCommand {
+    /// Start monitoring from the first band
+    Start,
+    /// Stop monitoring
+    Stop,
+    /// Record a spot from the host's decoder
+    Spot(Spot),
+}
+
+/// Round-robin band monitor
+#[derive(Clone, Debug)]
+pub struct BandMonitor {
+    /// Mode being monitored
+    mode: MonitorMode,
+    /// Bands in the schedule
+    bands: Vec<Band, MAX_MONITOR_BANDS>,
+    /// Index of the band currently being monitored
+    current: usize,
+    /// Time spent in the current slot (milliseconds)
+    slot_elapsed_ms: u32,
+    /// Number of completed slots
+    slot_count: u32,
+    /// Monitor running
+    running: bool,
+    /// Accumulated spots
+    spots: Vec<Spot, MAX_SPOTS>,
+    /// Own Maidenhead locator for spot distances (may be empty)
+    home_locator: String<6>,
+    /// Per-band condition scores, indexed by [`Band::index`]
+    conditions: BandConditions<{ Band::COUNT }>,
+}
+
+impl BandMonitor {
+    /// Create a new monitor with an empty band schedule
+    #[must_use]
+    pub const fn new(mode: MonitorMode) -> Self {
+        Self {
+            mode,
+            bands: Vec::new(),
+            current: 0,
+            slot_elapsed_ms: 0,
+            slot_count: 0,
+            running: false,
+            spots: Vec::new(),
+            home_locator: String::new(),
+            conditions: BandConditions::new(),
+        }
+    }
+
+    /// Create a monitor for the given bands (extra bands are ignored)
+    #[must_use]
+    pub fn with_bands(mode: MonitorMode, bands: &[Band]) -> Self {
+        let mut monitor = Self::new(mode);
+        for &band in bands {
+            monitor.add_band(band);
+        }
+        monitor
+    }
+
+    /// Get monitored mode
+    #[must_use]
+    pub const fn mode(&self) -> MonitorMode {
+        self.mode
+    }
+
+    /// Get scheduled bands
+    #[must_use]
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    /// Add a band to the schedule, returns false if full or duplicate
+    pub fn add_band(&mut self, band: Band) -> bool {
+        if self.bands.contains(&band) {
+            return false;
+        }
+        self.bands.push(band).is_ok()
+    }
+
+    /// Remove a band from the schedule
+    pub fn remove_band(&mut self, band: Band) {
+        if let Some(idx) = self.bands.iter().position(|&b| b == band) {
+            self.bands.remove(idx);
+            if self.current >= self.bands.len() {
+                self.current = 0;
+            }
+        }
+    }
+
+    /// Check if monitor is running
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Get band currently being monitored
+    #[must_use]
+    pub fn current_band(&self) -> Option<Band> {
+        self.bands.get(self.current).copied()
+    }
+
+    /// Get number of completed slots
+    #[must_use]
+    pub const fn slot_count(&self) -> u32 {
+        self.slot_count
+    }
+
+    /// Get time remaining in the current slot (milliseconds)
+    #[must_use]
+    pub const fn slot_remaining_ms(&self) -> u32 {
+        self.mode.slot_ms().saturating_sub(self.slot_elapsed_ms)
+    }
+
+    /// Start monitoring, returns the first frequency to tune
+    pub fn start(&mut self) -> MonitorAction {
+        let Some(band) = self.bands.first().copied() else {
+            return MonitorAction::None;
+        };
+        self.running = true;
+        self.current = 0;
+        self.slot_elapsed_ms = 0;
+        MonitorAction::Retune(self.mode.dial_frequency(band))
+    }
+
+    /// Stop monitoring (spots are kept)
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// Apply a command, returns the action to take
+    ///
+    /// Spots are stamped with the number of the last completed slot,
+    /// which is the one the host just decoded.
+    pub fn handle(&mut self, command: MonitorCommand) -> MonitorAction {
+        match command {
+            MonitorCommand::Start => self.start(),
+            MonitorCommand::Stop => {
+                self.stop();
+                MonitorAction::None
+            }
+            MonitorCommand::Spot(spot) => {
+                self.add_spot(Spot {
+                    slot: self.slot_count,
+                    ..spot
+                });
+                MonitorAction::None
+            }
+        }
+    }
+
+    /// Align to the slot clock given milliseconds since the last slot boundary
+    pub fn align(&mut self, ms_into_slot: u32) {
+        self.slot_elapsed_ms = ms_into_slot % self.mode.slot_ms();
+    }
+
+    /// Align to UTC slot boundaries, returns false if the clock is not set
+    pub fn align_to_clock(&mut self, clock: &Clock, now_ms: u32) -> bool {
+        match clock.ms_into_slot(now_ms, self.mode.slot_ms()) {
+            Some(ms) => {
+                self.align(ms);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Update scheduler (call periodically)
+    pub fn update(&mut self, elapsed_ms: u32) -> MonitorAction {
+        if !self.running || self.bands.is_empty() {
+            return MonitorAction::None;
+        }
+
+        self.slot_elapsed_ms = self.slot_elapsed_ms.saturating_add(elapsed_ms);
+        if self.slot_elapsed_ms < self.mode.slot_ms() {
+            return MonitorAction::None;
+        }
+
+        self.slot_elapsed_ms -= self.mode.slot_ms();
+        let band = self.bands[self.current];
+        self.slot_count = self.slot_count.wrapping_add(1);
+        self.current = (self.current + 1) % self.bands.len();
+        // Age spot activity once every band has had a slot
+        if self.current == 0 {
+            self.conditions.decay();
+        }
+
+        MonitorAction::SlotComplete {
+            band,
+            next: self.mode.dial_frequency(self.bands[self.current]),
+        }
+    }
+
+    /// Set own Maidenhead locator, returns false if it is not valid
+    pub fn set_home_locator(&mut self, locator: &str) -> bool {
+        if locator_to_lat_lon(locator).is_none() {
+            return false;
+        }
+        self.home_locator.clear();
+        self.home_locator.push_str(locator).is_ok()
+    }
+
+    /// Get own Maidenhead locator (empty if not set)
+    #[must_use]
+    pub fn home_locator(&self) -> &str {
+        &self.home_locator
+    }
+
+    /// Record a decoded spot, dropping the oldest if the table is full
+    pub fn add_spot(&mut self, spot: Spot) {
+        let distance_km = locator_distance_km(&self.home_locator, &spot.locator);
+        self.conditions
+            .add_spot(spot.band.index(), distance_km, spot.snr_db);
+        if self.spots.is_full() {
+            self.spots.remove(0);
+        }
+        let _ = self.spots.push(spot);
+    }
+
+    /// Get all recorded spots (oldest first)
+    #[must_use]
+    pub fn spots(&self) -> &[Spot] {
+        &self.spots
+    }
+
+    /// Count spots heard on a band
+    #[must_use]
+    pub fn spot_count(&self, band: Band) -> usize {
+        self.spots.iter().filter(|s| s.band == band).count()
+    }
+
+    /// Get the best SNR heard on a band
+    #[must_use]
+    pub fn best_snr(&self, band: Band) -> Option<i8> {
+        self.spots
+            .iter()
+            .filter(|s| s.band == band)
+            .map(|s| s.snr_db)
+            .max()
+    }
+
+    /// Record a noise floor reading for a band in dBm
+    pub fn add_noise_floor(&mut self, band: Band, dbm: f32) {
+        self.conditions.add_noise_floor(band.index(), dbm);
+    }
+
+    /// Get the condition score of a band (0-100, None if not observed)
+    #[must_use]
+    pub fn band_score(&self, band: Band) -> Option<u8> {
+        self.conditions.score(band.index())
+    }
+
+    /// Get the condition rating of a band
+    #[must_use]
+    pub fn band_condition(&self, band: Band) -> Option<Condition> {
+        self.conditions.condition(band.index())
+    }
+
+    /// Clear the spots table and condition scores
+    pub fn clear_spots(&mut self) {
+        self.spots.clear();
+        self.conditions.clear();
+    }
+}
+
+impl Default for BandMonitor {
+    fn default() -> Self {
+        Self::with_bands(MonitorMode::Wspr, &[Band::M40, Band::M30, Band::M20])
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for BandMonitor {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Monitor({}, running={}, slot={}, spots={})",
+            self.mode,
+            self.running,
+            self.slot_count,
+            self.spots.len()
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Class-E PA Drive
+//!
+//! Works out the HRTIM settings for the Class-E H-bridge and decides
+//! when the PA may be driven. The HRTIM driver only writes what
+//! [`PaController`] computes, so all of this runs on the host.
+//!
+//! # Drive
+//!
+//! Each bridge leg is a square wave at the carrier, the two legs in
+//! antiphase, with dead time between each leg's high and low switches.
+//! Output power is set by the PA supply, a PWM at [`SUPPLY_PWM_HZ`]
+//! whose duty comes from [`PowerLevel::as_pwm_duty`]. In
+//! [`EnvelopeMode::Polar`] (envelope elimination and restoration) the
+//! supply duty also follows the SSB envelope sample by sample, while
+//! the synthesizer carries the phase.
+//!
+//! # Interlocks
+//!
+//! The bridge is driven only between [`TxAction::EnablePa`] and
+//! [`TxAction::DisablePa`], and only with the T/R relay pulled in.
+//! Enabling the PA with the relay out is refused, and dropping the
+//! relay with the PA on stops the drive; both count as faults, since
+//! [`TxController`](crate::radio::transmit::TxController) never
+//! sequences them that way.
+
+use crate::radio::transmit::TxAction;
+use crate::types::{Frequency, PowerLevel};
+
+/// HRTIM input clock
+pub const HRTIM_CLOCK_HZ: u32 = 170_000_000;
+
+/// HRTIM high-resolution multiplier (DLL, 184 ps steps)
+pub const HRTIM_DLL_MULTIPLIER: u32 = 32;
+
+/// PA supply PWM frequency
+pub const SUPPLY_PWM_HZ: u32 = 500_000;
+
+/// Default dead time between a leg's high and low switches
+pub const DEFAULT_DEAD_TIME_NS: u32 = 5;
+
+/// Highest supply duty (as [`PowerLevel::as_pwm_duty`], 80%)
+pub const MAX_SUPPLY_DUTY: u16 = 52428;
+
+/// Shortest and longest HRTIM period in high-resolution ticks
+const PERIOD_TICKS: (u64, u64) = (0x60, 0xFFDF);
+
+/// Dead time generator clock as a multiple of the HRTIM clock
+const DEAD_TIME_MULTIPLIER: u64 = 8;
+
+/// Largest dead time register value
+const MAX_DEAD_TIME_TICKS: u64 = 511;
+
+/// Get the HRTIM period in high-resolution ticks for a frequency
+const fn period_ticks(hz: u32) -> Option<u64> {
+    if hz == 0 {
+        return None;
+    }
+    let ticks = HRTIM_CLOCK_HZ as u64 * HRTIM_DLL_MULTIPLIER as u64 / hz as u64;
+    if ticks < PERIOD_TICKS.0 || ticks > PERIOD_TICKS.1 {
+        None
+    } else {
+        Some(ticks)
+    }
+}
+
+/// HRTIM settings for the bridge at one carrier frequency
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BridgeTiming {
+    /// Carrier period in high-resolution ticks
+    period: u16,
+    /// Dead time in dead time generator ticks
+    dead_time: u16,
+}
+
+impl BridgeTiming {
+    /// Work out the timing for a carrier
+    ///
+    /// The dead time is limited to a quarter of the carrier period, so
+    /// each switch is on for at least a quarter cycle. `None` if the
+    /// carrier is outside the HRTIM's period range.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn for_carrier(carrier: Frequency, dead_time_ns: u32) -> Option<Self> {
+        let Some(period) = period_ticks(carrier.as_hz()) else {
+            return None;
+        };
+        let dtg_hz = HRTIM_CLOCK_HZ as u64 * DEAD_TIME_MULTIPLIER;
+        let mut dead_time = dead_time_ns as u64 * dtg_hz / 1_000_000_000;
+        // Period ticks are 4x finer than dead time ticks
+        let quarter = period / 4 / (HRTIM_DLL_MULTIPLIER as u64 / DEAD_TIME_MULTIPLIER);
+        if dead_time > quarter {
+            dead_time = quarter;
+        }
+        if dead_time > MAX_DEAD_TIME_TICKS {
+            dead_time = MAX_DEAD_TIME_TICKS;
+        }
+        Some(Self {
+            period: period as u16,
+            dead_time: dead_time as u16,
+        })
+    }
+
+    /// Get the carrier period in high-resolution ticks
+    #[must_use]
+    pub const fn period(&self) -> u16 {
+        self.period
+    }
+
+    /// Get the compare value for the middle of the period
+    ///
+    /// One leg switches high here and the other low, putting them in
+    /// antiphase.
+    #[must_use]
+    pub const fn half_period(&self) -> u16 {
+        self.period / 2
+    }
+
+    /// Get the dead time in dead time generator ticks
+    #[must_use]
+    pub const fn dead_time(&self) -> u16 {
+        self.dead_time
+    }
+}
+
+/// Supply PWM period in high-resolution ticks
+#[allow(clippy::cast_possible_truncation)]
+pub const SUPPLY_PERIOD: u16 = match period_ticks(SUPPLY_PWM_HZ) {
+    Some(ticks) => ticks as u16,
+    None => panic!("supply PWM outside HRTIM range"),
+};
+
+/// How the PA supply follows the transmit signal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnvelopeMode {
+    /// Supply set by the power level only (CW, FM, digital)
+    #[default]
+    Constant,
+    /// Supply follows the envelope (polar SSB)
+    Polar,
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for EnvelopeMode {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Constant => defmt::write!(f, "Constant"),
+            Self::Polar => defmt::write!(f, "Polar"),
+        }
+    }
+}
+
+/// What the HRTIM outputs should be
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaOutput {
+    /// Bridge timing, or `None` with the bridge outputs off
+    pub bridge: Option<BridgeTiming>,
+    /// Supply PWM compare value (0 to [`SUPPLY_PERIOD`])
+    pub supply: u16,
+}
+
+impl PaOutput {
+    /// All outputs off
+    pub const OFF: Self = Self {
+        bridge: None,
+        supply: 0,
+    };
+}
+
+/// PA drive planning and interlocks
+#[derive(Clone, Copy, Debug)]
+pub struct PaController {
+    /// Bridge timing for the carrier, if it is in range
+    bridge: Option<BridgeTiming>,
+    /// Dead time in nanoseconds
+    dead_time_ns: u32,
+    /// Envelope handling
+    mode: EnvelopeMode,
+    /// Power level from the transmit controller
+    power: PowerLevel,
+    /// T/R relay pulled in
+    relay_on: bool,
+    /// PA enabled by the transmit controller
+    enabled: bool,
+    /// Out-of-sequence actions seen
+    faults: u32,
+}
+
+impl PaController {
+    /// Create with the PA off and no carrier set
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bridge: None,
+            dead_time_ns: DEFAULT_DEAD_TIME_NS,
+            mode: EnvelopeMode::Constant,
+            power: PowerLevel::MIN,
+            relay_on: false,
+            enabled: false,
+            faults: 0,
+        }
+    }
+
+    /// Set the carrier frequency
+    ///
+    /// Returns `false` if it is out of range, which stops the drive.
+    pub fn set_carrier(&mut self, carrier: Frequency) -> bool {
+        self.bridge = BridgeTiming::for_carrier(carrier, self.dead_time_ns);
+        self.bridge.is_some()
+    }
+
+    /// Set the dead time, taking effect from the next carrier change
+    pub fn set_dead_time_ns(&mut self, ns: u32) {
+        self.dead_time_ns = ns;
+    }
+
+    /// Get the bridge timing for the carrier
+    #[must_use]
+    pub const fn bridge(&self) -> Option<BridgeTiming> {
+        self.bridge
+    }
+
+    /// Set how the supply follows the transmit signal
+    pub fn set_mode(&mut self, mode: EnvelopeMode) {
+        self.mode = mode;
+    }
+
+    /// Get how the supply follows the transmit signal
+    #[must_use]
+    pub const fn mode(&self) -> EnvelopeMode {
+        self.mode
+    }
+
+    /// Get the power level
+    #[must_use]
+    pub const fn power(&self) -> PowerLevel {
+        self.power
+    }
+
+    /// Check if the bridge is being driven
+    #[must_use]
+    pub const fn is_driving(&self) -> bool {
+        self.relay_on && self.enabled && self.bridge.is_some()
+    }
+
+    /// Get the number of out-of-sequence actions refused or forced off
+    #[must_use]
+    pub const fn faults(&self) -> u32 {
+        self.faults
+    }
+
+    /// Apply a transmit controller action and get the new outputs
+    pub fn apply(&mut self, action: TxAction) -> PaOutput {
+        match action {
+            TxAction::EnableTrRelay => self.relay_on = true,
+            TxAction::DisableTrRelay => {
+                if self.enabled {
+                    self.enabled = false;
+                    self.faults = self.faults.saturating_add(1);
+                }
+                self.relay_on = false;
+            }
+            TxAction::EnablePa if self.relay_on => self.enabled = true,
+            TxAction::EnablePa => self.faults = self.faults.saturating_add(1),
+            TxAction::DisablePa => self.enabled = false,
+            TxAction::SetPower(power) => self.power = power,
+            TxAction::None => {}
+        }
+        self.output(1.0)
+    }
+
+    /// Get the outputs for an envelope sample (0.0 to 1.0)
+    ///
+    /// The envelope only matters in [`EnvelopeMode::Polar`].
+    #[must_use]
+    pub fn output(&self, envelope: f32) -> PaOutput {
+        if !self.is_driving() {
+            return PaOutput::OFF;
+        }
+        PaOutput {
+            bridge: self.bridge,
+            supply: self.supply_compare(envelope),
+        }
+    }
+
+    /// Get the supply compare value for an envelope sample
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn supply_compare(&self, envelope: f32) -> u16 {
+        let duty = self.power.as_pwm_duty().min(MAX_SUPPLY_DUTY);
+        let duty = match self.mode {
+            EnvelopeMode::Constant => u32::from(duty),
+            EnvelopeMode::Polar => {
+                (f32::from(duty) * envelope.clamp(0.0, 1.0) + 0.5) as u32
+            }
+        };
+        (duty * u32::from(SUPPLY_PERIOD) / 65535) as u16
+    }
+}
+
+impl Default for PaController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,240 @@
+//! CW Practice Mode
+//!
+//! Lets a new operator key against the radio's own decoder without
+//! going on the air. The keyer still drives the sidetone, but the
+//! [`TxPolicy`](crate::radio::tx_policy::TxPolicy) denies transmit while
+//! practice is on, and every key transition is fed to a [`MorseDecoder`]
+//! so the sent text appears on the display and over CAT.
+//!
+//! Alongside the text, each mark and space is timed against the keyer's
+//! set speed: marks under two units are dits (ideal one unit), longer
+//! ones dahs (ideal three); spaces under two units are element spaces
+//! (ideal one), up to five are character spaces (ideal three). Word
+//! spaces and pauses are left out, as are glitches and tuning carriers.
+//! The [`PracticeReport`] gives the decoder's speed estimate against the
+//! set speed, the dah to dit ratio and an accuracy score, 100 less the
+//! mean timing error in percent.
+//!
+//! # Example
+//!
+//! ```ignore
+//! tx.policy_mut().set_practice(true);
+//! practice.reset();
+//! practice.set_target_wpm(keyer.wpm());
+//! // every sample
+//! keyer.process(paddle);
+//! if let Some(ch) = practice.update(keyer.is_key_down(), 1000.0 / SAMPLE_RATE as f32) {
+//!     cat.practice_char(ch);
+//! }
+//! ui.set_practice(&practice);
+//! ```
+
+use heapless::String;
+use sdr_mode_cw::{unit_ms, MorseDecoder};
+
+/// Decoded characters kept (oldest dropped first)
+pub const PRACTICE_TEXT_LEN: usize = 64;
+
+/// Marks shorter than this many units are ignored as contact bounce
+const GLITCH_UNITS: f32 = 0.3;
+
+/// Marks and spaces from this many units are ignored (dit/dah and
+/// element/character space boundary is two)
+const MARK_LIMIT_UNITS: f32 = 6.0;
+
+/// Spaces from this many units are word spaces and are not scored
+const WORD_SPACE_UNITS: f32 = 5.0;
+
+/// Timing summary of a practice session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PracticeReport {
+    /// Decoder's speed estimate in WPM (0 before the first element)
+    pub wpm: u8,
+    /// Keyer speed being practised in WPM
+    pub target_wpm: u8,
+    /// Dits and dahs timed
+    pub elements: u16,
+    /// Timing accuracy in percent (100 = perfect, 0 before the first element)
+    pub accuracy: u8,
+    /// Mean dah length in tenths of the mean dit (0 until both are sent)
+    pub dah_ratio: u8,
+}
+
+impl PracticeReport {
+    /// Get the speed estimate less the target in percent of the target
+    #[must_use]
+    pub fn speed_error_percent(&self) -> i16 {
+        if self.wpm == 0 || self.target_wpm == 0 {
+            return 0;
+        }
+        let diff = i32::from(self.wpm) - i32::from(self.target_wpm);
+        #[allow(clippy::cast_possible_truncation)]
+        let percent = (diff * 100 / i32::from(self.target_wpm)) as i16;
+        percent
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for PracticeReport {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{}/{} WPM {}% ({} el, 1:{}.{})",
+            self.wpm,
+            self.target_wpm,
+            self.accuracy,
+            self.elements,
+            self.dah_ratio / 10,
+            self.dah_ratio % 10
+        );
+    }
+}
+
+/// Practice decode and timing statistics
+#[derive(Clone, Debug)]
+pub struct PracticeSession {
+    /// Decoder for the practice text
+    decoder: MorseDecoder,
+    /// Keyer speed being practised in WPM
+    target_wpm: u8,
+    /// Decoded text, most recent last
+    text: String<PRACTICE_TEXT_LEN>,
+    /// Key state of the current run
+    key: bool,
+    /// Length of the current run in ms
+    run_ms: f32,
+    /// A mark has been sent since the last reset (spaces before it are idle)
+    started: bool,
+    /// Dits timed and their total length in ms
+    dits: u16,
+    dit_total_ms: f32,
+    /// Dahs timed and their total length in ms
+    dahs: u16,
+    dah_total_ms: f32,
+    /// Marks and spaces scored and their summed relative error
+    scored: u16,
+    error_total: f32,
+}
+
+impl PracticeSession {
+    /// Create a session practising at `target_wpm`
+    #[must_use]
+    pub fn new(target_wpm: u8) -> Self {
+        Self {
+            decoder: MorseDecoder::new(),
+            target_wpm: target_wpm.max(1),
+            text: String::new(),
+            key: false,
+            run_ms: 0.0,
+            started: false,
+            dits: 0,
+            dit_total_ms: 0.0,
+            dahs: 0,
+            dah_total_ms: 0.0,
+            scored: 0,
+            error_total: 0.0,
+        }
+    }
+
+    /// Set the keyer speed being practised (follow the keyer setting)
+    pub fn set_target_wpm(&mut self, wpm: u8) {
+        self.target_wpm = wpm.max(1);
+    }
+
+    /// Get the keyer speed being practised in WPM
+    #[must_use]
+    pub const fn target_wpm(&self) -> u8 {
+        self.target_wpm
+    }
+
+    /// Update with the key state over the last `elapsed_ms`
+    ///
+    /// Returns each character (or word space) as it is decoded; it is
+    /// also added to [`PracticeSession::text`].
+    pub fn update(&mut self, key_down: bool, elapsed_ms: f32) -> Option<char> {
+        if key_down != self.key {
+            self.score_run();
+            self.key = key_down;
+            self.run_ms = 0.0;
+        }
+        self.run_ms += elapsed_ms;
+
+        let ch = self.decoder.update(key_down, elapsed_ms)?;
+        if self.text.push(ch).is_err() {
+            // Drop the oldest character to make room
+            let mut kept: String<PRACTICE_TEXT_LEN> = self.text.chars().skip(1).collect();
+            let _ = kept.push(ch);
+            self.text = kept;
+        }
+        Some(ch)
+    }
+
+    /// Get the decoded text, most recent last
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the timing summary so far
+    #[must_use]
+    pub fn report(&self) -> PracticeReport {
+        let elements = self.dits.saturating_add(self.dahs);
+        if elements == 0 {
+            return PracticeReport {
+                target_wpm: self.target_wpm,
+                ..PracticeReport::default()
+            };
+        }
+
+        let mean_error = self.error_total / f32::from(self.scored.max(1));
+        let dah_ratio = if self.dits > 0 && self.dahs > 0 {
+            let dit = self.dit_total_ms / f32::from(self.dits);
+            let dah = self.dah_total_ms / f32::from(self.dahs);
+            dah / dit * 10.0
+        } else {
+            0.0
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        PracticeReport {
+            wpm: (self.decoder.wpm() + 0.5) as u8,
+            target_wpm: self.target_wpm,
+            elements,
+            accuracy: ((1.0 - mean_error) * 100.0 + 0.5).clamp(0.0, 100.0) as u8,
+            dah_ratio: (dah_ratio + 0.5).min(99.0) as u8,
+        }
+    }
+
+    /// Clear the text, the statistics and the decoder's speed estimate
+    pub fn reset(&mut self) {
+        *self = Self::new(self.target_wpm);
+    }
+
+    /// Time the run that just ended against the target speed
+    fn score_run(&mut self) {
+        let units = self.run_ms / unit_ms(f32::from(self.target_wpm));
+        if self.key {
+            if !(GLITCH_UNITS..MARK_LIMIT_UNITS).contains(&units) {
+                return;
+            }
+            self.started = true;
+            let ideal = if units < 2.0 {
+                self.dits = self.dits.saturating_add(1);
+                self.dit_total_ms += self.run_ms;
+                1.0
+            } else {
+                self.dahs = self.dahs.saturating_add(1);
+                self.dah_total_ms += self.run_ms;
+                3.0
+            };
+            self.score(units, ideal);
+        } else if self.started && units < WORD_SPACE_UNITS {
+            self.score(units, if units < 2.0 { 1.0 } else { 3.0 });
+        }
+    }
+
+    /// Add the relative error of a run of `units` against `ideal`
+    fn score(&mut self, units: f32, ideal: f32) {
+        self.error_total += (units - ideal).abs() / ideal;
+        self.scored = self.scored.saturating_add(1);
+    }
+}
@@ -0,0 +1,432 @@
+//! Activity Recorder
+//!
+//! Decides when to capture receive audio for unattended monitoring.
+//! Recording starts when the squelch opens or a decoder produces
+//! output, and stops once the channel has been quiet for the tail time.
+//!
+//! Each clip is written to the file store (see [`crate::storage`]) as
+//! `CLIPnnnnn.PCM`, 16-bit little-endian at the audio rate, the same
+//! format the voice keyer uses. The clip index with trigger, time and
+//! frequency is kept in RAM only; after a restart [`ActivityRecorder::load`]
+//! continues the numbering past the clips already in the store.
+//!
+//! # Example
+//!
+//! ```ignore
+//! recorder.load(&store);
+//! // every RX audio block
+//! let action = recorder.update(squelch_open, decoder_active, now_ms);
+//! recorder.apply(action, &mut store)?;
+//! recorder.record(&mut store, &audio)?;
+//! ```
+
+use heapless::{String, Vec};
+
+use crate::storage::{BlockDevice, FileKind, FileStore, StorageResult, MAX_NAME_LEN};
+
+/// Maximum number of clips kept in the index
+pub const MAX_CLIPS: usize = 32;
+
+/// Samples moved to storage at a time
+const CHUNK_SAMPLES: usize = 64;
+
+/// File name of a clip (`CLIP00042.PCM` for clip 42)
+#[must_use]
+pub fn clip_file_name(id: u32) -> String<MAX_NAME_LEN> {
+    let mut name = String::new();
+    let _ = core::fmt::write(&mut name, format_args!("CLIP{:05}.PCM", id % 100_000));
+    name
+}
+
+/// Clip identifier from a file name, if it is a clip
+fn clip_id(name: &str) -> Option<u32> {
+    name.strip_prefix("CLIP")?.strip_suffix(".PCM")?.parse().ok()
+}
+
+/// What started a recording
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordTrigger {
+    /// Squelch opened
+    Squelch,
+    /// Decoder produced output
+    Decoder,
+    /// Started by the operator
+    Manual,
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for RecordTrigger {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Squelch => defmt::write!(f, "SQL"),
+            Self::Decoder => defmt::write!(f, "DEC"),
+            Self::Manual => defmt::write!(f, "MAN"),
+        }
+    }
+}
+
+/// Metadata for a recorded clip
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClipInfo {
+    /// Clip identifier (monotonic)
+    pub id: u32,
+    /// Trigger that started the clip
+    pub trigger: RecordTrigger,
+    /// Start time (milliseconds since boot)
+    pub start_ms: u32,
+    /// Clip length in milliseconds (including tail)
+    pub duration_ms: u32,
+    /// Receive frequency in Hz
+    pub frequency_hz: u32,
+}
+
+impl ClipInfo {
+    /// File name used when the clip is written to storage
+    #[must_use]
+    pub fn file_name(&self) -> String<MAX_NAME_LEN> {
+        clip_file_name(self.id)
+    }
+}
+
+/// Action requested by the recorder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecorderAction {
+    /// No action needed
+    None,
+    /// Open a new clip and start writing audio
+    Start {
+        /// Identifier for the new clip
+        id: u32,
+        /// Trigger that started the clip
+        trigger: RecordTrigger,
+    },
+    /// Close the current clip
+    Stop(ClipInfo),
+}
+
+/// Recorder state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum RecorderState {
+    /// Waiting for activity
+    #[default]
+    Idle,
+    /// Capturing audio
+    Recording {
+        /// Clip identifier
+        id: u32,
+        /// Trigger that started the clip
+        trigger: RecordTrigger,
+        /// Clip start time
+        start_ms: u32,
+        /// Time of the last activity
+        last_activity_ms: u32,
+    },
+}
+
+/// Activity-triggered recorder
+#[derive(Clone, Debug)]
+pub struct ActivityRecorder {
+    /// Recorder armed
+    enabled: bool,
+    /// Quiet time before a clip is closed (milliseconds)
+    tail_ms: u32,
+    /// Maximum clip length (milliseconds, 0 = unlimited)
+    max_clip_ms: u32,
+    /// Current state
+    state: RecorderState,
+    /// Frequency recorded with the clip
+    frequency_hz: u32,
+    /// Next clip identifier
+    next_id: u32,
+    /// Current clip's file is open in the store
+    writing: bool,
+    /// Clip index (oldest first)
+    clips: Vec<ClipInfo, MAX_CLIPS>,
+}
+
+impl ActivityRecorder {
+    /// Default tail time (3 seconds)
+    pub const DEFAULT_TAIL_MS: u32 = 3_000;
+
+    /// Default maximum clip length (5 minutes)
+    pub const DEFAULT_MAX_CLIP_MS: u32 = 300_000;
+
+    /// Create a new (disarmed) recorder
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            tail_ms: Self::DEFAULT_TAIL_MS,
+            max_clip_ms: Self::DEFAULT_MAX_CLIP_MS,
+            state: RecorderState::Idle,
+            frequency_hz: 0,
+            next_id: 0,
+            writing: false,
+            clips: Vec::new(),
+        }
+    }
+
+    /// Continue clip numbering past the clips in a mounted file store
+    pub fn load<D: BlockDevice>(&mut self, store: &FileStore<D>) {
+        let last = store
+            .files()
+            .iter()
+            .filter(|entry| entry.kind == FileKind::Capture)
+            .filter_map(|entry| clip_id(&entry.name))
+            .max();
+        if let Some(id) = last {
+            self.next_id = self.next_id.max(id + 1);
+        }
+    }
+
+    /// Arm or disarm the recorder
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check if armed
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set tail time in milliseconds
+    pub fn set_tail_ms(&mut self, ms: u32) {
+        self.tail_ms = ms;
+    }
+
+    /// Set maximum clip length in milliseconds (0 = unlimited)
+    pub fn set_max_clip_ms(&mut self, ms: u32) {
+        self.max_clip_ms = ms;
+    }
+
+    /// Set the frequency stored with new clips
+    pub fn set_frequency(&mut self, hz: u32) {
+        self.frequency_hz = hz;
+    }
+
+    /// Check if a clip is being recorded
+    #[must_use]
+    pub const fn is_recording(&self) -> bool {
+        matches!(self.state, RecorderState::Recording { .. })
+    }
+
+    /// Start a clip manually (ignored if already recording)
+    pub fn start_manual(&mut self, now_ms: u32) -> RecorderAction {
+        self.trigger(RecordTrigger::Manual, now_ms)
+    }
+
+    /// Stop the current clip immediately
+    pub fn stop(&mut self, now_ms: u32) -> RecorderAction {
+        match self.state {
+            RecorderState::Recording { id, trigger, start_ms, .. } => {
+                self.finish(id, trigger, start_ms, now_ms)
+            }
+            RecorderState::Idle => RecorderAction::None,
+        }
+    }
+
+    /// Update with current activity (call periodically)
+    pub fn update(&mut self, squelch_open: bool, decoder_active: bool, now_ms: u32) -> RecorderAction {
+        if !self.enabled {
+            return self.stop(now_ms);
+        }
+
+        if squelch_open || decoder_active {
+            let trigger = if squelch_open {
+                RecordTrigger::Squelch
+            } else {
+                RecordTrigger::Decoder
+            };
+            return self.trigger(trigger, now_ms);
+        }
+
+        match self.state {
+            RecorderState::Recording { id, trigger, start_ms, last_activity_ms } => {
+                let quiet = now_ms.wrapping_sub(last_activity_ms);
+                if quiet >= self.tail_ms || self.clip_too_long(start_ms, now_ms) {
+                    self.finish(id, trigger, start_ms, now_ms)
+                } else {
+                    RecorderAction::None
+                }
+            }
+            RecorderState::Idle => RecorderAction::None,
+        }
+    }
+
+    /// Register activity, starting a clip if idle
+    fn trigger(&mut self, trigger: RecordTrigger, now_ms: u32) -> RecorderAction {
+        match &mut self.state {
+            RecorderState::Recording { id, trigger: t, start_ms, last_activity_ms } => {
+                *last_activity_ms = now_ms;
+                let (id, t, start_ms) = (*id, *t, *start_ms);
+                if self.clip_too_long(start_ms, now_ms) {
+                    self.finish(id, t, start_ms, now_ms)
+                } else {
+                    RecorderAction::None
+                }
+            }
+            RecorderState::Idle => {
+                let id = self.next_id;
+                self.next_id = self.next_id.wrapping_add(1);
+                self.state = RecorderState::Recording {
+                    id,
+                    trigger,
+                    start_ms: now_ms,
+                    last_activity_ms: now_ms,
+                };
+                RecorderAction::Start { id, trigger }
+            }
+        }
+    }
+
+    /// Check if the clip has hit the length limit
+    const fn clip_too_long(&self, start_ms: u32, now_ms: u32) -> bool {
+        self.max_clip_ms > 0 && now_ms.wrapping_sub(start_ms) >= self.max_clip_ms
+    }
+
+    /// Close the current clip and add it to the index
+    fn finish(&mut self, id: u32, trigger: RecordTrigger, start_ms: u32, now_ms: u32) -> RecorderAction {
+        self.state = RecorderState::Idle;
+        let clip = ClipInfo {
+            id,
+            trigger,
+            start_ms,
+            duration_ms: now_ms.wrapping_sub(start_ms),
+            frequency_hz: self.frequency_hz,
+        };
+        if self.clips.is_full() {
+            self.clips.remove(0);
+        }
+        let _ = self.clips.push(clip);
+        RecorderAction::Stop(clip)
+    }
+
+    /// Carry out an action from [`update`](Self::update), [`stop`](Self::stop)
+    /// or [`start_manual`](Self::start_manual) on the file store
+    ///
+    /// `Start` creates the clip's file and `Stop` closes it. If the file
+    /// cannot be created (the store is full or busy with another file)
+    /// the clip is still tracked but no audio is written.
+    ///
+    /// # Errors
+    /// Returns the file store's error
+    pub fn apply<D: BlockDevice>(
+        &mut self,
+        action: RecorderAction,
+        store: &mut FileStore<D>,
+    ) -> StorageResult<(), D::Error> {
+        match action {
+            RecorderAction::None => Ok(()),
+            RecorderAction::Start { id, .. } => {
+                self.close_file(store)?;
+                store.create(&clip_file_name(id), FileKind::Capture)?;
+                self.writing = true;
+                Ok(())
+            }
+            RecorderAction::Stop(_) => self.close_file(store),
+        }
+    }
+
+    /// Write a block of receive audio to the current clip
+    ///
+    /// Ignored unless a clip file is open. If the store fills up, what
+    /// was written is kept, the file is closed and the store's `Full`
+    /// error returned.
+    ///
+    /// # Errors
+    /// Returns the file store's error
+    pub fn record<D: BlockDevice>(
+        &mut self,
+        store: &mut FileStore<D>,
+        samples: &[f32],
+    ) -> StorageResult<(), D::Error> {
+        if !self.writing {
+            return Ok(());
+        }
+        let mut bytes = [0u8; CHUNK_SAMPLES * 2];
+        for chunk in samples.chunks(CHUNK_SAMPLES) {
+            for (pair, &sample) in bytes.chunks_exact_mut(2).zip(chunk) {
+                #[allow(clippy::cast_possible_truncation)]
+                let pcm = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+                pair.copy_from_slice(&pcm.to_le_bytes());
+            }
+            if let Err(err) = store.append(&bytes[..chunk.len() * 2]) {
+                self.close_file(store)?;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if audio is being written to the store
+    #[must_use]
+    pub const fn is_writing(&self) -> bool {
+        self.writing
+    }
+
+    /// Close the current clip's file, if open
+    fn close_file<D: BlockDevice>(&mut self, store: &mut FileStore<D>) -> StorageResult<(), D::Error> {
+        if self.writing {
+            self.writing = false;
+            store.close()?;
+        }
+        Ok(())
+    }
+
+    /// Get recorded clips (oldest first)
+    #[must_use]
+    pub fn clips(&self) -> &[ClipInfo] {
+        &self.clips
+    }
+
+    /// Remove a clip from the index, returns true if found
+    pub fn remove_clip(&mut self, id: u32) -> bool {
+        if let Some(idx) = self.clips.iter().position(|c| c.id == id) {
+            self.clips.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Clear the clip index
+    pub fn clear_clips(&mut self) {
+        self.clips.clear();
+    }
+
+    /// Remove a clip from the index and delete its file, returns true if found
+    ///
+    /// # Errors
+    /// Returns the file store's error
+    pub fn delete_clip<D: BlockDevice>(
+        &mut self,
+        id: u32,
+        store: &mut FileStore<D>,
+    ) -> StorageResult<bool, D::Error> {
+        let found = self.remove_clip(id);
+        if let Some(file) = store.find(&clip_file_name(id)).map(|entry| entry.id) {
+            store.delete(file)?;
+        }
+        Ok(found)
+    }
+}
+
+impl Default for ActivityRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for ActivityRecorder {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Recorder(en={}, rec={}, clips={})",
+            self.enabled,
+            self.is_recording(),
+            self.clips.len()
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! T/R Relay and Band Filter Sequencing
+//!
+//! Decides what the T/R relay and the low-pass filter relays should be
+//! doing and when the PA may be driven through them. The relay driver
+//! only writes what [`RelaySequencer`] asks for, so all of this runs on
+//! the host.
+//!
+//! # Sequencing
+//!
+//! Relays take a few milliseconds to close and stop bouncing, and
+//! switching one with RF on it burns the contacts. The sequencer sits
+//! between [`TxController`](crate::radio::transmit::TxController) and
+//! the PA:
+//!
+//! - [`TxAction::EnablePa`] is held back until the T/R relay and the
+//!   selected filter have been stable for their settle times, then
+//!   released from [`RelaySequencer::update`].
+//! - A band change while the T/R relay is pulled in is deferred until
+//!   it drops out, so the filter is never switched hot.
+//!
+//! # Faults
+//!
+//! Where the hardware reports relay positions (an auxiliary contact on
+//! the T/R relay, a readback of the filter board), each settled relay
+//! is checked against what it was told to do. A mismatch latches a
+//! [`RelayFault`] and stops the PA until [`RelaySequencer::clear_fault`].
+
+use crate::config::{LPF_RELAY_SETTLE_US, NUM_LPF_BANKS, TR_RELAY_SETTLE_US};
+use crate::radio::transmit::TxAction;
+use crate::types::Band;
+
+/// Shift register bit for the T/R relay (the LPF relays are bits 0-4)
+pub const TR_RELAY_BIT: u8 = 7;
+
+/// Relay settle times
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayTiming {
+    /// T/R relay operate and bounce time in microseconds
+    pub tr_settle_us: u32,
+    /// LPF relay operate and bounce time in microseconds
+    pub lpf_settle_us: u32,
+}
+
+impl RelayTiming {
+    /// Defaults from the relay datasheets
+    pub const DEFAULT: Self = Self {
+        tr_settle_us: TR_RELAY_SETTLE_US,
+        lpf_settle_us: LPF_RELAY_SETTLE_US,
+    };
+}
+
+impl Default for RelayTiming {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What the relay outputs should be
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayOutputs {
+    /// T/R relay pulled in (transmit)
+    pub tr: bool,
+    /// Selected LPF bank (0 to [`NUM_LPF_BANKS`] - 1)
+    pub lpf: u8,
+}
+
+impl RelayOutputs {
+    /// Get the outputs as a shift register byte
+    ///
+    /// One bit per LPF relay (only the selected one set) and
+    /// [`TR_RELAY_BIT`] for the T/R relay.
+    #[must_use]
+    pub const fn as_shift_word(self) -> u8 {
+        let lpf = 1 << self.lpf;
+        if self.tr {
+            lpf | 1 << TR_RELAY_BIT
+        } else {
+            lpf
+        }
+    }
+}
+
+/// Relay positions read back from the hardware
+///
+/// `None` where there is no feedback, which skips that check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RelaySense {
+    /// T/R relay auxiliary contact closed
+    pub tr: Option<bool>,
+    /// LPF bank reported by the filter board
+    pub lpf: Option<u8>,
+}
+
+/// Relay that did not follow its drive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelayFault {
+    /// T/R relay stayed out when pulled in
+    TrStuckOpen,
+    /// T/R relay stayed in when released
+    TrStuckClosed,
+    /// Filter board reports a different bank
+    LpfMismatch {
+        /// Bank selected
+        wanted: u8,
+        /// Bank reported
+        sensed: u8,
+    },
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for RelayFault {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::TrStuckOpen => defmt::write!(f, "T/R stuck open"),
+            Self::TrStuckClosed => defmt::write!(f, "T/R stuck closed"),
+            Self::LpfMismatch { wanted, sensed } => {
+                defmt::write!(f, "LPF {} reads {}", wanted, sensed);
+            }
+        }
+    }
+}
+
+/// T/R relay and band filter sequencer
+#[derive(Clone, Copy, Debug)]
+pub struct RelaySequencer {
+    /// Settle times
+    timing: RelayTiming,
+    /// Outputs as commanded
+    outputs: RelayOutputs,
+    /// Band change waiting for the T/R relay to drop out
+    pending_lpf: Option<u8>,
+    /// T/R relay settle time left in microseconds
+    tr_settle_us: u32,
+    /// LPF settle time left in microseconds
+    lpf_settle_us: u32,
+    /// PA enable held until the relays settle
+    pa_requested: bool,
+    /// PA enable passed on
+    pa_enabled: bool,
+    /// Latched fault
+    fault: Option<RelayFault>,
+    /// Faults seen since power-up
+    fault_count: u32,
+}
+
+impl RelaySequencer {
+    /// Create in receive with bank 0 selected and settled
+    #[must_use]
+    pub const fn new(timing: RelayTiming) -> Self {
+        Self {
+            timing,
+            outputs: RelayOutputs { tr: false, lpf: 0 },
+            pending_lpf: None,
+            tr_settle_us: 0,
+            lpf_settle_us: 0,
+            pa_requested: false,
+            pa_enabled: false,
+            fault: None,
+            fault_count: 0,
+        }
+    }
+
+    /// Get the settle times
+    #[must_use]
+    pub const fn timing(&self) -> RelayTiming {
+        self.timing
+    }
+
+    /// Set the settle times, taking effect from the next switch
+    pub fn set_timing(&mut self, timing: RelayTiming) {
+        self.timing = timing;
+    }
+
+    /// Get the outputs to drive
+    #[must_use]
+    pub const fn outputs(&self) -> RelayOutputs {
+        self.outputs
+    }
+
+    /// Select the filter for a band
+    ///
+    /// Returns `false` if the T/R relay is pulled in, in which case the
+    /// filter changes once it drops out.
+    pub fn select_band(&mut self, band: Band) -> bool {
+        self.select_lpf(band.lpf_index())
+    }
+
+    /// Select a filter bank directly (clamped to the banks fitted)
+    ///
+    /// Returns `false` if the change is deferred until receive.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn select_lpf(&mut self, bank: u8) -> bool {
+        let bank = bank.min(NUM_LPF_BANKS as u8 - 1);
+        if self.outputs.tr {
+            self.pending_lpf = (bank != self.outputs.lpf).then_some(bank);
+            return self.pending_lpf.is_none();
+        }
+        self.switch_lpf(bank);
+        true
+    }
+
+    /// Get the bank waiting to be selected at the end of the over
+    #[must_use]
+    pub const fn pending_lpf(&self) -> Option<u8> {
+        self.pending_lpf
+    }
+
+    /// Check if every relay has settled
+    #[must_use]
+    pub const fn is_settled(&self) -> bool {
+        self.tr_settle_us == 0 && self.lpf_settle_us == 0
+    }
+
+    /// Check if the PA is being allowed to drive
+    #[must_use]
+    pub const fn is_pa_enabled(&self) -> bool {
+        self.pa_enabled
+    }
+
+    /// Get the latched fault
+    #[must_use]
+    pub const fn fault(&self) -> Option<RelayFault> {
+        self.fault
+    }
+
+    /// Get the number of faults seen
+    #[must_use]
+    pub const fn fault_count(&self) -> u32 {
+        self.fault_count
+    }
+
+    /// Clear a latched fault once the cause is fixed
+    pub fn clear_fault(&mut self) {
+        self.fault = None;
+    }
+
+    /// Apply a transmit controller action
+    ///
+    /// Returns the action to pass on to the PA: [`TxAction::EnablePa`]
+    /// becomes [`TxAction::None`] until the relays have settled, when
+    /// [`update`](Self::update) releases it.
+    pub fn apply(&mut self, action: TxAction) -> TxAction {
+        match action {
+            TxAction::EnableTrRelay => {
+                if !self.outputs.tr {
+                    self.outputs.tr = true;
+                    self.tr_settle_us = self.timing.tr_settle_us;
+                }
+                TxAction::EnableTrRelay
+            }
+            TxAction::DisableTrRelay => {
+                self.pa_requested = false;
+                self.pa_enabled = false;
+                if self.outputs.tr {
+                    self.outputs.tr = false;
+                    self.tr_settle_us = self.timing.tr_settle_us;
+                }
+                if let Some(bank) = self.pending_lpf.take() {
+                    self.switch_lpf(bank);
+                }
+                TxAction::DisableTrRelay
+            }
+            TxAction::EnablePa => {
+                self.pa_requested = true;
+                self.release_pa()
+            }
+            TxAction::DisablePa => {
+                self.pa_requested = false;
+                self.pa_enabled = false;
+                TxAction::DisablePa
+            }
+            other => other,
+        }
+    }
+
+    /// Advance the settle timers and check the relays
+    ///
+    /// Returns [`TxAction::EnablePa`] when a held enable is released,
+    /// or [`TxAction::DisablePa`] when a fault stops the PA.
+    pub fn update(&mut self, elapsed_us: u32, sense: RelaySense) -> TxAction {
+        self.tr_settle_us = self.tr_settle_us.saturating_sub(elapsed_us);
+        self.lpf_settle_us = self.lpf_settle_us.saturating_sub(elapsed_us);
+
+        if let Some(fault) = self.check(sense) {
+            if self.fault.is_none() {
+                self.fault_count = self.fault_count.saturating_add(1);
+            }
+            self.fault = Some(fault);
+            if self.pa_enabled {
+                self.pa_enabled = false;
+                return TxAction::DisablePa;
+            }
+            return TxAction::None;
+        }
+
+        if self.pa_requested && !self.pa_enabled {
+            return self.release_pa();
+        }
+        TxAction::None
+    }
+
+    /// Change the filter and restart its settle time
+    fn switch_lpf(&mut self, bank: u8) {
+        self.pending_lpf = None;
+        if bank != self.outputs.lpf {
+            self.outputs.lpf = bank;
+            self.lpf_settle_us = self.timing.lpf_settle_us;
+        }
+    }
+
+    /// Pass on a requested PA enable if it is safe
+    fn release_pa(&mut self) -> TxAction {
+        if self.outputs.tr && self.is_settled() && self.fault.is_none() {
+            self.pa_enabled = true;
+            TxAction::EnablePa
+        } else {
+            TxAction::None
+        }
+    }
+
+    /// Compare settled relays with their readback
+    fn check(&self, sense: RelaySense) -> Option<RelayFault> {
+        if self.tr_settle_us == 0 {
+            match (self.outputs.tr, sense.tr) {
+                (true, Some(false)) => return Some(RelayFault::TrStuckOpen),
+                (false, Some(true)) => return Some(RelayFault::TrStuckClosed),
+                _ => {}
+            }
+        }
+        if self.lpf_settle_us == 0 {
+            if let Some(sensed) = sense.lpf {
+                if sensed != self.outputs.lpf {
+                    return Some(RelayFault::LpfMismatch {
+                        wanted: self.outputs.lpf,
+                        sensed,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Default for RelaySequencer {
+    fn default() -> Self {
+        Self::new(RelayTiming::DEFAULT)
+    }
+}
@@ -4,9 +4,10 @@
 //! Implements immutable state transitions for predictable behavior.
 
 use crate::types::{Band, Frequency, Mode, PowerLevel, TuningStep, TxRxState};
+use sdr_dsp_core::agc::AgcConfig;
 
 /// Complete radio state (immutable)
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RadioState {
     /// Current VFO frequency
     frequency: Frequency,
@@ -385,6 +386,96 @@ impl AgcMode {
         }
     }
 
+    /// Get hang time in milliseconds
+    #[must_use]
+    pub const fn hang_ms(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::Fast => 50,
+            Self::Medium => 200,
+            Self::Slow => 500,
+        }
+    }
+
+    /// Get hang threshold in dBFS (weaker signals decay without hang)
+    #[must_use]
+    pub const fn hang_threshold_db(self) -> f32 {
+        match self {
+            Self::Off => 0.0,
+            Self::Fast | Self::Medium => -80.0,
+            Self::Slow => -70.0,
+        }
+    }
+
+    /// Get output slope in dB per 10 dB above the knee
+    #[must_use]
+    pub const fn slope_db(self) -> f32 {
+        match self {
+            Self::Off | Self::Fast => 0.0,
+            Self::Medium => 2.0,
+            Self::Slow => 3.0,
+        }
+    }
+
+    /// Get maximum gain in dB
+    #[must_use]
+    pub const fn max_gain_db(self) -> f32 {
+        match self {
+            Self::Off => 0.0,
+            Self::Fast => 60.0,
+            Self::Medium => 70.0,
+            Self::Slow => 80.0,
+        }
+    }
+
+    /// Check if the dual-rate detector is used (fast recovery from clicks)
+    #[must_use]
+    pub const fn dual_rate(self) -> bool {
+        matches!(self, Self::Fast)
+    }
+
+    /// Get the full AGC configuration for this preset (None when off)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn agc_config(self) -> Option<AgcConfig> {
+        if matches!(self, Self::Off) {
+            return None;
+        }
+        let config = AgcConfig {
+            attack_ms: self.attack_ms() as f32,
+            decay_ms: self.decay_ms() as f32,
+            hang_ms: self.hang_ms() as f32,
+            hang_threshold_db: self.hang_threshold_db(),
+            slope_db: self.slope_db(),
+            dual_rate: self.dual_rate(),
+            ..AgcConfig::default()
+        };
+        Some(config.with_max_gain_db(self.max_gain_db()))
+    }
+
+    /// Compact code for storage (0 = off ... 3 = slow)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Fast => 1,
+            Self::Medium => 2,
+            Self::Slow => 3,
+        }
+    }
+
+    /// Parse a compact AGC code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Off),
+            1 => Some(Self::Fast),
+            2 => Some(Self::Medium),
+            3 => Some(Self::Slow),
+            _ => None,
+        }
+    }
+
     /// Cycle to next mode
     #[must_use]
     pub const fn next(self) -> Self {
@@ -440,6 +531,8 @@ pub enum RadioEvent {
     ToggleXit,
     /// Cycle AGC
     CycleAgc,
+    /// Set AGC mode
+    SetAgc(AgcMode),
     /// Toggle noise blanker
     ToggleNb,
     /// Toggle preamp
@@ -454,6 +547,16 @@ pub enum RadioEvent {
     CopyAtoB,
     /// Copy VFO B to A
     CopyBtoA,
+    /// Measure the received tone and center it (CW zero-beat, SSB passband)
+    AutoTune,
+    /// Move the dial by Hz (the answer to `AutoTune`)
+    ShiftFrequency(i32),
+    /// Capture decimated IQ samples for diagnostics
+    CaptureIq(u16),
+    /// Switch adaptive IQ balance on or off
+    SetIqAdaptive(bool),
+    /// Store the current state as the fixed startup state and save settings
+    StoreState,
 }
 
 #[cfg(feature = "embedded")]
@@ -474,6 +577,7 @@ impl defmt::Format for RadioEvent {
             Self::ClearRit => defmt::write!(f, "ClearRIT"),
             Self::ToggleXit => defmt::write!(f, "ToggleXIT"),
             Self::CycleAgc => defmt::write!(f, "CycleAGC"),
+            Self::SetAgc(mode) => defmt::write!(f, "SetAGC({})", mode),
             Self::ToggleNb => defmt::write!(f, "ToggleNB"),
             Self::TogglePreamp => defmt::write!(f, "TogglePreamp"),
             Self::ToggleAtt => defmt::write!(f, "ToggleAtt"),
@@ -481,6 +585,11 @@ impl defmt::Format for RadioEvent {
             Self::SwapVfo => defmt::write!(f, "SwapVFO"),
             Self::CopyAtoB => defmt::write!(f, "CopyA>B"),
             Self::CopyBtoA => defmt::write!(f, "CopyB>A"),
+            Self::AutoTune => defmt::write!(f, "AutoTune"),
+            Self::ShiftFrequency(hz) => defmt::write!(f, "Shift({})", hz),
+            Self::CaptureIq(count) => defmt::write!(f, "CaptureIq({})", count),
+            Self::SetIqAdaptive(on) => defmt::write!(f, "SetIqAdaptive({})", on),
+            Self::StoreState => defmt::write!(f, "StoreState"),
         }
     }
 }
@@ -509,6 +618,7 @@ pub fn apply_event(state: RadioState, event: RadioEvent) -> RadioState {
         RadioEvent::ClearRit => state.clear_rit(),
         RadioEvent::ToggleXit => state.toggle_xit(),
         RadioEvent::CycleAgc => state.with_agc(state.agc_mode.next()),
+        RadioEvent::SetAgc(mode) => state.with_agc(mode),
         RadioEvent::ToggleNb => state.toggle_nb(),
         RadioEvent::TogglePreamp => state.toggle_preamp(),
         RadioEvent::ToggleAtt => state.toggle_attenuator(),
@@ -516,5 +626,18 @@ pub fn apply_event(state: RadioState, event: RadioEvent) -> RadioState {
             // VFO operations require VfoManager, handled at higher level
             state
         }
+        // The DSP task measures the offset and answers with ShiftFrequency,
+        // or captures IQ or adapts its IQ balance, and the control task
+        // saves the settings, none of which changes anything shown
+        RadioEvent::AutoTune
+        | RadioEvent::CaptureIq(_)
+        | RadioEvent::SetIqAdaptive(_)
+        | RadioEvent::StoreState => state,
+        RadioEvent::ShiftFrequency(hz) => state
+            .frequency()
+            .as_hz()
+            .checked_add_signed(hz)
+            .and_then(Frequency::from_hz)
+            .map_or(state, |freq| state.with_frequency(freq)),
     }
 }
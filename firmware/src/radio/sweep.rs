@@ -0,0 +1,247 @@
+//! Antenna Analyzer Sweep
+//!
+//! Steps a low power carrier across a frequency span, reading the SWR
+//! bridge at each point, so SWR can be plotted against frequency while
+//! tuning an antenna.
+//!
+//! The sweep only sequences the steps. The caller tunes the Si5351 to
+//! each frequency it returns, keys a carrier at [`SWEEP_POWER`], reads
+//! the bridge when [`AntennaSweep::update`] says the carrier has settled
+//! and streams each recorded point over CAT (`ZWP`) as it arrives.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut sweep = AntennaSweep::new();
+//! let freq = sweep.start_band(Band::M40, DEFAULT_POINTS).unwrap();
+//! si5351.set_frequency(freq).await?;
+//! radio.key_carrier(SWEEP_POWER);
+//! // every millisecond tick
+//! if sweep.update(1) {
+//!     let next = sweep.record(bridge.read());
+//!     cat.sweep_point(sweep.len() - 1, sweep.last().unwrap());
+//!     match next {
+//!         Some(freq) => si5351.set_frequency(freq).await?,
+//!         None => radio.unkey(),
+//!     }
+//! }
+//! ```
+
+use heapless::Vec;
+
+use crate::types::{Band, Frequency, PowerLevel, SwrReading};
+
+/// Most points in one sweep
+pub const MAX_POINTS: usize = 101;
+
+/// Default number of points in a sweep
+pub const DEFAULT_POINTS: usize = 51;
+
+/// Carrier power while sweeping, low enough for any mismatch
+pub const SWEEP_POWER: PowerLevel = PowerLevel::from_percent(5);
+
+/// Default time for the synthesizer and bridge to settle after each step
+pub const DEFAULT_SETTLE_MS: u32 = 10;
+
+/// One measured point of a sweep
+#[derive(Clone, Copy, Debug)]
+pub struct SweepPoint {
+    /// Carrier frequency
+    pub frequency: Frequency,
+    /// Bridge reading at this frequency
+    pub reading: SwrReading,
+}
+
+impl SweepPoint {
+    /// Get the SWR at this point
+    #[must_use]
+    pub fn swr(&self) -> f32 {
+        self.reading.swr_ratio()
+    }
+}
+
+/// SWR versus frequency sweep
+#[derive(Clone, Debug)]
+pub struct AntennaSweep {
+    /// Sweep running
+    active: bool,
+    /// First and last frequency in Hz
+    start_hz: u32,
+    end_hz: u32,
+    /// Points requested
+    total: usize,
+    /// Points measured so far
+    points: Vec<SweepPoint, MAX_POINTS>,
+    /// Settle time after each step
+    settle_ms: u32,
+    /// Time since the last step
+    elapsed_ms: u32,
+}
+
+impl Default for AntennaSweep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AntennaSweep {
+    /// Create an idle sweep
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            active: false,
+            start_hz: 0,
+            end_hz: 0,
+            total: 0,
+            points: Vec::new(),
+            settle_ms: DEFAULT_SETTLE_MS,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// Start a sweep of `points` steps from `start` to `end` inclusive,
+    /// discarding any earlier curve
+    ///
+    /// Returns the first frequency to tune, or `None` if the span is
+    /// empty or `points` is outside 2 to [`MAX_POINTS`].
+    pub fn start(&mut self, start: Frequency, end: Frequency, points: usize) -> Option<Frequency> {
+        if end.as_hz() <= start.as_hz() || !(2..=MAX_POINTS).contains(&points) {
+            return None;
+        }
+        self.start_hz = start.as_hz();
+        self.end_hz = end.as_hz();
+        self.total = points;
+        self.points.clear();
+        self.elapsed_ms = 0;
+        self.active = true;
+        Some(start)
+    }
+
+    /// Start a sweep across a whole band
+    pub fn start_band(&mut self, band: Band, points: usize) -> Option<Frequency> {
+        self.start(
+            Frequency::from_hz(band.start_hz())?,
+            Frequency::from_hz(band.end_hz())?,
+            points,
+        )
+    }
+
+    /// Stop the sweep, keeping the points measured so far
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// Check if a sweep is running (the carrier should be keyed)
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Set the settle time after each step
+    pub fn set_settle_ms(&mut self, ms: u32) {
+        self.settle_ms = ms;
+    }
+
+    /// Get the settle time after each step
+    #[must_use]
+    pub const fn settle_ms(&self) -> u32 {
+        self.settle_ms
+    }
+
+    /// Advance the step timer
+    ///
+    /// Returns true once the carrier has settled, and the bridge should
+    /// be read and passed to [`AntennaSweep::record`].
+    pub fn update(&mut self, elapsed_ms: u32) -> bool {
+        if !self.active {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(elapsed_ms);
+        self.elapsed_ms >= self.settle_ms
+    }
+
+    /// Record the bridge reading at the current frequency
+    ///
+    /// Returns the next frequency to tune, or `None` when the sweep is
+    /// complete and the carrier should be unkeyed.
+    pub fn record(&mut self, reading: SwrReading) -> Option<Frequency> {
+        if !self.active {
+            return None;
+        }
+        let frequency = self.frequency_at(self.points.len())?;
+        let _ = self.points.push(SweepPoint { frequency, reading });
+        self.elapsed_ms = 0;
+        if self.points.len() >= self.total {
+            self.active = false;
+            return None;
+        }
+        self.frequency_at(self.points.len())
+    }
+
+    /// Get the frequency of point `index` of the current span
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn frequency_at(&self, index: usize) -> Option<Frequency> {
+        if index >= self.total {
+            return None;
+        }
+        let span = u64::from(self.end_hz - self.start_hz);
+        let offset = span * index as u64 / (self.total as u64 - 1);
+        Frequency::from_hz(self.start_hz + offset as u32)
+    }
+
+    /// Get the points measured so far, lowest frequency first
+    #[must_use]
+    pub fn points(&self) -> &[SweepPoint] {
+        &self.points
+    }
+
+    /// Get the most recently measured point
+    #[must_use]
+    pub fn last(&self) -> Option<&SweepPoint> {
+        self.points.last()
+    }
+
+    /// Get the number of points measured
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Check if no points have been measured
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Get the number of points in the sweep
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Get the point with the lowest SWR (the antenna's resonance)
+    #[must_use]
+    pub fn best(&self) -> Option<&SweepPoint> {
+        self.points
+            .iter()
+            .min_by(|a, b| a.swr().total_cmp(&b.swr()))
+    }
+
+    /// Get the span around the lowest SWR point where the SWR stays
+    /// below `limit`, e.g. the 2:1 bandwidth
+    #[must_use]
+    pub fn bandwidth(&self, limit: f32) -> Option<(Frequency, Frequency)> {
+        let best = self
+            .points
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.swr().total_cmp(&b.swr()))
+            .filter(|(_, point)| point.swr() < limit)?
+            .0;
+        let below = |point: &&SweepPoint| point.swr() < limit;
+        let low = self.points[..=best].iter().rev().take_while(below).last()?;
+        let high = self.points[best..].iter().take_while(below).last()?;
+        Some((low.frequency, high.frequency))
+    }
+}
@@ -0,0 +1,214 @@
+//! SWR Bridge Metering
+//!
+//! Turns forward and reflected detector readings from the SWR bridge
+//! into [`SwrReading`]s for
+//! [`TxController::update_swr`](crate::radio::transmit::TxController::update_swr).
+//! The bridge is sampled every
+//! [`SWR_SAMPLE_INTERVAL_MS`](crate::config::SWR_SAMPLE_INTERVAL_MS) and
+//! [`SWR_AVERAGE_SAMPLES`] samples are averaged into each reading, one
+//! every 20 ms: quick enough to catch a bad antenna within a CW dit,
+//! slow enough to ride over single SSB peaks.
+//!
+//! The detectors are diodes, so counts are far from proportional to
+//! power, least of all at low power. [`SwrCalibration`] maps counts to
+//! watts by interpolating between points measured into a dummy load
+//! against a reference wattmeter; both detectors share the table.
+//! Readings carry power in milliwatts, so
+//! [`SwrReading::swr_ratio`] works on a true power ratio.
+
+use crate::config::SWR_AVERAGE_SAMPLES;
+use crate::types::SwrReading;
+
+/// Most points in a calibration table
+pub const MAX_CAL_POINTS: usize = 8;
+
+/// Detector calibration from ADC counts to watts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwrCalibration {
+    /// Points as (counts, watts), counts increasing
+    points: [(u16, f32); MAX_CAL_POINTS],
+    /// Points in use
+    len: usize,
+}
+
+impl SwrCalibration {
+    /// Typical table for the 1N5711 detectors at 5 W full scale
+    pub const DEFAULT: Self = Self {
+        points: [
+            (0, 0.0),
+            (150, 0.05),
+            (400, 0.5),
+            (900, 2.0),
+            (1500, 5.0),
+            (2600, 15.0),
+            (0, 0.0),
+            (0, 0.0),
+        ],
+        len: 6,
+    };
+
+    /// Create from measured points
+    ///
+    /// Needs 2 to [`MAX_CAL_POINTS`] points with counts strictly
+    /// increasing and watts never decreasing.
+    #[must_use]
+    pub fn new(points: &[(u16, f32)]) -> Option<Self> {
+        if points.len() < 2 || points.len() > MAX_CAL_POINTS {
+            return None;
+        }
+        let ordered = points
+            .windows(2)
+            .all(|pair| pair[1].0 > pair[0].0 && pair[1].1 >= pair[0].1);
+        if !ordered || points[0].1 < 0.0 {
+            return None;
+        }
+        let mut table = [(0, 0.0); MAX_CAL_POINTS];
+        table[..points.len()].copy_from_slice(points);
+        Some(Self {
+            points: table,
+            len: points.len(),
+        })
+    }
+
+    /// Get the points in use
+    #[must_use]
+    pub fn points(&self) -> &[(u16, f32)] {
+        &self.points[..self.len]
+    }
+
+    /// Convert detector counts to watts
+    ///
+    /// Interpolates between the points; beyond the last point the last
+    /// segment is extended, and below the first the first point is
+    /// scaled towards zero.
+    #[must_use]
+    pub fn watts(&self, counts: u16) -> f32 {
+        let points = self.points();
+        let (first_counts, first_watts) = points[0];
+        if counts <= first_counts {
+            return if first_counts == 0 {
+                first_watts
+            } else {
+                first_watts * f32::from(counts) / f32::from(first_counts)
+            };
+        }
+        let segment = points
+            .windows(2)
+            .find(|pair| counts <= pair[1].0)
+            .unwrap_or(&points[points.len() - 2..]);
+        let (c0, w0) = segment[0];
+        let (c1, w1) = segment[1];
+        let fraction = (f32::from(counts) - f32::from(c0)) / (f32::from(c1) - f32::from(c0));
+        (w0 + (w1 - w0) * fraction).max(0.0)
+    }
+
+    /// Convert detector counts to milliwatts, saturating
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn milliwatts(&self, counts: u16) -> u16 {
+        (self.watts(counts) * 1000.0 + 0.5).min(f32::from(u16::MAX)) as u16
+    }
+}
+
+impl Default for SwrCalibration {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Averages bridge samples into calibrated SWR readings
+#[derive(Clone, Copy, Debug)]
+pub struct SwrMeter {
+    /// Counts to watts
+    calibration: SwrCalibration,
+    /// Sum of forward counts in the current average
+    forward_sum: u32,
+    /// Sum of reflected counts in the current average
+    reflected_sum: u32,
+    /// Samples in the current average
+    count: u32,
+    /// Latest reading
+    last: Option<SwrReading>,
+}
+
+impl SwrMeter {
+    /// Create a meter with a calibration
+    #[must_use]
+    pub const fn new(calibration: SwrCalibration) -> Self {
+        Self {
+            calibration,
+            forward_sum: 0,
+            reflected_sum: 0,
+            count: 0,
+            last: None,
+        }
+    }
+
+    /// Get the calibration
+    #[must_use]
+    pub const fn calibration(&self) -> &SwrCalibration {
+        &self.calibration
+    }
+
+    /// Replace the calibration, starting a fresh average
+    pub fn set_calibration(&mut self, calibration: SwrCalibration) {
+        self.calibration = calibration;
+        self.reset();
+    }
+
+    /// Add one sample of raw forward and reflected counts
+    ///
+    /// Returns a reading (power in milliwatts) every
+    /// [`SWR_AVERAGE_SAMPLES`] samples.
+    pub fn push(&mut self, forward: u16, reflected: u16) -> Option<SwrReading> {
+        self.forward_sum += u32::from(forward);
+        self.reflected_sum += u32::from(reflected);
+        self.count += 1;
+        if self.count < SWR_AVERAGE_SAMPLES {
+            return None;
+        }
+
+        let average = |sum: u32| u16::try_from(sum / SWR_AVERAGE_SAMPLES).unwrap_or(u16::MAX);
+        let reading = SwrReading {
+            forward: self.calibration.milliwatts(average(self.forward_sum)),
+            reflected: self.calibration.milliwatts(average(self.reflected_sum)),
+        };
+        self.forward_sum = 0;
+        self.reflected_sum = 0;
+        self.count = 0;
+        self.last = Some(reading);
+        Some(reading)
+    }
+
+    /// Get the latest reading
+    #[must_use]
+    pub const fn last(&self) -> Option<SwrReading> {
+        self.last
+    }
+
+    /// Get the latest forward power in watts
+    #[must_use]
+    pub fn forward_watts(&self) -> f32 {
+        self.last.map_or(0.0, |r| f32::from(r.forward) / 1000.0)
+    }
+
+    /// Get the latest reflected power in watts
+    #[must_use]
+    pub fn reflected_watts(&self) -> f32 {
+        self.last.map_or(0.0, |r| f32::from(r.reflected) / 1000.0)
+    }
+
+    /// Drop the partial average and the latest reading (e.g. at key-up)
+    pub fn reset(&mut self) {
+        self.forward_sum = 0;
+        self.reflected_sum = 0;
+        self.count = 0;
+        self.last = None;
+    }
+}
+
+impl Default for SwrMeter {
+    fn default() -> Self {
+        Self::new(SwrCalibration::DEFAULT)
+    }
+}
@@ -1,13 +1,34 @@
 //! Transmit Control
 //!
 //! Manages the transmit sequence including T/R switching,
-//! SWR protection, and power control.
+//! SWR protection, and power control. Every key-up is checked against
+//! the [`TxPolicy`] (band edges, mode segments, external inhibit).
+//!
+//! # Full Break-In (QSK)
+//!
+//! In QSK mode the keyer's key line drives the sequence element by
+//! element: receive audio is ramped down, the T/R relay pulls in, the PA
+//! keys for the element, and after key-up the relay is held for the RX
+//! recovery delay so the next element can follow without a relay cycle.
+//! Receive audio ramps back up only once the relay has settled, so relay
+//! clicks never reach the speaker.
 
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+
+use super::tx_policy::{TxPolicy, TxVerdict};
+use crate::config::{MAX_TX_POWER_WATTS, PD_MAX_TX_POWER_WATTS};
 use crate::types::{PowerLevel, SwrReading, TxRxState};
 
 /// T/R relay switching delay in microseconds
 const TR_RELAY_DELAY_US: u32 = 10_000;
 
+/// Default QSK receive recovery delay in milliseconds
+pub const QSK_DEFAULT_RECOVERY_MS: u16 = 8;
+
+/// Default receive mute ramp in milliseconds
+pub const QSK_DEFAULT_RAMP_MS: u16 = 2;
+
 /// Transmit state machine
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[derive(Default)]
@@ -54,6 +75,7 @@ impl defmt::Format for TxState {
 
 /// Transmit controller
 #[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct TxController {
     /// Current state
     state: TxState,
@@ -61,10 +83,18 @@ pub struct TxController {
     ptt: bool,
     /// VOX trigger state
     vox: bool,
+    /// VOX detector fed from the microphone and receive audio
+    vox_control: Vox,
     /// Requested power level
     power: PowerLevel,
-    /// Actual power output (may be reduced for SWR)
+    /// Actual power output (may be reduced for SWR or PA temperature)
     actual_power: PowerLevel,
+    /// PA thermal power limit in percent
+    thermal_limit: u8,
+    /// PA thermal cutoff tripped
+    thermal_cutoff: bool,
+    /// Output at full power in watts, set by the supply
+    power_ceiling_watts: f32,
     /// Last SWR reading
     last_swr: Option<SwrReading>,
     /// SWR protection trip count
@@ -75,8 +105,20 @@ pub struct TxController {
     timeout_s: u32,
     /// TX timeout limit (0 = disabled)
     timeout_limit_s: u32,
-    /// TX inhibit flag
-    inhibit: bool,
+    /// Transmit rules, including the external inhibit
+    policy: TxPolicy,
+    /// Full break-in mode
+    qsk: bool,
+    /// Key line from the keyer
+    key: bool,
+    /// T/R relay energised (QSK sequencing)
+    relay_on: bool,
+    /// Receive recovery delay after key-up (microseconds)
+    rx_recovery_us: u32,
+    /// Receive mute ramp length (microseconds)
+    mute_ramp_us: u32,
+    /// Receive audio gain (0.0 muted, 1.0 full)
+    rx_gain: f32,
 }
 
 impl TxController {
@@ -96,14 +138,24 @@ impl TxController {
             state: TxState::Rx,
             ptt: false,
             vox: false,
+            vox_control: Vox::new(),
             power: PowerLevel::default(),
             actual_power: PowerLevel::default(),
+            thermal_limit: 100,
+            thermal_cutoff: false,
+            power_ceiling_watts: MAX_TX_POWER_WATTS,
             last_swr: None,
             swr_trip_count: 0,
             switch_delay_us: 0,
             timeout_s: 0,
             timeout_limit_s: Self::DEFAULT_TIMEOUT_S,
-            inhibit: false,
+            policy: TxPolicy::new(),
+            qsk: false,
+            key: false,
+            relay_on: false,
+            rx_recovery_us: u32::from(QSK_DEFAULT_RECOVERY_MS) * 1000,
+            mute_ramp_us: u32::from(QSK_DEFAULT_RAMP_MS) * 1000,
+            rx_gain: 1.0,
         }
     }
 
@@ -159,7 +211,7 @@ impl TxController {
     pub fn set_power(&mut self, power: PowerLevel) {
         self.power = power;
         if !self.is_transmitting() {
-            self.actual_power = power;
+            self.actual_power = self.limited_power();
         }
     }
 
@@ -178,15 +230,97 @@ impl TxController {
         self.vox = triggered;
     }
 
-    /// Set TX inhibit
+    /// Get the VOX detector settings
+    #[must_use]
+    pub const fn vox_control(&self) -> &Vox {
+        &self.vox_control
+    }
+
+    /// Get mutable access to the VOX detector (gain, delay, anti-VOX)
+    pub fn vox_control_mut(&mut self) -> &mut Vox {
+        &mut self.vox_control
+    }
+
+    /// Run the VOX detector over a block of microphone and receive audio
+    ///
+    /// `rx` is the audio sent to the speaker over the same period; it
+    /// drives anti-VOX. The VOX trigger follows the detector.
+    pub fn process_vox_audio(&mut self, mic: &[f32], rx: &[f32]) {
+        if !self.vox_control.is_enabled() {
+            self.vox = false;
+            return;
+        }
+        self.vox = self.vox_control.process_block(mic, rx);
+    }
+
+    /// Set TX inhibit (the policy's external inhibit input)
     pub fn set_inhibit(&mut self, inhibit: bool) {
-        self.inhibit = inhibit;
+        self.policy.set_external_inhibit(inhibit);
+    }
+
+    /// Get the transmit policy
+    #[must_use]
+    pub const fn policy(&self) -> &TxPolicy {
+        &self.policy
+    }
+
+    /// Get mutable access to the transmit policy (operating point, override)
+    pub fn policy_mut(&mut self) -> &mut TxPolicy {
+        &mut self.policy
+    }
+
+    /// Check the transmit policy at the current operating point
+    #[must_use]
+    pub fn verdict(&self) -> TxVerdict {
+        self.policy.verdict()
+    }
+
+    /// Enable or disable full break-in (QSK)
+    pub fn set_qsk(&mut self, enabled: bool) {
+        self.qsk = enabled;
+    }
+
+    /// Check if full break-in is enabled
+    #[must_use]
+    pub const fn is_qsk(&self) -> bool {
+        self.qsk
+    }
+
+    /// Set key line state from the keyer
+    pub fn set_key(&mut self, down: bool) {
+        self.key = down;
+    }
+
+    /// Set receive recovery delay after key-up in milliseconds
+    ///
+    /// The relay stays in TX for this long after each element; longer
+    /// delays hold the relay between characters instead of elements.
+    pub fn set_rx_recovery_ms(&mut self, ms: u16) {
+        self.rx_recovery_us = u32::from(ms) * 1000;
+    }
+
+    /// Get receive recovery delay in milliseconds
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn rx_recovery_ms(&self) -> u16 {
+        (self.rx_recovery_us / 1000) as u16
+    }
+
+    /// Set receive mute ramp length in milliseconds (0 = hard mute)
+    pub fn set_mute_ramp_ms(&mut self, ms: u16) {
+        self.mute_ramp_us = u32::from(ms) * 1000;
+    }
+
+    /// Receive audio gain to apply (0.0 muted, 1.0 full)
+    #[must_use]
+    pub const fn rx_gain(&self) -> f32 {
+        self.rx_gain
     }
 
     /// Clear SWR protection trip
     pub fn clear_swr_trip(&mut self) {
         self.swr_trip_count = 0;
-        if self.state == TxState::Inhibited {
+        if self.state == TxState::Inhibited && !self.thermal_cutoff {
             self.state = TxState::Rx;
         }
     }
@@ -206,15 +340,199 @@ impl TxController {
             // High SWR - reduce power
             self.swr_trip_count += 1;
             let reduction = ((swr - Self::SWR_LIMIT) * 10.0) as u8;
-            let new_percent = self.power.as_percent().saturating_sub(reduction);
+            let new_percent = self.limited_power().as_percent().saturating_sub(reduction);
             self.actual_power = PowerLevel::from_percent(new_percent.max(10));
         }
     }
 
+    /// Apply the PA thermal limit and cutoff
+    ///
+    /// Power is held to `limit_percent` of full scale; a lower limit
+    /// takes effect at once, a higher one from the next transmission.
+    /// While `cutoff` holds TX is inhibited, and stays so until it
+    /// clears and PTT is released.
+    pub fn update_thermal(&mut self, limit_percent: u8, cutoff: bool) {
+        self.thermal_limit = limit_percent.min(100);
+        self.thermal_cutoff = cutoff;
+        if cutoff {
+            self.state = TxState::Inhibited;
+            self.actual_power = PowerLevel::MIN;
+        } else if self.is_transmitting() {
+            if self.actual_power.as_percent() > self.thermal_limit {
+                self.actual_power = PowerLevel::from_percent(self.thermal_limit);
+            }
+        } else {
+            self.actual_power = self.limited_power();
+        }
+    }
+
+    /// Get the PA thermal power limit in percent
+    #[must_use]
+    pub const fn thermal_limit(&self) -> u8 {
+        self.thermal_limit
+    }
+
+    /// Check if the PA thermal cutoff is holding TX off
+    #[must_use]
+    pub const fn is_thermal_cutoff(&self) -> bool {
+        self.thermal_cutoff
+    }
+
+    /// Set the output at full power in watts for the supply in use
+    ///
+    /// Power levels stay a percentage of this ceiling, raised from
+    /// [`MAX_TX_POWER_WATTS`] when a higher voltage USB-PD contract is
+    /// negotiated.
+    pub fn set_power_ceiling(&mut self, watts: f32) {
+        self.power_ceiling_watts = watts.clamp(0.0, PD_MAX_TX_POWER_WATTS);
+    }
+
+    /// Get the output at full power in watts
+    #[must_use]
+    pub const fn power_ceiling_watts(&self) -> f32 {
+        self.power_ceiling_watts
+    }
+
+    /// Get the current output power in watts
+    #[must_use]
+    pub fn output_watts(&self) -> f32 {
+        f32::from(self.actual_power.as_percent()) * self.power_ceiling_watts / 100.0
+    }
+
+    /// Requested power within the thermal limit
+    const fn limited_power(&self) -> PowerLevel {
+        if self.power.as_percent() > self.thermal_limit {
+            PowerLevel::from_percent(self.thermal_limit)
+        } else {
+            self.power
+        }
+    }
+
     /// Update state machine (call periodically)
     /// Returns actions to take
     pub fn update(&mut self, elapsed_us: u32) -> TxAction {
-        let want_tx = (self.ptt || self.vox) && !self.inhibit;
+        let action = if self.qsk {
+            self.update_qsk(elapsed_us)
+        } else {
+            self.update_ptt(elapsed_us)
+        };
+        self.ramp_rx_gain(elapsed_us);
+        action
+    }
+
+    /// Move receive gain towards full in RX and muted otherwise
+    #[allow(clippy::cast_precision_loss)]
+    fn ramp_rx_gain(&mut self, elapsed_us: u32) {
+        let target = if self.state == TxState::Rx || self.state == TxState::Inhibited {
+            1.0
+        } else {
+            0.0
+        };
+        if self.mute_ramp_us == 0 {
+            self.rx_gain = target;
+            return;
+        }
+        let step = elapsed_us as f32 / self.mute_ramp_us as f32;
+        self.rx_gain = if target > self.rx_gain {
+            (self.rx_gain + step).min(target)
+        } else {
+            (self.rx_gain - step).max(target)
+        };
+    }
+
+    /// Full break-in sequencing, one T/R cycle per key-down
+    fn update_qsk(&mut self, elapsed_us: u32) -> TxAction {
+        let want_tx = (self.key || self.ptt || self.vox) && !self.verdict().is_denied();
+
+        match self.state {
+            TxState::Rx => {
+                if want_tx {
+                    // Mute first; the relay pulls in once audio is down
+                    self.state = TxState::SwitchingToTx;
+                    self.timeout_s = 0;
+                }
+            }
+
+            TxState::SwitchingToTx => {
+                if !want_tx {
+                    self.state = if self.relay_on {
+                        self.switch_delay_us = self.rx_recovery_us;
+                        TxState::SwitchingToRx
+                    } else {
+                        TxState::Rx
+                    };
+                } else if !self.relay_on {
+                    if self.rx_gain <= 0.0 {
+                        self.relay_on = true;
+                        self.switch_delay_us = TR_RELAY_DELAY_US;
+                        return TxAction::EnableTrRelay;
+                    }
+                } else {
+                    self.switch_delay_us = self.switch_delay_us.saturating_sub(elapsed_us);
+                    if self.switch_delay_us == 0 {
+                        self.state = TxState::Tx;
+                        self.actual_power = self.limited_power();
+                        return TxAction::EnablePa;
+                    }
+                }
+            }
+
+            TxState::Tx => {
+                if self.timeout_limit_s > 0 && self.timeout_s >= self.timeout_limit_s {
+                    // Stuck key: stay off until it is released
+                    self.state = TxState::Inhibited;
+                    return TxAction::DisablePa;
+                }
+                if !want_tx {
+                    // Hold the relay for the recovery delay
+                    self.state = TxState::SwitchingToRx;
+                    self.switch_delay_us = self.rx_recovery_us;
+                    return TxAction::DisablePa;
+                }
+                return TxAction::SetPower(self.actual_power);
+            }
+
+            TxState::SwitchingToRx => {
+                if want_tx {
+                    if self.relay_on {
+                        // Next element within the recovery delay
+                        self.state = TxState::Tx;
+                        self.actual_power = self.limited_power();
+                        return TxAction::EnablePa;
+                    }
+                    self.state = TxState::SwitchingToTx;
+                    return TxAction::None;
+                }
+
+                self.switch_delay_us = self.switch_delay_us.saturating_sub(elapsed_us);
+                if self.switch_delay_us == 0 {
+                    if self.relay_on {
+                        // Release the relay and wait for it to settle muted
+                        self.relay_on = false;
+                        self.switch_delay_us = TR_RELAY_DELAY_US;
+                        return TxAction::DisableTrRelay;
+                    }
+                    self.state = TxState::Rx;
+                }
+            }
+
+            TxState::Inhibited => {
+                if self.relay_on {
+                    self.relay_on = false;
+                    return TxAction::DisableTrRelay;
+                }
+                if !want_tx && !self.thermal_cutoff {
+                    self.state = TxState::Rx;
+                }
+            }
+        }
+
+        TxAction::None
+    }
+
+    /// PTT/VOX sequencing
+    fn update_ptt(&mut self, elapsed_us: u32) -> TxAction {
+        let want_tx = (self.ptt || self.vox) && !self.verdict().is_denied();
 
         match self.state {
             TxState::Rx => {
@@ -236,7 +554,7 @@ impl TxController {
                 if self.switch_delay_us == 0 {
                     self.state = TxState::Tx;
                     self.timeout_s = 0;
-                    self.actual_power = self.power;
+                    self.actual_power = self.limited_power();
                     return TxAction::EnablePa;
                 }
             }
@@ -266,7 +584,7 @@ impl TxController {
             }
 
             TxState::Inhibited => {
-                if !want_tx {
+                if !want_tx && !self.thermal_cutoff {
                     self.state = TxState::Rx;
                 }
             }
@@ -333,7 +651,25 @@ impl defmt::Format for TxAction {
     }
 }
 
+/// Maximum VOX and anti-VOX gain setting
+pub const VOX_GAIN_MAX: u8 = 100;
+
+/// Default VOX gain setting
+pub const DEFAULT_VOX_GAIN: u8 = 33;
+
+/// Default anti-VOX gain setting
+pub const DEFAULT_ANTI_VOX_GAIN: u8 = 50;
+
+/// Maximum VOX delay (hang time) in milliseconds
+pub const VOX_DELAY_MAX_MS: u32 = 3000;
+
 /// VOX (Voice Operated Transmit) controller
+///
+/// The envelope of the microphone audio is compared with a threshold
+/// set by the VOX gain. With anti-trip enabled, the receive audio level
+/// raises the threshold so speaker audio reaching the microphone does
+/// not key the transmitter; the anti-VOX gain sets how much speaker
+/// audio the microphone is expected to pick up.
 #[derive(Clone, Copy, Debug)]
 pub struct Vox {
     /// VOX enabled
@@ -344,10 +680,18 @@ pub struct Vox {
     level: f32,
     /// Hang time in samples
     hang_samples: u32,
+    /// Hang time in milliseconds
+    hang_ms: u32,
     /// Hang counter
     hang_counter: u32,
     /// Anti-trip enabled (suppress speaker audio)
     anti_trip: bool,
+    /// VOX gain setting (0-100)
+    gain: u8,
+    /// Anti-VOX gain setting (0-100)
+    anti_vox_gain: u8,
+    /// Receive audio envelope for anti-VOX
+    rx_level: f32,
 }
 
 impl Vox {
@@ -359,8 +703,12 @@ impl Vox {
             threshold: 0.1,
             level: 0.0,
             hang_samples: 24000, // 500ms at 48kHz
+            hang_ms: 500,
             hang_counter: 0,
             anti_trip: true,
+            gain: DEFAULT_VOX_GAIN,
+            anti_vox_gain: DEFAULT_ANTI_VOX_GAIN,
+            rx_level: 0.0,
         }
     }
 
@@ -372,16 +720,81 @@ impl Vox {
         }
     }
 
+    /// Check if VOX is enabled
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     /// Set threshold (0.0-1.0)
     pub fn set_threshold(&mut self, threshold: f32) {
         self.threshold = threshold.clamp(0.0, 1.0);
     }
 
+    /// Get threshold
+    #[must_use]
+    pub const fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Set VOX gain (0-100), higher gain triggers on quieter speech
+    ///
+    /// Gain 0 puts the threshold at full scale, each step lowers it by
+    /// 0.6 dB down to -60 dBFS at full gain.
+    pub fn set_gain(&mut self, gain: u8) {
+        self.gain = gain.min(VOX_GAIN_MAX);
+        self.threshold = 10.0_f32.powf(-0.03 * f32::from(self.gain));
+    }
+
+    /// Get VOX gain setting
+    #[must_use]
+    pub const fn gain(&self) -> u8 {
+        self.gain
+    }
+
+    /// Set anti-VOX gain (0-100, 50 = speaker audio at unity)
+    pub fn set_anti_vox_gain(&mut self, gain: u8) {
+        self.anti_vox_gain = gain.min(VOX_GAIN_MAX);
+    }
+
+    /// Get anti-VOX gain setting
+    #[must_use]
+    pub const fn anti_vox_gain(&self) -> u8 {
+        self.anti_vox_gain
+    }
+
     /// Set hang time in milliseconds
     pub fn set_hang_ms(&mut self, ms: u32, sample_rate: u32) {
+        self.hang_ms = ms;
         self.hang_samples = ms * sample_rate / 1000;
     }
 
+    /// Get hang time in milliseconds
+    #[must_use]
+    pub const fn hang_ms(&self) -> u32 {
+        self.hang_ms
+    }
+
+    /// Feed one receive audio sample level for anti-VOX
+    pub fn process_rx(&mut self, rx_level: f32) {
+        if rx_level > self.rx_level {
+            self.rx_level = rx_level;
+        } else {
+            self.rx_level *= 0.999;
+        }
+    }
+
+    /// Threshold in effect, raised by receive audio when anti-trip is on
+    #[must_use]
+    pub fn effective_threshold(&self) -> f32 {
+        if self.anti_trip {
+            let coupling = f32::from(self.anti_vox_gain) / f32::from(DEFAULT_ANTI_VOX_GAIN);
+            self.threshold.max(self.rx_level * coupling)
+        } else {
+            self.threshold
+        }
+    }
+
     /// Process audio sample, returns true if TX should be active
     pub fn process(&mut self, audio_level: f32) -> bool {
         if !self.enabled {
@@ -395,7 +808,7 @@ impl Vox {
             self.level *= 0.999; // Slow decay
         }
 
-        if self.level > self.threshold {
+        if self.level > self.effective_threshold() {
             self.hang_counter = self.hang_samples;
             true
         } else if self.hang_counter > 0 {
@@ -406,6 +819,19 @@ impl Vox {
         }
     }
 
+    /// Process a block of microphone samples with the matching receive
+    /// audio, returns true if TX should be active at the end of the block
+    ///
+    /// Missing receive samples (a shorter `rx`) are taken as silence.
+    pub fn process_block(&mut self, mic: &[f32], rx: &[f32]) -> bool {
+        let mut active = self.is_triggered();
+        for (n, sample) in mic.iter().enumerate() {
+            self.process_rx(rx.get(n).map_or(0.0, |s| s.abs()));
+            active = self.process(sample.abs());
+        }
+        active
+    }
+
     /// Check if VOX is triggered
     #[must_use]
     pub const fn is_triggered(&self) -> bool {
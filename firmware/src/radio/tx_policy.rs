@@ -0,0 +1,280 @@
+//! Transmit Policy
+//!
+//! [`TxPolicy`] decides whether the radio may key at its transmit
+//! frequency and mode. [`TxController`](crate::radio::transmit::TxController)
+//! consults it before every key-up and drops out of transmit as soon as
+//! it denies, so retuning out of the band mid-over stops the carrier.
+//!
+//! The rules, in order:
+//!
+//! - an external inhibit input (linear amplifier, sequencer, station
+//!   interlock) always denies
+//! - keyer practice mode always denies, so practice sending never
+//!   reaches the air
+//! - a transmit frequency outside every amateur band denies
+//! - an emission that spills past a band edge (USB just below the top,
+//!   LSB just above the bottom) denies
+//! - phone modes in a CW-only segment only warn, as the band plan is
+//!   advice rather than a licence condition
+//!
+//! The user override turns the band and band-edge denials into warnings
+//! for operators licensed outside the amateur bands or working into a
+//! dummy load; it never lifts the external inhibit or practice mode.
+//!
+//! # Example
+//!
+//! ```ignore
+//! tx.policy_mut().set_operating(state.tx_frequency(), state.mode());
+//! tx.set_ptt(ptt_pressed);
+//! tx.update(elapsed_us); // keys only if the policy allows
+//! if ptt_pressed && tx.verdict().is_denied() {
+//!     cat.tx_policy(tx.policy());
+//! }
+//! ```
+
+use crate::types::{Band, Frequency, Mode};
+
+/// Why transmit is denied or warned about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxReason {
+    /// Transmit frequency is outside every band
+    OutOfBand,
+    /// The emission extends past a band edge
+    BandEdge,
+    /// Phone mode in a CW-only segment
+    ModeSegment,
+    /// The external inhibit input is asserted
+    ExternalInhibit,
+    /// Keyer practice mode is on
+    Practice,
+}
+
+impl TxReason {
+    /// CAT code (1 out of band, 2 band edge, 3 mode segment, 4 external,
+    /// 5 practice)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::OutOfBand => 1,
+            Self::BandEdge => 2,
+            Self::ModeSegment => 3,
+            Self::ExternalInhibit => 4,
+            Self::Practice => 5,
+        }
+    }
+
+    /// Parse a CAT code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::OutOfBand),
+            2 => Some(Self::BandEdge),
+            3 => Some(Self::ModeSegment),
+            4 => Some(Self::ExternalInhibit),
+            5 => Some(Self::Practice),
+            _ => None,
+        }
+    }
+
+    /// Short label for the display
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::OutOfBand => "OUT OF BAND",
+            Self::BandEdge => "BAND EDGE",
+            Self::ModeSegment => "CW SEGMENT",
+            Self::ExternalInhibit => "TX INHIBIT",
+            Self::Practice => "PRACTICE",
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for TxReason {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.label());
+    }
+}
+
+/// Outcome of a transmit policy check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TxVerdict {
+    /// Transmit permitted
+    #[default]
+    Clear,
+    /// Transmit permitted, but the operator should know why not
+    Warning(TxReason),
+    /// Transmit refused
+    Denied(TxReason),
+}
+
+impl TxVerdict {
+    /// Check if transmit is refused
+    #[must_use]
+    pub const fn is_denied(self) -> bool {
+        matches!(self, Self::Denied(_))
+    }
+
+    /// Get the reason for a warning or denial
+    #[must_use]
+    pub const fn reason(self) -> Option<TxReason> {
+        match self {
+            Self::Clear => None,
+            Self::Warning(reason) | Self::Denied(reason) => Some(reason),
+        }
+    }
+
+    /// CAT status code (0 clear, 1 warning, 2 denied)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Clear => 0,
+            Self::Warning(_) => 1,
+            Self::Denied(_) => 2,
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for TxVerdict {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Clear => defmt::write!(f, "Clear"),
+            Self::Warning(reason) => defmt::write!(f, "Warn({})", reason),
+            Self::Denied(reason) => defmt::write!(f, "Deny({})", reason),
+        }
+    }
+}
+
+/// Start of the phone segment of a band in Hz, `None` if the band is CW
+/// and data only
+#[must_use]
+pub const fn phone_start_hz(band: Band) -> Option<u32> {
+    match band {
+        Band::M80 => Some(3_600_000),
+        Band::M40 => Some(7_125_000),
+        Band::M30 => None,
+        Band::M20 => Some(14_150_000),
+        Band::M17 => Some(18_110_000),
+        Band::M15 => Some(21_200_000),
+    }
+}
+
+/// Lowest and highest frequency an emission occupies, in Hz
+#[must_use]
+pub const fn emission_hz(freq: Frequency, mode: Mode) -> (u32, u32) {
+    let hz = freq.as_hz();
+    let width = mode.bandwidth_hz();
+    match mode {
+        Mode::Usb => (hz, hz.saturating_add(width)),
+        Mode::Lsb => (hz.saturating_sub(width), hz),
+        Mode::Cw | Mode::CwR => (hz, hz),
+        Mode::Am | Mode::Fm => (hz.saturating_sub(width / 2), hz.saturating_add(width / 2)),
+    }
+}
+
+/// Transmit rules engine
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TxPolicy {
+    /// Transmit frequency and mode (band rules apply once set)
+    operating: Option<(Frequency, Mode)>,
+    /// External inhibit input asserted
+    external_inhibit: bool,
+    /// Band and band-edge denials relaxed to warnings
+    user_override: bool,
+    /// Keyer practice mode on
+    practice: bool,
+}
+
+impl TxPolicy {
+    /// Create a policy with no operating point, no inhibit, no override
+    /// and practice off
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            operating: None,
+            external_inhibit: false,
+            user_override: false,
+            practice: false,
+        }
+    }
+
+    /// Set the transmit frequency and mode to check
+    pub fn set_operating(&mut self, freq: Frequency, mode: Mode) {
+        self.operating = Some((freq, mode));
+    }
+
+    /// Get the transmit frequency and mode being checked
+    #[must_use]
+    pub const fn operating(&self) -> Option<(Frequency, Mode)> {
+        self.operating
+    }
+
+    /// Set the external inhibit input
+    pub fn set_external_inhibit(&mut self, asserted: bool) {
+        self.external_inhibit = asserted;
+    }
+
+    /// Check if the external inhibit input is asserted
+    #[must_use]
+    pub const fn external_inhibit(&self) -> bool {
+        self.external_inhibit
+    }
+
+    /// Set the user override of the band rules
+    pub fn set_override(&mut self, enabled: bool) {
+        self.user_override = enabled;
+    }
+
+    /// Check if the user override is on
+    #[must_use]
+    pub const fn is_override(&self) -> bool {
+        self.user_override
+    }
+
+    /// Turn keyer practice mode on or off
+    pub fn set_practice(&mut self, enabled: bool) {
+        self.practice = enabled;
+    }
+
+    /// Check if keyer practice mode is on
+    #[must_use]
+    pub const fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Check the rules at the current operating point
+    #[must_use]
+    pub fn verdict(&self) -> TxVerdict {
+        if self.external_inhibit {
+            return TxVerdict::Denied(TxReason::ExternalInhibit);
+        }
+        if self.practice {
+            return TxVerdict::Denied(TxReason::Practice);
+        }
+        let Some((freq, mode)) = self.operating else {
+            return TxVerdict::Clear;
+        };
+        let band_rule = |reason| {
+            if self.user_override {
+                TxVerdict::Warning(reason)
+            } else {
+                TxVerdict::Denied(reason)
+            }
+        };
+
+        let Some(band) = Band::from_frequency(freq) else {
+            return band_rule(TxReason::OutOfBand);
+        };
+        let (low, high) = emission_hz(freq, mode);
+        if low < band.start_hz() || high > band.end_hz() {
+            return band_rule(TxReason::BandEdge);
+        }
+
+        let phone = !matches!(mode, Mode::Cw | Mode::CwR);
+        if phone && phone_start_hz(band).is_none_or(|start| low < start) {
+            return TxVerdict::Warning(TxReason::ModeSegment);
+        }
+        TxVerdict::Clear
+    }
+}
@@ -0,0 +1,233 @@
+//! Two-Tone Transmit Test
+//!
+//! For PA alignment the radio sends the standard 700 Hz + 1900 Hz
+//! two-tone signal through the SSB modulator while the receiver samples
+//! the PA output. The loopback capture is correlated against both tones
+//! and their odd-order intermodulation products:
+//!
+//! - 3rd order at 2f1 - f2 and 2f2 - f1 (-500 Hz and 3100 Hz)
+//! - 5th order at 3f1 - 2f2 and 3f2 - 2f1 (-1700 Hz and 4300 Hz)
+//!
+//! Every frequency is a multiple of 100 Hz, so a window of whole tenths
+//! of a second separates them exactly. Each order is reported as its
+//! stronger product relative to one tone (dBc); subtract 6 dB for the
+//! figure relative to PEP.
+//!
+//! The audio filter and the rest of the chain rarely pass both tones
+//! equally, so after each window the tone levels are rebalanced, keeping
+//! the peak drive, until they reach the PA at the same level.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut test = TxTest::new(SAMPLE_RATE as f32);
+//! test.start(true);
+//! radio.start_tx();
+//! // in the TX sample loop, with the receiver on the PA sampler
+//! dac.write(test.next_iq());
+//! if let Some(report) = test.process_loopback(adc.read()) {
+//!     cat.tx_test(true, Some(report));
+//! }
+//! ```
+
+#[cfg(feature = "embedded")]
+use micromath::F32Ext;
+use sdr_dsp_core::{IqSample as CoreIq, Nco, ToneGenerator};
+
+use crate::dsp::modulation::{IqSample, SsbModulator};
+
+/// Low test tone in Hz
+pub const TONE_LOW_HZ: f32 = 700.0;
+
+/// High test tone in Hz
+pub const TONE_HIGH_HZ: f32 = 1900.0;
+
+/// Default peak drive level (0.0 to 1.0)
+pub const DEFAULT_DRIVE: f32 = 0.5;
+
+/// Measurement window in milliseconds
+pub const WINDOW_MS: u16 = 100;
+
+/// Lowest level reported in dB
+pub const FLOOR_DB: f32 = -99.9;
+
+/// Largest high to low tone amplitude ratio applied when rebalancing
+const MAX_TONE_RATIO: f32 = 10.0;
+
+/// Audio offsets correlated (USB): both tones, then the 3rd and 5th
+/// order products below and above them
+const REFERENCE_HZ: [f32; 6] = [
+    TONE_LOW_HZ,
+    TONE_HIGH_HZ,
+    2.0 * TONE_LOW_HZ - TONE_HIGH_HZ,
+    2.0 * TONE_HIGH_HZ - TONE_LOW_HZ,
+    3.0 * TONE_LOW_HZ - 2.0 * TONE_HIGH_HZ,
+    3.0 * TONE_HIGH_HZ - 2.0 * TONE_LOW_HZ,
+];
+
+/// Result of one measurement window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImdReport {
+    /// Level of the stronger tone in dB relative to full scale
+    pub tone_dbfs: f32,
+    /// Stronger 3rd order product relative to one tone in dB
+    pub imd3_dbc: f32,
+    /// Stronger 5th order product relative to one tone in dB
+    pub imd5_dbc: f32,
+    /// Level of the high tone relative to the low tone in dB
+    pub balance_db: f32,
+}
+
+/// Two-tone test signal generator and IMD meter
+pub struct TxTest {
+    /// Test running
+    active: bool,
+    /// Peak drive level
+    drive: f32,
+    /// High tone amplitude relative to the low tone
+    tone_ratio: f32,
+    /// Test tones
+    tones: ToneGenerator<2>,
+    /// Modulator the tones are sent through
+    modulator: SsbModulator,
+    /// Correlators, in [`REFERENCE_HZ`] order
+    references: [Nco; 6],
+    sums: [CoreIq; 6],
+    /// Samples per measurement window
+    window: u32,
+    /// Samples in the current window
+    count: u32,
+    /// Last completed measurement
+    report: Option<ImdReport>,
+}
+
+impl TxTest {
+    /// Create an idle test at `sample_rate` Hz
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            active: false,
+            drive: DEFAULT_DRIVE,
+            tone_ratio: 1.0,
+            tones: ToneGenerator::two_tone(sample_rate, TONE_LOW_HZ, TONE_HIGH_HZ, DEFAULT_DRIVE),
+            modulator: SsbModulator::new(sample_rate),
+            references: REFERENCE_HZ.map(|hz| Nco::new(sample_rate, hz)),
+            sums: [CoreIq::ZERO; 6],
+            window: ((sample_rate * f32::from(WINDOW_MS) / 1000.0) as u32).max(1),
+            count: 0,
+            report: None,
+        }
+    }
+
+    /// Start the test on the upper (or lower) sideband, discarding any
+    /// earlier measurement
+    pub fn start(&mut self, usb: bool) {
+        self.modulator.reset();
+        self.modulator.set_usb(usb);
+        self.tone_ratio = 1.0;
+        self.set_tone_levels();
+        self.tones.reset();
+        // The products mirror with the tones on LSB
+        let sign = if usb { 1.0 } else { -1.0 };
+        for (reference, hz) in self.references.iter_mut().zip(REFERENCE_HZ) {
+            reference.set_frequency(sign * hz);
+            reference.reset();
+        }
+        self.restart();
+        self.report = None;
+        self.active = true;
+    }
+
+    /// Stop the test, keeping the last measurement
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// Check if the test is running
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Set the peak drive level (0.0 to 1.0), shared by the two tones
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+        self.set_tone_levels();
+    }
+
+    /// Get the peak drive level
+    #[must_use]
+    pub const fn drive(&self) -> f32 {
+        self.drive
+    }
+
+    /// Generate the next transmit IQ sample (silence when stopped)
+    pub fn next_iq(&mut self) -> IqSample {
+        if !self.active {
+            return IqSample::default();
+        }
+        let audio = self.tones.next_sample();
+        self.modulator.process(audio)
+    }
+
+    /// Add a loopback IQ sample of the transmitted signal
+    ///
+    /// Returns a report each time a measurement window completes.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn process_loopback(&mut self, iq: IqSample) -> Option<ImdReport> {
+        if !self.active {
+            return None;
+        }
+        let sample = CoreIq::new(iq.i, iq.q);
+        for (reference, sum) in self.references.iter_mut().zip(self.sums.iter_mut()) {
+            *sum = *sum + reference.mix(sample);
+        }
+        self.count += 1;
+        if self.count < self.window {
+            return None;
+        }
+
+        let scale = 1.0 / self.count as f32;
+        let level = |sum: &CoreIq| sum.scale(scale).magnitude_squared();
+        let [low, high, imd3_low, imd3_high, imd5_low, imd5_high] = self.sums.each_ref().map(level);
+        self.restart();
+
+        let db = |ratio: f32| (10.0 * ratio.max(1e-20).log10()).max(FLOOR_DB);
+        let tone = 0.5 * (low + high);
+        let report = ImdReport {
+            tone_dbfs: db(low.max(high)),
+            imd3_dbc: db(imd3_low.max(imd3_high) / tone.max(1e-20)),
+            imd5_dbc: db(imd5_low.max(imd5_high) / tone.max(1e-20)),
+            balance_db: db(high / low.max(1e-20)).min(-FLOOR_DB),
+        };
+        self.report = Some(report);
+
+        // Level the tones for the next window
+        if low > 1e-20 && high > 1e-20 {
+            self.tone_ratio = (self.tone_ratio * (low / high).sqrt())
+                .clamp(1.0 / MAX_TONE_RATIO, MAX_TONE_RATIO);
+            self.set_tone_levels();
+        }
+        Some(report)
+    }
+
+    /// Get the last completed measurement
+    #[must_use]
+    pub const fn report(&self) -> Option<ImdReport> {
+        self.report
+    }
+
+    /// Share the drive between the tones at the current ratio
+    fn set_tone_levels(&mut self) {
+        let low = self.drive / (1.0 + self.tone_ratio);
+        self.tones.set_amplitude(0, low);
+        self.tones.set_amplitude(1, low * self.tone_ratio);
+    }
+
+    /// Discard the current partial window
+    fn restart(&mut self) {
+        self.sums = [CoreIq::ZERO; 6];
+        self.count = 0;
+    }
+}
@@ -0,0 +1,402 @@
+//! Voice Keyer
+//!
+//! Digital voice recorder for calling CQ on phone. Up to [`VOICE_SLOTS`]
+//! short messages are recorded from the microphone into the file store
+//! (see [`crate::storage`], on SPI flash or any other [`BlockDevice`],
+//! RAM included) as `VOICE1.PCM` to `VOICE4.PCM`, 16-bit little-endian
+//! at the audio rate, and played back into the transmit audio chain in
+//! place of the microphone.
+//!
+//! Playback asks for PTT while the message sounds. With a repeat interval
+//! set, PTT is released at the end of the message, the receiver is
+//! heard for the interval and the message is sent again, until playback
+//! is stopped (the operator's PTT or a CAT stop).
+//!
+//! Re-recording a slot deletes its old message first. The file store
+//! reuses the space only if that was the last file written, so a
+//! recorded-over slot may leave a hole until the store is formatted;
+//! [`VoiceKeyer::remaining_ms`] reports the recording time actually
+//! left.
+//!
+//! # Example
+//!
+//! ```ignore
+//! voice.load(&store);
+//! // button or CAT `PB1;`
+//! voice.play(0);
+//! // every TX audio block, before the speech processor
+//! voice.fill(&mut store, &mut mic)?;
+//! tx.set_ptt(ptt_pressed || voice.wants_ptt());
+//! ```
+
+use heapless::String;
+
+use crate::storage::{BlockDevice, FileKind, FileStore, StorageError, StorageResult, MAX_NAME_LEN};
+
+/// Number of message slots
+pub const VOICE_SLOTS: usize = 4;
+
+/// Longest message in milliseconds
+pub const MAX_MESSAGE_MS: u32 = 30_000;
+
+/// Longest repeat interval in seconds
+pub const MAX_REPEAT_S: u16 = 999;
+
+/// Samples moved to or from storage at a time
+const CHUNK_SAMPLES: usize = 64;
+
+/// Bytes per stored sample
+const SAMPLE_BYTES: u32 = 2;
+
+/// What the voice keyer is doing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum VoiceState {
+    /// Nothing recording or playing
+    #[default]
+    Idle,
+    /// Recording into a slot (0-based)
+    Recording(u8),
+    /// Playing a slot (0-based) on the air
+    Playing(u8),
+    /// Listening before a slot (0-based) repeats
+    Waiting(u8),
+}
+
+impl VoiceState {
+    /// CAT code (0 idle, 1 recording, 2 playing, 3 waiting to repeat)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Idle => 0,
+            Self::Recording(_) => 1,
+            Self::Playing(_) => 2,
+            Self::Waiting(_) => 3,
+        }
+    }
+
+    /// Get the slot recording, playing or waiting (0-based)
+    #[must_use]
+    pub const fn slot(self) -> Option<u8> {
+        match self {
+            Self::Idle => None,
+            Self::Recording(slot) | Self::Playing(slot) | Self::Waiting(slot) => Some(slot),
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for VoiceState {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Idle => defmt::write!(f, "Idle"),
+            Self::Recording(slot) => defmt::write!(f, "Rec({})", slot),
+            Self::Playing(slot) => defmt::write!(f, "Play({})", slot),
+            Self::Waiting(slot) => defmt::write!(f, "Wait({})", slot),
+        }
+    }
+}
+
+/// File name of a slot's message (`VOICE1.PCM` for slot 0)
+#[must_use]
+pub fn slot_file_name(slot: u8) -> String<MAX_NAME_LEN> {
+    let mut name = String::new();
+    let _ = core::fmt::write(&mut name, format_args!("VOICE{}.PCM", u32::from(slot) + 1));
+    name
+}
+
+/// Voice message recorder and player
+#[derive(Clone, Debug)]
+pub struct VoiceKeyer {
+    /// Audio sample rate in Hz
+    sample_rate: u32,
+    /// Current activity
+    state: VoiceState,
+    /// File identifier of each slot's message
+    files: [Option<u8>; VOICE_SLOTS],
+    /// Length of each slot's message in samples
+    lengths: [u32; VOICE_SLOTS],
+    /// Samples recorded or played so far
+    position: u32,
+    /// Pause between repeats in seconds (0 = play once)
+    repeat_s: u16,
+    /// Samples left before the next repeat
+    wait_samples: u32,
+}
+
+impl VoiceKeyer {
+    /// Create an idle voice keyer for audio at `sample_rate` Hz with no messages
+    #[must_use]
+    pub const fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            state: VoiceState::Idle,
+            files: [None; VOICE_SLOTS],
+            lengths: [0; VOICE_SLOTS],
+            position: 0,
+            repeat_s: 0,
+            wait_samples: 0,
+        }
+    }
+
+    /// Find the recorded messages in a mounted file store
+    pub fn load<D: BlockDevice>(&mut self, store: &FileStore<D>) {
+        #[allow(clippy::cast_possible_truncation)]
+        for slot in 0..VOICE_SLOTS as u8 {
+            let entry = store
+                .find(&slot_file_name(slot))
+                .filter(|entry| entry.kind == FileKind::Voice);
+            self.files[usize::from(slot)] = entry.map(|entry| entry.id);
+            self.lengths[usize::from(slot)] = entry.map_or(0, |entry| entry.length / SAMPLE_BYTES);
+        }
+    }
+
+    /// Get the current activity
+    #[must_use]
+    pub const fn state(&self) -> VoiceState {
+        self.state
+    }
+
+    /// Check if a slot holds a message
+    #[must_use]
+    pub fn has_message(&self, slot: u8) -> bool {
+        self.files
+            .get(usize::from(slot))
+            .is_some_and(Option::is_some)
+    }
+
+    /// Get the length of a slot's message in milliseconds (`None` if empty)
+    #[must_use]
+    pub fn message_ms(&self, slot: u8) -> Option<u32> {
+        self.has_message(slot)
+            .then(|| self.samples_to_ms(self.lengths[usize::from(slot)]))
+    }
+
+    /// Recording time left in the file store in milliseconds
+    #[must_use]
+    pub fn remaining_ms<D: BlockDevice>(&self, store: &FileStore<D>) -> u32 {
+        self.samples_to_ms(store.free_bytes() / SAMPLE_BYTES)
+    }
+
+    /// Set the pause between repeats in seconds (0 plays once)
+    pub fn set_repeat_s(&mut self, seconds: u16) {
+        self.repeat_s = seconds.min(MAX_REPEAT_S);
+    }
+
+    /// Get the pause between repeats in seconds
+    #[must_use]
+    pub const fn repeat_s(&self) -> u16 {
+        self.repeat_s
+    }
+
+    /// Start recording into a slot, replacing its message
+    ///
+    /// # Errors
+    /// Fails with `NotFound` for a slot out of range, `Busy` while
+    /// recording or playing, or the file store's error
+    pub fn start_recording<D: BlockDevice>(
+        &mut self,
+        slot: u8,
+        store: &mut FileStore<D>,
+    ) -> StorageResult<(), D::Error> {
+        if usize::from(slot) >= VOICE_SLOTS {
+            return Err(StorageError::NotFound);
+        }
+        if self.state != VoiceState::Idle {
+            return Err(StorageError::Busy);
+        }
+        self.delete(slot, store)?;
+        store.create(&slot_file_name(slot), FileKind::Voice)?;
+        self.state = VoiceState::Recording(slot);
+        self.position = 0;
+        Ok(())
+    }
+
+    /// Record a block of microphone audio
+    ///
+    /// Ignored unless recording. The message is closed once it reaches
+    /// [`MAX_MESSAGE_MS`]; if the store fills up first, what was recorded
+    /// is kept and the store's `Full` error returned.
+    ///
+    /// # Errors
+    /// Returns the file store's error
+    pub fn record<D: BlockDevice>(
+        &mut self,
+        store: &mut FileStore<D>,
+        samples: &[f32],
+    ) -> StorageResult<(), D::Error> {
+        if !matches!(self.state, VoiceState::Recording(_)) {
+            return Ok(());
+        }
+        let max_samples = self.ms_to_samples(MAX_MESSAGE_MS);
+        let room = (max_samples - self.position) as usize;
+        let samples = &samples[..samples.len().min(room)];
+
+        let mut bytes = [0u8; CHUNK_SAMPLES * SAMPLE_BYTES as usize];
+        for chunk in samples.chunks(CHUNK_SAMPLES) {
+            for (pair, &sample) in bytes.chunks_exact_mut(2).zip(chunk) {
+                #[allow(clippy::cast_possible_truncation)]
+                let pcm = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+                pair.copy_from_slice(&pcm.to_le_bytes());
+            }
+            if let Err(err) = store.append(&bytes[..chunk.len() * 2]) {
+                self.stop_recording(store)?;
+                return Err(err);
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.position += chunk.len() as u32;
+            }
+        }
+
+        if self.position >= max_samples {
+            self.stop_recording(store)?;
+        }
+        Ok(())
+    }
+
+    /// Finish recording, returning the message length in milliseconds
+    ///
+    /// Returns `None` if nothing was being recorded.
+    ///
+    /// # Errors
+    /// Returns the file store's error
+    pub fn stop_recording<D: BlockDevice>(
+        &mut self,
+        store: &mut FileStore<D>,
+    ) -> StorageResult<Option<u32>, D::Error> {
+        let VoiceState::Recording(slot) = self.state else {
+            return Ok(None);
+        };
+        self.state = VoiceState::Idle;
+        let entry = store.close()?;
+        self.files[usize::from(slot)] = Some(entry.id);
+        self.lengths[usize::from(slot)] = entry.length / SAMPLE_BYTES;
+        Ok(self.message_ms(slot))
+    }
+
+    /// Delete a slot's message
+    ///
+    /// # Errors
+    /// Fails with `Busy` while that slot is in use, or the file store's error
+    pub fn delete<D: BlockDevice>(
+        &mut self,
+        slot: u8,
+        store: &mut FileStore<D>,
+    ) -> StorageResult<(), D::Error> {
+        if self.state.slot() == Some(slot) {
+            return Err(StorageError::Busy);
+        }
+        if let Some(id) = self.files.get_mut(usize::from(slot)).and_then(Option::take) {
+            self.lengths[usize::from(slot)] = 0;
+            store.delete(id)?;
+        }
+        Ok(())
+    }
+
+    /// Start playing a slot from the beginning
+    ///
+    /// Returns `false` if the slot is empty or a message is being recorded.
+    pub fn play(&mut self, slot: u8) -> bool {
+        if !self.has_message(slot) || matches!(self.state, VoiceState::Recording(_)) {
+            return false;
+        }
+        self.state = VoiceState::Playing(slot);
+        self.position = 0;
+        true
+    }
+
+    /// Stop playback and any pending repeat
+    pub fn stop(&mut self) {
+        if matches!(self.state, VoiceState::Playing(_) | VoiceState::Waiting(_)) {
+            self.state = VoiceState::Idle;
+        }
+    }
+
+    /// Check if playback needs the transmitter keyed
+    #[must_use]
+    pub const fn wants_ptt(&self) -> bool {
+        matches!(self.state, VoiceState::Playing(_))
+    }
+
+    /// Replace a block of microphone audio with the message being played
+    ///
+    /// Call for every transmit audio block; it also times the pause
+    /// between repeats. The block is left alone unless a message is
+    /// playing, and filled with silence after the message ends.
+    ///
+    /// # Errors
+    /// Returns the file store's error (playback stops)
+    pub fn fill<D: BlockDevice>(
+        &mut self,
+        store: &mut FileStore<D>,
+        out: &mut [f32],
+    ) -> StorageResult<(), D::Error> {
+        match self.state {
+            VoiceState::Waiting(slot) => {
+                #[allow(clippy::cast_possible_truncation)]
+                let elapsed = out.len() as u32;
+                self.wait_samples = self.wait_samples.saturating_sub(elapsed);
+                if self.wait_samples == 0 {
+                    self.play(slot);
+                }
+                Ok(())
+            }
+            VoiceState::Playing(slot) => self.play_block(slot, store, out),
+            VoiceState::Idle | VoiceState::Recording(_) => Ok(()),
+        }
+    }
+
+    /// Read the next block of a message, moving on at its end
+    fn play_block<D: BlockDevice>(
+        &mut self,
+        slot: u8,
+        store: &mut FileStore<D>,
+        out: &mut [f32],
+    ) -> StorageResult<(), D::Error> {
+        let Some(id) = self.files[usize::from(slot)] else {
+            self.state = VoiceState::Idle;
+            return Ok(());
+        };
+        let mut bytes = [0u8; CHUNK_SAMPLES * SAMPLE_BYTES as usize];
+        for chunk in out.chunks_mut(CHUNK_SAMPLES) {
+            let offset = self.position * SAMPLE_BYTES;
+            let read = match store.read(id, offset, &mut bytes[..chunk.len() * 2]) {
+                Ok(read) => read / 2,
+                Err(err) => {
+                    self.state = VoiceState::Idle;
+                    return Err(err);
+                }
+            };
+            for (sample, pair) in chunk.iter_mut().zip(bytes.chunks_exact(2)).take(read) {
+                *sample = f32::from(i16::from_le_bytes([pair[0], pair[1]])) / f32::from(i16::MAX);
+            }
+            chunk[read..].fill(0.0);
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.position += read as u32;
+            }
+        }
+
+        if self.position >= self.lengths[usize::from(slot)] {
+            if self.repeat_s > 0 {
+                self.state = VoiceState::Waiting(slot);
+                self.wait_samples = u32::from(self.repeat_s) * self.sample_rate;
+            } else {
+                self.state = VoiceState::Idle;
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a sample count to milliseconds
+    #[allow(clippy::cast_possible_truncation)]
+    fn samples_to_ms(&self, samples: u32) -> u32 {
+        (u64::from(samples) * 1000 / u64::from(self.sample_rate.max(1))) as u32
+    }
+
+    /// Convert milliseconds to a sample count
+    #[allow(clippy::cast_possible_truncation)]
+    fn ms_to_samples(&self, ms: u32) -> u32 {
+        (u64::from(ms) * u64::from(self.sample_rate) / 1000) as u32
+    }
+}
@@ -0,0 +1,386 @@
+//! `WinKeyer` Emulation
+//!
+//! Implements the host side of the K1EL `WinKeyer` 2 serial protocol so
+//! contest loggers (N1MM+, TR4W, Win-Test) can send CW through the
+//! [`Keyer`] as if a `WinKeyer` were attached. The protocol is a byte
+//! stream: `0x00` introduces an admin command, `0x01`-`0x1F` are
+//! commands with a fixed number of parameter bytes, and printable ASCII
+//! is text to send.
+//!
+//! The parser only consumes bytes; [`WinKeyer::apply`] performs the
+//! command on the keyer and [`WinKeyer::poll`] yields the unsolicited
+//! status and echo bytes the host expects.
+
+use heapless::Vec;
+
+use super::keyer::{Keyer, KeyerMode, PaddleState, TEXT_QUEUE_LEN};
+
+/// Firmware version reported on host open (`WinKeyer` 2, revision 23)
+pub const WK_VERSION: u8 = 23;
+
+/// Status byte: fixed tag bits
+const STATUS_TAG: u8 = 0xC0;
+
+/// Status byte: key is down
+const STATUS_KEYDOWN: u8 = 0x08;
+
+/// Status byte: keyer busy sending
+const STATUS_BUSY: u8 = 0x04;
+
+/// Status byte: paddle break-in active
+const STATUS_BREAKIN: u8 = 0x02;
+
+/// Status byte: input buffer more than two thirds full
+const STATUS_XOFF: u8 = 0x01;
+
+/// Speed pot reply tag (no pot fitted, always reports zero)
+const SPEED_POT_TAG: u8 = 0x80;
+
+/// Mode register: swap paddles
+const MODE_SWAP: u8 = 0x08;
+
+/// Mode register: echo sent characters to the host
+const MODE_SERIAL_ECHO: u8 = 0x04;
+
+/// Admin subcommands
+mod admin {
+    pub const RESET: u8 = 0x01;
+    pub const HOST_OPEN: u8 = 0x02;
+    pub const HOST_CLOSE: u8 = 0x03;
+    pub const ECHO: u8 = 0x04;
+}
+
+/// Command bytes
+mod cmd {
+    pub const ADMIN: u8 = 0x00;
+    pub const SIDETONE: u8 = 0x01;
+    pub const SPEED: u8 = 0x02;
+    pub const WEIGHT: u8 = 0x03;
+    pub const PAUSE: u8 = 0x06;
+    pub const GET_POT: u8 = 0x07;
+    pub const BACKSPACE: u8 = 0x08;
+    pub const CLEAR: u8 = 0x0A;
+    pub const KEY_IMMEDIATE: u8 = 0x0B;
+    pub const FARNSWORTH: u8 = 0x0D;
+    pub const MODE: u8 = 0x0E;
+    pub const FIRST_EXTENSION: u8 = 0x10;
+    pub const SOFT_PADDLE: u8 = 0x14;
+    pub const STATUS: u8 = 0x15;
+    pub const POINTER: u8 = 0x16;
+    pub const RATIO: u8 = 0x17;
+    pub const MERGE: u8 = 0x1B;
+    pub const BUFFERED_SPEED: u8 = 0x1C;
+    pub const CANCEL_SPEED: u8 = 0x1E;
+}
+
+/// Number of parameter bytes following a command byte
+const fn param_count(command: u8) -> usize {
+    match command {
+        0x04 | 0x1B => 2,
+        0x05 => 3,
+        0x0F => 15,
+        0x07 | 0x08 | 0x0A | 0x13 | 0x15 | 0x1E | 0x1F => 0,
+        _ => 1,
+    }
+}
+
+/// Decoded `WinKeyer` command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WkCommand {
+    /// Reset to power-on defaults
+    Reset,
+    /// Host open (reply with version)
+    HostOpen,
+    /// Host close
+    HostClose,
+    /// Echo test (reply with the byte)
+    Echo(u8),
+    /// Sidetone divisor (frequency = 4000 / n Hz)
+    Sidetone(u8),
+    /// Speed in WPM (0 = use speed pot)
+    SetWpm(u8),
+    /// Weighting (10-90, 50 = standard)
+    SetWeight(u8),
+    /// Pause or resume buffered text
+    Pause(bool),
+    /// Read speed pot
+    GetSpeedPot,
+    /// Remove last buffered character
+    Backspace,
+    /// Abort sending and clear the buffer
+    ClearBuffer,
+    /// Key down (tune) or up
+    KeyImmediate(bool),
+    /// Farnsworth effective speed in WPM (0 = off)
+    SetFarnsworth(u8),
+    /// `WinKeyer` mode register
+    SetMode(u8),
+    /// First element extension in milliseconds
+    FirstExtension(u8),
+    /// Software paddle state
+    SoftwarePaddle(PaddleState),
+    /// Request status byte
+    RequestStatus,
+    /// Dit/dah ratio (33-66, 50 = 1:3)
+    SetRatio(u8),
+    /// Merge two characters into a prosign
+    Merge(char, char),
+    /// Change speed for buffered text
+    BufferedSpeed(u8),
+    /// Cancel buffered speed change
+    CancelBufferedSpeed,
+    /// Character to send
+    Text(char),
+    /// Recognised but not supported (command byte)
+    Ignored(u8),
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for WkCommand {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::HostOpen => defmt::write!(f, "HostOpen"),
+            Self::HostClose => defmt::write!(f, "HostClose"),
+            Self::SetWpm(wpm) => defmt::write!(f, "SetWpm({})", wpm),
+            Self::Text(c) => defmt::write!(f, "Text({})", c),
+            Self::Ignored(c) => defmt::write!(f, "Ignored(0x{:02X})", c),
+            _ => defmt::write!(f, "WK(...)"),
+        }
+    }
+}
+
+/// `WinKeyer` protocol emulator
+#[derive(Clone, Debug)]
+pub struct WinKeyer {
+    /// Command byte awaiting parameters
+    pending: Option<u8>,
+    /// Parameters received so far
+    params: Vec<u8, 15>,
+    /// Host has opened the session
+    host_open: bool,
+    /// Mode register (`0x0E` command)
+    mode: u8,
+    /// Software paddle state
+    soft_paddle: PaddleState,
+    /// Last status byte sent
+    last_status: u8,
+}
+
+impl WinKeyer {
+    /// Create a new emulator (host closed)
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            pending: None,
+            params: Vec::new(),
+            host_open: false,
+            mode: 0,
+            soft_paddle: PaddleState::new(false, false),
+            last_status: STATUS_TAG,
+        }
+    }
+
+    /// Feed a byte received from the host
+    pub fn feed(&mut self, byte: u8) -> Option<WkCommand> {
+        if self.pending.is_some() {
+            let _ = self.params.push(byte);
+            return self.complete();
+        }
+
+        match byte {
+            0x20..=0x7F => Some(WkCommand::Text(char::from(byte))),
+            0x80..=0xFF => None,
+            _ => {
+                self.pending = Some(byte);
+                self.params.clear();
+                self.complete()
+            }
+        }
+    }
+
+    /// Decode the pending command if all its parameters have arrived
+    fn complete(&mut self) -> Option<WkCommand> {
+        let command = self.pending?;
+        let needed = match (command, self.params.first()) {
+            // Echo test and pointer subcommand 3 carry an extra byte
+            (cmd::ADMIN, Some(&admin::ECHO)) | (cmd::POINTER, Some(&3)) => 2,
+            (cmd::ADMIN, _) => 1,
+            _ => param_count(command),
+        };
+        if self.params.len() < needed {
+            return None;
+        }
+        self.pending = None;
+
+        let p = |i: usize| self.params.get(i).copied().unwrap_or(0);
+        let decoded = match command {
+            cmd::ADMIN => match p(0) {
+                admin::RESET => WkCommand::Reset,
+                admin::HOST_OPEN => WkCommand::HostOpen,
+                admin::HOST_CLOSE => WkCommand::HostClose,
+                admin::ECHO => WkCommand::Echo(p(1)),
+                _ => WkCommand::Ignored(command),
+            },
+            cmd::SIDETONE => WkCommand::Sidetone(p(0)),
+            cmd::SPEED => WkCommand::SetWpm(p(0)),
+            cmd::WEIGHT => WkCommand::SetWeight(p(0)),
+            cmd::PAUSE => WkCommand::Pause(p(0) != 0),
+            cmd::GET_POT => WkCommand::GetSpeedPot,
+            cmd::BACKSPACE => WkCommand::Backspace,
+            cmd::CLEAR => WkCommand::ClearBuffer,
+            cmd::KEY_IMMEDIATE => WkCommand::KeyImmediate(p(0) != 0),
+            cmd::FARNSWORTH => WkCommand::SetFarnsworth(p(0)),
+            cmd::MODE => WkCommand::SetMode(p(0)),
+            cmd::FIRST_EXTENSION => WkCommand::FirstExtension(p(0)),
+            cmd::SOFT_PADDLE => {
+                WkCommand::SoftwarePaddle(PaddleState::new(p(0) & 0x02 != 0, p(0) & 0x01 != 0))
+            }
+            cmd::STATUS => WkCommand::RequestStatus,
+            cmd::RATIO => WkCommand::SetRatio(p(0)),
+            cmd::MERGE => WkCommand::Merge(char::from(p(0)), char::from(p(1))),
+            cmd::BUFFERED_SPEED => WkCommand::BufferedSpeed(p(0)),
+            cmd::CANCEL_SPEED => WkCommand::CancelBufferedSpeed,
+            _ => WkCommand::Ignored(command),
+        };
+        self.params.clear();
+        Some(decoded)
+    }
+
+    /// Check if the host has opened the session
+    #[must_use]
+    pub const fn is_host_open(&self) -> bool {
+        self.host_open
+    }
+
+    /// Perform a command on the keyer, returns an immediate reply byte
+    ///
+    /// Only admin commands are accepted while the host is closed.
+    /// Buffered speed changes take effect immediately.
+    pub fn apply(&mut self, command: WkCommand, keyer: &mut Keyer) -> Option<u8> {
+        match command {
+            WkCommand::HostOpen => {
+                self.host_open = true;
+                self.last_status = STATUS_TAG;
+                return Some(WK_VERSION);
+            }
+            WkCommand::HostClose => {
+                self.host_open = false;
+                keyer.clear_text();
+                keyer.set_tune(false);
+                return None;
+            }
+            WkCommand::Echo(byte) => return Some(byte),
+            WkCommand::Reset => {
+                *self = Self::new();
+                keyer.reset();
+                return None;
+            }
+            _ if !self.host_open => return None,
+            _ => {}
+        }
+
+        match command {
+            WkCommand::Sidetone(n) => {
+                let n = u16::from(n & 0x0F).max(1);
+                keyer.set_sidetone(4000 / n);
+            }
+            WkCommand::SetWpm(wpm) | WkCommand::BufferedSpeed(wpm) if wpm > 0 => keyer.set_wpm(wpm),
+            WkCommand::SetWeight(weight) => keyer.set_weight(weight),
+            WkCommand::Pause(paused) => keyer.set_text_paused(paused),
+            WkCommand::GetSpeedPot => return Some(SPEED_POT_TAG),
+            WkCommand::Backspace => {
+                let _ = keyer.backspace();
+            }
+            WkCommand::ClearBuffer => keyer.clear_text(),
+            WkCommand::KeyImmediate(on) => keyer.set_tune(on),
+            WkCommand::SetFarnsworth(wpm) => keyer.set_farnsworth(wpm),
+            WkCommand::SetMode(mode) => {
+                self.mode = mode;
+                keyer.set_mode(match (mode >> 4) & 0x03 {
+                    0 => KeyerMode::IambicB,
+                    1 => KeyerMode::IambicA,
+                    2 => KeyerMode::Ultimatic,
+                    _ => KeyerMode::Bug,
+                });
+            }
+            WkCommand::FirstExtension(ms) => keyer.set_first_extension_ms(ms),
+            WkCommand::SetRatio(ratio) => {
+                // Dah = 3 * ratio / 50 dits, in tenths
+                let tenths = u16::from(ratio) * 3 / 5;
+                keyer.set_dah_ratio(u8::try_from(tenths).unwrap_or(u8::MAX));
+            }
+            WkCommand::SoftwarePaddle(paddle) => self.soft_paddle = paddle,
+            WkCommand::RequestStatus => {
+                let status = self.status(keyer);
+                self.last_status = status;
+                return Some(status);
+            }
+            WkCommand::Merge(a, b) => {
+                let _ = ['<', a, b, '>'].iter().all(|&c| keyer.queue_char(c));
+            }
+            WkCommand::Text(c) => {
+                let _ = keyer.queue_char(c);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Combine hardware paddles with the software paddle and swap setting
+    #[must_use]
+    pub const fn paddles(&self, hardware: PaddleState) -> PaddleState {
+        let (dit, dah) = if self.mode & MODE_SWAP != 0 {
+            (hardware.dah, hardware.dit)
+        } else {
+            (hardware.dit, hardware.dah)
+        };
+        PaddleState::new(dit || self.soft_paddle.dit, dah || self.soft_paddle.dah)
+    }
+
+    /// Build the status byte
+    #[must_use]
+    pub fn status(&self, keyer: &Keyer) -> u8 {
+        let mut status = STATUS_TAG;
+        if !keyer.is_idle() {
+            status |= STATUS_BUSY;
+            if !keyer.is_sending_text() && !keyer.is_tuning() {
+                status |= STATUS_BREAKIN;
+            }
+        }
+        if keyer.is_key_down() {
+            status |= STATUS_KEYDOWN;
+        }
+        if keyer.text_pending() > TEXT_QUEUE_LEN * 2 / 3 {
+            status |= STATUS_XOFF;
+        }
+        status
+    }
+
+    /// Next unsolicited byte for the host (status change or echo)
+    ///
+    /// Call repeatedly until it returns `None`. Key-down changes are not
+    /// reported on their own, matching real hardware.
+    pub fn poll(&mut self, keyer: &mut Keyer) -> Option<u8> {
+        if !self.host_open {
+            return None;
+        }
+
+        let status = self.status(keyer) & !STATUS_KEYDOWN;
+        if status != self.last_status & !STATUS_KEYDOWN {
+            self.last_status = status;
+            return Some(status);
+        }
+
+        let sent = keyer.take_sent()?;
+        if self.mode & MODE_SERIAL_ECHO == 0 {
+            return None;
+        }
+        u8::try_from(sent).ok()
+    }
+}
+
+impl Default for WinKeyer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,269 @@
+//! Power-On Self Test
+//!
+//! Component-by-component checks run once at boot, before the radio is
+//! allowed to transmit. Each check records a pass/fail result and the raw
+//! value it was judged on, so a fault can be pinned to one part from the
+//! display, the defmt log or CAT (`ZP;`) without a bench setup.
+//!
+//! The hardware is exercised by the boot code; this module only holds the
+//! pass/fail criteria and the report.
+
+use crate::config::{POST_ADC_OFFSET_TOLERANCE, POST_PA_IDLE_CURRENT_MAX_MA};
+
+/// `Si5351A` device status: system initialisation in progress
+const SI5351_SYS_INIT: u8 = 0x80;
+
+/// `Si5351A` device status: PLL A loss of lock
+const SI5351_LOL_A: u8 = 0x20;
+
+/// `Si5351A` device status: crystal loss of signal
+const SI5351_LOS_XTAL: u8 = 0x08;
+
+/// ADC mid-scale reading (12-bit)
+const ADC_MID_SCALE: u16 = 2048;
+
+/// Component under test
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostItem {
+    /// `Si5351A` answers on I2C
+    Si5351,
+    /// `Si5351A` PLL A locked to the crystal
+    PllLock,
+    /// Display answers (OLED on I2C; an ST7735 is not probed) and accepts
+    /// its init sequence
+    Display,
+    /// I channel ADC idles near mid-scale
+    AdcI,
+    /// Q channel ADC idles near mid-scale
+    AdcQ,
+    /// PA draws no current with the drive off
+    PaCurrent,
+}
+
+impl PostItem {
+    /// Number of checks
+    pub const COUNT: usize = 6;
+
+    /// All checks in execution order
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Si5351,
+        Self::PllLock,
+        Self::Display,
+        Self::AdcI,
+        Self::AdcQ,
+        Self::PaCurrent,
+    ];
+
+    /// Position in the report
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Si5351 => 0,
+            Self::PllLock => 1,
+            Self::Display => 2,
+            Self::AdcI => 3,
+            Self::AdcQ => 4,
+            Self::PaCurrent => 5,
+        }
+    }
+
+    /// Get check by report position
+    #[must_use]
+    pub const fn from_index(index: usize) -> Option<Self> {
+        if index < Self::COUNT {
+            Some(Self::ALL[index])
+        } else {
+            None
+        }
+    }
+
+    /// Short label for the display (at most 6 characters)
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Si5351 => "SI5351",
+            Self::PllLock => "PLL",
+            Self::Display => "OLED",
+            Self::AdcI => "ADC I",
+            Self::AdcQ => "ADC Q",
+            Self::PaCurrent => "PA I",
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for PostItem {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.label());
+    }
+}
+
+/// Outcome of a single check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PostResult {
+    /// Check not run (or skipped because a prerequisite failed)
+    #[default]
+    NotRun,
+    /// Check passed
+    Pass,
+    /// Check failed
+    Fail,
+}
+
+impl PostResult {
+    /// Create from a boolean outcome
+    #[must_use]
+    pub const fn from_bool(ok: bool) -> Self {
+        if ok {
+            Self::Pass
+        } else {
+            Self::Fail
+        }
+    }
+
+    /// Single character code for CAT (`P`, `F` or `-`)
+    #[must_use]
+    pub const fn as_char(self) -> char {
+        match self {
+            Self::NotRun => '-',
+            Self::Pass => 'P',
+            Self::Fail => 'F',
+        }
+    }
+
+    /// Short text for the display
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NotRun => "--",
+            Self::Pass => "OK",
+            Self::Fail => "ERR",
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for PostResult {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::NotRun => defmt::write!(f, "not run"),
+            Self::Pass => defmt::write!(f, "PASS"),
+            Self::Fail => defmt::write!(f, "FAIL"),
+        }
+    }
+}
+
+/// Check the `Si5351A` device status register for PLL lock
+#[must_use]
+pub const fn check_pll_status(status: u8) -> PostResult {
+    PostResult::from_bool(status & (SI5351_SYS_INIT | SI5351_LOL_A | SI5351_LOS_XTAL) == 0)
+}
+
+/// Check an averaged idle ADC reading against mid-scale
+#[must_use]
+pub const fn check_adc_offset(raw_mean: u16) -> PostResult {
+    PostResult::from_bool(raw_mean.abs_diff(ADC_MID_SCALE) <= POST_ADC_OFFSET_TOLERANCE)
+}
+
+/// Check idle PA supply current in milliamps
+#[must_use]
+pub const fn check_pa_current(milliamps: u16) -> PostResult {
+    PostResult::from_bool(milliamps <= POST_PA_IDLE_CURRENT_MAX_MA)
+}
+
+/// Self test report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PostReport {
+    /// Result per check (indexed by [`PostItem::index`])
+    results: [PostResult; PostItem::COUNT],
+    /// Raw value each check was judged on
+    values: [u16; PostItem::COUNT],
+}
+
+impl PostReport {
+    /// Create an empty report (nothing run)
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            results: [PostResult::NotRun; PostItem::COUNT],
+            values: [0; PostItem::COUNT],
+        }
+    }
+
+    /// Record the outcome of a check
+    pub fn record(&mut self, item: PostItem, result: PostResult, value: u16) {
+        self.results[item.index()] = result;
+        self.values[item.index()] = value;
+    }
+
+    /// Record an I2C probe (value is 1 if the device acknowledged)
+    pub fn record_probe(&mut self, item: PostItem, acked: bool) {
+        self.record(item, PostResult::from_bool(acked), u16::from(acked));
+    }
+
+    /// Record the `Si5351A` status register
+    pub fn record_pll_status(&mut self, status: u8) {
+        self.record(PostItem::PllLock, check_pll_status(status), u16::from(status));
+    }
+
+    /// Record an averaged idle ADC reading
+    pub fn record_adc_offset(&mut self, item: PostItem, raw_mean: u16) {
+        self.record(item, check_adc_offset(raw_mean), raw_mean);
+    }
+
+    /// Record idle PA current in milliamps
+    pub fn record_pa_current(&mut self, milliamps: u16) {
+        self.record(PostItem::PaCurrent, check_pa_current(milliamps), milliamps);
+    }
+
+    /// Get the result of a check
+    #[must_use]
+    pub const fn result(&self, item: PostItem) -> PostResult {
+        self.results[item.index()]
+    }
+
+    /// Get the raw value a check was judged on
+    #[must_use]
+    pub const fn value(&self, item: PostItem) -> u16 {
+        self.values[item.index()]
+    }
+
+    /// Iterate over checks with their results
+    pub fn iter(&self) -> impl Iterator<Item = (PostItem, PostResult)> + '_ {
+        PostItem::ALL.iter().map(|&item| (item, self.result(item)))
+    }
+
+    /// Iterate over failed checks
+    pub fn failures(&self) -> impl Iterator<Item = PostItem> + '_ {
+        self.iter()
+            .filter(|(_, result)| *result == PostResult::Fail)
+            .map(|(item, _)| item)
+    }
+
+    /// Check if every check ran and passed
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| *r == PostResult::Pass)
+    }
+
+    /// Check if transmitting is safe
+    ///
+    /// Requires a locked synthesizer and an idle PA; display and receive
+    /// path faults do not block transmit.
+    #[must_use]
+    pub const fn tx_allowed(&self) -> bool {
+        matches!(self.result(PostItem::PllLock), PostResult::Pass)
+            && matches!(self.result(PostItem::PaCurrent), PostResult::Pass)
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for PostReport {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "POST(");
+        for (item, result) in self.iter() {
+            defmt::write!(f, " {}={}", item, result);
+        }
+        defmt::write!(f, " )");
+    }
+}
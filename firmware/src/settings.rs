@@ -0,0 +1,718 @@
+//! Persistent Settings
+//!
+//! Operator settings kept in the file store across power cycles. The
+//! startup policy decides what the radio tunes to at power-on: the
+//! last-used VFO, mode and power, or a fixed state configured once (for
+//! club and demo radios that should always come up the same way). The
+//! IQ orientation correction for boards with reversed I/Q wiring, the
+//! per-band S-meter calibration and the Si5351 crystal correction are
+//! kept here as well, along with the [`OperatorConfig`] edited from the
+//! settings menu, the per-mode RX and TX equalizer curves and the per-band
+//! IQ balance (image rejection) calibration.
+//!
+//! Settings are stored as a single small record with a magic, version
+//! and checksum; a missing or corrupt record falls back to defaults.
+//! Version 1 records (before the IQ orientation byte) still load, with
+//! the orientation left at normal; version 1 and 2 records (before the
+//! S-meter calibration) load with the nominal calibration, records
+//! before version 4 load with no crystal correction, records before
+//! version 5 load with the default operator configuration, records
+//! before version 6 load with the equalizers off and the factory curves,
+//! and records before version 7 load with no IQ balance correction.
+
+use crate::config::{DEFAULT_FREQUENCY_HZ, DEFAULT_MODE, DEFAULT_TUNING_STEP};
+use crate::dsp::equalizer::{EqCurve, EqPath, EqPresets, Equalizer, EQ_BANDS, MAX_EQ_GAIN_DB};
+use crate::dsp::si5351_calc::PpmCorrection;
+use crate::radio::keyer::Keyer;
+use crate::radio::state::{AgcMode, RadioState};
+use crate::radio::transmit::{Vox, DEFAULT_VOX_GAIN, VOX_DELAY_MAX_MS, VOX_GAIN_MAX};
+use crate::storage::{BlockDevice, FileKind, FileStore, StorageResult};
+use crate::types::{Band, Frequency, IqOrientation, Mode, PowerLevel, TuningStep};
+use sdr_dsp_core::agc::SmeterCalibration;
+use sdr_dsp_core::iq_balance::IqCalibration;
+
+/// File name of the settings record
+pub const SETTINGS_FILE: &str = "SETTINGS";
+
+/// Record magic
+const MAGIC: [u8; 4] = *b"SDRS";
+
+/// Record layout version
+const VERSION: u8 = 7;
+
+/// Encoded length of a startup state
+const STATE_LEN: usize = 7;
+
+/// Offset of the IQ orientation byte
+const IQ_OFFSET: usize = MAGIC.len() + 2 + 2 * STATE_LEN;
+
+/// Encoded length of a version 1 record
+const SETTINGS_V1_LEN: usize = IQ_OFFSET + 1;
+
+/// Encoded length of a version 2 record
+const SETTINGS_V2_LEN: usize = IQ_OFFSET + 2;
+
+/// Offset of the S-meter calibration table
+const CAL_OFFSET: usize = IQ_OFFSET + 1;
+
+/// Encoded length of one band's S-meter calibration
+const CAL_LEN: usize = 4;
+
+/// Offset of the crystal correction
+const XTAL_OFFSET: usize = CAL_OFFSET + CAL_LEN * Band::COUNT;
+
+/// Encoded length of a version 3 record
+const SETTINGS_V3_LEN: usize = XTAL_OFFSET + 1;
+
+/// Offset of the operator configuration
+const CONFIG_OFFSET: usize = XTAL_OFFSET + 4;
+
+/// Encoded length of a version 4 record
+const SETTINGS_V4_LEN: usize = CONFIG_OFFSET + 1;
+
+/// Encoded length of a version 5 operator configuration
+const CONFIG_V5_LEN: usize = 9;
+
+/// Encoded length of a version 5 record
+const SETTINGS_V5_LEN: usize = CONFIG_OFFSET + CONFIG_V5_LEN + 1;
+
+/// Encoded length of the operator configuration
+const CONFIG_LEN: usize = CONFIG_V5_LEN + 2;
+
+/// Offset of the equalizer curves (RX then TX, each SSB, CW, AM, FM)
+const EQ_OFFSET: usize = CONFIG_OFFSET + CONFIG_LEN;
+
+/// Encoded length of one path's equalizer curves
+const EQ_PRESETS_LEN: usize = EqPresets::COUNT * EQ_BANDS;
+
+/// Offset of the IQ balance calibration table
+const IQ_CAL_OFFSET: usize = EQ_OFFSET + 2 * EQ_PRESETS_LEN;
+
+/// Encoded length of a version 6 record
+const SETTINGS_V6_LEN: usize = IQ_CAL_OFFSET + 1;
+
+/// Encoded length of one band's IQ balance calibration
+const IQ_CAL_LEN: usize = 4;
+
+/// Encoded record length
+pub const SETTINGS_LEN: usize = IQ_CAL_OFFSET + IQ_CAL_LEN * Band::COUNT + 1;
+
+/// What to restore at power-on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StartupPolicy {
+    /// Restore the last-used state
+    #[default]
+    LastUsed,
+    /// Always boot to the configured fixed state
+    Fixed,
+}
+
+impl StartupPolicy {
+    /// Record and CAT code
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::LastUsed => 0,
+            Self::Fixed => 1,
+        }
+    }
+
+    /// Parse a record or CAT code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::LastUsed),
+            1 => Some(Self::Fixed),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for StartupPolicy {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::LastUsed => defmt::write!(f, "LastUsed"),
+            Self::Fixed => defmt::write!(f, "Fixed"),
+        }
+    }
+}
+
+/// Operating state applied at power-on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StartupState {
+    /// VFO frequency
+    pub frequency: Frequency,
+    /// Operating mode
+    pub mode: Mode,
+    /// Tuning step
+    pub step: TuningStep,
+    /// TX power level
+    pub power: PowerLevel,
+}
+
+impl StartupState {
+    /// Create the factory default state
+    ///
+    /// # Panics
+    /// Panics if `DEFAULT_FREQUENCY_HZ` is out of range (a configuration error)
+    #[must_use]
+    pub const fn new() -> Self {
+        let Some(frequency) = Frequency::from_hz(DEFAULT_FREQUENCY_HZ) else {
+            panic!("DEFAULT_FREQUENCY_HZ out of range");
+        };
+        Self {
+            frequency,
+            mode: DEFAULT_MODE,
+            step: DEFAULT_TUNING_STEP,
+            power: PowerLevel::from_percent(50),
+        }
+    }
+
+    /// Capture the persisted part of a radio state
+    #[must_use]
+    pub const fn from_radio(state: &RadioState) -> Self {
+        Self {
+            frequency: state.frequency(),
+            mode: state.mode(),
+            step: state.step(),
+            power: state.power(),
+        }
+    }
+
+    /// Build the radio state to boot into
+    #[must_use]
+    pub fn radio_state(&self) -> RadioState {
+        RadioState::new(self.frequency)
+            .with_mode(self.mode)
+            .with_step(self.step)
+            .with_power(self.power)
+    }
+
+    /// Encode into a record field
+    fn encode(self, out: &mut [u8]) {
+        out[..4].copy_from_slice(&self.frequency.as_hz().to_le_bytes());
+        out[4] = self.mode.code();
+        out[5] = self.step.code();
+        out[6] = self.power.as_percent();
+    }
+
+    /// Decode a record field
+    fn decode(data: &[u8]) -> Option<Self> {
+        let hz = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+        Some(Self {
+            frequency: Frequency::from_hz(hz)?,
+            mode: Mode::from_code(*data.get(4)?)?,
+            step: TuningStep::from_code(*data.get(5)?)?,
+            power: PowerLevel::from_percent(*data.get(6)?),
+        })
+    }
+}
+
+impl Default for StartupState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Highest display brightness setting
+pub const BRIGHTNESS_MAX: u8 = 100;
+
+/// Operator preferences edited from the settings menu
+///
+/// The menu writes these fields directly; the caller then pushes them
+/// into the keyer, VOX, AGC and display with the `apply` helpers and
+/// saves the settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OperatorConfig {
+    /// Keyer speed in WPM
+    pub keyer_wpm: u8,
+    /// CW sidetone pitch in Hz
+    pub sidetone_hz: u16,
+    /// AGC mode
+    pub agc: AgcMode,
+    /// VOX enabled
+    pub vox: bool,
+    /// VOX gain (0-100)
+    pub vox_gain: u8,
+    /// VOX delay (hang time) in milliseconds
+    pub vox_delay_ms: u16,
+    /// Display brightness (0-100)
+    pub brightness: u8,
+    /// Receive equalizer in circuit
+    pub rx_eq: bool,
+    /// Transmit equalizer in circuit
+    pub tx_eq: bool,
+}
+
+impl OperatorConfig {
+    /// Create the factory default configuration
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            keyer_wpm: Keyer::DEFAULT_WPM,
+            sidetone_hz: Keyer::DEFAULT_SIDETONE_HZ,
+            agc: AgcMode::Medium,
+            vox: false,
+            vox_gain: DEFAULT_VOX_GAIN,
+            vox_delay_ms: 500,
+            brightness: 80,
+            rx_eq: false,
+            tx_eq: false,
+        }
+    }
+
+    /// Apply the keyer speed and sidetone
+    pub fn apply_keyer(&self, keyer: &mut Keyer) {
+        keyer.set_wpm(self.keyer_wpm);
+        keyer.set_sidetone(self.sidetone_hz);
+    }
+
+    /// Apply the VOX settings
+    pub fn apply_vox(&self, vox: &mut Vox, sample_rate: u32) {
+        vox.set_enabled(self.vox);
+        vox.set_gain(self.vox_gain);
+        vox.set_hang_ms(u32::from(self.vox_delay_ms), sample_rate);
+    }
+
+    /// Switch the receive and transmit equalizers in or out
+    pub fn apply_eq(&self, rx: &mut Equalizer, tx: &mut Equalizer) {
+        rx.set_enabled(self.rx_eq);
+        tx.set_enabled(self.tx_eq);
+    }
+
+    /// Get the SSD1306 contrast for the brightness
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn contrast(&self) -> u8 {
+        let brightness = if self.brightness > BRIGHTNESS_MAX {
+            BRIGHTNESS_MAX
+        } else {
+            self.brightness
+        };
+        (brightness as u32 * 255 / BRIGHTNESS_MAX as u32) as u8
+    }
+
+    /// Encode into a record field
+    fn encode(self, out: &mut [u8]) {
+        out[0] = self.keyer_wpm;
+        out[1..3].copy_from_slice(&self.sidetone_hz.to_le_bytes());
+        out[3] = self.agc.code();
+        out[4] = u8::from(self.vox);
+        out[5] = self.vox_gain;
+        out[6..8].copy_from_slice(&self.vox_delay_ms.to_le_bytes());
+        out[8] = self.brightness;
+        out[9] = u8::from(self.rx_eq);
+        out[10] = u8::from(self.tx_eq);
+    }
+
+    /// Decode a record field, rejecting out of range values
+    ///
+    /// A version 5 field (without the equalizer switches) leaves both
+    /// equalizers off.
+    fn decode(data: &[u8]) -> Option<Self> {
+        let (rx_eq, tx_eq) = match data.get(CONFIG_V5_LEN..CONFIG_LEN) {
+            Some(eq) => (decode_bool(eq[0])?, decode_bool(eq[1])?),
+            None => (false, false),
+        };
+        let data = data.get(..CONFIG_V5_LEN)?;
+        let config = Self {
+            keyer_wpm: data[0],
+            sidetone_hz: u16::from_le_bytes([data[1], data[2]]),
+            agc: AgcMode::from_code(data[3])?,
+            vox: decode_bool(data[4])?,
+            vox_gain: data[5],
+            vox_delay_ms: u16::from_le_bytes([data[6], data[7]]),
+            brightness: data[8],
+            rx_eq,
+            tx_eq,
+        };
+        let valid = (Keyer::MIN_WPM..=Keyer::MAX_WPM).contains(&config.keyer_wpm)
+            && (Keyer::SIDETONE_RANGE_HZ.0..=Keyer::SIDETONE_RANGE_HZ.1)
+                .contains(&config.sidetone_hz)
+            && config.vox_gain <= VOX_GAIN_MAX
+            && u32::from(config.vox_delay_ms) <= VOX_DELAY_MAX_MS
+            && config.brightness <= BRIGHTNESS_MAX;
+        valid.then_some(config)
+    }
+}
+
+impl Default for OperatorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Persistent operator settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Startup policy
+    policy: StartupPolicy,
+    /// State used with [`StartupPolicy::Fixed`]
+    fixed: StartupState,
+    /// Last-used state
+    last: StartupState,
+    /// IQ orientation correction for the board
+    iq_orientation: IqOrientation,
+    /// S-meter calibration per band
+    smeter_calibration: [SmeterCalibration; Band::COUNT],
+    /// Si5351 crystal correction
+    xtal_correction: PpmCorrection,
+    /// Operator preferences
+    config: OperatorConfig,
+    /// Receive equalizer curve per mode
+    rx_eq: EqPresets,
+    /// Transmit equalizer curve per mode
+    tx_eq: EqPresets,
+    /// IQ balance calibration per band
+    iq_calibration: [IqCalibration; Band::COUNT],
+}
+
+impl Settings {
+    /// Create factory default settings
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            policy: StartupPolicy::LastUsed,
+            fixed: StartupState::new(),
+            last: StartupState::new(),
+            iq_orientation: IqOrientation::Normal,
+            smeter_calibration: [SmeterCalibration::IDENTITY; Band::COUNT],
+            xtal_correction: PpmCorrection::NONE,
+            config: OperatorConfig::new(),
+            rx_eq: EqPresets::defaults(EqPath::Rx),
+            tx_eq: EqPresets::defaults(EqPath::Tx),
+            iq_calibration: [IqCalibration::IDENTITY; Band::COUNT],
+        }
+    }
+
+    /// Get the startup policy
+    #[must_use]
+    pub const fn policy(&self) -> StartupPolicy {
+        self.policy
+    }
+
+    /// Set the startup policy
+    pub fn set_policy(&mut self, policy: StartupPolicy) {
+        self.policy = policy;
+    }
+
+    /// Get the fixed startup state
+    #[must_use]
+    pub const fn fixed(&self) -> &StartupState {
+        &self.fixed
+    }
+
+    /// Set the fixed startup state
+    pub fn set_fixed(&mut self, state: StartupState) {
+        self.fixed = state;
+    }
+
+    /// Get the last-used state
+    #[must_use]
+    pub const fn last(&self) -> &StartupState {
+        &self.last
+    }
+
+    /// Get the IQ orientation correction
+    #[must_use]
+    pub const fn iq_orientation(&self) -> IqOrientation {
+        self.iq_orientation
+    }
+
+    /// Set the IQ orientation correction
+    pub fn set_iq_orientation(&mut self, orientation: IqOrientation) {
+        self.iq_orientation = orientation;
+    }
+
+    /// Get the S-meter calibration of a band
+    #[must_use]
+    pub const fn smeter_calibration(&self, band: Band) -> SmeterCalibration {
+        self.smeter_calibration[band.index()]
+    }
+
+    /// Set the S-meter calibration of a band
+    pub fn set_smeter_calibration(&mut self, band: Band, calibration: SmeterCalibration) {
+        self.smeter_calibration[band.index()] = calibration;
+    }
+
+    /// Get the Si5351 crystal correction
+    #[must_use]
+    pub const fn xtal_correction(&self) -> PpmCorrection {
+        self.xtal_correction
+    }
+
+    /// Set the Si5351 crystal correction
+    pub fn set_xtal_correction(&mut self, correction: PpmCorrection) {
+        self.xtal_correction = correction;
+    }
+
+    /// Get the operator configuration
+    #[must_use]
+    pub const fn config(&self) -> &OperatorConfig {
+        &self.config
+    }
+
+    /// Get the operator configuration for editing
+    pub fn config_mut(&mut self) -> &mut OperatorConfig {
+        &mut self.config
+    }
+
+    /// Get the equalizer curves of an audio path
+    #[must_use]
+    pub const fn eq_presets(&self, path: EqPath) -> &EqPresets {
+        match path {
+            EqPath::Rx => &self.rx_eq,
+            EqPath::Tx => &self.tx_eq,
+        }
+    }
+
+    /// Set the equalizer curve of an audio path for a mode
+    pub fn set_eq_curve(&mut self, path: EqPath, mode: Mode, curve: EqCurve) {
+        match path {
+            EqPath::Rx => self.rx_eq.set(mode, curve),
+            EqPath::Tx => self.tx_eq.set(mode, curve),
+        }
+    }
+
+    /// Get the IQ balance calibration of a band
+    #[must_use]
+    pub const fn iq_calibration(&self, band: Band) -> IqCalibration {
+        self.iq_calibration[band.index()]
+    }
+
+    /// Get the IQ balance calibrations of all bands, for the receive
+    /// pipeline
+    #[must_use]
+    pub const fn iq_calibrations(&self) -> [IqCalibration; Band::COUNT] {
+        self.iq_calibration
+    }
+
+    /// Set the IQ balance calibration of a band
+    pub fn set_iq_calibration(&mut self, band: Band, calibration: IqCalibration) {
+        self.iq_calibration[band.index()] = calibration;
+    }
+
+    /// Record the current radio state as last used
+    ///
+    /// Returns `true` if anything persisted changed, so the caller only
+    /// writes flash when needed.
+    pub fn update_last(&mut self, state: &RadioState) -> bool {
+        let last = StartupState::from_radio(state);
+        let changed = last != self.last;
+        self.last = last;
+        changed
+    }
+
+    /// State to boot into according to the policy
+    #[must_use]
+    pub const fn startup_state(&self) -> &StartupState {
+        match self.policy {
+            StartupPolicy::LastUsed => &self.last,
+            StartupPolicy::Fixed => &self.fixed,
+        }
+    }
+
+    /// Encode into a record
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; SETTINGS_LEN] {
+        let mut out = [0u8; SETTINGS_LEN];
+        out[..4].copy_from_slice(&MAGIC);
+        out[4] = VERSION;
+        out[5] = self.policy.code();
+        self.fixed.encode(&mut out[6..6 + STATE_LEN]);
+        self.last.encode(&mut out[6 + STATE_LEN..IQ_OFFSET]);
+        out[IQ_OFFSET] = self.iq_orientation.code();
+        for (cal, chunk) in self
+            .smeter_calibration
+            .iter()
+            .zip(out[CAL_OFFSET..XTAL_OFFSET].chunks_exact_mut(CAL_LEN))
+        {
+            chunk[..2].copy_from_slice(&cal.offset_tenths_db().to_le_bytes());
+            chunk[2..].copy_from_slice(&cal.slope_permille().to_le_bytes());
+        }
+        out[XTAL_OFFSET..CONFIG_OFFSET].copy_from_slice(&self.xtal_correction.ppb().to_le_bytes());
+        self.config.encode(&mut out[CONFIG_OFFSET..EQ_OFFSET]);
+        let curves = self.rx_eq.curves().iter().chain(self.tx_eq.curves());
+        let fields = out[EQ_OFFSET..IQ_CAL_OFFSET].chunks_exact_mut(EQ_BANDS);
+        for (curve, chunk) in curves.zip(fields) {
+            for (byte, gain) in chunk.iter_mut().zip(curve.gains_db()) {
+                *byte = gain.to_le_bytes()[0];
+            }
+        }
+        for (cal, chunk) in self
+            .iq_calibration
+            .iter()
+            .zip(out[IQ_CAL_OFFSET..SETTINGS_LEN - 1].chunks_exact_mut(IQ_CAL_LEN))
+        {
+            chunk[..2].copy_from_slice(&cal.gain_hundredths_db().to_le_bytes());
+            chunk[2..].copy_from_slice(&cal.phase_hundredths_deg().to_le_bytes());
+        }
+        out[SETTINGS_LEN - 1] = checksum(&out[..SETTINGS_LEN - 1]);
+        out
+    }
+
+    /// Decode a record
+    ///
+    /// Returns `None` for a wrong magic, version or checksum, or any
+    /// field out of range.
+    #[must_use]
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let len = match *data.get(4)? {
+            1 => SETTINGS_V1_LEN,
+            2 => SETTINGS_V2_LEN,
+            3 => SETTINGS_V3_LEN,
+            4 => SETTINGS_V4_LEN,
+            5 => SETTINGS_V5_LEN,
+            6 => SETTINGS_V6_LEN,
+            VERSION => SETTINGS_LEN,
+            _ => return None,
+        };
+        let data = data.get(..len)?;
+        if data[..4] != MAGIC || checksum(&data[..len - 1]) != data[len - 1] {
+            return None;
+        }
+        let iq_orientation = if len == SETTINGS_V1_LEN {
+            IqOrientation::Normal
+        } else {
+            IqOrientation::from_code(data[IQ_OFFSET])?
+        };
+        let mut smeter_calibration = [SmeterCalibration::IDENTITY; Band::COUNT];
+        if len >= SETTINGS_V3_LEN {
+            for (cal, chunk) in smeter_calibration
+                .iter_mut()
+                .zip(data[CAL_OFFSET..XTAL_OFFSET].chunks_exact(CAL_LEN))
+            {
+                *cal = SmeterCalibration::new(
+                    i16::from_le_bytes([chunk[0], chunk[1]]),
+                    u16::from_le_bytes([chunk[2], chunk[3]]),
+                );
+            }
+        }
+        let xtal_correction = if len >= SETTINGS_V4_LEN {
+            let ppb = i32::from_le_bytes(data[XTAL_OFFSET..CONFIG_OFFSET].try_into().ok()?);
+            PpmCorrection::from_ppb(ppb)?
+        } else {
+            PpmCorrection::NONE
+        };
+        let config = if len >= SETTINGS_V5_LEN {
+            OperatorConfig::decode(&data[CONFIG_OFFSET..(len - 1).min(EQ_OFFSET)])?
+        } else {
+            OperatorConfig::new()
+        };
+        let (rx_eq, tx_eq) = if len >= SETTINGS_V6_LEN {
+            let (rx, tx) = data[EQ_OFFSET..IQ_CAL_OFFSET].split_at(EQ_PRESETS_LEN);
+            (decode_eq(rx)?, decode_eq(tx)?)
+        } else {
+            (
+                EqPresets::defaults(EqPath::Rx),
+                EqPresets::defaults(EqPath::Tx),
+            )
+        };
+        let mut iq_calibration = [IqCalibration::IDENTITY; Band::COUNT];
+        if len == SETTINGS_LEN {
+            for (cal, chunk) in iq_calibration
+                .iter_mut()
+                .zip(data[IQ_CAL_OFFSET..len - 1].chunks_exact(IQ_CAL_LEN))
+            {
+                *cal = decode_iq_calibration(chunk)?;
+            }
+        }
+        Some(Self {
+            policy: StartupPolicy::from_code(data[5])?,
+            fixed: StartupState::decode(&data[6..6 + STATE_LEN])?,
+            last: StartupState::decode(&data[6 + STATE_LEN..IQ_OFFSET])?,
+            iq_orientation,
+            smeter_calibration,
+            xtal_correction,
+            config,
+            rx_eq,
+            tx_eq,
+            iq_calibration,
+        })
+    }
+
+    /// Load settings from the file store
+    ///
+    /// Returns `None` if no valid record is stored.
+    pub fn load<D: BlockDevice>(store: &mut FileStore<D>) -> Option<Self> {
+        let id = store.find(SETTINGS_FILE)?.id;
+        let mut buf = [0u8; SETTINGS_LEN];
+        let n = store.read(id, 0, &mut buf).ok()?;
+        Self::from_bytes(&buf[..n])
+    }
+
+    /// Save settings to the file store, replacing any previous record
+    ///
+    /// # Errors
+    /// Returns the storage error if the record cannot be written
+    pub fn save<D: BlockDevice>(&self, store: &mut FileStore<D>) -> StorageResult<(), D::Error> {
+        if let Some(id) = store.find(SETTINGS_FILE).map(|entry| entry.id) {
+            store.delete(id)?;
+        }
+        store.create(SETTINGS_FILE, FileKind::Settings)?;
+        store.append(&self.to_bytes())?;
+        store.close()?;
+        Ok(())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for Settings {
+    fn format(&self, f: defmt::Formatter) {
+        let state = self.startup_state();
+        defmt::write!(
+            f,
+            "Settings({}, {} Hz, {}%, IQ {})",
+            self.policy,
+            state.frequency.as_hz(),
+            state.power.as_percent(),
+            self.iq_orientation
+        );
+    }
+}
+
+/// Decode a record flag byte
+fn decode_bool(byte: u8) -> Option<bool> {
+    match byte {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+/// Decode one path's equalizer curves, rejecting out of range gains
+fn decode_eq(data: &[u8]) -> Option<EqPresets> {
+    let mut curves = [EqCurve::FLAT; EqPresets::COUNT];
+    for (curve, chunk) in curves.iter_mut().zip(data.chunks_exact(EQ_BANDS)) {
+        let gains: [i8; EQ_BANDS] = core::array::from_fn(|band| i8::from_le_bytes([chunk[band]]));
+        if gains
+            .iter()
+            .any(|gain| gain.unsigned_abs() > MAX_EQ_GAIN_DB.unsigned_abs())
+        {
+            return None;
+        }
+        *curve = EqCurve::new(gains);
+    }
+    Some(EqPresets::from_curves(curves))
+}
+
+/// Decode one band's IQ balance calibration, rejecting out of range values
+fn decode_iq_calibration(data: &[u8]) -> Option<IqCalibration> {
+    let gain = i16::from_le_bytes([data[0], data[1]]);
+    let phase = i16::from_le_bytes([data[2], data[3]]);
+    let calibration = IqCalibration::new(gain, phase);
+    (calibration.gain_hundredths_db() == gain && calibration.phase_hundredths_deg() == phase)
+        .then_some(calibration)
+}
+
+/// Record checksum (byte sum, two's complement)
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)).wrapping_neg()
+}
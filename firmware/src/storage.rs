@@ -0,0 +1,524 @@
+//! Storage
+//!
+//! Flat file store for logs, captures and voice messages on block
+//! storage (SPI NOR flash or an SD card). The layout is kept simple so
+//! it works on NOR flash without read-modify-write cycles:
+//!
+//! - Block 0 holds a header and an append-only directory of fixed-size
+//!   entries. Entries are only ever programmed (bits 1 → 0), never
+//!   rewritten, so the directory block is erased only by `format`.
+//! - File data is stored contiguously, starting on a block boundary.
+//!   Only one file may be open for writing at a time and it always grows
+//!   at the end of the used area.
+//!
+//! Files still open when power is lost are discarded at mount time.
+//! Space freed by deleting the most recent files is reused; holes left
+//! by older files and directory slots are reclaimed by `format`.
+
+use heapless::{String, Vec};
+
+/// Maximum number of directory entries
+pub const MAX_FILES: usize = 64;
+
+/// Maximum file name length in bytes (ASCII)
+pub const MAX_NAME_LEN: usize = 20;
+
+/// Directory header magic
+const MAGIC: [u8; 8] = *b"SDRFS001";
+
+/// Header length in bytes (magic + reserved)
+const HEADER_LEN: u32 = 32;
+
+/// Directory entry length in bytes
+const ENTRY_LEN: u32 = 32;
+
+/// Entry state: slot never used (erased flash)
+const ENTRY_FREE: u8 = 0xFF;
+
+/// Entry state: file deleted
+const ENTRY_DELETED: u8 = 0x00;
+
+/// Length field value while a file is still open
+const LENGTH_OPEN: u32 = u32::MAX;
+
+/// Smallest block size able to hold the directory
+#[allow(clippy::cast_possible_truncation)]
+pub const MIN_BLOCK_SIZE: u32 = HEADER_LEN + ENTRY_LEN * MAX_FILES as u32;
+
+/// Block storage device
+///
+/// Addresses are absolute byte offsets. `write` may only clear bits
+/// (NOR semantics), so a block must be erased before it is rewritten.
+pub trait BlockDevice {
+    /// Device error type
+    type Error;
+
+    /// Erase block size in bytes
+    fn block_size(&self) -> u32;
+
+    /// Number of erase blocks
+    fn block_count(&self) -> u32;
+
+    /// Read bytes starting at an address
+    ///
+    /// # Errors
+    /// Returns the device error if the read fails
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Program bytes starting at an address
+    ///
+    /// # Errors
+    /// Returns the device error if programming fails
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Erase a block (all bytes read back as 0xFF)
+    ///
+    /// # Errors
+    /// Returns the device error if the erase fails
+    fn erase(&mut self, block: u32) -> Result<(), Self::Error>;
+}
+
+/// What a file contains
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// Text log (operation journal, spots)
+    Log,
+    /// Audio or IQ capture
+    Capture,
+    /// Voice keyer message
+    Voice,
+    /// Persistent settings record
+    Settings,
+}
+
+impl FileKind {
+    /// Directory entry code
+    const fn code(self) -> u8 {
+        match self {
+            Self::Log => 1,
+            Self::Capture => 2,
+            Self::Voice => 3,
+            Self::Settings => 4,
+        }
+    }
+
+    /// Parse a directory entry code
+    const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::Log),
+            2 => Some(Self::Capture),
+            3 => Some(Self::Voice),
+            4 => Some(Self::Settings),
+            _ => None,
+        }
+    }
+
+    /// Single character code used in protocol listings
+    #[must_use]
+    pub const fn as_char(self) -> char {
+        match self {
+            Self::Log => 'L',
+            Self::Capture => 'C',
+            Self::Voice => 'V',
+            Self::Settings => 'S',
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for FileKind {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Log => defmt::write!(f, "Log"),
+            Self::Capture => defmt::write!(f, "Capture"),
+            Self::Voice => defmt::write!(f, "Voice"),
+            Self::Settings => defmt::write!(f, "Settings"),
+        }
+    }
+}
+
+/// Directory entry for a closed file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileEntry {
+    /// File identifier (directory slot)
+    pub id: u8,
+    /// File contents
+    pub kind: FileKind,
+    /// File name
+    pub name: String<MAX_NAME_LEN>,
+    /// First data block
+    pub start_block: u32,
+    /// Length in bytes
+    pub length: u32,
+}
+
+/// Storage errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageError<E> {
+    /// Underlying device error
+    Device(E),
+    /// No valid directory found
+    NotFormatted,
+    /// Device block size too small for the directory
+    Unsupported,
+    /// No file with that identifier
+    NotFound,
+    /// A file with that name already exists
+    Exists,
+    /// Name empty, too long or not ASCII
+    InvalidName,
+    /// No free directory slots
+    DirectoryFull,
+    /// No free data space
+    Full,
+    /// Another file is open for writing
+    Busy,
+    /// No file is open for writing
+    NotOpen,
+}
+
+#[cfg(feature = "embedded")]
+impl<E> defmt::Format for StorageError<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Device(_) => defmt::write!(f, "Device"),
+            Self::NotFormatted => defmt::write!(f, "NotFormatted"),
+            Self::Unsupported => defmt::write!(f, "Unsupported"),
+            Self::NotFound => defmt::write!(f, "NotFound"),
+            Self::Exists => defmt::write!(f, "Exists"),
+            Self::InvalidName => defmt::write!(f, "InvalidName"),
+            Self::DirectoryFull => defmt::write!(f, "DirectoryFull"),
+            Self::Full => defmt::write!(f, "Full"),
+            Self::Busy => defmt::write!(f, "Busy"),
+            Self::NotOpen => defmt::write!(f, "NotOpen"),
+        }
+    }
+}
+
+/// Storage operation result
+pub type StorageResult<T, E> = Result<T, StorageError<E>>;
+
+/// File currently open for writing
+#[derive(Clone, Debug)]
+struct OpenFile {
+    /// Directory entry (length not yet final)
+    entry: FileEntry,
+    /// Bytes written so far
+    written: u32,
+}
+
+/// Flat file store on a block device
+pub struct FileStore<D: BlockDevice> {
+    /// Underlying device
+    device: D,
+    /// Closed files (directory order)
+    files: Vec<FileEntry, MAX_FILES>,
+    /// Number of directory slots in use (including deleted)
+    used_slots: usize,
+    /// File open for writing
+    open: Option<OpenFile>,
+}
+
+impl<D: BlockDevice> FileStore<D> {
+    /// Erase the directory and create an empty file store
+    ///
+    /// # Errors
+    /// Returns an error if the block size is too small or the device fails
+    pub fn format(mut device: D) -> StorageResult<Self, D::Error> {
+        if device.block_size() < MIN_BLOCK_SIZE || device.block_count() < 2 {
+            return Err(StorageError::Unsupported);
+        }
+        device.erase(0).map_err(StorageError::Device)?;
+        device.write(0, &MAGIC).map_err(StorageError::Device)?;
+
+        Ok(Self {
+            device,
+            files: Vec::new(),
+            used_slots: 0,
+            open: None,
+        })
+    }
+
+    /// Load an existing file store
+    ///
+    /// # Errors
+    /// Returns `NotFormatted` if no directory is present, or the device error
+    pub fn mount(mut device: D) -> StorageResult<Self, D::Error> {
+        if device.block_size() < MIN_BLOCK_SIZE || device.block_count() < 2 {
+            return Err(StorageError::Unsupported);
+        }
+
+        let mut magic = [0u8; 8];
+        device.read(0, &mut magic).map_err(StorageError::Device)?;
+        if magic != MAGIC {
+            return Err(StorageError::NotFormatted);
+        }
+
+        let mut store = Self {
+            device,
+            files: Vec::new(),
+            used_slots: 0,
+            open: None,
+        };
+
+        for slot in 0..MAX_FILES {
+            let mut raw = [0u8; ENTRY_LEN as usize];
+            let addr = Self::entry_addr(slot);
+            store.device.read(addr, &mut raw).map_err(StorageError::Device)?;
+
+            match raw[0] {
+                ENTRY_FREE => continue,
+                ENTRY_DELETED => {}
+                code => {
+                    let length = u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]);
+                    if length == LENGTH_OPEN {
+                        // Interrupted while writing, discard
+                        store
+                            .device
+                            .write(addr, &[ENTRY_DELETED])
+                            .map_err(StorageError::Device)?;
+                    } else if let Some(entry) = Self::parse_entry(slot, code, &raw, length) {
+                        let _ = store.files.push(entry);
+                    }
+                }
+            }
+            store.used_slots = slot + 1;
+        }
+
+        Ok(store)
+    }
+
+    /// Release the underlying device
+    pub fn release(self) -> D {
+        self.device
+    }
+
+    /// Get all closed files (directory order)
+    #[must_use]
+    pub fn files(&self) -> &[FileEntry] {
+        &self.files
+    }
+
+    /// Find a file by identifier
+    #[must_use]
+    pub fn file(&self, id: u8) -> Option<&FileEntry> {
+        self.files.iter().find(|f| f.id == id)
+    }
+
+    /// Find a file by name
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&FileEntry> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Check if a file is open for writing
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Total data capacity in bytes
+    #[must_use]
+    pub fn capacity_bytes(&self) -> u32 {
+        (self.device.block_count() - 1).saturating_mul(self.device.block_size())
+    }
+
+    /// Free data space in bytes
+    #[must_use]
+    pub fn free_bytes(&self) -> u32 {
+        let bs = self.device.block_size();
+        let used_end = match &self.open {
+            Some(open) => open.entry.start_block * bs + open.written,
+            None => self.next_free_block() * bs,
+        };
+        (self.device.block_count() * bs).saturating_sub(used_end)
+    }
+
+    /// Create a file and open it for writing, returns its identifier
+    ///
+    /// # Errors
+    /// Fails if another file is open, the name is invalid or taken,
+    /// or there is no directory or data space left
+    pub fn create(&mut self, name: &str, kind: FileKind) -> StorageResult<u8, D::Error> {
+        if self.open.is_some() {
+            return Err(StorageError::Busy);
+        }
+        if name.is_empty() || name.len() > MAX_NAME_LEN || !name.is_ascii() {
+            return Err(StorageError::InvalidName);
+        }
+        if self.find(name).is_some() {
+            return Err(StorageError::Exists);
+        }
+        if self.used_slots >= MAX_FILES {
+            return Err(StorageError::DirectoryFull);
+        }
+        let start_block = self.next_free_block();
+        if start_block >= self.device.block_count() {
+            return Err(StorageError::Full);
+        }
+
+        let slot = self.used_slots;
+        let mut raw = [0xFFu8; ENTRY_LEN as usize];
+        raw[0] = kind.code();
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            raw[1] = name.len() as u8;
+        }
+        raw[4..8].copy_from_slice(&start_block.to_le_bytes());
+        raw[12..12 + name.len()].copy_from_slice(name.as_bytes());
+        self.device
+            .write(Self::entry_addr(slot), &raw)
+            .map_err(StorageError::Device)?;
+        self.used_slots += 1;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let id = slot as u8;
+        let mut file_name = String::new();
+        let _ = file_name.push_str(name);
+        self.open = Some(OpenFile {
+            entry: FileEntry {
+                id,
+                kind,
+                name: file_name,
+                start_block,
+                length: 0,
+            },
+            written: 0,
+        });
+
+        Ok(id)
+    }
+
+    /// Append data to the open file
+    ///
+    /// # Errors
+    /// Fails if no file is open, the device is full or the device fails
+    pub fn append(&mut self, data: &[u8]) -> StorageResult<(), D::Error> {
+        let bs = self.device.block_size();
+        let end = self.device.block_count() * bs;
+        let Some(open) = self.open.as_mut() else {
+            return Err(StorageError::NotOpen);
+        };
+
+        let base = open.entry.start_block * bs;
+        if u64::from(base) + u64::from(open.written) + data.len() as u64 > u64::from(end) {
+            return Err(StorageError::Full);
+        }
+
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            let addr = base + open.written;
+            let offset_in_block = addr % bs;
+            if offset_in_block == 0 {
+                self.device
+                    .erase(addr / bs)
+                    .map_err(StorageError::Device)?;
+            }
+            let room = (bs - offset_in_block) as usize;
+            let n = remaining.len().min(room);
+            self.device
+                .write(addr, &remaining[..n])
+                .map_err(StorageError::Device)?;
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                open.written += n as u32;
+            }
+            remaining = &remaining[n..];
+        }
+
+        Ok(())
+    }
+
+    /// Close the open file, returns its final entry
+    ///
+    /// # Errors
+    /// Fails if no file is open or the device fails
+    pub fn close(&mut self) -> StorageResult<FileEntry, D::Error> {
+        let Some(open) = self.open.take() else {
+            return Err(StorageError::NotOpen);
+        };
+
+        let mut entry = open.entry;
+        entry.length = open.written;
+        let addr = Self::entry_addr(usize::from(entry.id)) + 8;
+        self.device
+            .write(addr, &entry.length.to_le_bytes())
+            .map_err(StorageError::Device)?;
+        let _ = self.files.push(entry.clone());
+
+        Ok(entry)
+    }
+
+    /// Read file data at an offset, returns the number of bytes read
+    ///
+    /// Returns 0 at end of file.
+    ///
+    /// # Errors
+    /// Fails if the file does not exist or the device fails
+    pub fn read(&mut self, id: u8, offset: u32, buf: &mut [u8]) -> StorageResult<usize, D::Error> {
+        let bs = self.device.block_size();
+        let entry = self.file(id).ok_or(StorageError::NotFound)?;
+        let available = entry.length.saturating_sub(offset) as usize;
+        let n = buf.len().min(available);
+        if n == 0 {
+            return Ok(0);
+        }
+
+        let addr = entry.start_block * bs + offset;
+        self.device
+            .read(addr, &mut buf[..n])
+            .map_err(StorageError::Device)?;
+        Ok(n)
+    }
+
+    /// Delete a closed file
+    ///
+    /// # Errors
+    /// Fails if the file does not exist or the device fails
+    pub fn delete(&mut self, id: u8) -> StorageResult<(), D::Error> {
+        let idx = self
+            .files
+            .iter()
+            .position(|f| f.id == id)
+            .ok_or(StorageError::NotFound)?;
+        self.device
+            .write(Self::entry_addr(usize::from(id)), &[ENTRY_DELETED])
+            .map_err(StorageError::Device)?;
+        self.files.remove(idx);
+        Ok(())
+    }
+
+    /// Byte address of a directory slot
+    #[allow(clippy::cast_possible_truncation)]
+    const fn entry_addr(slot: usize) -> u32 {
+        HEADER_LEN + slot as u32 * ENTRY_LEN
+    }
+
+    /// First block after the last live file
+    fn next_free_block(&self) -> u32 {
+        let bs = self.device.block_size();
+        self.files
+            .iter()
+            .map(|f| f.start_block + f.length.div_ceil(bs))
+            .max()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Decode a directory entry
+    fn parse_entry(slot: usize, code: u8, raw: &[u8], length: u32) -> Option<FileEntry> {
+        let kind = FileKind::from_code(code)?;
+        let name_len = usize::from(raw[1]).min(MAX_NAME_LEN);
+        let name_str = core::str::from_utf8(&raw[12..12 + name_len]).ok()?;
+        let mut name = String::new();
+        let _ = name.push_str(name_str);
+
+        Some(FileEntry {
+            id: u8::try_from(slot).ok()?,
+            kind,
+            name,
+            start_block: u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]),
+            length,
+        })
+    }
+}
@@ -3,6 +3,8 @@
 //! This module defines domain-specific types that enforce invariants
 //! at compile time and provide type safety throughout the codebase.
 
+pub mod ring;
+
 use core::fmt;
 #[cfg(feature = "embedded")]
 use micromath::F32Ext;
@@ -123,6 +125,35 @@ impl TuningStep {
         }
     }
 
+    /// Compact code for storage and wire formats (0 = 1 Hz ... 6 = 1 MHz)
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Hz1 => 0,
+            Self::Hz10 => 1,
+            Self::Hz100 => 2,
+            Self::KHz1 => 3,
+            Self::KHz10 => 4,
+            Self::KHz100 => 5,
+            Self::MHz1 => 6,
+        }
+    }
+
+    /// Parse a compact step code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Hz1),
+            1 => Some(Self::Hz10),
+            2 => Some(Self::Hz100),
+            3 => Some(Self::KHz1),
+            4 => Some(Self::KHz10),
+            5 => Some(Self::KHz100),
+            6 => Some(Self::MHz1),
+            _ => None,
+        }
+    }
+
     /// Cycle to next larger step
     #[must_use]
     pub const fn next_larger(self) -> Self {
@@ -209,6 +240,33 @@ impl Mode {
         }
     }
 
+    /// Kenwood `MD` mode digit, also used in storage and wire formats
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Lsb => 1,
+            Self::Usb => 2,
+            Self::Cw => 3,
+            Self::Fm => 4,
+            Self::Am => 5,
+            Self::CwR => 7,
+        }
+    }
+
+    /// Parse a Kenwood `MD` mode digit
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::Lsb),
+            2 => Some(Self::Usb),
+            3 => Some(Self::Cw),
+            4 => Some(Self::Fm),
+            5 => Some(Self::Am),
+            7 => Some(Self::CwR),
+            _ => None,
+        }
+    }
+
     /// Check if this mode uses sideband inversion
     #[must_use]
     pub const fn inverted_sideband(self) -> bool {
@@ -248,6 +306,32 @@ pub enum Band {
 }
 
 impl Band {
+    /// Number of bands
+    pub const COUNT: usize = 6;
+
+    /// All bands, lowest frequency first
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::M80,
+        Self::M40,
+        Self::M30,
+        Self::M20,
+        Self::M17,
+        Self::M15,
+    ];
+
+    /// Get the band's position in [`Band::ALL`]
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::M80 => 0,
+            Self::M40 => 1,
+            Self::M30 => 2,
+            Self::M20 => 3,
+            Self::M17 => 4,
+            Self::M15 => 5,
+        }
+    }
+
     /// Get the band for a given frequency
     #[must_use]
     pub const fn from_frequency(freq: Frequency) -> Option<Self> {
@@ -339,7 +423,7 @@ impl PowerLevel {
     /// Minimum power (1 mW)
     pub const MIN: Self = Self(0);
 
-    /// Maximum power (5W)
+    /// Maximum power (5 W, or the USB-PD ceiling)
     pub const MAX: Self = Self(100);
 
     /// Create a power level from percentage (0-100)
@@ -380,11 +464,11 @@ impl defmt::Format for PowerLevel {
 }
 
 /// SWR measurement result
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SwrReading {
-    /// Forward power in arbitrary ADC units
+    /// Forward power (milliwatts from [`SwrMeter`](crate::radio::swr::SwrMeter))
     pub forward: u16,
-    /// Reflected power in arbitrary ADC units
+    /// Reflected power, in the same units as `forward`
     pub reflected: u16,
 }
 
@@ -446,3 +530,64 @@ impl defmt::Format for TxRxState {
         }
     }
 }
+
+/// IQ channel orientation correction
+///
+/// Boards with the I and Q lines reversed (or one leg of the quadrature
+/// network inverted) receive and transmit a mirrored spectrum. Both
+/// corrections are their own inverse, so the same setting is applied at
+/// the receive input and the transmit output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IqOrientation {
+    /// I and Q as wired
+    #[default]
+    Normal,
+    /// Exchange I and Q
+    Swapped,
+    /// Negate Q
+    InvertQ,
+}
+
+impl IqOrientation {
+    /// Compact code for storage and CAT
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Swapped => 1,
+            Self::InvertQ => 2,
+        }
+    }
+
+    /// Parse a compact orientation code
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Normal),
+            1 => Some(Self::Swapped),
+            2 => Some(Self::InvertQ),
+            _ => None,
+        }
+    }
+
+    /// Correct a raw ADC/DAC sample pair
+    #[must_use]
+    pub const fn apply_raw(self, i: i16, q: i16) -> (i16, i16) {
+        match self {
+            Self::Normal => (i, q),
+            Self::Swapped => (q, i),
+            Self::InvertQ => (i, q.saturating_neg()),
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for IqOrientation {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Normal => defmt::write!(f, "Normal"),
+            Self::Swapped => defmt::write!(f, "Swapped"),
+            Self::InvertQ => defmt::write!(f, "InvertQ"),
+        }
+    }
+}
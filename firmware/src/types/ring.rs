@@ -0,0 +1,290 @@
+//! Lock-free SPSC Sample Rings
+//!
+//! Fixed-size single-producer, single-consumer queues for handing audio
+//! and I/Q from interrupt context (a DMA transfer-complete handler) to
+//! the async DSP task, or from the DSP task to a thread-mode task,
+//! without a critical section or a mutex.
+//!
+//! Like the CMSIS-DSP circular buffer helpers they move blocks: the
+//! producer writes what fits and counts the rest as dropped, and the
+//! consumer reads what is there and counts short reads as underruns,
+//! so a stalled task shows up as a number rather than a glitch nobody
+//! can find.
+//!
+//! Every slot is an atomic word, so there is no `unsafe`. The producer
+//! fills slots and then publishes them with a release store of its
+//! write count; the consumer acquires that count before reading, and
+//! hands slots back the same way. Both counts run freely and wrap, so
+//! the length must be a power of two.
+//!
+//! # Example
+//!
+//! ```ignore
+//! static AUDIO: StaticCell<AudioRing<256>> = StaticCell::new();
+//!
+//! let (mut producer, mut consumer) = AUDIO.init(AudioRing::new()).split();
+//! // DMA transfer complete interrupt
+//! producer.push_slice(&block);
+//! // DSP task
+//! if consumer.len() >= AUDIO_BLOCK_LEN {
+//!     consumer.pop_slice(&mut audio);
+//! }
+//! ```
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use crate::dsp::modulation::IqSample;
+
+/// Sample that can be queued in a [`SpscRing`]
+pub trait RingSample: Copy + Default {
+    /// Atomic storage for one sample
+    type Slot: Send + Sync;
+
+    /// Slot holding a zero sample
+    const EMPTY: Self::Slot;
+
+    /// Write a sample to a slot
+    fn store(slot: &Self::Slot, sample: Self);
+
+    /// Read a sample from a slot
+    fn load(slot: &Self::Slot) -> Self;
+}
+
+impl RingSample for f32 {
+    type Slot = AtomicU32;
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicU32 = AtomicU32::new(0);
+
+    fn store(slot: &AtomicU32, sample: Self) {
+        slot.store(sample.to_bits(), Ordering::Relaxed);
+    }
+
+    fn load(slot: &AtomicU32) -> Self {
+        Self::from_bits(slot.load(Ordering::Relaxed))
+    }
+}
+
+impl RingSample for IqSample {
+    type Slot = [AtomicU32; 2];
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+    fn store(slot: &[AtomicU32; 2], sample: Self) {
+        f32::store(&slot[0], sample.i);
+        f32::store(&slot[1], sample.q);
+    }
+
+    fn load(slot: &[AtomicU32; 2]) -> Self {
+        Self::new(f32::load(&slot[0]), f32::load(&slot[1]))
+    }
+}
+
+/// Single-producer, single-consumer ring of `N` samples
+///
+/// `N` must be a power of two. Use [`split`](Self::split) to get the
+/// two ends; the ring itself only reports its state.
+pub struct SpscRing<T: RingSample, const N: usize> {
+    /// Sample storage
+    slots: [T::Slot; N],
+    /// Samples written (wraps)
+    head: AtomicUsize,
+    /// Samples read (wraps)
+    tail: AtomicUsize,
+    /// Samples dropped because the ring was full
+    dropped: AtomicU32,
+    /// Reads that found fewer samples than asked for
+    underruns: AtomicU32,
+}
+
+/// Ring of audio samples
+pub type AudioRing<const N: usize> = SpscRing<f32, N>;
+
+/// Ring of I/Q samples
+pub type IqRing<const N: usize> = SpscRing<IqSample, N>;
+
+impl<T: RingSample, const N: usize> SpscRing<T, N> {
+    /// Index mask, checked at compile time
+    const MASK: usize = {
+        assert!(N.is_power_of_two(), "ring length must be a power of two");
+        N - 1
+    };
+
+    /// Create an empty ring
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { T::EMPTY }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+            underruns: AtomicU32::new(0),
+        }
+    }
+
+    /// Split into the producer and consumer ends
+    ///
+    /// Each end may be moved to its own context; borrowing the ring
+    /// mutably makes sure there is only ever one of each.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        let ring = &*self;
+        (Producer { ring }, Consumer { ring })
+    }
+
+    /// Get the number of samples the ring holds
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Get the number of samples queued
+    #[must_use]
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        self.head.load(Ordering::Acquire).wrapping_sub(tail)
+    }
+
+    /// Check if no samples are queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of samples dropped because the ring was full (wraps)
+    #[must_use]
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of reads that came up short (wraps)
+    #[must_use]
+    pub fn underruns(&self) -> u32 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Get the slot for a running count
+    fn slot(&self, count: usize) -> &T::Slot {
+        &self.slots[count & Self::MASK]
+    }
+}
+
+impl<T: RingSample, const N: usize> Default for SpscRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writing end of a [`SpscRing`]
+pub struct Producer<'a, T: RingSample, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+impl<T: RingSample, const N: usize> Producer<'_, T, N> {
+    /// Get the number of samples that can be queued without dropping any
+    #[must_use]
+    pub fn free(&self) -> usize {
+        N - self.ring.len()
+    }
+
+    /// Queue one sample
+    ///
+    /// Returns `false`, and counts the sample dropped, if the ring is full.
+    pub fn push(&mut self, sample: T) -> bool {
+        self.push_slice(&[sample]) == 1
+    }
+
+    /// Queue as many samples as fit, returning how many were queued
+    ///
+    /// The rest are counted as dropped.
+    pub fn push_slice(&mut self, samples: &[T]) -> usize {
+        let ring = self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        let free = N - head.wrapping_sub(ring.tail.load(Ordering::Acquire));
+        let count = samples.len().min(free);
+        for (n, &sample) in samples[..count].iter().enumerate() {
+            T::store(ring.slot(head.wrapping_add(n)), sample);
+        }
+        ring.head.store(head.wrapping_add(count), Ordering::Release);
+
+        let dropped = samples.len() - count;
+        if dropped > 0 {
+            let dropped = u32::try_from(dropped).unwrap_or(u32::MAX);
+            ring.dropped.fetch_add(dropped, Ordering::Relaxed);
+        }
+        count
+    }
+
+    /// Get the number of samples dropped because the ring was full (wraps)
+    #[must_use]
+    pub fn dropped(&self) -> u32 {
+        self.ring.dropped()
+    }
+}
+
+/// Reading end of a [`SpscRing`]
+pub struct Consumer<'a, T: RingSample, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+impl<T: RingSample, const N: usize> Consumer<'_, T, N> {
+    /// Get the number of samples queued
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Check if no samples are queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Take the oldest sample, if any
+    pub fn pop(&mut self) -> Option<T> {
+        let mut sample = [T::default()];
+        (self.take(&mut sample) == 1).then_some(sample[0])
+    }
+
+    /// Take up to `out.len()` samples, returning how many were read
+    ///
+    /// A read that finds fewer samples than asked for is counted as an
+    /// underrun; the rest of `out` is left as it was.
+    pub fn pop_slice(&mut self, out: &mut [T]) -> usize {
+        let count = self.take(out);
+        if count < out.len() {
+            self.ring.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+        count
+    }
+
+    /// Discard everything queued, returning how many samples were dropped
+    ///
+    /// Used to catch up after falling behind, so the next read is fresh.
+    pub fn clear(&mut self) -> usize {
+        let ring = self.ring;
+        let head = ring.head.load(Ordering::Acquire);
+        let discarded = head.wrapping_sub(ring.tail.load(Ordering::Relaxed));
+        ring.tail.store(head, Ordering::Release);
+        discarded
+    }
+
+    /// Get the number of reads that came up short (wraps)
+    #[must_use]
+    pub fn underruns(&self) -> u32 {
+        self.ring.underruns()
+    }
+
+    /// Copy out and release up to `out.len()` samples
+    fn take(&mut self, out: &mut [T]) -> usize {
+        let ring = self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let queued = ring.head.load(Ordering::Acquire).wrapping_sub(tail);
+        let count = out.len().min(queued);
+        for (n, sample) in out[..count].iter_mut().enumerate() {
+            *sample = T::load(ring.slot(tail.wrapping_add(n)));
+        }
+        ring.tail.store(tail.wrapping_add(count), Ordering::Release);
+        count
+    }
+}
@@ -1,11 +1,65 @@
 //! User Interface
 //!
 //! Display rendering and menu system for the SDR transceiver.
-
-use crate::drivers::display::{DisplayBuffer, StatusRenderer};
+//!
+//! The UI can run on the radio itself or on a detached front panel. A
+//! remote head keeps its own [`UiState`], feeds it status snapshots with
+//! [`UiState::apply_status`] and sends the resulting actions back with
+//! [`UiAction::to_remote`]; the radio turns them into the same
+//! [`UiAction`]s a local panel produces with [`UiAction::from_remote`].
+//!
+//! # Pages and Redraw
+//!
+//! Each [`Screen`] is a page drawn in full by `render_page` into the
+//! panel's frame buffer. The [`redraw`] scheduler then sends only the
+//! tiles that changed, in short transfers, so the UI task can yield to
+//! the DSP task between them. UI state, actions and redraw scheduling
+//! build on the host; drawing needs the `display` feature.
+//!
+//! # Settings
+//!
+//! The settings screen walks the [`menu`] hierarchy, whose items edit the
+//! [`OperatorConfig`] in place.
+//!
+//! # Scope
+//!
+//! The scope page shows the spectrum over a [`waterfall`] of recent
+//! rows. A press on the scope switches the encoder between tuning and
+//! moving the waterfall's reference level.
+
+pub mod menu;
+pub mod redraw;
+pub mod waterfall;
+
+#[cfg(feature = "display")]
+mod pages;
+
+#[cfg(feature = "display")]
+pub use pages::{
+    render_main_screen, render_memory_screen, render_menu_screen, render_messages_screen,
+    render_page, render_post_screen, render_practice_screen, render_scope_screen,
+    render_settings_screen, render_spectrum, render_waterfall, PageContext,
+};
+
+use crate::clock::DateTime;
+#[cfg(feature = "display")]
 use crate::drivers::encoder::{Direction, EncoderEvent};
-use crate::radio::state::RadioState;
+use crate::protocol::remote_head::{RemoteAction, RemoteStatus};
+#[cfg(feature = "display")]
+use crate::radio::keyer::NUM_MESSAGES;
+use crate::radio::practice::{PracticeReport, PracticeSession, PRACTICE_TEXT_LEN};
+use crate::radio::state::RadioEvent;
+use crate::radio::tx_policy::TxVerdict;
+use crate::settings::OperatorConfig;
 use crate::types::{Frequency, Mode};
+#[cfg(feature = "display")]
+use crate::ui::menu::MenuResponse;
+use crate::ui::menu::{MenuNavigator, SETTINGS_MENU};
+use crate::ui::waterfall::WaterfallView;
+use sdr_dsp_core::conditions::Condition;
+
+/// Number of memory channels in the memory list
+pub const MEMORY_CHANNELS: u8 = 100;
 
 /// UI screen/mode
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -23,8 +77,13 @@ pub enum Screen {
     Settings,
     /// Band scope (if display allows)
     Scope,
+    /// CW message memories
+    Messages,
+    /// Keyer practice (decoded text, no transmit)
+    Practice,
 }
 
+#[cfg(feature = "embedded")]
 impl defmt::Format for Screen {
     fn format(&self, f: defmt::Formatter) {
         match self {
@@ -34,6 +93,8 @@ impl defmt::Format for Screen {
             Self::Memory => defmt::write!(f, "Memory"),
             Self::Settings => defmt::write!(f, "Settings"),
             Self::Scope => defmt::write!(f, "Scope"),
+            Self::Messages => defmt::write!(f, "Messages"),
+            Self::Practice => defmt::write!(f, "Practice"),
         }
     }
 }
@@ -62,6 +123,9 @@ pub enum MenuAction {
     Back,
 }
 
+/// Menu command that centers the received tone ([`RadioEvent::AutoTune`])
+pub const ZERO_BEAT_COMMAND: &str = "ZeroBeat";
+
 /// Main menu items
 pub const MAIN_MENU: &[MenuItem] = &[
     MenuItem {
@@ -80,6 +144,18 @@ pub const MAIN_MENU: &[MenuItem] = &[
         label: "Settings",
         action: MenuAction::GoTo(Screen::Settings),
     },
+    MenuItem {
+        label: "Scope",
+        action: MenuAction::GoTo(Screen::Scope),
+    },
+    MenuItem {
+        label: "Practice",
+        action: MenuAction::GoTo(Screen::Practice),
+    },
+    MenuItem {
+        label: "Zero Beat",
+        action: MenuAction::Execute(ZERO_BEAT_COMMAND),
+    },
     MenuItem {
         label: "Back",
         action: MenuAction::Back,
@@ -95,10 +171,34 @@ pub struct UiState {
     prev_screen: Screen,
     /// Menu selection index
     menu_index: usize,
+    /// Selected CW message (0-based)
+    message_index: usize,
+    /// Selected memory channel
+    memory_index: u8,
+    /// Settings menu position
+    settings_menu: MenuNavigator<OperatorConfig>,
+    /// Mini waterfall reference level and range
+    waterfall: WaterfallView,
+    /// Encoder adjusts the waterfall reference level on the scope
+    scope_adjust: bool,
     /// S-meter level (0-100)
     s_meter: u8,
     /// SWR value
     swr: f32,
+    /// Displayed clock time (None hides the clock)
+    clock: Option<DateTime>,
+    /// Clock shows UTC rather than local time
+    clock_utc: bool,
+    /// Condition rating of the current band (None hides it)
+    band_condition: Option<Condition>,
+    /// Received CW tone offset from the sidetone in Hz (None without a tone)
+    pitch_offset: Option<i16>,
+    /// Transmit policy at the dial (warnings and denials are shown)
+    tx_verdict: TxVerdict,
+    /// Text decoded on the practice screen
+    practice_text: heapless::String<PRACTICE_TEXT_LEN>,
+    /// Timing report on the practice screen
+    practice_report: PracticeReport,
     /// Update flags
     needs_update: bool,
 }
@@ -111,8 +211,26 @@ impl UiState {
             screen: Screen::Main,
             prev_screen: Screen::Main,
             menu_index: 0,
+            message_index: 0,
+            memory_index: 0,
+            settings_menu: MenuNavigator::new(&SETTINGS_MENU),
+            waterfall: WaterfallView::new(),
+            scope_adjust: false,
             s_meter: 0,
             swr: 1.0,
+            clock: None,
+            clock_utc: true,
+            band_condition: None,
+            pitch_offset: None,
+            tx_verdict: TxVerdict::Clear,
+            practice_text: heapless::String::new(),
+            practice_report: PracticeReport {
+                wpm: 0,
+                target_wpm: 0,
+                elements: 0,
+                accuracy: 0,
+                dah_ratio: 0,
+            },
             needs_update: true,
         }
     }
@@ -128,12 +246,17 @@ impl UiState {
         self.prev_screen = self.screen;
         self.screen = screen;
         self.menu_index = 0;
+        self.scope_adjust = false;
+        if screen == Screen::Settings {
+            self.settings_menu.reset();
+        }
         self.needs_update = true;
     }
 
     /// Go back to previous screen
     pub fn go_back(&mut self) {
         self.screen = self.prev_screen;
+        self.scope_adjust = false;
         self.needs_update = true;
     }
 
@@ -153,6 +276,85 @@ impl UiState {
         }
     }
 
+    /// Update displayed clock (redraws only when the minute changes)
+    pub fn set_clock(&mut self, time: Option<DateTime>, utc: bool) {
+        let changed = match (self.clock, time) {
+            (Some(old), Some(new)) => old.minute != new.minute || old.hour != new.hour,
+            (None, None) => false,
+            _ => true,
+        };
+        if changed || self.clock_utc != utc {
+            self.clock = time;
+            self.clock_utc = utc;
+            self.needs_update = true;
+        }
+    }
+
+    /// Update the current band's condition rating
+    pub fn set_band_condition(&mut self, condition: Option<Condition>) {
+        if self.band_condition != condition {
+            self.band_condition = condition;
+            self.needs_update = true;
+        }
+    }
+
+    /// Update the CW tuning indicator (redraws on a 5 Hz change)
+    pub fn set_pitch_offset(&mut self, offset_hz: Option<i16>) {
+        let changed = match (self.pitch_offset, offset_hz) {
+            (Some(old), Some(new)) => old.abs_diff(new) >= 5,
+            (None, None) => false,
+            _ => true,
+        };
+        if changed {
+            self.pitch_offset = offset_hz;
+            self.needs_update = true;
+        }
+    }
+
+    /// Get the CW tuning indicator offset in Hz
+    #[must_use]
+    pub const fn pitch_offset(&self) -> Option<i16> {
+        self.pitch_offset
+    }
+
+    /// Update the transmit policy verdict shown on the main screen
+    pub fn set_tx_verdict(&mut self, verdict: TxVerdict) {
+        if self.tx_verdict != verdict {
+            self.tx_verdict = verdict;
+            self.needs_update = true;
+        }
+    }
+
+    /// Get the transmit policy verdict shown on the main screen
+    #[must_use]
+    pub const fn tx_verdict(&self) -> TxVerdict {
+        self.tx_verdict
+    }
+
+    /// Update the practice screen from a session (redraws on a new
+    /// character or a changed report)
+    pub fn set_practice(&mut self, session: &PracticeSession) {
+        let report = session.report();
+        if self.practice_text != session.text() || self.practice_report != report {
+            self.practice_text.clear();
+            let _ = self.practice_text.push_str(session.text());
+            self.practice_report = report;
+            self.needs_update = true;
+        }
+    }
+
+    /// Get the text shown on the practice screen
+    #[must_use]
+    pub fn practice_text(&self) -> &str {
+        &self.practice_text
+    }
+
+    /// Get the timing report shown on the practice screen
+    #[must_use]
+    pub const fn practice_report(&self) -> &PracticeReport {
+        &self.practice_report
+    }
+
     /// Check if display needs update
     #[must_use]
     pub const fn needs_update(&self) -> bool {
@@ -169,15 +371,68 @@ impl UiState {
         self.needs_update = true;
     }
 
+    /// Update meters and clock from a remote head status snapshot
+    pub fn apply_status(&mut self, status: &RemoteStatus) {
+        self.set_s_meter(status.s_meter);
+        self.set_swr(status.swr);
+        self.set_clock(status.clock, status.clock_utc);
+    }
+
+    /// Get selected menu item
+    #[must_use]
+    pub const fn menu_index(&self) -> usize {
+        self.menu_index
+    }
+
+    /// Get selected memory channel
+    #[must_use]
+    pub const fn memory_index(&self) -> u8 {
+        self.memory_index
+    }
+
+    /// Get the mini waterfall view
+    #[must_use]
+    pub const fn waterfall(&self) -> &WaterfallView {
+        &self.waterfall
+    }
+
+    /// Get the mini waterfall view for adjustment
+    pub fn waterfall_mut(&mut self) -> &mut WaterfallView {
+        self.needs_update = true;
+        &mut self.waterfall
+    }
+
+    /// Check if the encoder adjusts the waterfall reference level
+    #[must_use]
+    pub const fn is_scope_adjust(&self) -> bool {
+        self.scope_adjust
+    }
+
+    /// Get the settings menu position
+    #[must_use]
+    pub const fn settings_menu(&self) -> &MenuNavigator<OperatorConfig> {
+        &self.settings_menu
+    }
+
     /// Handle encoder event
+    ///
+    /// A short press acts on release, so a long press never also
+    /// triggers it. The settings screen edits the configuration, so it
+    /// goes through [`UiState::handle_settings_encoder`] instead.
+    #[cfg(feature = "display")]
     pub fn handle_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
         match self.screen {
             Screen::Main => self.handle_main_encoder(event),
             Screen::Menu => self.handle_menu_encoder(event),
+            Screen::Messages => self.handle_messages_encoder(event),
+            Screen::Memory => self.handle_memory_encoder(event),
+            Screen::Scope => self.handle_scope_encoder(event),
+            Screen::Practice => self.handle_practice_encoder(event),
             _ => None,
         }
     }
 
+    #[cfg(feature = "display")]
     fn handle_main_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
         match event {
             EncoderEvent::Rotate { direction, steps } => {
@@ -187,7 +442,7 @@ impl UiState {
                 };
                 Some(UiAction::Tune(delta))
             }
-            EncoderEvent::ButtonPress => Some(UiAction::NextStep),
+            EncoderEvent::ButtonRelease => Some(UiAction::NextStep),
             EncoderEvent::LongPress => {
                 self.set_screen(Screen::Menu);
                 None
@@ -196,6 +451,133 @@ impl UiState {
         }
     }
 
+    /// Scope screen: rotate tunes or moves the reference level, press
+    /// switches between them, long press exits
+    #[cfg(feature = "display")]
+    fn handle_scope_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
+        match event {
+            EncoderEvent::Rotate { direction, steps } if self.scope_adjust => {
+                let steps = i32::try_from(steps).unwrap_or(i32::MAX);
+                self.waterfall.adjust_ref_level(match direction {
+                    Direction::Clockwise => steps,
+                    Direction::CounterClockwise => -steps,
+                });
+                self.needs_update = true;
+                None
+            }
+            EncoderEvent::ButtonRelease => {
+                self.scope_adjust = !self.scope_adjust;
+                self.needs_update = true;
+                None
+            }
+            EncoderEvent::LongPress => {
+                self.go_back();
+                None
+            }
+            _ => self.handle_main_encoder(event),
+        }
+    }
+
+    /// Practice screen: long press exits (the paddles do the rest)
+    #[cfg(feature = "display")]
+    fn handle_practice_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
+        if matches!(event, EncoderEvent::LongPress) {
+            self.go_back();
+        }
+        None
+    }
+
+    /// Get selected CW message (0-based)
+    #[must_use]
+    pub const fn message_index(&self) -> usize {
+        self.message_index
+    }
+
+    /// Messages screen: rotate selects, press plays, long press aborts and exits
+    #[cfg(feature = "display")]
+    fn handle_messages_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
+        match event {
+            EncoderEvent::Rotate { direction, .. } => {
+                self.message_index = match direction {
+                    Direction::Clockwise => (self.message_index + 1) % NUM_MESSAGES,
+                    Direction::CounterClockwise => {
+                        (self.message_index + NUM_MESSAGES - 1) % NUM_MESSAGES
+                    }
+                };
+                self.needs_update = true;
+                None
+            }
+            EncoderEvent::ButtonRelease => Some(UiAction::PlayMessage(self.message_index as u8)),
+            EncoderEvent::LongPress => {
+                self.go_back();
+                Some(UiAction::AbortMessage)
+            }
+            _ => None,
+        }
+    }
+
+    /// Memory screen: rotate selects, press recalls and exits, long press exits
+    #[cfg(feature = "display")]
+    fn handle_memory_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
+        match event {
+            EncoderEvent::Rotate { direction, .. } => {
+                self.memory_index = match direction {
+                    Direction::Clockwise => (self.memory_index + 1) % MEMORY_CHANNELS,
+                    Direction::CounterClockwise => {
+                        (self.memory_index + MEMORY_CHANNELS - 1) % MEMORY_CHANNELS
+                    }
+                };
+                self.needs_update = true;
+                None
+            }
+            EncoderEvent::ButtonRelease => {
+                self.set_screen(Screen::Main);
+                Some(UiAction::RecallMemory(self.memory_index))
+            }
+            EncoderEvent::LongPress => {
+                self.go_back();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Settings screen: rotate selects or edits, press opens or finishes,
+    /// long press goes up a level
+    ///
+    /// Returns `true` when `config` changed, so the caller can apply and
+    /// save it.
+    #[cfg(feature = "display")]
+    pub fn handle_settings_encoder(
+        &mut self,
+        event: EncoderEvent,
+        config: &mut OperatorConfig,
+    ) -> bool {
+        let response = match event {
+            EncoderEvent::Rotate { direction, steps } => {
+                let steps = i32::try_from(steps).unwrap_or(i32::MAX);
+                let delta = match direction {
+                    Direction::Clockwise => steps,
+                    Direction::CounterClockwise => -steps,
+                };
+                self.settings_menu.rotate(delta, config)
+            }
+            EncoderEvent::ButtonRelease => self.settings_menu.press(config),
+            EncoderEvent::LongPress => self.settings_menu.back(),
+            _ => return false,
+        };
+        self.needs_update = true;
+        match response {
+            MenuResponse::Changed => true,
+            MenuResponse::Exit => {
+                self.go_back();
+                false
+            }
+            MenuResponse::None => false,
+        }
+    }
+
+    #[cfg(feature = "display")]
     fn handle_menu_encoder(&mut self, event: EncoderEvent) -> Option<UiAction> {
         match event {
             EncoderEvent::Rotate { direction, .. } => {
@@ -214,7 +596,7 @@ impl UiState {
                 self.needs_update = true;
                 None
             }
-            EncoderEvent::ButtonPress => {
+            EncoderEvent::ButtonRelease => {
                 let item = &MAIN_MENU[self.menu_index];
                 match item.action {
                     MenuAction::GoTo(screen) => {
@@ -256,8 +638,86 @@ pub enum UiAction {
     TogglePtt,
     /// Execute command by name
     Execute(&'static str),
+    /// Play stored CW message (0-based)
+    PlayMessage(u8),
+    /// Abort CW message playback
+    AbortMessage,
+    /// Recall memory channel
+    RecallMemory(u8),
 }
 
+impl UiAction {
+    /// Convert to an event for the radio state machine
+    ///
+    /// `None` for actions the main loop handles itself (other menu
+    /// commands, CW messages and memory recall).
+    #[must_use]
+    pub const fn to_radio_event(self, transmitting: bool) -> Option<RadioEvent> {
+        match self {
+            Self::Tune(steps) => Some(RadioEvent::Tune(steps)),
+            Self::SetFrequency(freq) => Some(RadioEvent::SetFrequency(freq)),
+            Self::SetMode(mode) => Some(RadioEvent::SetMode(mode)),
+            Self::NextStep => Some(RadioEvent::NextStep),
+            Self::TogglePtt if transmitting => Some(RadioEvent::StopTx),
+            Self::TogglePtt => Some(RadioEvent::StartTx),
+            // `ZERO_BEAT_COMMAND`, compared as bytes to stay const
+            Self::Execute(cmd) if matches!(cmd.as_bytes(), b"ZeroBeat") => {
+                Some(RadioEvent::AutoTune)
+            }
+            Self::Execute(_)
+            | Self::PlayMessage(_)
+            | Self::AbortMessage
+            | Self::RecallMemory(_) => None,
+        }
+    }
+
+    /// Convert to a remote head action
+    #[must_use]
+    pub fn to_remote(self) -> RemoteAction {
+        match self {
+            Self::Tune(steps) => RemoteAction::Tune(steps),
+            Self::SetFrequency(freq) => RemoteAction::SetFrequency(freq),
+            Self::SetMode(mode) => RemoteAction::SetMode(mode),
+            Self::NextStep => RemoteAction::NextStep,
+            Self::TogglePtt => RemoteAction::TogglePtt,
+            Self::Execute(cmd) => {
+                let mut name = heapless::String::new();
+                let _ = name.push_str(cmd);
+                RemoteAction::Execute(name)
+            }
+            Self::PlayMessage(index) => RemoteAction::PlayMessage(index),
+            Self::AbortMessage => RemoteAction::AbortMessage,
+            Self::RecallMemory(channel) => RemoteAction::RecallMemory(channel),
+        }
+    }
+
+    /// Convert an action received from a remote head
+    ///
+    /// `Execute` names are matched against the menu so the radio only
+    /// runs commands its own menu offers.
+    #[must_use]
+    pub fn from_remote(action: &RemoteAction) -> Option<Self> {
+        Some(match action {
+            RemoteAction::Tune(steps) => Self::Tune(*steps),
+            RemoteAction::SetFrequency(freq) => Self::SetFrequency(*freq),
+            RemoteAction::SetMode(mode) => Self::SetMode(*mode),
+            RemoteAction::NextStep => Self::NextStep,
+            RemoteAction::TogglePtt => Self::TogglePtt,
+            RemoteAction::Execute(name) => {
+                let cmd = MAIN_MENU.iter().find_map(|item| match item.action {
+                    MenuAction::Execute(cmd) if cmd == name.as_str() => Some(cmd),
+                    _ => None,
+                })?;
+                Self::Execute(cmd)
+            }
+            RemoteAction::PlayMessage(index) => Self::PlayMessage(*index),
+            RemoteAction::AbortMessage => Self::AbortMessage,
+            RemoteAction::RecallMemory(channel) => Self::RecallMemory(*channel),
+        })
+    }
+}
+
+#[cfg(feature = "embedded")]
 impl defmt::Format for UiAction {
     fn format(&self, f: defmt::Formatter) {
         match self {
@@ -267,72 +727,9 @@ impl defmt::Format for UiAction {
             Self::NextStep => defmt::write!(f, "NextStep"),
             Self::TogglePtt => defmt::write!(f, "TogglePtt"),
             Self::Execute(cmd) => defmt::write!(f, "Exec({})", cmd),
-        }
-    }
-}
-
-/// Render the main screen
-pub fn render_main_screen(buffer: &mut DisplayBuffer, state: &RadioState, ui: &UiState) {
-    buffer.clear();
-
-    // Render band
-    StatusRenderer::render_band(buffer, state.band());
-
-    // Render mode
-    StatusRenderer::render_mode(buffer, state.mode());
-
-    // Render TX/RX
-    StatusRenderer::render_txrx(buffer, state.txrx());
-
-    // Render frequency
-    StatusRenderer::render_frequency(buffer, state.frequency());
-
-    // Render tuning step
-    StatusRenderer::render_step(buffer, state.step());
-
-    // Render S-meter
-    StatusRenderer::render_smeter(buffer, ui.s_meter);
-
-    // Render SWR if transmitting
-    if state.is_transmitting() {
-        StatusRenderer::render_swr(buffer, ui.swr);
-    }
-}
-
-/// Render the menu screen
-pub fn render_menu_screen(buffer: &mut DisplayBuffer, menu_index: usize) {
-    use embedded_graphics::mono_font::ascii::FONT_6X10;
-    use embedded_graphics::mono_font::MonoTextStyle;
-    use embedded_graphics::pixelcolor::BinaryColor;
-    use embedded_graphics::prelude::*;
-    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
-    use embedded_graphics::text::{Baseline, Text};
-
-    buffer.clear();
-
-    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
-    let inv_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
-
-    // Title
-    let _ = Text::with_baseline("MENU", Point::new(50, 0), style, Baseline::Top).draw(buffer);
-
-    // Menu items
-    for (i, item) in MAIN_MENU.iter().enumerate() {
-        let y = 14 + i as i32 * 10;
-
-        if i == menu_index {
-            // Highlight selected item
-            let rect = Rectangle::new(Point::new(0, y - 1), Size::new(128, 10));
-            let _ = rect
-                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-                .draw(buffer);
-            let _ =
-                Text::with_baseline(item.label, Point::new(4, y), inv_style, Baseline::Top)
-                    .draw(buffer);
-        } else {
-            let _ =
-                Text::with_baseline(item.label, Point::new(4, y), style, Baseline::Top)
-                    .draw(buffer);
+            Self::PlayMessage(index) => defmt::write!(f, "PlayMsg({})", index),
+            Self::AbortMessage => defmt::write!(f, "AbortMsg"),
+            Self::RecallMemory(channel) => defmt::write!(f, "RecallMem({})", channel),
         }
     }
 }
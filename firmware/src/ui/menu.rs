@@ -0,0 +1,508 @@
+//! Settings Menu
+//!
+//! A hierarchy of typed menu items bound to the fields of a
+//! configuration struct. Each item carries plain `fn` accessors for its
+//! field, so menus are `static` tables with no allocation, and the
+//! navigator edits the configuration in place:
+//!
+//! - [`ItemKind::Toggle`] flips a boolean on a press
+//! - [`ItemKind::Number`] edits an integer within a range, in steps
+//! - [`ItemKind::Choice`] picks one of a list of labels
+//! - [`ItemKind::Submenu`] opens a nested menu
+//!
+//! With the encoder, turning moves the selection and a press opens the
+//! item; while a number or choice is being edited, turning changes it
+//! and a press (or long press) finishes. A long press goes up a level,
+//! leaving the menu from the top.
+//!
+//! [`SETTINGS_MENU`] binds the keyer, AGC, VOX, equalizer and display
+//! settings of [`OperatorConfig`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut nav = MenuNavigator::new(&SETTINGS_MENU);
+//! match nav.rotate(1, settings.config_mut()) {
+//!     MenuResponse::Changed => {
+//!         settings.config().apply_keyer(&mut keyer);
+//!         settings.save(&mut store)?;
+//!     }
+//!     MenuResponse::Exit => ui.go_back(),
+//!     MenuResponse::None => {}
+//! }
+//! ```
+
+use core::fmt::Write;
+
+use heapless::{String, Vec};
+
+use crate::radio::keyer::Keyer;
+use crate::radio::state::AgcMode;
+use crate::radio::transmit::{VOX_DELAY_MAX_MS, VOX_GAIN_MAX};
+use crate::settings::{OperatorConfig, BRIGHTNESS_MAX};
+
+/// Deepest menu nesting
+pub const MAX_DEPTH: usize = 4;
+
+/// Longest value text
+pub const VALUE_LEN: usize = 12;
+
+/// What a menu item edits
+pub enum ItemKind<T: 'static> {
+    /// Nested menu
+    Submenu(&'static Menu<T>),
+    /// Boolean, flipped by a press
+    Toggle {
+        /// Read the field
+        get: fn(&T) -> bool,
+        /// Write the field
+        set: fn(&mut T, bool),
+    },
+    /// Integer within a range
+    Number {
+        /// Read the field
+        get: fn(&T) -> i32,
+        /// Write the field (called with values within the range)
+        set: fn(&mut T, i32),
+        /// Lowest value
+        min: i32,
+        /// Highest value
+        max: i32,
+        /// Change per encoder detent
+        step: i32,
+        /// Unit shown after the value
+        unit: &'static str,
+    },
+    /// One of a list of labels
+    Choice {
+        /// Read the field as an index into `labels`
+        get: fn(&T) -> usize,
+        /// Write the field from an index into `labels`
+        set: fn(&mut T, usize),
+        /// Labels of the choices
+        labels: &'static [&'static str],
+    },
+    /// Up to the parent menu
+    Back,
+}
+
+/// Menu item
+pub struct Item<T: 'static> {
+    /// Item label
+    pub label: &'static str,
+    /// What the item edits
+    pub kind: ItemKind<T>,
+}
+
+impl<T> Item<T> {
+    /// Format the item's current value (empty for submenus and back)
+    #[must_use]
+    pub fn value(&self, config: &T) -> String<VALUE_LEN> {
+        let mut s = String::new();
+        match &self.kind {
+            ItemKind::Toggle { get, .. } => {
+                let _ = s.push_str(if get(config) { "On" } else { "Off" });
+            }
+            ItemKind::Number { get, unit, .. } => {
+                let _ = write!(s, "{}{}", get(config), unit);
+            }
+            ItemKind::Choice { get, labels, .. } => {
+                let _ = s.push_str(labels.get(get(config)).copied().unwrap_or("?"));
+            }
+            ItemKind::Submenu(_) => {
+                let _ = s.push('>');
+            }
+            ItemKind::Back => {}
+        }
+        s
+    }
+
+    /// Check if the item is edited by turning the encoder
+    #[must_use]
+    pub const fn is_editable(&self) -> bool {
+        matches!(self.kind, ItemKind::Number { .. } | ItemKind::Choice { .. })
+    }
+}
+
+/// A menu level
+pub struct Menu<T: 'static> {
+    /// Title shown above the items
+    pub title: &'static str,
+    /// Items in display order
+    pub items: &'static [Item<T>],
+}
+
+/// Result of a navigator input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuResponse {
+    /// Nothing for the caller to do (selection moved, level changed)
+    None,
+    /// A configuration field changed
+    Changed,
+    /// Left the top level
+    Exit,
+}
+
+/// Encoder navigation through a menu hierarchy
+pub struct MenuNavigator<T: 'static> {
+    /// Current level
+    menu: &'static Menu<T>,
+    /// Selected item in the current level
+    index: usize,
+    /// Parent levels with their selections
+    parents: Vec<(&'static Menu<T>, usize), MAX_DEPTH>,
+    /// Selected item is being edited
+    editing: bool,
+}
+
+impl<T> MenuNavigator<T> {
+    /// Create a navigator at the top of `root`
+    #[must_use]
+    pub const fn new(root: &'static Menu<T>) -> Self {
+        Self {
+            menu: root,
+            index: 0,
+            parents: Vec::new(),
+            editing: false,
+        }
+    }
+
+    /// Return to the top of the menu
+    pub fn reset(&mut self) {
+        if let Some(&(root, _)) = self.parents.first() {
+            self.menu = root;
+        }
+        self.parents.clear();
+        self.index = 0;
+        self.editing = false;
+    }
+
+    /// Get the current level
+    #[must_use]
+    pub const fn menu(&self) -> &'static Menu<T> {
+        self.menu
+    }
+
+    /// Get the selected item index
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get the selected item
+    #[must_use]
+    pub fn selected(&self) -> Option<&'static Item<T>> {
+        self.menu.items.get(self.index)
+    }
+
+    /// Check if the selected item is being edited
+    #[must_use]
+    pub const fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Get the nesting depth (0 at the top)
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Turn the encoder by `steps` detents
+    ///
+    /// Moves the selection, wrapping around, or changes the value being
+    /// edited, stopping at the ends of its range.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss
+    )]
+    pub fn rotate(&mut self, steps: i32, config: &mut T) -> MenuResponse {
+        let count = self.menu.items.len();
+        if count == 0 || steps == 0 {
+            return MenuResponse::None;
+        }
+        if !self.editing {
+            let moved = (self.index as i64 + i64::from(steps)).rem_euclid(count as i64);
+            self.index = moved as usize;
+            return MenuResponse::None;
+        }
+
+        match self.menu.items[self.index].kind {
+            ItemKind::Number {
+                get,
+                set,
+                min,
+                max,
+                step,
+                ..
+            } => {
+                let old = get(config);
+                let value = old
+                    .saturating_add(steps.saturating_mul(step))
+                    .clamp(min, max);
+                if value == old {
+                    return MenuResponse::None;
+                }
+                set(config, value);
+            }
+            ItemKind::Choice { get, set, labels } => {
+                let old = get(config) as i64;
+                let last = labels.len() as i64 - 1;
+                let value = (old + i64::from(steps)).clamp(0, last.max(0));
+                if value == old {
+                    return MenuResponse::None;
+                }
+                set(config, value as usize);
+            }
+            _ => return MenuResponse::None,
+        }
+        MenuResponse::Changed
+    }
+
+    /// Press the encoder button (short press)
+    pub fn press(&mut self, config: &mut T) -> MenuResponse {
+        if self.editing {
+            self.editing = false;
+            return MenuResponse::None;
+        }
+        let Some(item) = self.selected() else {
+            return MenuResponse::None;
+        };
+        match item.kind {
+            ItemKind::Submenu(menu) => {
+                if self.parents.push((self.menu, self.index)).is_ok() {
+                    self.menu = menu;
+                    self.index = 0;
+                }
+                MenuResponse::None
+            }
+            ItemKind::Toggle { get, set } => {
+                set(config, !get(config));
+                MenuResponse::Changed
+            }
+            ItemKind::Number { .. } | ItemKind::Choice { .. } => {
+                self.editing = true;
+                MenuResponse::None
+            }
+            ItemKind::Back => self.back(),
+        }
+    }
+
+    /// Go up a level (long press), finishing any edit first
+    pub fn back(&mut self) -> MenuResponse {
+        if self.editing {
+            self.editing = false;
+            return MenuResponse::None;
+        }
+        let Some((menu, index)) = self.parents.pop() else {
+            self.index = 0;
+            return MenuResponse::Exit;
+        };
+        self.menu = menu;
+        self.index = index;
+        MenuResponse::None
+    }
+}
+
+impl<T> core::fmt::Debug for MenuNavigator<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MenuNavigator")
+            .field("menu", &self.menu.title)
+            .field("index", &self.index)
+            .field("depth", &self.parents.len())
+            .field("editing", &self.editing)
+            .finish()
+    }
+}
+
+impl<T> Clone for MenuNavigator<T> {
+    fn clone(&self) -> Self {
+        Self {
+            menu: self.menu,
+            index: self.index,
+            parents: self.parents.clone(),
+            editing: self.editing,
+        }
+    }
+}
+
+/// AGC choice labels, in [`AgcMode::code`] order
+const AGC_LABELS: &[&str] = &["Off", "Fast", "Medium", "Slow"];
+
+/// Keyer settings
+pub static KEYER_MENU: Menu<OperatorConfig> = Menu {
+    title: "KEYER",
+    items: &[
+        Item {
+            label: "Speed",
+            kind: ItemKind::Number {
+                get: |c| i32::from(c.keyer_wpm),
+                set: |c, v| c.keyer_wpm = u8::try_from(v).unwrap_or(Keyer::DEFAULT_WPM),
+                min: Keyer::MIN_WPM as i32,
+                max: Keyer::MAX_WPM as i32,
+                step: 1,
+                unit: " WPM",
+            },
+        },
+        Item {
+            label: "Sidetone",
+            kind: ItemKind::Number {
+                get: |c| i32::from(c.sidetone_hz),
+                set: |c, v| {
+                    c.sidetone_hz = u16::try_from(v).unwrap_or(Keyer::DEFAULT_SIDETONE_HZ);
+                },
+                min: Keyer::SIDETONE_RANGE_HZ.0 as i32,
+                max: Keyer::SIDETONE_RANGE_HZ.1 as i32,
+                step: 10,
+                unit: " Hz",
+            },
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
+
+/// Receiver settings
+pub static RECEIVE_MENU: Menu<OperatorConfig> = Menu {
+    title: "RECEIVE",
+    items: &[
+        Item {
+            label: "AGC",
+            kind: ItemKind::Choice {
+                get: |c| usize::from(c.agc.code()),
+                set: |c, i| {
+                    c.agc = u8::try_from(i)
+                        .ok()
+                        .and_then(AgcMode::from_code)
+                        .unwrap_or_default();
+                },
+                labels: AGC_LABELS,
+            },
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
+
+/// VOX settings
+#[allow(clippy::cast_possible_wrap)]
+pub static VOX_MENU: Menu<OperatorConfig> = Menu {
+    title: "VOX",
+    items: &[
+        Item {
+            label: "VOX",
+            kind: ItemKind::Toggle {
+                get: |c| c.vox,
+                set: |c, v| c.vox = v,
+            },
+        },
+        Item {
+            label: "Gain",
+            kind: ItemKind::Number {
+                get: |c| i32::from(c.vox_gain),
+                set: |c, v| c.vox_gain = u8::try_from(v).unwrap_or(0),
+                min: 0,
+                max: VOX_GAIN_MAX as i32,
+                step: 1,
+                unit: "",
+            },
+        },
+        Item {
+            label: "Delay",
+            kind: ItemKind::Number {
+                get: |c| i32::from(c.vox_delay_ms),
+                set: |c, v| c.vox_delay_ms = u16::try_from(v).unwrap_or(0),
+                min: 0,
+                max: VOX_DELAY_MAX_MS as i32,
+                step: 50,
+                unit: " ms",
+            },
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
+
+/// Equalizer switches (the curves are set per mode over CAT)
+pub static EQUALIZER_MENU: Menu<OperatorConfig> = Menu {
+    title: "EQUALIZER",
+    items: &[
+        Item {
+            label: "RX EQ",
+            kind: ItemKind::Toggle {
+                get: |c| c.rx_eq,
+                set: |c, v| c.rx_eq = v,
+            },
+        },
+        Item {
+            label: "TX EQ",
+            kind: ItemKind::Toggle {
+                get: |c| c.tx_eq,
+                set: |c, v| c.tx_eq = v,
+            },
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
+
+/// Display settings
+pub static DISPLAY_MENU: Menu<OperatorConfig> = Menu {
+    title: "DISPLAY",
+    items: &[
+        Item {
+            label: "Brightness",
+            kind: ItemKind::Number {
+                get: |c| i32::from(c.brightness),
+                set: |c, v| c.brightness = u8::try_from(v).unwrap_or(BRIGHTNESS_MAX),
+                min: 0,
+                max: BRIGHTNESS_MAX as i32,
+                step: 5,
+                unit: "%",
+            },
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
+
+/// Top of the settings menu
+pub static SETTINGS_MENU: Menu<OperatorConfig> = Menu {
+    title: "SETTINGS",
+    items: &[
+        Item {
+            label: "Keyer",
+            kind: ItemKind::Submenu(&KEYER_MENU),
+        },
+        Item {
+            label: "Receive",
+            kind: ItemKind::Submenu(&RECEIVE_MENU),
+        },
+        Item {
+            label: "VOX",
+            kind: ItemKind::Submenu(&VOX_MENU),
+        },
+        Item {
+            label: "Equalizer",
+            kind: ItemKind::Submenu(&EQUALIZER_MENU),
+        },
+        Item {
+            label: "Display",
+            kind: ItemKind::Submenu(&DISPLAY_MENU),
+        },
+        Item {
+            label: "Back",
+            kind: ItemKind::Back,
+        },
+    ],
+};
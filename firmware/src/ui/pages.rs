@@ -0,0 +1,517 @@
+//! Screen Pages
+//!
+//! One render function per [`Screen`], all laid out for a 128x64 panel
+//! and drawn in full each frame; the redraw scheduler sends only what
+//! changed. [`render_page`] picks the page for the current screen.
+
+use crate::drivers::display::{DisplayBuffer, StatusRenderer};
+use crate::dsp::spectrum::{WaterfallBuffer, WaterfallRow};
+use crate::radio::keyer::{CwMemory, NUM_MESSAGES};
+use crate::radio::state::RadioState;
+use crate::radio::vfo::MemoryBank;
+use crate::selftest::PostReport;
+use crate::settings::OperatorConfig;
+use crate::types::Mode;
+use embedded_graphics::mono_font::ascii::FONT_6X10;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Baseline, Text};
+use heapless::String;
+use sdr_dsp_core::units;
+
+use super::menu::MenuNavigator;
+use super::redraw::Geometry;
+use super::waterfall::{WaterfallView, WATERFALL_ROWS};
+use super::{Screen, UiState, MAIN_MENU, MEMORY_CHANNELS};
+
+/// Height of the 128x64 page layout
+const MAIN_HEIGHT: u32 = 64;
+
+/// List rows below a title line
+const LIST_ROWS: usize = 5;
+
+/// Everything the pages draw from
+#[derive(Clone, Copy)]
+pub struct PageContext<'a> {
+    /// Radio state
+    pub state: &'a RadioState,
+    /// UI state (screen, selections, meters)
+    pub ui: &'a UiState,
+    /// Memory channels
+    pub memories: &'a MemoryBank,
+    /// CW message memories
+    pub messages: &'a CwMemory,
+    /// Operator configuration shown in the settings menu
+    pub config: &'a OperatorConfig,
+    /// Latest spectrum row, if the DSP is producing one
+    pub spectrum: Option<&'a WaterfallRow>,
+    /// Recent spectrum rows for the waterfall
+    pub waterfall: Option<&'a WaterfallBuffer<WATERFALL_ROWS>>,
+}
+
+/// Render the page for the current screen
+pub fn render_page(buffer: &mut DisplayBuffer, ctx: &PageContext<'_>) {
+    match ctx.ui.screen() {
+        Screen::Menu => render_menu_screen(buffer, ctx.ui.menu_index()),
+        Screen::Memory => render_memory_screen(buffer, ctx.memories, ctx.ui.memory_index()),
+        Screen::Scope => {
+            render_scope_screen(buffer, ctx.state, ctx.ui, ctx.spectrum, ctx.waterfall);
+        }
+        Screen::Messages => render_messages_screen(buffer, ctx.messages, ctx.ui.message_index()),
+        Screen::Settings => render_settings_screen(buffer, ctx.ui.settings_menu(), ctx.config),
+        Screen::Practice => render_practice_screen(buffer, ctx.ui),
+        Screen::Main | Screen::VfoEdit => {
+            render_main_screen(buffer, ctx.state, ctx.ui, ctx.spectrum);
+        }
+    }
+}
+
+/// Render the main screen
+///
+/// Panels taller than 64 rows show the spectrum below the dial.
+pub fn render_main_screen(
+    buffer: &mut DisplayBuffer,
+    state: &RadioState,
+    ui: &UiState,
+    spectrum: Option<&WaterfallRow>,
+) {
+    buffer.clear();
+
+    // Render band
+    StatusRenderer::render_band(buffer, state.band());
+    StatusRenderer::render_conditions(buffer, ui.band_condition);
+
+    // Render mode
+    StatusRenderer::render_mode(buffer, state.mode());
+
+    // Render TX/RX
+    StatusRenderer::render_txrx(buffer, state.txrx());
+
+    // Render frequency, and below it any transmit policy notice
+    StatusRenderer::render_frequency(buffer, state.frequency());
+    StatusRenderer::render_tx_notice(buffer, ui.tx_verdict());
+
+    // Render tuning step
+    StatusRenderer::render_step(buffer, state.step());
+
+    // Render S-meter
+    StatusRenderer::render_smeter(buffer, ui.s_meter);
+
+    // Render SWR if transmitting, otherwise the clock and in CW the tuning indicator
+    if state.is_transmitting() {
+        StatusRenderer::render_swr(buffer, ui.swr);
+    } else {
+        if let Some(time) = ui.clock {
+            StatusRenderer::render_clock(buffer, &time, ui.clock_utc);
+        }
+        if matches!(state.mode(), Mode::Cw | Mode::CwR) {
+            StatusRenderer::render_tuning(buffer, ui.pitch_offset);
+        }
+    }
+
+    if let Some(row) = spectrum {
+        let size = buffer.size();
+        if size.height >= 2 * MAIN_HEIGHT {
+            let area = Rectangle::new(
+                Point::new(0, MAIN_HEIGHT as i32),
+                Size::new(size.width, size.height - MAIN_HEIGHT),
+            );
+            render_spectrum(buffer, row, area);
+        }
+    }
+}
+
+/// Render the menu screen
+///
+/// Scrolls to keep the selected item among the five shown.
+pub fn render_menu_screen(buffer: &mut DisplayBuffer, menu_index: usize) {
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let inv_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+
+    // Title
+    let _ = Text::with_baseline("MENU", Point::new(50, 0), style, Baseline::Top).draw(buffer);
+
+    // Menu items
+    let first = menu_index.saturating_sub(LIST_ROWS - 1);
+    for (i, item) in MAIN_MENU.iter().enumerate().skip(first).take(LIST_ROWS) {
+        let y = 14 + (i - first) as i32 * 10;
+
+        if i == menu_index {
+            // Highlight selected item
+            let rect = Rectangle::new(Point::new(0, y - 1), Size::new(128, 10));
+            let _ = rect
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+            let _ =
+                Text::with_baseline(item.label, Point::new(4, y), inv_style, Baseline::Top)
+                    .draw(buffer);
+        } else {
+            let _ =
+                Text::with_baseline(item.label, Point::new(4, y), style, Baseline::Top)
+                    .draw(buffer);
+        }
+    }
+}
+
+/// Render the settings menu
+///
+/// Items are listed with their current values; the value being edited
+/// is highlighted instead of the whole row.
+pub fn render_settings_screen(
+    buffer: &mut DisplayBuffer,
+    menu: &MenuNavigator<OperatorConfig>,
+    config: &OperatorConfig,
+) {
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let inv_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+    let level = menu.menu();
+
+    // Title
+    let _ = Text::with_baseline(level.title, Point::new(0, 0), style, Baseline::Top).draw(buffer);
+
+    // Items with values
+    let index = menu.index();
+    let first = index.saturating_sub(LIST_ROWS - 1);
+    for (i, item) in level.items.iter().enumerate().skip(first).take(LIST_ROWS) {
+        let y = 14 + (i - first) as i32 * 10;
+        let value = item.value(config);
+        let value_x = 124 - value.len() as i32 * 6;
+        let selected = i == index;
+        let editing = selected && menu.is_editing();
+
+        if selected && !editing {
+            let rect = Rectangle::new(Point::new(0, y - 1), Size::new(128, 10));
+            let _ = rect
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+        } else if editing {
+            let width = value.len() as u32 * 6 + 4;
+            let rect = Rectangle::new(Point::new(value_x - 2, y - 1), Size::new(width, 10));
+            let _ = rect
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+        }
+
+        let label_style = if selected && !editing { inv_style } else { style };
+        let value_style = if selected { inv_style } else { style };
+        let _ = Text::with_baseline(item.label, Point::new(4, y), label_style, Baseline::Top)
+            .draw(buffer);
+        let _ = Text::with_baseline(&value, Point::new(value_x, y), value_style, Baseline::Top)
+            .draw(buffer);
+    }
+}
+
+/// Render the power-on self test report
+///
+/// Two columns of three checks with an overall verdict as the title.
+pub fn render_post_screen(buffer: &mut DisplayBuffer, report: &PostReport) {
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+    // Title
+    let title = if report.passed() { "POST PASS" } else { "POST FAIL" };
+    let _ = Text::with_baseline(title, Point::new(37, 0), style, Baseline::Top).draw(buffer);
+
+    // Checks
+    for (i, (item, result)) in report.iter().enumerate() {
+        let x = if i < 3 { 0 } else { 66 };
+        let y = 16 + (i % 3) as i32 * 14;
+
+        let mut s: String<10> = String::new();
+        let _ = core::fmt::write(&mut s, format_args!("{:<7}{}", item.label(), result.as_str()));
+        let _ = Text::with_baseline(&s, Point::new(x, y), style, Baseline::Top).draw(buffer);
+    }
+}
+
+/// Render the CW message screen
+///
+/// Shows the selected slot with its expanded text wrapped over four lines.
+pub fn render_messages_screen(buffer: &mut DisplayBuffer, memory: &CwMemory, index: usize) {
+
+    /// Characters per line in the 6x10 font
+    const LINE_CHARS: usize = 21;
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+    // Title
+    let mut title: String<20> = String::new();
+    let _ = core::fmt::write(
+        &mut title,
+        format_args!("CW MSG {}/{} #{:03}", index + 1, NUM_MESSAGES, memory.serial()),
+    );
+    let _ = Text::with_baseline(&title, Point::new(0, 0), style, Baseline::Top).draw(buffer);
+
+    // Message text
+    let Some(text) = memory.expand(index) else {
+        let _ = Text::with_baseline("(empty)", Point::new(0, 14), style, Baseline::Top)
+            .draw(buffer);
+        return;
+    };
+    for (line, chunk) in text.as_bytes().chunks(LINE_CHARS).take(4).enumerate() {
+        if let Ok(chunk) = core::str::from_utf8(chunk) {
+            let y = 14 + line as i32 * 12;
+            let _ = Text::with_baseline(chunk, Point::new(0, y), style, Baseline::Top).draw(buffer);
+        }
+    }
+}
+
+/// Render the keyer practice screen
+///
+/// Speeds in the title, the most recent decoded text over three lines
+/// and the timing report at the bottom.
+pub fn render_practice_screen(buffer: &mut DisplayBuffer, ui: &UiState) {
+    /// Characters per line in the 6x10 font
+    const LINE_CHARS: usize = 21;
+    /// Lines of decoded text
+    const TEXT_LINES: usize = 3;
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let report = ui.practice_report();
+
+    // Title: measured against set speed
+    let mut title: String<21> = String::new();
+    let _ = core::fmt::write(
+        &mut title,
+        format_args!("PRACTICE {:>2}/{:<2} WPM", report.wpm, report.target_wpm),
+    );
+    let _ = Text::with_baseline(&title, Point::new(0, 0), style, Baseline::Top).draw(buffer);
+
+    // Decoded text, the last full lines and the one being filled
+    let text = ui.practice_text().as_bytes();
+    let lines = text.len().div_ceil(LINE_CHARS);
+    let first = lines.saturating_sub(TEXT_LINES);
+    for (row, chunk) in text.chunks(LINE_CHARS).skip(first).enumerate() {
+        if let Ok(chunk) = core::str::from_utf8(chunk) {
+            let y = 14 + row as i32 * 12;
+            let _ = Text::with_baseline(chunk, Point::new(0, y), style, Baseline::Top).draw(buffer);
+        }
+    }
+
+    // Timing report
+    if report.elements > 0 {
+        let mut line: String<21> = String::new();
+        let _ = core::fmt::write(&mut line, format_args!("ACC {:3}%", report.accuracy));
+        if report.dah_ratio > 0 {
+            let _ = core::fmt::write(
+                &mut line,
+                format_args!("  1:{}.{}", report.dah_ratio / 10, report.dah_ratio % 10),
+            );
+        }
+        let _ = Text::with_baseline(&line, Point::new(0, 52), style, Baseline::Top).draw(buffer);
+    }
+}
+
+/// Render the memory channel list
+///
+/// Five channels around the selected one, each with its name (or
+/// frequency when unnamed) and mode.
+pub fn render_memory_screen(buffer: &mut DisplayBuffer, memories: &MemoryBank, selected: u8) {
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let inv_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+
+    // Title
+    let mut title: String<20> = String::new();
+    let _ = core::fmt::write(
+        &mut title,
+        format_args!("MEMORY {}/{}", memories.active_count(), MEMORY_CHANNELS),
+    );
+    let _ = Text::with_baseline(&title, Point::new(0, 0), style, Baseline::Top).draw(buffer);
+
+    // Channels, keeping the selection on screen
+    let first = selected.saturating_sub(LIST_ROWS as u8 - 1);
+    for (row, number) in (first..MEMORY_CHANNELS).take(LIST_ROWS).enumerate() {
+        let Some(channel) = memories.get(number) else {
+            break;
+        };
+        let mut line: String<24> = String::new();
+        if channel.active {
+            let name_len = channel.name.iter().position(|&b| b == 0).unwrap_or(8);
+            let label = match core::str::from_utf8(&channel.name[..name_len]) {
+                Ok(name) if !name.is_empty() => {
+                    String::<{ units::FREQUENCY_LEN }>::try_from(name).unwrap_or_default()
+                }
+                _ => units::format_frequency(u64::from(channel.frequency.as_hz())),
+            };
+            let _ = core::fmt::write(
+                &mut line,
+                format_args!("{:02} {:<11} {}", number, label.as_str(), mode_label(channel.mode)),
+            );
+        } else {
+            let _ = core::fmt::write(&mut line, format_args!("{number:02} ---"));
+        }
+
+        let y = 14 + row as i32 * 10;
+        if number == selected {
+            let rect = Rectangle::new(Point::new(0, y - 1), Size::new(128, 10));
+            let _ = rect
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(buffer);
+            let _ = Text::with_baseline(&line, Point::new(2, y), inv_style, Baseline::Top)
+                .draw(buffer);
+        } else {
+            let _ = Text::with_baseline(&line, Point::new(2, y), style, Baseline::Top).draw(buffer);
+        }
+    }
+}
+
+/// Render the band scope: frequency and mode over the spectrum, with the
+/// waterfall below when there are rows for it
+///
+/// While the encoder adjusts the waterfall, the title shows the
+/// reference level instead.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+pub fn render_scope_screen(
+    buffer: &mut DisplayBuffer,
+    state: &RadioState,
+    ui: &UiState,
+    spectrum: Option<&WaterfallRow>,
+    waterfall: Option<&WaterfallBuffer<WATERFALL_ROWS>>,
+) {
+    buffer.clear();
+
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+
+    // Title
+    let mut title: String<20> = String::new();
+    if ui.is_scope_adjust() {
+        let _ = core::fmt::write(
+            &mut title,
+            format_args!("REF {} dB", ui.waterfall().ref_level_db()),
+        );
+    } else {
+        let freq = units::format_frequency(u64::from(state.frequency().as_hz()));
+        let _ = core::fmt::write(
+            &mut title,
+            format_args!("{} {}", freq.as_str(), mode_label(state.mode())),
+        );
+    }
+    let _ = Text::with_baseline(&title, Point::new(0, 0), style, Baseline::Top).draw(buffer);
+
+    let size = buffer.size();
+    let height = size.height - 12;
+    let waterfall = waterfall.filter(|rows| !rows.is_empty());
+    let spectrum = spectrum.or_else(|| waterfall.and_then(|rows| rows.get(0)));
+    let Some(row) = spectrum else {
+        let _ = Text::with_baseline("No data", Point::new(43, 34), style, Baseline::Top)
+            .draw(buffer);
+        return;
+    };
+
+    match waterfall {
+        Some(rows) => {
+            // Spectrum over the top third, waterfall below
+            let bars = height / 3;
+            render_spectrum(
+                buffer,
+                row,
+                Rectangle::new(Point::new(0, 12), Size::new(size.width, bars)),
+            );
+            let top = 12 + bars as i32 + 1;
+            let area = Rectangle::new(
+                Point::new(0, top),
+                Size::new(size.width, size.height.saturating_sub(top as u32)),
+            );
+            render_waterfall(buffer, rows, ui.waterfall(), area);
+        }
+        None => render_spectrum(
+            buffer,
+            row,
+            Rectangle::new(Point::new(0, 12), Size::new(size.width, height)),
+        ),
+    }
+}
+
+/// Render waterfall rows filling `area`, newest at the top
+///
+/// One pixel row per waterfall row; levels are dithered against the
+/// view's reference level and range.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+pub fn render_waterfall<const ROWS: usize>(
+    buffer: &mut DisplayBuffer,
+    rows: &WaterfallBuffer<ROWS>,
+    view: &WaterfallView,
+    area: Rectangle,
+) {
+    let width = (area.size.width as usize).min(usize::from(Geometry::ST7735.width));
+    let mut line = [false; Geometry::ST7735.width as usize];
+
+    for y in 0..area.size.height {
+        let Some(row) = rows.get(y as usize) else {
+            break;
+        };
+        let py = area.top_left.y + y as i32;
+        view.render_line(row, py as u32, &mut line[..width]);
+        for (x, &lit) in line[..width].iter().enumerate() {
+            if lit {
+                let _ = Pixel(Point::new(area.top_left.x + x as i32, py), BinaryColor::On)
+                    .draw(buffer);
+            }
+        }
+    }
+}
+
+/// Render a spectrum row as a bar graph filling `area`
+///
+/// Columns are resampled from the row's 128, and a dotted line marks
+/// the centre (the tuned frequency).
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+pub fn render_spectrum(buffer: &mut DisplayBuffer, row: &WaterfallRow, area: Rectangle) {
+    let width = area.size.width;
+    let height = area.size.height;
+    if width == 0 || height == 0 {
+        return;
+    }
+    let bottom = area.top_left.y + height as i32 - 1;
+    let bar = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+
+    for x in 0..width {
+        let level = u32::from(row.power_at((x * 128 / width) as usize)).min(100);
+        let bar_height = level * height / 100;
+        if bar_height > 0 {
+            let px = area.top_left.x + x as i32;
+            let _ = Line::new(
+                Point::new(px, bottom - bar_height as i32 + 1),
+                Point::new(px, bottom),
+            )
+            .into_styled(bar)
+            .draw(buffer);
+        }
+    }
+
+    // Centre marker
+    let centre = area.top_left.x + width as i32 / 2;
+    for y in (area.top_left.y..=bottom).step_by(3) {
+        let _ = Pixel(Point::new(centre, y), BinaryColor::On).draw(buffer);
+    }
+}
+
+/// Get the short label for a mode
+const fn mode_label(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Lsb => "LSB",
+        Mode::Usb => "USB",
+        Mode::Cw => "CW",
+        Mode::CwR => "CWR",
+        Mode::Am => "AM",
+        Mode::Fm => "FM",
+    }
+}
@@ -0,0 +1,300 @@
+//! Partial Redraw Scheduling
+//!
+//! The display buses are slow next to the DSP: a full SSD1306 frame is
+//! 1 KiB over 400 kHz I2C, about 25 ms, and a full ST7735 frame is
+//! 40 KiB of RGB565. Pages are drawn in full into the 1 bpp frame
+//! buffer, which is cheap, and [`RedrawScheduler`] compares the result
+//! with what the panel already shows, tile by tile. Only changed tiles
+//! are sent, a few at a time, and the UI task yields between transfers
+//! so the DSP task never waits behind a whole frame. Frames are paced to
+//! [`DEFAULT_FRAME_MS`] so a busy meter cannot hog the bus either.
+//!
+//! The frame buffer uses the SSD1306 page layout: each byte is a column
+//! of eight pixels and bytes run left to right along a page of eight
+//! rows. A tile is [`TILE_WIDTH`] columns of one page, so its bytes are
+//! contiguous.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut scheduler = RedrawScheduler::new(Geometry::SSD1306);
+//! loop {
+//!     if ui.needs_update() && scheduler.frame_due(now_ms) {
+//!         render_page(display.buffer_mut(), &ctx);
+//!         scheduler.update(display.buffer().as_bytes());
+//!         ui.mark_updated();
+//!     }
+//!     while let Some(region) = scheduler.next_region() {
+//!         display.flush_region(region).await?;
+//!         yield_now().await;
+//!     }
+//!     Timer::after_millis(10).await;
+//! }
+//! ```
+
+/// Tile width in pixels
+pub const TILE_WIDTH: u16 = 16;
+
+/// Tile height in pixels (one page)
+pub const TILE_HEIGHT: u16 = 8;
+
+/// Most tiles on any supported panel
+pub const MAX_TILES: usize = 256;
+
+/// Largest frame buffer in bytes (160x128 at 1 bpp)
+pub const MAX_BUFFER_BYTES: usize = 160 * 128 / 8;
+
+/// Default minimum time between frames (20 frames per second)
+pub const DEFAULT_FRAME_MS: u32 = 50;
+
+/// Default most tiles sent in one transfer
+pub const DEFAULT_REGION_TILES: u16 = 4;
+
+/// Panel size in pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Geometry {
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+}
+
+impl Geometry {
+    /// 0.96" SSD1306 OLED
+    pub const SSD1306: Self = Self {
+        width: 128,
+        height: 64,
+    };
+
+    /// 1.8" ST7735 TFT in landscape
+    pub const ST7735: Self = Self {
+        width: 160,
+        height: 128,
+    };
+
+    /// Get the number of pages (rows of bytes)
+    #[must_use]
+    pub const fn pages(&self) -> u16 {
+        self.height.div_ceil(TILE_HEIGHT)
+    }
+
+    /// Get the frame buffer size in bytes
+    #[must_use]
+    pub const fn buffer_len(&self) -> usize {
+        self.width as usize * self.pages() as usize
+    }
+
+    /// Get the number of tiles across
+    #[must_use]
+    pub const fn tile_columns(&self) -> u16 {
+        self.width.div_ceil(TILE_WIDTH)
+    }
+
+    /// Get the number of tiles
+    #[must_use]
+    pub const fn tile_count(&self) -> usize {
+        self.tile_columns() as usize * self.pages() as usize
+    }
+
+    /// Check if the panel fits the scheduler and frame buffer
+    #[must_use]
+    pub const fn is_supported(&self) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.buffer_len() <= MAX_BUFFER_BYTES
+            && self.tile_count() <= MAX_TILES
+    }
+}
+
+/// Area of the panel to send, one page tall
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Left column
+    pub x: u16,
+    /// Top row (a multiple of [`TILE_HEIGHT`])
+    pub y: u16,
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+}
+
+impl Region {
+    /// Get the page the region lies in
+    #[must_use]
+    pub const fn page(&self) -> u16 {
+        self.y / TILE_HEIGHT
+    }
+
+    /// Get the region's bytes within a frame buffer of `geometry`
+    #[must_use]
+    pub const fn bytes(&self, geometry: Geometry) -> core::ops::Range<usize> {
+        let start = self.page() as usize * geometry.width as usize + self.x as usize;
+        start..start + self.width as usize
+    }
+}
+
+/// Decides which parts of the panel to send, and when
+#[derive(Clone, Debug)]
+pub struct RedrawScheduler {
+    /// Panel size
+    geometry: Geometry,
+    /// Frame buffer contents as of the last update
+    shadow: [u8; MAX_BUFFER_BYTES],
+    /// Tiles waiting to be sent, one bit each in row-major order
+    dirty: [u64; MAX_TILES / 64],
+    /// Minimum time between frames
+    frame_ms: u32,
+    /// Time of the last frame
+    last_frame_ms: Option<u32>,
+    /// Most tiles sent in one transfer
+    region_tiles: u16,
+}
+
+impl RedrawScheduler {
+    /// Create a scheduler for a panel
+    ///
+    /// The panel's contents are unknown, so the first update sends every
+    /// tile. Unsupported geometries are clamped to the SSD1306.
+    #[must_use]
+    pub const fn new(geometry: Geometry) -> Self {
+        let geometry = if geometry.is_supported() {
+            geometry
+        } else {
+            Geometry::SSD1306
+        };
+        let mut scheduler = Self {
+            geometry,
+            shadow: [0; MAX_BUFFER_BYTES],
+            dirty: [0; MAX_TILES / 64],
+            frame_ms: DEFAULT_FRAME_MS,
+            last_frame_ms: None,
+            region_tiles: DEFAULT_REGION_TILES,
+        };
+        scheduler.invalidate();
+        scheduler
+    }
+
+    /// Get the panel size
+    #[must_use]
+    pub const fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Set the minimum time between frames
+    pub fn set_frame_interval_ms(&mut self, ms: u32) {
+        self.frame_ms = ms;
+    }
+
+    /// Set the most tiles sent in one transfer
+    pub fn set_region_tiles(&mut self, tiles: u16) {
+        self.region_tiles = tiles.max(1);
+    }
+
+    /// Mark the whole panel for sending (e.g. after a panel reset)
+    pub const fn invalidate(&mut self) {
+        let count = self.geometry.tile_count();
+        let mut word = 0;
+        while word < self.dirty.len() {
+            let first = word * 64;
+            self.dirty[word] = if count >= first + 64 {
+                u64::MAX
+            } else if count > first {
+                (1 << (count - first)) - 1
+            } else {
+                0
+            };
+            word += 1;
+        }
+    }
+
+    /// Check if a new frame may be drawn at `now_ms`, starting it if so
+    pub fn frame_due(&mut self, now_ms: u32) -> bool {
+        let due = self
+            .last_frame_ms
+            .is_none_or(|last| now_ms.wrapping_sub(last) >= self.frame_ms);
+        if due {
+            self.last_frame_ms = Some(now_ms);
+        }
+        due
+    }
+
+    /// Compare a freshly drawn frame with the last one
+    ///
+    /// Marks the tiles that changed and returns how many there were.
+    /// Tiles still waiting from an earlier frame stay marked.
+    pub fn update(&mut self, frame: &[u8]) -> usize {
+        let width = usize::from(self.geometry.width);
+        let tile_width = usize::from(TILE_WIDTH);
+        let columns = usize::from(self.geometry.tile_columns());
+        let len = self.geometry.buffer_len().min(frame.len());
+        let mut changed = 0;
+
+        for tile in 0..self.geometry.tile_count() {
+            let start = tile / columns * width + tile % columns * tile_width;
+            let end = (start + tile_width).min(start - start % width + width).min(len);
+            if start >= end || frame[start..end] == self.shadow[start..end] {
+                continue;
+            }
+            self.shadow[start..end].copy_from_slice(&frame[start..end]);
+            if !self.is_dirty(tile) {
+                changed += 1;
+            }
+            self.dirty[tile / 64] |= 1 << (tile % 64);
+        }
+        changed
+    }
+
+    /// Take the next region to send
+    ///
+    /// Neighbouring changed tiles in a page are merged, up to the region
+    /// size, so each transfer is short and the caller can yield between
+    /// them.
+    #[must_use]
+    pub fn next_region(&mut self) -> Option<Region> {
+        let first = (0..self.geometry.tile_count()).find(|&tile| self.is_dirty(tile))?;
+        let columns = usize::from(self.geometry.tile_columns());
+        let row_end = first - first % columns + columns;
+
+        let mut last = first;
+        while last + 1 < row_end
+            && last + 1 - first < usize::from(self.region_tiles)
+            && self.is_dirty(last + 1)
+        {
+            last += 1;
+        }
+        for tile in first..=last {
+            self.dirty[tile / 64] &= !(1 << (tile % 64));
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let (x, y, tiles) = (
+            (first % columns) as u16 * TILE_WIDTH,
+            (first / columns) as u16 * TILE_HEIGHT,
+            (last - first + 1) as u16,
+        );
+        Some(Region {
+            x,
+            y,
+            width: (tiles * TILE_WIDTH).min(self.geometry.width - x),
+            height: TILE_HEIGHT.min(self.geometry.height - y),
+        })
+    }
+
+    /// Get the number of tiles waiting to be sent
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.dirty.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Check if the panel shows the last frame
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.dirty.iter().all(|&word| word == 0)
+    }
+
+    /// Check if a tile is waiting to be sent
+    const fn is_dirty(&self, tile: usize) -> bool {
+        self.dirty[tile / 64] & (1 << (tile % 64)) != 0
+    }
+}
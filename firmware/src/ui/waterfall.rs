@@ -0,0 +1,151 @@
+//! Mini Waterfall
+//!
+//! Scales [`WaterfallBuffer`](crate::dsp::spectrum::WaterfallBuffer)
+//! rows for the front panel so the rig shows a panadapter without a
+//! computer attached. Each row is one line of pixels, newest at the top,
+//! with its 128 columns downsampled to the width of the area (keeping the
+//! strongest signal in each pixel so narrow carriers do not vanish).
+//!
+//! The panels are 1 bpp, so levels are shown by density: a level between
+//! the floor and the reference maps to one of [`LEVELS`] intensities,
+//! drawn with a 4x4 ordered dither. The reference level is the power
+//! drawn solid; it and the displayed range are adjustable, like the
+//! reference level on a bench spectrum analyzer.
+
+use crate::dsp::spectrum::WaterfallRow;
+
+/// Rows kept for the mini waterfall (one per pixel row on a 128x64 panel)
+pub const WATERFALL_ROWS: usize = 64;
+
+/// Columns in a waterfall row
+pub const ROW_COLUMNS: usize = 128;
+
+/// Intensity steps from blank to solid
+pub const LEVELS: u8 = 16;
+
+/// Default reference level in dBFS
+pub const DEFAULT_REF_LEVEL_DB: i8 = -30;
+
+/// Default displayed range in dB
+pub const DEFAULT_RANGE_DB: u8 = 50;
+
+/// Reference level adjustment step in dB
+pub const REF_STEP_DB: i8 = 5;
+
+/// Lowest reference level in dBFS
+const MIN_REF_LEVEL_DB: i8 = -90;
+
+/// Narrowest and widest displayed range in dB
+const RANGE_DB: (u8, u8) = (10, 100);
+
+/// 4x4 Bayer threshold matrix
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Reference level and range for the mini waterfall
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WaterfallView {
+    /// Power drawn solid, in dBFS
+    ref_level_db: i8,
+    /// Span below the reference that is drawn, in dB
+    range_db: u8,
+}
+
+impl WaterfallView {
+    /// Create with the default reference level and range
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            ref_level_db: DEFAULT_REF_LEVEL_DB,
+            range_db: DEFAULT_RANGE_DB,
+        }
+    }
+
+    /// Get the reference level in dBFS
+    #[must_use]
+    pub const fn ref_level_db(&self) -> i8 {
+        self.ref_level_db
+    }
+
+    /// Set the reference level (clamped to -90..=0 dBFS)
+    pub fn set_ref_level_db(&mut self, db: i8) {
+        self.ref_level_db = db.clamp(MIN_REF_LEVEL_DB, 0);
+    }
+
+    /// Move the reference level by `steps` of [`REF_STEP_DB`]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn adjust_ref_level(&mut self, steps: i32) {
+        let db = i32::from(self.ref_level_db)
+            .saturating_add(steps.saturating_mul(i32::from(REF_STEP_DB)))
+            .clamp(i32::from(MIN_REF_LEVEL_DB), 0);
+        self.ref_level_db = db as i8;
+    }
+
+    /// Get the displayed range in dB
+    #[must_use]
+    pub const fn range_db(&self) -> u8 {
+        self.range_db
+    }
+
+    /// Set the displayed range (clamped to 10..=100 dB)
+    pub fn set_range_db(&mut self, db: u8) {
+        self.range_db = db.clamp(RANGE_DB.0, RANGE_DB.1);
+    }
+
+    /// Get the intensity (0 to [`LEVELS`]) of a row value
+    ///
+    /// Row values are 0-100 for -100..0 dBFS, as stored by
+    /// [`WaterfallRow::from_spectrum`].
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub const fn intensity(&self, power: u8) -> u8 {
+        let db = power as i32 - 100;
+        let floor = self.ref_level_db as i32 - self.range_db as i32;
+        let above = db - floor;
+        if above <= 0 {
+            0
+        } else if above >= self.range_db as i32 {
+            LEVELS
+        } else {
+            (above * LEVELS as i32 / self.range_db as i32) as u8
+        }
+    }
+
+    /// Check if the pixel at `(x, y)` is lit for an intensity
+    #[must_use]
+    pub const fn is_lit(intensity: u8, x: u32, y: u32) -> bool {
+        intensity > BAYER[(y % 4) as usize][(x % 4) as usize]
+    }
+
+    /// Get the strongest row value under pixel column `x` of `width`
+    #[must_use]
+    pub fn column_power(row: &WaterfallRow, x: u32, width: u32) -> u8 {
+        if width == 0 || x >= width {
+            return 0;
+        }
+        let columns = ROW_COLUMNS as u64;
+        let start = u64::from(x) * columns / u64::from(width);
+        let end = (u64::from(x + 1) * columns / u64::from(width)).max(start + 1);
+        (start..end)
+            .map(|col| usize::try_from(col).map_or(0, |col| row.power_at(col)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Fill `pixels` with one line of the waterfall at pixel row `y`
+    ///
+    /// Each entry is one pixel column across `pixels.len()`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn render_line(&self, row: &WaterfallRow, y: u32, pixels: &mut [bool]) {
+        let width = pixels.len() as u32;
+        for (x, pixel) in (0..width).zip(pixels.iter_mut()) {
+            let intensity = self.intensity(Self::column_power(row, x, width));
+            *pixel = Self::is_lit(intensity, x, y);
+        }
+    }
+}
+
+impl Default for WaterfallView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,194 @@
+//! Firmware Update
+//!
+//! Version reporting for host tools and the guards that decide when to
+//! reboot into the STM32 ROM DFU bootloader. Entering the bootloader is
+//! deliberately a two-step operation so a stray CAT byte or a bumped
+//! button cannot take the radio off the air:
+//!
+//! - CAT: `ZB1;` arms, `ZB2;` within [`CONFIRM_WINDOW_MS`] confirms
+//! - Buttons: encoder switch and PTT held together for [`BUTTON_HOLD_MS`]
+//! - USB: DFU_DETACH on the DFU runtime interface
+//!
+//! The CAT and button paths latch [`BootloaderGuard::is_requested`]; the
+//! task that owns the USB device checks it alongside the DFU detach flag
+//! and does the reboot itself with `hal::bootloader`.
+
+use crate::protocol::{CatCommand, CatResponse};
+
+/// Host protocol version, bumped when CAT extensions change incompatibly
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Time allowed between arming and confirming (milliseconds)
+pub const CONFIRM_WINDOW_MS: u32 = 5_000;
+
+/// Time both buttons must be held to enter the bootloader (milliseconds)
+pub const BUTTON_HOLD_MS: u32 = 3_000;
+
+/// Capability bits reported in the version handshake
+pub mod capability {
+    /// Flash file store with CAT listing/download
+    pub const STORAGE: u16 = 1 << 0;
+    /// USB Audio Class 2.0 IQ streaming
+    pub const USB_AUDIO: u16 = 1 << 1;
+    /// DFU runtime interface / bootloader entry
+    pub const DFU: u16 = 1 << 2;
+    /// USB Power Delivery sink
+    pub const USB_PD: u16 = 1 << 3;
+    /// Front-panel display and encoder (clear on headless builds)
+    pub const DISPLAY: u16 = 1 << 4;
+
+    /// Capabilities of this build
+    pub const CURRENT: u16 = STORAGE
+        | USB_AUDIO
+        | DFU
+        | if cfg!(feature = "usb-pd") { USB_PD } else { 0 }
+        | if cfg!(feature = "display") { DISPLAY } else { 0 };
+}
+
+/// Firmware version (semantic versioning)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FirmwareVersion {
+    /// Major version
+    pub major: u8,
+    /// Minor version
+    pub minor: u8,
+    /// Patch version
+    pub patch: u8,
+}
+
+impl FirmwareVersion {
+    /// Version of this build (from Cargo.toml)
+    pub const CURRENT: Self = Self {
+        major: parse_u8(env!("CARGO_PKG_VERSION_MAJOR")),
+        minor: parse_u8(env!("CARGO_PKG_VERSION_MINOR")),
+        patch: parse_u8(env!("CARGO_PKG_VERSION_PATCH")),
+    };
+
+    /// Create a version
+    #[must_use]
+    pub const fn new(major: u8, minor: u8, patch: u8) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// Check if a host speaking `other` can talk to this firmware
+    ///
+    /// Versions before 1.0 only match on the same minor version.
+    #[must_use]
+    pub const fn is_compatible(self, other: Self) -> bool {
+        if self.major == 0 || other.major == 0 {
+            self.major == other.major && self.minor == other.minor
+        } else {
+            self.major == other.major
+        }
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl defmt::Format for FirmwareVersion {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "v{}.{}.{}", self.major, self.minor, self.patch);
+    }
+}
+
+/// Parse a decimal version component at compile time
+const fn parse_u8(s: &str) -> u8 {
+    let bytes = s.as_bytes();
+    let mut value: u8 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0');
+        i += 1;
+    }
+    value
+}
+
+/// Guard for entering the ROM bootloader
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BootloaderGuard {
+    /// Time the CAT request was armed
+    armed_at_ms: Option<u32>,
+    /// Time both buttons have been held
+    held_ms: u32,
+    /// Entry confirmed by CAT or the buttons
+    requested: bool,
+}
+
+impl BootloaderGuard {
+    /// Create a new (disarmed) guard
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            armed_at_ms: None,
+            held_ms: 0,
+            requested: false,
+        }
+    }
+
+    /// Arm the CAT request
+    pub fn arm(&mut self, now_ms: u32) {
+        self.armed_at_ms = Some(now_ms);
+    }
+
+    /// Check if armed and still inside the confirm window
+    #[must_use]
+    pub const fn is_armed(&self, now_ms: u32) -> bool {
+        match self.armed_at_ms {
+            Some(at) => now_ms.wrapping_sub(at) <= CONFIRM_WINDOW_MS,
+            None => false,
+        }
+    }
+
+    /// Confirm the CAT request, returns true if the bootloader should be entered
+    pub fn confirm(&mut self, now_ms: u32) -> bool {
+        let ok = self.is_armed(now_ms);
+        self.armed_at_ms = None;
+        self.requested |= ok;
+        ok
+    }
+
+    /// Check if entry was confirmed by CAT or the buttons
+    #[must_use]
+    pub const fn is_requested(&self) -> bool {
+        self.requested
+    }
+
+    /// Handle a CAT bootloader command, returns false for other commands
+    ///
+    /// `ZB1;` arms and `ZB2;` confirms, each acknowledged with the same
+    /// step; a `ZB2;` that is not armed gets the error reply.
+    pub fn handle_cat(
+        &mut self,
+        command: &CatCommand,
+        now_ms: u32,
+        response: &mut CatResponse,
+    ) -> bool {
+        match command {
+            CatCommand::ArmBootloader => {
+                self.arm(now_ms);
+                response.bootloader(1);
+            }
+            CatCommand::EnterBootloader if self.confirm(now_ms) => response.bootloader(2),
+            CatCommand::EnterBootloader => response.error(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Cancel a pending request
+    pub fn cancel(&mut self) {
+        self.armed_at_ms = None;
+        self.held_ms = 0;
+    }
+
+    /// Update button state, returns true once both have been held long enough
+    pub fn update_buttons(&mut self, encoder_pressed: bool, ptt_pressed: bool, elapsed_ms: u32) -> bool {
+        if encoder_pressed && ptt_pressed {
+            self.held_ms = self.held_ms.saturating_add(elapsed_ms);
+            self.requested |= self.held_ms >= BUTTON_HOLD_MS;
+            self.held_ms >= BUTTON_HOLD_MS
+        } else {
+            self.held_ms = 0;
+            false
+        }
+    }
+}
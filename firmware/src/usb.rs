@@ -2,6 +2,11 @@
 //!
 //! Provides USB functionality for the SDR transceiver:
 //! - CDC ACM for CAT control and debug
-//! - USB Audio for IQ streaming (future)
+//! - Second CDC ACM emulating a K1EL WinKeyer for contest loggers
+//! - USB Audio Class 2.0 for IQ capture and TX audio playback
+//! - DFU runtime interface for firmware updates
 
+pub mod audio;
 pub mod cdc;
+pub mod dfu;
+pub mod winkeyer;
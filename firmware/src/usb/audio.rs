@@ -0,0 +1,662 @@
+//! USB Audio Class 2.0 IQ Streaming
+//!
+//! Exposes the receiver IQ signal as a 2-channel (I = left, Q = right)
+//! 16-bit capture stream and accepts a 2-channel playback stream for TX
+//! audio, so the radio appears as a standard stereo soundcard to SDR
+//! software without custom drivers.
+//!
+//! Topology:
+//!
+//! ```text
+//! Clock Source (1) ── 48/96 kHz, host programmable
+//! Radio Receiver IT (2) ──► USB Streaming OT (3) ──► iso IN  (capture)
+//! USB Streaming IT (4) ──► Radio Transmitter OT (5) ◄── iso OUT (playback)
+//! ```
+//!
+//! The device must be configured with `composite_with_iads = true` and
+//! the IAD device class triple (0xEF/0x02/0x01) so hosts bind the audio
+//! function alongside CDC ACM.
+//!
+//! [`UacIqClass::capture_only`] leaves out the playback path. The
+//! STM32G474 USB peripheral has eight endpoint registers and each bulk
+//! or isochronous endpoint takes a whole one, so with two CDC ACM ports
+//! there is room for the capture endpoint only.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use embassy_usb::control::{InResponse, OutResponse, Recipient, Request, RequestType};
+use embassy_usb::descriptor::{SynchronizationType, UsageType};
+use embassy_usb::driver::{Driver, Endpoint, EndpointError, EndpointIn, EndpointOut};
+use embassy_usb::types::InterfaceNumber;
+use embassy_usb::{Builder, Handler};
+
+use crate::dsp::modulation::IqSample;
+
+/// USB audio interface class
+const USB_CLASS_AUDIO: u8 = 0x01;
+
+/// Audio function subclass (undefined)
+const FUNCTION_SUBCLASS_UNDEFINED: u8 = 0x00;
+
+/// Audio control interface subclass
+const SUBCLASS_AUDIOCONTROL: u8 = 0x01;
+
+/// Audio streaming interface subclass
+const SUBCLASS_AUDIOSTREAMING: u8 = 0x02;
+
+/// UAC 2.0 protocol code
+const PROTOCOL_IP_VERSION_02_00: u8 = 0x20;
+
+/// Class-specific interface descriptor type
+const CS_INTERFACE: u8 = 0x24;
+
+/// Class-specific endpoint descriptor type
+const CS_ENDPOINT: u8 = 0x25;
+
+/// Class-specific descriptor subtypes and codes
+mod uac {
+    pub const AC_HEADER: u8 = 0x01;
+    pub const AC_INPUT_TERMINAL: u8 = 0x02;
+    pub const AC_OUTPUT_TERMINAL: u8 = 0x03;
+    pub const AC_CLOCK_SOURCE: u8 = 0x0A;
+    pub const AS_GENERAL: u8 = 0x01;
+    pub const AS_FORMAT_TYPE: u8 = 0x02;
+    pub const EP_GENERAL: u8 = 0x01;
+
+    pub const FORMAT_TYPE_I: u8 = 0x01;
+    pub const FORMAT_PCM: u32 = 0x0000_0001;
+    pub const CATEGORY_OTHER: u8 = 0xFF;
+
+    pub const TERMINAL_USB_STREAMING: u16 = 0x0101;
+    pub const TERMINAL_RADIO_RECEIVER: u16 = 0x0710;
+    pub const TERMINAL_RADIO_TRANSMITTER: u16 = 0x0711;
+
+    pub const REQUEST_CUR: u8 = 0x01;
+    pub const REQUEST_RANGE: u8 = 0x02;
+    pub const CS_SAM_FREQ_CONTROL: u8 = 0x01;
+    pub const CS_CLOCK_VALID_CONTROL: u8 = 0x02;
+}
+
+/// Entity IDs
+mod entity {
+    pub const CLOCK: u8 = 1;
+    pub const RX_INPUT: u8 = 2;
+    pub const RX_OUTPUT: u8 = 3;
+    pub const TX_INPUT: u8 = 4;
+    pub const TX_OUTPUT: u8 = 5;
+}
+
+/// Channels per stream (I/Q or L/R)
+pub const CHANNELS: usize = 2;
+
+/// Bytes per sample
+pub const SAMPLE_BYTES: usize = 2;
+
+/// Bytes per stereo frame
+pub const FRAME_BYTES: usize = CHANNELS * SAMPLE_BYTES;
+
+/// Front left/right channel configuration
+const CHANNEL_CONFIG: u32 = 0x0000_0003;
+
+/// Supported stream sample rate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum UacSampleRate {
+    /// 48 kHz
+    #[default]
+    Khz48,
+    /// 96 kHz
+    Khz96,
+}
+
+impl UacSampleRate {
+    /// All supported rates (ascending)
+    pub const ALL: [Self; 2] = [Self::Khz48, Self::Khz96];
+
+    /// Get rate in Hz
+    #[must_use]
+    pub const fn hz(self) -> u32 {
+        match self {
+            Self::Khz48 => 48_000,
+            Self::Khz96 => 96_000,
+        }
+    }
+
+    /// Parse a rate in Hz
+    #[must_use]
+    pub const fn from_hz(hz: u32) -> Option<Self> {
+        match hz {
+            48_000 => Some(Self::Khz48),
+            96_000 => Some(Self::Khz96),
+            _ => None,
+        }
+    }
+
+    /// Stereo frames per 1 ms USB frame
+    #[must_use]
+    pub const fn frames_per_packet(self) -> usize {
+        (self.hz() / 1000) as usize
+    }
+
+    /// Bytes per 1 ms packet
+    #[must_use]
+    pub const fn packet_bytes(self) -> usize {
+        self.frames_per_packet() * FRAME_BYTES
+    }
+}
+
+impl defmt::Format for UacSampleRate {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Khz48 => defmt::write!(f, "48kHz"),
+            Self::Khz96 => defmt::write!(f, "96kHz"),
+        }
+    }
+}
+
+/// Maximum isochronous packet size (highest rate plus one spare frame)
+#[allow(clippy::cast_possible_truncation)]
+const MAX_PACKET_SIZE: u16 = ((96 + 1) * FRAME_BYTES) as u16;
+
+/// State shared between the control handler and the class
+struct Shared {
+    /// Current sample rate in Hz
+    sample_rate: AtomicU32,
+    /// Host selected the capture alternate setting
+    capture_active: AtomicBool,
+    /// Host selected the playback alternate setting
+    playback_active: AtomicBool,
+    /// Sample rate changed since last checked
+    rate_changed: AtomicBool,
+}
+
+impl Shared {
+    const fn new() -> Self {
+        Self {
+            sample_rate: AtomicU32::new(UacSampleRate::Khz48.hz()),
+            capture_active: AtomicBool::new(false),
+            playback_active: AtomicBool::new(false),
+            rate_changed: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Control request handler
+struct Control<'d> {
+    shared: &'d Shared,
+    control_iface: InterfaceNumber,
+    capture_iface: InterfaceNumber,
+    playback_iface: Option<InterfaceNumber>,
+    /// Scratch buffer for IN responses
+    response: [u8; 26],
+}
+
+impl Control<'_> {
+    /// Check if a request targets our clock source entity
+    fn is_clock_request(&self, req: &Request) -> bool {
+        req.request_type == RequestType::Class
+            && req.recipient == Recipient::Interface
+            && (req.index & 0xFF) as u8 == self.control_iface.0
+            && (req.index >> 8) as u8 == entity::CLOCK
+    }
+}
+
+impl Handler for Control<'_> {
+    fn reset(&mut self) {
+        self.shared.capture_active.store(false, Ordering::Relaxed);
+        self.shared.playback_active.store(false, Ordering::Relaxed);
+    }
+
+    fn set_alternate_setting(&mut self, iface: InterfaceNumber, alternate_setting: u8) {
+        let active = alternate_setting != 0;
+        if iface == self.capture_iface {
+            self.shared.capture_active.store(active, Ordering::Relaxed);
+        } else if Some(iface) == self.playback_iface {
+            self.shared.playback_active.store(active, Ordering::Relaxed);
+        }
+    }
+
+    fn control_out(&mut self, req: Request, data: &[u8]) -> Option<OutResponse> {
+        if !self.is_clock_request(&req) {
+            return None;
+        }
+
+        let selector = (req.value >> 8) as u8;
+        if req.request != uac::REQUEST_CUR || selector != uac::CS_SAM_FREQ_CONTROL || data.len() < 4 {
+            return Some(OutResponse::Rejected);
+        }
+
+        let hz = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        match UacSampleRate::from_hz(hz) {
+            Some(rate) => {
+                let old = self.shared.sample_rate.swap(rate.hz(), Ordering::Relaxed);
+                if old != rate.hz() {
+                    self.shared.rate_changed.store(true, Ordering::Relaxed);
+                }
+                Some(OutResponse::Accepted)
+            }
+            None => Some(OutResponse::Rejected),
+        }
+    }
+
+    fn control_in<'a>(&'a mut self, req: Request, _buf: &'a mut [u8]) -> Option<InResponse<'a>> {
+        if !self.is_clock_request(&req) {
+            return None;
+        }
+
+        let selector = (req.value >> 8) as u8;
+        let len = match (req.request, selector) {
+            (uac::REQUEST_CUR, uac::CS_SAM_FREQ_CONTROL) => {
+                let hz = self.shared.sample_rate.load(Ordering::Relaxed);
+                self.response[..4].copy_from_slice(&hz.to_le_bytes());
+                4
+            }
+            (uac::REQUEST_CUR, uac::CS_CLOCK_VALID_CONTROL) => {
+                self.response[0] = 1;
+                1
+            }
+            (uac::REQUEST_RANGE, uac::CS_SAM_FREQ_CONTROL) => {
+                // Layout 3 parameter block: wNumSubRanges, then {MIN, MAX, RES}
+                #[allow(clippy::cast_possible_truncation)]
+                let count = UacSampleRate::ALL.len() as u16;
+                self.response[..2].copy_from_slice(&count.to_le_bytes());
+                for (i, rate) in UacSampleRate::ALL.iter().enumerate() {
+                    let base = 2 + i * 12;
+                    let hz = rate.hz().to_le_bytes();
+                    self.response[base..base + 4].copy_from_slice(&hz);
+                    self.response[base + 4..base + 8].copy_from_slice(&hz);
+                    self.response[base + 8..base + 12].copy_from_slice(&0u32.to_le_bytes());
+                }
+                2 + UacSampleRate::ALL.len() * 12
+            }
+            _ => return Some(InResponse::Rejected),
+        };
+
+        let len = len.min(usize::from(req.length));
+        Some(InResponse::Accepted(&self.response[..len]))
+    }
+}
+
+/// UAC2 class state (must outlive the USB device)
+pub struct UacState<'d> {
+    control: Option<Control<'d>>,
+    shared: Shared,
+}
+
+impl Default for UacState<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UacState<'_> {
+    /// Create new UAC2 state
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            control: None,
+            shared: Shared::new(),
+        }
+    }
+}
+
+/// Class-specific input terminal descriptor body
+fn input_terminal(id: u8, terminal_type: u16) -> [u8; 15] {
+    let tt = terminal_type.to_le_bytes();
+    let cfg = CHANNEL_CONFIG.to_le_bytes();
+    #[allow(clippy::cast_possible_truncation)]
+    let channels = CHANNELS as u8;
+    [
+        uac::AC_INPUT_TERMINAL,
+        id,
+        tt[0],
+        tt[1],
+        0, // bAssocTerminal
+        entity::CLOCK,
+        channels,
+        cfg[0],
+        cfg[1],
+        cfg[2],
+        cfg[3],
+        0, // iChannelNames
+        0, // bmControls
+        0,
+        0, // iTerminal
+    ]
+}
+
+/// Class-specific output terminal descriptor body
+fn output_terminal(id: u8, terminal_type: u16, source: u8) -> [u8; 10] {
+    let tt = terminal_type.to_le_bytes();
+    [
+        uac::AC_OUTPUT_TERMINAL,
+        id,
+        tt[0],
+        tt[1],
+        0, // bAssocTerminal
+        source,
+        entity::CLOCK,
+        0, // bmControls
+        0,
+        0, // iTerminal
+    ]
+}
+
+/// Class-specific AS general descriptor body
+fn as_general(terminal_link: u8) -> [u8; 14] {
+    let fmt = uac::FORMAT_PCM.to_le_bytes();
+    let cfg = CHANNEL_CONFIG.to_le_bytes();
+    #[allow(clippy::cast_possible_truncation)]
+    let channels = CHANNELS as u8;
+    [
+        uac::AS_GENERAL,
+        terminal_link,
+        0, // bmControls
+        uac::FORMAT_TYPE_I,
+        fmt[0],
+        fmt[1],
+        fmt[2],
+        fmt[3],
+        channels,
+        cfg[0],
+        cfg[1],
+        cfg[2],
+        cfg[3],
+        0, // iChannelNames
+    ]
+}
+
+/// Type I format descriptor body (16-bit in 2-byte subslots)
+#[allow(clippy::cast_possible_truncation)]
+const FORMAT_TYPE_I_16: [u8; 4] = [
+    uac::AS_FORMAT_TYPE,
+    uac::FORMAT_TYPE_I,
+    SAMPLE_BYTES as u8,
+    (SAMPLE_BYTES * 8) as u8,
+];
+
+/// Class-specific isochronous endpoint descriptor body
+const EP_GENERAL: [u8; 6] = [uac::EP_GENERAL, 0, 0, 0, 0, 0];
+
+/// USB Audio Class 2.0 IQ device
+pub struct UacIqClass<'d, D: Driver<'d>> {
+    shared: &'d Shared,
+    iq_in: D::EndpointIn,
+    tx_out: Option<D::EndpointOut>,
+}
+
+impl<'d, D: Driver<'d>> UacIqClass<'d, D> {
+    /// Add the audio function to a USB device builder
+    pub fn new(builder: &mut Builder<'d, D>, state: &'d mut UacState<'d>) -> Self {
+        Self::build(builder, state, true)
+    }
+
+    /// Add the audio function without the TX audio playback stream
+    pub fn capture_only(builder: &mut Builder<'d, D>, state: &'d mut UacState<'d>) -> Self {
+        Self::build(builder, state, false)
+    }
+
+    /// Add the audio function, with or without playback
+    fn build(builder: &mut Builder<'d, D>, state: &'d mut UacState<'d>, playback: bool) -> Self {
+        let UacState { control, shared } = state;
+        let shared: &'d Shared = shared;
+
+        let mut func = builder.function(
+            USB_CLASS_AUDIO,
+            FUNCTION_SUBCLASS_UNDEFINED,
+            PROTOCOL_IP_VERSION_02_00,
+        );
+
+        // Audio control interface
+        let control_iface = {
+            let mut iface = func.interface();
+            let number = iface.interface_number();
+            let mut alt = iface.alt_setting(
+                USB_CLASS_AUDIO,
+                SUBCLASS_AUDIOCONTROL,
+                PROTOCOL_IP_VERSION_02_00,
+                None,
+            );
+
+            // wTotalLength covers the header and all clock/terminal descriptors
+            let paths: u16 = if playback { 2 } else { 1 };
+            let total_len: u16 = 9 + 8 + paths * 17 + paths * 12;
+            let total = total_len.to_le_bytes();
+            alt.descriptor(
+                CS_INTERFACE,
+                &[uac::AC_HEADER, 0x00, 0x02, uac::CATEGORY_OTHER, total[0], total[1], 0],
+            );
+            alt.descriptor(
+                CS_INTERFACE,
+                // Internal programmable clock, frequency r/w, validity read-only
+                &[uac::AC_CLOCK_SOURCE, entity::CLOCK, 0x03, 0x07, 0, 0],
+            );
+            alt.descriptor(
+                CS_INTERFACE,
+                &input_terminal(entity::RX_INPUT, uac::TERMINAL_RADIO_RECEIVER),
+            );
+            alt.descriptor(
+                CS_INTERFACE,
+                &output_terminal(entity::RX_OUTPUT, uac::TERMINAL_USB_STREAMING, entity::RX_INPUT),
+            );
+            if playback {
+                alt.descriptor(
+                    CS_INTERFACE,
+                    &input_terminal(entity::TX_INPUT, uac::TERMINAL_USB_STREAMING),
+                );
+                alt.descriptor(
+                    CS_INTERFACE,
+                    &output_terminal(entity::TX_OUTPUT, uac::TERMINAL_RADIO_TRANSMITTER, entity::TX_INPUT),
+                );
+            }
+            number
+        };
+
+        // Capture streaming interface: alt 0 idle, alt 1 streaming
+        let (capture_iface, iq_in) = {
+            let mut iface = func.interface();
+            let number = iface.interface_number();
+            iface.alt_setting(USB_CLASS_AUDIO, SUBCLASS_AUDIOSTREAMING, PROTOCOL_IP_VERSION_02_00, None);
+            let mut alt = iface.alt_setting(
+                USB_CLASS_AUDIO,
+                SUBCLASS_AUDIOSTREAMING,
+                PROTOCOL_IP_VERSION_02_00,
+                None,
+            );
+            alt.descriptor(CS_INTERFACE, &as_general(entity::RX_OUTPUT));
+            alt.descriptor(CS_INTERFACE, &FORMAT_TYPE_I_16);
+            // The ADC runs from its own timer, not the USB SOF
+            let ep = alt.endpoint_isochronous_in(
+                MAX_PACKET_SIZE,
+                1,
+                SynchronizationType::Asynchronous,
+                UsageType::DataEndpoint,
+                &[],
+            );
+            alt.descriptor(CS_ENDPOINT, &EP_GENERAL);
+            (number, ep)
+        };
+
+        // Playback streaming interface: alt 0 idle, alt 1 streaming
+        let playback = playback.then(|| {
+            let mut iface = func.interface();
+            let number = iface.interface_number();
+            iface.alt_setting(USB_CLASS_AUDIO, SUBCLASS_AUDIOSTREAMING, PROTOCOL_IP_VERSION_02_00, None);
+            let mut alt = iface.alt_setting(
+                USB_CLASS_AUDIO,
+                SUBCLASS_AUDIOSTREAMING,
+                PROTOCOL_IP_VERSION_02_00,
+                None,
+            );
+            alt.descriptor(CS_INTERFACE, &as_general(entity::TX_INPUT));
+            alt.descriptor(CS_INTERFACE, &FORMAT_TYPE_I_16);
+            // No feedback endpoint, so the device follows the host rate
+            let ep = alt.endpoint_isochronous_out(
+                MAX_PACKET_SIZE,
+                1,
+                SynchronizationType::Adaptive,
+                UsageType::DataEndpoint,
+                &[],
+            );
+            alt.descriptor(CS_ENDPOINT, &EP_GENERAL);
+            (number, ep)
+        });
+        let (playback_iface, tx_out) = playback.unzip();
+        drop(func);
+
+        let control = control.insert(Control {
+            shared,
+            control_iface,
+            capture_iface,
+            playback_iface,
+            response: [0; 26],
+        });
+        builder.handler(control);
+
+        Self {
+            shared,
+            iq_in,
+            tx_out,
+        }
+    }
+
+    /// Get the sample rate selected by the host
+    #[must_use]
+    pub fn sample_rate(&self) -> UacSampleRate {
+        UacSampleRate::from_hz(self.shared.sample_rate.load(Ordering::Relaxed)).unwrap_or_default()
+    }
+
+    /// Check and clear the sample rate change flag
+    pub fn take_rate_change(&self) -> Option<UacSampleRate> {
+        if self.shared.rate_changed.swap(false, Ordering::Relaxed) {
+            Some(self.sample_rate())
+        } else {
+            None
+        }
+    }
+
+    /// Check if the host is capturing IQ
+    #[must_use]
+    pub fn capture_active(&self) -> bool {
+        self.shared.capture_active.load(Ordering::Relaxed)
+    }
+
+    /// Check if the host is playing TX audio
+    #[must_use]
+    pub fn playback_active(&self) -> bool {
+        self.shared.playback_active.load(Ordering::Relaxed)
+    }
+
+    /// Wait until the IQ endpoint is enabled by the host
+    pub async fn wait_capture_enabled(&mut self) {
+        self.iq_in.wait_enabled().await;
+    }
+
+    /// Wait until the TX audio endpoint is enabled by the host
+    ///
+    /// Never returns for a [`capture_only`](Self::capture_only) class.
+    pub async fn wait_playback_enabled(&mut self) {
+        match &mut self.tx_out {
+            Some(ep) => ep.wait_enabled().await,
+            None => core::future::pending().await,
+        }
+    }
+
+    /// Send one packet of interleaved IQ (see [`IqPacketizer`])
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint is disabled
+    pub async fn write_iq_packet(&mut self, packet: &[u8]) -> Result<(), EndpointError> {
+        self.iq_in.write(packet).await
+    }
+
+    /// Receive one packet of interleaved TX audio, returns bytes read
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint is disabled or the buffer is too
+    /// small, or [`EndpointError::Disabled`] for a
+    /// [`capture_only`](Self::capture_only) class
+    pub async fn read_tx_packet(&mut self, buf: &mut [u8]) -> Result<usize, EndpointError> {
+        match &mut self.tx_out {
+            Some(ep) => ep.read(buf).await,
+            None => Err(EndpointError::Disabled),
+        }
+    }
+}
+
+/// Splits an IQ sample stream into 1 ms isochronous packets
+///
+/// Samples are queued as separate I and Q values; `next_packet`
+/// returns a full packet once enough frames for the current rate are
+/// buffered.
+pub struct IqPacketizer {
+    buffer: [u8; MAX_PACKET_SIZE as usize],
+    frames: usize,
+    rate: UacSampleRate,
+}
+
+impl IqPacketizer {
+    /// Create a packetizer for a sample rate
+    #[must_use]
+    pub const fn new(rate: UacSampleRate) -> Self {
+        Self {
+            buffer: [0; MAX_PACKET_SIZE as usize],
+            frames: 0,
+            rate,
+        }
+    }
+
+    /// Change sample rate (discards buffered samples)
+    pub fn set_rate(&mut self, rate: UacSampleRate) {
+        self.rate = rate;
+        self.frames = 0;
+    }
+
+    /// Queue one IQ frame, returns false if a packet is waiting
+    pub fn push(&mut self, i: i16, q: i16) -> bool {
+        if self.is_ready() {
+            return false;
+        }
+        let base = self.frames * FRAME_BYTES;
+        self.buffer[base..base + 2].copy_from_slice(&i.to_le_bytes());
+        self.buffer[base + 2..base + 4].copy_from_slice(&q.to_le_bytes());
+        self.frames += 1;
+        true
+    }
+
+    /// Queue one I/Q sample (full scale 1.0), returns false if a packet
+    /// is waiting
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn push_iq(&mut self, sample: IqSample) -> bool {
+        let code = |x: f32| (x.clamp(-1.0, 1.0) * 32767.0) as i16;
+        self.push(code(sample.i), code(sample.q))
+    }
+
+    /// Check if a full packet is buffered
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        self.frames >= self.rate.frames_per_packet()
+    }
+
+    /// Take the buffered packet if complete
+    pub fn next_packet(&mut self) -> Option<&[u8]> {
+        if self.is_ready() {
+            let len = self.frames * FRAME_BYTES;
+            self.frames = 0;
+            Some(&self.buffer[..len])
+        } else {
+            None
+        }
+    }
+}
+
+/// Decode an interleaved 16-bit stereo packet into left/right samples
+///
+/// Returns the number of frames decoded.
+pub fn unpack_stereo(packet: &[u8], left: &mut [i16], right: &mut [i16]) -> usize {
+    let frames = (packet.len() / FRAME_BYTES).min(left.len()).min(right.len());
+    for (n, chunk) in packet.chunks_exact(FRAME_BYTES).take(frames).enumerate() {
+        left[n] = i16::from_le_bytes([chunk[0], chunk[1]]);
+        right[n] = i16::from_le_bytes([chunk[2], chunk[3]]);
+    }
+    frames
+}
@@ -0,0 +1,183 @@
+//! USB DFU Runtime Interface
+//!
+//! Advertises DFU 1.1 runtime mode so standard tools (`dfu-util`,
+//! STM32CubeProgrammer) can find the radio and ask it to detach. On
+//! DFU_DETACH the application reboots into the ROM bootloader, which
+//! re-enumerates in DFU mode and performs the actual download.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_usb::control::{InResponse, OutResponse, Recipient, Request, RequestType};
+use embassy_usb::driver::Driver;
+use embassy_usb::types::InterfaceNumber;
+use embassy_usb::{Builder, Handler};
+
+/// Application specific interface class
+const USB_CLASS_APPLICATION: u8 = 0xFE;
+
+/// DFU subclass
+const DFU_SUBCLASS: u8 = 0x01;
+
+/// DFU runtime protocol
+const DFU_PROTOCOL_RUNTIME: u8 = 0x01;
+
+/// DFU functional descriptor type
+const DFU_FUNCTIONAL: u8 = 0x21;
+
+/// Functional descriptor attributes: will detach, manifestation tolerant,
+/// can upload, can download
+const DFU_ATTRIBUTES: u8 = 0x0F;
+
+/// Detach timeout reported to the host (milliseconds)
+const DETACH_TIMEOUT_MS: u16 = 1000;
+
+/// Transfer size used by the ROM bootloader
+const TRANSFER_SIZE: u16 = 2048;
+
+/// DFU class requests
+mod request {
+    pub const DETACH: u8 = 0x00;
+    pub const GET_STATUS: u8 = 0x03;
+    pub const CLEAR_STATUS: u8 = 0x04;
+    pub const GET_STATE: u8 = 0x05;
+}
+
+/// DFU state: application idle
+const STATE_APP_IDLE: u8 = 0x00;
+
+/// DFU state: application detach
+const STATE_APP_DETACH: u8 = 0x01;
+
+/// DFU runtime state (must outlive the USB device)
+pub struct DfuState<'d> {
+    handler: Option<DfuHandler<'d>>,
+    detach: AtomicBool,
+}
+
+impl Default for DfuState<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DfuState<'_> {
+    /// Create new DFU state
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            handler: None,
+            detach: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Control request handler
+struct DfuHandler<'d> {
+    iface: InterfaceNumber,
+    detach: &'d AtomicBool,
+    response: [u8; 6],
+}
+
+impl DfuHandler<'_> {
+    fn is_ours(&self, req: &Request) -> bool {
+        req.request_type == RequestType::Class
+            && req.recipient == Recipient::Interface
+            && (req.index & 0xFF) as u8 == self.iface.0
+    }
+
+    fn state(&self) -> u8 {
+        if self.detach.load(Ordering::Relaxed) {
+            STATE_APP_DETACH
+        } else {
+            STATE_APP_IDLE
+        }
+    }
+}
+
+impl Handler for DfuHandler<'_> {
+    fn control_out(&mut self, req: Request, _data: &[u8]) -> Option<OutResponse> {
+        if !self.is_ours(&req) {
+            return None;
+        }
+        match req.request {
+            request::DETACH => {
+                self.detach.store(true, Ordering::Relaxed);
+                Some(OutResponse::Accepted)
+            }
+            request::CLEAR_STATUS => Some(OutResponse::Accepted),
+            _ => Some(OutResponse::Rejected),
+        }
+    }
+
+    fn control_in<'a>(&'a mut self, req: Request, _buf: &'a mut [u8]) -> Option<InResponse<'a>> {
+        if !self.is_ours(&req) {
+            return None;
+        }
+        match req.request {
+            request::GET_STATUS => {
+                // bStatus OK, bwPollTimeout 0, bState, iString 0
+                self.response = [0, 0, 0, 0, self.state(), 0];
+                Some(InResponse::Accepted(&self.response))
+            }
+            request::GET_STATE => {
+                self.response[0] = self.state();
+                Some(InResponse::Accepted(&self.response[..1]))
+            }
+            _ => Some(InResponse::Rejected),
+        }
+    }
+}
+
+/// DFU runtime interface
+pub struct DfuRuntime<'d> {
+    detach: &'d AtomicBool,
+}
+
+impl<'d> DfuRuntime<'d> {
+    /// Add the DFU runtime interface to a USB device builder
+    pub fn new<D: Driver<'d>>(builder: &mut Builder<'d, D>, state: &'d mut DfuState<'d>) -> Self {
+        let DfuState { handler, detach } = state;
+        let detach: &'d AtomicBool = detach;
+
+        let iface = {
+            let mut func = builder.function(USB_CLASS_APPLICATION, DFU_SUBCLASS, DFU_PROTOCOL_RUNTIME);
+            let mut iface = func.interface();
+            let number = iface.interface_number();
+            let mut alt =
+                iface.alt_setting(USB_CLASS_APPLICATION, DFU_SUBCLASS, DFU_PROTOCOL_RUNTIME, None);
+            let timeout = DETACH_TIMEOUT_MS.to_le_bytes();
+            let transfer = TRANSFER_SIZE.to_le_bytes();
+            alt.descriptor(
+                DFU_FUNCTIONAL,
+                &[
+                    DFU_ATTRIBUTES,
+                    timeout[0],
+                    timeout[1],
+                    transfer[0],
+                    transfer[1],
+                    0x1A, // bcdDFUVersion 1.1a
+                    0x01,
+                ],
+            );
+            number
+        };
+
+        let handler = handler.insert(DfuHandler {
+            iface,
+            detach,
+            response: [0; 6],
+        });
+        builder.handler(handler);
+
+        Self { detach }
+    }
+
+    /// Check if the host requested a detach
+    ///
+    /// The caller should finish any pending USB transfer and then call
+    /// `hal::bootloader::enter_bootloader`.
+    #[must_use]
+    pub fn detach_requested(&self) -> bool {
+        self.detach.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,94 @@
+//! `WinKeyer` Serial Port
+//!
+//! Second CDC ACM interface that speaks the K1EL `WinKeyer` protocol, so
+//! loggers see the radio as a CAT port plus a `WinKeyer` on two COM ports.
+//! Protocol handling lives in [`crate::radio::winkeyer`]; this module only
+//! moves bytes between the endpoint and the emulator.
+
+use embassy_usb::class::cdc_acm::CdcAcmClass;
+use embassy_usb::driver::{Driver, EndpointError};
+use embassy_usb::Builder;
+use heapless::Vec;
+
+use super::cdc::CdcState;
+use crate::config::USB_CDC_PACKET_SIZE;
+use crate::radio::keyer::Keyer;
+use crate::radio::winkeyer::WinKeyer;
+
+/// Reply buffer size (one packet)
+const REPLY_LEN: usize = USB_CDC_PACKET_SIZE as usize;
+
+/// `WinKeyer` CDC ACM port
+pub struct WinKeyerPort<'d, D: Driver<'d>> {
+    class: CdcAcmClass<'d, D>,
+}
+
+impl<'d, D: Driver<'d>> WinKeyerPort<'d, D> {
+    /// Add the `WinKeyer` serial interface to a USB device builder
+    pub fn new(builder: &mut Builder<'d, D>, state: &'d mut CdcState<'d>) -> Self {
+        Self {
+            class: CdcAcmClass::new(builder, state.state_mut(), USB_CDC_PACKET_SIZE),
+        }
+    }
+
+    /// Wait for the host to open the port (DTR set)
+    pub async fn wait_connection(&mut self) {
+        self.class.wait_connection().await;
+    }
+
+    /// Read one packet from the host and apply its commands to the keyer
+    ///
+    /// Immediate replies (version, status, echo test) are written back
+    /// in a single packet.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint is disabled (host disconnected)
+    pub async fn service(
+        &mut self,
+        winkeyer: &mut WinKeyer,
+        keyer: &mut Keyer,
+    ) -> Result<(), EndpointError> {
+        let mut buf = [0u8; REPLY_LEN];
+        let n = self.class.read_packet(&mut buf).await?;
+
+        let mut reply: Vec<u8, REPLY_LEN> = Vec::new();
+        for &byte in &buf[..n] {
+            if let Some(command) = winkeyer.feed(byte) {
+                if let Some(response) = winkeyer.apply(command, keyer) {
+                    let _ = reply.push(response);
+                }
+            }
+        }
+
+        if reply.is_empty() {
+            Ok(())
+        } else {
+            self.class.write_packet(&reply).await
+        }
+    }
+
+    /// Send pending status changes and character echoes
+    ///
+    /// Call periodically while the keyer is sending.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint is disabled (host disconnected)
+    pub async fn send_unsolicited(
+        &mut self,
+        winkeyer: &mut WinKeyer,
+        keyer: &mut Keyer,
+    ) -> Result<(), EndpointError> {
+        let mut out: Vec<u8, REPLY_LEN> = Vec::new();
+        while let Some(byte) = winkeyer.poll(keyer) {
+            if out.push(byte).is_err() {
+                break;
+            }
+        }
+
+        if out.is_empty() {
+            Ok(())
+        } else {
+            self.class.write_packet(&out).await
+        }
+    }
+}
@@ -3,6 +3,7 @@
 //! Tests for Automatic Gain Control
 //! Run with: cargo test --target x86_64-unknown-linux-gnu --no-default-features --features std --test agc_tests
 
+use sdr_dsp_core::agc::SmeterReading;
 use sdr_firmware::dsp::agc::{Agc, AgcConfig, SMeter};
 
 // =============================================================================
@@ -241,6 +242,22 @@ fn test_smeter_strong_signal() {
     assert!(s >= 5, "Strong signal should read high S-units, got {}", s);
 }
 
+#[test]
+fn test_smeter_reading() {
+    let mut meter = SMeter::new();
+    assert_eq!(meter.reading(), SmeterReading::S(0));
+
+    // Full-scale signal settles well above S9
+    for _ in 0..1000 {
+        meter.update_from_level(1.0);
+    }
+
+    match meter.reading() {
+        SmeterReading::S9Plus(db) => assert!(db >= 20, "Expected S9+20 or more, got S9+{}", db),
+        SmeterReading::S(n) => panic!("Expected S9+, got S{}", n),
+    }
+}
+
 #[test]
 fn test_smeter_weak_signal() {
     let mut meter = SMeter::new();
@@ -0,0 +1,123 @@
+//! DSP Stage Bypass Tests
+//!
+//! Crossfade behaviour of the bypass switches and A/B toggling in the
+//! audio chain.
+//! Run with: cargo test --features std
+
+use sdr_firmware::dsp::audio_chain::AudioChain;
+use sdr_firmware::dsp::bypass::{BypassSet, DspStage, StageBypass, BYPASS_FADE_MS};
+use sdr_firmware::dsp::filter_design::SsbBandwidth;
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Crossfade length in samples
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const FADE_SAMPLES: usize = (BYPASS_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+// =============================================================================
+// Stage Code Tests
+// =============================================================================
+
+#[test]
+fn test_stage_codes_round_trip() {
+    for stage in DspStage::ALL {
+        assert_eq!(DspStage::from_code(stage.code()), Some(stage));
+    }
+    assert_eq!(DspStage::from_code(4), None);
+    assert_eq!(DspStage::Agc.label(), "AGC");
+}
+
+// =============================================================================
+// Crossfade Tests
+// =============================================================================
+
+#[test]
+fn test_bypass_crossfades_without_steps() {
+    let mut bypass = StageBypass::new(SAMPLE_RATE);
+    // Dry and wet differ by a full unit, the worst case for a click
+    assert!((bypass.mix(0.0, 1.0) - 1.0).abs() < 1e-6);
+
+    bypass.set_bypassed(true);
+    let mut prev = 1.0;
+    for _ in 0..FADE_SAMPLES {
+        let out = bypass.mix(0.0, 1.0);
+        assert!(prev - out <= 1.5 / FADE_SAMPLES as f32, "Step too large");
+        prev = out;
+    }
+    assert!(prev.abs() < 1e-3, "Fade should finish in {BYPASS_FADE_MS} ms");
+    assert!(!bypass.is_fading());
+
+    bypass.set_bypassed(false);
+    assert!(bypass.is_fading());
+    let first = bypass.mix(0.0, 1.0);
+    assert!(first < 0.01, "Switching back should fade in");
+}
+
+#[test]
+fn test_bypass_set_toggle_and_mask() {
+    let mut set = BypassSet::new(SAMPLE_RATE);
+    assert_eq!(set.mask(), 0);
+
+    assert!(set.toggle(DspStage::Notch));
+    set.set(DspStage::Agc, true);
+    assert!(set.is_bypassed(DspStage::Notch));
+    assert_eq!(set.mask(), 0b1100);
+
+    assert!(!set.toggle(DspStage::Notch));
+    assert_eq!(set.mask(), 0b1000);
+
+    set.clear();
+    assert_eq!(set.mask(), 0);
+    // Cleared stages are fully back in circuit straight away
+    assert!((set.mix(DspStage::Agc, 0.0, 1.0) - 1.0).abs() < 1e-6);
+}
+
+// =============================================================================
+// Audio Chain A/B Tests
+// =============================================================================
+
+/// Run a 1 kHz tone through the chain and return the output
+fn run_tone(chain: &mut AudioChain, start: usize, len: usize) -> Vec<f32> {
+    (start..start + len)
+        .map(|n| {
+            let phase = 2.0 * core::f32::consts::PI * 1000.0 * n as f32 / SAMPLE_RATE;
+            chain.process(0.1 * phase.sin())
+        })
+        .collect()
+}
+
+#[test]
+fn test_chain_notch_bypass_is_audible_and_click_free() {
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    let notch = chain.notch_mut(0).unwrap();
+    notch.set_frequency(1000.0);
+    notch.set_enabled(true);
+    // Take the AGC out so its recovery doesn't mask the switch itself
+    chain.set_stage_bypass(DspStage::Agc, true);
+
+    // Let the filters settle
+    let settled = run_tone(&mut chain, 0, 48000);
+    let notched_peak = settled[47000..]
+        .iter()
+        .fold(0.0_f32, |peak, s| peak.max(s.abs()));
+
+    chain.set_stage_bypass(DspStage::Notch, true);
+    assert!(chain.is_stage_bypassed(DspStage::Notch));
+    let after = run_tone(&mut chain, 48000, 4800);
+
+    let mut prev = *settled.last().unwrap();
+    for &s in &after {
+        // A 0.05 peak 1 kHz tone moves less than 0.007 per sample
+        assert!((s - prev).abs() < 0.01, "Bypass switch should not click");
+        prev = s;
+    }
+    let bypassed_peak = after[2400..]
+        .iter()
+        .fold(0.0_f32, |peak, s| peak.max(s.abs()));
+    assert!(
+        bypassed_peak > notched_peak * 2.0,
+        "Bypassing the notch should let the tone through: {bypassed_peak} vs {notched_peak}"
+    );
+
+    assert!(!chain.toggle_stage_bypass(DspStage::Notch));
+}
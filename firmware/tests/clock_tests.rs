@@ -0,0 +1,168 @@
+//! Tests for calendar arithmetic, the wall clock and NMEA time parsing
+
+use sdr_firmware::clock::{days_in_month, is_leap_year, parse_nmea_rmc, Clock, DateTime, TimeSource};
+
+// ============================================================================
+// Calendar Tests
+// ============================================================================
+
+#[test]
+fn leap_years() {
+    assert!(is_leap_year(2024));
+    assert!(is_leap_year(2000));
+    assert!(!is_leap_year(2100));
+    assert!(!is_leap_year(2023));
+}
+
+#[test]
+fn month_lengths() {
+    assert_eq!(days_in_month(2024, 2), 29);
+    assert_eq!(days_in_month(2023, 2), 28);
+    assert_eq!(days_in_month(2023, 4), 30);
+    assert_eq!(days_in_month(2023, 12), 31);
+    assert_eq!(days_in_month(2023, 13), 0);
+}
+
+#[test]
+fn datetime_validation() {
+    assert!(DateTime::new(2024, 2, 29, 0, 0, 0).is_some());
+    assert!(DateTime::new(2023, 2, 29, 0, 0, 0).is_none());
+    assert!(DateTime::new(2024, 0, 1, 0, 0, 0).is_none());
+    assert!(DateTime::new(2024, 1, 1, 24, 0, 0).is_none());
+    assert!(DateTime::new(1969, 12, 31, 0, 0, 0).is_none());
+}
+
+#[test]
+fn unix_epoch() {
+    let epoch = DateTime::from_unix(0);
+    assert_eq!(epoch, DateTime::new(1970, 1, 1, 0, 0, 0).unwrap());
+    assert_eq!(epoch.to_unix(), 0);
+}
+
+#[test]
+fn unix_round_trip() {
+    // 2024-02-29 12:34:56 UTC
+    let time = DateTime::new(2024, 2, 29, 12, 34, 56).unwrap();
+    assert_eq!(time.to_unix(), 1_709_210_096);
+    assert_eq!(DateTime::from_unix(1_709_210_096), time);
+}
+
+#[test]
+fn unix_round_trip_sweep() {
+    let mut secs = 0u64;
+    while secs < 4_102_444_800 {
+        assert_eq!(DateTime::from_unix(secs).to_unix(), secs);
+        secs += 86_400 * 37 + 3_607;
+    }
+}
+
+#[test]
+fn weekday() {
+    // 1970-01-01 was a Thursday, 2024-06-01 a Saturday
+    assert_eq!(DateTime::new(1970, 1, 1, 0, 0, 0).unwrap().weekday(), 4);
+    assert_eq!(DateTime::new(2024, 6, 1, 0, 0, 0).unwrap().weekday(), 6);
+}
+
+#[test]
+fn offset_crosses_midnight() {
+    let utc = DateTime::new(2024, 12, 31, 23, 30, 0).unwrap();
+    let local = utc.offset_minutes(60);
+    assert_eq!(local, DateTime::new(2025, 1, 1, 0, 30, 0).unwrap());
+    let west = utc.offset_minutes(-300);
+    assert_eq!(west, DateTime::new(2024, 12, 31, 18, 30, 0).unwrap());
+}
+
+#[test]
+fn formatting() {
+    let time = DateTime::new(2024, 3, 5, 7, 8, 9).unwrap();
+    assert_eq!(time.format_hm().as_str(), "07:08");
+    assert_eq!(time.format_iso().as_str(), "2024-03-05T07:08:09Z");
+}
+
+// ============================================================================
+// Clock Tests
+// ============================================================================
+
+#[test]
+fn clock_unset() {
+    let clock = Clock::new();
+    assert!(!clock.is_set());
+    assert_eq!(clock.source(), TimeSource::None);
+    assert!(clock.utc(1000).is_none());
+    assert!(clock.ms_into_slot(1000, 15_000).is_none());
+}
+
+#[test]
+fn clock_advances_with_uptime() {
+    let mut clock = Clock::new();
+    let time = DateTime::new(2024, 6, 1, 12, 0, 0).unwrap();
+    clock.set(&time, 5_000, TimeSource::Cat);
+
+    assert!(clock.is_set());
+    assert_eq!(clock.source(), TimeSource::Cat);
+    assert_eq!(clock.utc(5_000), Some(time));
+    assert_eq!(
+        clock.utc(5_000 + 61_500),
+        DateTime::new(2024, 6, 1, 12, 1, 1)
+    );
+}
+
+#[test]
+fn clock_handles_uptime_wrap() {
+    let mut clock = Clock::new();
+    let time = DateTime::new(2024, 6, 1, 12, 0, 0).unwrap();
+    clock.set(&time, u32::MAX - 999, TimeSource::Gps);
+    assert_eq!(clock.utc(1_000), DateTime::new(2024, 6, 1, 12, 0, 2));
+}
+
+#[test]
+fn clock_local_offset() {
+    let mut clock = Clock::new();
+    clock.set(&DateTime::new(2024, 6, 1, 12, 0, 0).unwrap(), 0, TimeSource::Host);
+    clock.set_utc_offset(120);
+    assert_eq!(clock.local(0), DateTime::new(2024, 6, 1, 14, 0, 0));
+
+    clock.set_utc_offset(30 * 60);
+    assert_eq!(clock.utc_offset(), 14 * 60);
+}
+
+#[test]
+fn clock_slot_alignment() {
+    let mut clock = Clock::new();
+    clock.set_unix_ms(1_717_243_217_250, 0, TimeSource::Gps);
+    // 12:00:17.250 -> 2.25 s into an FT8 slot, 17.25 s into a WSPR slot
+    assert_eq!(clock.ms_into_slot(0, 15_000), Some(2_250));
+    assert_eq!(clock.ms_into_slot(0, 120_000), Some(17_250));
+}
+
+// ============================================================================
+// NMEA Tests
+// ============================================================================
+
+#[test]
+fn nmea_rmc_valid() {
+    let time = parse_nmea_rmc("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A");
+    // Two-digit years are taken as 20xx
+    assert_eq!(time, DateTime::new(2094, 3, 23, 12, 35, 19));
+}
+
+#[test]
+fn nmea_rmc_gnss_talker_without_checksum() {
+    let time = parse_nmea_rmc("$GNRMC,083559.00,A,4717.11437,N,00833.91522,E,0.004,77.52,091202,,,A");
+    assert_eq!(time, DateTime::new(2002, 12, 9, 8, 35, 59));
+}
+
+#[test]
+fn nmea_rmc_invalid_fix() {
+    assert!(parse_nmea_rmc("$GPRMC,123519,V,,,,,,,230394,,").is_none());
+}
+
+#[test]
+fn nmea_rmc_bad_checksum() {
+    assert!(parse_nmea_rmc("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*00").is_none());
+}
+
+#[test]
+fn nmea_other_sentence() {
+    assert!(parse_nmea_rmc("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47").is_none());
+}
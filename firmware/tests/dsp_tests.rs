@@ -3,10 +3,26 @@
 //! These tests run on the host with std feature enabled.
 //! Run with: cargo test --features std
 
+use sdr_firmware::dsp::audio_chain::{
+    AudioChain, Passband, MAX_IF_SHIFT_HZ, MAX_NOTCH_WIDTH_HZ, MIN_PASSBAND_HZ, NOTCH_COUNT,
+};
+use sdr_firmware::dsp::bypass::DspStage;
+use sdr_firmware::dsp::dtmf::{is_dtmf_digit, DtmfDetector, DTMF_BLOCK_MS};
+use sdr_firmware::dsp::equalizer::{EqCurve, EqPath, EqPresets, Equalizer, EQ_BANDS, MAX_EQ_GAIN_DB};
 use sdr_firmware::dsp::filter::{
     from_sample, to_sample, BiquadCoeffs, BiquadFilter, DcBlocker, FirCoefficients, FirFilter,
     MovingAverage,
 };
+use sdr_firmware::dsp::filter_design::{AmBandwidth, CwBandwidth, SsbBandwidth};
+use sdr_firmware::dsp::iq_capture::{CaptureState, IQ_CAPTURE_LEN, IQ_CHUNK_SAMPLES};
+use sdr_firmware::dsp::modulation::IqSample;
+use sdr_firmware::dsp::pipeline::{iq_from_adc, RxPipeline, DECIMATION};
+use sdr_firmware::radio::annunciator::Annunciation;
+use sdr_firmware::radio::control::DspCommand;
+use sdr_firmware::radio::state::AgcMode;
+use sdr_firmware::types::ring::IqRing;
+use sdr_firmware::types::{Band, Mode};
+use sdr_dsp_core::iq_balance::IqCalibration;
 
 // =============================================================================
 // Sample Conversion Tests
@@ -662,3 +678,686 @@ fn test_total_audio_chain_latency() {
         latency_ms
     );
 }
+
+// =============================================================================
+// Passband Tuning Tests
+// =============================================================================
+
+/// Output power of an audio chain (AGC bypassed) for a steady tone
+fn tone_power(chain: &mut AudioChain, hz: f32) -> f32 {
+    chain.set_stage_bypass(DspStage::Agc, true);
+    let rate = 48_000.0;
+    let mut power = 0.0;
+    for n in 0..9600 {
+        let phase = 2.0 * core::f32::consts::PI * hz * n as f32 / rate;
+        let out = chain.process(0.1 * phase.sin());
+        // Skip the settling time
+        if n >= 4800 {
+            power += out * out;
+        }
+    }
+    power
+}
+
+#[test]
+fn if_shift_slides_ssb_passband() {
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    assert_eq!(chain.passband(), Some(Passband::new(300.0, 2700.0)));
+
+    chain.set_if_shift(400.0);
+    assert_eq!(chain.passband(), Some(Passband::new(700.0, 3100.0)));
+
+    // Clamped shift, and the low edge stops short of DC
+    chain.set_if_shift(-5000.0);
+    assert_eq!(chain.if_shift(), -MAX_IF_SHIFT_HZ);
+    assert_eq!(chain.passband().unwrap().low_hz, MIN_PASSBAND_HZ);
+
+    chain.set_if_shift(0.0);
+    chain.set_passband_width(1000.0);
+    assert_eq!(chain.passband(), Some(Passband::new(1000.0, 2000.0)));
+    chain.set_passband_center(1200.0);
+    assert_eq!(chain.passband(), Some(Passband::new(700.0, 1700.0)));
+
+    // A bandwidth preset restores the preset edges
+    chain.set_ssb_bandwidth(SsbBandwidth::Narrow);
+    assert_eq!(chain.passband(), Some(Passband::new(400.0, 2200.0)));
+}
+
+#[test]
+fn if_shift_moves_interference_out_of_passband() {
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    let centered = tone_power(&mut chain, 2500.0);
+
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    chain.set_if_shift(-1200.0);
+    let shifted = tone_power(&mut chain, 2500.0);
+    assert!(
+        shifted * 3.0 < centered,
+        "shift should attenuate the tone: {shifted} vs {centered}"
+    );
+}
+
+#[test]
+fn cw_passband_follows_pitch_and_shift() {
+    let mut chain = AudioChain::new_cw(700.0, CwBandwidth::Hz400);
+    assert_eq!(chain.passband(), Some(Passband::new(500.0, 900.0)));
+
+    chain.set_if_shift(100.0);
+    assert_eq!(chain.passband(), Some(Passband::new(600.0, 1000.0)));
+    chain.set_cw_frequency(600.0);
+    assert_eq!(chain.passband(), Some(Passband::new(500.0, 900.0)));
+    chain.set_passband_width(100.0);
+    assert_eq!(chain.passband(), Some(Passband::new(650.0, 750.0)));
+
+    // A narrower CW filter rejects a tone the shift has moved away from
+    let on_pitch = tone_power(&mut chain, 700.0);
+    let off_pitch = tone_power(&mut chain, 1000.0);
+    assert!(off_pitch * 10.0 < on_pitch, "{off_pitch} vs {on_pitch}");
+
+    // No passband tuning in AM
+    let mut chain = AudioChain::new_am(AmBandwidth::Standard);
+    chain.set_if_shift(300.0);
+    assert_eq!(chain.passband(), None);
+}
+
+// =============================================================================
+// Dual Notch Tests
+// =============================================================================
+
+/// Peak output of an audio chain (AGC bypassed) for two steady tones
+fn two_tone_peak(chain: &mut AudioChain, low_hz: f32, high_hz: f32) -> f32 {
+    chain.set_stage_bypass(DspStage::Agc, true);
+    let rate = 48_000.0;
+    let tau = 2.0 * core::f32::consts::PI;
+    (0..48_000)
+        .map(|n| {
+            let t = n as f32 / rate;
+            let input = 0.05 * ((tau * low_hz * t).sin() + (tau * high_hz * t).sin());
+            (n, chain.process(input))
+        })
+        .filter(|&(n, _)| n >= 24_000)
+        .fold(0.0_f32, |peak, (_, s)| peak.max(s.abs()))
+}
+
+#[test]
+fn dual_notch_nulls_two_carriers() {
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    let open = two_tone_peak(&mut chain, 800.0, 1900.0);
+
+    // One notch takes out one carrier only
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    let notch = chain.notch_mut(0).unwrap();
+    notch.set_frequency(800.0);
+    notch.set_enabled(true);
+    let one = two_tone_peak(&mut chain, 800.0, 1900.0);
+    assert!(one > open * 0.4, "one carrier remains: {one} vs {open}");
+
+    let notch = chain.notch_mut(1).unwrap();
+    notch.set_frequency(1900.0);
+    notch.set_width(50.0);
+    notch.set_enabled(true);
+    let both = two_tone_peak(&mut chain, 800.0, 1900.0);
+    assert!(both * 10.0 < open, "both carriers nulled: {both} vs {open}");
+
+    assert!(chain.notch(NOTCH_COUNT).is_none());
+    assert_eq!(chain.notch(1).unwrap().width(), 50.0);
+    chain.notch_mut(1).unwrap().set_width(5000.0);
+    assert_eq!(chain.notch(1).unwrap().width(), MAX_NOTCH_WIDTH_HZ);
+}
+
+#[test]
+fn pipeline_keeps_notches_across_mode_change() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    let notch = pipeline.chain_mut().notch_mut(1).unwrap();
+    notch.set_frequency(1234.0);
+    notch.set_enabled(true);
+
+    pipeline.apply(DspCommand::SetMode(Mode::Am));
+    let notch = pipeline.chain_mut().notch(1).unwrap();
+    assert!(notch.is_enabled());
+    assert_eq!(notch.frequency(), 1234.0);
+    assert!(!pipeline.chain_mut().notch(0).unwrap().is_enabled());
+}
+
+// =============================================================================
+// Graphic Equalizer Tests
+// =============================================================================
+
+/// Linear gain for a whole number of dB
+fn db_gain(db: i8) -> f32 {
+    10.0_f32.powf(f32::from(db) / 20.0)
+}
+
+#[test]
+fn equalizer_bands_boost_and_cut() {
+    let mut eq = Equalizer::new(48_000.0);
+    eq.set_curve(EqCurve::new([12, 0, 0, 0, -12]));
+    let boost = eq.magnitude_at(200.0);
+    let cut = eq.magnitude_at(3000.0);
+    assert!((boost / db_gain(12) - 1.0).abs() < 0.1, "{boost}");
+    assert!((cut / db_gain(-12) - 1.0).abs() < 0.1, "{cut}");
+    // Between the two, within 3 dB of flat
+    let middle = eq.magnitude_at(1000.0);
+    assert!((0.7..1.4).contains(&middle), "{middle}");
+
+    // Off, or flat, passes the audio untouched
+    assert_eq!(eq.process(0.25), 0.25);
+    eq.set_enabled(true);
+    eq.set_curve(EqCurve::FLAT);
+    assert_eq!(eq.process(0.25), 0.25);
+
+    // Bands are clamped and bounded
+    assert!(eq.set_band(2, 40));
+    assert_eq!(eq.curve().gain_db(2), Some(MAX_EQ_GAIN_DB));
+    assert!(!eq.set_band(EQ_BANDS, 3));
+    assert_eq!(EqCurve::new([-20; EQ_BANDS]).gains_db(), [-MAX_EQ_GAIN_DB; EQ_BANDS]);
+}
+
+#[test]
+fn chain_equalizer_follows_the_agc() {
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    assert!(!chain.equalizer().is_enabled());
+    let flat = tone_power(&mut chain, 2000.0);
+
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::Standard);
+    chain.equalizer_mut().set_band(3, 12);
+    chain.equalizer_mut().set_enabled(true);
+    let boosted = tone_power(&mut chain, 2000.0);
+    // +12 dB is about 16x the power
+    assert!(boosted > flat * 10.0, "{boosted} vs {flat}");
+}
+
+#[test]
+fn pipeline_loads_eq_curve_per_mode() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    assert_eq!(
+        pipeline.chain_mut().equalizer().curve(),
+        EqCurve::rx_default(Mode::Usb)
+    );
+    pipeline.chain_mut().equalizer_mut().set_enabled(true);
+    let custom = EqCurve::new([0, 3, 6, 3, 0]);
+    pipeline.set_eq_curve(custom);
+
+    // Each mode has its own curve; the switch carries over
+    pipeline.apply(DspCommand::SetMode(Mode::Am));
+    let eq = pipeline.chain_mut().equalizer();
+    assert!(eq.is_enabled());
+    assert_eq!(eq.curve(), EqCurve::rx_default(Mode::Am));
+    pipeline.apply(DspCommand::SetMode(Mode::Lsb));
+    assert_eq!(pipeline.chain_mut().equalizer().curve(), custom);
+    assert_eq!(pipeline.eq_presets().get(Mode::Usb), custom);
+
+    pipeline.set_eq_presets(EqPresets::defaults(EqPath::Rx));
+    assert_eq!(
+        pipeline.chain_mut().equalizer().curve(),
+        EqCurve::rx_default(Mode::Lsb)
+    );
+}
+
+// =============================================================================
+// Passband SNR Tests
+// =============================================================================
+
+#[test]
+fn chain_measures_passband_snr() {
+    fn tone_snr(chain: &mut AudioChain, amplitude: f32) -> f32 {
+        chain.reset();
+        let mut seed = 1_u32;
+        for n in 0..48_000 {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (seed >> 8) as f32 / (1 << 24) as f32 * 0.02 - 0.01;
+            let tone = amplitude * (2.0 * core::f32::consts::PI * 1000.0 * n as f32 / 48_000.0).sin();
+            chain.process(tone + noise);
+        }
+        chain.snr().expect("measured").snr_db
+    }
+
+    let mut chain = AudioChain::new_ssb(SsbBandwidth::default());
+    assert!(chain.snr().is_none());
+    let strong = tone_snr(&mut chain, 0.05);
+    let weak = tone_snr(&mut chain, 0.005);
+    assert!(strong > 15.0, "{strong}");
+    assert!((strong - weak - 20.0).abs() < 3.0, "{strong} vs {weak}");
+
+    // The pipeline reports its chain's reading
+    let pipeline = RxPipeline::new(Mode::Usb);
+    assert!(pipeline.snr().is_none());
+}
+
+// =============================================================================
+// CW Pitch Tests
+// =============================================================================
+
+#[test]
+fn cw_chain_measures_pitch_offset() {
+    let mut chain = AudioChain::new_cw(700.0, CwBandwidth::default());
+    assert!(chain.pitch_offset_hz().is_none());
+    let tone = |n: usize| 0.1 * (2.0 * core::f32::consts::PI * 740.0 * n as f32 / 48_000.0).sin();
+    for n in 0..24_000 {
+        chain.process(tone(n));
+    }
+    let offset = chain.pitch_offset_hz().expect("tone measured");
+    assert!((offset - 40.0).abs() < 1.0, "{offset}");
+
+    // The reference follows the sidetone pitch
+    chain.set_sidetone_frequency(760.0);
+    for n in 0..24_000 {
+        chain.process(tone(n));
+    }
+    let offset = chain.pitch_offset_hz().expect("tone measured");
+    assert!((offset + 20.0).abs() < 1.0, "{offset}");
+
+    // SSB chains measure against the passband centre, AM chains not at all
+    let mut ssb = AudioChain::new_ssb(SsbBandwidth::Standard);
+    for n in 0..24_000 {
+        ssb.process(tone(n));
+    }
+    let offset = ssb.pitch_offset_hz().expect("tone measured");
+    assert!((offset + 760.0).abs() < 5.0, "{offset}");
+    let mut am = AudioChain::new_am(AmBandwidth::default());
+    for n in 0..24_000 {
+        am.process(tone(n));
+    }
+    assert!(am.pitch_offset_hz().is_none());
+}
+
+// =============================================================================
+
+/// Feed `ms` of two tones (or silence) to a detector, collecting digits
+fn feed_dtmf(
+    detector: &mut DtmfDetector,
+    tones: Option<(f32, f32, f32, f32)>,
+    ms: u32,
+    digits: &mut Vec<char>,
+) {
+    let tau = 2.0 * core::f32::consts::PI;
+    for n in 0..48 * ms {
+        let t = n as f32 / 48_000.0;
+        let sample = tones.map_or(0.0, |(low, low_amp, high, high_amp)| {
+            low_amp * (tau * low * t).sin() + high_amp * (tau * high * t).sin()
+        });
+        digits.extend(detector.process(sample));
+    }
+}
+
+#[test]
+fn dtmf_decodes_key_sequence() {
+    let mut detector = DtmfDetector::new(48_000);
+    let mut digits = Vec::new();
+    // Nothing while switched off
+    feed_dtmf(&mut detector, Some((770.0, 0.3, 1336.0, 0.3)), 100, &mut digits);
+    assert!(digits.is_empty());
+
+    detector.set_enabled(true);
+    let keys = [
+        ('1', 697.0, 1209.0),
+        ('5', 770.0, 1336.0),
+        ('9', 852.0, 1477.0),
+        ('#', 941.0, 1477.0),
+        ('D', 941.0, 1633.0),
+    ];
+    for (_, low, high) in keys {
+        feed_dtmf(&mut detector, Some((low, 0.3, high, 0.25)), 60, &mut digits);
+        feed_dtmf(&mut detector, None, 60, &mut digits);
+    }
+    assert_eq!(digits, keys.map(|(key, _, _)| key));
+    assert!(digits.iter().all(|&d| is_dtmf_digit(d)));
+    assert!(!is_dtmf_digit('E'));
+}
+
+#[test]
+fn dtmf_long_press_is_one_digit() {
+    let mut detector = DtmfDetector::new(48_000);
+    detector.set_enabled(true);
+    let mut digits = Vec::new();
+    feed_dtmf(&mut detector, Some((852.0, 0.3, 1336.0, 0.3)), 1000, &mut digits);
+    assert_eq!(digits, ['8']);
+
+    // A single short dropout does not repeat it; a real gap does
+    feed_dtmf(&mut detector, None, DTMF_BLOCK_MS, &mut digits);
+    feed_dtmf(&mut detector, Some((852.0, 0.3, 1336.0, 0.3)), 100, &mut digits);
+    assert_eq!(digits, ['8']);
+    feed_dtmf(&mut detector, None, 50, &mut digits);
+    feed_dtmf(&mut detector, Some((852.0, 0.3, 1336.0, 0.3)), 100, &mut digits);
+    assert_eq!(digits, ['8', '8']);
+}
+
+#[test]
+fn dtmf_rejects_invalid_tones() {
+    let mut detector = DtmfDetector::new(48_000);
+    detector.set_enabled(true);
+    let mut digits = Vec::new();
+
+    // Single tone, too much twist either way, too weak, off-grid tones
+    feed_dtmf(&mut detector, Some((770.0, 0.3, 1336.0, 0.0)), 200, &mut digits);
+    feed_dtmf(&mut detector, Some((770.0, 0.3, 1336.0, 0.09)), 200, &mut digits);
+    feed_dtmf(&mut detector, Some((770.0, 0.1, 1336.0, 0.3)), 200, &mut digits);
+    feed_dtmf(&mut detector, Some((770.0, 0.01, 1336.0, 0.01)), 200, &mut digits);
+    feed_dtmf(&mut detector, Some((1000.0, 0.3, 2000.0, 0.3)), 200, &mut digits);
+    assert!(digits.is_empty(), "{digits:?}");
+
+    // Two row tones at once are not a digit
+    let tau = 2.0 * core::f32::consts::PI;
+    for n in 0..48 * 200 {
+        let t = n as f32 / 48_000.0;
+        let sample = 0.2 * (tau * 697.0 * t).sin()
+            + 0.2 * (tau * 852.0 * t).sin()
+            + 0.2 * (tau * 1209.0 * t).sin();
+        assert!(detector.process(sample).is_none());
+    }
+
+    // A tone pair buried in a louder broadband tone is rejected
+    for n in 0..48 * 200 {
+        let t = n as f32 / 48_000.0;
+        let sample = 0.05 * (tau * 770.0 * t).sin()
+            + 0.05 * (tau * 1336.0 * t).sin()
+            + 0.6 * (tau * 3000.0 * t).sin();
+        assert!(detector.process(sample).is_none());
+    }
+}
+
+#[test]
+fn pipeline_queues_dtmf_digits() {
+    let mut pipeline = RxPipeline::new(Mode::Fm);
+    assert!(!pipeline.dtmf().is_enabled());
+    pipeline.dtmf_mut().set_enabled(true);
+    pipeline.reset();
+    assert_eq!(pipeline.pop_dtmf_digit(), None);
+}
+
+// =============================================================================
+// Receive Pipeline Tests
+// =============================================================================
+
+/// Interleaved I/Q ADC codes for a tone `hz` above the LO
+fn iq_tone(hz: f32, pairs: usize, amplitude: f32) -> Vec<u16> {
+    let rate = 192_000.0;
+    (0..pairs)
+        .flat_map(|n| {
+            let phase = 2.0 * core::f32::consts::PI * hz * n as f32 / rate;
+            [
+                (2048.0 + amplitude * phase.cos()) as u16,
+                (2048.0 + amplitude * phase.sin()) as u16,
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn pipeline_zero_beat_follows_sideband() {
+    for (mode, hz) in [(Mode::Cw, 760.0), (Mode::CwR, -760.0)] {
+        let mut pipeline = RxPipeline::new(mode);
+        assert_eq!(pipeline.zero_beat_hz(), None);
+        let iq = iq_tone(hz, 96_000, 500.0);
+        let mut audio = [0.0f32; 128];
+        for block in iq.chunks(512) {
+            pipeline.process_block(block, &mut audio);
+        }
+        let correction = pipeline.zero_beat_hz().expect("tone measured");
+        let expected = if mode == Mode::Cw { 60 } else { -60 };
+        assert!((correction - expected).abs() <= 2, "{mode:?}: {correction}");
+    }
+
+    // The sidetone pitch survives a mode change
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    pipeline.set_sidetone_hz(600.0);
+    pipeline.apply(DspCommand::SetMode(Mode::Cw));
+    assert_eq!(pipeline.sidetone_hz(), 600.0);
+}
+
+#[test]
+fn pipeline_auto_tune_centers_ssb_tone() {
+    // A data carrier 200 Hz above the middle of the 300-2700 Hz passband
+    for (mode, hz, expected) in [(Mode::Usb, 1700.0, 200), (Mode::Lsb, -1700.0, -200)] {
+        let mut pipeline = RxPipeline::new(mode);
+        pipeline.apply(DspCommand::AutoTune);
+        assert_eq!(pipeline.take_tune_correction(), None);
+
+        let iq = iq_tone(hz, 96_000, 500.0);
+        let mut audio = [0.0f32; 128];
+        for block in iq.chunks(512) {
+            pipeline.process_block(block, &mut audio);
+        }
+        pipeline.apply(DspCommand::AutoTune);
+        let correction = pipeline.take_tune_correction().expect("tone measured");
+        assert!((correction - expected).abs() <= 5, "{mode:?}: {correction}");
+
+        // The correction is taken once
+        assert_eq!(pipeline.take_tune_correction(), None);
+    }
+
+    // AM and FM have no reference to tune to
+    let mut pipeline = RxPipeline::new(Mode::Am);
+    let iq = iq_tone(1000.0, 96_000, 500.0);
+    let mut audio = [0.0f32; 128];
+    for block in iq.chunks(512) {
+        pipeline.process_block(block, &mut audio);
+    }
+    pipeline.apply(DspCommand::AutoTune);
+    assert_eq!(pipeline.take_tune_correction(), None);
+}
+
+#[test]
+fn pipeline_decimates_across_blocks() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    let mut audio = [0.0f32; 64];
+    assert_eq!(DECIMATION, 4);
+
+    // 6 pairs: one output now, the remaining two carry over
+    let iq = vec![2048u16; 12];
+    assert_eq!(pipeline.process_block(&iq, &mut audio), 1);
+    assert_eq!(pipeline.process_block(&iq[..4], &mut audio), 1);
+
+    // Output is limited to the audio buffer
+    let iq = vec![2048u16; 512];
+    assert_eq!(pipeline.process_block(&iq, &mut audio[..10]), 10);
+}
+
+#[test]
+fn pipeline_queued_samples_match_codes() {
+    let iq = iq_tone(1000.0, 2048, 500.0);
+    let mut direct = RxPipeline::new(Mode::Usb);
+    let mut queued = RxPipeline::new(Mode::Usb);
+    let mut ring = IqRing::<1024>::new();
+    let (mut producer, mut consumer) = ring.split();
+    let mut block = [Default::default(); 256];
+    let (mut expected, mut audio) = ([0.0f32; 64], [0.0f32; 64]);
+
+    assert_eq!(iq_from_adc(3072, 1024).i, 0.5);
+    assert_eq!(iq_from_adc(3072, 1024).q, -0.5);
+    for codes in iq.chunks(512) {
+        let samples: Vec<_> = codes.chunks(2).map(|p| iq_from_adc(p[0], p[1])).collect();
+        assert_eq!(producer.push_slice(&samples), 256);
+        assert_eq!(consumer.pop_slice(&mut block), 256);
+        assert_eq!(direct.process_block(codes, &mut expected), 64);
+        assert_eq!(queued.process_iq(&block, &mut audio), 64);
+        assert_eq!(audio, expected);
+    }
+    assert_eq!(consumer.underruns(), 0);
+}
+
+#[test]
+fn pipeline_taps_decimated_iq_alongside_audio() {
+    let mut plain = RxPipeline::new(Mode::Usb);
+    let mut tapped = RxPipeline::new(Mode::Usb);
+    // I at half scale, Q at minus a quarter
+    let block = [iq_from_adc(3072, 1536); 256];
+    let (mut expected, mut audio) = ([0.0f32; 64], [0.0f32; 64]);
+    let mut tap = [IqSample::default(); 64];
+
+    assert_eq!(plain.process_iq(&block, &mut expected), 64);
+    assert_eq!(tapped.process_iq_tap(&block, &mut audio, &mut tap), 64);
+    assert_eq!(audio, expected);
+    let first = tap[0];
+    assert!((first.i - 0.5).abs() < 1e-3 && (first.q + 0.25).abs() < 1e-3);
+    assert!(tap.iter().all(|s| s.i == first.i && s.q == first.q));
+
+    // A short tap only takes what fits
+    let mut short = [IqSample::default(); 8];
+    assert_eq!(tapped.process_iq_tap(&block, &mut audio, &mut short), 64);
+    assert!(short.iter().all(|s| s.i == first.i && s.q == first.q));
+}
+
+#[test]
+fn pipeline_captures_decimated_iq() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    let mut audio = [0.0f32; 64];
+    // I at half scale, Q at minus a quarter
+    let iq: Vec<u16> = [3072u16, 1536].repeat(64);
+    pipeline.process_block(&iq, &mut audio);
+    assert_eq!(pipeline.capture().state(), CaptureState::Idle);
+
+    pipeline.apply(DspCommand::CaptureIq(25));
+    assert_eq!(pipeline.capture().state(), CaptureState::Capturing);
+    assert_eq!(pipeline.capture_mut().next_chunk(), None);
+    pipeline.process_block(&iq, &mut audio);
+    assert_eq!(pipeline.capture().len(), 16);
+    pipeline.process_block(&iq, &mut audio);
+    assert_eq!(pipeline.capture().state(), CaptureState::Complete);
+    assert_eq!(pipeline.capture().len(), 25);
+    assert!(pipeline.capture().samples().iter().all(|&s| s == [16383, -8191]));
+
+    // Streamed out a chunk at a time
+    let capture = pipeline.capture_mut();
+    let offsets: Vec<usize> = core::iter::from_fn(|| capture.next_chunk()).collect();
+    assert_eq!(offsets, [0, IQ_CHUNK_SAMPLES, 2 * IQ_CHUNK_SAMPLES]);
+    assert_eq!(capture.chunk(20).len(), 5);
+    assert!(capture.chunk(25).is_empty());
+
+    // Re-arming starts over, and the length is clamped
+    assert_eq!(capture.arm(5000), IQ_CAPTURE_LEN);
+    assert!(capture.is_empty());
+    assert!(capture.chunk(0).is_empty());
+}
+
+#[test]
+fn pipeline_loads_band_iq_calibration() {
+    /// Captured Q of a steady I at half scale and Q at minus a quarter
+    fn captured_q(pipeline: &mut RxPipeline) -> f32 {
+        let mut audio = [0.0f32; 64];
+        let iq: Vec<u16> = [3072u16, 1536].repeat(64);
+        pipeline.apply(DspCommand::CaptureIq(4));
+        pipeline.process_block(&iq, &mut audio);
+        f32::from(pipeline.capture().samples()[3][1]) / 32767.0
+    }
+
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    let mut table = [IqCalibration::IDENTITY; Band::COUNT];
+    table[Band::M20.index()] = IqCalibration::new(0, 500);
+    pipeline.set_iq_calibrations(table);
+    assert!((captured_q(&mut pipeline) + 0.25).abs() < 1e-3);
+
+    // Q' = Q / cos 5 - I tan 5
+    pipeline.apply(DspCommand::SetBand(Some(Band::M20)));
+    assert_eq!(pipeline.iq_balance().calibration(), IqCalibration::new(0, 500));
+    assert!((captured_q(&mut pipeline) + 0.2947).abs() < 1e-3);
+
+    // Changing the current band's calibration applies at once
+    pipeline.set_iq_calibration(Band::M20, IqCalibration::new(-100, 0));
+    assert_eq!(pipeline.iq_calibration(Band::M20), IqCalibration::new(-100, 0));
+    assert!((captured_q(&mut pipeline) + 0.2805).abs() < 1e-3);
+    pipeline.set_iq_calibration(Band::M40, IqCalibration::new(300, 0));
+    assert_eq!(pipeline.iq_balance().calibration(), IqCalibration::new(-100, 0));
+
+    // Outside the bands there is no correction
+    pipeline.apply(DspCommand::SetBand(None));
+    assert_eq!(pipeline.iq_balance().calibration(), IqCalibration::IDENTITY);
+
+    pipeline.apply(DspCommand::SetIqAdaptive(true));
+    assert!(pipeline.iq_balance().is_adaptive());
+}
+
+#[test]
+fn pipeline_receives_tone_and_mutes_on_transmit() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    let iq = iq_tone(1000.0, 256, 1000.0);
+    let mut audio = [0.0f32; 64];
+
+    let mut energy = 0.0;
+    for _ in 0..40 {
+        let n = pipeline.process_block(&iq, &mut audio);
+        energy = audio[..n].iter().map(|s| s * s).sum::<f32>();
+    }
+    assert!(energy > 1e-4, "tone should be audible: {energy}");
+
+    pipeline.apply(DspCommand::SetTransmit(true));
+    let n = pipeline.process_block(&iq, &mut audio);
+    assert!(audio[..n].iter().all(|&s| s.abs() < 1e-6));
+}
+
+#[test]
+fn pipeline_announces_on_the_sidetone() {
+    let mut pipeline = RxPipeline::new(Mode::Usb);
+    pipeline.apply(DspCommand::SetTransmit(true));
+    let iq = [2048u16; 2 * 4 * DECIMATION * 48];
+    let mut audio = [0.0f32; 4 * 48];
+
+    pipeline.apply(DspCommand::Announce(Annunciation::Error));
+    let mut peak = 0.0f32;
+    for _ in 0..10 {
+        let n = pipeline.process_block(&iq, &mut audio);
+        peak = audio[..n].iter().fold(peak, |p, s| p.max(s.abs()));
+    }
+    assert!(peak > 0.1, "annunciation should sound: {peak}");
+
+    // "E" is one dit, so the sidetone is quiet again well within a second
+    for _ in 0..250 {
+        pipeline.process_block(&iq, &mut audio);
+    }
+    let n = pipeline.process_block(&iq, &mut audio);
+    assert!(audio[..n].iter().all(|&s| s.abs() < 1e-3));
+    assert!(!pipeline.annunciator_mut().is_active());
+}
+
+#[test]
+fn pipeline_follows_commands() {
+    let mut pipeline = RxPipeline::default();
+    pipeline.apply(DspCommand::SetMode(Mode::Am));
+    pipeline.apply(DspCommand::SetAgc(AgcMode::Off));
+    assert_eq!(pipeline.mode(), Mode::Am);
+    assert_eq!(pipeline.agc(), AgcMode::Off);
+    assert!(pipeline.chain_mut().is_stage_bypassed(DspStage::Agc));
+
+    // Mode changes keep the AGC and noise blanker settings
+    pipeline.apply(DspCommand::SetNoiseBlanker(true));
+    pipeline.apply(DspCommand::SetMode(Mode::Lsb));
+    let chain = pipeline.chain_mut();
+    assert!(chain.is_stage_bypassed(DspStage::Agc));
+    assert!(!chain.is_stage_bypassed(DspStage::NoiseBlanker));
+    assert!(pipeline.blanker().is_enabled());
+}
+
+#[test]
+fn pipeline_blanks_iq_impulses() {
+    /// Largest audio error impulses cause on a carrier
+    fn impulse_error(blanker: bool) -> f32 {
+        let mut clean = RxPipeline::new(Mode::Usb);
+        let mut noisy = RxPipeline::new(Mode::Usb);
+        for pipeline in [&mut clean, &mut noisy] {
+            pipeline.apply(DspCommand::SetAgc(AgcMode::Off));
+            pipeline.apply(DspCommand::SetNoiseBlanker(blanker));
+            pipeline.blanker_mut().set_width(50.0);
+        }
+        let iq = iq_tone(1000.0, 256, 20.0);
+        let mut impulsive = iq.clone();
+        // A two-sample impulse at full scale
+        impulsive[200..204].fill(4095);
+
+        let mut expected = [0.0f32; 64];
+        let mut audio = [0.0f32; 64];
+        let mut error = 0.0f32;
+        for block in 0..60 {
+            let n = clean.process_block(&iq, &mut expected);
+            let input = if block >= 40 { &impulsive } else { &iq };
+            noisy.process_block(input, &mut audio);
+            for (a, e) in audio[..n].iter().zip(&expected) {
+                error = error.max((a - e).abs());
+            }
+        }
+        error
+    }
+
+    let open = impulse_error(false);
+    let blanked = impulse_error(true);
+    assert!(blanked * 4.0 < open, "{blanked} vs {open}");
+}
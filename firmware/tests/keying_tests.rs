@@ -0,0 +1,166 @@
+//! CW Keying Envelope Tests
+//!
+//! Edge shape, timing and the spectral occupancy of a keyed carrier.
+//! Run with: cargo test --features std
+
+use sdr_firmware::dsp::keying::{
+    EnvelopeShape, KeyingEnvelope, MAX_RISE_TIME_MS, MIN_RISE_TIME_MS,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Carrier frequency used for the occupancy tests
+const CARRIER_HZ: f64 = 1000.0;
+
+/// Dot length at 30 WPM in samples (40 ms)
+const DOT_SAMPLES: usize = 1920;
+
+/// Key a carrier with three dots, shaped by `envelope` (or hard keyed)
+fn keyed_carrier(mut envelope: Option<KeyingEnvelope>) -> Vec<f64> {
+    let len = DOT_SAMPLES * 6;
+    (0..len)
+        .map(|n| {
+            let key_down = (n / DOT_SAMPLES).is_multiple_of(2);
+            let carrier =
+                (2.0 * std::f64::consts::PI * CARRIER_HZ * n as f64 / f64::from(SAMPLE_RATE)).sin();
+            let gain = match envelope.as_mut() {
+                Some(env) => {
+                    env.set_key(key_down);
+                    f64::from(env.next_gain())
+                }
+                None => f64::from(u8::from(key_down)),
+            };
+            carrier * gain
+        })
+        .collect()
+}
+
+/// Fraction of signal energy further than `offset_hz` from the carrier
+fn energy_outside(signal: &[f64], offset_hz: f64) -> f64 {
+    let n = signal.len() as f64;
+    let bin_hz = f64::from(SAMPLE_RATE) / n;
+    let mut inside = 0.0;
+    let mut outside = 0.0;
+    let mut freq = 0.0;
+    while freq < 6000.0 {
+        let w = 2.0 * std::f64::consts::PI * freq / f64::from(SAMPLE_RATE);
+        let (re, im) = signal
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (k, &x)| {
+                let phase = w * k as f64;
+                (re + x * phase.cos(), im - x * phase.sin())
+            });
+        let power = re * re + im * im;
+        if (freq - CARRIER_HZ).abs() > offset_hz {
+            outside += power;
+        } else {
+            inside += power;
+        }
+        freq += bin_hz;
+    }
+    outside / (inside + outside)
+}
+
+// =============================================================================
+// Edge Shape Tests
+// =============================================================================
+
+#[test]
+fn test_shapes_span_zero_to_one() {
+    for shape in [EnvelopeShape::RaisedCosine, EnvelopeShape::Blackman] {
+        assert!(shape.gain(0.0).abs() < 1e-6, "{shape:?} should start at 0");
+        assert!((shape.gain(1.0) - 1.0).abs() < 1e-6, "{shape:?} should end at 1");
+
+        let mut prev = 0.0;
+        for i in 1..=100 {
+            let g = shape.gain(i as f32 / 100.0);
+            assert!(g >= prev, "{shape:?} should rise monotonically");
+            prev = g;
+        }
+    }
+    assert!((EnvelopeShape::RaisedCosine.gain(0.5) - 0.5).abs() < 1e-6);
+    assert_eq!(EnvelopeShape::default(), EnvelopeShape::RaisedCosine);
+}
+
+// =============================================================================
+// Timing Tests
+// =============================================================================
+
+#[test]
+fn test_rise_time_reaches_full_drive() {
+    let mut env = KeyingEnvelope::new(EnvelopeShape::RaisedCosine, SAMPLE_RATE);
+    env.set_rise_time(4.0, SAMPLE_RATE);
+    assert!(!env.is_active());
+
+    env.set_key(true);
+    // 4 ms at 48 kHz = 192 samples
+    for _ in 0..191 {
+        assert!(env.next_gain() < 1.0);
+    }
+    assert!((env.next_gain() - 1.0).abs() < 1e-6);
+
+    env.set_key(false);
+    for _ in 0..192 {
+        env.next_gain();
+    }
+    assert!(!env.is_active());
+    assert!(env.process(1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_rise_time_clamped() {
+    let mut env = KeyingEnvelope::new(EnvelopeShape::Blackman, SAMPLE_RATE);
+    env.set_rise_time(1.0, SAMPLE_RATE);
+    assert!((env.rise_time_ms() - MIN_RISE_TIME_MS).abs() < f32::EPSILON);
+    env.set_rise_time(20.0, SAMPLE_RATE);
+    assert!((env.rise_time_ms() - MAX_RISE_TIME_MS).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_key_up_mid_rise_turns_around_smoothly() {
+    let mut env = KeyingEnvelope::new(EnvelopeShape::RaisedCosine, SAMPLE_RATE);
+    env.set_key(true);
+    let mut prev = 0.0;
+    for _ in 0..100 {
+        prev = env.next_gain();
+    }
+
+    env.set_key(false);
+    let next = env.next_gain();
+    assert!(next < prev, "Gain should start falling");
+    assert!(prev - next < 0.02, "No jump on early key-up");
+}
+
+// =============================================================================
+// Spectral Occupancy Tests
+// =============================================================================
+
+#[test]
+fn test_shaped_keying_reduces_key_clicks() {
+    let hard = energy_outside(&keyed_carrier(None), 200.0);
+
+    for shape in [EnvelopeShape::RaisedCosine, EnvelopeShape::Blackman] {
+        let mut env = KeyingEnvelope::new(shape, SAMPLE_RATE);
+        env.set_rise_time(5.0, SAMPLE_RATE);
+        let shaped = energy_outside(&keyed_carrier(Some(env)), 200.0);
+
+        // At least 10 dB less energy beyond +/-200 Hz than hard keying
+        assert!(
+            shaped < hard / 10.0,
+            "{shape:?} {shaped:e} vs hard keying {hard:e}"
+        );
+    }
+}
+
+#[test]
+fn test_longer_rise_narrows_occupancy() {
+    let mut fast = KeyingEnvelope::new(EnvelopeShape::RaisedCosine, SAMPLE_RATE);
+    fast.set_rise_time(MIN_RISE_TIME_MS, SAMPLE_RATE);
+    let mut slow = KeyingEnvelope::new(EnvelopeShape::RaisedCosine, SAMPLE_RATE);
+    slow.set_rise_time(MAX_RISE_TIME_MS, SAMPLE_RATE);
+
+    let fast = energy_outside(&keyed_carrier(Some(fast)), 150.0);
+    let slow = energy_outside(&keyed_carrier(Some(slow)), 150.0);
+    assert!(slow < fast, "8 ms {slow:e} should occupy less than 3 ms {fast:e}");
+}
@@ -6,6 +6,7 @@ use sdr_firmware::dsp::modulation::{
     AmDemodulator, AmModulator, FmDemodulator, HilbertTransform, IqSample,
 };
 use sdr_firmware::dsp::oscillator::SineOscillator;
+use sdr_firmware::types::IqOrientation;
 
 const SAMPLE_RATE: f32 = 48000.0;
 
@@ -574,6 +575,25 @@ fn test_ssb_modulator_usb_vs_lsb_q_sign_differs() {
     assert!(lsb_iq.q.is_finite());
 }
 
+#[test]
+fn test_ssb_modulator_orientation() {
+    let mut normal = SsbModulator::new(SAMPLE_RATE);
+    let mut swapped = SsbModulator::new(SAMPLE_RATE);
+    let mut inverted = SsbModulator::new(SAMPLE_RATE);
+    swapped.set_orientation(IqOrientation::Swapped);
+    inverted.set_orientation(IqOrientation::InvertQ);
+
+    let mut osc = make_sine_osc(1000.0, SAMPLE_RATE);
+    for _ in 0..200 {
+        let audio = osc.next();
+        let n = normal.process(audio);
+        let s = swapped.process(audio);
+        let v = inverted.process(audio);
+        assert!((s.i - n.q).abs() < 1e-6 && (s.q - n.i).abs() < 1e-6);
+        assert!((v.i - n.i).abs() < 1e-6 && (v.q + n.q).abs() < 1e-6);
+    }
+}
+
 // ============================================================================
 // Advanced Hilbert Transform Tests
 // ============================================================================
@@ -3,7 +3,7 @@
 //! Tests for digital oscillators (sine, quadrature, NCO)
 //! Run with: cargo test --target x86_64-unknown-linux-gnu --no-default-features --features std --test oscillator_tests
 
-use sdr_firmware::dsp::oscillator::{Nco, QuadratureOscillator, SineOscillator};
+use sdr_firmware::dsp::oscillator::{CwToneGenerator, Nco, QuadratureOscillator, SineOscillator};
 
 const EPSILON: f32 = 0.01;
 
@@ -555,3 +555,35 @@ fn test_quadrature_orthogonality_integration() {
         normalized
     );
 }
+
+// =============================================================================
+// CW Tone Generator Tests
+// =============================================================================
+
+#[test]
+fn test_cw_tone_raised_cosine_envelope() {
+    let sample_rate = 48000.0;
+    let mut tone = CwToneGenerator::new(700.0, sample_rate);
+    tone.set_key(true);
+
+    // 5 ms rise = 240 samples; sample the envelope along the way
+    let mut levels = Vec::new();
+    for _ in 0..240 {
+        tone.next();
+        levels.push(tone.level());
+    }
+
+    // Monotonic rise, slow at both ends, half amplitude at mid-ramp
+    assert!(levels.windows(2).all(|w| w[1] >= w[0]));
+    assert!(levels[11] < 0.01, "Start of ramp should be gentle, got {}", levels[11]);
+    assert!((levels[119] - 0.5).abs() < 0.02, "Mid-ramp should be ~0.5, got {}", levels[119]);
+    assert!((levels[239] - 1.0).abs() < EPSILON);
+
+    // Key up ramps back down to silence
+    tone.set_key(false);
+    for _ in 0..240 {
+        tone.next();
+    }
+    assert!(!tone.is_active());
+    assert_eq!(tone.next(), 0.0);
+}
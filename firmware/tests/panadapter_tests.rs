@@ -0,0 +1,215 @@
+//! Tests for the panadapter stream framing, rate control and drop handling
+
+use sdr_firmware::dsp::spectrum::WaterfallRow;
+use sdr_firmware::protocol::panadapter::{
+    PanadapterDecoder, PanadapterFrame, PanadapterStream, PeakListFrame, StreamItem, FRAME_BINS,
+    FRAME_START, MAX_FPS, MAX_FRAME_LEN, MAX_FRAME_PEAKS, MIN_FPS, PEAK_KIND,
+};
+use sdr_firmware::types::Frequency;
+
+fn row(timestamp: u32) -> WaterfallRow {
+    let mut row = WaterfallRow {
+        timestamp,
+        ..WaterfallRow::default()
+    };
+    for (col, power) in row.data.iter_mut().enumerate() {
+        *power = (col % 101) as i8;
+    }
+    row
+}
+
+fn frames(decoder: &mut PanadapterDecoder, bytes: &[u8]) -> Vec<PanadapterFrame> {
+    bytes
+        .iter()
+        .filter_map(|&b| match decoder.feed(b) {
+            Some(StreamItem::Frame(frame)) => Some(frame),
+            _ => None,
+        })
+        .collect()
+}
+
+// ============================================================================
+// Framing Tests
+// ============================================================================
+
+#[test]
+fn frame_round_trip() {
+    let center = Frequency::from_hz(14_074_000).unwrap();
+    let frame = PanadapterFrame::from_row(7, &row(1234), center, 24_000);
+    let bytes = frame.encode();
+    assert_eq!(bytes.len(), MAX_FRAME_LEN);
+    assert_eq!(bytes[0], FRAME_START);
+    assert_eq!(&bytes[2..4], &7u16.to_le_bytes());
+
+    let decoded = frames(&mut PanadapterDecoder::new(), &bytes);
+    assert_eq!(decoded, [frame]);
+    assert_eq!(decoded[0].power.len(), FRAME_BINS);
+    assert_eq!(decoded[0].power[100], 100);
+    assert_eq!(decoded[0].center_hz, 14_074_000);
+    assert_eq!(decoded[0].time_ms, 1234);
+}
+
+#[test]
+fn cat_text_passes_between_frames() {
+    let center = Frequency::from_hz(7_074_000).unwrap();
+    let frame = PanadapterFrame::from_row(0, &row(0), center, 48_000).encode();
+    let mut stream = b"FA00007074000;".to_vec();
+    stream.extend_from_slice(&frame);
+    stream.extend_from_slice(b"MD2;");
+
+    let mut decoder = PanadapterDecoder::new();
+    let mut text = Vec::new();
+    let mut count = 0;
+    for &byte in &stream {
+        match decoder.feed(byte) {
+            Some(StreamItem::Text(b)) => text.push(b),
+            Some(StreamItem::Frame(_)) => count += 1,
+            Some(StreamItem::Peaks(_)) | None => {}
+        }
+    }
+    assert_eq!(text, b"FA00007074000;MD2;");
+    assert_eq!(count, 1);
+    assert_eq!(decoder.errors(), 0);
+}
+
+#[test]
+fn corrupt_frame_is_discarded() {
+    let center = Frequency::from_hz(7_074_000).unwrap();
+    let mut bad = PanadapterFrame::from_row(0, &row(0), center, 48_000).encode();
+    bad[40] ^= 0x01;
+    let good = PanadapterFrame::from_row(1, &row(10), center, 48_000).encode();
+
+    let mut decoder = PanadapterDecoder::new();
+    assert!(frames(&mut decoder, &bad).is_empty());
+    assert_eq!(decoder.errors(), 1);
+    let decoded = frames(&mut decoder, &good);
+    assert_eq!(decoded.len(), 1);
+    assert_eq!(decoded[0].seq, 1);
+}
+
+// ============================================================================
+// Peak List Tests
+// ============================================================================
+
+/// Row at -100 dB with signals at the given columns and power codes
+fn signal_row(signals: &[(usize, i8)]) -> WaterfallRow {
+    let mut row = WaterfallRow::default();
+    for &(col, power) in signals {
+        row.data[col] = power;
+        row.data[col - 1] = power / 2;
+        row.data[col + 1] = power / 2;
+    }
+    row
+}
+
+#[test]
+fn peak_list_round_trip() {
+    let center = Frequency::from_hz(14_074_000).unwrap();
+    // 24 kHz over 128 bins: 187.5 Hz per bin, centre at bin 64
+    let row = signal_row(&[(32, 60), (96, 80)]);
+    let peaks = PeakListFrame::from_row(3, &row, center, 24_000, MAX_FRAME_PEAKS);
+    assert_eq!(peaks.seq, 3);
+    assert_eq!(peaks.center_hz, 14_074_000);
+    assert_eq!(peaks.peaks.len(), 2);
+    assert_eq!(peaks.peaks[0].level_db, -20);
+    assert!((peaks.peaks[0].offset_hz - 6000).abs() < 200);
+    assert_eq!(peaks.peaks[1].level_db, -40);
+    assert!((peaks.peaks[1].offset_hz + 6000).abs() < 200);
+    assert!(peaks.peaks[0].width_hz > 0);
+
+    let bytes = peaks.encode();
+    assert_eq!(bytes[1], PEAK_KIND);
+    let mut decoder = PanadapterDecoder::new();
+    let decoded: Vec<_> = bytes
+        .iter()
+        .filter_map(|&b| match decoder.feed(b) {
+            Some(StreamItem::Peaks(peaks)) => Some(peaks),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(decoded, [peaks]);
+    assert_eq!(decoder.errors(), 0);
+}
+
+#[test]
+fn peaks_follow_their_frame() {
+    let center = Frequency::from_hz(7_074_000).unwrap();
+    let row = signal_row(&[(64, 70)]);
+    let mut stream = PanadapterStream::new();
+    stream.set_rate(10);
+    assert!(stream.encode_peaks(&row, center, 48_000).is_none());
+    stream.set_peaks(1);
+
+    let mut decoder = PanadapterDecoder::new();
+    let mut items = Vec::new();
+    for step in 0..2u32 {
+        assert!(stream.poll(step * 100, true));
+        let mut bytes = stream.encode(&row, center, 48_000).to_vec();
+        bytes.extend_from_slice(&stream.encode_peaks(&row, center, 48_000).unwrap());
+        items.extend(bytes.iter().filter_map(|&b| decoder.feed(b)));
+    }
+    assert_eq!(items.len(), 4);
+    let StreamItem::Peaks(peaks) = &items[3] else {
+        panic!("expected a peak list");
+    };
+    assert_eq!(peaks.seq, 1);
+    assert_eq!(peaks.peaks.len(), 1);
+    assert!(peaks.peaks[0].offset_hz.abs() < 400);
+    assert_eq!(decoder.dropped(), 0);
+}
+
+// ============================================================================
+// Stream Tests
+// ============================================================================
+
+#[test]
+fn rate_is_clamped() {
+    let mut stream = PanadapterStream::new();
+    assert!(!stream.is_enabled());
+    assert!(!stream.poll(0, true));
+    stream.set_rate(1);
+    assert_eq!(stream.rate(), MIN_FPS);
+    stream.set_rate(60);
+    assert_eq!(stream.rate(), MAX_FPS);
+    stream.set_rate(0);
+    assert!(!stream.is_enabled());
+}
+
+#[test]
+fn frames_follow_the_rate() {
+    let mut stream = PanadapterStream::new();
+    stream.set_rate(20);
+    let sent = (0..1000).filter(|&ms| stream.poll(ms, true)).count();
+    assert_eq!(sent, 20);
+
+    // A stall sends one frame, not a burst
+    assert!(stream.poll(5000, true));
+    assert!(!stream.poll(5001, true));
+    assert!(!stream.poll(5049, true));
+    assert!(stream.poll(5050, true));
+}
+
+#[test]
+fn busy_endpoint_drops_and_host_counts_gap() {
+    let center = Frequency::from_hz(14_200_000).unwrap();
+    let mut stream = PanadapterStream::new();
+    stream.set_rate(10);
+    let mut decoder = PanadapterDecoder::new();
+    let mut received = Vec::new();
+    for step in 0..10u32 {
+        let ms = step * 100;
+        // Endpoint busy for two frames
+        let ready = !(3..5).contains(&step);
+        if stream.poll(ms, ready) {
+            received.extend(frames(
+                &mut decoder,
+                &stream.encode(&row(ms), center, 24_000),
+            ));
+        }
+    }
+    assert_eq!(stream.sent(), 8);
+    assert_eq!(stream.dropped(), 2);
+    assert_eq!(received.len(), 8);
+    assert_eq!(received[3].seq, 5);
+    assert_eq!(decoder.dropped(), 2);
+}
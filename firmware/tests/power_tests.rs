@@ -3,7 +3,17 @@
 //! Tests for battery monitoring, thermal management, and power control.
 //! Run with: cargo test --target x86_64-unknown-linux-gnu --no-default-features --features std --test power_tests
 
+use sdr_firmware::power::battery::{
+    BatteryGauge, Chemistry, LOW_BATTERY_TX_PERCENT, REST_SETTLE_MS,
+};
+use sdr_firmware::power::usb_pd::{
+    PdContract, Pdo, SinkState, TypeCCurrent, UsbPdSink, MAX_CURRENT_MA,
+};
+use sdr_firmware::power::thermal::{
+    lm75_temperature, DeratingCurve, PaThermal, Thermistor, CUTOFF_HYSTERESIS_C,
+};
 use sdr_firmware::power::{BatteryVoltage, PowerManager, PowerState, Temperature};
+use sdr_firmware::radio::transmit::TxController;
 
 // =============================================================================
 // Battery Voltage Tests
@@ -301,3 +311,380 @@ fn power_manager_pa_temp_accessor() {
     let pa = pm.pa_temp().unwrap();
     assert!((pa.celsius() - 55.0).abs() < 0.1);
 }
+
+#[test]
+fn power_manager_thermal_cutoff_hysteresis() {
+    let mut pm = PowerManager::new(4);
+    pm.update_pa_temp(Temperature::from_celsius(85.0));
+    assert!(pm.pa_thermal().is_cutoff());
+
+    // Still hot: the cutoff holds until the PA cools by the hysteresis
+    for _ in 0..50 {
+        pm.update_pa_temp(Temperature::from_celsius(75.0));
+    }
+    assert!(!pm.tx_allowed());
+    for _ in 0..50 {
+        pm.update_pa_temp(Temperature::from_celsius(50.0));
+    }
+    assert!(pm.tx_allowed());
+    assert_eq!(pm.thermal_limit(), 100);
+}
+
+// =============================================================================
+// PA Thermal Tests
+// =============================================================================
+
+#[test]
+fn thermistor_conversion() {
+    let ntc = Thermistor::DEFAULT;
+    // Equal to the series resistor at 25°C
+    let t = ntc.temperature(2048).unwrap().celsius();
+    assert!((t - 25.0).abs() < 0.2, "{t}");
+    // Lower resistance (lower reading) when hot
+    let hot = ntc.temperature(700).unwrap().celsius();
+    assert!(hot > 60.0 && hot < 75.0, "{hot}");
+    assert!(ntc.temperature(3500).unwrap().celsius() < 0.0);
+    // Open or shorted
+    assert!(ntc.temperature(0).is_none());
+    assert!(ntc.temperature(4095).is_none());
+}
+
+#[test]
+fn lm75_register_conversion() {
+    assert_eq!(lm75_temperature([0x19, 0x00]).celsius(), 25.0);
+    assert_eq!(lm75_temperature([0x00, 0x80]).celsius(), 0.5);
+    assert_eq!(lm75_temperature([0xFF, 0x00]).celsius(), -1.0);
+    assert_eq!(lm75_temperature([0x7D, 0x00]).celsius(), 125.0);
+}
+
+#[test]
+fn derating_curve_limits() {
+    let curve = DeratingCurve::DEFAULT;
+    assert_eq!(curve.limit_percent(25.0), 100);
+    assert_eq!(curve.limit_percent(curve.start_c), 100);
+    assert_eq!(curve.limit_percent(65.0), 63);
+    assert_eq!(curve.limit_percent(curve.end_c), curve.min_percent);
+    assert_eq!(curve.limit_percent(79.0), curve.min_percent);
+
+    // Continuous duty derates sooner
+    let continuous = DeratingCurve::CONTINUOUS;
+    assert!(continuous.limit_percent(60.0) < curve.limit_percent(60.0));
+    assert!(continuous.cutoff_c < curve.cutoff_c);
+}
+
+#[test]
+fn pa_thermal_smooths_and_derates() {
+    let mut thermal = PaThermal::default();
+    assert_eq!(thermal.limit_percent(), 100);
+    thermal.update(Temperature::from_celsius(40.0));
+
+    // One noisy reading barely moves the limit
+    thermal.update(Temperature::from_celsius(90.0));
+    assert!(!thermal.is_cutoff());
+    assert!(thermal.limit_percent() > 90, "{}", thermal.limit_percent());
+
+    // A sustained rise derates progressively, then cuts off
+    let mut last = thermal.limit_percent();
+    while !thermal.is_cutoff() {
+        thermal.update(Temperature::from_celsius(90.0));
+        assert!(thermal.limit_percent() <= last || thermal.is_cutoff());
+        last = thermal.limit_percent();
+    }
+    assert_eq!(thermal.limit_percent(), 0);
+    let t = thermal.temperature().unwrap().celsius();
+    assert!(t >= DeratingCurve::DEFAULT.cutoff_c, "{t}");
+
+    // Cooling to just under the cutoff is not enough
+    let hold = DeratingCurve::DEFAULT.cutoff_c - CUTOFF_HYSTERESIS_C + 1.0;
+    for _ in 0..50 {
+        thermal.update(Temperature::from_celsius(hold));
+    }
+    assert!(thermal.is_cutoff());
+    for _ in 0..50 {
+        thermal.update(Temperature::from_celsius(55.0));
+    }
+    assert!(!thermal.is_cutoff());
+    assert_eq!(thermal.limit_percent(), 100);
+}
+
+#[test]
+fn pa_thermal_sensor_fault() {
+    let mut thermal = PaThermal::default();
+    thermal.sensor_fault();
+    assert!(thermal.is_sensor_fault());
+    assert_eq!(thermal.limit_percent(), DeratingCurve::DEFAULT.min_percent);
+
+    thermal.update(Temperature::from_celsius(30.0));
+    assert!(!thermal.is_sensor_fault());
+    assert_eq!(thermal.limit_percent(), 100);
+
+    // Switching to a continuous duty curve applies at once
+    for _ in 0..50 {
+        thermal.update(Temperature::from_celsius(60.0));
+    }
+    let before = thermal.limit_percent();
+    thermal.set_curve(DeratingCurve::CONTINUOUS);
+    assert!(thermal.limit_percent() < before);
+}
+
+// =============================================================================
+// Battery Gauge Tests
+// =============================================================================
+
+#[test]
+fn chemistry_discharge_curves() {
+    for chemistry in [Chemistry::LiIon, Chemistry::LiFePo4, Chemistry::NiMh, Chemistry::LeadAcid] {
+        let curve = chemistry.discharge_curve();
+        assert_eq!(curve[0].1, 100);
+        assert_eq!(curve[curve.len() - 1].1, 0);
+        assert!(curve.windows(2).all(|p| p[0].0 > p[1].0 && p[0].1 > p[1].1));
+        assert_eq!(chemistry.soc_at(f32::from(curve[0].0) + 100.0), 1.0);
+        assert_eq!(chemistry.soc_at(f32::from(chemistry.empty_mv()) - 100.0), 0.0);
+        assert!(chemistry.default_low_mv() > chemistry.empty_mv());
+    }
+    assert!((Chemistry::LiIon.soc_at(3800.0) - 0.5).abs() < 1e-6);
+    assert!((Chemistry::LiIon.soc_at(3775.0) - 0.45).abs() < 1e-6);
+    // The same cell voltage means very different charge per chemistry
+    assert!(Chemistry::LiFePo4.soc_at(3300.0) > Chemistry::LiIon.soc_at(3300.0) + 0.5);
+}
+
+#[test]
+fn battery_gauge_starts_from_voltage() {
+    let mut gauge = BatteryGauge::new(Chemistry::LiIon, 3, 2000);
+    assert!(gauge.soc_percent().is_none());
+    assert_eq!(gauge.tx_power_limit(), 100);
+    gauge.update(11.4, 0, 100);
+    assert_eq!(gauge.soc_percent(), Some(50));
+    assert_eq!(gauge.voltage_mv(), 11_400);
+    assert_eq!(gauge.remaining_mah(), Some(1000));
+}
+
+#[test]
+fn battery_gauge_coulomb_counting() {
+    let mut gauge = BatteryGauge::new(Chemistry::LiIon, 1, 1000);
+    gauge.update(4.2, 0, 0);
+    assert_eq!(gauge.soc_percent(), Some(100));
+
+    // 1 A for 6 minutes draws 100 mAh, whatever the voltage sags to
+    for _ in 0..3600 {
+        gauge.update(3.6, 1000, 100);
+    }
+    assert_eq!(gauge.soc_percent(), Some(90));
+    assert_eq!(gauge.current_ma(), 1000);
+
+    // Charging counts back up
+    for _ in 0..600 {
+        gauge.update(4.1, -1000, 100);
+    }
+    assert_eq!(gauge.soc_percent(), Some(92));
+
+    // After a long rest the resting voltage takes over
+    for _ in 0..(REST_SETTLE_MS / 1000) {
+        gauge.update(3.8, 10, 1000);
+    }
+    assert_eq!(gauge.soc_percent(), Some(50));
+}
+
+#[test]
+fn battery_gauge_tx_power_limit() {
+    let mut gauge = BatteryGauge::new(Chemistry::LiFePo4, 4, 6000);
+    assert!((gauge.low_voltage() - 12.6).abs() < 1e-4);
+    gauge.update(13.2, 0, 100);
+    assert_eq!(gauge.tx_power_limit(), 100);
+
+    // Linear from the low voltage down to the empty voltage (10.0 V)
+    gauge.update(11.3, 2000, 100);
+    let limit = gauge.tx_power_limit();
+    assert!(limit > LOW_BATTERY_TX_PERCENT && limit < 100, "{limit}");
+    gauge.update(9.5, 2000, 100);
+    assert_eq!(gauge.tx_power_limit(), LOW_BATTERY_TX_PERCENT);
+    assert!(gauge.is_critical_at(9.5));
+
+    gauge.set_low_voltage(11.0);
+    gauge.update(11.3, 2000, 100);
+    assert_eq!(gauge.tx_power_limit(), 100);
+}
+
+#[test]
+fn power_manager_uses_battery_gauge() {
+    // 4 LiFePo4 cells at 3.25 V, low by the LiPo thresholds
+    let mut pm = PowerManager::new(4);
+    pm.set_battery_gauge(BatteryGauge::new(Chemistry::LiFePo4, 4, 6000));
+    let batt = BatteryVoltage::from_adc(1467, 11.0, 3.3);
+    pm.update_battery_gauge(batt, 0, 100);
+
+    assert_eq!(pm.state(), PowerState::Battery);
+    assert!(pm.tx_allowed());
+    assert_eq!(pm.effective_power_limit(), 100);
+    let soc = pm.battery_percent().unwrap();
+    assert!((29..=31).contains(&soc), "{soc}");
+
+    pm.battery_gauge_mut().unwrap().set_low_voltage(13.1);
+    assert!(pm.effective_power_limit() < 100);
+}
+
+// =============================================================================
+// USB-PD Sink Tests
+// =============================================================================
+
+/// Encode a fixed supply PDO
+fn fixed_pdo(mv: u32, ma: u32) -> u32 {
+    ((mv / 50) << 10) | (ma / 10)
+}
+
+/// Encode a PPS augmented PDO
+fn pps_pdo(min_mv: u32, max_mv: u32, ma: u32) -> u32 {
+    (3 << 30) | ((max_mv / 100) << 17) | ((min_mv / 100) << 8) | (ma / 50)
+}
+
+#[test]
+fn pdo_decoding() {
+    // Dual role and other flags above the voltage field are ignored
+    assert_eq!(
+        Pdo::from_raw(fixed_pdo(5000, 3000) | (1 << 29) | (1 << 26)),
+        Some(Pdo::Fixed { mv: 5000, max_ma: 3000 })
+    );
+    assert_eq!(
+        Pdo::from_raw(pps_pdo(3300, 11_000, 3000)),
+        Some(Pdo::Pps { min_mv: 3300, max_mv: 11_000, max_ma: 3000 })
+    );
+    let variable = (2 << 30) | ((12_000 / 50) << 20) | ((9_000 / 50) << 10) | 200;
+    assert_eq!(
+        Pdo::from_raw(variable),
+        Some(Pdo::Variable { min_mv: 9000, max_mv: 12_000, max_ma: 2000 })
+    );
+    let battery = (1 << 30) | ((16_800 / 50) << 20) | ((12_000 / 50) << 10) | 160;
+    assert_eq!(
+        Pdo::from_raw(battery),
+        Some(Pdo::Battery { min_mv: 12_000, max_mv: 16_800, max_mw: 40_000 })
+    );
+    // Reserved augmented type
+    assert!(Pdo::from_raw((3 << 30) | (1 << 28)).is_none());
+}
+
+#[test]
+fn pd_sink_selects_15v() {
+    let caps = [
+        fixed_pdo(5000, 3000),
+        fixed_pdo(9000, 3000),
+        fixed_pdo(15_000, 3000),
+        fixed_pdo(20_000, 5000),
+        pps_pdo(3300, 21_000, 5000),
+    ];
+    let request = UsbPdSink::select(&caps);
+    assert_eq!(request.position, 3);
+    assert_eq!(request.contract, PdContract { mv: 15_000, ma: 3000 });
+    // Object position 3, 3 A operating and maximum current
+    assert_eq!(request.rdo(), (3 << 28) | (1 << 25) | (1 << 24) | (300 << 10) | 300);
+
+    // Without 15 V the most power at or below it, current capped
+    let caps = [fixed_pdo(5000, 3000), fixed_pdo(9000, 5000), fixed_pdo(12_000, 1500)];
+    let request = UsbPdSink::select(&caps);
+    assert_eq!(request.position, 2);
+    assert_eq!(request.contract, PdContract { mv: 9000, ma: MAX_CURRENT_MA });
+
+    // 5 V only
+    let request = UsbPdSink::select(&[fixed_pdo(5000, 2000)]);
+    assert_eq!(request.position, 1);
+    assert!(!request.contract.is_high_voltage());
+}
+
+#[test]
+fn pd_sink_negotiation() {
+    let mut sink = UsbPdSink::new();
+    let mut tx = TxController::new();
+    assert_eq!(sink.state(), SinkState::Detached);
+    sink.attach(TypeCCurrent::High, &mut tx);
+    assert_eq!(sink.contract(), PdContract { mv: 5000, ma: 3000 });
+
+    sink.source_capabilities(&[fixed_pdo(5000, 3000), fixed_pdo(15_000, 3000)], &mut tx);
+    assert!(matches!(sink.state(), SinkState::Requested(_)));
+    // The rail does not change until the source says it is ready
+    sink.accepted();
+    assert!(!sink.contract().is_high_voltage());
+    sink.ps_ready(&mut tx);
+    assert_eq!(sink.state(), SinkState::Ready);
+    assert_eq!(sink.contract(), PdContract { mv: 15_000, ma: 3000 });
+    assert_eq!(sink.contract().power_mw(), 45_000);
+
+    sink.hard_reset(&mut tx);
+    assert_eq!(sink.state(), SinkState::WaitCapabilities);
+    assert!(!sink.contract().is_high_voltage());
+    sink.detach(&mut tx);
+    assert_eq!(sink.contract(), PdContract::DEFAULT_5V);
+}
+
+#[test]
+fn pd_sink_sets_tx_power_ceiling() {
+    let caps = [fixed_pdo(5000, 3000), fixed_pdo(15_000, 3000)];
+    let mut sink = UsbPdSink::new();
+    let mut tx = TxController::new();
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+
+    // Raised only once the supply has settled
+    sink.attach(TypeCCurrent::High, &mut tx);
+    sink.source_capabilities(&caps, &mut tx);
+    sink.accepted();
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+    sink.ps_ready(&mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 15.0);
+
+    // Back to 5 W on a hard reset
+    sink.hard_reset(&mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+
+    // And on detach
+    sink.source_capabilities(&caps, &mut tx);
+    sink.accepted();
+    sink.ps_ready(&mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 15.0);
+    sink.detach(&mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+
+    // A renegotiation drops to 5 W until the new contract is ready
+    sink.attach(TypeCCurrent::High, &mut tx);
+    sink.source_capabilities(&caps, &mut tx);
+    sink.accepted();
+    sink.ps_ready(&mut tx);
+    sink.source_capabilities(&caps, &mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+    sink.rejected(&mut tx);
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+}
+
+#[test]
+fn pd_sink_falls_back_to_type_c() {
+    let mut sink = UsbPdSink::new();
+    let mut tx = TxController::new();
+    sink.attach(TypeCCurrent::Medium, &mut tx);
+    sink.update(100);
+    assert_eq!(sink.state(), SinkState::WaitCapabilities);
+    sink.update(1000);
+    assert_eq!(sink.state(), SinkState::TypeCOnly);
+    assert_eq!(sink.contract(), PdContract { mv: 5000, ma: 1500 });
+
+    sink.attach(TypeCCurrent::Default, &mut tx);
+    sink.source_capabilities(&[fixed_pdo(5000, 3000), fixed_pdo(15_000, 3000)], &mut tx);
+    sink.rejected(&mut tx);
+    assert_eq!(sink.state(), SinkState::TypeCOnly);
+    assert_eq!(sink.contract(), PdContract::DEFAULT_5V);
+    // Late accept or PS_RDY after a reject are ignored
+    sink.accepted();
+    sink.ps_ready(&mut tx);
+    assert!(!sink.contract().is_high_voltage());
+    assert_eq!(tx.power_ceiling_watts(), 5.0);
+}
+
+#[test]
+fn pd_contract_tx_ceiling() {
+    assert_eq!(PdContract::DEFAULT_5V.tx_ceiling_watts(), 5.0);
+    assert_eq!(PdContract { mv: 5000, ma: 3000 }.tx_ceiling_watts(), 5.0);
+    assert_eq!(PdContract { mv: 15_000, ma: 3000 }.tx_ceiling_watts(), 15.0);
+    // Limited by the supply's power, then by the lower rail
+    let weak = PdContract { mv: 15_000, ma: 1500 }.tx_ceiling_watts();
+    assert!((weak - 11.25).abs() < 1e-3, "{weak}");
+    let nine = PdContract { mv: 9000, ma: 3000 }.tx_ceiling_watts();
+    assert!((nine - 5.4).abs() < 1e-3, "{nine}");
+    let twelve = PdContract { mv: 12_000, ma: 3000 }.tx_ceiling_watts();
+    assert!(twelve > nine && twelve < 15.0, "{twelve}");
+}
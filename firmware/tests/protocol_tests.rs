@@ -2,8 +2,30 @@
 //!
 //! Tests for Kenwood TS-2000 compatible CAT command parsing.
 
-use sdr_firmware::protocol::{CatCommand, CatParser, CatResponse};
-use sdr_firmware::types::{Frequency, Mode, PowerLevel};
+use sdr_firmware::protocol::panadapter::PanadapterStream;
+use sdr_firmware::protocol::{CatCommand, CatParser, CatResponse, FILE_CHUNK_LEN};
+use sdr_firmware::radio::dtmf::DtmfMode;
+use sdr_firmware::radio::loopback::{LoopbackCheck, LoopbackTest};
+use sdr_firmware::radio::monitor::MonitorCommand;
+use sdr_firmware::radio::practice::PracticeReport;
+use sdr_firmware::radio::sweep::SweepPoint;
+use sdr_firmware::radio::voice_keyer::VoiceKeyer;
+use sdr_firmware::radio::tx_policy::TxPolicy;
+use sdr_firmware::radio::tx_test::ImdReport;
+use sdr_firmware::clock::DateTime;
+use sdr_firmware::dsp::bypass::DspStage;
+use sdr_firmware::dsp::iq_capture::IqCapture;
+use sdr_firmware::dsp::modulation::IqSample;
+use sdr_firmware::dsp::equalizer::{EqCurve, EqPath};
+use sdr_firmware::dsp::si5351_calc::PpmCorrection;
+use sdr_firmware::selftest::{PostItem, PostReport, PostResult};
+use sdr_firmware::settings::StartupPolicy;
+use sdr_firmware::storage::{FileEntry, FileKind};
+use sdr_firmware::update::FirmwareVersion;
+use sdr_dsp_core::agc::SmeterCalibration;
+use sdr_dsp_core::iq_balance::{IqBalance, IqCalibration};
+use sdr_dsp_core::IqSample as CoreIq;
+use sdr_firmware::types::{Band, Frequency, IqOrientation, Mode, PowerLevel, SwrReading};
 
 // ============================================================================
 // Parser Basic Tests
@@ -565,5 +587,799 @@ fn test_response_as_bytes() {
     assert_eq!(resp.as_bytes(), b"ID019;");
 }
 
+// ============================================================================
+// File Store Command Tests
+// ============================================================================
+
+fn parse(cmd: &[u8]) -> Option<CatCommand> {
+    let mut parser = CatParser::new();
+    for c in cmd {
+        parser.feed(*c);
+    }
+    parser.feed(b';')
+}
+
+#[test]
+fn test_parse_list_files() {
+    assert!(matches!(parse(b"ZFL"), Some(CatCommand::ListFiles)));
+}
+
+#[test]
+fn test_parse_file_info() {
+    assert!(matches!(parse(b"ZFI03"), Some(CatCommand::ReadFileInfo(3))));
+}
+
+#[test]
+fn test_parse_read_file() {
+    let cmd = parse(b"ZFR050000000032");
+    assert!(matches!(cmd, Some(CatCommand::ReadFile { id: 5, offset: 32 })));
+}
+
+#[test]
+fn test_parse_read_file_short() {
+    assert!(parse(b"ZFR05").is_none());
+}
+
+#[test]
+fn test_parse_delete_file() {
+    assert!(matches!(parse(b"ZFD12"), Some(CatCommand::DeleteFile(12))));
+}
+
+#[test]
+fn test_response_file_count() {
+    let mut resp = CatResponse::new();
+    resp.file_count(7);
+    assert_eq!(resp.as_str(), "ZFL07;");
+}
+
+#[test]
+fn test_response_file_info() {
+    let mut resp = CatResponse::new();
+    let mut name = heapless::String::new();
+    name.push_str("CLIP00001.PCM").unwrap();
+    let entry = FileEntry {
+        id: 4,
+        kind: FileKind::Capture,
+        name,
+        start_block: 3,
+        length: 96_000,
+    };
+    resp.file_info(1, &entry);
+    assert_eq!(resp.as_str(), "ZFI0104C0000096000CLIP00001.PCM;");
+}
+
+#[test]
+fn test_response_file_data() {
+    let mut resp = CatResponse::new();
+    resp.file_data(2, 16, &[0x00, 0xAB, 0x7F]);
+    assert_eq!(resp.as_str(), "ZFR02000000001600AB7F;");
+}
+
+#[test]
+fn test_response_file_data_chunk_limit() {
+    let mut resp = CatResponse::new();
+    resp.file_data(0, 0, &[0x55; 64]);
+    assert_eq!(resp.as_str().len(), 15 + FILE_CHUNK_LEN * 2 + 1);
+}
+
+#[test]
+fn test_response_file_data_eof() {
+    let mut resp = CatResponse::new();
+    resp.file_data(2, 100, &[]);
+    assert_eq!(resp.as_str(), "ZFR020000000100;");
+}
+
+// ============================================================================
+// Version / Bootloader Command Tests
+// ============================================================================
+
+#[test]
+fn test_parse_read_version() {
+    assert!(matches!(parse(b"ZV"), Some(CatCommand::ReadVersion)));
+}
+
+#[test]
+fn test_parse_bootloader_steps() {
+    assert!(matches!(parse(b"ZB1"), Some(CatCommand::ArmBootloader)));
+    assert!(matches!(parse(b"ZB2"), Some(CatCommand::EnterBootloader)));
+    assert!(parse(b"ZB").is_none());
+    assert!(parse(b"ZB9").is_none());
+}
+
+#[test]
+fn test_response_version() {
+    let mut resp = CatResponse::new();
+    resp.version(FirmwareVersion::new(1, 2, 3), 1, 0x0007);
+    assert_eq!(resp.as_str(), "ZV010203010007;");
+}
+
+#[test]
+fn test_response_bootloader() {
+    let mut resp = CatResponse::new();
+    resp.bootloader(1);
+    assert_eq!(resp.as_str(), "ZB1;");
+}
+
+// ============================================================================
+// Clock Command Tests
+// ============================================================================
+
+#[test]
+fn test_parse_read_clock() {
+    assert!(matches!(parse(b"ZT"), Some(CatCommand::ReadClock)));
+}
+
+#[test]
+fn test_parse_set_clock() {
+    let expected = DateTime::new(2024, 2, 29, 23, 59, 58).unwrap();
+    match parse(b"ZT20240229235958") {
+        Some(CatCommand::SetClock(time)) => assert_eq!(time, expected),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_set_clock_invalid_date() {
+    assert!(parse(b"ZT20230229120000").is_none());
+}
+
+#[test]
+fn test_parse_utc_offset() {
+    assert!(matches!(parse(b"ZO"), Some(CatCommand::ReadUtcOffset)));
+    assert!(matches!(parse(b"ZO+0530"), Some(CatCommand::SetUtcOffset(330))));
+    assert!(matches!(parse(b"ZO-0800"), Some(CatCommand::SetUtcOffset(-480))));
+    assert!(parse(b"ZO0800").is_none());
+}
+
+#[test]
+fn test_response_clock() {
+    let mut resp = CatResponse::new();
+    resp.clock(&DateTime::new(2024, 1, 2, 3, 4, 5).unwrap());
+    assert_eq!(resp.as_str(), "ZT20240102030405;");
+}
+
+#[test]
+fn test_response_utc_offset() {
+    let mut resp = CatResponse::new();
+    resp.utc_offset(-330);
+    assert_eq!(resp.as_str(), "ZO-0530;");
+    resp.utc_offset(60);
+    assert_eq!(resp.as_str(), "ZO+0100;");
+}
+
+// ============================================================================
+// Self Test Command Tests
+// ============================================================================
+
+#[test]
+fn test_parse_self_test() {
+    assert!(matches!(parse(b"ZP"), Some(CatCommand::ReadSelfTest)));
+    assert!(matches!(
+        parse(b"ZP3"),
+        Some(CatCommand::ReadSelfTestItem(PostItem::AdcI))
+    ));
+    assert!(parse(b"ZP9").is_none());
+
+    assert!(matches!(parse(b"ZPL"), Some(CatCommand::ReadLoopbackTest)));
+    assert!(matches!(parse(b"ZPLR"), Some(CatCommand::RunLoopbackTest)));
+    assert!(matches!(
+        parse(b"ZPL2"),
+        Some(CatCommand::ReadLoopbackCheck(LoopbackCheck::FilterShape))
+    ));
+    assert!(parse(b"ZPL3").is_none());
+}
+
+#[test]
+fn test_response_self_test() {
+    let mut report = PostReport::new();
+    report.record_probe(PostItem::Si5351, true);
+    report.record_pll_status(0x20);
+    report.record_adc_offset(PostItem::AdcI, 3100);
+
+    let mut resp = CatResponse::new();
+    resp.self_test(&report);
+    assert_eq!(resp.as_str(), "ZPPF-F--;");
+
+    resp.self_test_item(&report, PostItem::AdcI);
+    assert_eq!(resp.as_str(), "ZP3F03100;");
+    assert_eq!(report.result(PostItem::AdcI), PostResult::Fail);
+}
+
+#[test]
+fn test_iq_capture_commands() {
+    assert!(matches!(parse(b"IQ"), Some(CatCommand::ReadIqCapture)));
+    assert!(matches!(parse(b"IQ1024"), Some(CatCommand::CaptureIq(1024))));
+    assert!(matches!(parse(b"IQR0010"), Some(CatCommand::ReadIqChunk(10))));
+    assert!(parse(b"IQ0000").is_none());
+    assert!(parse(b"IQ2048").is_none());
+    assert!(parse(b"IQR10").is_none());
+
+    let mut capture = IqCapture::new();
+    let mut resp = CatResponse::new();
+    resp.iq_capture(&capture);
+    assert_eq!(resp.as_str(), "IQ00000;");
+
+    capture.arm(11);
+    capture.push(IqSample::new(0.5, -0.25));
+    resp.iq_capture(&capture);
+    assert_eq!(resp.as_str(), "IQ10001;");
+    for n in 1..11 {
+        capture.push(IqSample::new(n as f32 / 32767.0, 1.0));
+    }
+    resp.iq_capture(&capture);
+    assert_eq!(resp.as_str(), "IQ20011;");
+
+    // Full chunk: 10 pairs, 40 bytes, 56 characters
+    resp.iq_chunk(&capture, 0);
+    assert!(resp.as_str().starts_with("IQD0000/z8B4A"));
+    assert_eq!(resp.as_str().len(), 64);
+    // Last sample 10, 32767 = 0A 00 FF 7F
+    resp.iq_chunk(&capture, 10);
+    assert_eq!(resp.as_str(), "IQD0010CgD/fw==;");
+    resp.iq_chunk(&capture, 11);
+    assert_eq!(resp.as_str(), "IQD0011;");
+}
+
+#[test]
+fn test_iq_balance_commands() {
+    assert!(matches!(
+        parse(b"IQB3"),
+        Some(CatCommand::ReadIqCalibration(Band::M20))
+    ));
+    match parse(b"IQB1-045+0180") {
+        Some(CatCommand::SetIqCalibration(Band::M40, cal)) => {
+            assert_eq!(cal, IqCalibration::new(-45, 180));
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(matches!(parse(b"IQA"), Some(CatCommand::ReadIqAdaptive)));
+    assert!(matches!(parse(b"IQA1"), Some(CatCommand::SetIqAdaptive(true))));
+    assert!(matches!(parse(b"IQA0"), Some(CatCommand::SetIqAdaptive(false))));
+    assert!(parse(b"IQA2").is_none());
+    assert!(parse(b"IQB9").is_none());
+    assert!(parse(b"IQB1045+0180").is_none());
+    assert!(parse(b"IQB1+045+180").is_none());
+    // Out of range (4 dB, 12 degrees)
+    assert!(parse(b"IQB1+400+0000").is_none());
+    assert!(parse(b"IQB1+000-1200").is_none());
+    // The capture commands still parse
+    assert!(matches!(parse(b"IQ0512"), Some(CatCommand::CaptureIq(512))));
+
+    let mut resp = CatResponse::new();
+    resp.iq_calibration(Band::M40, IqCalibration::new(-45, 180));
+    assert_eq!(resp.as_str(), "IQB1-045+0180;");
+
+    let mut balance = IqBalance::new();
+    resp.iq_adaptive(&balance);
+    assert_eq!(resp.as_str(), "IQA0--;");
+    // A tone with 1 dB of gain imbalance leaves the image about 25 dB down
+    for n in 0..512 {
+        let theta = core::f32::consts::TAU * 3000.0 * n as f32 / 48000.0;
+        balance.process(CoreIq::new(theta.cos(), 1.122 * theta.sin()));
+    }
+    balance.set_adaptive(true);
+    resp.iq_adaptive(&balance);
+    assert_eq!(resp.as_str(), "IQA125;");
+}
+
+#[test]
+fn test_panadapter_commands() {
+    assert!(matches!(parse(b"ZZ"), Some(CatCommand::ReadPanadapter)));
+    assert!(matches!(parse(b"ZZ15"), Some(CatCommand::SetPanadapterRate(15))));
+    assert!(matches!(parse(b"ZZ00"), Some(CatCommand::SetPanadapterRate(0))));
+    assert!(parse(b"ZZ5").is_none());
+    assert!(parse(b"ZZ150").is_none());
+
+    let mut stream = PanadapterStream::new();
+    let mut resp = CatResponse::new();
+    resp.panadapter(&stream);
+    assert_eq!(resp.as_str(), "ZZ0000000;");
+    stream.set_rate(25);
+    assert!(!stream.poll(0, false));
+    resp.panadapter(&stream);
+    assert_eq!(resp.as_str(), "ZZ2500001;");
+}
+
+#[test]
+fn test_panadapter_peak_commands() {
+    assert!(matches!(parse(b"ZZP"), Some(CatCommand::ReadPanadapterPeaks)));
+    assert!(matches!(parse(b"ZZP05"), Some(CatCommand::SetPanadapterPeaks(5))));
+    assert!(matches!(parse(b"ZZP00"), Some(CatCommand::SetPanadapterPeaks(0))));
+    assert!(parse(b"ZZP5").is_none());
+    assert!(parse(b"ZZP123").is_none());
+
+    let mut stream = PanadapterStream::new();
+    let mut resp = CatResponse::new();
+    resp.panadapter_peaks(&stream);
+    assert_eq!(resp.as_str(), "ZZP00;");
+    stream.set_peaks(20);
+    resp.panadapter_peaks(&stream);
+    assert_eq!(resp.as_str(), "ZZP08;");
+}
+
+#[test]
+fn test_response_loopback_test() {
+    let mut resp = CatResponse::new();
+    resp.loopback_test(true, None);
+    assert_eq!(resp.as_str(), "ZPL1---;");
+
+    let report = LoopbackTest::new().run_to_completion();
+    resp.loopback_test(false, Some(&report));
+    assert_eq!(resp.as_str(), "ZPL0PPP;");
+    resp.loopback_check(&report, LoopbackCheck::Gain);
+    assert_eq!(resp.as_str(), "ZPL0P+0030;");
+    resp.loopback_check(&report, LoopbackCheck::SidebandRejection);
+    assert_eq!(resp.as_str(), "ZPL1P+0999;");
+}
+
+// ============================================================================
+// CW Message Command Tests
+// ============================================================================
+
+#[test]
+fn test_parse_key_text() {
+    assert!(matches!(parse(b"KY"), Some(CatCommand::ReadKeyBuffer)));
+    match parse(b"KY CQ TEST") {
+        Some(CatCommand::SendCw(text)) => assert_eq!(text.as_str(), "CQ TEST"),
+        other => panic!("unexpected {other:?}"),
+    }
+    // Longer than the Kenwood 24 character limit
+    assert!(parse(b"KY ABCDEFGHIJKLMNOPQRSTUVWXYZ").is_none());
+}
+
+#[test]
+fn test_parse_message_commands() {
+    assert!(matches!(parse(b"ZM3"), Some(CatCommand::PlayMessage(3))));
+    assert!(matches!(parse(b"ZM0"), Some(CatCommand::AbortMessage)));
+    assert!(matches!(parse(b"ZMR8"), Some(CatCommand::ReadMessage(8))));
+    assert!(parse(b"ZM9").is_none());
+    match parse(b"ZMW1CQ {CALL}") {
+        Some(CatCommand::WriteMessage(1, text)) => assert_eq!(text.as_str(), "CQ {CALL}"),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_serial_and_callsign() {
+    assert!(matches!(parse(b"ZN"), Some(CatCommand::ReadSerial)));
+    assert!(matches!(parse(b"ZN0042"), Some(CatCommand::SetSerial(42))));
+    assert!(matches!(parse(b"ZC"), Some(CatCommand::ReadCallsign)));
+    match parse(b"ZCW1AW") {
+        Some(CatCommand::SetCallsign(call)) => assert_eq!(call.as_str(), "W1AW"),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn test_response_cw_messages() {
+    let mut resp = CatResponse::new();
+    resp.key_buffer(false);
+    assert_eq!(resp.as_str(), "KY0;");
+    resp.message(2, "TU {NR}");
+    assert_eq!(resp.as_str(), "ZMR2TU {NR};");
+    resp.serial(42);
+    assert_eq!(resp.as_str(), "ZN0042;");
+    resp.callsign("W1AW");
+    assert_eq!(resp.as_str(), "ZCW1AW;");
+}
+
+#[test]
+fn test_parse_startup_policy() {
+    assert!(matches!(parse(b"ZS"), Some(CatCommand::ReadStartupPolicy)));
+    assert!(matches!(
+        parse(b"ZS0"),
+        Some(CatCommand::SetStartupPolicy(StartupPolicy::LastUsed))
+    ));
+    assert!(matches!(
+        parse(b"ZS1"),
+        Some(CatCommand::SetStartupPolicy(StartupPolicy::Fixed))
+    ));
+    assert!(matches!(parse(b"ZSW"), Some(CatCommand::StoreStartupState)));
+    assert!(parse(b"ZS7").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.startup_policy(StartupPolicy::Fixed);
+    assert_eq!(resp.as_str(), "ZS1;");
+}
+
+#[test]
+fn test_parse_iq_orientation() {
+    assert!(matches!(parse(b"ZQ"), Some(CatCommand::ReadIqOrientation)));
+    assert!(matches!(
+        parse(b"ZQ1"),
+        Some(CatCommand::SetIqOrientation(IqOrientation::Swapped))
+    ));
+    assert!(matches!(
+        parse(b"ZQ2"),
+        Some(CatCommand::SetIqOrientation(IqOrientation::InvertQ))
+    ));
+    assert!(parse(b"ZQ3").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.iq_orientation(IqOrientation::InvertQ);
+    assert_eq!(resp.as_str(), "ZQ2;");
+}
+
 // Note: to_radio_event tests are only available in embedded mode
 // as they require the RadioEvent type from crate::radio::state
+
+#[test]
+fn test_parse_stage_bypass() {
+    assert!(matches!(
+        parse(b"ZD3"),
+        Some(CatCommand::ReadStageBypass(DspStage::Agc))
+    ));
+    assert!(matches!(
+        parse(b"ZD11"),
+        Some(CatCommand::SetStageBypass(DspStage::NoiseReduction, true))
+    ));
+    assert!(matches!(
+        parse(b"ZD20"),
+        Some(CatCommand::SetStageBypass(DspStage::Notch, false))
+    ));
+    assert!(parse(b"ZD").is_none());
+    assert!(parse(b"ZD4").is_none());
+    assert!(parse(b"ZD02").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.stage_bypass(DspStage::NoiseBlanker, true);
+    assert_eq!(resp.as_str(), "ZD01;");
+}
+
+#[test]
+fn test_parse_notch_commands() {
+    assert!(matches!(parse(b"BC1"), Some(CatCommand::ReadNotch(1))));
+    assert!(matches!(parse(b"BC01"), Some(CatCommand::SetNotchEnabled(0, true))));
+    assert!(matches!(parse(b"BC10"), Some(CatCommand::SetNotchEnabled(1, false))));
+    assert!(parse(b"BC2").is_none());
+    assert!(parse(b"BC05").is_none());
+
+    assert!(matches!(parse(b"BP0"), Some(CatCommand::ReadNotchFrequency(0))));
+    assert!(matches!(
+        parse(b"BP11500"),
+        Some(CatCommand::SetNotchFrequency {
+            index: 1,
+            frequency_hz: 1500,
+            width_hz: None
+        })
+    ));
+    assert!(matches!(
+        parse(b"BP008000050"),
+        Some(CatCommand::SetNotchFrequency {
+            index: 0,
+            frequency_hz: 800,
+            width_hz: Some(50)
+        })
+    ));
+    assert!(parse(b"BP00000").is_none());
+    assert!(parse(b"BP008000005").is_none());
+    assert!(parse(b"BP0800").is_none());
+    assert!(parse(b"BP21500").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.notch(1, true);
+    assert_eq!(resp.as_str(), "BC11;");
+    resp.notch_frequency(0, 800, 50);
+    assert_eq!(resp.as_str(), "BP008000050;");
+}
+
+#[test]
+fn test_parse_eq_commands() {
+    assert!(matches!(parse(b"EQ0"), Some(CatCommand::ReadEq(EqPath::Rx))));
+    assert!(matches!(
+        parse(b"EQ11"),
+        Some(CatCommand::SetEqEnabled(EqPath::Tx, true))
+    ));
+    assert!(matches!(
+        parse(b"EQ00"),
+        Some(CatCommand::SetEqEnabled(EqPath::Rx, false))
+    ));
+    assert!(matches!(
+        parse(b"EQ13+04"),
+        Some(CatCommand::SetEqBand {
+            path: EqPath::Tx,
+            band: 3,
+            gain_db: 4
+        })
+    ));
+    assert!(matches!(
+        parse(b"EQ00-12"),
+        Some(CatCommand::SetEqBand {
+            path: EqPath::Rx,
+            band: 0,
+            gain_db: -12
+        })
+    ));
+    assert!(parse(b"EQ2").is_none());
+    assert!(parse(b"EQ02").is_none());
+    assert!(parse(b"EQ05+04").is_none(), "only five bands");
+    assert!(parse(b"EQ01+13").is_none(), "past the gain range");
+    assert!(parse(b"EQ0104").is_none(), "sign required");
+    assert!(parse(b"EQ01++4").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.eq(EqPath::Tx, true, &EqCurve::new([-6, -2, 0, 3, 12]));
+    assert_eq!(resp.as_str(), "EQ11-06-02+00+03+12;");
+}
+
+#[test]
+fn test_parse_vox_settings() {
+    assert!(matches!(parse(b"VG"), Some(CatCommand::ReadVoxGain)));
+    assert!(matches!(parse(b"VG045"), Some(CatCommand::SetVoxGain(45))));
+    assert!(parse(b"VG101").is_none());
+    assert!(matches!(parse(b"VD"), Some(CatCommand::ReadVoxDelay)));
+    assert!(matches!(parse(b"VD0750"), Some(CatCommand::SetVoxDelay(750))));
+    assert!(parse(b"VD5000").is_none());
+    assert!(matches!(parse(b"ZA"), Some(CatCommand::ReadAntiVoxGain)));
+    assert!(matches!(parse(b"ZA080"), Some(CatCommand::SetAntiVoxGain(80))));
+
+    let mut resp = CatResponse::new();
+    resp.vox_gain(7);
+    assert_eq!(resp.as_str(), "VG007;");
+    resp.vox_delay(300);
+    assert_eq!(resp.as_str(), "VD0300;");
+    resp.anti_vox_gain(50);
+    assert_eq!(resp.as_str(), "ZA050;");
+}
+
+#[test]
+fn test_smeter_and_calibration() {
+    assert!(matches!(parse(b"SM0"), Some(CatCommand::ReadSMeter)));
+    assert!(matches!(parse(b"SM"), Some(CatCommand::ReadSMeter)));
+    assert!(parse(b"SM1").is_none());
+    assert!(matches!(parse(b"ZR"), Some(CatCommand::ReadSignalDbm)));
+    assert!(matches!(parse(b"ZU"), Some(CatCommand::ReadSnr)));
+
+    assert!(matches!(
+        parse(b"ZL3"),
+        Some(CatCommand::ReadSmeterCalibration(Band::M20))
+    ));
+    match parse(b"ZL1-0251100") {
+        Some(CatCommand::SetSmeterCalibration(Band::M40, cal)) => {
+            assert_eq!(cal, SmeterCalibration::new(-25, 1100));
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    // Unsigned offset, bad band, slope out of range
+    assert!(parse(b"ZL10251100").is_none());
+    assert!(parse(b"ZL9+0001000").is_none());
+    assert!(parse(b"ZL1+0009000").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.smeter(9.0);
+    assert_eq!(resp.as_str(), "SM00015;");
+    resp.smeter(19.0);
+    assert_eq!(resp.as_str(), "SM00030;");
+    resp.smeter(0.0);
+    assert_eq!(resp.as_str(), "SM00000;");
+    resp.signal_dbm(-73.4);
+    assert_eq!(resp.as_str(), "ZR-073;");
+    resp.snr(Some(12.4));
+    assert_eq!(resp.as_str(), "ZU+012;");
+    resp.snr(Some(-3.6));
+    assert_eq!(resp.as_str(), "ZU-004;");
+    resp.snr(None);
+    assert_eq!(resp.as_str(), "ZU;");
+    resp.smeter_calibration(Band::M40, SmeterCalibration::new(-25, 1100));
+    assert_eq!(resp.as_str(), "ZL1-0251100;");
+}
+
+#[test]
+fn test_dtmf_commands() {
+    assert!(matches!(parse(b"ZG"), Some(CatCommand::ReadDtmfMode)));
+    assert!(matches!(parse(b"ZG2"), Some(CatCommand::SetDtmfMode(DtmfMode::Remote))));
+    assert!(matches!(parse(b"ZG0"), Some(CatCommand::SetDtmfMode(DtmfMode::Off))));
+    assert!(parse(b"ZG3").is_none());
+    assert!(parse(b"ZGx").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.dtmf_mode(DtmfMode::Decode);
+    assert_eq!(resp.as_str(), "ZG1;");
+    resp.dtmf_digit('5');
+    assert_eq!(resp.as_str(), "ZH5;");
+    resp.dtmf_digit('#');
+    assert_eq!(resp.as_str(), "ZH#;");
+}
+
+#[test]
+fn test_tx_policy_commands() {
+    assert!(matches!(parse(b"ZJ"), Some(CatCommand::ReadTxPolicy)));
+    assert!(matches!(parse(b"ZJ1"), Some(CatCommand::SetTxOverride(true))));
+    assert!(matches!(parse(b"ZJ0"), Some(CatCommand::SetTxOverride(false))));
+    assert!(parse(b"ZJ2").is_none());
+
+    let mut policy = TxPolicy::new();
+    let mut resp = CatResponse::new();
+    resp.tx_policy(&policy);
+    assert_eq!(resp.as_str(), "ZJ000;");
+    policy.set_operating(Frequency::from_hz(9_000_000).unwrap(), Mode::Cw);
+    resp.tx_policy(&policy);
+    assert_eq!(resp.as_str(), "ZJ021;");
+    policy.set_override(true);
+    resp.tx_policy(&policy);
+    assert_eq!(resp.as_str(), "ZJ111;");
+}
+
+#[test]
+fn test_monitor_commands() {
+    assert!(matches!(parse(b"ZH1"), Some(CatCommand::Monitor(MonitorCommand::Start))));
+    assert!(matches!(parse(b"ZH0"), Some(CatCommand::Monitor(MonitorCommand::Stop))));
+    assert!(parse(b"ZH2").is_none());
+
+    let Some(CatCommand::Monitor(MonitorCommand::Spot(spot))) =
+        parse(b"ZHS00014097050-12K1ABC,FN42")
+    else {
+        panic!("spot not parsed");
+    };
+    assert_eq!(spot.band, Band::M20);
+    assert_eq!(spot.frequency_hz, 14_097_050);
+    assert_eq!(spot.snr_db, -12);
+    assert_eq!(spot.callsign.as_str(), "K1ABC");
+    assert_eq!(spot.locator.as_str(), "FN42");
+
+    // Locator optional, callsign and an in-band frequency required
+    assert!(matches!(parse(b"ZHS00007040100+03W1XYZ,"), Some(CatCommand::Monitor(_))));
+    assert!(parse(b"ZHS00014097050-12,FN42").is_none());
+    assert!(parse(b"ZHS00012000000-12K1ABC,FN42").is_none());
+}
+
+#[test]
+fn test_practice_commands() {
+    assert!(matches!(parse(b"ZY"), Some(CatCommand::ReadPractice)));
+    assert!(matches!(parse(b"ZY1"), Some(CatCommand::SetPractice(true))));
+    assert!(matches!(parse(b"ZY0"), Some(CatCommand::SetPractice(false))));
+    assert!(parse(b"ZY2").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.practice(false, &PracticeReport::default());
+    assert_eq!(resp.as_str(), "ZY0000000000;");
+    let report = PracticeReport {
+        wpm: 18,
+        target_wpm: 20,
+        elements: 42,
+        accuracy: 93,
+        dah_ratio: 31,
+    };
+    resp.practice(true, &report);
+    assert_eq!(resp.as_str(), "ZY1182009331;");
+    resp.practice_char('K');
+    assert_eq!(resp.as_str(), "ZYTK;");
+    resp.practice_char(';');
+    assert_eq!(resp.as_str(), "ZYT:;");
+}
+
+#[test]
+fn test_voice_keyer_commands() {
+    assert!(matches!(parse(b"PB"), Some(CatCommand::ReadVoice)));
+    assert!(matches!(parse(b"PB1"), Some(CatCommand::PlayVoice(0))));
+    assert!(matches!(parse(b"PB4"), Some(CatCommand::PlayVoice(3))));
+    assert!(matches!(parse(b"PB0"), Some(CatCommand::StopVoice)));
+    assert!(matches!(parse(b"PBR030"), Some(CatCommand::SetVoiceRepeat(30))));
+    assert!(parse(b"PB5").is_none());
+    assert!(parse(b"PBR30").is_none());
+    assert!(matches!(parse(b"LM"), Some(CatCommand::ReadVoiceStorage)));
+    assert!(matches!(parse(b"LM2"), Some(CatCommand::RecordVoice(1))));
+    assert!(matches!(parse(b"LM0"), Some(CatCommand::StopVoiceRecording)));
+    assert!(matches!(parse(b"LMD3"), Some(CatCommand::DeleteVoice(2))));
+    assert!(parse(b"LMD0").is_none());
+
+    let mut voice = VoiceKeyer::new(8000);
+    voice.set_repeat_s(15);
+    let mut resp = CatResponse::new();
+    resp.voice_status(&voice);
+    assert_eq!(resp.as_str(), "PB00015;");
+    resp.voice_storage(&voice, 63_499);
+    assert_eq!(resp.as_str(), "LM0063000000000000;");
+}
+
+#[test]
+fn test_auto_tune_command() {
+    assert!(matches!(parse(b"ZI"), Some(CatCommand::AutoTune)));
+    assert!(parse(b"ZI1").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.auto_tune(Some(60));
+    assert_eq!(resp.as_str(), "ZI+0060;");
+    resp.auto_tune(Some(-125));
+    assert_eq!(resp.as_str(), "ZI-0125;");
+    resp.auto_tune(None);
+    assert_eq!(resp.as_str(), "ZI;");
+}
+
+#[test]
+fn test_tx_test_commands() {
+    assert!(matches!(parse(b"ZX"), Some(CatCommand::ReadTxTest)));
+    assert!(matches!(parse(b"ZX1"), Some(CatCommand::SetTxTest(true))));
+    assert!(matches!(parse(b"ZX0"), Some(CatCommand::SetTxTest(false))));
+    assert!(parse(b"ZX2").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.tx_test(true, None);
+    assert_eq!(resp.as_str(), "ZX1;");
+    let report = ImdReport {
+        tone_dbfs: -6.0,
+        imd3_dbc: -32.46,
+        imd5_dbc: -120.0,
+        balance_db: 0.04,
+    };
+    resp.tx_test(false, Some(report));
+    assert_eq!(resp.as_str(), "ZX0-325-999+000;");
+}
+
+#[test]
+fn test_sweep_commands() {
+    assert!(matches!(parse(b"ZW"), Some(CatCommand::ReadSweep)));
+    assert!(matches!(parse(b"ZW0"), Some(CatCommand::StopSweep)));
+    assert!(matches!(parse(b"ZWP042"), Some(CatCommand::ReadSweepPoint(42))));
+    match parse(b"ZW10000700000000007300000051") {
+        Some(CatCommand::StartSweep { start, end, points }) => {
+            assert_eq!(start.as_hz(), 7_000_000);
+            assert_eq!(end.as_hz(), 7_300_000);
+            assert_eq!(points, 51);
+        }
+        other => panic!("{other:?}"),
+    }
+    // Reversed span, too many points, short
+    assert!(parse(b"ZW10000730000000007000000051").is_none());
+    assert!(parse(b"ZW10000700000000007300000102").is_none());
+    assert!(parse(b"ZW1000070000000000730000005").is_none());
+    assert!(parse(b"ZW2").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.sweep_status(true, 7, 51);
+    assert_eq!(resp.as_str(), "ZW1007051;");
+    let point = SweepPoint {
+        frequency: Frequency::from_hz(7_102_000).unwrap(),
+        reading: SwrReading { forward: 1000, reflected: 0 },
+    };
+    resp.sweep_point(3, &point);
+    assert_eq!(resp.as_str(), "ZWP003000071020000100;");
+    let point = SweepPoint {
+        reading: SwrReading { forward: 0, reflected: 10 },
+        ..point
+    };
+    resp.sweep_point(100, &point);
+    assert_eq!(resp.as_str(), "ZWP100000071020009999;");
+}
+
+#[test]
+fn test_battery_commands() {
+    assert!(matches!(parse(b"ZE"), Some(CatCommand::ReadBattery)));
+    assert!(matches!(parse(b"ZEL"), Some(CatCommand::ReadLowBatteryVoltage)));
+    assert!(matches!(parse(b"ZEL12600"), Some(CatCommand::SetLowBatteryVoltage(12_600))));
+    assert!(parse(b"ZEL126").is_none());
+    assert!(parse(b"ZEX").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.battery(Some(87), 12_480, 1350);
+    assert_eq!(resp.as_str(), "ZE08712480+1350;");
+    resp.battery(None, 4_100, -250);
+    assert_eq!(resp.as_str(), "ZE---04100-0250;");
+    resp.low_battery_voltage(12_600);
+    assert_eq!(resp.as_str(), "ZEL12600;");
+}
+
+#[test]
+fn test_xtal_correction_commands() {
+    assert!(matches!(parse(b"ZK"), Some(CatCommand::ReadXtalCorrection)));
+    match parse(b"ZK-012345") {
+        Some(CatCommand::SetXtalCorrection(correction)) => assert_eq!(correction.ppb(), -12_345),
+        other => panic!("unexpected {other:?}"),
+    }
+    match parse(b"ZKM0001000000000010000120") {
+        Some(CatCommand::CalibrateXtal { expected, measured }) => {
+            assert_eq!(expected.as_hz(), 10_000_000);
+            assert_eq!(measured.as_hz(), 10_000_120);
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(parse(b"ZK+300000").is_none(), "beyond the correction range");
+    assert!(parse(b"ZK12345").is_none());
+    assert!(parse(b"ZKM00010000000").is_none());
+
+    let mut resp = CatResponse::new();
+    resp.xtal_correction(PpmCorrection::from_ppb(12_000).unwrap());
+    assert_eq!(resp.as_str(), "ZK+012000;");
+    resp.xtal_correction(PpmCorrection::from_ppb(-250).unwrap());
+    assert_eq!(resp.as_str(), "ZK-000250;");
+}
@@ -2,11 +2,42 @@
 //!
 //! Tests VFO management, state machine, and transmit controller.
 
+use sdr_firmware::clock::{Clock, DateTime, TimeSource};
+use sdr_firmware::radio::annunciator::{Annunciation, Annunciator};
+#[cfg(feature = "aprs-beacon")]
+use sdr_firmware::radio::beacon::{AprsBeacon, MAX_COMMENT_LEN};
+use sdr_firmware::radio::dtmf::{DtmfMode, DtmfRemote, DIGIT_TIMEOUT_MS, MAX_CODE_LEN, MAX_COMMANDS};
+use sdr_firmware::radio::keyer::{Keyer, KeyerMode, PaddleState};
+use sdr_firmware::radio::winkeyer::{WinKeyer, WkCommand, WK_VERSION};
+use sdr_firmware::radio::recorder::{ActivityRecorder, RecordTrigger, RecorderAction, MAX_CLIPS};
 use sdr_firmware::radio::state::{
     apply_event, AgcMode, RadioEvent, RadioState, VfoSelect,
 };
-use sdr_firmware::radio::transmit::{TxAction, TxController, TxState, Vox};
+use sdr_dsp_core::conditions::Condition;
+use sdr_firmware::dsp::modulation::IqSample;
+#[cfg(feature = "aprs-beacon")]
+use sdr_mode_aprs::{AprsDecoder, AprsPacket, Ax25Error, Ax25Frame};
+use sdr_firmware::radio::practice::{PracticeReport, PracticeSession, PRACTICE_TEXT_LEN};
+use sdr_firmware::radio::monitor::{
+    BandMonitor, MonitorAction, MonitorCommand, MonitorMode, Spot, MAX_SPOTS,
+};
+use sdr_firmware::radio::transmit::{
+    TxAction, TxController, TxState, Vox, DEFAULT_VOX_GAIN, VOX_GAIN_MAX,
+};
+use sdr_firmware::radio::control::{DspCommand, RadioController};
+use sdr_firmware::radio::pa::{
+    BridgeTiming, EnvelopeMode, PaController, PaOutput, MAX_SUPPLY_DUTY, SUPPLY_PERIOD,
+};
+use sdr_firmware::radio::input::{ButtonEvent, PushButton, TuningAcceleration, MAX_MULTIPLIER};
+use sdr_firmware::radio::relay::{
+    RelayFault, RelayOutputs, RelaySense, RelaySequencer, RelayTiming, TR_RELAY_BIT,
+};
+use sdr_firmware::radio::swr::{SwrCalibration, SwrMeter, MAX_CAL_POINTS};
+use sdr_firmware::radio::sweep::{AntennaSweep, DEFAULT_POINTS, MAX_POINTS};
+use sdr_firmware::radio::tx_policy::{emission_hz, TxPolicy, TxReason, TxVerdict};
+use sdr_firmware::radio::tx_test::{ImdReport, TxTest, WINDOW_MS};
 use sdr_firmware::radio::vfo::{MemoryBank, MemoryChannel, VfoManager, VfoSettings};
+use sdr_firmware::config::SWR_AVERAGE_SAMPLES;
 use sdr_firmware::types::{Band, Frequency, Mode, PowerLevel, SwrReading, TuningStep, TxRxState};
 
 // ============================================================================
@@ -524,6 +555,23 @@ fn agc_mode_attack_decay() {
     assert_eq!(AgcMode::Slow.decay_ms(), 2000);
 }
 
+#[test]
+fn agc_mode_presets() {
+    assert!(AgcMode::Off.agc_config().is_none());
+
+    let fast = AgcMode::Fast.agc_config().unwrap();
+    assert!(fast.dual_rate);
+    assert!((fast.hang_ms - 50.0).abs() < f32::EPSILON);
+    assert!((fast.max_gain_db() - 60.0).abs() < 0.1);
+
+    let slow = AgcMode::Slow.agc_config().unwrap();
+    assert!(!slow.dual_rate);
+    assert!((slow.decay_ms - 2000.0).abs() < f32::EPSILON);
+    assert!((slow.slope_db - 3.0).abs() < f32::EPSILON);
+    assert!((slow.hang_threshold_db + 70.0).abs() < f32::EPSILON);
+    assert!(slow.max_gain_db() > AgcMode::Medium.max_gain_db());
+}
+
 #[test]
 fn agc_mode_next_cycles() {
     let mode = AgcMode::Off;
@@ -674,6 +722,12 @@ fn apply_event_cycle_agc() {
     assert_eq!(state.agc_mode(), AgcMode::Slow);
 }
 
+#[test]
+fn apply_event_set_agc() {
+    let state = apply_event(RadioState::default(), RadioEvent::SetAgc(AgcMode::Fast));
+    assert_eq!(state.agc_mode(), AgcMode::Fast);
+}
+
 #[test]
 fn apply_event_toggle_nb() {
     let state = RadioState::default();
@@ -798,6 +852,32 @@ fn tx_controller_inhibit_prevents_tx() {
     assert_eq!(ctrl.state(), TxState::Rx);
 }
 
+#[test]
+fn tx_controller_consults_policy() {
+    let mut ctrl = TxController::new();
+    ctrl.policy_mut()
+        .set_operating(Frequency::from_hz(5_000_000).unwrap(), Mode::Usb);
+    assert_eq!(ctrl.verdict(), TxVerdict::Denied(TxReason::OutOfBand));
+    ctrl.set_ptt(true);
+    assert_eq!(ctrl.update(0), TxAction::None);
+    assert_eq!(ctrl.state(), TxState::Rx);
+
+    // Back in the band it keys, and retuning out mid-over drops the PA
+    ctrl.policy_mut()
+        .set_operating(Frequency::from_hz(14_200_000).unwrap(), Mode::Usb);
+    assert_eq!(ctrl.update(0), TxAction::EnableTrRelay);
+    assert_eq!(ctrl.update(10_000), TxAction::EnablePa);
+    ctrl.policy_mut()
+        .set_operating(Frequency::from_hz(14_349_000).unwrap(), Mode::Usb);
+    assert_eq!(ctrl.update(0), TxAction::DisablePa);
+
+    // The override lets it key with a warning
+    ctrl.update(10_000);
+    ctrl.policy_mut().set_override(true);
+    assert_eq!(ctrl.verdict(), TxVerdict::Warning(TxReason::BandEdge));
+    assert_eq!(ctrl.update(0), TxAction::EnableTrRelay);
+}
+
 #[test]
 fn tx_controller_ptt_abort_during_switching() {
     let mut ctrl = TxController::new();
@@ -902,6 +982,78 @@ fn tx_controller_clear_swr_trip() {
     assert_eq!(ctrl.swr_trip_count(), 0);
 }
 
+#[test]
+fn tx_controller_thermal_derating() {
+    let mut ctrl = TxController::new();
+    ctrl.set_power(PowerLevel::from_percent(80));
+    ctrl.update_thermal(50, false);
+    assert_eq!(ctrl.actual_power().as_percent(), 50);
+
+    ctrl.set_ptt(true);
+    ctrl.update(0);
+    assert_eq!(ctrl.update(10000), TxAction::EnablePa);
+    assert_eq!(ctrl.update(0), TxAction::SetPower(PowerLevel::from_percent(50)));
+
+    // Heating reduces power during the transmission, cooling waits for the next
+    ctrl.update_thermal(30, false);
+    assert_eq!(ctrl.update(0), TxAction::SetPower(PowerLevel::from_percent(30)));
+    ctrl.update_thermal(100, false);
+    assert_eq!(ctrl.update(0), TxAction::SetPower(PowerLevel::from_percent(30)));
+    assert_eq!(ctrl.thermal_limit(), 100);
+
+    ctrl.set_ptt(false);
+    ctrl.update(0);
+    ctrl.update(10000);
+    ctrl.set_ptt(true);
+    ctrl.update(0);
+    ctrl.update(10000);
+    assert_eq!(ctrl.actual_power().as_percent(), 80);
+}
+
+#[test]
+fn tx_controller_thermal_cutoff() {
+    let mut ctrl = TxController::new();
+    ctrl.set_ptt(true);
+    ctrl.update(0);
+    ctrl.update(10000);
+    assert!(ctrl.is_transmitting());
+
+    ctrl.update_thermal(0, true);
+    assert_eq!(ctrl.state(), TxState::Inhibited);
+    assert_eq!(ctrl.actual_power().as_percent(), 0);
+    assert!(ctrl.is_thermal_cutoff());
+
+    // Releasing PTT or clearing an SWR trip does not end the cutoff
+    ctrl.set_ptt(false);
+    ctrl.update(10000);
+    ctrl.clear_swr_trip();
+    assert_eq!(ctrl.state(), TxState::Inhibited);
+    ctrl.set_ptt(true);
+    assert_eq!(ctrl.update(10000), TxAction::None);
+
+    // Cooled, but PTT must be released first
+    ctrl.update_thermal(25, false);
+    ctrl.update(10000);
+    assert_eq!(ctrl.state(), TxState::Inhibited);
+    ctrl.set_ptt(false);
+    ctrl.update(10000);
+    assert_eq!(ctrl.state(), TxState::Rx);
+}
+
+#[test]
+fn tx_controller_power_ceiling() {
+    let mut ctrl = TxController::new();
+    assert_eq!(ctrl.power_ceiling_watts(), 5.0);
+    ctrl.set_power(PowerLevel::from_percent(50));
+    assert_eq!(ctrl.output_watts(), 2.5);
+
+    // A 15 V USB-PD contract raises full power, settings stay in percent
+    ctrl.set_power_ceiling(15.0);
+    assert_eq!(ctrl.output_watts(), 7.5);
+    ctrl.set_power_ceiling(100.0);
+    assert_eq!(ctrl.power_ceiling_watts(), 15.0);
+}
+
 #[test]
 fn tx_controller_timeout() {
     let mut ctrl = TxController::new();
@@ -962,6 +1114,107 @@ fn tx_controller_last_swr() {
     assert!(ctrl.last_swr().is_some());
 }
 
+// ============================================================================
+// QSK Tests
+// ============================================================================
+
+/// QSK controller with a 1 ms mute ramp and 5 ms recovery
+fn qsk_controller() -> TxController {
+    let mut ctrl = TxController::new();
+    ctrl.set_qsk(true);
+    ctrl.set_mute_ramp_ms(1);
+    ctrl.set_rx_recovery_ms(5);
+    ctrl
+}
+
+/// Run updates in 500 us ticks, collecting actions other than `None`/`SetPower`
+fn qsk_run(ctrl: &mut TxController, us: u32) -> Vec<TxAction> {
+    (0..us / 500)
+        .map(|_| ctrl.update(500))
+        .filter(|a| !matches!(a, TxAction::None | TxAction::SetPower(_)))
+        .collect()
+}
+
+#[test]
+fn qsk_mutes_before_relay_and_keys_pa() {
+    let mut ctrl = qsk_controller();
+    assert!(ctrl.is_qsk());
+    ctrl.set_key(true);
+
+    // Relay waits for the mute ramp
+    assert_eq!(ctrl.update(500), TxAction::None);
+    assert!(ctrl.rx_gain() < 1.0);
+    assert_eq!(qsk_run(&mut ctrl, 1000), vec![TxAction::EnableTrRelay]);
+    assert_eq!(ctrl.rx_gain(), 0.0);
+
+    assert_eq!(qsk_run(&mut ctrl, 10_000), vec![TxAction::EnablePa]);
+    assert!(ctrl.is_transmitting());
+}
+
+#[test]
+fn qsk_holds_relay_between_elements() {
+    let mut ctrl = qsk_controller();
+    ctrl.set_key(true);
+    qsk_run(&mut ctrl, 15_000);
+    assert!(ctrl.is_transmitting());
+
+    // Key-up: PA off, relay held through a short element space
+    ctrl.set_key(false);
+    assert_eq!(qsk_run(&mut ctrl, 3000), vec![TxAction::DisablePa]);
+    ctrl.set_key(true);
+    assert_eq!(qsk_run(&mut ctrl, 500), vec![TxAction::EnablePa]);
+    assert!(ctrl.is_transmitting());
+}
+
+#[test]
+fn qsk_recovers_to_rx_after_delay() {
+    let mut ctrl = qsk_controller();
+    ctrl.set_key(true);
+    qsk_run(&mut ctrl, 15_000);
+
+    ctrl.set_key(false);
+    assert_eq!(
+        qsk_run(&mut ctrl, 6000),
+        vec![TxAction::DisablePa, TxAction::DisableTrRelay]
+    );
+    // Audio stays muted while the relay settles, then ramps back up
+    assert_eq!(ctrl.rx_gain(), 0.0);
+    qsk_run(&mut ctrl, 10_000);
+    assert_eq!(ctrl.state(), TxState::Rx);
+    qsk_run(&mut ctrl, 1000);
+    assert_eq!(ctrl.rx_gain(), 1.0);
+    assert_eq!(ctrl.rx_recovery_ms(), 5);
+}
+
+#[test]
+fn qsk_short_tap_never_pulls_relay() {
+    let mut ctrl = qsk_controller();
+    ctrl.set_key(true);
+    assert_eq!(ctrl.update(500), TxAction::None);
+    ctrl.set_key(false);
+    assert!(qsk_run(&mut ctrl, 2000).is_empty());
+    assert_eq!(ctrl.state(), TxState::Rx);
+}
+
+#[test]
+fn qsk_stuck_key_times_out() {
+    let mut ctrl = qsk_controller();
+    ctrl.set_timeout(1);
+    ctrl.set_key(true);
+    qsk_run(&mut ctrl, 15_000);
+    ctrl.tick_timeout();
+
+    assert_eq!(
+        qsk_run(&mut ctrl, 1000),
+        vec![TxAction::DisablePa, TxAction::DisableTrRelay]
+    );
+    assert_eq!(ctrl.state(), TxState::Inhibited);
+
+    ctrl.set_key(false);
+    qsk_run(&mut ctrl, 500);
+    assert_eq!(ctrl.state(), TxState::Rx);
+}
+
 // ============================================================================
 // VOX Tests
 // ============================================================================
@@ -1104,3 +1357,1658 @@ fn vox_envelope_follower() {
     // Level should decay slowly
     // (Internal state not directly accessible, but behavior is tested)
 }
+
+#[test]
+fn vox_gain_sets_threshold() {
+    let mut vox = Vox::new();
+    assert_eq!(vox.gain(), DEFAULT_VOX_GAIN);
+
+    vox.set_gain(0);
+    assert!((vox.threshold() - 1.0).abs() < 1e-6);
+    vox.set_gain(150);
+    assert_eq!(vox.gain(), VOX_GAIN_MAX);
+    assert!((vox.threshold() - 0.001).abs() < 1e-5);
+
+    vox.set_hang_ms(750, 48000);
+    assert_eq!(vox.hang_ms(), 750);
+}
+
+#[test]
+fn vox_anti_vox_ignores_speaker_audio() {
+    let mut vox = Vox::new();
+    vox.set_enabled(true);
+    vox.set_gain(50); // about -30 dBFS
+
+    // Loud receive audio leaking into the mic at half its level
+    let rx = [0.6_f32; 480];
+    let leak: Vec<f32> = rx.iter().map(|s| s * 0.5).collect();
+    assert!(!vox.process_block(&leak, &rx));
+
+    // Without anti-trip the same leak keys the transmitter
+    vox.set_anti_trip(false);
+    assert!(vox.process_block(&leak, &rx));
+    vox.set_enabled(false);
+    vox.set_anti_trip(true);
+    vox.set_enabled(true);
+
+    // Speech louder than the speaker audio still triggers
+    let speech = [0.9_f32; 480];
+    assert!(vox.process_block(&speech, &rx));
+}
+
+#[test]
+fn tx_controller_vox_from_mic_audio() {
+    let mut tx = TxController::new();
+    let silence = [0.0_f32; 480];
+    let speech = [0.5_f32; 480];
+
+    // Detector disabled: speech does nothing
+    tx.process_vox_audio(&speech, &silence);
+    tx.update(20_000);
+    assert_eq!(tx.state(), TxState::Rx);
+
+    tx.vox_control_mut().set_enabled(true);
+    tx.vox_control_mut().set_hang_ms(10, 48000);
+    tx.process_vox_audio(&speech, &silence);
+    for _ in 0..3 {
+        tx.update(10_000);
+    }
+    assert!(tx.is_transmitting());
+
+    // Silence long enough for the envelope and hang time to run out
+    for _ in 0..20 {
+        tx.process_vox_audio(&silence, &silence);
+    }
+    for _ in 0..3 {
+        tx.update(10_000);
+    }
+    assert!(!tx.is_transmitting());
+}
+
+// ============================================================================
+// Band Monitor Tests
+// ============================================================================
+
+fn make_spot(band: Band, snr_db: i8, slot: u32) -> Spot {
+    Spot {
+        band,
+        frequency_hz: 14_097_100,
+        snr_db,
+        callsign: heapless::String::try_from("K1ABC").unwrap(),
+        locator: heapless::String::try_from("FN42").unwrap(),
+        slot,
+    }
+}
+
+#[test]
+fn monitor_default_schedule() {
+    let monitor = BandMonitor::default();
+    assert_eq!(monitor.mode(), MonitorMode::Wspr);
+    assert_eq!(monitor.bands(), &[Band::M40, Band::M30, Band::M20]);
+    assert!(!monitor.is_running());
+}
+
+#[test]
+fn monitor_start_retunes_to_first_band() {
+    let mut monitor = BandMonitor::default();
+    let action = monitor.start();
+    assert_eq!(
+        action,
+        MonitorAction::Retune(Frequency::from_hz(7_038_600).unwrap())
+    );
+    assert!(monitor.is_running());
+    assert_eq!(monitor.current_band(), Some(Band::M40));
+}
+
+#[test]
+fn monitor_start_empty_schedule() {
+    let mut monitor = BandMonitor::new(MonitorMode::Ft8);
+    assert_eq!(monitor.start(), MonitorAction::None);
+    assert!(!monitor.is_running());
+}
+
+#[test]
+fn monitor_actions_become_usb_retunes() {
+    assert!(MonitorAction::None.radio_events().is_empty());
+    let dial = Frequency::from_hz(10_138_700).unwrap();
+    let next = MonitorAction::SlotComplete { band: Band::M40, next: dial };
+    for action in [MonitorAction::Retune(dial), next] {
+        let events = action.radio_events();
+        assert!(matches!(events[0], RadioEvent::SetMode(Mode::Usb)));
+        assert!(matches!(events[1], RadioEvent::SetFrequency(f) if f == dial));
+    }
+}
+
+#[test]
+fn monitor_commands_start_stop_and_spot() {
+    let mut monitor = BandMonitor::default();
+    assert!(matches!(monitor.handle(MonitorCommand::Start), MonitorAction::Retune(_)));
+    monitor.update(MonitorMode::Wspr.slot_ms());
+    let spot = MonitorCommand::Spot(make_spot(Band::M40, -12, 0));
+    assert_eq!(monitor.handle(spot), MonitorAction::None);
+    assert_eq!(monitor.spots()[0].slot, 1);
+    assert_eq!(monitor.handle(MonitorCommand::Stop), MonitorAction::None);
+    assert!(!monitor.is_running());
+}
+
+#[test]
+fn monitor_slot_rotation() {
+    let mut monitor = BandMonitor::default();
+    monitor.start();
+
+    assert_eq!(monitor.update(60_000), MonitorAction::None);
+    assert_eq!(monitor.slot_remaining_ms(), 60_000);
+
+    let action = monitor.update(60_000);
+    assert_eq!(
+        action,
+        MonitorAction::SlotComplete {
+            band: Band::M40,
+            next: Frequency::from_hz(10_138_700).unwrap(),
+        }
+    );
+    assert_eq!(monitor.current_band(), Some(Band::M30));
+    assert_eq!(monitor.slot_count(), 1);
+
+    monitor.update(120_000);
+    let action = monitor.update(120_000);
+    // Wraps back to 40m
+    assert_eq!(
+        action,
+        MonitorAction::SlotComplete {
+            band: Band::M20,
+            next: Frequency::from_hz(7_038_600).unwrap(),
+        }
+    );
+}
+
+#[test]
+fn monitor_ft8_slot_length() {
+    let mut monitor = BandMonitor::with_bands(MonitorMode::Ft8, &[Band::M20, Band::M15]);
+    monitor.start();
+    assert_eq!(monitor.update(14_999), MonitorAction::None);
+    assert!(matches!(
+        monitor.update(1),
+        MonitorAction::SlotComplete { band: Band::M20, .. }
+    ));
+}
+
+#[test]
+fn monitor_stop_halts_schedule() {
+    let mut monitor = BandMonitor::default();
+    monitor.start();
+    monitor.stop();
+    assert_eq!(monitor.update(500_000), MonitorAction::None);
+    assert_eq!(monitor.slot_count(), 0);
+}
+
+#[test]
+fn monitor_align_to_slot_clock() {
+    let mut monitor = BandMonitor::default();
+    monitor.start();
+    monitor.align(240_000 + 90_000);
+    assert_eq!(monitor.slot_remaining_ms(), 30_000);
+}
+
+#[test]
+fn monitor_band_management() {
+    let mut monitor = BandMonitor::new(MonitorMode::Wspr);
+    assert!(monitor.add_band(Band::M40));
+    assert!(!monitor.add_band(Band::M40));
+    assert!(monitor.add_band(Band::M20));
+    monitor.remove_band(Band::M40);
+    assert_eq!(monitor.bands(), &[Band::M20]);
+}
+
+#[test]
+fn monitor_spots_table() {
+    let mut monitor = BandMonitor::default();
+    monitor.add_spot(make_spot(Band::M20, -12, 0));
+    monitor.add_spot(make_spot(Band::M20, -3, 1));
+    monitor.add_spot(make_spot(Band::M40, -20, 2));
+
+    assert_eq!(monitor.spot_count(Band::M20), 2);
+    assert_eq!(monitor.best_snr(Band::M20), Some(-3));
+    assert_eq!(monitor.best_snr(Band::M30), None);
+
+    monitor.clear_spots();
+    assert!(monitor.spots().is_empty());
+}
+
+#[test]
+fn monitor_spots_drop_oldest() {
+    let mut monitor = BandMonitor::default();
+    for slot in 0..(MAX_SPOTS as u32 + 5) {
+        monitor.add_spot(make_spot(Band::M20, 0, slot));
+    }
+    assert_eq!(monitor.spots().len(), MAX_SPOTS);
+    assert_eq!(monitor.spots()[0].slot, 5);
+}
+
+#[test]
+fn monitor_band_conditions() {
+    let mut monitor = BandMonitor::with_bands(MonitorMode::Ft8, &[Band::M40, Band::M20]);
+    assert!(!monitor.set_home_locator("XX"));
+    assert!(monitor.set_home_locator("IO91"));
+    assert_eq!(monitor.home_locator(), "IO91");
+    assert_eq!(monitor.band_score(Band::M20), None);
+
+    // Quiet 20m full of transatlantic spots, noisy 40m with none
+    for slot in 0..20 {
+        monitor.add_spot(make_spot(Band::M20, -10, slot));
+    }
+    monitor.add_noise_floor(Band::M20, -118.0);
+    monitor.add_noise_floor(Band::M40, -92.0);
+
+    assert_eq!(monitor.band_condition(Band::M20), Some(Condition::Excellent));
+    assert_eq!(monitor.band_condition(Band::M40), Some(Condition::Poor));
+
+    // Spot activity ages once per full cycle of the schedule
+    let before = monitor.band_score(Band::M20).unwrap();
+    monitor.start();
+    monitor.update(15_000);
+    monitor.update(15_000);
+    assert!(monitor.band_score(Band::M20).unwrap() < before);
+
+    monitor.clear_spots();
+    assert_eq!(monitor.band_score(Band::M20), None);
+}
+
+#[test]
+fn monitor_align_to_clock() {
+    let mut monitor = BandMonitor::with_bands(MonitorMode::Ft8, &[Band::M40, Band::M20]);
+    let mut clock = Clock::new();
+    assert!(!monitor.align_to_clock(&clock, 0));
+
+    // 12:00:10 UTC is 10 s into an FT8 slot
+    let time = DateTime::new(2024, 6, 1, 12, 0, 10).unwrap();
+    clock.set(&time, 1_000, TimeSource::Gps);
+    assert!(monitor.align_to_clock(&clock, 1_000));
+    assert_eq!(monitor.slot_remaining_ms(), 5_000);
+}
+
+// ============================================================================
+// Activity Recorder Tests
+// ============================================================================
+
+#[test]
+fn recorder_disabled_ignores_activity() {
+    let mut rec = ActivityRecorder::new();
+    assert_eq!(rec.update(true, false, 0), RecorderAction::None);
+    assert!(!rec.is_recording());
+}
+
+#[test]
+fn recorder_squelch_starts_clip() {
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    assert_eq!(
+        rec.update(true, false, 100),
+        RecorderAction::Start { id: 0, trigger: RecordTrigger::Squelch }
+    );
+    assert!(rec.is_recording());
+    // Continued activity keeps recording
+    assert_eq!(rec.update(true, false, 200), RecorderAction::None);
+}
+
+#[test]
+fn recorder_decoder_trigger() {
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    assert_eq!(
+        rec.update(false, true, 0),
+        RecorderAction::Start { id: 0, trigger: RecordTrigger::Decoder }
+    );
+}
+
+#[test]
+fn recorder_stops_after_tail() {
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    rec.set_tail_ms(1_000);
+    rec.set_frequency(7_040_000);
+
+    rec.update(true, false, 0);
+    rec.update(true, false, 500);
+    assert_eq!(rec.update(false, false, 1_400), RecorderAction::None);
+
+    match rec.update(false, false, 1_500) {
+        RecorderAction::Stop(clip) => {
+            assert_eq!(clip.id, 0);
+            assert_eq!(clip.duration_ms, 1_500);
+            assert_eq!(clip.frequency_hz, 7_040_000);
+        }
+        other => panic!("expected Stop, got {:?}", other),
+    }
+    assert!(!rec.is_recording());
+    assert_eq!(rec.clips().len(), 1);
+}
+
+#[test]
+fn recorder_max_clip_length() {
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    rec.set_max_clip_ms(10_000);
+
+    rec.update(true, false, 0);
+    assert!(matches!(rec.update(true, false, 10_000), RecorderAction::Stop(_)));
+    // Next activity starts a fresh clip
+    assert_eq!(
+        rec.update(true, false, 10_001),
+        RecorderAction::Start { id: 1, trigger: RecordTrigger::Squelch }
+    );
+}
+
+#[test]
+fn recorder_manual_start_and_stop() {
+    let mut rec = ActivityRecorder::new();
+    assert_eq!(
+        rec.start_manual(0),
+        RecorderAction::Start { id: 0, trigger: RecordTrigger::Manual }
+    );
+    assert!(matches!(rec.stop(2_000), RecorderAction::Stop(clip) if clip.duration_ms == 2_000));
+    assert_eq!(rec.stop(3_000), RecorderAction::None);
+}
+
+#[test]
+fn recorder_disarm_closes_clip() {
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    rec.update(true, false, 0);
+    rec.set_enabled(false);
+    assert!(matches!(rec.update(true, false, 50), RecorderAction::Stop(_)));
+}
+
+#[test]
+fn recorder_clip_index() {
+    let mut rec = ActivityRecorder::new();
+    for i in 0..(MAX_CLIPS as u32 + 2) {
+        rec.start_manual(i * 10);
+        rec.stop(i * 10 + 5);
+    }
+    assert_eq!(rec.clips().len(), MAX_CLIPS);
+    assert_eq!(rec.clips()[0].id, 2);
+
+    assert!(rec.remove_clip(2));
+    assert!(!rec.remove_clip(2));
+    rec.clear_clips();
+    assert!(rec.clips().is_empty());
+}
+
+// ============================================================================
+// WinKeyer Tests
+// ============================================================================
+
+/// Feed bytes and apply every decoded command, collecting replies
+fn wk_send(wk: &mut WinKeyer, keyer: &mut Keyer, bytes: &[u8]) -> Vec<u8> {
+    let mut replies = Vec::new();
+    for &byte in bytes {
+        if let Some(cmd) = wk.feed(byte) {
+            replies.extend(wk.apply(cmd, keyer));
+        }
+    }
+    replies
+}
+
+/// Emulator with the host session open
+fn wk_open() -> (WinKeyer, Keyer) {
+    let mut wk = WinKeyer::new();
+    let mut keyer = Keyer::new(8000);
+    assert_eq!(wk_send(&mut wk, &mut keyer, &[0x00, 0x02]), vec![WK_VERSION]);
+    (wk, keyer)
+}
+
+#[test]
+fn winkeyer_parses_commands() {
+    let mut wk = WinKeyer::new();
+    assert_eq!(wk.feed(0x02), None);
+    assert_eq!(wk.feed(28), Some(WkCommand::SetWpm(28)));
+    assert_eq!(wk.feed(b'C'), Some(WkCommand::Text('C')));
+    assert_eq!(wk.feed(0x15), Some(WkCommand::RequestStatus));
+    assert_eq!(wk.feed(0x1B), None);
+    assert_eq!(wk.feed(b'A'), None);
+    assert_eq!(wk.feed(b'R'), Some(WkCommand::Merge('A', 'R')));
+}
+
+#[test]
+fn winkeyer_skips_unsupported_parameters() {
+    let mut wk = WinKeyer::new();
+    // Load defaults carries 15 parameter bytes, some of them printable
+    assert_eq!(wk.feed(0x0F), None);
+    for byte in 0..14u8 {
+        assert_eq!(wk.feed(b'A' + byte), None);
+    }
+    assert_eq!(wk.feed(0), Some(WkCommand::Ignored(0x0F)));
+    assert_eq!(wk.feed(b'K'), Some(WkCommand::Text('K')));
+}
+
+#[test]
+fn winkeyer_host_open_and_echo_test() {
+    let (mut wk, mut keyer) = wk_open();
+    assert!(wk.is_host_open());
+    assert_eq!(wk_send(&mut wk, &mut keyer, &[0x00, 0x04, 0x55]), vec![0x55]);
+
+    wk_send(&mut wk, &mut keyer, &[0x00, 0x03]);
+    assert!(!wk.is_host_open());
+}
+
+#[test]
+fn winkeyer_ignores_commands_while_closed() {
+    let mut wk = WinKeyer::new();
+    let mut keyer = Keyer::new(8000);
+    wk_send(&mut wk, &mut keyer, &[0x02, 35, b'T', b'E', b'S', b'T']);
+    assert_eq!(keyer.wpm(), Keyer::DEFAULT_WPM);
+    assert_eq!(keyer.text_pending(), 0);
+}
+
+#[test]
+fn winkeyer_configures_keyer() {
+    let (mut wk, mut keyer) = wk_open();
+    wk_send(&mut wk, &mut keyer, &[0x02, 32, 0x03, 60, 0x01, 0x05]);
+    assert_eq!(keyer.wpm(), 32);
+    assert_eq!(keyer.weight(), 60);
+    assert_eq!(keyer.sidetone(), 800);
+
+    // Speed 0 selects the (absent) pot and leaves the speed alone
+    wk_send(&mut wk, &mut keyer, &[0x02, 0]);
+    assert_eq!(keyer.wpm(), 32);
+
+    wk_send(&mut wk, &mut keyer, &[0x0E, 0x10]);
+    assert_eq!(keyer.mode(), KeyerMode::IambicA);
+    wk_send(&mut wk, &mut keyer, &[0x0E, 0x00]);
+    assert_eq!(keyer.mode(), KeyerMode::IambicB);
+
+    // Ratio 50 is the standard 1:3, 60 gives 3.6 dits per dah
+    wk_send(&mut wk, &mut keyer, &[0x17, 60, 0x10, 25]);
+    assert_eq!(keyer.dah_ratio(), 36);
+    assert_eq!(keyer.first_extension_ms(), 25);
+    wk_send(&mut wk, &mut keyer, &[0x17, 50]);
+    assert_eq!(keyer.dah_ratio(), 30);
+}
+
+#[test]
+fn winkeyer_farnsworth_and_merge() {
+    let (mut wk, mut keyer) = wk_open();
+    wk_send(&mut wk, &mut keyer, &[0x0D, 10]);
+    assert_eq!(keyer.farnsworth(), 10);
+    assert_eq!(keyer.effective_wpm(), 10);
+
+    // Merged characters are queued as a bracketed prosign
+    wk_send(&mut wk, &mut keyer, &[0x1B, b'S', b'K']);
+    assert_eq!(keyer.text_pending(), 4);
+}
+
+#[test]
+fn winkeyer_buffers_text_and_clears() {
+    let (mut wk, mut keyer) = wk_open();
+    wk_send(&mut wk, &mut keyer, b"CQ TEST");
+    assert_eq!(keyer.text_pending(), 7);
+
+    wk_send(&mut wk, &mut keyer, &[0x08]);
+    assert_eq!(keyer.text_pending(), 6);
+
+    wk_send(&mut wk, &mut keyer, &[0x0A]);
+    assert_eq!(keyer.text_pending(), 0);
+    assert!(!keyer.is_sending_text());
+}
+
+#[test]
+fn winkeyer_status_and_echo() {
+    let (mut wk, mut keyer) = wk_open();
+    // Enable serial echo
+    wk_send(&mut wk, &mut keyer, &[0x0E, 0x04]);
+    assert_eq!(wk_send(&mut wk, &mut keyer, &[0x15]), vec![0xC0]);
+
+    wk_send(&mut wk, &mut keyer, b"E");
+    keyer.process(PaddleState::default());
+
+    // Busy status first, then the echoed character
+    assert_eq!(wk.poll(&mut keyer), Some(0xC4));
+    assert_eq!(wk.poll(&mut keyer), Some(b'E'));
+    assert_eq!(wk.poll(&mut keyer), None);
+
+    for _ in 0..8000 {
+        keyer.process(PaddleState::default());
+    }
+    assert_eq!(wk.poll(&mut keyer), Some(0xC0));
+}
+
+#[test]
+fn winkeyer_tune_key_immediate() {
+    let (mut wk, mut keyer) = wk_open();
+    wk_send(&mut wk, &mut keyer, &[0x0B, 1]);
+    assert!(keyer.is_key_down());
+    assert_eq!(wk_send(&mut wk, &mut keyer, &[0x15]), vec![0xC0 | 0x04 | 0x08]);
+
+    wk_send(&mut wk, &mut keyer, &[0x0B, 0]);
+    assert!(!keyer.is_key_down());
+}
+
+#[test]
+fn winkeyer_paddle_swap_and_software_paddle() {
+    let (mut wk, mut keyer) = wk_open();
+    let dit = PaddleState::new(true, false);
+    assert_eq!(wk.paddles(dit), dit);
+
+    wk_send(&mut wk, &mut keyer, &[0x0E, 0x08]);
+    assert_eq!(wk.paddles(dit), PaddleState::new(false, true));
+
+    wk_send(&mut wk, &mut keyer, &[0x14, 0x02]);
+    assert_eq!(wk.paddles(PaddleState::default()), dit);
+}
+
+// ============================================================================
+// Annunciator Tests
+// ============================================================================
+
+/// Run an annunciator to completion in 1 ms ticks, returning (key-down ms, total ms)
+fn run_annunciator(ann: &mut Annunciator) -> (u32, u32) {
+    let (mut down, mut total) = (0, 0);
+    while ann.is_active() {
+        if ann.update(1) {
+            down += 1;
+        }
+        total += 1;
+        assert!(total < 60_000, "annunciation never finished");
+    }
+    (down, total)
+}
+
+#[test]
+fn annunciator_band_change_sends_mhz() {
+    let mut ann = Annunciator::new();
+    ann.set_wpm(20);
+    assert_eq!(ann.unit_ms(), 60);
+    assert_eq!(Annunciation::Band(Band::M40).text(), "7");
+    assert_eq!(Annunciation::Band(Band::M20).text(), "14");
+
+    // "7" is --... : 9 units of tone, 5 element gaps and a character gap
+    assert!(ann.announce_band(Band::M40));
+    assert!(ann.update(1));
+    let (down, total) = run_annunciator(&mut ann);
+    assert_eq!(down + 1, 9 * 60);
+    assert_eq!(total + 1, 16 * 60);
+    assert!(!ann.is_key_down());
+}
+
+#[test]
+fn annunciator_replaces_pending_event() {
+    let mut ann = Annunciator::new();
+    ann.set_wpm(20);
+    ann.announce_band(Band::M15);
+    ann.update(100);
+    // "E" replaces the rest of "21": one dit
+    assert!(ann.announce_error());
+    let (down, _) = run_annunciator(&mut ann);
+    assert_eq!(down, 60);
+
+    // Large steps are split across element boundaries
+    ann.announce_saved();
+    assert!(!ann.update(60 + 60));
+    assert!(ann.update(1));
+}
+
+#[test]
+fn annunciator_disabled_and_settings_clamped() {
+    let mut ann = Annunciator::new();
+    ann.set_wpm(100);
+    assert_eq!(ann.wpm(), 40);
+    ann.set_level(2.0);
+    assert!((ann.level() - 1.0).abs() < f32::EPSILON);
+
+    ann.announce_saved();
+    ann.set_enabled(false);
+    assert!(!ann.is_active());
+    assert!(!ann.announce_error());
+    assert!(!ann.update(10));
+
+    ann.set_enabled(true);
+    assert!(!ann.announce_text("THIS TEXT IS TOO LONG"));
+    assert!(ann.announce_text("73"));
+}
+
+// ============================================================================
+// APRS Beacon Tests
+// ============================================================================
+
+#[cfg(feature = "aprs-beacon")]
+#[test]
+fn beacon_frame_contents() {
+    let mut beacon = AprsBeacon::new("n0call", 7, 48_000.0).unwrap();
+    assert!(beacon.frame().is_none());
+    assert!(!beacon.send_now());
+
+    beacon.set_position(49.0583, -72.0292);
+    beacon.set_symbol('/', '>');
+    assert!(beacon.set_comment("uSDX 5W"));
+    assert!(!beacon.set_comment(&"x".repeat(MAX_COMMENT_LEN + 1)));
+    assert!(!beacon.set_comment("bad\n"));
+
+    let frame = beacon.frame().unwrap();
+    assert_eq!(
+        frame.to_string(),
+        "N0CALL-7>APZSDR,WIDE1-1,WIDE2-1:!4903.50N/07201.75W>uSDX 5W"
+    );
+
+    assert_eq!(beacon.set_path(&["WIDE1-1", "WIDE2-1", "WIDE3-3"]), Err(Ax25Error::TooManyDigipeaters));
+    beacon.set_path(&[]).unwrap();
+    assert!(beacon.frame().unwrap().digipeaters.is_empty());
+    assert_eq!(AprsBeacon::new("N0CALL", 16, 48_000.0).err(), Some(Ax25Error::BadAddress));
+}
+
+#[cfg(feature = "aprs-beacon")]
+#[test]
+fn beacon_interval_and_audio_decodes() {
+    let rate = 48_000.0;
+    let mut beacon = AprsBeacon::new("N0CALL", 0, rate).unwrap();
+    beacon.set_position(-33.86, 151.21);
+    beacon.set_interval_s(10);
+    assert_eq!(beacon.interval_s(), 60);
+
+    // Nothing while disabled
+    assert!(!beacon.update(120_000));
+    beacon.set_enabled(true);
+    assert!(!beacon.update(59_999));
+    assert!(beacon.update(1));
+    assert!(beacon.is_transmitting());
+    assert!(!beacon.update(120_000));
+
+    let mut decoder = AprsDecoder::new(rate);
+    let mut decoded = None;
+    let mut samples = 0;
+    while let Some(sample) = beacon.next_sample() {
+        samples += 1;
+        if let Some(bytes) = decoder.process(sample) {
+            decoded = Some(Ax25Frame::parse(&bytes).unwrap());
+        }
+    }
+    assert!(!beacon.is_transmitting());
+    // About 300 ms of flags plus the frame at 1200 baud
+    assert!(samples > 48_000 * 4 / 10 && samples < 48_000, "{samples}");
+
+    let decoded = decoded.unwrap();
+    assert_eq!(decoded, beacon.frame().unwrap());
+    let Some(AprsPacket::Position { position, .. }) = AprsPacket::parse(&decoded.info) else {
+        panic!("not a position");
+    };
+    assert!((position.latitude + 33.86).abs() < 1e-3);
+    assert!((position.longitude - 151.21).abs() < 1e-3);
+
+    // The next beacon is one interval later
+    assert!(!beacon.update(59_000));
+    assert!(beacon.update(1_000));
+    beacon.set_enabled(false);
+    assert!(!beacon.is_transmitting());
+}
+
+// ============================================================================
+// Two-Tone TX Test Tests
+// ============================================================================
+
+/// Run the two-tone test through a PA with cubic compression `k`,
+/// returning the last report
+fn two_tone_loopback(usb: bool, drive: f32, k: f32) -> ImdReport {
+    let mut test = TxTest::new(48_000.0);
+    test.set_drive(drive);
+    test.start(usb);
+    let mut last = None;
+    // Let the modulator filters settle, then measure
+    for _ in 0..48_000 {
+        let iq = test.next_iq();
+        let power = iq.i * iq.i + iq.q * iq.q;
+        let pa = IqSample::new(iq.i * (1.0 - k * power), iq.q * (1.0 - k * power));
+        if let Some(report) = test.process_loopback(pa) {
+            last = Some(report);
+        }
+    }
+    last.unwrap()
+}
+
+#[test]
+fn tx_test_clean_loopback_balances_tones() {
+    let report = two_tone_loopback(true, 0.5, 0.0);
+    assert!(report.imd3_dbc < -80.0, "{report:?}");
+    assert!(report.imd5_dbc < -80.0, "{report:?}");
+    // The modulator's audio filter favours one tone until rebalanced
+    assert!(report.balance_db.abs() < 0.5, "{report:?}");
+    assert!(report.tone_dbfs < 0.0 && report.tone_dbfs > -20.0, "{report:?}");
+}
+
+#[test]
+fn tx_test_measures_pa_compression() {
+    for usb in [true, false] {
+        let clean = two_tone_loopback(usb, 0.5, 0.0);
+        let mild = two_tone_loopback(usb, 0.5, 0.2);
+        let hard = two_tone_loopback(usb, 0.5, 0.6);
+        // Third order products from a cubic PA, rising with compression
+        assert!(mild.imd3_dbc > -50.0 && mild.imd3_dbc < -30.0, "{mild:?}");
+        assert!(hard.imd3_dbc > mild.imd3_dbc + 6.0, "{hard:?}");
+        assert!(hard.imd5_dbc < hard.imd3_dbc - 20.0, "{hard:?}");
+        assert!(clean.imd3_dbc < mild.imd3_dbc - 30.0);
+    }
+}
+
+#[test]
+fn tx_test_start_stop() {
+    let mut test = TxTest::new(48_000.0);
+    assert!(!test.is_active());
+    assert_eq!(test.next_iq().i, 0.0);
+    assert!(test.process_loopback(IqSample::new(0.1, 0.0)).is_none());
+
+    test.set_drive(2.0);
+    assert_eq!(test.drive(), 1.0);
+    test.start(true);
+    assert!(test.is_active());
+    let window = 48 * usize::from(WINDOW_MS);
+    let reports = (0..2 * window)
+        .filter_map(|_| {
+            let iq = test.next_iq();
+            test.process_loopback(iq)
+        })
+        .count();
+    assert_eq!(reports, 2);
+
+    // Stopping keeps the last measurement; starting again clears it
+    test.stop();
+    assert!(test.report().is_some());
+    test.start(false);
+    assert!(test.report().is_none());
+}
+
+// ============================================================================
+// Antenna Sweep Tests
+// ============================================================================
+
+/// Bridge reading for a dipole resonant at 7.1 MHz, 2:1 at +-100 kHz
+fn dipole_reading(freq: Frequency) -> SwrReading {
+    let offset = (freq.as_hz() as f32 - 7_100_000.0) / 100_000.0;
+    let swr = 1.1 + 0.9 * offset * offset;
+    let rho = (swr - 1.0) / (swr + 1.0);
+    SwrReading {
+        forward: 10_000,
+        reflected: (10_000.0 * rho * rho) as u16,
+    }
+}
+
+#[test]
+fn sweep_steps_across_band() {
+    let mut sweep = AntennaSweep::new();
+    let mut freq = sweep.start_band(Band::M40, DEFAULT_POINTS).unwrap();
+    assert_eq!(freq.as_hz(), 7_000_000);
+    assert!(sweep.is_active());
+
+    let mut tuned = vec![freq];
+    loop {
+        // Nothing to read until the carrier settles
+        assert!(!sweep.update(sweep.settle_ms() - 1));
+        assert!(sweep.update(1));
+        match sweep.record(dipole_reading(freq)) {
+            Some(next) => {
+                freq = next;
+                tuned.push(next);
+            }
+            None => break,
+        }
+    }
+    assert!(!sweep.is_active());
+    assert_eq!(sweep.len(), DEFAULT_POINTS);
+    assert_eq!(tuned.len(), DEFAULT_POINTS);
+    assert_eq!(sweep.last().unwrap().frequency.as_hz(), 7_300_000);
+    assert_eq!(tuned[1].as_hz(), 7_006_000);
+
+    let best = sweep.best().unwrap();
+    assert_eq!(best.frequency.as_hz(), 7_102_000);
+    assert!(best.swr() < 1.2, "{}", best.swr());
+    let (low, high) = sweep.bandwidth(2.0).unwrap();
+    assert!(low.as_hz() >= 7_000_000 && low.as_hz() < 7_010_000, "{low:?}");
+    assert!(high.as_hz() > 7_190_000 && high.as_hz() <= 7_200_000, "{high:?}");
+    assert!(sweep.bandwidth(1.05).is_none());
+}
+
+#[test]
+fn sweep_rejects_bad_spans() {
+    let mut sweep = AntennaSweep::new();
+    let low = Frequency::from_hz(14_000_000).unwrap();
+    let high = Frequency::from_hz(14_350_000).unwrap();
+    assert!(sweep.start(high, low, 11).is_none());
+    assert!(sweep.start(low, high, 1).is_none());
+    assert!(sweep.start(low, high, MAX_POINTS + 1).is_none());
+    assert!(!sweep.is_active());
+    assert!(!sweep.update(100));
+    assert!(sweep.record(SwrReading { forward: 100, reflected: 0 }).is_none());
+    assert!(sweep.is_empty());
+}
+
+#[test]
+fn sweep_stop_keeps_points() {
+    let mut sweep = AntennaSweep::new();
+    let low = Frequency::from_hz(14_000_000).unwrap();
+    let high = Frequency::from_hz(14_350_000).unwrap();
+    sweep.start(low, high, MAX_POINTS).unwrap();
+    let next = sweep.record(SwrReading { forward: 100, reflected: 0 }).unwrap();
+    assert_eq!(next.as_hz(), 14_003_500);
+    sweep.stop();
+    assert!(!sweep.update(1000));
+    assert!(sweep.record(SwrReading { forward: 100, reflected: 0 }).is_none());
+    assert_eq!(sweep.len(), 1);
+    assert_eq!(sweep.total(), MAX_POINTS);
+
+    // Restarting discards the old curve
+    sweep.start(low, high, 2).unwrap();
+    assert!(sweep.is_empty());
+}
+
+// ============================================================================
+// Front Panel Input Tests
+// ============================================================================
+
+#[test]
+fn acceleration_slow_turn_single_steps() {
+    let mut accel = TuningAcceleration::new();
+    // Five detents a second
+    for n in 0..10 {
+        assert_eq!(accel.detent(n * 200), 1);
+    }
+    assert!(matches!(accel.tune(false, 2000), RadioEvent::Tune(-1)));
+}
+
+#[test]
+fn acceleration_fast_spin_reaches_max() {
+    let mut accel = TuningAcceleration::new();
+    let mut multipliers = Vec::new();
+    // Spin up to 200 detents a second
+    let mut now = 0;
+    for interval in [60, 40, 30, 25, 20, 15, 10, 8, 5, 5, 5, 5] {
+        now += interval;
+        multipliers.push(accel.detent(now));
+    }
+    assert!(multipliers.windows(2).all(|pair| pair[0] <= pair[1]), "{multipliers:?}");
+    assert_eq!(*multipliers.last().unwrap(), MAX_MULTIPLIER);
+    assert!(matches!(accel.tune(true, now + 5), RadioEvent::Tune(100)));
+
+    // A pause drops back to single steps
+    assert_eq!(accel.detent(now + 500), 1);
+
+    // Menus turn acceleration off
+    accel.set_enabled(false);
+    for n in 1..10 {
+        assert_eq!(accel.detent(now + 500 + n * 5), 1);
+    }
+}
+
+#[test]
+fn push_button_debounce_and_long_press() {
+    let mut button = PushButton::new();
+
+    // Contact bounce shorter than the debounce time is ignored
+    assert_eq!(button.update(true, 0), None);
+    assert_eq!(button.update(false, 5), None);
+    assert_eq!(button.update(false, 100), None);
+    assert!(!button.is_pressed());
+
+    // Short press
+    assert_eq!(button.update(true, 200), None);
+    assert_eq!(button.update(true, 260), Some(ButtonEvent::Press));
+    assert!(button.is_pressed());
+    assert_eq!(button.update(false, 400), None);
+    assert_eq!(button.update(false, 460), Some(ButtonEvent::Release));
+
+    // Long press fires once while held, with no release after it
+    assert_eq!(button.update(true, 1000), None);
+    assert_eq!(button.update(true, 1060), Some(ButtonEvent::Press));
+    assert_eq!(button.update(true, 1500), None);
+    assert_eq!(button.update(true, 1560), Some(ButtonEvent::LongPress));
+    assert_eq!(button.update(true, 2000), None);
+    assert_eq!(button.update(false, 2100), None);
+    assert_eq!(button.update(false, 2200), None);
+    assert!(!button.is_pressed());
+}
+
+// ============================================================================
+// Radio Control Tests
+// ============================================================================
+
+fn controller(tx_allowed: bool) -> RadioController {
+    RadioController::new(RadioState::new(Frequency::from_hz(7_074_000).unwrap()), tx_allowed)
+}
+
+#[test]
+fn control_startup_programs_everything() {
+    let control = controller(true);
+    let effects = control.startup();
+    assert_eq!(effects.retune, Frequency::from_hz(7_074_000));
+    assert!(effects.dsp.contains(&DspCommand::SetMode(control.state().mode())));
+    assert!(effects.dsp.contains(&DspCommand::SetAgc(AgcMode::Medium)));
+    assert!(effects.dsp.contains(&DspCommand::SetTransmit(false)));
+    assert!(effects.dsp.contains(&DspCommand::SetBand(Some(Band::M40))));
+}
+
+#[test]
+fn control_tune_retunes_only() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::Tune(2));
+    assert_eq!(effects.retune, Frequency::from_hz(7_076_000));
+    assert!(effects.dsp.is_empty());
+    assert!(effects.state_changed);
+
+    // A step change shows on the display but touches no hardware
+    let effects = control.handle(RadioEvent::NextStep);
+    assert_eq!(effects.retune, None);
+    assert!(effects.dsp.is_empty());
+    assert!(effects.state_changed);
+}
+
+#[test]
+fn control_mode_and_agc_go_to_dsp() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::SetMode(Mode::Cw));
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetMode(Mode::Cw)]);
+
+    let effects = control.handle(RadioEvent::CycleAgc);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetAgc(AgcMode::Slow)]);
+
+    let effects = control.handle(RadioEvent::ToggleNb);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetNoiseBlanker(true)]);
+
+    // Setting the same mode again does nothing
+    assert!(control.handle(RadioEvent::SetMode(Mode::Cw)).is_empty());
+}
+
+#[test]
+fn control_transmit_mutes_and_uses_xit() {
+    let mut control = controller(true);
+    control.handle(RadioEvent::ToggleXit);
+    let effects = control.handle(RadioEvent::StartTx);
+    assert_eq!(control.state().txrx(), TxRxState::Tx);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetTransmit(true)]);
+
+    let effects = control.handle(RadioEvent::StopTx);
+    assert_eq!(control.state().txrx(), TxRxState::Rx);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetTransmit(false)]);
+}
+
+#[test]
+fn control_inhibited_transmit_stays_in_receive() {
+    let mut control = controller(false);
+    let effects = control.handle(RadioEvent::StartTx);
+    assert_eq!(control.state().txrx(), TxRxState::Rx);
+    assert!(effects.retune.is_none() && effects.dsp.is_empty() && !effects.state_changed);
+    assert_eq!(effects.announce, Some(Annunciation::Error));
+
+    // Inhibiting while transmitting drops back to receive
+    let mut control = controller(true);
+    control.handle(RadioEvent::StartTx);
+    let effects = control.set_tx_allowed(false);
+    assert_eq!(control.state().txrx(), TxRxState::Rx);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetTransmit(false)]);
+}
+
+#[test]
+fn control_auto_tune_asks_dsp_then_shifts() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::AutoTune);
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::AutoTune]);
+    assert_eq!(effects.retune, None);
+    assert!(!effects.state_changed);
+
+    // The DSP task answers with the correction
+    let effects = control.handle(RadioEvent::ShiftFrequency(-60));
+    assert_eq!(effects.retune, Frequency::from_hz(7_073_940));
+    assert!(effects.dsp.is_empty());
+    assert!(control.handle(RadioEvent::ShiftFrequency(0)).is_empty());
+
+    // A shift off the end of the range is ignored
+    let mut control = RadioController::new(
+        RadioState::new(Frequency::from_hz(Frequency::MIN_HZ).unwrap()),
+        true,
+    );
+    assert!(control.handle(RadioEvent::ShiftFrequency(-1000)).is_empty());
+
+    // Not while transmitting
+    let mut control = controller(true);
+    control.handle(RadioEvent::StartTx);
+    assert!(control.handle(RadioEvent::AutoTune).is_empty());
+}
+
+#[test]
+fn control_band_change_loads_iq_balance() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::SetFrequency(
+        Frequency::from_hz(14_074_000).unwrap(),
+    ));
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetBand(Some(Band::M20))]);
+
+    // Tuning within the band leaves it alone
+    assert!(control.handle(RadioEvent::Tune(1)).dsp.is_empty());
+
+    let effects = control.handle(RadioEvent::SetIqAdaptive(true));
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::SetIqAdaptive(true)]);
+    assert!(!effects.state_changed);
+}
+
+#[test]
+fn control_announces_band_changes() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::SetFrequency(
+        Frequency::from_hz(14_074_000).unwrap(),
+    ));
+    assert_eq!(effects.announce, Some(Annunciation::Band(Band::M20)));
+    assert_eq!(control.handle(RadioEvent::Tune(1)).announce, None);
+
+    // Leaving the bands is not announced
+    let effects = control.handle(RadioEvent::SetFrequency(
+        Frequency::from_hz(12_000_000).unwrap(),
+    ));
+    assert_eq!(effects.announce, None);
+    assert!(effects.dsp.contains(&DspCommand::SetBand(None)));
+}
+
+#[test]
+fn control_store_state_asks_for_save() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::StoreState);
+    assert!(effects.save);
+    assert!(!effects.state_changed);
+    assert!(!control.handle(RadioEvent::Tune(1)).save);
+}
+
+#[test]
+fn control_iq_capture_goes_to_dsp() {
+    let mut control = controller(true);
+    let effects = control.handle(RadioEvent::CaptureIq(512));
+    assert_eq!(effects.dsp.as_slice(), &[DspCommand::CaptureIq(512)]);
+    assert_eq!(effects.retune, None);
+    assert!(!effects.state_changed);
+}
+
+// ============================================================================
+// TX Policy Tests
+// ============================================================================
+
+fn policy_at(hz: u32, mode: Mode) -> TxVerdict {
+    let mut policy = TxPolicy::new();
+    policy.set_operating(Frequency::from_hz(hz).unwrap(), mode);
+    policy.verdict()
+}
+
+#[test]
+fn tx_policy_band_edges() {
+    // No operating point, no band rules
+    assert_eq!(TxPolicy::new().verdict(), TxVerdict::Clear);
+
+    assert_eq!(policy_at(14_250_000, Mode::Usb), TxVerdict::Clear);
+    assert_eq!(policy_at(10_120_000, Mode::Cw), TxVerdict::Clear);
+    assert_eq!(
+        policy_at(9_000_000, Mode::Cw),
+        TxVerdict::Denied(TxReason::OutOfBand)
+    );
+
+    // USB spills above the dial, LSB below it, AM both ways
+    assert_eq!(
+        policy_at(14_348_000, Mode::Usb),
+        TxVerdict::Denied(TxReason::BandEdge)
+    );
+    assert_eq!(policy_at(14_348_000, Mode::Lsb), TxVerdict::Clear);
+    assert_eq!(
+        policy_at(3_601_000, Mode::Lsb),
+        TxVerdict::Warning(TxReason::ModeSegment)
+    );
+    assert_eq!(
+        policy_at(7_001_000, Mode::Lsb),
+        TxVerdict::Denied(TxReason::BandEdge)
+    );
+    assert_eq!(policy_at(7_000_100, Mode::Cw), TxVerdict::Clear);
+    assert_eq!(
+        policy_at(21_448_000, Mode::Am),
+        TxVerdict::Denied(TxReason::BandEdge)
+    );
+    let (low, high) = emission_hz(Frequency::from_hz(21_400_000).unwrap(), Mode::Am);
+    assert_eq!((low, high), (21_397_000, 21_403_000));
+}
+
+#[test]
+fn tx_policy_mode_segments_warn() {
+    assert_eq!(
+        policy_at(14_050_000, Mode::Usb),
+        TxVerdict::Warning(TxReason::ModeSegment)
+    );
+    assert_eq!(policy_at(14_050_000, Mode::Cw), TxVerdict::Clear);
+    // 30 m has no phone segment at all
+    assert_eq!(
+        policy_at(10_130_000, Mode::Usb),
+        TxVerdict::Warning(TxReason::ModeSegment)
+    );
+    assert!(!policy_at(10_130_000, Mode::Usb).is_denied());
+}
+
+#[test]
+fn tx_policy_override_and_inhibit() {
+    let mut policy = TxPolicy::new();
+    policy.set_operating(Frequency::from_hz(5_000_000).unwrap(), Mode::Usb);
+    policy.set_override(true);
+    assert!(policy.is_override());
+    assert_eq!(policy.verdict(), TxVerdict::Warning(TxReason::OutOfBand));
+
+    // The override never lifts the external inhibit
+    policy.set_external_inhibit(true);
+    assert_eq!(policy.verdict(), TxVerdict::Denied(TxReason::ExternalInhibit));
+    policy.set_external_inhibit(false);
+
+    for reason in [
+        TxReason::OutOfBand,
+        TxReason::BandEdge,
+        TxReason::ModeSegment,
+        TxReason::ExternalInhibit,
+        TxReason::Practice,
+    ] {
+        assert_eq!(TxReason::from_code(reason.code()), Some(reason));
+    }
+    assert_eq!(TxReason::from_code(0), None);
+    assert_eq!(TxVerdict::Clear.reason(), None);
+    assert_eq!(TxVerdict::Denied(TxReason::BandEdge).code(), 2);
+}
+
+// ============================================================================
+// Keyer Practice Tests
+// ============================================================================
+
+/// Send `text` through the keyer at `wpm` and decode it in a practice session
+fn practice_keyer(text: &str, wpm: u8) -> PracticeSession {
+    let mut keyer = Keyer::new(8000);
+    keyer.set_wpm(wpm);
+    keyer.queue_text(text);
+    let mut session = PracticeSession::new(wpm);
+    // Run on past the last character so the word space is decoded
+    for _ in 0..8000 * 60 {
+        keyer.process(PaddleState::default());
+        session.update(keyer.is_key_down(), 0.125);
+        if !keyer.is_sending_text() && !session.text().is_empty() && !keyer.is_key_down() {
+            for _ in 0..8000 {
+                session.update(false, 0.125);
+            }
+            break;
+        }
+    }
+    session
+}
+
+#[test]
+fn practice_decodes_keyer() {
+    let session = practice_keyer("CQ TEST", 20);
+    assert_eq!(session.text(), "CQ TEST ");
+
+    let report = session.report();
+    assert_eq!(report.wpm, 20);
+    assert_eq!(report.target_wpm, 20);
+    assert_eq!(report.elements, 14);
+    assert!(report.accuracy >= 98, "{report:?}");
+    assert_eq!(report.dah_ratio, 30);
+    assert_eq!(report.speed_error_percent(), 0);
+}
+
+#[test]
+fn practice_scores_sloppy_timing() {
+    // Hand-keyed "A" and "N" at 20 WPM (60 ms unit) with short dahs and
+    // long element spaces
+    let mut session = PracticeSession::new(20);
+    let run = |session: &mut PracticeSession, key: bool, ms: u32| {
+        let mut decoded = None;
+        for _ in 0..ms {
+            decoded = session.update(key, 1.0).or(decoded);
+        }
+        decoded
+    };
+    for (first, second) in [(60, 120), (120, 60)] {
+        run(&mut session, true, first);
+        run(&mut session, false, 90);
+        run(&mut session, true, second);
+        run(&mut session, false, 200);
+    }
+    assert_eq!(session.text(), "AN");
+
+    // Dahs two units long, element spaces 1.5 units
+    let report = session.report();
+    assert_eq!(report.elements, 4);
+    assert_eq!(report.dah_ratio, 20);
+    assert!((70..=85).contains(&report.accuracy), "{report:?}");
+
+    // Starting again clears the text and the score
+    session.reset();
+    assert_eq!(session.text(), "");
+    assert_eq!(session.report(), PracticeReport { target_wpm: 20, ..PracticeReport::default() });
+}
+
+#[test]
+fn practice_text_keeps_latest() {
+    let mut session = practice_keyer("EEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEEE TTTTTTTTTTTTTTTTTTTTTTTTTTTTTT", 30);
+    assert_eq!(session.text().len(), PRACTICE_TEXT_LEN);
+    assert!(session.text().ends_with(" TTTTTTTTTTTTTTTTTTTTTTTTTTTTTT "));
+    session.set_target_wpm(25);
+    assert_eq!(session.target_wpm(), 25);
+    assert!(session.report().speed_error_percent() > 0);
+}
+
+#[test]
+fn practice_mode_blocks_transmit() {
+    let mut ctrl = TxController::new();
+    ctrl.policy_mut()
+        .set_operating(Frequency::from_hz(7_030_000).unwrap(), Mode::Cw);
+    ctrl.set_qsk(true);
+    ctrl.policy_mut().set_practice(true);
+    ctrl.policy_mut().set_override(true);
+    assert!(ctrl.policy().is_practice());
+    assert_eq!(ctrl.verdict(), TxVerdict::Denied(TxReason::Practice));
+    ctrl.set_key(true);
+    assert_eq!(ctrl.update(0), TxAction::None);
+    assert_eq!(ctrl.state(), TxState::Rx);
+
+    ctrl.policy_mut().set_practice(false);
+    ctrl.update(0);
+    assert_eq!(ctrl.state(), TxState::SwitchingToTx);
+}
+
+// ============================================================================
+// PA Drive Tests
+// ============================================================================
+
+fn keyed_pa(power: u8) -> PaController {
+    let mut pa = PaController::new();
+    assert!(pa.set_carrier(Frequency::from_hz(7_074_000).unwrap()));
+    pa.apply(TxAction::SetPower(PowerLevel::from_percent(power)));
+    pa.apply(TxAction::EnableTrRelay);
+    pa.apply(TxAction::EnablePa);
+    pa
+}
+
+#[test]
+fn bridge_timing_follows_carrier() {
+    let timing = BridgeTiming::for_carrier(Frequency::from_hz(7_000_000).unwrap(), 5).unwrap();
+    // 170 MHz x 32 / 7 MHz
+    assert_eq!(timing.period(), 777);
+    assert_eq!(timing.half_period(), 388);
+    // 5 ns at 1.36 GHz
+    assert_eq!(timing.dead_time(), 6);
+
+    // Dead time is limited to a quarter cycle
+    let timing = BridgeTiming::for_carrier(Frequency::from_hz(21_000_000).unwrap(), 100).unwrap();
+    assert_eq!(timing.dead_time(), timing.period() / 16);
+}
+
+#[test]
+fn pa_drives_only_with_relay_and_enable() {
+    let mut pa = PaController::new();
+    assert!(pa.set_carrier(Frequency::from_hz(14_074_000).unwrap()));
+    assert_eq!(pa.apply(TxAction::SetPower(PowerLevel::MAX)), PaOutput::OFF);
+    assert_eq!(pa.apply(TxAction::EnableTrRelay), PaOutput::OFF);
+
+    let on = pa.apply(TxAction::EnablePa);
+    assert!(pa.is_driving());
+    assert_eq!(on.bridge, pa.bridge());
+    assert!(on.supply > 0);
+
+    assert_eq!(pa.apply(TxAction::DisablePa), PaOutput::OFF);
+    assert_eq!(pa.apply(TxAction::DisableTrRelay), PaOutput::OFF);
+    assert_eq!(pa.faults(), 0);
+}
+
+#[test]
+fn pa_interlocks_count_faults() {
+    // Enabling with the relay out is refused
+    let mut pa = PaController::new();
+    assert!(pa.set_carrier(Frequency::from_hz(7_074_000).unwrap()));
+    assert_eq!(pa.apply(TxAction::EnablePa), PaOutput::OFF);
+    assert_eq!(pa.faults(), 1);
+
+    // Dropping the relay while keyed stops the drive
+    let mut pa = keyed_pa(50);
+    assert!(pa.is_driving());
+    assert_eq!(pa.apply(TxAction::DisableTrRelay), PaOutput::OFF);
+    assert_eq!(pa.faults(), 1);
+    assert_eq!(pa.apply(TxAction::EnableTrRelay), PaOutput::OFF);
+}
+
+#[test]
+fn pa_supply_scales_with_power() {
+    let half = keyed_pa(50).output(1.0).supply;
+    let full = keyed_pa(100).output(1.0).supply;
+    let expected = u32::from(PowerLevel::from_percent(50).as_pwm_duty())
+        * u32::from(SUPPLY_PERIOD)
+        / 65535;
+    assert_eq!(u32::from(half), expected);
+    // Full power is capped
+    assert_eq!(
+        u32::from(full),
+        u32::from(MAX_SUPPLY_DUTY) * u32::from(SUPPLY_PERIOD) / 65535
+    );
+    assert_eq!(keyed_pa(0).output(1.0).supply, 0);
+}
+
+#[test]
+fn polar_mode_follows_envelope() {
+    let mut pa = keyed_pa(50);
+    let full = pa.output(1.0).supply;
+    assert_eq!(pa.output(0.25).supply, full, "constant mode ignores the envelope");
+
+    pa.set_mode(EnvelopeMode::Polar);
+    assert_eq!(pa.output(1.0).supply, full);
+    let quarter = pa.output(0.25).supply;
+    assert!((i32::from(quarter) - i32::from(full) / 4).abs() <= 1);
+    assert_eq!(pa.output(-1.0).supply, 0);
+    assert_eq!(pa.output(2.0).supply, full);
+}
+
+// ============================================================================
+// SWR Metering Tests
+// ============================================================================
+
+#[test]
+fn swr_calibration_interpolates() {
+    let cal = SwrCalibration::new(&[(100, 1.0), (200, 3.0), (400, 11.0)]).unwrap();
+    assert!((cal.watts(150) - 2.0).abs() < 1e-4);
+    assert!((cal.watts(300) - 7.0).abs() < 1e-4);
+    assert!((cal.watts(200) - 3.0).abs() < 1e-4);
+    // Below the first point scales to zero, above the last extends
+    assert!((cal.watts(50) - 0.5).abs() < 1e-4);
+    assert!(cal.watts(0).abs() < 1e-6);
+    assert!((cal.watts(500) - 15.0).abs() < 1e-4);
+    assert_eq!(cal.milliwatts(150), 2000);
+    assert_eq!(cal.milliwatts(u16::MAX), u16::MAX);
+}
+
+#[test]
+fn swr_calibration_validation() {
+    assert!(SwrCalibration::new(&[(100, 1.0)]).is_none());
+    assert!(SwrCalibration::new(&[(100, 1.0), (100, 2.0)]).is_none());
+    assert!(SwrCalibration::new(&[(100, 2.0), (200, 1.0)]).is_none());
+    assert!(SwrCalibration::new(&[(0, -1.0), (200, 1.0)]).is_none());
+    let too_many: Vec<(u16, f32)> = (0..=MAX_CAL_POINTS as u16)
+        .map(|i| (i * 10, f32::from(i)))
+        .collect();
+    assert!(SwrCalibration::new(&too_many).is_none());
+    assert!(SwrCalibration::new(&too_many[..MAX_CAL_POINTS]).is_some());
+    assert_eq!(SwrCalibration::default().points().len(), 6);
+}
+
+#[test]
+fn swr_meter_averages_samples() {
+    let cal = SwrCalibration::new(&[(0, 0.0), (1000, 10.0)]).unwrap();
+    let mut meter = SwrMeter::new(cal);
+    for n in 0..SWR_AVERAGE_SAMPLES - 1 {
+        // Alternate around 500 counts forward, 100 reflected
+        let wobble = if n % 2 == 0 { 20 } else { 0 };
+        assert!(meter.push(490 + wobble, 100).is_none());
+    }
+    assert!(meter.last().is_none());
+    let reading = meter.push(500, 100).unwrap();
+    assert!((i32::from(reading.forward) - 5000).abs() <= 10);
+    assert_eq!(reading.reflected, 1000);
+    assert_eq!(meter.last(), Some(reading));
+    assert!((meter.forward_watts() - 5.0).abs() < 0.01);
+    assert!((meter.reflected_watts() - 1.0).abs() < 0.01);
+
+    // The next average starts fresh
+    assert!(meter.push(500, 100).is_none());
+    meter.reset();
+    assert!(meter.last().is_none());
+}
+
+#[test]
+fn swr_meter_ratio_uses_power() {
+    let cal = SwrCalibration::new(&[(0, 0.0), (1000, 10.0)]).unwrap();
+    let mut meter = SwrMeter::new(cal);
+    // 4 W forward, 1 W reflected: rho 0.5, SWR 3:1
+    let reading = (0..SWR_AVERAGE_SAMPLES)
+        .find_map(|_| meter.push(400, 100))
+        .unwrap();
+    assert!((reading.swr_ratio() - 3.0).abs() < 0.01);
+}
+
+#[test]
+fn swr_meter_feeds_tx_protection() {
+    let mut ctrl = TxController::new();
+    ctrl.set_ptt(true);
+    ctrl.update(0);
+    ctrl.update(10000);
+    assert!(ctrl.is_transmitting());
+
+    // Open antenna: almost all the power comes back
+    let mut meter = SwrMeter::default();
+    let reading = (0..SWR_AVERAGE_SAMPLES)
+        .find_map(|_| meter.push(1500, 1400))
+        .unwrap();
+    ctrl.update_swr(reading);
+    assert_eq!(ctrl.state(), TxState::Inhibited);
+    assert_eq!(ctrl.last_swr(), Some(reading));
+}
+
+// ============================================================================
+// Relay Sequencing Tests
+// ============================================================================
+
+const TEST_RELAY_TIMING: RelayTiming = RelayTiming {
+    tr_settle_us: 5000,
+    lpf_settle_us: 3000,
+};
+
+#[test]
+fn relay_holds_pa_until_settled() {
+    let mut relays = RelaySequencer::new(TEST_RELAY_TIMING);
+    assert_eq!(relays.apply(TxAction::EnableTrRelay), TxAction::EnableTrRelay);
+    assert!(relays.outputs().tr);
+    assert!(!relays.is_settled());
+
+    // The controller's enable arrives early and is held
+    assert_eq!(relays.apply(TxAction::EnablePa), TxAction::None);
+    assert_eq!(relays.update(4000, RelaySense::default()), TxAction::None);
+    assert!(!relays.is_pa_enabled());
+    assert_eq!(relays.update(1000, RelaySense::default()), TxAction::EnablePa);
+    assert!(relays.is_pa_enabled());
+    assert_eq!(relays.update(1000, RelaySense::default()), TxAction::None);
+
+    // Once settled an enable passes straight through
+    assert_eq!(relays.apply(TxAction::DisablePa), TxAction::DisablePa);
+    assert_eq!(relays.apply(TxAction::EnablePa), TxAction::EnablePa);
+
+    // Other actions pass through untouched
+    let power = TxAction::SetPower(PowerLevel::from_percent(40));
+    assert_eq!(relays.apply(power), power);
+}
+
+#[test]
+fn relay_never_enables_pa_in_receive() {
+    let mut relays = RelaySequencer::default();
+    assert_eq!(relays.apply(TxAction::EnablePa), TxAction::None);
+    assert_eq!(relays.update(100_000, RelaySense::default()), TxAction::None);
+
+    // Dropping the relay forgets a held enable
+    relays.apply(TxAction::EnableTrRelay);
+    relays.apply(TxAction::DisableTrRelay);
+    assert_eq!(relays.update(100_000, RelaySense::default()), TxAction::None);
+    assert!(!relays.is_pa_enabled());
+}
+
+#[test]
+fn relay_band_change_waits_for_receive() {
+    let mut relays = RelaySequencer::new(TEST_RELAY_TIMING);
+    assert!(relays.select_band(Band::M20));
+    assert_eq!(relays.outputs().lpf, Band::M20.lpf_index());
+    assert!(!relays.is_settled());
+
+    // A PA enable waits for the filter as well as the T/R relay
+    relays.update(3000, RelaySense::default());
+    relays.apply(TxAction::EnableTrRelay);
+    relays.apply(TxAction::EnablePa);
+    assert_eq!(relays.update(5000, RelaySense::default()), TxAction::EnablePa);
+
+    // No hot switching: the change lands after the over
+    assert!(!relays.select_band(Band::M80));
+    assert_eq!(relays.outputs().lpf, Band::M20.lpf_index());
+    assert_eq!(relays.pending_lpf(), Some(0));
+    relays.apply(TxAction::DisablePa);
+    relays.apply(TxAction::DisableTrRelay);
+    assert_eq!(relays.outputs(), RelayOutputs { tr: false, lpf: 0 });
+    assert_eq!(relays.pending_lpf(), None);
+
+    // Out-of-range banks are clamped
+    relays.select_lpf(9);
+    assert_eq!(relays.outputs().lpf, 4);
+}
+
+#[test]
+fn relay_detects_stuck_tr_relay() {
+    let mut relays = RelaySequencer::new(TEST_RELAY_TIMING);
+    relays.apply(TxAction::EnableTrRelay);
+    relays.apply(TxAction::EnablePa);
+    let open = RelaySense {
+        tr: Some(false),
+        lpf: None,
+    };
+    // Bounce during the settle time is ignored
+    assert_eq!(relays.update(1000, open), TxAction::None);
+    assert_eq!(relays.fault(), None);
+    assert_eq!(relays.update(4000, open), TxAction::None);
+    assert_eq!(relays.fault(), Some(RelayFault::TrStuckOpen));
+    assert!(!relays.is_pa_enabled());
+
+    // The fault latches until cleared
+    let closed = RelaySense {
+        tr: Some(true),
+        lpf: None,
+    };
+    assert_eq!(relays.update(1000, closed), TxAction::None);
+    assert_eq!(relays.apply(TxAction::EnablePa), TxAction::None);
+    relays.clear_fault();
+    assert_eq!(relays.update(1000, closed), TxAction::EnablePa);
+    assert_eq!(relays.fault_count(), 1);
+
+    // Welded contacts after key-up stop the next over
+    relays.apply(TxAction::DisablePa);
+    relays.apply(TxAction::DisableTrRelay);
+    relays.update(5000, closed);
+    assert_eq!(relays.fault(), Some(RelayFault::TrStuckClosed));
+    assert_eq!(relays.fault_count(), 2);
+}
+
+#[test]
+fn relay_fault_stops_driving_pa() {
+    let mut relays = RelaySequencer::new(TEST_RELAY_TIMING);
+    relays.apply(TxAction::EnableTrRelay);
+    relays.apply(TxAction::EnablePa);
+    assert_eq!(relays.update(5000, RelaySense::default()), TxAction::EnablePa);
+
+    let wrong_filter = RelaySense {
+        tr: Some(true),
+        lpf: Some(2),
+    };
+    assert_eq!(relays.update(1000, wrong_filter), TxAction::DisablePa);
+    assert_eq!(
+        relays.fault(),
+        Some(RelayFault::LpfMismatch {
+            wanted: 0,
+            sensed: 2
+        })
+    );
+}
+
+#[test]
+fn relay_shift_word_layout() {
+    let rx = RelayOutputs { tr: false, lpf: 3 };
+    assert_eq!(rx.as_shift_word(), 0b0000_1000);
+    let tx = RelayOutputs { tr: true, lpf: 0 };
+    assert_eq!(tx.as_shift_word(), 1 << TR_RELAY_BIT | 1);
+}
+
+// ============================================================================
+// DTMF Remote Control Tests
+// ============================================================================
+
+/// Key a sequence of digits 100 ms apart from `start_ms`, returning the events
+fn key_dtmf(remote: &mut DtmfRemote, digits: &str, start_ms: u32) -> Vec<RadioEvent> {
+    digits
+        .chars()
+        .zip((start_ms..).step_by(100))
+        .filter_map(|(digit, now_ms)| remote.push_digit(digit, now_ms))
+        .collect()
+}
+
+#[test]
+fn dtmf_remote_runs_mapped_commands() {
+    let mut remote = DtmfRemote::new();
+    remote.load_defaults();
+    assert!(remote.set_access_code("42"));
+
+    // Nothing until enabled
+    assert!(key_dtmf(&mut remote, "421#", 0).is_empty());
+    remote.set_enabled(true);
+
+    let events = key_dtmf(&mut remote, "421#429#", 0);
+    assert!(matches!(events[..], [RadioEvent::NextMode, RadioEvent::Tune(1)]));
+
+    // Wrong access code, unknown command, no access code at all
+    assert!(key_dtmf(&mut remote, "431#428#1#", 1000).is_empty());
+
+    // `*` starts over
+    let events = key_dtmf(&mut remote, "4*423#", 2000);
+    assert!(matches!(events[..], [RadioEvent::CycleAgc]));
+}
+
+#[test]
+fn dtmf_remote_entry_times_out() {
+    let mut remote = DtmfRemote::new();
+    remote.load_defaults();
+    remote.set_enabled(true);
+
+    assert!(remote.push_digit('1', 0).is_none());
+    assert_eq!(remote.entry(), "1");
+    // After a long pause the `1` is forgotten and `4` stands alone
+    assert!(remote.push_digit('4', DIGIT_TIMEOUT_MS + 1).is_none());
+    assert!(matches!(
+        remote.push_digit('#', DIGIT_TIMEOUT_MS + 100),
+        Some(RadioEvent::ToggleNb)
+    ));
+
+    // An overlong entry is rejected whole
+    let long = "1".repeat(3 * MAX_CODE_LEN);
+    assert!(key_dtmf(&mut remote, &(long + "#"), 20_000).is_empty());
+    assert_eq!(remote.entry(), "");
+}
+
+#[test]
+fn dtmf_remote_map_is_configurable() {
+    let mut remote = DtmfRemote::default();
+    assert_eq!(remote.command_count(), 0);
+    assert!(remote.set_command("8A", RadioEvent::ClearRit));
+    assert!(remote.set_command("8A", RadioEvent::ToggleRit));
+    assert_eq!(remote.command_count(), 1);
+    assert!(matches!(remote.command("8A"), Some(RadioEvent::ToggleRit)));
+
+    // Codes must be DTMF digits other than `*` and `#`
+    assert!(!remote.set_command("", RadioEvent::NextMode));
+    assert!(!remote.set_command("1#", RadioEvent::NextMode));
+    assert!(!remote.set_command("12x", RadioEvent::NextMode));
+    assert!(!remote.set_access_code("123456789"));
+    assert!(remote.set_access_code(""));
+
+    remote.set_enabled(true);
+    assert!(matches!(key_dtmf(&mut remote, "8A#", 0)[..], [RadioEvent::ToggleRit]));
+    assert!(remote.remove_command("8A"));
+    assert!(!remote.remove_command("8A"));
+
+    for i in 0..MAX_COMMANDS {
+        assert!(remote.set_command(&format!("{i}"), RadioEvent::NextStep));
+    }
+    assert!(!remote.set_command("99", RadioEvent::NextStep));
+    remote.clear_commands();
+    assert_eq!(remote.command_count(), 0);
+}
+
+#[test]
+fn dtmf_mode_codes() {
+    for mode in [DtmfMode::Off, DtmfMode::Decode, DtmfMode::Remote] {
+        assert_eq!(DtmfMode::from_code(mode.code()), Some(mode));
+    }
+    assert_eq!(DtmfMode::from_code(3), None);
+    assert_eq!(DtmfMode::default(), DtmfMode::Off);
+    assert!(!DtmfMode::Off.decodes());
+    assert!(DtmfMode::Remote.decodes());
+}
@@ -0,0 +1,180 @@
+//! Tests for the remote head framing, messages and radio-side session
+
+use heapless::{String, Vec};
+use sdr_firmware::clock::DateTime;
+use sdr_firmware::protocol::remote_head::{
+    crc8, FrameDecoder, HeadMessage, RadioLink, RadioMessage, RawFrame, RemoteAction,
+    RemoteStatus, FRAME_START, PROTOCOL_VERSION,
+};
+use sdr_firmware::radio::state::RadioState;
+use sdr_firmware::types::{Frequency, Mode, TuningStep, TxRxState};
+
+fn decode_all(bytes: &[u8]) -> std::vec::Vec<RawFrame> {
+    let mut decoder = FrameDecoder::new();
+    bytes.iter().filter_map(|&b| decoder.feed(b)).collect()
+}
+
+fn status() -> RemoteStatus {
+    let state = RadioState::new(Frequency::from_hz(14_074_000).unwrap())
+        .with_mode(Mode::Usb)
+        .with_step(TuningStep::Hz100)
+        .with_txrx(TxRxState::Tx);
+    RemoteStatus::new(&state, 57, 1.5)
+        .with_clock(DateTime::new(2024, 6, 1, 12, 34, 0), true)
+}
+
+// ============================================================================
+// Framing Tests
+// ============================================================================
+
+#[test]
+fn crc8_known_value() {
+    // CRC-8/SMBUS check value
+    assert_eq!(crc8(b"123456789"), 0xF4);
+}
+
+#[test]
+fn frame_round_trip() {
+    let frame = HeadMessage::Hello(PROTOCOL_VERSION).encode();
+    assert_eq!(frame[0], FRAME_START);
+    assert_eq!(frame.len(), 5);
+
+    let frames = decode_all(&frame);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(HeadMessage::decode(&frames[0]), Some(HeadMessage::Hello(PROTOCOL_VERSION)));
+}
+
+#[test]
+fn decoder_resyncs_after_noise_and_bad_crc() {
+    let good = HeadMessage::RequestStatus.encode();
+    let mut bad = HeadMessage::Action(RemoteAction::NextStep).encode();
+    let last = bad.len() - 1;
+    bad[last] ^= 0xFF;
+
+    let mut stream = vec![0x00, 0x13, 0x37];
+    stream.extend_from_slice(&bad);
+    stream.extend_from_slice(&good);
+
+    let mut decoder = FrameDecoder::new();
+    let frames: std::vec::Vec<_> = stream.iter().filter_map(|&b| decoder.feed(b)).collect();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(HeadMessage::decode(&frames[0]), Some(HeadMessage::RequestStatus));
+    assert_eq!(decoder.errors(), 1);
+}
+
+// ============================================================================
+// Message Tests
+// ============================================================================
+
+#[test]
+fn actions_round_trip() {
+    let mut name = String::new();
+    name.push_str("Calibrate").unwrap();
+    let actions = [
+        RemoteAction::Tune(-12),
+        RemoteAction::SetFrequency(Frequency::from_hz(7_030_000).unwrap()),
+        RemoteAction::SetMode(Mode::CwR),
+        RemoteAction::NextStep,
+        RemoteAction::TogglePtt,
+        RemoteAction::Execute(name),
+        RemoteAction::PlayMessage(3),
+        RemoteAction::AbortMessage,
+        RemoteAction::RecallMemory(42),
+    ];
+    for action in actions {
+        let frames = decode_all(&HeadMessage::Action(action.clone()).encode());
+        assert_eq!(HeadMessage::decode(&frames[0]), Some(HeadMessage::Action(action)));
+    }
+}
+
+#[test]
+fn status_round_trip() {
+    let status = status();
+    let frame = RadioMessage::Status(status).encode();
+    let frames = decode_all(&frame);
+    let Some(RadioMessage::Status(decoded)) = RadioMessage::decode(&frames[0]) else {
+        panic!("status not decoded");
+    };
+    assert_eq!(decoded, status);
+
+    let state = decoded.radio_state();
+    assert_eq!(state.frequency().as_hz(), 14_074_000);
+    assert_eq!(state.step(), TuningStep::Hz100);
+    assert!(state.is_transmitting());
+}
+
+#[test]
+fn status_without_clock() {
+    let status = status().with_clock(None, false);
+    let frames = decode_all(&RadioMessage::Status(status).encode());
+    assert_eq!(RadioMessage::decode(&frames[0]), Some(RadioMessage::Status(status)));
+}
+
+// ============================================================================
+// Radio Link Tests
+// ============================================================================
+
+fn feed_link(link: &mut RadioLink, bytes: &[u8], reply: &mut Vec<u8, 128>) -> Option<RemoteAction> {
+    let status = status();
+    bytes.iter().fold(None, |found, &b| link.feed(b, &status, reply).or(found))
+}
+
+#[test]
+fn link_hello_sends_welcome_and_status() {
+    let mut link = RadioLink::new();
+    let mut reply = Vec::new();
+    feed_link(&mut link, &HeadMessage::Hello(PROTOCOL_VERSION).encode(), &mut reply);
+    assert!(link.is_connected());
+
+    let messages: std::vec::Vec<_> = decode_all(&reply)
+        .iter()
+        .filter_map(RadioMessage::decode)
+        .collect();
+    assert_eq!(
+        messages,
+        vec![RadioMessage::Welcome(PROTOCOL_VERSION), RadioMessage::Status(status())]
+    );
+}
+
+#[test]
+fn link_ignores_actions_before_hello() {
+    let mut link = RadioLink::new();
+    let mut reply = Vec::new();
+    let action = HeadMessage::Action(RemoteAction::Tune(1)).encode();
+    assert_eq!(feed_link(&mut link, &action, &mut reply), None);
+
+    feed_link(&mut link, &HeadMessage::Hello(PROTOCOL_VERSION).encode(), &mut reply);
+    assert_eq!(feed_link(&mut link, &action, &mut reply), Some(RemoteAction::Tune(1)));
+}
+
+#[test]
+fn link_rejects_incompatible_version() {
+    let mut link = RadioLink::new();
+    let mut reply = Vec::new();
+    feed_link(&mut link, &HeadMessage::Hello(PROTOCOL_VERSION + 1).encode(), &mut reply);
+    assert!(!link.is_connected());
+    // Still tells the head which version the radio speaks
+    assert_eq!(
+        RadioMessage::decode(&decode_all(&reply)[0]),
+        Some(RadioMessage::Welcome(PROTOCOL_VERSION))
+    );
+}
+
+#[test]
+fn link_sends_status_on_change_and_refresh() {
+    let mut link = RadioLink::new();
+    let mut reply = Vec::new();
+    let status = status();
+    assert!(link.update(&status, 10).is_none());
+
+    feed_link(&mut link, &HeadMessage::Hello(PROTOCOL_VERSION).encode(), &mut reply);
+    assert!(link.update(&status, 10).is_none());
+
+    let changed = RemoteStatus { s_meter: 60, ..status };
+    assert!(link.update(&changed, 10).is_some());
+    assert!(link.update(&changed, 500).is_none());
+    assert!(link.update(&changed, 500).is_some());
+
+    link.disconnect();
+    assert!(link.update(&changed, 5000).is_none());
+}
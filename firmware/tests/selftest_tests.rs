@@ -0,0 +1,185 @@
+//! Tests for power-on self test criteria and reporting
+
+use sdr_firmware::config::{POST_ADC_OFFSET_TOLERANCE, POST_PA_IDLE_CURRENT_MAX_MA};
+use sdr_firmware::radio::annunciator::Annunciation;
+use sdr_firmware::radio::loopback::{
+    LoopbackCheck, LoopbackTest, GAIN_TOLERANCE_DB, MIN_SIDEBAND_REJECTION_DB, NOMINAL_GAIN_DB,
+};
+use sdr_firmware::selftest::{
+    check_adc_offset, check_pa_current, check_pll_status, PostItem, PostReport, PostResult,
+};
+
+/// Report with every check passing
+fn passing_report() -> PostReport {
+    let mut report = PostReport::new();
+    report.record_probe(PostItem::Si5351, true);
+    report.record_probe(PostItem::Display, true);
+    report.record_pll_status(0x00);
+    report.record_adc_offset(PostItem::AdcI, 2040);
+    report.record_adc_offset(PostItem::AdcQ, 2060);
+    report.record_pa_current(3);
+    report
+}
+
+// ============================================================================
+// Criteria Tests
+// ============================================================================
+
+#[test]
+fn pll_status_locked() {
+    assert_eq!(check_pll_status(0x00), PostResult::Pass);
+    // Loss of lock on the unused PLL B is ignored
+    assert_eq!(check_pll_status(0x40), PostResult::Pass);
+}
+
+#[test]
+fn pll_status_faults() {
+    assert_eq!(check_pll_status(0x80), PostResult::Fail);
+    assert_eq!(check_pll_status(0x20), PostResult::Fail);
+    assert_eq!(check_pll_status(0x08), PostResult::Fail);
+}
+
+#[test]
+fn adc_offset_window() {
+    assert_eq!(check_adc_offset(2048), PostResult::Pass);
+    assert_eq!(check_adc_offset(2048 + POST_ADC_OFFSET_TOLERANCE), PostResult::Pass);
+    assert_eq!(check_adc_offset(2048 - POST_ADC_OFFSET_TOLERANCE), PostResult::Pass);
+    assert_eq!(check_adc_offset(2049 + POST_ADC_OFFSET_TOLERANCE), PostResult::Fail);
+    // Shorted or open input
+    assert_eq!(check_adc_offset(0), PostResult::Fail);
+    assert_eq!(check_adc_offset(4095), PostResult::Fail);
+}
+
+#[test]
+fn pa_idle_current() {
+    assert_eq!(check_pa_current(0), PostResult::Pass);
+    assert_eq!(check_pa_current(POST_PA_IDLE_CURRENT_MAX_MA), PostResult::Pass);
+    assert_eq!(check_pa_current(POST_PA_IDLE_CURRENT_MAX_MA + 1), PostResult::Fail);
+}
+
+// ============================================================================
+// Report Tests
+// ============================================================================
+
+#[test]
+fn item_indices_round_trip() {
+    for (i, item) in PostItem::ALL.iter().enumerate() {
+        assert_eq!(item.index(), i);
+        assert_eq!(PostItem::from_index(i), Some(*item));
+        assert!(item.label().len() <= 6);
+    }
+    assert_eq!(PostItem::from_index(PostItem::COUNT), None);
+}
+
+#[test]
+fn empty_report_has_not_run() {
+    let report = PostReport::new();
+    assert!(report.iter().all(|(_, r)| r == PostResult::NotRun));
+    assert!(!report.passed());
+    assert!(!report.tx_allowed());
+    assert_eq!(report.failures().count(), 0);
+}
+
+#[test]
+fn all_checks_pass() {
+    let report = passing_report();
+    assert!(report.passed());
+    assert!(report.tx_allowed());
+    assert_eq!(report.value(PostItem::AdcQ), 2060);
+}
+
+#[test]
+fn failures_are_listed() {
+    let mut report = passing_report();
+    report.record_probe(PostItem::Display, false);
+    report.record_adc_offset(PostItem::AdcQ, 10);
+
+    let failed: Vec<_> = report.failures().collect();
+    assert_eq!(failed, vec![PostItem::Display, PostItem::AdcQ]);
+    assert!(!report.passed());
+    // Receive-side faults still allow transmit
+    assert!(report.tx_allowed());
+}
+
+#[test]
+fn tx_inhibited_on_pa_current() {
+    let mut report = passing_report();
+    report.record_pa_current(450);
+    assert!(!report.tx_allowed());
+    assert_eq!(report.value(PostItem::PaCurrent), 450);
+}
+
+#[test]
+fn tx_inhibited_on_unlocked_pll() {
+    let mut report = passing_report();
+    report.record_pll_status(0x20);
+    assert!(!report.tx_allowed());
+}
+
+#[test]
+fn post_annunciation() {
+    let mut report = passing_report();
+    assert_eq!(Annunciation::from_post(&report), Annunciation::Ready);
+    assert_eq!(Annunciation::Ready.text(), "OK");
+
+    // Headless builds never run the display check
+    report.record(PostItem::Display, PostResult::NotRun, 0);
+    assert_eq!(Annunciation::from_post(&report), Annunciation::Ready);
+
+    report.record_adc_offset(PostItem::AdcI, 10);
+    assert_eq!(Annunciation::from_post(&report), Annunciation::Error);
+}
+
+// ============================================================================
+// Loopback Tests
+// ============================================================================
+
+#[test]
+fn loopback_check_indices_round_trip() {
+    for check in LoopbackCheck::ALL {
+        assert_eq!(LoopbackCheck::from_index(check.index()), Some(check));
+    }
+    assert_eq!(LoopbackCheck::from_index(LoopbackCheck::COUNT), None);
+}
+
+#[test]
+fn loopback_passes_through_ssb_chain() {
+    let mut test = LoopbackTest::new();
+    assert_eq!(test.report(), None);
+    let report = test.run_to_completion();
+    assert!(report.passed(), "{report:?}");
+    assert_eq!(report.failures(), 0);
+    assert!(!test.is_active());
+    assert_eq!(test.report(), Some(report));
+
+    let gain = report.value_db(LoopbackCheck::Gain);
+    assert!((gain - NOMINAL_GAIN_DB).abs() < GAIN_TOLERANCE_DB / 2.0);
+    assert!(report.value_db(LoopbackCheck::SidebandRejection) > MIN_SIDEBAND_REJECTION_DB);
+    assert!(report.value_db(LoopbackCheck::FilterShape).abs() < 0.5);
+}
+
+#[test]
+fn loopback_runs_in_slices() {
+    let mut test = LoopbackTest::new();
+    test.start();
+    assert!(test.is_active());
+    let mut slices = 0;
+    let report = loop {
+        if let Some(report) = test.run(480) {
+            break report;
+        }
+        slices += 1;
+        assert!(test.progress().0 < test.progress().1);
+    };
+    // 50 ms settling and 100 ms measurement per tone, 10 ms slices
+    assert!(slices > 7 * 15 - 2);
+
+    // Same result as at boot; a restart discards the report until done
+    assert_eq!(LoopbackTest::new().run_to_completion(), report);
+    test.start();
+    assert_eq!(test.report(), None);
+    assert_eq!(test.run(100), None);
+    test.stop();
+    assert_eq!(test.run(usize::MAX), None);
+    assert_eq!(test.report(), None);
+}
@@ -0,0 +1,416 @@
+//! Tests for persistent settings and the startup policy
+
+use sdr_firmware::dsp::equalizer::{EqCurve, EqPath, EqPresets, EQ_BANDS};
+use sdr_firmware::dsp::si5351_calc::PpmCorrection;
+use sdr_firmware::radio::state::{AgcMode, RadioState};
+use sdr_firmware::settings::{
+    OperatorConfig, Settings, StartupPolicy, StartupState, SETTINGS_LEN,
+};
+use sdr_firmware::storage::{BlockDevice, FileKind, FileStore};
+use sdr_dsp_core::agc::SmeterCalibration;
+use sdr_dsp_core::iq_balance::IqCalibration;
+use sdr_firmware::types::{Band, Frequency, IqOrientation, Mode, PowerLevel, TuningStep};
+
+/// Encoded length of the per-band IQ balance calibrations at the end of a record
+const IQ_CAL_LEN: usize = 4 * Band::COUNT;
+
+/// Encoded length of the RX and TX equalizer curves, before the IQ balance
+const EQ_LEN: usize = 2 * EqPresets::COUNT * EQ_BANDS;
+
+/// Offset of the IQ balance calibrations
+const IQ_CAL_START: usize = SETTINGS_LEN - 1 - IQ_CAL_LEN;
+
+/// Offset of the operator configuration (11 bytes, then the curves)
+const CONFIG_START: usize = IQ_CAL_START - EQ_LEN - 11;
+
+/// RAM flash emulator (NOR semantics)
+struct RamFlash {
+    data: Vec<u8>,
+}
+
+impl RamFlash {
+    fn new() -> Self {
+        Self {
+            data: vec![0xFF; 4096 * 8],
+        }
+    }
+}
+
+impl BlockDevice for RamFlash {
+    type Error = ();
+
+    fn block_size(&self) -> u32 {
+        4096
+    }
+
+    fn block_count(&self) -> u32 {
+        8
+    }
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), ()> {
+        let start = addr as usize;
+        buf.copy_from_slice(self.data.get(start..start + buf.len()).ok_or(())?);
+        Ok(())
+    }
+
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), ()> {
+        let start = addr as usize;
+        let dst = self.data.get_mut(start..start + data.len()).ok_or(())?;
+        for (d, s) in dst.iter_mut().zip(data) {
+            *d &= *s;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, block: u32) -> Result<(), ()> {
+        let start = block as usize * 4096;
+        self.data.get_mut(start..start + 4096).ok_or(())?.fill(0xFF);
+        Ok(())
+    }
+}
+
+fn state(hz: u32, mode: Mode) -> RadioState {
+    RadioState::new(Frequency::from_hz(hz).unwrap())
+        .with_mode(mode)
+        .with_step(TuningStep::Hz100)
+        .with_power(PowerLevel::from_percent(80))
+}
+
+// ============================================================================
+// Startup Policy Tests
+// ============================================================================
+
+#[test]
+fn defaults_restore_last_used() {
+    let settings = Settings::new();
+    assert_eq!(settings.policy(), StartupPolicy::LastUsed);
+    assert_eq!(settings.startup_state(), &StartupState::new());
+    assert_eq!(settings.startup_state().frequency.as_hz(), 7_074_000);
+}
+
+#[test]
+fn last_used_policy_restores_last_state() {
+    let mut settings = Settings::new();
+    let radio = state(14_025_000, Mode::Cw);
+    assert!(settings.update_last(&radio));
+    assert!(!settings.update_last(&radio));
+
+    let restored = settings.startup_state().radio_state();
+    assert_eq!(restored.frequency(), radio.frequency());
+    assert_eq!(restored.mode(), Mode::Cw);
+    assert_eq!(restored.step(), TuningStep::Hz100);
+    assert_eq!(restored.power().as_percent(), 80);
+}
+
+#[test]
+fn fixed_policy_ignores_last_state() {
+    let mut settings = Settings::new();
+    settings.set_fixed(StartupState::from_radio(&state(3_573_000, Mode::Usb)));
+    settings.set_policy(StartupPolicy::Fixed);
+    settings.update_last(&state(21_074_000, Mode::Lsb));
+
+    let boot = settings.startup_state();
+    assert_eq!(boot.frequency.as_hz(), 3_573_000);
+    assert_eq!(boot.mode, Mode::Usb);
+    assert_eq!(settings.last().frequency.as_hz(), 21_074_000);
+}
+
+// ============================================================================
+// Record Encoding Tests
+// ============================================================================
+
+#[test]
+fn record_round_trip() {
+    let mut settings = Settings::new();
+    settings.set_policy(StartupPolicy::Fixed);
+    settings.set_fixed(StartupState::from_radio(&state(10_136_000, Mode::CwR)));
+    settings.update_last(&state(18_100_000, Mode::Am));
+    settings.set_iq_orientation(IqOrientation::Swapped);
+    settings.set_smeter_calibration(Band::M20, SmeterCalibration::new(-125, 1100));
+    settings.set_xtal_correction(PpmCorrection::from_ppb(-12_345).unwrap());
+
+    let bytes = settings.to_bytes();
+    assert_eq!(bytes.len(), SETTINGS_LEN);
+    assert_eq!(Settings::from_bytes(&bytes), Some(settings));
+}
+
+#[test]
+fn version1_record_loads_with_normal_iq() {
+    let mut settings = Settings::new();
+    settings.update_last(&state(14_060_000, Mode::Cw));
+    settings.set_iq_orientation(IqOrientation::InvertQ);
+
+    // Version 1 layout: magic, version, policy and two states, no IQ byte
+    let mut v1 = settings.to_bytes()[..4 + 2 + 2 * 7].to_vec();
+    v1[4] = 1;
+    let sum = v1.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v1.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v1).unwrap();
+    assert_eq!(loaded.iq_orientation(), IqOrientation::Normal);
+    assert_eq!(loaded.last(), settings.last());
+}
+
+#[test]
+fn version2_record_loads_with_nominal_smeter_calibration() {
+    let mut settings = Settings::new();
+    settings.set_iq_orientation(IqOrientation::Swapped);
+    settings.set_smeter_calibration(Band::M40, SmeterCalibration::new(60, 900));
+
+    // Version 2 layout: IQ byte, no calibration table
+    let mut v2 = settings.to_bytes()[..4 + 2 + 2 * 7 + 1].to_vec();
+    v2[4] = 2;
+    let sum = v2.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v2.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v2).unwrap();
+    assert_eq!(loaded.iq_orientation(), IqOrientation::Swapped);
+    assert_eq!(loaded.smeter_calibration(Band::M40), SmeterCalibration::IDENTITY);
+    assert_eq!(
+        settings.smeter_calibration(Band::M40).offset_tenths_db(),
+        60,
+        "calibration is kept per band"
+    );
+    assert_eq!(settings.smeter_calibration(Band::M80), SmeterCalibration::IDENTITY);
+}
+
+#[test]
+fn version3_record_loads_with_no_xtal_correction() {
+    let mut settings = Settings::new();
+    settings.set_smeter_calibration(Band::M40, SmeterCalibration::new(60, 900));
+    settings.set_xtal_correction(PpmCorrection::from_ppb(8_000).unwrap());
+
+    // Version 3 layout: calibration table, no crystal correction
+    let mut v3 = settings.to_bytes()[..CONFIG_START - 4].to_vec();
+    v3[4] = 3;
+    let sum = v3.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v3.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v3).unwrap();
+    assert_eq!(loaded.xtal_correction(), PpmCorrection::NONE);
+    assert_eq!(
+        loaded.smeter_calibration(Band::M40),
+        settings.smeter_calibration(Band::M40)
+    );
+}
+
+#[test]
+fn out_of_range_xtal_correction_rejected() {
+    let mut bytes = Settings::new().to_bytes();
+    bytes[CONFIG_START - 4..CONFIG_START].copy_from_slice(&1_000_000i32.to_le_bytes());
+    let sum = bytes[..SETTINGS_LEN - 1]
+        .iter()
+        .fold(0u8, |acc, &b| acc.wrapping_add(b));
+    bytes[SETTINGS_LEN - 1] = sum.wrapping_neg();
+    assert_eq!(Settings::from_bytes(&bytes), None);
+}
+
+#[test]
+fn operator_config_round_trips() {
+    let mut settings = Settings::new();
+    *settings.config_mut() = OperatorConfig {
+        keyer_wpm: 28,
+        sidetone_hz: 550,
+        agc: AgcMode::Slow,
+        vox: true,
+        vox_gain: 60,
+        vox_delay_ms: 1200,
+        brightness: 35,
+        rx_eq: true,
+        tx_eq: false,
+    };
+
+    let loaded = Settings::from_bytes(&settings.to_bytes()).unwrap();
+    assert_eq!(loaded.config(), settings.config());
+    assert_eq!(loaded, settings);
+}
+
+#[test]
+fn version4_record_loads_with_default_config() {
+    let mut settings = Settings::new();
+    settings.set_xtal_correction(PpmCorrection::from_ppb(-3_000).unwrap());
+    settings.config_mut().keyer_wpm = 35;
+
+    // Version 4 layout: crystal correction, no operator configuration
+    let mut v4 = settings.to_bytes()[..CONFIG_START].to_vec();
+    v4[4] = 4;
+    let sum = v4.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v4.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v4).unwrap();
+    assert_eq!(loaded.xtal_correction(), settings.xtal_correction());
+    assert_eq!(*loaded.config(), OperatorConfig::default());
+}
+
+#[test]
+fn equalizer_curves_round_trip() {
+    let mut settings = Settings::new();
+    assert_eq!(
+        *settings.eq_presets(EqPath::Rx),
+        EqPresets::defaults(EqPath::Rx)
+    );
+    assert_eq!(
+        settings.eq_presets(EqPath::Tx).get(Mode::Lsb),
+        EqCurve::tx_default(Mode::Usb)
+    );
+
+    settings.set_eq_curve(EqPath::Tx, Mode::Usb, EqCurve::new([-12, -6, 0, 6, 12]));
+    settings.set_eq_curve(EqPath::Rx, Mode::Am, EqCurve::new([3, 2, 1, 0, -1]));
+    let loaded = Settings::from_bytes(&settings.to_bytes()).unwrap();
+    assert_eq!(
+        loaded.eq_presets(EqPath::Tx).get(Mode::Lsb).gains_db(),
+        [-12, -6, 0, 6, 12],
+        "LSB and USB share a curve"
+    );
+    assert_eq!(
+        loaded.eq_presets(EqPath::Rx).get(Mode::Am).gains_db(),
+        [3, 2, 1, 0, -1]
+    );
+    assert_eq!(loaded, settings);
+
+    // A gain past the range is rejected
+    let mut bytes = settings.to_bytes();
+    bytes[IQ_CAL_START - 1] = 13;
+    let sum = bytes[..SETTINGS_LEN - 1]
+        .iter()
+        .fold(0u8, |acc, &b| acc.wrapping_add(b));
+    bytes[SETTINGS_LEN - 1] = sum.wrapping_neg();
+    assert_eq!(Settings::from_bytes(&bytes), None);
+}
+
+#[test]
+fn version5_record_loads_with_default_equalizers() {
+    let mut settings = Settings::new();
+    settings.config_mut().keyer_wpm = 30;
+    settings.config_mut().rx_eq = true;
+    settings.set_eq_curve(EqPath::Rx, Mode::Usb, EqCurve::FLAT);
+
+    // Version 5 layout: 9-byte operator configuration, no equalizer
+    let mut v5 = settings.to_bytes()[..CONFIG_START + 9].to_vec();
+    v5[4] = 5;
+    let sum = v5.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v5.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v5).unwrap();
+    assert_eq!(loaded.config().keyer_wpm, 30);
+    assert!(!loaded.config().rx_eq);
+    assert_eq!(
+        *loaded.eq_presets(EqPath::Rx),
+        EqPresets::defaults(EqPath::Rx)
+    );
+}
+
+#[test]
+fn iq_calibration_round_trips_per_band() {
+    let mut settings = Settings::new();
+    assert_eq!(settings.iq_calibration(Band::M20), IqCalibration::IDENTITY);
+    settings.set_iq_calibration(Band::M20, IqCalibration::new(-45, 180));
+    settings.set_iq_calibration(Band::M80, IqCalibration::new(120, -300));
+
+    let loaded = Settings::from_bytes(&settings.to_bytes()).unwrap();
+    assert_eq!(loaded.iq_calibration(Band::M20), IqCalibration::new(-45, 180));
+    assert_eq!(loaded.iq_calibration(Band::M80), IqCalibration::new(120, -300));
+    assert_eq!(loaded.iq_calibration(Band::M40), IqCalibration::IDENTITY);
+    assert_eq!(loaded.iq_calibrations()[Band::M20.index()], IqCalibration::new(-45, 180));
+    assert_eq!(loaded, settings);
+
+    // A phase past the range is rejected
+    let mut bytes = settings.to_bytes();
+    bytes[IQ_CAL_START + 2..IQ_CAL_START + 4].copy_from_slice(&2000i16.to_le_bytes());
+    let sum = bytes[..SETTINGS_LEN - 1]
+        .iter()
+        .fold(0u8, |acc, &b| acc.wrapping_add(b));
+    bytes[SETTINGS_LEN - 1] = sum.wrapping_neg();
+    assert_eq!(Settings::from_bytes(&bytes), None);
+}
+
+#[test]
+fn version6_record_loads_with_no_iq_correction() {
+    let mut settings = Settings::new();
+    settings.set_eq_curve(EqPath::Rx, Mode::Cw, EqCurve::new([0, 0, 6, 0, 0]));
+    settings.set_iq_calibration(Band::M40, IqCalibration::new(30, 90));
+
+    // Version 6 layout: equalizer curves, no IQ balance
+    let mut v6 = settings.to_bytes()[..IQ_CAL_START].to_vec();
+    v6[4] = 6;
+    let sum = v6.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    v6.push(sum.wrapping_neg());
+
+    let loaded = Settings::from_bytes(&v6).unwrap();
+    assert_eq!(loaded.eq_presets(EqPath::Rx).get(Mode::Cw).gains_db(), [0, 0, 6, 0, 0]);
+    assert_eq!(loaded.iq_calibration(Band::M40), IqCalibration::IDENTITY);
+}
+
+#[test]
+fn out_of_range_config_rejected() {
+    // (offset from the start of the configuration, bad value)
+    for (offset, value) in [
+        (0, 4u8),
+        (0, 51),
+        (3, 4),
+        (4, 2),
+        (5, 101),
+        (8, 101),
+        (9, 2),
+        (10, 2),
+    ] {
+        let mut bytes = Settings::new().to_bytes();
+        bytes[CONFIG_START + offset] = value;
+        let sum = bytes[..SETTINGS_LEN - 1]
+            .iter()
+            .fold(0u8, |acc, &b| acc.wrapping_add(b));
+        bytes[SETTINGS_LEN - 1] = sum.wrapping_neg();
+        assert_eq!(Settings::from_bytes(&bytes), None, "byte {offset} = {value}");
+    }
+}
+
+#[test]
+fn config_contrast_scales_brightness() {
+    let mut config = OperatorConfig::new();
+    config.brightness = 0;
+    assert_eq!(config.contrast(), 0);
+    config.brightness = 100;
+    assert_eq!(config.contrast(), 255);
+    config.brightness = 50;
+    assert_eq!(config.contrast(), 127);
+}
+
+#[test]
+fn corrupt_record_rejected() {
+    let mut bytes = Settings::new().to_bytes();
+    bytes[7] ^= 0x01;
+    assert_eq!(Settings::from_bytes(&bytes), None);
+
+    let mut bytes = Settings::new().to_bytes();
+    bytes[0] = b'X';
+    assert_eq!(Settings::from_bytes(&bytes), None);
+
+    assert_eq!(Settings::from_bytes(&[0u8; 4]), None);
+    assert_eq!(Settings::from_bytes(&[0xFF; SETTINGS_LEN]), None);
+}
+
+// ============================================================================
+// File Store Tests
+// ============================================================================
+
+#[test]
+fn load_from_empty_store() {
+    let mut store = FileStore::format(RamFlash::new()).unwrap();
+    assert_eq!(Settings::load(&mut store), None);
+}
+
+#[test]
+fn save_replaces_previous_record() {
+    let mut store = FileStore::format(RamFlash::new()).unwrap();
+    let mut settings = Settings::new();
+    settings.save(&mut store).unwrap();
+
+    settings.update_last(&state(7_030_000, Mode::Cw));
+    settings.save(&mut store).unwrap();
+
+    let files: Vec<_> = store.files().iter().filter(|f| f.kind == FileKind::Settings).collect();
+    assert_eq!(files.len(), 1);
+
+    let mut store = FileStore::mount(store.release()).unwrap();
+    assert_eq!(Settings::load(&mut store), Some(settings));
+}
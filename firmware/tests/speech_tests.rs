@@ -0,0 +1,160 @@
+//! Speech Processor Tests
+//!
+//! Compressor, limiter, ALC and the complete transmit audio chain.
+//! Run with: cargo test --features std
+
+use sdr_firmware::dsp::audio_chain::TxAudioChain;
+use sdr_firmware::dsp::equalizer::EqCurve;
+use sdr_firmware::dsp::modulation::IqSample;
+use sdr_firmware::dsp::speech::{Alc, Compressor, LIMITER_CEILING, MAX_COMPRESSION_DB};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Syllable-like test signal: a 500 Hz tone with a 4 Hz amplitude envelope
+fn speech_like(n: usize) -> f32 {
+    let t = n as f32 / SAMPLE_RATE;
+    let envelope = 0.1 + 0.9 * (2.0 * core::f32::consts::PI * 4.0 * t).sin().abs();
+    0.8 * envelope * (2.0 * core::f32::consts::PI * 500.0 * t).sin()
+}
+
+/// Ratio of RMS to peak level of a signal
+fn rms_to_peak(samples: &[f32]) -> f32 {
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    let peak = samples.iter().fold(0.0_f32, |p, s| p.max(s.abs()));
+    rms / peak
+}
+
+// =============================================================================
+// Compressor Tests
+// =============================================================================
+
+#[test]
+fn test_compressor_raises_average_level() {
+    let mut flat = Compressor::new(0.0, SAMPLE_RATE);
+    let mut compressed = Compressor::new(15.0, SAMPLE_RATE);
+
+    let flat_out: Vec<f32> = (0..48000).map(|n| flat.process(speech_like(n))).collect();
+    let comp_out: Vec<f32> = (0..48000).map(|n| compressed.process(speech_like(n))).collect();
+
+    let flat_ratio = rms_to_peak(&flat_out[4800..]);
+    let comp_ratio = rms_to_peak(&comp_out[4800..]);
+    assert!(
+        comp_ratio > flat_ratio * 1.1,
+        "Compression should raise average/peak: {comp_ratio} vs {flat_ratio}"
+    );
+}
+
+#[test]
+fn test_compressor_limits_peaks_and_clamps_setting() {
+    let mut comp = Compressor::new(50.0, SAMPLE_RATE);
+    assert!((comp.compression_db() - MAX_COMPRESSION_DB).abs() < f32::EPSILON);
+
+    // Step from silence to full scale: the attack overshoot is limited
+    for _ in 0..100 {
+        assert!(comp.process(1.0) <= LIMITER_CEILING);
+    }
+    assert!(comp.gain_reduction() < 1.0);
+
+    comp.reset();
+    assert!((comp.gain_reduction() - 1.0).abs() < f32::EPSILON);
+}
+
+// =============================================================================
+// ALC Tests
+// =============================================================================
+
+#[test]
+fn test_alc_cuts_immediately_and_recovers_slowly() {
+    let mut alc = Alc::new(SAMPLE_RATE);
+    alc.set_target(0.5);
+
+    // Output at twice the target halves the drive at once
+    alc.update(1.0);
+    assert!((alc.gain() - 0.5).abs() < 1e-6);
+    assert!((alc.reduction_db() - 6.02).abs() < 0.05);
+
+    // Quiet output lets the drive creep back up
+    alc.update(0.1);
+    let recovered = alc.gain();
+    assert!(recovered > 0.5 && recovered < 0.51);
+
+    alc.reset();
+    assert!(alc.reduction_db().abs() < 1e-3);
+}
+
+// =============================================================================
+// TX Audio Chain Tests
+// =============================================================================
+
+fn run_chain(chain: &mut TxAudioChain, gain: f32) -> Vec<IqSample> {
+    (0..48000).map(|n| chain.process(gain * speech_like(n))).collect()
+}
+
+#[test]
+fn test_tx_chain_alc_holds_output_at_target() {
+    let mut chain = TxAudioChain::new();
+    chain.set_alc_target(0.5);
+    // Overdriven microphone
+    chain.set_mic_gain_db(20.0);
+
+    let out = run_chain(&mut chain, 1.0);
+    let peak = out[4800..].iter().fold(0.0_f32, |p, iq| p.max(iq.magnitude()));
+    assert!(peak <= 0.5 * 1.05, "ALC should hold peaks at target: {peak}");
+    assert!(chain.alc_reduction_db() > 0.0);
+}
+
+#[test]
+fn test_tx_chain_processor_increases_talk_power() {
+    let mut plain = TxAudioChain::new();
+    plain.set_processor_enabled(false);
+    let mut processed = TxAudioChain::new();
+    processed.set_compression(15.0);
+    assert!(processed.is_processor_enabled());
+
+    let plain: Vec<f32> = run_chain(&mut plain, 1.0)[4800..]
+        .iter()
+        .map(IqSample::magnitude)
+        .collect();
+    let processed: Vec<f32> = run_chain(&mut processed, 1.0)[4800..]
+        .iter()
+        .map(IqSample::magnitude)
+        .collect();
+
+    assert!(
+        rms_to_peak(&processed) > rms_to_peak(&plain),
+        "Speech processor should raise average power for the same peak"
+    );
+}
+
+#[test]
+fn test_tx_eq_clamped() {
+    let mut chain = TxAudioChain::new();
+    assert_eq!(chain.eq(), (0.0, 0.0));
+    chain.set_eq(20.0, -3.0);
+    assert_eq!(chain.eq(), (12.0, -3.0));
+}
+
+#[test]
+fn test_tx_graphic_eq_shapes_mic_audio() {
+    /// Peak processed audio for a steady tone, compressor out
+    fn tone_peak(chain: &mut TxAudioChain, hz: f32) -> f32 {
+        chain.set_processor_enabled(false);
+        (0..9600)
+            .map(|n| {
+                let phase = 2.0 * core::f32::consts::PI * hz * n as f32 / SAMPLE_RATE;
+                chain.process_audio(0.1 * phase.sin())
+            })
+            .skip(4800)
+            .fold(0.0_f32, |peak, s| peak.max(s.abs()))
+    }
+
+    let mut chain = TxAudioChain::new();
+    assert!(!chain.equalizer().is_enabled());
+    let flat = tone_peak(&mut chain, 200.0);
+
+    // The low band cut by 12 dB takes the tone down by about 4x
+    chain.equalizer_mut().set_curve(EqCurve::new([-12, 0, 0, 3, 4]));
+    chain.equalizer_mut().set_enabled(true);
+    let cut = tone_peak(&mut chain, 200.0);
+    assert!(cut * 3.0 < flat, "{cut} vs {flat}");
+}
@@ -0,0 +1,504 @@
+//! Tests for the flat file store
+//!
+//! Uses a RAM-backed device with NOR flash semantics (programming can
+//! only clear bits, erase sets a block to 0xFF).
+
+use sdr_firmware::radio::recorder::{clip_file_name, ActivityRecorder, RecorderAction};
+use sdr_firmware::radio::voice_keyer::{
+    slot_file_name, VoiceKeyer, VoiceState, MAX_MESSAGE_MS, MAX_REPEAT_S,
+};
+use sdr_firmware::storage::{
+    BlockDevice, FileKind, FileStore, StorageError, MAX_FILES, MAX_NAME_LEN, MIN_BLOCK_SIZE,
+};
+
+/// RAM flash emulator
+struct RamFlash {
+    data: Vec<u8>,
+    block_size: u32,
+    erases: usize,
+}
+
+impl RamFlash {
+    fn new(block_size: u32, blocks: u32) -> Self {
+        Self {
+            data: vec![0xFF; (block_size * blocks) as usize],
+            block_size,
+            erases: 0,
+        }
+    }
+}
+
+impl BlockDevice for RamFlash {
+    type Error = ();
+
+    fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    fn block_count(&self) -> u32 {
+        self.data.len() as u32 / self.block_size
+    }
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), ()> {
+        let start = addr as usize;
+        let src = self.data.get(start..start + buf.len()).ok_or(())?;
+        buf.copy_from_slice(src);
+        Ok(())
+    }
+
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), ()> {
+        let start = addr as usize;
+        let dst = self.data.get_mut(start..start + data.len()).ok_or(())?;
+        for (d, s) in dst.iter_mut().zip(data) {
+            *d &= *s;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, block: u32) -> Result<(), ()> {
+        let bs = self.block_size as usize;
+        let start = block as usize * bs;
+        self.data.get_mut(start..start + bs).ok_or(())?.fill(0xFF);
+        self.erases += 1;
+        Ok(())
+    }
+}
+
+fn formatted() -> FileStore<RamFlash> {
+    FileStore::format(RamFlash::new(4096, 16)).unwrap()
+}
+
+// ============================================================================
+// Format / Mount Tests
+// ============================================================================
+
+#[test]
+fn mount_blank_device_not_formatted() {
+    let result = FileStore::mount(RamFlash::new(4096, 16));
+    assert!(matches!(result, Err(StorageError::NotFormatted)));
+}
+
+#[test]
+fn format_rejects_small_blocks() {
+    let result = FileStore::format(RamFlash::new(MIN_BLOCK_SIZE / 2, 64));
+    assert!(matches!(result, Err(StorageError::Unsupported)));
+}
+
+#[test]
+fn format_creates_empty_store() {
+    let store = formatted();
+    assert!(store.files().is_empty());
+    assert!(!store.is_open());
+    assert_eq!(store.capacity_bytes(), 15 * 4096);
+    assert_eq!(store.free_bytes(), 15 * 4096);
+}
+
+#[test]
+fn mount_after_format() {
+    let device = formatted().release();
+    let store = FileStore::mount(device).unwrap();
+    assert!(store.files().is_empty());
+}
+
+// ============================================================================
+// File Operation Tests
+// ============================================================================
+
+#[test]
+fn create_write_read_file() {
+    let mut store = formatted();
+    let id = store.create("journal.txt", FileKind::Log).unwrap();
+    assert!(store.is_open());
+    store.append(b"7074 FT8 ").unwrap();
+    store.append(b"K1ABC").unwrap();
+    let entry = store.close().unwrap();
+
+    assert_eq!(entry.id, id);
+    assert_eq!(entry.length, 14);
+    assert_eq!(store.files().len(), 1);
+
+    let mut buf = [0u8; 32];
+    let n = store.read(id, 0, &mut buf).unwrap();
+    assert_eq!(&buf[..n], b"7074 FT8 K1ABC");
+}
+
+#[test]
+fn read_at_offset_and_eof() {
+    let mut store = formatted();
+    let id = store.create("a", FileKind::Log).unwrap();
+    store.append(b"0123456789").unwrap();
+    store.close().unwrap();
+
+    let mut buf = [0u8; 4];
+    assert_eq!(store.read(id, 8, &mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"89");
+    assert_eq!(store.read(id, 10, &mut buf).unwrap(), 0);
+    assert_eq!(store.read(id, 50, &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn append_spans_blocks() {
+    let mut store = formatted();
+    let id = store.create("capture.iq", FileKind::Capture).unwrap();
+    let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+    store.append(&data[..3000]).unwrap();
+    store.append(&data[3000..]).unwrap();
+    let entry = store.close().unwrap();
+    assert_eq!(entry.length, 10_000);
+
+    let mut buf = vec![0u8; 10_000];
+    assert_eq!(store.read(id, 0, &mut buf).unwrap(), 10_000);
+    assert_eq!(buf, data);
+
+    // Directory + 3 data blocks
+    assert_eq!(store.release().erases, 4);
+}
+
+#[test]
+fn files_are_contiguous() {
+    let mut store = formatted();
+    store.create("one", FileKind::Voice).unwrap();
+    store.append(&[1u8; 5000]).unwrap();
+    let one = store.close().unwrap();
+
+    store.create("two", FileKind::Voice).unwrap();
+    store.append(&[2u8; 10]).unwrap();
+    let two = store.close().unwrap();
+
+    assert_eq!(one.start_block, 1);
+    assert_eq!(two.start_block, 3);
+    assert_eq!(store.free_bytes(), 12 * 4096);
+}
+
+#[test]
+fn files_survive_remount() {
+    let mut store = formatted();
+    store.create("cq.voice", FileKind::Voice).unwrap();
+    store.append(b"audio").unwrap();
+    store.close().unwrap();
+
+    let mut store = FileStore::mount(store.release()).unwrap();
+    let entry = store.find("cq.voice").unwrap().clone();
+    assert_eq!(entry.kind, FileKind::Voice);
+    assert_eq!(entry.length, 5);
+
+    let mut buf = [0u8; 5];
+    store.read(entry.id, 0, &mut buf).unwrap();
+    assert_eq!(&buf, b"audio");
+}
+
+#[test]
+fn unclosed_file_discarded_on_mount() {
+    let mut store = formatted();
+    store.create("kept", FileKind::Log).unwrap();
+    store.close().unwrap();
+    store.create("lost", FileKind::Log).unwrap();
+    store.append(b"partial").unwrap();
+
+    let store = FileStore::mount(store.release()).unwrap();
+    assert_eq!(store.files().len(), 1);
+    assert!(store.find("lost").is_none());
+}
+
+#[test]
+fn delete_file() {
+    let mut store = formatted();
+    let id = store.create("old", FileKind::Log).unwrap();
+    store.close().unwrap();
+    store.delete(id).unwrap();
+    assert!(store.files().is_empty());
+    assert!(matches!(store.delete(id), Err(StorageError::NotFound)));
+
+    let store = FileStore::mount(store.release()).unwrap();
+    assert!(store.files().is_empty());
+}
+
+#[test]
+fn deleting_last_file_reuses_space() {
+    let mut store = formatted();
+    let id = store.create("big", FileKind::Capture).unwrap();
+    store.append(&[0u8; 8192]).unwrap();
+    store.close().unwrap();
+    store.delete(id).unwrap();
+
+    store.create("new", FileKind::Capture).unwrap();
+    store.append(b"fresh").unwrap();
+    let entry = store.close().unwrap();
+    assert_eq!(entry.start_block, 1);
+
+    let mut buf = [0u8; 5];
+    store.read(entry.id, 0, &mut buf).unwrap();
+    assert_eq!(&buf, b"fresh");
+}
+
+// ============================================================================
+// Error Tests
+// ============================================================================
+
+#[test]
+fn only_one_open_file() {
+    let mut store = formatted();
+    store.create("a", FileKind::Log).unwrap();
+    assert!(matches!(store.create("b", FileKind::Log), Err(StorageError::Busy)));
+}
+
+#[test]
+fn append_without_open_fails() {
+    let mut store = formatted();
+    assert!(matches!(store.append(b"x"), Err(StorageError::NotOpen)));
+    assert!(matches!(store.close(), Err(StorageError::NotOpen)));
+}
+
+#[test]
+fn duplicate_name_rejected() {
+    let mut store = formatted();
+    store.create("a", FileKind::Log).unwrap();
+    store.close().unwrap();
+    assert!(matches!(store.create("a", FileKind::Log), Err(StorageError::Exists)));
+}
+
+#[test]
+fn invalid_names_rejected() {
+    let mut store = formatted();
+    let long = "x".repeat(MAX_NAME_LEN + 1);
+    assert!(matches!(store.create("", FileKind::Log), Err(StorageError::InvalidName)));
+    assert!(matches!(store.create(&long, FileKind::Log), Err(StorageError::InvalidName)));
+    assert!(matches!(store.create("héllo", FileKind::Log), Err(StorageError::InvalidName)));
+}
+
+#[test]
+fn device_full() {
+    let mut store = FileStore::format(RamFlash::new(4096, 3)).unwrap();
+    store.create("a", FileKind::Capture).unwrap();
+    store.append(&[0u8; 8192]).unwrap();
+    assert!(matches!(store.append(&[0u8]), Err(StorageError::Full)));
+    store.close().unwrap();
+    assert!(matches!(store.create("b", FileKind::Capture), Err(StorageError::Full)));
+}
+
+#[test]
+fn directory_full() {
+    let mut store = formatted();
+    for i in 0..MAX_FILES {
+        let id = store.create(&format!("f{i}"), FileKind::Log).unwrap();
+        store.close().unwrap();
+        store.delete(id).unwrap();
+    }
+    assert!(matches!(
+        store.create("extra", FileKind::Log),
+        Err(StorageError::DirectoryFull)
+    ));
+}
+
+// ============================================================================
+// Voice Keyer Tests
+// ============================================================================
+
+const VOICE_RATE: u32 = 8000;
+
+/// Store with room for about 32 seconds of voice
+fn voice_store() -> FileStore<RamFlash> {
+    FileStore::format(RamFlash::new(4096, 128)).unwrap()
+}
+
+/// Record `samples` of a ramp into `slot` in 100-sample blocks
+fn record_ramp(voice: &mut VoiceKeyer, store: &mut FileStore<RamFlash>, slot: u8, samples: usize) {
+    voice.start_recording(slot, store).unwrap();
+    let ramp: Vec<f32> = (0..samples).map(|i| (i % 200) as f32 / 200.0 - 0.5).collect();
+    for block in ramp.chunks(100) {
+        voice.record(store, block).unwrap();
+    }
+}
+
+#[test]
+fn voice_keyer_records_and_plays() {
+    let mut store = voice_store();
+    let mut voice = VoiceKeyer::new(VOICE_RATE);
+    record_ramp(&mut voice, &mut store, 0, 4000);
+    assert_eq!(voice.state(), VoiceState::Recording(0));
+    assert_eq!(voice.stop_recording(&mut store).unwrap(), Some(500));
+    assert_eq!(voice.message_ms(0), Some(500));
+    assert_eq!(voice.message_ms(1), None);
+    assert_eq!(store.find("VOICE1.PCM").unwrap().kind, FileKind::Voice);
+
+    // Playback replaces the mic audio and asks for PTT until the end
+    assert!(voice.play(0));
+    assert!(voice.wants_ptt());
+    let mut played = Vec::new();
+    let mut block = [0.9f32; 160];
+    while voice.wants_ptt() {
+        block.fill(0.9);
+        voice.fill(&mut store, &mut block).unwrap();
+        played.extend_from_slice(&block);
+    }
+    assert_eq!(voice.state(), VoiceState::Idle);
+    assert_eq!(played.len(), 4000);
+    for (i, sample) in played.iter().enumerate() {
+        let expected = (i % 200) as f32 / 200.0 - 0.5;
+        assert!((sample - expected).abs() < 1e-3, "{i}: {sample}");
+    }
+
+    // Idle, the mic audio is left alone
+    block.fill(0.9);
+    voice.fill(&mut store, &mut block).unwrap();
+    assert!(block.iter().all(|&s| s == 0.9));
+}
+
+#[test]
+fn voice_keyer_repeats_after_interval() {
+    let mut store = voice_store();
+    let mut voice = VoiceKeyer::new(VOICE_RATE);
+    record_ramp(&mut voice, &mut store, 2, 800);
+    voice.stop_recording(&mut store).unwrap();
+    voice.set_repeat_s(1);
+    assert_eq!(voice.repeat_s(), 1);
+    voice.set_repeat_s(5000);
+    assert_eq!(voice.repeat_s(), MAX_REPEAT_S);
+    voice.set_repeat_s(1);
+
+    assert!(voice.play(2));
+    let mut block = [0.0f32; 800];
+    voice.fill(&mut store, &mut block).unwrap();
+    assert_eq!(voice.state(), VoiceState::Waiting(2));
+    assert!(!voice.wants_ptt());
+
+    // One second of listening, then the message goes out again
+    for _ in 0..9 {
+        voice.fill(&mut store, &mut block).unwrap();
+    }
+    assert_eq!(voice.state(), VoiceState::Waiting(2));
+    voice.fill(&mut store, &mut block).unwrap();
+    assert_eq!(voice.state(), VoiceState::Playing(2));
+    voice.stop();
+    assert_eq!(voice.state(), VoiceState::Idle);
+}
+
+#[test]
+fn voice_keyer_storage_and_slots() {
+    let mut store = voice_store();
+    let mut voice = VoiceKeyer::new(VOICE_RATE);
+    let empty = voice.remaining_ms(&store);
+    assert_eq!(empty, 127 * 4096 / 2 * 1000 / VOICE_RATE);
+
+    // Messages stop at the maximum length
+    record_ramp(&mut voice, &mut store, 0, 31 * VOICE_RATE as usize);
+    assert_eq!(voice.state(), VoiceState::Idle);
+    assert_eq!(voice.message_ms(0), Some(MAX_MESSAGE_MS));
+    assert!(voice.remaining_ms(&store) < empty - MAX_MESSAGE_MS + 100);
+
+    // Re-recording the last message reuses its space
+    record_ramp(&mut voice, &mut store, 0, 800);
+    voice.stop_recording(&mut store).unwrap();
+    assert_eq!(voice.message_ms(0), Some(100));
+    record_ramp(&mut voice, &mut store, 3, 1600);
+    voice.stop_recording(&mut store).unwrap();
+
+    // Slots are found again after a remount
+    let mut store = FileStore::mount(store.release()).unwrap();
+    let mut voice = VoiceKeyer::new(VOICE_RATE);
+    voice.load(&store);
+    assert_eq!(voice.message_ms(0), Some(100));
+    assert_eq!(voice.message_ms(3), Some(200));
+    voice.delete(3, &mut store).unwrap();
+    assert!(!voice.has_message(3));
+    assert!(store.find("VOICE4.PCM").is_none());
+
+    // Bad slots, empty slots and overlapping use are refused
+    assert!(!voice.play(1));
+    assert!(matches!(
+        voice.start_recording(4, &mut store),
+        Err(StorageError::NotFound)
+    ));
+    voice.start_recording(1, &mut store).unwrap();
+    assert!(!voice.play(0));
+    assert!(matches!(
+        voice.start_recording(2, &mut store),
+        Err(StorageError::Busy)
+    ));
+    assert!(matches!(voice.delete(1, &mut store), Err(StorageError::Busy)));
+    assert_eq!(voice.stop_recording(&mut store).unwrap(), Some(0));
+    assert_eq!(slot_file_name(1).as_str(), "VOICE2.PCM");
+}
+
+// ============================================================================
+// Activity Recorder Tests
+// ============================================================================
+
+#[test]
+fn recorder_writes_clips_to_store() {
+    let mut store = voice_store();
+    let mut rec = ActivityRecorder::new();
+    rec.set_enabled(true);
+    rec.set_tail_ms(1_000);
+
+    let action = rec.update(true, false, 0);
+    rec.apply(action, &mut store).unwrap();
+    assert!(rec.is_writing());
+    assert!(store.is_open());
+
+    let audio = [0.5f32; 100];
+    rec.record(&mut store, &audio).unwrap();
+    rec.record(&mut store, &audio).unwrap();
+
+    let action = rec.update(false, false, 1_000);
+    assert!(matches!(action, RecorderAction::Stop(_)));
+    rec.apply(action, &mut store).unwrap();
+    assert!(!rec.is_writing());
+    assert!(!store.is_open());
+
+    let entry = store.find("CLIP00000.PCM").unwrap().clone();
+    assert_eq!(entry.kind, FileKind::Capture);
+    assert_eq!(entry.length, 400);
+    let mut pcm = [0u8; 2];
+    store.read(entry.id, 0, &mut pcm).unwrap();
+    assert_eq!(i16::from_le_bytes(pcm), (0.5 * f32::from(i16::MAX)) as i16);
+
+    // Audio outside a clip is not written
+    rec.record(&mut store, &audio).unwrap();
+    assert_eq!(store.find("CLIP00000.PCM").unwrap().length, 400);
+}
+
+#[test]
+fn recorder_numbering_survives_remount() {
+    let mut store = voice_store();
+    let mut rec = ActivityRecorder::new();
+    for i in 0..3 {
+        let action = rec.start_manual(i * 10);
+        rec.apply(action, &mut store).unwrap();
+        let action = rec.stop(i * 10 + 5);
+        rec.apply(action, &mut store).unwrap();
+    }
+
+    let mut store = FileStore::mount(store.release()).unwrap();
+    let mut rec = ActivityRecorder::new();
+    rec.load(&store);
+    let action = rec.start_manual(0);
+    assert!(matches!(action, RecorderAction::Start { id: 3, .. }));
+    rec.apply(action, &mut store).unwrap();
+    let action = rec.stop(5);
+    rec.apply(action, &mut store).unwrap();
+    assert!(store.find(&clip_file_name(3)).is_some());
+
+    assert!(rec.delete_clip(3, &mut store).unwrap());
+    assert!(store.find("CLIP00003.PCM").is_none());
+    assert!(!rec.delete_clip(3, &mut store).unwrap());
+}
+
+#[test]
+fn recorder_without_room_keeps_tracking() {
+    let mut store = voice_store();
+    let mut voice = VoiceKeyer::new(VOICE_RATE);
+    voice.start_recording(0, &mut store).unwrap();
+
+    // The voice keyer holds the store, the clip is tracked without a file
+    let mut rec = ActivityRecorder::new();
+    let action = rec.start_manual(0);
+    assert!(matches!(rec.apply(action, &mut store), Err(StorageError::Busy)));
+    assert!(rec.is_recording());
+    assert!(!rec.is_writing());
+    rec.record(&mut store, &[0.1; 10]).unwrap();
+    let action = rec.stop(100);
+    rec.apply(action, &mut store).unwrap();
+    assert_eq!(rec.clips().len(), 1);
+    assert!(store.is_open());
+}
@@ -3,7 +3,11 @@
 //! Tests for domain types (Frequency, Band, Mode, etc.)
 //! Run with: cargo test --target x86_64-unknown-linux-gnu --no-default-features --features std --test types_tests
 
-use sdr_firmware::types::{Band, Frequency, Mode, PowerLevel, SwrReading, TuningStep, TxRxState};
+use sdr_firmware::dsp::modulation::IqSample;
+use sdr_firmware::types::ring::{AudioRing, IqRing};
+use sdr_firmware::types::{
+    Band, Frequency, IqOrientation, Mode, PowerLevel, SwrReading, TuningStep, TxRxState,
+};
 
 // =============================================================================
 // Frequency Tests
@@ -323,6 +327,28 @@ fn test_txrx_state_equality() {
     assert_ne!(TxRxState::Tx, TxRxState::Rx);
 }
 
+// =============================================================================
+// IqOrientation Tests
+// =============================================================================
+
+#[test]
+fn test_iq_orientation_codes() {
+    assert_eq!(IqOrientation::default(), IqOrientation::Normal);
+    for orientation in [IqOrientation::Normal, IqOrientation::Swapped, IqOrientation::InvertQ] {
+        assert_eq!(IqOrientation::from_code(orientation.code()), Some(orientation));
+    }
+    assert_eq!(IqOrientation::from_code(3), None);
+}
+
+#[test]
+fn test_iq_orientation_apply_raw() {
+    assert_eq!(IqOrientation::Normal.apply_raw(100, -200), (100, -200));
+    assert_eq!(IqOrientation::Swapped.apply_raw(100, -200), (-200, 100));
+    assert_eq!(IqOrientation::InvertQ.apply_raw(100, -200), (100, 200));
+    // Full-scale negative Q saturates rather than wrapping
+    assert_eq!(IqOrientation::InvertQ.apply_raw(0, i16::MIN), (0, i16::MAX));
+}
+
 // =============================================================================
 // Band Edge and Frequency Boundary Tests
 // =============================================================================
@@ -475,3 +501,130 @@ fn test_swr_marginal() {
     let swr = reading.swr_ratio();
     assert!(swr > 1.5 && swr < 5.0, "Expected moderate SWR, got {}", swr);
 }
+
+// =============================================================================
+// SPSC Ring Tests
+// =============================================================================
+
+#[test]
+fn test_ring_push_pop_in_order() {
+    let mut ring = AudioRing::<8>::new();
+    let (mut producer, mut consumer) = ring.split();
+    assert!(consumer.is_empty());
+    assert_eq!(consumer.pop(), None);
+
+    assert!(producer.push(0.5));
+    assert_eq!(producer.push_slice(&[1.0, -1.0, 0.25]), 3);
+    assert_eq!(consumer.len(), 4);
+    assert_eq!(producer.free(), 4);
+
+    let mut out = [0.0; 3];
+    assert_eq!(consumer.pop_slice(&mut out), 3);
+    assert_eq!(out, [0.5, 1.0, -1.0]);
+    assert_eq!(consumer.pop(), Some(0.25));
+    assert_eq!(consumer.underruns(), 0);
+}
+
+#[test]
+fn test_ring_counts_dropped_samples() {
+    let mut ring = AudioRing::<4>::new();
+    let (mut producer, mut consumer) = ring.split();
+
+    assert_eq!(producer.push_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), 4);
+    assert!(!producer.push(7.0));
+    assert_eq!(producer.dropped(), 3);
+
+    // The oldest samples are kept, not overwritten
+    let mut out = [0.0; 4];
+    consumer.pop_slice(&mut out);
+    assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(ring.dropped(), 3);
+}
+
+#[test]
+fn test_ring_counts_short_reads() {
+    let mut ring = AudioRing::<8>::new();
+    let (mut producer, mut consumer) = ring.split();
+    producer.push_slice(&[1.0, 2.0]);
+
+    let mut out = [9.0; 4];
+    assert_eq!(consumer.pop_slice(&mut out), 2);
+    assert_eq!(out, [1.0, 2.0, 9.0, 9.0]);
+    assert_eq!(consumer.underruns(), 1);
+
+    // An empty read of nothing is not an underrun
+    assert_eq!(consumer.pop_slice(&mut []), 0);
+    assert_eq!(consumer.underruns(), 1);
+}
+
+#[test]
+fn test_ring_wraps_around() {
+    let mut ring = AudioRing::<4>::new();
+    let (mut producer, mut consumer) = ring.split();
+    let mut out = [0.0; 3];
+    for block in 0..10 {
+        let base = block as f32 * 3.0;
+        assert_eq!(producer.push_slice(&[base, base + 1.0, base + 2.0]), 3);
+        assert_eq!(consumer.pop_slice(&mut out), 3);
+        assert_eq!(out, [base, base + 1.0, base + 2.0]);
+    }
+    assert_eq!(producer.dropped(), 0);
+}
+
+#[test]
+fn test_ring_clear() {
+    let mut ring = AudioRing::<8>::new();
+    let (mut producer, mut consumer) = ring.split();
+    producer.push_slice(&[1.0; 5]);
+    assert_eq!(consumer.clear(), 5);
+    assert!(consumer.is_empty());
+    assert_eq!(producer.free(), 8);
+}
+
+#[test]
+fn test_iq_ring() {
+    let mut ring = IqRing::<4>::new();
+    let (mut producer, mut consumer) = ring.split();
+    producer.push_slice(&[IqSample::new(0.5, -0.5), IqSample::new(-1.0, 1.0)]);
+    let sample = consumer.pop().unwrap();
+    assert_eq!((sample.i, sample.q), (0.5, -0.5));
+    let sample = consumer.pop().unwrap();
+    assert_eq!((sample.i, sample.q), (-1.0, 1.0));
+    assert_eq!(ring.capacity(), 4);
+}
+
+#[test]
+fn test_ring_across_threads() {
+    const SAMPLES: usize = 20_000;
+    let mut ring = AudioRing::<64>::new();
+    let (mut producer, mut consumer) = ring.split();
+
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut next = 0;
+            while next < SAMPLES {
+                let block: Vec<f32> = (next..(next + 16).min(SAMPLES)).map(|n| n as f32).collect();
+                let mut sent = 0;
+                while sent < block.len() {
+                    let end = (sent + producer.free()).min(block.len());
+                    sent += producer.push_slice(&block[sent..end]);
+                    std::thread::yield_now();
+                }
+                next += block.len();
+            }
+        });
+
+        let mut expected = 0;
+        let mut out = [0.0; 24];
+        while expected < SAMPLES {
+            let count = consumer.len().min(out.len());
+            consumer.pop_slice(&mut out[..count]);
+            for &sample in &out[..count] {
+                assert_eq!(sample, expected as f32);
+                expected += 1;
+            }
+            std::thread::yield_now();
+        }
+    });
+    assert_eq!((ring.dropped(), ring.underruns()), (0, 0));
+}
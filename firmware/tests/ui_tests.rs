@@ -0,0 +1,493 @@
+//! Tests for UI state, the settings menu, the mini waterfall and partial
+//! redraw scheduling
+
+use sdr_firmware::dsp::spectrum::WaterfallRow;
+use sdr_firmware::radio::practice::PracticeSession;
+use sdr_firmware::radio::state::{AgcMode, RadioEvent};
+use sdr_firmware::radio::tx_policy::{TxReason, TxVerdict};
+use sdr_firmware::settings::OperatorConfig;
+use sdr_firmware::types::Frequency;
+use sdr_firmware::ui::menu::{MenuNavigator, MenuResponse, SETTINGS_MENU};
+use sdr_firmware::ui::redraw::{Geometry, RedrawScheduler, Region, TILE_WIDTH};
+use sdr_firmware::ui::waterfall::{WaterfallView, DEFAULT_REF_LEVEL_DB, LEVELS};
+use sdr_firmware::ui::{MenuAction, Screen, UiAction, UiState, MAIN_MENU, ZERO_BEAT_COMMAND};
+
+/// Send every pending region, returning them
+fn drain(scheduler: &mut RedrawScheduler) -> Vec<Region> {
+    core::iter::from_fn(|| scheduler.next_region()).collect()
+}
+
+// ============================================================================
+// Settings Menu Tests
+// ============================================================================
+
+/// Open the top-level item with `label`
+fn open(nav: &mut MenuNavigator<OperatorConfig>, config: &mut OperatorConfig, label: &str) {
+    nav.reset();
+    let index = SETTINGS_MENU
+        .items
+        .iter()
+        .position(|item| item.label == label)
+        .unwrap();
+    nav.rotate(i32::try_from(index).unwrap(), config);
+    assert_eq!(nav.press(config), MenuResponse::None);
+    assert_eq!(nav.depth(), 1);
+}
+
+#[test]
+fn menu_selection_wraps() {
+    let mut config = OperatorConfig::new();
+    let mut nav = MenuNavigator::new(&SETTINGS_MENU);
+    let count = SETTINGS_MENU.items.len();
+
+    assert_eq!(nav.rotate(-1, &mut config), MenuResponse::None);
+    assert_eq!(nav.index(), count - 1);
+    nav.rotate(2, &mut config);
+    assert_eq!(nav.index(), 1);
+    assert_eq!(config, OperatorConfig::new(), "moving never edits");
+}
+
+#[test]
+fn menu_edits_number_within_range() {
+    let mut config = OperatorConfig::new();
+    let mut nav = MenuNavigator::new(&SETTINGS_MENU);
+    open(&mut nav, &mut config, "Keyer");
+    assert_eq!(nav.menu().title, "KEYER");
+
+    // Speed: press to edit, turn to change
+    assert_eq!(nav.press(&mut config), MenuResponse::None);
+    assert!(nav.is_editing());
+    assert_eq!(nav.rotate(5, &mut config), MenuResponse::Changed);
+    assert_eq!(config.keyer_wpm, 25);
+    assert_eq!(nav.selected().unwrap().value(&config).as_str(), "25 WPM");
+
+    // Clamped at the top, with nothing left to change
+    nav.rotate(100, &mut config);
+    assert_eq!(config.keyer_wpm, 50);
+    assert_eq!(nav.rotate(1, &mut config), MenuResponse::None);
+
+    // Press finishes; turning moves the selection again
+    nav.press(&mut config);
+    assert!(!nav.is_editing());
+    nav.rotate(1, &mut config);
+    nav.press(&mut config);
+    nav.rotate(-50, &mut config);
+    assert_eq!(
+        config.sidetone_hz, 300,
+        "sidetone steps by 10 Hz down to 300"
+    );
+}
+
+#[test]
+fn menu_choice_and_toggle() {
+    let mut config = OperatorConfig::new();
+    let mut nav = MenuNavigator::new(&SETTINGS_MENU);
+
+    open(&mut nav, &mut config, "Receive");
+    nav.press(&mut config);
+    assert_eq!(nav.rotate(1, &mut config), MenuResponse::Changed);
+    assert_eq!(config.agc, AgcMode::Slow);
+    assert_eq!(
+        nav.rotate(1, &mut config),
+        MenuResponse::None,
+        "last choice"
+    );
+    nav.rotate(-3, &mut config);
+    assert_eq!(config.agc, AgcMode::Off);
+    assert_eq!(nav.selected().unwrap().value(&config).as_str(), "Off");
+
+    open(&mut nav, &mut config, "VOX");
+    assert_eq!(nav.press(&mut config), MenuResponse::Changed);
+    assert!(config.vox);
+    assert!(!nav.is_editing(), "toggles flip without editing");
+    assert_eq!(nav.selected().unwrap().value(&config).as_str(), "On");
+
+    open(&mut nav, &mut config, "Equalizer");
+    nav.rotate(1, &mut config);
+    assert_eq!(nav.press(&mut config), MenuResponse::Changed);
+    assert!(config.tx_eq);
+    assert!(!config.rx_eq);
+}
+
+#[test]
+fn menu_back_and_exit() {
+    let mut config = OperatorConfig::new();
+    let mut nav = MenuNavigator::new(&SETTINGS_MENU);
+    open(&mut nav, &mut config, "Display");
+
+    // Long press finishes the edit first, then goes up to the parent
+    nav.press(&mut config);
+    nav.rotate(-4, &mut config);
+    assert_eq!(config.brightness, 60);
+    assert_eq!(nav.back(), MenuResponse::None);
+    assert_eq!(nav.depth(), 1);
+    assert_eq!(nav.back(), MenuResponse::None);
+    assert_eq!(nav.depth(), 0);
+    assert_eq!(
+        nav.selected().unwrap().label,
+        "Display",
+        "selection restored"
+    );
+
+    // The Back item leaves the top level
+    nav.rotate(1, &mut config);
+    assert_eq!(nav.press(&mut config), MenuResponse::Exit);
+}
+
+#[test]
+fn settings_screen_starts_at_top() {
+    let mut ui = UiState::new();
+    ui.set_screen(Screen::Settings);
+    let nav = ui.settings_menu();
+    assert_eq!(nav.menu().title, SETTINGS_MENU.title);
+    assert_eq!((nav.depth(), nav.index()), (0, 0));
+    assert!(!nav.is_editing());
+}
+
+// ============================================================================
+// Mini Waterfall Tests
+// ============================================================================
+
+/// Waterfall row at a flat level (0-100 for -100..0 dBFS)
+fn flat_row(level: i8) -> WaterfallRow {
+    WaterfallRow {
+        timestamp: 0,
+        data: [level; 128],
+    }
+}
+
+#[test]
+fn waterfall_intensity_follows_reference_level() {
+    let mut view = WaterfallView::new();
+    view.set_ref_level_db(-20);
+    view.set_range_db(40);
+
+    // -60 dBFS and below is blank, -20 dBFS and above solid
+    assert_eq!(view.intensity(40), 0);
+    assert_eq!(view.intensity(80), LEVELS);
+    assert_eq!(view.intensity(100), LEVELS);
+    assert_eq!(view.intensity(60), LEVELS / 2);
+
+    // Lowering the reference brightens the same signal
+    let before = view.intensity(55);
+    view.adjust_ref_level(-2);
+    assert_eq!(view.ref_level_db(), -30);
+    assert!(view.intensity(55) > before);
+}
+
+#[test]
+fn waterfall_reference_level_is_clamped() {
+    let mut view = WaterfallView::default();
+    assert_eq!(view.ref_level_db(), DEFAULT_REF_LEVEL_DB);
+    view.adjust_ref_level(100);
+    assert_eq!(view.ref_level_db(), 0);
+    view.adjust_ref_level(-100);
+    assert_eq!(view.ref_level_db(), -90);
+    view.set_range_db(0);
+    assert_eq!(view.range_db(), 10);
+}
+
+#[test]
+fn waterfall_downsampling_keeps_peaks() {
+    let mut row = flat_row(10);
+    row.data[67] = 90;
+
+    // 128 columns into 64 pixels: the carrier lands in pixel 33
+    assert_eq!(WaterfallView::column_power(&row, 33, 64), 90);
+    assert_eq!(WaterfallView::column_power(&row, 32, 64), 10);
+    assert_eq!(WaterfallView::column_power(&row, 64, 64), 0, "outside the area");
+
+    // Wider than the row: columns repeat
+    assert_eq!(WaterfallView::column_power(&row, 134, 256), 90);
+}
+
+#[test]
+fn waterfall_dither_density_tracks_level() {
+    let view = WaterfallView::new();
+    let lit = |level: i8| {
+        let row = flat_row(level);
+        let mut count = 0;
+        for y in 0..4 {
+            let mut line = [false; 128];
+            view.render_line(&row, y, &mut line);
+            count += line.iter().filter(|&&p| p).count();
+        }
+        count
+    };
+
+    assert_eq!(lit(0), 0, "below the floor is blank");
+    assert_eq!(lit(100), 512, "above the reference is solid");
+    let (low, high) = (lit(40), lit(60));
+    assert!(0 < low && low < high && high < 512);
+}
+
+// ============================================================================
+// Redraw Scheduler Tests
+// ============================================================================
+
+#[test]
+fn geometry_sizes() {
+    assert_eq!(Geometry::SSD1306.buffer_len(), 1024);
+    assert_eq!(Geometry::SSD1306.tile_count(), 64);
+    assert_eq!(Geometry::ST7735.buffer_len(), 2560);
+    assert_eq!(Geometry::ST7735.tile_count(), 160);
+    assert!(Geometry::ST7735.is_supported());
+    assert!(!Geometry {
+        width: 320,
+        height: 240
+    }
+    .is_supported());
+}
+
+#[test]
+fn first_frame_sends_everything() {
+    let mut scheduler = RedrawScheduler::new(Geometry::SSD1306);
+    let frame = [0u8; 1024];
+    assert_eq!(scheduler.update(&frame), 0);
+    assert_eq!(scheduler.pending(), 64);
+
+    let regions = drain(&mut scheduler);
+    // Four tiles per region, two regions per page
+    assert_eq!(regions.len(), 16);
+    assert_eq!(
+        regions[0],
+        Region {
+            x: 0,
+            y: 0,
+            width: 64,
+            height: 8
+        }
+    );
+    assert_eq!(
+        regions[1],
+        Region {
+            x: 64,
+            y: 0,
+            width: 64,
+            height: 8
+        }
+    );
+    assert_eq!(regions[15].page(), 7);
+    assert!(scheduler.is_idle());
+
+    // An unchanged frame sends nothing
+    assert_eq!(scheduler.update(&frame), 0);
+    assert_eq!(scheduler.next_region(), None);
+}
+
+#[test]
+fn only_changed_tiles_are_sent() {
+    let mut scheduler = RedrawScheduler::new(Geometry::SSD1306);
+    let mut frame = [0u8; 1024];
+    let _ = drain(&mut scheduler);
+
+    // One byte in page 2, column 40 (tile 2 of that page)
+    frame[2 * 128 + 40] = 0xFF;
+    assert_eq!(scheduler.update(&frame), 1);
+    let regions = drain(&mut scheduler);
+    assert_eq!(
+        regions,
+        vec![Region {
+            x: 32,
+            y: 16,
+            width: 16,
+            height: 8
+        }]
+    );
+    assert_eq!(regions[0].bytes(Geometry::SSD1306), 288..304);
+
+    // Adjacent tiles merge, separate ones do not
+    frame[5 * 128] = 1;
+    frame[5 * 128 + 16] = 1;
+    frame[5 * 128 + 100] = 1;
+    assert_eq!(scheduler.update(&frame), 3);
+    let regions = drain(&mut scheduler);
+    assert_eq!(
+        regions,
+        vec![
+            Region {
+                x: 0,
+                y: 40,
+                width: 32,
+                height: 8
+            },
+            Region {
+                x: 96,
+                y: 40,
+                width: 16,
+                height: 8
+            },
+        ]
+    );
+}
+
+#[test]
+fn region_size_is_limited() {
+    let mut scheduler = RedrawScheduler::new(Geometry::ST7735);
+    scheduler.set_region_tiles(3);
+    let regions = drain(&mut scheduler);
+    // Ten tiles per page: 3 + 3 + 3 + 1
+    assert_eq!(regions.len(), 16 * 4);
+    assert!(regions.iter().all(|r| r.width <= 3 * TILE_WIDTH));
+    assert_eq!(
+        regions[3],
+        Region {
+            x: 144,
+            y: 0,
+            width: 16,
+            height: 8
+        }
+    );
+    assert_eq!(regions.last().unwrap().y, 120);
+}
+
+#[test]
+fn pending_tiles_survive_a_new_frame() {
+    let mut scheduler = RedrawScheduler::new(Geometry::SSD1306);
+    let mut frame = [0u8; 1024];
+    let _ = drain(&mut scheduler);
+
+    frame[0] = 1;
+    frame[1000] = 1;
+    scheduler.update(&frame);
+    let _ = scheduler.next_region();
+    assert_eq!(scheduler.pending(), 1);
+
+    // A new frame changing the sent tile again keeps the other pending
+    frame[0] = 2;
+    assert_eq!(scheduler.update(&frame), 1);
+    assert_eq!(scheduler.pending(), 2);
+
+    scheduler.invalidate();
+    assert_eq!(scheduler.pending(), 64);
+}
+
+#[test]
+fn frames_are_paced() {
+    let mut scheduler = RedrawScheduler::new(Geometry::SSD1306);
+    scheduler.set_frame_interval_ms(100);
+    assert!(scheduler.frame_due(1000));
+    assert!(!scheduler.frame_due(1050));
+    assert!(scheduler.frame_due(1100));
+    assert!(!scheduler.frame_due(1199));
+}
+
+// ============================================================================
+// UI State Tests
+// ============================================================================
+
+#[test]
+fn screen_navigation() {
+    let mut ui = UiState::new();
+    assert_eq!(ui.screen(), Screen::Main);
+    ui.mark_updated();
+
+    ui.set_screen(Screen::Memory);
+    assert_eq!(ui.screen(), Screen::Memory);
+    assert_eq!(ui.memory_index(), 0);
+    assert!(ui.needs_update());
+
+    ui.go_back();
+    assert_eq!(ui.screen(), Screen::Main);
+}
+
+#[test]
+fn pitch_offset_redraws_on_real_change() {
+    let mut ui = UiState::new();
+    ui.mark_updated();
+
+    ui.set_pitch_offset(Some(40));
+    assert!(ui.needs_update());
+    ui.mark_updated();
+
+    // Jitter of a few Hz is not worth a redraw
+    ui.set_pitch_offset(Some(43));
+    assert!(!ui.needs_update());
+    assert_eq!(ui.pitch_offset(), Some(40));
+    ui.set_pitch_offset(Some(35));
+    assert!(ui.needs_update());
+    ui.mark_updated();
+
+    ui.set_pitch_offset(None);
+    assert!(ui.needs_update());
+    assert_eq!(ui.pitch_offset(), None);
+}
+
+#[test]
+fn tx_verdict_redraws_on_change() {
+    let mut ui = UiState::new();
+    ui.mark_updated();
+    ui.set_tx_verdict(TxVerdict::Clear);
+    assert!(!ui.needs_update());
+
+    ui.set_tx_verdict(TxVerdict::Denied(TxReason::OutOfBand));
+    assert!(ui.needs_update());
+    assert_eq!(ui.tx_verdict().reason(), Some(TxReason::OutOfBand));
+}
+
+#[test]
+fn practice_screen_follows_session() {
+    assert!(MAIN_MENU
+        .iter()
+        .any(|item| matches!(item.action, MenuAction::GoTo(Screen::Practice))));
+
+    let mut ui = UiState::new();
+    let mut session = PracticeSession::new(20);
+    ui.set_practice(&session);
+    ui.mark_updated();
+    ui.set_practice(&session);
+    assert!(!ui.needs_update());
+
+    // "E" at 20 WPM: a 60 ms dit then a character space
+    for key in [true, false] {
+        for _ in 0..if key { 60 } else { 200 } {
+            session.update(key, 1.0);
+        }
+    }
+    ui.set_practice(&session);
+    assert!(ui.needs_update());
+    assert_eq!(ui.practice_text(), "E");
+    assert_eq!(ui.practice_report().elements, 1);
+    assert_eq!(ui.practice_report().target_wpm, 20);
+}
+
+#[test]
+fn actions_map_to_radio_events() {
+    let freq = Frequency::from_hz(7_030_000).unwrap();
+    assert!(matches!(
+        UiAction::Tune(-3).to_radio_event(false),
+        Some(RadioEvent::Tune(-3))
+    ));
+    assert!(matches!(
+        UiAction::SetFrequency(freq).to_radio_event(false),
+        Some(RadioEvent::SetFrequency(f)) if f == freq
+    ));
+    assert!(matches!(
+        UiAction::TogglePtt.to_radio_event(false),
+        Some(RadioEvent::StartTx)
+    ));
+    assert!(matches!(
+        UiAction::TogglePtt.to_radio_event(true),
+        Some(RadioEvent::StopTx)
+    ));
+    assert!(UiAction::RecallMemory(5).to_radio_event(false).is_none());
+    assert!(UiAction::PlayMessage(0).to_radio_event(false).is_none());
+    assert!(matches!(
+        UiAction::Execute(ZERO_BEAT_COMMAND).to_radio_event(false),
+        Some(RadioEvent::AutoTune)
+    ));
+    assert!(UiAction::Execute("Calibrate").to_radio_event(false).is_none());
+}
+
+#[test]
+fn actions_round_trip_through_remote() {
+    for action in [
+        UiAction::RecallMemory(7),
+        UiAction::NextStep,
+        UiAction::Tune(12),
+        UiAction::Execute(ZERO_BEAT_COMMAND),
+    ] {
+        let back = UiAction::from_remote(&action.to_remote()).unwrap();
+        assert_eq!(format!("{back:?}"), format!("{action:?}"));
+    }
+}
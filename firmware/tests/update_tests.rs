@@ -0,0 +1,148 @@
+//! Tests for firmware version reporting and bootloader entry guards
+
+use sdr_firmware::protocol::{CatParser, CatResponse};
+use sdr_firmware::update::{
+    capability, BootloaderGuard, FirmwareVersion, BUTTON_HOLD_MS, CONFIRM_WINDOW_MS,
+};
+
+// ============================================================================
+// Version Tests
+// ============================================================================
+
+#[test]
+fn current_version_matches_cargo() {
+    let v = FirmwareVersion::CURRENT;
+    let expected = format!("{}.{}.{}", v.major, v.minor, v.patch);
+    assert_eq!(expected, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn version_ordering() {
+    assert!(FirmwareVersion::new(1, 2, 3) < FirmwareVersion::new(1, 3, 0));
+    assert!(FirmwareVersion::new(2, 0, 0) > FirmwareVersion::new(1, 9, 9));
+}
+
+#[test]
+fn version_compatibility() {
+    let v1 = FirmwareVersion::new(1, 0, 0);
+    assert!(v1.is_compatible(FirmwareVersion::new(1, 4, 2)));
+    assert!(!v1.is_compatible(FirmwareVersion::new(2, 0, 0)));
+
+    // Pre-1.0 releases must match minor version
+    let v0 = FirmwareVersion::new(0, 1, 0);
+    assert!(v0.is_compatible(FirmwareVersion::new(0, 1, 5)));
+    assert!(!v0.is_compatible(FirmwareVersion::new(0, 2, 0)));
+}
+
+#[test]
+fn capabilities_include_dfu() {
+    assert_ne!(capability::CURRENT & capability::DFU, 0);
+}
+
+#[test]
+fn capabilities_report_display_only_when_built() {
+    assert_eq!(
+        capability::CURRENT & capability::DISPLAY != 0,
+        cfg!(feature = "display")
+    );
+}
+
+// ============================================================================
+// Bootloader Guard Tests
+// ============================================================================
+
+#[test]
+fn confirm_without_arm_rejected() {
+    let mut guard = BootloaderGuard::new();
+    assert!(!guard.confirm(100));
+}
+
+#[test]
+fn arm_then_confirm() {
+    let mut guard = BootloaderGuard::new();
+    guard.arm(1_000);
+    assert!(guard.is_armed(2_000));
+    assert!(guard.confirm(2_000));
+    // Confirm consumes the request
+    assert!(!guard.confirm(2_100));
+}
+
+#[test]
+fn confirm_window_expires() {
+    let mut guard = BootloaderGuard::new();
+    guard.arm(1_000);
+    assert!(!guard.confirm(1_000 + CONFIRM_WINDOW_MS + 1));
+}
+
+#[test]
+fn cancel_disarms() {
+    let mut guard = BootloaderGuard::new();
+    guard.arm(0);
+    guard.cancel();
+    assert!(!guard.is_armed(10));
+}
+
+#[test]
+fn buttons_must_be_held() {
+    let mut guard = BootloaderGuard::new();
+    assert!(!guard.update_buttons(true, true, BUTTON_HOLD_MS - 10));
+    assert!(guard.update_buttons(true, true, 10));
+}
+
+#[test]
+fn button_release_resets_hold() {
+    let mut guard = BootloaderGuard::new();
+    guard.update_buttons(true, true, BUTTON_HOLD_MS - 10);
+    assert!(!guard.update_buttons(true, false, 10));
+    assert!(!guard.update_buttons(true, true, 20));
+}
+
+#[test]
+fn buttons_held_latch_request() {
+    let mut guard = BootloaderGuard::new();
+    guard.update_buttons(true, true, BUTTON_HOLD_MS);
+    guard.update_buttons(false, false, 10);
+    assert!(guard.is_requested());
+}
+
+/// Feed CAT text through the parser and the guard, returning the replies
+fn drive_cat(guard: &mut BootloaderGuard, text: &str, now_ms: u32) -> String {
+    let mut parser = CatParser::new();
+    let mut replies = String::new();
+    for byte in text.bytes() {
+        if let Some(command) = parser.feed(byte) {
+            let mut response = CatResponse::new();
+            assert!(guard.handle_cat(&command, now_ms, &mut response));
+            replies.push_str(response.as_str());
+        }
+    }
+    replies
+}
+
+#[test]
+fn cat_sequence_requests_bootloader() {
+    let mut guard = BootloaderGuard::new();
+    assert_eq!(drive_cat(&mut guard, "ZB1;", 1_000), "ZB1;");
+    assert!(!guard.is_requested());
+    assert_eq!(drive_cat(&mut guard, "ZB2;", 2_000), "ZB2;");
+    assert!(guard.is_requested());
+}
+
+#[test]
+fn cat_confirm_without_arm_is_refused() {
+    let mut guard = BootloaderGuard::new();
+    assert_eq!(drive_cat(&mut guard, "ZB2;", 0), "?;");
+    assert_eq!(drive_cat(&mut guard, "ZB1;", 0), "ZB1;");
+    assert_eq!(drive_cat(&mut guard, "ZB2;", CONFIRM_WINDOW_MS + 1), "?;");
+    assert!(!guard.is_requested());
+}
+
+#[test]
+fn cat_ignores_other_commands() {
+    let mut parser = CatParser::new();
+    let command = b"ZV;".iter().find_map(|&b| parser.feed(b)).unwrap();
+    let mut guard = BootloaderGuard::new();
+    let mut response = CatResponse::new();
+    assert!(!guard.handle_cat(&command, 0, &mut response));
+    assert_eq!(response.as_str(), "");
+}
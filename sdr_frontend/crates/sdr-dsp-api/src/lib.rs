@@ -0,0 +1,381 @@
+//! Versioned API codes shared by the SDR DSP and its UI
+//!
+//! The numeric values of the enums in this crate are part of the
+//! contract between `sdr-dsp-wasm` and the JavaScript/Leptos side, so
+//! both build against this one definition rather than copying the
+//! codes. Existing values must never be renumbered; new entries are
+//! appended and announced through a minor version bump and a capability
+//! bit. The [`capability`] word is full, so new bits go in
+//! [`extended_capability`].
+//!
+//! With the `wasm-bindgen` feature the enums are also exported to
+//! JavaScript.
+
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+use sdr_dsp_core::DisplayScale;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// API major version (incremented on incompatible changes).
+pub const API_VERSION_MAJOR: u16 = 2;
+
+/// API minor version (incremented when features are added).
+pub const API_VERSION_MINOR: u16 = 0;
+
+/// Demodulation mode with stable numeric codes.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DemodMode {
+    /// Lower sideband.
+    Lsb = 0,
+    /// Upper sideband.
+    #[default]
+    Usb = 1,
+    /// Continuous wave.
+    Cw = 2,
+    /// Amplitude modulation.
+    Am = 3,
+    /// Frequency modulation.
+    Fm = 4,
+}
+
+impl DemodMode {
+    /// Convert a raw mode code, rejecting unknown values.
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Lsb),
+            1 => Some(Self::Usb),
+            2 => Some(Self::Cw),
+            3 => Some(Self::Am),
+            4 => Some(Self::Fm),
+            _ => None,
+        }
+    }
+
+    /// Get the stable numeric code.
+    #[must_use]
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Default audio filter bandwidth for this mode in Hz.
+    #[must_use]
+    pub fn default_bandwidth(self) -> f32 {
+        match self {
+            Self::Lsb | Self::Usb => 2700.0,
+            Self::Cw => 500.0,
+            Self::Am => 6000.0,
+            Self::Fm => 15000.0,
+        }
+    }
+}
+
+/// Tunable processor parameter with stable numeric codes.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parameter {
+    /// Mixer frequency offset in Hz.
+    FrequencyOffset = 0,
+    /// Audio filter bandwidth in Hz.
+    FilterBandwidth = 1,
+    /// AGC attack time in milliseconds.
+    AgcAttack = 2,
+    /// AGC decay time in milliseconds.
+    AgcDecay = 3,
+    /// AGC hang time in milliseconds.
+    AgcHang = 4,
+    /// AGC hang threshold in dBFS.
+    AgcHangThreshold = 5,
+    /// AGC output slope in dB per 10 dB above the knee.
+    AgcSlope = 6,
+    /// AGC maximum gain in dB.
+    AgcMaxGain = 7,
+    /// AGC dual-rate detector (0 = off, 1 = on).
+    AgcDualRate = 8,
+    /// S-meter calibration offset in dB.
+    SmeterOffset = 9,
+    /// S-meter calibration slope (1.0 = nominal).
+    SmeterSlope = 10,
+    /// Spectrum frames averaged (1 = none).
+    SpectrumAveraging = 11,
+    /// Spectrum dBFS to dBm gain in dB.
+    SpectrumGain = 12,
+    /// Noise reduction level (0 = off to 10).
+    NoiseReduction = 13,
+    /// Squelch (0 = off, 1 = on).
+    SquelchEnabled = 14,
+    /// Squelch opening level in dBm.
+    SquelchThreshold = 15,
+    /// Fine tuning (RIT) offset in Hz.
+    FineTune = 16,
+    /// IF shift of the audio passband in Hz.
+    IfShift = 17,
+    /// First notch (0 = off, 1 = on).
+    Notch1Enabled = 18,
+    /// First notch frequency in Hz.
+    Notch1Frequency = 19,
+    /// First notch width in Hz.
+    Notch1Width = 20,
+    /// Second notch (0 = off, 1 = on).
+    Notch2Enabled = 21,
+    /// Second notch frequency in Hz.
+    Notch2Frequency = 22,
+    /// Second notch width in Hz.
+    Notch2Width = 23,
+    /// IQ noise blanker (0 = off, 1 = on).
+    BlankerEnabled = 24,
+    /// Noise blanker threshold as a magnitude ratio over the average.
+    BlankerThreshold = 25,
+    /// Noise blanker width in microseconds.
+    BlankerWidth = 26,
+    /// Spectral subtraction strength (0 = off to 10).
+    SpectralNr = 27,
+    /// Graphic equalizer (0 = off, 1 = on).
+    EqEnabled = 28,
+    /// Equalizer 200 Hz band gain in dB.
+    EqBand1Gain = 29,
+    /// Equalizer 500 Hz band gain in dB.
+    EqBand2Gain = 30,
+    /// Equalizer 1 kHz band gain in dB.
+    EqBand3Gain = 31,
+    /// Equalizer 2 kHz band gain in dB.
+    EqBand4Gain = 32,
+    /// Equalizer 3 kHz band gain in dB.
+    EqBand5Gain = 33,
+    /// IQ balance Q gain correction in dB.
+    IqGain = 34,
+    /// IQ balance phase correction in degrees.
+    IqPhase = 35,
+    /// Adaptive IQ balance (0 = off, 1 = on).
+    IqAdaptive = 36,
+    /// Spectrum DC notch width in Hz either side of DC (0 = off).
+    SpectrumDcNotch = 37,
+    /// Waterfall reference (brightest) level in dBm.
+    WaterfallRefLevel = 38,
+    /// Waterfall span from darkest to brightest in dB.
+    WaterfallRange = 39,
+    /// Waterfall [`WaterfallScale`] code.
+    WaterfallScale = 40,
+    /// Waterfall levels follow the noise floor (0 = off, 1 = on).
+    WaterfallAutoLevel = 41,
+    /// Most spectrum peaks listed per frame (0 = off).
+    MaxPeaks = 42,
+    /// Spectrum peak threshold above the noise floor in dB.
+    PeakThreshold = 43,
+}
+
+impl Parameter {
+    /// Convert a raw parameter code, rejecting unknown values.
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::FrequencyOffset),
+            1 => Some(Self::FilterBandwidth),
+            2 => Some(Self::AgcAttack),
+            3 => Some(Self::AgcDecay),
+            4 => Some(Self::AgcHang),
+            5 => Some(Self::AgcHangThreshold),
+            6 => Some(Self::AgcSlope),
+            7 => Some(Self::AgcMaxGain),
+            8 => Some(Self::AgcDualRate),
+            9 => Some(Self::SmeterOffset),
+            10 => Some(Self::SmeterSlope),
+            11 => Some(Self::SpectrumAveraging),
+            12 => Some(Self::SpectrumGain),
+            13 => Some(Self::NoiseReduction),
+            14 => Some(Self::SquelchEnabled),
+            15 => Some(Self::SquelchThreshold),
+            16 => Some(Self::FineTune),
+            17 => Some(Self::IfShift),
+            18 => Some(Self::Notch1Enabled),
+            19 => Some(Self::Notch1Frequency),
+            20 => Some(Self::Notch1Width),
+            21 => Some(Self::Notch2Enabled),
+            22 => Some(Self::Notch2Frequency),
+            23 => Some(Self::Notch2Width),
+            24 => Some(Self::BlankerEnabled),
+            25 => Some(Self::BlankerThreshold),
+            26 => Some(Self::BlankerWidth),
+            27 => Some(Self::SpectralNr),
+            28 => Some(Self::EqEnabled),
+            29 => Some(Self::EqBand1Gain),
+            30 => Some(Self::EqBand2Gain),
+            31 => Some(Self::EqBand3Gain),
+            32 => Some(Self::EqBand4Gain),
+            33 => Some(Self::EqBand5Gain),
+            34 => Some(Self::IqGain),
+            35 => Some(Self::IqPhase),
+            36 => Some(Self::IqAdaptive),
+            37 => Some(Self::SpectrumDcNotch),
+            38 => Some(Self::WaterfallRefLevel),
+            39 => Some(Self::WaterfallRange),
+            40 => Some(Self::WaterfallScale),
+            41 => Some(Self::WaterfallAutoLevel),
+            42 => Some(Self::MaxPeaks),
+            43 => Some(Self::PeakThreshold),
+            _ => None,
+        }
+    }
+}
+
+/// Waterfall level scaling with stable numeric codes.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum WaterfallScale {
+    /// Even steps in power.
+    Linear = 0,
+    /// Even steps in dB.
+    #[default]
+    Log = 1,
+    /// Even steps in amplitude.
+    Sqrt = 2,
+}
+
+impl WaterfallScale {
+    /// Convert a raw scale code, rejecting unknown values.
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Linear),
+            1 => Some(Self::Log),
+            2 => Some(Self::Sqrt),
+            _ => None,
+        }
+    }
+
+    /// Get the stable numeric code.
+    #[must_use]
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl From<WaterfallScale> for DisplayScale {
+    fn from(scale: WaterfallScale) -> Self {
+        match scale {
+            WaterfallScale::Linear => Self::Linear,
+            WaterfallScale::Log => Self::Log,
+            WaterfallScale::Sqrt => Self::Sqrt,
+        }
+    }
+}
+
+/// Capability bit flags reported by the DSP's `get_capabilities`.
+pub mod capability {
+    /// SSB demodulation (LSB/USB).
+    pub const DEMOD_SSB: u32 = 1 << 0;
+    /// CW demodulation.
+    pub const DEMOD_CW: u32 = 1 << 1;
+    /// AM demodulation.
+    pub const DEMOD_AM: u32 = 1 << 2;
+    /// FM demodulation.
+    pub const DEMOD_FM: u32 = 1 << 3;
+    /// FFT spectrum output buffer.
+    pub const SPECTRUM: u32 = 1 << 4;
+    /// S-meter reading.
+    pub const SMETER: u32 = 1 << 5;
+    /// Adjustable AGC timing.
+    pub const AGC: u32 = 1 << 6;
+    /// Generic `set_parameter`/`get_parameter` access.
+    pub const PARAMETERS: u32 = 1 << 7;
+    /// AGC hang threshold, slope, maximum gain and dual-rate detector.
+    pub const AGC_ADVANCED: u32 = 1 << 8;
+    /// Calibrated S-meter in dBm with per-band offset and slope.
+    pub const SMETER_DBM: u32 = 1 << 9;
+    /// Averaged spectrum calibrated in dBm, with noise floor estimate.
+    pub const SPECTRUM_DBM: u32 = 1 << 10;
+    /// IQ file playback (`process_iq` at any input rate) and raw IQ recording.
+    pub const IQ_FILE: u32 = 1 << 11;
+    /// Parallel PSK31 decoders at chosen audio offsets.
+    pub const PSK31_BANK: u32 = 1 << 12;
+    /// PSK31 signal quality (phase SNR, EVM and idle IMD) per decoder.
+    pub const SIGNAL_QUALITY: u32 = 1 << 13;
+    /// PSK31 AFC bandwidth and pull-in range, with carrier lock state.
+    pub const AFC_CONTROL: u32 = 1 << 14;
+    /// SSTV image decoding (Scottie S1/S2, Martin M1/M2).
+    pub const SSTV: u32 = 1 << 15;
+    /// APRS (1200 baud AFSK AX.25) packet decoding.
+    pub const APRS: u32 = 1 << 16;
+    /// JS8 style keyboard-to-keyboard frame decoding.
+    pub const JS8: u32 = 1 << 17;
+    /// Feld Hell pixel columns for display.
+    pub const HELL: u32 = 1 << 18;
+    /// CW skimmer decoding every CW signal in the passband.
+    pub const CW_SKIMMER: u32 = 1 << 19;
+    /// Long FIR audio filter by FFT convolution, with its latency.
+    pub const FFT_FILTER: u32 = 1 << 20;
+    /// Buffer and spectrum sizes set at run time, and `process` at any input rate.
+    pub const CONFIGURABLE_BUFFERS: u32 = 1 << 21;
+    /// Buffers read and written as typed arrays, and `process_samples`.
+    pub const TYPED_ARRAYS: u32 = 1 << 22;
+    /// LMS audio noise reduction.
+    pub const NOISE_REDUCTION: u32 = 1 << 23;
+    /// Level squelch on the calibrated S-meter.
+    pub const SQUELCH: u32 = 1 << 24;
+    /// `apply_config` settings objects and `get_status`.
+    pub const CONFIG_OBJECTS: u32 = 1 << 25;
+    /// Independent receiver slices mixed into the audio output.
+    pub const RECEIVER_SLICES: u32 = 1 << 26;
+    /// `TxProcessor`: microphone audio to SSB I/Q.
+    pub const TX_DSP: u32 = 1 << 27;
+    /// Fine tuning (RIT) offset separate from the frequency offset.
+    pub const FINE_TUNE: u32 = 1 << 28;
+    /// IF shift and passband center controls.
+    pub const PASSBAND_TUNING: u32 = 1 << 29;
+    /// Two independent manual notches.
+    pub const DUAL_NOTCH: u32 = 1 << 30;
+    /// IQ-domain impulse noise blanker ahead of demodulation.
+    pub const NOISE_BLANKER: u32 = 1 << 31;
+}
+
+/// Capability bit flags reported by the DSP's `get_extended_capabilities`.
+pub mod extended_capability {
+    /// FFT spectral subtraction noise reduction.
+    pub const SPECTRAL_NR: u32 = 1 << 0;
+    /// Five-band graphic equalizer on receive and transmit audio.
+    pub const EQUALIZER: u32 = 1 << 1;
+    /// Passband signal power, noise floor and SNR of the audio.
+    pub const SNR_METER: u32 = 1 << 2;
+    /// IQ gain and phase balance (image rejection), manual or adaptive.
+    pub const IQ_BALANCE: u32 = 1 << 3;
+    /// Interpolation over the spectrum DC spike, left out of autoscaling.
+    pub const SPECTRUM_DC_NOTCH: u32 = 1 << 4;
+    /// Waterfall levels normalized in WASM: reference, range, scale and auto-level.
+    pub const WATERFALL_LEVELS: u32 = 1 << 5;
+    /// Strongest spectrum peaks listed per frame for signal browsing.
+    pub const SPECTRUM_PEAKS: u32 = 1 << 6;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_codes_round_trip() {
+        for code in 0..5 {
+            let mode = DemodMode::from_code(code).unwrap();
+            assert_eq!(mode.code(), code);
+        }
+        assert_eq!(DemodMode::from_code(5), None);
+        for code in 0..3 {
+            assert_eq!(WaterfallScale::from_code(code).unwrap().code(), code);
+        }
+        assert_eq!(WaterfallScale::from_code(3), None);
+        assert_eq!(DisplayScale::from(WaterfallScale::default()), DisplayScale::Log);
+    }
+
+    #[test]
+    fn test_parameter_codes() {
+        assert_eq!(Parameter::from_code(1), Some(Parameter::FilterBandwidth));
+        assert_eq!(Parameter::from_code(8), Some(Parameter::AgcDualRate));
+        assert_eq!(Parameter::from_code(10), Some(Parameter::SmeterSlope));
+        assert_eq!(Parameter::from_code(15), Some(Parameter::SquelchThreshold));
+        assert_eq!(Parameter::from_code(99), None);
+    }
+}
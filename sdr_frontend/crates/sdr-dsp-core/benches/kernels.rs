@@ -0,0 +1,115 @@
+//! Kernel benchmarks.
+//!
+//! Times the block kernels against the per-sample code they replace, at
+//! the sizes the browser chain uses: a 128-sample AudioWorklet quantum
+//! and a 511-tap audio filter. `cargo bench` on the host measures the
+//! scalar kernels; for the SIMD ones build the bench for `wasm32-wasi`
+//! with `--features simd` and `-C target-feature=+simd128` and run it
+//! under a WebAssembly runtime.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sdr_dsp_core::convolve::bandpass_taps;
+use sdr_dsp_core::filter::{Biquad, BiquadIq, FirFilter};
+use sdr_dsp_core::{kernels, FftConvolver, IqSample};
+
+/// AudioWorklet quantum
+const BLOCK: usize = 128;
+
+/// Audio filter length
+const TAPS: usize = 511;
+
+fn test_signal(n: usize) -> f32 {
+    ((n * 37 % 101) as f32 - 50.0) / 50.0
+}
+
+fn bench_dot(c: &mut Criterion) {
+    let a: Vec<f32> = (0..TAPS).map(test_signal).collect();
+    let b: Vec<f32> = (0..TAPS).map(|n| test_signal(n + 11)).collect();
+    let mut group = c.benchmark_group("dot_511");
+    group.bench_function("kernel", |bench| {
+        bench.iter(|| kernels::dot(black_box(&a), black_box(&b)));
+    });
+    group.bench_function("naive", |bench| {
+        bench.iter(|| {
+            black_box(&a)
+                .iter()
+                .zip(black_box(&b))
+                .fold(0.0f32, |acc, (x, y)| acc + x * y)
+        });
+    });
+    group.finish();
+}
+
+fn bench_mix(c: &mut Criterion) {
+    let lo: Vec<IqSample> = (0..BLOCK)
+        .map(|n| {
+            let phase = n as f32 * 0.1;
+            IqSample::new(phase.cos(), phase.sin())
+        })
+        .collect();
+    let input: Vec<IqSample> = (0..BLOCK)
+        .map(|n| IqSample::new(test_signal(n), test_signal(n + 5)))
+        .collect();
+    let mut group = c.benchmark_group("mix_128");
+    group.bench_function("kernel", |bench| {
+        let mut block = input.clone();
+        bench.iter(|| kernels::complex_multiply(black_box(&mut block), black_box(&lo)));
+    });
+    group.bench_function("per_sample", |bench| {
+        let mut block = input.clone();
+        bench.iter(|| {
+            for (s, l) in black_box(&mut block).iter_mut().zip(black_box(&lo)) {
+                *s = IqSample::new(s.i * l.i - s.q * l.q, s.i * l.q + s.q * l.i);
+            }
+        });
+    });
+    group.finish();
+}
+
+fn bench_biquad(c: &mut Criterion) {
+    let input: Vec<IqSample> = (0..BLOCK)
+        .map(|n| IqSample::new(test_signal(n), test_signal(n + 5)))
+        .collect();
+    let mut group = c.benchmark_group("biquad_iq_128");
+    group.bench_function("block", |bench| {
+        let mut filter = BiquadIq::lowpass(48000.0, 3000.0, 0.707);
+        let mut block = input.clone();
+        bench.iter(|| filter.process_block(black_box(&mut block)));
+    });
+    group.bench_function("per_sample", |bench| {
+        let mut filter = BiquadIq::lowpass(48000.0, 3000.0, 0.707);
+        let mut block = input.clone();
+        bench.iter(|| {
+            for s in black_box(&mut block).iter_mut() {
+                *s = filter.process(*s);
+            }
+        });
+    });
+    group.bench_function("real_block", |bench| {
+        let mut filter = Biquad::lowpass(48000.0, 3000.0, 0.707);
+        let mut block: Vec<f32> = input.iter().map(|s| s.i).collect();
+        bench.iter(|| filter.process_block(black_box(&mut block)));
+    });
+    group.finish();
+}
+
+fn bench_fir(c: &mut Criterion) {
+    let mut taps = [0.0; TAPS];
+    bandpass_taps(48000.0, 0.0, 2700.0, &mut taps);
+    let input: Vec<f32> = (0..BLOCK).map(test_signal).collect();
+    let mut group = c.benchmark_group("fir_511_128");
+    group.bench_function("direct", |bench| {
+        let mut filter = FirFilter::new(taps);
+        let mut block = input.clone();
+        bench.iter(|| filter.process_block(black_box(&mut block)));
+    });
+    group.bench_function("fft", |bench| {
+        let mut filter = FftConvolver::new(&taps).unwrap();
+        let mut block = input.clone();
+        bench.iter(|| filter.process_block(black_box(&mut block)));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_dot, bench_mix, bench_biquad, bench_fir);
+criterion_main!(benches);
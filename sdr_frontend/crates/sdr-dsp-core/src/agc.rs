@@ -6,6 +6,14 @@
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
+use crate::units::{self, S9_DBM};
+
+/// Default hang threshold in dBFS.
+pub const DEFAULT_HANG_THRESHOLD_DB: f32 = -80.0;
+
+/// Default release time of the fast detector in dual-rate mode, in milliseconds.
+pub const DEFAULT_FAST_DECAY_MS: f32 = 20.0;
+
 /// AGC configuration parameters.
 #[derive(Clone, Copy, Debug)]
 pub struct AgcConfig {
@@ -17,22 +25,23 @@ pub struct AgcConfig {
     pub decay_ms: f32,
     /// Hang time before decay starts, in milliseconds
     pub hang_ms: f32,
+    /// Input level below which hang is skipped (dBFS), so noise decays freely
+    pub hang_threshold_db: f32,
+    /// Output rise in dB per 10 dB of input above the knee (0 = flat)
+    pub slope_db: f32,
     /// Minimum gain (prevents over-amplification of noise)
     pub min_gain: f32,
-    /// Maximum gain
+    /// Maximum gain; the knee sits where this gain reaches the target
     pub max_gain: f32,
+    /// Dual-rate detector: a fast loop catches impulses and recovers quickly
+    pub dual_rate: bool,
+    /// Release time of the fast loop in milliseconds
+    pub fast_decay_ms: f32,
 }
 
 impl Default for AgcConfig {
     fn default() -> Self {
-        Self {
-            target_level: 0.3,
-            attack_ms: 5.0,
-            decay_ms: 500.0,
-            hang_ms: 200.0,
-            min_gain: 0.01,
-            max_gain: 1000.0,
-        }
+        Self::medium()
     }
 }
 
@@ -45,8 +54,12 @@ impl AgcConfig {
             attack_ms: 2.0,
             decay_ms: 100.0,
             hang_ms: 50.0,
+            hang_threshold_db: DEFAULT_HANG_THRESHOLD_DB,
+            slope_db: 0.0,
             min_gain: 0.01,
             max_gain: 1000.0,
+            dual_rate: false,
+            fast_decay_ms: DEFAULT_FAST_DECAY_MS,
         }
     }
 
@@ -58,8 +71,12 @@ impl AgcConfig {
             attack_ms: 5.0,
             decay_ms: 500.0,
             hang_ms: 200.0,
+            hang_threshold_db: DEFAULT_HANG_THRESHOLD_DB,
+            slope_db: 0.0,
             min_gain: 0.01,
             max_gain: 1000.0,
+            dual_rate: false,
+            fast_decay_ms: DEFAULT_FAST_DECAY_MS,
         }
     }
 
@@ -71,8 +88,45 @@ impl AgcConfig {
             attack_ms: 10.0,
             decay_ms: 2000.0,
             hang_ms: 500.0,
+            hang_threshold_db: DEFAULT_HANG_THRESHOLD_DB,
+            slope_db: 0.0,
             min_gain: 0.01,
             max_gain: 1000.0,
+            dual_rate: false,
+            fast_decay_ms: DEFAULT_FAST_DECAY_MS,
+        }
+    }
+
+    /// Set the maximum gain in dB.
+    #[must_use]
+    pub fn with_max_gain_db(mut self, max_gain_db: f32) -> Self {
+        self.max_gain = 10.0_f32.powf(max_gain_db / 20.0).max(self.min_gain);
+        self
+    }
+
+    /// Get the maximum gain in dB.
+    #[must_use]
+    pub fn max_gain_db(&self) -> f32 {
+        20.0 * self.max_gain.log10()
+    }
+
+    /// Input level (linear) at which the maximum gain brings it to the target.
+    #[must_use]
+    pub fn knee(&self) -> f32 {
+        self.target_level / self.max_gain
+    }
+
+    /// Output level the AGC aims for at a given input level.
+    ///
+    /// Flat at the target for a zero slope; otherwise rises by
+    /// `slope_db` for every 10 dB the input is above the knee.
+    #[must_use]
+    pub fn output_level(&self, input_level: f32) -> f32 {
+        let knee = self.knee();
+        if self.slope_db <= 0.0 || input_level <= knee {
+            self.target_level
+        } else {
+            self.target_level * (input_level / knee).powf(self.slope_db / 10.0)
         }
     }
 }
@@ -80,47 +134,64 @@ impl AgcConfig {
 /// Automatic Gain Control.
 ///
 /// Maintains consistent output level by adjusting gain based on input signal level.
-/// Features attack/decay time constants and hang time.
+/// Features attack/decay time constants, hang time gated by a hang threshold,
+/// an output slope above the knee and an optional dual-rate detector. In
+/// dual-rate mode the main loop follows the average level, so short impulses
+/// barely move it, while a fast loop with instant attack and a short release
+/// stops the impulses themselves without leaving a long gain hole behind.
 #[derive(Clone, Debug)]
 pub struct Agc {
     config: AgcConfig,
     /// Current gain
     gain: f32,
+    /// Main loop gain
+    slow_gain: f32,
+    /// Fast loop gain (dual-rate only)
+    fast_gain: f32,
     /// Attack coefficient (per sample)
     attack_coeff: f32,
     /// Decay coefficient (per sample)
     decay_coeff: f32,
+    /// Fast loop release coefficient (per sample)
+    fast_decay_coeff: f32,
     /// Hang counter (samples remaining)
     hang_counter: u32,
     /// Hang samples
     hang_samples: u32,
+    /// Hang threshold (linear)
+    hang_threshold: f32,
     /// Sample rate
     sample_rate: f32,
     /// Peak detector for input level
     peak_level: f32,
     /// Peak detector coefficient
     peak_coeff: f32,
+    /// Average detector for the dual-rate main loop
+    avg_level: f32,
 }
 
 impl Agc {
     /// Create a new AGC with given configuration.
     #[must_use]
     pub fn new(sample_rate: f32, config: AgcConfig) -> Self {
-        let attack_coeff = Self::time_to_coeff(config.attack_ms, sample_rate);
-        let decay_coeff = Self::time_to_coeff(config.decay_ms, sample_rate);
-        let hang_samples = (config.hang_ms * sample_rate / 1000.0) as u32;
-
-        Self {
+        let mut agc = Self {
             config,
             gain: 1.0,
-            attack_coeff,
-            decay_coeff,
+            slow_gain: 1.0,
+            fast_gain: 1.0,
+            attack_coeff: 1.0,
+            decay_coeff: 1.0,
+            fast_decay_coeff: 1.0,
             hang_counter: 0,
-            hang_samples,
+            hang_samples: 0,
+            hang_threshold: 0.0,
             sample_rate,
             peak_level: 0.0,
             peak_coeff: Self::time_to_coeff(10.0, sample_rate), // 10ms peak detector
-        }
+            avg_level: 0.0,
+        };
+        agc.set_config(config);
+        agc
     }
 
     /// Convert time constant (ms) to exponential coefficient.
@@ -132,6 +203,15 @@ impl Agc {
         }
     }
 
+    /// Gain that brings a detected level to the configured output level.
+    fn desired_gain(&self, level: f32) -> f32 {
+        if level > 1e-10 {
+            (self.config.output_level(level) / level).clamp(self.config.min_gain, self.config.max_gain)
+        } else {
+            self.config.max_gain
+        }
+    }
+
     /// Process a single sample.
     #[inline]
     pub fn process(&mut self, input: f32) -> f32 {
@@ -144,29 +224,54 @@ impl Agc {
             self.peak_level += self.peak_coeff * (input_abs - self.peak_level);
         }
 
-        // Calculate desired gain
-        let desired_gain = if self.peak_level > 1e-10 {
-            (self.config.target_level / self.peak_level).clamp(self.config.min_gain, self.config.max_gain)
+        // Main loop detector: peak, or average scaled to sine peak in dual-rate mode
+        let level = if self.config.dual_rate {
+            self.avg_level += self.attack_coeff * (input_abs - self.avg_level);
+            self.avg_level * core::f32::consts::FRAC_PI_2
         } else {
-            self.config.max_gain
+            self.peak_level
         };
+        let desired_gain = self.desired_gain(level);
 
         // Update gain with attack/decay/hang
-        if desired_gain < self.gain {
-            // Attack: reduce gain quickly
-            self.gain += self.attack_coeff * (desired_gain - self.gain);
-            self.hang_counter = self.hang_samples;
+        if desired_gain < self.slow_gain {
+            // Attack: reduce gain quickly, hang only on real signals
+            self.slow_gain += self.attack_coeff * (desired_gain - self.slow_gain);
+            self.hang_counter = if level >= self.hang_threshold {
+                self.hang_samples
+            } else {
+                0
+            };
         } else if self.hang_counter > 0 {
             // Hang: hold gain
             self.hang_counter -= 1;
         } else {
             // Decay: increase gain slowly
-            self.gain += self.decay_coeff * (desired_gain - self.gain);
+            self.slow_gain += self.decay_coeff * (desired_gain - self.slow_gain);
         }
 
+        self.gain = if self.config.dual_rate {
+            let fast_desired = self.desired_gain(self.peak_level);
+            if fast_desired < self.fast_gain {
+                self.fast_gain = fast_desired;
+            } else {
+                self.fast_gain += self.fast_decay_coeff * (fast_desired - self.fast_gain);
+            }
+            self.slow_gain.min(self.fast_gain)
+        } else {
+            self.slow_gain
+        };
+
         input * self.gain
     }
 
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
     /// Get current gain.
     #[must_use]
     pub fn gain(&self) -> f32 {
@@ -179,34 +284,149 @@ impl Agc {
         self.peak_level
     }
 
+    /// Get the configuration.
+    #[must_use]
+    pub fn config(&self) -> &AgcConfig {
+        &self.config
+    }
+
     /// Update configuration.
     pub fn set_config(&mut self, config: AgcConfig) {
         self.attack_coeff = Self::time_to_coeff(config.attack_ms, self.sample_rate);
         self.decay_coeff = Self::time_to_coeff(config.decay_ms, self.sample_rate);
+        self.fast_decay_coeff = Self::time_to_coeff(config.fast_decay_ms, self.sample_rate);
         self.hang_samples = (config.hang_ms * self.sample_rate / 1000.0) as u32;
+        self.hang_threshold = 10.0_f32.powf(config.hang_threshold_db / 20.0);
         self.config = config;
     }
 
     /// Reset AGC state.
     pub fn reset(&mut self) {
         self.gain = 1.0;
+        self.slow_gain = 1.0;
+        self.fast_gain = 1.0;
         self.hang_counter = 0;
         self.peak_level = 0.0;
+        self.avg_level = 0.0;
+    }
+}
+
+/// S-meter calibration.
+///
+/// Corrects a nominal dBm estimate to dBm at the antenna:
+/// `dbm = S9 + (nominal - S9) * slope + offset`. The slope pivots on S9
+/// so adjusting the S-unit spacing does not move the S9 point. Stored
+/// as integers (0.1 dB and 1/1000) so it can be persisted and compared
+/// exactly; the firmware keeps one per band.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmeterCalibration {
+    /// Offset in 0.1 dB
+    offset_tenths_db: i16,
+    /// Slope in 1/1000
+    slope_permille: u16,
+}
+
+impl SmeterCalibration {
+    /// Uncorrected calibration (offset 0 dB, slope 1).
+    pub const IDENTITY: Self = Self {
+        offset_tenths_db: 0,
+        slope_permille: 1000,
+    };
+
+    /// Largest offset magnitude in 0.1 dB.
+    pub const MAX_OFFSET_TENTHS_DB: i16 = 999;
+
+    /// Slope limits in 1/1000.
+    pub const SLOPE_RANGE_PERMILLE: (u16, u16) = (500, 2000);
+
+    /// Create a calibration from raw units (clamped to the valid range).
+    #[must_use]
+    pub fn new(offset_tenths_db: i16, slope_permille: u16) -> Self {
+        let (min_slope, max_slope) = Self::SLOPE_RANGE_PERMILLE;
+        Self {
+            offset_tenths_db: offset_tenths_db
+                .clamp(-Self::MAX_OFFSET_TENTHS_DB, Self::MAX_OFFSET_TENTHS_DB),
+            slope_permille: slope_permille.clamp(min_slope, max_slope),
+        }
+    }
+
+    /// Create a calibration from an offset in dB and a slope factor.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_db(offset_db: f32, slope: f32) -> Self {
+        let offset = (offset_db * 10.0)
+            .round()
+            .clamp(-f32::from(Self::MAX_OFFSET_TENTHS_DB), f32::from(Self::MAX_OFFSET_TENTHS_DB));
+        let slope = (slope * 1000.0).round().clamp(0.0, f32::from(u16::MAX));
+        Self::new(offset as i16, slope as u16)
+    }
+
+    /// Two-point calibration from known generator levels.
+    ///
+    /// Each point is `(nominal_dbm, actual_dbm)`: what the meter showed
+    /// uncorrected and what the generator delivered. Returns `None` if
+    /// both points have the same nominal level.
+    #[must_use]
+    pub fn from_points(a: (f32, f32), b: (f32, f32)) -> Option<Self> {
+        let span = b.0 - a.0;
+        if span.abs() < 1.0 {
+            return None;
+        }
+        let slope = (b.1 - a.1) / span;
+        let offset = a.1 - (S9_DBM + (a.0 - S9_DBM) * slope);
+        Some(Self::from_db(offset, slope))
+    }
+
+    /// Get the offset in 0.1 dB.
+    #[must_use]
+    pub fn offset_tenths_db(self) -> i16 {
+        self.offset_tenths_db
+    }
+
+    /// Get the slope in 1/1000.
+    #[must_use]
+    pub fn slope_permille(self) -> u16 {
+        self.slope_permille
+    }
+
+    /// Get the offset in dB.
+    #[must_use]
+    pub fn offset_db(self) -> f32 {
+        f32::from(self.offset_tenths_db) / 10.0
+    }
+
+    /// Get the slope factor.
+    #[must_use]
+    pub fn slope(self) -> f32 {
+        f32::from(self.slope_permille) / 1000.0
+    }
+
+    /// Correct a nominal level in dBm.
+    #[must_use]
+    pub fn apply(self, nominal_dbm: f32) -> f32 {
+        S9_DBM + (nominal_dbm - S9_DBM) * self.slope() + self.offset_db()
+    }
+}
+
+impl Default for SmeterCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
     }
 }
 
 /// S-Meter for signal strength display.
 ///
-/// Converts signal level to S-units (S0-S9, S9+10dB, etc.).
+/// Converts the detector level to dBm and IARU S-units (S9 = -73 dBm,
+/// 6 dB per S-unit). A detector level of 0 dB is nominally S9; a
+/// [`SmeterCalibration`] corrects that to the actual receiver gain.
 #[derive(Clone, Debug)]
 pub struct SMeter {
     /// Current signal level in dB (relative)
     level_db: f32,
     /// Smoothing coefficient
     coeff: f32,
-    /// Reference level for S9 (in linear units, for future calibration)
-    #[allow(dead_code)]
-    s9_reference: f32,
+    /// Calibration applied to the nominal level
+    calibration: SmeterCalibration,
 }
 
 impl SMeter {
@@ -226,7 +446,7 @@ impl SMeter {
         Self {
             level_db: -120.0,
             coeff,
-            s9_reference: 0.1, // Calibrate to your system
+            calibration: SmeterCalibration::IDENTITY,
         }
     }
 
@@ -247,30 +467,41 @@ impl SMeter {
         self.level_db
     }
 
+    /// Set the calibration.
+    pub fn set_calibration(&mut self, calibration: SmeterCalibration) {
+        self.calibration = calibration;
+    }
+
+    /// Get the calibration.
+    #[must_use]
+    pub fn calibration(&self) -> SmeterCalibration {
+        self.calibration
+    }
+
+    /// Get the calibrated signal level in dBm.
+    #[must_use]
+    pub fn dbm(&self) -> f32 {
+        self.calibration.apply(self.level_db + S9_DBM)
+    }
+
+    /// Get the signal level in S-units (9.0 = S9, above 9 is S9+).
+    #[must_use]
+    pub fn s_units(&self) -> f32 {
+        units::dbm_to_s_units(self.dbm())
+    }
+
     /// Get S-meter value (0.0 to 1.0 for S0-S9, >1.0 for S9+).
     ///
     /// Each S-unit is 6 dB.
     #[must_use]
     pub fn value(&self) -> f32 {
-        // S9 = 0 dB reference, each S-unit = 6 dB
-        // Map: S0 = -54 dB (relative to S9), S9 = 0 dB
-        let s_units = (self.level_db + 54.0) / 6.0;
-        s_units / 9.0 // Normalize so S9 = 1.0
+        self.s_units() / 9.0 // Normalize so S9 = 1.0
     }
 
     /// Get S-meter reading as string (e.g., "S7", "S9+10").
     #[must_use]
     pub fn reading(&self) -> SmeterReading {
-        let s_units = (self.level_db + 54.0) / 6.0;
-
-        if s_units < 0.0 {
-            SmeterReading::S(0)
-        } else if s_units <= 9.0 {
-            SmeterReading::S(s_units as u8)
-        } else {
-            let over = ((s_units - 9.0) * 6.0) as i8;
-            SmeterReading::S9Plus(over)
-        }
+        units::s_units_to_reading(self.s_units())
     }
 
     /// Reset S-meter.
@@ -285,6 +516,85 @@ impl Default for SMeter {
     }
 }
 
+/// Default squelch hysteresis in dB.
+pub const DEFAULT_SQUELCH_HYSTERESIS_DB: f32 = 3.0;
+
+/// Level squelch with hysteresis.
+///
+/// Opens when the signal level reaches the threshold and closes when it
+/// falls [`DEFAULT_SQUELCH_HYSTERESIS_DB`] below it, so a signal sitting
+/// at the threshold does not chatter. A disabled squelch is always open.
+#[derive(Clone, Copy, Debug)]
+pub struct Squelch {
+    /// Opening level (e.g. dBm from [`SMeter::dbm`])
+    threshold_db: f32,
+    /// Whether the squelch mutes at all
+    enabled: bool,
+    /// Whether the last level opened it
+    open: bool,
+}
+
+impl Squelch {
+    /// Create an enabled squelch opening at `threshold_db`.
+    #[must_use]
+    pub fn new(threshold_db: f32) -> Self {
+        Self {
+            threshold_db,
+            enabled: true,
+            open: false,
+        }
+    }
+
+    /// Update with the current level, returning whether audio should pass.
+    pub fn update(&mut self, level_db: f32) -> bool {
+        let close_db = self.threshold_db - DEFAULT_SQUELCH_HYSTERESIS_DB;
+        self.open = if self.open {
+            level_db >= close_db
+        } else {
+            level_db >= self.threshold_db
+        };
+        self.is_open()
+    }
+
+    /// Check if audio should pass.
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.open || !self.enabled
+    }
+
+    /// Set the opening level.
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db;
+    }
+
+    /// Get the opening level.
+    #[must_use]
+    pub fn threshold_db(&self) -> f32 {
+        self.threshold_db
+    }
+
+    /// Enable or disable muting.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check if muting is enabled.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl Default for Squelch {
+    /// Disabled, with the threshold at S3.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new(S9_DBM - 36.0)
+        }
+    }
+}
+
 /// S-meter reading representation.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SmeterReading {
@@ -343,6 +653,108 @@ mod tests {
         assert!(agc.gain() > 1.0);
     }
 
+    #[test]
+    fn test_agc_slope_above_knee() {
+        let config = AgcConfig {
+            slope_db: 5.0,
+            ..AgcConfig::fast()
+        }
+        .with_max_gain_db(40.0);
+        assert!((config.max_gain_db() - 40.0).abs() < 1e-3);
+        assert!((config.knee() - 0.003).abs() < 1e-6);
+
+        // 20 dB above the knee the output rises by 10 dB
+        let out = config.output_level(0.03);
+        assert!((20.0 * (out / 0.3).log10() - 10.0).abs() < 0.1, "{out}");
+        // Below the knee the output stays at the target
+        assert!((config.output_level(0.001) - 0.3).abs() < 1e-6);
+
+        let mut agc = Agc::new(48000.0, config);
+        let mut loud = 0.0_f32;
+        for _ in 0..48000 {
+            loud = agc.process(0.3);
+        }
+        assert!(loud > 0.6, "sloped AGC should let loud signals stand out: {loud}");
+    }
+
+    #[test]
+    fn test_agc_hang_threshold() {
+        let config = AgcConfig {
+            hang_threshold_db: -40.0,
+            ..AgcConfig::slow()
+        };
+
+        // Strong signal drops away: gain holds for the hang time
+        let mut agc = Agc::new(48000.0, config);
+        for _ in 0..4800 {
+            agc.process(0.5);
+        }
+        let held = agc.gain();
+        for _ in 0..4800 {
+            agc.process(0.0);
+        }
+        assert!((agc.gain() - held).abs() < 1e-6);
+
+        // Weak noise below the hang threshold starts decaying at once
+        let mut agc = Agc::new(48000.0, config);
+        for _ in 0..4800 {
+            agc.process(0.005);
+        }
+        let gain = agc.gain();
+        for _ in 0..4800 {
+            agc.process(0.0);
+        }
+        assert!(agc.gain() > gain);
+    }
+
+    #[test]
+    fn test_agc_dual_rate_recovers_from_impulse() {
+        let run = |dual_rate: bool| {
+            let mut agc = Agc::new(
+                48000.0,
+                AgcConfig {
+                    dual_rate,
+                    ..AgcConfig::slow()
+                },
+            );
+            let tone = |n: usize| 0.05 * (n as f32 * 0.1).sin();
+            for n in 0..48000 {
+                agc.process(tone(n));
+            }
+            let settled = agc.gain();
+            // 1 ms full-scale click
+            for _ in 0..48 {
+                agc.process(1.0);
+            }
+            for n in 0..4800 {
+                agc.process(tone(n));
+            }
+            agc.gain() / settled
+        };
+
+        let single = run(false);
+        let dual = run(true);
+        assert!(single < 0.5, "single-rate AGC holds a gain hole: {single}");
+        assert!(dual > 0.8, "dual-rate AGC recovers within 100 ms: {dual}");
+    }
+
+    #[test]
+    fn test_agc_block_matches_per_sample() {
+        let config = AgcConfig {
+            dual_rate: true,
+            ..AgcConfig::fast()
+        };
+        let input: [f32; 200] = core::array::from_fn(|n| if n % 50 < 5 { 0.9 } else { 0.01 });
+        let mut agc = Agc::new(8000.0, config);
+        let expected = input.map(|x| agc.process(x));
+
+        let mut block_agc = Agc::new(8000.0, config);
+        let mut samples = input;
+        block_agc.process_block(&mut samples);
+        assert_eq!(samples, expected);
+        assert_eq!(block_agc.gain(), agc.gain());
+    }
+
     #[test]
     fn test_smeter_s_units() {
         let mut meter = SMeter::new(48000.0, 10.0);
@@ -361,6 +773,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_smeter_calibration() {
+        let mut meter = SMeter::new(48000.0, 0.0);
+        // -20 dB detector level is nominally S9 - 20 dB = -93 dBm
+        meter.update(0.1);
+        assert!((meter.dbm() + 93.0).abs() < 0.01);
+
+        // Receiver with 10 dB more gain than nominal reads 10 dB low
+        meter.set_calibration(SmeterCalibration::from_db(-10.0, 1.0));
+        assert!((meter.dbm() + 103.0).abs() < 0.01);
+        assert_eq!(meter.reading(), SmeterReading::S(4));
+
+        // Two generator points: -73 dBm shows -63, -113 dBm shows -93
+        let cal = SmeterCalibration::from_points((-63.0, -73.0), (-93.0, -113.0)).unwrap();
+        assert_eq!(cal.slope_permille(), 1333);
+        assert!((cal.apply(-63.0) + 73.0).abs() < 0.1);
+        assert!((cal.apply(-93.0) + 113.0).abs() < 0.1);
+        assert!(SmeterCalibration::from_points((-63.0, -73.0), (-63.0, -80.0)).is_none());
+
+        // Out of range values are clamped
+        let cal = SmeterCalibration::new(5000, 10);
+        assert_eq!(cal.offset_tenths_db(), 999);
+        assert_eq!(cal.slope_permille(), 500);
+    }
+
+    #[test]
+    fn test_squelch_hysteresis() {
+        let mut squelch = Squelch::new(-100.0);
+        assert!(!squelch.update(-101.0));
+        assert!(squelch.update(-100.0));
+        // Stays open within the hysteresis, closes below it
+        assert!(squelch.update(-102.5));
+        assert!(!squelch.update(-104.0));
+        assert!(!squelch.update(-101.0));
+
+        squelch.set_enabled(false);
+        assert!(squelch.update(-130.0));
+        assert!(!Squelch::default().is_enabled());
+    }
+
     #[test]
     fn test_smeter_reading_string() {
         let s5 = SmeterReading::S(5);
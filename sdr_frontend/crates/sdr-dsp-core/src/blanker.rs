@@ -0,0 +1,218 @@
+//! IQ-domain impulse noise blanker.
+//!
+//! Ignition and power-line impulses are a few microseconds wide at the
+//! antenna, but the receive filters stretch them to milliseconds and the
+//! AGC ducks under them, so they are best removed before demodulation.
+//! The blanker tracks the average IQ power and zeroes a short window
+//! around any sample more than a threshold above it. A few samples of
+//! look-ahead let the window open before the impulse's leading edge.
+
+use crate::types::IqSample;
+
+/// Samples of look-ahead (and the blanker's delay).
+pub const BLANKER_LOOKAHEAD: usize = 4;
+
+/// Default threshold as a magnitude ratio over the average (20 dB).
+pub const DEFAULT_BLANKER_THRESHOLD: f32 = 10.0;
+
+/// Lowest threshold; anything closer to the average blanks the signal.
+pub const MIN_BLANKER_THRESHOLD: f32 = 2.0;
+
+/// Highest threshold.
+pub const MAX_BLANKER_THRESHOLD: f32 = 100.0;
+
+/// Default blanking width in microseconds.
+pub const DEFAULT_BLANK_WIDTH_US: f32 = 100.0;
+
+/// Widest blanking window in microseconds.
+pub const MAX_BLANK_WIDTH_US: f32 = 2000.0;
+
+/// Averaging time of the reference power in milliseconds.
+const AVERAGE_MS: f32 = 20.0;
+
+/// IQ impulse blanker.
+#[derive(Clone, Debug)]
+pub struct IqBlanker {
+    /// Sample rate in Hz.
+    sample_rate: f32,
+    /// Delay line (circular).
+    delay: [IqSample; BLANKER_LOOKAHEAD],
+    /// Next position in the delay line.
+    pos: usize,
+    /// Average IQ power, impulses excluded.
+    average: f32,
+    /// Averaging coefficient per sample.
+    coeff: f32,
+    /// Samples averaged so far, until the average has settled.
+    settling: u32,
+    /// Threshold as a magnitude ratio.
+    threshold: f32,
+    /// Blanking width in microseconds.
+    width_us: f32,
+    /// Blanking width in samples.
+    width: u32,
+    /// Output samples still to blank.
+    remaining: u32,
+    /// Blanking on.
+    enabled: bool,
+}
+
+impl IqBlanker {
+    /// Create a blanker with the default threshold and width, switched off.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        let mut blanker = Self {
+            sample_rate,
+            delay: [IqSample::ZERO; BLANKER_LOOKAHEAD],
+            pos: 0,
+            average: 0.0,
+            coeff: 1.0 / (AVERAGE_MS / 1000.0 * sample_rate).max(1.0),
+            settling: 0,
+            threshold: DEFAULT_BLANKER_THRESHOLD,
+            width_us: 0.0,
+            width: 1,
+            remaining: 0,
+            enabled: false,
+        };
+        blanker.set_width(DEFAULT_BLANK_WIDTH_US);
+        blanker
+    }
+
+    /// Switch blanking on or off, clearing the state when switched on.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Check if blanking is on.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the threshold as a magnitude ratio over the average (clamped to
+    /// [`MIN_BLANKER_THRESHOLD`] to [`MAX_BLANKER_THRESHOLD`]).
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.clamp(MIN_BLANKER_THRESHOLD, MAX_BLANKER_THRESHOLD);
+    }
+
+    /// Get the threshold as a magnitude ratio.
+    #[must_use]
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Set the blanking width in microseconds (clamped to at least one
+    /// sample and at most [`MAX_BLANK_WIDTH_US`]).
+    pub fn set_width(&mut self, width_us: f32) {
+        self.width_us = width_us.clamp(0.0, MAX_BLANK_WIDTH_US);
+        let samples = self.width_us * self.sample_rate / 1_000_000.0 + 0.5;
+        self.width = (samples as u32).max(1);
+    }
+
+    /// Get the blanking width in microseconds.
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        self.width_us
+    }
+
+    /// Blank one sample, returning the sample [`BLANKER_LOOKAHEAD`] earlier
+    /// (or the input unchanged when off).
+    pub fn process(&mut self, input: IqSample) -> IqSample {
+        if !self.enabled {
+            return input;
+        }
+        let power = input.magnitude_squared();
+        let limit = self.threshold * self.threshold * self.average;
+        let settled = self.settling as f32 * self.coeff >= 1.0;
+        if settled && power > limit {
+            // Blank the look-ahead before the impulse and the width after it
+            self.remaining = self.remaining.max(self.width + BLANKER_LOOKAHEAD as u32);
+            self.average += self.coeff * (limit - self.average);
+        } else {
+            self.average += self.coeff * (power - self.average);
+            self.settling = self.settling.saturating_add(1);
+        }
+
+        let output = self.delay[self.pos];
+        self.delay[self.pos] = input;
+        self.pos = (self.pos + 1) % BLANKER_LOOKAHEAD;
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            IqSample::ZERO
+        } else {
+            output
+        }
+    }
+
+    /// Blank a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [IqSample]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Clear the delay line and the average.
+    pub fn reset(&mut self) {
+        self.delay = [IqSample::ZERO; BLANKER_LOOKAHEAD];
+        self.pos = 0;
+        self.average = 0.0;
+        self.settling = 0;
+        self.remaining = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    #[test]
+    fn test_blanks_impulses_and_passes_signal() {
+        let sample_rate = 48000.0;
+        let mut blanker = IqBlanker::new(sample_rate);
+        blanker.set_enabled(true);
+        blanker.set_width(200.0);
+
+        let tone = |n: usize| {
+            let phase = TAU * 1000.0 * n as f32 / sample_rate;
+            IqSample::new(0.1 * phase.cos(), 0.1 * phase.sin())
+        };
+        let impulse = 4800;
+        let mut output = [IqSample::ZERO; 9600];
+        for (n, out) in output.iter_mut().enumerate() {
+            let input = if (impulse..impulse + 3).contains(&n) {
+                IqSample::new(5.0, -5.0)
+            } else {
+                tone(n)
+            };
+            *out = blanker.process(input);
+        }
+
+        // Delayed by the look-ahead, with the impulse gone
+        for (n, out) in output.iter().enumerate().skip(BLANKER_LOOKAHEAD) {
+            assert!(out.magnitude() < 0.11, "sample {n}: {out:?}");
+        }
+        let expected = tone(4000 - BLANKER_LOOKAHEAD);
+        assert!((output[4000].i - expected.i).abs() < 1e-6);
+        // The window opens before the impulse and covers the width after it
+        let window = impulse..impulse + BLANKER_LOOKAHEAD + 12;
+        assert!(output[window].iter().all(|s| *s == IqSample::ZERO));
+        assert!(output[impulse + 40].magnitude() > 0.09);
+    }
+
+    #[test]
+    fn test_settings_are_clamped() {
+        let mut blanker = IqBlanker::new(192000.0);
+        assert!(!blanker.is_enabled());
+        let input = IqSample::new(1.0, 0.0);
+        assert_eq!(blanker.process(input), input);
+
+        blanker.set_threshold(0.5);
+        assert_eq!(blanker.threshold(), MIN_BLANKER_THRESHOLD);
+        blanker.set_width(1e6);
+        assert_eq!(blanker.width(), MAX_BLANK_WIDTH_US);
+    }
+}
@@ -0,0 +1,327 @@
+//! Band condition scoring.
+//!
+//! Combines decoded FT8/WSPR spots with the noise floor history of each
+//! band into a single 0-100 score and a four-step rating, so the web UI
+//! band selector and the firmware display can show at a glance which
+//! bands are open.
+//!
+//! The score weighs three things:
+//! - activity: how many stations were decoded recently,
+//! - reach: how far away the most distant of them were,
+//! - quiet: how low the band's noise floor has been.
+//!
+//! Spot activity and reach decay each time [`BandConditions::decay`] is
+//! called (once per monitor cycle), so a band that closes drifts down
+//! rather than keeping a stale score.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Number of noise floor readings kept per band.
+pub const NOISE_HISTORY_LEN: usize = 16;
+
+/// Recent spot count treated as full activity.
+pub const SPOT_SATURATION: f32 = 20.0;
+
+/// Distance treated as full reach in km.
+pub const DX_DISTANCE_KM: f32 = 5000.0;
+
+/// Noise floor treated as fully quiet in dBm.
+pub const QUIET_NOISE_DBM: f32 = -120.0;
+
+/// Noise floor treated as unusable in dBm.
+pub const NOISY_NOISE_DBM: f32 = -90.0;
+
+/// Factor applied to activity and reach on each decay.
+pub const SPOT_DECAY: f32 = 0.5;
+
+/// Mean Earth radius in km.
+const EARTH_RADIUS_KM: f32 = 6371.0;
+
+/// Weights of activity, reach and quiet in the score.
+const WEIGHTS: (f32, f32, f32) = (0.35, 0.40, 0.25);
+
+/// Convert a 4- or 6-character Maidenhead locator to latitude and
+/// longitude in degrees (centre of the square).
+#[must_use]
+pub fn locator_to_lat_lon(locator: &str) -> Option<(f32, f32)> {
+    let b = locator.as_bytes();
+    if b.len() != 4 && b.len() != 6 {
+        return None;
+    }
+    let field = |c: u8| {
+        let c = c.to_ascii_uppercase();
+        (b'A'..=b'R').contains(&c).then(|| f32::from(c - b'A'))
+    };
+    let square = |c: u8| c.is_ascii_digit().then(|| f32::from(c - b'0'));
+
+    let mut lon = field(b[0])? * 20.0 - 180.0 + square(b[2])? * 2.0;
+    let mut lat = field(b[1])? * 10.0 - 90.0 + square(b[3])?;
+    if b.len() == 6 {
+        let sub = |c: u8| {
+            let c = c.to_ascii_lowercase();
+            (b'a'..=b'x').contains(&c).then(|| f32::from(c - b'a'))
+        };
+        lon += (sub(b[4])? + 0.5) * (2.0 / 24.0);
+        lat += (sub(b[5])? + 0.5) * (1.0 / 24.0);
+    } else {
+        lon += 1.0;
+        lat += 0.5;
+    }
+    Some((lat, lon))
+}
+
+/// Great-circle distance between two locators in km.
+#[must_use]
+pub fn locator_distance_km(from: &str, to: &str) -> Option<f32> {
+    let (lat1, lon1) = locator_to_lat_lon(from)?;
+    let (lat2, lon2) = locator_to_lat_lon(to)?;
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).max(0.0).sqrt());
+    Some(EARTH_RADIUS_KM * c)
+}
+
+/// Four-step band rating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Condition {
+    /// Score below 25
+    Poor,
+    /// Score 25 to 49
+    Fair,
+    /// Score 50 to 74
+    Good,
+    /// Score 75 and above
+    Excellent,
+}
+
+impl Condition {
+    /// Rating for a 0-100 score.
+    #[must_use]
+    pub fn from_score(score: u8) -> Self {
+        match score {
+            0..=24 => Self::Poor,
+            25..=49 => Self::Fair,
+            50..=74 => Self::Good,
+            _ => Self::Excellent,
+        }
+    }
+
+    /// Short label for displays.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Poor => "POOR",
+            Self::Fair => "FAIR",
+            Self::Good => "GOOD",
+            Self::Excellent => "EXC",
+        }
+    }
+}
+
+/// Accumulated observations for one band.
+#[derive(Clone, Copy, Debug)]
+struct BandStats {
+    /// Recent spot count (decayed)
+    activity: f32,
+    /// Longest recent spot distance in km (decayed)
+    reach_km: f32,
+    /// Best recent SNR in dB
+    best_snr_db: Option<i8>,
+    /// Noise floor history in dBm (ring buffer)
+    noise: [f32; NOISE_HISTORY_LEN],
+    /// Number of valid noise readings
+    noise_len: usize,
+    /// Next noise slot to write
+    noise_pos: usize,
+}
+
+impl BandStats {
+    const fn new() -> Self {
+        Self {
+            activity: 0.0,
+            reach_km: 0.0,
+            best_snr_db: None,
+            noise: [0.0; NOISE_HISTORY_LEN],
+            noise_len: 0,
+            noise_pos: 0,
+        }
+    }
+
+    fn has_data(&self) -> bool {
+        self.noise_len > 0 || self.activity > 0.0
+    }
+
+    fn mean_noise(&self) -> Option<f32> {
+        (self.noise_len > 0)
+            .then(|| self.noise[..self.noise_len].iter().sum::<f32>() / self.noise_len as f32)
+    }
+}
+
+/// Per-band condition aggregator for up to `N` bands.
+///
+/// Bands are identified by index; each caller maps its own band type
+/// to `0..N`.
+#[derive(Clone, Debug)]
+pub struct BandConditions<const N: usize> {
+    bands: [BandStats; N],
+}
+
+impl<const N: usize> BandConditions<N> {
+    /// Create an empty aggregator.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bands: [BandStats::new(); N],
+        }
+    }
+
+    /// Record a decoded spot.
+    ///
+    /// `distance_km` is `None` when the spot carried no locator; it then
+    /// counts toward activity only.
+    pub fn add_spot(&mut self, band: usize, distance_km: Option<f32>, snr_db: i8) {
+        let Some(stats) = self.bands.get_mut(band) else {
+            return;
+        };
+        stats.activity += 1.0;
+        if let Some(km) = distance_km {
+            stats.reach_km = stats.reach_km.max(km);
+        }
+        stats.best_snr_db = Some(stats.best_snr_db.map_or(snr_db, |best| best.max(snr_db)));
+    }
+
+    /// Record a noise floor reading in dBm.
+    pub fn add_noise_floor(&mut self, band: usize, dbm: f32) {
+        let Some(stats) = self.bands.get_mut(band) else {
+            return;
+        };
+        stats.noise[stats.noise_pos] = dbm;
+        stats.noise_pos = (stats.noise_pos + 1) % NOISE_HISTORY_LEN;
+        stats.noise_len = (stats.noise_len + 1).min(NOISE_HISTORY_LEN);
+    }
+
+    /// Age spot activity and reach on every band (once per monitor cycle).
+    pub fn decay(&mut self) {
+        for stats in &mut self.bands {
+            stats.activity *= SPOT_DECAY;
+            stats.reach_km *= SPOT_DECAY;
+            if stats.activity < 0.5 {
+                stats.activity = 0.0;
+                stats.best_snr_db = None;
+            }
+        }
+    }
+
+    /// Mean noise floor over the history in dBm.
+    #[must_use]
+    pub fn noise_floor_dbm(&self, band: usize) -> Option<f32> {
+        self.bands.get(band)?.mean_noise()
+    }
+
+    /// Best recent SNR in dB.
+    #[must_use]
+    pub fn best_snr_db(&self, band: usize) -> Option<i8> {
+        self.bands.get(band)?.best_snr_db
+    }
+
+    /// Condition score from 0 to 100, `None` if nothing was observed.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn score(&self, band: usize) -> Option<u8> {
+        let stats = self.bands.get(band)?;
+        if !stats.has_data() {
+            return None;
+        }
+        let activity = (stats.activity / SPOT_SATURATION).min(1.0);
+        let reach = (stats.reach_km / DX_DISTANCE_KM).min(1.0);
+        // Without noise readings, judge on spots alone
+        let quiet = stats.mean_noise().map_or(0.5, |dbm| {
+            ((NOISY_NOISE_DBM - dbm) / (NOISY_NOISE_DBM - QUIET_NOISE_DBM)).clamp(0.0, 1.0)
+        });
+        let (w_activity, w_reach, w_quiet) = WEIGHTS;
+        let score = 100.0 * (w_activity * activity + w_reach * reach + w_quiet * quiet);
+        Some(score.round().clamp(0.0, 100.0) as u8)
+    }
+
+    /// Condition rating, `None` if nothing was observed.
+    #[must_use]
+    pub fn condition(&self, band: usize) -> Option<Condition> {
+        self.score(band).map(Condition::from_score)
+    }
+
+    /// Forget everything observed on all bands.
+    pub fn clear(&mut self) {
+        self.bands = [BandStats::new(); N];
+    }
+}
+
+impl<const N: usize> Default for BandConditions<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locator_parsing_and_distance() {
+        let (lat, lon) = locator_to_lat_lon("JO01").unwrap();
+        assert!((lat - 51.5).abs() < 1e-3 && (lon - 1.0).abs() < 1e-3);
+        assert!(locator_to_lat_lon("JO0").is_none());
+        assert!(locator_to_lat_lon("ZZ99").is_none());
+
+        // London (IO91wm) to New York (FN20xr) is about 5570 km
+        let km = locator_distance_km("IO91wm", "FN20xr").unwrap();
+        assert!((km - 5570.0).abs() < 100.0, "{km}");
+        assert!(locator_distance_km("IO91", "IO91").unwrap() < 1.0);
+    }
+
+    #[test]
+    fn test_score_combines_spots_and_noise() {
+        let mut conditions: BandConditions<3> = BandConditions::new();
+        assert_eq!(conditions.score(0), None);
+
+        // Band 0: quiet, many distant spots
+        for _ in 0..20 {
+            conditions.add_spot(0, Some(6000.0), -10);
+        }
+        conditions.add_noise_floor(0, -125.0);
+        // Band 1: noisy, a few local spots
+        conditions.add_spot(1, Some(300.0), -20);
+        conditions.add_noise_floor(1, -88.0);
+
+        assert_eq!(conditions.score(0), Some(100));
+        assert_eq!(conditions.condition(0), Some(Condition::Excellent));
+        assert_eq!(conditions.condition(1), Some(Condition::Poor));
+        assert_eq!(conditions.best_snr_db(0), Some(-10));
+        // Out of range band
+        assert_eq!(conditions.score(7), None);
+    }
+
+    #[test]
+    fn test_decay_and_noise_history() {
+        let mut conditions: BandConditions<1> = BandConditions::new();
+        for _ in 0..10 {
+            conditions.add_spot(0, Some(4000.0), 0);
+        }
+        let before = conditions.score(0).unwrap();
+        conditions.decay();
+        assert!(conditions.score(0).unwrap() < before);
+        for _ in 0..8 {
+            conditions.decay();
+        }
+        assert_eq!(conditions.score(0), None);
+
+        for n in 0..NOISE_HISTORY_LEN + 4 {
+            conditions.add_noise_floor(0, if n < 4 { -60.0 } else { -110.0 });
+        }
+        // The noisy readings have rolled out of the history
+        assert!((conditions.noise_floor_dbm(0).unwrap() + 110.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,362 @@
+//! Fast convolution filtering.
+//!
+//! Runs long FIR filters in the frequency domain using overlap-save, so
+//! a brick-wall SSB filter of several hundred taps costs a couple of
+//! FFTs per block rather than hundreds of multiplies per sample.
+//!
+//! Each block of input is appended to the tail of the previous one, the
+//! whole FFT frame is transformed, multiplied by the filter spectrum and
+//! transformed back, and the samples untouched by circular wrap-around
+//! are kept. The price is latency: output appears one block after the
+//! input, on top of the filter's own group delay. [`FftConvolver::latency`]
+//! reports the total so the UI can show it.
+
+use crate::kernels;
+use crate::types::IqSample;
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Largest FFT frame.
+pub const MAX_FFT_SIZE: usize = 2048;
+
+/// Most filter taps (half the largest frame, so blocks are never tiny).
+pub const MAX_TAPS: usize = MAX_FFT_SIZE / 2;
+
+/// Design a windowed-sinc band-pass filter into `taps`.
+///
+/// The passband runs from `low_hz` to `high_hz`; a `low_hz` of zero gives
+/// a low-pass. The Blackman window keeps stopband ripple below about
+/// -74 dB, with a transition band of roughly `5.5 * sample_rate / taps.len()`.
+/// Taps are symmetric, so the filter is linear phase with a group delay
+/// of `(taps.len() - 1) / 2` samples.
+pub fn bandpass_taps(sample_rate: f32, low_hz: f32, high_hz: f32, taps: &mut [f32]) {
+    let len = taps.len();
+    if len == 0 {
+        return;
+    }
+    let nyquist = sample_rate / 2.0;
+    let low = (low_hz / sample_rate).clamp(0.0, 0.5);
+    let high = (high_hz.min(nyquist) / sample_rate).clamp(low, 0.5);
+    let center = (len - 1) as f32 / 2.0;
+    let two_pi = 2.0 * core::f32::consts::PI;
+
+    let sinc = |cutoff: f32, t: f32| {
+        if t.abs() < 1e-6 {
+            2.0 * cutoff
+        } else {
+            (two_pi * cutoff * t).sin() / (core::f32::consts::PI * t)
+        }
+    };
+
+    let span = if len > 1 { (len - 1) as f32 } else { 1.0 };
+    for (n, tap) in taps.iter_mut().enumerate() {
+        let t = n as f32 - center;
+        let phase = two_pi * n as f32 / span;
+        let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+        *tap = (sinc(high, t) - sinc(low, t)) * window;
+    }
+
+    // Unity gain at the middle of the passband
+    let mid = (low + high) / 2.0;
+    let (mut re, mut im) = (0.0, 0.0);
+    for (n, tap) in taps.iter().enumerate() {
+        let phase = two_pi * mid * n as f32;
+        re += tap * phase.cos();
+        im -= tap * phase.sin();
+    }
+    let gain = (re * re + im * im).sqrt();
+    if gain > 1e-9 {
+        for tap in taps.iter_mut() {
+            *tap /= gain;
+        }
+    }
+}
+
+/// Overlap-save FFT convolution FIR filter.
+#[derive(Clone)]
+pub struct FftConvolver {
+    /// FFT frame size (power of two)
+    fft_size: usize,
+    /// Filter length
+    num_taps: usize,
+    /// Twiddle factors, `exp(-2 pi i k / fft_size)` for the first half
+    twiddle: [IqSample; MAX_FFT_SIZE / 2],
+    /// Filter spectrum, scaled by `1 / fft_size` for the inverse FFT
+    spectrum: [IqSample; MAX_FFT_SIZE],
+    /// Previous block tail followed by the block being collected
+    history: [f32; MAX_FFT_SIZE],
+    /// FFT work area
+    work: [IqSample; MAX_FFT_SIZE],
+    /// Filtered output of the last block
+    output: [f32; MAX_FFT_SIZE],
+    /// Samples collected in the current block
+    fill: usize,
+}
+
+impl FftConvolver {
+    /// Create a convolver for a filter.
+    ///
+    /// The FFT frame is the smallest power of two at least twice the
+    /// filter length. Returns `None` for no taps or more than [`MAX_TAPS`].
+    #[must_use]
+    pub fn new(taps: &[f32]) -> Option<Self> {
+        let mut convolver = Self {
+            fft_size: 0,
+            num_taps: 1,
+            twiddle: [IqSample::default(); MAX_FFT_SIZE / 2],
+            spectrum: [IqSample::default(); MAX_FFT_SIZE],
+            history: [0.0; MAX_FFT_SIZE],
+            work: [IqSample::default(); MAX_FFT_SIZE],
+            output: [0.0; MAX_FFT_SIZE],
+            fill: 0,
+        };
+        convolver.set_taps(taps).then_some(convolver)
+    }
+
+    /// Replace the filter, clearing the signal history.
+    ///
+    /// Returns `false` and keeps the current filter if `taps` is empty or
+    /// longer than [`MAX_TAPS`].
+    pub fn set_taps(&mut self, taps: &[f32]) -> bool {
+        if taps.is_empty() || taps.len() > MAX_TAPS {
+            return false;
+        }
+        let fft_size = (taps.len() * 2).next_power_of_two().min(MAX_FFT_SIZE);
+        if fft_size != self.fft_size {
+            self.fft_size = fft_size;
+            let step = -2.0 * core::f32::consts::PI / fft_size as f32;
+            for (k, twiddle) in self.twiddle[..fft_size / 2].iter_mut().enumerate() {
+                let angle = step * k as f32;
+                *twiddle = IqSample::new(angle.cos(), angle.sin());
+            }
+        }
+        self.num_taps = taps.len();
+
+        let scale = 1.0 / fft_size as f32;
+        let spectrum = &mut self.spectrum[..fft_size];
+        spectrum.fill(IqSample::default());
+        for (bin, &tap) in spectrum.iter_mut().zip(taps) {
+            *bin = IqSample::new(tap * scale, 0.0);
+        }
+        fft_in_place(spectrum, &self.twiddle[..fft_size / 2]);
+
+        self.reset();
+        true
+    }
+
+    /// Get the filter length.
+    #[must_use]
+    pub fn num_taps(&self) -> usize {
+        self.num_taps
+    }
+
+    /// Get the FFT frame size.
+    #[must_use]
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Get the number of new samples filtered per FFT frame.
+    #[must_use]
+    pub fn block_len(&self) -> usize {
+        self.fft_size - self.num_taps + 1
+    }
+
+    /// Get the delay from input to output in samples.
+    ///
+    /// One block of buffering plus the group delay of a linear-phase
+    /// (symmetric) filter, such as those from [`bandpass_taps`].
+    #[must_use]
+    pub fn latency(&self) -> usize {
+        self.block_len() + (self.num_taps - 1) / 2
+    }
+
+    /// Get the delay from input to output in seconds.
+    #[must_use]
+    pub fn latency_seconds(&self, sample_rate: f32) -> f32 {
+        if sample_rate > 0.0 {
+            self.latency() as f32 / sample_rate
+        } else {
+            0.0
+        }
+    }
+
+    /// Process a single sample.
+    ///
+    /// Returns the filtered sample from one block earlier.
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.output[self.fill];
+        self.history[self.num_taps - 1 + self.fill] = input;
+        self.fill += 1;
+        if self.fill == self.block_len() {
+            self.filter_block();
+            self.fill = 0;
+        }
+        output
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Reset filter state.
+    pub fn reset(&mut self) {
+        self.history = [0.0; MAX_FFT_SIZE];
+        self.output = [0.0; MAX_FFT_SIZE];
+        self.fill = 0;
+    }
+
+    /// Filter the collected frame and keep its tail for the next one.
+    fn filter_block(&mut self) {
+        let n = self.fft_size;
+        let overlap = self.num_taps - 1;
+        let twiddle = &self.twiddle[..n / 2];
+
+        let work = &mut self.work[..n];
+        for (bin, &sample) in work.iter_mut().zip(&self.history[..n]) {
+            *bin = IqSample::new(sample, 0.0);
+        }
+        fft_in_place(work, twiddle);
+
+        // Multiply by the filter and conjugate, so a forward FFT inverts
+        kernels::complex_multiply(work, &self.spectrum[..n]);
+        for bin in work.iter_mut() {
+            bin.q = -bin.q;
+        }
+        fft_in_place(work, twiddle);
+
+        // The first `overlap` outputs are corrupted by wrap-around
+        for (out, bin) in self.output.iter_mut().zip(&work[overlap..]) {
+            *out = bin.i;
+        }
+        self.history.copy_within(n - overlap..n, 0);
+    }
+}
+
+/// In-place radix-2 DIT FFT with precomputed twiddles.
+pub(crate) fn fft_in_place(data: &mut [IqSample], twiddle: &[IqSample]) {
+    let n = data.len();
+
+    // Bit-reverse permutation
+    let mut j = 0;
+    for i in 0..n - 1 {
+        if i < j {
+            data.swap(i, j);
+        }
+        let mut k = n / 2;
+        while k <= j {
+            j -= k;
+            k /= 2;
+        }
+        j += k;
+    }
+
+    // Cooley-Tukey butterflies
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let w = twiddle[k * stride];
+                let a = data[start + k];
+                let b = data[start + k + half];
+                let t = IqSample::new(w.i * b.i - w.q * b.q, w.i * b.q + w.q * b.i);
+                data[start + k] = IqSample::new(a.i + t.i, a.q + t.q);
+                data[start + k + half] = IqSample::new(a.i - t.i, a.q - t.q);
+            }
+        }
+        len *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direct(taps: &[f32], input: &[f32], n: usize) -> f32 {
+        taps.iter()
+            .enumerate()
+            .filter(|(k, _)| *k <= n)
+            .map(|(k, tap)| tap * input[n - k])
+            .sum()
+    }
+
+    #[test]
+    fn test_matches_direct_convolution() {
+        let taps: [f32; 37] = core::array::from_fn(|k| ((k * 7 % 11) as f32 - 5.0) / 10.0);
+        let mut convolver = FftConvolver::new(&taps).unwrap();
+        assert_eq!(convolver.fft_size(), 128);
+        assert_eq!(convolver.block_len(), 92);
+
+        let input: [f32; 600] = core::array::from_fn(|n| ((n * 13 % 17) as f32 - 8.0) / 8.0);
+        let delay = convolver.block_len();
+        for (n, &x) in input.iter().enumerate() {
+            let y = convolver.process(x);
+            if n >= delay {
+                let expected = direct(&taps, &input, n - delay);
+                assert!((y - expected).abs() < 1e-4, "sample {n}: {y} vs {expected}");
+            } else {
+                assert_eq!(y, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_taps() {
+        assert!(FftConvolver::new(&[]).is_none());
+        assert!(FftConvolver::new(&[0.0; MAX_TAPS + 1]).is_none());
+
+        let mut convolver = FftConvolver::new(&[0.0; MAX_TAPS]).unwrap();
+        assert_eq!(convolver.fft_size(), MAX_FFT_SIZE);
+        assert!(!convolver.set_taps(&[]));
+        assert_eq!(convolver.num_taps(), MAX_TAPS);
+
+        // A single tap is a plain gain
+        let mut gain = FftConvolver::new(&[0.5]).unwrap();
+        assert_eq!(gain.block_len(), 2);
+        gain.process(1.0);
+        gain.process(-2.0);
+        assert!((gain.process(0.0) - 0.5).abs() < 1e-6);
+        assert!((gain.process(0.0) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_latency() {
+        let mut taps = [0.0; 511];
+        bandpass_taps(48000.0, 0.0, 2700.0, &mut taps);
+        let convolver = FftConvolver::new(&taps).unwrap();
+        assert_eq!(convolver.fft_size(), 1024);
+        assert_eq!(convolver.latency(), 514 + 255);
+        assert!((convolver.latency_seconds(48000.0) - 769.0 / 48000.0).abs() < 1e-6);
+        assert_eq!(convolver.latency_seconds(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_bandpass_brick_wall() {
+        let sample_rate = 48000.0;
+        let mut taps = [0.0; 511];
+        bandpass_taps(sample_rate, 300.0, 2700.0, &mut taps);
+        assert!((taps[0] - taps[510]).abs() < 1e-9, "taps are symmetric");
+
+        let level = |freq: f32| {
+            let mut convolver = FftConvolver::new(&taps).unwrap();
+            let mut peak = 0.0f32;
+            for n in 0..8000 {
+                let x = (2.0 * core::f32::consts::PI * freq * n as f32 / sample_rate).sin();
+                let y = convolver.process(x);
+                if n > 4000 {
+                    peak = peak.max(y.abs());
+                }
+            }
+            peak
+        };
+
+        assert!((level(1500.0) - 1.0).abs() < 0.02);
+        assert!(level(3500.0) < 0.001, "upper stopband");
+        assert!(level(100.0) < 0.05, "below the passband");
+    }
+}
@@ -0,0 +1,64 @@
+//! Block demodulators.
+//!
+//! Turn IQ that the NCO has already mixed down into audio, one output
+//! sample per input sample (up to the shorter of the two slices). They
+//! keep no state, so each is a single loop with nothing carried from
+//! one sample to the next, which the compiler can vectorize.
+
+use crate::types::IqSample;
+
+/// Lower sideband: I - Q.
+pub fn lsb(iq: &[IqSample], audio: &mut [f32]) {
+    for (out, s) in audio.iter_mut().zip(iq) {
+        *out = s.i - s.q;
+    }
+}
+
+/// Upper sideband (and CW): I + Q.
+pub fn usb(iq: &[IqSample], audio: &mut [f32]) {
+    for (out, s) in audio.iter_mut().zip(iq) {
+        *out = s.i + s.q;
+    }
+}
+
+/// AM envelope: the magnitude of each sample.
+pub fn am(iq: &[IqSample], audio: &mut [f32]) {
+    for (out, s) in audio.iter_mut().zip(iq) {
+        *out = s.magnitude();
+    }
+}
+
+/// Simplified FM: the phase of each sample in radians.
+pub fn fm(iq: &[IqSample], audio: &mut [f32]) {
+    for (out, s) in audio.iter_mut().zip(iq) {
+        *out = s.phase();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demodulators() {
+        let iq = [IqSample::new(3.0, 4.0), IqSample::new(0.0, -1.0)];
+        let mut audio = [0.0; 2];
+
+        lsb(&iq, &mut audio);
+        assert_eq!(audio, [-1.0, 1.0]);
+        usb(&iq, &mut audio);
+        assert_eq!(audio, [7.0, -1.0]);
+        am(&iq, &mut audio);
+        assert!((audio[0] - 5.0).abs() < 1e-3 && (audio[1] - 1.0).abs() < 1e-3);
+        fm(&iq, &mut audio);
+        assert!((audio[1] + core::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_demodulator_uses_shorter_slice() {
+        let iq = [IqSample::new(1.0, 1.0); 3];
+        let mut audio = [9.0; 5];
+        usb(&iq, &mut audio);
+        assert_eq!(audio, [2.0, 2.0, 2.0, 9.0, 9.0]);
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides biquad IIR filters, FIR filters, and DC blocking filters
 //! for audio and signal processing.
 
+use crate::kernels;
 use crate::types::IqSample;
 #[allow(unused_imports)]
 use micromath::F32Ext;
@@ -217,6 +218,21 @@ impl Biquad {
         output
     }
 
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        let c = self.coeffs;
+        let (mut z1, mut z2) = (self.z1, self.z2);
+        for sample in samples {
+            let input = *sample;
+            let output = c.b0 * input + z1;
+            z1 = c.b1 * input - c.a1 * output + z2;
+            z2 = c.b2 * input - c.a2 * output;
+            *sample = output;
+        }
+        self.z1 = z1;
+        self.z2 = z2;
+    }
+
     /// Process an IQ sample (applies filter to both I and Q).
     #[inline]
     pub fn process_iq(&mut self, input: IqSample) -> IqSample {
@@ -268,6 +284,25 @@ impl BiquadIq {
         IqSample::new(self.i_filter.process(input.i), self.q_filter.process(input.q))
     }
 
+    /// Process a block of IQ samples in place.
+    ///
+    /// I and Q are filtered together by [`kernels::biquad_iq`].
+    pub fn process_block(&mut self, samples: &mut [IqSample]) {
+        let mut state = [
+            self.i_filter.z1,
+            self.q_filter.z1,
+            self.i_filter.z2,
+            self.q_filter.z2,
+        ];
+        kernels::biquad_iq(&self.i_filter.coeffs, &mut state, samples);
+        [
+            self.i_filter.z1,
+            self.q_filter.z1,
+            self.i_filter.z2,
+            self.q_filter.z2,
+        ] = state;
+    }
+
     /// Reset both filter states.
     pub fn reset(&mut self) {
         self.i_filter.reset();
@@ -315,6 +350,19 @@ impl DcBlocker {
         output
     }
 
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        let (mut prev_input, mut prev_output) = (self.prev_input, self.prev_output);
+        for sample in samples {
+            let input = *sample;
+            prev_output = input - prev_input + self.alpha * prev_output;
+            prev_input = input;
+            *sample = prev_output;
+        }
+        self.prev_input = prev_input;
+        self.prev_output = prev_output;
+    }
+
     /// Reset filter state.
     pub fn reset(&mut self) {
         self.prev_input = 0.0;
@@ -354,6 +402,26 @@ impl DcBlockerIq {
         )
     }
 
+    /// Process a block of IQ samples in place.
+    pub fn process_block(&mut self, samples: &mut [IqSample]) {
+        let alpha = self.i_blocker.alpha;
+        let mut prev_in = IqSample::new(self.i_blocker.prev_input, self.q_blocker.prev_input);
+        let mut prev_out = IqSample::new(self.i_blocker.prev_output, self.q_blocker.prev_output);
+        for sample in samples {
+            let input = *sample;
+            prev_out = IqSample::new(
+                input.i - prev_in.i + alpha * prev_out.i,
+                input.q - prev_in.q + alpha * prev_out.q,
+            );
+            prev_in = input;
+            *sample = prev_out;
+        }
+        self.i_blocker.prev_input = prev_in.i;
+        self.q_blocker.prev_input = prev_in.q;
+        self.i_blocker.prev_output = prev_out.i;
+        self.q_blocker.prev_output = prev_out.q;
+    }
+
     /// Reset both blockers.
     pub fn reset(&mut self) {
         self.i_blocker.reset();
@@ -363,9 +431,11 @@ impl DcBlockerIq {
 
 /// Simple FIR filter with fixed tap count.
 ///
-/// Uses a circular buffer for efficient processing.
+/// Uses a circular buffer for efficient processing, with the taps run
+/// as two [`kernels::dot`] products either side of the wrap.
 #[derive(Clone, Debug)]
 pub struct FirFilter<const N: usize> {
+    /// Coefficients, oldest sample's first
     coeffs: [f32; N],
     buffer: [f32; N],
     write_pos: usize,
@@ -375,8 +445,14 @@ impl<const N: usize> FirFilter<N> {
     /// Create a new FIR filter with given coefficients.
     #[must_use]
     pub const fn new(coeffs: [f32; N]) -> Self {
+        let mut reversed = [0.0; N];
+        let mut k = 0;
+        while k < N {
+            reversed[N - 1 - k] = coeffs[k];
+            k += 1;
+        }
         Self {
-            coeffs,
+            coeffs: reversed,
             buffer: [0.0; N],
             write_pos: 0,
         }
@@ -384,26 +460,20 @@ impl<const N: usize> FirFilter<N> {
 
     /// Process a single sample.
     pub fn process(&mut self, input: f32) -> f32 {
-        // Write new sample to buffer
+        // Write new sample to buffer; the oldest is then at write_pos
         self.buffer[self.write_pos] = input;
-
-        // Compute convolution
-        let mut output = 0.0;
-        let mut read_pos = self.write_pos;
-
-        for coeff in &self.coeffs {
-            output += self.buffer[read_pos] * coeff;
-            if read_pos == 0 {
-                read_pos = N - 1;
-            } else {
-                read_pos -= 1;
-            }
-        }
-
-        // Advance write position
         self.write_pos = (self.write_pos + 1) % N;
 
-        output
+        let (newer, older) = self.buffer.split_at(self.write_pos);
+        let split = N - self.write_pos;
+        kernels::dot(older, &self.coeffs[..split]) + kernels::dot(newer, &self.coeffs[split..])
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.process(*sample);
+        }
     }
 
     /// Reset filter state.
@@ -438,6 +508,31 @@ mod tests {
         assert!(output.abs() < 0.1);
     }
 
+    #[test]
+    fn test_dc_blocker_block_matches_per_sample() {
+        let input: [IqSample; 90] = core::array::from_fn(|n| {
+            let t = n as f32;
+            IqSample::new(0.5 + (t * 0.2).sin(), -0.3 + (t * 0.05).cos())
+        });
+        let mut blocker = DcBlockerIq::new(0.99);
+        let expected = input.map(|s| blocker.process(s));
+
+        let mut block_blocker = DcBlockerIq::new(0.99);
+        let mut samples = input;
+        let (first, second) = samples.split_at_mut(33);
+        block_blocker.process_block(first);
+        block_blocker.process_block(second);
+        assert_eq!(samples, expected);
+
+        let mut real = DcBlocker::new(0.99);
+        let mut real_block = DcBlocker::new(0.99);
+        let mut block = input.map(|s| s.i);
+        real_block.process_block(&mut block);
+        for (out, s) in block.iter().zip(&input) {
+            assert_eq!(*out, real.process(s.i));
+        }
+    }
+
     #[test]
     fn test_lowpass_attenuates_high_freq() {
         let mut filter = Biquad::lowpass(48000.0, 1000.0, 0.707);
@@ -454,4 +549,27 @@ mod tests {
         // Should be significantly attenuated
         assert!(max_output < 0.5);
     }
+
+    #[test]
+    fn test_fir_impulse_response() {
+        let mut fir = FirFilter::new([0.5, 0.25, -0.125, 1.0, 2.0]);
+        let response: [f32; 8] =
+            core::array::from_fn(|n| fir.process(if n == 0 { 1.0 } else { 0.0 }));
+        assert_eq!(response, [0.5, 0.25, -0.125, 1.0, 2.0, 0.0, 0.0, 0.0]);
+
+        let mut block = [1.0, 1.0, 0.0, 0.0];
+        fir.process_block(&mut block);
+        assert_eq!(block, [0.5, 0.75, 0.125, 0.875]);
+    }
+
+    #[test]
+    fn test_biquad_block_matches_per_sample() {
+        let mut single = Biquad::lowpass(48000.0, 1000.0, 0.707);
+        let mut block = single.clone();
+        let mut samples: [f32; 64] = core::array::from_fn(|n| if n % 7 == 0 { 1.0 } else { -0.25 });
+        let expected = samples.map(|x| single.process(x));
+        block.process_block(&mut samples[..20]);
+        block.process_block(&mut samples[20..]);
+        assert_eq!(samples, expected);
+    }
 }
@@ -0,0 +1,375 @@
+//! Goertzel tone detector bank.
+//!
+//! [`GoertzelBank`] measures the power at up to `N` chosen frequencies
+//! over blocks of samples, at a cost of one multiply-add per tone per
+//! sample: far cheaper than an FFT when only a few frequencies matter,
+//! as for CTCSS and DTMF decoding, CW pitch tracking or scoring FT8
+//! candidate tones. The frequencies need not fall on FFT bins.
+//!
+//! Samples are pushed one at a time or in blocks; every
+//! [`block_len`](GoertzelBank::block_len) samples the powers are
+//! computed and the filters start over. [`compute`](GoertzelBank::compute)
+//! ends a block early. The block length sets the resolution: tones
+//! closer than about `sample_rate / block_len` Hz are not told apart.
+
+use core::f64::consts::{FRAC_PI_2, PI};
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Power of a full-scale sine as reported by [`GoertzelBank::power`].
+const FULL_SCALE_POWER: f32 = 0.5;
+
+/// One frequency of a [`GoertzelBank`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Detector {
+    frequency: f32,
+    /// 2·cos(ω)
+    coeff: f32,
+    s1: f32,
+    s2: f32,
+    /// Power of the last completed block
+    power: f32,
+}
+
+impl Detector {
+    const IDLE: Self = Self {
+        frequency: 0.0,
+        coeff: 0.0,
+        s1: 0.0,
+        s2: 0.0,
+        power: 0.0,
+    };
+
+    fn new(frequency: f32, sample_rate: f32) -> Self {
+        let omega = 2.0 * PI * f64::from(frequency) / f64::from(sample_rate);
+        Self {
+            frequency,
+            coeff: (2.0 * accurate_cos(omega)) as f32,
+            ..Self::IDLE
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, sample: f32) {
+        let s0 = sample + self.coeff * self.s1 - self.s2;
+        self.s2 = self.s1;
+        self.s1 = s0;
+    }
+
+    /// Squared magnitude of the block's DFT at the frequency.
+    fn magnitude_squared(&self) -> f32 {
+        (self.s1 * self.s1 + self.s2 * self.s2 - self.coeff * self.s1 * self.s2).max(0.0)
+    }
+}
+
+/// Cosine of `x` (0 to π radians) to full `f32` precision.
+///
+/// Low tones put the coefficient close to 2, where the `no_std` cosine
+/// approximation is off by enough to move a detector several Hz.
+fn accurate_cos(x: f64) -> f64 {
+    // Folded to 0..=π/2, ten Taylor terms are well past f32 precision
+    let (x, sign) = if x > FRAC_PI_2 { (PI - x, -1.0) } else { (x, 1.0) };
+    let x2 = x * x;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..=10 {
+        term *= -x2 / f64::from((2 * n - 1) * (2 * n));
+        sum += term;
+    }
+    sign * sum
+}
+
+/// Bank of up to `N` Goertzel tone detectors sharing one block length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoertzelBank<const N: usize> {
+    sample_rate: f32,
+    block_len: usize,
+    detectors: [Detector; N],
+    len: usize,
+    /// Samples pushed into the current block
+    filled: usize,
+    /// Length of the last completed block (0 before the first)
+    last_block: usize,
+}
+
+impl<const N: usize> GoertzelBank<N> {
+    /// Create an empty bank for `sample_rate` Hz computing every
+    /// `block_len` samples (at least one).
+    #[must_use]
+    pub fn new(sample_rate: f32, block_len: usize) -> Self {
+        Self {
+            sample_rate,
+            block_len: block_len.max(1),
+            detectors: [Detector::IDLE; N],
+            len: 0,
+            filled: 0,
+            last_block: 0,
+        }
+    }
+
+    /// Create a bank with a detector at each of `frequencies` Hz.
+    ///
+    /// Frequencies past the bank's capacity, or outside 0 Hz to Nyquist,
+    /// are left out.
+    #[must_use]
+    pub fn with_tones(sample_rate: f32, block_len: usize, frequencies: &[f32]) -> Self {
+        let mut bank = Self::new(sample_rate, block_len);
+        for &frequency in frequencies {
+            bank.add_tone(frequency);
+        }
+        bank
+    }
+
+    /// Add a detector at `frequency` Hz.
+    ///
+    /// Returns its index, or `None` if the bank is full or the frequency
+    /// is outside 0 Hz to Nyquist. Added mid-block, its first power
+    /// misses the samples already pushed.
+    pub fn add_tone(&mut self, frequency: f32) -> Option<usize> {
+        if !(0.0..=self.sample_rate / 2.0).contains(&frequency) {
+            return None;
+        }
+        let index = self.len;
+        *self.detectors.get_mut(index)? = Detector::new(frequency, self.sample_rate);
+        self.len += 1;
+        Some(index)
+    }
+
+    /// Move detector `index` to `frequency` Hz, restarting its block.
+    ///
+    /// Returns `false` if there is no such detector or the frequency is
+    /// outside 0 Hz to Nyquist.
+    pub fn set_frequency(&mut self, index: usize, frequency: f32) -> bool {
+        if !(0.0..=self.sample_rate / 2.0).contains(&frequency) {
+            return false;
+        }
+        let sample_rate = self.sample_rate;
+        match self.detectors[..self.len].get_mut(index) {
+            Some(detector) => {
+                *detector = Detector::new(frequency, sample_rate);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the frequency of detector `index` in Hz.
+    #[must_use]
+    pub fn frequency(&self, index: usize) -> Option<f32> {
+        self.detectors[..self.len].get(index).map(|d| d.frequency)
+    }
+
+    /// Remove all detectors.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.filled = 0;
+        self.last_block = 0;
+    }
+
+    /// Get the number of detectors.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the bank has no detectors.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the sample rate in Hz.
+    #[must_use]
+    pub const fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Get the block length in samples.
+    #[must_use]
+    pub const fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Set the block length in samples (at least one), restarting the block.
+    pub fn set_block_len(&mut self, block_len: usize) {
+        self.block_len = block_len.max(1);
+        self.restart();
+    }
+
+    /// Push one sample.
+    ///
+    /// Returns `true` when it completes a block and new powers are ready.
+    pub fn push(&mut self, sample: f32) -> bool {
+        for detector in &mut self.detectors[..self.len] {
+            detector.push(sample);
+        }
+        self.filled += 1;
+        if self.filled < self.block_len {
+            return false;
+        }
+        self.compute();
+        true
+    }
+
+    /// Push a block of samples.
+    ///
+    /// Returns `true` if at least one block completed; the powers are
+    /// those of the last one.
+    pub fn push_block(&mut self, samples: &[f32]) -> bool {
+        let mut done = false;
+        for &sample in samples {
+            done |= self.push(sample);
+        }
+        done
+    }
+
+    /// Compute the powers from the samples pushed so far and start a new
+    /// block.
+    ///
+    /// Called by [`push`](Self::push) at the end of each block; call it
+    /// directly to end a block early. Nothing changes if no samples have
+    /// been pushed since the last block.
+    pub fn compute(&mut self) {
+        if self.filled == 0 {
+            return;
+        }
+        let block = self.filled as f32;
+        let scale = 2.0 / (block * block);
+        for detector in &mut self.detectors[..self.len] {
+            detector.power = detector.magnitude_squared() * scale;
+            detector.s1 = 0.0;
+            detector.s2 = 0.0;
+        }
+        self.last_block = self.filled;
+        self.filled = 0;
+    }
+
+    /// Get the power of detector `index` over the last block.
+    ///
+    /// A sine of amplitude `A` at the detector's frequency reads `A²/2`,
+    /// its mean square. `None` if there is no such detector or no block
+    /// has completed.
+    #[must_use]
+    pub fn power(&self, index: usize) -> Option<f32> {
+        if self.last_block == 0 {
+            return None;
+        }
+        self.detectors[..self.len].get(index).map(|d| d.power)
+    }
+
+    /// Get the power of detector `index` in dB relative to a full-scale sine.
+    #[must_use]
+    pub fn power_dbfs(&self, index: usize) -> Option<f32> {
+        self.power(index)
+            .map(|power| 10.0 * (power / FULL_SCALE_POWER).max(1e-12).log10())
+    }
+
+    /// Get the powers of every detector over the last block, in index order
+    /// (zero before the first block).
+    pub fn powers(&self) -> impl Iterator<Item = f32> + '_ {
+        self.detectors[..self.len].iter().map(|d| d.power)
+    }
+
+    /// Get the index and power of the strongest detector over the last
+    /// block (`None` if empty or no block has completed).
+    #[must_use]
+    pub fn strongest(&self) -> Option<(usize, f32)> {
+        if self.last_block == 0 {
+            return None;
+        }
+        self.powers()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Discard the current block and the last powers, keeping the detectors.
+    pub fn reset(&mut self) {
+        self.restart();
+        for detector in &mut self.detectors[..self.len] {
+            detector.power = 0.0;
+        }
+        self.last_block = 0;
+    }
+
+    /// Discard the samples of the current block.
+    fn restart(&mut self) {
+        for detector in &mut self.detectors[..self.len] {
+            detector.s1 = 0.0;
+            detector.s2 = 0.0;
+        }
+        self.filled = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    fn sine(frequency: f32, amplitude: f32, n: usize) -> f32 {
+        amplitude * (2.0 * PI * frequency * n as f32 / 8000.0).sin()
+    }
+
+    #[test]
+    fn test_tone_power() {
+        let mut bank: GoertzelBank<4> = GoertzelBank::with_tones(8000.0, 400, &[1000.0, 1500.0]);
+        assert_eq!(bank.len(), 2);
+        assert_eq!(bank.power(0), None);
+
+        let mut done = 0;
+        for n in 0..800 {
+            if bank.push(sine(1000.0, 0.5, n)) {
+                done += 1;
+            }
+        }
+        assert_eq!(done, 2);
+        assert!((bank.power(0).unwrap() - 0.125).abs() < 0.005);
+        assert!((bank.power_dbfs(0).unwrap() - -6.02).abs() < 0.2);
+        assert!(bank.power(1).unwrap() < 1e-4);
+        assert_eq!(bank.strongest().map(|(index, _)| index), Some(0));
+    }
+
+    #[test]
+    fn test_dtmf_pair() {
+        // Digit 5: 770 Hz and 1336 Hz, over a 205 sample block as in DTMF decoders
+        const ROWS: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+        const COLUMNS: [f32; 3] = [1209.0, 1336.0, 1477.0];
+        let mut bank: GoertzelBank<8> = GoertzelBank::new(8000.0, 205);
+        for frequency in ROWS.iter().chain(&COLUMNS) {
+            assert!(bank.add_tone(*frequency).is_some());
+        }
+        let block: [f32; 205] =
+            core::array::from_fn(|n| sine(770.0, 0.3, n) + sine(1336.0, 0.3, n));
+        assert!(bank.push_block(&block));
+
+        let powers: [f32; 7] = core::array::from_fn(|i| bank.power(i).unwrap());
+        let row = (0..4).max_by(|&a, &b| powers[a].total_cmp(&powers[b]));
+        let column = (4..7).max_by(|&a, &b| powers[a].total_cmp(&powers[b]));
+        assert_eq!((row, column), (Some(1), Some(5)));
+        assert!(powers[1] > 10.0 * powers[0]);
+    }
+
+    #[test]
+    fn test_limits_and_early_compute() {
+        let mut bank: GoertzelBank<1> = GoertzelBank::new(8000.0, 1000);
+        assert_eq!(bank.add_tone(5000.0), None);
+        assert_eq!(bank.add_tone(600.0), Some(0));
+        assert_eq!(bank.add_tone(700.0), None);
+        assert!(!bank.set_frequency(1, 700.0));
+
+        // A short block can be computed early, scaled by its own length
+        for n in 0..200 {
+            assert!(!bank.push(sine(600.0, 1.0, n)));
+        }
+        bank.compute();
+        assert!((bank.power(0).unwrap() - 0.5).abs() < 0.02);
+
+        bank.reset();
+        assert_eq!(bank.power(0), None);
+        assert!(bank.set_frequency(0, 700.0));
+        assert_eq!(bank.frequency(0), Some(700.0));
+        bank.clear();
+        assert!(bank.is_empty());
+    }
+}
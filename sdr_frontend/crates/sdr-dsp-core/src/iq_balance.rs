@@ -0,0 +1,421 @@
+//! IQ amplitude and phase balance correction.
+//!
+//! A quadrature sampling detector never has its I and Q paths perfectly
+//! matched: a small gain difference and a quadrature error of a degree or
+//! two leave an image of every signal mirrored about the centre
+//! frequency, only 30-40 dB down. [`IqBalance`] removes it by scaling Q
+//! and subtracting the part of I that leaked into it:
+//!
+//! ```text
+//! Q' = (Q / g - I sin(phi)) / cos(phi)      g = 10^(gain_db / 20)
+//! ```
+//!
+//! The correction is set from an [`IqCalibration`] (normally stored per
+//! band, since the imbalance changes with frequency) or found adaptively.
+//! The image is what makes the output correlate with its own mirror, so
+//! the adaptive mode minimizes image power by driving that correlation
+//! (`E[I'^2] - E[Q'^2]` and `E[I'Q']`) to zero, nudging the gain and
+//! phase once per block. The same statistics give an estimate of the
+//! image rejection for calibration displays.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::types::IqSample;
+
+/// Samples per measurement and adaptation block.
+pub const IQ_BALANCE_BLOCK: usize = 512;
+
+/// Best image rejection reported, in dB.
+pub const MAX_IMAGE_REJECTION_DB: f32 = 80.0;
+
+/// Fraction of the measured error corrected per block when adaptive.
+const ADAPT_RATE: f32 = 0.1;
+
+/// Smoothing of the block statistics for the rejection estimate.
+const ESTIMATE_COEFF: f32 = 0.2;
+
+/// Block power below which nothing is measured (no signal).
+const MIN_POWER: f32 = 1e-12;
+
+/// dB per neper of amplitude (20 / ln 10).
+const DB_PER_NEPER: f32 = 8.685_89;
+
+/// Stored IQ balance correction.
+///
+/// Kept in fixed point for the settings record: gain in 0.01 dB (Q
+/// relative to I) and phase in 0.01 degree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IqCalibration {
+    /// Q gain error in 0.01 dB
+    gain_hundredths_db: i16,
+    /// Quadrature error in 0.01 degree
+    phase_hundredths_deg: i16,
+}
+
+impl IqCalibration {
+    /// No correction.
+    pub const IDENTITY: Self = Self {
+        gain_hundredths_db: 0,
+        phase_hundredths_deg: 0,
+    };
+
+    /// Largest gain error magnitude in 0.01 dB (3 dB).
+    pub const MAX_GAIN_HUNDREDTHS_DB: i16 = 300;
+
+    /// Largest phase error magnitude in 0.01 degree (10 degrees).
+    pub const MAX_PHASE_HUNDREDTHS_DEG: i16 = 1000;
+
+    /// Create a calibration from raw units (clamped to the valid range).
+    #[must_use]
+    pub fn new(gain_hundredths_db: i16, phase_hundredths_deg: i16) -> Self {
+        Self {
+            gain_hundredths_db: gain_hundredths_db
+                .clamp(-Self::MAX_GAIN_HUNDREDTHS_DB, Self::MAX_GAIN_HUNDREDTHS_DB),
+            phase_hundredths_deg: phase_hundredths_deg.clamp(
+                -Self::MAX_PHASE_HUNDREDTHS_DEG,
+                Self::MAX_PHASE_HUNDREDTHS_DEG,
+            ),
+        }
+    }
+
+    /// Create a calibration from a gain error in dB and a phase error in
+    /// degrees.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_db(gain_db: f32, phase_deg: f32) -> Self {
+        let gain_max = f32::from(Self::MAX_GAIN_HUNDREDTHS_DB);
+        let phase_max = f32::from(Self::MAX_PHASE_HUNDREDTHS_DEG);
+        Self::new(
+            (gain_db * 100.0).round().clamp(-gain_max, gain_max) as i16,
+            (phase_deg * 100.0).round().clamp(-phase_max, phase_max) as i16,
+        )
+    }
+
+    /// Get the gain error in 0.01 dB.
+    #[must_use]
+    pub const fn gain_hundredths_db(&self) -> i16 {
+        self.gain_hundredths_db
+    }
+
+    /// Get the phase error in 0.01 degree.
+    #[must_use]
+    pub const fn phase_hundredths_deg(&self) -> i16 {
+        self.phase_hundredths_deg
+    }
+
+    /// Get the gain error in dB.
+    #[must_use]
+    pub fn gain_db(&self) -> f32 {
+        f32::from(self.gain_hundredths_db) / 100.0
+    }
+
+    /// Get the phase error in degrees.
+    #[must_use]
+    pub fn phase_deg(&self) -> f32 {
+        f32::from(self.phase_hundredths_deg) / 100.0
+    }
+}
+
+impl Default for IqCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// IQ gain and phase corrector, optionally adaptive.
+#[derive(Clone, Debug)]
+pub struct IqBalance {
+    /// Q gain error in dB
+    gain_db: f32,
+    /// Quadrature error in degrees
+    phase_deg: f32,
+    /// Factor applied to Q
+    q_scale: f32,
+    /// Factor of I subtracted from Q
+    i_leak: f32,
+    /// Adapt the correction to the signal
+    adaptive: bool,
+    /// Block sums of I'^2, Q'^2 and I'Q'
+    sums: [f32; 3],
+    /// Samples in the current block
+    count: usize,
+    /// Smoothed block statistics, once measured
+    stats: Option<[f32; 3]>,
+}
+
+impl IqBalance {
+    /// Create a corrector with no correction, not adaptive.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            gain_db: 0.0,
+            phase_deg: 0.0,
+            q_scale: 1.0,
+            i_leak: 0.0,
+            adaptive: false,
+            sums: [0.0; 3],
+            count: 0,
+            stats: None,
+        }
+    }
+
+    /// Set the correction.
+    pub fn set_calibration(&mut self, calibration: IqCalibration) {
+        self.set_correction(calibration.gain_db(), calibration.phase_deg());
+    }
+
+    /// Get the correction in use (the adapted one when adaptive), e.g. to
+    /// store for the band.
+    #[must_use]
+    pub fn calibration(&self) -> IqCalibration {
+        IqCalibration::from_db(self.gain_db, self.phase_deg)
+    }
+
+    /// Get the gain correction in dB.
+    #[must_use]
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// Get the phase correction in degrees.
+    #[must_use]
+    pub fn phase_deg(&self) -> f32 {
+        self.phase_deg
+    }
+
+    /// Switch adaptive correction on or off.
+    ///
+    /// Switching it off keeps the correction reached so far.
+    pub fn set_adaptive(&mut self, adaptive: bool) {
+        self.adaptive = adaptive;
+    }
+
+    /// Check if the correction adapts to the signal.
+    #[must_use]
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Get the estimated image rejection in dB after correction.
+    ///
+    /// `None` until a block with signal has been measured. The estimate
+    /// assumes the band content has no image of its own (noise or
+    /// signals on one side only), as the adaptive mode does.
+    #[must_use]
+    pub fn image_rejection_db(&self) -> Option<f32> {
+        let [ii, qq, iq] = self.stats?;
+        let power = ii + qq;
+        if power < MIN_POWER {
+            return None;
+        }
+        // Image amplitude relative to the signal
+        let image = ((ii - qq) * (ii - qq) + 4.0 * iq * iq).sqrt() / (2.0 * power);
+        let floor = 10f32.powf(-MAX_IMAGE_REJECTION_DB / 20.0);
+        Some(-20.0 * image.max(floor).log10())
+    }
+
+    /// Correct one sample.
+    pub fn process(&mut self, input: IqSample) -> IqSample {
+        let output = IqSample::new(input.i, input.q * self.q_scale - input.i * self.i_leak);
+        self.sums[0] += output.i * output.i;
+        self.sums[1] += output.q * output.q;
+        self.sums[2] += output.i * output.q;
+        self.count += 1;
+        if self.count == IQ_BALANCE_BLOCK {
+            self.end_block();
+        }
+        output
+    }
+
+    /// Correct a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [IqSample]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Clear the measurement (the correction is kept).
+    pub fn reset(&mut self) {
+        self.sums = [0.0; 3];
+        self.count = 0;
+        self.stats = None;
+    }
+
+    /// Set the correction from a gain error in dB and a phase error in
+    /// degrees, clamped to the [`IqCalibration`] range.
+    fn set_correction(&mut self, gain_db: f32, phase_deg: f32) {
+        let gain_max = f32::from(IqCalibration::MAX_GAIN_HUNDREDTHS_DB) / 100.0;
+        let phase_max = f32::from(IqCalibration::MAX_PHASE_HUNDREDTHS_DEG) / 100.0;
+        self.gain_db = gain_db.clamp(-gain_max, gain_max);
+        self.phase_deg = phase_deg.clamp(-phase_max, phase_max);
+        // The corrections are small, so short series are exact to f32
+        // where micromath's approximations would cap the rejection
+        let x = self.gain_db / DB_PER_NEPER;
+        let gain = 1.0 + x * (1.0 + x / 2.0 * (1.0 + x / 3.0 * (1.0 + x / 4.0 * (1.0 + x / 5.0))));
+        let phi = self.phase_deg.to_radians();
+        let phi2 = phi * phi;
+        let sin = phi * (1.0 - phi2 / 6.0 * (1.0 - phi2 / 20.0));
+        let cos = 1.0 - phi2 / 2.0 * (1.0 - phi2 / 12.0 * (1.0 - phi2 / 30.0));
+        self.q_scale = 1.0 / (gain * cos);
+        self.i_leak = sin / cos;
+    }
+
+    /// Update the statistics at the end of a block, and adapt
+    fn end_block(&mut self) {
+        let sums = self.sums;
+        self.sums = [0.0; 3];
+        self.count = 0;
+        let [ii, qq, iq] = sums;
+        let power = ii + qq;
+        if power < MIN_POWER * IQ_BALANCE_BLOCK as f32 {
+            return;
+        }
+
+        self.stats = Some(match self.stats {
+            Some(stats) => {
+                core::array::from_fn(|k| stats[k] + ESTIMATE_COEFF * (sums[k] - stats[k]))
+            }
+            None => sums,
+        });
+
+        if self.adaptive {
+            // Q' too strong means the gain error is larger than corrected;
+            // I in Q' means the quadrature error is
+            let gain_error_db = DB_PER_NEPER * (qq - ii) / power;
+            let phase_error_deg = (2.0 * iq / power).to_degrees();
+            self.set_correction(
+                self.gain_db + ADAPT_RATE * gain_error_db,
+                self.phase_deg + ADAPT_RATE * phase_error_deg,
+            );
+        }
+    }
+}
+
+impl Default for IqBalance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Detector 0.5 dB and 2 degrees out
+    const PLUS_HALF_DB_2_DEG: (f32, f32, f32) = (1.059_253_7, 0.034_899_5, 0.999_390_8);
+
+    /// Detector -0.8 dB and -3 degrees out
+    const MINUS_0_8_DB_3_DEG: (f32, f32, f32) = (0.912_010_8, -0.052_335_96, 0.998_629_5);
+
+    /// A tone at `hz` through a detector with Q gain `g` and quadrature
+    /// error `phi`, given as `(g, sin(phi), cos(phi))` so the test signal
+    /// does not carry micromath's approximation errors
+    fn imbalanced(n: usize, hz: f32, (gain, sin, cos): (f32, f32, f32)) -> IqSample {
+        let theta = TAU * hz * (n % 48000) as f32 / SAMPLE_RATE;
+        let (s, c) = theta.sin_cos();
+        IqSample::new(0.5 * c, 0.5 * gain * (s * cos + c * sin))
+    }
+
+    /// Image power relative to the tone, measured by correlating with
+    /// both frequencies
+    fn image_db(samples: &[IqSample], hz: f32) -> f32 {
+        let power_at = |f: f32| {
+            let sum = samples
+                .iter()
+                .enumerate()
+                .fold(IqSample::ZERO, |acc, (n, s)| {
+                    acc + s.rotate(-TAU * f * n as f32 / SAMPLE_RATE)
+                });
+            sum.magnitude_squared()
+        };
+        10.0 * (power_at(-hz) / power_at(hz)).log10()
+    }
+
+    #[test]
+    fn test_calibration_is_clamped() {
+        let cal = IqCalibration::from_db(0.5, -1.25);
+        assert_eq!(cal.gain_hundredths_db(), 50);
+        assert_eq!(cal.phase_hundredths_deg(), -125);
+        assert!((cal.phase_deg() + 1.25).abs() < 1e-6);
+
+        let cal = IqCalibration::new(1000, -2000);
+        assert_eq!(
+            cal.gain_hundredths_db(),
+            IqCalibration::MAX_GAIN_HUNDREDTHS_DB
+        );
+        assert_eq!(
+            cal.phase_hundredths_deg(),
+            -IqCalibration::MAX_PHASE_HUNDREDTHS_DEG
+        );
+        assert_eq!(IqCalibration::default(), IqCalibration::IDENTITY);
+    }
+
+    #[test]
+    fn test_identity_passes_samples() {
+        let mut balance = IqBalance::new();
+        let input = IqSample::new(0.3, -0.7);
+        assert_eq!(balance.process(input), input);
+        assert!(balance.image_rejection_db().is_none());
+    }
+
+    #[test]
+    fn test_manual_correction_removes_image() {
+        let hz = 3000.0;
+        let input: [IqSample; 4800] =
+            core::array::from_fn(|n| imbalanced(n, hz, PLUS_HALF_DB_2_DEG));
+        assert!(image_db(&input, hz) > -35.0);
+
+        let mut balance = IqBalance::new();
+        balance.set_calibration(IqCalibration::from_db(0.5, 2.0));
+        let mut output = input;
+        balance.process_block(&mut output);
+        assert!(image_db(&output, hz) < -60.0);
+        assert!(balance.image_rejection_db().unwrap() > 60.0);
+        assert_eq!(balance.calibration(), IqCalibration::new(50, 200));
+    }
+
+    #[test]
+    fn test_adaptive_finds_correction() {
+        let hz = -6000.0;
+        let mut balance = IqBalance::new();
+        balance.set_adaptive(true);
+        let mut output = [IqSample::ZERO; 4800];
+        for block in 0..20 {
+            for (k, out) in output.iter_mut().enumerate() {
+                *out = balance.process(imbalanced(block * 4800 + k, hz, MINUS_0_8_DB_3_DEG));
+            }
+        }
+        assert!(image_db(&output, hz) < -50.0, "{}", image_db(&output, hz));
+        assert!(
+            (balance.gain_db() + 0.8).abs() < 0.05,
+            "{}",
+            balance.gain_db()
+        );
+        assert!(
+            (balance.phase_deg() + 3.0).abs() < 0.1,
+            "{}",
+            balance.phase_deg()
+        );
+        assert!(balance.image_rejection_db().unwrap() > 50.0);
+
+        // Switching off keeps what was found
+        balance.set_adaptive(false);
+        assert_eq!(balance.calibration(), IqCalibration::new(-80, -300));
+    }
+
+    #[test]
+    fn test_rejection_estimate_without_correction() {
+        let hz = 1000.0;
+        let mut balance = IqBalance::new();
+        for n in 0..9600 {
+            balance.process(imbalanced(n, hz, (1.122_018_5, 0.0, 1.0)));
+        }
+        // 1 dB gain error alone leaves the image about 25 dB down
+        let estimate = balance.image_rejection_db().unwrap();
+        assert!((estimate - 24.8).abs() < 1.0, "{estimate}");
+    }
+}
@@ -0,0 +1,381 @@
+//! Blind IQ imbalance estimation and correction.
+//!
+//! [`IqEstimator`] finds the detector's gain and quadrature errors from
+//! the received signal alone, with no calibration tone, using the
+//! Moseley-Slump feed-forward algorithm. For a detector whose Q path has
+//! gain `g` and phase error `phi`, three running averages of the input
+//! give the correction directly:
+//!
+//! ```text
+//! t1 = -E[sgn(I) Q]    t2 = E[|I|]    t3 = E[|Q|]
+//! c1 = t1 / t2 = -g sin(phi)
+//! c2 = sqrt(t3^2 - t1^2) / t2 = g cos(phi)
+//! Q' = (Q + c1 I) / c2
+//! ```
+//!
+//! It needs only signs, magnitudes and one square root per update, so it
+//! is cheap enough to run continuously. Like any blind method it assumes
+//! the band content is balanced on its own (noise, or signals not all on
+//! one side mirrored about the centre) and free of DC, so it belongs
+//! after the DC blocker. The averaging time constant trades tracking speed
+//! against estimate noise; the estimate can be frozen once settled and
+//! read back as an [`IqCalibration`] to store for the band.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::iq_balance::IqCalibration;
+use crate::types::IqSample;
+
+/// Default averaging time constant in samples (0.5 s at 48 kHz).
+pub const DEFAULT_TIME_CONSTANT: u32 = 24_000;
+
+/// Shortest averaging time constant in samples.
+pub const MIN_TIME_CONSTANT: u32 = 256;
+
+/// Longest averaging time constant in samples.
+pub const MAX_TIME_CONSTANT: u32 = 1 << 20;
+
+/// Samples between updates of the correction.
+const UPDATE_INTERVAL: u32 = 64;
+
+/// Time constants of averaging before the estimate counts as converged
+/// (within 5% of its final value).
+const SETTLE_TIME_CONSTANTS: u32 = 3;
+
+/// Average magnitude below which nothing is estimated (no signal).
+const MIN_LEVEL: f32 = 1e-6;
+
+/// dB per neper of amplitude (20 / ln 10).
+const DB_PER_NEPER: f32 = 8.685_89;
+
+/// Moseley-Slump blind IQ imbalance estimator and corrector.
+#[derive(Clone, Debug)]
+pub struct IqEstimator {
+    /// Averaging time constant in samples
+    time_constant: u32,
+    /// Running averages of -sgn(I) Q, |I| and |Q|
+    theta: [f32; 3],
+    /// Samples averaged, saturating once converged
+    seen: u32,
+    /// Samples since the correction was last updated
+    since_update: u32,
+    /// Hold the estimate
+    frozen: bool,
+    /// Correction in use, once estimated
+    correction: Option<Correction>,
+}
+
+/// Correction factors derived from the averages.
+#[derive(Clone, Copy, Debug)]
+struct Correction {
+    /// Factor of I added to Q (c1 / c2)
+    i_mix: f32,
+    /// Factor applied to Q (1 / c2)
+    q_scale: f32,
+    /// Q gain error in dB
+    gain_db: f32,
+    /// Quadrature error in degrees
+    phase_deg: f32,
+}
+
+impl IqEstimator {
+    /// Create an estimator with the default time constant and no
+    /// correction yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            time_constant: DEFAULT_TIME_CONSTANT,
+            theta: [0.0; 3],
+            seen: 0,
+            since_update: 0,
+            frozen: false,
+            correction: None,
+        }
+    }
+
+    /// Set the averaging time constant in samples, clamped to
+    /// [`MIN_TIME_CONSTANT`]..=[`MAX_TIME_CONSTANT`].
+    ///
+    /// Shorter follows band and tuning changes faster; longer gives a
+    /// steadier estimate on weak or sparse signals.
+    pub fn set_time_constant(&mut self, samples: u32) {
+        self.time_constant = samples.clamp(MIN_TIME_CONSTANT, MAX_TIME_CONSTANT);
+    }
+
+    /// Get the averaging time constant in samples.
+    #[must_use]
+    pub fn time_constant(&self) -> u32 {
+        self.time_constant
+    }
+
+    /// Freeze or resume estimation.
+    ///
+    /// A frozen estimator keeps applying the correction it has without
+    /// updating it, e.g. once converged or while transmitting.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Check if estimation is frozen.
+    #[must_use]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Check if the estimate has averaged long enough to be trusted.
+    #[must_use]
+    pub fn is_converged(&self) -> bool {
+        self.correction.is_some()
+            && self.seen >= SETTLE_TIME_CONSTANTS.saturating_mul(self.time_constant)
+    }
+
+    /// Get the estimated Q gain error in dB, once estimated.
+    #[must_use]
+    pub fn gain_db(&self) -> Option<f32> {
+        self.correction.map(|c| c.gain_db)
+    }
+
+    /// Get the estimated quadrature error in degrees, once estimated.
+    #[must_use]
+    pub fn phase_deg(&self) -> Option<f32> {
+        self.correction.map(|c| c.phase_deg)
+    }
+
+    /// Get the estimate as a stored calibration (clamped to its range),
+    /// e.g. to keep for the band or hand to an
+    /// [`IqBalance`](crate::iq_balance::IqBalance).
+    #[must_use]
+    pub fn calibration(&self) -> Option<IqCalibration> {
+        self.correction
+            .map(|c| IqCalibration::from_db(c.gain_db, c.phase_deg))
+    }
+
+    /// Estimate from and correct one sample.
+    pub fn process(&mut self, input: IqSample) -> IqSample {
+        if !self.frozen {
+            self.update(input);
+        }
+        match self.correction {
+            Some(c) => IqSample::new(input.i, input.q * c.q_scale + input.i * c.i_mix),
+            None => input,
+        }
+    }
+
+    /// Estimate from and correct a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [IqSample]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Discard the estimate and start again (the settings are kept).
+    pub fn reset(&mut self) {
+        self.theta = [0.0; 3];
+        self.seen = 0;
+        self.since_update = 0;
+        self.correction = None;
+    }
+
+    /// Fold a sample into the averages and refresh the correction
+    #[allow(clippy::cast_precision_loss)]
+    fn update(&mut self, input: IqSample) {
+        let settle = SETTLE_TIME_CONSTANTS.saturating_mul(self.time_constant);
+        self.seen = self.seen.saturating_add(1).min(settle);
+        // Running mean until one time constant in, so the start is not
+        // biased towards zero, then exponential
+        let coeff = 1.0 / self.seen.min(self.time_constant) as f32;
+        let sample = [
+            if input.i < 0.0 { input.q } else { -input.q },
+            input.i.abs(),
+            input.q.abs(),
+        ];
+        for (avg, x) in self.theta.iter_mut().zip(sample) {
+            *avg += coeff * (x - *avg);
+        }
+
+        self.since_update += 1;
+        if self.since_update >= UPDATE_INTERVAL {
+            self.since_update = 0;
+            if let Some(correction) = self.estimate() {
+                self.correction = Some(correction);
+            }
+        }
+    }
+
+    /// Derive the correction from the averages
+    ///
+    /// `None` without signal, or when the estimate is outside what any
+    /// detector could need (the band content is not balanced), in which
+    /// case the previous correction stays.
+    fn estimate(&self) -> Option<Correction> {
+        let [t1, t2, t3] = self.theta;
+        if t2 < MIN_LEVEL || t3 < MIN_LEVEL {
+            return None;
+        }
+        let c1 = t1 / t2;
+        let c2 = (t3 * t3 - t1 * t1).max(0.0).sqrt() / t2;
+        if c2 < MIN_LEVEL {
+            return None;
+        }
+        let gain = (c1 * c1 + c2 * c2).sqrt();
+        let gain_db = DB_PER_NEPER * ln_near_one(gain);
+        let phase_deg = asin_small(-c1 / gain).to_degrees();
+        let gain_max = f32::from(IqCalibration::MAX_GAIN_HUNDREDTHS_DB) / 100.0;
+        let phase_max = f32::from(IqCalibration::MAX_PHASE_HUNDREDTHS_DEG) / 100.0;
+        if gain_db.abs() > gain_max || phase_deg.abs() > phase_max {
+            return None;
+        }
+        Some(Correction {
+            i_mix: c1 / c2,
+            q_scale: 1.0 / c2,
+            gain_db,
+            phase_deg,
+        })
+    }
+}
+
+impl Default for IqEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Natural log of a value near one, by the atanh series (exact to f32
+/// within the calibration range, unlike micromath's approximation)
+fn ln_near_one(x: f32) -> f32 {
+    let u = (x - 1.0) / (x + 1.0);
+    let u2 = u * u;
+    2.0 * u * (1.0 + u2 / 3.0 * (1.0 + u2 * 3.0 / 5.0 * (1.0 + u2 * 5.0 / 7.0)))
+}
+
+/// Arcsine of a small value, by its series (the phase error is at most
+/// about 10 degrees)
+fn asin_small(x: f32) -> f32 {
+    let x2 = x * x;
+    x * (1.0 + x2 / 6.0 * (1.0 + x2 * 9.0 / 20.0 * (1.0 + x2 * 25.0 / 42.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Detector -0.8 dB and -3 degrees out, as `(g, sin(phi), cos(phi))`
+    const MINUS_0_8_DB_3_DEG: (f32, f32, f32) = (0.912_010_8, -0.052_335_96, 0.998_629_5);
+
+    /// Detector 1.5 dB and 5 degrees out
+    const PLUS_1_5_DB_5_DEG: (f32, f32, f32) = (1.188_502_2, 0.087_155_74, 0.996_194_7);
+
+    /// A tone at `hz` through an imbalanced detector
+    fn imbalanced(n: usize, hz: f32, (gain, sin, cos): (f32, f32, f32)) -> IqSample {
+        let theta = TAU * hz * (n % 48000) as f32 / SAMPLE_RATE;
+        let (s, c) = theta.sin_cos();
+        IqSample::new(0.5 * c, 0.5 * gain * (s * cos + c * sin))
+    }
+
+    /// Image power relative to the tone
+    fn image_db(samples: &[IqSample], hz: f32) -> f32 {
+        let power_at = |f: f32| {
+            let sum = samples
+                .iter()
+                .enumerate()
+                .fold(IqSample::ZERO, |acc, (n, s)| {
+                    acc + s.rotate(-TAU * f * n as f32 / SAMPLE_RATE)
+                });
+            sum.magnitude_squared()
+        };
+        10.0 * (power_at(-hz) / power_at(hz)).log10()
+    }
+
+    /// Run `seconds` of an imbalanced tone through, returning the last
+    /// 0.1 s of output
+    fn run(
+        estimator: &mut IqEstimator,
+        seconds: usize,
+        hz: f32,
+        detector: (f32, f32, f32),
+    ) -> [IqSample; 4800] {
+        let mut output = [IqSample::ZERO; 4800];
+        for block in 0..seconds * 10 {
+            for (k, out) in output.iter_mut().enumerate() {
+                *out = estimator.process(imbalanced(block * 4800 + k, hz, detector));
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn test_no_signal_passes_through() {
+        let mut estimator = IqEstimator::new();
+        for _ in 0..1000 {
+            assert_eq!(estimator.process(IqSample::ZERO), IqSample::ZERO);
+        }
+        assert!(estimator.gain_db().is_none());
+        assert!(estimator.calibration().is_none());
+        assert!(!estimator.is_converged());
+    }
+
+    #[test]
+    fn test_estimates_and_corrects_imbalance() {
+        let hz = 1510.0;
+        let mut estimator = IqEstimator::new();
+        let output = run(&mut estimator, 2, hz, MINUS_0_8_DB_3_DEG);
+
+        assert!(estimator.is_converged());
+        let gain = estimator.gain_db().unwrap();
+        let phase = estimator.phase_deg().unwrap();
+        assert!((gain + 0.8).abs() < 0.05, "{gain}");
+        assert!((phase + 3.0).abs() < 0.1, "{phase}");
+        assert_eq!(
+            estimator.calibration(),
+            Some(IqCalibration::from_db(gain, phase))
+        );
+        assert!(image_db(&output, hz) < -45.0, "{}", image_db(&output, hz));
+    }
+
+    #[test]
+    fn test_time_constant_sets_convergence() {
+        let hz = -4321.0;
+        let mut estimator = IqEstimator::new();
+        estimator.set_time_constant(1);
+        assert_eq!(estimator.time_constant(), MIN_TIME_CONSTANT);
+        estimator.set_time_constant(u32::MAX);
+        assert_eq!(estimator.time_constant(), MAX_TIME_CONSTANT);
+
+        // A short time constant settles within a tenth of a second
+        estimator.set_time_constant(1200);
+        for n in 0..4800 {
+            estimator.process(imbalanced(n, hz, PLUS_1_5_DB_5_DEG));
+        }
+        assert!(estimator.is_converged());
+        assert!((estimator.gain_db().unwrap() - 1.5).abs() < 0.1);
+        assert!((estimator.phase_deg().unwrap() - 5.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_frozen_holds_estimate() {
+        let hz = 1510.0;
+        let mut estimator = IqEstimator::new();
+        estimator.set_time_constant(4800);
+        run(&mut estimator, 1, hz, MINUS_0_8_DB_3_DEG);
+        let held = estimator.calibration().unwrap();
+
+        // The detector changes, but a frozen estimate does not follow
+        estimator.set_frozen(true);
+        assert!(estimator.is_frozen());
+        run(&mut estimator, 1, hz, PLUS_1_5_DB_5_DEG);
+        assert_eq!(estimator.calibration(), Some(held));
+
+        // Resumed, it tracks the new imbalance
+        estimator.set_frozen(false);
+        run(&mut estimator, 1, hz, PLUS_1_5_DB_5_DEG);
+        assert!((estimator.gain_db().unwrap() - 1.5).abs() < 0.1);
+
+        estimator.reset();
+        assert!(estimator.calibration().is_none());
+        assert_eq!(estimator.time_constant(), 4800);
+    }
+}
@@ -0,0 +1,222 @@
+//! Vectorized DSP kernels.
+//!
+//! The inner loops the browser DSP chain spends its time in: FIR dot
+//! products, complex multiplication for mixing, and biquads run over a
+//! block. With the `simd` feature on a `wasm32` build with `simd128`
+//! enabled (`RUSTFLAGS="-C target-feature=+simd128"`) they use 128-bit
+//! WebAssembly SIMD; otherwise a scalar version is used, written with
+//! independent accumulators so native builds can auto-vectorize it.
+//! Both give the same results to within float rounding.
+
+use crate::filter::BiquadCoeffs;
+use crate::types::IqSample;
+
+/// Check if the kernels use WebAssembly SIMD in this build.
+#[must_use]
+pub const fn is_accelerated() -> bool {
+    cfg!(all(
+        feature = "simd",
+        target_arch = "wasm32",
+        target_feature = "simd128"
+    ))
+}
+
+/// Sum of the products of `a` and `b` (up to the shorter length).
+#[must_use]
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
+    imp::dot(&a[..len], &b[..len])
+}
+
+/// Multiply each sample of `signal` by the matching sample of `lo`.
+///
+/// Used to mix a block against a local oscillator; samples past the end
+/// of `lo` are left as they are.
+pub fn complex_multiply(signal: &mut [IqSample], lo: &[IqSample]) {
+    let len = signal.len().min(lo.len());
+    imp::complex_multiply(&mut signal[..len], &lo[..len]);
+}
+
+/// Run a biquad (Direct Form II Transposed) over I and Q in place.
+///
+/// `state` holds the filter memory as `[z1_i, z1_q, z2_i, z2_q]`.
+pub fn biquad_iq(coeffs: &BiquadCoeffs, state: &mut [f32; 4], samples: &mut [IqSample]) {
+    imp::biquad_iq(coeffs, state, samples);
+}
+
+/// Plain Rust kernels.
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+mod imp {
+    use crate::filter::BiquadCoeffs;
+    use crate::types::IqSample;
+
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut acc = [0.0f32; 4];
+        for (x, y) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+            acc[0] += x[0] * y[0];
+            acc[1] += x[1] * y[1];
+            acc[2] += x[2] * y[2];
+            acc[3] += x[3] * y[3];
+        }
+        let split = a.len() - a.len() % 4;
+        let tail: f32 = a[split..].iter().zip(&b[split..]).map(|(x, y)| x * y).sum();
+        (acc[0] + acc[1]) + (acc[2] + acc[3]) + tail
+    }
+
+    pub fn complex_multiply(signal: &mut [IqSample], lo: &[IqSample]) {
+        for (s, l) in signal.iter_mut().zip(lo) {
+            *s = IqSample::new(s.i * l.i - s.q * l.q, s.i * l.q + s.q * l.i);
+        }
+    }
+
+    pub fn biquad_iq(c: &BiquadCoeffs, state: &mut [f32; 4], samples: &mut [IqSample]) {
+        let [mut z1_i, mut z1_q, mut z2_i, mut z2_q] = *state;
+        for s in samples {
+            let y_i = c.b0 * s.i + z1_i;
+            let y_q = c.b0 * s.q + z1_q;
+            z1_i = c.b1 * s.i - c.a1 * y_i + z2_i;
+            z1_q = c.b1 * s.q - c.a1 * y_q + z2_q;
+            z2_i = c.b2 * s.i - c.a2 * y_i;
+            z2_q = c.b2 * s.q - c.a2 * y_q;
+            *s = IqSample::new(y_i, y_q);
+        }
+        *state = [z1_i, z1_q, z2_i, z2_q];
+    }
+}
+
+/// WebAssembly SIMD kernels.
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+mod imp {
+    use core::arch::wasm32::{
+        f32x4, f32x4_add, f32x4_extract_lane, f32x4_mul, f32x4_splat, f32x4_sub, i32x4_shuffle,
+        v128,
+    };
+
+    use crate::filter::BiquadCoeffs;
+    use crate::types::IqSample;
+
+    /// Load four floats from the start of a slice.
+    #[inline(always)]
+    fn load(x: &[f32]) -> v128 {
+        f32x4(x[0], x[1], x[2], x[3])
+    }
+
+    /// Load two IQ samples as `[i0, q0, i1, q1]`.
+    #[inline(always)]
+    fn load_iq(x: &[IqSample]) -> v128 {
+        f32x4(x[0].i, x[0].q, x[1].i, x[1].q)
+    }
+
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut acc = f32x4_splat(0.0);
+        for (x, y) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+            acc = f32x4_add(acc, f32x4_mul(load(x), load(y)));
+        }
+        let split = a.len() - a.len() % 4;
+        let tail: f32 = a[split..].iter().zip(&b[split..]).map(|(x, y)| x * y).sum();
+        (f32x4_extract_lane::<0>(acc) + f32x4_extract_lane::<1>(acc))
+            + (f32x4_extract_lane::<2>(acc) + f32x4_extract_lane::<3>(acc))
+            + tail
+    }
+
+    pub fn complex_multiply(signal: &mut [IqSample], lo: &[IqSample]) {
+        let sign = f32x4(-1.0, 1.0, -1.0, 1.0);
+        let mut pairs = signal.chunks_exact_mut(2);
+        for (s, l) in (&mut pairs).zip(lo.chunks_exact(2)) {
+            let a = load_iq(s);
+            let b = load_iq(l);
+            // [i*c, q*c] + [-q*s, i*s] for each sample
+            let b_re = i32x4_shuffle::<0, 0, 2, 2>(b, b);
+            let b_im = i32x4_shuffle::<1, 1, 3, 3>(b, b);
+            let a_swap = i32x4_shuffle::<1, 0, 3, 2>(a, a);
+            let out = f32x4_add(f32x4_mul(a, b_re), f32x4_mul(sign, f32x4_mul(a_swap, b_im)));
+            s[0] = IqSample::new(f32x4_extract_lane::<0>(out), f32x4_extract_lane::<1>(out));
+            s[1] = IqSample::new(f32x4_extract_lane::<2>(out), f32x4_extract_lane::<3>(out));
+        }
+        if let ([s], [l]) = (pairs.into_remainder(), lo.chunks_exact(2).remainder()) {
+            *s = IqSample::new(s.i * l.i - s.q * l.q, s.i * l.q + s.q * l.i);
+        }
+    }
+
+    pub fn biquad_iq(c: &BiquadCoeffs, state: &mut [f32; 4], samples: &mut [IqSample]) {
+        // I and Q ride in lanes 0 and 1
+        let (b0, b1, b2) = (f32x4_splat(c.b0), f32x4_splat(c.b1), f32x4_splat(c.b2));
+        let (a1, a2) = (f32x4_splat(c.a1), f32x4_splat(c.a2));
+        let mut z1 = f32x4(state[0], state[1], 0.0, 0.0);
+        let mut z2 = f32x4(state[2], state[3], 0.0, 0.0);
+        for s in samples {
+            let x = f32x4(s.i, s.q, 0.0, 0.0);
+            let y = f32x4_add(f32x4_mul(b0, x), z1);
+            z1 = f32x4_add(f32x4_sub(f32x4_mul(b1, x), f32x4_mul(a1, y)), z2);
+            z2 = f32x4_sub(f32x4_mul(b2, x), f32x4_mul(a2, y));
+            *s = IqSample::new(f32x4_extract_lane::<0>(y), f32x4_extract_lane::<1>(y));
+        }
+        *state = [
+            f32x4_extract_lane::<0>(z1),
+            f32x4_extract_lane::<1>(z1),
+            f32x4_extract_lane::<0>(z2),
+            f32x4_extract_lane::<1>(z2),
+        ];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[allow(unused_imports)]
+    use micromath::F32Ext;
+
+    fn value(n: usize) -> f32 {
+        ((n * 37 % 101) as f32 - 50.0) / 50.0
+    }
+
+    fn iq_block<const N: usize>() -> [IqSample; N] {
+        core::array::from_fn(|n| IqSample::new(value(n), value(n + 9)))
+    }
+
+    #[test]
+    fn test_dot_matches_naive() {
+        let a: [f32; 511] = core::array::from_fn(|n| value(n + 1));
+        let b: [f32; 511] = core::array::from_fn(|n| value(n + 5));
+        for len in [0, 1, 3, 4, 7, 64, 511] {
+            let naive: f32 = a[..len].iter().zip(&b[..len]).map(|(x, y)| x * y).sum();
+            assert!((dot(&a[..len], &b[..len]) - naive).abs() < 1e-3, "len {len}");
+        }
+        // Mismatched lengths use the shorter
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[2.0, 2.0]), 6.0);
+    }
+
+    #[test]
+    fn test_complex_multiply() {
+        let original = iq_block::<5>();
+        let mut samples = original;
+        let lo: [IqSample; 4] = core::array::from_fn(|n| {
+            let phase = n as f32 * 0.7;
+            IqSample::new(phase.cos(), phase.sin())
+        });
+        complex_multiply(&mut samples, &lo);
+        for ((out, s), l) in samples.iter().zip(&original).zip(&lo) {
+            assert!((out.i - (s.i * l.i - s.q * l.q)).abs() < 1e-6);
+            assert!((out.q - (s.i * l.q + s.q * l.i)).abs() < 1e-6);
+        }
+        // Past the end of the oscillator block nothing changes
+        assert_eq!(samples[4], original[4]);
+    }
+
+    #[test]
+    fn test_biquad_iq_matches_per_sample() {
+        let coeffs = BiquadCoeffs::lowpass(48000.0, 2000.0, 0.707);
+        let mut i_filter = crate::Biquad::new(coeffs);
+        let mut q_filter = crate::Biquad::new(coeffs);
+        let mut samples = iq_block::<100>();
+        let expected = samples.map(|s| IqSample::new(i_filter.process(s.i), q_filter.process(s.q)));
+
+        let mut state = [0.0; 4];
+        let (first, second) = samples.split_at_mut(37);
+        biquad_iq(&coeffs, &mut state, first);
+        biquad_iq(&coeffs, &mut state, second);
+        for (out, want) in samples.iter().zip(&expected) {
+            assert!((out.i - want.i).abs() < 1e-5 && (out.q - want.q).abs() < 1e-5);
+        }
+    }
+}
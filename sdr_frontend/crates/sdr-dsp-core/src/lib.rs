@@ -7,9 +7,28 @@
 //!
 //! - [`types`] - Core types: IqSample, SignalMetrics
 //! - [`filter`] - Digital filters: Biquad, FIR, DC blocker
+//! - [`goertzel`] - Goertzel tone detector bank: CTCSS, DTMF, pitch tracking
+//! - [`convolve`] - Overlap-save FFT convolution for long FIR filters
+//! - [`kernels`] - Dot product, complex multiply and biquad kernels (WASM SIMD)
 //! - [`oscillator`] - Signal generators: NCO, quadrature oscillator
-//! - [`agc`] - Automatic gain control and S-meter
+//! - [`demod`] - Stateless block demodulators: SSB, AM, FM
+//! - [`modulate`] - Phasing-method SSB modulator for transmit
+//! - [`agc`] - Automatic gain control, S-meter and squelch
+//! - [`blanker`] - IQ-domain impulse noise blanker
+//! - [`iq_balance`] - IQ gain and phase imbalance (image rejection) correction
+//! - [`iq_estimator`] - Blind (Moseley-Slump) IQ imbalance estimation
+//! - [`nr`] - Adaptive LMS and spectral subtraction noise reduction
+//! - [`conditions`] - Per-band condition scores from spots and noise floor
 //! - [`spectrum`] - Spectrum analysis: sliding DFT, waterfall data
+//! - [`units`] - Level conversions and frequency/level/power formatting
+//! - [`occupancy`] - Per-bin occupancy statistics for QRM surveys
+//! - [`pitch`] - CW pitch and offset from the sidetone for zero-beating
+//! - [`quality`] - Decoder signal quality: SNR, EVM and two-tone IMD
+//! - [`resample`] - Sample rate conversion for IQ streams
+//! - [`snr`] - Audio passband signal power, noise floor and SNR
+//! - [`speech`] - Transmit speech compressor and limiter
+//! - [`tone`] - Tone generator bank for test signals, sidetone and GFSK
+//! - [`wav`] - WAV encoding and decoding for recordings and playback
 
 #![no_std]
 #![deny(unsafe_code)]
@@ -19,14 +38,50 @@
 extern crate std;
 
 pub mod agc;
+pub mod blanker;
+pub mod conditions;
+pub mod convolve;
+pub mod demod;
 pub mod filter;
+pub mod goertzel;
+pub mod iq_balance;
+pub mod iq_estimator;
+pub mod kernels;
+pub mod modulate;
+pub mod nr;
+pub mod occupancy;
 pub mod oscillator;
+pub mod pitch;
+pub mod quality;
+pub mod resample;
+pub mod snr;
 pub mod spectrum;
+pub mod speech;
+pub mod tone;
 pub mod types;
+pub mod units;
+pub mod wav;
 
 // Re-export commonly used types
-pub use agc::{Agc, AgcConfig, SMeter};
-pub use filter::{Biquad, BiquadCoeffs, DcBlocker};
-pub use oscillator::{CostasLoop, Nco, QuadratureOscillator};
-pub use spectrum::{FftSpectrum, SlidingDft, SpectrumBin, SpectrumConfig, WaterfallRow};
+pub use agc::{Agc, AgcConfig, SMeter, SmeterCalibration, Squelch};
+pub use blanker::IqBlanker;
+pub use convolve::FftConvolver;
+pub use filter::{Biquad, BiquadCoeffs, DcBlocker, DcBlockerIq};
+pub use goertzel::GoertzelBank;
+pub use iq_balance::{IqBalance, IqCalibration};
+pub use iq_estimator::IqEstimator;
+pub use modulate::SsbModulator;
+pub use nr::{NoiseReducer, SpectralSubtractor};
+pub use oscillator::{
+    CostasLoop, Nco, QuadratureOscillator, COSTAS_LOCK_THRESHOLD, COSTAS_UNLOCK_THRESHOLD,
+};
+pub use pitch::PitchMeter;
+pub use quality::{ImdMeter, QualityMeter, SignalQuality};
+pub use spectrum::{
+    find_peaks, DcNotch, DisplayRange, DisplayScale, FftSpectrum, SlidingDft, SpectrumAverager,
+    SpectrumBin, SpectrumCalibration, SpectrumConfig, SpectrumPeak, SpectrumView, WaterfallRow,
+};
+pub use snr::{SnrMeter, SnrReading};
+pub use speech::Compressor;
+pub use tone::ToneGenerator;
 pub use types::{IqSample, SignalMetrics};
@@ -0,0 +1,141 @@
+//! SSB modulation.
+//!
+//! Turns band-limited audio into baseband IQ by the phasing method: I is
+//! the audio delayed to line up with Q, the audio through a Hilbert
+//! transformer. Upper sideband puts the audio at positive frequencies,
+//! lower sideband at negative ones, ready for the radio's mixer.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::kernels;
+use crate::types::IqSample;
+
+/// Hilbert transformer length (odd, so the delay is a whole sample count).
+pub const HILBERT_TAPS: usize = 127;
+
+/// Delay through the modulator in samples.
+pub const MODULATOR_DELAY: usize = (HILBERT_TAPS - 1) / 2;
+
+/// Phasing-method SSB modulator.
+#[derive(Clone, Debug)]
+pub struct SsbModulator {
+    /// Hilbert transformer taps, newest sample first.
+    taps: [f32; HILBERT_TAPS],
+    /// Audio history, written twice so the newest `HILBERT_TAPS` are contiguous.
+    history: [f32; HILBERT_TAPS * 2],
+    /// Next write position in the first half of the history.
+    pos: usize,
+    /// Upper (true) or lower sideband.
+    usb: bool,
+}
+
+impl SsbModulator {
+    /// Create a modulator for the upper (`usb`) or lower sideband.
+    #[must_use]
+    pub fn new(usb: bool) -> Self {
+        let center = MODULATOR_DELAY as f32;
+        let span = (HILBERT_TAPS - 1) as f32;
+        let two_pi = 2.0 * core::f32::consts::PI;
+        let mut taps = [0.0; HILBERT_TAPS];
+        for (n, tap) in taps.iter_mut().enumerate() {
+            // Odd offsets only, Blackman windowed
+            let k = n as f32 - center;
+            if (n + MODULATOR_DELAY) % 2 == 1 {
+                let phase = two_pi * n as f32 / span;
+                let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                *tap = 2.0 / (core::f32::consts::PI * k) * window;
+            }
+        }
+        // Stored newest first, which negates the antisymmetric response
+        taps.reverse();
+        Self {
+            taps,
+            history: [0.0; HILBERT_TAPS * 2],
+            pos: 0,
+            usb,
+        }
+    }
+
+    /// Select the upper (true) or lower sideband.
+    pub fn set_usb(&mut self, usb: bool) {
+        self.usb = usb;
+    }
+
+    /// Check if the modulator makes upper sideband.
+    #[must_use]
+    pub fn is_usb(&self) -> bool {
+        self.usb
+    }
+
+    /// Modulate one audio sample.
+    pub fn process(&mut self, audio: f32) -> IqSample {
+        self.history[self.pos] = audio;
+        self.history[self.pos + HILBERT_TAPS] = audio;
+        self.pos = (self.pos + 1) % HILBERT_TAPS;
+
+        // Oldest to newest
+        let window = &self.history[self.pos..self.pos + HILBERT_TAPS];
+        let i = window[MODULATOR_DELAY];
+        let q = kernels::dot(window, &self.taps);
+        IqSample::new(i, if self.usb { q } else { -q })
+    }
+
+    /// Modulate a block of audio into `iq` (up to the shorter length).
+    pub fn process_block(&mut self, audio: &[f32], iq: &mut [IqSample]) {
+        for (out, &sample) in iq.iter_mut().zip(audio) {
+            *out = self.process(sample);
+        }
+    }
+
+    /// Clear the audio history.
+    pub fn reset(&mut self) {
+        self.history = [0.0; HILBERT_TAPS * 2];
+        self.pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    /// Power at +`freq` and -`freq` in a block of IQ.
+    fn sideband_powers(iq: &[IqSample], freq: f32) -> (f32, f32) {
+        let (mut up, mut down) = (IqSample::ZERO, IqSample::ZERO);
+        for (n, s) in iq.iter().enumerate() {
+            let phase = TAU * freq * n as f32;
+            let (c, sn) = (phase.cos(), phase.sin());
+            // Correlate with e^(-j phase) and e^(+j phase)
+            up = IqSample::new(up.i + s.i * c + s.q * sn, up.q + s.q * c - s.i * sn);
+            down = IqSample::new(down.i + s.i * c - s.q * sn, down.q + s.q * c + s.i * sn);
+        }
+        (up.i * up.i + up.q * up.q, down.i * down.i + down.q * down.q)
+    }
+
+    #[test]
+    fn test_sideband_selection() {
+        for usb in [true, false] {
+            let mut modulator = SsbModulator::new(usb);
+            let freq = 1000.0 / 48000.0;
+            let audio: [f32; 2048] =
+                core::array::from_fn(|n| 0.5 * (TAU * freq * n as f32).cos());
+            let mut iq = [IqSample::ZERO; 2048];
+            modulator.process_block(&audio, &mut iq);
+
+            let (up, down) = sideband_powers(&iq[HILBERT_TAPS..], freq);
+            let (wanted, unwanted) = if usb { (up, down) } else { (down, up) };
+            // Opposite sideband suppressed by at least 40 dB
+            assert!(wanted > unwanted * 1e4, "usb {usb}: {wanted} vs {unwanted}");
+        }
+    }
+
+    #[test]
+    fn test_delay_lines_up_i_with_audio() {
+        let mut modulator = SsbModulator::new(true);
+        for n in 0..MODULATOR_DELAY {
+            assert_eq!(modulator.process(if n == 0 { 1.0 } else { 0.0 }).i, 0.0);
+        }
+        assert_eq!(modulator.process(0.0).i, 1.0);
+    }
+}
@@ -0,0 +1,457 @@
+//! Adaptive noise reduction.
+//!
+//! A normalized LMS line enhancer: an adaptive FIR predicts each audio
+//! sample from samples a few milliseconds old. Speech and CW are
+//! correlated over that span and are predicted; white noise is not, so
+//! the prediction is the signal with much of the hiss removed.
+//!
+//! [`SpectralSubtractor`] works in the frequency domain instead: it
+//! estimates the noise floor in every FFT bin and scales each bin by a
+//! Wiener gain, which copes with coloured noise the predictor cannot.
+
+use crate::convolve::fft_in_place;
+use crate::types::IqSample;
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Number of adaptive filter taps.
+pub const NR_TAPS: usize = 32;
+
+/// Delay between the input and the newest tap, in samples.
+///
+/// Long enough to decorrelate the noise, short enough that the signal
+/// is still predictable from it.
+pub const NR_DELAY: usize = 16;
+
+/// Highest noise reduction level.
+pub const MAX_NR_LEVEL: u8 = 10;
+
+/// Weight leakage per sample, so the filter forgets stale signals.
+const LEAKAGE: f32 = 0.9999;
+
+/// History length, a power of two holding the delay and the taps.
+const HISTORY: usize = 64;
+
+/// LMS noise reducer.
+#[derive(Clone, Debug)]
+pub struct NoiseReducer {
+    /// Filter weights.
+    weights: [f32; NR_TAPS],
+    /// Past input samples (circular).
+    history: [f32; HISTORY],
+    /// Next write position in the history.
+    pos: usize,
+    /// Adaptation step size (0 = off).
+    mu: f32,
+    /// Level from 0 (off) to [`MAX_NR_LEVEL`].
+    level: u8,
+}
+
+impl NoiseReducer {
+    /// Create a noise reducer at a level from 0 (off) to [`MAX_NR_LEVEL`].
+    #[must_use]
+    pub fn new(level: u8) -> Self {
+        let mut nr = Self {
+            weights: [0.0; NR_TAPS],
+            history: [0.0; HISTORY],
+            pos: 0,
+            mu: 0.0,
+            level: 0,
+        };
+        nr.set_level(level);
+        nr
+    }
+
+    /// Set the level from 0 (off) to [`MAX_NR_LEVEL`]; higher adapts faster
+    /// and removes more noise at the cost of a more processed sound.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level.min(MAX_NR_LEVEL);
+        self.mu = f32::from(self.level) * 0.005;
+    }
+
+    /// Get the level.
+    #[must_use]
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Check if noise reduction is on (level above 0).
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.level > 0
+    }
+
+    /// Process one sample.
+    pub fn process(&mut self, input: f32) -> f32 {
+        if self.level == 0 {
+            return input;
+        }
+
+        let pos = self.pos;
+        let tap = |n: usize| (pos + HISTORY - NR_DELAY - n) & (HISTORY - 1);
+        let mut estimate = 0.0;
+        let mut power = 0.0;
+        for (n, &w) in self.weights.iter().enumerate() {
+            let x = self.history[tap(n)];
+            estimate += w * x;
+            power += x * x;
+        }
+
+        // Normalized LMS update
+        let step = self.mu * (input - estimate) / (power + 1e-6);
+        for (n, w) in self.weights.iter_mut().enumerate() {
+            *w = *w * LEAKAGE + step * self.history[tap(n)];
+        }
+
+        self.history[self.pos] = input;
+        self.pos = (self.pos + 1) & (HISTORY - 1);
+        estimate
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        if self.level == 0 {
+            return;
+        }
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Reset the filter, keeping the level.
+    pub fn reset(&mut self) {
+        self.weights = [0.0; NR_TAPS];
+        self.history = [0.0; HISTORY];
+        self.pos = 0;
+    }
+}
+
+impl Default for NoiseReducer {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// FFT frame of the spectral subtractor, in samples.
+pub const SPECTRAL_FRAME: usize = 256;
+
+/// Highest spectral subtraction strength.
+pub const MAX_SPECTRAL_STRENGTH: u8 = 10;
+
+/// New samples per frame (frames overlap by half).
+const SPECTRAL_HOP: usize = SPECTRAL_FRAME / 2;
+
+/// Bins from DC to Nyquist.
+const SPECTRAL_BINS: usize = SPECTRAL_FRAME / 2 + 1;
+
+/// How fast the noise floor estimate may rise, in dB per second.
+const NOISE_RISE_DB_PER_S: f32 = 3.0;
+
+/// Smoothing of bin power from frame to frame.
+const POWER_SMOOTHING: f32 = 0.5;
+
+/// Slower smoothing of bin power for the noise floor, so its minimum
+/// sits near the average noise rather than the deepest dips.
+const NOISE_SMOOTHING: f32 = 0.05;
+
+/// Smoothing of bin gains from frame to frame, against musical noise.
+const GAIN_SMOOTHING: f32 = 0.4;
+
+/// Spectral subtraction noise reducer.
+///
+/// Frames of [`SPECTRAL_FRAME`] samples overlapping by half go through a
+/// square-root Hann window and an FFT. Each bin's noise floor follows the
+/// smoothed bin power down at once and back up slowly, so speech and CW
+/// stay above it. The Wiener gain `1 - a * noise / power`, with the
+/// over-subtraction factor `a` and a floor set by the strength, is
+/// smoothed across frames and neighbouring bins so the residual noise
+/// does not warble, and the frames are windowed again and overlap-added.
+#[derive(Clone, Debug)]
+pub struct SpectralSubtractor {
+    /// Square-root Hann window, used for analysis and synthesis.
+    window: [f32; SPECTRAL_FRAME],
+    /// Twiddle factors, `exp(-2 pi i k / SPECTRAL_FRAME)`.
+    twiddle: [IqSample; SPECTRAL_FRAME / 2],
+    /// Previous hop followed by the hop being collected.
+    input: [f32; SPECTRAL_FRAME],
+    /// Overlap-add accumulator.
+    overlap: [f32; SPECTRAL_FRAME],
+    /// Output for the hop being collected.
+    output: [f32; SPECTRAL_HOP],
+    /// FFT work area.
+    work: [IqSample; SPECTRAL_FRAME],
+    /// Smoothed power per bin.
+    power: [f32; SPECTRAL_BINS],
+    /// Slowly averaged power per bin.
+    average: [f32; SPECTRAL_BINS],
+    /// Noise floor estimate per bin.
+    noise: [f32; SPECTRAL_BINS],
+    /// Smoothed gain per bin.
+    gain: [f32; SPECTRAL_BINS],
+    /// Samples collected in the current hop.
+    fill: usize,
+    /// Frames processed since the last reset.
+    frames: u32,
+    /// Noise floor rise per frame (linear power ratio).
+    noise_rise: f32,
+    /// Over-subtraction factor.
+    over_subtraction: f32,
+    /// Lowest gain.
+    floor: f32,
+    /// Strength from 0 (off) to [`MAX_SPECTRAL_STRENGTH`].
+    strength: u8,
+}
+
+impl SpectralSubtractor {
+    /// Create a reducer at a strength from 0 (off) to [`MAX_SPECTRAL_STRENGTH`].
+    #[must_use]
+    pub fn new(sample_rate: f32, strength: u8) -> Self {
+        let step = core::f32::consts::PI / SPECTRAL_FRAME as f32;
+        let hop_seconds = SPECTRAL_HOP as f32 / sample_rate;
+        let mut reducer = Self {
+            window: core::array::from_fn(|n| (step * n as f32).sin()),
+            twiddle: core::array::from_fn(|k| {
+                let angle = -2.0 * step * k as f32;
+                IqSample::new(angle.cos(), angle.sin())
+            }),
+            input: [0.0; SPECTRAL_FRAME],
+            overlap: [0.0; SPECTRAL_FRAME],
+            output: [0.0; SPECTRAL_HOP],
+            work: [IqSample::ZERO; SPECTRAL_FRAME],
+            power: [0.0; SPECTRAL_BINS],
+            average: [0.0; SPECTRAL_BINS],
+            noise: [f32::MAX; SPECTRAL_BINS],
+            gain: [1.0; SPECTRAL_BINS],
+            fill: 0,
+            frames: 0,
+            noise_rise: 10.0_f32.powf(NOISE_RISE_DB_PER_S * hop_seconds / 10.0),
+            over_subtraction: 1.0,
+            floor: 1.0,
+            strength: 0,
+        };
+        reducer.set_strength(strength);
+        reducer
+    }
+
+    /// Set the strength from 0 (off) to [`MAX_SPECTRAL_STRENGTH`].
+    ///
+    /// Higher strengths subtract more of the noise estimate and let the
+    /// gain fall further, from about 8 dB at 1 to 24 dB at 10.
+    pub fn set_strength(&mut self, strength: u8) {
+        self.strength = strength.min(MAX_SPECTRAL_STRENGTH);
+        let strength = f32::from(self.strength);
+        self.over_subtraction = 1.0 + 0.3 * strength;
+        self.floor = 10.0_f32.powf(-(6.0 + 1.8 * strength) / 20.0);
+    }
+
+    /// Get the strength.
+    #[must_use]
+    pub fn strength(&self) -> u8 {
+        self.strength
+    }
+
+    /// Check if the reducer is on (strength above 0).
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.strength > 0
+    }
+
+    /// Get the delay from input to output in samples.
+    #[must_use]
+    pub fn latency(&self) -> usize {
+        SPECTRAL_FRAME
+    }
+
+    /// Process one sample, returning the sample [`SPECTRAL_FRAME`] earlier
+    /// (or the input unchanged when off).
+    pub fn process(&mut self, input: f32) -> f32 {
+        if self.strength == 0 {
+            return input;
+        }
+        let output = self.output[self.fill];
+        self.input[SPECTRAL_HOP + self.fill] = input;
+        self.fill += 1;
+        if self.fill == SPECTRAL_HOP {
+            self.process_frame();
+            self.fill = 0;
+        }
+        output
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        if self.strength == 0 {
+            return;
+        }
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Clear the frames and the noise estimate, keeping the strength.
+    pub fn reset(&mut self) {
+        self.input = [0.0; SPECTRAL_FRAME];
+        self.overlap = [0.0; SPECTRAL_FRAME];
+        self.output = [0.0; SPECTRAL_HOP];
+        self.power = [0.0; SPECTRAL_BINS];
+        self.average = [0.0; SPECTRAL_BINS];
+        self.noise = [f32::MAX; SPECTRAL_BINS];
+        self.gain = [1.0; SPECTRAL_BINS];
+        self.fill = 0;
+        self.frames = 0;
+    }
+
+    /// Filter the collected frame and overlap-add it into the output.
+    fn process_frame(&mut self) {
+        for ((bin, &sample), &w) in self.work.iter_mut().zip(&self.input).zip(&self.window) {
+            *bin = IqSample::new(sample * w, 0.0);
+        }
+        fft_in_place(&mut self.work, &self.twiddle);
+
+        // Average over the first frames until the slow average takes over
+        self.frames = self.frames.saturating_add(1);
+        let averaging = NOISE_SMOOTHING.max(1.0 / self.frames as f32);
+        for (k, bin) in self.work[..SPECTRAL_BINS].iter().enumerate() {
+            let power = bin.magnitude_squared();
+            self.power[k] += POWER_SMOOTHING * (power - self.power[k]);
+            self.average[k] += averaging * (power - self.average[k]);
+            let noise = (self.noise[k] * self.noise_rise).min(self.average[k]);
+            self.noise[k] = noise;
+
+            let target = if self.power[k] > 0.0 {
+                (1.0 - self.over_subtraction * noise / self.power[k]).max(self.floor)
+            } else {
+                self.floor
+            };
+            self.gain[k] += GAIN_SMOOTHING * (target - self.gain[k]);
+        }
+
+        // Smooth across neighbouring bins, mirroring the gain onto the
+        // negative frequencies, and conjugate so a forward FFT inverts
+        for k in 0..SPECTRAL_BINS {
+            let below = self.gain[k.saturating_sub(1)];
+            let above = self.gain[(k + 1).min(SPECTRAL_BINS - 1)];
+            let gain = (below + 2.0 * self.gain[k] + above) / 4.0;
+            self.work[k] = self.work[k].scale(gain).conjugate();
+            if k > 0 && k < SPECTRAL_FRAME / 2 {
+                let mirror = SPECTRAL_FRAME - k;
+                self.work[mirror] = self.work[mirror].scale(gain).conjugate();
+            }
+        }
+        fft_in_place(&mut self.work, &self.twiddle);
+
+        let scale = 1.0 / SPECTRAL_FRAME as f32;
+        for ((acc, bin), &w) in self.overlap.iter_mut().zip(&self.work).zip(&self.window) {
+            *acc += bin.i * scale * w;
+        }
+        self.output.copy_from_slice(&self.overlap[..SPECTRAL_HOP]);
+        self.overlap.copy_within(SPECTRAL_HOP.., 0);
+        self.overlap[SPECTRAL_HOP..].fill(0.0);
+        self.input.copy_within(SPECTRAL_HOP.., 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+    #[allow(unused_imports)]
+    use micromath::F32Ext;
+
+    /// Deterministic white noise in -0.5..0.5.
+    fn noise(state: &mut u32) -> f32 {
+        *state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (*state >> 8) as f32 / (1 << 24) as f32 - 0.5
+    }
+
+    #[test]
+    fn test_nr_off_passes_through() {
+        let mut nr = NoiseReducer::default();
+        assert!(!nr.is_enabled());
+        let mut block = [0.1, -0.2, 0.3];
+        nr.process_block(&mut block);
+        assert_eq!(block, [0.1, -0.2, 0.3]);
+        nr.set_level(50);
+        assert_eq!(nr.level(), MAX_NR_LEVEL);
+    }
+
+    #[test]
+    fn test_nr_removes_noise_from_tone() {
+        let mut nr = NoiseReducer::new(5);
+        let mut state = 1;
+        let (mut noisy_err, mut clean_err) = (0.0, 0.0);
+        for n in 0..48000 {
+            let tone = 0.5 * (TAU * 700.0 * n as f32 / 48000.0).sin();
+            let input = tone + 0.5 * noise(&mut state);
+            let output = nr.process(input);
+            // Measure once converged
+            if n >= 24000 {
+                noisy_err += (input - tone) * (input - tone);
+                clean_err += (output - tone) * (output - tone);
+            }
+        }
+        assert!(clean_err < noisy_err / 2.0, "{clean_err} vs {noisy_err}");
+    }
+
+    #[test]
+    fn test_spectral_subtraction_removes_noise_from_keyed_tone() {
+        let mut nr = SpectralSubtractor::new(48000.0, 6);
+        let latency = nr.latency();
+        // Keyed 100 ms on, 100 ms off, starting with noise alone
+        let tone = |n: usize| {
+            let keyed = (n / 4800) % 2 == 1;
+            if keyed {
+                0.5 * (TAU * 700.0 * n as f32 / 48000.0).sin()
+            } else {
+                0.0
+            }
+        };
+        let mut state = 1;
+        let (mut noisy_err, mut clean_err) = (0.0, 0.0);
+        for n in 0..48000 {
+            let input = tone(n) + 0.2 * noise(&mut state);
+            let output = nr.process(input);
+            if n >= 24000 {
+                let expected = tone(n - latency);
+                noisy_err += (input - tone(n)) * (input - tone(n));
+                clean_err += (output - expected) * (output - expected);
+            }
+        }
+        assert!(clean_err < noisy_err / 4.0, "{clean_err} vs {noisy_err}");
+    }
+
+    #[test]
+    fn test_spectral_subtraction_passes_signal_above_noise() {
+        // A tone far above the noise floor keeps its gain, so the
+        // overlapping frames rebuild it
+        let mut nr = SpectralSubtractor::new(48000.0, 10);
+        let mut state = 1;
+        let input = |n: usize, state: &mut u32| {
+            let tone = if n >= 4800 {
+                0.5 * (TAU * 1000.0 * n as f32 / 48000.0).sin()
+            } else {
+                0.0
+            };
+            tone + 1e-4 * noise(state)
+        };
+        let mut inputs = [0.0; 9600];
+        for n in 0..inputs.len() {
+            inputs[n] = input(n, &mut state);
+            let output = nr.process(inputs[n]);
+            // Once the gains have opened
+            if n >= 4800 + 8 * SPECTRAL_FRAME {
+                let expected = inputs[n - SPECTRAL_FRAME];
+                assert!(
+                    (output - expected).abs() < 0.02,
+                    "{n}: {output} vs {expected}"
+                );
+            }
+        }
+
+        nr.set_strength(0);
+        assert!(!nr.is_enabled());
+        assert_eq!(nr.process(0.25), 0.25);
+        nr.set_strength(99);
+        assert_eq!(nr.strength(), MAX_SPECTRAL_STRENGTH);
+    }
+}
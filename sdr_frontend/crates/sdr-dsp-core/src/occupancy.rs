@@ -0,0 +1,329 @@
+//! Spectrum occupancy statistics for QRM reporting.
+//!
+//! Records, for each bin of a selected span, the percentage of spectrum
+//! frames in which the level was above a threshold, together with the
+//! mean and peak level. Run over minutes or hours, this shows whether
+//! interference is continuous, intermittent or tied to particular
+//! frequencies, which is the evidence needed to track down and report a
+//! local noise source.
+
+use core::fmt::{self, Write};
+
+use crate::spectrum::MAX_BINS;
+
+/// Occupancy above which a bin is counted as busy in the summary (percent).
+pub const BUSY_PERCENT: f32 = 10.0;
+
+/// Level assumed for bins that never reported a value (dB).
+const FLOOR_DB: f32 = -120.0;
+
+/// Span, threshold and duration of an occupancy survey.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OccupancyConfig {
+    /// First bin of the span
+    pub start_bin: usize,
+    /// Last bin of the span (inclusive)
+    pub end_bin: usize,
+    /// Level at or above which a bin counts as occupied (dB)
+    pub threshold_db: f32,
+    /// Number of spectrum frames to record (0 = until stopped)
+    pub frames: u32,
+}
+
+impl OccupancyConfig {
+    /// Number of bins in the span.
+    #[must_use]
+    pub fn num_bins(&self) -> usize {
+        self.end_bin.saturating_sub(self.start_bin) + 1
+    }
+}
+
+impl Default for OccupancyConfig {
+    fn default() -> Self {
+        Self {
+            start_bin: 0,
+            end_bin: 255,
+            threshold_db: -80.0,
+            frames: 0,
+        }
+    }
+}
+
+/// Summary of a finished or running survey.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OccupancySummary {
+    /// Frames recorded
+    pub frames: u32,
+    /// Occupancy averaged over the span (percent)
+    pub mean_percent: f32,
+    /// Bins at or above [`BUSY_PERCENT`] occupancy
+    pub busy_bins: usize,
+    /// Most occupied bin
+    pub busiest_bin: usize,
+    /// Occupancy of the most occupied bin (percent)
+    pub busiest_percent: f32,
+    /// Highest level seen anywhere in the span (dB)
+    pub peak_db: f32,
+}
+
+/// Per-bin occupancy recorder over a span of the spectrum.
+#[derive(Clone)]
+pub struct OccupancyRecorder {
+    /// Survey settings (span clamped to [`MAX_BINS`])
+    config: OccupancyConfig,
+    /// Frames with the bin at or above threshold
+    above: [u32; MAX_BINS],
+    /// Sum of levels for the mean
+    sum_db: [f32; MAX_BINS],
+    /// Highest level seen
+    peak_db: [f32; MAX_BINS],
+    /// Frames recorded
+    frames: u32,
+}
+
+impl OccupancyRecorder {
+    /// Create a recorder for the given survey.
+    #[must_use]
+    pub fn new(config: OccupancyConfig) -> Self {
+        let end_bin = config.end_bin.min(MAX_BINS - 1);
+        let config = OccupancyConfig {
+            start_bin: config.start_bin.min(end_bin),
+            end_bin,
+            ..config
+        };
+        Self {
+            config,
+            above: [0; MAX_BINS],
+            sum_db: [0.0; MAX_BINS],
+            peak_db: [FLOOR_DB; MAX_BINS],
+            frames: 0,
+        }
+    }
+
+    /// Get the survey settings.
+    #[must_use]
+    pub fn config(&self) -> &OccupancyConfig {
+        &self.config
+    }
+
+    /// Add one spectrum frame (levels in dB, indexed by bin).
+    ///
+    /// Bins of the span missing from a short frame are taken as the
+    /// floor. Frames pushed after the survey is complete are ignored.
+    /// Returns `true` while the survey is still running.
+    pub fn push(&mut self, spectrum_db: &[f32]) -> bool {
+        if self.is_complete() {
+            return false;
+        }
+        for bin in self.config.start_bin..=self.config.end_bin {
+            let level = spectrum_db.get(bin).copied().unwrap_or(FLOOR_DB);
+            if level >= self.config.threshold_db {
+                self.above[bin] += 1;
+            }
+            self.sum_db[bin] += level;
+            self.peak_db[bin] = self.peak_db[bin].max(level);
+        }
+        self.frames += 1;
+        !self.is_complete()
+    }
+
+    /// Check if the configured number of frames has been recorded.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.config.frames > 0 && self.frames >= self.config.frames
+    }
+
+    /// Number of frames recorded.
+    #[must_use]
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Survey progress (0.0 to 1.0, 0.0 for open-ended surveys).
+    #[must_use]
+    pub fn progress(&self) -> f32 {
+        if self.config.frames == 0 {
+            0.0
+        } else {
+            (self.frames as f32 / self.config.frames as f32).min(1.0)
+        }
+    }
+
+    /// Percentage of frames in which a bin was occupied.
+    #[must_use]
+    pub fn occupancy_percent(&self, bin: usize) -> f32 {
+        if self.frames == 0 || !self.in_span(bin) {
+            return 0.0;
+        }
+        100.0 * self.above[bin] as f32 / self.frames as f32
+    }
+
+    /// Mean level of a bin (dB).
+    #[must_use]
+    pub fn mean_db(&self, bin: usize) -> f32 {
+        if self.frames == 0 || !self.in_span(bin) {
+            return FLOOR_DB;
+        }
+        self.sum_db[bin] / self.frames as f32
+    }
+
+    /// Peak level of a bin (dB).
+    #[must_use]
+    pub fn peak_db(&self, bin: usize) -> f32 {
+        if self.in_span(bin) {
+            self.peak_db[bin]
+        } else {
+            FLOOR_DB
+        }
+    }
+
+    /// Summarize the survey so far.
+    #[must_use]
+    pub fn summary(&self) -> OccupancySummary {
+        let mut summary = OccupancySummary {
+            frames: self.frames,
+            busiest_bin: self.config.start_bin,
+            peak_db: FLOOR_DB,
+            ..OccupancySummary::default()
+        };
+        let mut total = 0.0;
+        for bin in self.config.start_bin..=self.config.end_bin {
+            let percent = self.occupancy_percent(bin);
+            total += percent;
+            if percent >= BUSY_PERCENT {
+                summary.busy_bins += 1;
+            }
+            if percent > summary.busiest_percent {
+                summary.busiest_percent = percent;
+                summary.busiest_bin = bin;
+            }
+            summary.peak_db = summary.peak_db.max(self.peak_db[bin]);
+        }
+        summary.mean_percent = total / self.config.num_bins() as f32;
+        summary
+    }
+
+    /// Write the survey as CSV, one row per bin.
+    ///
+    /// `bin_hz` maps a bin index to the frequency shown in the report,
+    /// so callers can label bins with absolute RF frequencies.
+    ///
+    /// # Errors
+    /// Returns an error if the writer fails.
+    pub fn write_csv<W: Write>(&self, out: &mut W, bin_hz: impl Fn(usize) -> f64) -> fmt::Result {
+        let summary = self.summary();
+        writeln!(
+            out,
+            "# frames={} threshold_db={:.1} mean_occupancy={:.1}% busy_bins={}",
+            summary.frames, self.config.threshold_db, summary.mean_percent, summary.busy_bins
+        )?;
+        writeln!(out, "frequency_hz,occupancy_percent,mean_db,peak_db")?;
+        for bin in self.config.start_bin..=self.config.end_bin {
+            writeln!(
+                out,
+                "{:.0},{:.1},{:.1},{:.1}",
+                bin_hz(bin),
+                self.occupancy_percent(bin),
+                self.mean_db(bin),
+                self.peak_db(bin)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Clear all statistics and start again with the same settings.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    fn in_span(&self, bin: usize) -> bool {
+        (self.config.start_bin..=self.config.end_bin).contains(&bin)
+    }
+}
+
+impl Default for OccupancyRecorder {
+    fn default() -> Self {
+        Self::new(OccupancyConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn survey(frames: u32) -> OccupancyRecorder {
+        OccupancyRecorder::new(OccupancyConfig {
+            start_bin: 10,
+            end_bin: 19,
+            threshold_db: -60.0,
+            frames,
+        })
+    }
+
+    #[test]
+    fn test_occupancy_percent() {
+        let mut rec = survey(4);
+        let mut frame = [-100.0; 32];
+        // Bin 12 always occupied, bin 15 half the time
+        for n in 0..4 {
+            frame[12] = -40.0;
+            frame[15] = if n % 2 == 0 { -50.0 } else { -90.0 };
+            let running = rec.push(&frame);
+            assert_eq!(running, n < 3);
+        }
+        assert!(rec.is_complete());
+        assert!(!rec.push(&frame));
+        assert_eq!(rec.frames(), 4);
+
+        assert!((rec.occupancy_percent(12) - 100.0).abs() < 1e-3);
+        assert!((rec.occupancy_percent(15) - 50.0).abs() < 1e-3);
+        assert_eq!(rec.occupancy_percent(11), 0.0);
+        // Outside the span
+        assert_eq!(rec.occupancy_percent(25), 0.0);
+        assert!((rec.mean_db(15) + 70.0).abs() < 1e-3);
+        assert!((rec.peak_db(15) + 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut rec = survey(0);
+        let mut frame = [-100.0; 32];
+        frame[17] = -30.0;
+        for _ in 0..10 {
+            rec.push(&frame);
+        }
+        assert!(!rec.is_complete());
+
+        let summary = rec.summary();
+        assert_eq!(summary.frames, 10);
+        assert_eq!(summary.busy_bins, 1);
+        assert_eq!(summary.busiest_bin, 17);
+        assert!((summary.mean_percent - 10.0).abs() < 1e-3);
+        assert!((summary.peak_db + 30.0).abs() < 1e-3);
+
+        rec.reset();
+        assert_eq!(rec.frames(), 0);
+        assert_eq!(rec.summary().busy_bins, 0);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut rec = OccupancyRecorder::new(OccupancyConfig {
+            start_bin: 1,
+            end_bin: 2,
+            threshold_db: -60.0,
+            frames: 1,
+        });
+        rec.push(&[-100.0, -50.0, -70.0]);
+
+        let mut csv: heapless::String<256> = heapless::String::new();
+        rec.write_csv(&mut csv, |bin| 14_070_000.0 + bin as f64 * 100.0)
+            .unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("# frames=1"));
+        assert_eq!(lines.next(), Some("frequency_hz,occupancy_percent,mean_db,peak_db"));
+        assert_eq!(lines.next(), Some("14070100,100.0,-50.0,-50.0"));
+        assert_eq!(lines.next(), Some("14070200,0.0,-70.0,-70.0"));
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides digital oscillators for frequency mixing, carrier generation,
 //! and quadrature signal generation.
 
+use crate::kernels;
 use crate::types::IqSample;
 #[allow(unused_imports)]
 use micromath::F32Ext;
@@ -115,8 +116,32 @@ impl Nco {
         let lo = self.next_iq();
         input.multiply(lo.conjugate())
     }
+
+    /// Mix a block of IQ samples with the NCO output in place.
+    ///
+    /// Same result as [`mix`](Self::mix) per sample. The phase ramp is
+    /// built first, then the oscillator, then the samples are multiplied
+    /// by [`kernels::complex_multiply`], so each pass is a simple loop.
+    pub fn mix_block(&mut self, samples: &mut [IqSample]) {
+        let mut phases = [0.0; MIX_CHUNK];
+        let mut lo = [IqSample::ZERO; MIX_CHUNK];
+        for chunk in samples.chunks_mut(MIX_CHUNK) {
+            let phases = &mut phases[..chunk.len()];
+            for phase in phases.iter_mut() {
+                *phase = self.phase;
+                self.advance();
+            }
+            for (l, phase) in lo.iter_mut().zip(phases.iter()) {
+                *l = IqSample::new(phase.cos(), -phase.sin());
+            }
+            kernels::complex_multiply(chunk, &lo[..chunk.len()]);
+        }
+    }
 }
 
+/// Samples of local oscillator generated at a time by [`Nco::mix_block`].
+const MIX_CHUNK: usize = 64;
+
 /// Wrap phase to range [-π, π].
 #[inline]
 fn wrap_phase(phase: f32) -> f32 {
@@ -193,70 +218,148 @@ impl QuadratureOscillator {
     }
 }
 
+/// Lock detector level above which a [`CostasLoop`] reports lock.
+pub const COSTAS_LOCK_THRESHOLD: f32 = 0.7;
+
+/// Lock detector level below which a locked [`CostasLoop`] reports loss of lock.
+pub const COSTAS_UNLOCK_THRESHOLD: f32 = 0.5;
+
+/// Smoothing of the lock detector (about 1000 samples).
+const LOCK_DETECT_ALPHA: f32 = 0.001;
+
 /// Costas loop for carrier tracking.
 ///
-/// Used for BPSK/QPSK demodulation to track carrier phase and frequency.
+/// Used for BPSK demodulation to track carrier phase and frequency. The
+/// tracked symbols lie on the real axis.
+///
+/// The loop bandwidth sets how fast the loop follows the carrier; the
+/// pull-in range limits how far the frequency correction (AFC) may move
+/// from the center frequency. A range of zero tracks phase only.
 #[derive(Clone, Debug)]
 pub struct CostasLoop {
     /// NCO for carrier generation
     nco: Nco,
-    /// Loop filter integrator
+    /// Loop filter integrator (frequency correction in rad/sample)
     integrator: f32,
     /// Proportional gain (phase tracking)
     kp: f32,
     /// Integral gain (frequency tracking)
     ki: f32,
-    /// Maximum frequency offset in Hz
+    /// Loop bandwidth in Hz
+    loop_bandwidth: f32,
+    /// Maximum frequency correction in rad/sample
     max_freq_offset: f32,
+    /// Average magnitude used to normalize the phase detector
+    amplitude: f32,
+    /// Averaged I² - Q² (lock detector numerator)
+    lock_in_phase: f32,
+    /// Averaged I² + Q² (lock detector denominator)
+    lock_power: f32,
+    /// Lock state with hysteresis
+    locked: bool,
+    /// Sample rate in Hz
+    sample_rate: f32,
 }
 
 impl CostasLoop {
     /// Create a new Costas loop.
     ///
+    /// The pull-in range defaults to twice the loop bandwidth.
+    ///
     /// # Arguments
     /// * `sample_rate` - Sample rate in Hz
     /// * `center_freq` - Expected carrier frequency in Hz
     /// * `loop_bandwidth` - Loop bandwidth in Hz (affects tracking speed)
     #[must_use]
     pub fn new(sample_rate: f32, center_freq: f32, loop_bandwidth: f32) -> Self {
+        let mut costas = Self {
+            nco: Nco::new(sample_rate, center_freq),
+            integrator: 0.0,
+            kp: 0.0,
+            ki: 0.0,
+            loop_bandwidth: 0.0,
+            max_freq_offset: 0.0,
+            amplitude: 0.0,
+            lock_in_phase: 0.0,
+            lock_power: 0.0,
+            locked: false,
+            sample_rate,
+        };
+        costas.set_loop_bandwidth(loop_bandwidth);
+        costas.set_pull_in_range(loop_bandwidth * 2.0);
+        costas
+    }
+
+    /// Set the loop bandwidth in Hz.
+    pub fn set_loop_bandwidth(&mut self, loop_bandwidth: f32) {
         // Calculate loop gains from bandwidth using standard formulas
         let damping = 0.707; // Butterworth response
-        let bn_ts = loop_bandwidth / sample_rate;
+        let loop_bandwidth = loop_bandwidth.max(0.0);
+        let bn_ts = loop_bandwidth / self.sample_rate;
         let denom = 1.0 + 2.0 * damping * bn_ts + bn_ts * bn_ts;
 
-        let kp = 4.0 * damping * bn_ts / denom;
-        let ki = 4.0 * bn_ts * bn_ts / denom;
+        self.kp = 4.0 * damping * bn_ts / denom;
+        self.ki = 4.0 * bn_ts * bn_ts / denom;
+        self.loop_bandwidth = loop_bandwidth;
+    }
 
-        Self {
-            nco: Nco::new(sample_rate, center_freq),
-            integrator: 0.0,
-            kp,
-            ki,
-            max_freq_offset: loop_bandwidth * 2.0,
-        }
+    /// Get the loop bandwidth in Hz.
+    #[must_use]
+    pub fn loop_bandwidth(&self) -> f32 {
+        self.loop_bandwidth
+    }
+
+    /// Limit the frequency correction to ±`range_hz` (0 disables AFC).
+    pub fn set_pull_in_range(&mut self, range_hz: f32) {
+        self.max_freq_offset = 2.0 * core::f32::consts::PI * range_hz.max(0.0) / self.sample_rate;
+        self.integrator = self
+            .integrator
+            .clamp(-self.max_freq_offset, self.max_freq_offset);
+    }
+
+    /// Get the pull-in range in Hz.
+    #[must_use]
+    pub fn pull_in_range(&self) -> f32 {
+        self.max_freq_offset * self.sample_rate / (2.0 * core::f32::consts::PI)
     }
 
     /// Process an IQ sample and return tracked output with phase error.
     ///
-    /// Returns (tracked_sample, phase_error).
+    /// Returns (tracked_sample, phase_error). The phase error is normalized
+    /// by the average signal magnitude, so it approximates the angle in
+    /// radians between the sample and the real axis.
     pub fn process(&mut self, input: IqSample) -> (IqSample, f32) {
         // Mix down with NCO
         let mixed = self.nco.mix(input);
 
-        // Calculate phase error (for BPSK: I * sign(Q))
-        let phase_error = mixed.i * mixed.q.signum();
-
-        // Update loop filter
-        self.integrator += self.ki * phase_error;
-
-        // Clamp frequency offset
-        self.integrator = self
-            .integrator
+        // Calculate phase error (for BPSK: Q * sign(I))
+        let magnitude = mixed.magnitude();
+        self.amplitude += 0.001 * (magnitude - self.amplitude);
+        let phase_error = if self.amplitude > 1e-9 {
+            (mixed.q * mixed.i.signum() / self.amplitude).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        // Update loop filter and clamp frequency offset
+        self.integrator = (self.integrator + self.ki * phase_error)
             .clamp(-self.max_freq_offset, self.max_freq_offset);
 
-        // Update NCO frequency
-        let freq_correction = self.kp * phase_error + self.integrator;
-        self.nco.adjust_frequency(freq_correction);
+        // Steer the NCO phase toward the carrier
+        self.nco.adjust_phase(self.kp * phase_error + self.integrator);
+
+        // Lock detector: cos(2θ) weighted by power, near 1 when symbols
+        // sit on the real axis and near 0 for a free-running carrier
+        let i2 = mixed.i * mixed.i;
+        let q2 = mixed.q * mixed.q;
+        self.lock_in_phase += LOCK_DETECT_ALPHA * (i2 - q2 - self.lock_in_phase);
+        self.lock_power += LOCK_DETECT_ALPHA * (i2 + q2 - self.lock_power);
+        let level = self.lock_level();
+        if level > COSTAS_LOCK_THRESHOLD {
+            self.locked = true;
+        } else if level < COSTAS_UNLOCK_THRESHOLD {
+            self.locked = false;
+        }
 
         (mixed, phase_error)
     }
@@ -264,13 +367,32 @@ impl CostasLoop {
     /// Get current frequency offset in Hz.
     #[must_use]
     pub fn frequency_offset(&self) -> f32 {
-        self.integrator
+        self.integrator * self.sample_rate / (2.0 * core::f32::consts::PI)
+    }
+
+    /// Get the lock detector level (-1 to 1, 1 when fully locked).
+    #[must_use]
+    pub fn lock_level(&self) -> f32 {
+        if self.lock_power <= 1e-12 {
+            return 0.0;
+        }
+        self.lock_in_phase / self.lock_power
+    }
+
+    /// Check whether the loop is locked to a carrier.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.locked
     }
 
     /// Reset loop state.
     pub fn reset(&mut self) {
         self.nco.reset();
         self.integrator = 0.0;
+        self.amplitude = 0.0;
+        self.lock_in_phase = 0.0;
+        self.lock_power = 0.0;
+        self.locked = false;
     }
 }
 
@@ -303,7 +425,31 @@ mod tests {
         }
 
         // 1000 Hz should have ~2 complete cycles per 2ms
-        assert!(zero_crossings >= 1 && zero_crossings <= 3, "Expected 1-3 zero crossings, got {}", zero_crossings);
+        assert!(
+            (1..=3).contains(&zero_crossings),
+            "Expected 1-3 zero crossings, got {}",
+            zero_crossings
+        );
+    }
+
+    #[test]
+    fn test_nco_mix_block_matches_mix() {
+        let input: [IqSample; 150] = core::array::from_fn(|n| {
+            let t = n as f32;
+            IqSample::new((t * 0.3).sin(), (t * 0.11).cos())
+        });
+        let mut nco = Nco::new(48000.0, 1700.0);
+        let expected = input.map(|s| nco.mix(s));
+
+        let mut block_nco = Nco::new(48000.0, 1700.0);
+        let mut samples = input;
+        let (first, second) = samples.split_at_mut(70);
+        block_nco.mix_block(first);
+        block_nco.mix_block(second);
+        for (out, want) in samples.iter().zip(&expected) {
+            assert!((out.i - want.i).abs() < 1e-5 && (out.q - want.q).abs() < 1e-5);
+        }
+        assert_eq!(block_nco.phase(), nco.phase());
     }
 
     #[test]
@@ -340,4 +486,53 @@ mod tests {
         let wrapped = wrap_phase(3.0 * core::f32::consts::PI);
         assert!(wrapped.abs() - core::f32::consts::PI < 1e-5);
     }
+
+    #[test]
+    fn test_costas_tracks_offset_carrier() {
+        let rate = 8000.0;
+        let mut costas = CostasLoop::new(rate, 0.0, 50.0);
+        let mut carrier = Nco::new(rate, 5.0);
+        carrier.set_phase(1.0);
+
+        let mut error = 1.0;
+        for n in 0..16000 {
+            // BPSK symbols flipping every 256 samples
+            let sign = if (n / 256) % 2 == 0 { 1.0 } else { -1.0 };
+            let (_, phase_error) = costas.process(carrier.next_iq().scale(sign));
+            error = phase_error;
+        }
+
+        assert!(error.abs() < 0.05, "Phase error: {}", error);
+        assert!((costas.frequency_offset() - 5.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_costas_pull_in_range_and_lock() {
+        let rate = 8000.0;
+        let mut costas = CostasLoop::new(rate, 0.0, 50.0);
+        assert!((costas.pull_in_range() - 100.0).abs() < 1e-3);
+        costas.set_pull_in_range(3.0);
+        costas.set_loop_bandwidth(20.0);
+        assert!((costas.loop_bandwidth() - 20.0).abs() < 1e-6);
+
+        // A carrier outside the pull-in range is not acquired
+        let mut carrier = Nco::new(rate, 10.0);
+        for _ in 0..16000 {
+            costas.process(carrier.next_iq());
+        }
+        assert!((costas.frequency_offset() - 3.0).abs() < 1e-3);
+        assert!(!costas.is_locked());
+
+        // Widening the range lets the loop pull in and lock
+        costas.set_pull_in_range(20.0);
+        for _ in 0..16000 {
+            costas.process(carrier.next_iq());
+        }
+        assert!((costas.frequency_offset() - 10.0).abs() < 0.5);
+        assert!(costas.is_locked());
+        assert!(costas.lock_level() > COSTAS_LOCK_THRESHOLD);
+
+        costas.reset();
+        assert!(!costas.is_locked());
+    }
 }
@@ -0,0 +1,272 @@
+//! CW pitch estimation.
+//!
+//! [`PitchMeter`] measures the pitch of the strongest tone near a
+//! reference pitch, normally the CW sidetone, and reports how far the
+//! received signal is from it. Tuned so that the two match, the station
+//! is zero-beat: the transmitter lands on the other station's frequency.
+//! The offset drives a tuning indicator and an automatic zero-beat.
+//!
+//! A [`GoertzelBank`] of [`PITCH_TONES`] detectors spans the search
+//! range around the reference. Each block, the strongest detector is
+//! taken as the tone if it stands [`MIN_PEAK_DB`] over the median of the
+//! bank, and the pitch is refined between detectors from the balance of
+//! the peak and its stronger neighbour. Readings are smoothed
+//! and held through the gaps between CW elements for [`PITCH_HOLD_MS`].
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::goertzel::GoertzelBank;
+
+/// Detectors across the search range.
+pub const PITCH_TONES: usize = 16;
+
+/// Default search range either side of the reference pitch in Hz.
+pub const DEFAULT_PITCH_RANGE_HZ: f32 = 300.0;
+
+/// Narrowest search range either side of the reference in Hz.
+pub const MIN_PITCH_RANGE_HZ: f32 = 50.0;
+
+/// Margin of the peak over the median detector for a valid tone, in dB.
+pub const MIN_PEAK_DB: f32 = 10.0;
+
+/// How long a reading is held after the tone goes away, in milliseconds.
+pub const PITCH_HOLD_MS: f32 = 500.0;
+
+/// Weight of each new block in the smoothed pitch.
+const PITCH_SMOOTHING: f32 = 0.3;
+
+/// Pitch of the strongest tone near a reference pitch.
+#[derive(Clone, Debug)]
+pub struct PitchMeter {
+    bank: GoertzelBank<PITCH_TONES>,
+    /// Reference pitch in Hz.
+    center_hz: f32,
+    /// Search range either side of the reference in Hz.
+    range_hz: f32,
+    /// Minimum peak power (linear, full-scale sine = 0.5).
+    min_power: f32,
+    /// Smoothed pitch, while valid.
+    pitch_hz: Option<f32>,
+    /// Blocks left before a missing tone clears the reading.
+    hold_blocks: u32,
+    /// Blocks to hold a reading.
+    hold_len: u32,
+}
+
+impl PitchMeter {
+    /// Create a meter for audio at `sample_rate` Hz around `center_hz`.
+    #[must_use]
+    pub fn new(sample_rate: f32, center_hz: f32) -> Self {
+        let mut meter = Self {
+            bank: GoertzelBank::new(sample_rate, 1),
+            center_hz,
+            range_hz: DEFAULT_PITCH_RANGE_HZ,
+            min_power: 0.5e-6,
+            pitch_hz: None,
+            hold_blocks: 0,
+            hold_len: 0,
+        };
+        meter.retune();
+        meter
+    }
+
+    /// Set the reference pitch in Hz, clearing the reading.
+    pub fn set_center_hz(&mut self, center_hz: f32) {
+        self.center_hz = center_hz;
+        self.retune();
+    }
+
+    /// Get the reference pitch in Hz.
+    #[must_use]
+    pub fn center_hz(&self) -> f32 {
+        self.center_hz
+    }
+
+    /// Set the search range either side of the reference in Hz (at
+    /// least [`MIN_PITCH_RANGE_HZ`]), clearing the reading.
+    ///
+    /// A wider range catches signals further off but measures in longer
+    /// blocks, so it answers more slowly.
+    pub fn set_range_hz(&mut self, range_hz: f32) {
+        self.range_hz = range_hz.max(MIN_PITCH_RANGE_HZ);
+        self.retune();
+    }
+
+    /// Get the search range either side of the reference in Hz.
+    #[must_use]
+    pub fn range_hz(&self) -> f32 {
+        self.range_hz
+    }
+
+    /// Set the weakest tone measured, in dBFS.
+    pub fn set_min_level_dbfs(&mut self, dbfs: f32) {
+        self.min_power = 0.5 * 10.0_f32.powf(dbfs / 10.0);
+    }
+
+    /// Measure one sample.
+    pub fn process(&mut self, sample: f32) {
+        if self.bank.push(sample) {
+            self.measure_block();
+        }
+    }
+
+    /// Measure a block of samples.
+    pub fn process_block(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.process(sample);
+        }
+    }
+
+    /// Get the smoothed pitch of the tone in Hz (`None` without a tone).
+    #[must_use]
+    pub fn pitch_hz(&self) -> Option<f32> {
+        self.pitch_hz
+    }
+
+    /// Get the tone's pitch less the reference in Hz (`None` without a
+    /// tone); positive when the tone is higher.
+    #[must_use]
+    pub fn offset_hz(&self) -> Option<f32> {
+        self.pitch_hz.map(|pitch| pitch - self.center_hz)
+    }
+
+    /// Clear the block and the reading.
+    pub fn reset(&mut self) {
+        self.bank.reset();
+        self.pitch_hz = None;
+        self.hold_blocks = 0;
+    }
+
+    /// Lay the detectors out across the range and size the block to match.
+    fn retune(&mut self) {
+        let sample_rate = self.bank.sample_rate();
+        let low = (self.center_hz - self.range_hz).max(0.0);
+        let high = (self.center_hz + self.range_hz).min(sample_rate / 2.0);
+        // Detectors one block resolution apart, for the interpolation
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let block_len = (sample_rate * (PITCH_TONES - 1) as f32 / (high - low).max(1.0)) as usize;
+        self.bank.clear();
+        self.bank.set_block_len(block_len);
+        let spacing = sample_rate / self.bank.block_len() as f32;
+        for i in 0..PITCH_TONES {
+            self.bank.add_tone(low + spacing * i as f32);
+        }
+
+        let block_ms = self.bank.block_len() as f32 * 1000.0 / sample_rate;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let hold_len = (PITCH_HOLD_MS / block_ms).ceil().max(1.0) as u32;
+        self.hold_len = hold_len;
+        self.reset();
+    }
+
+    /// Find the tone in a completed block and fold it into the reading.
+    fn measure_block(&mut self) {
+        match self.block_pitch() {
+            Some(pitch) => {
+                self.pitch_hz = Some(match self.pitch_hz {
+                    Some(old) => old + PITCH_SMOOTHING * (pitch - old),
+                    None => pitch,
+                });
+                self.hold_blocks = self.hold_len;
+            }
+            None if self.hold_blocks > 0 => self.hold_blocks -= 1,
+            None => self.pitch_hz = None,
+        }
+    }
+
+    /// Pitch of the block's peak, if it stands clear of the rest.
+    fn block_pitch(&self) -> Option<f32> {
+        let mut powers = [0.0; PITCH_TONES];
+        for (slot, power) in powers.iter_mut().zip(self.bank.powers()) {
+            *slot = power;
+        }
+        let (peak, peak_power) = self.bank.strongest()?;
+        if peak_power < self.min_power {
+            return None;
+        }
+        let mut sorted = powers;
+        sorted.sort_unstable_by(f32::total_cmp);
+        let median = sorted[PITCH_TONES / 2];
+        if peak_power < median * 10.0_f32.powf(MIN_PEAK_DB / 10.0) {
+            return None;
+        }
+
+        // Spaced one block resolution apart, the detectors either side of
+        // a tone read |sinc| of its distance from each, so the share of
+        // the larger neighbour in the pair's magnitude is exactly how far
+        // the tone sits towards it
+        let frequency = self.bank.frequency(peak)?;
+        let spacing = self.bank.frequency(1)? - self.bank.frequency(0)?;
+        let magnitude = |i: usize| powers.get(i).map_or(0.0, |power| power.sqrt());
+        let (below, above) = (
+            peak.checked_sub(1).map_or(0.0, magnitude),
+            magnitude(peak + 1),
+        );
+        let centre = peak_power.sqrt();
+        let shift = if above >= below {
+            above / (centre + above)
+        } else {
+            -below / (centre + below)
+        };
+        Some(frequency + shift * spacing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    fn tone(meter: &mut PitchMeter, frequency: f32, amplitude: f32, samples: usize) {
+        for n in 0..samples {
+            meter.process(amplitude * (TAU * frequency * n as f32 / 48000.0).sin());
+        }
+    }
+
+    #[test]
+    fn test_pitch_offset() {
+        let mut meter = PitchMeter::new(48000.0, 700.0);
+        assert_eq!(meter.offset_hz(), None);
+        for frequency in [700.0, 612.0, 755.0, 903.0, 480.0] {
+            meter.reset();
+            tone(&mut meter, frequency, 0.2, 24000);
+            let offset = meter.offset_hz().unwrap();
+            assert!(
+                (offset - (frequency - 700.0)).abs() < 1.0,
+                "{frequency} Hz read as {offset:+}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_holds_through_gaps() {
+        let mut meter = PitchMeter::new(48000.0, 600.0);
+        tone(&mut meter, 650.0, 0.2, 9600);
+        assert!(meter.pitch_hz().is_some());
+
+        // A key-up shorter than the hold keeps the reading, a long one clears it
+        tone(&mut meter, 650.0, 0.0, 4800);
+        assert!((meter.offset_hz().unwrap() - 50.0).abs() < 1.0);
+        tone(&mut meter, 650.0, 0.0, 48000);
+        assert_eq!(meter.pitch_hz(), None);
+
+        // Too weak, or outside the range, is no tone
+        meter.set_min_level_dbfs(-40.0);
+        tone(&mut meter, 650.0, 0.001, 9600);
+        assert_eq!(meter.pitch_hz(), None);
+        tone(&mut meter, 1500.0, 0.2, 9600);
+        assert_eq!(meter.pitch_hz(), None);
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut meter = PitchMeter::new(48000.0, 700.0);
+        meter.set_range_hz(10.0);
+        assert_eq!(meter.range_hz(), MIN_PITCH_RANGE_HZ);
+        meter.set_center_hz(500.0);
+        assert_eq!(meter.center_hz(), 500.0);
+        tone(&mut meter, 520.0, 0.2, 48000);
+        assert!((meter.offset_hz().unwrap() - 20.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,265 @@
+//! Signal Quality Measurement.
+//!
+//! Shared quality figures for digital mode decoders:
+//!
+//! - SNR from the carrier tracking (Costas loop) phase error
+//! - Error vector magnitude of the symbol decisions
+//! - IMD of the two-tone idle signal of phase-reversal modes (PSK31),
+//!   measured only while the transmitter idles
+//!
+//! Decoders feed a [`QualityMeter`] once per symbol and an [`ImdMeter`]
+//! with baseband samples during idle, then report [`SignalQuality`].
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::oscillator::Nco;
+use crate::types::IqSample;
+
+/// Quality figures reported by a decoder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignalQuality {
+    /// SNR estimated from the carrier phase error in dB
+    pub snr_db: f32,
+    /// Third-order IMD relative to the idle tones in dB (None until measured)
+    pub imd_db: Option<f32>,
+    /// RMS error vector magnitude in percent of the symbol amplitude
+    pub evm_percent: f32,
+}
+
+impl SignalQuality {
+    /// Quality of a signal that has not been measured.
+    pub const UNKNOWN: Self = Self {
+        snr_db: 0.0,
+        imd_db: None,
+        evm_percent: 100.0,
+    };
+
+    /// EVM in dB (20·log10 of the RMS error ratio).
+    #[must_use]
+    pub fn evm_db(&self) -> f32 {
+        20.0 * (self.evm_percent / 100.0).max(1e-5).log10()
+    }
+}
+
+impl Default for SignalQuality {
+    fn default() -> Self {
+        Self::UNKNOWN
+    }
+}
+
+/// Default symbol-rate smoothing for [`QualityMeter`].
+pub const DEFAULT_QUALITY_SMOOTHING: f32 = 0.05;
+
+/// Running SNR and EVM estimate from symbol decisions.
+///
+/// BPSK symbols are expected on the real axis after carrier tracking.
+#[derive(Clone, Debug)]
+pub struct QualityMeter {
+    alpha: f32,
+    /// Mean square of the carrier phase error
+    phase_error_power: f32,
+    /// Mean square error vector
+    error_power: f32,
+    /// Mean symbol amplitude
+    amplitude: f32,
+    imd_db: Option<f32>,
+    symbols: u32,
+}
+
+impl QualityMeter {
+    /// Create a meter with the given per-symbol smoothing factor (0 to 1).
+    #[must_use]
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.001, 1.0),
+            phase_error_power: 0.0,
+            error_power: 0.0,
+            amplitude: 0.0,
+            imd_db: None,
+            symbols: 0,
+        }
+    }
+
+    /// Add a symbol decision sample and the loop phase error at that symbol.
+    ///
+    /// `phase_error` is the carrier loop error in radians, as returned by
+    /// [`CostasLoop::process`](crate::oscillator::CostasLoop::process).
+    pub fn push_symbol(&mut self, symbol: IqSample, phase_error: f32) {
+        let power = symbol.magnitude_squared();
+        if power <= 1e-12 {
+            return;
+        }
+        let magnitude = power.sqrt();
+        let alpha = if self.symbols == 0 { 1.0 } else { self.alpha };
+        self.symbols = self.symbols.saturating_add(1);
+
+        self.amplitude += alpha * (magnitude - self.amplitude);
+        let ideal = IqSample::new(self.amplitude.copysign(symbol.i), 0.0);
+        let error = symbol - ideal;
+        self.error_power += alpha * (error.magnitude_squared() - self.error_power);
+
+        self.phase_error_power += alpha * (phase_error * phase_error - self.phase_error_power);
+    }
+
+    /// Record an IMD measurement.
+    pub fn set_imd(&mut self, imd_db: f32) {
+        self.imd_db = Some(imd_db);
+    }
+
+    /// Number of symbols measured.
+    #[must_use]
+    pub fn symbols(&self) -> u32 {
+        self.symbols
+    }
+
+    /// Current quality estimate.
+    #[must_use]
+    pub fn quality(&self) -> SignalQuality {
+        if self.symbols == 0 {
+            return SignalQuality {
+                imd_db: self.imd_db,
+                ..SignalQuality::UNKNOWN
+            };
+        }
+        // Phase jitter variance of a BPSK carrier is 1/(2·SNR)
+        let snr = 1.0 / (2.0 * self.phase_error_power.max(1e-6));
+        let evm = self.error_power.sqrt() / self.amplitude.max(1e-9);
+        SignalQuality {
+            snr_db: 10.0 * snr.log10(),
+            imd_db: self.imd_db,
+            evm_percent: evm * 100.0,
+        }
+    }
+
+    /// Forget all measurements.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.alpha);
+    }
+}
+
+impl Default for QualityMeter {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUALITY_SMOOTHING)
+    }
+}
+
+/// Symbols per IMD measurement window.
+pub const IMD_WINDOW_SYMBOLS: usize = 16;
+
+/// Two-tone IMD meter for phase-reversal idle signals.
+///
+/// An idle PSK31 carrier reversing phase every symbol is a pair of tones
+/// at ±baud/2 from the carrier; transmitter nonlinearity adds third-order
+/// products at ±3·baud/2. The meter correlates baseband samples against
+/// all four over a whole number of symbols.
+#[derive(Clone, Debug)]
+pub struct ImdMeter {
+    /// References at -f3, -f1, +f1, +f3
+    tones: [Nco; 4],
+    sums: [IqSample; 4],
+    window: usize,
+    count: usize,
+}
+
+impl ImdMeter {
+    /// Create a meter for `baud` symbols per second at `sample_rate`.
+    #[must_use]
+    pub fn new(sample_rate: f32, baud: f32) -> Self {
+        let f1 = baud / 2.0;
+        let f3 = 3.0 * baud / 2.0;
+        let symbol_len = sample_rate / baud;
+        Self {
+            tones: [
+                Nco::new(sample_rate, -f3),
+                Nco::new(sample_rate, -f1),
+                Nco::new(sample_rate, f1),
+                Nco::new(sample_rate, f3),
+            ],
+            sums: [IqSample::ZERO; 4],
+            window: ((symbol_len * IMD_WINDOW_SYMBOLS as f32) as usize).max(1),
+            count: 0,
+        }
+    }
+
+    /// Add a baseband sample taken while the signal idles.
+    ///
+    /// Returns the IMD in dB each time a window completes.
+    pub fn push(&mut self, sample: IqSample) -> Option<f32> {
+        for (tone, sum) in self.tones.iter_mut().zip(self.sums.iter_mut()) {
+            *sum = *sum + tone.mix(sample);
+        }
+        self.count += 1;
+        if self.count < self.window {
+            return None;
+        }
+
+        let power = |s: &IqSample| s.magnitude_squared();
+        let fundamental = power(&self.sums[1]) + power(&self.sums[2]);
+        let products = power(&self.sums[0]) + power(&self.sums[3]);
+        self.restart();
+        if fundamental <= 1e-12 {
+            return None;
+        }
+        Some(10.0 * (products / fundamental).max(1e-10).log10())
+    }
+
+    /// Discard a partial window (e.g. when data interrupts the idle).
+    pub fn restart(&mut self) {
+        self.sums = [IqSample::ZERO; 4];
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn test_imd_two_tone() {
+        let rate = 8000.0;
+        let baud = 31.25;
+        let mut meter = ImdMeter::new(rate, baud);
+        let tone = |f: f32, n: usize, amp: f32| {
+            let phase = 2.0 * PI * f * n as f32 / rate;
+            IqSample::new(amp * phase.cos(), amp * phase.sin())
+        };
+
+        let mut result = None;
+        for n in 0..5000 {
+            let s = tone(-15.625, n, 1.0)
+                + tone(15.625, n, 1.0)
+                + tone(-46.875, n, 0.1)
+                + tone(46.875, n, 0.1);
+            if let Some(imd) = meter.push(s) {
+                result = Some(imd);
+                break;
+            }
+        }
+        let imd = result.unwrap();
+        assert!((imd + 20.0).abs() < 1.0, "{imd}");
+    }
+
+    #[test]
+    fn test_quality_meter_evm_and_snr() {
+        let mut meter = QualityMeter::new(0.1);
+        assert_eq!(meter.quality(), SignalQuality::UNKNOWN);
+
+        // Clean symbols with a small fixed error
+        for k in 0..200 {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            meter.push_symbol(IqSample::new(sign, 0.1), 0.01);
+        }
+        meter.set_imd(-25.0);
+        let q = meter.quality();
+        assert!((q.evm_percent - 10.0).abs() < 1.0, "{}", q.evm_percent);
+        assert!((q.evm_db() + 20.0).abs() < 1.0);
+        // Phase jitter of 0.01 rad RMS is about 37 dB SNR
+        assert!((q.snr_db - 37.0).abs() < 1.0, "{}", q.snr_db);
+        assert_eq!(q.imd_db, Some(-25.0));
+
+        meter.reset();
+        assert_eq!(meter.symbols(), 0);
+    }
+}
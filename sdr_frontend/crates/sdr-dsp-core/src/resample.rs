@@ -0,0 +1,149 @@
+//! Sample Rate Conversion.
+//!
+//! Streaming linear-interpolation resampler for IQ, used when a capture
+//! or device runs at a different rate from the DSP chain. Linear
+//! interpolation is adequate for replaying captures into a demodulator
+//! whose filters are much narrower than the sample rate; it is not a
+//! replacement for a polyphase filter ahead of decimation.
+
+use crate::types::IqSample;
+
+/// Streaming linear-interpolation IQ resampler.
+#[derive(Clone, Debug)]
+pub struct IqResampler {
+    /// Input samples consumed per output sample
+    step: f32,
+    /// Position of the next output between `prev` and the next input
+    phase: f32,
+    /// Last input sample of the previous block
+    prev: IqSample,
+}
+
+impl IqResampler {
+    /// Create a resampler from `input_rate` to `output_rate` (Hz).
+    #[must_use]
+    pub fn new(input_rate: f32, output_rate: f32) -> Self {
+        let mut resampler = Self {
+            step: 1.0,
+            phase: 0.0,
+            prev: IqSample::default(),
+        };
+        resampler.set_rates(input_rate, output_rate);
+        resampler
+    }
+
+    /// Change the conversion ratio, keeping the stream position.
+    pub fn set_rates(&mut self, input_rate: f32, output_rate: f32) {
+        self.step = if input_rate > 0.0 && output_rate > 0.0 {
+            input_rate / output_rate
+        } else {
+            1.0
+        };
+    }
+
+    /// Input samples consumed per output sample.
+    #[must_use]
+    pub fn ratio(&self) -> f32 {
+        self.step
+    }
+
+    /// Check if the rates match (samples pass through unchanged).
+    #[must_use]
+    pub fn is_passthrough(&self) -> bool {
+        (self.step - 1.0).abs() < f32::EPSILON
+    }
+
+    /// Largest output count a block of `input_len` samples can produce.
+    #[must_use]
+    pub fn max_output(&self, input_len: usize) -> usize {
+        (input_len as f32 / self.step) as usize + 1
+    }
+
+    /// Resample a block, returning the number of outputs written.
+    ///
+    /// Blocks may be any length; interpolation continues across block
+    /// boundaries. Output stops early if `output` is too short, dropping
+    /// the rest of the block.
+    pub fn process(&mut self, input: &[IqSample], output: &mut [IqSample]) -> usize {
+        let mut written = 0;
+        // `phase` is measured from `prev` (index -1 of this block), so
+        // the output lies between inputs `next - 1` and `next`
+        while written < output.len() {
+            let next = self.phase as usize;
+            if next >= input.len() {
+                break;
+            }
+            let a = if next == 0 { self.prev } else { input[next - 1] };
+            let b = input[next];
+            let frac = self.phase - next as f32;
+            output[written] = a + (b - a) * frac;
+            written += 1;
+            self.phase += self.step;
+        }
+
+        if let Some(&last) = input.last() {
+            self.prev = last;
+            self.phase = (self.phase - input.len() as f32).max(0.0);
+        }
+        written
+    }
+
+    /// Reset the stream position.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.prev = IqSample::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(start: usize) -> [IqSample; 8] {
+        core::array::from_fn(|i| {
+            let v = (start + i) as f32;
+            IqSample::new(v, -v)
+        })
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mut resampler = IqResampler::new(48000.0, 48000.0);
+        assert!(resampler.is_passthrough());
+        let input = ramp(1);
+        let mut output = [IqSample::default(); 16];
+        let n = resampler.process(&input, &mut output);
+        // First output interpolates from the (zero) previous sample
+        assert_eq!(n, 8);
+        assert!((output[0].i - 0.0).abs() < 1e-6);
+        assert!((output[7].i - 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_upsample_continues_across_blocks() {
+        let mut resampler = IqResampler::new(24000.0, 48000.0);
+        let mut output = [IqSample::default(); 32];
+        let first = resampler.process(&ramp(1)[..4], &mut output);
+        let second = resampler.process(&ramp(5)[..4], &mut output[first..]);
+        let total = first + second;
+        assert_eq!(total, 16);
+        // A ramp stays a ramp with half the step
+        for pair in output[..total].windows(2) {
+            assert!((pair[1].i - pair[0].i - 0.5).abs() < 1e-5);
+            assert!((pair[1].q - pair[0].q + 0.5).abs() < 1e-5);
+        }
+        assert!(resampler.max_output(4) >= 8);
+    }
+
+    #[test]
+    fn test_downsample_ratio() {
+        let mut resampler = IqResampler::new(48000.0, 16000.0);
+        assert!((resampler.ratio() - 3.0).abs() < 1e-6);
+        let mut output = [IqSample::default(); 8];
+        let mut total = 0;
+        for block in 0..3 {
+            total += resampler.process(&ramp(1 + block * 8), &mut output);
+        }
+        assert_eq!(total, 8);
+    }
+}
@@ -0,0 +1,268 @@
+//! Audio Passband SNR Measurement.
+//!
+//! [`SnrMeter`] measures the demodulated audio inside the receive
+//! passband: the signal power, the noise floor under it and their ratio,
+//! averaged over a rolling window. It needs no carrier lock or decoder,
+//! so it reads the same for any mode, and is meant for lining up a
+//! digital mode signal in the passband while watching its SNR.
+//!
+//! Each [`SNR_FRAME`]-sample frame is windowed and transformed. The
+//! noise floor per bin is the median bin of the passband (scaled for the
+//! exponential spread of noise power), which holds as long as the
+//! signals fill less than half the passband; whatever power is left
+//! above it is signal. Decoder figures such as the Costas loop SNR are
+//! in [`quality`](crate::quality) and [`SignalMetrics`](crate::SignalMetrics).
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::spectrum::{FftSpectrum, MAX_BINS};
+
+/// Samples per measurement frame.
+pub const SNR_FRAME: usize = MAX_BINS;
+
+/// Default averaging window in milliseconds.
+pub const DEFAULT_SNR_WINDOW_MS: f32 = 1000.0;
+
+/// Default passband lower edge in Hz.
+pub const DEFAULT_SNR_LOW_HZ: f32 = 300.0;
+
+/// Default passband upper edge in Hz.
+pub const DEFAULT_SNR_HIGH_HZ: f32 = 2700.0;
+
+/// Lowest SNR reported, in dB.
+pub const MIN_SNR_DB: f32 = -30.0;
+
+/// Reference bandwidth of WSJT-X style SNR reports, in Hz.
+pub const WSJT_BANDWIDTH_HZ: f32 = 2500.0;
+
+/// Bins in a frame's one-sided spectrum.
+const SNR_BINS: usize = SNR_FRAME / 2;
+
+/// Fewest passband bins worth measuring.
+const MIN_PASSBAND_BINS: usize = 3;
+
+/// Mean over median of exponentially distributed noise power (1 / ln 2).
+const NOISE_MEDIAN_SCALE: f32 = core::f32::consts::LOG2_E;
+
+/// Bin power sum of a full-scale sine through the Hann window (3N²/32).
+const FULL_SCALE_POWER: f32 = 3.0 * (SNR_FRAME * SNR_FRAME) as f32 / 32.0;
+
+/// Averaged passband measurement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnrReading {
+    /// Signal power in the passband, noise excluded, in dBFS.
+    pub signal_dbfs: f32,
+    /// Noise power in the passband in dBFS.
+    pub noise_dbfs: f32,
+    /// Signal to noise ratio in the passband in dB.
+    pub snr_db: f32,
+    /// Width of the measured passband in Hz.
+    pub bandwidth_hz: f32,
+}
+
+impl SnrReading {
+    /// SNR with the noise referred to another bandwidth, in dB.
+    ///
+    /// With [`WSJT_BANDWIDTH_HZ`] this matches the reports of WSJT-X.
+    #[must_use]
+    pub fn snr_in_bandwidth_db(&self, bandwidth_hz: f32) -> f32 {
+        self.snr_db + 10.0 * (self.bandwidth_hz / bandwidth_hz.max(1.0)).log10()
+    }
+}
+
+/// Rolling signal, noise floor and SNR measurement of passband audio.
+#[derive(Clone)]
+pub struct SnrMeter {
+    sample_rate: f32,
+    spectrum: FftSpectrum,
+    /// Scratch for a frame's power spectrum.
+    bins: [f32; SNR_BINS],
+    /// Passband bins, first and one past the last.
+    first_bin: usize,
+    end_bin: usize,
+    window_ms: f32,
+    /// Averaging coefficient per frame.
+    coeff: f32,
+    /// Averaged signal and noise power, normalized to full scale.
+    signal: f32,
+    noise: f32,
+    /// Frames averaged so far.
+    frames: u32,
+}
+
+impl SnrMeter {
+    /// Create a meter for the default passband and window.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        let mut meter = Self {
+            sample_rate,
+            spectrum: FftSpectrum::new(SNR_FRAME),
+            bins: [0.0; SNR_BINS],
+            first_bin: 0,
+            end_bin: 0,
+            window_ms: 0.0,
+            coeff: 1.0,
+            signal: 0.0,
+            noise: 0.0,
+            frames: 0,
+        };
+        meter.set_passband(DEFAULT_SNR_LOW_HZ, DEFAULT_SNR_HIGH_HZ);
+        meter.set_window_ms(DEFAULT_SNR_WINDOW_MS);
+        meter
+    }
+
+    /// Set the passband edges in Hz; bins centered inside it are measured.
+    pub fn set_passband(&mut self, low_hz: f32, high_hz: f32) {
+        let bin_hz = self.sample_rate / SNR_FRAME as f32;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (first, last) = (
+            (low_hz / bin_hz).ceil().max(1.0) as usize,
+            (high_hz / bin_hz).floor().max(0.0) as usize,
+        );
+        self.first_bin = first.min(SNR_BINS - 1);
+        self.end_bin = (last + 1).clamp(self.first_bin, SNR_BINS);
+    }
+
+    /// Get the measured passband width in Hz.
+    #[must_use]
+    pub fn bandwidth_hz(&self) -> f32 {
+        (self.end_bin - self.first_bin) as f32 * self.sample_rate / SNR_FRAME as f32
+    }
+
+    /// Set the averaging window in milliseconds (at least one frame).
+    pub fn set_window_ms(&mut self, window_ms: f32) {
+        let frame_ms = SNR_FRAME as f32 * 1000.0 / self.sample_rate;
+        self.window_ms = window_ms.max(frame_ms);
+        self.coeff = frame_ms / self.window_ms;
+    }
+
+    /// Get the averaging window in milliseconds.
+    #[must_use]
+    pub fn window_ms(&self) -> f32 {
+        self.window_ms
+    }
+
+    /// Measure one sample.
+    pub fn process(&mut self, sample: f32) {
+        self.spectrum.push(sample);
+        if self.spectrum.is_ready() {
+            self.measure_frame();
+        }
+    }
+
+    /// Measure a block of samples.
+    pub fn process_block(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.process(sample);
+        }
+    }
+
+    /// Get the averaged reading (`None` until a frame has been measured).
+    #[must_use]
+    pub fn reading(&self) -> Option<SnrReading> {
+        if self.frames == 0 {
+            return None;
+        }
+        let floor = 10.0_f32.powf(MIN_SNR_DB / 10.0);
+        let noise = self.noise.max(1e-12);
+        let signal = self.signal.max(noise * floor);
+        Some(SnrReading {
+            signal_dbfs: 10.0 * signal.log10(),
+            noise_dbfs: 10.0 * noise.log10(),
+            snr_db: 10.0 * (signal / noise).log10(),
+            bandwidth_hz: self.bandwidth_hz(),
+        })
+    }
+
+    /// Clear the frame and the averages.
+    pub fn reset(&mut self) {
+        self.spectrum.reset();
+        self.signal = 0.0;
+        self.noise = 0.0;
+        self.frames = 0;
+    }
+
+    /// Split a full frame into signal and noise and fold it into the averages.
+    fn measure_frame(&mut self) {
+        self.spectrum.compute_power(&mut self.bins);
+        let passband = &mut self.bins[self.first_bin..self.end_bin];
+        if passband.len() < MIN_PASSBAND_BINS {
+            return;
+        }
+        let total: f32 = passband.iter().sum();
+        let count = passband.len();
+        let (_, median, _) = passband.select_nth_unstable_by(count / 2, f32::total_cmp);
+        let noise = (*median * NOISE_MEDIAN_SCALE * count as f32).min(total);
+        let signal = total - noise;
+
+        // The first frame seeds the average so the reading starts close
+        let coeff = if self.frames == 0 { 1.0 } else { self.coeff };
+        self.signal += coeff * (signal / FULL_SCALE_POWER - self.signal);
+        self.noise += coeff * (noise / FULL_SCALE_POWER - self.noise);
+        self.frames = self.frames.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::TAU;
+
+    /// Uniform noise in -amplitude..amplitude from a fixed seed.
+    fn noise(seed: &mut u32, amplitude: f32) -> f32 {
+        *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (*seed >> 8) as f32 / (1 << 24) as f32 * 2.0 * amplitude - amplitude
+    }
+
+    #[test]
+    fn test_tone_in_noise() {
+        let sample_rate = 48000.0;
+        let mut meter = SnrMeter::new(sample_rate);
+        assert!(meter.reading().is_none());
+
+        // 0.05 amplitude tone over white noise of variance 0.1^2 / 3
+        let mut seed = 1;
+        for n in 0..96000 {
+            let tone = 0.05 * (TAU * 1000.0 * n as f32 / sample_rate).sin();
+            meter.process(tone + noise(&mut seed, 0.1));
+        }
+        let reading = meter.reading().unwrap();
+        let noise_power = 0.1 * 0.1 / 3.0 * meter.bandwidth_hz() / 24000.0;
+        let expected_snr = 10.0 * (0.05 * 0.05 / 2.0 / noise_power).log10();
+        assert!((reading.snr_db - expected_snr).abs() < 1.5, "{reading:?}");
+        assert!((reading.signal_dbfs - -26.0).abs() < 1.0, "{reading:?}");
+        let expected_noise = 10.0 * (noise_power / 0.5).log10();
+        assert!(
+            (reading.noise_dbfs - expected_noise).abs() < 1.5,
+            "{reading:?}"
+        );
+
+        // Referred to 2500 Hz the noise is a little wider
+        let wsjt = reading.snr_in_bandwidth_db(WSJT_BANDWIDTH_HZ);
+        assert!(wsjt < reading.snr_db && wsjt > reading.snr_db - 1.0);
+    }
+
+    #[test]
+    fn test_noise_only_and_passband() {
+        let mut meter = SnrMeter::new(48000.0);
+        let mut seed = 7;
+        for _ in 0..48000 {
+            meter.process(noise(&mut seed, 0.1));
+        }
+        assert!(meter.reading().unwrap().snr_db < -5.0);
+
+        // A tone outside the passband is not signal
+        meter.set_passband(300.0, 1000.0);
+        meter.reset();
+        for n in 0..48000 {
+            let tone = 0.5 * (TAU * 2000.0 * n as f32 / 48000.0).sin();
+            meter.process(tone + noise(&mut seed, 0.1));
+        }
+        assert!(meter.reading().unwrap().snr_db < 0.0);
+        assert!((meter.bandwidth_hz() - 656.25).abs() < 1.0);
+
+        meter.set_window_ms(0.0);
+        assert!((meter.window_ms() - SNR_FRAME as f32 / 48.0).abs() < 1e-3);
+    }
+}
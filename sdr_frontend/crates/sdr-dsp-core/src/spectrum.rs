@@ -2,9 +2,16 @@
 //!
 //! Provides sliding DFT for efficient spectrum computation
 //! and data structures for waterfall display.
+//!
+//! FFT frames can be scaled to dBFS, averaged and calibrated to dBm
+//! with [`SpectrumAverager`] using a per-band gain from
+//! [`SpectrumCalibration`]. The noise floor estimate drives
+//! [`DisplayRange`] so the waterfall color map follows the band.
 
 use micromath::F32Ext;
 
+use crate::units::S9_DBM;
+
 /// Maximum number of FFT bins supported.
 pub const MAX_BINS: usize = 512;
 
@@ -247,6 +254,19 @@ impl FftSpectrum {
 
     /// Compute FFT and return power spectrum in dB.
     pub fn compute(&mut self, output: &mut [f32]) {
+        self.compute_power(output);
+        let len = output.len().min(self.size / 2);
+        for value in &mut output[..len] {
+            *value = if *value > 1e-20 {
+                10.0 * value.log10()
+            } else {
+                -120.0
+            };
+        }
+    }
+
+    /// Compute FFT and return the linear power spectrum.
+    pub fn compute_power(&mut self, output: &mut [f32]) {
         // Apply window and copy to real buffer
         for i in 0..self.size {
             let idx = (self.write_pos + i) % self.size;
@@ -260,12 +280,7 @@ impl FftSpectrum {
         // Compute power spectrum
         let len = output.len().min(self.size / 2);
         for i in 0..len {
-            let power = self.real[i] * self.real[i] + self.imag[i] * self.imag[i];
-            output[i] = if power > 1e-20 {
-                10.0 * power.log10()
-            } else {
-                -120.0
-            };
+            output[i] = self.real[i] * self.real[i] + self.imag[i] * self.imag[i];
         }
     }
 
@@ -330,6 +345,618 @@ impl Default for FftSpectrum {
     }
 }
 
+impl FftSpectrum {
+    /// Level of a full-scale sine in [`compute`](Self::compute) output, in dB.
+    #[must_use]
+    pub fn full_scale_db(&self) -> f32 {
+        // Peak bin of a unit sine is half the window sum
+        let gain = self.window[..self.size].iter().sum::<f32>() / 2.0;
+        20.0 * gain.log10()
+    }
+
+    /// Compute FFT and return the power spectrum in dBFS.
+    pub fn compute_dbfs(&mut self, output: &mut [f32]) {
+        self.compute(output);
+        let full_scale = self.full_scale_db();
+        let len = output.len().min(self.size / 2);
+        for value in &mut output[..len] {
+            *value -= full_scale;
+        }
+    }
+}
+
+/// Fraction of bins below the noise floor estimate.
+const NOISE_FLOOR_QUANTILE: usize = 4;
+
+/// Margin kept below the noise floor by [`DisplayRange::auto`], in dB.
+pub const NOISE_MARGIN_DB: f32 = 6.0;
+
+/// Smallest span used by [`DisplayRange::auto`], in dB.
+pub const MIN_DISPLAY_RANGE_DB: f32 = 30.0;
+
+/// Default dBFS to dBm gain: full scale reads S9, as on the S-meter.
+pub const DEFAULT_SPECTRUM_GAIN_DB: f32 = S9_DBM;
+
+/// Estimate the noise floor of a spectrum frame (lower quartile, dB).
+#[must_use]
+pub fn noise_floor_db(bins: &[f32]) -> Option<f32> {
+    if bins.is_empty() {
+        return None;
+    }
+    let len = bins.len().min(MAX_BINS);
+    let mut sorted = [0.0_f32; MAX_BINS];
+    sorted[..len].copy_from_slice(&bins[..len]);
+    sorted[..len].sort_unstable_by(f32::total_cmp);
+    Some(sorted[len / NOISE_FLOOR_QUANTILE])
+}
+
+/// Most peaks [`find_peaks`] is expected to report per frame.
+pub const MAX_PEAKS: usize = 16;
+
+/// Default peak threshold above the noise floor, in dB.
+pub const DEFAULT_PEAK_THRESHOLD_DB: f32 = 10.0;
+
+/// Level below a peak at which its width is measured, in dB.
+pub const PEAK_WIDTH_DB: f32 = 6.0;
+
+/// Signal found in a spectrum frame by [`find_peaks`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpectrumPeak {
+    /// Frequency from the first bin in Hz, interpolated between bins
+    pub frequency_hz: f32,
+    /// Peak level in dB
+    pub level_db: f32,
+    /// Width [`PEAK_WIDTH_DB`] down in Hz
+    pub width_hz: f32,
+}
+
+/// Find the strongest peaks in a spectrum frame in dB.
+///
+/// A peak is a local maximum at least `threshold_db` above the frame's
+/// noise floor. Fills `peaks` strongest first, up to its length, and
+/// returns the number found. Frequencies are offsets from the first bin,
+/// as for [`SpectrumConfig::bin_frequency`].
+pub fn find_peaks(
+    bins: &[f32],
+    bin_width_hz: f32,
+    threshold_db: f32,
+    peaks: &mut [SpectrumPeak],
+) -> usize {
+    let Some(floor) = noise_floor_db(bins) else {
+        return 0;
+    };
+    let threshold = floor + threshold_db;
+    let mut count = 0;
+    for (bin, &level) in bins.iter().enumerate() {
+        let left = bin.checked_sub(1).map(|k| bins[k]);
+        let right = bins.get(bin + 1).copied();
+        // Strict on the left so a flat top counts once
+        if level < threshold
+            || left.is_some_and(|left| left >= level)
+            || right.is_some_and(|right| right > level)
+        {
+            continue;
+        }
+        // Strongest first: skip if weaker than a full list, else insert
+        let at = peaks[..count]
+            .iter()
+            .position(|peak| level > peak.level_db)
+            .unwrap_or(count);
+        if at == peaks.len() {
+            continue;
+        }
+        count = (count + 1).min(peaks.len());
+        peaks.copy_within(at..count - 1, at + 1);
+        peaks[at] = measure_peak(bins, bin, bin_width_hz);
+    }
+    count
+}
+
+/// Interpolate a peak's frequency and measure its width
+fn measure_peak(bins: &[f32], bin: usize, bin_width_hz: f32) -> SpectrumPeak {
+    let level = bins[bin];
+    // Parabola through the peak and its neighbours
+    let offset = match (bin.checked_sub(1).map(|k| bins[k]), bins.get(bin + 1)) {
+        (Some(left), Some(&right)) => {
+            let curve = left - 2.0 * level + right;
+            if curve < 0.0 {
+                (0.5 * (left - right) / curve).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            }
+        }
+        _ => 0.0,
+    };
+
+    // Bin positions where the level falls PEAK_WIDTH_DB below the peak
+    let edge = level - PEAK_WIDTH_DB;
+    let crossing = |inside: usize, outside: usize| {
+        let (a, b) = (bins[inside], bins[outside]);
+        let fraction = if a > b { (a - edge) / (a - b) } else { 1.0 };
+        inside as f32 + (outside as f32 - inside as f32) * fraction
+    };
+    let mut low = bin;
+    while low > 0 && bins[low - 1] > edge {
+        low -= 1;
+    }
+    let mut high = bin;
+    while high + 1 < bins.len() && bins[high + 1] > edge {
+        high += 1;
+    }
+    let low_edge = if low > 0 { crossing(low, low - 1) } else { 0.0 };
+    let high_edge = if high + 1 < bins.len() {
+        crossing(high, high + 1)
+    } else {
+        high as f32
+    };
+
+    SpectrumPeak {
+        frequency_hz: (bin as f32 + offset) * bin_width_hz,
+        level_db: level,
+        width_hz: (high_edge - low_edge) * bin_width_hz,
+    }
+}
+
+/// Suppression of the DC spike at the centre frequency.
+///
+/// A QSD or soundcard leaves a DC offset that shows as a spike in the
+/// centre bin, often the strongest thing on the display. The notch
+/// replaces the bins within `width` of DC by a straight line between the
+/// levels either side, and [`SpectrumAverager`] leaves those bins out of
+/// its noise floor and peak so they never drive autoscaling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DcNotch {
+    /// Bin holding DC
+    pub dc_bin: usize,
+    /// Bins either side of DC replaced (0 = the DC bin alone)
+    pub width: usize,
+}
+
+impl DcNotch {
+    /// Notch `width` bins either side of `dc_bin`.
+    #[must_use]
+    pub fn new(dc_bin: usize, width: usize) -> Self {
+        Self { dc_bin, width }
+    }
+
+    /// Notch at least `width_hz` either side of `dc_bin`, for bins
+    /// `bin_width_hz` wide.
+    #[must_use]
+    pub fn from_hz(dc_bin: usize, width_hz: f32, bin_width_hz: f32) -> Self {
+        let width = if bin_width_hz > 0.0 {
+            (width_hz.max(0.0) / bin_width_hz).ceil() as usize
+        } else {
+            0
+        };
+        Self::new(dc_bin, width)
+    }
+
+    /// Check if a bin is inside the notch.
+    #[must_use]
+    pub fn contains(&self, bin: usize) -> bool {
+        bin.abs_diff(self.dc_bin) <= self.width
+    }
+
+    /// Interpolate over the notched bins of a frame in dB.
+    ///
+    /// At the edge of the frame (DC in the first bin of a real spectrum)
+    /// the notch takes the level of the nearest bin outside it.
+    pub fn apply(&self, bins: &mut [f32]) {
+        let first = self.dc_bin.saturating_sub(self.width);
+        if first >= bins.len() {
+            return;
+        }
+        let last = (self.dc_bin + self.width).min(bins.len() - 1);
+        let left = first.checked_sub(1).map(|bin| bins[bin]);
+        let right = bins.get(last + 1).copied();
+        let (from, to) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            (Some(level), None) | (None, Some(level)) => (level, level),
+            (None, None) => return,
+        };
+        let steps = (last - first + 2) as f32;
+        for (k, value) in bins[first..=last].iter_mut().enumerate() {
+            *value = from + (to - from) * (k + 1) as f32 / steps;
+        }
+    }
+}
+
+/// Exponential averaging and dBm calibration of spectrum frames.
+///
+/// Frames are averaged in linear power so the noise floor is not
+/// biased low, then offset by the calibration gain. An optional
+/// [`DcNotch`] hides the DC spike.
+#[derive(Clone)]
+pub struct SpectrumAverager {
+    /// Averaged power (linear)
+    power: [f32; MAX_BINS],
+    /// Calibrated output in dB
+    output: [f32; MAX_BINS],
+    /// Number of valid bins
+    num_bins: usize,
+    /// Frames averaged (1 = none)
+    averaging: u8,
+    /// dBFS to dBm gain
+    gain_db: f32,
+    /// First frame seen since reset
+    primed: bool,
+    /// DC spike suppression
+    dc_notch: Option<DcNotch>,
+}
+
+impl SpectrumAverager {
+    /// Create an averager over `averaging` frames (1 = no averaging).
+    #[must_use]
+    pub fn new(averaging: u8) -> Self {
+        Self {
+            power: [0.0; MAX_BINS],
+            output: [0.0; MAX_BINS],
+            num_bins: 0,
+            averaging: averaging.max(1),
+            gain_db: DEFAULT_SPECTRUM_GAIN_DB,
+            primed: false,
+            dc_notch: None,
+        }
+    }
+
+    /// Set the number of frames averaged (1 = no averaging).
+    pub fn set_averaging(&mut self, averaging: u8) {
+        self.averaging = averaging.max(1);
+    }
+
+    /// Get the number of frames averaged.
+    #[must_use]
+    pub fn averaging(&self) -> u8 {
+        self.averaging
+    }
+
+    /// Set the dBFS to dBm gain (e.g. from [`SpectrumCalibration`]).
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        self.gain_db = gain_db;
+    }
+
+    /// Get the dBFS to dBm gain.
+    #[must_use]
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// Average a dBFS frame and return the calibrated spectrum in dBm.
+    pub fn process(&mut self, dbfs: &[f32]) -> &[f32] {
+        let len = dbfs.len().min(MAX_BINS);
+        if len != self.num_bins {
+            self.num_bins = len;
+            self.primed = false;
+        }
+        let alpha = 1.0 / f32::from(self.averaging);
+        for ((avg, out), &db) in self.power[..len]
+            .iter_mut()
+            .zip(&mut self.output[..len])
+            .zip(&dbfs[..len])
+        {
+            let power = 10.0_f32.powf(db / 10.0);
+            *avg = if self.primed { *avg + alpha * (power - *avg) } else { power };
+            *out = 10.0 * avg.max(1e-20).log10() + self.gain_db;
+        }
+        self.primed = true;
+        if let Some(notch) = self.dc_notch {
+            notch.apply(&mut self.output[..len]);
+        }
+        self.output()
+    }
+
+    /// Set or clear the DC spike notch.
+    pub fn set_dc_notch(&mut self, dc_notch: Option<DcNotch>) {
+        self.dc_notch = dc_notch;
+    }
+
+    /// Get the DC spike notch.
+    #[must_use]
+    pub fn dc_notch(&self) -> Option<DcNotch> {
+        self.dc_notch
+    }
+
+    /// Get the last calibrated spectrum in dBm.
+    #[must_use]
+    pub fn output(&self) -> &[f32] {
+        &self.output[..self.num_bins]
+    }
+
+    /// Estimate the noise floor of the averaged spectrum in dBm, outside
+    /// the DC notch.
+    #[must_use]
+    pub fn noise_floor_db(&self) -> Option<f32> {
+        let mut bins = [0.0_f32; MAX_BINS];
+        let mut len = 0;
+        for level in self.measured() {
+            bins[len] = level;
+            len += 1;
+        }
+        noise_floor_db(&bins[..len])
+    }
+
+    /// Get the strongest bin of the averaged spectrum in dBm, outside the
+    /// DC notch.
+    #[must_use]
+    pub fn peak_db(&self) -> Option<f32> {
+        self.measured().reduce(f32::max)
+    }
+
+    /// Levels of the bins outside the DC notch
+    fn measured(&self) -> impl Iterator<Item = f32> + '_ {
+        self.output()
+            .iter()
+            .enumerate()
+            .filter(|&(bin, _)| !self.dc_notch.is_some_and(|notch| notch.contains(bin)))
+            .map(|(_, &level)| level)
+    }
+
+    /// Clear the average.
+    pub fn reset(&mut self) {
+        self.primed = false;
+    }
+}
+
+impl Default for SpectrumAverager {
+    fn default() -> Self {
+        Self::new(SpectrumConfig::default().averaging)
+    }
+}
+
+/// Per-band dBFS to dBm gain table for `N` bands.
+///
+/// Bands are identified by index, as in
+/// [`BandConditions`](crate::conditions::BandConditions).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrumCalibration<const N: usize> {
+    gain_db: [f32; N],
+}
+
+impl<const N: usize> SpectrumCalibration<N> {
+    /// Create a table with the default gain on every band.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            gain_db: [DEFAULT_SPECTRUM_GAIN_DB; N],
+        }
+    }
+
+    /// Set the gain of a band.
+    pub fn set_gain_db(&mut self, band: usize, gain_db: f32) {
+        if let Some(gain) = self.gain_db.get_mut(band) {
+            *gain = gain_db;
+        }
+    }
+
+    /// Get the gain of a band (default gain outside the table).
+    #[must_use]
+    pub fn gain_db(&self, band: usize) -> f32 {
+        self.gain_db.get(band).copied().unwrap_or(DEFAULT_SPECTRUM_GAIN_DB)
+    }
+
+    /// Derive a band's gain from a known signal.
+    ///
+    /// `measured_db` is what the calibrated spectrum showed for a
+    /// generator delivering `actual_dbm`.
+    pub fn calibrate(&mut self, band: usize, measured_db: f32, actual_dbm: f32) {
+        let gain = self.gain_db(band) + actual_dbm - measured_db;
+        self.set_gain_db(band, gain);
+    }
+}
+
+impl<const N: usize> Default for SpectrumCalibration<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How levels are spread over the color map within a [`DisplayRange`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DisplayScale {
+    /// Even steps in power: only strong signals stand out.
+    Linear,
+    /// Even steps in dB, the usual waterfall.
+    #[default]
+    Log,
+    /// Even steps in amplitude, between the two.
+    Sqrt,
+}
+
+/// Level span mapped onto the waterfall color map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayRange {
+    /// Level shown as the darkest color, in dB
+    pub floor_db: f32,
+    /// Span from darkest to brightest, in dB
+    pub range_db: f32,
+}
+
+impl DisplayRange {
+    /// Fixed range from a reference (top) level and span.
+    #[must_use]
+    pub fn new(ref_db: f32, range_db: f32) -> Self {
+        Self {
+            floor_db: ref_db - range_db,
+            range_db: range_db.max(1.0),
+        }
+    }
+
+    /// Auto-range from the noise floor and strongest signal.
+    ///
+    /// The floor sits just below the noise so the background is dark
+    /// but visible; the top follows the peak with a minimum span.
+    #[must_use]
+    pub fn auto(noise_floor_db: f32, peak_db: f32) -> Self {
+        let floor_db = noise_floor_db - NOISE_MARGIN_DB;
+        Self {
+            floor_db,
+            range_db: (peak_db - floor_db).max(MIN_DISPLAY_RANGE_DB),
+        }
+    }
+
+    /// Level shown as the brightest color, in dB.
+    #[must_use]
+    pub fn ref_db(&self) -> f32 {
+        self.floor_db + self.range_db
+    }
+
+    /// Map a level to the color map position (0.0 to 1.0).
+    #[must_use]
+    pub fn normalize(&self, db: f32) -> f32 {
+        ((db - self.floor_db) / self.range_db).clamp(0.0, 1.0)
+    }
+
+    /// Map a level to the color map position (0.0 to 1.0) on a scale.
+    ///
+    /// The floor maps to 0.0 and the reference level to 1.0 on every
+    /// scale; they differ in how the levels between are spread.
+    #[must_use]
+    pub fn normalize_scaled(&self, db: f32, scale: DisplayScale) -> f32 {
+        let divisor = match scale {
+            DisplayScale::Log => return self.normalize(db),
+            DisplayScale::Linear => 10.0,
+            DisplayScale::Sqrt => 20.0,
+        };
+        let level = |db: f32| 10.0_f32.powf((db - self.ref_db()) / divisor);
+        let floor = level(self.floor_db);
+        ((level(db.min(self.ref_db())) - floor) / (1.0 - floor)).clamp(0.0, 1.0)
+    }
+}
+
+/// Maximum panadapter zoom factor.
+pub const MAX_ZOOM: f32 = 16.0;
+
+/// Zoomed and panned window onto the spectrum.
+///
+/// The full spectrum covers offsets `0..span_hz` from its first bin;
+/// the view shows `span_hz / zoom` of it starting at `start_hz`. Screen
+/// positions are fractions of the display width (0.0 left, 1.0 right).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrumView {
+    span_hz: f32,
+    zoom: f32,
+    start_hz: f32,
+}
+
+impl SpectrumView {
+    /// Unzoomed view of a spectrum `span_hz` wide.
+    #[must_use]
+    pub fn new(span_hz: f32) -> Self {
+        Self {
+            span_hz: span_hz.max(1.0),
+            zoom: 1.0,
+            start_hz: 0.0,
+        }
+    }
+
+    /// Unzoomed view of the bins produced with `config`.
+    #[must_use]
+    pub fn from_config(config: &SpectrumConfig) -> Self {
+        Self::new(config.sample_rate as f32 / 2.0)
+    }
+
+    /// Full spectrum width in Hz.
+    #[must_use]
+    pub fn span_hz(&self) -> f32 {
+        self.span_hz
+    }
+
+    /// Change the full spectrum width (e.g. new sample rate), keeping zoom.
+    pub fn set_span_hz(&mut self, span_hz: f32) {
+        self.span_hz = span_hz.max(1.0);
+        self.clamp_start();
+    }
+
+    /// Current zoom factor (1.0 shows the full span).
+    #[must_use]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Width of the visible window in Hz.
+    #[must_use]
+    pub fn visible_span_hz(&self) -> f32 {
+        self.span_hz / self.zoom
+    }
+
+    /// Offset of the left edge of the display in Hz.
+    #[must_use]
+    pub fn start_hz(&self) -> f32 {
+        self.start_hz
+    }
+
+    /// Offset in Hz under a screen position.
+    #[must_use]
+    pub fn offset_hz(&self, x: f32) -> f32 {
+        self.start_hz + x.clamp(0.0, 1.0) * self.visible_span_hz()
+    }
+
+    /// Screen position of an offset, `None` when it is out of view.
+    #[must_use]
+    pub fn position(&self, offset_hz: f32) -> Option<f32> {
+        let x = (offset_hz - self.start_hz) / self.visible_span_hz();
+        (0.0..=1.0).contains(&x).then_some(x)
+    }
+
+    /// Multiply the zoom by `factor`, keeping the offset under `x` fixed.
+    pub fn zoom_at(&mut self, factor: f32, x: f32) {
+        let x = x.clamp(0.0, 1.0);
+        let anchor = self.offset_hz(x);
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        self.start_hz = anchor - x * self.visible_span_hz();
+        self.clamp_start();
+    }
+
+    /// Drag the spectrum by `dx` display widths (positive moves it right).
+    pub fn pan(&mut self, dx: f32) {
+        self.start_hz -= dx * self.visible_span_hz();
+        self.clamp_start();
+    }
+
+    /// Return to the unzoomed view.
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.start_hz = 0.0;
+    }
+
+    /// Resample the visible part of `bins` onto `output` pixels.
+    ///
+    /// `bins` must cover the full span; each pixel takes the bin at its
+    /// centre, so zooming in repeats bins rather than inventing detail.
+    pub fn resample(&self, bins: &[f32], output: &mut [f32]) {
+        if bins.is_empty() || output.is_empty() {
+            return;
+        }
+        let last = bins.len() - 1;
+        let pixels = output.len() as f32;
+        for (i, out) in output.iter_mut().enumerate() {
+            let offset = self.offset_hz((i as f32 + 0.5) / pixels);
+            let bin = (offset / self.span_hz * bins.len() as f32) as usize;
+            *out = bins[bin.min(last)];
+        }
+    }
+
+    fn clamp_start(&mut self) {
+        let max_start = self.span_hz - self.visible_span_hz();
+        self.start_hz = self.start_hz.clamp(0.0, max_start.max(0.0));
+    }
+}
+
+impl Default for SpectrumView {
+    fn default() -> Self {
+        Self::from_config(&SpectrumConfig::default())
+    }
+}
+
+/// Round a frequency to the nearest multiple of the tuning step.
+#[must_use]
+pub fn snap_to_step(hz: u64, step_hz: u64) -> u64 {
+    if step_hz == 0 {
+        return hz;
+    }
+    (hz + step_hz / 2) / step_hz * step_hz
+}
+
 /// Waterfall row data (for display).
 #[derive(Clone, Debug)]
 pub struct WaterfallRow {
@@ -433,6 +1060,164 @@ mod tests {
         assert_eq!(row.data[2], 0); // -80 dB = min brightness
     }
 
+    #[test]
+    fn test_fft_dbfs_and_calibrated_average() {
+        let mut fft = FftSpectrum::new(256);
+        // Full-scale sine centred on bin 16
+        let freq = 16.0 / 256.0;
+        for i in 0..256 {
+            fft.push((2.0 * core::f32::consts::PI * freq * i as f32).sin());
+        }
+        let mut dbfs = [0.0; 128];
+        fft.compute_dbfs(&mut dbfs);
+        assert!(dbfs[16].abs() < 0.5, "{}", dbfs[16]);
+
+        let mut averager = SpectrumAverager::new(4);
+        averager.set_gain_db(-60.0);
+        let out = averager.process(&dbfs);
+        assert!((out[16] + 60.0).abs() < 0.5);
+
+        // A frame 10 dB down only moves the average a quarter of the way
+        let quieter: [f32; 128] = core::array::from_fn(|i| dbfs[i] - 10.0);
+        let out = averager.process(&quieter);
+        let expected = 10.0 * (0.75 + 0.25 * 0.1_f32).log10() - 60.0;
+        assert!((out[16] - expected).abs() < 0.5);
+        assert!(averager.peak_db().unwrap() > averager.noise_floor_db().unwrap() + 40.0);
+    }
+
+    #[test]
+    fn test_dc_notch_interpolates_and_skips_autoscale() {
+        let mut bins: [f32; 16] = core::array::from_fn(|bin| -100.0 - bin as f32);
+        bins[8] = -20.0;
+        let notch = DcNotch::new(8, 1);
+        assert!(notch.contains(7) && notch.contains(9) && !notch.contains(10));
+        let mut frame = bins;
+        notch.apply(&mut frame);
+        // A straight line from bin 6 to bin 10
+        for (bin, level) in frame.iter().enumerate().take(10).skip(7) {
+            assert!((level + 100.0 + bin as f32).abs() < 1e-3, "{level}");
+        }
+        assert_eq!(frame[..7], bins[..7]);
+
+        // DC in the first bin of a real spectrum takes the next level
+        let mut frame = bins;
+        frame[0] = -10.0;
+        DcNotch::from_hz(0, 200.0, 187.5).apply(&mut frame);
+        assert_eq!(frame[..3], [-103.0; 3]);
+        assert_eq!(DcNotch::from_hz(0, 0.0, 187.5).width, 0);
+
+        let mut averager = SpectrumAverager::new(1);
+        averager.set_gain_db(0.0);
+        averager.process(&bins);
+        assert_eq!(averager.peak_db(), Some(-20.0));
+        averager.set_dc_notch(Some(notch));
+        assert_eq!(averager.dc_notch(), Some(notch));
+        let out = averager.process(&bins);
+        assert!(out[8] < -100.0);
+        assert!((averager.peak_db().unwrap() + 100.0).abs() < 1e-3);
+        assert!((averager.noise_floor_db().unwrap() + 112.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_find_peaks_strongest_first() {
+        let mut bins = [-110.0_f32; 128];
+        // Three signals, the middle one strongest, and one in the noise
+        for (centre, level) in [(20, -60.0), (50, -40.0), (90, -70.0), (110, -105.0)] {
+            bins[centre] = level;
+            bins[centre - 1] = level - 3.0;
+            bins[centre + 1] = level - 9.0;
+        }
+        let mut peaks = [SpectrumPeak::default(); 2];
+        assert_eq!(find_peaks(&bins, 100.0, DEFAULT_PEAK_THRESHOLD_DB, &mut peaks), 2);
+        assert_eq!(peaks[0].level_db, -40.0);
+        assert_eq!(peaks[1].level_db, -60.0);
+
+        // Pulled towards the stronger neighbour; 6 dB down 2/3 of a bin
+        // above and 3/67 of one below the lower neighbour
+        let peak = peaks[0];
+        assert!(peak.frequency_hz > 4950.0 && peak.frequency_hz < 5000.0, "{peak:?}");
+        assert!((peak.width_hz - 171.14).abs() < 0.01, "{}", peak.width_hz);
+
+        // All three found with room for more
+        let mut peaks = [SpectrumPeak::default(); MAX_PEAKS];
+        assert_eq!(find_peaks(&bins, 100.0, DEFAULT_PEAK_THRESHOLD_DB, &mut peaks), 3);
+        assert_eq!(peaks[2].level_db, -70.0);
+
+        // Nothing stands out of flat noise
+        let flat = [-110.0_f32; 64];
+        assert_eq!(find_peaks(&flat, 100.0, 10.0, &mut peaks), 0);
+        assert_eq!(find_peaks(&[], 100.0, 10.0, &mut peaks), 0);
+    }
+
+    #[test]
+    fn test_noise_floor_and_auto_range() {
+        let mut bins = [-110.0_f32; 64];
+        bins[10] = -50.0;
+        bins[11] = -55.0;
+        assert_eq!(noise_floor_db(&bins), Some(-110.0));
+        assert_eq!(noise_floor_db(&[]), None);
+
+        let range = DisplayRange::auto(-110.0, -50.0);
+        assert!((range.floor_db + 116.0).abs() < 1e-3);
+        assert!((range.ref_db() + 50.0).abs() < 1e-3);
+        assert!(range.normalize(-110.0) > 0.0 && range.normalize(-110.0) < 0.2);
+        assert!((range.normalize(0.0) - 1.0).abs() < f32::EPSILON);
+
+        // Every scale spans floor to reference; halfway in dB sits lower
+        // on the linear scales
+        let mid = range.floor_db + range.range_db / 2.0;
+        for scale in [DisplayScale::Linear, DisplayScale::Log, DisplayScale::Sqrt] {
+            assert!(range.normalize_scaled(range.floor_db - 10.0, scale).abs() < 1e-6);
+            assert!((range.normalize_scaled(range.ref_db(), scale) - 1.0).abs() < 1e-3);
+        }
+        assert!((range.normalize_scaled(mid, DisplayScale::Log) - 0.5).abs() < 1e-3);
+        let sqrt = range.normalize_scaled(mid, DisplayScale::Sqrt);
+        let linear = range.normalize_scaled(mid, DisplayScale::Linear);
+        assert!(linear < sqrt && sqrt < 0.5, "{linear} {sqrt}");
+
+        // Quiet band keeps a minimum span
+        let range = DisplayRange::auto(-120.0, -118.0);
+        assert!((range.range_db - MIN_DISPLAY_RANGE_DB).abs() < 1e-3);
+
+        let mut cal: SpectrumCalibration<2> = SpectrumCalibration::new();
+        cal.calibrate(1, -70.0, -73.0);
+        assert!((cal.gain_db(1) - (DEFAULT_SPECTRUM_GAIN_DB - 3.0)).abs() < 1e-3);
+        assert!((cal.gain_db(5) - DEFAULT_SPECTRUM_GAIN_DB).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_spectrum_view_zoom_pan_and_snap() {
+        let mut view = SpectrumView::new(24_000.0);
+        assert!((view.offset_hz(0.5) - 12_000.0).abs() < 1e-3);
+
+        // Zooming keeps the frequency under the cursor in place
+        view.zoom_at(4.0, 0.25);
+        assert!((view.visible_span_hz() - 6_000.0).abs() < 1e-3);
+        assert!((view.offset_hz(0.25) - 6_000.0).abs() < 1e-3);
+        assert_eq!(view.position(20_000.0), None);
+
+        // Panning stops at the edges of the span
+        view.pan(-10.0);
+        assert!((view.start_hz() - 18_000.0).abs() < 1e-3);
+        view.pan(0.5);
+        assert!((view.start_hz() - 15_000.0).abs() < 1e-3);
+        view.zoom_at(100.0, 0.0);
+        assert!((view.zoom() - MAX_ZOOM).abs() < f32::EPSILON);
+
+        let bins: [f32; 8] = core::array::from_fn(|i| i as f32);
+        let mut out = [0.0; 4];
+        SpectrumView::new(8.0).resample(&bins, &mut out);
+        assert_eq!(out, [1.0, 3.0, 5.0, 7.0]);
+        let mut zoomed = SpectrumView::new(8.0);
+        zoomed.zoom_at(2.0, 1.0);
+        zoomed.resample(&bins, &mut out);
+        assert_eq!(out, [4.0, 5.0, 6.0, 7.0]);
+
+        assert_eq!(snap_to_step(7_074_049, 100), 7_074_000);
+        assert_eq!(snap_to_step(7_074_050, 100), 7_074_100);
+        assert_eq!(snap_to_step(7_074_049, 0), 7_074_049);
+    }
+
     #[test]
     fn test_fft_power_of_two() {
         let fft = FftSpectrum::new(100); // Should round up to 128
@@ -0,0 +1,142 @@
+//! Transmit speech processing.
+//!
+//! The compressor raises the average level of speech relative to its
+//! peaks, so the same PEP carries more talk power, with a limiter to
+//! catch what its attack lets through. It matches the firmware speech
+//! processor, so a browser transmit chain sounds the same on air.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Maximum compression in dB.
+pub const MAX_COMPRESSION_DB: f32 = 20.0;
+
+/// Limiter ceiling (linear, full scale = 1.0).
+pub const LIMITER_CEILING: f32 = 0.95;
+
+/// Compressor ratio above threshold.
+const COMPRESSOR_RATIO: f32 = 4.0;
+
+/// Compressor attack time in milliseconds.
+const COMPRESSOR_ATTACK_MS: f32 = 2.0;
+
+/// Compressor release time in milliseconds.
+const COMPRESSOR_RELEASE_MS: f32 = 150.0;
+
+/// One-pole smoothing coefficient for a time constant.
+fn time_coeff(ms: f32, sample_rate: f32) -> f32 {
+    let samples = (ms / 1000.0 * sample_rate).max(1.0);
+    1.0 - (-1.0 / samples).exp()
+}
+
+/// Speech compressor with a brick-wall limiter.
+///
+/// The threshold sits `compression_db` below full scale and make-up gain
+/// restores full-scale peaks, so raising the compression raises the
+/// average level while peaks stay put.
+#[derive(Clone, Copy, Debug)]
+pub struct Compressor {
+    /// Compression amount in dB (0 = off).
+    compression_db: f32,
+    /// Threshold (linear).
+    threshold: f32,
+    /// Make-up gain (linear).
+    makeup: f32,
+    /// Peak envelope.
+    envelope: f32,
+    /// Attack coefficient.
+    attack: f32,
+    /// Release coefficient.
+    release: f32,
+}
+
+impl Compressor {
+    /// Create a compressor with the given compression in dB.
+    #[must_use]
+    pub fn new(sample_rate: f32, compression_db: f32) -> Self {
+        let mut compressor = Self {
+            compression_db: 0.0,
+            threshold: 1.0,
+            makeup: 1.0,
+            envelope: 0.0,
+            attack: time_coeff(COMPRESSOR_ATTACK_MS, sample_rate),
+            release: time_coeff(COMPRESSOR_RELEASE_MS, sample_rate),
+        };
+        compressor.set_compression(compression_db);
+        compressor
+    }
+
+    /// Set compression in dB (clamped to 0 to [`MAX_COMPRESSION_DB`]).
+    pub fn set_compression(&mut self, compression_db: f32) {
+        self.compression_db = compression_db.clamp(0.0, MAX_COMPRESSION_DB);
+        self.threshold = 10.0_f32.powf(-self.compression_db / 20.0);
+        let makeup_db = self.compression_db * (1.0 - 1.0 / COMPRESSOR_RATIO);
+        self.makeup = 10.0_f32.powf(makeup_db / 20.0);
+    }
+
+    /// Get compression in dB.
+    #[must_use]
+    pub fn compression_db(&self) -> f32 {
+        self.compression_db
+    }
+
+    /// Get the gain applied before make-up (linear, 1.0 = no reduction).
+    #[must_use]
+    pub fn gain_reduction(&self) -> f32 {
+        if self.envelope > self.threshold {
+            (self.threshold / self.envelope).powf(1.0 - 1.0 / COMPRESSOR_RATIO)
+        } else {
+            1.0
+        }
+    }
+
+    /// Process one sample.
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coeff = if level > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope += coeff * (level - self.envelope);
+
+        let output = input * self.gain_reduction() * self.makeup;
+        output.clamp(-LIMITER_CEILING, LIMITER_CEILING)
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Reset the envelope.
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressor_raises_quiet_speech_and_limits_peaks() {
+        let mut compressor = Compressor::new(48000.0, 12.0);
+        let mut quiet = 0.0;
+        for _ in 0..4800 {
+            quiet = compressor.process(0.1);
+        }
+        // 12 dB at 4:1 gives 9 dB of make-up below the threshold
+        assert!((quiet - 0.1 * 10.0_f32.powf(9.0 / 20.0)).abs() < 1e-3, "{quiet}");
+
+        for _ in 0..4800 {
+            assert!(compressor.process(2.0) <= LIMITER_CEILING);
+        }
+        assert!(compressor.gain_reduction() < 1.0);
+
+        compressor.set_compression(100.0);
+        assert_eq!(compressor.compression_db(), MAX_COMPRESSION_DB);
+    }
+}
@@ -0,0 +1,365 @@
+//! Direct digital synthesis tone generators.
+//!
+//! [`ToneGenerator`] is a bank of phase accumulator oscillators summed
+//! into one output, each tone with its own frequency, amplitude and
+//! starting phase: one tone for a CW sidetone, two for a two-tone
+//! transmit test, more for multi-tone test signals. Frequency changes are
+//! phase continuous, so a tone can be swept or frequency shift keyed
+//! sample by sample; [`gfsk_tone`] gives the smoothed tone number for
+//! FT8 style Gaussian FSK.
+
+use core::f32::consts::PI;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::types::IqSample;
+
+/// Phase accumulator steps in a full turn.
+const STEPS_PER_TURN: f64 = 4_294_967_296.0;
+
+/// Radians per phase accumulator step.
+const RADIANS_PER_STEP: f32 = 2.0 * PI / 4_294_967_296.0;
+
+/// Bandwidth-time product of the FT8 (and JS8) GFSK pulse.
+pub const FT8_GFSK_BT: f32 = 2.0;
+
+/// Convert a frequency in Hz to a phase step per sample.
+fn phase_step(frequency: f32, sample_rate: f32) -> u32 {
+    // Through i64 so negative frequencies wrap to a backwards step
+    (f64::from(frequency) / f64::from(sample_rate) * STEPS_PER_TURN) as i64 as u32
+}
+
+/// Convert a phase in radians to accumulator steps.
+fn phase_steps(radians: f32) -> u32 {
+    (f64::from(radians) / (2.0 * core::f64::consts::PI) * STEPS_PER_TURN) as i64 as u32
+}
+
+/// One tone of a [`ToneGenerator`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Tone {
+    frequency: f32,
+    amplitude: f32,
+    /// Phase restored by reset, in accumulator steps
+    start_phase: u32,
+    phase: u32,
+    /// Phase advance per sample
+    step: u32,
+}
+
+impl Tone {
+    const SILENT: Self = Self {
+        frequency: 0.0,
+        amplitude: 0.0,
+        start_phase: 0,
+        phase: 0,
+        step: 0,
+    };
+
+    /// Current phase in radians (-π to π).
+    #[inline]
+    fn angle(&self) -> f32 {
+        self.phase as i32 as f32 * RADIANS_PER_STEP
+    }
+
+    #[inline]
+    fn advance(&mut self) {
+        self.phase = self.phase.wrapping_add(self.step);
+    }
+}
+
+/// Bank of up to `N` summed sine tones.
+///
+/// Each tone runs a 32-bit phase accumulator, so frequencies are exact to
+/// a small fraction of a hertz and the phase never drifts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneGenerator<const N: usize> {
+    sample_rate: f32,
+    tones: [Tone; N],
+    len: usize,
+}
+
+impl<const N: usize> ToneGenerator<N> {
+    /// Create an empty bank for `sample_rate` Hz.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            tones: [Tone::SILENT; N],
+            len: 0,
+        }
+    }
+
+    /// Create a bank with one tone of `amplitude` at `frequency` Hz.
+    #[must_use]
+    pub fn single(sample_rate: f32, frequency: f32, amplitude: f32) -> Self {
+        let mut bank = Self::new(sample_rate);
+        bank.add_tone(frequency, amplitude, 0.0);
+        bank
+    }
+
+    /// Create a two-tone test signal with a peak of `amplitude`, shared
+    /// equally between tones at `low_hz` and `high_hz`.
+    ///
+    /// Needs room for two tones; a smaller bank gets only the low tone.
+    #[must_use]
+    pub fn two_tone(sample_rate: f32, low_hz: f32, high_hz: f32, amplitude: f32) -> Self {
+        let mut bank = Self::new(sample_rate);
+        bank.add_tone(low_hz, amplitude / 2.0, 0.0);
+        bank.add_tone(high_hz, amplitude / 2.0, 0.0);
+        bank
+    }
+
+    /// Add a tone at `frequency` Hz with peak `amplitude`, starting at
+    /// `phase` radians.
+    ///
+    /// Returns the index of the tone, or `None` if the bank is full.
+    pub fn add_tone(&mut self, frequency: f32, amplitude: f32, phase: f32) -> Option<usize> {
+        let index = self.len;
+        let tone = self.tones.get_mut(index)?;
+        let start_phase = phase_steps(phase);
+        *tone = Tone {
+            frequency,
+            amplitude,
+            start_phase,
+            phase: start_phase,
+            step: phase_step(frequency, self.sample_rate),
+        };
+        self.len += 1;
+        Some(index)
+    }
+
+    /// Remove all tones.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Get the number of tones.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the bank has no tones.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the sample rate in Hz.
+    #[must_use]
+    pub const fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Change the sample rate, keeping every tone's frequency.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for tone in &mut self.tones[..self.len] {
+            tone.step = phase_step(tone.frequency, sample_rate);
+        }
+    }
+
+    /// Set the frequency of tone `index` in Hz without a phase jump.
+    ///
+    /// Ignored if there is no such tone.
+    pub fn set_frequency(&mut self, index: usize, frequency: f32) {
+        let sample_rate = self.sample_rate;
+        if let Some(tone) = self.tones[..self.len].get_mut(index) {
+            tone.frequency = frequency;
+            tone.step = phase_step(frequency, sample_rate);
+        }
+    }
+
+    /// Get the frequency of tone `index` in Hz.
+    #[must_use]
+    pub fn frequency(&self, index: usize) -> Option<f32> {
+        self.tones[..self.len].get(index).map(|t| t.frequency)
+    }
+
+    /// Set the peak amplitude of tone `index`.
+    ///
+    /// Ignored if there is no such tone.
+    pub fn set_amplitude(&mut self, index: usize, amplitude: f32) {
+        if let Some(tone) = self.tones[..self.len].get_mut(index) {
+            tone.amplitude = amplitude;
+        }
+    }
+
+    /// Get the peak amplitude of tone `index`.
+    #[must_use]
+    pub fn amplitude(&self, index: usize) -> Option<f32> {
+        self.tones[..self.len].get(index).map(|t| t.amplitude)
+    }
+
+    /// Set the phase of tone `index` in radians, now and after
+    /// [`reset`](Self::reset).
+    ///
+    /// Ignored if there is no such tone.
+    pub fn set_phase(&mut self, index: usize, phase: f32) {
+        if let Some(tone) = self.tones[..self.len].get_mut(index) {
+            tone.start_phase = phase_steps(phase);
+            tone.phase = tone.start_phase;
+        }
+    }
+
+    /// Get the highest possible output level, when every tone peaks
+    /// together.
+    #[must_use]
+    pub fn peak_amplitude(&self) -> f32 {
+        self.tones[..self.len]
+            .iter()
+            .map(|t| t.amplitude.abs())
+            .sum()
+    }
+
+    /// Generate the next sample, the sum of the tones' sines.
+    #[inline]
+    pub fn next_sample(&mut self) -> f32 {
+        let mut sum = 0.0;
+        for tone in &mut self.tones[..self.len] {
+            sum += tone.amplitude * tone.angle().sin();
+            tone.advance();
+        }
+        sum
+    }
+
+    /// Generate the next complex sample, the sum of the tones as
+    /// `amplitude * e^(j*phase)`, for IQ transmit signals.
+    #[inline]
+    pub fn next_iq(&mut self) -> IqSample {
+        let mut sum = IqSample::new(0.0, 0.0);
+        for tone in &mut self.tones[..self.len] {
+            let angle = tone.angle();
+            sum = sum + IqSample::new(angle.cos(), angle.sin()) * tone.amplitude;
+            tone.advance();
+        }
+        sum
+    }
+
+    /// Fill `output` with the next samples.
+    pub fn fill(&mut self, output: &mut [f32]) {
+        for sample in output {
+            *sample = self.next_sample();
+        }
+    }
+
+    /// Return every tone to its starting phase.
+    pub fn reset(&mut self) {
+        for tone in &mut self.tones[..self.len] {
+            tone.phase = tone.start_phase;
+        }
+    }
+}
+
+/// Error function, to within about 1.5e-7 (Abramowitz and Stegun 7.1.26).
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_6
+            + t * (-0.284_496_74 + t * (1.421_413_7 + t * (-1.453_152 + t * 1.061_405_4))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
+
+/// Get the Gaussian frequency pulse for bandwidth-time product `bt`, `t`
+/// symbols from the middle of its symbol.
+///
+/// The pulses of successive symbols sum to one, so a steady run of one
+/// tone stays on that tone.
+#[must_use]
+pub fn gfsk_pulse(bt: f32, t: f32) -> f32 {
+    // π * sqrt(2 / ln 2)
+    const K: f32 = 5.336_446;
+    0.5 * (erf(K * bt * (t + 0.5)) - erf(K * bt * (t - 0.5)))
+}
+
+/// Get the smoothed tone number at `position` (0.0 to 1.0) through the
+/// `current` symbol, between the `previous` and `next` symbols' tones.
+///
+/// Only neighbouring symbols are considered, which is plenty for the
+/// FT8 pulse ([`FT8_GFSK_BT`]). At the ends of a transmission pass the
+/// current tone as the missing neighbour.
+#[must_use]
+pub fn gfsk_tone(bt: f32, previous: f32, current: f32, next: f32, position: f32) -> f32 {
+    let t = position - 0.5;
+    previous * gfsk_pulse(bt, t + 1.0)
+        + current * gfsk_pulse(bt, t)
+        + next * gfsk_pulse(bt, t - 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Level of `frequency` in `samples`, by correlation.
+    fn level(samples: &[f32], frequency: f32, sample_rate: f32) -> f32 {
+        let (mut i, mut q) = (0.0, 0.0);
+        for (n, &x) in samples.iter().enumerate() {
+            let angle = 2.0 * PI * frequency * n as f32 / sample_rate;
+            i += x * angle.cos();
+            q += x * angle.sin();
+        }
+        2.0 * (i * i + q * q).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_two_tone_levels() {
+        let rate = 8000.0;
+        let mut bank = ToneGenerator::<4>::two_tone(rate, 700.0, 1900.0, 0.8);
+        assert_eq!(bank.len(), 2);
+        assert!((bank.peak_amplitude() - 0.8).abs() < 1e-6);
+
+        let mut samples = [0.0; 800];
+        bank.fill(&mut samples);
+        assert!(samples.iter().all(|s| s.abs() <= 0.801));
+        assert!((level(&samples, 700.0, rate) - 0.4).abs() < 0.01);
+        assert!((level(&samples, 1900.0, rate) - 0.4).abs() < 0.01);
+        assert!(level(&samples, 1300.0, rate) < 0.01);
+
+        // Full bank
+        assert_eq!(bank.add_tone(2500.0, 0.1, 0.0), Some(2));
+        assert_eq!(bank.add_tone(3000.0, 0.1, 0.0), Some(3));
+        assert_eq!(bank.add_tone(3500.0, 0.1, 0.0), None);
+    }
+
+    #[test]
+    fn test_phase_and_frequency_changes() {
+        let mut bank = ToneGenerator::<1>::new(48000.0);
+        bank.add_tone(1000.0, 1.0, PI / 2.0);
+        assert!((bank.next_sample() - 1.0).abs() < 1e-3);
+
+        // Changing frequency keeps the waveform continuous
+        let before = bank.next_sample();
+        bank.set_frequency(0, 1100.0);
+        let after = bank.next_sample();
+        assert!((after - before).abs() < 0.15, "{before} {after}");
+        assert_eq!(bank.frequency(0), Some(1100.0));
+
+        // Reset returns to the starting phase
+        bank.reset();
+        assert!((bank.next_sample() - 1.0).abs() < 1e-3);
+
+        // Negative frequencies rotate the other way
+        let mut iq = ToneGenerator::<1>::single(48000.0, -1000.0, 1.0);
+        iq.next_iq();
+        assert!(iq.next_iq().q < 0.0);
+    }
+
+    #[test]
+    fn test_gfsk_shaping() {
+        // A steady tone stays put
+        for position in [0.0, 0.3, 0.5, 1.0] {
+            let tone = gfsk_tone(FT8_GFSK_BT, 3.0, 3.0, 3.0, position);
+            assert!((tone - 3.0).abs() < 1e-3, "{tone}");
+        }
+        // A step is halfway at the symbol boundary and settled mid-symbol
+        assert!((gfsk_tone(FT8_GFSK_BT, 0.0, 0.0, 4.0, 1.0) - 2.0).abs() < 0.01);
+        assert!((gfsk_tone(FT8_GFSK_BT, 0.0, 4.0, 4.0, 0.5) - 4.0).abs() < 0.01);
+        assert!(gfsk_tone(FT8_GFSK_BT, 0.0, 4.0, 4.0, 0.1) < 4.0);
+    }
+}
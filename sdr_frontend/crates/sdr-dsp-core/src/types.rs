@@ -170,6 +170,8 @@ pub struct SignalMetrics {
     pub imd_db: f32,
     /// AFC frequency offset in Hz.
     pub afc_offset_hz: f32,
+    /// Whether the carrier loop is locked.
+    pub carrier_locked: bool,
     /// Symbol timing error (normalized).
     pub timing_error: f32,
     /// Whether signal is above squelch threshold.
@@ -186,6 +188,7 @@ impl SignalMetrics {
             snr_db: -30.0,
             imd_db: -30.0,
             afc_offset_hz: 0.0,
+            carrier_locked: false,
             timing_error: 0.0,
             squelch_open: false,
             confidence: 0.0,
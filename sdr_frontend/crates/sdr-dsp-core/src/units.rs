@@ -0,0 +1,212 @@
+//! Unit Conversion and Formatting.
+//!
+//! Shared conversions between signal level units and the text rendering
+//! of frequencies, levels and power, so the firmware display, CAT and the
+//! web UI show the same quantity the same way.
+//!
+//! Levels follow the IARU HF S-meter convention: S9 is -73 dBm
+//! (50 µV into 50 Ω) and each S-unit is 6 dB.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use core::fmt::Write;
+
+use heapless::String;
+
+use crate::agc::SmeterReading;
+
+/// Level of S9 in dBm (IARU HF convention).
+pub const S9_DBM: f32 = -73.0;
+
+/// Level change per S-unit in dB.
+pub const DB_PER_S_UNIT: f32 = 6.0;
+
+/// System impedance for voltage conversions in ohms.
+pub const IMPEDANCE_OHMS: f32 = 50.0;
+
+/// Maximum length of a formatted frequency (`"999.999.999.999"`).
+pub const FREQUENCY_LEN: usize = 16;
+
+/// Maximum length of a formatted level or power.
+pub const VALUE_LEN: usize = 12;
+
+/// Convert dBm to S-units (9.0 = S9, above 9 is S9+).
+#[must_use]
+pub fn dbm_to_s_units(dbm: f32) -> f32 {
+    9.0 + (dbm - S9_DBM) / DB_PER_S_UNIT
+}
+
+/// Convert S-units to dBm.
+#[must_use]
+pub fn s_units_to_dbm(s_units: f32) -> f32 {
+    S9_DBM + (s_units - 9.0) * DB_PER_S_UNIT
+}
+
+/// Convert an S-unit value to a meter reading.
+///
+/// Below S9 the reading is rounded to the nearest S-unit; above S9 it
+/// is the rounded number of dB over S9.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn s_units_to_reading(s_units: f32) -> SmeterReading {
+    let over_db = ((s_units - 9.0) * DB_PER_S_UNIT).round();
+    if over_db >= 1.0 {
+        SmeterReading::S9Plus(over_db.min(f32::from(i8::MAX)) as i8)
+    } else {
+        SmeterReading::S(s_units.round().clamp(0.0, 9.0) as u8)
+    }
+}
+
+/// Convert a level in dBm to a meter reading.
+#[must_use]
+pub fn dbm_to_reading(dbm: f32) -> SmeterReading {
+    s_units_to_reading(dbm_to_s_units(dbm))
+}
+
+/// Convert dBm to watts.
+#[must_use]
+pub fn dbm_to_watts(dbm: f32) -> f32 {
+    10.0_f32.powf((dbm - 30.0) / 10.0)
+}
+
+/// Convert watts to dBm (non-positive power gives -200 dBm).
+#[must_use]
+pub fn watts_to_dbm(watts: f32) -> f32 {
+    if watts <= 0.0 {
+        -200.0
+    } else {
+        10.0 * watts.log10() + 30.0
+    }
+}
+
+/// Convert dBm to RMS microvolts across [`IMPEDANCE_OHMS`].
+#[must_use]
+pub fn dbm_to_microvolts(dbm: f32) -> f32 {
+    (dbm_to_watts(dbm) * IMPEDANCE_OHMS).sqrt() * 1e6
+}
+
+/// Convert RMS microvolts across [`IMPEDANCE_OHMS`] to dBm.
+#[must_use]
+pub fn microvolts_to_dbm(microvolts: f32) -> f32 {
+    let volts = microvolts * 1e-6;
+    watts_to_dbm(volts * volts / IMPEDANCE_OHMS)
+}
+
+/// Format a frequency in Hz with dot grouping every three digits.
+///
+/// `14_074_000` renders as `"14.074.000"`, `474_200` as `"474.200"`.
+/// No padding is added; callers align the result as needed.
+#[must_use]
+pub fn format_frequency(hz: u64) -> String<FREQUENCY_LEN> {
+    let mut digits = [0u8; 20];
+    let mut n = hz;
+    let mut len = 0;
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            digits[len] = b'0' + (n % 10) as u8;
+        }
+        len += 1;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+
+    let mut out = String::new();
+    for i in (0..len).rev() {
+        let _ = out.push(char::from(digits[i]));
+        if i > 0 && i % 3 == 0 {
+            let _ = out.push('.');
+        }
+    }
+    out
+}
+
+/// Format a level in whole dBm (e.g. `"-73 dBm"`).
+#[must_use]
+pub fn format_dbm(dbm: f32) -> String<VALUE_LEN> {
+    let mut out = String::new();
+    let rounded = dbm.round();
+    // Avoid printing "-0 dBm"
+    let value = if rounded == 0.0 { 0.0 } else { rounded };
+    let _ = write!(out, "{value:.0} dBm");
+    out
+}
+
+/// Format a level as an S-meter reading (e.g. `"S7"`, `"S9+20"`).
+#[must_use]
+pub fn format_s_meter(dbm: f32) -> String<8> {
+    dbm_to_reading(dbm).to_string()
+}
+
+/// Format power with precision suited to its magnitude.
+///
+/// Below 1 W power is shown in whole milliwatts (`"250 mW"`), below
+/// 10 W with one decimal (`"5.0 W"`) and above that in whole watts
+/// (`"100 W"`). Rounding is applied before choosing the range, so
+/// 0.9996 W shows as `"1.0 W"` rather than `"1000 mW"`.
+#[must_use]
+pub fn format_watts(watts: f32) -> String<VALUE_LEN> {
+    let mut out = String::new();
+    let watts = watts.max(0.0);
+    let milliwatts = (watts * 1000.0).round();
+    let tenths = (watts * 10.0).round();
+    if milliwatts < 1000.0 {
+        let _ = write!(out, "{milliwatts:.0} mW");
+    } else if tenths < 100.0 {
+        let _ = write!(out, "{:.1} W", tenths / 10.0);
+    } else {
+        let _ = write!(out, "{:.0} W", watts.round());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_frequency_grouping() {
+        assert_eq!(format_frequency(14_074_000).as_str(), "14.074.000");
+        assert_eq!(format_frequency(7_000_000).as_str(), "7.000.000");
+        assert_eq!(format_frequency(474_200).as_str(), "474.200");
+        assert_eq!(format_frequency(144_300_000).as_str(), "144.300.000");
+        assert_eq!(format_frequency(999).as_str(), "999");
+        assert_eq!(format_frequency(0).as_str(), "0");
+    }
+
+    #[test]
+    fn test_s_unit_conversion() {
+        assert!((dbm_to_s_units(-73.0) - 9.0).abs() < 1e-4);
+        assert!((dbm_to_s_units(-121.0) - 1.0).abs() < 1e-4);
+        assert!((s_units_to_dbm(9.0) + 73.0).abs() < 1e-4);
+        assert_eq!(dbm_to_reading(-73.0), SmeterReading::S(9));
+        assert_eq!(dbm_to_reading(-85.0), SmeterReading::S(7));
+        assert_eq!(dbm_to_reading(-53.0), SmeterReading::S9Plus(20));
+        assert_eq!(dbm_to_reading(-140.0), SmeterReading::S(0));
+        assert_eq!(format_s_meter(-63.0).as_str(), "S9+10");
+    }
+
+    #[test]
+    fn test_power_conversion() {
+        assert!((dbm_to_watts(30.0) - 1.0).abs() < 1e-3);
+        assert!((watts_to_dbm(5.0) - 36.99).abs() < 0.01);
+        assert_eq!(watts_to_dbm(0.0), -200.0);
+        // S9 is 50 µV into 50 ohms
+        assert!((dbm_to_microvolts(S9_DBM) - 50.0).abs() < 0.5);
+        assert!((microvolts_to_dbm(50.0) - S9_DBM).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_format_levels() {
+        assert_eq!(format_dbm(-73.2).as_str(), "-73 dBm");
+        assert_eq!(format_dbm(-0.2).as_str(), "0 dBm");
+        assert_eq!(format_watts(0.25).as_str(), "250 mW");
+        assert_eq!(format_watts(0.9996).as_str(), "1.0 W");
+        assert_eq!(format_watts(5.0).as_str(), "5.0 W");
+        assert_eq!(format_watts(9.97).as_str(), "10 W");
+        assert_eq!(format_watts(100.4).as_str(), "100 W");
+    }
+}
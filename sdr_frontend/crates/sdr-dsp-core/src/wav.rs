@@ -0,0 +1,281 @@
+//! WAV (RIFF PCM) Encoding and Decoding.
+//!
+//! Writes 16-bit PCM WAV data without allocating: the caller streams
+//! sample chunks through [`encode_pcm16`] and prepends a [`WavFormat::header`]
+//! once the total length is known. Mono is used for demodulated audio,
+//! stereo (I left, Q right) for raw IQ.
+//!
+//! For playback, [`parse_header`] locates the sample data of a 16-bit or
+//! 32-bit float WAV file and [`decode_samples`] converts it (or headerless
+//! raw s16/f32 captures) to floats.
+
+/// Length of the canonical PCM WAV header in bytes.
+pub const HEADER_LEN: usize = 44;
+
+/// Bytes per 16-bit sample.
+pub const BYTES_PER_SAMPLE: usize = 2;
+
+/// Largest data chunk a WAV file can describe (RIFF sizes are 32-bit).
+pub const MAX_DATA_LEN: usize = u32::MAX as usize - HEADER_LEN;
+
+/// Sample layout of a WAV file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WavFormat {
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u16,
+}
+
+impl WavFormat {
+    /// Single-channel audio.
+    #[must_use]
+    pub const fn mono(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: 1,
+        }
+    }
+
+    /// Interleaved I/Q as a stereo pair.
+    #[must_use]
+    pub const fn iq(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: 2,
+        }
+    }
+
+    /// Bytes per second of PCM data.
+    #[must_use]
+    pub const fn byte_rate(&self) -> u32 {
+        self.sample_rate * self.block_align() as u32
+    }
+
+    /// Bytes per frame (one sample of every channel).
+    #[must_use]
+    pub const fn block_align(&self) -> u16 {
+        self.channels * BYTES_PER_SAMPLE as u16
+    }
+
+    /// Duration in seconds of `data_len` bytes of PCM data.
+    #[must_use]
+    pub fn duration_secs(&self, data_len: usize) -> f32 {
+        if self.byte_rate() == 0 {
+            return 0.0;
+        }
+        data_len as f32 / self.byte_rate() as f32
+    }
+
+    /// Build the header for `data_len` bytes of PCM data.
+    ///
+    /// Lengths beyond [`MAX_DATA_LEN`] are clamped.
+    #[must_use]
+    pub fn header(&self, data_len: usize) -> [u8; HEADER_LEN] {
+        let data_len = data_len.min(MAX_DATA_LEN) as u32;
+        let mut header = [0u8; HEADER_LEN];
+        header[0..4].copy_from_slice(b"RIFF");
+        header[4..8].copy_from_slice(&(data_len + HEADER_LEN as u32 - 8).to_le_bytes());
+        header[8..12].copy_from_slice(b"WAVE");
+        header[12..16].copy_from_slice(b"fmt ");
+        header[16..20].copy_from_slice(&16u32.to_le_bytes());
+        // Format 1 = integer PCM
+        header[20..22].copy_from_slice(&1u16.to_le_bytes());
+        header[22..24].copy_from_slice(&self.channels.to_le_bytes());
+        header[24..28].copy_from_slice(&self.sample_rate.to_le_bytes());
+        header[28..32].copy_from_slice(&self.byte_rate().to_le_bytes());
+        header[32..34].copy_from_slice(&self.block_align().to_le_bytes());
+        header[34..36].copy_from_slice(&(BYTES_PER_SAMPLE as u16 * 8).to_le_bytes());
+        header[36..40].copy_from_slice(b"data");
+        header[40..44].copy_from_slice(&data_len.to_le_bytes());
+        header
+    }
+}
+
+/// Encoding of stored samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Signed 16-bit little-endian integers
+    Pcm16,
+    /// 32-bit little-endian IEEE floats
+    Float32,
+}
+
+impl SampleFormat {
+    /// Bytes per stored sample.
+    #[must_use]
+    pub const fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::Pcm16 => 2,
+            Self::Float32 => 4,
+        }
+    }
+}
+
+/// Sample data located in a WAV file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WavInfo {
+    /// Sample rate and channel count
+    pub format: WavFormat,
+    /// Encoding of the samples
+    pub sample_format: SampleFormat,
+    /// Byte offset of the sample data
+    pub data_offset: usize,
+    /// Length of the sample data in bytes (truncated to the file)
+    pub data_len: usize,
+}
+
+/// Read a little-endian u16 at `pos`.
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+/// Read a little-endian u32 at `pos`.
+fn read_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Parse a WAV header, walking the chunks to `fmt ` and `data`.
+///
+/// Accepts 16-bit integer PCM and 32-bit float data; other encodings,
+/// and files without a `data` chunk, return `None`.
+#[must_use]
+pub fn parse_header(bytes: &[u8]) -> Option<WavInfo> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut pos = 12;
+    let mut fmt = None;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = read_u32(bytes, pos + 4)? as usize;
+        let body = pos + 8;
+        if id == b"fmt " {
+            let tag = read_u16(bytes, body)?;
+            let channels = read_u16(bytes, body + 2)?;
+            let sample_rate = read_u32(bytes, body + 4)?;
+            let bits = read_u16(bytes, body + 14)?;
+            let sample_format = match (tag, bits) {
+                (1, 16) => SampleFormat::Pcm16,
+                (3, 32) => SampleFormat::Float32,
+                _ => return None,
+            };
+            fmt = Some((WavFormat { sample_rate, channels }, sample_format));
+        } else if id == b"data" {
+            let (format, sample_format) = fmt?;
+            return Some(WavInfo {
+                format,
+                sample_format,
+                data_offset: body,
+                data_len: len.min(bytes.len() - body),
+            });
+        }
+        // Chunks are padded to an even length
+        pos = body.saturating_add(len).saturating_add(len & 1);
+    }
+    None
+}
+
+/// Decode stored samples to floats in -1.0 to 1.0.
+///
+/// Returns the number of samples written, limited by `output` and by
+/// whole samples in `bytes`.
+pub fn decode_samples(bytes: &[u8], format: SampleFormat, output: &mut [f32]) -> usize {
+    let chunks = bytes.chunks_exact(format.bytes_per_sample());
+    let mut written = 0;
+    for (chunk, out) in chunks.zip(output.iter_mut()) {
+        *out = match format {
+            SampleFormat::Pcm16 => f32::from(i16::from_le_bytes([chunk[0], chunk[1]])) / 32768.0,
+            SampleFormat::Float32 => f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+        };
+        written += 1;
+    }
+    written
+}
+
+/// Convert a sample in -1.0 to 1.0 to 16-bit PCM (clipping out of range).
+#[must_use]
+pub fn to_pcm16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Encode samples as little-endian 16-bit PCM.
+///
+/// Returns the number of bytes written, which stops short when `output`
+/// is full.
+pub fn encode_pcm16(samples: &[f32], output: &mut [u8]) -> usize {
+    let mut written = 0;
+    for (sample, out) in samples.iter().zip(output.chunks_exact_mut(BYTES_PER_SAMPLE)) {
+        out.copy_from_slice(&to_pcm16(*sample).to_le_bytes());
+        written += BYTES_PER_SAMPLE;
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_header() {
+        let format = WavFormat::mono(48000);
+        let header = format.header(96000);
+        assert_eq!(&header[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(header[4..8].try_into().unwrap()), 96036);
+        assert_eq!(&header[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes([header[22], header[23]]), 1);
+        assert_eq!(u32::from_le_bytes(header[28..32].try_into().unwrap()), 96000);
+        assert_eq!(u32::from_le_bytes(header[40..44].try_into().unwrap()), 96000);
+        assert!((format.duration_secs(96000) - 1.0).abs() < f32::EPSILON);
+
+        let iq = WavFormat::iq(48000);
+        assert_eq!(iq.block_align(), 4);
+        assert_eq!(iq.byte_rate(), 192_000);
+    }
+
+    #[test]
+    fn test_wav_parse_and_decode() {
+        let format = WavFormat::iq(8000);
+        let mut file = [0u8; HEADER_LEN + 8];
+        file[..HEADER_LEN].copy_from_slice(&format.header(8));
+        encode_pcm16(&[0.5, -0.5, 0.25, 0.0], &mut file[HEADER_LEN..]);
+
+        let info = parse_header(&file).unwrap();
+        assert_eq!(info.format, format);
+        assert_eq!(info.sample_format, SampleFormat::Pcm16);
+        assert_eq!(info.data_offset, HEADER_LEN);
+        assert_eq!(info.data_len, 8);
+
+        let mut samples = [0.0; 8];
+        let data = &file[info.data_offset..info.data_offset + info.data_len];
+        assert_eq!(decode_samples(data, info.sample_format, &mut samples), 4);
+        assert!((samples[0] - 0.5).abs() < 1e-3);
+        assert!((samples[1] + 0.5).abs() < 1e-3);
+
+        // Raw float capture
+        let raw: [u8; 8] = [0, 0, 0, 0x3F, 0, 0, 0x80, 0xBF];
+        assert_eq!(decode_samples(&raw, SampleFormat::Float32, &mut samples), 2);
+        assert_eq!(&samples[..2], &[0.5, -1.0]);
+
+        // Truncated data chunk and unsupported encodings
+        assert_eq!(parse_header(&file[..HEADER_LEN + 3]).unwrap().data_len, 3);
+        let mut bad = file;
+        bad[34] = 8;
+        assert_eq!(parse_header(&bad), None);
+        assert_eq!(parse_header(b"RIFF"), None);
+    }
+
+    #[test]
+    fn test_pcm16_encoding() {
+        assert_eq!(to_pcm16(0.0), 0);
+        assert_eq!(to_pcm16(1.0), i16::MAX);
+        assert_eq!(to_pcm16(-2.0), -i16::MAX);
+
+        let mut out = [0u8; 5];
+        let written = encode_pcm16(&[0.5, -0.5, 1.0], &mut out);
+        assert_eq!(written, 4);
+        assert_eq!(i16::from_le_bytes([out[0], out[1]]), 16383);
+        assert_eq!(i16::from_le_bytes([out[2], out[3]]), -16383);
+    }
+}
@@ -0,0 +1,91 @@
+//! Versioned API surface for the WASM bindings.
+//!
+//! The stable codes and capability bits come from [`sdr_dsp_api`], which
+//! the UI builds against too; this module adds the exported calls that
+//! report them.
+
+pub use sdr_dsp_api::{
+    capability, extended_capability, DemodMode, Parameter, WaterfallScale, API_VERSION_MAJOR,
+    API_VERSION_MINOR,
+};
+use wasm_bindgen::prelude::*;
+
+/// Get the API version packed as `major << 16 | minor`.
+#[wasm_bindgen]
+#[must_use]
+pub fn get_api_version() -> u32 {
+    (u32::from(API_VERSION_MAJOR) << 16) | u32::from(API_VERSION_MINOR)
+}
+
+/// Get the capability bit mask supported by this build.
+#[wasm_bindgen]
+#[must_use]
+pub fn get_capabilities() -> u32 {
+    capability::DEMOD_SSB
+        | capability::DEMOD_CW
+        | capability::DEMOD_AM
+        | capability::DEMOD_FM
+        | capability::SPECTRUM
+        | capability::SMETER
+        | capability::AGC
+        | capability::PARAMETERS
+        | capability::AGC_ADVANCED
+        | capability::SMETER_DBM
+        | capability::SPECTRUM_DBM
+        | capability::IQ_FILE
+        | capability::PSK31_BANK
+        | capability::SIGNAL_QUALITY
+        | capability::AFC_CONTROL
+        | capability::SSTV
+        | capability::APRS
+        | capability::JS8
+        | capability::HELL
+        | capability::CW_SKIMMER
+        | capability::FFT_FILTER
+        | capability::CONFIGURABLE_BUFFERS
+        | capability::TYPED_ARRAYS
+        | capability::NOISE_REDUCTION
+        | capability::SQUELCH
+        | capability::CONFIG_OBJECTS
+        | capability::RECEIVER_SLICES
+        | capability::TX_DSP
+        | capability::FINE_TUNE
+        | capability::PASSBAND_TUNING
+        | capability::DUAL_NOTCH
+        | capability::NOISE_BLANKER
+}
+
+/// Get the extended capability bit mask supported by this build.
+#[wasm_bindgen]
+#[must_use]
+pub fn get_extended_capabilities() -> u32 {
+    extended_capability::SPECTRAL_NR
+        | extended_capability::EQUALIZER
+        | extended_capability::SNR_METER
+        | extended_capability::IQ_BALANCE
+        | extended_capability::SPECTRUM_DC_NOTCH
+        | extended_capability::WATERFALL_LEVELS
+        | extended_capability::SPECTRUM_PEAKS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_capabilities() {
+        assert_ne!(get_extended_capabilities() & extended_capability::SPECTRAL_NR, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::EQUALIZER, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::SNR_METER, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::IQ_BALANCE, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::SPECTRUM_DC_NOTCH, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::WATERFALL_LEVELS, 0);
+        assert_ne!(get_extended_capabilities() & extended_capability::SPECTRUM_PEAKS, 0);
+    }
+
+    #[test]
+    fn test_api_version_packing() {
+        assert_eq!(get_api_version() >> 16, u32::from(API_VERSION_MAJOR));
+        assert_eq!(get_api_version() & 0xFFFF, u32::from(API_VERSION_MINOR));
+    }
+}
@@ -0,0 +1,234 @@
+//! Serialized configuration and status for [`DspProcessor`](crate::DspProcessor).
+//!
+//! Lets the worklet apply a whole settings object posted from the UI in
+//! one call, and report everything the UI meters need in one object,
+//! instead of a round trip per setter and getter. Field names are
+//! camelCase on the JS side; every field is optional, and omitted fields
+//! keep their current value:
+//!
+//! ```js
+//! dsp.apply_config({
+//!     mode: DemodMode.Usb,
+//!     filterBandwidth: 2400,
+//!     agc: { decayMs: 500, hangMs: 200 },
+//!     nb: { enabled: true, threshold: 8, width: 100 },
+//!     iq: { gainDb: 0.4, phaseDeg: -1.2, adaptive: false },
+//!     nr: { level: 4, spectral: 5 },
+//!     squelch: { enabled: true, thresholdDbm: -110 },
+//!     notches: [{ enabled: true, frequency: 800 }, { enabled: false }],
+//!     eq: { enabled: true, gains: [-3, 0, 0, 2, 1] },
+//!     spectrum: { size: 256, averaging: 4 },
+//!     waterfall: { refLevelDb: -50, rangeDb: 70, scale: WaterfallScale.Log },
+//! });
+//! const { smeterDbm, snrDb, squelchOpen, cpuLoad } = dsp.get_status();
+//! ```
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Settings applied by [`DspProcessor::apply_config`](crate::DspProcessor::apply_config).
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct DspConfig {
+    /// [`DemodMode`](crate::DemodMode) code (also resets the filter bandwidth).
+    pub mode: Option<u8>,
+    /// Mixer frequency offset in Hz.
+    pub frequency_offset: Option<f32>,
+    /// Fine tuning (RIT) offset in Hz, on top of the frequency offset.
+    pub fine_tune: Option<f32>,
+    /// Audio filter bandwidth in Hz.
+    pub filter_bandwidth: Option<f32>,
+    /// IF shift of the audio passband in Hz.
+    pub if_shift: Option<f32>,
+    /// AGC settings.
+    pub agc: Option<AgcSettings>,
+    /// IQ noise blanker settings.
+    pub nb: Option<NbSettings>,
+    /// IQ balance settings (normally the calibration stored for the band).
+    pub iq: Option<IqSettings>,
+    /// Noise reduction settings.
+    pub nr: Option<NrSettings>,
+    /// Squelch settings.
+    pub squelch: Option<SquelchSettings>,
+    /// Manual notch settings, first notch first.
+    pub notches: Option<Vec<NotchSettings>>,
+    /// Graphic equalizer settings.
+    pub eq: Option<EqSettings>,
+    /// Spectrum analyzer settings.
+    pub spectrum: Option<SpectrumSettings>,
+    /// Waterfall level settings.
+    pub waterfall: Option<WaterfallSettings>,
+}
+
+/// AGC settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct AgcSettings {
+    /// Attack time in milliseconds.
+    pub attack_ms: Option<f32>,
+    /// Decay time in milliseconds.
+    pub decay_ms: Option<f32>,
+    /// Hang time in milliseconds.
+    pub hang_ms: Option<f32>,
+    /// Hang threshold in dBFS.
+    pub hang_threshold_db: Option<f32>,
+    /// Output slope in dB per 10 dB above the knee.
+    pub slope_db: Option<f32>,
+    /// Maximum gain in dB.
+    pub max_gain_db: Option<f32>,
+    /// Dual-rate detector.
+    pub dual_rate: Option<bool>,
+}
+
+/// IQ noise blanker settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct NbSettings {
+    /// Blanker on.
+    pub enabled: Option<bool>,
+    /// Threshold as a magnitude ratio over the average signal.
+    pub threshold: Option<f32>,
+    /// Blanking width in microseconds.
+    pub width: Option<f32>,
+}
+
+/// IQ balance settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct IqSettings {
+    /// Q gain correction in dB.
+    pub gain_db: Option<f32>,
+    /// Phase correction in degrees.
+    pub phase_deg: Option<f32>,
+    /// Adapt the correction to the signal.
+    pub adaptive: Option<bool>,
+}
+
+/// Noise reduction settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct NrSettings {
+    /// Level from 0 (off) to [`MAX_NR_LEVEL`](sdr_dsp_core::nr::MAX_NR_LEVEL).
+    pub level: Option<u8>,
+    /// Spectral subtraction strength from 0 (off) to
+    /// [`MAX_SPECTRAL_STRENGTH`](sdr_dsp_core::nr::MAX_SPECTRAL_STRENGTH).
+    pub spectral: Option<u8>,
+}
+
+/// Squelch settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct SquelchSettings {
+    /// Mute audio below the threshold.
+    pub enabled: Option<bool>,
+    /// Opening level in dBm (calibrated S-meter).
+    pub threshold_dbm: Option<f32>,
+}
+
+/// Manual notch settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct NotchSettings {
+    /// Notch on.
+    pub enabled: Option<bool>,
+    /// Notch frequency in Hz.
+    pub frequency: Option<f32>,
+    /// Notch width in Hz.
+    pub width: Option<f32>,
+}
+
+/// Graphic equalizer settings.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct EqSettings {
+    /// Equalizer on.
+    pub enabled: Option<bool>,
+    /// Band gains in dB, lowest band first; all
+    /// [`EQ_BANDS`](crate::equalizer::EQ_BANDS) must be given.
+    pub gains: Option<Vec<f32>>,
+}
+
+/// Spectrum analyzer settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct SpectrumSettings {
+    /// FFT size, a power of two from
+    /// [`MIN_SPECTRUM_SIZE`](crate::MIN_SPECTRUM_SIZE) up.
+    pub size: Option<usize>,
+    /// Frames averaged (1 = none).
+    pub averaging: Option<u8>,
+    /// dBFS to dBm gain in dB.
+    pub gain_db: Option<f32>,
+    /// DC notch width in Hz either side of DC (0 = off).
+    pub dc_notch_hz: Option<f32>,
+    /// Most peaks listed per frame (0 = off).
+    pub max_peaks: Option<usize>,
+    /// Peak threshold above the noise floor in dB.
+    pub peak_threshold_db: Option<f32>,
+}
+
+/// Waterfall level settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct WaterfallSettings {
+    /// Reference (brightest) level in dBm.
+    pub ref_level_db: Option<f32>,
+    /// Span from darkest to brightest in dB.
+    pub range_db: Option<f32>,
+    /// [`WaterfallScale`](crate::WaterfallScale) code.
+    pub scale: Option<u8>,
+    /// Levels follow the noise floor and strongest signal.
+    pub auto_level: Option<bool>,
+}
+
+/// Snapshot returned by [`DspProcessor::get_status`](crate::DspProcessor::get_status).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DspStatus {
+    /// S-meter value (1.0 = S9).
+    pub smeter: f32,
+    /// Calibrated signal level in dBm.
+    pub smeter_dbm: f32,
+    /// Current AGC gain in dB.
+    pub agc_gain_db: f32,
+    /// Audio SNR in the passband in dB (NaN until measured).
+    pub snr_db: f32,
+    /// Estimated image rejection in dB (NaN until measured).
+    pub image_rejection_db: f32,
+    /// Whether the squelch is passing audio (always true when disabled).
+    pub squelch_open: bool,
+    /// Processing time as a fraction of real time, averaged.
+    pub cpu_load: f32,
+    /// Blocks processed.
+    pub frame_count: u32,
+}
+
+/// Reason a [`DspConfig`] was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Unknown [`DemodMode`](crate::DemodMode) code.
+    UnknownMode(u8),
+    /// Spectrum size not a power of two in the supported range.
+    SpectrumSize(usize),
+    /// More notch settings than [`NOTCH_COUNT`](crate::NOTCH_COUNT).
+    NotchCount(usize),
+    /// Equalizer gains not one per [`EQ_BANDS`](crate::equalizer::EQ_BANDS).
+    EqBandCount(usize),
+    /// Unknown [`WaterfallScale`](crate::WaterfallScale) code.
+    UnknownWaterfallScale(u8),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownMode(code) => write!(f, "unknown demodulation mode {code}"),
+            Self::SpectrumSize(size) => write!(f, "unsupported spectrum size {size}"),
+            Self::NotchCount(count) => write!(f, "{count} notches configured"),
+            Self::EqBandCount(count) => write!(f, "{count} equalizer gains configured"),
+            Self::UnknownWaterfallScale(code) => write!(f, "unknown waterfall scale {code}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
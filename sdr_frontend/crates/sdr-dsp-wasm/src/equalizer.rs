@@ -0,0 +1,138 @@
+//! Five-band graphic equalizer.
+//!
+//! [`DspProcessor`](crate::DspProcessor) runs one on the leveled audio
+//! after the AGC, so a boost does not pump the gain, and
+//! [`TxProcessor`](crate::TxProcessor) runs one after the compressor to
+//! shape the microphone audio for the band. Each band is a peaking
+//! biquad at a fixed center; changing mode loads that mode's factory
+//! curve, matching the firmware equalizer.
+
+use sdr_dsp_core::{Biquad, BiquadCoeffs};
+
+use crate::DemodMode;
+
+/// Number of equalizer bands.
+pub const EQ_BANDS: usize = 5;
+
+/// Band center frequencies in Hz.
+pub const EQ_BAND_HZ: [f32; EQ_BANDS] = [200.0, 500.0, 1000.0, 2000.0, 3000.0];
+
+/// Largest band gain in dB (either direction).
+pub const MAX_EQ_GAIN_DB: f32 = 12.0;
+
+/// Band Q (about 1.4 octaves, so neighbouring bands overlap smoothly).
+const EQ_Q: f32 = 1.0;
+
+/// Factory receive curve for a mode, in dB per band.
+pub(crate) fn rx_default_curve(mode: DemodMode) -> [f32; EQ_BANDS] {
+    match mode {
+        DemodMode::Lsb | DemodMode::Usb => [-3.0, 0.0, 0.0, 2.0, 1.0],
+        DemodMode::Cw => [-6.0, -2.0, 0.0, -4.0, -8.0],
+        DemodMode::Am => [2.0, 1.0, 0.0, 0.0, -2.0],
+        DemodMode::Fm => [0.0, 0.0, 0.0, -1.0, -3.0],
+    }
+}
+
+/// Factory transmit curve for SSB, in dB per band.
+pub(crate) const TX_DEFAULT_CURVE: [f32; EQ_BANDS] = [-6.0, -2.0, 0.0, 3.0, 4.0];
+
+/// Graphic equalizer and its settings.
+pub(crate) struct GraphicEq {
+    sample_rate: f32,
+    bands: [Biquad; EQ_BANDS],
+    gains_db: [f32; EQ_BANDS],
+    enabled: bool,
+}
+
+impl GraphicEq {
+    /// Create an equalizer with a flat curve, switched off.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            bands: core::array::from_fn(|_| Biquad::new(BiquadCoeffs::unity())),
+            gains_db: [0.0; EQ_BANDS],
+            enabled: false,
+        }
+    }
+
+    /// Equalize a block of audio in place (nothing when off or flat).
+    pub fn process_block(&mut self, audio: &mut [f32]) {
+        if !self.enabled || self.gains_db.iter().all(|&gain| gain == 0.0) {
+            return;
+        }
+        for band in &mut self.bands {
+            band.process_block(audio);
+        }
+    }
+
+    /// Set one band's gain in dB, clamped to +/-[`MAX_EQ_GAIN_DB`].
+    ///
+    /// Keeps the filter state so a drag does not click. Returns `false`
+    /// and changes nothing if there is no such band.
+    pub fn set_gain(&mut self, band: usize, gain_db: f32) -> bool {
+        if band >= EQ_BANDS || gain_db.is_nan() {
+            return false;
+        }
+        self.gains_db[band] = gain_db.clamp(-MAX_EQ_GAIN_DB, MAX_EQ_GAIN_DB);
+        self.bands[band].set_coeffs(peaking_coeffs(
+            self.sample_rate,
+            EQ_BAND_HZ[band],
+            self.gains_db[band],
+        ));
+        true
+    }
+
+    /// Get one band's gain in dB (NaN if there is no such band).
+    pub fn gain(&self, band: usize) -> f32 {
+        self.gains_db.get(band).copied().unwrap_or(f32::NAN)
+    }
+
+    /// Set every band's gain in dB, lowest band first.
+    pub fn set_gains(&mut self, gains_db: &[f32; EQ_BANDS]) {
+        for (band, &gain) in gains_db.iter().enumerate() {
+            self.set_gain(band, gain);
+        }
+    }
+
+    /// Switch the equalizer on or off, clearing its state when switched on.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Check if the equalizer is on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Clear the filter state.
+    pub fn reset(&mut self) {
+        for band in &mut self.bands {
+            band.reset();
+        }
+    }
+}
+
+/// Design peaking-EQ coefficients with `std` trigonometry.
+///
+/// 0 dB gives a unity filter. Like the notches, this avoids the
+/// `no_std` approximations in [`BiquadCoeffs`].
+fn peaking_coeffs(sample_rate: f32, frequency_hz: f32, gain_db: f32) -> BiquadCoeffs {
+    if gain_db == 0.0 {
+        return BiquadCoeffs::unity();
+    }
+    let amplitude = 10.0_f32.powf(gain_db / 40.0);
+    let omega = core::f32::consts::TAU * frequency_hz / sample_rate;
+    let alpha = omega.sin() / (2.0 * EQ_Q);
+    let a0 = 1.0 + alpha / amplitude;
+    let b1 = -2.0 * omega.cos() / a0;
+    BiquadCoeffs {
+        b0: (1.0 + alpha * amplitude) / a0,
+        b1,
+        b2: (1.0 - alpha * amplitude) / a0,
+        a1: b1,
+        a2: (1.0 - alpha / amplitude) / a0,
+    }
+}
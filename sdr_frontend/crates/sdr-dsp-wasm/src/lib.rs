@@ -1,44 +1,219 @@
 //! WASM bindings for SDR DSP processing.
 //!
 //! This crate provides WebAssembly bindings for the DSP modules,
-//! designed to run in an AudioWorklet for real-time audio processing.
+//! designed to run in an AudioWorklet for real-time audio processing:
+//! [`DspProcessor`] for receive and [`TxProcessor`] for transmit.
+//!
+//! The JS side should check [`get_api_version`], [`get_capabilities`] and
+//! [`get_extended_capabilities`] before relying on optional features, and use the numbered
+//! [`DemodMode`] and [`Parameter`] codes rather than ad-hoc integers.
+
+pub mod api;
+pub mod config;
+pub mod equalizer;
+pub mod notch;
+pub mod slice;
+pub mod tx;
 
-use sdr_dsp_core::{Agc, AgcConfig, Biquad, DcBlocker, FftSpectrum, IqSample, Nco, SMeter};
+pub use api::{
+    get_api_version, get_capabilities, get_extended_capabilities, DemodMode, Parameter,
+    WaterfallScale,
+};
+pub use config::{ConfigError, DspConfig, DspStatus};
+pub use equalizer::EQ_BANDS;
+pub use notch::NOTCH_COUNT;
+pub use slice::MAX_SLICES;
+pub use tx::TxProcessor;
+
+use js_sys::Float32Array;
+use sdr_dsp_core::convolve::bandpass_taps;
+use sdr_dsp_core::resample::IqResampler;
+use sdr_dsp_core::spectrum::{
+    find_peaks, SpectrumPeak, DEFAULT_PEAK_THRESHOLD_DB, MAX_BINS, MAX_PEAKS,
+};
+use sdr_dsp_core::wav::to_pcm16;
+use sdr_dsp_core::{
+    demod, Agc, AgcConfig, DcBlockerIq, DcNotch, DisplayRange, FftConvolver, FftSpectrum, IqBalance, IqBlanker,
+    IqCalibration, IqSample, Nco, NoiseReducer, SMeter, SmeterCalibration, SnrMeter,
+    SpectralSubtractor, SpectrumAverager, Squelch,
+};
+use sdr_mode_aprs::AprsDecoder;
+use sdr_mode_cw::{CwSkimmer, DEFAULT_HIGH_HZ as CW_HIGH_HZ, DEFAULT_LOW_HZ as CW_LOW_HZ};
+use sdr_mode_hell::{HellDecoder, COLUMN_HEIGHT as HELL_COLUMN_HEIGHT};
+use sdr_mode_js8::{Js8Decoder, FRAME_BYTES as JS8_FRAME_BYTES};
+use sdr_mode_psk31::{DecoderBank, Psk31DecoderConfig};
+use sdr_mode_sstv::{SstvDecoder, SstvEvent, IMAGE_WIDTH as SSTV_WIDTH};
+use equalizer::GraphicEq;
+use notch::ManualNotch;
+use slice::ReceiverSlice;
 use wasm_bindgen::prelude::*;
 
-/// Audio buffer size (matches AudioWorklet quantum).
+/// Default audio buffer size (matches AudioWorklet quantum).
 pub const BUFFER_SIZE: usize = 128;
 
-/// Spectrum FFT size.
+/// Largest buffer size accepted by [`DspProcessor::set_buffer_size`].
+pub const MAX_BUFFER_SIZE: usize = 8192;
+
+/// Default spectrum FFT size.
 pub const SPECTRUM_SIZE: usize = 512;
 
+/// Smallest spectrum FFT size accepted by [`DspProcessor::apply_config`].
+pub const MIN_SPECTRUM_SIZE: usize = 64;
+
+/// Default waterfall reference (brightest) level in dBm.
+pub const DEFAULT_WATERFALL_REF_DBM: f32 = -40.0;
+
+/// Default waterfall span in dB.
+pub const DEFAULT_WATERFALL_RANGE_DB: f32 = 80.0;
+
+/// Default number of spectrum peaks listed per frame.
+pub const DEFAULT_MAX_PEAKS: usize = 8;
+
+/// Values per spectrum peak: frequency (Hz), level (dBm), width (Hz).
+pub const PEAK_FIELDS: usize = 3;
+
+/// Audio filter length (runs by FFT convolution).
+pub const AUDIO_FILTER_TAPS: usize = 511;
+
+/// Default raw IQ recording limit in sample pairs (one minute at 48 kHz).
+pub const DEFAULT_IQ_RECORD_LIMIT: usize = 48_000 * 60;
+
+/// Decoded PSK31 characters held until read, as (channel, char) pairs.
+pub const PSK_DECODED_CAPACITY: usize = 256;
+
+/// Decoded SSTV scanlines held until read.
+pub const SSTV_LINE_CAPACITY: usize = 4;
+
+/// Decoded AX.25 frames held until read.
+pub const APRS_FRAME_CAPACITY: usize = 8;
+
+/// Decoded JS8 frames held until read.
+pub const JS8_FRAME_CAPACITY: usize = 4;
+
+/// Default JS8 audio frequency (lowest tone) in Hz.
+pub const JS8_DEFAULT_FREQUENCY_HZ: f32 = 1500.0;
+
+/// Feld Hell columns held until read (about half a character).
+pub const HELL_COLUMN_CAPACITY: usize = 4;
+
+/// Skimmed CW characters held until read, as (signal, char) pairs.
+pub const CW_DECODED_CAPACITY: usize = 256;
+
+/// Values per skimmed CW signal: id, frequency (Hz), WPM, SNR (dB).
+pub const CW_SIGNAL_FIELDS: usize = 4;
+
+/// Largest fine tuning offset either side of the frequency offset, in Hz.
+pub const MAX_FINE_TUNE_HZ: f32 = 9999.0;
+
+/// Default fine tuning step in Hz.
+pub const DEFAULT_FINE_TUNE_STEP_HZ: f32 = 10.0;
+
+/// Largest IF shift either way in Hz.
+pub const MAX_IF_SHIFT_HZ: f32 = 1200.0;
+
+/// Narrowest audio passband in Hz, however far it is shifted.
+pub const MIN_PASSBAND_WIDTH_HZ: f32 = 50.0;
+
+/// Smoothing of the CPU load estimate per block.
+const CPU_LOAD_SMOOTHING: f32 = 0.05;
+
 /// DSP processor for AudioWorklet integration.
 ///
 /// Handles IQ demodulation, filtering, AGC, and spectrum analysis.
 #[wasm_bindgen]
 pub struct DspProcessor {
-    // Input/output buffers (interleaved I,Q for input)
-    input_buffer: [f32; BUFFER_SIZE * 2],
-    output_buffer: [f32; BUFFER_SIZE],
-    spectrum_buffer: [f32; SPECTRUM_SIZE],
+    // Input/output buffers (interleaved I,Q for input), sized by
+    // buffer_size and the input rate, and audio produced by the last call
+    buffer_size: usize,
+    input_buffer: Vec<f32>,
+    output_buffer: Vec<f32>,
+    output_len: usize,
+    spectrum_buffer: Vec<f32>,
+    waterfall_buffer: Vec<f32>,
+    spectrum_peaks: Vec<f32>,
+
+    // Scratch for the input block and its resampled copy
+    iq_block: Vec<IqSample>,
+    resampled: Vec<IqSample>,
 
     // DSP components
-    dc_blocker_i: DcBlocker,
-    dc_blocker_q: DcBlocker,
+    dc_blocker: DcBlockerIq,
+    iq_balance: IqBalance,
+    blanker: IqBlanker,
     nco: Nco,
-    audio_filter: Biquad,
+    audio_filter: Box<FftConvolver>,
     agc: Agc,
+    nr: NoiseReducer,
+    spectral_nr: SpectralSubtractor,
+    notches: [ManualNotch; NOTCH_COUNT],
+    equalizer: GraphicEq,
+    squelch: Squelch,
     smeter: SMeter,
+    snr: SnrMeter,
     spectrum: FftSpectrum,
+    spectrum_averager: SpectrumAverager,
 
     // Configuration
     sample_rate: f32,
-    mode: u8,         // 0=LSB, 1=USB, 2=CW, 3=AM, 4=FM
+    mode: DemodMode,
     freq_offset: f32, // Audio frequency offset in Hz
+    fine_tune: f32,   // RIT-style offset on top of freq_offset in Hz
+    fine_tune_step: f32,
+    filter_bandwidth: f32,
+    if_shift: f32, // Audio passband shift in Hz
+    spectrum_dc_notch_hz: f32, // Spectrum DC notch either side of DC in Hz
+    waterfall_range: DisplayRange, // Set waterfall levels
+    waterfall_levels: DisplayRange, // Levels in use (followed the band when auto)
+    waterfall_scale: WaterfallScale,
+    waterfall_auto: bool,
+    max_peaks: usize,
+    peak_threshold_db: f32,
+    agc_config: AgcConfig,
+
+    // Input resampling and raw IQ recording
+    input_rate: f32,
+    input_resampler: IqResampler,
+    iq_record: Vec<f32>,
+    iq_record_limit: usize,
+    iq_recording: bool,
+
+    // Parallel PSK31 decoders and their output, as (channel, char code) pairs
+    psk_bank: DecoderBank,
+    psk_decoded: Vec<u32>,
+
+    // SSTV decoder (when enabled), its image mode and scanlines not yet read
+    sstv: Option<Box<SstvDecoder>>,
+    sstv_vis: i32,
+    sstv_line_numbers: Vec<u32>,
+    sstv_pixels: Vec<u8>,
+
+    // APRS packet decoder (when enabled) and frames not yet read
+    aprs: Option<Box<AprsDecoder>>,
+    aprs_frame_lengths: Vec<u32>,
+    aprs_frames: Vec<u8>,
+
+    // JS8 frame decoder (when enabled), its audio frequency and frames not yet read
+    js8: Option<Box<Js8Decoder>>,
+    js8_frequency: f32,
+    js8_frames: Vec<u8>,
+
+    // Feld Hell decoder (when enabled), its tone frequency and columns not yet read
+    hell: Option<Box<HellDecoder>>,
+    hell_frequency: f32,
+    hell_columns: Vec<u8>,
+
+    // CW skimmer (when enabled), characters not yet read and the signal table
+    cw_skimmer: Option<Box<CwSkimmer>>,
+    cw_decoded: Vec<u32>,
+    cw_signals: Vec<f32>,
+
+    // Receiver slices by id
+    slices: Vec<Option<ReceiverSlice>>,
 
     // State
     frame_count: u32,
     smeter_value: f32,
+    cpu_load: f32,
 }
 
 #[wasm_bindgen]
@@ -51,171 +226,825 @@ impl DspProcessor {
 
         let agc_config = AgcConfig::medium();
 
-        Self {
-            input_buffer: [0.0; BUFFER_SIZE * 2],
-            output_buffer: [0.0; BUFFER_SIZE],
-            spectrum_buffer: [0.0; SPECTRUM_SIZE],
-            dc_blocker_i: DcBlocker::default(),
-            dc_blocker_q: DcBlocker::default(),
+        let mut processor = Self {
+            buffer_size: BUFFER_SIZE,
+            input_buffer: Vec::new(),
+            output_buffer: Vec::new(),
+            output_len: 0,
+            spectrum_buffer: vec![0.0; SPECTRUM_SIZE],
+            waterfall_buffer: vec![0.0; SPECTRUM_SIZE / 2],
+            spectrum_peaks: Vec::with_capacity(MAX_PEAKS * PEAK_FIELDS),
+            iq_block: Vec::new(),
+            resampled: Vec::new(),
+            dc_blocker: DcBlockerIq::default(),
+            iq_balance: IqBalance::new(),
+            blanker: IqBlanker::new(sample_rate),
             nco: Nco::new(sample_rate, 0.0),
-            audio_filter: Biquad::lowpass(sample_rate, 2700.0, 0.707),
+            audio_filter: Box::new(
+                FftConvolver::new(&audio_filter_taps(sample_rate, 2700.0))
+                    .expect("AUDIO_FILTER_TAPS within MAX_TAPS"),
+            ),
             agc: Agc::new(sample_rate, agc_config),
+            nr: NoiseReducer::default(),
+            spectral_nr: SpectralSubtractor::new(sample_rate, 0),
+            notches: core::array::from_fn(|_| ManualNotch::new(sample_rate)),
+            equalizer: GraphicEq::new(sample_rate),
+            squelch: Squelch::default(),
             smeter: SMeter::new(sample_rate, 100.0),
+            snr: SnrMeter::new(sample_rate),
             spectrum: FftSpectrum::new(SPECTRUM_SIZE),
+            spectrum_averager: SpectrumAverager::default(),
             sample_rate,
-            mode: 1, // USB default
+            mode: DemodMode::Usb,
             freq_offset: 1500.0,
+            fine_tune: 0.0,
+            fine_tune_step: DEFAULT_FINE_TUNE_STEP_HZ,
+            filter_bandwidth: 2700.0,
+            if_shift: 0.0,
+            spectrum_dc_notch_hz: 0.0,
+            waterfall_range: DisplayRange::new(
+                DEFAULT_WATERFALL_REF_DBM,
+                DEFAULT_WATERFALL_RANGE_DB,
+            ),
+            waterfall_levels: DisplayRange::new(
+                DEFAULT_WATERFALL_REF_DBM,
+                DEFAULT_WATERFALL_RANGE_DB,
+            ),
+            waterfall_scale: WaterfallScale::default(),
+            waterfall_auto: false,
+            max_peaks: DEFAULT_MAX_PEAKS,
+            peak_threshold_db: DEFAULT_PEAK_THRESHOLD_DB,
+            agc_config,
+            input_rate: sample_rate,
+            input_resampler: IqResampler::new(sample_rate, sample_rate),
+            iq_record: Vec::new(),
+            iq_record_limit: DEFAULT_IQ_RECORD_LIMIT,
+            iq_recording: false,
+            psk_bank: DecoderBank::new(Psk31DecoderConfig {
+                sample_rate,
+                ..Psk31DecoderConfig::default()
+            }),
+            psk_decoded: Vec::with_capacity(PSK_DECODED_CAPACITY * 2),
+            sstv: None,
+            sstv_vis: -1,
+            sstv_line_numbers: Vec::with_capacity(SSTV_LINE_CAPACITY),
+            sstv_pixels: Vec::with_capacity(SSTV_LINE_CAPACITY * SSTV_WIDTH * 3),
+            aprs: None,
+            aprs_frame_lengths: Vec::with_capacity(APRS_FRAME_CAPACITY),
+            aprs_frames: Vec::new(),
+            js8: None,
+            js8_frequency: JS8_DEFAULT_FREQUENCY_HZ,
+            js8_frames: Vec::with_capacity(JS8_FRAME_CAPACITY * JS8_FRAME_BYTES),
+            hell: None,
+            hell_frequency: sdr_mode_hell::DEFAULT_TONE_HZ,
+            hell_columns: Vec::with_capacity(HELL_COLUMN_CAPACITY * HELL_COLUMN_HEIGHT),
+            cw_skimmer: None,
+            cw_decoded: Vec::with_capacity(CW_DECODED_CAPACITY * 2),
+            cw_signals: Vec::new(),
+            slices: (0..MAX_SLICES).map(|_| None).collect(),
             frame_count: 0,
             smeter_value: 0.0,
+            cpu_load: 0.0,
+        };
+        processor.resize_buffers();
+        let (low, high) = processor.passband();
+        processor.snr.set_passband(low, high);
+        processor.equalizer.set_gains(&equalizer::rx_default_curve(processor.mode));
+        processor
+    }
+
+    /// Set the number of I/Q pairs the input buffer holds (1 to [`MAX_BUFFER_SIZE`]).
+    ///
+    /// Reallocates the input and output buffers, so their pointers must
+    /// be fetched again. Returns `false` and changes nothing if the size
+    /// is out of range.
+    #[wasm_bindgen]
+    pub fn set_buffer_size(&mut self, size: usize) -> bool {
+        if !(1..=MAX_BUFFER_SIZE).contains(&size) {
+            return false;
         }
+        self.buffer_size = size;
+        self.resize_buffers();
+        true
+    }
+
+    /// Get the number of I/Q pairs the input buffer holds.
+    #[wasm_bindgen]
+    pub fn get_buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Get the spectrum FFT size (the spectrum buffer holds half as many bins).
+    #[wasm_bindgen]
+    pub fn get_spectrum_size(&self) -> usize {
+        self.spectrum_buffer.len()
+    }
+
+    /// Get the number of audio samples the last [`Self::process`] wrote.
+    ///
+    /// Equal to the input count unless the input rate differs from the
+    /// processor rate.
+    #[wasm_bindgen]
+    pub fn get_output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Get pointer to waterfall buffer for WASM memory access.
+    ///
+    /// For callers using the raw exports. The pointer is into WASM memory,
+    /// so read it again after any call that can allocate; with the
+    /// wasm-bindgen glue use [`Self::read_waterfall`] or
+    /// [`Self::waterfall_view`].
+    #[wasm_bindgen]
+    pub fn get_waterfall_buffer_ptr(&self) -> *const f32 {
+        self.waterfall_buffer.as_ptr()
+    }
+
+    /// Copy interleaved I/Q into the input buffer for [`Self::process`].
+    ///
+    /// Returns the number of sample pairs copied (at most the buffer size).
+    #[wasm_bindgen]
+    pub fn write_input(&mut self, iq: &[f32]) -> usize {
+        let pairs = (iq.len() / 2).min(self.buffer_size);
+        self.input_buffer[..pairs * 2].copy_from_slice(&iq[..pairs * 2]);
+        pairs
+    }
+
+    /// Copy the audio from the last [`Self::process`] into `out`.
+    ///
+    /// Returns the number of samples copied.
+    #[wasm_bindgen]
+    pub fn read_output(&self, out: &mut [f32]) -> usize {
+        let count = out.len().min(self.output_len);
+        out[..count].copy_from_slice(&self.output_buffer[..count]);
+        count
+    }
+
+    /// Copy the spectrum (dBm per bin) into `out`.
+    ///
+    /// Returns the number of bins copied.
+    #[wasm_bindgen]
+    pub fn read_spectrum(&self, out: &mut [f32]) -> usize {
+        let bins = &self.spectrum_buffer[..self.spectrum_buffer.len() / 2];
+        let count = out.len().min(bins.len());
+        out[..count].copy_from_slice(&bins[..count]);
+        count
     }
 
-    /// Get pointer to input buffer for WASM memory access.
+    /// Copy the waterfall (color map position, 0.0 to 1.0, per bin) into `out`.
+    ///
+    /// Returns the number of bins copied.
+    #[wasm_bindgen]
+    pub fn read_waterfall(&self, out: &mut [f32]) -> usize {
+        let count = out.len().min(self.waterfall_buffer.len());
+        out[..count].copy_from_slice(&self.waterfall_buffer[..count]);
+        count
+    }
+
+    /// Get a view of the input buffer, to fill without copying.
+    ///
+    /// The view is into WASM memory and is detached when the memory
+    /// grows, so take a fresh one for each block rather than keeping it.
+    #[wasm_bindgen]
+    pub fn input_view(&mut self) -> Float32Array {
+        // SAFETY: the view is over a live buffer and nothing in Rust
+        // allocates before JS is back in control of it
+        unsafe { Float32Array::view(&self.input_buffer) }
+    }
+
+    /// Get a view of the audio from the last [`Self::process`].
+    ///
+    /// See [`Self::input_view`] for how long it is valid.
     #[wasm_bindgen]
-    pub fn get_input_buffer_ptr(&mut self) -> *mut f32 {
-        self.input_buffer.as_mut_ptr()
+    pub fn output_view(&self) -> Float32Array {
+        // SAFETY: as for input_view
+        unsafe { Float32Array::view(&self.output_buffer[..self.output_len]) }
     }
 
-    /// Get pointer to output buffer for WASM memory access.
+    /// Get a view of the spectrum (dBm per bin).
+    ///
+    /// See [`Self::input_view`] for how long it is valid.
     #[wasm_bindgen]
-    pub fn get_output_buffer_ptr(&self) -> *const f32 {
-        self.output_buffer.as_ptr()
+    pub fn spectrum_view(&self) -> Float32Array {
+        let bins = self.spectrum_buffer.len() / 2;
+        // SAFETY: as for input_view
+        unsafe { Float32Array::view(&self.spectrum_buffer[..bins]) }
     }
 
-    /// Get pointer to spectrum buffer for WASM memory access.
+    /// Get a view of the waterfall (color map position, 0.0 to 1.0, per bin).
+    ///
+    /// See [`Self::input_view`] for how long it is valid.
     #[wasm_bindgen]
-    pub fn get_spectrum_buffer_ptr(&self) -> *const f32 {
-        self.spectrum_buffer.as_ptr()
+    pub fn waterfall_view(&self) -> Float32Array {
+        // SAFETY: as for input_view
+        unsafe { Float32Array::view(&self.waterfall_buffer) }
     }
 
     /// Process audio samples.
     ///
-    /// Input: interleaved I/Q samples (I0, Q0, I1, Q1, ...)
-    /// Output: mono audio samples
+    /// Input: interleaved I/Q samples (I0, Q0, I1, Q1, ...) at the input rate
+    /// Output: mono audio samples at the processor rate, see [`Self::get_output_len`]
     #[wasm_bindgen]
     pub fn process(&mut self, num_samples: usize) {
-        let samples = num_samples.min(BUFFER_SIZE);
-
-        for idx in 0..samples {
-            // Extract I/Q from interleaved buffer
-            let raw_i = self.input_buffer[idx * 2];
-            let raw_q = self.input_buffer[idx * 2 + 1];
-
-            // DC blocking
-            let i_sample = self.dc_blocker_i.process(raw_i);
-            let q_sample = self.dc_blocker_q.process(raw_q);
-
-            let iq = IqSample::new(i_sample, q_sample);
-
-            // Mix to audio frequency
-            let mixed = self.nco.mix(iq);
-
-            // Demodulate based on mode
-            let audio = match self.mode {
-                0 => self.demod_lsb(mixed),
-                1 => self.demod_usb(mixed),
-                2 => self.demod_cw(mixed),
-                3 => self.demod_am(mixed),
-                4 => self.demod_fm(mixed),
-                _ => self.demod_usb(mixed),
-            };
+        let started_ms = now_ms();
+        let samples = num_samples.min(self.buffer_size);
+
+        // Extract I/Q from interleaved buffer, resampling to the processor rate
+        let mut iq = core::mem::take(&mut self.iq_block);
+        iq.clear();
+        iq.extend(
+            self.input_buffer[..samples * 2]
+                .chunks_exact(2)
+                .map(|pair| IqSample::new(pair[0], pair[1])),
+        );
+        let mut resampled = core::mem::take(&mut self.resampled);
+        resampled.resize(self.input_resampler.max_output(samples), IqSample::ZERO);
+        let count = self.input_resampler.process(&iq, &mut resampled);
+        resampled.truncate(count);
+        core::mem::swap(&mut iq, &mut resampled);
+        self.resampled = resampled;
+        let samples = iq.len();
+
+        if self.iq_recording {
+            let room = self.iq_record_limit.saturating_sub(self.iq_record.len() / 2);
+            for sample in &iq[..samples.min(room)] {
+                self.iq_record.extend_from_slice(&[sample.i, sample.q]);
+            }
+        }
+
+        // Remove DC, correct the IQ balance and blank impulses before
+        // anything else sees them
+        self.dc_blocker.process_block(&mut iq);
+        self.iq_balance.process_block(&mut iq);
+        self.blanker.process_block(&mut iq);
 
-            // Apply audio filter
-            let filtered = self.audio_filter.process(audio);
+        // S-meter and spectrum analyzer see the input level
+        for sample in &iq {
+            let magnitude = sample.magnitude();
+            self.smeter.update(magnitude);
+            self.spectrum.push(magnitude);
+        }
 
-            // AGC
-            let output = self.agc.process(filtered);
+        // Receiver slices mix their own offsets from the same I/Q
+        for slice in self.slices.iter_mut().flatten() {
+            slice.process(&iq);
+        }
 
-            // Update S-meter
-            self.smeter.update(iq.magnitude());
+        // Mix to audio frequency and demodulate into the output buffer
+        self.nco.mix_block(&mut iq);
+        let mut output = core::mem::take(&mut self.output_buffer);
+        let output_len = samples.min(output.len());
+        let audio = &mut output[..output_len];
+        demodulate(self.mode, &iq, audio);
+        self.iq_block = iq;
 
-            // Store output
-            self.output_buffer[idx] = output;
+        // Mode decoders take the unfiltered audio
+        for &sample in audio.iter() {
+            self.decode(sample);
+        }
 
-            // Feed spectrum analyzer
-            self.spectrum.push(iq.magnitude());
+        // Audio filter, noise reduction, notches, AGC and equalizer, muted
+        // by the squelch; the SNR is measured on the filtered audio
+        self.audio_filter.process_block(audio);
+        self.snr.process_block(audio);
+        self.nr.process_block(audio);
+        self.spectral_nr.process_block(audio);
+        for notch in &mut self.notches {
+            notch.process_block(audio);
+        }
+        self.agc.process_block(audio);
+        self.equalizer.process_block(audio);
+        if !self.squelch.update(self.smeter.dbm()) {
+            audio.fill(0.0);
         }
+        for slice in self.slices.iter().flatten() {
+            slice.mix_into(audio);
+        }
+        self.output_buffer = output;
+        self.output_len = output_len;
 
         // Update S-meter reading
         self.smeter_value = self.smeter.value();
 
+        if let Some(skimmer) = self.cw_skimmer.as_ref() {
+            self.cw_signals.clear();
+            for signal in skimmer.signals() {
+                self.cw_signals.extend_from_slice(&[
+                    signal.id as f32,
+                    signal.frequency_hz,
+                    signal.wpm,
+                    signal.snr_db,
+                ]);
+            }
+        }
+
         // Compute spectrum if buffer full
         if self.spectrum.is_ready() {
-            self.spectrum.compute(&mut self.spectrum_buffer);
+            let mut dbfs = [0.0; MAX_BINS / 2];
+            let dbfs = &mut dbfs[..self.spectrum_buffer.len() / 2];
+            self.spectrum.compute_dbfs(dbfs);
+            let dbm = self.spectrum_averager.process(dbfs);
+            self.spectrum_buffer[..dbm.len()].copy_from_slice(dbm);
+            self.update_peaks();
+            self.update_waterfall();
         }
 
         self.frame_count += 1;
+        self.update_cpu_load(now_ms() - started_ms, num_samples.min(self.buffer_size));
+    }
+
+    /// Process a block of interleaved I/Q of any length (e.g. from a file).
+    ///
+    /// The input is run through [`Self::process`] a buffer at a time,
+    /// resampled from the rate set with [`Self::set_input_rate`] to the
+    /// processor rate. Demodulated audio is written to `audio`; returns
+    /// the number of audio samples produced (audio beyond the length of
+    /// `audio` is dropped).
+    #[wasm_bindgen]
+    pub fn process_iq(&mut self, iq: &[f32], audio: &mut [f32]) -> usize {
+        let mut written = 0;
+        self.process_blocks(iq, |block| {
+            let out = (audio.len() - written).min(block.len());
+            audio[written..written + out].copy_from_slice(&block[..out]);
+            written += out;
+        });
+        written
     }
 
-    /// LSB demodulation (I - Q shifted).
-    fn demod_lsb(&self, iq: IqSample) -> f32 {
-        // Simple LSB: take I component (after mixing)
-        iq.i - iq.q
+    /// Process interleaved I/Q of any length and return all the audio.
+    ///
+    /// Like [`Self::process_iq`], but allocates the output, so JS needs
+    /// no buffers or pointers at all.
+    #[wasm_bindgen]
+    pub fn process_samples(&mut self, iq: &[f32]) -> Vec<f32> {
+        let mut audio = Vec::with_capacity(self.input_resampler.max_output(iq.len() / 2));
+        self.process_blocks(iq, |block| audio.extend_from_slice(block));
+        audio
     }
 
-    /// USB demodulation (I + Q shifted).
-    fn demod_usb(&self, iq: IqSample) -> f32 {
-        // Simple USB: I + Q
-        iq.i + iq.q
+    /// Set the sample rate of I/Q passed to [`Self::process`] and [`Self::process_iq`] in Hz.
+    ///
+    /// Input at any other rate than the processor's is resampled to it,
+    /// so the output buffer may be reallocated: fetch its pointer again.
+    #[wasm_bindgen]
+    pub fn set_input_rate(&mut self, input_rate: f32) {
+        self.input_rate = input_rate;
+        self.input_resampler = IqResampler::new(input_rate, self.sample_rate);
+        self.resize_buffers();
     }
 
-    /// CW demodulation (beat frequency oscillator).
-    fn demod_cw(&self, iq: IqSample) -> f32 {
-        // CW is essentially USB with narrow filter
-        iq.i + iq.q
+    /// Get the input I/Q sample rate in Hz.
+    #[wasm_bindgen]
+    pub fn get_input_rate(&self) -> f32 {
+        self.input_rate
     }
 
-    /// AM demodulation (envelope detection).
-    fn demod_am(&self, iq: IqSample) -> f32 {
-        iq.magnitude()
+    /// Start recording raw input I/Q, keeping at most `max_pairs` sample pairs.
+    ///
+    /// Any previous recording is discarded.
+    #[wasm_bindgen]
+    pub fn start_iq_record(&mut self, max_pairs: usize) {
+        self.iq_record.clear();
+        self.iq_record_limit = max_pairs;
+        self.iq_recording = true;
     }
 
-    /// FM demodulation (phase derivative).
-    fn demod_fm(&self, iq: IqSample) -> f32 {
-        // Simplified FM demod using phase
-        iq.phase()
+    /// Stop recording (the recording is kept until taken).
+    #[wasm_bindgen]
+    pub fn stop_iq_record(&mut self) {
+        self.iq_recording = false;
     }
 
-    /// Set operating mode.
+    /// Check if raw I/Q is being recorded.
     #[wasm_bindgen]
-    pub fn set_mode(&mut self, mode: u8) {
-        self.mode = mode;
+    pub fn is_iq_recording(&self) -> bool {
+        self.iq_recording
+    }
 
-        // Adjust filter bandwidth based on mode
-        let bandwidth = match mode {
-            0 | 1 => 2700.0, // SSB
-            2 => 500.0,      // CW
-            3 => 6000.0,     // AM
-            4 => 15000.0,    // FM
-            _ => 2700.0,
-        };
+    /// Get the number of sample pairs recorded.
+    #[wasm_bindgen]
+    pub fn get_iq_record_len(&self) -> usize {
+        self.iq_record.len() / 2
+    }
 
-        self.audio_filter = Biquad::lowpass(self.sample_rate, bandwidth, 0.707);
+    /// Take the recording as interleaved f32 I/Q, clearing it.
+    #[wasm_bindgen]
+    pub fn take_iq_record(&mut self) -> Vec<f32> {
+        core::mem::take(&mut self.iq_record)
     }
 
-    /// Set frequency offset for mixing.
+    /// Take the recording as raw interleaved s16 little-endian I/Q, clearing it.
     #[wasm_bindgen]
-    pub fn set_frequency_offset(&mut self, offset_hz: f32) {
-        self.freq_offset = offset_hz;
-        self.nco.set_frequency(offset_hz);
+    pub fn take_iq_record_s16(&mut self) -> Vec<u8> {
+        self.take_iq_record()
+            .iter()
+            .flat_map(|&s| to_pcm16(s).to_le_bytes())
+            .collect()
     }
 
-    /// Set filter bandwidth in Hz.
+    /// Run the SSTV decoder on one audio sample and queue finished lines.
+    fn process_sstv(&mut self, audio: f32) {
+        let Some(sstv) = self.sstv.as_mut() else {
+            return;
+        };
+        match sstv.process(audio) {
+            Some(SstvEvent::Started(mode)) => {
+                self.sstv_vis = i32::from(mode.vis_code());
+            }
+            // Drop lines nobody read rather than grow without bound
+            Some(SstvEvent::Line(line)) if self.sstv_line_numbers.len() < SSTV_LINE_CAPACITY => {
+                self.sstv_line_numbers.push(u32::from(line));
+                self.sstv_pixels.extend_from_slice(sstv.line());
+            }
+            Some(SstvEvent::Finished) => {
+                self.sstv_vis = -1;
+            }
+            Some(SstvEvent::Line(_)) | None => {}
+        }
+    }
+
+    /// Run the enabled mode decoders on one audio sample.
+    fn decode(&mut self, audio: f32) {
+        // Decode PSK31 across the whole passband, ahead of the filter
+        if !self.psk_bank.is_empty() {
+            for decoded in self.psk_bank.process(IqSample::new(audio, 0.0)) {
+                if self.psk_decoded.len() < PSK_DECODED_CAPACITY * 2 {
+                    self.psk_decoded.push(decoded.channel);
+                    self.psk_decoded.push(u32::from(decoded.ch));
+                }
+            }
+        }
+
+        // Decode SSTV from the unfiltered audio as well
+        if self.sstv.is_some() {
+            self.process_sstv(audio);
+        }
+        if let Some(aprs) = self.aprs.as_mut() {
+            if let Some(frame) = aprs.process(audio) {
+                if self.aprs_frame_lengths.len() < APRS_FRAME_CAPACITY {
+                    self.aprs_frame_lengths.push(frame.len() as u32);
+                    self.aprs_frames.extend_from_slice(&frame);
+                }
+            }
+        }
+        if let Some(js8) = self.js8.as_mut() {
+            if let Some(frame) = js8.process(audio) {
+                if self.js8_frames.len() < JS8_FRAME_CAPACITY * JS8_FRAME_BYTES {
+                    self.js8_frames.extend_from_slice(&frame.to_bytes());
+                }
+            }
+        }
+        if let Some(hell) = self.hell.as_mut() {
+            if let Some(column) = hell.process(audio) {
+                if self.hell_columns.len() < HELL_COLUMN_CAPACITY * HELL_COLUMN_HEIGHT {
+                    self.hell_columns.extend_from_slice(&column);
+                }
+            }
+        }
+        if let Some(skimmer) = self.cw_skimmer.as_mut() {
+            for decoded in skimmer.process(audio) {
+                if self.cw_decoded.len() < CW_DECODED_CAPACITY * 2 {
+                    self.cw_decoded.push(decoded.signal);
+                    self.cw_decoded.push(u32::from(decoded.ch));
+                }
+            }
+        }
+    }
+
+    /// Get the current [`DemodMode`] code.
     #[wasm_bindgen]
-    pub fn set_filter_bandwidth(&mut self, bandwidth_hz: f32) {
-        self.audio_filter = Biquad::lowpass(self.sample_rate, bandwidth_hz, 0.707);
+    pub fn get_mode(&self) -> u8 {
+        self.mode.code()
     }
 
-    /// Set AGC parameters.
+    /// Set a parameter by its [`Parameter`] code.
+    ///
+    /// Returns `false` if the parameter code is unknown.
     #[wasm_bindgen]
-    pub fn set_agc(&mut self, attack_ms: f32, decay_ms: f32, hang_ms: f32) {
-        let config = AgcConfig {
-            attack_ms,
-            decay_ms,
-            hang_ms,
-            ..AgcConfig::default()
+    pub fn set_parameter(&mut self, param: u8, value: f32) -> bool {
+        let Some(param) = Parameter::from_code(param) else {
+            return false;
         };
-        self.agc.set_config(config);
+        match param {
+            Parameter::FrequencyOffset => self.set_frequency_offset(value),
+            Parameter::FilterBandwidth => self.set_filter_bandwidth(value),
+            Parameter::AgcAttack => {
+                self.agc_config.attack_ms = value;
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcDecay => {
+                self.agc_config.decay_ms = value;
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcHang => {
+                self.agc_config.hang_ms = value;
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcHangThreshold => {
+                self.agc_config.hang_threshold_db = value;
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcSlope => {
+                self.agc_config.slope_db = value.max(0.0);
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcMaxGain => {
+                self.agc_config = self.agc_config.with_max_gain_db(value);
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::AgcDualRate => {
+                self.agc_config.dual_rate = value != 0.0;
+                self.agc.set_config(self.agc_config);
+            }
+            Parameter::SmeterOffset => {
+                let slope = self.smeter.calibration().slope();
+                self.smeter.set_calibration(SmeterCalibration::from_db(value, slope));
+            }
+            Parameter::SmeterSlope => {
+                let offset = self.smeter.calibration().offset_db();
+                self.smeter.set_calibration(SmeterCalibration::from_db(offset, value));
+            }
+            Parameter::SpectrumAveraging => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                self.spectrum_averager
+                    .set_averaging(value.clamp(1.0, f32::from(u8::MAX)) as u8);
+            }
+            Parameter::SpectrumGain => self.spectrum_averager.set_gain_db(value),
+            Parameter::SpectrumDcNotch => self.set_spectrum_dc_notch(value),
+            Parameter::WaterfallRefLevel => {
+                self.set_waterfall_levels(value, self.waterfall_range.range_db);
+            }
+            Parameter::WaterfallRange => {
+                self.set_waterfall_levels(self.waterfall_range.ref_db(), value);
+            }
+            Parameter::WaterfallScale => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let code = value.clamp(0.0, f32::from(u8::MAX)) as u8;
+                _ = self.set_waterfall_scale(code);
+            }
+            Parameter::WaterfallAutoLevel => self.set_waterfall_auto_level(value != 0.0),
+            Parameter::MaxPeaks => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                self.set_max_peaks(value.max(0.0) as usize);
+            }
+            Parameter::PeakThreshold => self.set_peak_threshold(value),
+            Parameter::NoiseReduction => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                self.nr.set_level(value.clamp(0.0, f32::from(u8::MAX)) as u8);
+            }
+            Parameter::SquelchEnabled => self.squelch.set_enabled(value != 0.0),
+            Parameter::SquelchThreshold => self.squelch.set_threshold_db(value),
+            Parameter::FineTune => self.set_fine_tune(value),
+            Parameter::IfShift => self.set_if_shift(value),
+            Parameter::Notch1Enabled => {
+                self.set_notch_enabled(0, value != 0.0);
+            }
+            Parameter::Notch1Frequency => {
+                self.set_notch(0, value, self.notches[0].width());
+            }
+            Parameter::Notch1Width => {
+                self.set_notch(0, self.notches[0].frequency(), value);
+            }
+            Parameter::Notch2Enabled => {
+                self.set_notch_enabled(1, value != 0.0);
+            }
+            Parameter::Notch2Frequency => {
+                self.set_notch(1, value, self.notches[1].width());
+            }
+            Parameter::Notch2Width => {
+                self.set_notch(1, self.notches[1].frequency(), value);
+            }
+            Parameter::BlankerEnabled => self.blanker.set_enabled(value != 0.0),
+            Parameter::BlankerThreshold => self.blanker.set_threshold(value),
+            Parameter::BlankerWidth => self.blanker.set_width(value),
+            Parameter::IqGain => self.set_iq_balance(value, self.iq_balance.phase_deg()),
+            Parameter::IqPhase => self.set_iq_balance(self.iq_balance.gain_db(), value),
+            Parameter::IqAdaptive => self.iq_balance.set_adaptive(value != 0.0),
+            Parameter::SpectralNr => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                self.spectral_nr
+                    .set_strength(value.clamp(0.0, f32::from(u8::MAX)) as u8);
+            }
+            Parameter::EqEnabled => self.equalizer.set_enabled(value != 0.0),
+            Parameter::EqBand1Gain => _ = self.equalizer.set_gain(0, value),
+            Parameter::EqBand2Gain => _ = self.equalizer.set_gain(1, value),
+            Parameter::EqBand3Gain => _ = self.equalizer.set_gain(2, value),
+            Parameter::EqBand4Gain => _ = self.equalizer.set_gain(3, value),
+            Parameter::EqBand5Gain => _ = self.equalizer.set_gain(4, value),
+        }
+        true
+    }
+
+    /// Get a parameter by its [`Parameter`] code (NaN if unknown).
+    #[wasm_bindgen]
+    pub fn get_parameter(&self, param: u8) -> f32 {
+        match Parameter::from_code(param) {
+            Some(Parameter::FrequencyOffset) => self.freq_offset,
+            Some(Parameter::FilterBandwidth) => self.filter_bandwidth,
+            Some(Parameter::AgcAttack) => self.agc_config.attack_ms,
+            Some(Parameter::AgcDecay) => self.agc_config.decay_ms,
+            Some(Parameter::AgcHang) => self.agc_config.hang_ms,
+            Some(Parameter::AgcHangThreshold) => self.agc_config.hang_threshold_db,
+            Some(Parameter::AgcSlope) => self.agc_config.slope_db,
+            Some(Parameter::AgcMaxGain) => self.agc_config.max_gain_db(),
+            Some(Parameter::AgcDualRate) => f32::from(u8::from(self.agc_config.dual_rate)),
+            Some(Parameter::SmeterOffset) => self.smeter.calibration().offset_db(),
+            Some(Parameter::SmeterSlope) => self.smeter.calibration().slope(),
+            Some(Parameter::SpectrumAveraging) => f32::from(self.spectrum_averager.averaging()),
+            Some(Parameter::SpectrumGain) => self.spectrum_averager.gain_db(),
+            Some(Parameter::SpectrumDcNotch) => self.spectrum_dc_notch_hz,
+            Some(Parameter::WaterfallRefLevel) => self.get_waterfall_ref_level(),
+            Some(Parameter::WaterfallRange) => self.get_waterfall_range(),
+            Some(Parameter::WaterfallScale) => f32::from(self.waterfall_scale.code()),
+            Some(Parameter::WaterfallAutoLevel) => f32::from(u8::from(self.waterfall_auto)),
+            Some(Parameter::MaxPeaks) => self.max_peaks as f32,
+            Some(Parameter::PeakThreshold) => self.peak_threshold_db,
+            Some(Parameter::NoiseReduction) => f32::from(self.nr.level()),
+            Some(Parameter::SquelchEnabled) => f32::from(u8::from(self.squelch.is_enabled())),
+            Some(Parameter::SquelchThreshold) => self.squelch.threshold_db(),
+            Some(Parameter::FineTune) => self.fine_tune,
+            Some(Parameter::IfShift) => self.if_shift,
+            Some(Parameter::Notch1Enabled) => f32::from(u8::from(self.notches[0].is_enabled())),
+            Some(Parameter::Notch1Frequency) => self.notches[0].frequency(),
+            Some(Parameter::Notch1Width) => self.notches[0].width(),
+            Some(Parameter::Notch2Enabled) => f32::from(u8::from(self.notches[1].is_enabled())),
+            Some(Parameter::Notch2Frequency) => self.notches[1].frequency(),
+            Some(Parameter::Notch2Width) => self.notches[1].width(),
+            Some(Parameter::BlankerEnabled) => f32::from(u8::from(self.blanker.is_enabled())),
+            Some(Parameter::BlankerThreshold) => self.blanker.threshold(),
+            Some(Parameter::BlankerWidth) => self.blanker.width(),
+            Some(Parameter::IqGain) => self.iq_balance.gain_db(),
+            Some(Parameter::IqPhase) => self.iq_balance.phase_deg(),
+            Some(Parameter::IqAdaptive) => f32::from(u8::from(self.iq_balance.is_adaptive())),
+            Some(Parameter::SpectralNr) => f32::from(self.spectral_nr.strength()),
+            Some(Parameter::EqEnabled) => f32::from(u8::from(self.equalizer.is_enabled())),
+            Some(Parameter::EqBand1Gain) => self.equalizer.gain(0),
+            Some(Parameter::EqBand2Gain) => self.equalizer.gain(1),
+            Some(Parameter::EqBand3Gain) => self.equalizer.gain(2),
+            Some(Parameter::EqBand4Gain) => self.equalizer.gain(3),
+            Some(Parameter::EqBand5Gain) => self.equalizer.gain(4),
+            None => f32::NAN,
+        }
+    }
+
+    /// Get the fine tuning offset in Hz.
+    #[wasm_bindgen]
+    pub fn get_fine_tune(&self) -> f32 {
+        self.fine_tune
+    }
+
+    /// Move the fine tuning by a number of steps (negative moves down).
+    ///
+    /// Returns the new fine tuning offset in Hz.
+    #[wasm_bindgen]
+    pub fn step_fine_tune(&mut self, steps: i32) -> f32 {
+        #[allow(clippy::cast_precision_loss)]
+        self.set_fine_tune(self.fine_tune + steps as f32 * self.fine_tune_step);
+        self.fine_tune
+    }
+
+    /// Set the fine tuning step in Hz.
+    #[wasm_bindgen]
+    pub fn set_fine_tune_step(&mut self, step_hz: f32) {
+        self.fine_tune_step = step_hz.abs();
+    }
+
+    /// Get the fine tuning step in Hz.
+    #[wasm_bindgen]
+    pub fn get_fine_tune_step(&self) -> f32 {
+        self.fine_tune_step
+    }
+
+    /// Get the IF shift in Hz.
+    #[wasm_bindgen]
+    pub fn get_if_shift(&self) -> f32 {
+        self.if_shift
+    }
+
+    /// Center the passband on an audio frequency in Hz, keeping its width.
+    ///
+    /// Sets the IF shift, so the center lands where asked only as far as
+    /// [`MAX_IF_SHIFT_HZ`] allows.
+    #[wasm_bindgen]
+    pub fn set_passband_center(&mut self, center_hz: f32) {
+        self.set_if_shift(center_hz - self.filter_bandwidth / 2.0);
+    }
+
+    /// Get the center of the passband in use, in Hz.
+    #[wasm_bindgen]
+    pub fn get_passband_center(&self) -> f32 {
+        let (low, high) = self.passband();
+        (low + high) / 2.0
+    }
+
+    /// Get the lower edge of the passband in use, in Hz.
+    #[wasm_bindgen]
+    pub fn get_passband_low(&self) -> f32 {
+        self.passband().0
+    }
+
+    /// Get the upper edge of the passband in use, in Hz.
+    #[wasm_bindgen]
+    pub fn get_passband_high(&self) -> f32 {
+        self.passband().1
+    }
+
+    /// Check if a manual notch is on (`false` if there is no such notch).
+    #[wasm_bindgen]
+    pub fn get_notch_enabled(&self, index: u32) -> bool {
+        self.notches.get(index as usize).is_some_and(ManualNotch::is_enabled)
+    }
+
+    /// Get a manual notch frequency in Hz (NaN if there is no such notch).
+    #[wasm_bindgen]
+    pub fn get_notch_frequency(&self, index: u32) -> f32 {
+        self.notches.get(index as usize).map_or(f32::NAN, ManualNotch::frequency)
+    }
+
+    /// Get a manual notch width in Hz (NaN if there is no such notch).
+    #[wasm_bindgen]
+    pub fn get_notch_width(&self, index: u32) -> f32 {
+        self.notches.get(index as usize).map_or(f32::NAN, ManualNotch::width)
+    }
+
+    /// Check if the graphic equalizer is on.
+    #[wasm_bindgen]
+    pub fn get_eq_enabled(&self) -> bool {
+        self.equalizer.is_enabled()
+    }
+
+    /// Get an equalizer band gain in dB (NaN if there is no such band).
+    #[wasm_bindgen]
+    pub fn get_eq_band(&self, band: u32) -> f32 {
+        self.equalizer.gain(band as usize)
+    }
+
+    /// Check if the IQ noise blanker is on.
+    #[wasm_bindgen]
+    pub fn get_noise_blanker(&self) -> bool {
+        self.blanker.is_enabled()
+    }
+
+    /// Get the noise blanker threshold as a magnitude ratio.
+    #[wasm_bindgen]
+    pub fn get_noise_blanker_threshold(&self) -> f32 {
+        self.blanker.threshold()
+    }
+
+    /// Get the noise blanker width in microseconds.
+    #[wasm_bindgen]
+    pub fn get_noise_blanker_width(&self) -> f32 {
+        self.blanker.width()
+    }
+
+    /// Get the IQ balance gain correction in dB (the adapted one when
+    /// adaptive).
+    #[wasm_bindgen]
+    pub fn get_iq_gain_db(&self) -> f32 {
+        self.iq_balance.gain_db()
+    }
+
+    /// Get the IQ balance phase correction in degrees (the adapted one
+    /// when adaptive).
+    #[wasm_bindgen]
+    pub fn get_iq_phase_deg(&self) -> f32 {
+        self.iq_balance.phase_deg()
+    }
+
+    /// Check if the IQ balance is adaptive.
+    #[wasm_bindgen]
+    pub fn get_iq_adaptive(&self) -> bool {
+        self.iq_balance.is_adaptive()
+    }
+
+    /// Get the estimated image rejection after correction in dB (NaN
+    /// before the first measurement).
+    #[wasm_bindgen]
+    pub fn get_image_rejection_db(&self) -> f32 {
+        self.iq_balance.image_rejection_db().unwrap_or(f32::NAN)
+    }
+
+    /// Get the delay added by the audio filter in milliseconds.
+    #[wasm_bindgen]
+    pub fn get_filter_latency_ms(&self) -> f32 {
+        self.audio_filter.latency_seconds(self.sample_rate) * 1000.0
+    }
+
+    /// Apply a [`DspConfig`] object in one call.
+    ///
+    /// Fields left out keep their current values. Fails, changing
+    /// nothing, if the object has unknown fields, an unknown mode or an
+    /// unsupported spectrum size.
+    #[wasm_bindgen]
+    pub fn apply_config(&mut self, config: JsValue) -> Result<(), JsError> {
+        let config: DspConfig = serde_wasm_bindgen::from_value(config)?;
+        Ok(self.apply(&config)?)
+    }
+
+    /// Get a [`DspStatus`] object with the current meter readings.
+    #[wasm_bindgen]
+    pub fn get_status(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.status())?)
     }
 
     /// Get current S-meter value (0.0 to ~1.5).
@@ -224,27 +1053,2033 @@ impl DspProcessor {
         self.smeter_value
     }
 
-    /// Get current frame count.
+    /// Get the calibrated signal level in dBm.
     #[wasm_bindgen]
-    pub fn get_frame_count(&self) -> u32 {
-        self.frame_count
+    pub fn get_smeter_dbm(&self) -> f32 {
+        self.smeter.dbm()
     }
 
-    /// Reset processor state.
+    /// Get the noise floor of the averaged spectrum in dBm (NaN before the first frame).
     #[wasm_bindgen]
-    pub fn reset(&mut self) {
-        self.dc_blocker_i.reset();
-        self.dc_blocker_q.reset();
-        self.nco.reset();
-        self.agc.reset();
-        self.smeter.reset();
-        self.spectrum.reset();
-        self.frame_count = 0;
+    pub fn get_noise_floor(&self) -> f32 {
+        self.spectrum_averager.noise_floor_db().unwrap_or(f32::NAN)
     }
-}
 
-/// Create a new DSP processor (factory function).
-#[wasm_bindgen]
-pub fn create_processor(sample_rate: f32) -> DspProcessor {
-    DspProcessor::new(sample_rate)
+    /// Get the spectrum DC notch width in Hz either side of DC.
+    #[wasm_bindgen]
+    pub fn get_spectrum_dc_notch(&self) -> f32 {
+        self.spectrum_dc_notch_hz
+    }
+
+    /// Get the waterfall reference level in use in dBm.
+    #[wasm_bindgen]
+    pub fn get_waterfall_ref_level(&self) -> f32 {
+        self.waterfall_levels.ref_db()
+    }
+
+    /// Get the waterfall span in use in dB.
+    #[wasm_bindgen]
+    pub fn get_waterfall_range(&self) -> f32 {
+        self.waterfall_levels.range_db
+    }
+
+    /// Get the waterfall [`WaterfallScale`] code.
+    #[wasm_bindgen]
+    pub fn get_waterfall_scale(&self) -> u8 {
+        self.waterfall_scale.code()
+    }
+
+    /// Check if waterfall auto-level is on.
+    #[wasm_bindgen]
+    pub fn get_waterfall_auto_level(&self) -> bool {
+        self.waterfall_auto
+    }
+
+    /// Get the most spectrum peaks listed per frame.
+    #[wasm_bindgen]
+    pub fn get_max_peaks(&self) -> usize {
+        self.max_peaks
+    }
+
+    /// Get the peak threshold above the noise floor in dB.
+    #[wasm_bindgen]
+    pub fn get_peak_threshold(&self) -> f32 {
+        self.peak_threshold_db
+    }
+
+    /// Get the number of peaks in the last spectrum frame.
+    #[wasm_bindgen]
+    pub fn get_spectrum_peak_count(&self) -> usize {
+        self.spectrum_peaks.len() / PEAK_FIELDS
+    }
+
+    /// Copy the last frame's peaks, strongest first, into `out` as
+    /// frequency (Hz from the first bin), level (dBm) and width (Hz).
+    ///
+    /// Returns the number of values copied (whole peaks only).
+    #[wasm_bindgen]
+    pub fn read_spectrum_peaks(&self, out: &mut [f32]) -> usize {
+        let count = (out.len() / PEAK_FIELDS * PEAK_FIELDS).min(self.spectrum_peaks.len());
+        out[..count].copy_from_slice(&self.spectrum_peaks[..count]);
+        count
+    }
+
+    /// Get a view of the last frame's peaks, laid out as for
+    /// [`Self::read_spectrum_peaks`].
+    ///
+    /// See [`Self::input_view`] for how long it is valid.
+    #[wasm_bindgen]
+    pub fn spectrum_peaks_view(&self) -> Float32Array {
+        // SAFETY: as for input_view
+        unsafe { Float32Array::view(&self.spectrum_peaks) }
+    }
+
+    /// Get the audio SNR in the passband in dB (NaN before the first frame).
+    ///
+    /// Measured on the filtered audio ahead of noise reduction and the
+    /// AGC, averaged over about a second.
+    #[wasm_bindgen]
+    pub fn get_snr_db(&self) -> f32 {
+        self.snr.reading().map_or(f32::NAN, |reading| reading.snr_db)
+    }
+
+    /// Get the audio SNR referred to a 2500 Hz noise bandwidth, as WSJT-X
+    /// reports it, in dB (NaN before the first frame).
+    #[wasm_bindgen]
+    pub fn get_snr_2500_db(&self) -> f32 {
+        self.snr.reading().map_or(f32::NAN, |reading| {
+            reading.snr_in_bandwidth_db(sdr_dsp_core::snr::WSJT_BANDWIDTH_HZ)
+        })
+    }
+
+    /// Get the signal power in the passband, noise excluded, in dBFS
+    /// (NaN before the first frame).
+    #[wasm_bindgen]
+    pub fn get_signal_power_dbfs(&self) -> f32 {
+        self.snr.reading().map_or(f32::NAN, |reading| reading.signal_dbfs)
+    }
+
+    /// Get the noise power in the passband in dBFS (NaN before the first frame).
+    #[wasm_bindgen]
+    pub fn get_passband_noise_dbfs(&self) -> f32 {
+        self.snr.reading().map_or(f32::NAN, |reading| reading.noise_dbfs)
+    }
+
+    /// Get current frame count.
+    #[wasm_bindgen]
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Start a PSK31 decoder at an audio offset (e.g. a clicked trace).
+    ///
+    /// Returns the channel id, an existing channel's id if one is already
+    /// at that offset, or -1 if every decoder is in use.
+    #[wasm_bindgen]
+    pub fn add_psk_decoder(&mut self, offset_hz: f32) -> i32 {
+        self.psk_bank.add(offset_hz).map_or(-1, |id| id as i32)
+    }
+
+    /// Stop a PSK31 decoder.
+    #[wasm_bindgen]
+    pub fn remove_psk_decoder(&mut self, id: u32) -> bool {
+        self.psk_bank.remove(id)
+    }
+
+    /// Stop every PSK31 decoder.
+    #[wasm_bindgen]
+    pub fn clear_psk_decoders(&mut self) {
+        self.psk_bank.clear();
+        self.psk_decoded.clear();
+    }
+
+    /// Get the number of running PSK31 decoders.
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_count(&self) -> usize {
+        self.psk_bank.len()
+    }
+
+    /// Get the AFC-tracked offset of a PSK31 decoder (NaN if unknown).
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_offset(&self, id: u32) -> f32 {
+        self.psk_bank.tracked_offset(id).unwrap_or(f32::NAN)
+    }
+
+    /// Get the SNR of a PSK31 decoder in dB (NaN if unknown).
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_snr(&self, id: u32) -> f32 {
+        self.psk_bank.metrics(id).map_or(f32::NAN, |m| m.snr_db)
+    }
+
+    /// Check whether a PSK31 decoder's carrier loop is locked.
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_locked(&self, id: u32) -> bool {
+        self.psk_bank.is_locked(id).unwrap_or(false)
+    }
+
+    /// Configure AFC for every PSK31 decoder.
+    ///
+    /// `bandwidth_hz` sets how fast the carrier loop follows drift and
+    /// `range_hz` how far it may pull from the clicked offset.
+    #[wasm_bindgen]
+    pub fn set_psk_afc(&mut self, enabled: bool, bandwidth_hz: f32, range_hz: f32) {
+        self.psk_bank
+            .set_afc(enabled, bandwidth_hz.max(1.0), range_hz.max(0.0));
+    }
+
+    /// Get the SNR of a PSK31 decoder from its carrier phase jitter in dB
+    /// (NaN if unknown).
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_phase_snr(&self, id: u32) -> f32 {
+        self.psk_bank.quality(id).map_or(f32::NAN, |q| q.snr_db)
+    }
+
+    /// Get the error vector magnitude of a PSK31 decoder in percent
+    /// (NaN if unknown).
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_evm(&self, id: u32) -> f32 {
+        self.psk_bank.quality(id).map_or(f32::NAN, |q| q.evm_percent)
+    }
+
+    /// Get the idle IMD of a PSK31 decoder in dB (NaN until the signal
+    /// has idled long enough to measure).
+    #[wasm_bindgen]
+    pub fn get_psk_decoder_imd(&self, id: u32) -> f32 {
+        self.psk_bank
+            .quality(id)
+            .and_then(|q| q.imd_db)
+            .unwrap_or(f32::NAN)
+    }
+
+    /// Get pointer to decoded PSK31 output (channel, char code pairs).
+    #[wasm_bindgen]
+    pub fn get_psk_decoded_ptr(&self) -> *const u32 {
+        self.psk_decoded.as_ptr()
+    }
+
+    /// Get the number of u32 values of decoded PSK31 output.
+    #[wasm_bindgen]
+    pub fn get_psk_decoded_len(&self) -> usize {
+        self.psk_decoded.len()
+    }
+
+    /// Discard decoded PSK31 output after reading it.
+    #[wasm_bindgen]
+    pub fn clear_psk_decoded(&mut self) {
+        self.psk_decoded.clear();
+    }
+
+    /// Enable or disable the SSTV decoder.
+    ///
+    /// While enabled the decoder waits for a VIS header and then delivers
+    /// scanlines, read with [`get_sstv_pixels_ptr`](Self::get_sstv_pixels_ptr).
+    #[wasm_bindgen]
+    pub fn set_sstv_enabled(&mut self, enabled: bool) {
+        if enabled == self.sstv.is_some() {
+            return;
+        }
+        self.sstv = enabled.then(|| Box::new(SstvDecoder::new(self.sample_rate)));
+        self.sstv_vis = -1;
+        self.clear_sstv_lines();
+    }
+
+    /// Get the VIS code of the image being received (-1 if none).
+    #[wasm_bindgen]
+    pub fn get_sstv_mode(&self) -> i32 {
+        self.sstv_vis
+    }
+
+    /// Get the number of pixels per SSTV scanline.
+    #[wasm_bindgen]
+    pub fn get_sstv_width(&self) -> usize {
+        SSTV_WIDTH
+    }
+
+    /// Get the number of SSTV scanlines waiting to be read.
+    #[wasm_bindgen]
+    pub fn get_sstv_line_count(&self) -> usize {
+        self.sstv_line_numbers.len()
+    }
+
+    /// Get pointer to the line numbers of the waiting SSTV scanlines.
+    #[wasm_bindgen]
+    pub fn get_sstv_line_numbers_ptr(&self) -> *const u32 {
+        self.sstv_line_numbers.as_ptr()
+    }
+
+    /// Get pointer to the waiting SSTV scanlines (RGB, width × 3 bytes each).
+    #[wasm_bindgen]
+    pub fn get_sstv_pixels_ptr(&self) -> *const u8 {
+        self.sstv_pixels.as_ptr()
+    }
+
+    /// Discard waiting SSTV scanlines after reading them.
+    #[wasm_bindgen]
+    pub fn clear_sstv_lines(&mut self) {
+        self.sstv_line_numbers.clear();
+        self.sstv_pixels.clear();
+    }
+
+    /// Enable or disable the APRS packet decoder.
+    ///
+    /// While enabled, AX.25 frames that pass the FCS check are queued
+    /// (without FCS) for [`get_aprs_frames_ptr`](Self::get_aprs_frames_ptr).
+    #[wasm_bindgen]
+    pub fn set_aprs_enabled(&mut self, enabled: bool) {
+        if enabled == self.aprs.is_some() {
+            return;
+        }
+        self.aprs = enabled.then(|| Box::new(AprsDecoder::new(self.sample_rate)));
+        self.clear_aprs_frames();
+    }
+
+    /// Get the number of AX.25 frames waiting to be read.
+    #[wasm_bindgen]
+    pub fn get_aprs_frame_count(&self) -> usize {
+        self.aprs_frame_lengths.len()
+    }
+
+    /// Get pointer to the byte lengths of the waiting frames.
+    #[wasm_bindgen]
+    pub fn get_aprs_frame_lengths_ptr(&self) -> *const u32 {
+        self.aprs_frame_lengths.as_ptr()
+    }
+
+    /// Get pointer to the waiting frames, back to back.
+    #[wasm_bindgen]
+    pub fn get_aprs_frames_ptr(&self) -> *const u8 {
+        self.aprs_frames.as_ptr()
+    }
+
+    /// Discard waiting frames after reading them.
+    #[wasm_bindgen]
+    pub fn clear_aprs_frames(&mut self) {
+        self.aprs_frame_lengths.clear();
+        self.aprs_frames.clear();
+    }
+
+    /// Enable or disable the JS8 frame decoder.
+    ///
+    /// While enabled, frames that pass the CRC are queued as packed words
+    /// for [`get_js8_frames_ptr`](Self::get_js8_frames_ptr).
+    #[wasm_bindgen]
+    pub fn set_js8_enabled(&mut self, enabled: bool) {
+        if enabled == self.js8.is_some() {
+            return;
+        }
+        self.js8 = enabled.then(|| Box::new(Js8Decoder::new(self.sample_rate, self.js8_frequency)));
+        self.clear_js8_frames();
+    }
+
+    /// Set the JS8 audio frequency (lowest tone) in Hz.
+    #[wasm_bindgen]
+    pub fn set_js8_frequency(&mut self, frequency_hz: f32) {
+        self.js8_frequency = frequency_hz;
+        if let Some(js8) = self.js8.as_mut() {
+            js8.set_frequency(frequency_hz);
+        }
+    }
+
+    /// Get the number of bytes per JS8 frame.
+    #[wasm_bindgen]
+    pub fn get_js8_frame_size(&self) -> usize {
+        JS8_FRAME_BYTES
+    }
+
+    /// Get the number of JS8 frames waiting to be read.
+    #[wasm_bindgen]
+    pub fn get_js8_frame_count(&self) -> usize {
+        self.js8_frames.len() / JS8_FRAME_BYTES
+    }
+
+    /// Get pointer to the waiting JS8 frames, back to back.
+    #[wasm_bindgen]
+    pub fn get_js8_frames_ptr(&self) -> *const u8 {
+        self.js8_frames.as_ptr()
+    }
+
+    /// Discard waiting JS8 frames after reading them.
+    #[wasm_bindgen]
+    pub fn clear_js8_frames(&mut self) {
+        self.js8_frames.clear();
+    }
+
+    /// Enable or disable the Feld Hell decoder.
+    ///
+    /// While enabled, received columns are queued for
+    /// [`get_hell_columns_ptr`](Self::get_hell_columns_ptr).
+    #[wasm_bindgen]
+    pub fn set_hell_enabled(&mut self, enabled: bool) {
+        if enabled == self.hell.is_some() {
+            return;
+        }
+        self.hell = enabled.then(|| Box::new(HellDecoder::new(self.sample_rate, self.hell_frequency)));
+        self.clear_hell_columns();
+    }
+
+    /// Set the Feld Hell tone frequency in Hz.
+    #[wasm_bindgen]
+    pub fn set_hell_frequency(&mut self, frequency_hz: f32) {
+        self.hell_frequency = frequency_hz;
+        if let Some(hell) = self.hell.as_mut() {
+            hell.set_frequency(frequency_hz);
+        }
+    }
+
+    /// Get the number of pixels per Feld Hell column.
+    #[wasm_bindgen]
+    pub fn get_hell_column_height(&self) -> usize {
+        HELL_COLUMN_HEIGHT
+    }
+
+    /// Get the number of Feld Hell columns waiting to be read.
+    #[wasm_bindgen]
+    pub fn get_hell_column_count(&self) -> usize {
+        self.hell_columns.len() / HELL_COLUMN_HEIGHT
+    }
+
+    /// Get pointer to the waiting Feld Hell columns, back to back, each
+    /// top pixel first (0 no tone to 255 full tone).
+    #[wasm_bindgen]
+    pub fn get_hell_columns_ptr(&self) -> *const u8 {
+        self.hell_columns.as_ptr()
+    }
+
+    /// Discard waiting Feld Hell columns after reading them.
+    #[wasm_bindgen]
+    pub fn clear_hell_columns(&mut self) {
+        self.hell_columns.clear();
+    }
+
+    /// Enable or disable the CW skimmer.
+    ///
+    /// While enabled, every CW signal found between 300 and 1200 Hz is
+    /// decoded; characters are queued for
+    /// [`get_cw_decoded_ptr`](Self::get_cw_decoded_ptr) and the signals
+    /// listed at [`get_cw_signals_ptr`](Self::get_cw_signals_ptr).
+    #[wasm_bindgen]
+    pub fn set_cw_skimmer_enabled(&mut self, enabled: bool) {
+        if enabled == self.cw_skimmer.is_some() {
+            return;
+        }
+        self.cw_skimmer =
+            enabled.then(|| Box::new(CwSkimmer::new(self.sample_rate, CW_LOW_HZ, CW_HIGH_HZ)));
+        self.cw_signals.clear();
+        self.clear_cw_decoded();
+    }
+
+    /// Get pointer to skimmed CW characters as (signal id, char) pairs.
+    #[wasm_bindgen]
+    pub fn get_cw_decoded_ptr(&self) -> *const u32 {
+        self.cw_decoded.as_ptr()
+    }
+
+    /// Get number of u32 values in the skimmed CW buffer (twice the characters).
+    #[wasm_bindgen]
+    pub fn get_cw_decoded_len(&self) -> usize {
+        self.cw_decoded.len()
+    }
+
+    /// Discard skimmed CW characters after reading them.
+    #[wasm_bindgen]
+    pub fn clear_cw_decoded(&mut self) {
+        self.cw_decoded.clear();
+    }
+
+    /// Get the number of CW signals being tracked.
+    #[wasm_bindgen]
+    pub fn get_cw_signal_count(&self) -> usize {
+        self.cw_signals.len() / CW_SIGNAL_FIELDS
+    }
+
+    /// Get pointer to the tracked CW signals, [`CW_SIGNAL_FIELDS`] values
+    /// each, as of the last block processed.
+    #[wasm_bindgen]
+    pub fn get_cw_signals_ptr(&self) -> *const f32 {
+        self.cw_signals.as_ptr()
+    }
+
+    /// Start a receiver slice at a mixer offset (as the
+    /// `frequencyOffset` setting) with a [`DemodMode`] code.
+    ///
+    /// The slice has its own filter (at the mode's default bandwidth) and
+    /// AGC, and is heard at full volume. Returns the slice id, or -1 if
+    /// the mode is unknown or all [`MAX_SLICES`] are in use.
+    #[wasm_bindgen]
+    pub fn add_slice(&mut self, offset_hz: f32, mode: u8) -> i32 {
+        let Some(mode) = DemodMode::from_code(mode) else {
+            return -1;
+        };
+        let Some(id) = self.slices.iter().position(Option::is_none) else {
+            return -1;
+        };
+        self.slices[id] = Some(ReceiverSlice::new(self.sample_rate, offset_hz, mode));
+        id as i32
+    }
+
+    /// Stop a receiver slice.
+    #[wasm_bindgen]
+    pub fn remove_slice(&mut self, id: u32) -> bool {
+        self.slices
+            .get_mut(id as usize)
+            .and_then(Option::take)
+            .is_some()
+    }
+
+    /// Get the number of running receiver slices.
+    #[wasm_bindgen]
+    pub fn get_slice_count(&self) -> usize {
+        self.slices.iter().flatten().count()
+    }
+
+    /// Set a slice's mixer offset in Hz.
+    #[wasm_bindgen]
+    pub fn set_slice_offset(&mut self, id: u32, offset_hz: f32) -> bool {
+        self.slice_mut(id).map(|slice| slice.set_offset(offset_hz)).is_some()
+    }
+
+    /// Get a slice's mixer offset in Hz (NaN if there is no such slice).
+    #[wasm_bindgen]
+    pub fn get_slice_offset(&self, id: u32) -> f32 {
+        self.slice(id).map_or(f32::NAN, ReceiverSlice::offset)
+    }
+
+    /// Set a slice's [`DemodMode`] code, resetting its bandwidth.
+    #[wasm_bindgen]
+    pub fn set_slice_mode(&mut self, id: u32, mode: u8) -> bool {
+        let Some(mode) = DemodMode::from_code(mode) else {
+            return false;
+        };
+        self.slice_mut(id).map(|slice| slice.set_mode(mode)).is_some()
+    }
+
+    /// Get a slice's [`DemodMode`] code (255 if there is no such slice).
+    #[wasm_bindgen]
+    pub fn get_slice_mode(&self, id: u32) -> u8 {
+        self.slice(id).map_or(u8::MAX, |slice| slice.mode().code())
+    }
+
+    /// Set a slice's audio filter bandwidth in Hz.
+    #[wasm_bindgen]
+    pub fn set_slice_bandwidth(&mut self, id: u32, bandwidth_hz: f32) -> bool {
+        self.slice_mut(id)
+            .map(|slice| slice.set_bandwidth(bandwidth_hz))
+            .is_some()
+    }
+
+    /// Get a slice's audio filter bandwidth in Hz (NaN if there is no such slice).
+    #[wasm_bindgen]
+    pub fn get_slice_bandwidth(&self, id: u32) -> f32 {
+        self.slice(id).map_or(f32::NAN, ReceiverSlice::bandwidth)
+    }
+
+    /// Set a slice's AGC attack, decay and hang times in milliseconds.
+    #[wasm_bindgen]
+    pub fn set_slice_agc(&mut self, id: u32, attack_ms: f32, decay_ms: f32, hang_ms: f32) -> bool {
+        self.slice_mut(id)
+            .map(|slice| slice.set_agc(attack_ms, decay_ms, hang_ms))
+            .is_some()
+    }
+
+    /// Set the level a slice is mixed into the output at (0 = not heard).
+    #[wasm_bindgen]
+    pub fn set_slice_volume(&mut self, id: u32, volume: f32) -> bool {
+        self.slice_mut(id).map(|slice| slice.set_volume(volume)).is_some()
+    }
+
+    /// Get the level a slice is mixed into the output at (NaN if there is no such slice).
+    #[wasm_bindgen]
+    pub fn get_slice_volume(&self, id: u32) -> f32 {
+        self.slice(id).map_or(f32::NAN, ReceiverSlice::volume)
+    }
+
+    /// Copy a slice's own audio from the last [`Self::process`] into `out`.
+    ///
+    /// Returns the number of samples copied (0 if there is no such slice).
+    #[wasm_bindgen]
+    pub fn read_slice_audio(&self, id: u32, out: &mut [f32]) -> usize {
+        let Some(slice) = self.slice(id) else {
+            return 0;
+        };
+        let count = out.len().min(slice.audio().len());
+        out[..count].copy_from_slice(&slice.audio()[..count]);
+        count
+    }
+
+    /// Reset processor state.
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.dc_blocker.reset();
+        self.iq_balance.reset();
+        self.blanker.reset();
+        self.nco.reset();
+        self.audio_filter.reset();
+        self.agc.reset();
+        self.nr.reset();
+        self.spectral_nr.reset();
+        for notch in &mut self.notches {
+            notch.reset();
+        }
+        self.equalizer.reset();
+        self.smeter.reset();
+        self.snr.reset();
+        self.spectrum.reset();
+        self.spectrum_averager.reset();
+        self.spectrum_peaks.clear();
+        self.input_resampler.reset();
+        self.psk_bank.reset();
+        self.psk_decoded.clear();
+        if let Some(sstv) = self.sstv.as_mut() {
+            sstv.reset();
+        }
+        self.sstv_vis = -1;
+        self.clear_sstv_lines();
+        if let Some(aprs) = self.aprs.as_mut() {
+            aprs.reset();
+        }
+        self.clear_aprs_frames();
+        if let Some(js8) = self.js8.as_mut() {
+            js8.reset();
+        }
+        self.clear_js8_frames();
+        if let Some(hell) = self.hell.as_mut() {
+            hell.reset();
+        }
+        self.clear_hell_columns();
+        if let Some(skimmer) = self.cw_skimmer.as_mut() {
+            skimmer.reset();
+        }
+        self.cw_signals.clear();
+        self.clear_cw_decoded();
+        for slice in self.slices.iter_mut().flatten() {
+            slice.reset();
+        }
+        self.frame_count = 0;
+        self.cpu_load = 0.0;
+    }
+}
+
+impl DspProcessor {
+    /// Apply a [`DspConfig`], as [`Self::apply_config`] does from JS.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid setting; nothing is changed.
+    pub fn apply(&mut self, config: &DspConfig) -> Result<(), ConfigError> {
+        let mode = config
+            .mode
+            .map(|code| DemodMode::from_code(code).ok_or(ConfigError::UnknownMode(code)))
+            .transpose()?;
+        let spectrum = config.spectrum.unwrap_or_default();
+        if let Some(size) = spectrum.size.filter(|&size| !valid_spectrum_size(size)) {
+            return Err(ConfigError::SpectrumSize(size));
+        }
+        let waterfall = config.waterfall.unwrap_or_default();
+        let waterfall_scale = waterfall
+            .scale
+            .map(|code| {
+                WaterfallScale::from_code(code).ok_or(ConfigError::UnknownWaterfallScale(code))
+            })
+            .transpose()?;
+        let notches = config.notches.as_deref().unwrap_or_default();
+        if notches.len() > NOTCH_COUNT {
+            return Err(ConfigError::NotchCount(notches.len()));
+        }
+        let eq_gains = match config.eq.as_ref().and_then(|eq| eq.gains.as_deref()) {
+            Some(gains) => Some(
+                <[f32; EQ_BANDS]>::try_from(gains)
+                    .map_err(|_| ConfigError::EqBandCount(gains.len()))?,
+            ),
+            None => None,
+        };
+
+        if let Some(mode) = mode {
+            self.set_mode(mode.code());
+        }
+        if let Some(offset) = config.frequency_offset {
+            self.set_frequency_offset(offset);
+        }
+        if let Some(offset) = config.fine_tune {
+            self.set_fine_tune(offset);
+        }
+        if let Some(bandwidth) = config.filter_bandwidth {
+            self.set_filter_bandwidth(bandwidth);
+        }
+        if let Some(shift) = config.if_shift {
+            self.set_if_shift(shift);
+        }
+        if let Some(agc) = config.agc {
+            let current = self.agc_config;
+            self.set_agc(
+                agc.attack_ms.unwrap_or(current.attack_ms),
+                agc.decay_ms.unwrap_or(current.decay_ms),
+                agc.hang_ms.unwrap_or(current.hang_ms),
+                agc.hang_threshold_db.unwrap_or(f32::NAN),
+                agc.slope_db.unwrap_or(f32::NAN),
+                agc.max_gain_db.unwrap_or(f32::NAN),
+                agc.dual_rate.unwrap_or(current.dual_rate),
+            );
+        }
+        if let Some(nr) = config.nr {
+            if let Some(level) = nr.level {
+                self.nr.set_level(level);
+            }
+            if let Some(strength) = nr.spectral {
+                self.spectral_nr.set_strength(strength);
+            }
+        }
+        if let Some(nb) = config.nb {
+            if let Some(threshold) = nb.threshold {
+                self.blanker.set_threshold(threshold);
+            }
+            if let Some(width) = nb.width {
+                self.blanker.set_width(width);
+            }
+            if let Some(enabled) = nb.enabled {
+                self.blanker.set_enabled(enabled);
+            }
+        }
+        if let Some(iq) = config.iq {
+            self.set_iq_balance(
+                iq.gain_db.unwrap_or(self.iq_balance.gain_db()),
+                iq.phase_deg.unwrap_or(self.iq_balance.phase_deg()),
+            );
+            if let Some(adaptive) = iq.adaptive {
+                self.iq_balance.set_adaptive(adaptive);
+            }
+        }
+        for (index, settings) in (0..).zip(notches) {
+            let notch = &self.notches[index as usize];
+            self.set_notch(
+                index,
+                settings.frequency.unwrap_or(notch.frequency()),
+                settings.width.unwrap_or(notch.width()),
+            );
+            if let Some(enabled) = settings.enabled {
+                self.set_notch_enabled(index, enabled);
+            }
+        }
+        if let Some(gains) = eq_gains {
+            self.equalizer.set_gains(&gains);
+        }
+        if let Some(enabled) = config.eq.as_ref().and_then(|eq| eq.enabled) {
+            self.equalizer.set_enabled(enabled);
+        }
+        if let Some(squelch) = config.squelch {
+            if let Some(threshold) = squelch.threshold_dbm {
+                self.squelch.set_threshold_db(threshold);
+            }
+            if let Some(enabled) = squelch.enabled {
+                self.squelch.set_enabled(enabled);
+            }
+        }
+        if let Some(size) = spectrum.size {
+            self.set_spectrum_size(size);
+        }
+        if let Some(averaging) = spectrum.averaging {
+            self.spectrum_averager.set_averaging(averaging.max(1));
+        }
+        if let Some(gain) = spectrum.gain_db {
+            self.spectrum_averager.set_gain_db(gain);
+        }
+        if let Some(width) = spectrum.dc_notch_hz {
+            self.set_spectrum_dc_notch(width);
+        }
+        if let Some(count) = spectrum.max_peaks {
+            self.set_max_peaks(count);
+        }
+        if let Some(threshold) = spectrum.peak_threshold_db {
+            self.set_peak_threshold(threshold);
+        }
+        if waterfall.ref_level_db.is_some() || waterfall.range_db.is_some() {
+            self.set_waterfall_levels(
+                waterfall.ref_level_db.unwrap_or(self.waterfall_range.ref_db()),
+                waterfall.range_db.unwrap_or(self.waterfall_range.range_db),
+            );
+        }
+        if let Some(scale) = waterfall_scale {
+            self.set_waterfall_scale(scale.code());
+        }
+        if let Some(auto) = waterfall.auto_level {
+            self.set_waterfall_auto_level(auto);
+        }
+        Ok(())
+    }
+
+    /// Get the current meter readings, as [`Self::get_status`] does for JS.
+    #[must_use]
+    pub fn status(&self) -> DspStatus {
+        DspStatus {
+            smeter: self.smeter_value,
+            smeter_dbm: self.smeter.dbm(),
+            agc_gain_db: 20.0 * self.agc.gain().max(1e-10).log10(),
+            snr_db: self.get_snr_db(),
+            image_rejection_db: self.get_image_rejection_db(),
+            squelch_open: self.squelch.is_open(),
+            cpu_load: self.cpu_load,
+            frame_count: self.frame_count,
+        }
+    }
+
+    /// Fold the time taken for a block of input pairs into the CPU load.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn update_cpu_load(&mut self, elapsed_ms: f64, pairs: usize) {
+        if pairs == 0 {
+            return;
+        }
+        let block_ms = pairs as f64 * 1000.0 / f64::from(self.input_rate);
+        let load = (elapsed_ms / block_ms) as f32;
+        self.cpu_load += CPU_LOAD_SMOOTHING * (load - self.cpu_load);
+    }
+
+    /// Audio passband edges in Hz: the filter bandwidth moved by the IF shift.
+    fn passband(&self) -> (f32, f32) {
+        let nyquist = self.sample_rate / 2.0;
+        let low = self.if_shift.clamp(0.0, nyquist - MIN_PASSBAND_WIDTH_HZ);
+        let high = (self.if_shift + self.filter_bandwidth)
+            .clamp(low + MIN_PASSBAND_WIDTH_HZ, nyquist);
+        (low, high)
+    }
+
+    /// Redesign the audio filter for the passband.
+    fn update_audio_filter(&mut self) {
+        let (low, high) = self.passband();
+        let mut taps = [0.0; AUDIO_FILTER_TAPS];
+        bandpass_taps(self.sample_rate, low, high, &mut taps);
+        self.audio_filter.set_taps(&taps);
+        self.snr.set_passband(low, high);
+    }
+
+    /// List the strongest peaks of the spectrum frame.
+    fn update_peaks(&mut self) {
+        self.spectrum_peaks.clear();
+        let mut peaks = [SpectrumPeak::default(); MAX_PEAKS];
+        let bins = &self.spectrum_buffer[..self.spectrum_buffer.len() / 2];
+        let bin_width = self.sample_rate / self.spectrum_buffer.len() as f32;
+        let count =
+            find_peaks(bins, bin_width, self.peak_threshold_db, &mut peaks[..self.max_peaks]);
+        for peak in &peaks[..count] {
+            self.spectrum_peaks
+                .extend_from_slice(&[peak.frequency_hz, peak.level_db, peak.width_hz]);
+        }
+    }
+
+    /// Choose the waterfall levels and map the spectrum onto the color map.
+    fn update_waterfall(&mut self) {
+        let auto = self
+            .waterfall_auto
+            .then(|| {
+                let floor = self.spectrum_averager.noise_floor_db()?;
+                let peak = self.spectrum_averager.peak_db()?;
+                Some(DisplayRange::auto(floor, peak))
+            })
+            .flatten();
+        self.waterfall_levels = auto.unwrap_or(self.waterfall_range);
+        let scale = self.waterfall_scale.into();
+        for (out, &dbm) in self.waterfall_buffer.iter_mut().zip(&self.spectrum_buffer) {
+            *out = self.waterfall_levels.normalize_scaled(dbm, scale);
+        }
+    }
+
+    /// Size the spectrum DC notch for the FFT; DC is the first bin.
+    fn update_dc_notch(&mut self) {
+        let notch = (self.spectrum_dc_notch_hz > 0.0).then(|| {
+            let bin_width = self.sample_rate / self.spectrum_buffer.len() as f32;
+            DcNotch::from_hz(0, self.spectrum_dc_notch_hz, bin_width)
+        });
+        self.spectrum_averager.set_dc_notch(notch);
+    }
+
+    /// Get a running slice by id.
+    fn slice(&self, id: u32) -> Option<&ReceiverSlice> {
+        self.slices.get(id as usize)?.as_ref()
+    }
+
+    /// Get a running slice by id, mutably.
+    fn slice_mut(&mut self, id: u32) -> Option<&mut ReceiverSlice> {
+        self.slices.get_mut(id as usize)?.as_mut()
+    }
+
+    /// Run interleaved I/Q through [`Self::process`] a buffer at a time,
+    /// passing each block of audio to `sink`.
+    fn process_blocks(&mut self, iq: &[f32], mut sink: impl FnMut(&[f32])) {
+        for block in iq.chunks(self.buffer_size * 2) {
+            let pairs = self.write_input(block);
+            self.process(pairs);
+            sink(&self.output_buffer[..self.output_len]);
+        }
+    }
+
+    /// Allocate the buffers for the buffer size and input rate.
+    fn resize_buffers(&mut self) {
+        let output_len = self
+            .input_resampler
+            .max_output(self.buffer_size)
+            .max(self.buffer_size);
+        self.input_buffer = vec![0.0; self.buffer_size * 2];
+        self.output_buffer = vec![0.0; output_len];
+        self.output_len = 0;
+        self.iq_block = Vec::with_capacity(output_len);
+        self.resampled = Vec::with_capacity(output_len);
+    }
+
+    /// Set operating mode from a [`DemodMode`] code.
+    ///
+    /// Returns `false` and leaves the mode unchanged if the code is unknown.
+    fn set_mode(&mut self, mode: u8) -> bool {
+        let Some(mode) = DemodMode::from_code(mode) else {
+            return false;
+        };
+        self.mode = mode;
+        self.equalizer.set_gains(&equalizer::rx_default_curve(mode));
+
+        // Adjust filter bandwidth based on mode, with the passband centered
+        self.if_shift = 0.0;
+        self.set_filter_bandwidth(mode.default_bandwidth());
+        true
+    }
+
+    /// Set frequency offset for mixing.
+    ///
+    /// The mixer runs at this plus the fine tuning offset.
+    fn set_frequency_offset(&mut self, offset_hz: f32) {
+        self.freq_offset = offset_hz;
+        self.nco.set_frequency(offset_hz + self.fine_tune);
+    }
+
+    /// Set the fine tuning (RIT) offset in Hz, within [`MAX_FINE_TUNE_HZ`].
+    ///
+    /// Kept apart from the frequency offset so RIT and passband tuning
+    /// can be cleared without losing the tuned frequency. The mixer
+    /// keeps its phase, so the audio does not click as it moves.
+    fn set_fine_tune(&mut self, offset_hz: f32) {
+        self.fine_tune = offset_hz.clamp(-MAX_FINE_TUNE_HZ, MAX_FINE_TUNE_HZ);
+        self.nco.set_frequency(self.freq_offset + self.fine_tune);
+    }
+
+    /// Set filter bandwidth in Hz.
+    ///
+    /// This is the passband width; any IF shift is kept.
+    fn set_filter_bandwidth(&mut self, bandwidth_hz: f32) {
+        self.filter_bandwidth = bandwidth_hz;
+        self.update_audio_filter();
+    }
+
+    /// Set the IF shift in Hz, within [`MAX_IF_SHIFT_HZ`].
+    ///
+    /// Slides the audio passband, normally 0 Hz to the filter bandwidth,
+    /// up or down to move it off an interfering signal without retuning.
+    /// A shift below zero narrows the passband from the top instead, since
+    /// its lower edge cannot go below 0 Hz. A mode change clears it.
+    fn set_if_shift(&mut self, shift_hz: f32) {
+        self.if_shift = shift_hz.clamp(-MAX_IF_SHIFT_HZ, MAX_IF_SHIFT_HZ);
+        self.update_audio_filter();
+    }
+
+    /// Tune a manual notch (0 to [`NOTCH_COUNT`] - 1) to a frequency and width in Hz.
+    ///
+    /// Tuning does not switch the notch on. Returns `false` if there is no
+    /// such notch.
+    fn set_notch(&mut self, index: u32, frequency_hz: f32, width_hz: f32) -> bool {
+        let Some(notch) = self.notches.get_mut(index as usize) else {
+            return false;
+        };
+        notch.tune(frequency_hz, width_hz);
+        true
+    }
+
+    /// Switch a manual notch on or off. Returns `false` if there is no such notch.
+    fn set_notch_enabled(&mut self, index: u32, enabled: bool) -> bool {
+        let Some(notch) = self.notches.get_mut(index as usize) else {
+            return false;
+        };
+        notch.set_enabled(enabled);
+        true
+    }
+
+    /// Set the IQ balance correction: the Q gain error in dB (within
+    /// ±3 dB) and the quadrature error in degrees (within ±10 degrees).
+    ///
+    /// The UI keeps these per band, as the front end's imbalance changes
+    /// with frequency, and sets them on each band change.
+    fn set_iq_balance(&mut self, gain_db: f32, phase_deg: f32) {
+        self.iq_balance
+            .set_calibration(IqCalibration::from_db(gain_db, phase_deg));
+    }
+
+    /// Set AGC parameters.
+    ///
+    /// Besides the time constants this sets the hang threshold (dBFS),
+    /// the output slope (dB per 10 dB above the knee), the maximum gain
+    /// (dB) and the dual-rate detector. A NaN hang threshold, slope or
+    /// maximum gain keeps the current setting.
+    #[allow(clippy::too_many_arguments)]
+    fn set_agc(
+        &mut self,
+        attack_ms: f32,
+        decay_ms: f32,
+        hang_ms: f32,
+        hang_threshold_db: f32,
+        slope_db: f32,
+        max_gain_db: f32,
+        dual_rate: bool,
+    ) {
+        let keep = |value: f32, current: f32| if value.is_nan() { current } else { value };
+        let mut config = AgcConfig {
+            attack_ms,
+            decay_ms,
+            hang_ms,
+            hang_threshold_db: keep(hang_threshold_db, self.agc_config.hang_threshold_db),
+            slope_db: keep(slope_db, self.agc_config.slope_db).max(0.0),
+            dual_rate,
+            ..self.agc_config
+        };
+        if !max_gain_db.is_nan() {
+            config = config.with_max_gain_db(max_gain_db);
+        }
+        self.agc_config = config;
+        self.agc.set_config(self.agc_config);
+    }
+
+    /// Set the spectrum FFT size, a power of two from [`MIN_SPECTRUM_SIZE`] to `MAX_BINS`.
+    ///
+    /// Reallocates the spectrum buffer, so its pointer must be fetched
+    /// again, and restarts spectrum averaging. Returns `false` and
+    /// changes nothing if the size is not allowed.
+    fn set_spectrum_size(&mut self, size: usize) -> bool {
+        if !valid_spectrum_size(size) {
+            return false;
+        }
+        self.spectrum = FftSpectrum::new(size);
+        self.spectrum_averager.reset();
+        self.spectrum_buffer = vec![0.0; size];
+        self.waterfall_buffer = vec![0.0; size / 2];
+        self.spectrum_peaks.clear();
+        self.update_dc_notch();
+        true
+    }
+
+    /// Set the spectrum DC notch width in Hz either side of DC (0 = off).
+    ///
+    /// The bins it covers are interpolated over and left out of the
+    /// noise floor, so the detector's DC spike neither shows on the
+    /// waterfall nor drives its autoscaling.
+    fn set_spectrum_dc_notch(&mut self, width_hz: f32) {
+        self.spectrum_dc_notch_hz = width_hz.max(0.0);
+        self.update_dc_notch();
+    }
+
+    /// Set the waterfall levels: reference (brightest) level in dBm and
+    /// span in dB down to the darkest.
+    ///
+    /// Used as set unless auto-level is on.
+    fn set_waterfall_levels(&mut self, ref_dbm: f32, range_db: f32) {
+        self.waterfall_range = DisplayRange::new(ref_dbm, range_db);
+        self.update_waterfall();
+    }
+
+    /// Set the waterfall scaling by [`WaterfallScale`] code.
+    ///
+    /// Returns `false` and changes nothing for an unknown code.
+    fn set_waterfall_scale(&mut self, scale: u8) -> bool {
+        let Some(scale) = WaterfallScale::from_code(scale) else {
+            return false;
+        };
+        self.waterfall_scale = scale;
+        self.update_waterfall();
+        true
+    }
+
+    /// Switch waterfall auto-level on or off.
+    ///
+    /// When on, the darkest level sits just below the spectrum noise
+    /// floor and the span follows the strongest signal, frame by frame.
+    fn set_waterfall_auto_level(&mut self, auto: bool) {
+        self.waterfall_auto = auto;
+        self.update_waterfall();
+    }
+
+    /// Set the most spectrum peaks listed per frame (0 = off, at most `MAX_PEAKS`).
+    fn set_max_peaks(&mut self, count: usize) {
+        self.max_peaks = count.min(MAX_PEAKS);
+        self.spectrum_peaks.truncate(self.max_peaks * PEAK_FIELDS);
+    }
+
+    /// Set how far above the noise floor a peak must stand, in dB.
+    fn set_peak_threshold(&mut self, threshold_db: f32) {
+        self.peak_threshold_db = threshold_db.max(0.0);
+    }
+}
+
+/// Create a new DSP processor (factory function).
+#[wasm_bindgen]
+pub fn create_processor(sample_rate: f32) -> DspProcessor {
+    DspProcessor::new(sample_rate)
+}
+
+/// Design the audio low-pass filter taps for a bandwidth.
+fn audio_filter_taps(sample_rate: f32, bandwidth_hz: f32) -> [f32; AUDIO_FILTER_TAPS] {
+    let mut taps = [0.0; AUDIO_FILTER_TAPS];
+    bandpass_taps(sample_rate, 0.0, bandwidth_hz, &mut taps);
+    taps
+}
+
+/// Demodulate mixed IQ to audio for a mode.
+fn demodulate(mode: DemodMode, iq: &[IqSample], audio: &mut [f32]) {
+    match mode {
+        DemodMode::Lsb => demod::lsb(iq, audio),
+        // CW is USB with a narrow filter
+        DemodMode::Usb | DemodMode::Cw => demod::usb(iq, audio),
+        DemodMode::Am => demod::am(iq, audio),
+        DemodMode::Fm => demod::fm(iq, audio),
+    }
+}
+
+/// Check a spectrum FFT size: a power of two from [`MIN_SPECTRUM_SIZE`] to `MAX_BINS`.
+fn valid_spectrum_size(size: usize) -> bool {
+    size.is_power_of_two() && (MIN_SPECTRUM_SIZE..=MAX_BINS).contains(&size)
+}
+
+/// Current time in milliseconds, for the CPU load estimate.
+///
+/// `Date.now()` only ticks in whole milliseconds, but the block start
+/// falls at random within a tick, so the average over many blocks is
+/// still right.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Current time in milliseconds, for the CPU load estimate.
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    START.get_or_init(std::time::Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iq_file_playback_and_record() {
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.start_iq_record(100);
+
+        // One second of a 1 kHz tone at 24 kHz, upsampled to 48 kHz
+        dsp.set_input_rate(24000.0);
+        let iq: Vec<f32> = (0..24000)
+            .flat_map(|n| {
+                let phase = 2.0 * core::f32::consts::PI * 1000.0 * n as f32 / 24000.0;
+                [0.5 * phase.cos(), 0.5 * phase.sin()]
+            })
+            .collect();
+        let mut audio = vec![0.0; 60000];
+        let produced = dsp.process_iq(&iq, &mut audio);
+        assert!((47_990..=48_000).contains(&produced), "{produced}");
+        assert!(audio[1000..produced].iter().any(|s| s.abs() > 0.01));
+
+        // Recording stops at the limit
+        assert_eq!(dsp.get_iq_record_len(), 100);
+        dsp.stop_iq_record();
+        let raw = dsp.take_iq_record_s16();
+        assert_eq!(raw.len(), 400);
+        assert_eq!(dsp.get_iq_record_len(), 0);
+    }
+
+    #[test]
+    fn test_buffer_sizes_and_input_rate() {
+        let mut dsp = DspProcessor::new(48000.0);
+        assert!(!dsp.set_buffer_size(0));
+        assert!(!dsp.set_buffer_size(MAX_BUFFER_SIZE + 1));
+        assert!(dsp.set_buffer_size(480));
+        assert_eq!(dsp.get_buffer_size(), 480);
+        assert!(!dsp.set_spectrum_size(300));
+        assert!(!dsp.set_spectrum_size(MIN_SPECTRUM_SIZE / 2));
+        assert!(dsp.set_spectrum_size(256));
+        assert_eq!(dsp.get_spectrum_size(), 256);
+
+        // A whole buffer at the processor rate comes out one for one
+        dsp.input_buffer.fill(0.1);
+        dsp.process(1000);
+        assert_eq!(dsp.get_output_len(), 480);
+
+        // 96 kHz input is halved, 24 kHz doubled
+        dsp.set_input_rate(96000.0);
+        assert_eq!(dsp.get_input_rate(), 96000.0);
+        let mut produced = 0;
+        for _ in 0..10 {
+            dsp.process(480);
+            produced += dsp.get_output_len();
+        }
+        assert!((2399..=2401).contains(&produced), "{produced}");
+        dsp.set_input_rate(24000.0);
+        dsp.process(480);
+        assert!(dsp.get_output_len() >= 959 && dsp.get_output_len() <= dsp.output_buffer.len());
+
+        // The spectrum fills at the new size
+        for _ in 0..4 {
+            dsp.process(480);
+        }
+        assert!(dsp.spectrum_buffer[..128].iter().any(|&bin| bin != 0.0));
+    }
+
+    #[test]
+    fn test_slice_buffers_match_process_iq() {
+        let iq: Vec<f32> = (0..3000).map(|n| ((n * 37 % 101) as f32 - 50.0) / 500.0).collect();
+
+        let mut by_slices = DspProcessor::new(48000.0);
+        assert_eq!(by_slices.write_input(&iq[..7]), 3);
+        assert_eq!(by_slices.write_input(&iq[..1024]), BUFFER_SIZE);
+        by_slices.process(BUFFER_SIZE);
+        let mut out = [0.0; BUFFER_SIZE + 10];
+        assert_eq!(by_slices.read_output(&mut out), BUFFER_SIZE);
+        let mut bins = [0.0; SPECTRUM_SIZE];
+        assert_eq!(by_slices.read_spectrum(&mut bins), SPECTRUM_SIZE / 2);
+
+        let mut by_iq = DspProcessor::new(48000.0);
+        let mut audio = [0.0; BUFFER_SIZE];
+        assert_eq!(by_iq.process_iq(&iq[..BUFFER_SIZE * 2], &mut audio), BUFFER_SIZE);
+        assert_eq!(&out[..BUFFER_SIZE], &audio[..]);
+
+        // process_samples returns everything, across buffers
+        let mut whole = DspProcessor::new(48000.0);
+        whole.set_input_rate(96000.0);
+        let samples = whole.process_samples(&iq);
+        assert!((749..=751).contains(&samples.len()), "{}", samples.len());
+    }
+
+    #[test]
+    fn test_apply_config_and_status() {
+        let mut dsp = DspProcessor::new(48000.0);
+        let config = DspConfig {
+            mode: Some(DemodMode::Lsb.code()),
+            filter_bandwidth: Some(2400.0),
+            agc: Some(config::AgcSettings {
+                decay_ms: Some(800.0),
+                dual_rate: Some(true),
+                ..Default::default()
+            }),
+            nr: Some(config::NrSettings {
+                level: Some(4),
+                spectral: Some(3),
+            }),
+            squelch: Some(config::SquelchSettings {
+                enabled: Some(true),
+                threshold_dbm: Some(-20.0),
+            }),
+            spectrum: Some(config::SpectrumSettings {
+                size: Some(256),
+                averaging: Some(4),
+                gain_db: None,
+                dc_notch_hz: Some(200.0),
+                max_peaks: Some(4),
+                peak_threshold_db: None,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        assert_eq!(dsp.get_mode(), DemodMode::Lsb.code());
+        assert_eq!(dsp.get_parameter(Parameter::FilterBandwidth as u8), 2400.0);
+        assert_eq!(dsp.get_parameter(Parameter::AgcDecay as u8), 800.0);
+        assert_eq!(dsp.get_parameter(Parameter::AgcDualRate as u8), 1.0);
+        assert_eq!(dsp.get_parameter(Parameter::AgcAttack as u8), AgcConfig::medium().attack_ms);
+        assert_eq!(dsp.nr.level(), 4);
+        assert_eq!(dsp.get_parameter(Parameter::SpectralNr as u8), 3.0);
+        assert!(dsp.set_parameter(Parameter::SpectralNr as u8, 50.0));
+        assert_eq!(dsp.spectral_nr.strength(), sdr_dsp_core::nr::MAX_SPECTRAL_STRENGTH);
+        assert_eq!(dsp.get_spectrum_size(), 256);
+        assert_eq!(dsp.get_parameter(Parameter::SpectrumDcNotch as u8), 200.0);
+        assert_eq!(dsp.get_max_peaks(), 4);
+
+        // A weak signal stays below the squelch: silence, and the status says so
+        dsp.input_buffer.fill(0.01);
+        dsp.process(BUFFER_SIZE);
+        assert!(dsp.output_buffer[..dsp.get_output_len()].iter().all(|&s| s == 0.0));
+        let status = dsp.status();
+        assert!(!status.squelch_open);
+        assert_eq!(status.frame_count, 1);
+        assert!(status.cpu_load >= 0.0);
+
+        // Invalid settings are rejected before anything changes
+        let bad = DspConfig {
+            filter_bandwidth: Some(500.0),
+            spectrum: Some(config::SpectrumSettings {
+                size: Some(100),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&bad), Err(ConfigError::SpectrumSize(100)));
+        let bad_mode = DspConfig {
+            mode: Some(9),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&bad_mode), Err(ConfigError::UnknownMode(9)));
+        assert_eq!(dsp.get_parameter(Parameter::FilterBandwidth as u8), 2400.0);
+    }
+
+    #[test]
+    fn test_receiver_slices() {
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.set_frequency_offset(1500.0);
+        assert_eq!(dsp.add_slice(0.0, 9), -1);
+        let id = dsp.add_slice(1500.0, DemodMode::Usb.code());
+        assert_eq!(id, 0);
+        let id = id as u32;
+        assert!(dsp.set_slice_volume(id, 0.0));
+        assert!(dsp.set_slice_bandwidth(id, dsp.filter_bandwidth));
+
+        // A slice set up like the main receiver hears the same audio
+        let iq: Vec<f32> = (0..BUFFER_SIZE * 2)
+            .map(|n| ((n * 37 % 101) as f32 - 50.0) / 500.0)
+            .collect();
+        let mut main = [0.0; BUFFER_SIZE];
+        let mut slice = [0.0; BUFFER_SIZE];
+        for _ in 0..8 {
+            dsp.write_input(&iq);
+            dsp.process(BUFFER_SIZE);
+            assert_eq!(dsp.read_output(&mut main), BUFFER_SIZE);
+            assert_eq!(dsp.read_slice_audio(id, &mut slice), BUFFER_SIZE);
+            assert_eq!(main, slice);
+        }
+        assert!(slice.iter().any(|&s| s != 0.0));
+
+        // Heard at full volume it is added to the output
+        assert!(dsp.set_slice_volume(id, 1.0));
+        dsp.write_input(&iq);
+        dsp.process(BUFFER_SIZE);
+        dsp.read_output(&mut main);
+        dsp.read_slice_audio(id, &mut slice);
+        assert!(main.iter().zip(&slice).all(|(m, s)| (m - 2.0 * s).abs() < 1e-6));
+
+        assert!(dsp.set_slice_mode(id, DemodMode::Cw.code()));
+        assert_eq!(dsp.get_slice_bandwidth(id), DemodMode::Cw.default_bandwidth());
+        assert!(dsp.set_slice_offset(id, 700.0));
+        assert_eq!(dsp.get_slice_offset(id), 700.0);
+
+        // Slots run out, and free up again
+        for _ in 1..MAX_SLICES {
+            assert!(dsp.add_slice(0.0, DemodMode::Lsb.code()) >= 0);
+        }
+        assert_eq!(dsp.add_slice(0.0, DemodMode::Lsb.code()), -1);
+        assert!(dsp.remove_slice(id));
+        assert!(!dsp.remove_slice(id));
+        assert!(!dsp.set_slice_volume(id, 1.0));
+        assert_eq!(dsp.get_slice_count(), MAX_SLICES - 1);
+        assert_eq!(dsp.add_slice(0.0, DemodMode::Am.code()), id as i32);
+    }
+
+    #[test]
+    fn test_tx_processor() {
+        let mut tx = TxProcessor::new(48000.0);
+        assert!(!tx.set_mode(DemodMode::Am.code()));
+        tx.set_compression_db(6.0);
+        tx.set_mic_gain_db(-6.0);
+        assert!((tx.get_mic_gain_db() + 6.0).abs() < 1e-4);
+
+        let audio: Vec<f32> = (0..4800)
+            .map(|n| 0.5 * (core::f32::consts::TAU * 1000.0 * n as f32 / 48000.0).sin())
+            .collect();
+        for (mode, sign) in [(DemodMode::Usb, 1.0), (DemodMode::Lsb, -1.0)] {
+            assert!(tx.set_mode(mode.code()));
+            tx.reset();
+            let iq = tx.process_samples(&audio);
+            assert_eq!(iq.len(), audio.len() * 2);
+
+            // The carrier turns one way for USB and the other for LSB
+            let pairs: Vec<IqSample> = iq
+                .chunks_exact(2)
+                .map(|p| IqSample::new(p[0], p[1]))
+                .collect();
+            let rotation: f32 = pairs[2400..]
+                .windows(2)
+                .map(|w| w[0].i * w[1].q - w[0].q * w[1].i)
+                .sum();
+            assert!(rotation * sign > 0.0, "{mode:?}: {rotation}");
+        }
+    }
+
+    #[test]
+    fn test_fine_tune() {
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.set_frequency_offset(1500.0);
+        dsp.set_fine_tune(-120.0);
+        assert!((dsp.nco.frequency() - 1380.0).abs() < 1e-2);
+        assert_eq!(dsp.get_parameter(Parameter::FrequencyOffset as u8), 1500.0);
+
+        // Retuning keeps the fine tuning on top
+        dsp.set_frequency_offset(1000.0);
+        assert!((dsp.nco.frequency() - 880.0).abs() < 1e-2);
+        assert_eq!(dsp.step_fine_tune(3), -90.0);
+        dsp.set_fine_tune_step(100.0);
+        assert_eq!(dsp.step_fine_tune(-1), -190.0);
+        assert_eq!(dsp.step_fine_tune(1000), MAX_FINE_TUNE_HZ);
+
+        // The mixer phase carries on through a step
+        let phase = dsp.nco.phase();
+        dsp.set_fine_tune(0.0);
+        assert_eq!(dsp.nco.phase(), phase);
+        assert_eq!(dsp.get_fine_tune(), 0.0);
+    }
+
+    #[test]
+    fn test_if_shift() {
+        /// Power out of the audio filter for a steady tone, after it settles.
+        fn tone_power(dsp: &mut DspProcessor, hz: f32) -> f32 {
+            dsp.audio_filter.reset();
+            let mut audio: Vec<f32> = (0..4096)
+                .map(|n| (core::f32::consts::TAU * hz * n as f32 / 48000.0).sin())
+                .collect();
+            dsp.audio_filter.process_block(&mut audio);
+            audio[2048..].iter().map(|s| s * s).sum()
+        }
+
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.set_mode(DemodMode::Usb.code());
+        assert_eq!((dsp.get_passband_low(), dsp.get_passband_high()), (0.0, 2700.0));
+        let centered = tone_power(&mut dsp, 2500.0);
+
+        // Shifting down drops a tone near the top edge out of the passband
+        assert!(dsp.set_parameter(Parameter::IfShift as u8, -1200.0));
+        assert_eq!(dsp.get_passband_high(), 1500.0);
+        let shifted = tone_power(&mut dsp, 2500.0);
+        assert!(shifted * 100.0 < centered, "{shifted} vs {centered}");
+
+        // Centering keeps the width, within the shift limit
+        dsp.set_passband_center(1600.0);
+        assert_eq!(dsp.get_if_shift(), 250.0);
+        assert_eq!((dsp.get_passband_low(), dsp.get_passband_high()), (250.0, 2950.0));
+        dsp.set_filter_bandwidth(500.0);
+        assert_eq!(dsp.get_passband_center(), 500.0);
+        dsp.set_passband_center(5000.0);
+        assert_eq!(dsp.get_if_shift(), MAX_IF_SHIFT_HZ);
+
+        // The dial is untouched, and a mode change centers the passband again
+        assert_eq!(dsp.get_parameter(Parameter::FrequencyOffset as u8), 1500.0);
+        dsp.set_mode(DemodMode::Lsb.code());
+        assert_eq!(dsp.get_parameter(Parameter::IfShift as u8), 0.0);
+    }
+
+    #[test]
+    fn test_dual_notch() {
+        /// Peak audio out of the notches for two tones, after they settle.
+        fn two_tone_peak(dsp: &mut DspProcessor) -> f32 {
+            let mut audio: Vec<f32> = (0..9600)
+                .map(|n| {
+                    let t = n as f32 / 48000.0;
+                    (core::f32::consts::TAU * 800.0 * t).sin()
+                        + (core::f32::consts::TAU * 1900.0 * t).sin()
+                })
+                .collect();
+            for notch in &mut dsp.notches {
+                notch.reset();
+                notch.process_block(&mut audio);
+            }
+            audio[4800..].iter().fold(0.0, |peak, s| s.abs().max(peak))
+        }
+
+        let mut dsp = DspProcessor::new(48000.0);
+        let open = two_tone_peak(&mut dsp);
+        assert!(dsp.set_notch(0, 800.0, 50.0));
+        assert!(dsp.set_notch_enabled(0, true));
+        assert!(two_tone_peak(&mut dsp) > open * 0.4);
+
+        assert!(dsp.set_parameter(Parameter::Notch2Frequency as u8, 1900.0));
+        assert!(dsp.set_parameter(Parameter::Notch2Enabled as u8, 1.0));
+        let both = two_tone_peak(&mut dsp);
+        assert!(both * 10.0 < open, "{both} vs {open}");
+
+        assert_eq!(dsp.get_notch_width(1), notch::DEFAULT_NOTCH_WIDTH_HZ);
+        assert!(!dsp.set_notch(NOTCH_COUNT as u32, 1000.0, 100.0));
+        assert!(dsp.get_notch_frequency(NOTCH_COUNT as u32).is_nan());
+
+        // Settings objects address the notches in order
+        let config = DspConfig {
+            notches: Some(vec![
+                config::NotchSettings { enabled: Some(false), ..Default::default() },
+                config::NotchSettings { width: Some(5000.0), ..Default::default() },
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        assert!(!dsp.get_notch_enabled(0));
+        assert_eq!(dsp.get_notch_frequency(0), 800.0);
+        assert!(dsp.get_notch_enabled(1));
+        assert_eq!(dsp.get_parameter(Parameter::Notch2Width as u8), notch::MAX_NOTCH_WIDTH_HZ);
+        let config = DspConfig {
+            notches: Some(vec![config::NotchSettings::default(); NOTCH_COUNT + 1]),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Err(ConfigError::NotchCount(NOTCH_COUNT + 1)));
+    }
+
+    #[test]
+    fn test_noise_blanker() {
+        /// Largest audio error against the clean carrier.
+        fn impulse_error(blanker: bool) -> f32 {
+            let carrier = |n: usize| {
+                let phase = core::f32::consts::TAU * 1000.0 * n as f32 / 48000.0;
+                (0.01 * phase.cos(), 0.01 * phase.sin())
+            };
+            let mut clean = Vec::new();
+            let mut noisy = Vec::new();
+            for n in 0..24000 {
+                let (i, q) = carrier(n);
+                clean.extend_from_slice(&[i, q]);
+                // A short impulse every 100 ms
+                let impulse = n > 4800 && n % 4800 < 2;
+                noisy.extend_from_slice(&if impulse { [2.0, 2.0] } else { [i, q] });
+            }
+
+            let mut reference = DspProcessor::new(48000.0);
+            reference.set_parameter(Parameter::BlankerEnabled as u8, f32::from(u8::from(blanker)));
+            let mut dsp = DspProcessor::new(48000.0);
+            dsp.set_parameter(Parameter::BlankerEnabled as u8, f32::from(u8::from(blanker)));
+            let expected = reference.process_samples(&clean);
+            let audio = dsp.process_samples(&noisy);
+            audio.iter().zip(&expected).fold(0.0, |max, (a, b)| (a - b).abs().max(max))
+        }
+
+        let off = impulse_error(false);
+        let on = impulse_error(true);
+        assert!(on * 4.0 < off, "{on} vs {off}");
+
+        let mut dsp = DspProcessor::new(48000.0);
+        assert!(!dsp.get_noise_blanker());
+        assert!(dsp.set_parameter(Parameter::BlankerThreshold as u8, 6.0));
+        assert_eq!(dsp.get_noise_blanker_threshold(), 6.0);
+        let config = DspConfig {
+            nb: Some(config::NbSettings {
+                enabled: Some(true),
+                width: Some(250.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        assert!(dsp.get_noise_blanker());
+        assert_eq!(dsp.get_parameter(Parameter::BlankerWidth as u8), 250.0);
+    }
+
+    #[test]
+    fn test_iq_balance() {
+        /// A carrier at -6 kHz through a detector 0.7 dB and 2.5 degrees out.
+        fn imbalanced() -> Vec<f32> {
+            let (gain, phase) = (10f32.powf(0.7 / 20.0), 2.5f32.to_radians());
+            (0..48000)
+                .flat_map(|n| {
+                    let theta = -core::f32::consts::TAU * 6000.0 * n as f32 / 48000.0;
+                    [0.1 * theta.cos(), 0.1 * gain * (theta + phase).sin()]
+                })
+                .collect()
+        }
+
+        let mut dsp = DspProcessor::new(48000.0);
+        assert!(dsp.get_image_rejection_db().is_nan());
+        dsp.process_samples(&imbalanced());
+        let uncorrected = dsp.get_image_rejection_db();
+        assert!(uncorrected < 30.0, "{uncorrected}");
+
+        // Adapting finds the detector's errors, and they stay when stopped
+        assert!(dsp.set_parameter(Parameter::IqAdaptive as u8, 1.0));
+        dsp.process_samples(&imbalanced());
+        assert!(dsp.set_parameter(Parameter::IqAdaptive as u8, 0.0));
+        assert!((dsp.get_iq_gain_db() - 0.7).abs() < 0.05, "{}", dsp.get_iq_gain_db());
+        assert!((dsp.get_iq_phase_deg() - 2.5).abs() < 0.1, "{}", dsp.get_iq_phase_deg());
+        dsp.process_samples(&imbalanced());
+        assert!(dsp.status().image_rejection_db > 50.0);
+
+        // A stored calibration comes back through a settings object
+        let config = DspConfig {
+            iq: Some(config::IqSettings {
+                gain_db: Some(-0.25),
+                phase_deg: Some(1.5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        assert_eq!(dsp.get_parameter(Parameter::IqGain as u8), -0.25);
+        assert_eq!(dsp.get_iq_phase_deg(), 1.5);
+        assert!(!dsp.get_iq_adaptive());
+    }
+
+    #[test]
+    fn test_spectrum_dc_notch() {
+        // Noise: its magnitude has a mean, which lands in the DC bin
+        let mut seed = 1u32;
+        let noise: Vec<f32> = (0..2 * SPECTRUM_SIZE * 8)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+            })
+            .collect();
+
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.process_samples(&noise);
+        let floor = dsp.get_noise_floor();
+        assert!(dsp.spectrum_buffer[0] > floor + 20.0);
+
+        // 200 Hz covers three 93.75 Hz bins past DC, which take the next level
+        assert!(dsp.set_parameter(Parameter::SpectrumDcNotch as u8, 200.0));
+        assert_eq!(dsp.get_spectrum_dc_notch(), 200.0);
+        dsp.process_samples(&noise);
+        assert!(dsp.spectrum_buffer[..4].iter().all(|&bin| bin == dsp.spectrum_buffer[4]));
+        assert!(dsp.spectrum_buffer[0] < dsp.get_noise_floor() + 20.0);
+
+        // It follows the FFT size, and 0 turns it off
+        assert!(dsp.set_spectrum_size(128));
+        assert_eq!(dsp.spectrum_averager.dc_notch(), Some(DcNotch::new(0, 1)));
+        dsp.set_spectrum_dc_notch(0.0);
+        assert_eq!(dsp.spectrum_averager.dc_notch(), None);
+    }
+
+    #[test]
+    fn test_waterfall_levels() {
+        // A strong tone 6 kHz up in a little noise
+        let mut seed = 7u32;
+        let iq: Vec<f32> = (0..2 * SPECTRUM_SIZE * 8)
+            .map(|k| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = ((seed >> 8) as f32 / (1 << 24) as f32 - 0.5) * 1e-3;
+                let phase = core::f32::consts::TAU * 6000.0 * (k / 2) as f32 / 48000.0;
+                noise + 0.5 * if k % 2 == 0 { phase.cos() } else { phase.sin() }
+            })
+            .collect();
+
+        let mut dsp = DspProcessor::new(48000.0);
+        assert_eq!(dsp.get_waterfall_scale(), WaterfallScale::Log.code());
+        assert!(!dsp.set_waterfall_scale(9));
+        dsp.process_samples(&iq);
+        let mut waterfall = [0.0; SPECTRUM_SIZE / 2];
+        assert_eq!(dsp.read_waterfall(&mut waterfall), SPECTRUM_SIZE / 2);
+        assert!(waterfall.iter().all(|level| (0.0..=1.0).contains(level)));
+
+        // Fixed levels place each bin by its dBm
+        assert!(dsp.set_parameter(Parameter::WaterfallRefLevel as u8, -50.0));
+        assert!(dsp.set_parameter(Parameter::WaterfallRange as u8, 60.0));
+        assert_eq!(dsp.get_waterfall_ref_level(), -50.0);
+        let range = DisplayRange::new(-50.0, 60.0);
+        for bin in [0, 10, 64] {
+            let expected = range.normalize(dsp.spectrum_buffer[bin]);
+            assert!((dsp.waterfall_buffer[bin] - expected).abs() < 1e-6);
+        }
+
+        // A linear scale spreads the same levels lower
+        let log = dsp.waterfall_buffer.clone();
+        assert!(dsp.set_parameter(Parameter::WaterfallScale as u8, 0.0));
+        assert!(dsp.waterfall_buffer.iter().zip(&log).all(|(lin, log)| lin <= log));
+
+        // Auto-level puts the floor under the noise and the top at the peak
+        let config = DspConfig {
+            waterfall: Some(config::WaterfallSettings {
+                auto_level: Some(true),
+                scale: Some(WaterfallScale::Log.code()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        dsp.process_samples(&iq);
+        let floor = dsp.get_noise_floor();
+        let darkest = dsp.get_waterfall_ref_level() - dsp.get_waterfall_range();
+        let margin = sdr_dsp_core::spectrum::NOISE_MARGIN_DB;
+        assert!((darkest - (floor - margin)).abs() < 1e-3, "{darkest} {floor}");
+        let peak = dsp.waterfall_buffer.iter().copied().fold(0.0, f32::max);
+        assert!((peak - 1.0).abs() < 1e-6);
+        assert_eq!(dsp.get_parameter(Parameter::WaterfallAutoLevel as u8), 1.0);
+
+        let bad = DspConfig {
+            waterfall: Some(config::WaterfallSettings {
+                scale: Some(3),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&bad), Err(ConfigError::UnknownWaterfallScale(3)));
+    }
+
+    #[test]
+    fn test_spectrum_peaks() {
+        // A 3 kHz carrier on I alone: its magnitude, which the spectrum
+        // analyzes, is a rectified cosine with lines at 6, 12, 18 kHz
+        let iq: Vec<f32> = (0..SPECTRUM_SIZE * 8)
+            .flat_map(|n| {
+                let phase = core::f32::consts::TAU * 3000.0 * n as f32 / 48000.0;
+                [0.5 * phase.cos(), 0.0]
+            })
+            .collect();
+
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.set_spectrum_dc_notch(200.0);
+        dsp.process_samples(&iq);
+        let count = dsp.get_spectrum_peak_count();
+        assert!(count >= 2, "{count}");
+        let mut peaks = [0.0; MAX_PEAKS * PEAK_FIELDS];
+        assert_eq!(dsp.read_spectrum_peaks(&mut peaks), count * PEAK_FIELDS);
+        assert!((peaks[0] - 6000.0).abs() < 50.0, "{}", peaks[0]);
+        assert!(peaks[2] > 0.0);
+        assert!(peaks[1] >= peaks[PEAK_FIELDS + 1]);
+        assert!(peaks[..count * PEAK_FIELDS]
+            .chunks(PEAK_FIELDS)
+            .all(|peak| peak[1] > dsp.get_noise_floor() + DEFAULT_PEAK_THRESHOLD_DB));
+
+        // Only whole peaks are copied
+        let mut short = [0.0; PEAK_FIELDS + 1];
+        assert_eq!(dsp.read_spectrum_peaks(&mut short), PEAK_FIELDS);
+
+        // The list follows the limit, and 0 turns it off
+        assert!(dsp.set_parameter(Parameter::MaxPeaks as u8, 1.0));
+        dsp.process_samples(&iq);
+        assert_eq!(dsp.get_spectrum_peak_count(), 1);
+        dsp.set_max_peaks(0);
+        dsp.process_samples(&iq);
+        assert_eq!(dsp.get_spectrum_peak_count(), 0);
+        dsp.set_max_peaks(100);
+        assert_eq!(dsp.get_parameter(Parameter::MaxPeaks as u8), MAX_PEAKS as f32);
+    }
+
+    #[test]
+    fn test_snr_meter() {
+        /// SNR of a carrier over IQ noise, and the passband noise in dBFS.
+        fn carrier_snr(dsp: &mut DspProcessor, carrier: f32) -> (f32, f32) {
+            let mut seed = 1_u32;
+            let mut noise = || {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1 << 24) as f32 * 0.02 - 0.01
+            };
+            let iq: Vec<f32> = (0..48000)
+                .flat_map(|n| {
+                    let phase = core::f32::consts::TAU * 1000.0 * n as f32 / 48000.0;
+                    [carrier * phase.cos() + noise(), carrier * phase.sin() + noise()]
+                })
+                .collect();
+            dsp.reset();
+            dsp.process_samples(&iq);
+            (dsp.get_snr_db(), dsp.get_passband_noise_dbfs())
+        }
+
+        let mut dsp = DspProcessor::new(48000.0);
+        assert!(dsp.get_snr_db().is_nan());
+        assert!(dsp.status().snr_db.is_nan());
+        let (strong, _) = carrier_snr(&mut dsp, 0.05);
+        let (weak, noise) = carrier_snr(&mut dsp, 0.002);
+        let (none, noise_only) = carrier_snr(&mut dsp, 0.0);
+        assert!(strong > weak + 20.0, "{strong} vs {weak}");
+        assert!(none < weak, "{none} vs {weak}");
+        // A weak signal leaves the noise floor where it was
+        assert!((noise - noise_only).abs() < 1.0, "{noise} vs {noise_only}");
+        assert!(dsp.get_snr_2500_db() < dsp.get_snr_db() + 1.0);
+        assert!(dsp.get_signal_power_dbfs().is_finite());
+
+        // Narrowing the filter narrows the measurement with it
+        dsp.set_filter_bandwidth(500.0);
+        assert!(dsp.snr.bandwidth_hz() <= 500.0);
+    }
+
+    #[test]
+    fn test_graphic_equalizer() {
+        /// Steady-state peak of a tone through the equalizer.
+        fn tone_peak(dsp: &mut DspProcessor, frequency: f32) -> f32 {
+            let mut audio: Vec<f32> = (0..9600)
+                .map(|n| (core::f32::consts::TAU * frequency * n as f32 / 48000.0).sin())
+                .collect();
+            dsp.equalizer.reset();
+            dsp.equalizer.process_block(&mut audio);
+            audio[4800..].iter().fold(0.0, |peak, s| s.abs().max(peak))
+        }
+
+        let mut dsp = DspProcessor::new(48000.0);
+        assert!(!dsp.get_eq_enabled());
+        assert_eq!(dsp.get_eq_band(0), -3.0);
+        assert!((tone_peak(&mut dsp, 200.0) - 1.0).abs() < 1e-6);
+
+        // A boost and a cut land on their bands
+        assert!(dsp.set_parameter(Parameter::EqEnabled as u8, 1.0));
+        assert!(dsp.set_parameter(Parameter::EqBand1Gain as u8, 12.0));
+        assert!(dsp.set_parameter(Parameter::EqBand5Gain as u8, -20.0));
+        assert_eq!(dsp.get_eq_band(4), -equalizer::MAX_EQ_GAIN_DB);
+        assert!(tone_peak(&mut dsp, 200.0) > 3.0);
+        assert!(tone_peak(&mut dsp, 3000.0) < 0.35);
+        assert!(dsp.get_eq_band(EQ_BANDS as u32).is_nan());
+
+        // A mode change loads its curve and keeps the switch
+        dsp.set_mode(DemodMode::Cw.code());
+        assert_eq!(dsp.get_parameter(Parameter::EqBand5Gain as u8), -8.0);
+        assert_eq!(dsp.get_parameter(Parameter::EqEnabled as u8), 1.0);
+
+        let config = DspConfig {
+            eq: Some(config::EqSettings {
+                enabled: Some(false),
+                gains: Some(vec![1.0, 2.0, 3.0, 4.0, 5.0]),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Ok(()));
+        assert!(!dsp.get_eq_enabled());
+        assert_eq!(dsp.get_eq_band(2), 3.0);
+        let config = DspConfig {
+            eq: Some(config::EqSettings { gains: Some(vec![0.0; 3]), ..Default::default() }),
+            ..Default::default()
+        };
+        assert_eq!(dsp.apply(&config), Err(ConfigError::EqBandCount(3)));
+    }
+
+    #[test]
+    fn test_psk_decoder_bank() {
+        use sdr_mode_psk31::{Psk31Encoder, Psk31EncoderConfig, MAX_DECODERS};
+
+        let mut dsp = DspProcessor::new(48000.0);
+        dsp.set_frequency_offset(0.0);
+        let id = dsp.add_psk_decoder(1000.0);
+        assert!(id >= 0);
+        assert_eq!(dsp.add_psk_decoder(1005.0), id);
+        let other = dsp.add_psk_decoder(2000.0);
+        assert_eq!(dsp.get_psk_decoder_count(), 2);
+
+        let mut encoder = Psk31Encoder::new(Psk31EncoderConfig {
+            carrier_freq_hz: 1000.0,
+            ..Psk31EncoderConfig::default()
+        });
+        encoder.queue_text("  cq cq  ");
+        let iq: Vec<f32> = (0..48000)
+            .flat_map(|_| [encoder.next_sample().unwrap_or(0.0), 0.0])
+            .collect();
+        let mut audio = vec![0.0; 48000];
+        dsp.process_iq(&iq, &mut audio);
+
+        let len = dsp.get_psk_decoded_len();
+        assert!(len > 0 && len.is_multiple_of(2));
+        assert!(dsp.psk_decoded.chunks(2).all(|p| p[0] == id as u32 || p[0] == other as u32));
+        assert!(dsp.get_psk_decoder_snr(id as u32) > dsp.get_psk_decoder_snr(other as u32));
+        assert!(dsp.get_psk_decoder_evm(id as u32) < dsp.get_psk_decoder_evm(other as u32));
+        assert!(dsp.get_psk_decoder_phase_snr(id as u32).is_finite());
+        assert!(dsp.get_psk_decoder_imd(99).is_nan());
+        assert!(dsp.get_psk_decoder_locked(id as u32));
+        dsp.set_psk_afc(false, 50.0, 100.0);
+        assert_eq!(dsp.get_psk_decoder_offset(id as u32), 1000.0);
+        dsp.clear_psk_decoded();
+        assert_eq!(dsp.get_psk_decoded_len(), 0);
+
+        assert!(dsp.remove_psk_decoder(other as u32));
+        for n in 1..MAX_DECODERS {
+            dsp.add_psk_decoder(2000.0 + 100.0 * n as f32);
+        }
+        assert_eq!(dsp.add_psk_decoder(500.0), -1);
+        dsp.clear_psk_decoders();
+        assert_eq!(dsp.get_psk_decoder_count(), 0);
+    }
+
+    #[test]
+    fn test_sstv_lines() {
+        use sdr_mode_sstv::SstvMode;
+
+        let rate = 48000.0;
+        let mut dsp = DspProcessor::new(rate);
+        dsp.set_frequency_offset(0.0);
+        dsp.set_sstv_enabled(true);
+
+        // VIS header for Martin M2, then a line and a half of mid-grey
+        let mode = SstvMode::MartinM2;
+        let mut tones = vec![(1900.0, 300.0), (1200.0, 10.0), (1900.0, 300.0), (1200.0, 30.0)];
+        let mut parity = false;
+        for k in 0..7 {
+            let one = mode.vis_code() & (1 << k) != 0;
+            parity ^= one;
+            tones.push((if one { 1100.0 } else { 1300.0 }, 30.0));
+        }
+        tones.push((if parity { 1100.0 } else { 1300.0 }, 30.0));
+        tones.push((1200.0, 30.0));
+        tones.push((1900.0, mode.timing().line_ms * 1.5));
+
+        let mut phase = 0.0f32;
+        let mut iq = Vec::new();
+        for (hz, ms) in tones {
+            for _ in 0..(ms * rate / 1000.0) as usize {
+                phase = (phase + 2.0 * core::f32::consts::PI * hz / rate) % (2.0 * core::f32::consts::PI);
+                iq.extend_from_slice(&[0.5 * phase.sin(), 0.0]);
+            }
+        }
+        let mut audio = vec![0.0; iq.len() / 2];
+        dsp.process_iq(&iq, &mut audio);
+
+        assert_eq!(dsp.get_sstv_mode(), i32::from(mode.vis_code()));
+        assert_eq!(dsp.get_sstv_line_count(), 1);
+        assert_eq!(dsp.sstv_line_numbers, vec![0]);
+        assert_eq!(dsp.sstv_pixels.len(), dsp.get_sstv_width() * 3);
+        // 1900 Hz is half way from black to white
+        let level = dsp.sstv_pixels[dsp.get_sstv_width() * 3 / 2];
+        assert!((100..156).contains(&level), "{level}");
+
+        dsp.clear_sstv_lines();
+        assert_eq!(dsp.get_sstv_line_count(), 0);
+        dsp.set_sstv_enabled(false);
+        assert_eq!(dsp.get_sstv_mode(), -1);
+    }
+
+    #[test]
+    fn test_aprs_frames() {
+        use sdr_mode_aprs::{Address, AprsEncoder, Ax25Frame};
+
+        let rate = 48000.0;
+        let mut dsp = DspProcessor::new(rate);
+        dsp.set_frequency_offset(0.0);
+        dsp.set_aprs_enabled(true);
+
+        let frame = Ax25Frame::new_ui(
+            Address::parse("APZSDR").unwrap(),
+            Address::parse("N0CALL-9").unwrap(),
+            &[],
+            b">Testing",
+        )
+        .unwrap();
+        let mut encoder = AprsEncoder::new(rate, 0.5);
+        encoder.load(&frame).unwrap();
+        let mut iq = Vec::new();
+        while let Some(sample) = encoder.next_sample() {
+            iq.extend_from_slice(&[sample, 0.0]);
+        }
+        let mut audio = vec![0.0; iq.len() / 2];
+        dsp.process_iq(&iq, &mut audio);
+
+        assert_eq!(dsp.get_aprs_frame_count(), 1);
+        assert_eq!(dsp.aprs_frame_lengths, vec![dsp.aprs_frames.len() as u32]);
+        assert_eq!(Ax25Frame::parse(&dsp.aprs_frames), Ok(frame));
+
+        dsp.clear_aprs_frames();
+        assert_eq!(dsp.get_aprs_frame_count(), 0);
+        dsp.set_aprs_enabled(false);
+        dsp.process_iq(&iq, &mut audio);
+        assert_eq!(dsp.get_aprs_frame_count(), 0);
+    }
+
+    #[test]
+    fn test_js8_frames() {
+        use sdr_mode_js8::{Js8Encoder, Js8Frame};
+
+        let rate = 12000.0;
+        let mut dsp = DspProcessor::new(rate);
+        dsp.set_frequency_offset(0.0);
+        dsp.set_js8_frequency(1200.0);
+        dsp.set_js8_enabled(true);
+
+        let mut encoder = Js8Encoder::new(rate, 0.5, 1200.0);
+        encoder.queue_text("CQ DE N0CALL").unwrap();
+        let sent = encoder.start_frame().unwrap();
+        let mut iq = vec![0.0; rate as usize];
+        while let Some(sample) = encoder.next_sample() {
+            iq.extend_from_slice(&[sample, 0.0]);
+        }
+        iq.resize(iq.len() + rate as usize, 0.0);
+        let mut audio = vec![0.0; iq.len() / 2];
+        dsp.process_iq(&iq, &mut audio);
+
+        assert_eq!(dsp.get_js8_frame_count(), 1);
+        assert_eq!(Js8Frame::from_bytes(&dsp.js8_frames), Some(sent));
+
+        dsp.clear_js8_frames();
+        assert_eq!(dsp.get_js8_frame_count(), 0);
+        dsp.set_js8_enabled(false);
+        dsp.process_iq(&iq, &mut audio);
+        assert_eq!(dsp.get_js8_frame_count(), 0);
+    }
+
+    #[test]
+    fn test_hell_columns() {
+        use sdr_mode_hell::HellEncoder;
+
+        let rate = 12000.0;
+        let mut dsp = DspProcessor::new(rate);
+        dsp.set_frequency_offset(0.0);
+        dsp.set_hell_frequency(900.0);
+        dsp.set_hell_enabled(true);
+
+        let mut encoder = HellEncoder::new(rate, 0.5, 900.0);
+        encoder.queue_text("E");
+        let mut iq = Vec::new();
+        while let Some(sample) = encoder.next_sample() {
+            iq.extend_from_slice(&[sample, 0.0]);
+        }
+        let mut audio = vec![0.0; iq.len() / 2];
+        dsp.process_iq(&iq, &mut audio);
+
+        // Columns beyond the capacity are dropped; the first is the
+        // full height stroke of the E
+        assert_eq!(dsp.get_hell_column_count(), HELL_COLUMN_CAPACITY);
+        let first = &dsp.hell_columns[..HELL_COLUMN_HEIGHT];
+        assert!(first.iter().all(|&pixel| pixel > 128), "{first:?}");
+
+        dsp.clear_hell_columns();
+        dsp.set_hell_enabled(false);
+        dsp.process_iq(&iq, &mut audio);
+        assert_eq!(dsp.get_hell_column_count(), 0);
+    }
+
+    #[test]
+    fn test_cw_skimmer() {
+        use core::f32::consts::PI;
+        use sdr_mode_cw::{elements, unit_ms};
+
+        let rate = 12000.0;
+        let mut dsp = DspProcessor::new(rate);
+        dsp.set_frequency_offset(0.0);
+        dsp.set_cw_skimmer_enabled(true);
+
+        // Two seconds of silence for the noise floor, then "TEST " at 20 WPM
+        let unit = (unit_ms(20.0) * rate / 1000.0) as usize;
+        let mut keying = vec![false; 2 * rate as usize];
+        for ch in "TEST".chars() {
+            for element in elements(ch).unwrap().bytes() {
+                let len = if element == b'-' { 3 } else { 1 };
+                keying.extend(std::iter::repeat_n(true, len * unit));
+                keying.extend(std::iter::repeat_n(false, unit));
+            }
+            keying.extend(std::iter::repeat_n(false, 2 * unit));
+        }
+        keying.extend(std::iter::repeat_n(false, 10 * unit));
+        let mut seed = 7u32;
+        let mut iq = Vec::with_capacity(keying.len() * 2);
+        for (n, key) in keying.into_iter().enumerate() {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (seed >> 8) as f32 / (1u32 << 24) as f32 * 0.02 - 0.01;
+            let tone = if key {
+                0.2 * (2.0 * PI * 700.0 * n as f32 / rate).sin()
+            } else {
+                0.0
+            };
+            iq.extend_from_slice(&[tone + noise, 0.0]);
+        }
+        let mut audio = vec![0.0; iq.len() / 2];
+        dsp.process_iq(&iq, &mut audio);
+
+        assert_eq!(dsp.get_cw_signal_count(), 1);
+        assert!(
+            (dsp.cw_signals[1] - 700.0).abs() <= 25.0,
+            "{:?}",
+            dsp.cw_signals
+        );
+        let text: String = dsp
+            .cw_decoded
+            .chunks(2)
+            .filter_map(|pair| char::from_u32(pair[1]))
+            .collect();
+        assert!(text.contains("EST"), "{text}");
+
+        dsp.set_cw_skimmer_enabled(false);
+        assert_eq!(dsp.get_cw_signal_count(), 0);
+        assert_eq!(dsp.get_cw_decoded_len(), 0);
+    }
+
+    #[test]
+    fn test_audio_filter_latency() {
+        let mut dsp = DspProcessor::new(48000.0);
+        let latency = dsp.get_filter_latency_ms();
+        // One 514 sample block plus 255 samples of group delay
+        assert!((latency - 769.0 / 48.0).abs() < 0.01, "{latency}");
+
+        // Narrowing the filter keeps the length and so the latency
+        dsp.set_mode(DemodMode::Cw.code());
+        assert!((dsp.get_filter_latency_ms() - latency).abs() < 1e-6);
+    }
 }
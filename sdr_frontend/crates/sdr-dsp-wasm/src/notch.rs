@@ -0,0 +1,117 @@
+//! Manual notch filters.
+//!
+//! [`DspProcessor`](crate::DspProcessor) runs [`NOTCH_COUNT`] notches in
+//! series on the audio, after noise reduction and ahead of the AGC, so a
+//! nulled carrier no longer pumps the gain. Each has its own frequency,
+//! width and switch, so two carriers can be nulled at once.
+
+use sdr_dsp_core::{Biquad, BiquadCoeffs};
+
+/// Number of independent manual notches.
+pub const NOTCH_COUNT: usize = 2;
+
+/// Default notch width in Hz.
+pub const DEFAULT_NOTCH_WIDTH_HZ: f32 = 100.0;
+
+/// Narrowest notch in Hz.
+pub const MIN_NOTCH_WIDTH_HZ: f32 = 10.0;
+
+/// Widest notch in Hz.
+pub const MAX_NOTCH_WIDTH_HZ: f32 = 1000.0;
+
+/// Default notch frequency in Hz.
+const DEFAULT_NOTCH_HZ: f32 = 1000.0;
+
+/// One notch and its settings.
+pub(crate) struct ManualNotch {
+    sample_rate: f32,
+    filter: Biquad,
+    frequency_hz: f32,
+    width_hz: f32,
+    enabled: bool,
+}
+
+impl ManualNotch {
+    /// Create a notch at the default frequency and width, switched off.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            filter: Biquad::new(notch_coeffs(
+                sample_rate,
+                DEFAULT_NOTCH_HZ,
+                DEFAULT_NOTCH_WIDTH_HZ,
+            )),
+            frequency_hz: DEFAULT_NOTCH_HZ,
+            width_hz: DEFAULT_NOTCH_WIDTH_HZ,
+            enabled: false,
+        }
+    }
+
+    /// Filter a block of audio in place (nothing when off).
+    pub fn process_block(&mut self, audio: &mut [f32]) {
+        if self.enabled {
+            self.filter.process_block(audio);
+        }
+    }
+
+    /// Tune the notch, keeping the filter state so a sweep does not click.
+    ///
+    /// The frequency is kept below Nyquist and the width within
+    /// [`MIN_NOTCH_WIDTH_HZ`] to [`MAX_NOTCH_WIDTH_HZ`].
+    pub fn tune(&mut self, frequency_hz: f32, width_hz: f32) {
+        let nyquist = self.sample_rate / 2.0;
+        self.frequency_hz = frequency_hz.clamp(1.0, nyquist - 1.0);
+        self.width_hz = width_hz.clamp(MIN_NOTCH_WIDTH_HZ, MAX_NOTCH_WIDTH_HZ);
+        self.filter.set_coeffs(notch_coeffs(
+            self.sample_rate,
+            self.frequency_hz,
+            self.width_hz,
+        ));
+    }
+
+    /// Get the notch frequency in Hz.
+    pub fn frequency(&self) -> f32 {
+        self.frequency_hz
+    }
+
+    /// Get the notch width in Hz.
+    pub fn width(&self) -> f32 {
+        self.width_hz
+    }
+
+    /// Switch the notch on or off, clearing its state when switched on.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.filter.reset();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Check if the notch is on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Clear the filter state.
+    pub fn reset(&mut self) {
+        self.filter.reset();
+    }
+}
+
+/// Design notch coefficients with `std` trigonometry.
+///
+/// [`BiquadCoeffs::notch`] uses the `no_std` approximations, which can
+/// put a narrow notch at a few hundred Hz tens of Hz off its carrier.
+fn notch_coeffs(sample_rate: f32, frequency_hz: f32, width_hz: f32) -> BiquadCoeffs {
+    let omega = core::f32::consts::TAU * frequency_hz / sample_rate;
+    let alpha = omega.sin() * width_hz / (2.0 * frequency_hz);
+    let a0 = 1.0 + alpha;
+    let b1 = -2.0 * omega.cos() / a0;
+    BiquadCoeffs {
+        b0: 1.0 / a0,
+        b1,
+        b2: 1.0 / a0,
+        a1: b1,
+        a2: (1.0 - alpha) / a0,
+    }
+}
@@ -0,0 +1,144 @@
+//! Independent receiver slices.
+//!
+//! A slice is a second receiver inside the same sampled bandwidth: its
+//! own mixer offset, demodulator, audio filter and AGC, run on the same
+//! DC-blocked I/Q as the main receiver. Its audio is mixed into the main
+//! output at the slice volume and also kept on its own, so the UI can
+//! listen to one signal while a decoder reads another.
+
+use sdr_dsp_core::{Agc, AgcConfig, FftConvolver, IqSample, Nco};
+
+use crate::{audio_filter_taps, demodulate, DemodMode};
+
+/// Most slices one [`DspProcessor`](crate::DspProcessor) runs besides the main receiver.
+pub const MAX_SLICES: usize = 4;
+
+/// One sub-receiver.
+pub(crate) struct ReceiverSlice {
+    sample_rate: f32,
+    nco: Nco,
+    offset_hz: f32,
+    mode: DemodMode,
+    bandwidth_hz: f32,
+    audio_filter: Box<FftConvolver>,
+    agc: Agc,
+    agc_config: AgcConfig,
+    volume: f32,
+
+    // Scratch for the mixed block and the audio it produced
+    iq: Vec<IqSample>,
+    audio: Vec<f32>,
+}
+
+impl ReceiverSlice {
+    /// Create a slice at a mixer offset, with the mode's default bandwidth.
+    pub fn new(sample_rate: f32, offset_hz: f32, mode: DemodMode) -> Self {
+        let bandwidth_hz = mode.default_bandwidth();
+        let agc_config = AgcConfig::medium();
+        Self {
+            sample_rate,
+            nco: Nco::new(sample_rate, offset_hz),
+            offset_hz,
+            mode,
+            bandwidth_hz,
+            audio_filter: Box::new(
+                FftConvolver::new(&audio_filter_taps(sample_rate, bandwidth_hz))
+                    .expect("AUDIO_FILTER_TAPS within MAX_TAPS"),
+            ),
+            agc: Agc::new(sample_rate, agc_config),
+            agc_config,
+            volume: 1.0,
+            iq: Vec::new(),
+            audio: Vec::new(),
+        }
+    }
+
+    /// Demodulate a block of DC-blocked I/Q into the slice audio.
+    pub fn process(&mut self, iq: &[IqSample]) {
+        self.iq.clear();
+        self.iq.extend_from_slice(iq);
+        self.nco.mix_block(&mut self.iq);
+        self.audio.resize(iq.len(), 0.0);
+        demodulate(self.mode, &self.iq, &mut self.audio);
+        self.audio_filter.process_block(&mut self.audio);
+        self.agc.process_block(&mut self.audio);
+    }
+
+    /// Add the slice audio, at the slice volume, to `output`.
+    pub fn mix_into(&self, output: &mut [f32]) {
+        if self.volume == 0.0 {
+            return;
+        }
+        for (out, &sample) in output.iter_mut().zip(&self.audio) {
+            *out += self.volume * sample;
+        }
+    }
+
+    /// Audio from the last block.
+    pub fn audio(&self) -> &[f32] {
+        &self.audio
+    }
+
+    /// Set the mixer offset in Hz.
+    pub fn set_offset(&mut self, offset_hz: f32) {
+        self.offset_hz = offset_hz;
+        self.nco.set_frequency(offset_hz);
+    }
+
+    /// Get the mixer offset in Hz.
+    pub fn offset(&self) -> f32 {
+        self.offset_hz
+    }
+
+    /// Set the mode, resetting the bandwidth to its default.
+    pub fn set_mode(&mut self, mode: DemodMode) {
+        self.mode = mode;
+        self.set_bandwidth(mode.default_bandwidth());
+    }
+
+    /// Get the mode.
+    pub fn mode(&self) -> DemodMode {
+        self.mode
+    }
+
+    /// Set the audio filter bandwidth in Hz.
+    pub fn set_bandwidth(&mut self, bandwidth_hz: f32) {
+        self.bandwidth_hz = bandwidth_hz;
+        self.audio_filter
+            .set_taps(&audio_filter_taps(self.sample_rate, bandwidth_hz));
+    }
+
+    /// Get the audio filter bandwidth in Hz.
+    pub fn bandwidth(&self) -> f32 {
+        self.bandwidth_hz
+    }
+
+    /// Set the AGC time constants, keeping the other settings.
+    pub fn set_agc(&mut self, attack_ms: f32, decay_ms: f32, hang_ms: f32) {
+        self.agc_config = AgcConfig {
+            attack_ms,
+            decay_ms,
+            hang_ms,
+            ..self.agc_config
+        };
+        self.agc.set_config(self.agc_config);
+    }
+
+    /// Set the level mixed into the main output (0 = not heard).
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.max(0.0);
+    }
+
+    /// Get the level mixed into the main output.
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Clear the filter, mixer and AGC state.
+    pub fn reset(&mut self) {
+        self.nco.reset();
+        self.audio_filter.reset();
+        self.agc.reset();
+        self.audio.clear();
+    }
+}
@@ -0,0 +1,274 @@
+//! Transmit DSP for AudioWorklet integration.
+//!
+//! [`TxProcessor`] takes microphone audio and runs the transmit chain:
+//! a high-pass to take out rumble and hum, mic gain, the speech
+//! compressor, the graphic equalizer, a band-pass to the SSB bandwidth,
+//! and the SSB modulator.
+//! The result is interleaved baseband I/Q at the processor rate, ready
+//! to stream to the radio over WebUSB or serial.
+
+use sdr_dsp_core::convolve::bandpass_taps;
+use sdr_dsp_core::{Biquad, Compressor, FftConvolver, IqSample, SsbModulator};
+use wasm_bindgen::prelude::*;
+
+use crate::equalizer::{GraphicEq, TX_DEFAULT_CURVE};
+use crate::{DemodMode, BUFFER_SIZE, MAX_BUFFER_SIZE};
+
+/// Transmit audio filter length (runs by FFT convolution).
+pub const TX_FILTER_TAPS: usize = 255;
+
+/// Default high-pass corner in Hz.
+pub const DEFAULT_TX_HIGHPASS_HZ: f32 = 200.0;
+
+/// Default transmit bandwidth in Hz (upper edge of the audio passband).
+pub const DEFAULT_TX_BANDWIDTH_HZ: f32 = 2700.0;
+
+/// Lower edge of the transmit audio passband in Hz.
+const TX_LOW_EDGE_HZ: f32 = 100.0;
+
+/// Transmit processor: microphone audio in, interleaved I/Q out.
+#[wasm_bindgen]
+pub struct TxProcessor {
+    // Audio input and interleaved I,Q output, sized by buffer_size
+    buffer_size: usize,
+    input_buffer: Vec<f32>,
+    output_buffer: Vec<f32>,
+    iq_block: Vec<IqSample>,
+
+    // DSP components
+    highpass: Biquad,
+    compressor: Compressor,
+    equalizer: GraphicEq,
+    bandpass: Box<FftConvolver>,
+    modulator: SsbModulator,
+
+    // Configuration
+    sample_rate: f32,
+    mode: DemodMode,
+    highpass_hz: f32,
+    bandwidth_hz: f32,
+    mic_gain: f32,
+}
+
+#[wasm_bindgen]
+impl TxProcessor {
+    /// Create a transmit processor (upper sideband, no compression).
+    ///
+    /// The equalizer holds the SSB factory curve but starts switched off.
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> Self {
+        let mut equalizer = GraphicEq::new(sample_rate);
+        equalizer.set_gains(&TX_DEFAULT_CURVE);
+        Self {
+            buffer_size: BUFFER_SIZE,
+            input_buffer: vec![0.0; BUFFER_SIZE],
+            output_buffer: vec![0.0; BUFFER_SIZE * 2],
+            iq_block: vec![IqSample::ZERO; BUFFER_SIZE],
+            highpass: Biquad::highpass(sample_rate, DEFAULT_TX_HIGHPASS_HZ, 0.707),
+            compressor: Compressor::new(sample_rate, 0.0),
+            equalizer,
+            bandpass: Box::new(
+                FftConvolver::new(&tx_filter_taps(sample_rate, DEFAULT_TX_BANDWIDTH_HZ))
+                    .expect("TX_FILTER_TAPS within MAX_TAPS"),
+            ),
+            modulator: SsbModulator::new(true),
+            sample_rate,
+            mode: DemodMode::Usb,
+            highpass_hz: DEFAULT_TX_HIGHPASS_HZ,
+            bandwidth_hz: DEFAULT_TX_BANDWIDTH_HZ,
+            mic_gain: 1.0,
+        }
+    }
+
+    /// Set the number of audio samples the input buffer holds (1 to [`MAX_BUFFER_SIZE`]).
+    ///
+    /// Reallocates the buffers, so their pointers must be fetched again.
+    /// Returns `false` and changes nothing if the size is out of range.
+    #[wasm_bindgen]
+    pub fn set_buffer_size(&mut self, size: usize) -> bool {
+        if !(1..=MAX_BUFFER_SIZE).contains(&size) {
+            return false;
+        }
+        self.buffer_size = size;
+        self.input_buffer = vec![0.0; size];
+        self.output_buffer = vec![0.0; size * 2];
+        self.iq_block = vec![IqSample::ZERO; size];
+        true
+    }
+
+    /// Get the number of audio samples the input buffer holds.
+    #[wasm_bindgen]
+    pub fn get_buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Get pointer to the audio input buffer for WASM memory access.
+    ///
+    /// For callers using the raw exports; see
+    /// [`DspProcessor::get_waterfall_buffer_ptr`](crate::DspProcessor::get_waterfall_buffer_ptr).
+    #[wasm_bindgen]
+    pub fn get_input_buffer_ptr(&mut self) -> *mut f32 {
+        self.input_buffer.as_mut_ptr()
+    }
+
+    /// Get pointer to the interleaved I/Q output buffer for WASM memory access.
+    #[wasm_bindgen]
+    pub fn get_output_buffer_ptr(&self) -> *const f32 {
+        self.output_buffer.as_ptr()
+    }
+
+    /// Modulate `num_samples` of audio from the input buffer.
+    ///
+    /// Writes twice as many values (I0, Q0, I1, Q1, ...) to the output buffer.
+    #[wasm_bindgen]
+    pub fn process(&mut self, num_samples: usize) {
+        let samples = num_samples.min(self.buffer_size);
+        let audio = &mut self.input_buffer[..samples];
+        self.highpass.process_block(audio);
+        for sample in audio.iter_mut() {
+            *sample *= self.mic_gain;
+        }
+        self.compressor.process_block(audio);
+        self.equalizer.process_block(audio);
+        self.bandpass.process_block(audio);
+
+        let iq = &mut self.iq_block[..samples];
+        self.modulator.process_block(audio, iq);
+        for (out, s) in self.output_buffer.chunks_exact_mut(2).zip(iq.iter()) {
+            out[0] = s.i;
+            out[1] = s.q;
+        }
+    }
+
+    /// Modulate audio of any length, returning interleaved I/Q.
+    #[wasm_bindgen]
+    pub fn process_samples(&mut self, audio: &[f32]) -> Vec<f32> {
+        let mut iq = Vec::with_capacity(audio.len() * 2);
+        for block in audio.chunks(self.buffer_size) {
+            self.input_buffer[..block.len()].copy_from_slice(block);
+            self.process(block.len());
+            iq.extend_from_slice(&self.output_buffer[..block.len() * 2]);
+        }
+        iq
+    }
+
+    /// Set the sideband from a [`DemodMode`] code (LSB or USB only).
+    ///
+    /// Returns `false` and leaves the mode unchanged for any other mode.
+    #[wasm_bindgen]
+    pub fn set_mode(&mut self, mode: u8) -> bool {
+        match DemodMode::from_code(mode) {
+            Some(mode @ (DemodMode::Lsb | DemodMode::Usb)) => {
+                self.mode = mode;
+                self.modulator.set_usb(mode == DemodMode::Usb);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the current [`DemodMode`] code.
+    #[wasm_bindgen]
+    pub fn get_mode(&self) -> u8 {
+        self.mode.code()
+    }
+
+    /// Set the microphone gain in dB.
+    #[wasm_bindgen]
+    pub fn set_mic_gain_db(&mut self, gain_db: f32) {
+        self.mic_gain = 10.0_f32.powf(gain_db / 20.0);
+    }
+
+    /// Get the microphone gain in dB.
+    #[wasm_bindgen]
+    pub fn get_mic_gain_db(&self) -> f32 {
+        20.0 * self.mic_gain.log10()
+    }
+
+    /// Set the speech compression in dB (0 = off, up to 20).
+    #[wasm_bindgen]
+    pub fn set_compression_db(&mut self, compression_db: f32) {
+        self.compressor.set_compression(compression_db);
+    }
+
+    /// Get the speech compression in dB.
+    #[wasm_bindgen]
+    pub fn get_compression_db(&self) -> f32 {
+        self.compressor.compression_db()
+    }
+
+    /// Set the microphone high-pass corner in Hz.
+    #[wasm_bindgen]
+    pub fn set_highpass(&mut self, cutoff_hz: f32) {
+        self.highpass_hz = cutoff_hz;
+        self.highpass = Biquad::highpass(self.sample_rate, cutoff_hz, 0.707);
+    }
+
+    /// Get the microphone high-pass corner in Hz.
+    #[wasm_bindgen]
+    pub fn get_highpass(&self) -> f32 {
+        self.highpass_hz
+    }
+
+    /// Set the transmit bandwidth (upper audio edge) in Hz.
+    #[wasm_bindgen]
+    pub fn set_bandwidth(&mut self, bandwidth_hz: f32) {
+        self.bandwidth_hz = bandwidth_hz;
+        self.bandpass
+            .set_taps(&tx_filter_taps(self.sample_rate, bandwidth_hz));
+    }
+
+    /// Get the transmit bandwidth in Hz.
+    #[wasm_bindgen]
+    pub fn get_bandwidth(&self) -> f32 {
+        self.bandwidth_hz
+    }
+
+    /// Switch the transmit equalizer on or off.
+    #[wasm_bindgen]
+    pub fn set_eq_enabled(&mut self, enabled: bool) {
+        self.equalizer.set_enabled(enabled);
+    }
+
+    /// Check if the transmit equalizer is on.
+    #[wasm_bindgen]
+    pub fn get_eq_enabled(&self) -> bool {
+        self.equalizer.is_enabled()
+    }
+
+    /// Set a transmit equalizer band (0 to [`EQ_BANDS`](crate::EQ_BANDS) - 1)
+    /// gain in dB. Returns `false` if there is no such band.
+    #[wasm_bindgen]
+    pub fn set_eq_band(&mut self, band: u32, gain_db: f32) -> bool {
+        self.equalizer.set_gain(band as usize, gain_db)
+    }
+
+    /// Get a transmit equalizer band gain in dB (NaN if there is no such band).
+    #[wasm_bindgen]
+    pub fn get_eq_band(&self, band: u32) -> f32 {
+        self.equalizer.gain(band as usize)
+    }
+
+    /// Reset filter, compressor, equalizer and modulator state.
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.highpass.reset();
+        self.compressor.reset();
+        self.equalizer.reset();
+        self.bandpass.reset();
+        self.modulator.reset();
+    }
+}
+
+/// Create a TX processor (for raw WASM export).
+#[wasm_bindgen]
+pub fn create_tx_processor(sample_rate: f32) -> TxProcessor {
+    TxProcessor::new(sample_rate)
+}
+
+/// Design the transmit audio band-pass taps for a bandwidth.
+fn tx_filter_taps(sample_rate: f32, bandwidth_hz: f32) -> [f32; TX_FILTER_TAPS] {
+    let mut taps = [0.0; TX_FILTER_TAPS];
+    bandpass_taps(sample_rate, TX_LOW_EDGE_HZ, bandwidth_hz, &mut taps);
+    taps
+}
@@ -0,0 +1,209 @@
+//! Bell 202 AFSK modem.
+//!
+//! Bits are NRZI coded: a 0 switches between the mark and space tones,
+//! a 1 keeps the current tone. The demodulator is an FM discriminator
+//! centred between the tones followed by a digital PLL that samples
+//! each bit in its middle.
+
+use core::f32::consts::PI;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use sdr_dsp_core::filter::{Biquad, BiquadIq};
+use sdr_dsp_core::{IqSample, Nco};
+
+/// Mark tone in Hz.
+pub const MARK_HZ: f32 = 1200.0;
+
+/// Space tone in Hz.
+pub const SPACE_HZ: f32 = 2200.0;
+
+/// Bit rate in baud.
+pub const BAUD_RATE: f32 = 1200.0;
+
+/// Mixing frequency, midway between the tones.
+const CENTER_HZ: f32 = (MARK_HZ + SPACE_HZ) / 2.0;
+
+/// Low-pass cutoff of the complex baseband in Hz.
+const BASEBAND_CUTOFF_HZ: f32 = 1100.0;
+
+/// Low-pass cutoff of the discriminator output in Hz.
+const DATA_CUTOFF_HZ: f32 = 1000.0;
+
+/// Fraction of the bit clock error corrected at each transition.
+const CLOCK_GAIN: f32 = 0.25;
+
+/// AFSK demodulator with bit clock recovery.
+#[derive(Clone, Debug)]
+pub struct AfskDemodulator {
+    nco: Nco,
+    /// Two cascaded low-pass sections on the baseband
+    filters: [BiquadIq; 2],
+    prev: IqSample,
+    /// Discriminator output filter
+    data_filter: Biquad,
+    /// Bit clock phase (0 to 1, bits are sampled on wrap)
+    clock: f32,
+    /// Clock advance per sample
+    clock_step: f32,
+    /// Current tone (true for space)
+    level: bool,
+    /// Tone at the last bit sample, for NRZI decoding
+    last_level: bool,
+}
+
+impl AfskDemodulator {
+    /// Create a demodulator for audio at `sample_rate`.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            nco: Nco::new(sample_rate, CENTER_HZ),
+            filters: [
+                BiquadIq::lowpass(sample_rate, BASEBAND_CUTOFF_HZ, 0.541),
+                BiquadIq::lowpass(sample_rate, BASEBAND_CUTOFF_HZ, 1.307),
+            ],
+            prev: IqSample::ZERO,
+            data_filter: Biquad::lowpass(sample_rate, DATA_CUTOFF_HZ, 0.707),
+            clock: 0.0,
+            clock_step: BAUD_RATE / sample_rate,
+            level: false,
+            last_level: false,
+        }
+    }
+
+    /// Demodulate one audio sample.
+    ///
+    /// Returns a decoded bit (after NRZI) once per bit period.
+    pub fn process(&mut self, audio: f32) -> Option<bool> {
+        let mut baseband = self.nco.mix(IqSample::from_real(audio));
+        for filter in &mut self.filters {
+            baseband = filter.process(baseband);
+        }
+
+        // Sine of the phase advance: positive above the centre (space)
+        let power = baseband.magnitude_squared() + self.prev.magnitude_squared();
+        let cross = self.prev.i * baseband.q - self.prev.q * baseband.i;
+        self.prev = baseband;
+        let discriminator = if power > 1e-20 { cross / power } else { 0.0 };
+        let level = self.data_filter.process(discriminator) > 0.0;
+
+        if level != self.level {
+            // Pull the clock so transitions fall half way between samples
+            self.level = level;
+            self.clock -= (self.clock - 0.5) * CLOCK_GAIN;
+        }
+
+        self.clock += self.clock_step;
+        if self.clock < 1.0 {
+            return None;
+        }
+        self.clock -= 1.0;
+        let bit = level == self.last_level;
+        self.last_level = level;
+        Some(bit)
+    }
+
+    /// Reset demodulator state.
+    pub fn reset(&mut self) {
+        self.nco.reset();
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.prev = IqSample::ZERO;
+        self.data_filter.reset();
+        self.clock = 0.0;
+        self.level = false;
+        self.last_level = false;
+    }
+}
+
+/// Phase-continuous AFSK modulator.
+#[derive(Clone, Debug)]
+pub struct AfskModulator {
+    sample_rate: f32,
+    amplitude: f32,
+    samples_per_bit: f32,
+    /// Samples left in the current bit
+    remaining: f32,
+    phase: f32,
+    /// Sending the mark tone
+    mark: bool,
+}
+
+impl AfskModulator {
+    /// Create a modulator for `sample_rate` with peak `amplitude`.
+    #[must_use]
+    pub fn new(sample_rate: f32, amplitude: f32) -> Self {
+        Self {
+            sample_rate,
+            amplitude,
+            samples_per_bit: sample_rate / BAUD_RATE,
+            remaining: 0.0,
+            phase: 0.0,
+            mark: true,
+        }
+    }
+
+    /// Generate the next audio sample, taking a new bit from `bits` at
+    /// each bit boundary.
+    ///
+    /// Returns `None` when `bits` runs out.
+    pub fn next_sample(&mut self, bits: &mut impl Iterator<Item = bool>) -> Option<f32> {
+        if self.remaining <= 0.0 {
+            if !bits.next()? {
+                self.mark = !self.mark;
+            }
+            self.remaining += self.samples_per_bit;
+        }
+        self.remaining -= 1.0;
+
+        let hz = if self.mark { MARK_HZ } else { SPACE_HZ };
+        self.phase += 2.0 * PI * hz / self.sample_rate;
+        if self.phase > PI {
+            self.phase -= 2.0 * PI;
+        }
+        Some(self.amplitude * self.phase.sin())
+    }
+
+    /// Reset to the start of a transmission.
+    pub fn reset(&mut self) {
+        self.remaining = 0.0;
+        self.phase = 0.0;
+        self.mark = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_round_trip() {
+        // Pseudo-random bits
+        let mut state = 0xACE1u16;
+        let bits: [bool; 400] = core::array::from_fn(|_| {
+            state = (state >> 1) ^ if state & 1 != 0 { 0xB400 } else { 0 };
+            state & 1 != 0
+        });
+
+        for rate in [44100.0, 48000.0] {
+            let mut modulator = AfskModulator::new(rate, 0.5);
+            let mut demod = AfskDemodulator::new(rate);
+            let mut source = bits.iter().copied();
+            let mut received = [false; 420];
+            let mut count = 0;
+            while let Some(sample) = modulator.next_sample(&mut source) {
+                if let Some(bit) = demod.process(sample) {
+                    if count < received.len() {
+                        received[count] = bit;
+                        count += 1;
+                    }
+                }
+            }
+
+            // Find the sent bits after the filter delay and clock settling
+            let found = (0..20).any(|delay| received[delay + 50..delay + 390] == bits[50..390]);
+            assert!(found, "{rate}");
+        }
+    }
+}
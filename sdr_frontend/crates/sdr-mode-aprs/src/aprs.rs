@@ -0,0 +1,322 @@
+//! APRS information field parsing and encoding.
+//!
+//! The first byte of the information field identifies the packet type.
+//! Positions (`!`, `=`, and `/`, `@` with a timestamp) carry latitude,
+//! longitude and a map symbol, either as text (`4903.50N/07201.75W>`)
+//! or base-91 compressed, followed by a free-text comment. Status
+//! reports (`>`) carry text only.
+
+use core::fmt;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Length of an uncompressed position (latitude, table, longitude, code).
+const UNCOMPRESSED_LEN: usize = 19;
+
+/// Length of a compressed position.
+const COMPRESSED_LEN: usize = 13;
+
+/// Length of a DHM or HMS timestamp.
+const TIMESTAMP_LEN: usize = 7;
+
+/// A station position and map symbol.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    /// Latitude in degrees, north positive
+    pub latitude: f32,
+    /// Longitude in degrees, east positive
+    pub longitude: f32,
+    /// Symbol table (`/` primary, `\` alternate, or an overlay character)
+    pub symbol_table: char,
+    /// Symbol code within the table (e.g. `>` car, `-` house)
+    pub symbol_code: char,
+}
+
+impl Position {
+    /// Parse an uncompressed position such as `4903.50N/07201.75W>`.
+    ///
+    /// Spaces for position ambiguity are read as zeros.
+    #[must_use]
+    pub fn parse_uncompressed(data: &[u8]) -> Option<Self> {
+        let data = data.get(..UNCOMPRESSED_LEN)?;
+        let latitude = angle(&data[0..2], &data[2..7], data[7], b'N', b'S')?;
+        let longitude = angle(&data[9..12], &data[12..17], data[17], b'E', b'W')?;
+        if latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+            return None;
+        }
+        Some(Self {
+            latitude,
+            longitude,
+            symbol_table: char::from(data[8]),
+            symbol_code: char::from(data[18]),
+        })
+    }
+
+    /// Parse a compressed position such as `/5L!!<*e7> sT`.
+    #[must_use]
+    pub fn parse_compressed(data: &[u8]) -> Option<Self> {
+        let data = data.get(..COMPRESSED_LEN)?;
+        let y = base91(&data[1..5])?;
+        let x = base91(&data[5..9])?;
+        // Overlay digits are sent as a-j
+        let table = match data[0] {
+            b @ b'a'..=b'j' => b - b'a' + b'0',
+            b => b,
+        };
+        Some(Self {
+            latitude: 90.0 - y as f32 / 380_926.0,
+            longitude: -180.0 + x as f32 / 190_463.0,
+            symbol_table: char::from(table),
+            symbol_code: char::from(data[9]),
+        })
+    }
+
+    /// Write as an uncompressed position, rounded to 0.01 minute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer fails.
+    pub fn write_uncompressed(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        let (lat_deg, lat_min) = degrees_minutes(self.latitude);
+        let (lon_deg, lon_min) = degrees_minutes(self.longitude);
+        write!(
+            out,
+            "{:02}{:02}.{:02}{}{}{:03}{:02}.{:02}{}{}",
+            lat_deg,
+            lat_min / 100,
+            lat_min % 100,
+            if self.latitude < 0.0 { 'S' } else { 'N' },
+            self.symbol_table,
+            lon_deg,
+            lon_min / 100,
+            lon_min % 100,
+            if self.longitude < 0.0 { 'W' } else { 'E' },
+            self.symbol_code,
+        )
+    }
+}
+
+/// A parsed APRS packet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AprsPacket<'a> {
+    /// Position report
+    Position {
+        /// Station position and symbol
+        position: Position,
+        /// Station can receive APRS messages (`=` and `@`)
+        messaging: bool,
+        /// Text after the position
+        comment: &'a str,
+    },
+    /// Status report
+    Status(&'a str),
+    /// Any other packet type, by data type identifier
+    Other(u8),
+}
+
+impl<'a> AprsPacket<'a> {
+    /// Parse an AX.25 information field.
+    ///
+    /// Returns `None` for an empty field or a malformed position.
+    #[must_use]
+    pub fn parse(info: &'a [u8]) -> Option<Self> {
+        let (&kind, rest) = info.split_first()?;
+        match kind {
+            b'!' | b'=' => Self::parse_position(rest, kind == b'='),
+            b'/' | b'@' => Self::parse_position(rest.get(TIMESTAMP_LEN..)?, kind == b'@'),
+            b'>' => Some(Self::Status(text(rest))),
+            _ => Some(Self::Other(kind)),
+        }
+    }
+
+    fn parse_position(data: &'a [u8], messaging: bool) -> Option<Self> {
+        let (position, len) = match data.first()? {
+            b'0'..=b'9' | b' ' => (Position::parse_uncompressed(data)?, UNCOMPRESSED_LEN),
+            _ => (Position::parse_compressed(data)?, COMPRESSED_LEN),
+        };
+        Some(Self::Position {
+            position,
+            messaging,
+            comment: text(&data[len..]),
+        })
+    }
+
+    /// Write the information field for this packet.
+    ///
+    /// Positions are written uncompressed without a timestamp.
+    /// [`AprsPacket::Other`] writes nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer fails.
+    pub fn write(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        match self {
+            Self::Position {
+                position,
+                messaging,
+                comment,
+            } => {
+                out.write_char(if *messaging { '=' } else { '!' })?;
+                position.write_uncompressed(out)?;
+                out.write_str(comment)
+            }
+            Self::Status(status) => {
+                out.write_char('>')?;
+                out.write_str(status)
+            }
+            Self::Other(_) => Ok(()),
+        }
+    }
+}
+
+/// Read `DD` (or `DDD`) degrees and `MM.mm` minutes with a hemisphere.
+fn angle(
+    degrees: &[u8],
+    minutes: &[u8],
+    hemisphere: u8,
+    positive: u8,
+    negative: u8,
+) -> Option<f32> {
+    let sign = match hemisphere {
+        h if h == positive => 1.0,
+        h if h == negative => -1.0,
+        _ => return None,
+    };
+    let mut value = 0.0;
+    for &d in degrees {
+        value = value * 10.0 + digit(d)?;
+    }
+    if minutes[2] != b'.' {
+        return None;
+    }
+    let whole = digit(minutes[0])? * 10.0 + digit(minutes[1])?;
+    let hundredths = digit(minutes[3])? * 10.0 + digit(minutes[4])?;
+    Some(sign * (value + (whole + hundredths / 100.0) / 60.0))
+}
+
+/// Value of a decimal digit, with a space (ambiguity) as zero.
+fn digit(b: u8) -> Option<f32> {
+    match b {
+        b'0'..=b'9' => Some(f32::from(b - b'0')),
+        b' ' => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Decode base-91 digits (`!` is 0).
+fn base91(data: &[u8]) -> Option<u32> {
+    data.iter().try_fold(0u32, |acc, &b| {
+        (33..=123)
+            .contains(&b)
+            .then(|| acc * 91 + u32::from(b - 33))
+    })
+}
+
+/// Split an angle into whole degrees and hundredths of a minute.
+fn degrees_minutes(angle: f32) -> (u32, u32) {
+    let hundredths = (angle.abs() * 6000.0).round() as u32;
+    (hundredths / 6000, hundredths % 6000)
+}
+
+/// Text of a field, empty if it is not UTF-8.
+fn text(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::String;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_parse_positions() {
+        let Some(AprsPacket::Position {
+            position,
+            messaging,
+            comment,
+        }) = AprsPacket::parse(b"!4903.50N/07201.75W>Test 1234")
+        else {
+            panic!("not a position");
+        };
+        assert!(close(position.latitude, 49.058_33));
+        assert!(close(position.longitude, -72.029_17));
+        assert_eq!((position.symbol_table, position.symbol_code), ('/', '>'));
+        assert!(!messaging);
+        assert_eq!(comment, "Test 1234");
+
+        // Timestamped, with messaging and ambiguity
+        let Some(AprsPacket::Position {
+            position,
+            messaging,
+            ..
+        }) = AprsPacket::parse(b"@092345z4903.5 S\\07201.  E-")
+        else {
+            panic!("not a position");
+        };
+        assert!(close(position.latitude, -49.05833));
+        assert!(close(position.longitude, 72.01667));
+        assert!(messaging);
+
+        // Compressed example from the APRS specification
+        let Some(AprsPacket::Position {
+            position, comment, ..
+        }) = AprsPacket::parse(b"=/5L!!<*e7> sTComment")
+        else {
+            panic!("not a position");
+        };
+        assert!(close(position.latitude, 49.5));
+        assert!(close(position.longitude, -72.75));
+        assert_eq!(position.symbol_code, '>');
+        assert_eq!(comment, "Comment");
+
+        assert_eq!(AprsPacket::parse(b"!4903.50X/07201.75W>"), None);
+        assert_eq!(
+            AprsPacket::parse(b">Net tonight"),
+            Some(AprsPacket::Status("Net tonight"))
+        );
+        assert_eq!(
+            AprsPacket::parse(b":N0CALL   :hi"),
+            Some(AprsPacket::Other(b':'))
+        );
+        assert_eq!(AprsPacket::parse(b""), None);
+    }
+
+    #[test]
+    fn test_write_position() {
+        let packet = AprsPacket::Position {
+            position: Position {
+                latitude: -33.86,
+                longitude: 151.2099,
+                symbol_table: '/',
+                symbol_code: '-',
+            },
+            messaging: false,
+            comment: "QRP",
+        };
+        let mut info = String::<64>::new();
+        packet.write(&mut info).unwrap();
+        assert_eq!(info.as_str(), "!3351.60S/15112.59E-QRP");
+        let Some(AprsPacket::Position { position, .. }) = AprsPacket::parse(info.as_bytes()) else {
+            panic!("not a position");
+        };
+        assert!((position.latitude + 33.86).abs() < 2e-4);
+        assert!((position.longitude - 151.2099).abs() < 2e-4);
+
+        // Minutes never round up to 60
+        let mut info = String::<64>::new();
+        Position {
+            latitude: 10.999_99,
+            longitude: -0.5,
+            symbol_table: '/',
+            symbol_code: '>',
+        }
+        .write_uncompressed(&mut info)
+        .unwrap();
+        assert_eq!(info.as_str(), "1100.00N/00030.00W>");
+    }
+}
@@ -0,0 +1,334 @@
+//! AX.25 UI frames.
+//!
+//! A frame starts with the destination, source and up to eight
+//! digipeater addresses, seven bytes each: the callsign shifted left
+//! one bit and padded with spaces, then a byte holding the SSID, the
+//! has-been-repeated bit and the end-of-addresses bit. APRS uses
+//! unnumbered information (UI) frames with no layer 3 protocol.
+
+use core::fmt;
+
+use heapless::{String, Vec};
+
+use crate::hdlc::Frame;
+
+/// Maximum digipeaters in the path.
+pub const MAX_DIGIPEATERS: usize = 8;
+
+/// Maximum information field length in bytes.
+pub const MAX_INFO_LEN: usize = 256;
+
+/// Control field of a UI frame.
+pub const CONTROL_UI: u8 = 0x03;
+
+/// PID for no layer 3 protocol.
+pub const PID_NO_LAYER3: u8 = 0xF0;
+
+/// Encoded address length in bytes.
+const ADDRESS_LEN: usize = 7;
+
+/// Poll/final bit of the control field.
+const POLL_FINAL: u8 = 0x10;
+
+/// AX.25 frame errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ax25Error {
+    /// Frame ends inside the address or control fields
+    TooShort,
+    /// Callsign or SSID not valid
+    BadAddress,
+    /// More than [`MAX_DIGIPEATERS`] digipeaters
+    TooManyDigipeaters,
+    /// Information field longer than [`MAX_INFO_LEN`]
+    InfoTooLong,
+    /// Encoded frame longer than [`MAX_FRAME_LEN`](crate::hdlc::MAX_FRAME_LEN)
+    TooLong,
+    /// Not a UI frame
+    NotUiFrame,
+}
+
+/// Station address: callsign and SSID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Address {
+    /// Callsign, 1 to 6 upper case letters and digits
+    pub callsign: String<6>,
+    /// Secondary station identifier (0 to 15)
+    pub ssid: u8,
+    /// Digipeater has repeated the frame
+    pub repeated: bool,
+}
+
+impl Address {
+    /// Create an address.
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::BadAddress`] unless the callsign is 1 to 6 letters and
+    /// digits and the SSID is at most 15.
+    pub fn new(callsign: &str, ssid: u8) -> Result<Self, Ax25Error> {
+        let valid = !callsign.is_empty()
+            && callsign.len() <= 6
+            && callsign.bytes().all(|b| b.is_ascii_alphanumeric())
+            && ssid <= 15;
+        if !valid {
+            return Err(Ax25Error::BadAddress);
+        }
+        let mut call = String::new();
+        for ch in callsign.chars() {
+            let _ = call.push(ch.to_ascii_uppercase());
+        }
+        Ok(Self {
+            callsign: call,
+            ssid,
+            repeated: false,
+        })
+    }
+
+    /// Parse text such as `N0CALL-9` or `WIDE2-1`, with `*` if repeated.
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::BadAddress`] if the text is not a valid address.
+    pub fn parse(text: &str) -> Result<Self, Ax25Error> {
+        let (text, repeated) = match text.strip_suffix('*') {
+            Some(t) => (t, true),
+            None => (text, false),
+        };
+        let (call, ssid) = match text.split_once('-') {
+            Some((call, ssid)) => (call, ssid.parse().map_err(|_| Ax25Error::BadAddress)?),
+            None => (text, 0),
+        };
+        let mut address = Self::new(call, ssid)?;
+        address.repeated = repeated;
+        Ok(address)
+    }
+
+    /// Decode seven address bytes, returning the address and whether it
+    /// is the last one.
+    fn decode(bytes: &[u8]) -> Result<(Self, bool), Ax25Error> {
+        let mut call = String::<6>::new();
+        for &b in &bytes[..6] {
+            let ch = b >> 1;
+            if ch == b' ' {
+                continue;
+            }
+            if !ch.is_ascii_alphanumeric() {
+                return Err(Ax25Error::BadAddress);
+            }
+            let _ = call.push(char::from(ch));
+        }
+        if call.is_empty() {
+            return Err(Ax25Error::BadAddress);
+        }
+        let last = bytes[6];
+        let address = Self {
+            callsign: call,
+            ssid: (last >> 1) & 0x0F,
+            repeated: last & 0x80 != 0,
+        };
+        Ok((address, last & 0x01 != 0))
+    }
+
+    /// Append the seven address bytes.
+    ///
+    /// `flag` is the top bit: command/response for the destination and
+    /// source, has-been-repeated for digipeaters.
+    fn encode(&self, flag: bool, last: bool, out: &mut Frame) -> Result<(), Ax25Error> {
+        let mut bytes = [b' ' << 1; ADDRESS_LEN];
+        for (slot, b) in bytes.iter_mut().zip(self.callsign.bytes()) {
+            *slot = b << 1;
+        }
+        bytes[6] = 0x60 | (self.ssid << 1) | if flag { 0x80 } else { 0 } | u8::from(last);
+        out.extend_from_slice(&bytes)
+            .map_err(|()| Ax25Error::TooLong)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.callsign)?;
+        if self.ssid != 0 {
+            write!(f, "-{}", self.ssid)?;
+        }
+        if self.repeated {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+/// An AX.25 UI frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ax25Frame {
+    /// Destination (for APRS, the software identifier)
+    pub destination: Address,
+    /// Sending station
+    pub source: Address,
+    /// Digipeater path
+    pub digipeaters: Vec<Address, MAX_DIGIPEATERS>,
+    /// Protocol identifier
+    pub pid: u8,
+    /// Information field
+    pub info: Vec<u8, MAX_INFO_LEN>,
+}
+
+impl Ax25Frame {
+    /// Create a UI frame with no layer 3 protocol.
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::TooManyDigipeaters`] or [`Ax25Error::InfoTooLong`] if
+    /// the path or information field do not fit.
+    pub fn new_ui(
+        destination: Address,
+        source: Address,
+        digipeaters: &[Address],
+        info: &[u8],
+    ) -> Result<Self, Ax25Error> {
+        let mut path = Vec::new();
+        for digi in digipeaters {
+            path.push(digi.clone())
+                .map_err(|_| Ax25Error::TooManyDigipeaters)?;
+        }
+        Ok(Self {
+            destination,
+            source,
+            digipeaters: path,
+            pid: PID_NO_LAYER3,
+            info: Vec::from_slice(info).map_err(|()| Ax25Error::InfoTooLong)?,
+        })
+    }
+
+    /// Parse a frame received without its FCS.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Ax25Error`] if the frame is malformed or not a UI frame.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Ax25Error> {
+        let mut addresses = bytes.chunks(ADDRESS_LEN);
+        let mut next = || -> Result<(Address, bool), Ax25Error> {
+            match addresses.next() {
+                Some(chunk) if chunk.len() == ADDRESS_LEN => Address::decode(chunk),
+                _ => Err(Ax25Error::TooShort),
+            }
+        };
+
+        let (mut destination, _) = next()?;
+        let (mut source, mut last) = next()?;
+        // The top bits of these two are command/response, not repeated
+        destination.repeated = false;
+        source.repeated = false;
+        let mut digipeaters = Vec::new();
+        while !last {
+            let (digi, end) = next()?;
+            digipeaters
+                .push(digi)
+                .map_err(|_| Ax25Error::TooManyDigipeaters)?;
+            last = end;
+        }
+
+        let rest = &bytes[ADDRESS_LEN * (2 + digipeaters.len())..];
+        let [control, pid, info @ ..] = rest else {
+            return Err(Ax25Error::TooShort);
+        };
+        if control & !POLL_FINAL != CONTROL_UI {
+            return Err(Ax25Error::NotUiFrame);
+        }
+        Ok(Self {
+            destination,
+            source,
+            digipeaters,
+            pid: *pid,
+            info: Vec::from_slice(info).map_err(|()| Ax25Error::InfoTooLong)?,
+        })
+    }
+
+    /// Encode the frame (without FCS).
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::TooLong`] if it exceeds [`MAX_FRAME_LEN`](crate::hdlc::MAX_FRAME_LEN).
+    pub fn encode(&self) -> Result<Frame, Ax25Error> {
+        let mut out = Frame::new();
+        self.destination.encode(true, false, &mut out)?;
+        self.source
+            .encode(false, self.digipeaters.is_empty(), &mut out)?;
+        for (k, digi) in self.digipeaters.iter().enumerate() {
+            digi.encode(digi.repeated, k + 1 == self.digipeaters.len(), &mut out)?;
+        }
+        out.extend_from_slice(&[CONTROL_UI, self.pid])
+            .map_err(|()| Ax25Error::TooLong)?;
+        out.extend_from_slice(&self.info)
+            .map_err(|()| Ax25Error::TooLong)?;
+        Ok(out)
+    }
+}
+
+/// TNC2 monitor format, e.g. `N0CALL-9>APRS,WIDE1-1*:!4903.50N/07201.75W>`.
+///
+/// Bytes outside printable ASCII in the information field are shown as
+/// `<0xNN>`.
+impl fmt::Display for Ax25Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}>{}", self.source, self.destination)?;
+        for digi in &self.digipeaters {
+            write!(f, ",{}", digi)?;
+        }
+        f.write_str(":")?;
+        for &b in &self.info {
+            if b == b' ' || b.is_ascii_graphic() {
+                write!(f, "{}", char::from(b))?;
+            } else {
+                write!(f, "<0x{:02x}>", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_address_parse() {
+        let address = Address::parse("n0call-9").unwrap();
+        assert_eq!(address.callsign.as_str(), "N0CALL");
+        assert_eq!(address.ssid, 9);
+        assert!(Address::parse("WIDE2-1*").unwrap().repeated);
+        assert_eq!(Address::parse("TOOLONG1"), Err(Ax25Error::BadAddress));
+        assert_eq!(Address::parse("N0CALL-16"), Err(Ax25Error::BadAddress));
+        assert_eq!(Address::parse("N0-CALL"), Err(Ax25Error::BadAddress));
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut wide = Address::parse("WIDE1-1").unwrap();
+        wide.repeated = true;
+        let frame = Ax25Frame::new_ui(
+            Address::parse("APRS").unwrap(),
+            Address::parse("N0CALL-9").unwrap(),
+            &[wide, Address::parse("WIDE2-1").unwrap()],
+            b"!4903.50N/07201.75W>Test",
+        )
+        .unwrap();
+
+        let bytes = frame.encode().unwrap();
+        assert_eq!(bytes.len(), 4 * 7 + 2 + 24);
+        assert_eq!(&bytes[..7], &[0x82, 0xA0, 0xA4, 0xA6, 0x40, 0x40, 0xE0]);
+        assert_eq!(Ax25Frame::parse(&bytes), Ok(frame.clone()));
+
+        let mut text = String::<128>::new();
+        write!(text, "{}", frame).unwrap();
+        assert_eq!(
+            text.as_str(),
+            "N0CALL-9>APRS,WIDE1-1*,WIDE2-1:!4903.50N/07201.75W>Test"
+        );
+
+        assert_eq!(Ax25Frame::parse(&bytes[..20]), Err(Ax25Error::TooShort));
+        let mut not_ui = bytes.clone();
+        not_ui[28] = 0x00;
+        assert_eq!(Ax25Frame::parse(&not_ui), Err(Ax25Error::NotUiFrame));
+    }
+}
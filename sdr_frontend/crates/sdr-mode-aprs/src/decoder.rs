@@ -0,0 +1,51 @@
+//! APRS packet decoder.
+
+use crate::afsk::AfskDemodulator;
+use crate::hdlc::{Frame, HdlcDeframer};
+
+/// Audio to AX.25 frame decoder.
+///
+/// Parse the frames with [`Ax25Frame::parse`](crate::Ax25Frame::parse)
+/// and the information field with [`AprsPacket::parse`](crate::AprsPacket::parse).
+#[derive(Clone, Debug)]
+pub struct AprsDecoder {
+    demod: AfskDemodulator,
+    deframer: HdlcDeframer,
+    /// Frames received with a valid FCS
+    frames: u32,
+}
+
+impl AprsDecoder {
+    /// Create a decoder for audio at `sample_rate`.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            demod: AfskDemodulator::new(sample_rate),
+            deframer: HdlcDeframer::new(),
+            frames: 0,
+        }
+    }
+
+    /// Process one audio sample.
+    ///
+    /// Returns a frame (without FCS) when one ends with a valid FCS.
+    pub fn process(&mut self, audio: f32) -> Option<Frame> {
+        let bit = self.demod.process(audio)?;
+        let frame = self.deframer.push_bit(bit)?;
+        self.frames = self.frames.wrapping_add(1);
+        Some(frame)
+    }
+
+    /// Get the number of frames received.
+    #[must_use]
+    pub fn frame_count(&self) -> u32 {
+        self.frames
+    }
+
+    /// Reset decoder state.
+    pub fn reset(&mut self) {
+        self.demod.reset();
+        self.deframer.reset();
+        self.frames = 0;
+    }
+}
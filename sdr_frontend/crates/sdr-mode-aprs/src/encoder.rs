@@ -0,0 +1,148 @@
+//! APRS packet encoder for beaconing.
+
+use crate::afsk::{AfskModulator, BAUD_RATE};
+use crate::ax25::{Ax25Error, Ax25Frame};
+use crate::hdlc::HdlcFramer;
+
+/// Destination (software identifier) used for packets sent by this project.
+pub const TOCALL: &str = "APZSDR";
+
+/// Default time of flags sent before the frame while the receiver and
+/// transmitter settle, in ms.
+pub const DEFAULT_TX_DELAY_MS: u32 = 300;
+
+/// Flags sent after the frame.
+const TAIL_FLAGS: u16 = 3;
+
+/// AX.25 frame to AFSK audio encoder.
+#[derive(Clone, Debug)]
+pub struct AprsEncoder {
+    framer: HdlcFramer,
+    modulator: AfskModulator,
+    /// Opening flags
+    tx_delay_flags: u16,
+}
+
+impl AprsEncoder {
+    /// Create an encoder for `sample_rate` with peak `amplitude`.
+    #[must_use]
+    pub fn new(sample_rate: f32, amplitude: f32) -> Self {
+        let mut encoder = Self {
+            framer: HdlcFramer::new(),
+            modulator: AfskModulator::new(sample_rate, amplitude),
+            tx_delay_flags: 0,
+        };
+        encoder.set_tx_delay(DEFAULT_TX_DELAY_MS);
+        encoder
+    }
+
+    /// Set the time of flags sent before each frame, in ms.
+    pub fn set_tx_delay(&mut self, ms: u32) {
+        let flags = ms as f32 * BAUD_RATE / 8000.0;
+        self.tx_delay_flags = (flags as u16).max(1);
+    }
+
+    /// Start sending a frame, replacing any frame in progress.
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::TooLong`] if the frame does not fit.
+    pub fn load(&mut self, frame: &Ax25Frame) -> Result<(), Ax25Error> {
+        let bytes = frame.encode()?;
+        self.framer.load(&bytes, self.tx_delay_flags, TAIL_FLAGS)?;
+        self.modulator.reset();
+        Ok(())
+    }
+
+    /// Check if a frame is being sent.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.framer.is_busy()
+    }
+
+    /// Generate the next audio sample, or `None` when the frame is done.
+    pub fn next_sample(&mut self) -> Option<f32> {
+        self.modulator.next_sample(&mut self.framer)
+    }
+
+    /// Stop sending.
+    pub fn reset(&mut self) {
+        self.framer = HdlcFramer::new();
+        self.modulator.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aprs::{AprsPacket, Position};
+    use crate::ax25::Address;
+    use crate::decoder::AprsDecoder;
+    use heapless::String;
+
+    fn beacon() -> Ax25Frame {
+        let mut info = String::<64>::new();
+        AprsPacket::Position {
+            position: Position {
+                latitude: 49.0583,
+                longitude: -72.0292,
+                symbol_table: '/',
+                symbol_code: '-',
+            },
+            messaging: false,
+            comment: "SDR beacon",
+        }
+        .write(&mut info)
+        .unwrap();
+        Ax25Frame::new_ui(
+            Address::parse(TOCALL).unwrap(),
+            Address::parse("N0CALL-7").unwrap(),
+            &[Address::parse("WIDE2-1").unwrap()],
+            info.as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode_loopback() {
+        let frame = beacon();
+        let rate = 48000.0;
+        let mut encoder = AprsEncoder::new(rate, 0.5);
+        let mut decoder = AprsDecoder::new(rate);
+
+        // Twice, with some silence and noise between
+        let mut noise = 1u32;
+        let mut received = 0;
+        for _ in 0..2 {
+            encoder.load(&frame).unwrap();
+            assert!(encoder.is_busy());
+            let silence = core::iter::repeat_with(|| {
+                noise = noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (noise >> 16) as f32 / 65536.0 * 0.05 - 0.025
+            })
+            .take(2000)
+            .collect::<heapless::Vec<f32, 2000>>();
+            for sample in silence
+                .into_iter()
+                .chain(core::iter::from_fn(|| encoder.next_sample()))
+            {
+                if let Some(bytes) = decoder.process(sample) {
+                    assert_eq!(Ax25Frame::parse(&bytes), Ok(frame.clone()));
+                    received += 1;
+                }
+            }
+            assert!(!encoder.is_busy());
+        }
+        assert_eq!(received, 2);
+        assert_eq!(decoder.frame_count(), 2);
+
+        let Some(AprsPacket::Position {
+            position, comment, ..
+        }) = AprsPacket::parse(&frame.info)
+        else {
+            panic!("not a position");
+        };
+        assert!((position.latitude - 49.0583).abs() < 2e-4);
+        assert_eq!(comment, "SDR beacon");
+    }
+}
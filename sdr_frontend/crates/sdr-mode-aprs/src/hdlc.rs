@@ -0,0 +1,291 @@
+//! HDLC framing: flags, bit stuffing and the frame check sequence.
+//!
+//! Bits go out least significant first. Frames are delimited by the
+//! flag 0x7E; inside a frame a 0 is inserted after every five 1s so the
+//! flag cannot appear in the data. The last two bytes of a frame are
+//! the FCS, a CRC-16 (X.25) sent low byte first.
+
+use heapless::Vec;
+
+use crate::ax25::Ax25Error;
+
+/// HDLC flag byte.
+pub const FLAG: u8 = 0x7E;
+
+/// Largest frame handled, including the FCS: ten addresses, control,
+/// PID, 256 bytes of information and FCS.
+pub const MAX_FRAME_LEN: usize = 330;
+
+/// Shortest frame accepted, including the FCS: two addresses, control, FCS.
+const MIN_FRAME_LEN: usize = 17;
+
+/// Frame bytes.
+pub type Frame = Vec<u8, MAX_FRAME_LEN>;
+
+/// Compute the AX.25 frame check sequence (CRC-16/X.25).
+#[must_use]
+pub fn fcs(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Receive side: recovers frames from a stream of (NRZI-decoded) bits.
+#[derive(Clone, Debug, Default)]
+pub struct HdlcDeframer {
+    /// Last eight bits, newest in the top bit
+    pattern: u8,
+    /// Consecutive 1 bits
+    ones: u8,
+    /// A flag has been seen and no abort since
+    in_frame: bool,
+    /// Byte being assembled
+    byte: u8,
+    /// Bits in `byte`
+    bits: u8,
+    buffer: Frame,
+}
+
+impl HdlcDeframer {
+    /// Create a new deframer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one bit.
+    ///
+    /// Returns a frame (without the FCS) when a closing flag ends one
+    /// with a valid FCS.
+    pub fn push_bit(&mut self, bit: bool) -> Option<Frame> {
+        self.pattern = (self.pattern >> 1) | if bit { 0x80 } else { 0 };
+        if self.pattern == FLAG {
+            let frame = self.finish();
+            self.in_frame = true;
+            self.buffer.clear();
+            self.byte = 0;
+            self.bits = 0;
+            self.ones = 0;
+            return frame;
+        }
+
+        if bit {
+            self.ones = self.ones.saturating_add(1);
+            if self.ones > 6 {
+                // Abort or idle line
+                self.in_frame = false;
+                return None;
+            }
+        } else {
+            let stuffed = self.ones == 5;
+            self.ones = 0;
+            if stuffed {
+                return None;
+            }
+        }
+
+        if !self.in_frame {
+            return None;
+        }
+        self.byte = (self.byte >> 1) | if bit { 0x80 } else { 0 };
+        self.bits += 1;
+        if self.bits == 8 {
+            if self.buffer.push(self.byte).is_err() {
+                self.in_frame = false;
+            }
+            self.byte = 0;
+            self.bits = 0;
+        }
+        None
+    }
+
+    /// Check the frame closed by a flag.
+    ///
+    /// The first seven bits of the flag have been shifted in as data by
+    /// then, so a byte-aligned frame leaves exactly seven spare bits.
+    fn finish(&mut self) -> Option<Frame> {
+        if !self.in_frame || self.bits != 7 || self.buffer.len() < MIN_FRAME_LEN {
+            return None;
+        }
+        let n = self.buffer.len() - 2;
+        let received = u16::from_le_bytes([self.buffer[n], self.buffer[n + 1]]);
+        if fcs(&self.buffer[..n]) != received {
+            return None;
+        }
+        let mut frame = core::mem::take(&mut self.buffer);
+        frame.truncate(n);
+        Some(frame)
+    }
+
+    /// Reset deframer state.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Framer stage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Stage {
+    #[default]
+    Idle,
+    /// Opening flags left
+    Preamble(u16),
+    Data,
+    /// Closing flags left
+    Postamble(u16),
+}
+
+/// Transmit side: turns a frame into bits with flags, stuffing and FCS.
+///
+/// Iterate to get the bits (before NRZI encoding).
+#[derive(Clone, Debug, Default)]
+pub struct HdlcFramer {
+    stage: Stage,
+    /// Frame followed by its FCS
+    data: Frame,
+    /// Next byte of `data`
+    position: usize,
+    /// Next bit of the current byte or flag
+    bit: u8,
+    /// Consecutive 1 bits sent from `data`
+    ones: u8,
+    postamble: u16,
+}
+
+impl HdlcFramer {
+    /// Create an idle framer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start sending `frame` (without FCS) between `preamble` and
+    /// `postamble` flags (at least one of each is sent).
+    ///
+    /// # Errors
+    ///
+    /// [`Ax25Error::TooLong`] if the frame and FCS exceed [`MAX_FRAME_LEN`].
+    pub fn load(&mut self, frame: &[u8], preamble: u16, postamble: u16) -> Result<(), Ax25Error> {
+        let mut data = Frame::new();
+        data.extend_from_slice(frame)
+            .map_err(|()| Ax25Error::TooLong)?;
+        data.extend_from_slice(&fcs(frame).to_le_bytes())
+            .map_err(|()| Ax25Error::TooLong)?;
+        *self = Self {
+            stage: Stage::Preamble(preamble.max(1)),
+            data,
+            position: 0,
+            bit: 0,
+            ones: 0,
+            postamble: postamble.max(1),
+        };
+        Ok(())
+    }
+
+    /// Check if bits are left to send.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    /// Get the next bit, or `None` once the closing flags are sent.
+    pub fn next_bit(&mut self) -> Option<bool> {
+        if self.stage == Stage::Idle {
+            return None;
+        }
+        if self.ones == 5 {
+            // Stuff a 0, including after the last data byte
+            self.ones = 0;
+            return Some(false);
+        }
+
+        match self.stage {
+            Stage::Idle => None,
+            Stage::Preamble(left) | Stage::Postamble(left) => {
+                let bit = (FLAG >> self.bit) & 1 != 0;
+                self.bit += 1;
+                if self.bit == 8 {
+                    self.bit = 0;
+                    self.stage = match self.stage {
+                        Stage::Preamble(1) => Stage::Data,
+                        Stage::Preamble(_) => Stage::Preamble(left - 1),
+                        Stage::Postamble(1) => Stage::Idle,
+                        _ => Stage::Postamble(left - 1),
+                    };
+                }
+                Some(bit)
+            }
+            Stage::Data => {
+                let bit = (self.data[self.position] >> self.bit) & 1 != 0;
+                self.ones = if bit { self.ones + 1 } else { 0 };
+                self.bit += 1;
+                if self.bit == 8 {
+                    self.bit = 0;
+                    self.position += 1;
+                    if self.position == self.data.len() {
+                        self.stage = Stage::Postamble(self.postamble);
+                    }
+                }
+                Some(bit)
+            }
+        }
+    }
+}
+
+impl Iterator for HdlcFramer {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        self.next_bit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fcs() {
+        assert_eq!(fcs(b"123456789"), 0x906E);
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        // Runs of 1s force stuffing, including right before the FCS
+        let mut frame = [0u8; 40];
+        for (k, byte) in frame.iter_mut().enumerate() {
+            *byte = if k % 3 == 0 { 0xFF } else { FLAG ^ k as u8 };
+        }
+
+        let mut framer = HdlcFramer::new();
+        framer.load(&frame, 4, 2).unwrap();
+        let mut deframer = HdlcDeframer::new();
+        let mut received = None;
+        for bit in framer.by_ref() {
+            if let Some(f) = deframer.push_bit(bit) {
+                received = Some(f);
+            }
+        }
+        assert!(!framer.is_busy());
+        assert_eq!(received.as_deref(), Some(&frame[..]));
+
+        // A corrupted bit fails the FCS
+        framer.load(&frame, 4, 2).unwrap();
+        let mut received = None;
+        for (k, bit) in framer.enumerate() {
+            if let Some(f) = deframer.push_bit(if k == 100 { !bit } else { bit }) {
+                received = Some(f);
+            }
+        }
+        assert_eq!(received, None);
+    }
+}
@@ -0,0 +1,29 @@
+//! AX.25/APRS Packet Decoder/Encoder
+//!
+//! Implements 1200 baud Bell 202 AFSK packet radio (1200 Hz mark,
+//! 2200 Hz space) as used by APRS on VHF FM.
+//!
+//! # Features
+//! - AFSK demodulation with bit clock recovery
+//! - HDLC deframing with bit unstuffing and FCS (CRC) checking
+//! - AX.25 UI frame parsing and encoding
+//! - APRS position (uncompressed and compressed) and status parsing
+//! - Matching encoder for position beacons
+
+#![no_std]
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod afsk;
+pub mod aprs;
+pub mod ax25;
+pub mod decoder;
+pub mod encoder;
+pub mod hdlc;
+
+pub use afsk::{AfskDemodulator, AfskModulator, BAUD_RATE, MARK_HZ, SPACE_HZ};
+pub use aprs::{AprsPacket, Position};
+pub use ax25::{Address, Ax25Error, Ax25Frame, MAX_DIGIPEATERS, MAX_INFO_LEN};
+pub use decoder::AprsDecoder;
+pub use encoder::{AprsEncoder, TOCALL};
+pub use hdlc::{fcs, Frame, HdlcDeframer, HdlcFramer, MAX_FRAME_LEN};
@@ -0,0 +1,213 @@
+//! Key timing to text decoder.
+//!
+//! Marks are sorted into dits and dahs by comparing them with running
+//! averages of each, so the decoder follows the sender's speed and
+//! weighting. Spaces longer than two units end a character; longer than
+//! five, a word.
+
+use crate::morse::Pattern;
+
+/// Speed assumed before any marks are heard, in WPM.
+pub const DEFAULT_WPM: f32 = 20.0;
+
+/// Slowest speed followed, in WPM.
+pub const MIN_WPM: f32 = 5.0;
+/// Fastest speed followed, in WPM.
+pub const MAX_WPM: f32 = 60.0;
+
+/// Weight of each new mark in the dit and dah averages.
+const ADAPT_RATE: f32 = 0.25;
+
+/// Spaces longer than this many units end a character.
+const CHAR_SPACE_UNITS: f32 = 2.0;
+
+/// Spaces longer than this many units end a word.
+const WORD_SPACE_UNITS: f32 = 5.0;
+
+/// Marks shorter than this fraction of a dit are ignored as noise.
+const GLITCH_DITS: f32 = 0.3;
+
+/// Marks longer than this many units (a tuning carrier) are ignored
+/// and discard the character.
+const MAX_MARK_UNITS: f32 = 10.0;
+
+/// Get the dit length in milliseconds at `wpm` (PARIS timing).
+#[must_use]
+pub fn unit_ms(wpm: f32) -> f32 {
+    1200.0 / wpm
+}
+
+/// Adaptive Morse timing decoder.
+///
+/// Fed the key state at regular intervals, e.g. from an envelope
+/// detector or a straight key input.
+#[derive(Clone, Debug)]
+pub struct MorseDecoder {
+    /// Key down during the current run
+    key: bool,
+    /// Length of the current run in ms
+    run_ms: f32,
+    /// Average dit and dah lengths in ms
+    dit_ms: f32,
+    dah_ms: f32,
+    pattern: Pattern,
+    /// A character has been sent since the last word space
+    in_word: bool,
+}
+
+impl Default for MorseDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MorseDecoder {
+    /// Create a decoder expecting [`DEFAULT_WPM`].
+    #[must_use]
+    pub fn new() -> Self {
+        let dit_ms = unit_ms(DEFAULT_WPM);
+        Self {
+            key: false,
+            run_ms: 0.0,
+            dit_ms,
+            dah_ms: 3.0 * dit_ms,
+            pattern: Pattern::new(),
+            in_word: false,
+        }
+    }
+
+    /// Get the estimated unit (dit) length in ms.
+    #[must_use]
+    pub fn unit_ms(&self) -> f32 {
+        (self.dit_ms + self.dah_ms / 3.0) / 2.0
+    }
+
+    /// Get the estimated speed in WPM.
+    #[must_use]
+    pub fn wpm(&self) -> f32 {
+        1200.0 / self.unit_ms()
+    }
+
+    /// Check whether the key is down.
+    #[must_use]
+    pub fn is_key_down(&self) -> bool {
+        self.key
+    }
+
+    /// Update with the key state over the last `elapsed_ms`.
+    ///
+    /// Returns a character once the space after it is long enough, and a
+    /// space once the gap after a word is.
+    pub fn update(&mut self, key_down: bool, elapsed_ms: f32) -> Option<char> {
+        if key_down != self.key {
+            if self.key {
+                self.mark(self.run_ms);
+            }
+            self.key = key_down;
+            self.run_ms = 0.0;
+        }
+        self.run_ms += elapsed_ms;
+        if self.key {
+            return None;
+        }
+
+        let unit = self.unit_ms();
+        if !self.pattern.is_empty() && self.run_ms > CHAR_SPACE_UNITS * unit {
+            let ch = self.pattern.decode();
+            self.pattern.clear();
+            if ch.is_some() {
+                self.in_word = true;
+            }
+            return ch;
+        }
+        if self.in_word && self.run_ms > WORD_SPACE_UNITS * unit {
+            self.in_word = false;
+            return Some(' ');
+        }
+        None
+    }
+
+    /// Classify a finished mark of `ms`.
+    fn mark(&mut self, ms: f32) {
+        if ms < GLITCH_DITS * self.dit_ms {
+            return;
+        }
+        if ms > MAX_MARK_UNITS * self.unit_ms() {
+            self.pattern.clear();
+            return;
+        }
+        let dah = ms * ms > self.dit_ms * self.dah_ms;
+        if dah {
+            self.dah_ms += (ms - self.dah_ms) * ADAPT_RATE;
+            self.dit_ms = self.dit_ms.clamp(self.dah_ms / 4.0, self.dah_ms / 2.0);
+        } else {
+            self.dit_ms += (ms - self.dit_ms) * ADAPT_RATE;
+            self.dah_ms = self.dah_ms.clamp(2.0 * self.dit_ms, 4.0 * self.dit_ms);
+        }
+        // Keep within the speed range, preserving the weighting
+        let unit = self.unit_ms();
+        let ratio = unit.clamp(unit_ms(MAX_WPM), unit_ms(MIN_WPM)) / unit;
+        self.dit_ms *= ratio;
+        self.dah_ms *= ratio;
+        if !self.pattern.push(dah) {
+            // Longer than any character
+            self.pattern.clear();
+        }
+    }
+
+    /// Forget the character in progress and the speed estimate.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::elements;
+
+    /// Key `text` at `wpm` with dahs `weight` units long, calling `step`
+    /// with the key state every `tick_ms`.
+    fn key_text(text: &str, wpm: f32, weight: f32, tick_ms: f32, mut step: impl FnMut(bool)) {
+        let unit = unit_ms(wpm);
+        let mut run = |key: bool, units: f32| {
+            for _ in 0..(units * unit / tick_ms).round() as usize {
+                step(key);
+            }
+        };
+        for word in text.split(' ') {
+            for ch in word.chars() {
+                for element in elements(ch).unwrap().bytes() {
+                    run(true, if element == b'-' { weight } else { 1.0 });
+                    run(false, 1.0);
+                }
+                run(false, 2.0);
+            }
+            run(false, 4.0);
+        }
+    }
+
+    fn decode(text: &str, wpm: f32, weight: f32) -> (heapless::String<64>, f32) {
+        let mut decoder = MorseDecoder::new();
+        let mut out = heapless::String::new();
+        key_text(text, wpm, weight, 2.0, |key| {
+            if let Some(ch) = decoder.update(key, 2.0) {
+                let _ = out.push(ch);
+            }
+        });
+        (out, decoder.wpm())
+    }
+
+    #[test]
+    fn test_decode_speeds() {
+        for wpm in [12.0, 20.0, 35.0] {
+            let (text, estimate) = decode("VVV CQ CQ DE N0CALL K", wpm, 3.0);
+            // The first characters may be lost while the speed is learned
+            assert!(text.ends_with("CQ DE N0CALL K "), "{wpm}: {text}");
+            assert!((estimate - wpm).abs() < wpm * 0.1, "{wpm}: {estimate}");
+        }
+        // Heavy weighting
+        let (text, _) = decode("TEST TEST 5NN", 25.0, 3.8);
+        assert!(text.ends_with("TEST 5NN "), "{text}");
+    }
+}
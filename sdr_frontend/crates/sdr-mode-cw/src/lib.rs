@@ -0,0 +1,25 @@
+//! CW (Morse) Decoding and Skimmer
+//!
+//! Decodes Morse code from key timing, and skims the CW passband for
+//! several signals at once, decoding each in parallel with its own
+//! speed estimate.
+//!
+//! # Features
+//! - Morse table and element patterns
+//! - Adaptive timing decoder following speed and weighting
+//! - Goertzel bank skimmer tracking up to ten signals
+
+#![no_std]
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod decoder;
+pub mod morse;
+pub mod skimmer;
+
+pub use decoder::{unit_ms, MorseDecoder, DEFAULT_WPM, MAX_WPM, MIN_WPM};
+pub use morse::{elements, Pattern, MAX_ELEMENTS, MORSE_TABLE};
+pub use skimmer::{
+    CwSignal, CwSkimmer, SkimmedChar, BIN_SPACING_HZ, DEFAULT_HIGH_HZ, DEFAULT_LOW_HZ, MAX_BINS,
+    MAX_SIGNALS,
+};
@@ -0,0 +1,163 @@
+//! Morse code table.
+
+/// Most elements in a character.
+pub const MAX_ELEMENTS: usize = 7;
+
+/// Characters and their elements (`.` dit, `-` dah).
+pub const MORSE_TABLE: [(char, &str); 54] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('/', "-..-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('\'', ".----."),
+    (':', "---..."),
+    (';', "-.-.-."),
+    ('"', ".-..-."),
+    ('@', ".--.-."),
+    ('!', "-.-.--"),
+    ('&', ".-..."),
+    ('_', "..--.-"),
+    ('$', "...-..-"),
+];
+
+/// Get the elements of `ch` (`.` dit, `-` dah), or `None` if it has no
+/// Morse equivalent. Lower case is sent as upper case.
+#[must_use]
+pub fn elements(ch: char) -> Option<&'static str> {
+    let upper = ch.to_ascii_uppercase();
+    MORSE_TABLE
+        .iter()
+        .find(|(c, _)| *c == upper)
+        .map(|(_, elements)| *elements)
+}
+
+/// Elements of a character being received.
+///
+/// Dahs are one bits, the first element the most significant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    bits: u8,
+    len: u8,
+}
+
+impl Pattern {
+    /// Create an empty pattern.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { bits: 0, len: 0 }
+    }
+
+    /// Add an element, returning `false` if the pattern is already as
+    /// long as any character.
+    pub fn push(&mut self, dah: bool) -> bool {
+        if usize::from(self.len) >= MAX_ELEMENTS {
+            return false;
+        }
+        self.bits = (self.bits << 1) | u8::from(dah);
+        self.len += 1;
+        true
+    }
+
+    /// Get the number of elements.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Check whether no elements have been added.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Look the pattern up in [`MORSE_TABLE`].
+    #[must_use]
+    pub fn decode(&self) -> Option<char> {
+        MORSE_TABLE
+            .iter()
+            .find(|(_, elements)| Self::from_elements(elements) == *self)
+            .map(|(ch, _)| *ch)
+    }
+
+    /// Build a pattern from `.` and `-` characters.
+    fn from_elements(elements: &str) -> Self {
+        let mut pattern = Self::new();
+        for element in elements.bytes() {
+            pattern.push(element == b'-');
+        }
+        pattern
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_round_trip() {
+        for (ch, elements) in MORSE_TABLE {
+            assert!(elements.len() <= MAX_ELEMENTS);
+            let mut pattern = Pattern::new();
+            for element in elements.bytes() {
+                assert!(pattern.push(element == b'-'));
+            }
+            assert_eq!(pattern.decode(), Some(ch), "{elements}");
+        }
+        assert_eq!(elements('k'), Some("-.-"));
+        assert_eq!(elements('#'), None);
+
+        // Eight dits is an error, not a character
+        let mut pattern = Pattern::new();
+        for _ in 0..MAX_ELEMENTS {
+            pattern.push(false);
+        }
+        assert!(!pattern.push(false));
+        assert_eq!(pattern.decode(), None);
+    }
+}
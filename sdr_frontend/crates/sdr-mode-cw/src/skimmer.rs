@@ -0,0 +1,514 @@
+//! Multi-signal CW skimmer.
+//!
+//! The audio is low-pass filtered and decimated to about 4 kHz. Every
+//! 8 ms a bank of Goertzel filters, 25 Hz apart across the passband,
+//! measures the power over the last 32 ms (Hann windowed). Bins standing
+//! well above their noise floor start a signal track; each track keys a
+//! [`MorseDecoder`] from the power in its bin and is dropped once it
+//! fades or after ten seconds without a mark.
+//!
+//! Signals need to be about 100 Hz apart to be told apart.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use core::f32::consts::PI;
+use heapless::Vec;
+use sdr_dsp_core::filter::Biquad;
+
+use crate::decoder::MorseDecoder;
+
+/// Most signals tracked at once.
+pub const MAX_SIGNALS: usize = 10;
+
+/// Most Goertzel bins across the passband.
+pub const MAX_BINS: usize = 64;
+
+/// Spacing of the Goertzel bins in Hz.
+pub const BIN_SPACING_HZ: f32 = 25.0;
+
+/// Default passband in Hz.
+pub const DEFAULT_LOW_HZ: f32 = 300.0;
+/// Default passband upper edge in Hz.
+pub const DEFAULT_HIGH_HZ: f32 = 1200.0;
+
+/// Target rate of the decimated audio in Hz.
+const ANALYSIS_RATE: f32 = 4000.0;
+
+/// Length of the analysis window in seconds.
+const WINDOW_SECONDS: f32 = 0.032;
+
+/// Analysis rows per window length.
+const HOPS_PER_WINDOW: usize = 4;
+
+/// Longest window in samples (the decimated rate is under 5 kHz).
+const MAX_WINDOW: usize = 160;
+
+/// Bin power above the noise floor that starts a track, in dB.
+const DETECT_DB: f32 = 16.0;
+
+/// Tracks whose recent peak falls below this, in dB above the noise
+/// floor, are dropped.
+const DROP_DB: f32 = 12.0;
+
+/// Lowest power above the noise floor that counts as key down, in dB.
+const KEY_MIN_DB: f32 = 10.0;
+
+/// Key down threshold below the track's recent peak, in dB (half
+/// amplitude, so marks keep their length through the window).
+const KEY_BELOW_PEAK_DB: f32 = 6.0;
+
+/// Key up threshold below the key down threshold, in dB.
+const HYSTERESIS_DB: f32 = 2.0;
+
+/// Decay of a track's peak level per row, in dB.
+const PEAK_DECAY_DB: f32 = 0.02;
+
+/// Rise of a bin's noise floor per row while above it, in dB.
+///
+/// With [`NOISE_FALL_DB`] this settles on the power exceeded in 80% of
+/// rows: the noise, about 6.5 dB below its mean, even while a signal is
+/// keyed in the bin.
+const NOISE_RISE_DB: f32 = 0.05;
+
+/// Fall of a bin's noise floor per row while below it, in dB.
+const NOISE_FALL_DB: f32 = 0.2;
+
+/// Rows after a reset during which the noise floor settles (rising as
+/// fast as it falls) and no tracks are started.
+const WARMUP_ROWS: usize = 125;
+
+/// Rows a bin must stay above [`DETECT_DB`] to start a track, so noise
+/// peaks and key clicks are not taken for signals.
+const DETECT_ROWS: u8 = 6;
+
+/// Bins either side checked for a much stronger signal whose window
+/// sidelobes or key clicks could be mistaken for a new one.
+const MASK_BINS: usize = 8;
+
+/// A candidate more than this far below a bin within [`MASK_BINS`] is
+/// ignored, in dB.
+const MASK_DB: f32 = 25.0;
+
+/// New tracks start at least this many bins from existing ones.
+const TRACK_SPACING_BINS: usize = 3;
+
+/// Tracks without a mark for this long are dropped, in seconds.
+const TIMEOUT_SECONDS: f32 = 10.0;
+
+/// A character decoded from one signal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SkimmedChar {
+    /// Signal id, stable while the signal is tracked
+    pub signal: u32,
+    /// Decoded character (space between words)
+    pub ch: char,
+}
+
+/// A tracked signal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CwSignal {
+    /// Signal id
+    pub id: u32,
+    /// Audio frequency in Hz
+    pub frequency_hz: f32,
+    /// Estimated speed in WPM
+    pub wpm: f32,
+    /// Recent peak level above the noise floor in dB
+    pub snr_db: f32,
+}
+
+/// One signal being decoded.
+#[derive(Clone, Debug)]
+struct Track {
+    id: u32,
+    bin: usize,
+    decoder: MorseDecoder,
+    /// Recent peak level above the noise floor, in dB
+    peak_db: f32,
+    /// Rows since the last key down
+    idle_rows: u32,
+}
+
+/// Goertzel bank CW skimmer.
+#[derive(Clone, Debug)]
+pub struct CwSkimmer {
+    sample_rate: f32,
+    low_hz: f32,
+    /// Anti-alias filter before decimation
+    filters: [Biquad; 2],
+    decimation: usize,
+    decimation_phase: usize,
+    analysis_rate: f32,
+    /// Last window of decimated samples (oldest at `position`)
+    window: [f32; MAX_WINDOW],
+    window_len: usize,
+    position: usize,
+    hop: usize,
+    since_hop: usize,
+    hann: [f32; MAX_WINDOW],
+    /// Goertzel coefficient of each bin
+    coeffs: [f32; MAX_BINS],
+    bins: usize,
+    /// Noise floor of each bin in dB
+    noise_db: [f32; MAX_BINS],
+    /// Consecutive rows each bin has been above [`DETECT_DB`]
+    detect_rows: [u8; MAX_BINS],
+    /// Rows analysed, up to [`WARMUP_ROWS`]
+    rows: usize,
+    tracks: Vec<Track, MAX_SIGNALS>,
+    next_id: u32,
+}
+
+impl CwSkimmer {
+    /// Create a skimmer for audio at `sample_rate` covering `low_hz` to
+    /// `high_hz`.
+    ///
+    /// The passband is limited to [`MAX_BINS`] bins and to below 1.6 kHz.
+    #[must_use]
+    pub fn new(sample_rate: f32, low_hz: f32, high_hz: f32) -> Self {
+        let decimation = ((sample_rate / ANALYSIS_RATE).round() as usize).max(1);
+        let analysis_rate = sample_rate / decimation as f32;
+        let window_len =
+            ((analysis_rate * WINDOW_SECONDS) as usize).clamp(HOPS_PER_WINDOW, MAX_WINDOW);
+        let high_hz = high_hz.min(0.4 * analysis_rate);
+        let cutoff = (high_hz * 1.2).min(0.45 * analysis_rate);
+        let mut skimmer = Self {
+            sample_rate,
+            low_hz,
+            filters: [
+                Biquad::lowpass(sample_rate, cutoff, 0.541),
+                Biquad::lowpass(sample_rate, cutoff, 1.307),
+            ],
+            decimation,
+            decimation_phase: 0,
+            analysis_rate,
+            window: [0.0; MAX_WINDOW],
+            window_len,
+            position: 0,
+            hop: window_len / HOPS_PER_WINDOW,
+            since_hop: 0,
+            hann: core::array::from_fn(|n| {
+                0.5 - 0.5 * (2.0 * PI * (n as f32 + 0.5) / window_len as f32).cos()
+            }),
+            coeffs: [0.0; MAX_BINS],
+            bins: 0,
+            noise_db: [0.0; MAX_BINS],
+            detect_rows: [0; MAX_BINS],
+            rows: 0,
+            tracks: Vec::new(),
+            next_id: 1,
+        };
+        skimmer.bins = (((high_hz - low_hz) / BIN_SPACING_HZ) as usize + 1).min(MAX_BINS);
+        for bin in 0..skimmer.bins {
+            let omega = 2.0 * PI * skimmer.bin_frequency(bin) / analysis_rate;
+            skimmer.coeffs[bin] = 2.0 * omega.cos();
+        }
+        skimmer
+    }
+
+    /// Get the sample rate in Hz.
+    #[must_use]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Get the audio frequency of a bin in Hz.
+    fn bin_frequency(&self, bin: usize) -> f32 {
+        self.low_hz + bin as f32 * BIN_SPACING_HZ
+    }
+
+    /// Get the time between analysis rows in ms.
+    fn row_ms(&self) -> f32 {
+        1000.0 * self.hop as f32 / self.analysis_rate
+    }
+
+    /// Process one audio sample, returning the characters completed.
+    pub fn process(&mut self, audio: f32) -> Vec<SkimmedChar, MAX_SIGNALS> {
+        let mut filtered = audio;
+        for filter in &mut self.filters {
+            filtered = filter.process(filtered);
+        }
+        self.decimation_phase += 1;
+        if self.decimation_phase < self.decimation {
+            return Vec::new();
+        }
+        self.decimation_phase = 0;
+
+        self.window[self.position] = filtered;
+        self.position = (self.position + 1) % self.window_len;
+        self.since_hop += 1;
+        if self.since_hop < self.hop {
+            return Vec::new();
+        }
+        self.since_hop = 0;
+        self.analyze()
+    }
+
+    /// Run the skimmer over a block, passing each character to `on_char`.
+    pub fn process_block(&mut self, samples: &[f32], mut on_char: impl FnMut(SkimmedChar)) {
+        for &sample in samples {
+            for decoded in self.process(sample) {
+                on_char(decoded);
+            }
+        }
+    }
+
+    /// Measure the power in every bin over the window, in dB.
+    fn bin_powers(&self) -> [f32; MAX_BINS] {
+        let mut powers = [0.0; MAX_BINS];
+        for (bin, power) in powers.iter_mut().enumerate().take(self.bins) {
+            let coeff = self.coeffs[bin];
+            let (mut s1, mut s2) = (0.0f32, 0.0f32);
+            for n in 0..self.window_len {
+                let sample = self.window[(self.position + n) % self.window_len];
+                let s0 = sample * self.hann[n] + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+            let linear = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(1e-20);
+            *power = 10.0 * linear.log10();
+        }
+        powers
+    }
+
+    /// Update the noise floor, tracks and decoders from one row.
+    fn analyze(&mut self) -> Vec<SkimmedChar, MAX_SIGNALS> {
+        let powers = self.bin_powers();
+        // Start the noise floor from the first full window
+        if self.rows < HOPS_PER_WINDOW {
+            self.noise_db = powers;
+        }
+        let warming_up = self.rows < WARMUP_ROWS;
+        if warming_up {
+            self.rows += 1;
+        }
+        let rise_db = if warming_up {
+            NOISE_FALL_DB
+        } else {
+            NOISE_RISE_DB
+        };
+        for (noise, &power) in self.noise_db.iter_mut().zip(&powers).take(self.bins) {
+            if power < *noise {
+                *noise -= (*noise - power).min(NOISE_FALL_DB);
+            } else {
+                *noise += (power - *noise).min(rise_db);
+            }
+        }
+        if warming_up {
+            return Vec::new();
+        }
+
+        self.find_signals(&powers);
+
+        let row_ms = self.row_ms();
+        let timeout_rows = (TIMEOUT_SECONDS * 1000.0 / row_ms) as u32;
+        let mut decoded = Vec::new();
+        for track in &mut self.tracks {
+            // Follow slow drift to a stronger neighbouring bin, while
+            // keyed so noise cannot pull the track away
+            let low = track.bin.saturating_sub(1);
+            let high = (track.bin + 1).min(self.bins - 1);
+            if let Some(best) = (low..=high).max_by(|&a, &b| powers[a].total_cmp(&powers[b])) {
+                if track.decoder.is_key_down() && powers[best] > powers[track.bin] + HYSTERESIS_DB {
+                    track.bin = best;
+                }
+            }
+
+            let snr_db = powers[track.bin] - self.noise_db[track.bin];
+            track.peak_db = (track.peak_db - PEAK_DECAY_DB).max(snr_db);
+            let threshold = (track.peak_db - KEY_BELOW_PEAK_DB).max(KEY_MIN_DB);
+            let key = if track.decoder.is_key_down() {
+                snr_db > threshold - HYSTERESIS_DB
+            } else {
+                snr_db > threshold
+            };
+            track.idle_rows = if key { 0 } else { track.idle_rows + 1 };
+            if let Some(ch) = track.decoder.update(key, row_ms) {
+                // Capacity matches the track count
+                let _ = decoded.push(SkimmedChar {
+                    signal: track.id,
+                    ch,
+                });
+            }
+        }
+        self.tracks
+            .retain(|track| track.idle_rows < timeout_rows && track.peak_db >= DROP_DB);
+        // Tracks that drifted onto the same signal: keep the oldest
+        let mut k = 1;
+        while k < self.tracks.len() {
+            let bin = self.tracks[k].bin;
+            if self.tracks[..k]
+                .iter()
+                .any(|t| t.bin.abs_diff(bin) < TRACK_SPACING_BINS)
+            {
+                self.tracks.remove(k);
+            } else {
+                k += 1;
+            }
+        }
+        decoded
+    }
+
+    /// Start tracks on new peaks well above the noise floor.
+    fn find_signals(&mut self, powers: &[f32; MAX_BINS]) {
+        let levels = powers.iter().zip(&self.noise_db).take(self.bins);
+        for (rows, (power, noise)) in self.detect_rows.iter_mut().zip(levels) {
+            *rows = if power - noise < DETECT_DB {
+                0
+            } else {
+                rows.saturating_add(1)
+            };
+        }
+        for bin in 0..self.bins {
+            if self.tracks.is_full() {
+                return;
+            }
+            let snr_db = powers[bin] - self.noise_db[bin];
+            let is_peak = (bin == 0 || powers[bin] >= powers[bin - 1])
+                && (bin + 1 >= self.bins || powers[bin] >= powers[bin + 1]);
+            let near_track = self
+                .tracks
+                .iter()
+                .any(|t| t.bin.abs_diff(bin) < TRACK_SPACING_BINS);
+            let masked = (bin.saturating_sub(MASK_BINS)..(bin + MASK_BINS + 1).min(self.bins))
+                .any(|other| powers[other] > powers[bin] + MASK_DB);
+            if self.detect_rows[bin] < DETECT_ROWS || !is_peak || near_track || masked {
+                continue;
+            }
+            let _ = self.tracks.push(Track {
+                id: self.next_id,
+                bin,
+                decoder: MorseDecoder::new(),
+                peak_db: snr_db,
+                idle_rows: 0,
+            });
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+    }
+
+    /// Get the signals being tracked.
+    pub fn signals(&self) -> impl Iterator<Item = CwSignal> + '_ {
+        self.tracks.iter().map(|track| CwSignal {
+            id: track.id,
+            frequency_hz: self.bin_frequency(track.bin),
+            wpm: track.decoder.wpm(),
+            snr_db: track.peak_db,
+        })
+    }
+
+    /// Get the number of signals being tracked.
+    #[must_use]
+    pub fn signal_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Drop all tracks and forget the noise floor.
+    pub fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.decimation_phase = 0;
+        self.window = [0.0; MAX_WINDOW];
+        self.position = 0;
+        self.since_hop = 0;
+        self.detect_rows = [0; MAX_BINS];
+        self.rows = 0;
+        self.tracks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::unit_ms;
+    use crate::morse::elements;
+
+    /// Keyed CW for `text` at `wpm`, with 5 ms raised cosine edges.
+    fn cw_samples(
+        rate: f32,
+        frequency_hz: f32,
+        wpm: f32,
+        delay_ms: f32,
+        text: &'static str,
+    ) -> impl Iterator<Item = f32> {
+        let unit = unit_ms(wpm) * rate / 1000.0;
+        let keying = text.chars().flat_map(move |ch| {
+            let elements = elements(ch).unwrap_or("");
+            let gap = if ch == ' ' { 4.0 } else { 2.0 };
+            elements
+                .bytes()
+                .flat_map(|element| {
+                    [
+                        (true, if element == b'-' { 3.0 } else { 1.0 }),
+                        (false, 1.0),
+                    ]
+                })
+                .chain(core::iter::once((false, gap)))
+        });
+        let delay = core::iter::once((false, delay_ms * rate / 1000.0 / unit));
+        let ramp = 0.005 * rate;
+        let mut n = 0usize;
+        delay
+            .chain(keying)
+            .flat_map(move |(key, units)| {
+                let len = (units * unit) as usize;
+                (0..len).map(move |k| {
+                    let edge = (k as f32 / ramp).min((len - k) as f32 / ramp).min(1.0);
+                    if key {
+                        0.5 - 0.5 * (PI * edge).cos()
+                    } else {
+                        0.0
+                    }
+                })
+            })
+            .map(move |envelope| {
+                let phase = 2.0 * PI * frequency_hz * n as f32 / rate;
+                n += 1;
+                0.2 * envelope * phase.sin()
+            })
+            .chain(core::iter::repeat(0.0))
+    }
+
+    #[test]
+    fn test_skims_two_signals() {
+        let rate = 12000.0;
+        let first = cw_samples(rate, 600.0, 18.0, 500.0, "CQ DE N0CALL K ");
+        let second = cw_samples(rate, 850.0, 28.0, 900.0, "TEST DE K1ABC TEST ");
+        // Band noise, about 30 dB below the signals in a bin
+        let mut seed = 1u32;
+        let mut noise = move || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32 * 0.1 - 0.05
+        };
+
+        let mut skimmer = CwSkimmer::new(rate, DEFAULT_LOW_HZ, DEFAULT_HIGH_HZ);
+        let mut texts: Vec<(u32, heapless::String<64>), MAX_SIGNALS> = Vec::new();
+        for (a, b) in first.zip(second).take(14 * rate as usize) {
+            for decoded in skimmer.process(a + b + noise()) {
+                match texts.iter_mut().find(|(id, _)| *id == decoded.signal) {
+                    Some((_, text)) => {
+                        let _ = text.push(decoded.ch);
+                    }
+                    None => {
+                        let mut text = heapless::String::new();
+                        let _ = text.push(decoded.ch);
+                        let _ = texts.push((decoded.signal, text));
+                    }
+                }
+            }
+        }
+
+        let found: heapless::Vec<CwSignal, MAX_SIGNALS> = skimmer.signals().collect();
+        assert_eq!(found.len(), 2, "{found:?}");
+        for (frequency, wpm, copy) in [(600.0, 18.0, "N0CALL K"), (850.0, 28.0, "K1ABC TEST")] {
+            let signal = skimmer
+                .signals()
+                .find(|s| (s.frequency_hz - frequency).abs() <= BIN_SPACING_HZ)
+                .unwrap();
+            assert!((signal.wpm - wpm).abs() < 3.0, "{signal:?}");
+            let (_, text) = texts.iter().find(|(id, _)| *id == signal.id).unwrap();
+            assert!(text.contains(copy), "{frequency}: {text}");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Audio to pixel decoder.
+//!
+//! The tone is mixed to baseband and low-pass filtered, and its
+//! magnitude averaged over each pixel time. The receiver free-runs: a
+//! small clock difference from the sender makes the text slant, and the
+//! start of a column is arbitrary, which is why Hell displays draw each
+//! column twice, one above the other.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use sdr_dsp_core::filter::BiquadIq;
+use sdr_dsp_core::{IqSample, Nco};
+
+use crate::font::COLUMN_HEIGHT;
+use crate::PIXEL_RATE;
+
+/// Cutoff of the tone filter in Hz (a little over the pixel rate).
+const FILTER_CUTOFF_HZ: f32 = 150.0;
+
+/// Decay of the reference level per pixel (about 1.6 s time constant).
+const PEAK_DECAY: f32 = 0.995;
+
+/// Smallest reference level, so silence stays blank.
+const MIN_PEAK: f32 = 1e-4;
+
+/// One received column, top pixel first, 0 (no tone) to 255 (full tone).
+pub type Column = [u8; COLUMN_HEIGHT];
+
+/// Feld Hell audio to pixel decoder.
+#[derive(Clone, Debug)]
+pub struct HellDecoder {
+    sample_rate: f32,
+    nco: Nco,
+    /// Two cascaded sections, fourth order Butterworth
+    filters: [BiquadIq; 2],
+    /// Samples left of the current pixel
+    remaining: f32,
+    sum: f32,
+    count: u32,
+    /// Reference level for full intensity
+    peak: f32,
+    column: Column,
+    /// Pixels of the column received
+    pixel: usize,
+    /// Columns completed
+    columns: u32,
+}
+
+impl HellDecoder {
+    /// Create a decoder for audio at `sample_rate` with the tone at
+    /// `frequency` Hz.
+    #[must_use]
+    pub fn new(sample_rate: f32, frequency: f32) -> Self {
+        Self {
+            sample_rate,
+            nco: Nco::new(sample_rate, frequency),
+            filters: [
+                BiquadIq::lowpass(sample_rate, FILTER_CUTOFF_HZ, 0.541),
+                BiquadIq::lowpass(sample_rate, FILTER_CUTOFF_HZ, 1.307),
+            ],
+            remaining: sample_rate / PIXEL_RATE,
+            sum: 0.0,
+            count: 0,
+            peak: MIN_PEAK,
+            column: [0; COLUMN_HEIGHT],
+            pixel: 0,
+            columns: 0,
+        }
+    }
+
+    /// Set the tone frequency in Hz.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.nco.set_frequency(frequency);
+    }
+
+    /// Get the tone frequency in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.nco.frequency()
+    }
+
+    /// Process one audio sample, returning each column as it completes.
+    pub fn process(&mut self, audio: f32) -> Option<Column> {
+        let mut baseband = self.nco.mix(IqSample::from_real(audio));
+        for filter in &mut self.filters {
+            baseband = filter.process(baseband);
+        }
+        self.sum += baseband.magnitude_squared().sqrt();
+        self.count += 1;
+
+        self.remaining -= 1.0;
+        if self.remaining > 0.0 {
+            return None;
+        }
+        self.remaining += self.sample_rate / PIXEL_RATE;
+
+        let level = self.sum / self.count as f32;
+        self.sum = 0.0;
+        self.count = 0;
+        self.peak = (self.peak * PEAK_DECAY).max(level).max(MIN_PEAK);
+        let intensity = (level / self.peak * 255.0).min(255.0) as u8;
+
+        // Pixels arrive bottom first
+        self.column[COLUMN_HEIGHT - 1 - self.pixel] = intensity;
+        self.pixel += 1;
+        if self.pixel < COLUMN_HEIGHT {
+            return None;
+        }
+        self.pixel = 0;
+        self.columns = self.columns.wrapping_add(1);
+        Some(self.column)
+    }
+
+    /// Get the number of columns received.
+    #[must_use]
+    pub fn column_count(&self) -> u32 {
+        self.columns
+    }
+
+    /// Reset decoder state.
+    pub fn reset(&mut self) {
+        self.nco.reset();
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.remaining = self.sample_rate / PIXEL_RATE;
+        self.sum = 0.0;
+        self.count = 0;
+        self.peak = MIN_PEAK;
+        self.column = [0; COLUMN_HEIGHT];
+        self.pixel = 0;
+        self.columns = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{glyph_columns, CHAR_COLUMNS};
+    use crate::HellEncoder;
+
+    #[test]
+    fn test_encode_decode_loopback() {
+        let rate = 8000.0;
+        let text = "CQ DE N0CALL";
+        let mut encoder = HellEncoder::new(rate, 0.5, 1000.0);
+        encoder.queue_text(text);
+        let mut decoder = HellDecoder::new(rate, 1000.0);
+
+        let mut received = [[0u8; COLUMN_HEIGHT]; 12 * CHAR_COLUMNS];
+        let mut count = 0;
+        while let Some(sample) = encoder.next_sample() {
+            if let Some(column) = decoder.process(sample) {
+                received[count] = column;
+                count += 1;
+            }
+        }
+        assert_eq!(count, text.len() * CHAR_COLUMNS);
+        assert_eq!(decoder.column_count() as usize, count);
+
+        // Nearly every pixel matches the font
+        let mut errors = 0;
+        let sent = text.chars().flat_map(glyph_columns);
+        for (column, bits) in received.iter().zip(sent) {
+            for (row, &intensity) in column.iter().enumerate() {
+                let on = bits & (1 << row) != 0;
+                if on != (intensity > 128) {
+                    errors += 1;
+                }
+            }
+        }
+        assert!(errors < count * COLUMN_HEIGHT / 50, "{errors} pixel errors");
+    }
+}
@@ -0,0 +1,181 @@
+//! Text to audio encoder.
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use core::f32::consts::{PI, TAU};
+use heapless::Deque;
+
+use crate::font::{glyph_columns, CHAR_COLUMNS, COLUMN_HEIGHT};
+use crate::PIXEL_RATE;
+
+/// Characters that can be queued.
+pub const TEXT_CAPACITY: usize = 256;
+
+/// Rise and fall time of the keyed tone in seconds.
+///
+/// Raised cosine edges over a quarter of a pixel keep the signal within
+/// the usual 245 Hz without blurring the pixels.
+const RAMP_SECONDS: f32 = 0.002;
+
+/// Text to Feld Hell audio encoder.
+///
+/// Queued characters are sent column by column, each column bottom to
+/// top, keying a tone on for set pixels.
+#[derive(Clone, Debug)]
+pub struct HellEncoder {
+    sample_rate: f32,
+    amplitude: f32,
+    frequency: f32,
+    phase: f32,
+    queue: Deque<char, TEXT_CAPACITY>,
+    /// Columns of the character being sent
+    columns: [u16; CHAR_COLUMNS],
+    /// Index of the column being sent, [`CHAR_COLUMNS`] between characters
+    column: usize,
+    /// Pixels of the column already sent
+    pixel: usize,
+    /// Samples left of the current pixel
+    remaining: f32,
+    /// Tone keyed on for the current pixel
+    key: bool,
+    /// Keying ramp position (0.0 off, 1.0 on)
+    level: f32,
+}
+
+impl HellEncoder {
+    /// Create an encoder for audio at `sample_rate` with peak level
+    /// `amplitude` and a tone at `frequency` Hz.
+    #[must_use]
+    pub fn new(sample_rate: f32, amplitude: f32, frequency: f32) -> Self {
+        Self {
+            sample_rate,
+            amplitude,
+            frequency,
+            phase: 0.0,
+            queue: Deque::new(),
+            columns: [0; CHAR_COLUMNS],
+            column: CHAR_COLUMNS,
+            pixel: COLUMN_HEIGHT,
+            remaining: 0.0,
+            key: false,
+            level: 0.0,
+        }
+    }
+
+    /// Set the tone frequency in Hz.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    /// Get the tone frequency in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Queue text for sending, returning the number of characters queued
+    /// (fewer than given when the queue fills).
+    pub fn queue_text(&mut self, text: &str) -> usize {
+        text.chars()
+            .take_while(|&ch| self.queue.push_back(ch).is_ok())
+            .count()
+    }
+
+    /// Get the number of characters waiting, not counting the one being
+    /// sent.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Check whether there is anything left to send.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.queue.is_empty() || self.column < CHAR_COLUMNS || self.level > 0.0
+    }
+
+    /// Move on to the next pixel, returning `false` when nothing is left.
+    fn next_pixel(&mut self) -> bool {
+        self.pixel += 1;
+        if self.pixel >= COLUMN_HEIGHT {
+            self.pixel = 0;
+            self.column += 1;
+            if self.column >= CHAR_COLUMNS {
+                let Some(ch) = self.queue.pop_front() else {
+                    self.key = false;
+                    self.column = CHAR_COLUMNS;
+                    self.pixel = COLUMN_HEIGHT;
+                    return false;
+                };
+                self.columns = glyph_columns(ch);
+                self.column = 0;
+            }
+        }
+        // Columns are scanned from the bottom pixel up
+        let bit = COLUMN_HEIGHT - 1 - self.pixel;
+        self.key = self.columns[self.column] & (1 << bit) != 0;
+        true
+    }
+
+    /// Generate the next audio sample, or `None` when everything queued
+    /// has been sent.
+    pub fn next_sample(&mut self) -> Option<f32> {
+        if self.remaining <= 0.0 {
+            if !self.next_pixel() && self.level <= 0.0 {
+                return None;
+            }
+            self.remaining += self.sample_rate / PIXEL_RATE;
+        }
+        self.remaining -= 1.0;
+
+        let step = 1.0 / (RAMP_SECONDS * self.sample_rate);
+        self.level = if self.key {
+            (self.level + step).min(1.0)
+        } else {
+            (self.level - step).max(0.0)
+        };
+        let envelope = 0.5 - 0.5 * (PI * self.level).cos();
+
+        let sample = self.amplitude * envelope * self.phase.sin();
+        self.phase += TAU * self.frequency / self.sample_rate;
+        if self.phase >= TAU {
+            self.phase -= TAU;
+        }
+        Some(sample)
+    }
+
+    /// Drop queued text and stop sending.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.column = CHAR_COLUMNS;
+        self.pixel = COLUMN_HEIGHT;
+        self.remaining = 0.0;
+        self.key = false;
+        self.level = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_timing() {
+        let rate = 8000.0;
+        let mut encoder = HellEncoder::new(rate, 1.0, 1000.0);
+        assert_eq!(encoder.queue_text("HI"), 2);
+        assert!(encoder.is_busy());
+        let samples = core::iter::from_fn(|| encoder.next_sample()).count();
+        // 2.5 characters per second
+        let expected = 2.0 * rate * (CHAR_COLUMNS * COLUMN_HEIGHT) as f32 / PIXEL_RATE;
+        assert!((samples as f32 - expected).abs() <= 2.0, "{samples}");
+        assert!(!encoder.is_busy());
+
+        let long = [b'A'; TEXT_CAPACITY + 10];
+        let long = core::str::from_utf8(&long).unwrap();
+        assert_eq!(encoder.queue_text(long), TEXT_CAPACITY);
+        encoder.clear();
+        assert_eq!(encoder.next_sample(), None);
+    }
+}
@@ -0,0 +1,29 @@
+//! Feld Hell (Hellschreiber) Encoder/Decoder
+//!
+//! Feld Hell sends text as a facsimile: each character is scanned as
+//! seven columns of fourteen pixels, bottom to top, by switching a
+//! single tone on and off at 122.5 pixels per second (2.5 characters
+//! per second). The receiver does not recognise characters; it paints
+//! the received pixels in columns and the operator reads the result.
+//!
+//! # Features
+//! - On/off keying from a built-in 5×7 font, with shaped edges
+//! - Tone detector producing columns of pixel intensities for display
+
+#![no_std]
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod decoder;
+pub mod encoder;
+pub mod font;
+
+pub use decoder::{Column, HellDecoder};
+pub use encoder::{HellEncoder, TEXT_CAPACITY};
+pub use font::{glyph_columns, CHAR_COLUMNS, COLUMN_HEIGHT};
+
+/// Pixel rate in pixels per second.
+pub const PIXEL_RATE: f32 = 122.5;
+
+/// Default audio tone in Hz.
+pub const DEFAULT_TONE_HZ: f32 = 1000.0;
@@ -0,0 +1,165 @@
+//! Frame decoder.
+//!
+//! Every quarter symbol the decoder scores the three Costas arrays of a
+//! frame ending at the newest row, at each frequency offset searched.
+//! Once the best score passes its peak above the threshold, the data
+//! symbols at that time and offset are turned into soft bits, the two
+//! copies of the word combined, and the CRC checked.
+
+use crate::frame::{Js8Frame, WORD_BITS};
+use crate::modem::{
+    soft_bits, sync_tone, Row, ToneAnalyzer, COSTAS, NUM_BINS, NUM_SYMBOLS, NUM_TONES,
+    ROWS_PER_SYMBOL, SEARCH_STEPS, SYNC_POSITIONS,
+};
+
+/// Rows from the first to the last symbol of a frame.
+const FRAME_ROWS: usize = (NUM_SYMBOLS - 1) * ROWS_PER_SYMBOL + 1;
+
+/// Rows kept: a frame, plus the rows waited while a sync peak is confirmed.
+const HISTORY_ROWS: usize = FRAME_ROWS + ROWS_PER_SYMBOL;
+
+/// Smallest mean fraction of symbol energy in the Costas tones to try a
+/// decode (1/8 for noise, 1 for a clean signal).
+const SYNC_THRESHOLD: f32 = 0.4;
+
+/// Possible frame found by the sync search.
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    score: f32,
+    /// Half-tone offset (0 is [`SEARCH_STEPS`] half tones low)
+    step: usize,
+    /// Row of the last symbol
+    row: u32,
+}
+
+/// Audio to frame decoder.
+///
+/// Frames can start at any time (not only on slot boundaries) within a
+/// few hertz of the frequency set.
+#[derive(Clone, Debug)]
+pub struct Js8Decoder {
+    analyzer: ToneAnalyzer,
+    /// Recent rows, indexed by row number modulo [`HISTORY_ROWS`]
+    rows: [Row; HISTORY_ROWS],
+    /// Rows received
+    row_count: u32,
+    candidate: Option<Candidate>,
+    /// Row before which no frame is looked for (the end of the last one)
+    holdoff: u32,
+    /// Frames decoded
+    frames: u32,
+}
+
+impl Js8Decoder {
+    /// Create a decoder for audio at `sample_rate` with tone 0 at `base_hz`.
+    #[must_use]
+    pub fn new(sample_rate: f32, base_hz: f32) -> Self {
+        Self {
+            analyzer: ToneAnalyzer::new(sample_rate, base_hz),
+            rows: [[0.0; NUM_BINS]; HISTORY_ROWS],
+            row_count: 0,
+            candidate: None,
+            holdoff: 0,
+            frames: 0,
+        }
+    }
+
+    /// Set the nominal frequency of tone 0 in Hz.
+    pub fn set_frequency(&mut self, base_hz: f32) {
+        self.analyzer.set_frequency(base_hz);
+        self.candidate = None;
+    }
+
+    /// Get the nominal frequency of tone 0 in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.analyzer.frequency()
+    }
+
+    /// Process one audio sample, returning a frame that passes its CRC.
+    pub fn process(&mut self, audio: f32) -> Option<Js8Frame> {
+        let row = self.analyzer.process(audio)?;
+        let newest = self.row_count;
+        self.rows[newest as usize % HISTORY_ROWS] = row;
+        self.row_count = self.row_count.wrapping_add(1);
+        if (self.row_count as usize) < FRAME_ROWS || newest < self.holdoff {
+            return None;
+        }
+
+        let (score, step) = self.sync(newest);
+        if score >= SYNC_THRESHOLD && self.candidate.is_none_or(|c| score >= c.score) {
+            // Still climbing towards the peak
+            self.candidate = Some(Candidate {
+                score,
+                step,
+                row: newest,
+            });
+            return None;
+        }
+        let candidate = self.candidate.take()?;
+        let frame = self.demodulate(candidate)?;
+        self.holdoff = candidate.row + FRAME_ROWS as u32;
+        self.frames = self.frames.wrapping_add(1);
+        Some(frame)
+    }
+
+    /// Get the tone energies of `symbol` in a frame ending at row `last`,
+    /// at half-tone offset `step`.
+    fn energies(&self, last: u32, symbol: usize, step: usize) -> [f32; NUM_TONES] {
+        let row = last - ((NUM_SYMBOLS - 1 - symbol) * ROWS_PER_SYMBOL) as u32;
+        let row = &self.rows[row as usize % HISTORY_ROWS];
+        core::array::from_fn(|tone| row[step + 2 * tone])
+    }
+
+    /// Find the best sync score and offset for a frame ending at row `last`.
+    fn sync(&self, last: u32) -> (f32, usize) {
+        let mut best = (0.0, 0);
+        for step in 0..=2 * SEARCH_STEPS {
+            let mut score = 0.0;
+            for start in SYNC_POSITIONS {
+                for (k, &tone) in COSTAS.iter().enumerate() {
+                    let energies = self.energies(last, start + k, step);
+                    let total = energies.iter().sum::<f32>().max(1e-20);
+                    score += energies[usize::from(tone)] / total;
+                }
+            }
+            score /= (SYNC_POSITIONS.len() * COSTAS.len()) as f32;
+            if score > best.0 {
+                best = (score, step);
+            }
+        }
+        best
+    }
+
+    /// Combine the soft bits of both copies of the word and check the CRC.
+    fn demodulate(&self, candidate: Candidate) -> Option<Js8Frame> {
+        let mut metrics = [0.0f32; WORD_BITS];
+        let data = (0..NUM_SYMBOLS).filter(|&symbol| sync_tone(symbol).is_none());
+        for (k, symbol) in data.enumerate() {
+            let energies = self.energies(candidate.row, symbol, candidate.step);
+            let word_symbol = k % (WORD_BITS / 3);
+            for (bit, soft) in soft_bits(&energies).into_iter().enumerate() {
+                metrics[3 * word_symbol + bit] += soft;
+            }
+        }
+        let word = metrics.iter().fold(0u128, |word, &metric| {
+            (word << 1) | u128::from(metric > 0.0)
+        });
+        Js8Frame::from_word(word)
+    }
+
+    /// Get the number of frames decoded.
+    #[must_use]
+    pub fn frame_count(&self) -> u32 {
+        self.frames
+    }
+
+    /// Reset decoder state.
+    pub fn reset(&mut self) {
+        self.analyzer.reset();
+        self.row_count = 0;
+        self.candidate = None;
+        self.holdoff = 0;
+        self.frames = 0;
+    }
+}
@@ -0,0 +1,246 @@
+//! Outgoing message queue and frame encoder.
+
+use heapless::{Deque, Vec};
+
+use crate::frame::{Js8Frame, FRAME_CHARS};
+use crate::message::{MAX_MESSAGE_FRAMES, MAX_MESSAGE_LEN};
+use crate::modem::{channel_tones, FskModulator};
+
+/// Most frames waiting to be sent.
+pub const MAX_QUEUED_FRAMES: usize = 4 * MAX_MESSAGE_FRAMES;
+
+/// Message queue errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Js8Error {
+    /// Message has no text
+    Empty,
+    /// Message longer than [`MAX_MESSAGE_LEN`]
+    TooLong,
+    /// Not enough room in the queue for the message
+    QueueFull,
+    /// No message has been queued to send again
+    NothingToRepeat,
+}
+
+/// Queues text as frames and turns them into audio.
+///
+/// The caller starts each frame on a slot boundary with
+/// [`Js8Encoder::start_frame`] and keys the transmitter while
+/// [`Js8Encoder::is_busy`].
+#[derive(Clone, Debug)]
+pub struct Js8Encoder {
+    modulator: FskModulator,
+    queue: Deque<Js8Frame, MAX_QUEUED_FRAMES>,
+    /// Frames of the last message queued, for retransmission
+    last_message: Vec<Js8Frame, MAX_MESSAGE_FRAMES>,
+}
+
+impl Js8Encoder {
+    /// Create an encoder for `sample_rate` with peak `amplitude`, tone 0
+    /// at `base_hz`.
+    #[must_use]
+    pub fn new(sample_rate: f32, amplitude: f32, base_hz: f32) -> Self {
+        Self {
+            modulator: FskModulator::new(sample_rate, amplitude, base_hz),
+            queue: Deque::new(),
+            last_message: Vec::new(),
+        }
+    }
+
+    /// Set the frequency of tone 0 in Hz, from the next frame.
+    pub fn set_frequency(&mut self, base_hz: f32) {
+        self.modulator.set_frequency(base_hz);
+    }
+
+    /// Get the frequency of tone 0 in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.modulator.frequency()
+    }
+
+    /// Queue a message, returning the number of frames it takes.
+    ///
+    /// Leading and trailing spaces are dropped.
+    ///
+    /// # Errors
+    ///
+    /// [`Js8Error::Empty`], [`Js8Error::TooLong`] or
+    /// [`Js8Error::QueueFull`], leaving the queue unchanged.
+    pub fn queue_text(&mut self, text: &str) -> Result<usize, Js8Error> {
+        let text = text.trim();
+        let len = text.chars().count();
+        if len == 0 {
+            return Err(Js8Error::Empty);
+        }
+        if len > MAX_MESSAGE_LEN {
+            return Err(Js8Error::TooLong);
+        }
+        let count = len.div_ceil(FRAME_CHARS);
+        if self.queue.capacity() - self.queue.len() < count {
+            return Err(Js8Error::QueueFull);
+        }
+
+        let mut frames = Vec::new();
+        let mut chars = text
+            .char_indices()
+            .map(|(k, _)| k)
+            .step_by(FRAME_CHARS)
+            .peekable();
+        while let Some(start) = chars.next() {
+            let end = chars.peek().copied().unwrap_or(text.len());
+            let frame = Js8Frame::new(
+                &text[start..end],
+                frames.is_empty(),
+                end == text.len(),
+                false,
+            );
+            let _ = frames.push(frame);
+        }
+        for frame in &frames {
+            let _ = self.queue.push_back(frame.clone());
+        }
+        self.last_message = frames;
+        Ok(count)
+    }
+
+    /// Queue the last message again, marked as a repeat, returning the
+    /// number of frames it takes.
+    ///
+    /// # Errors
+    ///
+    /// [`Js8Error::NothingToRepeat`] or [`Js8Error::QueueFull`].
+    pub fn retransmit(&mut self) -> Result<usize, Js8Error> {
+        if self.last_message.is_empty() {
+            return Err(Js8Error::NothingToRepeat);
+        }
+        if self.queue.capacity() - self.queue.len() < self.last_message.len() {
+            return Err(Js8Error::QueueFull);
+        }
+        for frame in &self.last_message {
+            let mut frame = frame.clone();
+            frame.repeat = true;
+            let _ = self.queue.push_back(frame);
+        }
+        Ok(self.last_message.len())
+    }
+
+    /// Get the number of frames waiting to be sent.
+    #[must_use]
+    pub fn queued_frames(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Start sending the next queued frame, returning it.
+    ///
+    /// Returns `None` if a frame is still being sent or none are queued.
+    pub fn start_frame(&mut self) -> Option<Js8Frame> {
+        if self.modulator.is_busy() {
+            return None;
+        }
+        let frame = self.queue.pop_front()?;
+        self.modulator.start(&channel_tones(&frame.to_symbols()));
+        Some(frame)
+    }
+
+    /// Check if a frame is being sent.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.modulator.is_busy()
+    }
+
+    /// Generate the next audio sample, or `None` when the frame is done.
+    pub fn next_sample(&mut self) -> Option<f32> {
+        self.modulator.next_sample()
+    }
+
+    /// Stop sending and empty the queue (the last message can still be
+    /// retransmitted).
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.modulator.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Js8Decoder;
+    use crate::message::MessageAssembler;
+
+    #[test]
+    fn test_queue() {
+        let mut encoder = Js8Encoder::new(12000.0, 0.5, 1500.0);
+        assert_eq!(encoder.retransmit(), Err(Js8Error::NothingToRepeat));
+        assert_eq!(encoder.queue_text("   "), Err(Js8Error::Empty));
+        let long = [b'A'; MAX_MESSAGE_LEN + 1];
+        assert_eq!(
+            encoder.queue_text(core::str::from_utf8(&long).unwrap()),
+            Err(Js8Error::TooLong)
+        );
+
+        assert_eq!(encoder.queue_text(" CQ CQ DE N0CALL N0CALL PSE K "), Ok(3));
+        assert_eq!(encoder.retransmit(), Ok(3));
+        assert_eq!(encoder.queued_frames(), 6);
+        for (k, text) in ["CQ CQ DE N0C", "ALL N0CALL P"].iter().enumerate() {
+            let frame = encoder.start_frame().unwrap();
+            assert_eq!(frame.text.as_str(), *text);
+            assert_eq!(
+                (frame.first, frame.last, frame.repeat),
+                (k == 0, false, false)
+            );
+            // Busy until the frame has been sent
+            assert_eq!(encoder.start_frame(), None);
+            while encoder.next_sample().is_some() {}
+        }
+        let frame = encoder.start_frame().unwrap();
+        assert_eq!((frame.text.as_str(), frame.last), ("SE K", true));
+        encoder.clear();
+        assert_eq!(encoder.start_frame(), None);
+
+        // Fill the queue
+        while encoder.queue_text("A MESSAGE LONGER THAN A FRAME").is_ok() {}
+        assert_eq!(
+            encoder.queue_text("A MESSAGE LONGER THAN A FRAME"),
+            Err(Js8Error::QueueFull)
+        );
+        assert!(encoder.queued_frames() > MAX_QUEUED_FRAMES - 3);
+    }
+
+    #[test]
+    fn test_encode_decode_loopback() {
+        let rate = 12000.0;
+        let base = 1000.0;
+        // Sent 2 Hz high into noise
+        let mut encoder = Js8Encoder::new(rate, 0.3, base + 2.0);
+        let mut decoder = Js8Decoder::new(rate, base);
+        let mut assembler = MessageAssembler::new();
+        encoder.queue_text("Hello from the SDR, 73!").unwrap();
+
+        let mut noise = 1u32;
+        let mut messages = 0;
+        let mut frames = 0;
+        while let Some(sent) = encoder.start_frame() {
+            // A second of silence either side
+            let gap = rate as usize;
+            let silence = core::iter::repeat_n(0.0, gap);
+            let audio = silence
+                .chain(core::iter::from_fn(|| encoder.next_sample()))
+                .chain(core::iter::repeat_n(0.0, gap));
+            for sample in audio {
+                noise = noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let sample = sample + ((noise >> 16) as f32 / 65536.0 - 0.5) * 0.3;
+                if let Some(frame) = decoder.process(sample) {
+                    assert_eq!(frame, sent);
+                    frames += 1;
+                    if let Some(message) = assembler.push(&frame) {
+                        assert_eq!(message.as_str(), "HELLO FROM THE SDR, 73!");
+                        messages += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(frames, 2);
+        assert_eq!(messages, 1);
+        assert_eq!(decoder.frame_count(), 2);
+    }
+}
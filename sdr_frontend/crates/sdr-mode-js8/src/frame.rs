@@ -0,0 +1,205 @@
+//! Free-text frames.
+//!
+//! A frame carries 12 characters from a 64 character alphabet (six bits
+//! each), three flag bits and a 12-bit CRC: 87 bits, exactly the 29
+//! data symbols of one half of a modem frame. The word is sent in both
+//! halves.
+
+use heapless::String;
+
+use crate::modem::DATA_SYMBOLS;
+
+/// Characters per frame.
+pub const FRAME_CHARS: usize = 12;
+
+/// Bits in a frame word, including the CRC.
+pub const WORD_BITS: usize = 87;
+
+/// Bytes of a frame word packed for transport (big endian).
+pub const FRAME_BYTES: usize = 11;
+
+/// Character set. Lower case is sent as upper case, other characters
+/// as `?`.
+pub const ALPHABET: &[u8; 64] =
+    b" 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ.,?/-+!@#&()'\":;=<>*%$_[]^~";
+
+/// Bits of text and flags covered by the CRC.
+const PAYLOAD_BITS: usize = FRAME_CHARS * 6 + 3;
+
+/// CRC-12 polynomial (x^12 + x^11 + x^3 + x^2 + x + 1).
+const CRC_POLY: u16 = 0x80F;
+
+/// Flag bits.
+const FLAG_FIRST: u128 = 1;
+const FLAG_LAST: u128 = 2;
+const FLAG_REPEAT: u128 = 4;
+
+/// One frame of a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Js8Frame {
+    /// Text, up to [`FRAME_CHARS`] characters from [`ALPHABET`]
+    pub text: String<FRAME_CHARS>,
+    /// First frame of a message
+    pub first: bool,
+    /// Last frame of a message
+    pub last: bool,
+    /// Part of a retransmitted message
+    pub repeat: bool,
+}
+
+/// Map a character to its alphabet index.
+fn char_index(ch: char) -> u8 {
+    let upper = ch.to_ascii_uppercase();
+    ALPHABET
+        .iter()
+        .position(|&b| char::from(b) == upper)
+        .or_else(|| ALPHABET.iter().position(|&b| b == b'?'))
+        .map_or(0, |index| index as u8)
+}
+
+/// Compute the CRC-12 of the low `bits` bits of `payload`, most
+/// significant first.
+fn crc12(payload: u128, bits: usize) -> u16 {
+    let mut crc = 0u16;
+    for k in (0..bits).rev() {
+        let bit = (payload >> k) & 1 != 0;
+        let top = crc & 0x800 != 0;
+        crc = (crc << 1) & 0xFFF;
+        if top != bit {
+            crc ^= CRC_POLY;
+        }
+    }
+    crc
+}
+
+impl Js8Frame {
+    /// Create a frame from up to [`FRAME_CHARS`] characters of `text`
+    /// (the rest is dropped), mapped onto [`ALPHABET`].
+    #[must_use]
+    pub fn new(text: &str, first: bool, last: bool, repeat: bool) -> Self {
+        let mut mapped = String::new();
+        for ch in text.chars().take(FRAME_CHARS) {
+            let _ = mapped.push(char::from(ALPHABET[usize::from(char_index(ch))]));
+        }
+        Self {
+            text: mapped,
+            first,
+            last,
+            repeat,
+        }
+    }
+
+    /// Pack the frame into its 87-bit word: text (first character most
+    /// significant, padded with spaces), flags, CRC.
+    #[must_use]
+    pub fn to_word(&self) -> u128 {
+        let mut chars = self.text.chars();
+        let mut payload = 0u128;
+        for _ in 0..FRAME_CHARS {
+            let index = chars.next().map_or(0, char_index);
+            payload = (payload << 6) | u128::from(index);
+        }
+        let flags = [
+            (self.first, FLAG_FIRST),
+            (self.last, FLAG_LAST),
+            (self.repeat, FLAG_REPEAT),
+        ];
+        payload <<= 3;
+        for (set, flag) in flags {
+            if set {
+                payload |= flag;
+            }
+        }
+        (payload << 12) | u128::from(crc12(payload, PAYLOAD_BITS))
+    }
+
+    /// Unpack an 87-bit word, or `None` if the CRC does not match.
+    ///
+    /// Trailing spaces (padding) are removed from the last frame of a
+    /// message.
+    #[must_use]
+    pub fn from_word(word: u128) -> Option<Self> {
+        if word >> WORD_BITS != 0 {
+            return None;
+        }
+        let payload = word >> 12;
+        if crc12(payload, PAYLOAD_BITS) != (word & 0xFFF) as u16 {
+            return None;
+        }
+        let last = payload & FLAG_LAST != 0;
+        let mut text = String::<FRAME_CHARS>::new();
+        for k in (0..FRAME_CHARS).rev() {
+            let index = (payload >> (3 + 6 * k)) & 0x3F;
+            let _ = text.push(char::from(ALPHABET[index as usize]));
+        }
+        if last {
+            let len = text.trim_end().len();
+            text.truncate(len);
+        }
+        Some(Self {
+            text,
+            first: payload & FLAG_FIRST != 0,
+            last,
+            repeat: payload & FLAG_REPEAT != 0,
+        })
+    }
+
+    /// Get the data symbols (three-bit values) sent for this frame: the
+    /// word in each half.
+    #[must_use]
+    pub fn to_symbols(&self) -> [u8; DATA_SYMBOLS] {
+        let word = self.to_word();
+        let half = DATA_SYMBOLS / 2;
+        core::array::from_fn(|k| {
+            let shift = WORD_BITS - 3 * (k % half + 1);
+            ((word >> shift) & 7) as u8
+        })
+    }
+
+    /// Pack the word into bytes for transport.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; FRAME_BYTES] {
+        let bytes = self.to_word().to_be_bytes();
+        core::array::from_fn(|k| bytes[16 - FRAME_BYTES + k])
+    }
+
+    /// Unpack bytes written by [`Js8Frame::to_bytes`], or `None` if they
+    /// are the wrong length or fail the CRC.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != FRAME_BYTES {
+            return None;
+        }
+        let word = bytes
+            .iter()
+            .fold(0u128, |word, &b| (word << 8) | u128::from(b));
+        Self::from_word(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_round_trip() {
+        let frame = Js8Frame::new("cq cq de n0c", true, false, false);
+        assert_eq!(frame.text.as_str(), "CQ CQ DE N0C");
+        let word = frame.to_word();
+        assert!(word >> WORD_BITS == 0);
+        assert_eq!(Js8Frame::from_word(word), Some(frame.clone()));
+        assert_eq!(Js8Frame::from_bytes(&frame.to_bytes()), Some(frame));
+
+        // Padding is trimmed from the last frame, unknown characters map to ?
+        let last = Js8Frame::new("all{ ", false, true, true);
+        assert_eq!(last.text.as_str(), "ALL? ");
+        let decoded = Js8Frame::from_word(last.to_word()).unwrap();
+        assert_eq!(decoded.text.as_str(), "ALL?");
+        assert!(decoded.last && decoded.repeat && !decoded.first);
+
+        // Any single bit error fails the CRC
+        for bit in 0..WORD_BITS {
+            assert_eq!(Js8Frame::from_word(word ^ (1 << bit)), None, "{bit}");
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! JS8 Style Keyboard-to-Keyboard Mode
+//!
+//! Slow free-text chat over the FT8 modem: 8-FSK with 6.25 Hz tone
+//! spacing, 0.16 s symbols and three 7×7 Costas arrays for sync, one
+//! 12.64 s frame per 15 s slot.
+//!
+//! This is scaffolding rather than an on-air compatible JS8 or FT8
+//! implementation: each frame carries 12 characters of text, frame
+//! flags and a CRC, sent twice (once per data block) and combined from
+//! soft decisions instead of LDPC coded.
+//!
+//! # Features
+//! - FT8 style tone modulator and sync-searching tone detector
+//! - Free text split into frames, reassembled on receive
+//! - Outgoing message queue with retransmission of the last message
+
+#![no_std]
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod decoder;
+pub mod encoder;
+pub mod frame;
+pub mod message;
+pub mod modem;
+
+pub use decoder::Js8Decoder;
+pub use encoder::{Js8Encoder, Js8Error, MAX_QUEUED_FRAMES};
+pub use frame::{Js8Frame, FRAME_BYTES, FRAME_CHARS};
+pub use message::{MessageAssembler, MAX_MESSAGE_FRAMES, MAX_MESSAGE_LEN};
+pub use modem::{FRAME_SECONDS, NUM_TONES, SLOT_SECONDS, TONE_SPACING_HZ};
@@ -0,0 +1,94 @@
+//! Message reassembly.
+
+use heapless::String;
+
+use crate::frame::{Js8Frame, FRAME_CHARS};
+
+/// Most frames in a message.
+pub const MAX_MESSAGE_FRAMES: usize = 8;
+
+/// Most characters in a message.
+pub const MAX_MESSAGE_LEN: usize = MAX_MESSAGE_FRAMES * FRAME_CHARS;
+
+/// Message text.
+pub type Message = String<MAX_MESSAGE_LEN>;
+
+/// Joins received frames back into messages.
+///
+/// Frames carry no sequence number, so a message missing a middle frame
+/// is delivered without it; one missing its first frame is dropped.
+#[derive(Clone, Debug, Default)]
+pub struct MessageAssembler {
+    text: Message,
+    /// A first frame has been received and not yet its last
+    active: bool,
+}
+
+impl MessageAssembler {
+    /// Create an assembler with no message in progress.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a received frame.
+    ///
+    /// Returns the message when its last frame arrives. A first frame
+    /// discards any message in progress.
+    pub fn push(&mut self, frame: &Js8Frame) -> Option<Message> {
+        if frame.first {
+            self.text.clear();
+            self.active = true;
+        } else if !self.active {
+            return None;
+        }
+        if self.text.push_str(&frame.text).is_err() {
+            self.reset();
+            return None;
+        }
+        if !frame.last {
+            return None;
+        }
+        self.active = false;
+        Some(core::mem::take(&mut self.text))
+    }
+
+    /// Get the text received so far of a message in progress.
+    #[must_use]
+    pub fn partial(&self) -> Option<&str> {
+        self.active.then_some(self.text.as_str())
+    }
+
+    /// Drop any message in progress.
+    pub fn reset(&mut self) {
+        self.text.clear();
+        self.active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assembly() {
+        let mut assembler = MessageAssembler::new();
+        // Tail of a message whose start was missed
+        assert_eq!(
+            assembler.push(&Js8Frame::new("LOST", false, true, false)),
+            None
+        );
+
+        assert_eq!(
+            assembler.push(&Js8Frame::new("HELLO THERE ", true, false, false)),
+            None
+        );
+        assert_eq!(assembler.partial(), Some("HELLO THERE "));
+        let message = assembler.push(&Js8Frame::new("73", false, true, false));
+        assert_eq!(message.as_deref(), Some("HELLO THERE 73"));
+        assert_eq!(assembler.partial(), None);
+
+        let single = Js8Frame::new("QSL", true, true, true);
+        assert_eq!(assembler.push(&single).as_deref(), Some("QSL"));
+    }
+}
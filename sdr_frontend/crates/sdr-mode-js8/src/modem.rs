@@ -0,0 +1,430 @@
+//! FT8 style 8-FSK modem.
+//!
+//! A frame is 79 symbols of 0.16 s, each one of eight tones 6.25 Hz
+//! apart (50 Hz occupied). Symbols 0-6, 36-42 and 72-78 are the 7×7
+//! Costas array used for time and frequency sync; the other 58 carry
+//! three Gray coded bits each.
+//!
+//! The receive side mixes the tones to baseband, decimates to 200 Hz
+//! and measures the energy of each tone over a one-symbol window every
+//! quarter symbol. Bins are spaced half a tone apart so the decoder can
+//! search a few hertz either side of the nominal frequency.
+
+use core::f32::consts::PI;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use sdr_dsp_core::filter::BiquadIq;
+use sdr_dsp_core::tone::{gfsk_tone, FT8_GFSK_BT};
+use sdr_dsp_core::{IqSample, Nco, ToneGenerator};
+
+/// Tones in the alphabet.
+pub const NUM_TONES: usize = 8;
+
+/// Tone spacing in Hz (one over the symbol time).
+pub const TONE_SPACING_HZ: f32 = 6.25;
+
+/// Symbol time in seconds.
+pub const SYMBOL_SECONDS: f32 = 0.16;
+
+/// Symbols per frame.
+pub const NUM_SYMBOLS: usize = 79;
+
+/// Data symbols per frame.
+pub const DATA_SYMBOLS: usize = 58;
+
+/// Frame duration in seconds.
+pub const FRAME_SECONDS: f32 = NUM_SYMBOLS as f32 * SYMBOL_SECONDS;
+
+/// Transmit slot in seconds: frames start on multiples of this.
+pub const SLOT_SECONDS: f32 = 15.0;
+
+/// Costas array sent at each sync position.
+pub const COSTAS: [u8; 7] = [3, 1, 4, 0, 6, 5, 2];
+
+/// First symbol of each Costas array.
+pub const SYNC_POSITIONS: [usize; 3] = [0, 36, 72];
+
+/// Tone for each three-bit value, so adjacent tones differ in one bit.
+pub const GRAY_MAP: [u8; NUM_TONES] = [0, 1, 3, 2, 5, 6, 4, 7];
+
+/// Tone detector sample rate in Hz.
+pub const ANALYSIS_RATE: f32 = 200.0;
+
+/// Detector rows (energy measurements) per symbol.
+pub const ROWS_PER_SYMBOL: usize = 4;
+
+/// Half-tone steps searched either side of the nominal frequency.
+pub const SEARCH_STEPS: usize = 4;
+
+/// Energy bins per row: all tones at every searched offset.
+pub const NUM_BINS: usize = 2 * (NUM_TONES - 1) + 1 + 2 * SEARCH_STEPS;
+
+/// Tone energies, half a tone apart starting [`SEARCH_STEPS`] half
+/// tones below tone 0.
+pub type Row = [f32; NUM_BINS];
+
+/// Analysis samples per symbol.
+const SYMBOL_SAMPLES: usize = 32;
+
+/// Analysis samples between rows.
+const ROW_SAMPLES: usize = SYMBOL_SAMPLES / ROWS_PER_SYMBOL;
+
+/// Rate after the first (boxcar) decimation stage in Hz.
+const FIRST_STAGE_RATE: f32 = 1000.0;
+
+/// Anti-alias cutoff ahead of the boxcar in Hz.
+const PREFILTER_CUTOFF_HZ: f32 = 400.0;
+
+/// Anti-alias cutoff ahead of the 200 Hz decimation in Hz.
+const BASEBAND_CUTOFF_HZ: f32 = 45.0;
+
+/// Transmit envelope rise and fall time in seconds.
+const RAMP_SECONDS: f32 = 0.01;
+
+/// Get the Costas tone sent at `symbol`, or `None` for a data symbol.
+#[must_use]
+pub fn sync_tone(symbol: usize) -> Option<u8> {
+    SYNC_POSITIONS
+        .iter()
+        .find_map(|&start| COSTAS.get(symbol.wrapping_sub(start)).copied())
+}
+
+/// Build the tones of a frame from its data symbols (three-bit values).
+#[must_use]
+pub fn channel_tones(data: &[u8; DATA_SYMBOLS]) -> [u8; NUM_SYMBOLS] {
+    let mut data = data.iter();
+    core::array::from_fn(|symbol| match sync_tone(symbol) {
+        Some(tone) => tone,
+        None => data
+            .next()
+            .map_or(0, |&value| GRAY_MAP[usize::from(value & 7)]),
+    })
+}
+
+/// Soft decisions for the three bits (most significant first) of a
+/// data symbol, from its tone energies.
+///
+/// Each is the strongest tone with the bit set less the strongest with
+/// it clear, relative to the total: positive for a 1.
+#[must_use]
+pub fn soft_bits(energies: &[f32; NUM_TONES]) -> [f32; 3] {
+    let total = energies.iter().sum::<f32>().max(1e-20);
+    core::array::from_fn(|bit| {
+        let mask = 4 >> bit;
+        let (mut one, mut zero) = (0.0f32, 0.0f32);
+        for (value, &tone) in GRAY_MAP.iter().enumerate() {
+            let energy = energies[usize::from(tone)];
+            if value & mask != 0 {
+                one = one.max(energy);
+            } else {
+                zero = zero.max(energy);
+            }
+        }
+        (one - zero) / total
+    })
+}
+
+/// Phase-continuous 8-FSK modulator.
+///
+/// Tone changes are smoothed with the FT8 Gaussian pulse, which keeps
+/// the signal within its 50 Hz.
+#[derive(Clone, Debug)]
+pub struct FskModulator {
+    osc: ToneGenerator<1>,
+    /// Frequency of tone 0 in Hz
+    base_hz: f32,
+    tones: [u8; NUM_SYMBOLS],
+    /// Symbols started (`NUM_SYMBOLS` when the last is under way)
+    symbol: usize,
+    samples_per_symbol: f32,
+    /// Samples left in the current symbol
+    remaining: f32,
+    /// Samples sent and total in the frame, for the envelope
+    sent: u32,
+    total: u32,
+    ramp: f32,
+}
+
+impl FskModulator {
+    /// Create an idle modulator for `sample_rate` with peak `amplitude`,
+    /// tone 0 at `base_hz`.
+    #[must_use]
+    pub fn new(sample_rate: f32, amplitude: f32, base_hz: f32) -> Self {
+        let samples_per_symbol = sample_rate * SYMBOL_SECONDS;
+        Self {
+            osc: ToneGenerator::single(sample_rate, base_hz, amplitude),
+            base_hz,
+            tones: [0; NUM_SYMBOLS],
+            symbol: NUM_SYMBOLS,
+            samples_per_symbol,
+            remaining: 0.0,
+            sent: 0,
+            total: (samples_per_symbol * NUM_SYMBOLS as f32) as u32,
+            ramp: (sample_rate * RAMP_SECONDS).max(1.0),
+        }
+    }
+
+    /// Set the frequency of tone 0 for the next frame, in Hz.
+    pub fn set_frequency(&mut self, base_hz: f32) {
+        self.base_hz = base_hz;
+    }
+
+    /// Get the frequency of tone 0 in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.base_hz
+    }
+
+    /// Start sending a frame, replacing any frame in progress.
+    pub fn start(&mut self, tones: &[u8; NUM_SYMBOLS]) {
+        self.tones = *tones;
+        self.symbol = 0;
+        self.remaining = 0.0;
+        self.osc.reset();
+        self.sent = 0;
+    }
+
+    /// Check if a frame is being sent.
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.symbol < NUM_SYMBOLS || self.remaining > 0.0
+    }
+
+    /// Generate the next audio sample, or `None` when the frame is done.
+    pub fn next_sample(&mut self) -> Option<f32> {
+        if self.remaining <= 0.0 {
+            if self.symbol >= NUM_SYMBOLS {
+                return None;
+            }
+            self.symbol += 1;
+            self.remaining += self.samples_per_symbol;
+        }
+        let position = 1.0 - self.remaining / self.samples_per_symbol;
+        self.remaining -= 1.0;
+
+        // Neighbouring tones, repeating the current one at the frame ends
+        let current = self.symbol - 1;
+        let tone = |k: usize| f32::from(self.tones[k]);
+        let previous = current.checked_sub(1).map_or(tone(current), tone);
+        let next = if current + 1 < NUM_SYMBOLS {
+            tone(current + 1)
+        } else {
+            tone(current)
+        };
+        let shaped = gfsk_tone(FT8_GFSK_BT, previous, tone(current), next, position);
+        self.osc
+            .set_frequency(0, self.base_hz + shaped * TONE_SPACING_HZ);
+
+        let position = self.sent as f32;
+        let envelope = (position / self.ramp)
+            .min(self.total.saturating_sub(self.sent) as f32 / self.ramp)
+            .min(1.0);
+        self.sent += 1;
+        Some(envelope * self.osc.next_sample())
+    }
+
+    /// Stop sending.
+    pub fn reset(&mut self) {
+        self.symbol = NUM_SYMBOLS;
+        self.remaining = 0.0;
+    }
+}
+
+/// Tone energy detector.
+///
+/// Produces a [`Row`] of tone energies over the last symbol every
+/// quarter symbol.
+#[derive(Clone, Debug)]
+pub struct ToneAnalyzer {
+    sample_rate: f32,
+    base_hz: f32,
+    /// Mixes the middle of the tone set to zero
+    nco: Nco,
+    prefilter: BiquadIq,
+    /// Samples averaged by the boxcar stage
+    block: usize,
+    sum: IqSample,
+    summed: usize,
+    /// Two cascaded low-pass sections at the boxcar output rate
+    filters: [BiquadIq; 2],
+    /// Analysis samples per boxcar output sample, and accumulated phase
+    decimation_step: f32,
+    decimation_phase: f32,
+    /// Last symbol of analysis samples (oldest at `position`)
+    window: [IqSample; SYMBOL_SAMPLES],
+    position: usize,
+    since_row: usize,
+    /// DFT coefficients of each bin
+    twiddles: [[IqSample; SYMBOL_SAMPLES]; NUM_BINS],
+}
+
+impl ToneAnalyzer {
+    /// Create a detector for audio at `sample_rate` with tone 0 at `base_hz`.
+    #[must_use]
+    pub fn new(sample_rate: f32, base_hz: f32) -> Self {
+        let block = ((sample_rate / FIRST_STAGE_RATE) as usize).max(1);
+        let first_stage_rate = sample_rate / block as f32;
+        let twiddles = core::array::from_fn(|bin| {
+            // Relative to the mixing frequency, the middle of the tone set
+            let bin_hz = (bin as f32 - SEARCH_STEPS as f32) * TONE_SPACING_HZ / 2.0
+                - (NUM_TONES - 1) as f32 * TONE_SPACING_HZ / 2.0;
+            core::array::from_fn(|n| {
+                let angle = -2.0 * PI * bin_hz * n as f32 / ANALYSIS_RATE;
+                IqSample::new(angle.cos(), angle.sin())
+            })
+        });
+        Self {
+            sample_rate,
+            base_hz,
+            nco: Nco::new(sample_rate, Self::center_hz(base_hz)),
+            prefilter: BiquadIq::lowpass(sample_rate, PREFILTER_CUTOFF_HZ, 0.707),
+            block,
+            sum: IqSample::ZERO,
+            summed: 0,
+            filters: [
+                BiquadIq::lowpass(first_stage_rate, BASEBAND_CUTOFF_HZ, 0.541),
+                BiquadIq::lowpass(first_stage_rate, BASEBAND_CUTOFF_HZ, 1.307),
+            ],
+            decimation_step: ANALYSIS_RATE / first_stage_rate,
+            decimation_phase: 0.0,
+            window: [IqSample::ZERO; SYMBOL_SAMPLES],
+            position: 0,
+            since_row: 0,
+            twiddles,
+        }
+    }
+
+    /// Mixing frequency for tone 0 at `base_hz`.
+    fn center_hz(base_hz: f32) -> f32 {
+        base_hz + (NUM_TONES - 1) as f32 * TONE_SPACING_HZ / 2.0
+    }
+
+    /// Set the nominal frequency of tone 0 in Hz.
+    pub fn set_frequency(&mut self, base_hz: f32) {
+        self.base_hz = base_hz;
+        self.nco.set_frequency(Self::center_hz(base_hz));
+    }
+
+    /// Get the nominal frequency of tone 0 in Hz.
+    #[must_use]
+    pub fn frequency(&self) -> f32 {
+        self.base_hz
+    }
+
+    /// Get the audio sample rate in Hz.
+    #[must_use]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Process one audio sample, returning a row every quarter symbol.
+    pub fn process(&mut self, audio: f32) -> Option<Row> {
+        let mixed = self
+            .prefilter
+            .process(self.nco.mix(IqSample::from_real(audio)));
+        self.sum = self.sum + mixed;
+        self.summed += 1;
+        if self.summed < self.block {
+            return None;
+        }
+        let mut baseband = self.sum * (1.0 / self.block as f32);
+        self.sum = IqSample::ZERO;
+        self.summed = 0;
+        for filter in &mut self.filters {
+            baseband = filter.process(baseband);
+        }
+
+        self.decimation_phase += self.decimation_step;
+        if self.decimation_phase < 1.0 {
+            return None;
+        }
+        self.decimation_phase -= 1.0;
+        self.window[self.position] = baseband;
+        self.position = (self.position + 1) % SYMBOL_SAMPLES;
+        self.since_row += 1;
+        if self.since_row < ROW_SAMPLES {
+            return None;
+        }
+        self.since_row = 0;
+
+        Some(core::array::from_fn(|bin| {
+            let mut acc = IqSample::ZERO;
+            for (n, twiddle) in self.twiddles[bin].iter().enumerate() {
+                acc = acc + self.window[(self.position + n) % SYMBOL_SAMPLES] * *twiddle;
+            }
+            acc.magnitude_squared()
+        }))
+    }
+
+    /// Reset detector state.
+    pub fn reset(&mut self) {
+        self.nco.reset();
+        self.prefilter.reset();
+        self.sum = IqSample::ZERO;
+        self.summed = 0;
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.decimation_phase = 0.0;
+        self.window = [IqSample::ZERO; SYMBOL_SAMPLES];
+        self.position = 0;
+        self.since_row = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_tones() {
+        let data = core::array::from_fn(|k| (k % 8) as u8);
+        let tones = channel_tones(&data);
+        assert_eq!(tones[..7], COSTAS);
+        assert_eq!(tones[36..43], COSTAS);
+        assert_eq!(tones[72..], COSTAS);
+        assert_eq!(tones[7], GRAY_MAP[0]);
+        assert_eq!(tones[10], GRAY_MAP[3]);
+        assert_eq!(tones[43], GRAY_MAP[29 % 8]);
+        assert_eq!(
+            (0..NUM_SYMBOLS).filter(|&k| sync_tone(k).is_none()).count(),
+            DATA_SYMBOLS
+        );
+    }
+
+    #[test]
+    fn test_tone_detection() {
+        // Each tone in turn, a quarter tone off nominal
+        let rate = 48000.0;
+        let base = 1500.0;
+        let tones: [u8; NUM_SYMBOLS] = core::array::from_fn(|k| (k % NUM_TONES) as u8);
+        let mut modulator = FskModulator::new(rate, 0.5, base + TONE_SPACING_HZ / 4.0);
+        let mut analyzer = ToneAnalyzer::new(rate, base);
+        modulator.start(&tones);
+
+        let mut correct = 0;
+        let mut rows = 0;
+        while let Some(sample) = modulator.next_sample() {
+            let Some(row) = analyzer.process(sample) else {
+                continue;
+            };
+            rows += 1;
+            // Rows just before a symbol boundary cover one whole symbol,
+            // after the filter delay
+            if rows % ROWS_PER_SYMBOL != 0 || rows < 2 * ROWS_PER_SYMBOL {
+                continue;
+            }
+            let symbol = rows / ROWS_PER_SYMBOL - 1;
+            let strongest = (0..NUM_TONES)
+                .max_by(|&a, &b| row[SEARCH_STEPS + 2 * a].total_cmp(&row[SEARCH_STEPS + 2 * b]))
+                .unwrap();
+            if strongest == usize::from(tones[symbol]) {
+                correct += 1;
+            }
+        }
+        assert!(!modulator.is_busy());
+        assert!(rows >= NUM_SYMBOLS * ROWS_PER_SYMBOL - 2, "{rows}");
+        assert!(correct >= NUM_SYMBOLS - 3, "{correct}");
+    }
+}
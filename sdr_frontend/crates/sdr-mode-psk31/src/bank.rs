@@ -0,0 +1,316 @@
+//! Parallel PSK31 decoders.
+//!
+//! A [`DecoderBank`] runs one [`Psk31Decoder`] per channel, each mixed
+//! down from its own audio offset, so several PSK31 signals in the
+//! passband are decoded at once. Channels are added by offset (e.g. when
+//! a trace is clicked on the waterfall) and identified by a stable id
+//! that survives other channels being removed.
+
+use crate::decoder::{Psk31Decoder, Psk31DecoderConfig};
+use heapless::Vec;
+use sdr_dsp_core::{IqSample, SignalMetrics, SignalQuality};
+
+/// Maximum number of parallel decoders.
+pub const MAX_DECODERS: usize = 8;
+
+/// Offsets closer than this are treated as the same signal (Hz).
+///
+/// A PSK31 signal occupies about 60 Hz, so two clicks on one trace land
+/// within this distance.
+pub const MIN_SPACING_HZ: f32 = 30.0;
+
+/// Decoder bank error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BankError {
+    /// All decoder slots are in use
+    Full,
+}
+
+/// A character decoded on one channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedChar {
+    /// Channel id returned by [`DecoderBank::add`]
+    pub channel: u32,
+    /// Decoded character
+    pub ch: char,
+}
+
+/// One decoder and its audio offset.
+struct Channel {
+    id: u32,
+    offset_hz: f32,
+    decoder: Psk31Decoder,
+}
+
+/// Bank of PSK31 decoders at different audio offsets.
+pub struct DecoderBank {
+    config: Psk31DecoderConfig,
+    channels: Vec<Channel, MAX_DECODERS>,
+    next_id: u32,
+}
+
+impl DecoderBank {
+    /// Create an empty bank.
+    ///
+    /// `config` is the template for every channel; its center frequency
+    /// is replaced by the channel offset.
+    #[must_use]
+    pub fn new(config: Psk31DecoderConfig) -> Self {
+        Self {
+            config,
+            channels: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Start decoding at `offset_hz`, returning the channel id.
+    ///
+    /// If a channel is already within [`MIN_SPACING_HZ`] its id is
+    /// returned instead of adding a duplicate.
+    pub fn add(&mut self, offset_hz: f32) -> Result<u32, BankError> {
+        if let Some(id) = self.find(offset_hz) {
+            return Ok(id);
+        }
+
+        let config = Psk31DecoderConfig {
+            center_freq_hz: offset_hz,
+            ..self.config.clone()
+        };
+        let id = self.next_id;
+        self.channels
+            .push(Channel {
+                id,
+                offset_hz,
+                decoder: Psk31Decoder::new(config),
+            })
+            .map_err(|_| BankError::Full)?;
+        self.next_id = self.next_id.wrapping_add(1);
+        Ok(id)
+    }
+
+    /// Stop decoding a channel, returns false if it does not exist.
+    pub fn remove(&mut self, id: u32) -> bool {
+        match self.index(id) {
+            Some(index) => {
+                self.channels.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every channel.
+    pub fn clear(&mut self) {
+        self.channels.clear();
+    }
+
+    /// Move a channel to a new offset, returns false if it does not exist.
+    pub fn retune(&mut self, id: u32, offset_hz: f32) -> bool {
+        let Some(index) = self.index(id) else {
+            return false;
+        };
+        let channel = &mut self.channels[index];
+        channel.offset_hz = offset_hz;
+        channel.decoder.set_frequency(offset_hz);
+        true
+    }
+
+    /// Find the channel within [`MIN_SPACING_HZ`] of `offset_hz`.
+    #[must_use]
+    pub fn find(&self, offset_hz: f32) -> Option<u32> {
+        self.channels
+            .iter()
+            .filter(|c| (c.offset_hz - offset_hz).abs() < MIN_SPACING_HZ)
+            .min_by(|a, b| {
+                let da = (a.offset_hz - offset_hz).abs();
+                let db = (b.offset_hz - offset_hz).abs();
+                da.total_cmp(&db)
+            })
+            .map(|c| c.id)
+    }
+
+    /// Number of active channels.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Check if no channels are active.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Ids and offsets of the active channels, in the order added.
+    pub fn channels(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.channels.iter().map(|c| (c.id, c.offset_hz))
+    }
+
+    /// Offset of a channel including its AFC correction (Hz).
+    #[must_use]
+    pub fn tracked_offset(&self, id: u32) -> Option<f32> {
+        let channel = &self.channels[self.index(id)?];
+        Some(channel.offset_hz + channel.decoder.afc_offset())
+    }
+
+    /// Check whether a channel's carrier loop is locked.
+    #[must_use]
+    pub fn is_locked(&self, id: u32) -> Option<bool> {
+        Some(self.channels[self.index(id)?].decoder.is_locked())
+    }
+
+    /// Set AFC on/off, loop bandwidth and pull-in range (Hz) for every
+    /// channel, including channels added later.
+    pub fn set_afc(&mut self, enabled: bool, bandwidth_hz: f32, range_hz: f32) {
+        self.config.afc_enabled = enabled;
+        self.config.afc_bandwidth = bandwidth_hz;
+        self.config.afc_range_hz = range_hz;
+        for channel in &mut self.channels {
+            channel.decoder.set_afc_bandwidth(bandwidth_hz);
+            channel.decoder.set_afc_range(range_hz);
+            channel.decoder.set_afc_enabled(enabled);
+        }
+    }
+
+    /// Signal quality of a channel.
+    #[must_use]
+    pub fn metrics(&self, id: u32) -> Option<SignalMetrics> {
+        Some(self.channels[self.index(id)?].decoder.metrics())
+    }
+
+    /// SNR, EVM and IMD of a channel.
+    #[must_use]
+    pub fn quality(&self, id: u32) -> Option<SignalQuality> {
+        Some(self.channels[self.index(id)?].decoder.quality())
+    }
+
+    /// Run every channel on one sample.
+    ///
+    /// Returns the characters completed by this sample; squelched and
+    /// undecodable symbols produce nothing.
+    pub fn process(&mut self, iq: IqSample) -> Vec<DecodedChar, MAX_DECODERS> {
+        let mut decoded = Vec::new();
+        for channel in &mut self.channels {
+            if let Ok(Some(ch)) = channel.decoder.process(iq) {
+                // Capacity matches the channel count
+                let _ = decoded.push(DecodedChar {
+                    channel: channel.id,
+                    ch,
+                });
+            }
+        }
+        decoded
+    }
+
+    /// Run every channel on a block, passing each character to `on_char`.
+    pub fn process_block(&mut self, samples: &[IqSample], mut on_char: impl FnMut(DecodedChar)) {
+        for &iq in samples {
+            for decoded in self.process(iq) {
+                on_char(decoded);
+            }
+        }
+    }
+
+    /// Reset every channel, keeping their offsets.
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.decoder.reset();
+            channel.decoder.set_frequency(channel.offset_hz);
+        }
+    }
+
+    /// Position of a channel in the slot list.
+    fn index(&self, id: u32) -> Option<usize> {
+        self.channels.iter().position(|c| c.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{Psk31Encoder, Psk31EncoderConfig};
+
+    /// Real-valued PSK31 audio at `carrier_hz`.
+    fn psk_tone(carrier_hz: f32, text: &str, out: &mut [f32]) {
+        let mut encoder = Psk31Encoder::new(Psk31EncoderConfig {
+            carrier_freq_hz: carrier_hz,
+            ..Psk31EncoderConfig::default()
+        });
+        encoder.queue_text(text);
+        for sample in out.iter_mut() {
+            *sample = encoder.next_sample().unwrap_or(0.0);
+        }
+    }
+
+    #[test]
+    fn test_add_find_remove() {
+        let mut bank = DecoderBank::new(Psk31DecoderConfig::default());
+        let a = bank.add(1000.0).unwrap();
+        let b = bank.add(1500.0).unwrap();
+        assert_ne!(a, b);
+        // A second click on the same trace reuses the channel
+        assert_eq!(bank.add(1010.0), Ok(a));
+        assert_eq!(bank.len(), 2);
+        assert_eq!(bank.find(1495.0), Some(b));
+        assert_eq!(bank.find(1200.0), None);
+
+        assert!(bank.remove(a));
+        assert!(!bank.remove(a));
+        // Ids stay stable after removal
+        assert_eq!(bank.find(1500.0), Some(b));
+        assert!(bank.retune(b, 1600.0));
+        assert_eq!(bank.channels().next(), Some((b, 1600.0)));
+
+        bank.clear();
+        assert!(bank.is_empty());
+        for i in 0..MAX_DECODERS {
+            bank.add(500.0 + 100.0 * i as f32).unwrap();
+        }
+        assert_eq!(bank.add(3000.0), Err(BankError::Full));
+    }
+
+    #[test]
+    fn test_channels_match_single_decoders() {
+        const LEN: usize = 48000;
+        let mut low = [0.0f32; LEN];
+        let mut high = [0.0f32; LEN];
+        psk_tone(1000.0, "  cq cq  ", &mut low);
+        psk_tone(1800.0, "  test  ", &mut high);
+
+        let mut bank = DecoderBank::new(Psk31DecoderConfig::default());
+        let low_id = bank.add(1000.0).unwrap();
+        let high_id = bank.add(1800.0).unwrap();
+        let mut single = Psk31Decoder::new(Psk31DecoderConfig {
+            center_freq_hz: 1000.0,
+            ..Psk31DecoderConfig::default()
+        });
+
+        let mut bank_chars: Vec<char, 64> = Vec::new();
+        let mut single_chars: Vec<char, 64> = Vec::new();
+        for i in 0..LEN {
+            let iq = IqSample::new(low[i] + high[i], 0.0);
+            for decoded in bank.process(iq) {
+                if decoded.channel == low_id {
+                    let _ = bank_chars.push(decoded.ch);
+                }
+            }
+            // The single decoder only hears its own signal
+            if let Ok(Some(ch)) = single.process(IqSample::new(low[i], 0.0)) {
+                let _ = single_chars.push(ch);
+            }
+        }
+
+        // The other signal is filtered out of each channel
+        assert!(!single_chars.is_empty());
+        assert_eq!(bank_chars, single_chars);
+        let low_snr = bank.metrics(low_id).unwrap().snr_db;
+        let high_snr = bank.metrics(high_id).unwrap().snr_db;
+        assert!((low_snr - high_snr).abs() < 3.0);
+        assert!(bank.tracked_offset(high_id).is_some());
+        assert_eq!(bank.is_locked(low_id), Some(true));
+
+        bank.set_afc(false, 20.0, 10.0);
+        assert_eq!(bank.tracked_offset(low_id), Some(1000.0));
+        assert_eq!(bank.is_locked(99), None);
+    }
+}
@@ -3,7 +3,15 @@
 use crate::varicode::{VaricodeDecoder, VaricodeError};
 #[allow(unused_imports)]
 use micromath::F32Ext;
-use sdr_dsp_core::{Biquad, CostasLoop, IqSample, Nco, SignalMetrics};
+use sdr_dsp_core::{
+    Biquad, CostasLoop, ImdMeter, IqSample, Nco, QualityMeter, SignalMetrics, SignalQuality,
+};
+
+/// PSK31 baud rate.
+const BAUD_RATE: f32 = 31.25;
+
+/// Consecutive phase reversals (zero bits) before the signal counts as idle.
+const IDLE_BITS: u32 = 8;
 
 /// PSK31 decoder configuration.
 #[derive(Clone, Debug)]
@@ -16,6 +24,8 @@ pub struct Psk31DecoderConfig {
     pub afc_enabled: bool,
     /// AFC bandwidth in Hz
     pub afc_bandwidth: f32,
+    /// AFC pull-in range in Hz (± from the center frequency)
+    pub afc_range_hz: f32,
     /// Squelch threshold (0.0 to 1.0)
     pub squelch_threshold: f32,
     /// QPSK mode (false = BPSK)
@@ -29,6 +39,7 @@ impl Default for Psk31DecoderConfig {
             center_freq_hz: 1500.0,
             afc_enabled: true,
             afc_bandwidth: 50.0,
+            afc_range_hz: 100.0,
             squelch_threshold: 0.3,
             qpsk_mode: false,
         }
@@ -48,8 +59,10 @@ pub struct Psk31Decoder {
     sample_count: f32,
     timing_error: f32,
 
-    // Matched filter (raised cosine)
-    matched_filter: Biquad,
+    // Matched filter (raised cosine), one per channel so I and Q keep
+    // separate state
+    matched_filter_i: Biquad,
+    matched_filter_q: Biquad,
 
     // Symbol history for timing recovery
     prev_sample: IqSample,
@@ -64,8 +77,9 @@ pub struct Psk31Decoder {
     // Signal quality
     signal_power: f32,
     noise_power: f32,
-    imd_peak: f32,
-    imd_avg: f32,
+    quality: QualityMeter,
+    imd_meter: ImdMeter,
+    idle_bits: u32,
 
     // AFC state
     afc_offset: f32,
@@ -93,29 +107,32 @@ impl Psk31Decoder {
         let sample_rate = config.sample_rate;
         let center_freq = config.center_freq_hz;
 
-        // PSK31 baud rate
-        const BAUD_RATE: f32 = 31.25;
         let samples_per_symbol = sample_rate / BAUD_RATE;
 
         // Matched filter bandwidth (approximately baud rate)
         let matched_filter = Biquad::lowpass(sample_rate, BAUD_RATE * 1.5, 0.707);
 
+        let mut costas = CostasLoop::new(sample_rate, 0.0, config.afc_bandwidth);
+        costas.set_pull_in_range(Self::pull_in_range(&config));
+
         Self {
             config: config.clone(),
             nco: Nco::new(sample_rate, center_freq),
-            costas: CostasLoop::new(sample_rate, 0.0, config.afc_bandwidth),
+            costas,
             samples_per_symbol,
             sample_count: 0.0,
             timing_error: 0.0,
-            matched_filter,
+            matched_filter_i: matched_filter.clone(),
+            matched_filter_q: matched_filter,
             prev_sample: IqSample::ZERO,
             prev_prev_sample: IqSample::ZERO,
             prev_phase: 0.0,
             varicode: VaricodeDecoder::new(),
             signal_power: 0.0,
             noise_power: 0.001,
-            imd_peak: 0.0,
-            imd_avg: 0.0,
+            quality: QualityMeter::default(),
+            imd_meter: ImdMeter::new(sample_rate, BAUD_RATE),
+            idle_bits: 0,
             afc_offset: 0.0,
         }
     }
@@ -128,10 +145,20 @@ impl Psk31Decoder {
         let baseband = self.nco.mix(iq);
 
         // 2. Apply matched filter
-        let filtered = self.matched_filter.process_iq(baseband);
+        let filtered = IqSample::new(
+            self.matched_filter_i.process(baseband.i),
+            self.matched_filter_q.process(baseband.q),
+        );
 
         // 3. Carrier tracking via Costas loop
-        let (tracked, _phase_error) = self.costas.process(filtered);
+        let (tracked, phase_error) = self.costas.process(filtered);
+
+        // IMD is measured on the unfiltered baseband while the signal idles
+        if self.idle_bits >= IDLE_BITS {
+            if let Some(imd_db) = self.imd_meter.push(baseband) {
+                self.quality.set_imd(imd_db);
+            }
+        }
 
         // 4. Update AFC
         if self.config.afc_enabled {
@@ -165,12 +192,14 @@ impl Psk31Decoder {
             // Decision: phase change near 0 = 1, near π = 0
             let bit = phase_diff.abs() < core::f32::consts::FRAC_PI_2;
 
-            // 8. Update IMD estimate
-            let mag = tracked.magnitude();
-            if mag > self.imd_peak {
-                self.imd_peak = mag;
+            // 8. Update quality: SNR and EVM per symbol, idle run for IMD
+            self.quality.push_symbol(tracked, phase_error);
+            if bit {
+                self.idle_bits = 0;
+                self.imd_meter.restart();
+            } else {
+                self.idle_bits = self.idle_bits.saturating_add(1);
             }
-            self.imd_avg = 0.99 * self.imd_avg + 0.01 * mag;
 
             // 9. Check squelch
             let snr = self.signal_power / self.noise_power.max(0.0001);
@@ -180,11 +209,7 @@ impl Psk31Decoder {
 
             // 10. Varicode decode
             match self.varicode.push_bit(bit) {
-                Ok(Some(ch)) => {
-                    // Reset IMD on character decode
-                    self.imd_peak = 0.0;
-                    return Ok(Some(ch));
-                }
+                Ok(Some(ch)) => return Ok(Some(ch)),
                 Ok(None) => {}
                 Err(e) => return Err(Psk31Error::Varicode(e)),
             }
@@ -217,28 +242,70 @@ impl Psk31Decoder {
                 .max(0.001)
                 .log10();
 
-        let imd_db = if self.imd_peak > 0.0 {
-            20.0 * (self.imd_avg / self.imd_peak.max(0.001)).max(0.001).log10()
-        } else {
-            -30.0
-        };
+        let imd_db = self.quality.quality().imd_db.unwrap_or(-30.0);
 
         SignalMetrics {
             snr_db,
             imd_db,
             afc_offset_hz: self.afc_offset,
+            carrier_locked: self.costas.is_locked(),
             timing_error: self.timing_error,
             squelch_open: self.signal_power > self.config.squelch_threshold * self.noise_power,
             confidence: (snr_db / 20.0).clamp(0.0, 1.0),
         }
     }
 
+    /// Get SNR (from the Costas loop error), EVM and idle IMD.
+    #[must_use]
+    pub fn quality(&self) -> SignalQuality {
+        self.quality.quality()
+    }
+
     /// Get AFC frequency offset in Hz.
     #[must_use]
     pub fn afc_offset(&self) -> f32 {
         self.afc_offset
     }
 
+    /// Check whether the carrier loop is locked.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.costas.is_locked()
+    }
+
+    /// Enable or disable AFC (frequency tracking).
+    ///
+    /// With AFC off the carrier loop still tracks phase, but does not
+    /// move away from the center frequency.
+    pub fn set_afc_enabled(&mut self, enabled: bool) {
+        self.config.afc_enabled = enabled;
+        self.costas.set_pull_in_range(Self::pull_in_range(&self.config));
+        if !enabled {
+            self.afc_offset = 0.0;
+        }
+    }
+
+    /// Set the AFC loop bandwidth in Hz.
+    pub fn set_afc_bandwidth(&mut self, bandwidth_hz: f32) {
+        self.config.afc_bandwidth = bandwidth_hz;
+        self.costas.set_loop_bandwidth(bandwidth_hz);
+    }
+
+    /// Set the AFC pull-in range in Hz.
+    pub fn set_afc_range(&mut self, range_hz: f32) {
+        self.config.afc_range_hz = range_hz;
+        self.costas.set_pull_in_range(Self::pull_in_range(&self.config));
+    }
+
+    /// Frequency correction allowed by `config`.
+    fn pull_in_range(config: &Psk31DecoderConfig) -> f32 {
+        if config.afc_enabled {
+            config.afc_range_hz
+        } else {
+            0.0
+        }
+    }
+
     /// Set center frequency.
     pub fn set_frequency(&mut self, freq_hz: f32) {
         self.nco.set_frequency(freq_hz);
@@ -248,6 +315,8 @@ impl Psk31Decoder {
     pub fn reset(&mut self) {
         self.nco.reset();
         self.costas.reset();
+        self.matched_filter_i.reset();
+        self.matched_filter_q.reset();
         self.varicode.reset();
         self.sample_count = 0.0;
         self.timing_error = 0.0;
@@ -255,7 +324,68 @@ impl Psk31Decoder {
         self.prev_prev_sample = IqSample::ZERO;
         self.prev_phase = 0.0;
         self.signal_power = 0.0;
-        self.imd_peak = 0.0;
-        self.imd_avg = 0.0;
+        self.quality.reset();
+        self.imd_meter.restart();
+        self.idle_bits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn test_idle_quality() {
+        let config = Psk31DecoderConfig::default();
+        let rate = config.sample_rate;
+        let mut decoder = Psk31Decoder::new(config);
+        assert_eq!(decoder.quality().imd_db, None);
+
+        // Idle: a phase reversal every symbol is two tones at ±baud/2
+        for n in 0..(rate as usize * 3) {
+            let t = n as f32 / rate;
+            let envelope = (PI * BAUD_RATE * t).cos();
+            let sample = 0.5 * envelope * (2.0 * PI * 1500.0 * t).cos();
+            let _ = decoder.process(IqSample::new(sample, 0.0));
+        }
+
+        let quality = decoder.quality();
+        let imd = quality.imd_db.expect("IMD measured during idle");
+        assert!(imd < -25.0, "{imd}");
+        assert!(quality.evm_percent.is_finite());
+        assert!(quality.snr_db.is_finite());
+    }
+
+    #[test]
+    fn test_afc_range_and_lock() {
+        let config = Psk31DecoderConfig {
+            afc_range_hz: 5.0,
+            ..Psk31DecoderConfig::default()
+        };
+        let rate = config.sample_rate;
+        let mut decoder = Psk31Decoder::new(config);
+        assert!(!decoder.is_locked());
+
+        // Unmodulated carrier 3 Hz above the center frequency
+        let run = |decoder: &mut Psk31Decoder| {
+            for n in 0..(rate as usize * 2) {
+                let t = n as f32 / rate;
+                let sample = 0.5 * (2.0 * PI * 1503.0 * t).cos();
+                let _ = decoder.process(IqSample::new(sample, 0.0));
+            }
+        };
+        run(&mut decoder);
+        assert!(decoder.is_locked());
+        assert!(decoder.metrics().carrier_locked);
+        assert!((decoder.afc_offset() - 3.0).abs() < 0.5, "{}", decoder.afc_offset());
+
+        // Narrowing the range below the offset clamps the correction
+        decoder.set_afc_range(1.0);
+        run(&mut decoder);
+        assert!((decoder.afc_offset() - 1.0).abs() < 1e-3);
+
+        decoder.set_afc_enabled(false);
+        assert_eq!(decoder.afc_offset(), 0.0);
     }
 }
@@ -7,16 +7,19 @@
 //! - BPSK demodulation with Costas loop carrier tracking
 //! - Varicode encoding/decoding
 //! - AFC (Automatic Frequency Control)
-//! - Signal quality metrics (IMD, SNR)
+//! - Signal quality metrics (IMD, SNR, EVM)
+//! - Decoder bank for several signals in the passband at once
 
 #![no_std]
 #![deny(unsafe_code)]
 #![warn(missing_docs)]
 
+pub mod bank;
 pub mod decoder;
 pub mod encoder;
 pub mod varicode;
 
+pub use bank::{BankError, DecodedChar, DecoderBank, MAX_DECODERS};
 pub use decoder::{Psk31Decoder, Psk31DecoderConfig};
 pub use encoder::{Psk31Encoder, Psk31EncoderConfig};
 pub use varicode::{VaricodeDecoder, VaricodeEncoder, VARICODE_TABLE};
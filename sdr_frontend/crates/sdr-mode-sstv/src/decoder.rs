@@ -0,0 +1,371 @@
+//! SSTV image decoder.
+//!
+//! Demodulates the tone frequency, waits for a VIS header, then slices
+//! each line into green, blue and red scans by time. Every pixel is the
+//! average frequency over its share of the scan. Lines are timed from
+//! the header and nudged by the end of each sync pulse, so a small
+//! sample rate error does not slant the picture.
+
+use crate::demod::FrequencyDemod;
+use crate::mode::{LineTiming, SstvMode, BLACK_HZ, IMAGE_HEIGHT, IMAGE_WIDTH, SYNC_HZ, WHITE_HZ};
+use crate::vis::{VisDetector, VIS_BIT_MS};
+
+/// Frequency below which a sample counts as sync, in Hz.
+const SYNC_THRESHOLD_HZ: f32 = (SYNC_HZ + BLACK_HZ) / 2.0;
+
+/// Window around the expected sync end searched for the edge, in ms.
+const SYNC_WINDOW_MS: f32 = 3.0;
+
+/// Fraction of the measured sync error corrected per line.
+const SYNC_GAIN: f32 = 0.5;
+
+/// Decoder event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SstvEvent {
+    /// A VIS header announced a new image
+    Started(SstvMode),
+    /// A scanline is complete; read it with [`SstvDecoder::line`]
+    Line(u16),
+    /// The last line of the image is complete
+    Finished,
+}
+
+/// Image in progress.
+#[derive(Clone, Copy, Debug)]
+struct Reception {
+    mode: SstvMode,
+    timing: LineTiming,
+    /// Current line number
+    line: u16,
+    /// Position within the line in samples (negative before the image)
+    position: f32,
+}
+
+/// SSTV decoder.
+pub struct SstvDecoder {
+    samples_per_ms: f32,
+    demod: FrequencyDemod,
+    vis: VisDetector,
+    reception: Option<Reception>,
+    /// Consecutive samples below the sync threshold
+    sync_run: u32,
+    /// Per-pixel frequency sums and counts for G, B, R
+    sums: [[f32; IMAGE_WIDTH]; 3],
+    counts: [[u16; IMAGE_WIDTH]; 3],
+    /// Last completed line, RGB
+    rgb: [u8; IMAGE_WIDTH * 3],
+    /// Last line received, [`SstvEvent::Finished`] not yet reported
+    finished: bool,
+}
+
+impl SstvDecoder {
+    /// Create a decoder for audio at `sample_rate`.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            samples_per_ms: sample_rate / 1000.0,
+            demod: FrequencyDemod::new(sample_rate),
+            vis: VisDetector::new(sample_rate),
+            reception: None,
+            sync_run: 0,
+            sums: [[0.0; IMAGE_WIDTH]; 3],
+            counts: [[0; IMAGE_WIDTH]; 3],
+            rgb: [0; IMAGE_WIDTH * 3],
+            finished: false,
+        }
+    }
+
+    /// Process one audio sample.
+    pub fn process(&mut self, audio: f32) -> Option<SstvEvent> {
+        let frequency = self.demod.process(audio);
+
+        // A new header restarts reception, even mid-image
+        if let Some(mode) = self.vis.process(frequency) {
+            self.start(mode);
+            return Some(SstvEvent::Started(mode));
+        }
+
+        let Some(mut reception) = self.reception else {
+            // Report the end once, on the sample after the last line
+            return core::mem::take(&mut self.finished).then_some(SstvEvent::Finished);
+        };
+        let event = self.receive(&mut reception, frequency);
+        self.reception = if self.finished { None } else { Some(reception) };
+        event
+    }
+
+    /// Start receiving an image in `mode` without waiting for a header.
+    ///
+    /// The image is assumed to start with the next sample.
+    pub fn start(&mut self, mode: SstvMode) {
+        let delay_ms = VIS_BIT_MS + mode.start_sync_ms();
+        self.reception = Some(Reception {
+            mode,
+            timing: mode.timing(),
+            line: 0,
+            position: -delay_ms * self.samples_per_ms,
+        });
+        self.clear_line();
+        self.finished = false;
+    }
+
+    /// Mode of the image being received.
+    #[must_use]
+    pub fn mode(&self) -> Option<SstvMode> {
+        self.reception.map(|r| r.mode)
+    }
+
+    /// Line being received.
+    #[must_use]
+    pub fn current_line(&self) -> Option<u16> {
+        self.reception.map(|r| r.line)
+    }
+
+    /// Last completed scanline as RGB triplets.
+    #[must_use]
+    pub fn line(&self) -> &[u8; IMAGE_WIDTH * 3] {
+        &self.rgb
+    }
+
+    /// Abandon the image being received and wait for a new header.
+    pub fn reset(&mut self) {
+        self.demod.reset();
+        self.vis.restart();
+        self.reception = None;
+        self.sync_run = 0;
+        self.finished = false;
+        self.clear_line();
+    }
+
+    /// Advance reception by one frequency sample.
+    fn receive(&mut self, reception: &mut Reception, frequency: f32) -> Option<SstvEvent> {
+        let timing = reception.timing;
+        reception.position += 1.0;
+        let ms = reception.position / self.samples_per_ms;
+
+        // Track the trailing edge of the sync pulse
+        if frequency < SYNC_THRESHOLD_HZ {
+            self.sync_run = self.sync_run.saturating_add(1);
+        } else {
+            let run_ms = self.sync_run as f32 / self.samples_per_ms;
+            let error_ms = ms - timing.sync_end_ms();
+            if run_ms > timing.sync_ms / 2.0 && error_ms.abs() < SYNC_WINDOW_MS {
+                reception.position -= SYNC_GAIN * error_ms * self.samples_per_ms;
+            }
+            self.sync_run = 0;
+        }
+
+        if ms >= 0.0 {
+            for (channel, &start) in timing.scan_start_ms.iter().enumerate() {
+                let offset = ms - start;
+                if (0.0..timing.scan_ms).contains(&offset) {
+                    let x = ((offset / timing.scan_ms) * IMAGE_WIDTH as f32) as usize;
+                    let x = x.min(IMAGE_WIDTH - 1);
+                    self.sums[channel][x] += frequency;
+                    self.counts[channel][x] = self.counts[channel][x].saturating_add(1);
+                }
+            }
+        }
+
+        if ms < timing.line_ms {
+            return None;
+        }
+
+        // Line complete
+        reception.position -= timing.line_ms * self.samples_per_ms;
+        self.finish_line();
+        let line = reception.line;
+        reception.line += 1;
+        if usize::from(reception.line) >= IMAGE_HEIGHT {
+            self.finished = true;
+        }
+        Some(SstvEvent::Line(line))
+    }
+
+    /// Convert the accumulated scans to RGB and clear them.
+    fn finish_line(&mut self) {
+        // Scans are sent G, B, R
+        const RGB_FROM_SCAN: [usize; 3] = [2, 0, 1];
+        for (color, &scan) in RGB_FROM_SCAN.iter().enumerate() {
+            let mut previous = 0;
+            for x in 0..IMAGE_WIDTH {
+                let count = self.counts[scan][x];
+                let value = if count == 0 {
+                    previous
+                } else {
+                    let frequency = self.sums[scan][x] / f32::from(count);
+                    let level = (frequency - BLACK_HZ) / (WHITE_HZ - BLACK_HZ);
+                    (level.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+                };
+                self.rgb[x * 3 + color] = value;
+                previous = value;
+            }
+        }
+        self.clear_line();
+    }
+
+    /// Clear the per-pixel accumulators.
+    fn clear_line(&mut self) {
+        self.sums = [[0.0; IMAGE_WIDTH]; 3];
+        self.counts = [[0; IMAGE_WIDTH]; 3];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vis::LEADER_HZ;
+    use core::f32::consts::PI;
+
+    /// Phase-continuous FM tone generator.
+    struct ToneWriter<F: FnMut(f32)> {
+        rate: f32,
+        phase: f32,
+        /// Exact elapsed time in ms and samples written
+        time_ms: f64,
+        written: u64,
+        out: F,
+    }
+
+    impl<F: FnMut(f32)> ToneWriter<F> {
+        fn tone(&mut self, hz: f32, ms: f32) {
+            self.time_ms += f64::from(ms);
+            let end = (self.time_ms * f64::from(self.rate) / 1000.0) as u64;
+            while self.written < end {
+                self.phase = (self.phase + 2.0 * PI * hz / self.rate) % (2.0 * PI);
+                (self.out)(0.5 * self.phase.sin());
+                self.written += 1;
+            }
+        }
+    }
+
+    /// Frequency of a pixel level.
+    fn level_hz(level: u8) -> f32 {
+        BLACK_HZ + (WHITE_HZ - BLACK_HZ) * f32::from(level) / 255.0
+    }
+
+    /// Send the VIS header and `lines` lines of a test pattern.
+    ///
+    /// The pattern is red on the left half, blue on the right.
+    fn transmit(mode: SstvMode, rate: f32, lines: usize, out: impl FnMut(f32)) {
+        let mut w = ToneWriter {
+            rate,
+            phase: 0.0,
+            time_ms: 0.0,
+            written: 0,
+            out,
+        };
+        w.tone(LEADER_HZ, 300.0);
+        w.tone(SYNC_HZ, 10.0);
+        w.tone(LEADER_HZ, 300.0);
+        w.tone(SYNC_HZ, VIS_BIT_MS);
+        let code = mode.vis_code();
+        let mut parity = false;
+        for k in 0..7 {
+            let one = code & (1 << k) != 0;
+            parity ^= one;
+            w.tone(if one { 1100.0 } else { 1300.0 }, VIS_BIT_MS);
+        }
+        w.tone(if parity { 1100.0 } else { 1300.0 }, VIS_BIT_MS);
+        w.tone(SYNC_HZ, VIS_BIT_MS);
+        if mode.is_scottie() {
+            w.tone(SYNC_HZ, mode.start_sync_ms());
+        }
+
+        let timing = mode.timing();
+        let pixel_ms = timing.scan_ms / IMAGE_WIDTH as f32;
+        let pixel = |scan: usize, x: usize| -> u8 {
+            let left = x < IMAGE_WIDTH / 2;
+            // Scans are G, B, R
+            match (scan, left) {
+                (1, false) | (2, true) => 255,
+                _ => 0,
+            }
+        };
+        for _ in 0..lines {
+            let mut t = 0.0;
+            let send = |w: &mut ToneWriter<_>, hz: f32, until: f32, t: &mut f32| {
+                w.tone(hz, until - *t);
+                *t = until;
+            };
+            for scan in 0..3 {
+                let start = timing.scan_start_ms[scan];
+                // Tolerate rounding in the summed pixel times
+                if timing.sync_start_ms <= start && timing.sync_start_ms + 0.01 >= t {
+                    send(&mut w, BLACK_HZ, timing.sync_start_ms, &mut t);
+                    send(&mut w, SYNC_HZ, timing.sync_end_ms(), &mut t);
+                }
+                send(&mut w, BLACK_HZ, start, &mut t);
+                for x in 0..IMAGE_WIDTH {
+                    send(&mut w, level_hz(pixel(scan, x)), t + pixel_ms, &mut t);
+                }
+            }
+            send(&mut w, BLACK_HZ, timing.line_ms, &mut t);
+        }
+    }
+
+    fn decode(mode: SstvMode, rate_tx: f32, rate_rx: f32) {
+        let mut decoder = SstvDecoder::new(rate_rx);
+        let mut started = None;
+        let mut lines = 0;
+        let mut checked = 0;
+        transmit(mode, rate_tx, 20, |sample| match decoder.process(sample) {
+            Some(SstvEvent::Started(m)) => started = Some(m),
+            Some(SstvEvent::Line(n)) => {
+                assert_eq!(usize::from(n), lines);
+                lines += 1;
+                if n >= 5 {
+                    let rgb = decoder.line();
+                    let left = &rgb[40 * 3..40 * 3 + 3];
+                    let right = &rgb[280 * 3..280 * 3 + 3];
+                    assert!(
+                        left[0] > 200 && left[1] < 50 && left[2] < 50,
+                        "{n}: {left:?}"
+                    );
+                    assert!(
+                        right[0] < 50 && right[1] < 50 && right[2] > 200,
+                        "{n}: {right:?}"
+                    );
+                    checked += 1;
+                }
+            }
+            _ => {}
+        });
+        assert_eq!(started, Some(mode));
+        assert!(lines >= 19, "{lines}");
+        assert!(checked > 10);
+    }
+
+    #[test]
+    fn test_finished_after_last_line() {
+        let mut decoder = SstvDecoder::new(12000.0);
+        decoder.start(SstvMode::MartinM2);
+        let mut last = None;
+        let mut finished = false;
+        for _ in 0..(12.0 * 227.0 * IMAGE_HEIGHT as f32) as usize + 12000 {
+            match decoder.process(0.0) {
+                Some(SstvEvent::Line(n)) => last = Some(n),
+                Some(SstvEvent::Finished) => finished = true,
+                _ => {}
+            }
+        }
+        assert_eq!(last, Some(IMAGE_HEIGHT as u16 - 1));
+        assert!(finished);
+        assert_eq!(decoder.mode(), None);
+    }
+
+    #[test]
+    fn test_decode_modes() {
+        for mode in SstvMode::all() {
+            decode(*mode, 12000.0, 12000.0);
+        }
+    }
+
+    #[test]
+    fn test_sync_tracking_corrects_clock_error() {
+        // Transmitter clock 0.5% fast; uncorrected the picture would
+        // shift by a third of its width over the test lines
+        decode(SstvMode::MartinM2, 12060.0, 12000.0);
+        decode(SstvMode::ScottieS2, 12060.0, 12000.0);
+    }
+}
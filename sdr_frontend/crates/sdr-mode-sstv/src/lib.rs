@@ -0,0 +1,24 @@
+//! SSTV Image Decoder
+//!
+//! Decodes slow-scan television images sent as an FM audio subcarrier
+//! (1500 Hz black to 2300 Hz white, 1200 Hz sync).
+//!
+//! # Features
+//! - Scottie S1/S2 and Martin M1/M2 line formats
+//! - VIS header detection to pick the mode automatically
+//! - Sync tracking to correct slant from sample rate mismatch
+//! - One RGB scanline delivered at a time
+
+#![no_std]
+#![deny(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod decoder;
+pub mod demod;
+pub mod mode;
+pub mod vis;
+
+pub use decoder::{SstvDecoder, SstvEvent};
+pub use demod::FrequencyDemod;
+pub use mode::{LineTiming, SstvMode, IMAGE_HEIGHT, IMAGE_WIDTH};
+pub use vis::VisDetector;
@@ -0,0 +1,172 @@
+//! SSTV modes and line timing.
+
+/// Pixels per scanline in the supported modes.
+pub const IMAGE_WIDTH: usize = 320;
+
+/// Scanlines per image in the supported modes.
+pub const IMAGE_HEIGHT: usize = 256;
+
+/// Sync pulse frequency in Hz.
+pub const SYNC_HZ: f32 = 1200.0;
+
+/// Frequency of a black pixel in Hz.
+pub const BLACK_HZ: f32 = 1500.0;
+
+/// Frequency of a white pixel in Hz.
+pub const WHITE_HZ: f32 = 2300.0;
+
+/// Supported SSTV modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SstvMode {
+    /// Scottie S1 (110 s)
+    ScottieS1,
+    /// Scottie S2 (71 s)
+    ScottieS2,
+    /// Martin M1 (114 s)
+    MartinM1,
+    /// Martin M2 (58 s)
+    MartinM2,
+}
+
+/// Position of the sync pulse and color scans within a line, in ms.
+///
+/// Both families send green, blue, red; Martin starts each line with
+/// the sync pulse, Scottie sends it between blue and red.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineTiming {
+    /// Start of the sync pulse
+    pub sync_start_ms: f32,
+    /// Sync pulse length
+    pub sync_ms: f32,
+    /// Start of the green, blue and red scans
+    pub scan_start_ms: [f32; 3],
+    /// Length of one color scan
+    pub scan_ms: f32,
+    /// Whole line length
+    pub line_ms: f32,
+}
+
+impl LineTiming {
+    /// End of the sync pulse (the edge used for tracking).
+    #[must_use]
+    pub fn sync_end_ms(&self) -> f32 {
+        self.sync_start_ms + self.sync_ms
+    }
+}
+
+impl SstvMode {
+    /// Get display name for the mode.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            SstvMode::ScottieS1 => "Scottie S1",
+            SstvMode::ScottieS2 => "Scottie S2",
+            SstvMode::MartinM1 => "Martin M1",
+            SstvMode::MartinM2 => "Martin M2",
+        }
+    }
+
+    /// VIS code announcing the mode.
+    #[must_use]
+    pub const fn vis_code(&self) -> u8 {
+        match self {
+            SstvMode::ScottieS1 => 60,
+            SstvMode::ScottieS2 => 56,
+            SstvMode::MartinM1 => 44,
+            SstvMode::MartinM2 => 40,
+        }
+    }
+
+    /// Look up a mode by VIS code.
+    #[must_use]
+    pub fn from_vis(code: u8) -> Option<Self> {
+        Self::all().iter().copied().find(|m| m.vis_code() == code)
+    }
+
+    /// All supported modes.
+    #[must_use]
+    pub fn all() -> &'static [SstvMode] {
+        &[
+            SstvMode::ScottieS1,
+            SstvMode::ScottieS2,
+            SstvMode::MartinM1,
+            SstvMode::MartinM2,
+        ]
+    }
+
+    /// Check if this is a Scottie mode (sync pulse mid-line).
+    #[must_use]
+    pub const fn is_scottie(&self) -> bool {
+        matches!(self, SstvMode::ScottieS1 | SstvMode::ScottieS2)
+    }
+
+    /// Extra sync pulse sent once before the first line, in ms.
+    #[must_use]
+    pub const fn start_sync_ms(&self) -> f32 {
+        if self.is_scottie() {
+            9.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Line timing of the mode.
+    #[must_use]
+    pub fn timing(&self) -> LineTiming {
+        match self {
+            SstvMode::ScottieS1 => scottie(138.240),
+            SstvMode::ScottieS2 => scottie(88.064),
+            SstvMode::MartinM1 => martin(146.432),
+            SstvMode::MartinM2 => martin(73.216),
+        }
+    }
+}
+
+/// Martin line: sync, porch, then G, B, R each followed by a separator.
+fn martin(scan_ms: f32) -> LineTiming {
+    const SYNC: f32 = 4.862;
+    const GAP: f32 = 0.572;
+    let first = SYNC + GAP;
+    LineTiming {
+        sync_start_ms: 0.0,
+        sync_ms: SYNC,
+        scan_start_ms: [first, first + scan_ms + GAP, first + 2.0 * (scan_ms + GAP)],
+        scan_ms,
+        line_ms: first + 3.0 * (scan_ms + GAP),
+    }
+}
+
+/// Scottie line: separator, G, separator, B, sync, porch, R.
+fn scottie(scan_ms: f32) -> LineTiming {
+    const SYNC: f32 = 9.0;
+    const GAP: f32 = 1.5;
+    let blue = GAP + scan_ms + GAP;
+    let sync = blue + scan_ms;
+    let red = sync + SYNC + GAP;
+    LineTiming {
+        sync_start_ms: sync,
+        sync_ms: SYNC,
+        scan_start_ms: [GAP, blue, red],
+        scan_ms,
+        line_ms: red + scan_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_lengths() {
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        assert!(close(SstvMode::MartinM1.timing().line_ms, 446.446));
+        assert!(close(SstvMode::MartinM2.timing().line_ms, 226.798));
+        assert!(close(SstvMode::ScottieS1.timing().line_ms, 428.22));
+        assert!(close(SstvMode::ScottieS2.timing().line_ms, 277.692));
+
+        for mode in SstvMode::all() {
+            assert_eq!(SstvMode::from_vis(mode.vis_code()), Some(*mode));
+        }
+        assert_eq!(SstvMode::from_vis(0), None);
+    }
+}
@@ -0,0 +1,221 @@
+//! VIS (Vertical Interval Signaling) header detection.
+//!
+//! The header is a 1900 Hz leader (300 ms), a 1200 Hz break (10 ms), a
+//! second leader, then ten 30 ms bits at 1200 Hz (start), 1100 Hz (one)
+//! or 1300 Hz (zero): start bit, seven data bits LSB first, even parity
+//! and stop bit. The image begins right after the stop bit.
+
+use crate::mode::{SstvMode, SYNC_HZ};
+
+/// Leader tone frequency in Hz.
+pub const LEADER_HZ: f32 = 1900.0;
+
+/// Length of one VIS bit in ms.
+pub const VIS_BIT_MS: f32 = 30.0;
+
+/// Minimum leader before the start bit is accepted, in ms.
+const MIN_LEADER_MS: f32 = 150.0;
+
+/// Time at 1200 Hz that separates the start bit from the break, in ms.
+const START_BIT_MS: f32 = 20.0;
+
+/// Frequency tolerance for the leader and start bit in Hz.
+const TOLERANCE_HZ: f32 = 80.0;
+
+/// Off-tone time tolerated while the demodulator settles on a new tone, in ms.
+const GLITCH_MS: f32 = 5.0;
+
+/// Detector state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Waiting for enough leader tone
+    Leader,
+    /// At 1200 Hz after the leader: break or start bit
+    Start { since: u32 },
+    /// Reading the data and parity bits
+    Bits { since: u32 },
+}
+
+/// VIS header detector working on demodulated tone frequencies.
+#[derive(Clone, Debug)]
+pub struct VisDetector {
+    samples_per_ms: f32,
+    state: State,
+    /// Samples since the detector started
+    clock: u32,
+    /// Samples of leader tone
+    leader: u32,
+    /// Consecutive samples matching neither expected tone
+    off: u32,
+    /// Frequency sums and counts for the 8 data/parity bits
+    sums: [f32; 8],
+    counts: [u32; 8],
+}
+
+impl VisDetector {
+    /// Create a detector for `sample_rate`.
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            samples_per_ms: sample_rate / 1000.0,
+            state: State::Leader,
+            clock: 0,
+            leader: 0,
+            off: 0,
+            sums: [0.0; 8],
+            counts: [0; 8],
+        }
+    }
+
+    /// Feed one tone frequency sample.
+    ///
+    /// Returns the mode when a header with a known code and valid parity
+    /// ends with this sample's parity bit; the image starts one stop bit
+    /// ([`VIS_BIT_MS`]) later.
+    pub fn process(&mut self, frequency: f32) -> Option<SstvMode> {
+        self.clock = self.clock.wrapping_add(1);
+        let near = |hz: f32| (frequency - hz).abs() < TOLERANCE_HZ;
+
+        match self.state {
+            State::Leader => {
+                if near(LEADER_HZ) {
+                    self.leader = self.leader.saturating_add(1);
+                    self.off = 0;
+                } else if near(SYNC_HZ) && self.elapsed_ms(self.leader) >= MIN_LEADER_MS {
+                    self.off = 0;
+                    self.state = State::Start { since: self.clock };
+                } else if self.settled() {
+                    self.leader = 0;
+                }
+                None
+            }
+            State::Start { since } => {
+                let ms = self.elapsed_ms(self.clock.wrapping_sub(since));
+                if near(SYNC_HZ) {
+                    self.off = 0;
+                    if ms >= START_BIT_MS {
+                        self.sums = [0.0; 8];
+                        self.counts = [0; 8];
+                        self.state = State::Bits { since };
+                    }
+                } else if near(LEADER_HZ) && ms < START_BIT_MS {
+                    // That was the break; the second leader follows
+                    self.leader = 0;
+                    self.off = 0;
+                    self.state = State::Leader;
+                } else if self.settled() {
+                    self.restart();
+                }
+                None
+            }
+            State::Bits { since } => {
+                // Position in bits from the start bit's leading edge
+                let bits = self.elapsed_ms(self.clock.wrapping_sub(since)) / VIS_BIT_MS;
+                let index = bits as usize;
+                if (1..=8).contains(&index) {
+                    // Average the middle of each bit, away from the edges
+                    let within = bits - index as f32;
+                    if (0.2..0.8).contains(&within) {
+                        self.sums[index - 1] += frequency;
+                        self.counts[index - 1] += 1;
+                    }
+                    None
+                } else if index > 8 {
+                    let mode = self.decode();
+                    self.restart();
+                    mode
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Return to searching for a leader.
+    pub fn restart(&mut self) {
+        self.state = State::Leader;
+        self.leader = 0;
+        self.off = 0;
+    }
+
+    /// Count an off-tone sample, returning true once it is more than a glitch.
+    fn settled(&mut self) -> bool {
+        self.off = self.off.saturating_add(1);
+        self.elapsed_ms(self.off) > GLITCH_MS
+    }
+
+    /// Convert a sample count to ms.
+    fn elapsed_ms(&self, samples: u32) -> f32 {
+        samples as f32 / self.samples_per_ms
+    }
+
+    /// Decode the collected bits into a mode.
+    fn decode(&self) -> Option<SstvMode> {
+        let mut code = 0u8;
+        let mut ones = 0;
+        for (k, (&sum, &count)) in self.sums.iter().zip(self.counts.iter()).enumerate() {
+            if count == 0 {
+                return None;
+            }
+            // 1100 Hz is a one, 1300 Hz a zero
+            let one = sum / (count as f32) < 1200.0;
+            if one {
+                ones += 1;
+                if k < 7 {
+                    code |= 1 << k;
+                }
+            }
+        }
+        if ones % 2 != 0 {
+            return None;
+        }
+        SstvMode::from_vis(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tone frequencies of a VIS header at 1 sample per 0.1 ms.
+    fn header(code: u8, parity_error: bool, out: &mut impl FnMut(f32)) {
+        let mut tone = |hz: f32, ms: f32| {
+            for _ in 0..(ms * 10.0) as usize {
+                out(hz);
+            }
+        };
+        tone(LEADER_HZ, 300.0);
+        tone(1200.0, 10.0);
+        tone(LEADER_HZ, 300.0);
+        tone(1200.0, VIS_BIT_MS);
+        let mut parity = parity_error;
+        for k in 0..7 {
+            let one = code & (1 << k) != 0;
+            parity ^= one;
+            tone(if one { 1100.0 } else { 1300.0 }, VIS_BIT_MS);
+        }
+        tone(if parity { 1100.0 } else { 1300.0 }, VIS_BIT_MS);
+        tone(1200.0, VIS_BIT_MS);
+    }
+
+    #[test]
+    fn test_vis_modes() {
+        for mode in SstvMode::all() {
+            let mut detector = VisDetector::new(10_000.0);
+            let mut found = None;
+            header(mode.vis_code(), false, &mut |hz| {
+                if let Some(m) = detector.process(hz) {
+                    found = Some(m);
+                }
+            });
+            assert_eq!(found, Some(*mode));
+        }
+
+        let mut detector = VisDetector::new(10_000.0);
+        let mut found = None;
+        header(SstvMode::MartinM1.vis_code(), true, &mut |hz| {
+            found = found.or(detector.process(hz));
+        });
+        assert_eq!(found, None);
+    }
+}
@@ -1,35 +1,128 @@
 //! Main application component.
 
+pub mod shortcuts;
+
 use leptos::*;
+use sdr_dsp_core::units;
 
 use crate::components::{
-    FrequencyDisplay, ModeSelector, RadioMode, RxTextDisplay, SMeterDisplay, TxInput, Waterfall,
+    BandScope, BandSelector, FrequencyDisplay, ModeSelector, RadioMode, RxTextDisplay, SMeterDisplay,
+    TxInput, Waterfall,
+};
+use crate::app::shortcuts::{create_shortcut_effect, ShortcutHelp};
+use crate::aprs::AprsPanel;
+use crate::audio::{
+    create_audio_effect, create_wav_recorder_effect, AgcSpeed, RecordSource, RecordingState,
+    MAX_NOISE_REDUCTION,
 };
+use crate::bookmarks::{create_bookmark_effect, BookmarkMarkers, BookmarkPanel};
+use crate::conditions::create_conditions_effect;
+use crate::cw_skimmer::CwSkimmerPanel;
+use crate::hell::HellPanel;
+use crate::iq_file::IqFileControls;
+use crate::js8::Js8ChatPanel;
+use crate::layout::{create_layout_effect, is_compact, LayoutMode};
+use crate::logbook::{create_logbook_effect, LogbookPanel};
+use crate::panadapter::{
+    create_panadapter_effect, drag_tune, tune_to_offset, DEMOD_OFFSET_HZ, TUNING_STEPS,
+};
+use crate::psk_channels::{PskChannelsPanel, PskCommand};
+use crate::qrm::QrmPanel;
+use crate::recorder::{create_recorder_effect, RecorderPanel};
+use crate::sstv::SstvPanel;
+use crate::state::profiles::{create_persistence_effect, ProfileSelector};
 use crate::state::{provide_app_context, AppContext};
+use crate::theme::{PaletteEditor, Theme};
+use crate::usb_iq::{IqSource, UsbIqStream};
 
 /// Root application component.
 #[component]
 pub fn App() -> impl IntoView {
     // Provide application context
     let ctx = provide_app_context();
+    create_persistence_effect(ctx.clone());
+    create_audio_effect(ctx.clone());
+    create_recorder_effect(ctx.clone());
+    create_wav_recorder_effect(ctx.clone());
+    create_conditions_effect(ctx.clone());
+    create_panadapter_effect(ctx.clone());
+    create_bookmark_effect(ctx.clone());
+    create_logbook_effect(ctx.clone());
+    create_shortcut_effect(ctx.clone());
+    create_layout_effect(ctx.clone());
+
+    let ctx_tune = ctx.clone();
+    // In PSK31 mode a click decodes the trace instead of retuning
+    let on_tune = Callback::new(move |offset_hz| {
+        if ctx_tune.mode.get_untracked() == RadioMode::Psk31 {
+            ctx_tune.psk_command.set(Some(PskCommand::Add(offset_hz)));
+        } else {
+            tune_to_offset(&ctx_tune, offset_hz);
+        }
+    });
+
+    // Part of a tuning step dragged but not yet applied
+    let drag_pending = store_value(0.0f32);
+    let on_drag_tune = Callback::new(move |shift_hz| {
+        drag_pending.update_value(|pending| drag_tune(&ctx, shift_hz, pending));
+    });
+
+    let compact = move || is_compact(&ctx);
+    // Side panels shown in the compact layout
+    let panels_open = create_rw_signal(false);
 
     view! {
-        <main class="sdr-app">
+        <main class="sdr-app" class:compact=compact style=move || ctx.theme.get().style()>
             <Header ctx=ctx.clone() />
-            <div class="main-content">
+            <div
+                class="main-content"
+                style=move || if compact() { "display: flex; flex-direction: column;" } else { "" }
+            >
                 <div class="display-section">
-                    <Waterfall
+                    <BandScope
                         width=512
-                        height=256
                         spectrum=ctx.spectrum.read_only()
+                        range=ctx.display_range.read_only()
+                        view=ctx.spectrum_view.read_only()
+                        mode=ctx.mode.read_only()
+                        bandwidth=ctx.bandwidth.read_only()
+                        bfo_hz=DEMOD_OFFSET_HZ
+                        theme=ctx.theme.read_only()
                     />
+                    <div class="waterfall-container" style="position: relative;">
+                        <Waterfall
+                            width=512
+                            height=256
+                            spectrum=ctx.spectrum.read_only()
+                            range=ctx.display_range.read_only()
+                            palette=ctx.palette.read_only()
+                            view=ctx.spectrum_view
+                            on_tune=on_tune
+                            on_drag_tune=on_drag_tune
+                        />
+                        <BookmarkMarkers ctx=ctx.clone() />
+                    </div>
                     <SpectrumInfo ctx=ctx.clone() />
                 </div>
-                <div class="control-section">
+                {move || compact().then(|| view! {
+                    <button class="panels-toggle" on:click=move |_| panels_open.update(|o| *o = !*o)>
+                        {move || if panels_open.get() { "Hide panels" } else { "Panels" }}
+                    </button>
+                })}
+                <div class="control-section" class:hidden=move || compact() && !panels_open.get()>
                     <DigitalModePanel ctx=ctx.clone() />
+                    <BookmarkPanel ctx=ctx.clone() />
+                    <LogbookPanel ctx=ctx.clone() />
+                    <WavRecorderControls ctx=ctx.clone() />
+                    <RecorderPanel ctx=ctx.clone() />
+                    <QrmPanel ctx=ctx.clone() />
+                    <PaletteEditor ctx=ctx.clone() />
+                    <AprsPanel ctx=ctx.clone() />
+                    <CwSkimmerPanel ctx=ctx.clone() />
                 </div>
             </div>
             <StatusBar ctx=ctx.clone() />
+            <ShortcutHelp ctx=ctx.clone() />
         </main>
     }
 }
@@ -55,8 +148,14 @@ fn Header(ctx: AppContext) -> impl IntoView {
                 mode=ctx.mode.read_only()
                 on_change=on_mode_change
             />
-            <SMeterDisplay value=ctx.smeter.read_only() />
+            <BandSelector
+                frequency=ctx.frequency.read_only()
+                conditions=ctx.band_conditions.read_only()
+                on_change=on_freq_change
+            />
+            <SMeterDisplay value=ctx.smeter.read_only() dbm=ctx.smeter_dbm.read_only() />
             <AudioControls ctx=ctx.clone() />
+            <ProfileSelector ctx=ctx.clone() />
         </header>
     }
 }
@@ -78,8 +177,37 @@ fn AudioControls(ctx: AppContext) -> impl IntoView {
         }
     };
 
+    let on_source_change = move |ev: web_sys::Event| {
+        let source = match event_target_value(&ev).as_str() {
+            "usb" => IqSource::Usb,
+            "file" => IqSource::File,
+            _ => IqSource::Soundcard,
+        };
+        ctx.iq_source.set(source);
+    };
+
     view! {
         <div class="audio-controls">
+            <select
+                class="iq-source"
+                on:change=on_source_change
+                disabled=move || ctx.audio_running.get()
+            >
+                <option value="soundcard" selected=move || ctx.iq_source.get() == IqSource::Soundcard>
+                    {IqSource::Soundcard.name()}
+                </option>
+                {UsbIqStream::is_available().then(|| view! {
+                    <option value="usb" selected=move || ctx.iq_source.get() == IqSource::Usb>
+                        {IqSource::Usb.name()}
+                    </option>
+                })}
+                <option value="file" selected=move || ctx.iq_source.get() == IqSource::File>
+                    {IqSource::File.name()}
+                </option>
+            </select>
+            {move || (ctx.iq_source.get() == IqSource::File).then(|| view! {
+                <IqFileControls ctx=ctx />
+            })}
             <button
                 class="audio-toggle"
                 class:running=move || ctx.audio_running.get()
@@ -91,6 +219,94 @@ fn AudioControls(ctx: AppContext) -> impl IntoView {
     }
 }
 
+/// WAV recorder controls (start/stop/pause, source and size cap).
+#[component]
+fn WavRecorderControls(ctx: AppContext) -> impl IntoView {
+    let state = ctx.wav_state;
+    let idle = move || state.get() == RecordingState::Idle;
+
+    let toggle_record = move |_| {
+        if idle() {
+            state.set(RecordingState::Recording);
+        } else {
+            state.set(RecordingState::Idle);
+        }
+    };
+
+    let toggle_pause = move |_| {
+        state.update(|s| {
+            *s = match *s {
+                RecordingState::Recording => RecordingState::Paused,
+                RecordingState::Paused => RecordingState::Recording,
+                RecordingState::Idle => RecordingState::Idle,
+            }
+        });
+    };
+
+    let on_source_change = move |ev: web_sys::Event| {
+        let source = match event_target_value(&ev).as_str() {
+            "iq" => RecordSource::Iq,
+            _ => RecordSource::Audio,
+        };
+        ctx.wav_source.set(source);
+    };
+
+    let on_max_change = move |ev: web_sys::Event| {
+        if let Ok(mb) = event_target_value(&ev).parse::<u32>() {
+            ctx.wav_max_mb.set(mb.max(1));
+        }
+    };
+
+    let elapsed = move || {
+        let format = ctx.wav_source.get().wav_format(ctx.audio_sample_rate.get());
+        let bytes = ctx.wav_bytes.get();
+        let secs = format.duration_secs(bytes) as u32;
+        format!(
+            "{:02}:{:02} {:.1} MB",
+            secs / 60,
+            secs % 60,
+            bytes as f64 / 1_000_000.0
+        )
+    };
+
+    view! {
+        <div class="wav-recorder">
+            <h3>"WAV Recorder"</h3>
+            <select class="wav-source" on:change=on_source_change disabled=move || !idle()>
+                <option value="audio" selected=move || ctx.wav_source.get() == RecordSource::Audio>
+                    {RecordSource::Audio.name()}
+                </option>
+                <option value="iq" selected=move || ctx.wav_source.get() == RecordSource::Iq>
+                    {RecordSource::Iq.name()}
+                </option>
+            </select>
+            <label>
+                "Max MB"
+                <input
+                    type="number"
+                    min="1"
+                    max="2000"
+                    prop:value=move || ctx.wav_max_mb.get().to_string()
+                    on:change=on_max_change
+                    disabled=move || !idle()
+                />
+            </label>
+            <button
+                class="wav-record"
+                class:recording=move || !idle()
+                on:click=toggle_record
+                disabled=move || idle() && !ctx.audio_running.get()
+            >
+                {move || if idle() { "Record" } else { "Stop" }}
+            </button>
+            <button on:click=toggle_pause disabled=idle>
+                {move || if state.get() == RecordingState::Paused { "Resume" } else { "Pause" }}
+            </button>
+            <span class="wav-elapsed">{elapsed}</span>
+        </div>
+    }
+}
+
 /// Spectrum info display (frequency markers, etc).
 #[component]
 fn SpectrumInfo(ctx: AppContext) -> impl IntoView {
@@ -108,10 +324,110 @@ fn SpectrumInfo(ctx: AppContext) -> impl IntoView {
         }
     };
 
+    let zoom_display = move || {
+        let zoom = ctx.spectrum_view.get().zoom();
+        if zoom > 1.0 {
+            format!("Zoom: x{:.1}", zoom)
+        } else {
+            String::new()
+        }
+    };
+
+    let on_step_change = move |ev: web_sys::Event| {
+        if let Ok(step) = event_target_value(&ev).parse() {
+            ctx.tuning_step.set(step);
+        }
+    };
+
+    let on_bandwidth_change = move |ev: web_sys::Event| {
+        if let Ok(bandwidth) = event_target_value(&ev).parse() {
+            ctx.bandwidth.set(bandwidth);
+        }
+    };
+
+    let on_agc_change = move |ev: web_sys::Event| {
+        if let Some(speed) = AgcSpeed::from_name(&event_target_value(&ev)) {
+            ctx.agc_speed.set(speed);
+        }
+    };
+
+    let on_noise_reduction_change = move |ev: web_sys::Event| {
+        if let Ok(level) = event_target_value(&ev).parse::<u8>() {
+            ctx.noise_reduction.set(level.min(MAX_NOISE_REDUCTION));
+        }
+    };
+
+    let noise_floor = move || {
+        ctx.noise_floor
+            .get()
+            .map(|dbm| format!("Noise: {}", units::format_dbm(dbm).as_str()))
+            .unwrap_or_default()
+    };
+
     view! {
         <div class="spectrum-info">
             <span class="center-freq">{center_freq}</span>
             <span class="afc-offset">{afc_display}</span>
+            <span class="zoom">{zoom_display}</span>
+            <span class="noise-floor">{noise_floor}</span>
+            <select
+                class="tuning-step"
+                prop:value=move || ctx.tuning_step.get().to_string()
+                on:change=on_step_change
+            >
+                {TUNING_STEPS
+                    .iter()
+                    .map(|step| view! { <option value=step.to_string()>{format!("{} Hz", step)}</option> })
+                    .collect_view()}
+            </select>
+            <label class="bandwidth">
+                "BW "
+                <input
+                    type="range"
+                    min="100"
+                    max="6000"
+                    step="50"
+                    prop:value=move || ctx.bandwidth.get().to_string()
+                    on:input=on_bandwidth_change
+                />
+                {move || format!("{:.0} Hz", ctx.bandwidth.get())}
+            </label>
+            <label class="agc">
+                "AGC "
+                <select on:change=on_agc_change>
+                    {AgcSpeed::all()
+                        .iter()
+                        .map(|&speed| view! {
+                            <option value=speed.name() selected=move || ctx.agc_speed.get() == speed>
+                                {speed.name()}
+                            </option>
+                        })
+                        .collect_view()}
+                </select>
+            </label>
+            <label class="noise-reduction">
+                "NR "
+                <input
+                    type="range"
+                    min="0"
+                    max=MAX_NOISE_REDUCTION.to_string()
+                    step="1"
+                    prop:value=move || ctx.noise_reduction.get().to_string()
+                    on:input=on_noise_reduction_change
+                />
+                {move || match ctx.noise_reduction.get() {
+                    0 => "Off".to_string(),
+                    level => level.to_string(),
+                }}
+            </label>
+            <label class="auto-range">
+                <input
+                    type="checkbox"
+                    prop:checked=move || ctx.auto_range.get()
+                    on:change=move |ev| ctx.auto_range.set(event_target_checked(&ev))
+                />
+                "Auto range"
+            </label>
         </div>
     }
 }
@@ -141,6 +457,18 @@ fn DigitalModePanel(ctx: AppContext) -> impl IntoView {
                 />
             </div>
             <AfcControls ctx=ctx.clone() />
+            {move || (ctx.mode.get() == RadioMode::Psk31).then(|| view! {
+                <PskChannelsPanel ctx=ctx />
+            })}
+            {move || (ctx.mode.get() == RadioMode::Sstv).then(|| view! {
+                <SstvPanel ctx=ctx />
+            })}
+            {move || (ctx.mode.get() == RadioMode::Js8).then(|| view! {
+                <Js8ChatPanel ctx=ctx />
+            })}
+            {move || (ctx.mode.get() == RadioMode::Hell).then(|| view! {
+                <HellPanel ctx=ctx />
+            })}
         </div>
     }
 }
@@ -152,6 +480,18 @@ fn AfcControls(ctx: AppContext) -> impl IntoView {
         ctx.afc_enabled.update(|v| *v = !*v);
     };
 
+    let on_bandwidth_change = move |ev: web_sys::Event| {
+        if let Ok(bandwidth) = event_target_value(&ev).parse() {
+            ctx.afc_bandwidth.set(bandwidth);
+        }
+    };
+
+    let on_range_change = move |ev: web_sys::Event| {
+        if let Ok(range) = event_target_value(&ev).parse() {
+            ctx.afc_range.set(range);
+        }
+    };
+
     view! {
         <div class="afc-controls">
             <label>
@@ -162,6 +502,32 @@ fn AfcControls(ctx: AppContext) -> impl IntoView {
                 />
                 "AFC"
             </label>
+            <label title="How fast AFC follows drift">
+                "Rate "
+                <input
+                    type="range"
+                    min="5"
+                    max="100"
+                    step="5"
+                    prop:value=move || ctx.afc_bandwidth.get().to_string()
+                    on:input=on_bandwidth_change
+                    disabled=move || !ctx.afc_enabled.get()
+                />
+                {move || format!("{:.0} Hz", ctx.afc_bandwidth.get())}
+            </label>
+            <label title="How far AFC may pull from the clicked frequency">
+                "Range "
+                <input
+                    type="range"
+                    min="10"
+                    max="200"
+                    step="10"
+                    prop:value=move || ctx.afc_range.get().to_string()
+                    on:input=on_range_change
+                    disabled=move || !ctx.afc_enabled.get()
+                />
+                {move || format!("±{:.0} Hz", ctx.afc_range.get())}
+            </label>
         </div>
     }
 }
@@ -181,10 +547,50 @@ fn StatusBar(ctx: AppContext) -> impl IntoView {
 
     let mode_text = move || ctx.mode.get().name();
 
+    let on_layout_change = move |ev: web_sys::Event| {
+        if let Some(layout) = LayoutMode::from_name(&event_target_value(&ev)) {
+            ctx.layout_mode.set(layout);
+        }
+    };
+
+    let on_theme_change = move |ev: web_sys::Event| {
+        if let Some(theme) = Theme::from_name(&event_target_value(&ev)) {
+            ctx.theme.set(theme);
+        }
+    };
+
     view! {
         <footer class="status-bar">
             <span class="status">{status_text}</span>
             <span class="mode">{mode_text}</span>
+            <span class="muted" class:hidden=move || !ctx.muted.get()>"MUTE"</span>
+            <button
+                class="shortcut-toggle"
+                title="Keyboard shortcuts (?)"
+                on:click=move |_| ctx.shortcut_help.update(|shown| *shown = !*shown)
+            >
+                "?"
+            </button>
+            <select class="layout-mode" title="Layout" on:change=on_layout_change>
+                {LayoutMode::all()
+                    .iter()
+                    .map(|&layout| view! {
+                        <option value=layout.name() selected=move || ctx.layout_mode.get() == layout>
+                            {layout.name()}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
+            <select class="theme" title="Theme" on:change=on_theme_change>
+                {Theme::all()
+                    .iter()
+                    .map(|&theme| view! {
+                        <option value=theme.name() selected=move || ctx.theme.get() == theme>
+                            {theme.name()}
+                        </option>
+                    })
+                    .collect_view()}
+            </select>
             <span class="version">"SDR Frontend v0.1.0"</span>
         </footer>
     }
@@ -0,0 +1,441 @@
+//! Keyboard shortcuts.
+//!
+//! Keys bound to tuning, mode switching, mute, transmit and bookmark
+//! recall, kept in localStorage so rebinding survives a page reload.
+//! Arrow keys tune by the tuning step, ten times that with Shift and a
+//! hundred times with Alt. Transmit is push to talk and only works once
+//! armed in the help overlay: it keys while its key is held and drops
+//! when the key is released or the window loses focus.
+//!
+//! Keys typed into text fields, or already handled by a focused control
+//! such as the frequency keypad, are left alone.
+
+use leptos::*;
+use sdr_dsp_core::spectrum::snap_to_step;
+
+use crate::bookmarks::go_to_bookmark;
+use crate::components::{RadioMode, MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+use crate::state::AppContext;
+
+/// localStorage key holding the bindings.
+const STORAGE_KEY: &str = "sdr-shortcuts";
+
+/// Header line of the stored format.
+const STORAGE_HEADER: &str = "sdr-shortcuts 1";
+
+/// Bookmarks reachable from the number keys.
+pub const RECALL_SLOTS: u8 = 9;
+
+/// Something a key can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Tune up one step
+    TuneUp,
+    /// Tune down one step
+    TuneDown,
+    /// Switch to the next mode
+    NextMode,
+    /// Switch to the previous mode
+    PreviousMode,
+    /// Mute or unmute the speaker
+    ToggleMute,
+    /// Transmit while held (when armed)
+    Transmit,
+    /// Go to a bookmark (1-based, in frequency order)
+    Recall(u8),
+    /// Show or hide the shortcut list
+    Help,
+}
+
+impl Action {
+    /// Get the description shown in the help overlay.
+    pub fn description(&self) -> String {
+        match self {
+            Action::TuneUp => "Tune up".into(),
+            Action::TuneDown => "Tune down".into(),
+            Action::NextMode => "Next mode".into(),
+            Action::PreviousMode => "Previous mode".into(),
+            Action::ToggleMute => "Mute / unmute".into(),
+            Action::Transmit => "Transmit (hold)".into(),
+            Action::Recall(slot) => format!("Bookmark {}", slot),
+            Action::Help => "Show shortcuts".into(),
+        }
+    }
+
+    /// Get the name used in the stored format.
+    fn code(&self) -> String {
+        match self {
+            Action::TuneUp => "tune-up".into(),
+            Action::TuneDown => "tune-down".into(),
+            Action::NextMode => "next-mode".into(),
+            Action::PreviousMode => "previous-mode".into(),
+            Action::ToggleMute => "mute".into(),
+            Action::Transmit => "transmit".into(),
+            Action::Recall(slot) => format!("recall-{}", slot),
+            Action::Help => "help".into(),
+        }
+    }
+
+    /// Parse a name written by [`Action::code`].
+    fn from_code(code: &str) -> Option<Action> {
+        let action = match code {
+            "tune-up" => Action::TuneUp,
+            "tune-down" => Action::TuneDown,
+            "next-mode" => Action::NextMode,
+            "previous-mode" => Action::PreviousMode,
+            "mute" => Action::ToggleMute,
+            "transmit" => Action::Transmit,
+            "help" => Action::Help,
+            _ => {
+                let slot = code.strip_prefix("recall-")?.parse().ok()?;
+                return (1..=RECALL_SLOTS).contains(&slot).then_some(Action::Recall(slot));
+            }
+        };
+        Some(action)
+    }
+}
+
+/// A key bound to an action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    /// Key as reported by `KeyboardEvent.key`, letters in lower case
+    pub key: String,
+    /// What the key does
+    pub action: Action,
+}
+
+impl Binding {
+    fn new(key: &str, action: Action) -> Self {
+        Self {
+            key: key.to_string(),
+            action,
+        }
+    }
+}
+
+/// Get the default bindings.
+pub fn default_bindings() -> Vec<Binding> {
+    let mut bindings = vec![
+        Binding::new("ArrowUp", Action::TuneUp),
+        Binding::new("ArrowRight", Action::TuneUp),
+        Binding::new("ArrowDown", Action::TuneDown),
+        Binding::new("ArrowLeft", Action::TuneDown),
+        Binding::new("m", Action::NextMode),
+        Binding::new("n", Action::PreviousMode),
+        Binding::new("u", Action::ToggleMute),
+        Binding::new(" ", Action::Transmit),
+    ];
+    bindings.extend(
+        (1..=RECALL_SLOTS).map(|slot| Binding::new(&slot.to_string(), Action::Recall(slot))),
+    );
+    bindings.push(Binding::new("?", Action::Help));
+    bindings
+}
+
+/// Normalize a `KeyboardEvent.key` for matching (letters in lower case).
+pub fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+/// Get the label shown for a key.
+pub fn key_label(key: &str) -> String {
+    match key {
+        " " => "Space".into(),
+        "ArrowUp" => "↑".into(),
+        "ArrowDown" => "↓".into(),
+        "ArrowLeft" => "←".into(),
+        "ArrowRight" => "→".into(),
+        key if key.chars().count() == 1 => key.to_uppercase(),
+        key => key.to_string(),
+    }
+}
+
+/// Find the action bound to a key.
+pub fn action_for(bindings: &[Binding], key: &str) -> Option<Action> {
+    let key = normalize_key(key);
+    bindings.iter().find(|b| b.key == key).map(|b| b.action)
+}
+
+/// Tuning step multiplier for the held modifiers.
+pub fn step_multiplier(shift: bool, alt: bool) -> u64 {
+    match (shift, alt) {
+        (_, true) => 100,
+        (true, false) => 10,
+        (false, false) => 1,
+    }
+}
+
+/// Encode bindings for storage.
+pub fn encode_bindings(bindings: &[Binding]) -> String {
+    let mut text = String::from(STORAGE_HEADER);
+    for binding in bindings {
+        text.push('\n');
+        text.push_str(&format!("{}\t{}", binding.action.code(), binding.key));
+    }
+    text
+}
+
+/// Decode stored bindings, skipping lines that do not parse.
+pub fn decode_bindings(text: &str) -> Option<Vec<Binding>> {
+    let mut lines = text.lines();
+    if lines.next() != Some(STORAGE_HEADER) {
+        return None;
+    }
+    let bindings = lines
+        .filter_map(|line| {
+            let (code, key) = line.split_once('\t')?;
+            let action = Action::from_code(code)?;
+            (!key.is_empty()).then(|| Binding::new(key, action))
+        })
+        .collect();
+    Some(bindings)
+}
+
+/// Get the page's localStorage.
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Load bindings from localStorage, or the defaults.
+pub fn load_bindings() -> Vec<Binding> {
+    storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|text| decode_bindings(&text))
+        .unwrap_or_else(default_bindings)
+}
+
+/// Save bindings to localStorage.
+pub fn save_bindings(bindings: &[Binding]) {
+    if let Some(storage) = storage() {
+        if let Err(e) = storage.set_item(STORAGE_KEY, &encode_bindings(bindings)) {
+            web_sys::console::error_1(&format!("Shortcut save error: {:?}", e).into());
+        }
+    }
+}
+
+/// Check if a key event belongs to a text field or a focused control.
+fn is_claimed(ev: &web_sys::KeyboardEvent) -> bool {
+    if ev.default_prevented() || ev.ctrl_key() || ev.meta_key() {
+        return true;
+    }
+    let tag = event_target::<web_sys::Element>(ev).tag_name();
+    matches!(tag.as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+}
+
+/// Tune by the tuning step times `multiplier`, up or down.
+fn tune(ctx: &AppContext, up: bool, multiplier: u64) {
+    let step = ctx.tuning_step.get_untracked().max(1) * multiplier;
+    let current = ctx.frequency.get_untracked();
+    let target = if up { current.saturating_add(step) } else { current.saturating_sub(step) };
+    let snapped = snap_to_step(target, step);
+    ctx.frequency.set(snapped.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ));
+}
+
+/// Switch mode, forward or back through [`RadioMode::all`].
+fn step_mode(ctx: &AppContext, forward: bool) {
+    let modes = RadioMode::all();
+    let current = ctx.mode.get_untracked();
+    let index = modes.iter().position(|&m| m == current).unwrap_or(0);
+    let next = if forward { index + 1 } else { index + modes.len() - 1 };
+    ctx.mode.set(modes[next % modes.len()]);
+}
+
+/// Create the effect that saves binding changes and the window key
+/// listeners that run the actions.
+pub fn create_shortcut_effect(ctx: AppContext) {
+    ctx.shortcuts.set(load_bindings());
+    create_effect(move |previous: Option<()>| {
+        ctx.shortcuts.with(|bindings| {
+            // Skip the run that only reflects what was just loaded
+            if previous.is_some() {
+                save_bindings(bindings);
+            }
+        });
+    });
+
+    // Whether the transmit key keyed the radio, so only it unkeys it
+    let keyed = store_value(false);
+
+    let _ = window_event_listener(ev::keydown, move |ev| {
+        if is_claimed(&ev) {
+            return;
+        }
+        if ev.key() == "Escape" && ctx.shortcut_help.get_untracked() {
+            ctx.shortcut_help.set(false);
+            return;
+        }
+        let Some(action) = ctx.shortcuts.with_untracked(|b| action_for(b, &ev.key())) else {
+            return;
+        };
+        ev.prevent_default();
+        match action {
+            Action::TuneUp | Action::TuneDown => {
+                let multiplier = step_multiplier(ev.shift_key(), ev.alt_key());
+                tune(&ctx, action == Action::TuneUp, multiplier);
+            }
+            Action::NextMode => step_mode(&ctx, true),
+            Action::PreviousMode => step_mode(&ctx, false),
+            Action::ToggleMute => ctx.muted.update(|m| *m = !*m),
+            Action::Transmit => {
+                let idle = !ctx.transmitting.get_untracked();
+                if !ev.repeat() && idle && ctx.tx_shortcut_armed.get_untracked() {
+                    keyed.set_value(true);
+                    ctx.transmitting.set(true);
+                }
+            }
+            Action::Recall(slot) => {
+                let index = usize::from(slot) - 1;
+                let bookmark = ctx.bookmarks.with_untracked(|list| list.get(index).cloned());
+                if let Some(bookmark) = bookmark {
+                    go_to_bookmark(&ctx, &bookmark);
+                }
+            }
+            Action::Help => ctx.shortcut_help.update(|shown| *shown = !*shown),
+        }
+    });
+
+    let _ = window_event_listener(ev::keyup, move |ev| {
+        let action = ctx.shortcuts.with_untracked(|b| action_for(b, &ev.key()));
+        if action == Some(Action::Transmit) && keyed.get_value() {
+            keyed.set_value(false);
+            ctx.transmitting.set(false);
+        }
+    });
+
+    let _ = window_event_listener(ev::blur, move |_| {
+        if keyed.get_value() {
+            keyed.set_value(false);
+            ctx.transmitting.set(false);
+        }
+    });
+}
+
+/// Overlay listing the shortcuts, with rebinding and the transmit arm switch.
+#[component]
+pub fn ShortcutHelp(ctx: AppContext) -> impl IntoView {
+    // Binding waiting for its new key
+    let rebinding = create_rw_signal(None::<usize>);
+
+    let on_rebind_key = move |ev: web_sys::KeyboardEvent| {
+        let Some(index) = rebinding.get_untracked() else {
+            return;
+        };
+        // Keep the key away from the shortcut listener
+        ev.prevent_default();
+        ev.stop_propagation();
+        let key = ev.key();
+        if !matches!(key.as_str(), "Escape" | "Shift" | "Alt" | "Control" | "Meta") {
+            let key = normalize_key(&key);
+            ctx.shortcuts.update(|bindings| {
+                if let Some(binding) = bindings.get_mut(index) {
+                    binding.key = key;
+                }
+            });
+        }
+        rebinding.set(None);
+    };
+
+    let rows = move || {
+        ctx.shortcuts
+            .get()
+            .into_iter()
+            .enumerate()
+            .map(|(index, binding)| {
+                let waiting = move || rebinding.get() == Some(index);
+                let label = key_label(&binding.key);
+                view! {
+                    <tr>
+                        <td class="shortcut-key">
+                            <button
+                                class:waiting=waiting
+                                title="Click, then press the new key"
+                                on:click=move |_| rebinding.set(Some(index))
+                                on:keydown=on_rebind_key
+                                on:blur=move |_| rebinding.set(None)
+                            >
+                                {move || if waiting() { "…".to_string() } else { label.clone() }}
+                            </button>
+                        </td>
+                        <td>{binding.action.description()}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="shortcut-help" class:hidden=move || !ctx.shortcut_help.get()>
+            <h3>"Keyboard Shortcuts"</h3>
+            <table>{rows}</table>
+            <p>"Shift: 10x step, Alt: 100x step"</p>
+            <label title="Let the transmit key key the radio while held">
+                <input
+                    type="checkbox"
+                    prop:checked=move || ctx.tx_shortcut_armed.get()
+                    on:change=move |ev| ctx.tx_shortcut_armed.set(event_target_checked(&ev))
+                />
+                "Arm transmit key"
+            </label>
+            <button on:click=move |_| ctx.shortcuts.set(default_bindings())>"Defaults"</button>
+            <button on:click=move |_| ctx.shortcut_help.set(false)>"Close"</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_for_default_bindings() {
+        let bindings = default_bindings();
+        assert_eq!(action_for(&bindings, "ArrowUp"), Some(Action::TuneUp));
+        assert_eq!(action_for(&bindings, "M"), Some(Action::NextMode));
+        assert_eq!(action_for(&bindings, " "), Some(Action::Transmit));
+        assert_eq!(action_for(&bindings, "9"), Some(Action::Recall(9)));
+        assert_eq!(action_for(&bindings, "?"), Some(Action::Help));
+        assert_eq!(action_for(&bindings, "x"), None);
+        assert_eq!(action_for(&bindings, "Enter"), None);
+    }
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!(normalize_key("Q"), "q");
+        assert_eq!(normalize_key("ArrowUp"), "ArrowUp");
+        assert_eq!(key_label(" "), "Space");
+        assert_eq!(key_label("m"), "M");
+        assert_eq!(key_label("ArrowLeft"), "←");
+        assert_eq!(key_label("PageUp"), "PageUp");
+    }
+
+    #[test]
+    fn test_step_multiplier() {
+        assert_eq!(step_multiplier(false, false), 1);
+        assert_eq!(step_multiplier(true, false), 10);
+        assert_eq!(step_multiplier(false, true), 100);
+        assert_eq!(step_multiplier(true, true), 100);
+    }
+
+    #[test]
+    fn test_bindings_round_trip() {
+        let bindings = default_bindings();
+        assert_eq!(decode_bindings(&encode_bindings(&bindings)), Some(bindings));
+    }
+
+    #[test]
+    fn test_decode_bindings_skips_bad_lines() {
+        let text = format!(
+            "{}\ntune-up\tk\nrecall-10\t0\nrecall-0\t0\nwarp\tw\nhelp\t\nmute",
+            STORAGE_HEADER
+        );
+        assert_eq!(
+            decode_bindings(&text),
+            Some(vec![Binding::new("k", Action::TuneUp)])
+        );
+        assert_eq!(decode_bindings("sdr-shortcuts 2\nhelp\th"), None);
+    }
+}
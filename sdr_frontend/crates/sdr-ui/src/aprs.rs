@@ -0,0 +1,237 @@
+//! APRS station list and plot.
+//!
+//! With APRS enabled the worklet decodes 1200 baud AX.25 packets from
+//! the demodulated audio (tune an FM APRS frequency such as 144.390 or
+//! 144.800 MHz) and sends each frame that passes its FCS check. Frames
+//! are shown in TNC2 monitor format, and stations reporting a position
+//! are plotted relative to each other.
+
+use leptos::*;
+use sdr_mode_aprs::{AprsPacket, Ax25Frame, Position};
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::state::AppContext;
+
+/// Packets kept in the monitor log.
+pub const MAX_LOG_LINES: usize = 200;
+
+/// Plot size in pixels.
+const PLOT_WIDTH: u32 = 320;
+const PLOT_HEIGHT: u32 = 240;
+
+/// Smallest span plotted in degrees of latitude.
+const MIN_SPAN_DEG: f64 = 0.1;
+
+/// A station heard on APRS.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AprsStation {
+    /// Callsign with SSID
+    pub callsign: String,
+    /// Last reported position
+    pub position: Option<Position>,
+    /// Last position comment
+    pub comment: String,
+    /// Last status report
+    pub status: String,
+    /// Time last heard (ms since the epoch)
+    pub last_heard: f64,
+    /// Packets received
+    pub packets: u32,
+}
+
+/// Record an AX.25 frame from the worklet.
+pub fn frame_received(ctx: &AppContext, bytes: &[u8]) {
+    let frame = match Ax25Frame::parse(bytes) {
+        Ok(frame) => frame,
+        Err(e) => {
+            web_sys::console::warn_1(&format!("APRS: bad frame: {:?}", e).into());
+            return;
+        }
+    };
+    let now = js_sys::Date::now();
+
+    ctx.aprs_log.update(|log| {
+        log.push(frame.to_string());
+        let excess = log.len().saturating_sub(MAX_LOG_LINES);
+        log.drain(..excess);
+    });
+
+    let mut source = frame.source.clone();
+    source.repeated = false;
+    let callsign = source.to_string();
+    ctx.aprs_stations.update(|stations| {
+        let index = match stations.iter().position(|s| s.callsign == callsign) {
+            Some(index) => index,
+            None => {
+                stations.push(AprsStation {
+                    callsign,
+                    position: None,
+                    comment: String::new(),
+                    status: String::new(),
+                    last_heard: now,
+                    packets: 0,
+                });
+                stations.len() - 1
+            }
+        };
+        let station = &mut stations[index];
+        station.last_heard = now;
+        station.packets += 1;
+        match AprsPacket::parse(&frame.info) {
+            Some(AprsPacket::Position {
+                position, comment, ..
+            }) => {
+                station.position = Some(position);
+                station.comment = comment.to_string();
+            }
+            Some(AprsPacket::Status(status)) => station.status = status.to_string(),
+            _ => {}
+        }
+        // Most recently heard first
+        stations.sort_by(|a, b| b.last_heard.total_cmp(&a.last_heard));
+    });
+}
+
+/// Format a position as decimal degrees, e.g. "49.0583 N 72.0292 W".
+pub fn position_label(position: &Position) -> String {
+    format!(
+        "{:.4} {} {:.4} {}",
+        position.latitude.abs(),
+        if position.latitude < 0.0 { 'S' } else { 'N' },
+        position.longitude.abs(),
+        if position.longitude < 0.0 { 'W' } else { 'E' },
+    )
+}
+
+/// Leptos component listing APRS stations and packets.
+#[component]
+pub fn AprsPanel(ctx: AppContext) -> impl IntoView {
+    let canvas_ref = create_node_ref::<leptos::html::Canvas>();
+
+    // Replot whenever a station is heard
+    create_effect(move |_| {
+        let Some(canvas) = canvas_ref.get() else {
+            return;
+        };
+        let Some(context) = context_2d(&canvas) else {
+            return;
+        };
+        ctx.aprs_stations.with(|stations| draw_plot(&context, stations));
+    });
+
+    let clear = move |_| {
+        ctx.aprs_stations.set(Vec::new());
+        ctx.aprs_log.set(Vec::new());
+    };
+
+    view! {
+        <div class="aprs-panel">
+            <h3>"APRS"</h3>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || ctx.aprs_enabled.get()
+                    on:change=move |ev| ctx.aprs_enabled.set(event_target_checked(&ev))
+                />
+                "Decode 1200 baud packets"
+            </label>
+            <canvas
+                node_ref=canvas_ref
+                class="aprs-plot"
+                width=PLOT_WIDTH
+                height=PLOT_HEIGHT
+                style="display: block;"
+            />
+            <table class="aprs-stations">
+                <thead>
+                    <tr>
+                        <th>"Station"</th>
+                        <th>"Position"</th>
+                        <th>"Comment"</th>
+                        <th>"Heard"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || ctx.aprs_stations.get()
+                        .into_iter()
+                        .map(|station| {
+                            let heard = js_sys::Date::new(&station.last_heard.into())
+                                .to_locale_time_string("en-GB");
+                            let comment = if station.status.is_empty() {
+                                station.comment
+                            } else {
+                                format!("{} {}", station.comment, station.status).trim().to_string()
+                            };
+                            view! {
+                                <tr>
+                                    <td>{station.callsign}</td>
+                                    <td>{station.position.as_ref().map(position_label)}</td>
+                                    <td>{comment}</td>
+                                    <td>{format!("{} ({})", String::from(heard), station.packets)}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+            <pre class="aprs-log">
+                {move || ctx.aprs_log.with(|log| log.join("\n"))}
+            </pre>
+            <button on:click=clear>"Clear"</button>
+        </div>
+    }
+}
+
+/// Get the 2D drawing context of a canvas.
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+}
+
+/// Plot stations with a position, scaled to fit.
+fn draw_plot(context: &CanvasRenderingContext2d, stations: &[AprsStation]) {
+    let width = f64::from(PLOT_WIDTH);
+    let height = f64::from(PLOT_HEIGHT);
+    context.set_fill_style_str("#000000");
+    context.fill_rect(0.0, 0.0, width, height);
+
+    let points: Vec<(&str, f64, f64)> = stations
+        .iter()
+        .filter_map(|s| {
+            let p = s.position.as_ref()?;
+            Some((s.callsign.as_str(), f64::from(p.latitude), f64::from(p.longitude)))
+        })
+        .collect();
+    if points.is_empty() {
+        return;
+    }
+
+    let (mut south, mut north, mut west, mut east) = (90.0f64, -90.0f64, 180.0f64, -180.0f64);
+    for &(_, lat, lon) in &points {
+        south = south.min(lat);
+        north = north.max(lat);
+        west = west.min(lon);
+        east = east.max(lon);
+    }
+    // Equal distances in both directions at the middle latitude
+    let lon_scale = ((south + north) / 2.0).to_radians().cos().max(0.01);
+    let span = (north - south)
+        .max((east - west) * lon_scale * height / width)
+        .max(MIN_SPAN_DEG);
+    let degrees_per_pixel = span * 1.2 / height;
+    let (mid_lat, mid_lon) = ((south + north) / 2.0, (west + east) / 2.0);
+
+    context.set_font("11px monospace");
+    for (callsign, lat, lon) in points {
+        let x = width / 2.0 + (lon - mid_lon) * lon_scale / degrees_per_pixel;
+        let y = height / 2.0 - (lat - mid_lat) / degrees_per_pixel;
+        context.set_fill_style_str("#40ff40");
+        context.fill_rect(x - 2.0, y - 2.0, 4.0, 4.0);
+        context.set_fill_style_str("#c0c0c0");
+        let _ = context.fill_text(callsign, x + 4.0, y - 4.0);
+    }
+}
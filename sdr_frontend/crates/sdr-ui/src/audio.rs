@@ -1,14 +1,210 @@
 //! Web Audio API integration for SDR processing.
 //!
 //! Handles AudioContext creation, AudioWorklet loading, and
-//! data transfer between the audio thread and UI.
+//! data transfer between the audio thread and UI. Also records the
+//! demodulated audio or raw IQ to downloadable WAV files.
 
 use leptos::*;
+use sdr_dsp_core::agc::AgcConfig;
+use sdr_dsp_core::wav::{encode_pcm16, WavFormat, BYTES_PER_SAMPLE, HEADER_LEN};
+use sdr_dsp_core::{SignalQuality, SmeterCalibration};
+use sdr_dsp_api::{capability, Parameter, API_VERSION_MAJOR};
+use sdr_mode_sstv::SstvMode;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{AudioContext, AudioWorkletNode, AudioWorkletNodeOptions};
 
+use crate::aprs;
+use crate::components::{HamBand, RadioMode};
+use crate::cw_skimmer::{self, CwSpot};
+use crate::hell;
+use crate::iq_file::PLAYBACK_INTERVAL_MS;
+use crate::js8;
+use crate::psk_channels::{self, PskCommand};
+use crate::serial::sleep_ms;
+use crate::sstv::{self, SstvLine};
 use crate::state::AppContext;
+use crate::usb_iq::{IqFrameParser, IqSource, UsbIqStream, FLAG_OVERFLOW};
+
+/// Highest noise reduction level.
+pub const MAX_NOISE_REDUCTION: u8 = 10;
+
+/// Default WAV recording size cap in megabytes.
+pub const DEFAULT_WAV_MAX_MB: u32 = 100;
+
+/// WAV recorder state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordingState {
+    /// Not recording
+    #[default]
+    Idle,
+    /// Capturing samples
+    Recording,
+    /// Recording open, samples discarded
+    Paused,
+}
+
+/// What the WAV recorder captures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordSource {
+    /// Demodulated audio (mono)
+    #[default]
+    Audio,
+    /// Raw IQ (stereo, I left and Q right)
+    Iq,
+}
+
+impl RecordSource {
+    /// Get display name for the source.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecordSource::Audio => "Audio",
+            RecordSource::Iq => "IQ",
+        }
+    }
+
+    /// WAV layout of recordings from this source.
+    pub fn wav_format(&self, sample_rate: f32) -> WavFormat {
+        let rate = sample_rate.round() as u32;
+        match self {
+            RecordSource::Audio => WavFormat::mono(rate),
+            RecordSource::Iq => WavFormat::iq(rate),
+        }
+    }
+}
+
+/// AGC speed preset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AgcSpeed {
+    /// Fast recovery, for CW
+    Fast,
+    /// For SSB
+    #[default]
+    Medium,
+    /// Slow recovery, for AM and weak signals
+    Slow,
+}
+
+impl AgcSpeed {
+    /// Get all presets, fastest first.
+    pub fn all() -> &'static [AgcSpeed] {
+        &[AgcSpeed::Fast, AgcSpeed::Medium, AgcSpeed::Slow]
+    }
+
+    /// Get display name for the preset.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgcSpeed::Fast => "Fast",
+            AgcSpeed::Medium => "Medium",
+            AgcSpeed::Slow => "Slow",
+        }
+    }
+
+    /// Parse a display name written by [`AgcSpeed::name`].
+    pub fn from_name(name: &str) -> Option<AgcSpeed> {
+        AgcSpeed::all().iter().copied().find(|s| s.name() == name)
+    }
+
+    /// Get the AGC settings of the preset.
+    pub fn config(&self) -> AgcConfig {
+        match self {
+            AgcSpeed::Fast => AgcConfig::fast(),
+            AgcSpeed::Medium => AgcConfig::medium(),
+            AgcSpeed::Slow => AgcConfig::slow(),
+        }
+    }
+}
+
+/// In-memory 16-bit PCM WAV recording with a size cap.
+pub struct WavRecorder {
+    format: WavFormat,
+    data: Vec<u8>,
+    max_bytes: usize,
+}
+
+impl WavRecorder {
+    /// Start an empty recording holding at most `max_bytes` of samples.
+    pub fn new(format: WavFormat, max_bytes: usize) -> Self {
+        Self {
+            format,
+            data: Vec::new(),
+            max_bytes: max_bytes.min(sdr_dsp_core::wav::MAX_DATA_LEN),
+        }
+    }
+
+    /// Append samples (interleaved for IQ), returning false once the cap is reached.
+    pub fn push(&mut self, samples: &[f32]) -> bool {
+        let block = usize::from(self.format.block_align());
+        let room = (self.max_bytes - self.data.len()) / block * block;
+        let len = (samples.len() * BYTES_PER_SAMPLE).min(room);
+        let start = self.data.len();
+        self.data.resize(start + len, 0);
+        encode_pcm16(samples, &mut self.data[start..]);
+        !self.is_full()
+    }
+
+    /// Bytes of sample data recorded.
+    pub fn data_len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check if the size cap is reached.
+    pub fn is_full(&self) -> bool {
+        self.max_bytes - self.data.len() < usize::from(self.format.block_align())
+    }
+
+    /// Finish the recording as a complete WAV file.
+    pub fn finish(self) -> Vec<u8> {
+        let mut file = Vec::with_capacity(HEADER_LEN + self.data.len());
+        file.extend_from_slice(&self.format.header(self.data.len()));
+        file.extend_from_slice(&self.data);
+        file
+    }
+}
+
+/// Offer bytes to the user as a file download.
+pub(crate) fn download_bytes(filename: &str, bytes: &[u8], mime: &str) -> Result<(), JsValue> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("No document")?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
+/// API version and capabilities the worklet's DSP reports when ready.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DspApi {
+    /// Version packed as `major << 16 | minor`
+    pub version: u32,
+    /// [`capability`] bits
+    pub capabilities: u32,
+    /// [`sdr_dsp_api::extended_capability`] bits
+    pub extended_capabilities: u32,
+}
+
+impl DspApi {
+    /// Check the DSP speaks this build's major version and takes settings objects.
+    pub fn is_compatible(&self) -> bool {
+        self.version >> 16 == u32::from(API_VERSION_MAJOR)
+            && self.supports(capability::CONFIG_OBJECTS)
+    }
+
+    /// Check the DSP reports all of some [`capability`] bits.
+    pub fn supports(&self, bits: u32) -> bool {
+        self.capabilities & bits == bits
+    }
+}
 
 /// Audio pipeline manager.
 ///
@@ -27,14 +223,20 @@ impl AudioPipeline {
         }
     }
 
+    /// Start the audio pipeline with soundcard IQ input.
+    pub async fn start(&mut self) -> Result<(), JsValue> {
+        self.start_with_source(IqSource::Soundcard).await
+    }
+
     /// Start the audio pipeline.
     ///
     /// This will:
     /// 1. Create an AudioContext
     /// 2. Load the AudioWorklet processor
-    /// 3. Connect to audio input (microphone/line-in for IQ)
+    /// 3. Connect to audio input (microphone/line-in for IQ), unless
+    ///    IQ arrives over USB or from a file via [`AudioPipeline::push_iq`]
     /// 4. Start processing
-    pub async fn start(&mut self) -> Result<(), JsValue> {
+    pub async fn start_with_source(&mut self, source: IqSource) -> Result<(), JsValue> {
         // Create AudioContext
         let ctx = AudioContext::new()?;
 
@@ -52,6 +254,18 @@ impl AudioPipeline {
         // Create the AudioWorkletNode
         let node = AudioWorkletNode::new_with_options(&ctx, "sdr-dsp-processor", &options)?;
 
+        if source != IqSource::Soundcard {
+            // IQ is pushed from USB or a file; the worklet only needs an output
+            node.connect_with_audio_node(&ctx.destination())?;
+            let resume_promise = ctx.resume()?;
+            wasm_bindgen_futures::JsFuture::from(resume_promise).await?;
+
+            self.ctx = Some(ctx);
+            self.worklet_node = Some(node);
+            self.send_message(&source_message(source)?)?;
+            return Ok(());
+        }
+
         // Get audio input (stereo for I/Q)
         let navigator = web_sys::window()
             .ok_or("No window")?
@@ -103,6 +317,11 @@ impl AudioPipeline {
         self.ctx.is_some()
     }
 
+    /// Get the AudioContext sample rate, if running.
+    pub fn sample_rate(&self) -> Option<f32> {
+        self.ctx.as_ref().map(|ctx| ctx.sample_rate())
+    }
+
     /// Get the AudioWorkletNode for message passing.
     pub fn worklet_node(&self) -> Option<&AudioWorkletNode> {
         self.worklet_node.as_ref()
@@ -116,31 +335,217 @@ impl AudioPipeline {
         Ok(())
     }
 
-    /// Set the operating mode.
-    pub fn set_mode(&self, mode: u8) -> Result<(), JsValue> {
+    /// Apply a DSP settings object (the WASM `DspConfig`, camelCase).
+    ///
+    /// Fields left out keep their values; the worklet answers a rejected
+    /// object with an `error` message.
+    pub fn apply_config(&self, config: &js_sys::Object) -> Result<(), JsValue> {
         let msg = js_sys::Object::new();
-        js_sys::Reflect::set(&msg, &"type".into(), &"setMode".into())?;
-        js_sys::Reflect::set(&msg, &"mode".into(), &mode.into())?;
+        js_sys::Reflect::set(&msg, &"type".into(), &"applyConfig".into())?;
+        js_sys::Reflect::set(&msg, &"config".into(), config)?;
         self.send_message(&msg.into())
     }
 
+    /// Set the operating mode.
+    pub fn set_mode(&self, mode: u8) -> Result<(), JsValue> {
+        let config = js_sys::Object::new();
+        js_sys::Reflect::set(&config, &"mode".into(), &mode.into())?;
+        self.apply_config(&config)
+    }
+
     /// Set the frequency offset.
     pub fn set_frequency_offset(&self, offset_hz: f32) -> Result<(), JsValue> {
+        let config = js_sys::Object::new();
+        js_sys::Reflect::set(&config, &"frequencyOffset".into(), &offset_hz.into())?;
+        self.apply_config(&config)
+    }
+
+    /// Push interleaved I/Q samples received over USB.
+    pub fn push_iq(&self, samples: &[f32]) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"iqData".into())?;
+        js_sys::Reflect::set(&msg, &"data".into(), &js_sys::Float32Array::from(samples).into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the demodulated audio tap.
+    pub fn set_audio_tap(&self, enabled: bool) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setAudioTap".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the raw IQ tap.
+    pub fn set_iq_tap(&self, enabled: bool) -> Result<(), JsValue> {
         let msg = js_sys::Object::new();
-        js_sys::Reflect::set(&msg, &"type".into(), &"setFrequency".into())?;
-        js_sys::Reflect::set(&msg, &"frequency".into(), &offset_hz.into())?;
+        js_sys::Reflect::set(&msg, &"type".into(), &"setIqTap".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
         self.send_message(&msg.into())
     }
 
+    /// Set a DSP parameter.
+    pub fn set_parameter(&self, param: Parameter, value: f32) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setParameter".into())?;
+        js_sys::Reflect::set(&msg, &"param".into(), &(param as u8).into())?;
+        js_sys::Reflect::set(&msg, &"value".into(), &value.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Set the S-meter calibration.
+    pub fn set_smeter_calibration(&self, calibration: SmeterCalibration) -> Result<(), JsValue> {
+        self.set_parameter(Parameter::SmeterOffset, calibration.offset_db())?;
+        self.set_parameter(Parameter::SmeterSlope, calibration.slope())
+    }
+
+    /// Set the spectrum averaging and dBFS to dBm gain.
+    pub fn set_spectrum_calibration(&self, averaging: u8, gain_db: f32) -> Result<(), JsValue> {
+        let spectrum = js_sys::Object::new();
+        js_sys::Reflect::set(&spectrum, &"averaging".into(), &averaging.into())?;
+        js_sys::Reflect::set(&spectrum, &"gainDb".into(), &gain_db.into())?;
+        let config = js_sys::Object::new();
+        js_sys::Reflect::set(&config, &"spectrum".into(), &spectrum)?;
+        self.apply_config(&config)
+    }
+
+    /// Send a request to the PSK31 decoder bank.
+    pub fn send_psk_command(&self, command: PskCommand) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        match command {
+            PskCommand::Add(offset_hz) => {
+                js_sys::Reflect::set(&msg, &"type".into(), &"addPskDecoder".into())?;
+                js_sys::Reflect::set(&msg, &"offsetHz".into(), &offset_hz.into())?;
+            }
+            PskCommand::Remove(id) => {
+                js_sys::Reflect::set(&msg, &"type".into(), &"removePskDecoder".into())?;
+                js_sys::Reflect::set(&msg, &"id".into(), &id.into())?;
+            }
+            PskCommand::Clear => {
+                js_sys::Reflect::set(&msg, &"type".into(), &"clearPskDecoders".into())?;
+            }
+        }
+        self.send_message(&msg.into())
+    }
+
+    /// Configure AFC of the PSK31 decoders.
+    pub fn set_psk_afc(&self, enabled: bool, bandwidth_hz: f32, range_hz: f32) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setPskAfc".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        js_sys::Reflect::set(&msg, &"bandwidthHz".into(), &bandwidth_hz.into())?;
+        js_sys::Reflect::set(&msg, &"rangeHz".into(), &range_hz.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the SSTV image decoder.
+    pub fn set_sstv_enabled(&self, enabled: bool) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setSstv".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the APRS packet decoder.
+    pub fn set_aprs_enabled(&self, enabled: bool) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setAprs".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the JS8 frame decoder at an audio frequency.
+    pub fn set_js8(&self, enabled: bool, frequency_hz: f32) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setJs8".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        js_sys::Reflect::set(&msg, &"frequencyHz".into(), &frequency_hz.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the Feld Hell decoder at a tone frequency.
+    pub fn set_hell(&self, enabled: bool, frequency_hz: f32) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setHell".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        js_sys::Reflect::set(&msg, &"frequencyHz".into(), &frequency_hz.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Enable or disable the CW skimmer.
+    pub fn set_cw_skimmer_enabled(&self, enabled: bool) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setCwSkimmer".into())?;
+        js_sys::Reflect::set(&msg, &"enabled".into(), &enabled.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Mute or unmute the demodulated audio output (taps are unaffected).
+    pub fn set_muted(&self, muted: bool) -> Result<(), JsValue> {
+        let msg = js_sys::Object::new();
+        js_sys::Reflect::set(&msg, &"type".into(), &"setMuted".into())?;
+        js_sys::Reflect::set(&msg, &"muted".into(), &muted.into())?;
+        self.send_message(&msg.into())
+    }
+
+    /// Set the AGC timing, hang threshold and dual-rate detector.
+    pub fn set_agc(&self, config: AgcConfig) -> Result<(), JsValue> {
+        let agc = js_sys::Object::new();
+        js_sys::Reflect::set(&agc, &"attackMs".into(), &config.attack_ms.into())?;
+        js_sys::Reflect::set(&agc, &"decayMs".into(), &config.decay_ms.into())?;
+        js_sys::Reflect::set(&agc, &"hangMs".into(), &config.hang_ms.into())?;
+        js_sys::Reflect::set(&agc, &"hangThresholdDb".into(), &config.hang_threshold_db.into())?;
+        js_sys::Reflect::set(&agc, &"dualRate".into(), &config.dual_rate.into())?;
+        let settings = js_sys::Object::new();
+        js_sys::Reflect::set(&settings, &"agc".into(), &agc)?;
+        self.apply_config(&settings)
+    }
+
+    /// Set the noise reduction level (0 = off to [`MAX_NOISE_REDUCTION`]).
+    pub fn set_noise_reduction(&self, level: u8) -> Result<(), JsValue> {
+        let nr = js_sys::Object::new();
+        js_sys::Reflect::set(&nr, &"level".into(), &level.min(MAX_NOISE_REDUCTION).into())?;
+        let config = js_sys::Object::new();
+        js_sys::Reflect::set(&config, &"nr".into(), &nr)?;
+        self.apply_config(&config)
+    }
+
     /// Set filter bandwidth.
     pub fn set_bandwidth(&self, bandwidth_hz: f32) -> Result<(), JsValue> {
         let msg = js_sys::Object::new();
-        js_sys::Reflect::set(&msg, &"type".into(), &"setBandwidth".into())?;
+        js_sys::Reflect::set(&msg, &"type".into(), &"setFilter".into())?;
         js_sys::Reflect::set(&msg, &"bandwidth".into(), &bandwidth_hz.into())?;
         self.send_message(&msg.into())
     }
 }
 
+/// S-meter calibration of the band tuned (nominal outside the bands).
+fn smeter_calibration(ctx: &AppContext) -> SmeterCalibration {
+    HamBand::from_frequency(ctx.frequency.get())
+        .map(|b| ctx.smeter_calibration.with(|c| c[b.index()]))
+        .unwrap_or_default()
+}
+
+/// Spectrum averaging and dBFS to dBm gain of the band tuned.
+fn spectrum_calibration(ctx: &AppContext) -> (u8, f32) {
+    let band = HamBand::from_frequency(ctx.frequency.get()).map_or(usize::MAX, |b| b.index());
+    let gain_db = ctx.spectrum_calibration.with(|c| c.gain_db(band));
+    (ctx.spectrum_averaging.get(), gain_db)
+}
+
+/// Build the worklet message selecting the IQ source.
+fn source_message(source: IqSource) -> Result<JsValue, JsValue> {
+    let msg = js_sys::Object::new();
+    js_sys::Reflect::set(&msg, &"type".into(), &"setSource".into())?;
+    let name = match source {
+        IqSource::Soundcard => "soundcard",
+        IqSource::Usb => "usb",
+        IqSource::File => "file",
+    };
+    js_sys::Reflect::set(&msg, &"source".into(), &name.into())?;
+    Ok(msg.into())
+}
+
 impl Default for AudioPipeline {
     fn default() -> Self {
         Self::new()
@@ -154,7 +559,19 @@ pub fn create_audio_effect(app_ctx: AppContext) {
     // Clone for each effect
     let ctx_for_audio = app_ctx.clone();
     let ctx_for_mode = app_ctx.clone();
-    let ctx_for_bandwidth = app_ctx;
+    let ctx_for_bandwidth = app_ctx.clone();
+    let ctx_for_calibration = app_ctx.clone();
+    let ctx_for_tap = app_ctx.clone();
+    let ctx_for_iq_tap = app_ctx.clone();
+    let ctx_for_psk = app_ctx.clone();
+    let ctx_for_afc = app_ctx.clone();
+    let ctx_for_aprs = app_ctx.clone();
+    let ctx_for_js8 = app_ctx.clone();
+    let ctx_for_hell = app_ctx.clone();
+    let ctx_for_cw = app_ctx.clone();
+    let ctx_for_mute = app_ctx.clone();
+    let ctx_for_ready = app_ctx.clone();
+    let ctx_for_receive = app_ctx;
 
     // Effect to start/stop audio based on audio_running signal
     create_effect(move |_| {
@@ -165,8 +582,25 @@ pub fn create_audio_effect(app_ctx: AppContext) {
             // Start audio
             let ctx_inner = ctx.clone();
             spawn_local(async move {
+                let source = ctx_inner.iq_source.get_untracked();
+
+                // Open USB first, while the click's user activation is still valid
+                let usb = if source == IqSource::Usb {
+                    match UsbIqStream::connect().await {
+                        Ok(stream) => Some(stream),
+                        Err(e) => {
+                            web_sys::console::error_1(&format!("USB IQ connect failed: {:?}", e).into());
+                            ctx_inner.audio_running.set(false);
+                            return;
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                ctx_inner.dsp_api.set(None);
                 let mut new_pipeline = AudioPipeline::new();
-                match new_pipeline.start().await {
+                match new_pipeline.start_with_source(source).await {
                     Ok(()) => {
                         web_sys::console::log_1(&"Audio pipeline started".into());
                         // Set up message handler for spectrum data
@@ -180,7 +614,14 @@ pub fn create_audio_effect(app_ctx: AppContext) {
                                 onmessage.forget(); // Leak the closure (it lives for the pipeline lifetime)
                             }
                         }
+                        // The rest of the setup waits for the DSP's ready message
                         pipeline.set_value(new_pipeline);
+
+                        if let Some(stream) = usb {
+                            spawn_local(run_usb_stream(stream, ctx_inner.clone(), pipeline));
+                        } else if source == IqSource::File {
+                            spawn_local(run_file_stream(ctx_inner.clone(), pipeline));
+                        }
                     }
                     Err(e) => {
                         web_sys::console::error_1(&format!("Failed to start audio: {:?}", e).into());
@@ -190,6 +631,7 @@ pub fn create_audio_effect(app_ctx: AppContext) {
             });
         } else {
             // Stop audio
+            ctx.dsp_api.set(None);
             pipeline.update_value(|p| {
                 if p.is_running() {
                     p.stop();
@@ -199,12 +641,27 @@ pub fn create_audio_effect(app_ctx: AppContext) {
         }
     });
 
+    // Effect to send the whole setup once the worklet's DSP is ready
+    create_effect(move |_| {
+        let Some(api) = ctx_for_ready.dsp_api.get() else {
+            return;
+        };
+        pipeline.with_value(|p| {
+            if p.is_running() {
+                untrack(|| configure_pipeline(p, &ctx_for_ready, api));
+            }
+        });
+    });
+
     // Effect to update mode when it changes
     create_effect(move |_| {
         let mode = ctx_for_mode.mode.get();
         pipeline.with_value(|p| {
             if p.is_running() {
                 let _ = p.set_mode(mode.code());
+                if dsp_supports(&ctx_for_mode, capability::SSTV) {
+                    let _ = p.set_sstv_enabled(mode == RadioMode::Sstv);
+                }
             }
         });
     });
@@ -218,6 +675,335 @@ pub fn create_audio_effect(app_ctx: AppContext) {
             }
         });
     });
+
+    // Effect to apply the S-meter calibration of the band tuned
+    create_effect(move |_| {
+        let calibration = smeter_calibration(&ctx_for_calibration);
+        let (averaging, gain_db) = spectrum_calibration(&ctx_for_calibration);
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_calibration, capability::SMETER_DBM) {
+                let _ = p.set_smeter_calibration(calibration);
+            }
+            if p.is_running() && dsp_supports(&ctx_for_calibration, capability::SPECTRUM_DBM) {
+                let _ = p.set_spectrum_calibration(averaging, gain_db);
+            }
+        });
+    });
+
+    // Effect to stream audio only while a recorder needs it
+    create_effect(move |_| {
+        let enabled = audio_tap_wanted(&ctx_for_tap);
+        pipeline.with_value(|p| {
+            if p.is_running() {
+                let _ = p.set_audio_tap(enabled);
+            }
+        });
+    });
+
+    // Effect to stream raw IQ only while it is being recorded
+    create_effect(move |_| {
+        let enabled = iq_tap_wanted(&ctx_for_iq_tap);
+        pipeline.with_value(|p| {
+            if p.is_running() {
+                let _ = p.set_iq_tap(enabled);
+            }
+        });
+    });
+
+    // Effect to update PSK31 AFC settings
+    create_effect(move |_| {
+        let (enabled, bandwidth, range) = afc_settings(&ctx_for_afc);
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_afc, capability::AFC_CONTROL) {
+                let _ = p.set_psk_afc(enabled, bandwidth, range);
+            }
+        });
+    });
+
+    // Effect to run the APRS decoder only while it is wanted
+    create_effect(move |_| {
+        let enabled = ctx_for_aprs.aprs_enabled.get();
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_aprs, capability::APRS) {
+                let _ = p.set_aprs_enabled(enabled);
+            }
+        });
+    });
+
+    // Effect to run the JS8 decoder in JS8 mode, at the chosen frequency
+    create_effect(move |_| {
+        let (enabled, frequency) = js8_settings(&ctx_for_js8);
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_js8, capability::JS8) {
+                let _ = p.set_js8(enabled, frequency);
+            }
+        });
+    });
+
+    // Effect to run the Feld Hell decoder in Hell mode, at the chosen frequency
+    create_effect(move |_| {
+        let (enabled, frequency) = hell_settings(&ctx_for_hell);
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_hell, capability::HELL) {
+                let _ = p.set_hell(enabled, frequency);
+            }
+        });
+    });
+
+    // Effect to run the CW skimmer only while it is wanted
+    create_effect(move |_| {
+        let enabled = ctx_for_cw.cw_skimmer_enabled.get();
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_cw, capability::CW_SKIMMER) {
+                let _ = p.set_cw_skimmer_enabled(enabled);
+            }
+        });
+    });
+
+    // Effect to apply the AGC preset and noise reduction level
+    create_effect(move |_| {
+        let agc = ctx_for_receive.agc_speed.get().config();
+        let noise_reduction = ctx_for_receive.noise_reduction.get();
+        pipeline.with_value(|p| {
+            if p.is_running() {
+                let _ = p.set_agc(agc);
+            }
+            if p.is_running() && dsp_supports(&ctx_for_receive, capability::NOISE_REDUCTION) {
+                let _ = p.set_noise_reduction(noise_reduction);
+            }
+        });
+    });
+
+    // Effect to mute the speaker output
+    create_effect(move |_| {
+        let muted = ctx_for_mute.muted.get();
+        pipeline.with_value(|p| {
+            if p.is_running() {
+                let _ = p.set_muted(muted);
+            }
+        });
+    });
+
+    // Effect to forward PSK31 decoder bank requests
+    create_effect(move |_| {
+        let Some(command) = ctx_for_psk.psk_command.get() else {
+            return;
+        };
+        pipeline.with_value(|p| {
+            if p.is_running() && dsp_supports(&ctx_for_psk, capability::PSK31_BANK) {
+                let _ = p.send_psk_command(command);
+            }
+        });
+    });
+}
+
+/// Check the worklet's DSP has reported ready with some [`capability`] bits.
+fn dsp_supports(ctx: &AppContext, bits: u32) -> bool {
+    ctx.dsp_api.get_untracked().is_some_and(|api| api.supports(bits))
+}
+
+/// Send the whole receive setup to a worklet whose DSP has just reported ready.
+///
+/// Settings sent before then are dropped by the worklet. Optional
+/// decoders and calibrations are only sent if the DSP reports them.
+fn configure_pipeline(p: &AudioPipeline, ctx: &AppContext, api: DspApi) {
+    let mode = ctx.mode.get();
+    let _ = p.set_muted(ctx.muted.get());
+    let _ = p.set_mode(mode.code());
+    let _ = p.set_bandwidth(ctx.bandwidth.get());
+    let _ = p.set_agc(ctx.agc_speed.get().config());
+    let _ = p.set_audio_tap(audio_tap_wanted(ctx));
+    let _ = p.set_iq_tap(iq_tap_wanted(ctx));
+    if api.supports(capability::NOISE_REDUCTION) {
+        let _ = p.set_noise_reduction(ctx.noise_reduction.get());
+    }
+    if api.supports(capability::SMETER_DBM) {
+        let _ = p.set_smeter_calibration(smeter_calibration(ctx));
+    }
+    if api.supports(capability::SPECTRUM_DBM) {
+        let (averaging, gain_db) = spectrum_calibration(ctx);
+        let _ = p.set_spectrum_calibration(averaging, gain_db);
+    }
+    if api.supports(capability::SSTV) {
+        let _ = p.set_sstv_enabled(mode == RadioMode::Sstv);
+    }
+    if api.supports(capability::APRS) {
+        let _ = p.set_aprs_enabled(ctx.aprs_enabled.get());
+    }
+    if api.supports(capability::JS8) {
+        let (enabled, frequency) = js8_settings(ctx);
+        let _ = p.set_js8(enabled, frequency);
+    }
+    if api.supports(capability::HELL) {
+        let (enabled, frequency) = hell_settings(ctx);
+        let _ = p.set_hell(enabled, frequency);
+    }
+    if api.supports(capability::CW_SKIMMER) {
+        let _ = p.set_cw_skimmer_enabled(ctx.cw_skimmer_enabled.get());
+    }
+    if api.supports(capability::AFC_CONTROL) {
+        let (enabled, bandwidth, range) = afc_settings(ctx);
+        let _ = p.set_psk_afc(enabled, bandwidth, range);
+    }
+    if api.supports(capability::PSK31_BANK) {
+        // Restart the PSK31 channels; the worklet assigns new ids
+        let offsets: Vec<f32> = ctx
+            .psk_channels
+            .try_update(|list| list.drain(..).map(|c| c.offset_hz).collect())
+            .unwrap_or_default();
+        for offset in offsets {
+            let _ = p.send_psk_command(PskCommand::Add(offset));
+        }
+    }
+}
+
+/// AFC enable, loop bandwidth and pull-in range for the PSK31 decoders.
+fn afc_settings(ctx: &AppContext) -> (bool, f32, f32) {
+    (ctx.afc_enabled.get(), ctx.afc_bandwidth.get(), ctx.afc_range.get())
+}
+
+/// JS8 decoder enable and audio frequency.
+fn js8_settings(ctx: &AppContext) -> (bool, f32) {
+    (ctx.mode.get() == RadioMode::Js8, ctx.js8_frequency.get())
+}
+
+/// Feld Hell decoder enable and tone frequency.
+fn hell_settings(ctx: &AppContext) -> (bool, f32) {
+    (ctx.mode.get() == RadioMode::Hell, ctx.hell_frequency.get())
+}
+
+/// Check if the activity recorder or a WAV audio recording needs the audio tap.
+fn audio_tap_wanted(ctx: &AppContext) -> bool {
+    ctx.recorder_enabled.get()
+        || (ctx.wav_state.get() != RecordingState::Idle
+            && ctx.wav_source.get() == RecordSource::Audio)
+}
+
+/// Check if a WAV IQ recording needs the IQ tap.
+fn iq_tap_wanted(ctx: &AppContext) -> bool {
+    ctx.wav_state.get() != RecordingState::Idle && ctx.wav_source.get() == RecordSource::Iq
+}
+
+/// Create an effect that records WAV files from the audio or IQ tap.
+///
+/// Setting `wav_state` to `Recording` from idle opens a recording;
+/// returning it to `Idle` (or reaching the size cap) downloads the file.
+pub fn create_wav_recorder_effect(ctx: AppContext) {
+    let recorder = store_value(None::<WavRecorder>);
+
+    // Open and close recordings
+    create_effect(move |_| {
+        let state = ctx.wav_state.get();
+        let open = recorder.with_value(Option::is_some);
+        if state != RecordingState::Idle && !open {
+            let source = ctx.wav_source.get_untracked();
+            let format = source.wav_format(ctx.audio_sample_rate.get_untracked());
+            let max_bytes = ctx.wav_max_mb.get_untracked() as usize * 1_000_000;
+            recorder.set_value(Some(WavRecorder::new(format, max_bytes)));
+            ctx.wav_bytes.set(0);
+        } else if state == RecordingState::Idle && open {
+            if let Some(wav) = recorder.try_update_value(Option::take).flatten() {
+                let filename = format!(
+                    "sdr-{}-{}-{}.wav",
+                    ctx.frequency.get_untracked(),
+                    ctx.wav_source.get_untracked().name().to_lowercase(),
+                    js_sys::Date::now() as u64
+                );
+                if let Err(e) = download_bytes(&filename, &wav.finish(), "audio/wav") {
+                    web_sys::console::error_1(&format!("WAV download error: {:?}", e).into());
+                }
+            }
+        }
+    });
+
+    // Capture samples from the tap of the selected source
+    create_effect(move |_| {
+        let recording = ctx.wav_state.get_untracked() == RecordingState::Recording;
+        let push = |samples: &Vec<f32>| {
+            if !recording {
+                return None;
+            }
+            recorder
+                .try_update_value(|r| r.as_mut().map(|wav| (wav.push(samples), wav.data_len())))
+                .flatten()
+        };
+        let pushed = match ctx.wav_source.get() {
+            RecordSource::Audio => ctx.audio_tap.with(push),
+            RecordSource::Iq => ctx.iq_tap.with(push),
+        };
+        if let Some((room_left, len)) = pushed {
+            ctx.wav_bytes.set(len);
+            if !room_left {
+                ctx.wav_state.set(RecordingState::Idle);
+            }
+        }
+    });
+}
+
+/// Pump IQ frames from USB into the worklet until audio is stopped.
+async fn run_usb_stream(stream: UsbIqStream, ctx: AppContext, pipeline: StoredValue<AudioPipeline>) {
+    let mut parser = IqFrameParser::new();
+    ctx.usb_streaming.set(true);
+
+    while ctx.audio_running.get_untracked() {
+        match stream.read().await {
+            Ok(bytes) => {
+                parser.push(&bytes);
+                while let Some(frame) = parser.next_frame() {
+                    if frame.flags & FLAG_OVERFLOW != 0 {
+                        web_sys::console::warn_1(&"USB IQ: ADC overflow".into());
+                    }
+                    pipeline.with_value(|p| {
+                        let _ = p.push_iq(&frame.samples);
+                    });
+                }
+                ctx.usb_dropped_frames.set(parser.dropped_frames());
+            }
+            Err(e) => {
+                web_sys::console::error_1(&format!("USB IQ read error: {:?}", e).into());
+                ctx.audio_running.set(false);
+                break;
+            }
+        }
+    }
+
+    let _ = stream.close().await;
+    ctx.usb_streaming.set(false);
+}
+
+/// Play the loaded IQ file into the worklet in real time.
+///
+/// Stops audio at the end of the file unless looping is enabled.
+async fn run_file_stream(ctx: AppContext, pipeline: StoredValue<AudioPipeline>) {
+    let rate = pipeline
+        .with_value(AudioPipeline::sample_rate)
+        .unwrap_or_else(|| ctx.audio_sample_rate.get_untracked());
+    let Some(samples) = ctx.iq_file.with_untracked(|f| f.as_ref().map(|c| c.resampled(rate))) else {
+        web_sys::console::error_1(&"IQ file playback: no file loaded".into());
+        ctx.audio_running.set(false);
+        return;
+    };
+
+    let block = (rate * PLAYBACK_INTERVAL_MS as f32 / 1000.0) as usize * 2;
+    let mut pos = 0;
+    ctx.iq_file_position.set(0.0);
+
+    while ctx.audio_running.get_untracked() {
+        if pos >= samples.len() {
+            if !ctx.iq_file_loop.get_untracked() {
+                ctx.audio_running.set(false);
+                break;
+            }
+            pos = 0;
+        }
+        let end = (pos + block).min(samples.len());
+        pipeline.with_value(|p| {
+            let _ = p.push_iq(&samples[pos..end]);
+        });
+        pos = end;
+        ctx.iq_file_position.set(pos as f32 / samples.len() as f32);
+        sleep_ms(PLAYBACK_INTERVAL_MS).await;
+    }
 }
 
 /// Handle messages from the AudioWorklet.
@@ -230,6 +1016,27 @@ fn handle_worklet_message(ctx: &AppContext, ev: web_sys::MessageEvent) {
             let type_str = msg_type.as_string().unwrap_or_default();
 
             match type_str.as_str() {
+                "ready" => {
+                    // DSP loaded: check it speaks our API before configuring it
+                    let number = |name: &str| {
+                        js_sys::Reflect::get(&obj, &name.into())
+                            .ok()
+                            .and_then(|v| v.as_f64())
+                            .map_or(0, |v| v as u32)
+                    };
+                    let api = DspApi {
+                        version: number("apiVersion"),
+                        capabilities: number("capabilities"),
+                        extended_capabilities: number("extendedCapabilities"),
+                    };
+                    if api.is_compatible() {
+                        ctx.dsp_api.set(Some(api));
+                    } else {
+                        web_sys::console::error_1(
+                            &format!("Unsupported DSP API version {:#x}", api.version).into(),
+                        );
+                    }
+                }
                 "spectrum" => {
                     // Spectrum data from worklet
                     if let Ok(spectrum_val) = js_sys::Reflect::get(&obj, &"data".into()) {
@@ -247,17 +1054,202 @@ fn handle_worklet_message(ctx: &AppContext, ev: web_sys::MessageEvent) {
                             ctx.smeter.set(v as f32);
                         }
                     }
+                    if let Ok(val) = js_sys::Reflect::get(&obj, &"dbm".into()) {
+                        if let Some(dbm) = val.as_f64() {
+                            ctx.smeter_dbm.set(dbm as f32);
+                        }
+                    }
+                }
+                "audio" => {
+                    // Demodulated audio tap for the recorder
+                    if let Ok(audio_val) = js_sys::Reflect::get(&obj, &"data".into()) {
+                        if let Ok(array) = audio_val.dyn_into::<js_sys::Float32Array>() {
+                            ctx.audio_tap.set(array.to_vec());
+                        }
+                    }
+                    if let Ok(rate) = js_sys::Reflect::get(&obj, &"sampleRate".into()) {
+                        if let Some(rate) = rate.as_f64() {
+                            ctx.audio_sample_rate.set(rate as f32);
+                        }
+                    }
+                }
+                "iq" => {
+                    // Raw IQ tap for WAV recording
+                    if let Ok(iq_val) = js_sys::Reflect::get(&obj, &"data".into()) {
+                        if let Ok(array) = iq_val.dyn_into::<js_sys::Float32Array>() {
+                            ctx.iq_tap.set(array.to_vec());
+                        }
+                    }
                 }
                 "decoded" => {
-                    // Decoded text from digital mode
+                    // Decoded text from digital mode, or from a PSK31 bank channel
+                    let channel = js_sys::Reflect::get(&obj, &"channel".into())
+                        .ok()
+                        .and_then(|c| c.as_f64());
                     if let Ok(text) = js_sys::Reflect::get(&obj, &"text".into()) {
                         if let Some(s) = text.as_string() {
-                            ctx.rx_text.update(|t| t.push_str(&s));
+                            match channel {
+                                Some(id) => psk_channels::append_text(ctx, id as u32, &s),
+                                None => ctx.rx_text.update(|t| t.push_str(&s)),
+                            }
+                        }
+                    }
+                }
+                "pskDecoder" => {
+                    // Decoder bank reply to addPskDecoder (id -1 when full)
+                    let number = |name: &str| {
+                        js_sys::Reflect::get(&obj, &name.into())
+                            .ok()
+                            .and_then(|v| v.as_f64())
+                    };
+                    if let (Some(id), Some(offset)) = (number("id"), number("offsetHz")) {
+                        psk_channels::channel_added(ctx, id as i32, offset as f32);
+                    }
+                }
+                "pskQuality" => {
+                    // Periodic quality, AFC offset and lock of a PSK31 bank channel
+                    // (NaN if unknown)
+                    let number = |name: &str| {
+                        js_sys::Reflect::get(&obj, &name.into())
+                            .ok()
+                            .and_then(|v| v.as_f64())
+                            .filter(|v| v.is_finite())
+                            .map(|v| v as f32)
+                    };
+                    let Some(id) = number("channel").map(|id| id as u32) else {
+                        return;
+                    };
+                    if let (Some(snr_db), Some(evm_percent)) = (number("snr"), number("evm")) {
+                        let quality = SignalQuality {
+                            snr_db,
+                            imd_db: number("imd"),
+                            evm_percent,
+                        };
+                        psk_channels::set_quality(ctx, id, quality);
+                    }
+                    let locked = js_sys::Reflect::get(&obj, &"locked".into())
+                        .ok()
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+                    psk_channels::set_tracking(ctx, id, number("offsetHz"), locked);
+                }
+                "aprsFrame" => {
+                    // AX.25 frame (without FCS) from the APRS decoder
+                    if let Ok(data) = js_sys::Reflect::get(&obj, &"data".into()) {
+                        if let Ok(array) = data.dyn_into::<js_sys::Uint8Array>() {
+                            aprs::frame_received(ctx, &array.to_vec());
                         }
                     }
                 }
+                "js8Frame" => {
+                    // Packed frame word from the JS8 decoder
+                    if let Ok(data) = js_sys::Reflect::get(&obj, &"data".into()) {
+                        if let Ok(array) = data.dyn_into::<js_sys::Uint8Array>() {
+                            js8::frame_received(ctx, &array.to_vec());
+                        }
+                    }
+                }
+                "hellColumns" => {
+                    // Feld Hell pixel columns, back to back, top pixel first
+                    if let Ok(data) = js_sys::Reflect::get(&obj, &"pixels".into()) {
+                        if let Ok(array) = data.dyn_into::<js_sys::Uint8Array>() {
+                            hell::columns_received(ctx, array.to_vec());
+                        }
+                    }
+                }
+                "cwDecoded" => {
+                    // Characters copied from one CW skimmer signal
+                    let id = js_sys::Reflect::get(&obj, &"signal".into())
+                        .ok()
+                        .and_then(|v| v.as_f64());
+                    let text = js_sys::Reflect::get(&obj, &"text".into())
+                        .ok()
+                        .and_then(|v| v.as_string());
+                    if let (Some(id), Some(text)) = (id, text) {
+                        cw_skimmer::text_received(ctx, id as u32, &text);
+                    }
+                }
+                "cwSignals" => {
+                    // Signals tracked by the CW skimmer
+                    let Ok(list) = js_sys::Reflect::get(&obj, &"signals".into()) else {
+                        return;
+                    };
+                    let Ok(list) = list.dyn_into::<js_sys::Array>() else {
+                        return;
+                    };
+                    let signals = list
+                        .iter()
+                        .filter_map(|signal| {
+                            let number = |name: &str| {
+                                js_sys::Reflect::get(&signal, &name.into())
+                                    .ok()
+                                    .and_then(|v| v.as_f64())
+                            };
+                            Some(CwSpot {
+                                id: number("id")? as u32,
+                                frequency_hz: number("frequencyHz")? as f32,
+                                wpm: number("wpm")? as f32,
+                                snr_db: number("snr")? as f32,
+                                text: String::new(),
+                            })
+                        })
+                        .collect();
+                    cw_skimmer::signals_received(ctx, signals);
+                }
+                "sstvLine" => {
+                    // Finished SSTV scanline (RGB) with the VIS code of its image
+                    let number = |name: &str| {
+                        js_sys::Reflect::get(&obj, &name.into())
+                            .ok()
+                            .and_then(|v| v.as_f64())
+                    };
+                    let Some(line) = number("line") else {
+                        return;
+                    };
+                    let mode = number("mode")
+                        .and_then(|code| u8::try_from(code as i32).ok())
+                        .and_then(SstvMode::from_vis);
+                    if let Ok(pixels) = js_sys::Reflect::get(&obj, &"pixels".into()) {
+                        if let Ok(array) = pixels.dyn_into::<js_sys::Uint8Array>() {
+                            let line = SstvLine {
+                                line: line as u16,
+                                mode,
+                                rgb: array.to_vec(),
+                            };
+                            sstv::line_received(ctx, line);
+                        }
+                    }
+                }
+                "error" => {
+                    // WASM init failure or a rejected settings object
+                    if let Ok(message) = js_sys::Reflect::get(&obj, &"message".into()) {
+                        web_sys::console::error_1(&message);
+                    }
+                }
                 _ => {}
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dsp_api_compatibility() {
+        let version = u32::from(API_VERSION_MAJOR) << 16 | 3;
+        let api = DspApi {
+            version,
+            capabilities: capability::CONFIG_OBJECTS | capability::APRS,
+            extended_capabilities: 0,
+        };
+        assert!(api.is_compatible());
+        assert!(api.supports(capability::APRS));
+        assert!(!api.supports(capability::APRS | capability::JS8));
+
+        // Another major version, or no settings objects, is refused
+        assert!(!DspApi { version: version + (1 << 16), ..api }.is_compatible());
+        assert!(!DspApi { capabilities: capability::APRS, ..api }.is_compatible());
+    }
+}
@@ -0,0 +1,378 @@
+//! Frequency bookmarks.
+//!
+//! Named frequencies with mode, tags and notes, kept in localStorage so
+//! they survive a page reload. Bookmarks inside the displayed span are
+//! drawn as markers over the waterfall, and the sidebar lists them with
+//! a search box.
+
+use leptos::*;
+use sdr_dsp_core::spectrum::SpectrumView;
+
+use crate::components::{HamBand, RadioMode};
+use crate::panadapter::DEMOD_OFFSET_HZ;
+use crate::state::AppContext;
+
+/// localStorage key holding the bookmarks.
+const STORAGE_KEY: &str = "sdr-bookmarks";
+
+/// Header line of the stored format.
+const STORAGE_HEADER: &str = "sdr-bookmarks 1";
+
+/// A named frequency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    /// Dial frequency in Hz
+    pub frequency: u64,
+    /// Operating mode
+    pub mode: RadioMode,
+    /// Display name
+    pub name: String,
+    /// Free-form tags (e.g. "net", "beacon")
+    pub tags: Vec<String>,
+    /// Notes
+    pub notes: String,
+}
+
+impl Bookmark {
+    /// Get the band containing the bookmark.
+    pub fn band(&self) -> Option<HamBand> {
+        HamBand::from_frequency(self.frequency)
+    }
+
+    /// Check if the name, band, tags or notes contain `query` (case-insensitive).
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let band = self.band().map_or("", |b| b.name());
+        [self.name.as_str(), self.notes.as_str(), band, self.mode.name()]
+            .into_iter()
+            .chain(self.tags.iter().map(String::as_str))
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+
+    /// Encode as one tab-separated line.
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.frequency,
+            self.mode.name(),
+            clean_field(&self.name),
+            clean_field(&self.tags.join(",")),
+            clean_field(&self.notes)
+        )
+    }
+
+    /// Decode a line written by [`Bookmark::to_line`].
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let frequency = fields.next()?.parse().ok()?;
+        let mode = RadioMode::from_name(fields.next()?)?;
+        let name = fields.next()?.to_string();
+        let tags = parse_tags(fields.next().unwrap_or(""));
+        let notes = fields.next().unwrap_or("").to_string();
+        Some(Self {
+            frequency,
+            mode,
+            name,
+            tags,
+            notes,
+        })
+    }
+}
+
+/// Replace the separators of the stored format with spaces.
+fn clean_field(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Split a comma-separated tag list.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Encode bookmarks for storage.
+pub fn encode_bookmarks(bookmarks: &[Bookmark]) -> String {
+    let mut text = String::from(STORAGE_HEADER);
+    for bookmark in bookmarks {
+        text.push('\n');
+        text.push_str(&bookmark.to_line());
+    }
+    text
+}
+
+/// Decode stored bookmarks, skipping lines that do not parse.
+pub fn decode_bookmarks(text: &str) -> Vec<Bookmark> {
+    let mut lines = text.lines();
+    if lines.next() != Some(STORAGE_HEADER) {
+        return Vec::new();
+    }
+    lines.filter_map(Bookmark::from_line).collect()
+}
+
+/// Get the page's localStorage.
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Load bookmarks from localStorage.
+pub fn load_bookmarks() -> Vec<Bookmark> {
+    storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .map(|text| decode_bookmarks(&text))
+        .unwrap_or_default()
+}
+
+/// Save bookmarks to localStorage.
+pub fn save_bookmarks(bookmarks: &[Bookmark]) {
+    if let Some(storage) = storage() {
+        if let Err(e) = storage.set_item(STORAGE_KEY, &encode_bookmarks(bookmarks)) {
+            web_sys::console::error_1(&format!("Bookmark save error: {:?}", e).into());
+        }
+    }
+}
+
+/// Tune to a bookmark.
+pub fn go_to_bookmark(ctx: &AppContext, bookmark: &Bookmark) {
+    ctx.frequency.set(bookmark.frequency);
+    ctx.mode.set(bookmark.mode);
+}
+
+/// Spectrum offset of a frequency while tuned to `dial_hz`.
+fn spectrum_offset(frequency: u64, dial_hz: u64) -> f32 {
+    (frequency as i64 - dial_hz as i64) as f32 + DEMOD_OFFSET_HZ
+}
+
+/// Create the effect that loads bookmarks and saves every change.
+pub fn create_bookmark_effect(ctx: AppContext) {
+    ctx.bookmarks.set(load_bookmarks());
+    create_effect(move |previous: Option<()>| {
+        ctx.bookmarks.with(|list| {
+            // Skip the run that only reflects what was just loaded
+            if previous.is_some() {
+                save_bookmarks(list);
+            }
+        });
+    });
+}
+
+/// Bookmark markers drawn over the waterfall.
+#[component]
+pub fn BookmarkMarkers(ctx: AppContext) -> impl IntoView {
+    let markers = move || {
+        let view: SpectrumView = ctx.spectrum_view.get();
+        let dial = ctx.frequency.get();
+        ctx.bookmarks
+            .get()
+            .into_iter()
+            .filter_map(|bookmark| {
+                let x = view.position(spectrum_offset(bookmark.frequency, dial))?;
+                Some((x, bookmark))
+            })
+            .map(|(x, bookmark)| {
+                let style = format!(
+                    "position: absolute; top: 0; left: {:.2}%; pointer-events: auto;",
+                    x * 100.0
+                );
+                let name = bookmark.name.clone();
+                view! {
+                    <span
+                        class="bookmark-marker"
+                        style=style
+                        title=bookmark.notes.clone()
+                        on:click=move |_| go_to_bookmark(&ctx, &bookmark)
+                    >
+                        {name}
+                    </span>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="bookmark-markers" style="position: absolute; inset: 0; pointer-events: none;">
+            {markers}
+        </div>
+    }
+}
+
+/// Bookmark sidebar with search and an add form.
+#[component]
+pub fn BookmarkPanel(ctx: AppContext) -> impl IntoView {
+    let bookmarks = ctx.bookmarks;
+    let query = create_rw_signal(String::new());
+    let name = create_rw_signal(String::new());
+    let tags = create_rw_signal(String::new());
+    let notes = create_rw_signal(String::new());
+
+    let add = move |_| {
+        let label = name.get_untracked().trim().to_string();
+        if label.is_empty() {
+            return;
+        }
+        let bookmark = Bookmark {
+            frequency: ctx.frequency.get_untracked(),
+            mode: ctx.mode.get_untracked(),
+            name: label,
+            tags: parse_tags(&tags.get_untracked()),
+            notes: notes.get_untracked(),
+        };
+        bookmarks.update(|list| {
+            list.push(bookmark);
+            list.sort_by_key(|b| b.frequency);
+        });
+        name.set(String::new());
+        tags.set(String::new());
+        notes.set(String::new());
+    };
+
+    let delete = move |index: usize| {
+        bookmarks.update(|list| {
+            if index < list.len() {
+                list.remove(index);
+            }
+        });
+    };
+
+    view! {
+        <div class="bookmark-panel">
+            <h3>"Bookmarks"</h3>
+            <input
+                type="search"
+                class="bookmark-search"
+                placeholder="Search"
+                prop:value=move || query.get()
+                on:input=move |ev| query.set(event_target_value(&ev))
+            />
+            <ul class="bookmark-list">
+                {move || bookmarks.get()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, b)| query.with(|q| b.matches(q)))
+                    .map(|(index, bookmark)| {
+                        let label = format!(
+                            "{:.3} MHz {} {}",
+                            bookmark.frequency as f64 / 1_000_000.0,
+                            bookmark.mode.name(),
+                            bookmark.band().map_or("", |b| b.name())
+                        );
+                        let name = bookmark.name.clone();
+                        let tags = bookmark.tags.join(", ");
+                        let notes = bookmark.notes.clone();
+                        view! {
+                            <li class="bookmark">
+                                <button
+                                    class="bookmark-go"
+                                    on:click=move |_| go_to_bookmark(&ctx, &bookmark)
+                                >
+                                    {name}
+                                </button>
+                                <span class="bookmark-label">{label}</span>
+                                <span class="bookmark-tags">{tags}</span>
+                                <span class="bookmark-notes">{notes}</span>
+                                <button on:click=move |_| delete(index)>"Delete"</button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+            <div class="bookmark-add">
+                <input
+                    type="text"
+                    placeholder="Name"
+                    prop:value=move || name.get()
+                    on:input=move |ev| name.set(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    placeholder="Tags (comma separated)"
+                    prop:value=move || tags.get()
+                    on:input=move |ev| tags.set(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    placeholder="Notes"
+                    prop:value=move || notes.get()
+                    on:input=move |ev| notes.set(event_target_value(&ev))
+                />
+                <button on:click=add>"Add Current"</button>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark() -> Bookmark {
+        Bookmark {
+            frequency: 7_074_000,
+            mode: RadioMode::Usb,
+            name: "FT8".into(),
+            tags: vec!["digital".into(), "daily".into()],
+            notes: "Busy evenings".into(),
+        }
+    }
+
+    #[test]
+    fn test_bookmarks_round_trip() {
+        let plain = Bookmark {
+            frequency: 3_560_000,
+            mode: RadioMode::Cw,
+            name: "QRP".into(),
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let list = vec![bookmark(), plain];
+        assert_eq!(decode_bookmarks(&encode_bookmarks(&list)), list);
+    }
+
+    #[test]
+    fn test_bookmark_separators_cleaned() {
+        let mut odd = bookmark();
+        odd.name = "Net\tcontrol".into();
+        odd.notes = "line one\nline two".into();
+        let decoded = decode_bookmarks(&encode_bookmarks(&[odd]));
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].name, "Net control");
+        assert_eq!(decoded[0].notes, "line one line two");
+    }
+
+    #[test]
+    fn test_decode_bookmarks_skips_bad_lines() {
+        let text = format!(
+            "{}\nabc\tUSB\tBad\n14074000\tQAM\tBad\n14074000\tUSB\tGood",
+            STORAGE_HEADER
+        );
+        let decoded = decode_bookmarks(&text);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].name, "Good");
+        assert!(decoded[0].tags.is_empty());
+
+        assert!(decode_bookmarks("sdr-bookmarks 2\n14074000\tUSB\tGood").is_empty());
+    }
+
+    #[test]
+    fn test_bookmark_matches() {
+        let b = bookmark();
+        assert!(b.matches(""));
+        assert!(b.matches("ft8"));
+        assert!(b.matches("40M"));
+        assert!(b.matches("usb"));
+        assert!(b.matches("Daily"));
+        assert!(b.matches("evenings"));
+        assert!(!b.matches("beacon"));
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags(" net, ,beacon ,"), vec!["net", "beacon"]);
+        assert!(parse_tags("").is_empty());
+    }
+}
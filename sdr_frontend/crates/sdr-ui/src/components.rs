@@ -1,5 +1,7 @@
 //! UI components for SDR frontend.
 
+pub mod band_scope;
+pub mod band_selector;
 pub mod frequency_display;
 pub mod mode_selector;
 pub mod rx_text;
@@ -7,7 +9,11 @@ pub mod s_meter;
 pub mod tx_input;
 pub mod waterfall;
 
-pub use frequency_display::FrequencyDisplay;
+pub use band_scope::{BandScope, BAND_SCOPE_HEIGHT};
+pub use band_selector::{BandSelector, HamBand, NUM_BANDS};
+pub use frequency_display::{
+    FrequencyDisplay, DIAL_TOUCH_STEP_PX, MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ,
+};
 pub use mode_selector::{ModeSelector, RadioMode};
 pub use rx_text::RxTextDisplay;
 pub use s_meter::SMeterDisplay;
@@ -0,0 +1,132 @@
+//! Band Scope Component.
+//!
+//! Spectrum line display drawn above the waterfall, sharing its zoom and
+//! pan. The receive filter passband is shaded and the BFO (demodulation
+//! offset) marked, so signals can be lined up before tuning. Colors
+//! follow the [`Theme`].
+
+use leptos::*;
+use sdr_dsp_core::spectrum::{DisplayRange, SpectrumView};
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use super::{RadioMode, WATERFALL_WIDTH};
+use crate::theme::{Theme, ThemeColors};
+
+/// Band scope height in pixels.
+pub const BAND_SCOPE_HEIGHT: usize = 128;
+
+/// Band scope (spectrum line) component.
+#[component]
+pub fn BandScope(
+    /// Width of the canvas in pixels
+    #[prop(default = WATERFALL_WIDTH)]
+    width: usize,
+    /// Height of the canvas in pixels
+    #[prop(default = BAND_SCOPE_HEIGHT)]
+    height: usize,
+    /// Signal providing spectrum data (Vec<f32> of dBm values)
+    spectrum: ReadSignal<Vec<f32>>,
+    /// Level span mapped onto the vertical axis
+    range: ReadSignal<DisplayRange>,
+    /// Zoomed and panned window onto the spectrum
+    view: ReadSignal<SpectrumView>,
+    /// Current mode
+    mode: ReadSignal<RadioMode>,
+    /// Receive filter bandwidth in Hz
+    bandwidth: ReadSignal<f32>,
+    /// Spectrum offset demodulated by the DSP in Hz
+    bfo_hz: f32,
+    /// Color theme
+    theme: ReadSignal<Theme>,
+) -> impl IntoView {
+    let canvas_ref = create_node_ref::<leptos::html::Canvas>();
+
+    // Redraw on new spectrum frames and whenever the passband moves
+    create_effect(move |_| {
+        let Some(canvas) = canvas_ref.get() else {
+            return;
+        };
+        let canvas_el: &HtmlCanvasElement = &canvas;
+        canvas_el.set_width(width as u32);
+        canvas_el.set_height(height as u32);
+        let Some(context) = context_2d(canvas_el) else {
+            return;
+        };
+
+        let view = view.get();
+        let range = range.get();
+        let passband = mode.get().passband(bandwidth.get(), bfo_hz);
+        let colors = theme.get().colors();
+        let mut trace = vec![0.0; width];
+        spectrum.with(|s| view.resample(s, &mut trace));
+
+        let (w, h) = (width as f64, height as f64);
+        draw(&context, w, h, &colors, &view, &range, passband, bfo_hz, &trace);
+    });
+
+    view! {
+        <canvas
+            node_ref=canvas_ref
+            class="band-scope-canvas"
+            style="display: block; max-width: 100%; height: auto;"
+        />
+    }
+}
+
+/// Get the 2D drawing context of a canvas.
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+}
+
+/// Draw one band scope frame.
+#[allow(clippy::too_many_arguments)]
+fn draw(
+    context: &CanvasRenderingContext2d,
+    width: f64,
+    height: f64,
+    colors: &ThemeColors,
+    view: &SpectrumView,
+    range: &DisplayRange,
+    passband: (f32, f32),
+    bfo_hz: f32,
+    trace: &[f32],
+) {
+    context.set_fill_style_str(colors.scope_background);
+    context.fill_rect(0.0, 0.0, width, height);
+
+    // Passband, clipped to the visible window
+    let start = view.start_hz();
+    let visible = view.visible_span_hz();
+    let left = ((passband.0 - start) / visible).clamp(0.0, 1.0) as f64 * width;
+    let right = ((passband.1 - start) / visible).clamp(0.0, 1.0) as f64 * width;
+    if right > left {
+        context.set_fill_style_str(colors.passband);
+        context.fill_rect(left, 0.0, right - left, height);
+    }
+
+    if let Some(x) = view.position(bfo_hz) {
+        let x = x as f64 * width;
+        context.set_stroke_style_str(colors.bfo);
+        context.begin_path();
+        context.move_to(x, 0.0);
+        context.line_to(x, height);
+        context.stroke();
+    }
+
+    context.set_stroke_style_str(colors.trace);
+    context.begin_path();
+    for (i, &db) in trace.iter().enumerate() {
+        let y = (1.0 - range.normalize(db) as f64) * height;
+        if i == 0 {
+            context.move_to(i as f64, y);
+        } else {
+            context.line_to(i as f64, y);
+        }
+    }
+    context.stroke();
+}
@@ -0,0 +1,154 @@
+//! Band Selector Component.
+//!
+//! Button group for jumping between amateur bands, with a condition
+//! badge on each band showing its current score.
+
+use leptos::*;
+use sdr_dsp_core::conditions::{BandConditions, Condition};
+
+/// Number of bands in the selector.
+pub const NUM_BANDS: usize = 6;
+
+/// Amateur bands covered by the radio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HamBand {
+    /// 80 meters
+    M80,
+    /// 40 meters
+    M40,
+    /// 30 meters
+    M30,
+    /// 20 meters
+    M20,
+    /// 17 meters
+    M17,
+    /// 15 meters
+    M15,
+}
+
+impl HamBand {
+    /// Get all bands, lowest frequency first.
+    pub fn all() -> &'static [HamBand] {
+        &[
+            HamBand::M80,
+            HamBand::M40,
+            HamBand::M30,
+            HamBand::M20,
+            HamBand::M17,
+            HamBand::M15,
+        ]
+    }
+
+    /// Get display name for the band.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HamBand::M80 => "80m",
+            HamBand::M40 => "40m",
+            HamBand::M30 => "30m",
+            HamBand::M20 => "20m",
+            HamBand::M17 => "17m",
+            HamBand::M15 => "15m",
+        }
+    }
+
+    /// Get the band's index into [`BandConditions`] (same order as the firmware).
+    pub fn index(&self) -> usize {
+        match self {
+            HamBand::M80 => 0,
+            HamBand::M40 => 1,
+            HamBand::M30 => 2,
+            HamBand::M20 => 3,
+            HamBand::M17 => 4,
+            HamBand::M15 => 5,
+        }
+    }
+
+    /// Get the band edges in Hz.
+    pub fn range(&self) -> (u64, u64) {
+        match self {
+            HamBand::M80 => (3_500_000, 4_000_000),
+            HamBand::M40 => (7_000_000, 7_300_000),
+            HamBand::M30 => (10_100_000, 10_150_000),
+            HamBand::M20 => (14_000_000, 14_350_000),
+            HamBand::M17 => (18_068_000, 18_168_000),
+            HamBand::M15 => (21_000_000, 21_450_000),
+        }
+    }
+
+    /// Get the FT8 dial frequency used when switching to the band.
+    pub fn dial_frequency(&self) -> u64 {
+        match self {
+            HamBand::M80 => 3_573_000,
+            HamBand::M40 => 7_074_000,
+            HamBand::M30 => 10_136_000,
+            HamBand::M20 => 14_074_000,
+            HamBand::M17 => 18_100_000,
+            HamBand::M15 => 21_074_000,
+        }
+    }
+
+    /// Get the band containing a frequency.
+    pub fn from_frequency(hz: u64) -> Option<HamBand> {
+        HamBand::all().iter().copied().find(|b| {
+            let (low, high) = b.range();
+            (low..=high).contains(&hz)
+        })
+    }
+}
+
+/// CSS class for a condition badge.
+fn condition_class(condition: Option<Condition>) -> &'static str {
+    match condition {
+        Some(Condition::Excellent) => "band-condition excellent",
+        Some(Condition::Good) => "band-condition good",
+        Some(Condition::Fair) => "band-condition fair",
+        Some(Condition::Poor) => "band-condition poor",
+        None => "band-condition unknown",
+    }
+}
+
+/// Band selector with per-band condition badges.
+#[component]
+pub fn BandSelector(
+    /// Current frequency in Hz
+    frequency: ReadSignal<u64>,
+    /// Per-band condition scores
+    conditions: ReadSignal<BandConditions<NUM_BANDS>>,
+    /// Callback with the new dial frequency when a band is chosen
+    on_change: Callback<u64>,
+) -> impl IntoView {
+    view! {
+        <div class="band-selector">
+            {HamBand::all()
+                .iter()
+                .map(|&b| {
+                    let is_selected = move || HamBand::from_frequency(frequency.get()) == Some(b);
+                    let score = move || conditions.with(|c| c.score(b.index()));
+                    let badge = move || {
+                        score()
+                            .map(|s| Condition::from_score(s).label())
+                            .unwrap_or("--")
+                    };
+                    let title = move || {
+                        score()
+                            .map(|s| format!("{} condition score {}/100", b.name(), s))
+                            .unwrap_or_else(|| format!("{}: no observations yet", b.name()))
+                    };
+                    view! {
+                        <button
+                            class="band-button"
+                            class:selected=is_selected
+                            title=title
+                            on:click=move |_| on_change.call(b.dial_frequency())
+                        >
+                            {b.name()}
+                            <span class=move || condition_class(score().map(Condition::from_score))>
+                                {badge}
+                            </span>
+                        </button>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
@@ -1,15 +1,61 @@
 //! Frequency Display Component.
 //!
-//! VFO display with digit tuning capability.
+//! VFO display with digit tuning capability. A digit tunes up when the
+//! top half is clicked or the wheel turns up, and down for the bottom
+//! half; on a touch screen, dragging a digit up or down tunes it a step
+//! per [`DIAL_TOUCH_STEP_PX`]. With the display focused, typing digits
+//! starts direct entry:
+//! `M` ends it in MHz, `K` or Enter in kHz, Backspace deletes and Escape
+//! cancels. Entries outside the radio's range are refused.
 
 use leptos::*;
+use sdr_dsp_core::units;
 
-/// Format frequency in MHz with proper grouping.
+/// Lowest frequency the radio tunes, as the firmware's `Frequency`.
+pub const MIN_FREQUENCY_HZ: u64 = 3_500_000;
+
+/// Highest frequency the radio tunes, as the firmware's `Frequency`.
+pub const MAX_FREQUENCY_HZ: u64 = 21_450_000;
+
+/// Longest keypad entry, in characters.
+const MAX_ENTRY_LEN: usize = 12;
+
+/// Finger travel in pixels per tuning step when dragging a digit.
+pub const DIAL_TOUCH_STEP_PX: i32 = 24;
+
+/// Characters in the formatted frequency, separators included.
+const DISPLAY_LEN: usize = 11;
+
+/// Format frequency with proper grouping, right-aligned to nine digits.
 fn format_frequency(hz: u64) -> String {
-    let mhz = hz / 1_000_000;
-    let khz = (hz % 1_000_000) / 1_000;
-    let hz_rem = hz % 1_000;
-    format!("{:>3}.{:03}.{:03}", mhz, khz, hz_rem)
+    format!("{:>width$}", units::format_frequency(hz).as_str(), width = DISPLAY_LEN)
+}
+
+/// Check a frequency is in the radio's range.
+pub fn valid_frequency(hz: u64) -> Option<u64> {
+    (MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ).contains(&hz).then_some(hz)
+}
+
+/// Convert a keypad entry (digits with at most one decimal point) in
+/// units of `unit_hz` to a valid frequency in Hz.
+pub fn parse_entry(entry: &str, unit_hz: u64) -> Option<u64> {
+    let (whole, fraction) = entry.split_once('.').unwrap_or((entry, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut hz = match whole {
+        "" => 0,
+        whole => whole.parse::<u64>().ok()?.checked_mul(unit_hz)?,
+    };
+    let mut scale = unit_hz;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        hz += u64::from(digit - b'0') * scale;
+    }
+    valid_frequency(hz)
 }
 
 /// Frequency display component with digit-based tuning.
@@ -25,6 +71,13 @@ pub fn FrequencyDisplay(
 ) -> impl IntoView {
     let formatted = move || format_frequency(frequency.get());
 
+    // Keypad entry in progress, and whether the last attempt was refused
+    let entry = create_rw_signal(None::<String>);
+    let invalid = create_rw_signal(false);
+    // Digit being dragged by touch and the finger's last counted position
+    let touch: StoredValue<Option<(usize, i32)>> = store_value(None);
+    let touch_y = |ev: &web_sys::TouchEvent| ev.touches().get(0).map(|t| t.client_y());
+
     // Tuning step sizes for each digit position
     let step_sizes: [u64; 9] = [
         100_000_000, // 100 MHz
@@ -49,45 +102,166 @@ pub fn FrequencyDisplay(
         } else {
             current.saturating_sub(step)
         };
-        on_change.call(new_freq.clamp(100_000, 30_000_000_000)); // 100kHz to 30GHz
+        on_change.call(new_freq.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ));
+    };
+
+    let commit_entry = move |unit_hz: u64| {
+        let hz = entry.with_untracked(|e| e.as_deref().and_then(|e| parse_entry(e, unit_hz)));
+        match hz {
+            Some(hz) => {
+                entry.set(None);
+                invalid.set(false);
+                on_change.call(hz);
+            }
+            None => invalid.set(true),
+        }
+    };
+
+    let handle_keydown = move |ev: web_sys::KeyboardEvent| {
+        if !active || ev.ctrl_key() || ev.alt_key() || ev.meta_key() {
+            return;
+        }
+        let key = ev.key();
+        let editing = entry.with_untracked(Option::is_some);
+        match key.as_str() {
+            "Enter" | "k" | "K" if editing => commit_entry(1_000),
+            "m" | "M" if editing => commit_entry(1_000_000),
+            "Escape" if editing => {
+                entry.set(None);
+                invalid.set(false);
+            }
+            "Backspace" if editing => {
+                entry.update(|e| {
+                    if let Some(text) = e {
+                        text.pop();
+                    }
+                    if e.as_deref() == Some("") {
+                        *e = None;
+                    }
+                });
+                invalid.set(false);
+            }
+            _ => {
+                let Some(ch) = key.chars().next().filter(|_| key.len() == 1) else {
+                    return;
+                };
+                if !ch.is_ascii_digit() && ch != '.' {
+                    return;
+                }
+                entry.update(|e| {
+                    let text = e.get_or_insert_with(String::new);
+                    if text.len() < MAX_ENTRY_LEN && !(ch == '.' && text.contains('.')) {
+                        text.push(ch);
+                    }
+                });
+                invalid.set(false);
+            }
+        }
+        ev.prevent_default();
     };
 
     view! {
-        <div class="frequency-display" class:active=active>
-            <div class="frequency-digits">
-                {move || {
-                    let freq_str = formatted();
-                    freq_str
-                        .chars()
-                        .enumerate()
-                        .map(|(i, ch)| {
-                            if ch == '.' {
-                                view! { <span class="separator">"."</span> }.into_view()
-                            } else {
-                                let digit_idx = match i {
-                                    0..=2 => i,
-                                    4..=6 => i - 1,
-                                    8..=10 => i - 2,
-                                    _ => 0,
-                                };
-                                view! {
-                                    <span
-                                        class="digit"
-                                        on:wheel=move |ev| {
-                                            ev.prevent_default();
-                                            let delta = if ev.delta_y() < 0.0 { 1 } else { -1 };
-                                            handle_digit_click(digit_idx, delta);
-                                        }
-                                    >
-                                        {ch.to_string()}
-                                    </span>
-                                }.into_view()
-                            }
-                        })
-                        .collect_view()
-                }}
+        <div
+            class="frequency-display"
+            class:active=active
+            class:entry=move || entry.with(Option::is_some)
+            tabindex="0"
+            on:keydown=handle_keydown
+            on:blur=move |_| {
+                entry.set(None);
+                invalid.set(false);
+            }
+        >
+            {move || entry.get().map(|text| view! {
+                <div class="frequency-entry" class:invalid=move || invalid.get()>
+                    {text}
+                    <span class="entry-hint">"M = MHz, K/Enter = kHz"</span>
+                </div>
+            })}
+            <div class="frequency-digits" class:hidden=move || entry.with(Option::is_some)>
+                {(0..DISPLAY_LEN)
+                    .map(|i| {
+                        // Spans stay put as the frequency changes, so a
+                        // touch drag keeps its target
+                        let digit_idx = match i {
+                            3 | 7 => return view! { <span class="separator">"."</span> }.into_view(),
+                            0..=2 => i,
+                            4..=6 => i - 1,
+                            _ => i - 2,
+                        };
+                        let ch = move || formatted().chars().nth(i).unwrap_or(' ').to_string();
+                        view! {
+                            <span
+                                class="digit"
+                                style="touch-action: none;"
+                                on:click=move |ev: web_sys::MouseEvent| {
+                                    let digit = event_target::<web_sys::Element>(&ev);
+                                    let upper = ev.offset_y() < digit.client_height() / 2;
+                                    handle_digit_click(digit_idx, if upper { 1 } else { -1 });
+                                }
+                                on:touchstart=move |ev: web_sys::TouchEvent| {
+                                    touch.set_value(touch_y(&ev).map(|y| (digit_idx, y)));
+                                }
+                                on:touchmove=move |ev: web_sys::TouchEvent| {
+                                    ev.prevent_default();
+                                    let (Some((digit, last)), Some(y)) = (touch.get_value(), touch_y(&ev)) else {
+                                        return;
+                                    };
+                                    // Dragging up tunes up
+                                    let steps = (last - y) / DIAL_TOUCH_STEP_PX;
+                                    for _ in 0..steps.abs() {
+                                        handle_digit_click(digit, steps.signum());
+                                    }
+                                    touch.set_value(Some((digit, last - steps * DIAL_TOUCH_STEP_PX)));
+                                }
+                                on:touchend=move |_| touch.set_value(None)
+                                on:wheel=move |ev| {
+                                    ev.prevent_default();
+                                    let delta = if ev.delta_y() < 0.0 { 1 } else { -1 };
+                                    handle_digit_click(digit_idx, delta);
+                                }
+                            >
+                                {ch}
+                            </span>
+                        }.into_view()
+                    })
+                    .collect_view()}
             </div>
             <div class="frequency-unit">"MHz"</div>
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entry_units() {
+        assert_eq!(parse_entry("7.074", 1_000_000), Some(7_074_000));
+        assert_eq!(parse_entry("14", 1_000_000), Some(14_000_000));
+        assert_eq!(parse_entry("7074", 1_000), Some(7_074_000));
+        assert_eq!(parse_entry("7074.5", 1_000), Some(7_074_500));
+        assert_eq!(parse_entry("3500", 1_000), Some(MIN_FREQUENCY_HZ));
+    }
+
+    #[test]
+    fn test_parse_entry_rejects() {
+        assert_eq!(parse_entry("", 1_000), None);
+        assert_eq!(parse_entry(".", 1_000), None);
+        assert_eq!(parse_entry("7.0.1", 1_000_000), None);
+        assert_eq!(parse_entry("7a", 1_000_000), None);
+        assert_eq!(parse_entry("-7", 1_000_000), None);
+        // Outside the radio's range
+        assert_eq!(parse_entry("1.8", 1_000_000), None);
+        assert_eq!(parse_entry("28", 1_000_000), None);
+        assert_eq!(parse_entry("99999999999999999999", 1_000), None);
+    }
+
+    #[test]
+    fn test_valid_frequency() {
+        assert_eq!(valid_frequency(MAX_FREQUENCY_HZ), Some(MAX_FREQUENCY_HZ));
+        assert_eq!(valid_frequency(MAX_FREQUENCY_HZ + 1), None);
+        assert_eq!(valid_frequency(MIN_FREQUENCY_HZ - 1), None);
+    }
+}
@@ -3,6 +3,7 @@
 //! Dropdown/button group for selecting operating mode.
 
 use leptos::*;
+use sdr_dsp_api::DemodMode;
 
 /// Radio operating modes.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -21,6 +22,12 @@ pub enum RadioMode {
     Psk31,
     /// RTTY Digital Mode
     Rtty,
+    /// Slow-scan television
+    Sstv,
+    /// JS8 style keyboard-to-keyboard chat
+    Js8,
+    /// Feld Hell (Hellschreiber)
+    Hell,
 }
 
 impl RadioMode {
@@ -34,25 +41,63 @@ impl RadioMode {
             RadioMode::Fm => "FM",
             RadioMode::Psk31 => "PSK31",
             RadioMode::Rtty => "RTTY",
+            RadioMode::Sstv => "SSTV",
+            RadioMode::Js8 => "JS8",
+            RadioMode::Hell => "HELL",
         }
     }
 
-    /// Get mode code for DSP processor.
-    pub fn code(&self) -> u8 {
+    /// Parse a display name written by [`RadioMode::name`].
+    pub fn from_name(name: &str) -> Option<RadioMode> {
+        RadioMode::all().iter().copied().find(|m| m.name() == name)
+    }
+
+    /// Get the DSP demodulator for this mode.
+    pub fn demod(&self) -> DemodMode {
         match self {
-            RadioMode::Lsb => 0,
-            RadioMode::Usb => 1,
-            RadioMode::Cw => 2,
-            RadioMode::Am => 3,
-            RadioMode::Fm => 4,
-            RadioMode::Psk31 => 1, // Uses USB with digital decoder
-            RadioMode::Rtty => 1,  // Uses USB with digital decoder
+            RadioMode::Lsb => DemodMode::Lsb,
+            RadioMode::Usb => DemodMode::Usb,
+            RadioMode::Cw => DemodMode::Cw,
+            RadioMode::Am => DemodMode::Am,
+            RadioMode::Fm => DemodMode::Fm,
+            RadioMode::Psk31 => DemodMode::Usb, // Uses USB with digital decoder
+            RadioMode::Rtty => DemodMode::Usb,  // Uses USB with digital decoder
+            RadioMode::Sstv => DemodMode::Usb,  // Uses USB with image decoder
+            RadioMode::Js8 => DemodMode::Usb,   // Uses USB with frame decoder
+            RadioMode::Hell => DemodMode::Usb,  // Uses USB with pixel decoder
         }
     }
 
+    /// Get mode code for DSP processor.
+    pub fn code(&self) -> u8 {
+        self.demod().code()
+    }
+
     /// Check if this is a digital mode.
     pub fn is_digital(&self) -> bool {
-        matches!(self, RadioMode::Psk31 | RadioMode::Rtty)
+        matches!(
+            self,
+            RadioMode::Psk31 | RadioMode::Rtty | RadioMode::Sstv | RadioMode::Js8 | RadioMode::Hell
+        )
+    }
+
+    /// Audio offsets covered by the receive filter, in Hz.
+    ///
+    /// `bfo_hz` is the spectrum offset demodulated to zero; sidebands sit
+    /// on one side of it, other modes are centred on it.
+    pub fn passband(&self, bandwidth_hz: f32, bfo_hz: f32) -> (f32, f32) {
+        match self {
+            RadioMode::Lsb => (bfo_hz - bandwidth_hz, bfo_hz),
+            RadioMode::Usb
+            | RadioMode::Psk31
+            | RadioMode::Rtty
+            | RadioMode::Sstv
+            | RadioMode::Js8
+            | RadioMode::Hell => (bfo_hz, bfo_hz + bandwidth_hz),
+            RadioMode::Cw | RadioMode::Am | RadioMode::Fm => {
+                (bfo_hz - bandwidth_hz / 2.0, bfo_hz + bandwidth_hz / 2.0)
+            }
+        }
     }
 
     /// All available modes.
@@ -65,6 +110,9 @@ impl RadioMode {
             RadioMode::Fm,
             RadioMode::Psk31,
             RadioMode::Rtty,
+            RadioMode::Sstv,
+            RadioMode::Js8,
+            RadioMode::Hell,
         ]
     }
 }
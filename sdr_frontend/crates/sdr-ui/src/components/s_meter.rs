@@ -1,25 +1,20 @@
 //! S-Meter Component.
 //!
-//! Signal strength meter display.
+//! Signal strength meter display in IARU S-units and calibrated dBm.
 
 use leptos::*;
+use sdr_dsp_core::units;
 
 /// S-meter display component.
 #[component]
 pub fn SMeterDisplay(
     /// S-meter value (0.0 = S0, 1.0 = S9, >1.0 = S9+)
     value: ReadSignal<f32>,
+    /// Calibrated signal level in dBm
+    dbm: ReadSignal<f32>,
 ) -> impl IntoView {
-    let s_reading = move || {
-        let v = value.get();
-        let s_units = v * 9.0;
-        if s_units <= 9.0 {
-            format!("S{}", s_units.round() as u8)
-        } else {
-            let over_db = ((s_units - 9.0) * 6.0).round() as i32;
-            format!("S9+{}", over_db)
-        }
-    };
+    let s_reading = move || String::from(units::format_s_meter(dbm.get()).as_str());
+    let dbm_reading = move || String::from(units::format_dbm(dbm.get()).as_str());
 
     let bar_width = move || {
         let v = value.get().clamp(0.0, 1.5);
@@ -51,6 +46,7 @@ pub fn SMeterDisplay(
                 <div class=bar_class style:width=bar_width></div>
             </div>
             <div class="s-meter-reading">{s_reading}</div>
+            <div class="s-meter-dbm">{dbm_reading}</div>
         </div>
     }
 }
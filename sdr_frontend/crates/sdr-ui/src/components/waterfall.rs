@@ -1,21 +1,45 @@
 //! WebGL2 Waterfall Display Component.
 //!
 //! Renders spectrum data as a scrolling waterfall display using WebGL2.
-//! Uses texture streaming for efficient updates.
+//! Uses texture streaming for efficient updates. Clicking tunes, dragging
+//! pans and the scroll wheel zooms the displayed span. On a touch screen
+//! a tap tunes, a one-finger drag retunes by the distance dragged and a
+//! two-finger pinch zooms. Levels are colored through a [`Palette`].
 
 use leptos::*;
+use sdr_dsp_core::spectrum::{DisplayRange, SpectrumView};
 use wasm_bindgen::prelude::*;
 use web_sys::{
     HtmlCanvasElement, WebGl2RenderingContext as GL, WebGlProgram, WebGlShader, WebGlTexture,
     WebGlUniformLocation, WebGlVertexArrayObject,
 };
 
+use crate::theme::{Palette, PALETTE_TABLE_LEN};
+
 /// Waterfall display width in pixels (FFT bins).
 pub const WATERFALL_WIDTH: usize = 512;
 
 /// Waterfall display height in pixels (history rows).
 pub const WATERFALL_HEIGHT: usize = 256;
 
+/// Zoom factor applied per scroll-wheel notch.
+pub const ZOOM_STEP: f32 = 1.25;
+
+/// Pointer travel in pixels before a press becomes a drag.
+const DRAG_THRESHOLD_PX: i32 = 3;
+
+/// Finger travel in pixels before a touch becomes a drag.
+const TOUCH_SLOP_PX: f32 = 8.0;
+
+/// Touch gesture in progress.
+#[derive(Clone, Copy, Debug)]
+enum Gesture {
+    /// One finger: start and last x, and whether it has dragged
+    Drag(f32, f32, bool),
+    /// Two fingers: last distance between them
+    Pinch(f32),
+}
+
 /// Vertex shader source for textured quad.
 const VERTEX_SHADER_SRC: &str = r#"#version 300 es
 layout(location = 0) in vec2 a_position;
@@ -30,6 +54,9 @@ void main() {
 "#;
 
 /// Fragment shader source with color palette mapping.
+///
+/// Levels are looked up in a palette texture of [`PALETTE_TABLE_LEN`]
+/// colors, one per level step, set with [`WaterfallRenderer::set_palette`].
 const FRAGMENT_SHADER_SRC: &str = r#"#version 300 es
 precision mediump float;
 
@@ -37,42 +64,18 @@ in vec2 v_texcoord;
 out vec4 fragColor;
 
 uniform sampler2D u_texture;
+uniform sampler2D u_palette;
 uniform float u_row_offset;
 
-// Color palette: black -> blue -> cyan -> green -> yellow -> red -> white
-vec3 colormap(float value) {
-    float v = clamp(value, 0.0, 1.0);
-
-    if (v < 0.2) {
-        // Black to blue
-        float t = v / 0.2;
-        return vec3(0.0, 0.0, t);
-    } else if (v < 0.4) {
-        // Blue to cyan
-        float t = (v - 0.2) / 0.2;
-        return vec3(0.0, t, 1.0);
-    } else if (v < 0.6) {
-        // Cyan to green
-        float t = (v - 0.4) / 0.2;
-        return vec3(0.0, 1.0, 1.0 - t);
-    } else if (v < 0.8) {
-        // Green to yellow
-        float t = (v - 0.6) / 0.2;
-        return vec3(t, 1.0, 0.0);
-    } else {
-        // Yellow to white
-        float t = (v - 0.8) / 0.2;
-        return vec3(1.0, 1.0 - t * 0.5, t);
-    }
-}
-
 void main() {
     // Apply circular buffer offset for scrolling
     vec2 tc = v_texcoord;
     tc.y = fract(tc.y + u_row_offset);
 
     float intensity = texture(u_texture, tc).r;
-    vec3 color = colormap(intensity);
+    // Sample at texel centres so level 0 and 1 hit the end colors
+    float x = (clamp(intensity, 0.0, 1.0) * 255.0 + 0.5) / 256.0;
+    vec3 color = texture(u_palette, vec2(x, 0.5)).rgb;
     fragColor = vec4(color, 1.0);
 }
 "#;
@@ -83,6 +86,7 @@ pub struct WaterfallRenderer {
     program: WebGlProgram,
     vao: WebGlVertexArrayObject,
     texture: WebGlTexture,
+    palette: WebGlTexture,
     u_row_offset: WebGlUniformLocation,
     texture_data: Vec<u8>,
     current_row: usize,
@@ -110,10 +114,31 @@ impl WaterfallRenderer {
         let u_row_offset = gl
             .get_uniform_location(&program, "u_row_offset")
             .ok_or("Failed to get u_row_offset location")?;
+        let u_texture = gl
+            .get_uniform_location(&program, "u_texture")
+            .ok_or("Failed to get u_texture location")?;
+        let u_palette = gl
+            .get_uniform_location(&program, "u_palette")
+            .ok_or("Failed to get u_palette location")?;
+
+        // Waterfall data on texture unit 0, palette on unit 1
+        gl.uniform1i(Some(&u_texture), 0);
+        gl.uniform1i(Some(&u_palette), 1);
 
         // Create VAO with fullscreen quad
         let vao = create_fullscreen_quad(&gl)?;
 
+        // Create palette texture, one texel per level step
+        gl.active_texture(GL::TEXTURE1);
+        let palette = gl.create_texture().ok_or("Failed to create palette texture")?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(&palette));
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        upload_palette(&gl, &Palette::default())?;
+        gl.active_texture(GL::TEXTURE0);
+
         // Create texture for waterfall data
         let texture = gl.create_texture().ok_or("Failed to create texture")?;
         gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
@@ -141,6 +166,7 @@ impl WaterfallRenderer {
             program,
             vao,
             texture,
+            palette,
             u_row_offset,
             texture_data,
             current_row: 0,
@@ -178,6 +204,16 @@ impl WaterfallRenderer {
         self.current_row = (self.current_row + 1) % WATERFALL_HEIGHT;
     }
 
+    /// Set the palette levels are colored through.
+    pub fn set_palette(&self, palette: &Palette) {
+        self.gl.active_texture(GL::TEXTURE1);
+        self.gl.bind_texture(GL::TEXTURE_2D, Some(&self.palette));
+        if let Err(e) = upload_palette(&self.gl, palette) {
+            web_sys::console::error_1(&format!("Waterfall palette error: {:?}", e).into());
+        }
+        self.gl.active_texture(GL::TEXTURE0);
+    }
+
     /// Render the waterfall display.
     pub fn render(&self) {
         self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
@@ -185,6 +221,9 @@ impl WaterfallRenderer {
 
         self.gl.use_program(Some(&self.program));
         self.gl.bind_vertex_array(Some(&self.vao));
+        self.gl.active_texture(GL::TEXTURE1);
+        self.gl.bind_texture(GL::TEXTURE_2D, Some(&self.palette));
+        self.gl.active_texture(GL::TEXTURE0);
         self.gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
 
         // Set row offset for circular buffer scrolling
@@ -216,6 +255,21 @@ impl WaterfallRenderer {
     }
 }
 
+/// Upload a palette's lookup table to the bound texture.
+fn upload_palette(gl: &GL, palette: &Palette) -> Result<(), JsValue> {
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        GL::TEXTURE_2D,
+        0,
+        GL::RGB8 as i32,
+        PALETTE_TABLE_LEN as i32,
+        1,
+        0,
+        GL::RGB,
+        GL::UNSIGNED_BYTE,
+        Some(&palette.lookup_table()),
+    )
+}
+
 /// Compile a WebGL shader.
 fn compile_shader(gl: &GL, shader_type: u32, source: &str) -> Result<WebGlShader, String> {
     let shader = gl
@@ -303,11 +357,51 @@ pub fn Waterfall(
     /// Height of the canvas in pixels
     #[prop(default = WATERFALL_HEIGHT)]
     height: usize,
-    /// Signal providing spectrum data (Vec<f32> of normalized values)
+    /// Signal providing spectrum data (Vec<f32> of dBm values)
     spectrum: ReadSignal<Vec<f32>>,
+    /// Level span mapped onto the color palette
+    range: ReadSignal<DisplayRange>,
+    /// Colors levels are mapped through
+    palette: ReadSignal<Palette>,
+    /// Zoomed and panned window onto the spectrum
+    view: RwSignal<SpectrumView>,
+    /// Called with the spectrum offset in Hz when the display is clicked
+    on_tune: Callback<f32>,
+    /// Called with a retune in Hz as a finger drags the display
+    #[prop(optional)]
+    on_drag_tune: Option<Callback<f32>>,
 ) -> impl IntoView {
     let canvas_ref = create_node_ref::<leptos::html::Canvas>();
     let renderer: StoredValue<Option<WaterfallRenderer>> = store_value(None);
+    // Press position and last position of the pointer, and whether it dragged
+    let drag: StoredValue<Option<(i32, i32, bool)>> = store_value(None);
+
+    // Displayed canvas width, which CSS may scale from the pixel width
+    let client_width = move || {
+        canvas_ref
+            .get_untracked()
+            .map_or(width as i32, |c| c.client_width())
+            .max(1) as f32
+    };
+    // Pointer position as a fraction of the displayed width
+    let pointer_x = move |ev: &web_sys::MouseEvent| ev.offset_x() as f32 / client_width();
+    let gesture: StoredValue<Option<Gesture>> = store_value(None);
+    // Finger positions in displayed pixels from the canvas corner
+    let touch_points = move |ev: &web_sys::TouchEvent| -> Vec<(f32, f32)> {
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return Vec::new();
+        };
+        let rect = canvas.get_bounding_client_rect();
+        let touches = ev.touches();
+        (0..touches.length())
+            .filter_map(|i| touches.get(i))
+            .map(|t| {
+                let x = t.client_x() as f64 - rect.left();
+                let y = t.client_y() as f64 - rect.top();
+                (x as f32, y as f32)
+            })
+            .collect()
+    };
 
     // Initialize WebGL on mount
     create_effect(move |_| {
@@ -318,6 +412,7 @@ pub fn Waterfall(
 
             match WaterfallRenderer::new(canvas_el) {
                 Ok(r) => {
+                    r.set_palette(&palette.get_untracked());
                     renderer.set_value(Some(r));
                 }
                 Err(e) => {
@@ -327,9 +422,25 @@ pub fn Waterfall(
         }
     });
 
+    // Recolor the history when the palette changes
+    create_effect(move |_| {
+        let palette = palette.get();
+        renderer.with_value(|r| {
+            if let Some(renderer) = r {
+                renderer.set_palette(&palette);
+                renderer.render();
+            }
+        });
+    });
+
     // Update waterfall when spectrum changes
     create_effect(move |_| {
-        let data = spectrum.get();
+        let range = range.get_untracked();
+        let mut data = vec![0.0; WATERFALL_WIDTH];
+        spectrum.with(|s| view.get_untracked().resample(s, &mut data));
+        for val in &mut data {
+            *val = range.normalize(*val);
+        }
         renderer.update_value(|r| {
             if let Some(ref mut renderer) = r {
                 renderer.push_row(&data);
@@ -338,11 +449,96 @@ pub fn Waterfall(
         });
     });
 
+    let on_mousedown = move |ev: web_sys::MouseEvent| {
+        drag.set_value(Some((ev.offset_x(), ev.offset_x(), false)));
+    };
+
+    let on_mousemove = move |ev: web_sys::MouseEvent| {
+        let Some((start, last, dragging)) = drag.get_value() else {
+            return;
+        };
+        let x = ev.offset_x();
+        if dragging || (x - start).abs() >= DRAG_THRESHOLD_PX {
+            view.update(|v| v.pan((x - last) as f32 / client_width()));
+            drag.set_value(Some((start, x, true)));
+        }
+    };
+
+    let on_mouseup = move |ev: web_sys::MouseEvent| {
+        if let Some((_, _, false)) = drag.get_value() {
+            on_tune.call(view.get_untracked().offset_hz(pointer_x(&ev)));
+        }
+        drag.set_value(None);
+    };
+
+    let on_wheel = move |ev: web_sys::WheelEvent| {
+        ev.prevent_default();
+        let factor = if ev.delta_y() < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+        let x = pointer_x(&ev);
+        view.update(|v| v.zoom_at(factor, x));
+    };
+
+    let on_touchstart = move |ev: web_sys::TouchEvent| {
+        ev.prevent_default();
+        gesture.set_value(match touch_points(&ev)[..] {
+            [(x, _)] => Some(Gesture::Drag(x, x, false)),
+            [(x0, y0), (x1, y1)] => Some(Gesture::Pinch((x1 - x0).hypot(y1 - y0))),
+            _ => None,
+        });
+    };
+
+    let on_touchmove = move |ev: web_sys::TouchEvent| {
+        ev.prevent_default();
+        let width = client_width();
+        match (gesture.get_value(), &touch_points(&ev)[..]) {
+            (Some(Gesture::Drag(start, last, dragged)), &[(x, _)]) => {
+                if !dragged && (x - start).abs() < TOUCH_SLOP_PX {
+                    return;
+                }
+                // The spectrum follows the finger, so dragging right tunes down
+                let shift_hz = (last - x) / width * view.get_untracked().visible_span_hz();
+                if let Some(on_drag_tune) = on_drag_tune {
+                    on_drag_tune.call(shift_hz);
+                }
+                gesture.set_value(Some(Gesture::Drag(start, x, true)));
+            }
+            (Some(Gesture::Pinch(last)), &[(x0, y0), (x1, y1)]) => {
+                let distance = (x1 - x0).hypot(y1 - y0);
+                if last > 0.0 && distance > 0.0 {
+                    let center = (x0 + x1) / 2.0 / width;
+                    view.update(|v| v.zoom_at(distance / last, center));
+                }
+                gesture.set_value(Some(Gesture::Pinch(distance)));
+            }
+            _ => {}
+        }
+    };
+
+    let on_touchend = move |ev: web_sys::TouchEvent| {
+        ev.prevent_default();
+        if let Some(Gesture::Drag(start, _, false)) = gesture.get_value() {
+            on_tune.call(view.get_untracked().offset_hz(start / client_width()));
+        }
+        // Lifting one finger of a pinch ends it rather than starting a drag
+        gesture.set_value(None);
+    };
+
     view! {
         <canvas
             node_ref=canvas_ref
             class="waterfall-canvas"
-            style="display: block; image-rendering: pixelated;"
+            style="display: block; max-width: 100%; height: auto; image-rendering: pixelated; \
+                cursor: crosshair; touch-action: none;"
+            on:mousedown=on_mousedown
+            on:mousemove=on_mousemove
+            on:mouseup=on_mouseup
+            on:mouseleave=move |_| drag.set_value(None)
+            on:wheel=on_wheel
+            on:dblclick=move |_| view.update(SpectrumView::reset)
+            on:touchstart=on_touchstart
+            on:touchmove=on_touchmove
+            on:touchend=on_touchend
+            on:touchcancel=move |_| gesture.set_value(None)
         />
     }
 }
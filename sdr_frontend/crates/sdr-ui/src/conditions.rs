@@ -0,0 +1,59 @@
+//! Band condition tracking.
+//!
+//! Feeds the shared band condition scorer with the noise floor of the
+//! band currently tuned and with decoded FT8/WSPR spots, so the band
+//! selector can show which bands are open.
+
+use leptos::*;
+use sdr_dsp_core::conditions::locator_distance_km;
+use sdr_dsp_core::spectrum::noise_floor_db;
+
+use crate::components::HamBand;
+use crate::state::AppContext;
+
+/// Interval between noise floor readings, in milliseconds.
+pub const NOISE_INTERVAL_MS: f64 = 10_000.0;
+
+/// Interval between spot activity decays, in milliseconds.
+pub const DECAY_INTERVAL_MS: f64 = 300_000.0;
+
+/// Record a decoded FT8/WSPR spot heard at the given RF frequency.
+pub fn record_spot(ctx: &AppContext, frequency: u64, locator: &str, snr_db: i8) {
+    let Some(band) = HamBand::from_frequency(frequency) else {
+        return;
+    };
+    let distance_km = ctx
+        .home_locator
+        .with_untracked(|home| locator_distance_km(home, locator));
+    ctx.band_conditions
+        .update(|c| c.add_spot(band.index(), distance_km, snr_db));
+}
+
+/// Create the effect that samples the noise floor and ages spot activity.
+pub fn create_conditions_effect(ctx: AppContext) {
+    let last_noise_ms = store_value(0.0_f64);
+    let last_decay_ms = store_value(js_sys::Date::now());
+
+    create_effect(move |_| {
+        let floor = ctx.spectrum.with(|s| noise_floor_db(s));
+        let now = js_sys::Date::now();
+
+        if now - last_decay_ms.get_value() >= DECAY_INTERVAL_MS {
+            last_decay_ms.set_value(now);
+            ctx.band_conditions.update(|c| c.decay());
+        }
+
+        if !ctx.audio_running.get_untracked() || ctx.transmitting.get_untracked() {
+            return;
+        }
+        if now - last_noise_ms.get_value() < NOISE_INTERVAL_MS {
+            return;
+        }
+        let band = HamBand::from_frequency(ctx.frequency.get_untracked());
+        if let (Some(band), Some(floor)) = (band, floor) {
+            last_noise_ms.set_value(now);
+            ctx.band_conditions
+                .update(|c| c.add_noise_floor(band.index(), floor));
+        }
+    });
+}
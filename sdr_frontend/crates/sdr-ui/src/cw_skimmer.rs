@@ -0,0 +1,112 @@
+//! CW skimmer band activity list.
+//!
+//! With the skimmer enabled the worklet finds every CW signal in the
+//! audio passband (300 to 1200 Hz) and decodes them all at once. The
+//! signal table arrives a few times a second and decoded characters as
+//! they complete; each signal gets a row with its tone, speed, SNR and
+//! the latest text copied from it.
+
+use leptos::*;
+use sdr_mode_cw::{DEFAULT_HIGH_HZ, DEFAULT_LOW_HZ};
+
+use crate::state::AppContext;
+
+/// Characters of decoded text kept per signal.
+pub const MAX_TEXT_CHARS: usize = 80;
+
+/// A CW signal found by the skimmer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CwSpot {
+    /// Skimmer track id
+    pub id: u32,
+    /// Audio tone in Hz
+    pub frequency_hz: f32,
+    /// Estimated speed in WPM
+    pub wpm: f32,
+    /// Peak over noise in dB
+    pub snr_db: f32,
+    /// Latest decoded text, oldest first
+    pub text: String,
+}
+
+/// Update the band activity list from the worklet's signal table,
+/// keeping the text of signals still tracked.
+pub fn signals_received(ctx: &AppContext, signals: Vec<CwSpot>) {
+    ctx.cw_spots.update(|spots| {
+        let mut updated: Vec<CwSpot> = signals
+            .into_iter()
+            .map(|mut signal| {
+                if let Some(old) = spots.iter_mut().find(|s| s.id == signal.id) {
+                    signal.text = std::mem::take(&mut old.text);
+                }
+                signal
+            })
+            .collect();
+        // Lowest tone first
+        updated.sort_by(|a, b| a.frequency_hz.total_cmp(&b.frequency_hz));
+        *spots = updated;
+    });
+}
+
+/// Append characters decoded from signal `id`.
+pub fn text_received(ctx: &AppContext, id: u32, text: &str) {
+    ctx.cw_spots.update(|spots| {
+        let Some(spot) = spots.iter_mut().find(|s| s.id == id) else {
+            return;
+        };
+        spot.text.push_str(text);
+        let excess = spot.text.chars().count().saturating_sub(MAX_TEXT_CHARS);
+        if excess > 0 {
+            spot.text = spot.text.chars().skip(excess).collect();
+        }
+    });
+}
+
+/// Leptos component listing the signals found by the CW skimmer.
+#[component]
+pub fn CwSkimmerPanel(ctx: AppContext) -> impl IntoView {
+    let toggle = move |ev| {
+        let enabled = event_target_checked(&ev);
+        ctx.cw_skimmer_enabled.set(enabled);
+        if !enabled {
+            ctx.cw_spots.set(Vec::new());
+        }
+    };
+
+    view! {
+        <div class="cw-skimmer-panel">
+            <h3>"Band Activity"</h3>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || ctx.cw_skimmer_enabled.get()
+                    on:change=toggle
+                />
+                {format!("Skim CW ({DEFAULT_LOW_HZ:.0}-{DEFAULT_HIGH_HZ:.0} Hz)")}
+            </label>
+            <table class="cw-spots">
+                <thead>
+                    <tr>
+                        <th>"Tone"</th>
+                        <th>"WPM"</th>
+                        <th>"SNR"</th>
+                        <th>"Text"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || ctx.cw_spots.get()
+                        .into_iter()
+                        .map(|spot| view! {
+                            <tr>
+                                <td>{format!("{:.0} Hz", spot.frequency_hz)}</td>
+                                <td>{format!("{:.0}", spot.wpm)}</td>
+                                <td>{format!("{:.0} dB", spot.snr_db)}</td>
+                                <td class="cw-text">{spot.text}</td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
@@ -0,0 +1,190 @@
+//! Feld Hell (Hellschreiber) send and receive.
+//!
+//! In Hell mode the worklet sends the received pixel columns, which are
+//! scrolled onto a canvas right to left. Each column is drawn twice, one
+//! above the other, so text stays readable however the columns happen to
+//! be aligned. Outgoing text is keyed from the built-in font and played
+//! to the audio output, keying the radio through VOX.
+
+use leptos::*;
+use sdr_mode_hell::{HellEncoder, COLUMN_HEIGHT, TEXT_CAPACITY};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::recorder::play_samples;
+use crate::serial::sleep_ms;
+use crate::state::AppContext;
+
+/// Columns shown on the canvas.
+pub const DISPLAY_COLUMNS: usize = 600;
+
+/// Sample rate of the transmitted audio in Hz.
+pub const TX_SAMPLE_RATE: f32 = 48000.0;
+
+/// Peak level of the transmitted audio (0.0 to 1.0).
+pub const TX_LEVEL: f32 = 0.5;
+
+/// Default tone frequency in Hz.
+pub const DEFAULT_FREQUENCY_HZ: f32 = sdr_mode_hell::DEFAULT_TONE_HZ;
+
+/// Lowest and highest tone frequency offered, in Hz.
+const MIN_FREQUENCY_HZ: f32 = 300.0;
+const MAX_FREQUENCY_HZ: f32 = 3000.0;
+
+/// Canvas height: each column drawn twice.
+const DISPLAY_HEIGHT: usize = 2 * COLUMN_HEIGHT;
+
+/// Create the transmit encoder.
+pub fn new_encoder(frequency_hz: f32) -> HellEncoder {
+    HellEncoder::new(TX_SAMPLE_RATE, TX_LEVEL, frequency_hz)
+}
+
+/// Record pixel columns from the worklet.
+pub fn columns_received(ctx: &AppContext, pixels: Vec<u8>) {
+    if pixels.len() >= COLUMN_HEIGHT {
+        ctx.hell_columns.set(Some(pixels));
+    }
+}
+
+/// Play `text` to the audio output, marking the radio as transmitting
+/// until it has been sent.
+async fn run_transmit(ctx: AppContext, text: String) {
+    let mut encoder = new_encoder(ctx.hell_frequency.get_untracked());
+    encoder.queue_text(&text);
+    let samples: Vec<f32> = std::iter::from_fn(|| encoder.next_sample()).collect();
+    if let Err(e) = play_samples(&samples, TX_SAMPLE_RATE) {
+        web_sys::console::error_1(&format!("Hell: audio output failed: {:?}", e).into());
+        return;
+    }
+    ctx.transmitting.set(true);
+    sleep_ms((samples.len() as f32 / TX_SAMPLE_RATE * 1000.0) as i32).await;
+    ctx.transmitting.set(false);
+}
+
+/// Leptos component for Feld Hell.
+#[component]
+pub fn HellPanel(ctx: AppContext) -> impl IntoView {
+    let canvas_ref = create_node_ref::<leptos::html::Canvas>();
+    let draft = create_rw_signal(String::new());
+
+    // Scroll the picture left and paint new columns at the right edge
+    create_effect(move |_| {
+        let Some(pixels) = ctx.hell_columns.get() else {
+            return;
+        };
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        let Some(context) = context_2d(&canvas) else {
+            return;
+        };
+        if let Err(e) = draw_columns(&context, &canvas, &pixels) {
+            web_sys::console::warn_1(&e);
+        }
+    });
+
+    let clear = move |_| {
+        if let Some(context) = canvas_ref.get_untracked().as_deref().and_then(context_2d) {
+            context.clear_rect(0.0, 0.0, DISPLAY_COLUMNS as f64, DISPLAY_HEIGHT as f64);
+        }
+    };
+
+    let send = move || {
+        let text = draft.get_untracked();
+        if text.trim().is_empty() || ctx.transmitting.get_untracked() {
+            return;
+        }
+        draft.set(String::new());
+        spawn_local(run_transmit(ctx, text));
+    };
+
+    let set_frequency = move |ev| {
+        if let Ok(hz) = event_target_value(&ev).parse::<f32>() {
+            ctx.hell_frequency
+                .set(hz.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ));
+        }
+    };
+
+    view! {
+        <div class="hell-panel">
+            <h4>"Feld Hell"</h4>
+            <label>
+                "Tone (Hz) "
+                <input
+                    type="number"
+                    min=MIN_FREQUENCY_HZ
+                    max=MAX_FREQUENCY_HZ
+                    step="10"
+                    prop:value=move || ctx.hell_frequency.get()
+                    on:change=set_frequency
+                />
+            </label>
+            <canvas
+                node_ref=canvas_ref
+                class="hell-canvas"
+                width=DISPLAY_COLUMNS
+                height=DISPLAY_HEIGHT
+                style="display: block; width: 100%; height: 112px; image-rendering: pixelated; background: #fff;"
+            />
+            <button on:click=clear>"Clear"</button>
+            <div class="hell-compose">
+                <input
+                    type="text"
+                    maxlength=TEXT_CAPACITY
+                    placeholder="Text to send"
+                    prop:value=move || draft.get()
+                    on:input=move |ev| draft.set(event_target_value(&ev))
+                    on:keydown=move |ev: web_sys::KeyboardEvent| {
+                        if ev.key() == "Enter" {
+                            send();
+                        }
+                    }
+                />
+                <button
+                    on:click=move |_| send()
+                    disabled=move || ctx.transmitting.get() || draft.with(|d| d.trim().is_empty())
+                >
+                    "Send"
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// Get the 2D drawing context of a canvas.
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+}
+
+/// Scroll the canvas and paint columns (top pixel first, 0 to 255 tone
+/// level) as dark on light, twice over.
+fn draw_columns(
+    context: &CanvasRenderingContext2d,
+    canvas: &HtmlCanvasElement,
+    pixels: &[u8],
+) -> Result<(), JsValue> {
+    let count = (pixels.len() / COLUMN_HEIGHT).min(DISPLAY_COLUMNS);
+    if count == 0 {
+        return Ok(());
+    }
+    context.draw_image_with_html_canvas_element(canvas, -(count as f64), 0.0)?;
+
+    let mut rgba = Vec::with_capacity(count * DISPLAY_HEIGHT * 4);
+    for row in 0..DISPLAY_HEIGHT {
+        for column in 0..count {
+            let shade = 255 - pixels[column * COLUMN_HEIGHT + row % COLUMN_HEIGHT];
+            rgba.extend_from_slice(&[shade, shade, shade, 255]);
+        }
+    }
+    let image = ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(&rgba),
+        count as u32,
+        DISPLAY_HEIGHT as u32,
+    )?;
+    context.put_image_data(&image, (DISPLAY_COLUMNS - count) as f64, 0.0)
+}
@@ -0,0 +1,242 @@
+//! IQ File Playback.
+//!
+//! Decodes a recorded capture (a stereo WAV file, or headerless raw
+//! s16/f32 I/Q pairs) so it can be streamed into the worklet in place of
+//! live input. Captures are resampled to the audio rate before playback,
+//! so demodulators and decoders see the signal at its original speed.
+
+use leptos::*;
+use sdr_dsp_core::resample::IqResampler;
+use sdr_dsp_core::types::IqSample;
+use sdr_dsp_core::wav::{decode_samples, parse_header, SampleFormat};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::state::AppContext;
+
+/// Interval between blocks pushed to the worklet during playback.
+pub const PLAYBACK_INTERVAL_MS: i32 = 50;
+
+/// Sample rate assumed for raw captures until the user picks one.
+pub const DEFAULT_RAW_RATE: f32 = 48000.0;
+
+/// Sample encoding of headerless captures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RawIqFormat {
+    /// Signed 16-bit little-endian pairs
+    #[default]
+    S16,
+    /// 32-bit float little-endian pairs
+    F32,
+}
+
+impl RawIqFormat {
+    /// Get display name for the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RawIqFormat::S16 => "Raw s16",
+            RawIqFormat::F32 => "Raw f32",
+        }
+    }
+
+    /// Get the stored sample encoding.
+    pub fn sample_format(&self) -> SampleFormat {
+        match self {
+            RawIqFormat::S16 => SampleFormat::Pcm16,
+            RawIqFormat::F32 => SampleFormat::Float32,
+        }
+    }
+}
+
+/// A decoded IQ capture.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IqCapture {
+    /// File name
+    pub name: String,
+    /// Capture sample rate in Hz
+    pub sample_rate: f32,
+    /// Interleaved I/Q samples
+    pub samples: Vec<f32>,
+}
+
+impl IqCapture {
+    /// Decode a capture file.
+    ///
+    /// WAV files carry their own rate and encoding; anything without a
+    /// RIFF header is read as raw pairs in `raw_format` at `raw_rate`.
+    pub fn decode(
+        name: &str,
+        bytes: &[u8],
+        raw_format: RawIqFormat,
+        raw_rate: f32,
+    ) -> Result<Self, String> {
+        let (data, sample_format, sample_rate) = match parse_header(bytes) {
+            Some(info) => {
+                if info.format.channels != 2 {
+                    return Err(format!(
+                        "{} has {} channel(s); IQ captures need 2",
+                        name, info.format.channels
+                    ));
+                }
+                let data = &bytes[info.data_offset..info.data_offset + info.data_len];
+                (data, info.sample_format, info.format.sample_rate as f32)
+            }
+            None if bytes.starts_with(b"RIFF") => {
+                return Err(format!("{} is not a 16-bit or float WAV file", name));
+            }
+            None => (bytes, raw_format.sample_format(), raw_rate),
+        };
+
+        let mut samples = vec![0.0; data.len() / sample_format.bytes_per_sample()];
+        let count = decode_samples(data, sample_format, &mut samples);
+        // Drop a trailing unpaired sample
+        samples.truncate(count & !1);
+        if samples.is_empty() {
+            return Err(format!("{} contains no samples", name));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            sample_rate,
+            samples,
+        })
+    }
+
+    /// Number of I/Q pairs.
+    pub fn pairs(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    /// Duration in seconds.
+    pub fn duration_secs(&self) -> f32 {
+        if self.sample_rate <= 0.0 {
+            return 0.0;
+        }
+        self.pairs() as f32 / self.sample_rate
+    }
+
+    /// Interleaved samples converted to `output_rate`.
+    pub fn resampled(&self, output_rate: f32) -> Vec<f32> {
+        let mut resampler = IqResampler::new(self.sample_rate, output_rate);
+        if resampler.is_passthrough() {
+            return self.samples.clone();
+        }
+        let input: Vec<IqSample> = self
+            .samples
+            .chunks_exact(2)
+            .map(|p| IqSample::new(p[0], p[1]))
+            .collect();
+        let mut output = vec![IqSample::default(); resampler.max_output(input.len())];
+        let count = resampler.process(&input, &mut output);
+        output[..count].iter().flat_map(|s| [s.i, s.q]).collect()
+    }
+}
+
+/// Read the contents of a user-selected file.
+pub async fn read_file(file: &web_sys::File) -> Result<Vec<u8>, JsValue> {
+    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Load the file chosen in a file input as the playback capture.
+fn load_selected_file(ctx: AppContext, ev: web_sys::Event) {
+    let Some(file) = ev
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        .and_then(|input| input.files())
+        .and_then(|files| files.get(0))
+    else {
+        return;
+    };
+
+    spawn_local(async move {
+        let bytes = match read_file(&file).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                web_sys::console::error_1(&format!("IQ file read error: {:?}", e).into());
+                return;
+            }
+        };
+        let raw_format = ctx.iq_file_raw_format.get_untracked();
+        let raw_rate = ctx.iq_file_raw_rate.get_untracked();
+        match IqCapture::decode(&file.name(), &bytes, raw_format, raw_rate) {
+            Ok(capture) => {
+                ctx.iq_file_position.set(0.0);
+                ctx.iq_file.set(Some(capture));
+            }
+            Err(e) => {
+                web_sys::console::error_1(&format!("IQ file decode error: {}", e).into());
+                ctx.iq_file.set(None);
+            }
+        }
+    });
+}
+
+/// File picker and playback status for the file IQ source.
+#[component]
+pub fn IqFileControls(ctx: AppContext) -> impl IntoView {
+    let on_format_change = move |ev: web_sys::Event| {
+        let format = match event_target_value(&ev).as_str() {
+            "f32" => RawIqFormat::F32,
+            _ => RawIqFormat::S16,
+        };
+        ctx.iq_file_raw_format.set(format);
+    };
+
+    let status = move || {
+        ctx.iq_file.with(|file| match file {
+            Some(capture) => format!(
+                "{} ({:.1} s @ {:.0} Hz) {:.0}%",
+                capture.name,
+                capture.duration_secs(),
+                capture.sample_rate,
+                ctx.iq_file_position.get() * 100.0
+            ),
+            None => "No file loaded".to_string(),
+        })
+    };
+
+    view! {
+        <div class="iq-file-controls">
+            <input
+                type="file"
+                accept=".wav,.raw,.iq,.bin"
+                disabled=move || ctx.audio_running.get()
+                on:change=move |ev| load_selected_file(ctx, ev)
+            />
+            <label title="Encoding of files without a WAV header">
+                <select on:change=on_format_change>
+                    <option value="s16" selected=move || ctx.iq_file_raw_format.get() == RawIqFormat::S16>
+                        {RawIqFormat::S16.name()}
+                    </option>
+                    <option value="f32" selected=move || ctx.iq_file_raw_format.get() == RawIqFormat::F32>
+                        {RawIqFormat::F32.name()}
+                    </option>
+                </select>
+                <input
+                    type="number"
+                    min="1000"
+                    step="1000"
+                    prop:value=move || ctx.iq_file_raw_rate.get()
+                    on:change=move |ev| {
+                        if let Ok(rate) = event_target_value(&ev).parse::<f32>() {
+                            if rate > 0.0 {
+                                ctx.iq_file_raw_rate.set(rate);
+                            }
+                        }
+                    }
+                />
+                " Hz"
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || ctx.iq_file_loop.get()
+                    on:change=move |ev| ctx.iq_file_loop.set(event_target_checked(&ev))
+                />
+                " Loop"
+            </label>
+            <span class="iq-file-status">{status}</span>
+        </div>
+    }
+}
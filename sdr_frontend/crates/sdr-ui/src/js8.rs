@@ -0,0 +1,243 @@
+//! JS8 style keyboard-to-keyboard chat.
+//!
+//! In JS8 mode the worklet decodes frames at the chosen audio frequency
+//! and sends each one that passes its CRC; they are joined back into
+//! messages here. Outgoing messages are queued as frames and played to
+//! the audio output one per 15 s slot, keying the radio through VOX.
+
+use leptos::*;
+use sdr_mode_js8::{Js8Encoder, Js8Error, Js8Frame, FRAME_SECONDS, MAX_MESSAGE_LEN, SLOT_SECONDS};
+
+use crate::recorder::play_samples;
+use crate::serial::sleep_ms;
+use crate::state::AppContext;
+
+/// Messages kept in the chat.
+pub const MAX_CHAT_MESSAGES: usize = 200;
+
+/// Sample rate of the transmitted audio in Hz.
+pub const TX_SAMPLE_RATE: f32 = 48000.0;
+
+/// Peak level of the transmitted audio (0.0 to 1.0).
+pub const TX_LEVEL: f32 = 0.5;
+
+/// Default audio frequency (lowest tone) in Hz.
+pub const DEFAULT_FREQUENCY_HZ: f32 = 1500.0;
+
+/// Lowest and highest audio frequency offered, in Hz.
+const MIN_FREQUENCY_HZ: f32 = 200.0;
+const MAX_FREQUENCY_HZ: f32 = 3000.0;
+
+/// A message in the chat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Js8Message {
+    /// Time sent or received (ms since the epoch)
+    pub time: f64,
+    /// Message text
+    pub text: String,
+    /// Sent by this station
+    pub outgoing: bool,
+    /// A retransmission
+    pub repeat: bool,
+}
+
+/// Create the transmit encoder.
+pub fn new_encoder(frequency_hz: f32) -> Js8Encoder {
+    Js8Encoder::new(TX_SAMPLE_RATE, TX_LEVEL, frequency_hz)
+}
+
+/// Add a message to the chat, dropping the oldest beyond the limit.
+fn add_message(ctx: &AppContext, text: String, outgoing: bool, repeat: bool) {
+    let message = Js8Message {
+        time: js_sys::Date::now(),
+        text,
+        outgoing,
+        repeat,
+    };
+    ctx.js8_messages.update(|messages| {
+        messages.push(message);
+        let excess = messages.len().saturating_sub(MAX_CHAT_MESSAGES);
+        messages.drain(..excess);
+    });
+}
+
+/// Record a packed frame from the worklet.
+pub fn frame_received(ctx: &AppContext, bytes: &[u8]) {
+    let Some(frame) = Js8Frame::from_bytes(bytes) else {
+        web_sys::console::warn_1(&"JS8: bad frame".into());
+        return;
+    };
+    let message = ctx.js8_assembler.try_update(|a| a.push(&frame)).flatten();
+    if let Some(text) = message {
+        add_message(ctx, text.to_string(), false, frame.repeat);
+    }
+}
+
+/// Describe a queueing error for the chat.
+fn error_text(error: Js8Error) -> String {
+    match error {
+        Js8Error::Empty => "Nothing to send".to_string(),
+        Js8Error::TooLong => format!("Messages are limited to {} characters", MAX_MESSAGE_LEN),
+        Js8Error::QueueFull => "Transmit queue is full".to_string(),
+        Js8Error::NothingToRepeat => "No message to repeat".to_string(),
+    }
+}
+
+/// Send queued frames, one at the start of each slot, until none are left.
+///
+/// Audio goes to the default output, which should be the radio's USB
+/// audio input with VOX enabled.
+async fn run_transmit(ctx: AppContext) {
+    let slot_ms = f64::from(SLOT_SECONDS) * 1000.0;
+    loop {
+        if ctx.js8_encoder.with_value(Js8Encoder::queued_frames) == 0 {
+            break;
+        }
+        let wait_ms = slot_ms - js_sys::Date::now() % slot_ms;
+        sleep_ms(wait_ms as i32).await;
+
+        let samples = ctx.js8_encoder.try_update_value(|encoder| {
+            encoder.start_frame()?;
+            Some(std::iter::from_fn(|| encoder.next_sample()).collect::<Vec<f32>>())
+        });
+        ctx.js8_queued
+            .set(ctx.js8_encoder.with_value(Js8Encoder::queued_frames));
+        let Some(Some(samples)) = samples else {
+            // Stopped while waiting
+            break;
+        };
+        if let Err(e) = play_samples(&samples, TX_SAMPLE_RATE) {
+            web_sys::console::error_1(&format!("JS8: audio output failed: {:?}", e).into());
+            break;
+        }
+        ctx.transmitting.set(true);
+        sleep_ms((FRAME_SECONDS * 1000.0) as i32).await;
+        ctx.transmitting.set(false);
+    }
+    ctx.js8_sending.set(false);
+}
+
+/// Start the transmit loop unless it is already running.
+fn start_sending(ctx: AppContext) {
+    if !ctx.js8_sending.get_untracked() {
+        ctx.js8_sending.set(true);
+        spawn_local(run_transmit(ctx));
+    }
+}
+
+/// Leptos component for JS8 chat.
+#[component]
+pub fn Js8ChatPanel(ctx: AppContext) -> impl IntoView {
+    let draft = create_rw_signal(String::new());
+    let error = create_rw_signal(None::<String>);
+
+    let queue = move |result: Result<usize, Js8Error>| {
+        ctx.js8_queued
+            .set(ctx.js8_encoder.with_value(Js8Encoder::queued_frames));
+        match result {
+            Ok(_) => {
+                error.set(None);
+                start_sending(ctx);
+                true
+            }
+            Err(e) => {
+                error.set(Some(error_text(e)));
+                false
+            }
+        }
+    };
+
+    let send = move || {
+        let text = draft.get_untracked();
+        let result = ctx.js8_encoder.try_update_value(|e| e.queue_text(&text));
+        if queue(result.unwrap_or(Err(Js8Error::QueueFull))) {
+            add_message(&ctx, text.trim().to_uppercase(), true, false);
+            draft.set(String::new());
+        }
+    };
+
+    let repeat = move |_| {
+        let result = ctx.js8_encoder.try_update_value(Js8Encoder::retransmit);
+        queue(result.unwrap_or(Err(Js8Error::NothingToRepeat)));
+    };
+
+    // The transmit loop ends at the next slot (a frame already playing
+    // runs to the end)
+    let stop = move |_| {
+        ctx.js8_encoder.update_value(Js8Encoder::clear);
+        ctx.js8_queued.set(0);
+    };
+
+    let set_frequency = move |ev| {
+        if let Ok(hz) = event_target_value(&ev).parse::<f32>() {
+            let hz = hz.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ);
+            ctx.js8_frequency.set(hz);
+            ctx.js8_encoder.update_value(|e| e.set_frequency(hz));
+        }
+    };
+
+    view! {
+        <div class="js8-panel">
+            <h3>"JS8 Chat"</h3>
+            <label>
+                "Audio frequency (Hz) "
+                <input
+                    type="number"
+                    min=MIN_FREQUENCY_HZ
+                    max=MAX_FREQUENCY_HZ
+                    step="10"
+                    prop:value=move || ctx.js8_frequency.get()
+                    on:change=set_frequency
+                />
+            </label>
+            <div class="js8-chat">
+                {move || ctx.js8_messages.get()
+                    .into_iter()
+                    .map(|message| {
+                        let time = js_sys::Date::new(&message.time.into())
+                            .to_locale_time_string("en-GB");
+                        view! {
+                            <div class="js8-message" class:outgoing=message.outgoing>
+                                <span class="js8-time">{String::from(time)}</span>
+                                <span class="js8-text">
+                                    {message.text}
+                                    {message.repeat.then_some(" (repeat)")}
+                                </span>
+                            </div>
+                        }
+                    })
+                    .collect_view()}
+                {move || ctx.js8_assembler.with(|a| a.partial().map(str::to_string)).map(|text| view! {
+                    <div class="js8-message partial">{text}"…"</div>
+                })}
+            </div>
+            <div class="js8-compose">
+                <input
+                    type="text"
+                    maxlength=MAX_MESSAGE_LEN
+                    placeholder="Message"
+                    prop:value=move || draft.get()
+                    on:input=move |ev| draft.set(event_target_value(&ev))
+                    on:keydown=move |ev: web_sys::KeyboardEvent| {
+                        if ev.key() == "Enter" {
+                            send();
+                        }
+                    }
+                />
+                <button on:click=move |_| send() disabled=move || draft.with(|d| d.trim().is_empty())>
+                    "Send"
+                </button>
+                <button on:click=repeat>"Repeat Last"</button>
+                <button on:click=stop disabled=move || !ctx.js8_sending.get()>"Stop"</button>
+            </div>
+            <div class="js8-status">
+                {move || match (ctx.transmitting.get(), ctx.js8_queued.get()) {
+                    (true, queued) => format!("Transmitting, {} frames queued", queued),
+                    (false, 0) => "Idle".to_string(),
+                    (false, queued) => format!("{} frames queued for the next slot", queued),
+                }}
+            </div>
+            {move || error.get().map(|e| view! { <div class="js8-error">{e}</div> })}
+        </div>
+    }
+}
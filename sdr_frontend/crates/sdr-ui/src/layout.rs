@@ -0,0 +1,75 @@
+//! Responsive layout.
+//!
+//! Switches to a one-column compact layout on narrow screens, such as a
+//! phone connected to the rig over USB-OTG, with the side panels folded
+//! away behind a button. The layout can also be chosen by hand.
+
+use leptos::*;
+
+use crate::state::AppContext;
+
+/// Widest viewport, in CSS pixels, given the compact layout automatically.
+pub const COMPACT_MAX_WIDTH_PX: f64 = 768.0;
+
+/// Layout choice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Compact on narrow screens, desktop otherwise
+    #[default]
+    Auto,
+    /// Always the desktop layout
+    Desktop,
+    /// Always the compact layout
+    Compact,
+}
+
+impl LayoutMode {
+    /// Get all layout choices.
+    pub fn all() -> &'static [LayoutMode] {
+        &[LayoutMode::Auto, LayoutMode::Desktop, LayoutMode::Compact]
+    }
+
+    /// Get display name for the layout.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutMode::Auto => "Auto",
+            LayoutMode::Desktop => "Desktop",
+            LayoutMode::Compact => "Phone",
+        }
+    }
+
+    /// Parse a display name written by [`LayoutMode::name`].
+    pub fn from_name(name: &str) -> Option<LayoutMode> {
+        LayoutMode::all().iter().copied().find(|m| m.name() == name)
+    }
+
+    /// Check if the compact layout applies at a viewport width in CSS pixels.
+    pub fn is_compact(&self, viewport_width: f64) -> bool {
+        match self {
+            LayoutMode::Auto => viewport_width <= COMPACT_MAX_WIDTH_PX,
+            LayoutMode::Desktop => false,
+            LayoutMode::Compact => true,
+        }
+    }
+}
+
+/// Get the viewport width in CSS pixels.
+fn viewport_width() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.inner_width().ok())
+        .and_then(|w| w.as_f64())
+        .unwrap_or(f64::INFINITY)
+}
+
+/// Check if the compact layout is in use (tracked).
+pub fn is_compact(ctx: &AppContext) -> bool {
+    ctx.layout_mode.get().is_compact(ctx.viewport_width.get())
+}
+
+/// Create the window listener that follows the viewport width.
+pub fn create_layout_effect(ctx: AppContext) {
+    ctx.viewport_width.set(viewport_width());
+    let _ = window_event_listener(ev::resize, move |_| {
+        ctx.viewport_width.set(viewport_width());
+    });
+}
@@ -2,16 +2,61 @@
 //!
 //! Provides a browser-based interface for SDR operation including:
 //! - Waterfall display
-//! - Frequency control
-//! - Digital mode decoding
+//! - Frequency control, with keypad entry
+//! - Keyboard shortcuts
+//! - Touch tuning and a compact layout for phones
+//! - Saved UI state and named settings profiles
+//! - Dark and light themes with editable waterfall palettes
+//! - Band selection with condition scores
+//! - Frequency bookmarks
+//! - IQ file playback
+//! - QSO logbook with ADIF export
+//! - Digital mode decoding, with parallel PSK31 channels
+//! - SSTV image reception
+//! - APRS packet decoding with a station list
+//! - JS8 style keyboard-to-keyboard chat
+//! - Feld Hell (Hellschreiber) send and receive
+//! - CW skimmer band activity list
+//! - QRM occupancy surveys
 //! - Radio control via Web Serial
 
 pub mod app;
+pub mod aprs;
 pub mod audio;
+pub mod bookmarks;
 pub mod components;
+pub mod conditions;
+pub mod cw_skimmer;
+pub mod hell;
+pub mod iq_file;
+pub mod js8;
+pub mod layout;
+pub mod logbook;
+pub mod panadapter;
+pub mod psk_channels;
+pub mod qrm;
+pub mod recorder;
 pub mod serial;
+pub mod sstv;
 pub mod state;
+pub mod theme;
+pub mod usb_iq;
 
 pub use app::App;
-pub use audio::{create_audio_effect, AudioPipeline};
-pub use serial::{CatControlPanel, CatProtocol, CatSerial};
+pub use aprs::AprsPanel;
+pub use audio::{create_audio_effect, create_wav_recorder_effect, AudioPipeline};
+pub use bookmarks::{create_bookmark_effect, BookmarkPanel};
+pub use conditions::{create_conditions_effect, record_spot};
+pub use cw_skimmer::CwSkimmerPanel;
+pub use hell::HellPanel;
+pub use iq_file::{IqCapture, IqFileControls};
+pub use js8::Js8ChatPanel;
+pub use layout::{create_layout_effect, LayoutMode};
+pub use logbook::{create_logbook_effect, LogbookPanel};
+pub use panadapter::create_panadapter_effect;
+pub use psk_channels::PskChannelsPanel;
+pub use qrm::QrmPanel;
+pub use recorder::{create_recorder_effect, RecorderPanel};
+pub use usb_iq::{IqSource, UsbIqStream};
+pub use serial::{CatControlPanel, CatProtocol, CatSerial, ConnectionState};
+pub use sstv::SstvPanel;
@@ -0,0 +1,593 @@
+//! QSO logbook.
+//!
+//! Logs contacts with the frequency, mode and time taken from the rig
+//! state, keeps them in IndexedDB and exports them as ADIF. The call and
+//! received report can be filled in from the digital decoder output and
+//! the transmit (keyer) text, so a contact is logged with a few clicks.
+
+use leptos::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::audio::download_bytes;
+use crate::components::{HamBand, RadioMode};
+use crate::recorder::{call_method, idb_request};
+use crate::state::AppContext;
+
+/// IndexedDB database name.
+const DB_NAME: &str = "sdr-logbook";
+
+/// IndexedDB object store holding the QSOs.
+const STORE_NAME: &str = "qsos";
+
+/// ADIF specification version written in the export header.
+const ADIF_VERSION: &str = "3.1.4";
+
+/// Program name written in the export header.
+const PROGRAM_ID: &str = "sdr-ui";
+
+/// Characters of decoded text searched when prefilling.
+const PREFILL_WINDOW: usize = 200;
+
+/// A logged contact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Qso {
+    /// Log key (wall-clock start time in ms since the epoch)
+    pub id: f64,
+    /// Station worked
+    pub call: String,
+    /// Dial frequency in Hz
+    pub frequency: u64,
+    /// Operating mode
+    pub mode: RadioMode,
+    /// Report sent
+    pub rst_sent: String,
+    /// Report received
+    pub rst_rcvd: String,
+    /// Operator name
+    pub name: String,
+    /// Comment
+    pub comment: String,
+    /// Own callsign at the time of the contact
+    pub station_call: String,
+    /// Own Maidenhead locator
+    pub my_grid: String,
+}
+
+impl Qso {
+    /// Get the band containing the contact.
+    pub fn band(&self) -> Option<HamBand> {
+        HamBand::from_frequency(self.frequency)
+    }
+
+    /// Get a short description for the log list.
+    pub fn label(&self) -> String {
+        let date = js_sys::Date::new(&JsValue::from_f64(self.id));
+        format!(
+            "{} {} {:.3} MHz {} {}/{}",
+            adif_time(&date),
+            self.call,
+            self.frequency as f64 / 1_000_000.0,
+            self.mode.name(),
+            self.rst_sent,
+            self.rst_rcvd
+        )
+    }
+
+    /// Encode as one ADIF record (terminated by `<EOR>`).
+    pub fn to_adif(&self) -> String {
+        let date = js_sys::Date::new(&JsValue::from_f64(self.id));
+        let (mode, submode) = adif_mode(self.mode);
+        let mut record = String::new();
+        record.push_str(&adif_field("CALL", &self.call));
+        record.push_str(&adif_field("QSO_DATE", &adif_date(&date)));
+        record.push_str(&adif_field("TIME_ON", &adif_time(&date)));
+        record.push_str(&adif_field(
+            "FREQ",
+            &format!("{:.6}", self.frequency as f64 / 1_000_000.0),
+        ));
+        if let Some(band) = self.band() {
+            record.push_str(&adif_field("BAND", band.name()));
+        }
+        record.push_str(&adif_field("MODE", mode));
+        if let Some(submode) = submode {
+            record.push_str(&adif_field("SUBMODE", submode));
+        }
+        record.push_str(&adif_field("RST_SENT", &self.rst_sent));
+        record.push_str(&adif_field("RST_RCVD", &self.rst_rcvd));
+        record.push_str(&adif_field("NAME", &self.name));
+        record.push_str(&adif_field("COMMENT", &self.comment));
+        record.push_str(&adif_field("STATION_CALLSIGN", &self.station_call));
+        record.push_str(&adif_field("MY_GRIDSQUARE", &self.my_grid));
+        record.push_str("<EOR>\n");
+        record
+    }
+}
+
+/// Encode one ADIF field, or nothing if the value is empty.
+///
+/// ADIF lengths count characters of an ASCII file, so other characters
+/// are replaced.
+pub fn adif_field(name: &str, value: &str) -> String {
+    let value: String = value
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '?' })
+        .collect();
+    if value.is_empty() {
+        return String::new();
+    }
+    format!("<{}:{}>{} ", name, value.len(), value)
+}
+
+/// ADIF MODE and SUBMODE for a radio mode.
+pub fn adif_mode(mode: RadioMode) -> (&'static str, Option<&'static str>) {
+    match mode {
+        RadioMode::Lsb => ("SSB", Some("LSB")),
+        RadioMode::Usb => ("SSB", Some("USB")),
+        RadioMode::Cw => ("CW", None),
+        RadioMode::Am => ("AM", None),
+        RadioMode::Fm => ("FM", None),
+        RadioMode::Psk31 => ("PSK", Some("PSK31")),
+        RadioMode::Rtty => ("RTTY", None),
+        RadioMode::Sstv => ("SSTV", None),
+        RadioMode::Js8 => ("MFSK", Some("JS8")),
+        RadioMode::Hell => ("HELL", None),
+    }
+}
+
+/// UTC date as YYYYMMDD.
+fn adif_date(date: &js_sys::Date) -> String {
+    format!(
+        "{:04}{:02}{:02}",
+        date.get_utc_full_year(),
+        date.get_utc_month() + 1,
+        date.get_utc_date()
+    )
+}
+
+/// UTC time as HHMMSS.
+fn adif_time(date: &js_sys::Date) -> String {
+    format!(
+        "{:02}{:02}{:02}",
+        date.get_utc_hours(),
+        date.get_utc_minutes(),
+        date.get_utc_seconds()
+    )
+}
+
+/// Encode a complete ADIF file.
+pub fn encode_adif(qsos: &[Qso]) -> String {
+    let mut text = String::from("SDR Web UI logbook export\n");
+    text.push_str(&adif_field("ADIF_VER", ADIF_VERSION));
+    text.push_str(&adif_field("PROGRAMID", PROGRAM_ID));
+    text.push_str("<EOH>\n");
+    for qso in qsos {
+        text.push_str(&qso.to_adif());
+    }
+    text
+}
+
+/// Default report for a mode from the S-meter reading.
+///
+/// Phone modes use RS (e.g. "57"), CW and digital modes RST ("579").
+pub fn default_rst(mode: RadioMode, s_units: f32) -> String {
+    let strength = (s_units.round() as i32).clamp(1, 9);
+    match mode {
+        RadioMode::Lsb | RadioMode::Usb | RadioMode::Am | RadioMode::Fm => {
+            format!("5{}", strength)
+        }
+        _ => format!("5{}9", strength),
+    }
+}
+
+/// Check if a word looks like a callsign (letters and digits, with a
+/// digit inside, optionally with a /P style suffix or prefix).
+fn is_callsign(word: &str) -> bool {
+    let base = word
+        .split('/')
+        .max_by_key(|part| part.len())
+        .unwrap_or("");
+    let len_ok = (3..=7).contains(&base.len()) && word.len() <= 12;
+    let chars_ok = word.chars().all(|c| c.is_ascii_alphanumeric() || c == '/');
+    // A digit with letters on both sides of it, e.g. K1ABC or 9A1AA
+    let digit_inside = base
+        .char_indices()
+        .any(|(i, c)| c.is_ascii_digit() && i > 0 && i + 1 < base.len())
+        && base.chars().any(|c| c.is_ascii_alphabetic())
+        && base.chars().last().is_some_and(|c| c.is_ascii_alphabetic());
+    len_ok && chars_ok && digit_inside
+}
+
+/// Check if a word is an RST report (599, 5NN).
+///
+/// Two-digit RS reports are not matched; in decoded text they are
+/// indistinguishable from other numbers.
+fn is_report(word: &str) -> bool {
+    let digits: String = word.chars().map(|c| if c == 'N' { '9' } else { c }).collect();
+    let bytes = digits.as_bytes();
+    bytes.len() == 3
+        && (b'1'..=b'5').contains(&bytes[0])
+        && bytes[1..].iter().all(|b| (b'1'..=b'9').contains(b))
+}
+
+/// Upper-case words of the tail of some decoded text.
+fn recent_words(text: &str) -> Vec<String> {
+    let start = text
+        .char_indices()
+        .rev()
+        .nth(PREFILL_WINDOW)
+        .map_or(0, |(i, _)| i);
+    text[start..]
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '/')
+        .filter(|w| !w.is_empty())
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// Find the most recent callsign in `text` other than `my_call`.
+pub fn find_callsign(text: &str, my_call: &str) -> Option<String> {
+    let my_call = my_call.trim().to_uppercase();
+    recent_words(text)
+        .into_iter()
+        .rev()
+        .find(|w| is_callsign(w) && *w != my_call)
+}
+
+/// Find the most recent signal report in `text`, with cut numbers expanded.
+pub fn find_report(text: &str) -> Option<String> {
+    recent_words(text)
+        .into_iter()
+        .rev()
+        .find(|w| is_report(w))
+        .map(|w| w.replace('N', "9"))
+}
+
+/// IndexedDB-backed QSO storage.
+#[derive(Clone)]
+pub struct LogStore {
+    db: JsValue,
+}
+
+impl LogStore {
+    /// Open (and create if needed) the logbook database.
+    pub async fn open() -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("No window")?;
+        let factory = js_sys::Reflect::get(&window, &"indexedDB".into())?;
+        if factory.is_undefined() {
+            return Err("IndexedDB not available".into());
+        }
+
+        let request = call_method(&factory, "open", &js_sys::Array::of2(&DB_NAME.into(), &1.into()))?;
+
+        // Create the object store on first use
+        let req = request.clone();
+        let on_upgrade = Closure::once_into_js(move |_: JsValue| {
+            if let Ok(db) = js_sys::Reflect::get(&req, &"result".into()) {
+                let options = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&options, &"keyPath".into(), &"id".into());
+                let _ = call_method(
+                    &db,
+                    "createObjectStore",
+                    &js_sys::Array::of2(&STORE_NAME.into(), &options),
+                );
+            }
+        });
+        js_sys::Reflect::set(&request, &"onupgradeneeded".into(), &on_upgrade)?;
+
+        let db = idb_request(request).await?;
+        Ok(Self { db })
+    }
+
+    /// Get the QSO object store in the given transaction mode.
+    fn store(&self, mode: &str) -> Result<JsValue, JsValue> {
+        let tx = call_method(
+            &self.db,
+            "transaction",
+            &js_sys::Array::of2(&STORE_NAME.into(), &mode.into()),
+        )?;
+        call_method(&tx, "objectStore", &js_sys::Array::of1(&STORE_NAME.into()))
+    }
+
+    /// Store a QSO (replacing one with the same id).
+    pub async fn save(&self, qso: &Qso) -> Result<(), JsValue> {
+        let record = js_sys::Object::new();
+        js_sys::Reflect::set(&record, &"id".into(), &qso.id.into())?;
+        js_sys::Reflect::set(&record, &"frequency".into(), &(qso.frequency as f64).into())?;
+        js_sys::Reflect::set(&record, &"mode".into(), &qso.mode.name().into())?;
+        for (key, value) in [
+            ("call", &qso.call),
+            ("rstSent", &qso.rst_sent),
+            ("rstRcvd", &qso.rst_rcvd),
+            ("name", &qso.name),
+            ("comment", &qso.comment),
+            ("stationCall", &qso.station_call),
+            ("myGrid", &qso.my_grid),
+        ] {
+            js_sys::Reflect::set(&record, &key.into(), &value.as_str().into())?;
+        }
+
+        let store = self.store("readwrite")?;
+        let request = call_method(&store, "put", &js_sys::Array::of1(&record))?;
+        idb_request(request).await?;
+        Ok(())
+    }
+
+    /// List stored QSOs (oldest first).
+    pub async fn list(&self) -> Result<Vec<Qso>, JsValue> {
+        let store = self.store("readonly")?;
+        let request = call_method(&store, "getAll", &js_sys::Array::new())?;
+        let records = idb_request(request).await?.dyn_into::<js_sys::Array>()?;
+
+        let number = |record: &JsValue, name: &str| {
+            js_sys::Reflect::get(record, &name.into())
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+        };
+        let text = |record: &JsValue, name: &str| {
+            js_sys::Reflect::get(record, &name.into())
+                .ok()
+                .and_then(|v| v.as_string())
+                .unwrap_or_default()
+        };
+
+        Ok(records
+            .iter()
+            .map(|record| Qso {
+                id: number(&record, "id"),
+                call: text(&record, "call"),
+                frequency: number(&record, "frequency") as u64,
+                mode: RadioMode::from_name(&text(&record, "mode")).unwrap_or(RadioMode::Usb),
+                rst_sent: text(&record, "rstSent"),
+                rst_rcvd: text(&record, "rstRcvd"),
+                name: text(&record, "name"),
+                comment: text(&record, "comment"),
+                station_call: text(&record, "stationCall"),
+                my_grid: text(&record, "myGrid"),
+            })
+            .collect())
+    }
+
+    /// Delete a QSO.
+    pub async fn delete(&self, id: f64) -> Result<(), JsValue> {
+        let store = self.store("readwrite")?;
+        let request = call_method(&store, "delete", &js_sys::Array::of1(&id.into()))?;
+        idb_request(request).await?;
+        Ok(())
+    }
+}
+
+/// Create the effect that loads the logbook.
+///
+/// The own callsign is restored from the most recent QSO.
+pub fn create_logbook_effect(ctx: AppContext) {
+    spawn_local(async move {
+        let result = match LogStore::open().await {
+            Ok(db) => db.list().await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(list) => {
+                if let Some(last) = list.last() {
+                    ctx.my_call.set(last.station_call.clone());
+                }
+                ctx.log_qsos.set(list);
+            }
+            Err(e) => {
+                web_sys::console::error_1(&format!("Logbook storage error: {:?}", e).into());
+            }
+        }
+    });
+}
+
+/// Leptos component for the QSO entry form, log list and ADIF export.
+#[component]
+pub fn LogbookPanel(ctx: AppContext) -> impl IntoView {
+    let qsos = ctx.log_qsos;
+    let call = create_rw_signal(String::new());
+    let rst_sent = create_rw_signal(String::new());
+    let rst_rcvd = create_rw_signal(String::new());
+    let name = create_rw_signal(String::new());
+    let comment = create_rw_signal(String::new());
+    // Time the current contact was first filled in
+    let started_ms = store_value(None::<f64>);
+
+    // Fill the form from decoded text and the keyer/transmit text
+    let prefill = move || {
+        let my_call = ctx.my_call.get_untracked();
+        let rx = ctx.rx_text.get_untracked();
+        let tx = ctx.tx_buffer.get_untracked();
+        if let Some(found) = find_callsign(&tx, &my_call).or_else(|| find_callsign(&rx, &my_call)) {
+            call.set(found);
+        }
+        if let Some(report) = find_report(&rx) {
+            rst_rcvd.set(report);
+        }
+        rst_sent.set(default_rst(ctx.mode.get_untracked(), ctx.smeter.get_untracked()));
+        if started_ms.get_value().is_none() {
+            started_ms.set_value(Some(js_sys::Date::now()));
+        }
+    };
+
+    // Prefill automatically while the form is empty
+    create_effect(move |_| {
+        ctx.rx_text.with(|_| ());
+        ctx.tx_buffer.with(|_| ());
+        if call.with_untracked(String::is_empty) {
+            prefill();
+        }
+    });
+
+    let clear = move || {
+        call.set(String::new());
+        rst_sent.set(String::new());
+        rst_rcvd.set(String::new());
+        name.set(String::new());
+        comment.set(String::new());
+        started_ms.set_value(None);
+    };
+
+    let log = move |_| {
+        let station = call.get_untracked().trim().to_uppercase();
+        if station.is_empty() {
+            return;
+        }
+        let mode = ctx.mode.get_untracked();
+        let rst_default = default_rst(mode, ctx.smeter.get_untracked());
+        let or_default = |value: String| {
+            if value.trim().is_empty() {
+                rst_default.clone()
+            } else {
+                value
+            }
+        };
+        let qso = Qso {
+            id: started_ms.get_value().unwrap_or_else(js_sys::Date::now),
+            call: station,
+            frequency: ctx.frequency.get_untracked(),
+            mode,
+            rst_sent: or_default(rst_sent.get_untracked()),
+            rst_rcvd: or_default(rst_rcvd.get_untracked()),
+            name: name.get_untracked(),
+            comment: comment.get_untracked(),
+            station_call: ctx.my_call.get_untracked().trim().to_uppercase(),
+            my_grid: ctx.home_locator.get_untracked(),
+        };
+        clear();
+
+        spawn_local(async move {
+            let result = match LogStore::open().await {
+                Ok(db) => db.save(&qso).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => qsos.update(|list| list.push(qso)),
+                Err(e) => {
+                    web_sys::console::error_1(&format!("Logbook save error: {:?}", e).into());
+                }
+            }
+        });
+    };
+
+    let delete = move |id: f64| {
+        spawn_local(async move {
+            if let Ok(db) = LogStore::open().await {
+                if db.delete(id).await.is_ok() {
+                    qsos.update(|list| list.retain(|q| q.id != id));
+                }
+            }
+        });
+    };
+
+    let export = move |_| {
+        let text = qsos.with(|list| encode_adif(list));
+        let filename = format!("sdr-log-{}.adi", js_sys::Date::now() as u64);
+        if let Err(e) = download_bytes(&filename, text.as_bytes(), "text/plain") {
+            web_sys::console::error_1(&format!("ADIF export error: {:?}", e).into());
+        }
+    };
+
+    let text_input = move |placeholder: &'static str, signal: RwSignal<String>| {
+        view! {
+            <input
+                type="text"
+                placeholder=placeholder
+                prop:value=move || signal.get()
+                on:input=move |ev| signal.set(event_target_value(&ev))
+            />
+        }
+    };
+
+    view! {
+        <div class="logbook-panel">
+            <h3>"Logbook"</h3>
+            <div class="logbook-entry">
+                {text_input("My call", ctx.my_call)}
+                {text_input("Call", call)}
+                {text_input("RST sent", rst_sent)}
+                {text_input("RST rcvd", rst_rcvd)}
+                {text_input("Name", name)}
+                {text_input("Comment", comment)}
+                <button on:click=move |_| prefill() title="Fill from decoded and transmit text">
+                    "Fill"
+                </button>
+                <button on:click=log>"Log QSO"</button>
+                <button on:click=move |_| clear()>"Clear"</button>
+            </div>
+            <ul class="logbook-list">
+                {move || qsos.get()
+                    .into_iter()
+                    .rev()
+                    .map(|qso| {
+                        let id = qso.id;
+                        view! {
+                            <li class="logbook-qso">
+                                <span class="logbook-label">{qso.label()}</span>
+                                <button on:click=move |_| delete(id)>"Delete"</button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+            <button on:click=export disabled=move || qsos.with(Vec::is_empty)>
+                "Export ADIF"
+            </button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adif_field() {
+        assert_eq!(adif_field("CALL", "K1ABC"), "<CALL:5>K1ABC ");
+        assert_eq!(adif_field("NAME", "  Bob "), "<NAME:3>Bob ");
+        assert_eq!(adif_field("COMMENT", ""), "");
+        assert_eq!(adif_field("COMMENT", "   "), "");
+    }
+
+    #[test]
+    fn test_adif_field_escapes_non_ascii() {
+        // Lengths stay correct for an ASCII reader
+        assert_eq!(adif_field("NAME", "Jürgen"), "<NAME:6>J?rgen ");
+        assert_eq!(adif_field("COMMENT", "a\tb"), "<COMMENT:3>a?b ");
+        // A value holding ADIF markup is kept whole inside its length
+        assert_eq!(adif_field("COMMENT", "<EOR>"), "<COMMENT:5><EOR> ");
+    }
+
+    #[test]
+    fn test_adif_mode() {
+        assert_eq!(adif_mode(RadioMode::Usb), ("SSB", Some("USB")));
+        assert_eq!(adif_mode(RadioMode::Cw), ("CW", None));
+        assert_eq!(adif_mode(RadioMode::Psk31), ("PSK", Some("PSK31")));
+        assert_eq!(adif_mode(RadioMode::Js8), ("MFSK", Some("JS8")));
+    }
+
+    #[test]
+    fn test_encode_adif_header() {
+        let text = encode_adif(&[]);
+        assert!(text.contains("<ADIF_VER:5>3.1.4 "));
+        assert!(text.contains("<PROGRAMID:6>sdr-ui "));
+        assert!(text.ends_with("<EOH>\n"));
+    }
+
+    #[test]
+    fn test_default_rst() {
+        assert_eq!(default_rst(RadioMode::Usb, 7.4), "57");
+        assert_eq!(default_rst(RadioMode::Cw, 9.8), "599");
+        assert_eq!(default_rst(RadioMode::Rtty, 0.0), "519");
+    }
+
+    #[test]
+    fn test_find_callsign_and_report() {
+        let text = "cq cq de k1abc k1abc pse k\nk1abc de w2xyz/p 5nn 5nn tu";
+        assert_eq!(find_callsign(text, "k1abc").as_deref(), Some("W2XYZ/P"));
+        assert_eq!(find_callsign(text, "W2XYZ/P").as_deref(), Some("K1ABC"));
+        assert_eq!(find_report(text).as_deref(), Some("599"));
+        assert_eq!(find_callsign("cq test 73", ""), None);
+        assert_eq!(find_report("rst 57"), None);
+    }
+}
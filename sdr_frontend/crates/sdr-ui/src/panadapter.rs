@@ -0,0 +1,75 @@
+//! Panadapter display range and tuning.
+//!
+//! Tracks the noise floor and strongest signal of the calibrated
+//! spectrum and smooths them into the waterfall's display range, so the
+//! color map follows the band instead of a fixed dB window. Also maps
+//! waterfall clicks back to a dial frequency.
+
+use leptos::*;
+use sdr_dsp_core::spectrum::{noise_floor_db, snap_to_step, DisplayRange};
+
+use crate::components::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+use crate::state::AppContext;
+
+/// Smoothing factor applied to each new range estimate.
+pub const RANGE_SMOOTHING: f32 = 0.1;
+
+/// Audio offset demodulated by the DSP (its default mixing offset), in Hz.
+pub const DEMOD_OFFSET_HZ: f32 = 1500.0;
+
+/// Tuning steps offered for click-to-tune, in Hz.
+pub const TUNING_STEPS: [u64; 5] = [1, 10, 100, 500, 1000];
+
+/// Tune so a signal at `offset_hz` in the spectrum lands in the passband.
+///
+/// The new dial frequency is snapped to the tuning step.
+pub fn tune_to_offset(ctx: &AppContext, offset_hz: f32) {
+    let shift = (offset_hz - DEMOD_OFFSET_HZ).round() as i64;
+    let target = ctx.frequency.get_untracked().saturating_add_signed(shift);
+    ctx.frequency.set(snap_to_step(target, ctx.tuning_step.get_untracked()));
+}
+
+/// Retune by a shift dragged on the waterfall, in whole tuning steps.
+///
+/// `pending` carries the part of a step not yet applied from one call to
+/// the next, so slow drags still tune.
+pub fn drag_tune(ctx: &AppContext, shift_hz: f32, pending: &mut f32) {
+    let step = ctx.tuning_step.get_untracked().max(1);
+    *pending += shift_hz;
+    let steps = (*pending / step as f32).trunc();
+    if steps == 0.0 {
+        return;
+    }
+    *pending -= steps * step as f32;
+    let target = ctx.frequency.get_untracked().saturating_add_signed(steps as i64 * step as i64);
+    ctx.frequency.set(snap_to_step(target, step).clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ));
+}
+
+/// Create the effects that auto-range the waterfall and size its span.
+pub fn create_panadapter_effect(ctx: AppContext) {
+    // Spectrum bins cover audio offsets up to Nyquist
+    create_effect(move |_| {
+        let span_hz = ctx.audio_sample_rate.get() / 2.0;
+        ctx.spectrum_view.update(|view| view.set_span_hz(span_hz));
+    });
+
+    create_effect(move |_| {
+        let Some((floor, peak)) = ctx.spectrum.with(|s| {
+            let floor = noise_floor_db(s)?;
+            let peak = s.iter().copied().reduce(f32::max)?;
+            Some((floor, peak))
+        }) else {
+            return;
+        };
+        ctx.noise_floor.set(Some(floor));
+
+        if !ctx.auto_range.get_untracked() {
+            return;
+        }
+        let target = DisplayRange::auto(floor, peak);
+        ctx.display_range.update(|range| {
+            range.floor_db += RANGE_SMOOTHING * (target.floor_db - range.floor_db);
+            range.range_db += RANGE_SMOOTHING * (target.range_db - range.range_db);
+        });
+    });
+}
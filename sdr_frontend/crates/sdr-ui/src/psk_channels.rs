@@ -0,0 +1,160 @@
+//! Parallel PSK31 channels.
+//!
+//! In PSK31 mode a click on a waterfall trace starts a decoder at that
+//! audio offset in the worklet's decoder bank, without retuning, so
+//! several signals in the passband are read at once. Each channel keeps
+//! its own received text and signal quality readout.
+
+use leptos::*;
+use sdr_dsp_core::quality::SignalQuality;
+
+use crate::state::AppContext;
+
+/// Characters of text kept per channel.
+pub const MAX_CHANNEL_TEXT: usize = 2000;
+
+/// Request to the worklet's decoder bank.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PskCommand {
+    /// Start a decoder at an audio offset (Hz)
+    Add(f32),
+    /// Stop a decoder by channel id
+    Remove(u32),
+    /// Stop every decoder
+    Clear,
+}
+
+/// A running decoder and its text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PskChannel {
+    /// Channel id assigned by the decoder bank
+    pub id: u32,
+    /// Audio offset in Hz
+    pub offset_hz: f32,
+    /// Received text
+    pub text: String,
+    /// Latest SNR, EVM and IMD reported by the decoder
+    pub quality: Option<SignalQuality>,
+    /// Offset followed by AFC in Hz, when known
+    pub tracked_hz: Option<f32>,
+    /// Carrier loop locked
+    pub locked: bool,
+}
+
+/// Record a decoder started by the worklet (ignores repeats and failures).
+pub fn channel_added(ctx: &AppContext, id: i32, offset_hz: f32) {
+    let Ok(id) = u32::try_from(id) else {
+        web_sys::console::warn_1(&"PSK31: all decoders in use".into());
+        return;
+    };
+    ctx.psk_channels.update(|list| {
+        if !list.iter().any(|c| c.id == id) {
+            list.push(PskChannel {
+                id,
+                offset_hz,
+                text: String::new(),
+                quality: None,
+                tracked_hz: None,
+                locked: false,
+            });
+        }
+    });
+}
+
+/// Append decoded text to a channel.
+pub fn append_text(ctx: &AppContext, id: u32, text: &str) {
+    ctx.psk_channels.update(|list| {
+        if let Some(channel) = list.iter_mut().find(|c| c.id == id) {
+            channel.text.push_str(text);
+            let excess = channel.text.chars().count().saturating_sub(MAX_CHANNEL_TEXT);
+            if excess > 0 {
+                let cut = channel
+                    .text
+                    .char_indices()
+                    .nth(excess)
+                    .map_or(channel.text.len(), |(i, _)| i);
+                channel.text.drain(..cut);
+            }
+        }
+    });
+}
+
+/// Update a channel's signal quality.
+pub fn set_quality(ctx: &AppContext, id: u32, quality: SignalQuality) {
+    ctx.psk_channels.update(|list| {
+        if let Some(channel) = list.iter_mut().find(|c| c.id == id) {
+            channel.quality = Some(quality);
+        }
+    });
+}
+
+/// Update a channel's AFC-tracked offset and lock state.
+pub fn set_tracking(ctx: &AppContext, id: u32, tracked_hz: Option<f32>, locked: bool) {
+    ctx.psk_channels.update(|list| {
+        if let Some(channel) = list.iter_mut().find(|c| c.id == id) {
+            channel.tracked_hz = tracked_hz;
+            channel.locked = locked;
+        }
+    });
+}
+
+/// Format a quality readout, e.g. "SNR 18 dB  EVM 12%  IMD -26 dB".
+pub fn quality_label(quality: &SignalQuality) -> String {
+    let imd = match quality.imd_db {
+        Some(imd) => format!("{:.0} dB", imd),
+        None => "--".to_string(),
+    };
+    format!(
+        "SNR {:.0} dB  EVM {:.0}%  IMD {}",
+        quality.snr_db, quality.evm_percent, imd
+    )
+}
+
+/// Leptos component listing the parallel PSK31 channels.
+#[component]
+pub fn PskChannelsPanel(ctx: AppContext) -> impl IntoView {
+    let channels = ctx.psk_channels;
+
+    let remove = move |id: u32| {
+        ctx.psk_command.set(Some(PskCommand::Remove(id)));
+        channels.update(|list| list.retain(|c| c.id != id));
+    };
+
+    let clear = move |_| {
+        ctx.psk_command.set(Some(PskCommand::Clear));
+        channels.set(Vec::new());
+    };
+
+    view! {
+        <div class="psk-channels">
+            <h4>"Channels"</h4>
+            <p class="psk-channels-hint">"Click a trace on the waterfall to decode it"</p>
+            <ul class="psk-channel-list">
+                {move || channels.get()
+                    .into_iter()
+                    .map(|channel| {
+                        let id = channel.id;
+                        view! {
+                            <li class="psk-channel">
+                                <span class="psk-channel-lock" class:locked=channel.locked>
+                                    {if channel.locked { "LOCK" } else { "----" }}
+                                </span>
+                                <span class="psk-channel-offset">
+                                    {format!("{:.0} Hz", channel.tracked_hz.unwrap_or(channel.offset_hz))}
+                                </span>
+                                <span class="psk-channel-quality">
+                                    {channel.quality.as_ref().map(quality_label)}
+                                </span>
+                                <span class="psk-channel-text">{channel.text}</span>
+                                <button on:click=move |_| remove(id)>"Close"</button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+            <button on:click=clear disabled=move || channels.with(Vec::is_empty)>
+                "Close All"
+            </button>
+        </div>
+    }
+}
@@ -0,0 +1,222 @@
+//! QRM occupancy survey.
+//!
+//! Logs how often each spectrum bin in a selected span is above a
+//! threshold over a chosen duration, then exports the statistics as CSV
+//! so a local interference source can be documented and reported.
+
+use leptos::*;
+use sdr_dsp_core::occupancy::{OccupancyConfig, OccupancyRecorder, OccupancySummary};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::state::AppContext;
+
+/// FFT size of the spectrum produced by the worklet.
+const SPECTRUM_SIZE: usize = 512;
+
+/// Default survey length in minutes.
+pub const DEFAULT_DURATION_MIN: f64 = 10.0;
+
+/// Default occupancy threshold in dB.
+pub const DEFAULT_THRESHOLD_DB: f32 = -80.0;
+
+/// A survey in progress.
+struct Survey {
+    recorder: OccupancyRecorder,
+    started_ms: f64,
+    duration_ms: f64,
+}
+
+/// Spectrum bin width in Hz at the given sample rate.
+fn bin_width(sample_rate: f32) -> f64 {
+    f64::from(sample_rate) / SPECTRUM_SIZE as f64
+}
+
+/// Build the survey span from audio offsets in Hz.
+fn survey_config(low_hz: f64, high_hz: f64, threshold_db: f32, sample_rate: f32) -> OccupancyConfig {
+    let width = bin_width(sample_rate);
+    let last_bin = SPECTRUM_SIZE / 2 - 1;
+    let start_bin = ((low_hz.min(high_hz) / width).max(0.0) as usize).min(last_bin);
+    let end_bin = ((low_hz.max(high_hz) / width).max(0.0) as usize).min(last_bin);
+    OccupancyConfig {
+        start_bin,
+        end_bin,
+        threshold_db,
+        frames: 0,
+    }
+}
+
+/// Offer text to the user as a file download.
+fn download_text(filename: &str, text: &str) -> Result<(), JsValue> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/csv");
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("No document")?;
+    let link = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
+/// Leptos component for the QRM survey controls and summary.
+#[component]
+pub fn QrmPanel(ctx: AppContext) -> impl IntoView {
+    let low_hz = create_rw_signal(200.0_f64);
+    let high_hz = create_rw_signal(3000.0_f64);
+    let threshold_db = create_rw_signal(DEFAULT_THRESHOLD_DB);
+    let duration_min = create_rw_signal(DEFAULT_DURATION_MIN);
+    let running = create_rw_signal(false);
+    let progress = create_rw_signal(0.0_f64);
+    let summary = create_rw_signal(None::<OccupancySummary>);
+    let survey = store_value(None::<Survey>);
+
+    // Feed each spectrum frame into the running survey
+    create_effect(move |_| {
+        let spectrum = ctx.spectrum.get();
+        if !running.get_untracked() {
+            return;
+        }
+        let now = js_sys::Date::now();
+        let done = survey
+            .try_update_value(|s| {
+                let s = s.as_mut()?;
+                s.recorder.push(&spectrum);
+                let elapsed = now - s.started_ms;
+                progress.set((elapsed / s.duration_ms).min(1.0));
+                summary.set(Some(s.recorder.summary()));
+                Some(elapsed >= s.duration_ms)
+            })
+            .flatten()
+            .unwrap_or(true);
+        if done {
+            running.set(false);
+        }
+    });
+
+    let start = move |_| {
+        let config = survey_config(
+            low_hz.get_untracked(),
+            high_hz.get_untracked(),
+            threshold_db.get_untracked(),
+            ctx.audio_sample_rate.get_untracked(),
+        );
+        survey.set_value(Some(Survey {
+            recorder: OccupancyRecorder::new(config),
+            started_ms: js_sys::Date::now(),
+            duration_ms: duration_min.get_untracked() * 60_000.0,
+        }));
+        progress.set(0.0);
+        summary.set(None);
+        running.set(true);
+    };
+
+    let stop = move |_| running.set(false);
+
+    let export = move |_| {
+        let dial = ctx.frequency.get_untracked() as f64;
+        let width = bin_width(ctx.audio_sample_rate.get_untracked());
+        let csv = survey.with_value(|s| {
+            s.as_ref().map(|s| {
+                let mut csv = String::new();
+                let _ = s
+                    .recorder
+                    .write_csv(&mut csv, |bin| dial + bin as f64 * width);
+                csv
+            })
+        });
+        if let Some(csv) = csv {
+            let name = format!("qrm-{}.csv", ctx.frequency.get_untracked());
+            if let Err(e) = download_text(&name, &csv) {
+                web_sys::console::error_1(&format!("QRM export error: {:?}", e).into());
+            }
+        }
+    };
+
+    let parse_f64 = |ev: &web_sys::Event| event_target_value(ev).parse::<f64>().ok();
+
+    let summary_text = move || {
+        summary.get().map(|s| {
+            let width = bin_width(ctx.audio_sample_rate.get());
+            format!(
+                "{} frames, {:.1}% mean occupancy, {} busy bins, busiest +{:.0} Hz at {:.0}%, peak {:.0} dB",
+                s.frames,
+                s.mean_percent,
+                s.busy_bins,
+                s.busiest_bin as f64 * width,
+                s.busiest_percent,
+                s.peak_db
+            )
+        })
+    };
+
+    view! {
+        <div class="qrm-panel">
+            <h3>"QRM Survey"</h3>
+            <div class="qrm-controls">
+                <label>
+                    "From (Hz)"
+                    <input
+                        type="number"
+                        min="0"
+                        step="100"
+                        prop:value=move || low_hz.get().to_string()
+                        on:change=move |ev| if let Some(v) = parse_f64(&ev) { low_hz.set(v) }
+                        disabled=move || running.get()
+                    />
+                </label>
+                <label>
+                    "To (Hz)"
+                    <input
+                        type="number"
+                        min="0"
+                        step="100"
+                        prop:value=move || high_hz.get().to_string()
+                        on:change=move |ev| if let Some(v) = parse_f64(&ev) { high_hz.set(v) }
+                        disabled=move || running.get()
+                    />
+                </label>
+                <label>
+                    "Threshold (dB)"
+                    <input
+                        type="number"
+                        step="1"
+                        prop:value=move || threshold_db.get().to_string()
+                        on:change=move |ev| if let Some(v) = parse_f64(&ev) { threshold_db.set(v as f32) }
+                        disabled=move || running.get()
+                    />
+                </label>
+                <label>
+                    "Duration (min)"
+                    <input
+                        type="number"
+                        min="1"
+                        step="1"
+                        prop:value=move || duration_min.get().to_string()
+                        on:change=move |ev| if let Some(v) = parse_f64(&ev) { duration_min.set(v.max(1.0)) }
+                        disabled=move || running.get()
+                    />
+                </label>
+            </div>
+            <div class="qrm-actions">
+                {move || if running.get() {
+                    view! { <button on:click=stop>"Stop"</button> }
+                } else {
+                    view! { <button on:click=start disabled=move || !ctx.audio_running.get()>"Start"</button> }
+                }}
+                <button on:click=export disabled=move || running.get() || summary.get().is_none()>
+                    "Export CSV"
+                </button>
+                <progress max="1" prop:value=move || progress.get() />
+            </div>
+            <p class="qrm-summary">{summary_text}</p>
+        </div>
+    }
+}
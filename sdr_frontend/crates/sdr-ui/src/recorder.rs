@@ -0,0 +1,502 @@
+//! Activity-triggered receive audio recorder.
+//!
+//! Captures demodulated audio when the squelch opens or the digital
+//! decoder produces text, closes the clip after a quiet tail time, and
+//! keeps the clips in IndexedDB so they survive a page reload.
+
+use leptos::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::state::AppContext;
+
+/// Default quiet time before a clip is closed, in milliseconds.
+pub const DEFAULT_TAIL_MS: f64 = 3000.0;
+
+/// Maximum clip length in milliseconds.
+pub const MAX_CLIP_MS: f64 = 300_000.0;
+
+/// IndexedDB database name.
+const DB_NAME: &str = "sdr-recorder";
+
+/// IndexedDB object store holding the clips.
+const STORE_NAME: &str = "clips";
+
+/// What started a recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordTrigger {
+    /// Squelch opened (S-meter above threshold)
+    Squelch,
+    /// Digital decoder produced text
+    Decoder,
+}
+
+impl RecordTrigger {
+    /// Get display name for the trigger.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecordTrigger::Squelch => "SQL",
+            RecordTrigger::Decoder => "DEC",
+        }
+    }
+
+    /// Parse a stored trigger name.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "DEC" => RecordTrigger::Decoder,
+            _ => RecordTrigger::Squelch,
+        }
+    }
+}
+
+/// Metadata for a stored clip.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipInfo {
+    /// Clip key (wall-clock start time in ms since the epoch)
+    pub id: f64,
+    /// Clip length in milliseconds
+    pub duration_ms: f64,
+    /// Receive frequency in Hz
+    pub frequency: u64,
+    /// Operating mode name
+    pub mode: String,
+    /// Trigger that started the clip
+    pub trigger: RecordTrigger,
+    /// Audio sample rate in Hz
+    pub sample_rate: f32,
+}
+
+impl ClipInfo {
+    /// Get a short description for the clip list.
+    pub fn label(&self) -> String {
+        let date = js_sys::Date::new(&JsValue::from_f64(self.id));
+        format!(
+            "{} {:.3} MHz {} [{}] {:.1}s",
+            String::from(date.to_locale_time_string("en-GB")),
+            self.frequency as f64 / 1_000_000.0,
+            self.mode,
+            self.trigger.name(),
+            self.duration_ms / 1000.0
+        )
+    }
+}
+
+/// A finished recording ready to be stored.
+pub struct FinishedClip {
+    /// Trigger that started the clip
+    pub trigger: RecordTrigger,
+    /// Wall-clock start time in ms since the epoch
+    pub started_ms: f64,
+    /// Clip length in milliseconds
+    pub duration_ms: f64,
+    /// Captured audio samples
+    pub samples: Vec<f32>,
+}
+
+/// Trigger state machine and sample accumulator.
+pub struct ActivityRecorder {
+    tail_ms: f64,
+    recording: Option<(RecordTrigger, f64)>,
+    last_activity_ms: f64,
+    samples: Vec<f32>,
+}
+
+impl ActivityRecorder {
+    /// Create a new recorder with the given tail time.
+    pub fn new(tail_ms: f64) -> Self {
+        Self {
+            tail_ms,
+            recording: None,
+            last_activity_ms: 0.0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Check if a clip is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Register activity, returns true if a new clip was started.
+    pub fn activity(&mut self, trigger: RecordTrigger, now_ms: f64) -> bool {
+        self.last_activity_ms = now_ms;
+        if self.recording.is_none() {
+            self.recording = Some((trigger, now_ms));
+            self.samples.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Append audio to the current clip (ignored when idle).
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        if self.recording.is_some() {
+            self.samples.extend_from_slice(samples);
+        }
+    }
+
+    /// Close the clip if the tail time or length limit has passed.
+    pub fn poll(&mut self, now_ms: f64) -> Option<FinishedClip> {
+        let (_, started_ms) = self.recording?;
+        let quiet = now_ms - self.last_activity_ms >= self.tail_ms;
+        let too_long = now_ms - started_ms >= MAX_CLIP_MS;
+        if quiet || too_long {
+            self.finish(now_ms)
+        } else {
+            None
+        }
+    }
+
+    /// Close the current clip immediately.
+    pub fn finish(&mut self, now_ms: f64) -> Option<FinishedClip> {
+        let (trigger, started_ms) = self.recording.take()?;
+        Some(FinishedClip {
+            trigger,
+            started_ms,
+            duration_ms: now_ms - started_ms,
+            samples: std::mem::take(&mut self.samples),
+        })
+    }
+}
+
+/// Wrap an IDBRequest in a future resolving to its result.
+pub(crate) async fn idb_request(request: JsValue) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let req = request.clone();
+        let on_success = Closure::once_into_js(move |_: JsValue| {
+            let result = js_sys::Reflect::get(&req, &"result".into()).unwrap_or(JsValue::NULL);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        });
+        let req = request.clone();
+        let on_error = Closure::once_into_js(move |_: JsValue| {
+            let error = js_sys::Reflect::get(&req, &"error".into()).unwrap_or(JsValue::NULL);
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+        let _ = js_sys::Reflect::set(&request, &"onsuccess".into(), &on_success);
+        let _ = js_sys::Reflect::set(&request, &"onerror".into(), &on_error);
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await
+}
+
+/// Call a method on a JS object by name.
+pub(crate) fn call_method(target: &JsValue, name: &str, args: &js_sys::Array) -> Result<JsValue, JsValue> {
+    let method = js_sys::Reflect::get(target, &name.into())?.dyn_into::<js_sys::Function>()?;
+    method.apply(target, args)
+}
+
+/// IndexedDB-backed clip storage.
+#[derive(Clone)]
+pub struct ClipStore {
+    db: JsValue,
+}
+
+impl ClipStore {
+    /// Open (and create if needed) the clip database.
+    pub async fn open() -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("No window")?;
+        let factory = js_sys::Reflect::get(&window, &"indexedDB".into())?;
+        if factory.is_undefined() {
+            return Err("IndexedDB not available".into());
+        }
+
+        let request = call_method(&factory, "open", &js_sys::Array::of2(&DB_NAME.into(), &1.into()))?;
+
+        // Create the object store on first use
+        let req = request.clone();
+        let on_upgrade = Closure::once_into_js(move |_: JsValue| {
+            if let Ok(db) = js_sys::Reflect::get(&req, &"result".into()) {
+                let options = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&options, &"keyPath".into(), &"id".into());
+                let _ = call_method(
+                    &db,
+                    "createObjectStore",
+                    &js_sys::Array::of2(&STORE_NAME.into(), &options),
+                );
+            }
+        });
+        js_sys::Reflect::set(&request, &"onupgradeneeded".into(), &on_upgrade)?;
+
+        let db = idb_request(request).await?;
+        Ok(Self { db })
+    }
+
+    /// Get the clip object store in the given transaction mode.
+    fn store(&self, mode: &str) -> Result<JsValue, JsValue> {
+        let tx = call_method(
+            &self.db,
+            "transaction",
+            &js_sys::Array::of2(&STORE_NAME.into(), &mode.into()),
+        )?;
+        call_method(&tx, "objectStore", &js_sys::Array::of1(&STORE_NAME.into()))
+    }
+
+    /// Store a clip with its audio.
+    pub async fn save(&self, info: &ClipInfo, samples: &[f32]) -> Result<(), JsValue> {
+        let record = js_sys::Object::new();
+        js_sys::Reflect::set(&record, &"id".into(), &info.id.into())?;
+        js_sys::Reflect::set(&record, &"durationMs".into(), &info.duration_ms.into())?;
+        js_sys::Reflect::set(&record, &"frequency".into(), &(info.frequency as f64).into())?;
+        js_sys::Reflect::set(&record, &"mode".into(), &info.mode.as_str().into())?;
+        js_sys::Reflect::set(&record, &"trigger".into(), &info.trigger.name().into())?;
+        js_sys::Reflect::set(&record, &"sampleRate".into(), &info.sample_rate.into())?;
+        js_sys::Reflect::set(
+            &record,
+            &"samples".into(),
+            &js_sys::Float32Array::from(samples).into(),
+        )?;
+
+        let store = self.store("readwrite")?;
+        let request = call_method(&store, "put", &js_sys::Array::of1(&record))?;
+        idb_request(request).await?;
+        Ok(())
+    }
+
+    /// List stored clips (oldest first).
+    pub async fn list(&self) -> Result<Vec<ClipInfo>, JsValue> {
+        let store = self.store("readonly")?;
+        let request = call_method(&store, "getAll", &js_sys::Array::new())?;
+        let records = idb_request(request).await?.dyn_into::<js_sys::Array>()?;
+
+        let number = |record: &JsValue, name: &str| {
+            js_sys::Reflect::get(record, &name.into())
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+        };
+        let text = |record: &JsValue, name: &str| {
+            js_sys::Reflect::get(record, &name.into())
+                .ok()
+                .and_then(|v| v.as_string())
+                .unwrap_or_default()
+        };
+
+        Ok(records
+            .iter()
+            .map(|record| ClipInfo {
+                id: number(&record, "id"),
+                duration_ms: number(&record, "durationMs"),
+                frequency: number(&record, "frequency") as u64,
+                mode: text(&record, "mode"),
+                trigger: RecordTrigger::from_name(&text(&record, "trigger")),
+                sample_rate: number(&record, "sampleRate") as f32,
+            })
+            .collect())
+    }
+
+    /// Load the audio samples of a clip.
+    pub async fn load_samples(&self, id: f64) -> Result<Vec<f32>, JsValue> {
+        let store = self.store("readonly")?;
+        let request = call_method(&store, "get", &js_sys::Array::of1(&id.into()))?;
+        let record = idb_request(request).await?;
+        let samples = js_sys::Reflect::get(&record, &"samples".into())?
+            .dyn_into::<js_sys::Float32Array>()?;
+        Ok(samples.to_vec())
+    }
+
+    /// Delete a clip.
+    pub async fn delete(&self, id: f64) -> Result<(), JsValue> {
+        let store = self.store("readwrite")?;
+        let request = call_method(&store, "delete", &js_sys::Array::of1(&id.into()))?;
+        idb_request(request).await?;
+        Ok(())
+    }
+}
+
+/// Play back samples through a one-shot AudioContext.
+pub fn play_samples(samples: &[f32], sample_rate: f32) -> Result<(), JsValue> {
+    let ctx = web_sys::AudioContext::new()?;
+    let buffer = ctx.create_buffer(1, samples.len().max(1) as u32, sample_rate)?;
+    buffer.copy_to_channel(samples, 0)?;
+
+    let source = ctx.create_buffer_source()?;
+    source.set_buffer(Some(&buffer));
+    source.connect_with_audio_node(&ctx.destination())?;
+    source.start()?;
+    Ok(())
+}
+
+/// Create an effect that runs the activity recorder from app state.
+///
+/// Squelch is derived from the S-meter against `recorder_squelch`;
+/// decoder activity is any growth of the received text.
+pub fn create_recorder_effect(ctx: AppContext) {
+    let recorder = store_value(ActivityRecorder::new(DEFAULT_TAIL_MS));
+    let store = store_value(None::<ClipStore>);
+    let last_rx_len = store_value(0usize);
+
+    // Open the database and load the clip list
+    let clips = ctx.recorder_clips;
+    spawn_local(async move {
+        match ClipStore::open().await {
+            Ok(db) => {
+                if let Ok(list) = db.list().await {
+                    clips.set(list);
+                }
+                store.set_value(Some(db));
+            }
+            Err(e) => {
+                web_sys::console::error_1(&format!("Recorder storage error: {:?}", e).into());
+            }
+        }
+    });
+
+    // Decoder activity
+    let ctx_decoder = ctx.clone();
+    create_effect(move |_| {
+        let len = ctx_decoder.rx_text.with(|t| t.len());
+        let grew = last_rx_len.with_value(|last| len > *last);
+        last_rx_len.set_value(len);
+        if grew && ctx_decoder.recorder_enabled.get_untracked() {
+            recorder.update_value(|r| {
+                r.activity(RecordTrigger::Decoder, js_sys::Date::now());
+            });
+        }
+    });
+
+    // Squelch activity and audio capture, driven by the audio tap
+    create_effect(move |_| {
+        let block = ctx.audio_tap.get();
+        let now = js_sys::Date::now();
+
+        if !ctx.recorder_enabled.get_untracked() {
+            let finished = recorder.try_update_value(|r| r.finish(now)).flatten();
+            ctx.recorder_active.set(false);
+            if let Some(clip) = finished {
+                save_clip(&ctx, store, clip);
+            }
+            return;
+        }
+
+        if ctx.smeter.get_untracked() >= ctx.recorder_squelch.get_untracked() {
+            recorder.update_value(|r| {
+                r.activity(RecordTrigger::Squelch, now);
+            });
+        }
+
+        let finished = recorder
+            .try_update_value(|r| {
+                r.push_samples(&block);
+                r.poll(now)
+            })
+            .flatten();
+        ctx.recorder_active
+            .set(recorder.with_value(ActivityRecorder::is_recording));
+
+        if let Some(clip) = finished {
+            save_clip(&ctx, store, clip);
+        }
+    });
+}
+
+/// Store a finished clip and add it to the clip list.
+fn save_clip(ctx: &AppContext, store: StoredValue<Option<ClipStore>>, clip: FinishedClip) {
+    let info = ClipInfo {
+        id: clip.started_ms,
+        duration_ms: clip.duration_ms,
+        frequency: ctx.frequency.get_untracked(),
+        mode: ctx.mode.get_untracked().name().to_string(),
+        trigger: clip.trigger,
+        sample_rate: ctx.audio_sample_rate.get_untracked(),
+    };
+    let clips = ctx.recorder_clips;
+    let Some(db) = store.get_value() else {
+        return;
+    };
+
+    spawn_local(async move {
+        match db.save(&info, &clip.samples).await {
+            Ok(()) => clips.update(|list| list.push(info)),
+            Err(e) => {
+                web_sys::console::error_1(&format!("Recorder save error: {:?}", e).into());
+            }
+        }
+    });
+}
+
+/// Leptos component for the recorder controls and clip list.
+#[component]
+pub fn RecorderPanel(ctx: AppContext) -> impl IntoView {
+    let enabled = ctx.recorder_enabled;
+    let active = ctx.recorder_active;
+    let squelch = ctx.recorder_squelch;
+    let clips = ctx.recorder_clips;
+
+    let on_squelch = move |ev: web_sys::Event| {
+        if let Ok(value) = event_target_value(&ev).parse::<f32>() {
+            squelch.set(value);
+        }
+    };
+
+    let play = move |clip: ClipInfo| {
+        spawn_local(async move {
+            let result = match ClipStore::open().await {
+                Ok(db) => match db.load_samples(clip.id).await {
+                    Ok(samples) => play_samples(&samples, clip.sample_rate),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                web_sys::console::error_1(&format!("Playback error: {:?}", e).into());
+            }
+        });
+    };
+
+    let delete = move |id: f64| {
+        spawn_local(async move {
+            if let Ok(db) = ClipStore::open().await {
+                if db.delete(id).await.is_ok() {
+                    clips.update(|list| list.retain(|c| c.id != id));
+                }
+            }
+        });
+    };
+
+    view! {
+        <div class="recorder-panel">
+            <h3>"Recorder"</h3>
+            <div class="recorder-controls">
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || enabled.get()
+                        on:change=move |_| enabled.update(|v| *v = !*v)
+                    />
+                    "Armed"
+                </label>
+                <span class="recording-indicator" class:active=move || active.get()>
+                    "REC"
+                </span>
+                <label>
+                    "Squelch"
+                    <input
+                        type="range"
+                        min="0"
+                        max="1.5"
+                        step="0.05"
+                        prop:value=move || squelch.get().to_string()
+                        on:input=on_squelch
+                    />
+                </label>
+            </div>
+            <ul class="clip-list">
+                {move || clips.get()
+                    .into_iter()
+                    .rev()
+                    .map(|clip| {
+                        let id = clip.id;
+                        let label = clip.label();
+                        view! {
+                            <li class="clip">
+                                <span class="clip-label">{label}</span>
+                                <button on:click=move |_| play(clip.clone())>"Play"</button>
+                                <button on:click=move |_| delete(id)>"Delete"</button>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ul>
+        </div>
+    }
+}
@@ -12,6 +12,59 @@ use wasm_bindgen::JsCast;
 
 use crate::state::AppContext;
 
+/// Start byte of a binary panadapter frame.
+pub const PANADAPTER_FRAME_START: u8 = 0xFE;
+
+/// Spectrum frame streamed by the radio alongside CAT (`ZZ` command).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanadapterFrame {
+    /// Sequence number; a gap means frames were dropped.
+    pub seq: u16,
+    /// Radio timestamp in milliseconds.
+    pub time_ms: u32,
+    /// Centre frequency in Hz.
+    pub center_hz: u64,
+    /// Span in Hz.
+    pub span_hz: u32,
+    /// Power per bin, 0 (-100 dB) to 100 (0 dB).
+    pub power: Vec<u8>,
+}
+
+/// Signal in a panadapter peak list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PanadapterPeak {
+    /// Frequency in Hz.
+    pub frequency_hz: u64,
+    /// Level in dB, -100 to 0.
+    pub level_db: i8,
+    /// Width 6 dB down in Hz.
+    pub width_hz: u16,
+}
+
+/// Strongest signals in a panadapter frame (`ZZP` command), strongest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanadapterPeaks {
+    /// Sequence number of the spectrum frame the peaks were found in.
+    pub seq: u16,
+    /// Peaks, strongest first.
+    pub peaks: Vec<PanadapterPeak>,
+}
+
+/// CRC-8 (polynomial 0x07) used by the radio's binary frames.
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
 /// CAT (Computer Aided Transceiver) command protocol.
 ///
 /// Implements a subset of the Kenwood TS-2000 protocol.
@@ -43,6 +96,230 @@ impl CatProtocol {
         format!("TX{};", if transmit { 1 } else { 0 })
     }
 
+    /// Create VOX on/off command.
+    pub fn vox_set(enabled: bool) -> String {
+        format!("VX{};", if enabled { 1 } else { 0 })
+    }
+
+    /// Create VOX gain command (0-100).
+    pub fn vox_gain_set(gain: u8) -> String {
+        format!("VG{:03};", gain.min(100))
+    }
+
+    /// Create VOX delay command in milliseconds (0-3000).
+    pub fn vox_delay_set(ms: u16) -> String {
+        format!("VD{:04};", ms.min(3000))
+    }
+
+    /// Create anti-VOX gain command (0-100).
+    pub fn anti_vox_set(gain: u8) -> String {
+        format!("ZA{:03};", gain.min(100))
+    }
+
+    /// Create clock set command from the host's UTC time (ZT20240601120000;).
+    pub fn clock_set(date: &js_sys::Date) -> String {
+        format!(
+            "ZT{:04}{:02}{:02}{:02}{:02}{:02};",
+            date.get_utc_full_year(),
+            date.get_utc_month() + 1,
+            date.get_utc_date(),
+            date.get_utc_hours(),
+            date.get_utc_minutes(),
+            date.get_utc_seconds()
+        )
+    }
+
+    /// Create antenna sweep start command (ZW1 start Hz, end Hz, points).
+    pub fn sweep_start(start_hz: u64, end_hz: u64, points: u8) -> String {
+        format!("ZW1{:011}{:011}{:03};", start_hz, end_hz, points)
+    }
+
+    /// Create antenna sweep stop command.
+    pub fn sweep_stop() -> &'static str {
+        "ZW0;"
+    }
+
+    /// Parse antenna sweep point (ZWP003000071020000150;) into the point
+    /// index, frequency in Hz and SWR.
+    pub fn parse_sweep_point(response: &str) -> Option<(u8, u64, f32)> {
+        let body = response.strip_prefix("ZWP")?.strip_suffix(';')?;
+        if body.len() != 18 {
+            return None;
+        }
+        let index = body.get(..3)?.parse().ok()?;
+        let hz = body.get(3..14)?.parse().ok()?;
+        let swr: u16 = body.get(14..)?.parse().ok()?;
+        Some((index, hz, f32::from(swr) / 100.0))
+    }
+
+    /// Create battery gauge query command.
+    pub fn battery_query() -> &'static str {
+        "ZE;"
+    }
+
+    /// Create low battery voltage command in mV (TX power is reduced below it).
+    pub fn low_battery_set(mv: u16) -> String {
+        format!("ZEL{:05};", mv)
+    }
+
+    /// Parse battery gauge response (ZE08712480+1350;) into the state of
+    /// charge in percent (None until estimated), voltage in mV and
+    /// current in mA (positive when discharging).
+    pub fn parse_battery(response: &str) -> Option<(Option<u8>, u32, i32)> {
+        let body = response.strip_prefix("ZE")?.strip_suffix(';')?;
+        if body.len() != 13 {
+            return None;
+        }
+        let soc = match body.get(..3)? {
+            "---" => None,
+            digits => Some(digits.parse().ok()?),
+        };
+        let mv = body.get(3..8)?.parse().ok()?;
+        let ma = body.get(8..)?.parse().ok()?;
+        Some((soc, mv, ma))
+    }
+
+    /// Create crystal correction query command.
+    pub fn xtal_correction_query() -> &'static str {
+        "ZK;"
+    }
+
+    /// Create crystal correction command in parts per billion.
+    pub fn xtal_correction_set(ppb: i32) -> String {
+        format!("ZK{:+07};", ppb)
+    }
+
+    /// Create crystal calibration command from the frequency an output was
+    /// set to and the frequency it was measured at, in Hz.
+    pub fn xtal_calibrate(expected_hz: u64, measured_hz: u64) -> String {
+        format!("ZKM{:011}{:011};", expected_hz, measured_hz)
+    }
+
+    /// Parse crystal correction response (ZK+012000;) into parts per billion.
+    pub fn parse_xtal_correction(response: &str) -> Option<i32> {
+        response
+            .strip_prefix("ZK")?
+            .strip_suffix(';')?
+            .parse()
+            .ok()
+    }
+
+    /// Create keyer practice mode command (transmit is blocked while on).
+    pub fn practice_set(enabled: bool) -> String {
+        format!("ZY{};", if enabled { 1 } else { 0 })
+    }
+
+    /// Create keyer practice report query command.
+    pub fn practice_query() -> &'static str {
+        "ZY;"
+    }
+
+    /// Parse keyer practice report (ZY1182009331;) into practice mode,
+    /// measured and set speed in WPM, timing accuracy in percent and the
+    /// dah to dit ratio in tenths.
+    pub fn parse_practice(response: &str) -> Option<(bool, u8, u8, u8, u8)> {
+        let body = response.strip_prefix("ZY")?.strip_suffix(';')?;
+        if body.len() != 10 || !body.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let enabled = body.get(..1)? == "1";
+        let wpm = body.get(1..3)?.parse().ok()?;
+        let target_wpm = body.get(3..5)?.parse().ok()?;
+        let accuracy = body.get(5..8)?.parse().ok()?;
+        let dah_ratio = body.get(8..)?.parse().ok()?;
+        Some((enabled, wpm, target_wpm, accuracy, dah_ratio))
+    }
+
+    /// Parse a character decoded in keyer practice (ZYTK;).
+    pub fn parse_practice_char(response: &str) -> Option<char> {
+        let mut chars = response.strip_prefix("ZYT")?.strip_suffix(';')?.chars();
+        let ch = chars.next()?;
+        chars.next().is_none().then_some(ch)
+    }
+
+    /// Create panadapter stream command (10-30 frames per second, 0 stops).
+    pub fn panadapter_set(fps: u8) -> String {
+        format!("ZZ{:02};", fps)
+    }
+
+    /// Create panadapter stream state query command.
+    pub fn panadapter_query() -> &'static str {
+        "ZZ;"
+    }
+
+    /// Parse panadapter stream state (ZZ1500003;) into the rate in frames
+    /// per second and the frames the radio dropped.
+    pub fn parse_panadapter(response: &str) -> Option<(u8, u32)> {
+        let body = response.strip_prefix("ZZ")?.strip_suffix(';')?;
+        if body.len() != 7 || !body.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((body.get(..2)?.parse().ok()?, body.get(2..)?.parse().ok()?))
+    }
+
+    /// Parse a binary panadapter frame, start bytes to CRC.
+    ///
+    /// Frames start with [`PANADAPTER_FRAME_START`], which never occurs
+    /// in CAT text, so the serial reader splits them out of the stream.
+    pub fn parse_panadapter_frame(frame: &[u8]) -> Option<PanadapterFrame> {
+        let body = frame.strip_prefix(&[PANADAPTER_FRAME_START, b'P'])?;
+        let (&crc, body) = body.split_last()?;
+        if crc8(body) != crc {
+            return None;
+        }
+        let word = |at: usize| Some(u32::from_le_bytes(body.get(at..at + 4)?.try_into().ok()?));
+        let bins = usize::from(*body.get(14)?);
+        Some(PanadapterFrame {
+            seq: u16::from_le_bytes(body.get(..2)?.try_into().ok()?),
+            time_ms: word(2)?,
+            center_hz: u64::from(word(6)?),
+            span_hz: word(10)?,
+            power: body.get(15..15 + bins)?.to_vec(),
+        })
+    }
+
+    /// Create panadapter peak list command (peaks per frame, up to 8; 0 stops).
+    pub fn panadapter_peaks_set(peaks: u8) -> String {
+        format!("ZZP{:02};", peaks)
+    }
+
+    /// Parse peaks listed per panadapter frame (ZZP08;).
+    pub fn parse_panadapter_peaks(response: &str) -> Option<u8> {
+        let body = response.strip_prefix("ZZP")?.strip_suffix(';')?;
+        if body.len() != 2 || !body.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        body.parse().ok()
+    }
+
+    /// Parse a binary panadapter peak list, start bytes to CRC, into
+    /// absolute frequencies.
+    pub fn parse_panadapter_peaks_frame(frame: &[u8]) -> Option<PanadapterPeaks> {
+        let body = frame.strip_prefix(&[PANADAPTER_FRAME_START, b'K'])?;
+        let (&crc, body) = body.split_last()?;
+        if crc8(body) != crc {
+            return None;
+        }
+        let center = i64::from(u32::from_le_bytes(body.get(2..6)?.try_into().ok()?));
+        let count = usize::from(*body.get(6)?);
+        let peaks = body
+            .get(7..7 + count * 7)?
+            .chunks_exact(7)
+            .map(|entry| {
+                let offset = i32::from_le_bytes(entry[..4].try_into().ok()?);
+                Some(PanadapterPeak {
+                    frequency_hz: u64::try_from(center + i64::from(offset)).ok()?,
+                    level_db: i8::from_le_bytes([entry[4]]),
+                    width_hz: u16::from_le_bytes(entry[5..].try_into().ok()?),
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(PanadapterPeaks {
+            seq: u16::from_le_bytes(body.get(..2)?.try_into().ok()?),
+            peaks,
+        })
+    }
+
     /// Parse frequency response (FA00014070000;).
     pub fn parse_frequency(response: &str) -> Option<u64> {
         if response.starts_with("FA") && response.ends_with(';') {
@@ -64,13 +341,154 @@ impl CatProtocol {
     }
 }
 
+/// Baud rates offered in the port configuration UI.
+pub const BAUD_RATES: &[u32] = &[4800, 9600, 19200, 38400, 57600, 115200];
+
+/// Default CAT baud rate.
+pub const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// Maximum automatic reconnect attempts before giving up.
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Delay between reconnect attempts in milliseconds.
+pub const RECONNECT_INTERVAL_MS: i32 = 2000;
+
+/// CAT serial connection state machine.
+///
+/// ```text
+/// Disconnected -> Connecting -> Connected
+///                      |            |
+///                      v            v (port lost)
+///                   Failed <- Reconnecting
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    /// No port open
+    #[default]
+    Disconnected,
+    /// Opening a port
+    Connecting,
+    /// Port open and usable
+    Connected,
+    /// Port lost, trying to reopen it
+    Reconnecting {
+        /// Current attempt number (1-based)
+        attempt: u32,
+    },
+    /// Connection failed or reconnect gave up
+    Failed,
+}
+
+/// Something that moves the connection state on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// Operator asked to open a port
+    Connect,
+    /// Port opened
+    Opened,
+    /// Opening the port failed
+    OpenFailed,
+    /// Open port vanished
+    PortLost {
+        /// Try to reopen it
+        auto_reconnect: bool,
+    },
+    /// Reconnect attempt did not find the port
+    RetryFailed,
+    /// Operator closed the port
+    Disconnect,
+}
+
+impl ConnectionState {
+    /// Get status text for display.
+    pub fn label(&self) -> String {
+        match self {
+            ConnectionState::Disconnected => "Disconnected".to_string(),
+            ConnectionState::Connecting => "Connecting...".to_string(),
+            ConnectionState::Connected => "Connected".to_string(),
+            ConnectionState::Reconnecting { attempt } => {
+                format!("Reconnecting ({}/{})...", attempt, MAX_RECONNECT_ATTEMPTS)
+            }
+            ConnectionState::Failed => "Failed".to_string(),
+        }
+    }
+
+    /// Check if the link is usable.
+    pub fn is_connected(&self) -> bool {
+        matches!(self, ConnectionState::Connected)
+    }
+
+    /// Check if a connection attempt is in progress.
+    pub fn is_busy(&self) -> bool {
+        matches!(
+            self,
+            ConnectionState::Connecting | ConnectionState::Reconnecting { .. }
+        )
+    }
+
+    /// Get the state after an event.
+    ///
+    /// Events that do not apply to the current state leave it unchanged,
+    /// so a port that opens after the operator disconnected stays closed.
+    pub fn next(self, event: ConnectionEvent) -> ConnectionState {
+        match (self, event) {
+            (_, ConnectionEvent::Disconnect) => ConnectionState::Disconnected,
+            (ConnectionState::Disconnected | ConnectionState::Failed, ConnectionEvent::Connect) => {
+                ConnectionState::Connecting
+            }
+            (state, ConnectionEvent::Opened) if state.is_busy() => ConnectionState::Connected,
+            (ConnectionState::Connecting, ConnectionEvent::OpenFailed) => ConnectionState::Failed,
+            (ConnectionState::Connected, ConnectionEvent::PortLost { auto_reconnect }) => {
+                if auto_reconnect {
+                    ConnectionState::Reconnecting { attempt: 1 }
+                } else {
+                    ConnectionState::Disconnected
+                }
+            }
+            (
+                ConnectionState::Reconnecting { attempt },
+                ConnectionEvent::RetryFailed | ConnectionEvent::OpenFailed,
+            ) => {
+                if attempt < MAX_RECONNECT_ATTEMPTS {
+                    ConnectionState::Reconnecting { attempt: attempt + 1 }
+                } else {
+                    ConnectionState::Failed
+                }
+            }
+            (state, _) => state,
+        }
+    }
+}
+
+/// USB identification of a serial port.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PortInfo {
+    /// USB vendor ID (None for non-USB ports)
+    pub usb_vendor_id: Option<u16>,
+    /// USB product ID (None for non-USB ports)
+    pub usb_product_id: Option<u16>,
+}
+
+impl PortInfo {
+    /// Get display label for the port.
+    pub fn label(&self) -> String {
+        match (self.usb_vendor_id, self.usb_product_id) {
+            (Some(vid), Some(pid)) => format!("USB {:04X}:{:04X}", vid, pid),
+            _ => "Serial port".to_string(),
+        }
+    }
+}
+
 /// Web Serial port wrapper for CAT control.
 ///
 /// Note: This is a stub implementation. The Web Serial API
 /// requires unstable web-sys features that may not be available.
+#[derive(Clone)]
 pub struct CatSerial {
     connected: bool,
     port: Option<js_sys::Object>,
+    port_info: Option<PortInfo>,
+    baud_rate: u32,
 }
 
 impl CatSerial {
@@ -79,6 +497,8 @@ impl CatSerial {
         Self {
             connected: false,
             port: None,
+            port_info: None,
+            baud_rate: DEFAULT_BAUD_RATE,
         }
     }
 
@@ -93,24 +513,88 @@ impl CatSerial {
         }
     }
 
-    /// Request and open a serial port.
-    pub async fn connect(&mut self, baud_rate: u32) -> Result<(), JsValue> {
+    /// Get the `navigator.serial` object.
+    fn serial_api() -> Result<JsValue, JsValue> {
         if !Self::is_available() {
             return Err("Web Serial API not available".into());
         }
-
         let window = web_sys::window().ok_or("No window")?;
-        let navigator = window.navigator();
-
-        // Get serial object from navigator
-        let serial = js_sys::Reflect::get(&navigator, &"serial".into())?;
+        js_sys::Reflect::get(&window.navigator(), &"serial".into())
+    }
 
-        // Call requestPort()
+    /// Ask the user to pick a port (requires a user gesture).
+    pub async fn request_port() -> Result<js_sys::Object, JsValue> {
+        let serial = Self::serial_api()?;
         let request_port = js_sys::Reflect::get(&serial, &"requestPort".into())?;
         let request_port_fn = request_port.dyn_into::<js_sys::Function>()?;
         let promise = request_port_fn.call0(&serial)?;
         let port = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(promise)).await?;
+        port.dyn_into::<js_sys::Object>()
+    }
+
+    /// List ports the user has previously granted access to.
+    pub async fn list_ports() -> Result<Vec<js_sys::Object>, JsValue> {
+        let serial = Self::serial_api()?;
+        let get_ports = js_sys::Reflect::get(&serial, &"getPorts".into())?
+            .dyn_into::<js_sys::Function>()?;
+        let promise = get_ports.call0(&serial)?;
+        let ports = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(promise)).await?;
+        let ports = ports.dyn_into::<js_sys::Array>()?;
+        Ok(ports
+            .iter()
+            .filter_map(|port| port.dyn_into::<js_sys::Object>().ok())
+            .collect())
+    }
 
+    /// Read USB identification from a port.
+    pub fn port_info(port: &JsValue) -> PortInfo {
+        let info = js_sys::Reflect::get(port, &"getInfo".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+            .and_then(|f| f.call0(port).ok());
+
+        let field = |name: &str| {
+            info.as_ref()
+                .and_then(|info| js_sys::Reflect::get(info, &name.into()).ok())
+                .and_then(|v| v.as_f64())
+                .map(|v| v as u16)
+        };
+
+        PortInfo {
+            usb_vendor_id: field("usbVendorId"),
+            usb_product_id: field("usbProductId"),
+        }
+    }
+
+    /// Register a callback for `navigator.serial` "connect"/"disconnect" events.
+    ///
+    /// The callback receives the event target (the affected port).
+    pub fn add_event_listener(
+        event: &str,
+        callback: impl Fn(JsValue) + 'static,
+    ) -> Result<(), JsValue> {
+        let serial = Self::serial_api()?;
+        let listener = Closure::wrap(Box::new(move |ev: JsValue| {
+            let target = js_sys::Reflect::get(&ev, &"target".into()).unwrap_or(JsValue::NULL);
+            callback(target);
+        }) as Box<dyn FnMut(JsValue)>);
+
+        let add_fn = js_sys::Reflect::get(&serial, &"addEventListener".into())?
+            .dyn_into::<js_sys::Function>()?;
+        add_fn.call2(&serial, &JsValue::from(event), listener.as_ref())?;
+        listener.forget(); // Listener lives for the page lifetime
+
+        Ok(())
+    }
+
+    /// Request and open a serial port.
+    pub async fn connect(&mut self, baud_rate: u32) -> Result<(), JsValue> {
+        let port = Self::request_port().await?;
+        self.open(port, baud_rate).await
+    }
+
+    /// Open a specific port at the given baud rate.
+    pub async fn open(&mut self, port: js_sys::Object, baud_rate: u32) -> Result<(), JsValue> {
         // Call port.open({ baudRate })
         let options = js_sys::Object::new();
         js_sys::Reflect::set(&options, &"baudRate".into(), &baud_rate.into())?;
@@ -120,12 +604,53 @@ impl CatSerial {
         let open_promise = open_fn.call1(&port, &options)?;
         wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(open_promise)).await?;
 
-        self.port = Some(port.dyn_into::<js_sys::Object>()?);
+        self.port_info = Some(Self::port_info(&port));
+        self.port = Some(port);
+        self.baud_rate = baud_rate;
         self.connected = true;
 
         Ok(())
     }
 
+    /// Try to reopen the last used port after it was lost.
+    ///
+    /// Returns `Ok(false)` if the device is not currently present.
+    pub async fn reconnect(&mut self) -> Result<bool, JsValue> {
+        self.mark_lost();
+
+        let wanted = self.port_info;
+        let port = Self::list_ports()
+            .await?
+            .into_iter()
+            .find(|port| wanted.map_or(true, |info| Self::port_info(port) == info));
+
+        match port {
+            Some(port) => {
+                self.open(port, self.baud_rate).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Forget the open port after the device vanished (keeps its identity).
+    pub fn mark_lost(&mut self) {
+        self.port = None;
+        self.connected = false;
+    }
+
+    /// Check if an event target is the currently open port.
+    pub fn is_port(&self, target: &JsValue) -> bool {
+        self.port
+            .as_ref()
+            .map_or(false, |port| js_sys::Object::is(port, target))
+    }
+
+    /// Get configured baud rate.
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
     /// Disconnect from the serial port.
     pub async fn disconnect(&mut self) -> Result<(), JsValue> {
         if let Some(port) = self.port.take() {
@@ -248,6 +773,26 @@ impl CatSerial {
         let cmd = CatProtocol::ptt(transmit);
         self.send(&cmd).await
     }
+
+    /// Send VOX on/off, gain, delay and anti-VOX gain.
+    pub async fn set_vox(
+        &self,
+        enabled: bool,
+        gain: u8,
+        delay_ms: u16,
+        anti_vox: u8,
+    ) -> Result<(), JsValue> {
+        self.send(&CatProtocol::vox_gain_set(gain)).await?;
+        self.send(&CatProtocol::vox_delay_set(delay_ms)).await?;
+        self.send(&CatProtocol::anti_vox_set(anti_vox)).await?;
+        self.send(&CatProtocol::vox_set(enabled)).await
+    }
+
+    /// Set the radio clock from the host's (NTP-disciplined) time.
+    pub async fn sync_clock(&self) -> Result<(), JsValue> {
+        let cmd = CatProtocol::clock_set(&js_sys::Date::new_0());
+        self.send(&cmd).await
+    }
 }
 
 impl Default for CatSerial {
@@ -256,29 +801,170 @@ impl Default for CatSerial {
     }
 }
 
+/// Wait for the given number of milliseconds.
+pub(crate) async fn sleep_ms(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Apply an event to the CAT connection state, returning the new state.
+fn advance(ctx: &AppContext, event: ConnectionEvent) -> ConnectionState {
+    let state = ctx.cat_connection.get_untracked().next(event);
+    ctx.cat_connection.set(state);
+    state
+}
+
+/// Reopen a lost port, updating the connection state on each attempt.
+async fn run_reconnect(ctx: AppContext, serial: StoredValue<CatSerial>) {
+    loop {
+        sleep_ms(RECONNECT_INTERVAL_MS).await;
+
+        // User may have disconnected while we were waiting
+        if !ctx.cat_connection.get_untracked().is_busy() {
+            return;
+        }
+
+        let mut port = serial.get_value();
+        let event = match port.reconnect().await {
+            Ok(true) => ConnectionEvent::Opened,
+            Ok(false) => ConnectionEvent::RetryFailed,
+            Err(e) => {
+                ctx.cat_error.set(Some(format!("{:?}", e)));
+                ConnectionEvent::OpenFailed
+            }
+        };
+        serial.set_value(port);
+
+        match advance(&ctx, event) {
+            ConnectionState::Connected => {
+                ctx.cat_error.set(None);
+                web_sys::console::log_1(&"CAT serial reconnected".into());
+                return;
+            }
+            ConnectionState::Failed => {
+                ctx.cat_error
+                    .set(Some("Radio not found, reconnect gave up".to_string()));
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Leptos component for CAT serial controls.
 #[component]
 pub fn CatControlPanel(ctx: AppContext) -> impl IntoView {
-    let connected = create_rw_signal(false);
-    let status = create_rw_signal("Disconnected".to_string());
     let available = CatSerial::is_available();
+    let serial = store_value(CatSerial::new());
+    let ports = store_value(Vec::<js_sys::Object>::new());
+    let port_labels = create_rw_signal(Vec::<String>::new());
+    let selected_port = create_rw_signal(0usize);
+
+    let refresh_ports = move || {
+        spawn_local(async move {
+            match CatSerial::list_ports().await {
+                Ok(list) => {
+                    port_labels.set(
+                        list.iter()
+                            .map(|port| CatSerial::port_info(port).label())
+                            .collect(),
+                    );
+                    ports.set_value(list);
+                }
+                Err(e) => {
+                    web_sys::console::error_1(&format!("CAT port list error: {:?}", e).into());
+                }
+            }
+        });
+    };
+
+    if available {
+        refresh_ports();
+
+        // Start the reconnect loop when our port disappears
+        let ctx_lost = ctx.clone();
+        let _ = CatSerial::add_event_listener("disconnect", move |target| {
+            if !serial.with_value(|s| s.is_port(&target)) {
+                return;
+            }
+            serial.update_value(CatSerial::mark_lost);
+            refresh_ports();
+            let auto_reconnect = ctx_lost.cat_auto_reconnect.get_untracked();
+            match advance(&ctx_lost, ConnectionEvent::PortLost { auto_reconnect }) {
+                ConnectionState::Reconnecting { .. } => {
+                    spawn_local(run_reconnect(ctx_lost.clone(), serial));
+                }
+                _ => ctx_lost.cat_error.set(Some("Radio disconnected".to_string())),
+            }
+        });
+
+        // Newly plugged-in ports show up in the list
+        let _ = CatSerial::add_event_listener("connect", move |_| refresh_ports());
+    }
+
+    let cat_connection = ctx.cat_connection;
+    let cat_error = ctx.cat_error;
+    let cat_baud_rate = ctx.cat_baud_rate;
+    let cat_auto_reconnect = ctx.cat_auto_reconnect;
 
     // Clone ctx for each closure
+    let ctx_add = ctx.clone();
     let ctx_connect = ctx.clone();
+    let ctx_disconnect = ctx.clone();
     let ctx_sync = ctx;
 
+    let add_port = move |_: web_sys::MouseEvent| {
+        let ctx = ctx_add.clone();
+        spawn_local(async move {
+            match CatSerial::request_port().await {
+                Ok(_) => {
+                    if let Ok(list) = CatSerial::list_ports().await {
+                        selected_port.set(list.len().saturating_sub(1));
+                        port_labels.set(
+                            list.iter()
+                                .map(|port| CatSerial::port_info(port).label())
+                                .collect(),
+                        );
+                        ports.set_value(list);
+                    }
+                }
+                Err(e) => ctx.cat_error.set(Some(format!("{:?}", e))),
+            }
+        });
+    };
+
     let connect = move |_: web_sys::MouseEvent| {
-        let _ctx = ctx_connect.clone();
+        let ctx = ctx_connect.clone();
+        let Some(port) = ports.with_value(|list| list.get(selected_port.get_untracked()).cloned())
+        else {
+            ctx.cat_error.set(Some("No port selected".to_string()));
+            return;
+        };
+        let baud_rate = ctx.cat_baud_rate.get_untracked();
+
+        if advance(&ctx, ConnectionEvent::Connect) != ConnectionState::Connecting {
+            return;
+        }
         spawn_local(async move {
-            let mut serial = CatSerial::new();
-            match serial.connect(9600).await {
+            let mut new_serial = CatSerial::new();
+            match new_serial.open(port, baud_rate).await {
                 Ok(()) => {
-                    connected.set(true);
-                    status.set("Connected".to_string());
+                    // Disconnected while the port was opening
+                    if advance(&ctx, ConnectionEvent::Opened) != ConnectionState::Connected {
+                        let _ = new_serial.disconnect().await;
+                        return;
+                    }
+                    serial.set_value(new_serial);
+                    ctx.cat_error.set(None);
                     web_sys::console::log_1(&"CAT serial connected".into());
                 }
                 Err(e) => {
-                    status.set(format!("Error: {:?}", e));
+                    advance(&ctx, ConnectionEvent::OpenFailed);
+                    ctx.cat_error.set(Some(format!("{:?}", e)));
                     web_sys::console::error_1(&format!("CAT connect error: {:?}", e).into());
                 }
             }
@@ -286,26 +972,90 @@ pub fn CatControlPanel(ctx: AppContext) -> impl IntoView {
     };
 
     let disconnect = move |_: web_sys::MouseEvent| {
+        let ctx = ctx_disconnect.clone();
+        // Stops any reconnect loop in progress
+        advance(&ctx, ConnectionEvent::Disconnect);
         spawn_local(async move {
-            let mut serial = CatSerial::new();
-            if let Err(e) = serial.disconnect().await {
+            let mut port = serial.get_value();
+            if let Err(e) = port.disconnect().await {
                 web_sys::console::error_1(&format!("CAT disconnect error: {:?}", e).into());
             }
-            connected.set(false);
-            status.set("Disconnected".to_string());
+            serial.set_value(port);
         });
     };
 
     let sync_from_radio = move |_: web_sys::MouseEvent| {
         let ctx = ctx_sync.clone();
         spawn_local(async move {
-            let serial = CatSerial::new();
-            if let Ok(Some(freq)) = serial.get_frequency().await {
-                ctx.frequency.set(freq);
+            let port = serial.get_value();
+            match port.get_frequency().await {
+                Ok(Some(freq)) => ctx.frequency.set(freq),
+                Ok(None) => {}
+                Err(e) => ctx.cat_error.set(Some(format!("{:?}", e))),
+            }
+            if let Err(e) = port.sync_clock().await {
+                ctx.cat_error.set(Some(format!("{:?}", e)));
             }
         });
     };
 
+    let on_port_change = move |ev: web_sys::Event| {
+        if let Ok(index) = event_target_value(&ev).parse() {
+            selected_port.set(index);
+        }
+    };
+
+    let on_baud_change = move |ev: web_sys::Event| {
+        if let Ok(baud) = event_target_value(&ev).parse() {
+            cat_baud_rate.set(baud);
+        }
+    };
+
+    // Follow UI tuning (dial, band buttons, waterfall clicks) on the radio
+    let ctx_tune = ctx_sync.clone();
+    create_effect(move |_| {
+        let hz = ctx_tune.frequency.get();
+        if !ctx_tune.cat_connection.get_untracked().is_connected() {
+            return;
+        }
+        let cat_error = ctx_tune.cat_error;
+        spawn_local(async move {
+            let port = serial.get_value();
+            if let Err(e) = port.set_frequency(hz).await {
+                cat_error.set(Some(format!("{:?}", e)));
+            }
+        });
+    });
+
+    // Push VOX settings to the radio whenever they change
+    let ctx_vox = ctx_sync.clone();
+    create_effect(move |_| {
+        let enabled = ctx_vox.vox_enabled.get();
+        let gain = ctx_vox.vox_gain.get();
+        let delay_ms = ctx_vox.vox_delay_ms.get();
+        let anti_vox = ctx_vox.anti_vox_gain.get();
+        if !ctx_vox.cat_connection.get_untracked().is_connected() {
+            return;
+        }
+        let cat_error = ctx_vox.cat_error;
+        spawn_local(async move {
+            let port = serial.get_value();
+            if let Err(e) = port.set_vox(enabled, gain, delay_ms, anti_vox).await {
+                cat_error.set(Some(format!("{:?}", e)));
+            }
+        });
+    });
+    let vox_enabled = ctx_sync.vox_enabled;
+    let vox_gain = ctx_sync.vox_gain;
+    let vox_delay_ms = ctx_sync.vox_delay_ms;
+    let anti_vox_gain = ctx_sync.anti_vox_gain;
+
+    let connected = move || cat_connection.get().is_connected();
+    let idle = move || {
+        let state = cat_connection.get();
+        !state.is_connected() && !state.is_busy()
+    };
+
     view! {
         <div class="cat-control-panel">
             <h3>"CAT Control"</h3>
@@ -313,28 +1063,121 @@ pub fn CatControlPanel(ctx: AppContext) -> impl IntoView {
                 view! {
                     <div class="cat-status">
                         <span class="status-indicator" class:connected=connected />
-                        <span class="status-text">{move || status.get()}</span>
+                        <span class="status-text">{move || cat_connection.get().label()}</span>
+                        <span class="status-error">
+                            {move || cat_error.get().unwrap_or_default()}
+                        </span>
+                    </div>
+                    <div class="cat-config">
+                        <select
+                            class="cat-port"
+                            prop:value=move || selected_port.get().to_string()
+                            on:change=on_port_change
+                            disabled=move || !idle()
+                        >
+                            {move || port_labels.get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, label)| view! {
+                                    <option value=i.to_string()>{label}</option>
+                                })
+                                .collect_view()}
+                        </select>
+                        <button on:click=add_port disabled=move || !idle()>
+                            "Add Port"
+                        </button>
+                        <select
+                            class="cat-baud"
+                            prop:value=move || cat_baud_rate.get().to_string()
+                            on:change=on_baud_change
+                            disabled=move || !idle()
+                        >
+                            {BAUD_RATES.iter().map(|baud| view! {
+                                <option value=baud.to_string()>{baud.to_string()}</option>
+                            }).collect_view()}
+                        </select>
+                        <label>
+                            <input
+                                type="checkbox"
+                                prop:checked=move || cat_auto_reconnect.get()
+                                on:change=move |_| cat_auto_reconnect.update(|v| *v = !*v)
+                            />
+                            "Auto-reconnect"
+                        </label>
                     </div>
                     <div class="cat-buttons">
                         <button
                             on:click=connect
-                            disabled=connected
+                            disabled=move || !idle() || port_labels.get().is_empty()
                         >
                             "Connect"
                         </button>
                         <button
                             on:click=disconnect
-                            disabled=move || !connected.get()
+                            disabled=idle
                         >
                             "Disconnect"
                         </button>
                         <button
                             on:click=sync_from_radio
-                            disabled=move || !connected.get()
+                            disabled=move || !connected()
                         >
                             "Sync"
                         </button>
                     </div>
+                    <div class="cat-vox">
+                        <label>
+                            <input
+                                type="checkbox"
+                                prop:checked=move || vox_enabled.get()
+                                on:change=move |_| vox_enabled.update(|v| *v = !*v)
+                            />
+                            "VOX"
+                        </label>
+                        <label>
+                            "Gain"
+                            <input
+                                type="range"
+                                min="0"
+                                max="100"
+                                prop:value=move || vox_gain.get().to_string()
+                                on:change=move |ev| {
+                                    if let Ok(v) = event_target_value(&ev).parse() {
+                                        vox_gain.set(v);
+                                    }
+                                }
+                            />
+                        </label>
+                        <label>
+                            "Delay (ms)"
+                            <input
+                                type="number"
+                                min="0"
+                                max="3000"
+                                step="50"
+                                prop:value=move || vox_delay_ms.get().to_string()
+                                on:change=move |ev| {
+                                    if let Ok(v) = event_target_value(&ev).parse::<u16>() {
+                                        vox_delay_ms.set(v.min(3000));
+                                    }
+                                }
+                            />
+                        </label>
+                        <label>
+                            "Anti-VOX"
+                            <input
+                                type="range"
+                                min="0"
+                                max="100"
+                                prop:value=move || anti_vox_gain.get().to_string()
+                                on:change=move |ev| {
+                                    if let Ok(v) = event_target_value(&ev).parse() {
+                                        anti_vox_gain.set(v);
+                                    }
+                                }
+                            />
+                        </label>
+                    </div>
                 }.into_view()
             } else {
                 view! {
@@ -347,3 +1190,78 @@ pub fn CatControlPanel(ctx: AppContext) -> impl IntoView {
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_and_disconnect() {
+        let state = ConnectionState::default().next(ConnectionEvent::Connect);
+        assert_eq!(state, ConnectionState::Connecting);
+        assert!(state.is_busy());
+        // A second click while opening changes nothing
+        assert_eq!(state.next(ConnectionEvent::Connect), state);
+
+        let state = state.next(ConnectionEvent::Opened);
+        assert_eq!(state, ConnectionState::Connected);
+        assert!(state.is_connected());
+        assert!(!state.is_busy());
+        assert_eq!(state.next(ConnectionEvent::Connect), state);
+
+        assert_eq!(
+            state.next(ConnectionEvent::Disconnect),
+            ConnectionState::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_connect_failure() {
+        let state = ConnectionState::Connecting.next(ConnectionEvent::OpenFailed);
+        assert_eq!(state, ConnectionState::Failed);
+        assert_eq!(state.label(), "Failed");
+        // The operator can try again
+        assert_eq!(state.next(ConnectionEvent::Connect), ConnectionState::Connecting);
+    }
+
+    #[test]
+    fn test_disconnect_while_opening() {
+        let state = ConnectionState::Connecting.next(ConnectionEvent::Disconnect);
+        assert_eq!(state.next(ConnectionEvent::Opened), ConnectionState::Disconnected);
+        assert_eq!(state.next(ConnectionEvent::OpenFailed), ConnectionState::Disconnected);
+    }
+
+    #[test]
+    fn test_port_lost() {
+        let lost = |auto_reconnect| ConnectionEvent::PortLost { auto_reconnect };
+        assert_eq!(
+            ConnectionState::Connected.next(lost(false)),
+            ConnectionState::Disconnected
+        );
+        assert_eq!(
+            ConnectionState::Connected.next(lost(true)),
+            ConnectionState::Reconnecting { attempt: 1 }
+        );
+        // Only an open port can be lost
+        assert_eq!(
+            ConnectionState::Disconnected.next(lost(true)),
+            ConnectionState::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_reconnect_attempts() {
+        let mut state = ConnectionState::Reconnecting { attempt: 1 };
+        assert_eq!(state.label(), format!("Reconnecting (1/{})...", MAX_RECONNECT_ATTEMPTS));
+        for attempt in 2..=MAX_RECONNECT_ATTEMPTS {
+            state = state.next(ConnectionEvent::RetryFailed);
+            assert_eq!(state, ConnectionState::Reconnecting { attempt });
+        }
+        assert_eq!(state.next(ConnectionEvent::RetryFailed), ConnectionState::Failed);
+        assert_eq!(state.next(ConnectionEvent::OpenFailed), ConnectionState::Failed);
+
+        let state = ConnectionState::Reconnecting { attempt: 3 };
+        assert_eq!(state.next(ConnectionEvent::Opened), ConnectionState::Connected);
+        assert_eq!(state.next(ConnectionEvent::Disconnect), ConnectionState::Disconnected);
+    }
+}
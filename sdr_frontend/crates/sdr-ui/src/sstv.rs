@@ -0,0 +1,127 @@
+//! SSTV image reception.
+//!
+//! In SSTV mode the worklet's decoder waits for a VIS header, then sends
+//! each finished scanline as RGB bytes. Lines are painted onto a canvas
+//! as they arrive, and the picture can be saved as a PNG.
+
+use leptos::*;
+use sdr_mode_sstv::{SstvMode, IMAGE_HEIGHT, IMAGE_WIDTH};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::state::AppContext;
+
+/// A received scanline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SstvLine {
+    /// Line number from the top of the image
+    pub line: u16,
+    /// Mode announced by the VIS header
+    pub mode: Option<SstvMode>,
+    /// RGB bytes, three per pixel
+    pub rgb: Vec<u8>,
+}
+
+/// Record a scanline from the worklet.
+pub fn line_received(ctx: &AppContext, line: SstvLine) {
+    if line.mode.is_some() {
+        ctx.sstv_mode.set(line.mode);
+    }
+    ctx.sstv_line.set(Some(line));
+}
+
+/// Leptos component showing the image being received.
+#[component]
+pub fn SstvPanel(ctx: AppContext) -> impl IntoView {
+    let canvas_ref = create_node_ref::<leptos::html::Canvas>();
+    let (lines, set_lines) = create_signal(0usize);
+
+    // Paint each line as it arrives, starting a new picture on line 0
+    create_effect(move |_| {
+        let Some(line) = ctx.sstv_line.get() else {
+            return;
+        };
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        let Some(context) = context_2d(&canvas) else {
+            return;
+        };
+        if line.line == 0 {
+            context.clear_rect(0.0, 0.0, IMAGE_WIDTH as f64, IMAGE_HEIGHT as f64);
+        }
+        if let Err(e) = draw_line(&context, &line) {
+            web_sys::console::warn_1(&e);
+        }
+        set_lines.set(usize::from(line.line) + 1);
+    });
+
+    let save = move |_| {
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        if let Err(e) = save_png(&canvas) {
+            web_sys::console::error_1(&e);
+        }
+    };
+
+    let status = move || match ctx.sstv_mode.get() {
+        Some(mode) => format!("{}  line {}/{}", mode.name(), lines.get(), IMAGE_HEIGHT),
+        None => "Waiting for VIS header".to_string(),
+    };
+
+    view! {
+        <div class="sstv-panel">
+            <h4>"SSTV"</h4>
+            <canvas
+                node_ref=canvas_ref
+                class="sstv-canvas"
+                width=IMAGE_WIDTH
+                height=IMAGE_HEIGHT
+                style="display: block; background: #000;"
+            />
+            <span class="sstv-status">{status}</span>
+            <button on:click=save disabled=move || lines.get() == 0>"Save"</button>
+        </div>
+    }
+}
+
+/// Get the 2D drawing context of a canvas.
+fn context_2d(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+}
+
+/// Paint one scanline.
+fn draw_line(context: &CanvasRenderingContext2d, line: &SstvLine) -> Result<(), JsValue> {
+    let width = line.rgb.len() / 3;
+    if width == 0 {
+        return Ok(());
+    }
+    let rgba: Vec<u8> = line
+        .rgb
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect();
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&rgba), width as u32, 1)?;
+    context.put_image_data(&image, 0.0, f64::from(line.line))
+}
+
+/// Offer the canvas to the user as a PNG download.
+fn save_png(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+    let url = canvas.to_data_url_with_type("image/png")?;
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or("No document")?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(&format!("sstv_{}.png", js_sys::Date::now() as u64));
+    anchor.click();
+    Ok(())
+}
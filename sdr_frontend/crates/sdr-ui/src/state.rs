@@ -1,7 +1,32 @@
 //! Application state management.
 
-use crate::components::RadioMode;
+pub mod profiles;
+
+use crate::app::shortcuts::Binding;
+use crate::aprs::AprsStation;
+use crate::audio::{AgcSpeed, DspApi, RecordSource, RecordingState, DEFAULT_WAV_MAX_MB};
+use crate::bookmarks::Bookmark;
+use crate::components::{RadioMode, NUM_BANDS};
+use crate::cw_skimmer::CwSpot;
+use crate::hell;
+use crate::iq_file::{IqCapture, RawIqFormat, DEFAULT_RAW_RATE};
+use crate::js8::{self, Js8Message};
+use crate::layout::LayoutMode;
+use crate::logbook::Qso;
+use crate::psk_channels::{PskChannel, PskCommand};
+use crate::recorder::ClipInfo;
+use crate::usb_iq::IqSource;
+use crate::serial::{ConnectionState, DEFAULT_BAUD_RATE};
+use crate::sstv::SstvLine;
+use crate::state::profiles::Profile;
+use crate::theme::{Palette, Theme};
 use leptos::*;
+use sdr_dsp_core::conditions::BandConditions;
+use sdr_dsp_core::units;
+use sdr_dsp_core::spectrum::{DisplayRange, SpectrumCalibration, SpectrumView};
+use sdr_mode_js8::{Js8Encoder, MessageAssembler};
+use sdr_mode_sstv::SstvMode;
+use sdr_dsp_core::SmeterCalibration;
 
 /// Radio state: frequency, mode, transmit status.
 #[derive(Clone, Debug)]
@@ -14,6 +39,10 @@ pub struct RadioState {
     pub transmitting: bool,
     /// Filter bandwidth in Hz
     pub bandwidth: f32,
+    /// AGC speed preset
+    pub agc_speed: AgcSpeed,
+    /// Noise reduction level (0 = off)
+    pub noise_reduction: u8,
 }
 
 impl Default for RadioState {
@@ -23,21 +52,35 @@ impl Default for RadioState {
             mode: RadioMode::Usb,
             transmitting: false,
             bandwidth: 2700.0,
+            agc_speed: AgcSpeed::default(),
+            noise_reduction: 0,
         }
     }
 }
 
 /// Display state: spectrum, waterfall, S-meter.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct DisplayState {
-    /// Current spectrum data (normalized 0.0-1.0)
+    /// Current spectrum data (calibrated dBm per bin)
     pub spectrum: Vec<f32>,
     /// S-meter value (0.0 = S0, 1.0 = S9)
     pub smeter: f32,
+    /// Calibrated signal level in dBm
+    pub smeter_dbm: f32,
+}
+
+impl Default for DisplayState {
+    fn default() -> Self {
+        Self {
+            spectrum: Vec::new(),
+            smeter: 0.0,
+            smeter_dbm: units::s_units_to_dbm(0.0),
+        }
+    }
 }
 
 /// Digital decoder state.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct DecoderState {
     /// Received text buffer
     pub rx_text: String,
@@ -47,29 +90,190 @@ pub struct DecoderState {
     pub afc_offset: f32,
     /// AFC enabled
     pub afc_enabled: bool,
+    /// AFC loop bandwidth in Hz
+    pub afc_bandwidth: f32,
+    /// AFC pull-in range in Hz
+    pub afc_range: f32,
+}
+
+impl Default for DecoderState {
+    fn default() -> Self {
+        Self {
+            rx_text: String::new(),
+            tx_buffer: String::new(),
+            afc_offset: 0.0,
+            afc_enabled: true,
+            afc_bandwidth: 50.0,
+            afc_range: 100.0,
+        }
+    }
+}
+
+/// CAT serial link state.
+#[derive(Clone, Debug)]
+pub struct CatState {
+    /// Connection state machine
+    pub connection: ConnectionState,
+    /// Last error reported by the serial link
+    pub error: Option<String>,
+    /// Configured baud rate
+    pub baud_rate: u32,
+    /// Reconnect automatically when the port drops
+    pub auto_reconnect: bool,
+}
+
+impl Default for CatState {
+    fn default() -> Self {
+        Self {
+            connection: ConnectionState::Disconnected,
+            error: None,
+            baud_rate: DEFAULT_BAUD_RATE,
+            auto_reconnect: true,
+        }
+    }
 }
 
 /// Application context providing global state.
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct AppContext {
     /// Radio state signals
     pub frequency: RwSignal<u64>,
     pub mode: RwSignal<RadioMode>,
     pub transmitting: RwSignal<bool>,
     pub bandwidth: RwSignal<f32>,
+    pub agc_speed: RwSignal<AgcSpeed>,
+    pub noise_reduction: RwSignal<u8>,
+
+    /// Saved settings profiles and the one last chosen
+    pub profiles: RwSignal<Vec<Profile>>,
+    pub active_profile: RwSignal<Option<String>>,
 
     /// Display state signals
     pub spectrum: RwSignal<Vec<f32>>,
     pub smeter: RwSignal<f32>,
+    pub smeter_dbm: RwSignal<f32>,
+    /// S-meter calibration per band
+    pub smeter_calibration: RwSignal<[SmeterCalibration; NUM_BANDS]>,
+
+    /// Panadapter signals
+    pub spectrum_calibration: RwSignal<SpectrumCalibration<NUM_BANDS>>,
+    pub spectrum_averaging: RwSignal<u8>,
+    pub noise_floor: RwSignal<Option<f32>>,
+    pub auto_range: RwSignal<bool>,
+    pub display_range: RwSignal<DisplayRange>,
+    pub spectrum_view: RwSignal<SpectrumView>,
+    pub tuning_step: RwSignal<u64>,
+
+    /// Frequency bookmarks, sorted by frequency
+    pub bookmarks: RwSignal<Vec<Bookmark>>,
 
     /// Decoder state signals
     pub rx_text: RwSignal<String>,
     pub tx_buffer: RwSignal<String>,
     pub afc_offset: RwSignal<f32>,
     pub afc_enabled: RwSignal<bool>,
+    pub afc_bandwidth: RwSignal<f32>,
+    pub afc_range: RwSignal<f32>,
+
+    /// Parallel PSK31 channels and the pending decoder bank request
+    pub psk_channels: RwSignal<Vec<PskChannel>>,
+    pub psk_command: RwSignal<Option<PskCommand>>,
+
+    /// Latest SSTV scanline and the mode of the image being received
+    pub sstv_line: RwSignal<Option<SstvLine>>,
+    pub sstv_mode: RwSignal<Option<SstvMode>>,
+
+    /// APRS decoding, stations heard and packet log
+    pub aprs_enabled: RwSignal<bool>,
+    pub aprs_stations: RwSignal<Vec<AprsStation>>,
+    pub aprs_log: RwSignal<Vec<String>>,
 
-    /// Audio pipeline running
+    /// JS8 audio frequency, chat, message being received and transmit queue
+    pub js8_frequency: RwSignal<f32>,
+    pub js8_messages: RwSignal<Vec<Js8Message>>,
+    pub js8_assembler: RwSignal<MessageAssembler>,
+    pub js8_encoder: StoredValue<Js8Encoder>,
+    pub js8_queued: RwSignal<usize>,
+    pub js8_sending: RwSignal<bool>,
+
+    /// Feld Hell tone frequency and the latest received columns
+    pub hell_frequency: RwSignal<f32>,
+    pub hell_columns: RwSignal<Option<Vec<u8>>>,
+
+    /// CW skimmer running and the signals it has found
+    pub cw_skimmer_enabled: RwSignal<bool>,
+    pub cw_spots: RwSignal<Vec<CwSpot>>,
+
+    /// Audio pipeline running and speaker muted
     pub audio_running: RwSignal<bool>,
+    pub muted: RwSignal<bool>,
+
+    /// API the worklet DSP reported when ready (None until then, or if incompatible)
+    pub dsp_api: RwSignal<Option<DspApi>>,
+
+    /// Layout choice and viewport width in CSS pixels
+    pub layout_mode: RwSignal<LayoutMode>,
+    pub viewport_width: RwSignal<f64>,
+
+    /// Color theme and waterfall palette
+    pub theme: RwSignal<Theme>,
+    pub palette: RwSignal<Palette>,
+
+    /// Keyboard shortcuts, help overlay shown and transmit key armed
+    pub shortcuts: RwSignal<Vec<Binding>>,
+    pub shortcut_help: RwSignal<bool>,
+    pub tx_shortcut_armed: RwSignal<bool>,
+
+    /// CAT serial state signals
+    pub cat_connection: RwSignal<ConnectionState>,
+    pub cat_error: RwSignal<Option<String>>,
+    pub cat_baud_rate: RwSignal<u32>,
+    pub cat_auto_reconnect: RwSignal<bool>,
+
+    /// IQ input source and USB stream status
+    pub iq_source: RwSignal<IqSource>,
+    pub usb_streaming: RwSignal<bool>,
+    pub usb_dropped_frames: RwSignal<u32>,
+
+    /// IQ file playback signals (position is 0.0 to 1.0)
+    pub iq_file: RwSignal<Option<IqCapture>>,
+    pub iq_file_raw_format: RwSignal<RawIqFormat>,
+    pub iq_file_raw_rate: RwSignal<f32>,
+    pub iq_file_loop: RwSignal<bool>,
+    pub iq_file_position: RwSignal<f32>,
+
+    /// Demodulated audio block from the worklet tap
+    pub audio_tap: RwSignal<Vec<f32>>,
+    pub audio_sample_rate: RwSignal<f32>,
+
+    /// Raw IQ block (interleaved) from the worklet tap
+    pub iq_tap: RwSignal<Vec<f32>>,
+
+    /// WAV recorder signals
+    pub wav_state: RwSignal<RecordingState>,
+    pub wav_source: RwSignal<RecordSource>,
+    pub wav_bytes: RwSignal<usize>,
+    pub wav_max_mb: RwSignal<u32>,
+
+    /// Activity recorder signals
+    pub recorder_enabled: RwSignal<bool>,
+    pub recorder_active: RwSignal<bool>,
+    pub recorder_squelch: RwSignal<f32>,
+    pub recorder_clips: RwSignal<Vec<ClipInfo>>,
+
+    /// Logbook: own callsign and logged QSOs (oldest first)
+    pub my_call: RwSignal<String>,
+    pub log_qsos: RwSignal<Vec<Qso>>,
+
+    /// VOX settings sent to the radio over CAT
+    pub vox_enabled: RwSignal<bool>,
+    pub vox_gain: RwSignal<u8>,
+    pub vox_delay_ms: RwSignal<u16>,
+    pub anti_vox_gain: RwSignal<u8>,
+
+    /// Band condition signals
+    pub band_conditions: RwSignal<BandConditions<NUM_BANDS>>,
+    pub home_locator: RwSignal<String>,
 }
 
 impl AppContext {
@@ -78,19 +282,93 @@ impl AppContext {
         let radio = RadioState::default();
         let display = DisplayState::default();
         let decoder = DecoderState::default();
+        let cat = CatState::default();
 
         Self {
             frequency: create_rw_signal(radio.frequency),
             mode: create_rw_signal(radio.mode),
             transmitting: create_rw_signal(radio.transmitting),
             bandwidth: create_rw_signal(radio.bandwidth),
+            agc_speed: create_rw_signal(radio.agc_speed),
+            noise_reduction: create_rw_signal(radio.noise_reduction),
+            profiles: create_rw_signal(Vec::new()),
+            active_profile: create_rw_signal(None),
             spectrum: create_rw_signal(display.spectrum),
             smeter: create_rw_signal(display.smeter),
+            smeter_dbm: create_rw_signal(display.smeter_dbm),
+            smeter_calibration: create_rw_signal([SmeterCalibration::IDENTITY; NUM_BANDS]),
+            spectrum_calibration: create_rw_signal(SpectrumCalibration::new()),
+            spectrum_averaging: create_rw_signal(4),
+            noise_floor: create_rw_signal(None),
+            auto_range: create_rw_signal(true),
+            display_range: create_rw_signal(DisplayRange::new(-40.0, 100.0)),
+            spectrum_view: create_rw_signal(SpectrumView::default()),
+            tuning_step: create_rw_signal(100),
+            bookmarks: create_rw_signal(Vec::new()),
             rx_text: create_rw_signal(decoder.rx_text),
             tx_buffer: create_rw_signal(decoder.tx_buffer),
             afc_offset: create_rw_signal(decoder.afc_offset),
             afc_enabled: create_rw_signal(decoder.afc_enabled),
+            afc_bandwidth: create_rw_signal(decoder.afc_bandwidth),
+            afc_range: create_rw_signal(decoder.afc_range),
+            psk_channels: create_rw_signal(Vec::new()),
+            psk_command: create_rw_signal(None),
+            sstv_line: create_rw_signal(None),
+            sstv_mode: create_rw_signal(None),
+            aprs_enabled: create_rw_signal(false),
+            aprs_stations: create_rw_signal(Vec::new()),
+            aprs_log: create_rw_signal(Vec::new()),
+            js8_frequency: create_rw_signal(js8::DEFAULT_FREQUENCY_HZ),
+            js8_messages: create_rw_signal(Vec::new()),
+            js8_assembler: create_rw_signal(MessageAssembler::new()),
+            js8_encoder: store_value(js8::new_encoder(js8::DEFAULT_FREQUENCY_HZ)),
+            js8_queued: create_rw_signal(0),
+            js8_sending: create_rw_signal(false),
+            hell_frequency: create_rw_signal(hell::DEFAULT_FREQUENCY_HZ),
+            hell_columns: create_rw_signal(None),
+            cw_skimmer_enabled: create_rw_signal(false),
+            cw_spots: create_rw_signal(Vec::new()),
             audio_running: create_rw_signal(false),
+            muted: create_rw_signal(false),
+            dsp_api: create_rw_signal(None),
+            layout_mode: create_rw_signal(LayoutMode::default()),
+            viewport_width: create_rw_signal(f64::INFINITY),
+            theme: create_rw_signal(Theme::default()),
+            palette: create_rw_signal(Palette::default()),
+            shortcuts: create_rw_signal(Vec::new()),
+            shortcut_help: create_rw_signal(false),
+            tx_shortcut_armed: create_rw_signal(false),
+            cat_connection: create_rw_signal(cat.connection),
+            cat_error: create_rw_signal(cat.error),
+            cat_baud_rate: create_rw_signal(cat.baud_rate),
+            cat_auto_reconnect: create_rw_signal(cat.auto_reconnect),
+            iq_source: create_rw_signal(IqSource::default()),
+            usb_streaming: create_rw_signal(false),
+            usb_dropped_frames: create_rw_signal(0),
+            iq_file: create_rw_signal(None),
+            iq_file_raw_format: create_rw_signal(RawIqFormat::default()),
+            iq_file_raw_rate: create_rw_signal(DEFAULT_RAW_RATE),
+            iq_file_loop: create_rw_signal(false),
+            iq_file_position: create_rw_signal(0.0),
+            audio_tap: create_rw_signal(Vec::new()),
+            audio_sample_rate: create_rw_signal(48000.0),
+            iq_tap: create_rw_signal(Vec::new()),
+            wav_state: create_rw_signal(RecordingState::default()),
+            wav_source: create_rw_signal(RecordSource::default()),
+            wav_bytes: create_rw_signal(0),
+            wav_max_mb: create_rw_signal(DEFAULT_WAV_MAX_MB),
+            recorder_enabled: create_rw_signal(false),
+            recorder_active: create_rw_signal(false),
+            recorder_squelch: create_rw_signal(0.5),
+            recorder_clips: create_rw_signal(Vec::new()),
+            my_call: create_rw_signal(String::new()),
+            log_qsos: create_rw_signal(Vec::new()),
+            vox_enabled: create_rw_signal(false),
+            vox_gain: create_rw_signal(33),
+            vox_delay_ms: create_rw_signal(500),
+            anti_vox_gain: create_rw_signal(50),
+            band_conditions: create_rw_signal(BandConditions::new()),
+            home_locator: create_rw_signal(String::new()),
         }
     }
 }
@@ -0,0 +1,502 @@
+//! UI state persistence and profiles.
+//!
+//! The tuning, receive, waterfall and theme settings are kept in
+//! localStorage as they change and restored on load. Named profiles (e.g.
+//! "CW contest", "FT8") hold their own copy of the same settings and are
+//! switched from a dropdown.
+//!
+//! Settings are stored as `key=value` lines; keys that are missing or do
+//! not parse keep their current value, so older saves still load.
+
+use leptos::*;
+use sdr_dsp_core::spectrum::DisplayRange;
+
+use crate::audio::AgcSpeed;
+use crate::components::RadioMode;
+use crate::state::AppContext;
+use crate::theme::{Palette, Theme};
+
+/// localStorage key holding the last settings.
+const STATE_KEY: &str = "sdr-ui-state";
+
+/// Header line of the stored settings.
+const STATE_HEADER: &str = "sdr-ui-state 1";
+
+/// localStorage key holding the profiles.
+const PROFILES_KEY: &str = "sdr-profiles";
+
+/// Header line of the stored profiles.
+const PROFILES_HEADER: &str = "sdr-profiles 1";
+
+/// Settings saved between sessions and in profiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiSettings {
+    /// Dial frequency in Hz
+    pub frequency: u64,
+    /// Operating mode
+    pub mode: RadioMode,
+    /// Filter bandwidth in Hz
+    pub bandwidth: f32,
+    /// AGC speed preset
+    pub agc_speed: AgcSpeed,
+    /// Noise reduction level
+    pub noise_reduction: u8,
+    /// Tuning step in Hz
+    pub tuning_step: u64,
+    /// Spectrum frames averaged
+    pub spectrum_averaging: u8,
+    /// Waterfall range follows the band
+    pub auto_range: bool,
+    /// Waterfall range when not automatic
+    pub display_range: DisplayRange,
+    /// Color theme
+    pub theme: Theme,
+    /// Waterfall palette
+    pub palette: Palette,
+}
+
+impl UiSettings {
+    /// Read the settings from the context (tracked).
+    pub fn capture(ctx: &AppContext) -> Self {
+        Self {
+            frequency: ctx.frequency.get(),
+            mode: ctx.mode.get(),
+            bandwidth: ctx.bandwidth.get(),
+            agc_speed: ctx.agc_speed.get(),
+            noise_reduction: ctx.noise_reduction.get(),
+            tuning_step: ctx.tuning_step.get(),
+            spectrum_averaging: ctx.spectrum_averaging.get(),
+            auto_range: ctx.auto_range.get(),
+            display_range: ctx.display_range.get(),
+            theme: ctx.theme.get(),
+            palette: ctx.palette.get(),
+        }
+    }
+
+    /// Apply the settings to the context.
+    pub fn apply(&self, ctx: &AppContext) {
+        batch(|| {
+            ctx.frequency.set(self.frequency);
+            ctx.mode.set(self.mode);
+            ctx.bandwidth.set(self.bandwidth);
+            ctx.agc_speed.set(self.agc_speed);
+            ctx.noise_reduction.set(self.noise_reduction);
+            ctx.tuning_step.set(self.tuning_step);
+            ctx.spectrum_averaging.set(self.spectrum_averaging);
+            ctx.auto_range.set(self.auto_range);
+            ctx.display_range.set(self.display_range);
+            ctx.theme.set(self.theme);
+            ctx.palette.set(self.palette.clone());
+        });
+    }
+
+    /// Append the settings as `key=value` lines.
+    fn write_lines(&self, text: &mut String) {
+        let fields = [
+            ("frequency", self.frequency.to_string()),
+            ("mode", self.mode.name().to_string()),
+            ("bandwidth", self.bandwidth.to_string()),
+            ("agc", self.agc_speed.name().to_string()),
+            ("nr", self.noise_reduction.to_string()),
+            ("step", self.tuning_step.to_string()),
+            ("averaging", self.spectrum_averaging.to_string()),
+            ("auto-range", u8::from(self.auto_range).to_string()),
+            ("range-floor", self.display_range.floor_db.to_string()),
+            ("range-span", self.display_range.range_db.to_string()),
+            ("theme", self.theme.name().to_string()),
+            ("palette", self.palette.encode()),
+        ];
+        for (key, value) in fields {
+            text.push('\n');
+            text.push_str(key);
+            text.push('=');
+            text.push_str(&value);
+        }
+    }
+
+    /// Update the setting named by a `key=value` line, if it parses.
+    fn read_line(&mut self, line: &str) {
+        let Some((key, value)) = line.split_once('=') else {
+            return;
+        };
+        match key {
+            "frequency" => set_parsed(&mut self.frequency, value),
+            "mode" => {
+                if let Some(mode) = RadioMode::from_name(value) {
+                    self.mode = mode;
+                }
+            }
+            "bandwidth" => set_parsed(&mut self.bandwidth, value),
+            "agc" => {
+                if let Some(speed) = AgcSpeed::from_name(value) {
+                    self.agc_speed = speed;
+                }
+            }
+            "nr" => set_parsed(&mut self.noise_reduction, value),
+            "step" => set_parsed(&mut self.tuning_step, value),
+            "averaging" => set_parsed(&mut self.spectrum_averaging, value),
+            "auto-range" => self.auto_range = value == "1",
+            "range-floor" => set_parsed(&mut self.display_range.floor_db, value),
+            "range-span" => set_parsed(&mut self.display_range.range_db, value),
+            "theme" => {
+                if let Some(theme) = Theme::from_name(value) {
+                    self.theme = theme;
+                }
+            }
+            "palette" => {
+                if let Some(palette) = Palette::parse(value) {
+                    self.palette = palette;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Overwrite `field` with `value` if it parses.
+fn set_parsed<T: std::str::FromStr>(field: &mut T, value: &str) {
+    if let Ok(parsed) = value.parse() {
+        *field = parsed;
+    }
+}
+
+/// Named settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    /// Display name
+    pub name: String,
+    /// Settings restored when the profile is chosen
+    pub settings: UiSettings,
+}
+
+/// Encode the last settings and chosen profile for storage.
+pub fn encode_state(settings: &UiSettings, profile: Option<&str>) -> String {
+    let mut text = String::from(STATE_HEADER);
+    settings.write_lines(&mut text);
+    if let Some(name) = profile {
+        text.push_str("\nprofile=");
+        text.push_str(&clean_name(name));
+    }
+    text
+}
+
+/// Decode stored settings over `base`, with the chosen profile.
+pub fn decode_state(text: &str, base: &UiSettings) -> Option<(UiSettings, Option<String>)> {
+    let mut lines = text.lines();
+    if lines.next() != Some(STATE_HEADER) {
+        return None;
+    }
+    let mut settings = base.clone();
+    let mut profile = None;
+    for line in lines {
+        match line.strip_prefix("profile=") {
+            Some(name) => profile = Some(name.to_string()),
+            None => settings.read_line(line),
+        }
+    }
+    Some((settings, profile))
+}
+
+/// Encode profiles for storage, each a `[name]` line then its settings.
+pub fn encode_profiles(profiles: &[Profile]) -> String {
+    let mut text = String::from(PROFILES_HEADER);
+    for profile in profiles {
+        text.push_str("\n[");
+        text.push_str(&clean_name(&profile.name));
+        text.push(']');
+        profile.settings.write_lines(&mut text);
+    }
+    text
+}
+
+/// Decode stored profiles; settings missing from a profile come from `base`.
+pub fn decode_profiles(text: &str, base: &UiSettings) -> Vec<Profile> {
+    let mut lines = text.lines();
+    if lines.next() != Some(PROFILES_HEADER) {
+        return Vec::new();
+    }
+    let mut profiles: Vec<Profile> = Vec::new();
+    for line in lines {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            profiles.push(Profile {
+                name: name.to_string(),
+                settings: base.clone(),
+            });
+        } else if let Some(profile) = profiles.last_mut() {
+            profile.settings.read_line(line);
+        }
+    }
+    profiles
+}
+
+/// Replace the line breaks of the stored formats with spaces.
+fn clean_name(name: &str) -> String {
+    name.replace(['\n', '\r'], " ")
+}
+
+/// Add a profile, replacing any of the same name.
+pub fn store_profile(profiles: &mut Vec<Profile>, profile: Profile) {
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+}
+
+/// Get the page's localStorage.
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Read a stored item.
+fn load_item(key: &str) -> Option<String> {
+    storage()?.get_item(key).ok().flatten()
+}
+
+/// Write a stored item.
+fn save_item(key: &str, text: &str) {
+    if let Some(storage) = storage() {
+        if let Err(e) = storage.set_item(key, text) {
+            web_sys::console::error_1(&format!("UI state save error: {:?}", e).into());
+        }
+    }
+}
+
+/// Create the effects that restore the saved settings and profiles and
+/// save every change.
+///
+/// Run before the effects that act on the settings, so they start from
+/// the restored values.
+pub fn create_persistence_effect(ctx: AppContext) {
+    let base = untrack(|| UiSettings::capture(&ctx));
+    if let Some(text) = load_item(PROFILES_KEY) {
+        ctx.profiles.set(decode_profiles(&text, &base));
+    }
+    if let Some((settings, profile)) = load_item(STATE_KEY).and_then(|t| decode_state(&t, &base)) {
+        settings.apply(&ctx);
+        let known = ctx.profiles.with_untracked(|list| {
+            profile.filter(|name| list.iter().any(|p| &p.name == name))
+        });
+        ctx.active_profile.set(known);
+    }
+
+    create_effect(move |previous: Option<()>| {
+        let settings = UiSettings::capture(&ctx);
+        let profile = ctx.active_profile.get();
+        // Skip the run that only reflects what was just loaded
+        if previous.is_some() {
+            save_item(STATE_KEY, &encode_state(&settings, profile.as_deref()));
+        }
+    });
+
+    create_effect(move |previous: Option<()>| {
+        ctx.profiles.with(|list| {
+            if previous.is_some() {
+                save_item(PROFILES_KEY, &encode_profiles(list));
+            }
+        });
+    });
+}
+
+/// Profile dropdown with save, save-as and delete.
+#[component]
+pub fn ProfileSelector(ctx: AppContext) -> impl IntoView {
+    let new_name = create_rw_signal(String::new());
+    let has_profile = move || ctx.active_profile.with(Option::is_some);
+
+    let on_select = move |ev: web_sys::Event| {
+        let name = event_target_value(&ev);
+        let chosen = ctx
+            .profiles
+            .with_untracked(|list| list.iter().find(|p| p.name == name).cloned());
+        if let Some(profile) = &chosen {
+            profile.settings.apply(&ctx);
+        }
+        ctx.active_profile.set(chosen.map(|p| p.name));
+    };
+
+    let save_as = move |name: String| {
+        let settings = untrack(|| UiSettings::capture(&ctx));
+        ctx.profiles.update(|list| {
+            store_profile(
+                list,
+                Profile {
+                    name: name.clone(),
+                    settings,
+                },
+            )
+        });
+        ctx.active_profile.set(Some(name));
+    };
+
+    let on_save = move |_| {
+        if let Some(name) = ctx.active_profile.get_untracked() {
+            save_as(name);
+        }
+    };
+
+    let on_save_as = move |_| {
+        let name = clean_name(new_name.get_untracked().trim());
+        if !name.is_empty() {
+            save_as(name);
+            new_name.set(String::new());
+        }
+    };
+
+    let on_delete = move |_| {
+        if let Some(name) = ctx.active_profile.get_untracked() {
+            ctx.profiles.update(|list| list.retain(|p| p.name != name));
+            ctx.active_profile.set(None);
+        }
+    };
+
+    let options = move || {
+        ctx.profiles
+            .get()
+            .into_iter()
+            .map(|profile| {
+                let name = profile.name.clone();
+                let selected =
+                    move || ctx.active_profile.with(|a| a.as_deref() == Some(name.as_str()));
+                view! {
+                    <option value=profile.name.clone() selected=selected>
+                        {profile.name}
+                    </option>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="profile-selector">
+            <select class="profile" on:change=on_select>
+                <option value="" selected=move || !has_profile()>"No profile"</option>
+                {options}
+            </select>
+            <button
+                on:click=on_save
+                disabled=move || !has_profile()
+                title="Save the current settings to this profile"
+            >
+                "Save"
+            </button>
+            <button on:click=on_delete disabled=move || !has_profile()>"Delete"</button>
+            <input
+                type="text"
+                placeholder="New profile"
+                prop:value=move || new_name.get()
+                on:input=move |ev| new_name.set(event_target_value(&ev))
+            />
+            <button on:click=on_save_as>"Save as"</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> UiSettings {
+        UiSettings {
+            frequency: 7_074_000,
+            mode: RadioMode::Usb,
+            bandwidth: 2700.0,
+            agc_speed: AgcSpeed::Medium,
+            noise_reduction: 0,
+            tuning_step: 100,
+            spectrum_averaging: 4,
+            auto_range: true,
+            display_range: DisplayRange::new(-20.0, 80.0),
+            theme: Theme::Dark,
+            palette: Palette::default(),
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let base = settings();
+        let changed = UiSettings {
+            frequency: 14_060_000,
+            mode: RadioMode::Cw,
+            bandwidth: 500.0,
+            agc_speed: AgcSpeed::Fast,
+            noise_reduction: 3,
+            tuning_step: 10,
+            spectrum_averaging: 8,
+            auto_range: false,
+            display_range: DisplayRange::new(-30.0, 60.0),
+            theme: Theme::Light,
+            palette: Palette::presets()[1].1.clone(),
+        };
+
+        let text = encode_state(&changed, Some("CW\ncontest"));
+        let (decoded, profile) = decode_state(&text, &base).unwrap();
+        assert_eq!(decoded, changed);
+        assert_eq!(profile.as_deref(), Some("CW contest"));
+
+        let (_, profile) = decode_state(&encode_state(&changed, None), &base).unwrap();
+        assert_eq!(profile, None);
+    }
+
+    #[test]
+    fn test_state_keeps_unknown_values() {
+        let base = settings();
+        let text = format!("{}\nfrequency=abc\nmode=QAM\nnr=2\nspare=1", STATE_HEADER);
+        let (decoded, _) = decode_state(&text, &base).unwrap();
+        assert_eq!(decoded.frequency, base.frequency);
+        assert_eq!(decoded.mode, base.mode);
+        assert_eq!(decoded.noise_reduction, 2);
+
+        assert!(decode_state("sdr-ui-state 2\nnr=2", &base).is_none());
+    }
+
+    #[test]
+    fn test_profiles_round_trip() {
+        let base = settings();
+        let mut profiles = Vec::new();
+        store_profile(
+            &mut profiles,
+            Profile {
+                name: "FT8".into(),
+                settings: base.clone(),
+            },
+        );
+        let cw = UiSettings {
+            mode: RadioMode::Cw,
+            bandwidth: 400.0,
+            ..base.clone()
+        };
+        store_profile(
+            &mut profiles,
+            Profile {
+                name: "CW".into(),
+                settings: cw.clone(),
+            },
+        );
+        assert_eq!(decode_profiles(&encode_profiles(&profiles), &base), profiles);
+
+        // Storing under an existing name replaces it
+        store_profile(
+            &mut profiles,
+            Profile {
+                name: "FT8".into(),
+                settings: cw,
+            },
+        );
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].settings.mode, RadioMode::Cw);
+    }
+
+    #[test]
+    fn test_profiles_missing_settings_from_base() {
+        let base = settings();
+        let text = format!("{}\n[Night]\ntheme=Light\n[Empty]", PROFILES_HEADER);
+        let profiles = decode_profiles(&text, &base);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "Night");
+        assert_eq!(profiles[0].settings.theme, Theme::Light);
+        assert_eq!(profiles[0].settings.frequency, base.frequency);
+        assert_eq!(profiles[1].settings, base);
+
+        assert!(decode_profiles("garbage", &base).is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+//! Themes and waterfall palettes.
+//!
+//! A dark or light theme sets the page and band scope colors. The
+//! waterfall maps levels through a palette of color stops, from weakest
+//! at 0.0 to strongest at 1.0, chosen from the presets or edited by hand.
+//! Both are part of the saved settings, so each profile keeps its own.
+
+use leptos::*;
+
+use crate::state::AppContext;
+
+/// Most color stops in a palette.
+pub const MAX_PALETTE_STOPS: usize = 8;
+
+/// Entries in the palette lookup table sent to the waterfall.
+pub const PALETTE_TABLE_LEN: usize = 256;
+
+/// Color theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Light text on dark backgrounds
+    #[default]
+    Dark,
+    /// Dark text on light backgrounds
+    Light,
+}
+
+/// Colors used by a theme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThemeColors {
+    /// Page background
+    pub background: &'static str,
+    /// Panel and control background
+    pub surface: &'static str,
+    /// Text
+    pub text: &'static str,
+    /// Band scope background
+    pub scope_background: &'static str,
+    /// Band scope spectrum trace
+    pub trace: &'static str,
+    /// Band scope passband shading
+    pub passband: &'static str,
+    /// Band scope BFO marker
+    pub bfo: &'static str,
+}
+
+impl Theme {
+    /// Get all themes.
+    pub fn all() -> &'static [Theme] {
+        &[Theme::Dark, Theme::Light]
+    }
+
+    /// Get display name for the theme.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+
+    /// Parse a display name written by [`Theme::name`].
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::all().iter().copied().find(|t| t.name() == name)
+    }
+
+    /// Get the theme's colors.
+    pub fn colors(&self) -> ThemeColors {
+        match self {
+            Theme::Dark => ThemeColors {
+                background: "#121418",
+                surface: "#1e2228",
+                text: "#e0e4ea",
+                scope_background: "#000000",
+                trace: "#ffd700",
+                passband: "rgba(120, 170, 255, 0.25)",
+                bfo: "#ff4040",
+            },
+            Theme::Light => ThemeColors {
+                background: "#f4f5f7",
+                surface: "#ffffff",
+                text: "#1a1c20",
+                scope_background: "#ffffff",
+                trace: "#1040a0",
+                passband: "rgba(40, 90, 200, 0.18)",
+                bfo: "#d01010",
+            },
+        }
+    }
+
+    /// Get the inline style applying the theme to the page.
+    ///
+    /// The colors are also set as `--sdr-*` custom properties for the
+    /// stylesheet to use.
+    pub fn style(&self) -> String {
+        let colors = self.colors();
+        format!(
+            "background: {bg}; color: {text}; color-scheme: {scheme}; \
+             --sdr-background: {bg}; --sdr-surface: {surface}; --sdr-text: {text};",
+            bg = colors.background,
+            surface = colors.surface,
+            text = colors.text,
+            scheme = self.name().to_lowercase(),
+        )
+    }
+}
+
+/// Color at a point of a palette.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop {
+    /// Level from 0.0 (weakest) to 1.0 (strongest)
+    pub position: f32,
+    /// Red, green and blue
+    pub color: [u8; 3],
+}
+
+impl GradientStop {
+    /// Create a stop, with the position clamped to 0.0..=1.0.
+    pub fn new(position: f32, color: [u8; 3]) -> Self {
+        Self {
+            position: position.clamp(0.0, 1.0),
+            color,
+        }
+    }
+}
+
+/// Waterfall color gradient.
+///
+/// Holds two to [`MAX_PALETTE_STOPS`] stops in position order. Levels
+/// between stops are blended linearly; levels outside them take the
+/// nearest stop's color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    stops: Vec<GradientStop>,
+}
+
+impl Palette {
+    /// Create a palette from stops, sorted by position.
+    ///
+    /// Returns `None` for fewer than two or more than
+    /// [`MAX_PALETTE_STOPS`] stops.
+    pub fn new(mut stops: Vec<GradientStop>) -> Option<Self> {
+        if !(2..=MAX_PALETTE_STOPS).contains(&stops.len()) {
+            return None;
+        }
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Some(Self { stops })
+    }
+
+    /// Build a preset from `(position, color)` pairs.
+    fn preset(stops: &[(f32, [u8; 3])]) -> Self {
+        Self {
+            stops: stops.iter().map(|&(p, c)| GradientStop::new(p, c)).collect(),
+        }
+    }
+
+    /// Black, blue, cyan, green, yellow to white (the original waterfall).
+    pub fn classic() -> Self {
+        Self::preset(&[
+            (0.0, [0, 0, 0]),
+            (0.2, [0, 0, 255]),
+            (0.4, [0, 255, 255]),
+            (0.6, [0, 255, 0]),
+            (0.8, [255, 255, 0]),
+            (1.0, [255, 128, 255]),
+        ])
+    }
+
+    /// Black to white.
+    pub fn grayscale() -> Self {
+        Self::preset(&[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])])
+    }
+
+    /// Black, red, yellow to white.
+    pub fn heat() -> Self {
+        Self::preset(&[
+            (0.0, [0, 0, 0]),
+            (0.4, [200, 0, 0]),
+            (0.75, [255, 220, 0]),
+            (1.0, [255, 255, 255]),
+        ])
+    }
+
+    /// White, blue to dark red, for the light theme.
+    pub fn paper() -> Self {
+        Self::preset(&[
+            (0.0, [255, 255, 255]),
+            (0.4, [160, 190, 255]),
+            (0.7, [0, 60, 200]),
+            (1.0, [120, 0, 0]),
+        ])
+    }
+
+    /// Get the presets with their names.
+    pub fn presets() -> Vec<(&'static str, Palette)> {
+        vec![
+            ("Classic", Self::classic()),
+            ("Grayscale", Self::grayscale()),
+            ("Heat", Self::heat()),
+            ("Paper", Self::paper()),
+        ]
+    }
+
+    /// Get the stops in position order.
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    /// Get the color at a level from 0.0 to 1.0.
+    pub fn color_at(&self, level: f32) -> [u8; 3] {
+        let first = self.stops[0];
+        if level <= first.position {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if level <= b.position {
+                let width = b.position - a.position;
+                let t = if width > 0.0 { (level - a.position) / width } else { 1.0 };
+                let mix = |x: u8, y: u8| {
+                    let (x, y) = (f32::from(x), f32::from(y));
+                    (x + (y - x) * t).round() as u8
+                };
+                return [
+                    mix(a.color[0], b.color[0]),
+                    mix(a.color[1], b.color[1]),
+                    mix(a.color[2], b.color[2]),
+                ];
+            }
+        }
+        self.stops[self.stops.len() - 1].color
+    }
+
+    /// Get the lookup table for the waterfall: [`PALETTE_TABLE_LEN`]
+    /// RGB triples from level 0.0 to 1.0.
+    pub fn lookup_table(&self) -> Vec<u8> {
+        let last = (PALETTE_TABLE_LEN - 1) as f32;
+        (0..PALETTE_TABLE_LEN)
+            .flat_map(|i| self.color_at(i as f32 / last))
+            .collect()
+    }
+
+    /// Get a CSS `linear-gradient` previewing the palette left to right.
+    pub fn css_gradient(&self) -> String {
+        let stops: Vec<String> = self
+            .stops
+            .iter()
+            .map(|s| format!("{} {:.0}%", color_hex(s.color), s.position * 100.0))
+            .collect();
+        format!("linear-gradient(to right, {})", stops.join(", "))
+    }
+
+    /// Set the color of a stop.
+    pub fn set_color(&mut self, index: usize, color: [u8; 3]) {
+        if let Some(stop) = self.stops.get_mut(index) {
+            stop.color = color;
+        }
+    }
+
+    /// Move a stop, keeping it between its neighbours so the order holds.
+    pub fn set_position(&mut self, index: usize, position: f32) {
+        if index >= self.stops.len() {
+            return;
+        }
+        let low = index.checked_sub(1).map_or(0.0, |i| self.stops[i].position);
+        let high = self.stops.get(index + 1).map_or(1.0, |s| s.position);
+        self.stops[index].position = position.clamp(low, high);
+    }
+
+    /// Add a stop in the middle of the widest gap, blending its
+    /// neighbours. Does nothing at [`MAX_PALETTE_STOPS`].
+    pub fn add_stop(&mut self) {
+        if self.stops.len() >= MAX_PALETTE_STOPS {
+            return;
+        }
+        let widest = (1..self.stops.len()).max_by(|&a, &b| {
+            let gap = |i: usize| self.stops[i].position - self.stops[i - 1].position;
+            gap(a).total_cmp(&gap(b))
+        });
+        if let Some(index) = widest {
+            let position = (self.stops[index - 1].position + self.stops[index].position) / 2.0;
+            let stop = GradientStop::new(position, self.color_at(position));
+            self.stops.insert(index, stop);
+        }
+    }
+
+    /// Remove a stop, keeping at least two.
+    pub fn remove_stop(&mut self, index: usize) {
+        if self.stops.len() > 2 && index < self.stops.len() {
+            self.stops.remove(index);
+        }
+    }
+
+    /// Encode as `position:rrggbb` pairs separated by commas.
+    pub fn encode(&self) -> String {
+        let stops: Vec<String> = self
+            .stops
+            .iter()
+            .map(|s| format!("{}:{}", s.position, &color_hex(s.color)[1..]))
+            .collect();
+        stops.join(",")
+    }
+
+    /// Parse a palette written by [`Palette::encode`].
+    pub fn parse(text: &str) -> Option<Self> {
+        let stops = text
+            .split(',')
+            .map(|stop| {
+                let (position, color) = stop.split_once(':')?;
+                let position: f32 = position.trim().parse().ok()?;
+                if !position.is_finite() {
+                    return None;
+                }
+                Some(GradientStop::new(position, parse_hex(color)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Self::new(stops)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+/// Format a color as `#rrggbb`.
+pub fn color_hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Parse a `#rrggbb` or `rrggbb` color.
+pub fn parse_hex(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Waterfall palette editor: presets, and a color and position per stop.
+#[component]
+pub fn PaletteEditor(ctx: AppContext) -> impl IntoView {
+    let on_preset = move |ev: web_sys::Event| {
+        let name = event_target_value(&ev);
+        if let Some((_, palette)) = Palette::presets().into_iter().find(|(n, _)| *n == name) {
+            ctx.palette.set(palette);
+        }
+    };
+
+    let preset_options = move || {
+        let current = ctx.palette.get();
+        let custom = !Palette::presets().iter().any(|(_, p)| *p == current);
+        let presets = Palette::presets()
+            .into_iter()
+            .map(|(name, palette)| {
+                let selected = palette == current;
+                view! { <option value=name selected=selected>{name}</option> }
+            })
+            .collect_view();
+        view! {
+            <option value="" selected=custom disabled=true>"Custom"</option>
+            {presets}
+        }
+    };
+
+    // Stop rows are rebuilt on every change, so they update on `change`
+    // rather than `input`, which would replace a control while in use
+    let stop_rows = move || {
+        let palette = ctx.palette.get();
+        let removable = palette.stops().len() > 2;
+        palette
+            .stops()
+            .iter()
+            .enumerate()
+            .map(|(index, stop)| {
+                let on_color = move |ev: web_sys::Event| {
+                    if let Some(color) = parse_hex(&event_target_value(&ev)) {
+                        ctx.palette.update(|p| p.set_color(index, color));
+                    }
+                };
+                let on_position = move |ev: web_sys::Event| {
+                    if let Ok(percent) = event_target_value(&ev).parse::<f32>() {
+                        ctx.palette.update(|p| p.set_position(index, percent / 100.0));
+                    }
+                };
+                let percent = stop.position * 100.0;
+                view! {
+                    <div class="palette-stop">
+                        <input type="color" value=color_hex(stop.color) on:change=on_color />
+                        <input
+                            type="range"
+                            min="0"
+                            max="100"
+                            step="1"
+                            value=format!("{:.0}", percent)
+                            on:change=on_position
+                        />
+                        <span>{format!("{:.0}%", percent)}</span>
+                        <button
+                            on:click=move |_| ctx.palette.update(|p| p.remove_stop(index))
+                            disabled=!removable
+                            title="Remove this stop"
+                        >
+                            "×"
+                        </button>
+                    </div>
+                }
+            })
+            .collect_view()
+    };
+
+    let full = move || ctx.palette.with(|p| p.stops().len() >= MAX_PALETTE_STOPS);
+
+    view! {
+        <div class="palette-editor">
+            <h3>"Waterfall Colors"</h3>
+            <label>
+                "Palette "
+                <select on:change=on_preset>{preset_options}</select>
+            </label>
+            <div
+                class="palette-preview"
+                style=move || {
+                    let gradient = ctx.palette.with(Palette::css_gradient);
+                    format!("height: 16px; background: {};", gradient)
+                }
+            />
+            {stop_rows}
+            <button on:click=move |_| ctx.palette.update(Palette::add_stop) disabled=full>
+                "Add stop"
+            </button>
+        </div>
+    }
+}
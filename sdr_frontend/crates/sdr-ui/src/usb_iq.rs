@@ -0,0 +1,262 @@
+//! WebUSB IQ streaming transport.
+//!
+//! Receives interleaved IQ frames from the radio's vendor-specific USB
+//! bulk endpoint and converts them to the interleaved `f32` layout the
+//! AudioWorklet DspProcessor expects.
+//!
+//! Frame layout (little endian):
+//!
+//! | Offset | Size | Field                          |
+//! |--------|------|--------------------------------|
+//! | 0      | 2    | Magic `"IQ"`                   |
+//! | 2      | 2    | Sequence number                |
+//! | 4      | 2    | Sample pair count `n`          |
+//! | 6      | 1    | Flags (bit 0 = ADC overflow)   |
+//! | 7      | 1    | Reserved                       |
+//! | 8      | 4n   | `n` × (I `i16`, Q `i16`)       |
+//!
+//! Note: WebUSB requires Chrome/Edge and an HTTPS context.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// USB vendor ID of the radio (pid.codes test VID).
+pub const USB_VENDOR_ID: u16 = 0x1209;
+
+/// USB product ID of the radio.
+pub const USB_PRODUCT_ID: u16 = 0x0001;
+
+/// Interface carrying the IQ bulk endpoint.
+pub const IQ_INTERFACE: u8 = 2;
+
+/// Bulk IN endpoint number for IQ frames.
+pub const IQ_ENDPOINT: u8 = 3;
+
+/// Bytes requested per bulk transfer.
+pub const TRANSFER_SIZE: u32 = 4096;
+
+/// Frame header length in bytes.
+pub const FRAME_HEADER_LEN: usize = 8;
+
+/// Frame magic bytes.
+const FRAME_MAGIC: [u8; 2] = *b"IQ";
+
+/// Frame flag: ADC overflow occurred while capturing this frame.
+pub const FLAG_OVERFLOW: u8 = 0x01;
+
+/// Where the IQ samples for the DSP come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IqSource {
+    /// Stereo soundcard input (L = I, R = Q)
+    #[default]
+    Soundcard,
+    /// WebUSB bulk stream from the radio
+    Usb,
+    /// Recorded capture played back from a file
+    File,
+}
+
+impl IqSource {
+    /// Get display name for the source.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IqSource::Soundcard => "Soundcard",
+            IqSource::Usb => "USB",
+            IqSource::File => "File",
+        }
+    }
+}
+
+/// A decoded IQ frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IqFrame {
+    /// Frame sequence number
+    pub sequence: u16,
+    /// Frame flags
+    pub flags: u8,
+    /// Interleaved I/Q samples normalized to -1.0..1.0
+    pub samples: Vec<f32>,
+}
+
+/// Incremental frame parser.
+///
+/// Bulk transfers do not respect frame boundaries, so bytes are
+/// accumulated until a complete frame is available. Garbage before a
+/// frame magic is skipped.
+#[derive(Default)]
+pub struct IqFrameParser {
+    buffer: Vec<u8>,
+    last_sequence: Option<u16>,
+    dropped_frames: u32,
+}
+
+impl IqFrameParser {
+    /// Create a new parser.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append received bytes.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Extract the next complete frame, if any.
+    pub fn next_frame(&mut self) -> Option<IqFrame> {
+        loop {
+            // Resynchronize on the magic
+            let start = self.buffer.windows(2).position(|w| w == FRAME_MAGIC);
+            match start {
+                Some(0) => {}
+                Some(pos) => {
+                    self.buffer.drain(..pos);
+                }
+                None => {
+                    // Keep a trailing 'I' that may start the next magic
+                    let keep = usize::from(self.buffer.last() == Some(&FRAME_MAGIC[0]));
+                    let len = self.buffer.len();
+                    self.buffer.drain(..len - keep);
+                    return None;
+                }
+            }
+
+            if self.buffer.len() < FRAME_HEADER_LEN {
+                return None;
+            }
+
+            let sequence = u16::from_le_bytes([self.buffer[2], self.buffer[3]]);
+            let count = usize::from(u16::from_le_bytes([self.buffer[4], self.buffer[5]]));
+            let flags = self.buffer[6];
+            let frame_len = FRAME_HEADER_LEN + count * 4;
+
+            if count == 0 {
+                // Not a valid frame, skip the magic and resync
+                self.buffer.drain(..2);
+                continue;
+            }
+            if self.buffer.len() < frame_len {
+                return None;
+            }
+
+            let samples = self.buffer[FRAME_HEADER_LEN..frame_len]
+                .chunks_exact(2)
+                .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+                .collect();
+            self.buffer.drain(..frame_len);
+
+            if let Some(last) = self.last_sequence {
+                let gap = sequence.wrapping_sub(last).wrapping_sub(1);
+                self.dropped_frames = self.dropped_frames.saturating_add(u32::from(gap));
+            }
+            self.last_sequence = Some(sequence);
+
+            return Some(IqFrame {
+                sequence,
+                flags,
+                samples,
+            });
+        }
+    }
+
+    /// Number of frames lost according to sequence gaps.
+    pub fn dropped_frames(&self) -> u32 {
+        self.dropped_frames
+    }
+
+    /// Clear buffered bytes and statistics.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.last_sequence = None;
+        self.dropped_frames = 0;
+    }
+}
+
+/// Call an async method on a JS object and await the returned promise.
+async fn call_async(target: &JsValue, name: &str, args: &js_sys::Array) -> Result<JsValue, JsValue> {
+    let method = js_sys::Reflect::get(target, &name.into())?.dyn_into::<js_sys::Function>()?;
+    let promise = method.apply(target, args)?;
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(promise)).await
+}
+
+/// WebUSB connection to the radio's IQ endpoint.
+#[derive(Clone)]
+pub struct UsbIqStream {
+    device: JsValue,
+}
+
+impl UsbIqStream {
+    /// Check if WebUSB is available.
+    pub fn is_available() -> bool {
+        if let Some(window) = web_sys::window() {
+            js_sys::Reflect::has(&window.navigator(), &"usb".into()).unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
+    /// Ask the user to pick the radio and open the IQ interface.
+    pub async fn connect() -> Result<Self, JsValue> {
+        if !Self::is_available() {
+            return Err("WebUSB not available".into());
+        }
+        let window = web_sys::window().ok_or("No window")?;
+        let usb = js_sys::Reflect::get(&window.navigator(), &"usb".into())?;
+
+        // requestDevice({ filters: [{ vendorId, productId }] })
+        let filter = js_sys::Object::new();
+        js_sys::Reflect::set(&filter, &"vendorId".into(), &USB_VENDOR_ID.into())?;
+        js_sys::Reflect::set(&filter, &"productId".into(), &USB_PRODUCT_ID.into())?;
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, &"filters".into(), &js_sys::Array::of1(&filter))?;
+
+        let device = call_async(&usb, "requestDevice", &js_sys::Array::of1(&options)).await?;
+
+        call_async(&device, "open", &js_sys::Array::new()).await?;
+        call_async(&device, "selectConfiguration", &js_sys::Array::of1(&1.into())).await?;
+        call_async(
+            &device,
+            "claimInterface",
+            &js_sys::Array::of1(&IQ_INTERFACE.into()),
+        )
+        .await?;
+
+        Ok(Self { device })
+    }
+
+    /// Read one bulk transfer worth of bytes.
+    pub async fn read(&self) -> Result<Vec<u8>, JsValue> {
+        let result = call_async(
+            &self.device,
+            "transferIn",
+            &js_sys::Array::of2(&IQ_ENDPOINT.into(), &TRANSFER_SIZE.into()),
+        )
+        .await?;
+
+        let status = js_sys::Reflect::get(&result, &"status".into())?
+            .as_string()
+            .unwrap_or_default();
+        if status != "ok" {
+            return Err(format!("USB transfer status: {}", status).into());
+        }
+
+        let data = js_sys::Reflect::get(&result, &"data".into())?.dyn_into::<js_sys::DataView>()?;
+        let bytes = js_sys::Uint8Array::new_with_byte_offset_and_length(
+            &data.buffer(),
+            data.byte_offset() as u32,
+            data.byte_length() as u32,
+        );
+        Ok(bytes.to_vec())
+    }
+
+    /// Release the interface and close the device.
+    pub async fn close(&self) -> Result<(), JsValue> {
+        call_async(
+            &self.device,
+            "releaseInterface",
+            &js_sys::Array::of1(&IQ_INTERFACE.into()),
+        )
+        .await?;
+        call_async(&self.device, "close", &js_sys::Array::new()).await?;
+        Ok(())
+    }
+}
@@ -3,20 +3,61 @@
  *
  * Runs Rust WASM DSP code in the AudioWorklet thread for
  * real-time IQ processing with low latency.
+ *
+ * Loads sdr-dsp-wasm through its wasm-bindgen glue (built with
+ * `wasm-bindgen --target web` next to this file), so blocks move through
+ * the typed calls rather than offsets into WASM memory.
  */
 
+import {
+    initSync,
+    DspProcessor,
+    get_api_version,
+    get_capabilities,
+    get_extended_capabilities,
+} from './sdr_dsp_wasm.js';
+
 class SdrDspProcessor extends AudioWorkletProcessor {
     constructor(options) {
         super();
 
         this.wasmReady = false;
-        this.wasmInstance = null;
         this.wasmExports = null;
-        this.dspProcessor = null;
+        this.dsp = null;
+        this.iqBlock = new Float32Array(256); // interleaved I/Q, one block
+        this.audioBlock = new Float32Array(128);
+        this.peakBlock = new Float32Array(3 * 16); // frequency, level, width
         this.spectrumBuffer = null;
         this.spectrumView = null;
         this.frameCount = 0;
 
+        // IQ source: 'soundcard' (stereo input), or 'usb' / 'file' (pushed frames)
+        this.iqSource = 'soundcard';
+        this.iqRing = new Float32Array(32768); // interleaved I/Q
+        this.iqRead = 0;
+        this.iqWrite = 0;
+
+        // Speaker output muted (the taps still run)
+        this.muted = false;
+
+        // Demodulated audio tap (for the recorder)
+        this.audioTap = false;
+        this.tapBuffer = new Float32Array(1024);
+        this.tapLength = 0;
+
+        // Raw IQ tap (for IQ WAV recording), interleaved I/Q
+        this.iqTap = false;
+        this.iqTapBuffer = new Float32Array(2048);
+        this.iqTapLength = 0;
+
+        // Running PSK31 decoder ids, polled for signal quality
+        this.pskChannels = new Set();
+        this.qualityFrameCount = 0;
+
+        // CW skimmer running, polled for its signal table
+        this.cwSkimmer = false;
+        this.cwSignalFrameCount = 0;
+
         // Handle messages from main thread
         this.port.onmessage = (event) => this.handleMessage(event.data);
     }
@@ -27,68 +68,155 @@ class SdrDspProcessor extends AudioWorkletProcessor {
                 await this.initWasm(data.wasmModule, data.spectrumBuffer);
                 break;
 
-            case 'setMode':
-                if (this.wasmExports && this.dspProcessor) {
-                    this.wasmExports.set_mode(this.dspProcessor, data.mode);
+            case 'applyConfig':
+                this.applyConfig(data.config);
+                break;
+
+            case 'setFilter':
+                this.applyConfig({ filterBandwidth: data.bandwidth });
+                break;
+
+            case 'setParameter':
+                if (this.dsp) {
+                    this.dsp.set_parameter(data.param, data.value);
                 }
                 break;
 
-            case 'setFrequency':
-                if (this.wasmExports && this.dspProcessor) {
-                    this.wasmExports.set_frequency_offset(this.dspProcessor, data.offsetHz);
+            case 'setSource':
+                this.iqSource = data.source;
+                this.iqRead = 0;
+                this.iqWrite = 0;
+                break;
+
+            case 'iqData':
+                this.pushIq(data.data);
+                break;
+
+            case 'setInputRate':
+                if (this.dsp) {
+                    this.dsp.set_input_rate(data.sampleRate || sampleRate);
                 }
                 break;
 
-            case 'setFilter':
-                if (this.wasmExports && this.dspProcessor) {
-                    this.wasmExports.set_filter_bandwidth(this.dspProcessor, data.bandwidth);
+            case 'setAudioTap':
+                this.audioTap = !!data.enabled;
+                this.tapLength = 0;
+                break;
+
+            case 'setMuted':
+                this.muted = !!data.muted;
+                break;
+
+            case 'setIqTap':
+                this.iqTap = !!data.enabled;
+                this.iqTapLength = 0;
+                break;
+
+            case 'addPskDecoder':
+                if (this.dsp) {
+                    const id = this.dsp.add_psk_decoder(data.offsetHz);
+                    if (id >= 0) this.pskChannels.add(id);
+                    this.port.postMessage({ type: 'pskDecoder', id, offsetHz: data.offsetHz });
                 }
                 break;
 
-            case 'setAgc':
-                if (this.wasmExports && this.dspProcessor) {
-                    this.wasmExports.set_agc(
-                        this.dspProcessor,
-                        data.attack_ms,
-                        data.decay_ms,
-                        data.hang_ms
-                    );
+            case 'removePskDecoder':
+                if (this.dsp) {
+                    this.dsp.remove_psk_decoder(data.id);
+                    this.pskChannels.delete(data.id);
+                }
+                break;
+
+            case 'setPskAfc':
+                if (this.dsp) {
+                    this.dsp.set_psk_afc(data.enabled, data.bandwidthHz, data.rangeHz);
+                }
+                break;
+
+            case 'clearPskDecoders':
+                if (this.dsp) {
+                    this.dsp.clear_psk_decoders();
+                    this.pskChannels.clear();
+                }
+                break;
+
+            case 'setSstv':
+                if (this.dsp) {
+                    this.dsp.set_sstv_enabled(data.enabled);
+                }
+                break;
+
+            case 'setAprs':
+                if (this.dsp) {
+                    this.dsp.set_aprs_enabled(data.enabled);
+                }
+                break;
+
+            case 'setJs8':
+                if (this.dsp) {
+                    this.dsp.set_js8_frequency(data.frequencyHz);
+                    this.dsp.set_js8_enabled(data.enabled);
+                }
+                break;
+
+            case 'setHell':
+                if (this.dsp) {
+                    this.dsp.set_hell_frequency(data.frequencyHz);
+                    this.dsp.set_hell_enabled(data.enabled);
+                }
+                break;
+
+            case 'setCwSkimmer':
+                if (this.dsp) {
+                    this.dsp.set_cw_skimmer_enabled(data.enabled);
+                    this.cwSkimmer = data.enabled;
                 }
                 break;
 
             case 'reset':
-                if (this.wasmExports && this.dspProcessor) {
-                    this.wasmExports.reset(this.dspProcessor);
+                if (this.dsp) {
+                    this.dsp.reset();
                 }
                 break;
         }
     }
 
-    async initWasm(wasmModule, spectrumBuffer) {
+    applyConfig(config) {
+        if (!this.dsp) {
+            return;
+        }
         try {
-            // Minimal imports for WASM
-            const imports = {
-                env: {
-                    // Math functions (micromath should provide these, but just in case)
-                    sinf: Math.sin,
-                    cosf: Math.cos,
-                    sqrtf: Math.sqrt,
-                    log10f: Math.log10,
-                    powf: Math.pow,
-                    expf: Math.exp,
-                    atan2f: Math.atan2,
-                    fabsf: Math.abs,
-                    floorf: Math.floor,
-                    ceilf: Math.ceil,
-                }
-            };
+            // Settings object as documented for DspConfig; fields left
+            // out keep their values, and a bad object changes nothing
+            this.dsp.apply_config(config);
+        } catch (error) {
+            this.port.postMessage({
+                type: 'error',
+                message: `Config rejected: ${error.message}`
+            });
+        }
+    }
 
-            // Instantiate WASM module
-            this.wasmInstance = await WebAssembly.instantiate(wasmModule, imports);
-            this.wasmExports = this.wasmInstance.exports;
+    pushIq(samples) {
+        const size = this.iqRing.length;
+        for (let i = 0; i < samples.length; i++) {
+            this.iqRing[this.iqWrite] = samples[i];
+            this.iqWrite = (this.iqWrite + 1) % size;
+            if (this.iqWrite === this.iqRead) {
+                // Overrun: drop the oldest pair
+                this.iqRead = (this.iqRead + 2) % size;
+            }
+        }
+    }
+
+    async initWasm(wasmModule, spectrumBuffer) {
+        try {
+            // Instantiate WASM module through the glue; its exports are
+            // kept for the decoder tables still read out of WASM memory
+            this.wasmExports = initSync({ module: wasmModule });
 
             // Create DSP processor instance
-            this.dspProcessor = this.wasmExports.create_processor(sampleRate);
+            this.dsp = new DspProcessor(sampleRate);
 
             // Setup SharedArrayBuffer for spectrum data
             if (spectrumBuffer) {
@@ -97,7 +225,12 @@ class SdrDspProcessor extends AudioWorkletProcessor {
             }
 
             this.wasmReady = true;
-            this.port.postMessage({ type: 'ready' });
+            this.port.postMessage({
+                type: 'ready',
+                apiVersion: get_api_version(),
+                capabilities: get_capabilities(),
+                extendedCapabilities: get_extended_capabilities(),
+            });
 
         } catch (error) {
             this.port.postMessage({
@@ -108,59 +241,248 @@ class SdrDspProcessor extends AudioWorkletProcessor {
     }
 
     process(inputs, outputs, parameters) {
+        const usbSource = this.iqSource !== 'soundcard';
+
         // Skip if WASM not ready or no input
-        if (!this.wasmReady || inputs[0].length === 0) {
+        if (!this.wasmReady || (!usbSource && inputs[0].length === 0)) {
             return true;
         }
 
         const input = inputs[0];
         const output = outputs[0];
-        const numSamples = input[0]?.length || 128;
+        const numSamples = usbSource ? (output[0]?.length || 128) : (input[0]?.length || 128);
 
-        // Get I and Q channels (stereo input: L=I, R=Q)
-        const iChannel = input[0] || new Float32Array(numSamples);
-        const qChannel = input[1] || new Float32Array(numSamples);
-
-        // Get WASM buffer pointers
-        const inputPtr = this.wasmExports.get_input_buffer_ptr(this.dspProcessor);
-        const outputPtr = this.wasmExports.get_output_buffer_ptr(this.dspProcessor);
-        const spectrumPtr = this.wasmExports.get_spectrum_buffer_ptr(this.dspProcessor);
+        // Get I and Q channels (stereo input: L=I, R=Q, or from the pushed-IQ ring)
+        let iChannel = input[0] || new Float32Array(numSamples);
+        let qChannel = input[1] || new Float32Array(numSamples);
+        if (usbSource) {
+            iChannel = new Float32Array(numSamples);
+            qChannel = new Float32Array(numSamples);
+            const size = this.iqRing.length;
+            for (let i = 0; i < numSamples && this.iqRead !== this.iqWrite; i++) {
+                iChannel[i] = this.iqRing[this.iqRead];
+                qChannel[i] = this.iqRing[(this.iqRead + 1) % size];
+                this.iqRead = (this.iqRead + 2) % size;
+            }
+        }
 
-        // Copy input to WASM memory (interleaved I/Q)
-        const wasmMemory = new Float32Array(this.wasmExports.memory.buffer);
-        const inputOffset = inputPtr / 4; // Convert byte offset to f32 index
+        // Batch raw IQ for the IQ recorder tap
+        if (this.iqTap) {
+            for (let i = 0; i < numSamples; i++) {
+                this.iqTapBuffer[this.iqTapLength++] = iChannel[i];
+                this.iqTapBuffer[this.iqTapLength++] = qChannel[i];
+                if (this.iqTapLength === this.iqTapBuffer.length) {
+                    const block = this.iqTapBuffer.slice(0);
+                    this.port.postMessage(
+                        { type: 'iq', data: block, sampleRate: sampleRate },
+                        [block.buffer]
+                    );
+                    this.iqTapLength = 0;
+                }
+            }
+        }
 
+        // Copy input to WASM (interleaved I/Q)
+        if (this.iqBlock.length < numSamples * 2) {
+            this.iqBlock = new Float32Array(numSamples * 2);
+            this.audioBlock = new Float32Array(numSamples);
+        }
+        const iq = this.iqBlock.subarray(0, numSamples * 2);
         for (let i = 0; i < numSamples; i++) {
-            wasmMemory[inputOffset + i * 2] = iChannel[i];
-            wasmMemory[inputOffset + i * 2 + 1] = qChannel[i];
+            iq[i * 2] = iChannel[i];
+            iq[i * 2 + 1] = qChannel[i];
         }
+        this.dsp.write_input(iq);
 
         // Process audio through WASM DSP
-        this.wasmExports.process(this.dspProcessor, numSamples);
+        this.dsp.process(numSamples);
 
-        // Copy output from WASM memory (mono audio)
-        const outputOffset = outputPtr / 4;
+        // Copy output from WASM (mono audio; a different count if the IQ
+        // input rate is not the AudioContext rate)
+        const audio = this.audioBlock.subarray(0, numSamples);
+        const outputLen = this.dsp.read_output(audio);
         for (let i = 0; i < numSamples; i++) {
-            const sample = wasmMemory[outputOffset + i];
+            const sample = i < outputLen && !this.muted ? audio[i] : 0;
             if (output[0]) output[0][i] = sample;
             if (output[1]) output[1][i] = sample; // Duplicate to both channels
         }
 
+        // Batch demodulated audio for the recorder tap
+        if (this.audioTap) {
+            for (let i = 0; i < outputLen; i++) {
+                this.tapBuffer[this.tapLength++] = audio[i];
+                if (this.tapLength === this.tapBuffer.length) {
+                    const block = this.tapBuffer.slice(0);
+                    this.port.postMessage(
+                        { type: 'audio', data: block, sampleRate: sampleRate },
+                        [block.buffer]
+                    );
+                    this.tapLength = 0;
+                }
+            }
+        }
+
+        // Forward characters from the PSK31 decoder bank, one message per channel
+        const decodedLen = this.dsp.get_psk_decoded_len();
+        if (decodedLen > 0) {
+            const decodedPtr = this.dsp.get_psk_decoded_ptr();
+            const pairs = new Uint32Array(this.wasmExports.memory.buffer, decodedPtr, decodedLen);
+            const texts = new Map();
+            for (let i = 0; i < decodedLen; i += 2) {
+                const text = texts.get(pairs[i]) || '';
+                texts.set(pairs[i], text + String.fromCodePoint(pairs[i + 1]));
+            }
+            this.dsp.clear_psk_decoded();
+            for (const [channel, text] of texts) {
+                this.port.postMessage({ type: 'decoded', channel, text });
+            }
+        }
+
+        // Forward finished SSTV scanlines as RGB bytes, one message per line
+        const sstvLines = this.dsp.get_sstv_line_count();
+        if (sstvLines > 0) {
+            const width = this.dsp.get_sstv_width();
+            const mode = this.dsp.get_sstv_mode();
+            const numbers = new Uint32Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_sstv_line_numbers_ptr(),
+                sstvLines
+            );
+            const pixels = new Uint8Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_sstv_pixels_ptr(),
+                sstvLines * width * 3
+            );
+            for (let i = 0; i < sstvLines; i++) {
+                const rgb = pixels.slice(i * width * 3, (i + 1) * width * 3);
+                this.port.postMessage(
+                    { type: 'sstvLine', line: numbers[i], mode, width, pixels: rgb },
+                    [rgb.buffer]
+                );
+            }
+            this.dsp.clear_sstv_lines();
+        }
+
+        // Forward AX.25 frames from the APRS decoder, one message per frame
+        const aprsFrames = this.dsp.get_aprs_frame_count();
+        if (aprsFrames > 0) {
+            const lengths = new Uint32Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_aprs_frame_lengths_ptr(),
+                aprsFrames
+            );
+            const framesPtr = this.dsp.get_aprs_frames_ptr();
+            let offset = 0;
+            for (let i = 0; i < aprsFrames; i++) {
+                const frame = new Uint8Array(
+                    this.wasmExports.memory.buffer, framesPtr + offset, lengths[i]).slice();
+                offset += lengths[i];
+                this.port.postMessage({ type: 'aprsFrame', data: frame }, [frame.buffer]);
+            }
+            this.dsp.clear_aprs_frames();
+        }
+
+        // Forward JS8 frames (packed words, decoded by the UI)
+        const js8Frames = this.dsp.get_js8_frame_count();
+        if (js8Frames > 0) {
+            const size = this.dsp.get_js8_frame_size();
+            const framesPtr = this.dsp.get_js8_frames_ptr();
+            for (let i = 0; i < js8Frames; i++) {
+                const frame = new Uint8Array(
+                    this.wasmExports.memory.buffer, framesPtr + i * size, size).slice();
+                this.port.postMessage({ type: 'js8Frame', data: frame }, [frame.buffer]);
+            }
+            this.dsp.clear_js8_frames();
+        }
+
+        // Forward Feld Hell columns, back to back, top pixel first
+        const hellColumns = this.dsp.get_hell_column_count();
+        if (hellColumns > 0) {
+            const height = this.dsp.get_hell_column_height();
+            const pixels = new Uint8Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_hell_columns_ptr(),
+                hellColumns * height
+            ).slice();
+            this.port.postMessage({ type: 'hellColumns', height, pixels }, [pixels.buffer]);
+            this.dsp.clear_hell_columns();
+        }
+
+        // Forward characters from the CW skimmer, one message per signal
+        const cwLen = this.dsp.get_cw_decoded_len();
+        if (cwLen > 0) {
+            const pairs = new Uint32Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_cw_decoded_ptr(),
+                cwLen
+            );
+            const texts = new Map();
+            for (let i = 0; i < cwLen; i += 2) {
+                const text = texts.get(pairs[i]) || '';
+                texts.set(pairs[i], text + String.fromCodePoint(pairs[i + 1]));
+            }
+            this.dsp.clear_cw_decoded();
+            for (const [signal, text] of texts) {
+                this.port.postMessage({ type: 'cwDecoded', signal, text });
+            }
+        }
+
+        // Report the CW skimmer's signal table every 128 frames
+        this.cwSignalFrameCount++;
+        if (this.cwSignalFrameCount >= 128 && this.cwSkimmer) {
+            this.cwSignalFrameCount = 0;
+            const count = this.dsp.get_cw_signal_count();
+            const fields = new Float32Array(
+                this.wasmExports.memory.buffer,
+                this.dsp.get_cw_signals_ptr(),
+                count * 4
+            );
+            const signals = [];
+            for (let i = 0; i < count; i++) {
+                signals.push({
+                    id: fields[i * 4],
+                    frequencyHz: fields[i * 4 + 1],
+                    wpm: fields[i * 4 + 2],
+                    snr: fields[i * 4 + 3],
+                });
+            }
+            this.port.postMessage({ type: 'cwSignals', signals });
+        }
+
+        // Report PSK31 signal quality, AFC offset and lock every 128 frames (~340ms at 48kHz)
+        this.qualityFrameCount++;
+        if (this.qualityFrameCount >= 128 && this.pskChannels.size > 0) {
+            this.qualityFrameCount = 0;
+            for (const channel of this.pskChannels) {
+                this.port.postMessage({
+                    type: 'pskQuality',
+                    channel,
+                    snr: this.dsp.get_psk_decoder_phase_snr(channel),
+                    evm: this.dsp.get_psk_decoder_evm(channel),
+                    imd: this.dsp.get_psk_decoder_imd(channel),
+                    offsetHz: this.dsp.get_psk_decoder_offset(channel),
+                    locked: this.dsp.get_psk_decoder_locked(channel),
+                });
+            }
+        }
+
         // Copy spectrum data to SharedArrayBuffer every 8 frames (~21ms at 48kHz)
         this.frameCount++;
         if (this.frameCount >= 8 && this.spectrumView) {
             this.frameCount = 0;
 
-            const spectrumOffset = spectrumPtr / 4;
-            const spectrumSize = 512;
-
-            for (let i = 0; i < spectrumSize; i++) {
-                this.spectrumView[i] = wasmMemory[spectrumOffset + i];
-            }
+            this.dsp.read_spectrum(this.spectrumView);
 
             // Send S-meter update
-            const smeter = this.wasmExports.get_smeter(this.dspProcessor);
-            this.port.postMessage({ type: 'smeter', value: smeter });
+            const smeter = this.dsp.get_smeter();
+            const dbm = this.dsp.get_smeter_dbm();
+            this.port.postMessage({ type: 'smeter', value: smeter, dbm });
+
+            // Send the strongest spectrum peaks
+            const peakValues = this.dsp.read_spectrum_peaks(this.peakBlock);
+            if (peakValues > 0) {
+                this.port.postMessage({ type: 'peaks', data: this.peakBlock.slice(0, peakValues) });
+            }
         }
 
         return true; // Keep processor alive